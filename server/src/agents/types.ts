@@ -7,6 +7,8 @@ export interface AgentDefinition {
   max_turns?: number
   /** Max output tokens requested from the model */
   max_output_tokens?: number
+  /** Wall-clock budget for one step's tool calls, in milliseconds */
+  step_timeout_ms?: number
   /** System prompt — the markdown body below the frontmatter */
   system_prompt: string
   /** Short description shown in the delegate tool listing */
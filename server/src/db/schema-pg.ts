@@ -4,8 +4,10 @@ import {
   integer,
   bigint,
   boolean,
+  doublePrecision,
   index,
   primaryKey,
+  uniqueIndex,
 } from 'drizzle-orm/pg-core'
 
 export const users = pgTable('users', {
@@ -26,6 +28,7 @@ export const sessions = pgTable('sessions', {
   title: text('title'),
   summary: text('summary'),
   status: text('status').default('active'),
+  deletedAt: bigint('deleted_at', { mode: 'number' }),
   createdAt: bigint('created_at', { mode: 'number' }),
   updatedAt: bigint('updated_at', { mode: 'number' }),
 })
@@ -44,6 +47,7 @@ export const agents = pgTable(
     waitingFor: text('waiting_for').default('[]'),
     result: text('result'),
     error: text('error'),
+    errorCode: text('error_code'),
     turnCount: integer('turn_count').default(0),
     plan: text('plan'),
     createdAt: bigint('created_at', { mode: 'number' }),
@@ -75,6 +79,8 @@ export const items = pgTable(
     turnNumber: integer('turn_number'),
     durationMs: integer('duration_ms'),
     createdAt: bigint('created_at', { mode: 'number' }).notNull(),
+    /** Set when the content is edited after creation; sync compares it, falling back to createdAt. */
+    updatedAt: bigint('updated_at', { mode: 'number' }),
   },
   (table) => [
     index('items_agent_id_sequence_idx').on(table.agentId, table.sequence),
@@ -117,6 +123,7 @@ export const toolOutputs = pgTable('tool_outputs', {
 export const preferences = pgTable('preferences', {
   key: text('key').primaryKey(),
   value: text('value').notNull(),
+  updatedAt: bigint('updated_at', { mode: 'number' }),
 })
 
 export const workflowRuns = pgTable(
@@ -274,3 +281,261 @@ export const telegramUpdateDedupe = pgTable(
     primaryKey({ columns: [table.connectionId, table.telegramUpdateId] }),
   ],
 )
+
+export const messageRevisions = pgTable(
+  'message_revisions',
+  {
+    id: text('id').primaryKey(),
+    itemId: text('item_id').notNull(),
+    sessionId: text('session_id').notNull(),
+    revision: integer('revision').notNull(),
+    role: text('role'),
+    content: text('content'),
+    contentBlocks: text('content_blocks'),
+    reason: text('reason').notNull(),
+    createdAt: bigint('created_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [
+    index('message_revisions_item_id_idx').on(table.itemId, table.revision),
+    index('message_revisions_session_id_idx').on(table.sessionId),
+  ],
+)
+
+export const usageRecords = pgTable(
+  'usage_records',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id'),
+    sessionId: text('session_id'),
+    agentId: text('agent_id'),
+    provider: text('provider').notNull(),
+    model: text('model').notNull(),
+    phase: text('phase'),
+    inputTokens: integer('input_tokens').notNull(),
+    outputTokens: integer('output_tokens').notNull(),
+    cacheReadTokens: integer('cache_read_tokens').default(0).notNull(),
+    cacheWriteTokens: integer('cache_write_tokens').default(0).notNull(),
+    costUsd: doublePrecision('cost_usd').default(0).notNull(),
+    cacheSavingsUsd: doublePrecision('cache_savings_usd').default(0).notNull(),
+    toolContext: text('tool_context'),
+    promptComposition: text('prompt_composition'),
+    createdAt: bigint('created_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [
+    index('usage_records_user_created_idx').on(table.userId, table.createdAt),
+    index('usage_records_session_id_idx').on(table.sessionId),
+  ],
+)
+
+export const approvalRecords = pgTable(
+  'approval_records',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id'),
+    sessionId: text('session_id').notNull(),
+    agentId: text('agent_id').notNull(),
+    callId: text('call_id').notNull(),
+    toolName: text('tool_name').notNull(),
+    args: text('args').notNull(),
+    amendedArgs: text('amended_args'),
+    outcome: text('outcome').notNull(),
+    scope: text('scope'),
+    ruleId: text('rule_id'),
+    requestedAt: bigint('requested_at', { mode: 'number' }).notNull(),
+    decidedAt: bigint('decided_at', { mode: 'number' }).notNull(),
+    latencyMs: integer('latency_ms').notNull(),
+  },
+  (table) => [
+    index('approval_records_user_decided_idx').on(table.userId, table.decidedAt),
+    index('approval_records_session_id_idx').on(table.sessionId),
+    index('approval_records_tool_name_idx').on(table.toolName),
+  ],
+)
+
+export const modelPrices = pgTable('model_prices', {
+  modelId: text('model_id').primaryKey(),
+  input: doublePrecision('input').notNull(),
+  output: doublePrecision('output').notNull(),
+  cacheRead: doublePrecision('cache_read'),
+  cacheWrite: doublePrecision('cache_write'),
+  source: text('source').notNull(),
+  updatedAt: bigint('updated_at', { mode: 'number' }).notNull(),
+})
+
+export const modelCatalog = pgTable(
+  'model_catalog',
+  {
+    provider: text('provider').notNull(),
+    modelId: text('model_id').notNull(),
+    displayName: text('display_name'),
+    contextWindow: integer('context_window'),
+    maxOutputTokens: integer('max_output_tokens'),
+    vision: boolean('vision'),
+    structuredOutputs: boolean('structured_outputs'),
+    tools: boolean('tools'),
+    fetchedAt: bigint('fetched_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [primaryKey({ columns: [table.provider, table.modelId] })]
+)
+
+export const uploads = pgTable(
+  'uploads',
+  {
+    userId: text('user_id').notNull(),
+    hash: text('hash').notNull(),
+    name: text('name').notNull(),
+    mediaType: text('media_type').notNull(),
+    kind: text('kind').notNull(),
+    bytes: bigint('bytes', { mode: 'number' }).notNull(),
+    createdAt: bigint('created_at', { mode: 'number' }).notNull(),
+    lastUsedAt: bigint('last_used_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [
+    primaryKey({ columns: [table.userId, table.hash] }),
+    index('uploads_hash_idx').on(table.hash),
+  ],
+)
+
+export const memories = pgTable(
+  'memories',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id').notNull(),
+    content: text('content').notNull(),
+    kind: text('kind').notNull(),
+    category: text('category').default('other').notNull(),
+    confidence: doublePrecision('confidence').notNull(),
+    embedding: text('embedding'),
+    embeddingModel: text('embedding_model'),
+    sourceSessionId: text('source_session_id'),
+    sourceItemId: text('source_item_id'),
+    disabled: boolean('disabled').default(false).notNull(),
+    createdAt: bigint('created_at', { mode: 'number' }).notNull(),
+    updatedAt: bigint('updated_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [
+    index('memories_user_id_idx').on(table.userId, table.updatedAt),
+  ],
+)
+
+export const knowledgeSources = pgTable(
+  'knowledge_sources',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id').notNull(),
+    kind: text('kind').notNull(),
+    location: text('location').notNull(),
+    name: text('name').notNull(),
+    status: text('status').notNull(),
+    error: text('error'),
+    documents: text('documents').notNull(),
+    indexedAt: bigint('indexed_at', { mode: 'number' }),
+    createdAt: bigint('created_at', { mode: 'number' }).notNull(),
+    updatedAt: bigint('updated_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [
+    index('knowledge_sources_user_id_idx').on(table.userId, table.createdAt),
+    index('knowledge_sources_status_idx').on(table.status),
+  ],
+)
+
+export const entities = pgTable(
+  'entities',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id').notNull(),
+    kind: text('kind').notNull(),
+    key: text('key').notNull(),
+    name: text('name').notNull(),
+    email: text('email'),
+    details: text('details').notNull(),
+    mentions: integer('mentions').notNull(),
+    embedding: text('embedding'),
+    embeddingModel: text('embedding_model'),
+    lastSeenAt: bigint('last_seen_at', { mode: 'number' }).notNull(),
+    createdAt: bigint('created_at', { mode: 'number' }).notNull(),
+    updatedAt: bigint('updated_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [
+    uniqueIndex('entities_user_kind_key_idx').on(table.userId, table.kind, table.key),
+  ],
+)
+
+export const scratchpadEntries = pgTable(
+  'scratchpad_entries',
+  {
+    sessionId: text('session_id').notNull(),
+    key: text('key').notNull(),
+    value: text('value').notNull(),
+    updatedAt: bigint('updated_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [
+    primaryKey({ columns: [table.sessionId, table.key] }),
+  ],
+)
+
+export const feeds = pgTable(
+  'feeds',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id').notNull(),
+    url: text('url').notNull(),
+    title: text('title').notNull(),
+    siteUrl: text('site_url'),
+    etag: text('etag'),
+    lastModified: text('last_modified'),
+    error: text('error'),
+    fetchedAt: bigint('fetched_at', { mode: 'number' }),
+    createdAt: bigint('created_at', { mode: 'number' }).notNull(),
+    updatedAt: bigint('updated_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [
+    uniqueIndex('feeds_user_url_idx').on(table.userId, table.url),
+  ],
+)
+
+export const feedItems = pgTable(
+  'feed_items',
+  {
+    id: text('id').primaryKey(),
+    feedId: text('feed_id').notNull(),
+    guid: text('guid').notNull(),
+    title: text('title').notNull(),
+    link: text('link'),
+    author: text('author'),
+    summary: text('summary').notNull(),
+    content: text('content').notNull(),
+    publishedAt: bigint('published_at', { mode: 'number' }).notNull(),
+    createdAt: bigint('created_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [
+    uniqueIndex('feed_items_feed_guid_idx').on(table.feedId, table.guid),
+    index('feed_items_feed_published_idx').on(table.feedId, table.publishedAt),
+  ],
+)
+
+export const controllerPromptVersions = pgTable(
+  'controller_prompt_versions',
+  {
+    name: text('name').notNull(),
+    version: integer('version').notNull(),
+    content: text('content').notNull(),
+    createdAt: bigint('created_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [
+    primaryKey({ columns: [table.name, table.version] }),
+  ],
+)
+
+export const experimentSessions = pgTable(
+  'experiment_sessions',
+  {
+    sessionId: text('session_id').primaryKey(),
+    experimentId: text('experiment_id').notNull(),
+    variant: text('variant').notNull(),
+    createdAt: bigint('created_at', { mode: 'number' }).notNull(),
+  },
+  (table) => [
+    index('experiment_sessions_experiment_idx').on(table.experimentId),
+  ],
+)
@@ -1,4 +1,4 @@
-import { sqliteTable, text, integer, index, primaryKey } from 'drizzle-orm/sqlite-core'
+import { sqliteTable, text, integer, real, index, primaryKey, uniqueIndex } from 'drizzle-orm/sqlite-core'
 
 export const users = sqliteTable('users', {
   id: text('id').primaryKey(),
@@ -18,6 +18,7 @@ export const sessions = sqliteTable('sessions', {
   title: text('title'),
   summary: text('summary'),
   status: text('status').default('active'),
+  deletedAt: integer('deleted_at'),
   createdAt: integer('created_at'),
   updatedAt: integer('updated_at'),
 })
@@ -36,6 +37,7 @@ export const agents = sqliteTable(
     waitingFor: text('waiting_for').default('[]'),
     result: text('result'),
     error: text('error'),
+    errorCode: text('error_code'),
     turnCount: integer('turn_count').default(0),
     plan: text('plan'),
     createdAt: integer('created_at'),
@@ -67,6 +69,8 @@ export const items = sqliteTable(
     turnNumber: integer('turn_number'),
     durationMs: integer('duration_ms'),
     createdAt: integer('created_at').notNull(),
+    /** Set when the content is edited after creation; sync compares it, falling back to createdAt. */
+    updatedAt: integer('updated_at'),
   },
   (table) => [
     index('items_agent_id_sequence_idx').on(table.agentId, table.sequence),
@@ -109,6 +113,7 @@ export const toolOutputs = sqliteTable('tool_outputs', {
 export const preferences = sqliteTable('preferences', {
   key: text('key').primaryKey(),
   value: text('value').notNull(),
+  updatedAt: integer('updated_at'),
 })
 
 export const workflowRuns = sqliteTable(
@@ -266,3 +271,261 @@ export const telegramUpdateDedupe = sqliteTable(
     primaryKey({ columns: [table.connectionId, table.telegramUpdateId] }),
   ]
 )
+
+export const messageRevisions = sqliteTable(
+  'message_revisions',
+  {
+    id: text('id').primaryKey(),
+    itemId: text('item_id').notNull(),
+    sessionId: text('session_id').notNull(),
+    revision: integer('revision').notNull(),
+    role: text('role'),
+    content: text('content'),
+    contentBlocks: text('content_blocks'),
+    reason: text('reason').notNull(),
+    createdAt: integer('created_at').notNull(),
+  },
+  (table) => [
+    index('message_revisions_item_id_idx').on(table.itemId, table.revision),
+    index('message_revisions_session_id_idx').on(table.sessionId),
+  ]
+)
+
+export const usageRecords = sqliteTable(
+  'usage_records',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id'),
+    sessionId: text('session_id'),
+    agentId: text('agent_id'),
+    provider: text('provider').notNull(),
+    model: text('model').notNull(),
+    phase: text('phase'),
+    inputTokens: integer('input_tokens').notNull(),
+    outputTokens: integer('output_tokens').notNull(),
+    cacheReadTokens: integer('cache_read_tokens').default(0).notNull(),
+    cacheWriteTokens: integer('cache_write_tokens').default(0).notNull(),
+    costUsd: real('cost_usd').default(0).notNull(),
+    cacheSavingsUsd: real('cache_savings_usd').default(0).notNull(),
+    toolContext: text('tool_context'),
+    promptComposition: text('prompt_composition'),
+    createdAt: integer('created_at').notNull(),
+  },
+  (table) => [
+    index('usage_records_user_created_idx').on(table.userId, table.createdAt),
+    index('usage_records_session_id_idx').on(table.sessionId),
+  ]
+)
+
+export const approvalRecords = sqliteTable(
+  'approval_records',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id'),
+    sessionId: text('session_id').notNull(),
+    agentId: text('agent_id').notNull(),
+    callId: text('call_id').notNull(),
+    toolName: text('tool_name').notNull(),
+    args: text('args').notNull(),
+    amendedArgs: text('amended_args'),
+    outcome: text('outcome').notNull(),
+    scope: text('scope'),
+    ruleId: text('rule_id'),
+    requestedAt: integer('requested_at').notNull(),
+    decidedAt: integer('decided_at').notNull(),
+    latencyMs: integer('latency_ms').notNull(),
+  },
+  (table) => [
+    index('approval_records_user_decided_idx').on(table.userId, table.decidedAt),
+    index('approval_records_session_id_idx').on(table.sessionId),
+    index('approval_records_tool_name_idx').on(table.toolName),
+  ]
+)
+
+export const modelPrices = sqliteTable('model_prices', {
+  modelId: text('model_id').primaryKey(),
+  input: real('input').notNull(),
+  output: real('output').notNull(),
+  cacheRead: real('cache_read'),
+  cacheWrite: real('cache_write'),
+  source: text('source').notNull(),
+  updatedAt: integer('updated_at').notNull(),
+})
+
+export const modelCatalog = sqliteTable(
+  'model_catalog',
+  {
+    provider: text('provider').notNull(),
+    modelId: text('model_id').notNull(),
+    displayName: text('display_name'),
+    contextWindow: integer('context_window'),
+    maxOutputTokens: integer('max_output_tokens'),
+    vision: integer('vision'),
+    structuredOutputs: integer('structured_outputs'),
+    tools: integer('tools'),
+    fetchedAt: integer('fetched_at').notNull(),
+  },
+  (table) => [primaryKey({ columns: [table.provider, table.modelId] })]
+)
+
+export const uploads = sqliteTable(
+  'uploads',
+  {
+    userId: text('user_id').notNull(),
+    hash: text('hash').notNull(),
+    name: text('name').notNull(),
+    mediaType: text('media_type').notNull(),
+    kind: text('kind').notNull(),
+    bytes: integer('bytes').notNull(),
+    createdAt: integer('created_at').notNull(),
+    lastUsedAt: integer('last_used_at').notNull(),
+  },
+  (table) => [
+    primaryKey({ columns: [table.userId, table.hash] }),
+    index('uploads_hash_idx').on(table.hash),
+  ]
+)
+
+export const memories = sqliteTable(
+  'memories',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id').notNull(),
+    content: text('content').notNull(),
+    kind: text('kind').notNull(),
+    category: text('category').default('other').notNull(),
+    confidence: real('confidence').notNull(),
+    embedding: text('embedding'),
+    embeddingModel: text('embedding_model'),
+    sourceSessionId: text('source_session_id'),
+    sourceItemId: text('source_item_id'),
+    disabled: integer('disabled').default(0).notNull(),
+    createdAt: integer('created_at').notNull(),
+    updatedAt: integer('updated_at').notNull(),
+  },
+  (table) => [
+    index('memories_user_id_idx').on(table.userId, table.updatedAt),
+  ]
+)
+
+export const knowledgeSources = sqliteTable(
+  'knowledge_sources',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id').notNull(),
+    kind: text('kind').notNull(),
+    location: text('location').notNull(),
+    name: text('name').notNull(),
+    status: text('status').notNull(),
+    error: text('error'),
+    documents: text('documents').notNull(),
+    indexedAt: integer('indexed_at'),
+    createdAt: integer('created_at').notNull(),
+    updatedAt: integer('updated_at').notNull(),
+  },
+  (table) => [
+    index('knowledge_sources_user_id_idx').on(table.userId, table.createdAt),
+    index('knowledge_sources_status_idx').on(table.status),
+  ]
+)
+
+export const entities = sqliteTable(
+  'entities',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id').notNull(),
+    kind: text('kind').notNull(),
+    key: text('key').notNull(),
+    name: text('name').notNull(),
+    email: text('email'),
+    details: text('details').notNull(),
+    mentions: integer('mentions').notNull(),
+    embedding: text('embedding'),
+    embeddingModel: text('embedding_model'),
+    lastSeenAt: integer('last_seen_at').notNull(),
+    createdAt: integer('created_at').notNull(),
+    updatedAt: integer('updated_at').notNull(),
+  },
+  (table) => [
+    uniqueIndex('entities_user_kind_key_idx').on(table.userId, table.kind, table.key),
+  ]
+)
+
+export const scratchpadEntries = sqliteTable(
+  'scratchpad_entries',
+  {
+    sessionId: text('session_id').notNull(),
+    key: text('key').notNull(),
+    value: text('value').notNull(),
+    updatedAt: integer('updated_at').notNull(),
+  },
+  (table) => [
+    primaryKey({ columns: [table.sessionId, table.key] }),
+  ]
+)
+
+export const feeds = sqliteTable(
+  'feeds',
+  {
+    id: text('id').primaryKey(),
+    userId: text('user_id').notNull(),
+    url: text('url').notNull(),
+    title: text('title').notNull(),
+    siteUrl: text('site_url'),
+    etag: text('etag'),
+    lastModified: text('last_modified'),
+    error: text('error'),
+    fetchedAt: integer('fetched_at'),
+    createdAt: integer('created_at').notNull(),
+    updatedAt: integer('updated_at').notNull(),
+  },
+  (table) => [
+    uniqueIndex('feeds_user_url_idx').on(table.userId, table.url),
+  ]
+)
+
+export const feedItems = sqliteTable(
+  'feed_items',
+  {
+    id: text('id').primaryKey(),
+    feedId: text('feed_id').notNull(),
+    guid: text('guid').notNull(),
+    title: text('title').notNull(),
+    link: text('link'),
+    author: text('author'),
+    summary: text('summary').notNull(),
+    content: text('content').notNull(),
+    publishedAt: integer('published_at').notNull(),
+    createdAt: integer('created_at').notNull(),
+  },
+  (table) => [
+    uniqueIndex('feed_items_feed_guid_idx').on(table.feedId, table.guid),
+    index('feed_items_feed_published_idx').on(table.feedId, table.publishedAt),
+  ]
+)
+
+export const controllerPromptVersions = sqliteTable(
+  'controller_prompt_versions',
+  {
+    name: text('name').notNull(),
+    version: integer('version').notNull(),
+    content: text('content').notNull(),
+    createdAt: integer('created_at').notNull(),
+  },
+  (table) => [
+    primaryKey({ columns: [table.name, table.version] }),
+  ]
+)
+
+export const experimentSessions = sqliteTable(
+  'experiment_sessions',
+  {
+    sessionId: text('session_id').primaryKey(),
+    experimentId: text('experiment_id').notNull(),
+    variant: text('variant').notNull(),
+    createdAt: integer('created_at').notNull(),
+  },
+  (table) => [
+    index('experiment_sessions_experiment_idx').on(table.experimentId),
+  ]
+)
@@ -1,4 +1,4 @@
-import type { Agent, AgentConfig, AgentStatus, WaitingFor } from './types'
+import type { Agent, AgentConfig, AgentErrorCode, AgentStatus, WaitingFor } from './types'
 
 interface CreateAgentInput {
   id: string
@@ -36,6 +36,7 @@ export function createAgent(input: CreateAgentInput): Agent {
     waitingFor: [],
     result: null,
     error: null,
+    errorCode: null,
     turnCount: 0,
     plan: null,
     createdAt: timestamp,
@@ -93,13 +94,14 @@ export function completeAgent(agent: Agent, result: string): Agent {
   }
 }
 
-export function failAgent(agent: Agent, error: string): Agent {
+export function failAgent(agent: Agent, error: string, errorCode: AgentErrorCode = 'unknown'): Agent {
   transition(agent, 'failed', ['pending', 'running', 'waiting'])
   const timestamp = now()
   return {
     ...agent,
     status: 'failed',
     error,
+    errorCode,
     updatedAt: timestamp,
     completedAt: timestamp,
   }
@@ -123,6 +125,7 @@ export function resumeAgent(agent: Agent): Agent {
     status: 'running',
     result: null,
     error: null,
+    errorCode: null,
     completedAt: null,
     updatedAt: now(),
   }
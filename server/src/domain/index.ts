@@ -5,6 +5,7 @@ export type {
   ItemType,
   ItemRole,
   SessionStatus,
+  AgentErrorCode,
   WaitingFor,
   AgentConfig,
   Agent,
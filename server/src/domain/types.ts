@@ -1,3 +1,5 @@
+import type { AgentErrorCode, ApprovalBatch } from '../events/payloads.js'
+
 export type CallId = string
 export type AgentStatus = 'pending' | 'running' | 'waiting' | 'completed' | 'failed' | 'cancelled'
 export type WaitType = 'tool' | 'approval' | 'agent' | 'human' | 'workflow'
@@ -5,6 +7,7 @@ export type ItemType = 'message' | 'function_call' | 'function_call_output' | 'r
 export type ItemRole = 'system' | 'user' | 'assistant'
 export type SessionStatus = 'active' | 'archived'
 export type AgentResponseFormat = 'markdown' | 'telegram_html'
+export type { AgentErrorCode, ApprovalBatch }
 
 export interface WaitingFor {
   callId: CallId
@@ -12,6 +15,8 @@ export interface WaitingFor {
   name: string
   args?: Record<string, unknown>
   description?: string
+  /** Set on approvals that can only be granted together with the rest of their batch. */
+  batch?: ApprovalBatch
 }
 
 export interface AgentConfig {
@@ -21,6 +26,13 @@ export interface AgentConfig {
   max_output_tokens?: number
   max_tool_calls_per_step: number
   tool_execution_timeout_ms: number
+  /**
+   * Wall-clock budget for the tool calls of one step, including approved
+   * calls that run one after another. Calls still running when it passes
+   * time out, and calls not yet started are skipped. Unset leaves only the
+   * per-call timeout.
+   */
+  step_timeout_ms?: number
   /** User-facing response formatting contract for the final transport. */
   response_format?: AgentResponseFormat
   system_prompt?: string
@@ -49,6 +61,8 @@ export interface Agent {
   waitingFor: WaitingFor[]
   result: string | null
   error: string | null
+  /** Classified reason for `error`, so a reopened session can still offer recovery. */
+  errorCode: AgentErrorCode | null
   turnCount: number
   plan: Plan | null
   createdAt: number
@@ -66,6 +80,8 @@ export interface Session {
   title: string | null
   summary: string | null
   status: SessionStatus
+  /** Set while the session sits in the trash; null for live sessions. */
+  deletedAt: number | null
   createdAt: number
   updatedAt: number
 }
@@ -79,10 +95,20 @@ export interface User {
 }
 
 export interface ItemContentBlock {
-  type: 'text' | 'image'
+  type: 'text' | 'image' | 'document' | 'video'
   text?: string
   media_type?: string
+  /** File name of an attached document, image or video. */
+  name?: string
+  /** Text extracted from a document, read from an image or transcribed from a video, sent to the model in place of the payload. */
+  chunks?: string[]
+  /** Text too large to inline; the model retrieves chunks with attachments.search instead. */
+  retrieval?: boolean
+  /** Base64 payload; only present in memory, persisted items carry `hash` instead. */
   data?: string
+  /** SHA-256 of the payload in the attachment store. */
+  hash?: string
+  bytes?: number
 }
 
 export interface Item {
@@ -1,10 +1,21 @@
 import { EventEmitter } from 'events'
-import type { AgentEvent, EventFilter, EventSink, EventSource } from './types.js'
+import {
+  EVENT_TYPES,
+  type AgentEvent,
+  type EventFilter,
+  type EventPacing,
+  type EventSink,
+  type EventSource,
+} from './types.js'
 
 // Drop oldest events when a subscriber falls behind — prevents unbounded memory growth
 // under slow SSE clients or paused consumers.
 const MAX_QUEUE_SIZE = 500
 
+// Replay history is a per-session ring buffer kept for the most recently active sessions.
+const REPLAY_EVENTS_PER_SESSION = 5000
+const REPLAY_SESSIONS = 50
+
 function matchesFilter(event: AgentEvent, filter: EventFilter): boolean {
   if (filter.agent_id && event.agent_id !== filter.agent_id) return false
   if (filter.session_id && event.session_id !== filter.session_id) return false
@@ -12,65 +23,186 @@ function matchesFilter(event: AgentEvent, filter: EventFilter): boolean {
   return true
 }
 
+// Subscribers scoped to a session listen on that session's channel only, so a
+// busy run does not wake every open stream. Unscoped subscribers see everything.
+function channelFor(filter: EventFilter): string {
+  return filter.session_id ? `session:${filter.session_id}` : 'event'
+}
+
+export const UNPACED: EventPacing = { batchMs: 0, maxEventsPerSecond: 0 }
+
+function isTextEvent(event: AgentEvent): boolean {
+  return event.type === EVENT_TYPES.TEXT_DELTA || event.type === EVENT_TYPES.COMPANION_TEXT
+}
+
+/**
+ * Fold `next` into a still-undelivered `previous`: text from the same agent
+ * and source concatenates, and a newer tool heartbeat replaces an older one.
+ * Null when the two must stay separate.
+ */
+export function coalesceEvents(previous: AgentEvent, next: AgentEvent): AgentEvent | null {
+  if (previous.agent_id !== next.agent_id || previous.session_id !== next.session_id) return null
+  if (
+    previous.type === next.type &&
+    (previous.type === EVENT_TYPES.TEXT_DELTA || previous.type === EVENT_TYPES.COMPANION_TEXT) &&
+    (next.type === EVENT_TYPES.TEXT_DELTA || next.type === EVENT_TYPES.COMPANION_TEXT) &&
+    (previous.payload.sourceCallId ?? null) === (next.payload.sourceCallId ?? null)
+  ) {
+    return { ...previous, payload: { ...previous.payload, text: previous.payload.text + next.payload.text } }
+  }
+  if (
+    previous.type === EVENT_TYPES.TOOL_PROGRESS &&
+    next.type === EVENT_TYPES.TOOL_PROGRESS &&
+    previous.payload.callId === next.payload.callId
+  ) {
+    return next
+  }
+  return null
+}
+
 export class AgentEventEmitter implements EventSink, EventSource {
   private emitter = new EventEmitter()
+  private history = new Map<string, AgentEvent[]>()
+  private readonly queues = new Set<unknown[]>()
+
+  constructor(private readonly pacing: EventPacing = UNPACED) {
+    // One listener per open stream or waiting agent; the default cap of 10 is a false alarm
+    this.emitter.setMaxListeners(0)
+  }
 
   emit(event: AgentEvent): void {
+    this.remember(event)
     this.emitter.emit('event', event)
+    this.emitter.emit(`session:${event.session_id}`, event)
+  }
+
+  /** Events at exactly `since` are included; clients should expect one repeat. */
+  replay(filter: EventFilter & { session_id: string }, since = 0): AgentEvent[] {
+    const events = this.history.get(filter.session_id) ?? []
+    return events.filter((event) => event.timestamp >= since && matchesFilter(event, filter))
+  }
+
+  private remember(event: AgentEvent): void {
+    let events = this.history.get(event.session_id)
+    if (events) {
+      // Re-insert so Map order tracks recency
+      this.history.delete(event.session_id)
+    } else {
+      events = []
+      if (this.history.size >= REPLAY_SESSIONS) {
+        this.history.delete(this.history.keys().next().value!)
+      }
+    }
+    this.history.set(event.session_id, events)
+    events.push(event)
+    if (events.length > REPLAY_EVENTS_PER_SESSION) events.shift()
+  }
+
+  /** Open subscriptions and their undelivered events, for metrics. */
+  queueStats(): { subscribers: number; queued: number; maxQueued: number } {
+    let queued = 0
+    let maxQueued = 0
+    for (const queue of this.queues) {
+      queued += queue.length
+      maxQueued = Math.max(maxQueued, queue.length)
+    }
+    return { subscribers: this.queues.size, queued, maxQueued }
   }
 
-  subscribe(filter: EventFilter): AsyncIterable<AgentEvent> {
+  /**
+   * Live events matching `filter`. Streams to clients should pass the
+   * runtime's pacing (the default); internal consumers can opt out with UNPACED.
+   */
+  subscribe(filter: EventFilter, pacing: EventPacing = this.pacing): AsyncIterable<AgentEvent> {
     const emitter = this.emitter
+    const queues = this.queues
+    const channel = channelFor(filter)
+    const minGapMs = pacing.maxEventsPerSecond > 0 ? 1000 / pacing.maxEventsPerSecond : 0
+    const coalesce = pacing.batchMs > 0 || minGapMs > 0
     return {
       [Symbol.asyncIterator]() {
-        const queue: AgentEvent[] = []
+        const queue: Array<{ event: AgentEvent; queuedAt: number }> = []
+        queues.add(queue)
         let resolve: ((value: IteratorResult<AgentEvent>) => void) | null = null
+        let timer: NodeJS.Timeout | null = null
+        let lastDeliveredAt = 0
         let done = false
 
+        // Milliseconds until the head of the queue may go out
+        const delayFor = (): number => {
+          const now = Date.now()
+          let delay = minGapMs > 0 ? lastDeliveredAt + minGapMs - now : 0
+          const head = queue[0]
+          // A lone text delta may still grow; give it the batch window
+          if (pacing.batchMs > 0 && queue.length === 1 && isTextEvent(head.event)) {
+            delay = Math.max(delay, head.queuedAt + pacing.batchMs - now)
+          }
+          return delay
+        }
+
+        const pump = () => {
+          if (!resolve || timer || queue.length === 0) return
+          const delay = delayFor()
+          if (delay > 0) {
+            timer = setTimeout(() => {
+              timer = null
+              pump()
+            }, delay)
+            return
+          }
+          const r = resolve
+          resolve = null
+          lastDeliveredAt = Date.now()
+          r({ value: queue.shift()!.event, done: false })
+        }
+
         const listener = (event: AgentEvent) => {
           if (!matchesFilter(event, filter)) return
-          if (resolve) {
-            const r = resolve
-            resolve = null
-            r({ value: event, done: false })
+          const last = queue[queue.length - 1]
+          const merged = coalesce && last ? coalesceEvents(last.event, event) : null
+          if (merged) {
+            last.event = merged
           } else {
             if (queue.length >= MAX_QUEUE_SIZE) {
               queue.shift() // drop oldest to maintain backpressure bound
             }
-            queue.push(event)
+            queue.push({ event, queuedAt: Date.now() })
           }
+          pump()
         }
 
-        emitter.on('event', listener)
+        const finish = () => {
+          done = true
+          queues.delete(queue)
+          emitter.off(channel, listener)
+          if (timer) {
+            clearTimeout(timer)
+            timer = null
+          }
+          if (resolve) {
+            resolve({ value: undefined as unknown as AgentEvent, done: true })
+            resolve = null
+          }
+        }
+
+        emitter.on(channel, listener)
 
         return {
           next(): Promise<IteratorResult<AgentEvent>> {
             if (done) {
               return Promise.resolve({ value: undefined as unknown as AgentEvent, done: true })
             }
-            if (queue.length > 0) {
-              return Promise.resolve({ value: queue.shift()!, done: false })
-            }
             return new Promise<IteratorResult<AgentEvent>>((r) => {
               resolve = r
+              pump()
             })
           },
           return(): Promise<IteratorResult<AgentEvent>> {
-            done = true
-            emitter.off('event', listener)
-            if (resolve) {
-              resolve({ value: undefined as unknown as AgentEvent, done: true })
-              resolve = null
-            }
+            finish()
             return Promise.resolve({ value: undefined as unknown as AgentEvent, done: true })
           },
           throw(err: unknown): Promise<IteratorResult<AgentEvent>> {
-            done = true
-            emitter.off('event', listener)
-            if (resolve) {
-              resolve({ value: undefined as unknown as AgentEvent, done: true })
-              resolve = null
-            }
+            finish()
             return Promise.reject(err)
           },
         }
@@ -79,20 +211,21 @@ export class AgentEventEmitter implements EventSink, EventSource {
   }
 
   subscribeOnce(filter: EventFilter, signal?: AbortSignal): Promise<AgentEvent> {
+    const channel = channelFor(filter)
     return new Promise<AgentEvent>((resolve, reject) => {
       const listener = (event: AgentEvent) => {
         if (!matchesFilter(event, filter)) return
         signal?.removeEventListener('abort', onAbort)
-        this.emitter.off('event', listener)
+        this.emitter.off(channel, listener)
         resolve(event)
       }
 
       const onAbort = () => {
-        this.emitter.off('event', listener)
+        this.emitter.off(channel, listener)
         reject(new DOMException('subscribeOnce aborted', 'AbortError'))
       }
 
-      this.emitter.on('event', listener)
+      this.emitter.on(channel, listener)
       signal?.addEventListener('abort', onAbort, { once: true })
     })
   }
@@ -0,0 +1,392 @@
+/**
+ * Wire contract for agent events, shared with the frontend.
+ *
+ * This file must stay import-free: `bun run gen:event-types` copies it into
+ * src/lib/types/server-events.ts so both sides compile against the same
+ * payloads. Bump EVENT_SCHEMA_VERSION when a payload changes incompatibly;
+ * every serialized event carries it as `v`.
+ */
+
+export const EVENT_SCHEMA_VERSION = 1
+
+export const EVENT_TYPES = {
+  AGENT_STARTED: 'agent:started',
+  AGENT_COMPLETED: 'agent:completed',
+  AGENT_FAILED: 'agent:failed',
+  AGENT_WAITING: 'agent:waiting',
+  RUN_QUEUED: 'run:queued',
+  TURN_STARTED: 'turn:started',
+  TURN_COMPLETED: 'turn:completed',
+  PROMPT_COMPOSITION: 'prompt:composition',
+  TOOL_STARTED: 'tool:started',
+  TOOL_COMPLETED: 'tool:completed',
+  TOOL_PROGRESS: 'tool:progress',
+  TOOL_PROPOSED: 'tool:proposed',
+  TOOL_APPROVED: 'tool:approved',
+  TOOL_DENIED: 'tool:denied',
+  TOOL_APPROVAL_REMINDER: 'tool:approval_reminder',
+  TOOL_APPROVAL_EXPIRED: 'tool:approval_expired',
+  STEP_PROPOSED: 'step:proposed',
+  STEP_STARTED: 'step:started',
+  STEP_COMPLETED: 'step:completed',
+  PHASE_CHANGED: 'phase:changed',
+  COMPANION_TEXT: 'companion:text',
+  TEXT_DELTA: 'text:delta',
+  WORKFLOW_STARTED: 'workflow:started',
+  WORKFLOW_COMPLETED: 'workflow:completed',
+  WORKFLOW_FAILED: 'workflow:failed',
+  WORKFLOW_PROGRESS: 'workflow:progress',
+  WORKFLOW_DISCUSSION_STARTED: 'workflow:discussion_started',
+  WORKFLOW_DISCUSSION_TURN: 'workflow:discussion_turn',
+  TASK_QUEUED: 'task:queued',
+  TASK_RUNNING: 'task:running',
+  TASK_CALLBACK_PENDING: 'task:callback_pending',
+  TASK_COMPLETED: 'task:completed',
+  TASK_FAILED: 'task:failed',
+  TASK_METADATA_UPDATED: 'task:metadata_updated',
+  BUDGET_THRESHOLD: 'budget:threshold',
+  BUDGET_FALLBACK: 'budget:fallback',
+  OFFLINE_FALLBACK: 'offline:fallback',
+  GUARDRAIL_TRIGGERED: 'guardrail:triggered',
+  MAINTENANCE_COMPLETED: 'maintenance:completed',
+  EMBEDDINGS_PROGRESS: 'embeddings:progress',
+  EMBEDDINGS_COMPLETED: 'embeddings:completed',
+  TRANSCRIPTION_PROGRESS: 'transcription:progress',
+  TRANSCRIPTION_COMPLETED: 'transcription:completed',
+  TRANSCRIPTION_FAILED: 'transcription:failed',
+  CONVERSATION_CLAIMED: 'conversation:claimed',
+  CONVERSATION_UPDATED: 'conversation:updated',
+  DIGEST_READY: 'digest:ready',
+} as const
+
+// ---------------------------------------------------------------------------
+// Shared payload pieces
+// ---------------------------------------------------------------------------
+
+/** Position in the delegation tree of the agent that emitted the event. */
+export interface AgentLineage {
+  parentId: string | null
+  depth: number
+}
+
+/**
+ * Why a run or tool call failed. Clients switch on this to offer a recovery
+ * action (re-enter a key, retry later, raise the budget) instead of only
+ * showing the message.
+ */
+export const AGENT_ERROR_CODES = [
+  'provider_auth',
+  'rate_limited',
+  'provider_unavailable',
+  'offline',
+  'context_overflow',
+  'tool_timeout',
+  'tool_failed',
+  'approval_denied',
+  'approval_expired',
+  'budget_exceeded',
+  'max_turns',
+  'guardrail_stop',
+  'cancelled',
+  'interrupted',
+  'stalled',
+  'persistence_failed',
+  'unknown',
+] as const
+
+export type AgentErrorCode = (typeof AGENT_ERROR_CODES)[number]
+
+export interface WaitingForPayload {
+  callId: string
+  type: 'tool' | 'approval' | 'agent' | 'human' | 'workflow'
+  name: string
+  args?: Record<string, unknown>
+  description?: string
+}
+
+/**
+ * What a write-capable tool would change, shown in the approval prompt in
+ * place of raw args.
+ */
+export type ToolChange =
+  | {
+      kind: 'file'
+      path: string
+      operation: 'create' | 'overwrite' | 'edit' | 'append'
+      /** Unified diff (`--- a/`, `+++ b/`, `@@` hunks); empty when nothing changes. */
+      diff: string
+      /** The changed region was too large to diff line by line. */
+      truncated: boolean
+    }
+  | {
+      kind: 'message'
+      to: string[]
+      cc?: string[]
+      subject: string
+      body: string
+    }
+  | {
+      /** Record updates such as a calendar event: each changed field, before → after. */
+      kind: 'fields'
+      target: string
+      changes: Array<{ field: string; before: unknown; after: unknown }>
+    }
+
+/** Returned by a tool's preview hook for a proposed call. */
+export interface ToolPreview {
+  summary: string
+  details?: Record<string, unknown>
+  change?: ToolChange
+}
+
+/**
+ * A batch of proposed calls too large to approve one by one. Its calls can
+ * only be approved together, with one "run entire batch" confirmation.
+ */
+export interface ApprovalBatch {
+  id: string
+  callIds: string[]
+  /** Changed lines, messages and fields across the batch, from the call previews. */
+  mutations: number
+  /** Which threshold the batch crossed. */
+  reason: 'calls' | 'mutations'
+  summary: string
+}
+
+export interface TaskEventPayload {
+  taskId: string
+  title: string
+  status: string
+  callbackAgentId?: string
+  callbackSessionId?: string
+  outputNote?: string
+  outputArtifact?: string
+}
+
+export interface MaintenanceCompletedPayload {
+  database: {
+    dialect: 'sqlite' | 'postgres'
+    sizeBytesBefore: number
+    sizeBytesAfter: number
+    reclaimedBytes: number
+    reindexed: string[]
+    integrity: string | null
+  }
+  artifacts: Array<{
+    name: 'sessions' | 'attachments' | 'tasks' | 'notes' | 'traces'
+    path: string
+    bytes: number
+    files: number
+  }>
+  totalBytes: number
+  health: { status: 'ok' | 'degraded'; warnings: string[] }
+  durationMs: number
+  completedAt: number
+}
+
+export interface EmbeddingMaintenancePayload {
+  /** Knowledge sources queued for re-indexing because their files changed. */
+  knowledgeSources: number
+  /** Projects re-ingested because their files changed. */
+  projects: number
+  /** Documents and project files embedded with the current model. */
+  documents: number
+  memories: number
+  /** True when the per-run limit left work for the next run. */
+  throttled: boolean
+  durationMs: number
+  completedAt: number
+}
+
+/** Size of one slice of a prompt. Tokens are estimated (about 4 characters each). */
+export interface PromptPart {
+  bytes: number
+  tokens: number
+}
+
+/**
+ * What a controller prompt is made of. The first five parts add up to
+ * `total`, and so do `stablePrefix` and `tail`.
+ */
+export interface PromptComposition {
+  /** System messages: controller prompt, task, instructions, and the tool list for non-native providers. */
+  system: PromptPart
+  /** Native tool definitions. */
+  tools: PromptPart
+  /** User and assistant messages, including tool call arguments. */
+  conversation: PromptPart
+  /** Tool results sent inline. */
+  toolResults: PromptPart
+  /** Tool results replaced by an artifact reference because they exceeded the inline limit. */
+  toolSummaries: PromptPart
+  /** Everything up to the last assistant message; unchanged since the previous turn, so cacheable. */
+  stablePrefix: PromptPart
+  /** Messages added since the previous turn. */
+  tail: PromptPart
+  total: PromptPart
+}
+
+/** How well the persisted tool outputs back up a final response, from the check run before it was saved. */
+export interface ResponseVerification {
+  confidence: 'high' | 'medium' | 'low'
+  /** Statements in the response the outputs do not support or contradict. */
+  unsupportedClaims: string[]
+  /** Tool outputs of the turn the check could look into. */
+  checkedOutputs: number
+  model: string
+}
+
+/**
+ * A configured guardrail stopped a run. `recoverable` pauses it until the
+ * user replies; `fatal` ends it with the `guardrail_stop` error code.
+ */
+export interface GuardrailTrigger {
+  guardrail: 'consecutive_failures' | 'cost_ceiling' | 'forbidden_tool' | 'content'
+  severity: 'recoverable' | 'fatal'
+  reason: string
+}
+
+/** How far a run has got, sent with every completed step so clients can show more than a spinner. */
+export interface RunProgress {
+  /** Plan steps done (completed or skipped); without a plan, the steps this run has completed. */
+  stepsCompleted: number
+  /** Plan steps still pending or running; null while the agent has no plan. */
+  stepsRemaining: number | null
+  /** Since the run started; a run resumed after an approval or answer counts from its resumption. */
+  elapsedMs: number
+  turnsUsed: number
+  maxTurns: number
+}
+
+// ---------------------------------------------------------------------------
+// Payload per event type
+// ---------------------------------------------------------------------------
+
+export interface EventPayloads {
+  'agent:started': AgentLineage & { task: string; model: string; sourceCallId?: string | null }
+  'agent:completed': AgentLineage & { result: string; verification?: ResponseVerification }
+  'agent:failed': AgentLineage & { error: string; errorCode: AgentErrorCode }
+  'agent:waiting': AgentLineage & { waitingFor: WaitingForPayload[] }
+  /** The run waits for a free slot or for another run of the conversation; sent again whenever `position` (1-based) moves. */
+  'run:queued': AgentLineage & { position: number }
+  'turn:started': AgentLineage & { turn: number }
+  'turn:completed': AgentLineage & { turn: number; outcome: string }
+  'prompt:composition': AgentLineage & { turn: number; composition: PromptComposition }
+  'tool:started': AgentLineage & { callId: string; name: string; args: Record<string, unknown> }
+  /** `errorCode` is set when `success` is false. */
+  'tool:completed': AgentLineage & { callId: string; name: string; success: boolean; output: string; durationMs: number; errorCode?: AgentErrorCode }
+  /** Heartbeat while a tool runs; progress and message only when the tool reports them. */
+  'tool:progress': AgentLineage & { callId: string; name: string; elapsedMs: number; progress?: number; message?: string }
+  /** `batch` is set when the call belongs to an escalated batch that is approved as a whole. */
+  'tool:proposed': AgentLineage & { callId: string; name: string; args: Record<string, unknown>; preview?: ToolPreview; batch?: ApprovalBatch }
+  'tool:approved': { callId: string; name: string }
+  /** `feedback` is set when the user explained the denial; the agent then carries on with it. */
+  'tool:denied': { callId: string; name: string; feedback?: string }
+  /** Sent at 50% and 90% of the approval window while the call is still unanswered. */
+  'tool:approval_reminder': AgentLineage & { callId: string; name: string; elapsedMs: number; remainingMs: number; timeoutMs: number }
+  /** Nobody answered in time; the agent resumes with an `approval_expired` error for the call. */
+  'tool:approval_expired': AgentLineage & { callId: string; name: string; timeoutMs: number }
+  'step:proposed': AgentLineage & { action: string; turn: number }
+  'step:started': AgentLineage & { stepType: string; turn: number }
+  'step:completed': AgentLineage & { stepType: string; turn: number; outcomeType: string; progress: RunProgress }
+  'companion:text': AgentLineage & { text: string; sourceCallId?: string | null }
+  'text:delta': AgentLineage & { text: string; sourceCallId?: string | null }
+  'workflow:started': { runId: string; workflowName: string; input: unknown }
+  'workflow:completed': { runId: string; workflowName: string; output: unknown }
+  'workflow:failed': { runId: string; workflowName: string; error: string }
+  'workflow:progress': { runId: string; workflowName: string; event: string; data: unknown }
+  'workflow:discussion_started': { runId: string; workflowName: string; prompt: string; timestamp_ms: number }
+  'workflow:discussion_turn': { runId: string; workflowName: string; role: 'user' | 'assistant'; content: string }
+  'task:queued': TaskEventPayload
+  'task:running': TaskEventPayload
+  'task:callback_pending': TaskEventPayload
+  'task:completed': TaskEventPayload
+  'task:failed': TaskEventPayload & { error?: string }
+  'task:metadata_updated': TaskEventPayload
+  'budget:threshold': {
+    period: 'weekly' | 'monthly'
+    periodStart: number
+    limitUsd: number
+    spentUsd: number
+    fraction: number
+    exceeded: boolean
+    threshold: number
+  }
+  /** A spending cap was hit mid-run and the agent moved to the cap's fallback model. */
+  'budget:fallback': AgentLineage & {
+    from: string
+    to: string
+    caps: Array<{ scope: string; period: 'daily' | 'weekly' | 'monthly'; limitUsd: number; spentUsd: number }>
+  }
+  /** The machine went offline and the run moved from a remote provider to the local OFFLINE_MODEL. */
+  'offline:fallback': AgentLineage & { from: string; to: string }
+  'guardrail:triggered': AgentLineage & GuardrailTrigger & { turn: number }
+  'maintenance:completed': MaintenanceCompletedPayload
+  /** Background re-embedding; `done` of `total` items in the current phase. */
+  'embeddings:progress': { phase: 'knowledge' | 'projects' | 'documents' | 'memories'; done: number; total: number }
+  'embeddings:completed': EmbeddingMaintenancePayload
+  /** Background audio transcription; `progress` is 0–100 when the backend reports it. */
+  'transcription:progress': { jobId: string; fileName: string | null; progress: number }
+  'transcription:completed': { jobId: string; fileName: string | null; text: string }
+  'transcription:failed': { jobId: string; fileName: string | null; error: string }
+  /** A window took over (or released, windowId null) a conversation. */
+  'conversation:claimed': { windowId: string | null; previousWindowId: string | null }
+  /** The conversation was renamed, e.g. titled after its first exchange. */
+  'conversation:updated': { title: string }
+  /** A daily digest was written into the session; `excerpt` is its opening line. */
+  'digest:ready': { date: string; excerpt: string }
+}
+
+export type ServerEventType = keyof EventPayloads
+
+/** Fields every serialized event carries alongside its payload. */
+export interface ServerEventEnvelope {
+  v: number
+  agentId: string
+  sessionId: string
+  timestamp: number
+}
+
+/**
+ * An event as sent over SSE, the replay endpoint and the WebSocket bridge:
+ * envelope and payload flattened into one object, discriminated on `type`.
+ */
+export type ServerEvent<T extends ServerEventType = ServerEventType> = {
+  [K in T]: { type: K } & ServerEventEnvelope & EventPayloads[K]
+}[T]
+
+// ---------------------------------------------------------------------------
+// Chat completion stream (POST /api/chat/completions with stream: true)
+// ---------------------------------------------------------------------------
+
+/** The completion stream renames these agent events; see mapEventToSSE in routes/chat.ts. */
+export interface ChatStreamPayloads {
+  text_delta: EventPayloads['text:delta']
+  tool_start: EventPayloads['tool:started']
+  tool_end: EventPayloads['tool:completed']
+  tool_progress: EventPayloads['tool:progress']
+  approval: EventPayloads['tool:proposed']
+  tool_approved: EventPayloads['tool:approved']
+  tool_denied: EventPayloads['tool:denied']
+  agent_status: EventPayloads['agent:waiting']
+  agent_started: EventPayloads['agent:started']
+  subagent_done: EventPayloads['agent:completed']
+  subagent_error: EventPayloads['agent:failed']
+}
+
+export type ChatStreamEvent<T extends keyof ChatStreamPayloads = keyof ChatStreamPayloads> = {
+  [K in T]: { type: K } & ServerEventEnvelope & ChatStreamPayloads[K]
+}[T]
+
+/** Final event of the completion stream. */
+export interface ChatStreamDone {
+  id: string
+  sessionId: string
+  status: string
+  result?: string
+  error?: string
+  errorCode?: AgentErrorCode
+  waitingFor?: WaitingForPayload[]
+  turnCount: number
+  verification?: ResponseVerification
+}
@@ -0,0 +1,48 @@
+const DEFAULT_FLUSH_MS = 30
+const DEFAULT_MAX_CHARS = 400
+
+export interface TextCoalescerOptions {
+  /** Longest a delta waits for more text before it goes out. */
+  flushMs?: number
+  /** Buffered text at which a flush happens at once, without waiting for the timer. */
+  maxChars?: number
+}
+
+/**
+ * Buffers streamed text and hands it on every `flushMs` or once `maxChars`
+ * have built up, whichever comes first, so a fast provider emits a few
+ * events per second instead of one per token. Callers must `flush()` when
+ * the stream ends so the tail is not lost.
+ */
+export class TextCoalescer {
+  private text = ''
+  private timer: NodeJS.Timeout | null = null
+  private readonly flushMs: number
+  private readonly maxChars: number
+
+  constructor(private readonly deliver: (text: string) => void, options: TextCoalescerOptions = {}) {
+    this.flushMs = options.flushMs ?? DEFAULT_FLUSH_MS
+    this.maxChars = options.maxChars ?? DEFAULT_MAX_CHARS
+  }
+
+  push(text: string): void {
+    if (!text) return
+    this.text += text
+    if (this.text.length >= this.maxChars || this.flushMs <= 0) {
+      this.flush()
+    } else if (!this.timer) {
+      this.timer = setTimeout(() => this.flush(), this.flushMs)
+    }
+  }
+
+  flush(): void {
+    if (this.timer) {
+      clearTimeout(this.timer)
+      this.timer = null
+    }
+    if (!this.text) return
+    const text = this.text
+    this.text = ''
+    this.deliver(text)
+  }
+}
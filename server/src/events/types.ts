@@ -1,12 +1,22 @@
-import type { WaitingFor } from '../domain/types.js'
+import {
+  EVENT_SCHEMA_VERSION,
+  EVENT_TYPES,
+  type EventPayloads,
+  type ServerEvent,
+} from './payloads.js'
+
+export { EVENT_SCHEMA_VERSION, EVENT_TYPES }
+export type { AgentErrorCode, ChatStreamDone, EventPayloads, GuardrailTrigger, ResponseVerification, RunProgress, ServerEvent, TaskEventPayload } from './payloads.js'
 
 export interface EventSink {
   emit(event: AgentEvent): void
 }
 
 export interface EventSource {
-  subscribe(filter: EventFilter): AsyncIterable<AgentEvent>
+  subscribe(filter: EventFilter, pacing?: EventPacing): AsyncIterable<AgentEvent>
   subscribeOnce(filter: EventFilter, signal?: AbortSignal): Promise<AgentEvent>
+  /** Recently emitted events for a session, oldest first, so a reloaded UI can catch up. */
+  replay(filter: EventFilter & { session_id: string }, since?: number): AgentEvent[]
 }
 
 // ---------------------------------------------------------------------------
@@ -18,13 +28,18 @@ export type AgentEvent =
   | AgentCompletedEvent
   | AgentFailedEvent
   | AgentWaitingEvent
+  | RunQueuedEvent
   | TurnStartedEvent
   | TurnCompletedEvent
+  | PromptCompositionEvent
   | ToolStartedEvent
   | ToolCompletedEvent
+  | ToolProgressEvent
   | ToolProposedEvent
   | ToolApprovedEvent
   | ToolDeniedEvent
+  | ToolApprovalReminderEvent
+  | ToolApprovalExpiredEvent
   | StepProposedEvent
   | StepStartedEvent
   | StepCompletedEvent
@@ -42,6 +57,19 @@ export type AgentEvent =
   | TaskCompletedEvent
   | TaskFailedEvent
   | TaskMetadataUpdatedEvent
+  | BudgetThresholdEvent
+  | BudgetFallbackEvent
+  | OfflineFallbackEvent
+  | GuardrailTriggeredEvent
+  | MaintenanceCompletedEvent
+  | EmbeddingsProgressEvent
+  | EmbeddingsCompletedEvent
+  | TranscriptionProgressEvent
+  | TranscriptionCompletedEvent
+  | TranscriptionFailedEvent
+  | ConversationClaimedEvent
+  | ConversationUpdatedEvent
+  | DigestReadyEvent
 
 interface BaseEvent {
   agent_id: string
@@ -51,156 +79,244 @@ interface BaseEvent {
 
 export interface AgentStartedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.AGENT_STARTED
-  payload: { task: string; model: string; parentId: string | null; sourceCallId?: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.AGENT_STARTED]
 }
 
 export interface AgentCompletedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.AGENT_COMPLETED
-  payload: { result: string; parentId: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.AGENT_COMPLETED]
 }
 
 export interface AgentFailedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.AGENT_FAILED
-  payload: { error: string; parentId: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.AGENT_FAILED]
 }
 
 export interface AgentWaitingEvent extends BaseEvent {
   type: typeof EVENT_TYPES.AGENT_WAITING
-  payload: { waitingFor: WaitingFor[]; parentId: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.AGENT_WAITING]
+}
+
+export interface RunQueuedEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.RUN_QUEUED
+  payload: EventPayloads[typeof EVENT_TYPES.RUN_QUEUED]
 }
 
 export interface TurnStartedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TURN_STARTED
-  payload: { turn: number; parentId: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.TURN_STARTED]
 }
 
 export interface TurnCompletedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TURN_COMPLETED
-  payload: { turn: number; outcome: string; parentId: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.TURN_COMPLETED]
+}
+
+export interface PromptCompositionEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.PROMPT_COMPOSITION
+  payload: EventPayloads[typeof EVENT_TYPES.PROMPT_COMPOSITION]
 }
 
 export interface ToolStartedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TOOL_STARTED
-  payload: { callId: string; name: string; args: Record<string, unknown>; parentId: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.TOOL_STARTED]
 }
 
 export interface ToolCompletedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TOOL_COMPLETED
-  payload: { callId: string; name: string; success: boolean; output: string; durationMs: number; parentId: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.TOOL_COMPLETED]
+}
+
+export interface ToolProgressEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.TOOL_PROGRESS
+  payload: EventPayloads[typeof EVENT_TYPES.TOOL_PROGRESS]
 }
 
 export interface ToolProposedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TOOL_PROPOSED
-  payload: { callId: string; name: string; args: Record<string, unknown>; parentId: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.TOOL_PROPOSED]
 }
 
 export interface ToolApprovedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TOOL_APPROVED
-  payload: { callId: string; name: string }
+  payload: EventPayloads[typeof EVENT_TYPES.TOOL_APPROVED]
 }
 
 export interface ToolDeniedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TOOL_DENIED
-  payload: { callId: string; name: string }
+  payload: EventPayloads[typeof EVENT_TYPES.TOOL_DENIED]
+}
+
+export interface ToolApprovalReminderEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.TOOL_APPROVAL_REMINDER
+  payload: EventPayloads[typeof EVENT_TYPES.TOOL_APPROVAL_REMINDER]
+}
+
+export interface ToolApprovalExpiredEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.TOOL_APPROVAL_EXPIRED
+  payload: EventPayloads[typeof EVENT_TYPES.TOOL_APPROVAL_EXPIRED]
 }
 
 export interface StepProposedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.STEP_PROPOSED
-  payload: { action: string; turn: number; parentId: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.STEP_PROPOSED]
 }
 
 export interface StepStartedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.STEP_STARTED
-  payload: { stepType: string; turn: number; parentId: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.STEP_STARTED]
 }
 
 export interface StepCompletedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.STEP_COMPLETED
-  payload: { stepType: string; turn: number; outcomeType: string; parentId: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.STEP_COMPLETED]
 }
 
 export interface CompanionTextEvent extends BaseEvent {
   type: typeof EVENT_TYPES.COMPANION_TEXT
-  payload: { text: string; parentId: string | null; sourceCallId?: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.COMPANION_TEXT]
 }
 
 export interface TextDeltaEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TEXT_DELTA
-  payload: { text: string; parentId: string | null; sourceCallId?: string | null; depth: number }
+  payload: EventPayloads[typeof EVENT_TYPES.TEXT_DELTA]
 }
 
 // --- Workflow events ---
 
 export interface WorkflowStartedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.WORKFLOW_STARTED
-  payload: { runId: string; workflowName: string; input: unknown }
+  payload: EventPayloads[typeof EVENT_TYPES.WORKFLOW_STARTED]
 }
 
 export interface WorkflowCompletedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.WORKFLOW_COMPLETED
-  payload: { runId: string; workflowName: string; output: unknown }
+  payload: EventPayloads[typeof EVENT_TYPES.WORKFLOW_COMPLETED]
 }
 
 export interface WorkflowFailedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.WORKFLOW_FAILED
-  payload: { runId: string; workflowName: string; error: string }
+  payload: EventPayloads[typeof EVENT_TYPES.WORKFLOW_FAILED]
 }
 
 export interface WorkflowProgressEvent extends BaseEvent {
   type: typeof EVENT_TYPES.WORKFLOW_PROGRESS
-  payload: { runId: string; workflowName: string; event: string; data: unknown }
+  payload: EventPayloads[typeof EVENT_TYPES.WORKFLOW_PROGRESS]
 }
 
 export interface WorkflowDiscussionStartedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.WORKFLOW_DISCUSSION_STARTED
-  payload: { runId: string; workflowName: string; prompt: string; timestamp_ms: number }
+  payload: EventPayloads[typeof EVENT_TYPES.WORKFLOW_DISCUSSION_STARTED]
 }
 
 export interface WorkflowDiscussionTurnEvent extends BaseEvent {
   type: typeof EVENT_TYPES.WORKFLOW_DISCUSSION_TURN
-  payload: { runId: string; workflowName: string; role: 'user' | 'assistant'; content: string }
+  payload: EventPayloads[typeof EVENT_TYPES.WORKFLOW_DISCUSSION_TURN]
 }
 
 // --- Task events ---
 
-export interface TaskEventPayload {
-  taskId: string
-  title: string
-  status: string
-  callbackAgentId?: string
-  callbackSessionId?: string
-  outputNote?: string
-  outputArtifact?: string
-}
-
 export interface TaskQueuedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TASK_QUEUED
-  payload: TaskEventPayload
+  payload: EventPayloads[typeof EVENT_TYPES.TASK_QUEUED]
 }
 
 export interface TaskRunningEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TASK_RUNNING
-  payload: TaskEventPayload
+  payload: EventPayloads[typeof EVENT_TYPES.TASK_RUNNING]
 }
 
 export interface TaskCallbackPendingEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TASK_CALLBACK_PENDING
-  payload: TaskEventPayload
+  payload: EventPayloads[typeof EVENT_TYPES.TASK_CALLBACK_PENDING]
 }
 
 export interface TaskCompletedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TASK_COMPLETED
-  payload: TaskEventPayload
+  payload: EventPayloads[typeof EVENT_TYPES.TASK_COMPLETED]
 }
 
 export interface TaskFailedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TASK_FAILED
-  payload: TaskEventPayload & { error?: string }
+  payload: EventPayloads[typeof EVENT_TYPES.TASK_FAILED]
 }
 
 export interface TaskMetadataUpdatedEvent extends BaseEvent {
   type: typeof EVENT_TYPES.TASK_METADATA_UPDATED
-  payload: TaskEventPayload
+  payload: EventPayloads[typeof EVENT_TYPES.TASK_METADATA_UPDATED]
+}
+
+// --- Usage events ---
+
+export interface BudgetThresholdEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.BUDGET_THRESHOLD
+  payload: EventPayloads[typeof EVENT_TYPES.BUDGET_THRESHOLD]
+}
+
+export interface BudgetFallbackEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.BUDGET_FALLBACK
+  payload: EventPayloads[typeof EVENT_TYPES.BUDGET_FALLBACK]
+}
+
+export interface OfflineFallbackEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.OFFLINE_FALLBACK
+  payload: EventPayloads[typeof EVENT_TYPES.OFFLINE_FALLBACK]
+}
+
+export interface GuardrailTriggeredEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.GUARDRAIL_TRIGGERED
+  payload: EventPayloads[typeof EVENT_TYPES.GUARDRAIL_TRIGGERED]
+}
+
+// --- Maintenance events ---
+
+export interface MaintenanceCompletedEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.MAINTENANCE_COMPLETED
+  payload: EventPayloads[typeof EVENT_TYPES.MAINTENANCE_COMPLETED]
+}
+
+export interface EmbeddingsProgressEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.EMBEDDINGS_PROGRESS
+  payload: EventPayloads[typeof EVENT_TYPES.EMBEDDINGS_PROGRESS]
+}
+
+export interface EmbeddingsCompletedEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.EMBEDDINGS_COMPLETED
+  payload: EventPayloads[typeof EVENT_TYPES.EMBEDDINGS_COMPLETED]
+}
+
+// --- Transcription events ---
+
+export interface TranscriptionProgressEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.TRANSCRIPTION_PROGRESS
+  payload: EventPayloads[typeof EVENT_TYPES.TRANSCRIPTION_PROGRESS]
+}
+
+export interface TranscriptionCompletedEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.TRANSCRIPTION_COMPLETED
+  payload: EventPayloads[typeof EVENT_TYPES.TRANSCRIPTION_COMPLETED]
+}
+
+export interface TranscriptionFailedEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.TRANSCRIPTION_FAILED
+  payload: EventPayloads[typeof EVENT_TYPES.TRANSCRIPTION_FAILED]
+}
+
+// --- Window events ---
+
+export interface ConversationClaimedEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.CONVERSATION_CLAIMED
+  payload: EventPayloads[typeof EVENT_TYPES.CONVERSATION_CLAIMED]
+}
+
+export interface ConversationUpdatedEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.CONVERSATION_UPDATED
+  payload: EventPayloads[typeof EVENT_TYPES.CONVERSATION_UPDATED]
+}
+
+export interface DigestReadyEvent extends BaseEvent {
+  type: typeof EVENT_TYPES.DIGEST_READY
+  payload: EventPayloads[typeof EVENT_TYPES.DIGEST_READY]
 }
 
 // ---------------------------------------------------------------------------
@@ -213,34 +329,22 @@ export interface EventFilter {
   types?: string[]
 }
 
-export const EVENT_TYPES = {
-  AGENT_STARTED: 'agent:started',
-  AGENT_COMPLETED: 'agent:completed',
-  AGENT_FAILED: 'agent:failed',
-  AGENT_WAITING: 'agent:waiting',
-  TURN_STARTED: 'turn:started',
-  TURN_COMPLETED: 'turn:completed',
-  TOOL_STARTED: 'tool:started',
-  TOOL_COMPLETED: 'tool:completed',
-  TOOL_PROPOSED: 'tool:proposed',
-  TOOL_APPROVED: 'tool:approved',
-  TOOL_DENIED: 'tool:denied',
-  STEP_PROPOSED: 'step:proposed',
-  STEP_STARTED: 'step:started',
-  STEP_COMPLETED: 'step:completed',
-  PHASE_CHANGED: 'phase:changed',
-  COMPANION_TEXT: 'companion:text',
-  TEXT_DELTA: 'text:delta',
-  WORKFLOW_STARTED: 'workflow:started',
-  WORKFLOW_COMPLETED: 'workflow:completed',
-  WORKFLOW_FAILED: 'workflow:failed',
-  WORKFLOW_PROGRESS: 'workflow:progress',
-  WORKFLOW_DISCUSSION_STARTED: 'workflow:discussion_started',
-  WORKFLOW_DISCUSSION_TURN: 'workflow:discussion_turn',
-  TASK_QUEUED: 'task:queued',
-  TASK_RUNNING: 'task:running',
-  TASK_CALLBACK_PENDING: 'task:callback_pending',
-  TASK_COMPLETED: 'task:completed',
-  TASK_FAILED: 'task:failed',
-  TASK_METADATA_UPDATED: 'task:metadata_updated',
-} as const
+/** Delivery pacing for subscribers; zero disables either knob. */
+export interface EventPacing {
+  /** Hold streamed text this long so consecutive deltas merge into one event. */
+  batchMs: number
+  /** Cap on events delivered per subscriber each second; the backlog coalesces while it waits. */
+  maxEventsPerSecond: number
+}
+
+/** Flatten an event into the versioned shape clients receive (see events/payloads.ts). */
+export function toWireEvent(event: AgentEvent, sessionId = event.session_id): ServerEvent {
+  return {
+    type: event.type,
+    v: EVENT_SCHEMA_VERSION,
+    agentId: event.agent_id,
+    sessionId,
+    timestamp: event.timestamp,
+    ...event.payload,
+  } as ServerEvent
+}
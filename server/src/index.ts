@@ -1,22 +1,41 @@
 import 'dotenv/config'
-import path from 'path'
 import { serve } from '@hono/node-server'
 import { Hono } from 'hono'
 import { cors } from 'hono/cors'
 import { logger as honoLogger } from 'hono/logger'
 import { logger } from './lib/logger.js'
-import { loadConfig } from './lib/config.js'
+import { loadEnvConfig } from './lib/config.js'
+import { applyWorkspace, WorkspaceSwitcher } from './lib/workspace.js'
 import { initRuntime, shutdownRuntime } from './lib/runtime.js'
 import type { RuntimeContext } from './lib/runtime.js'
 import { chatRoutes } from './routes/chat.js'
 import { audioRoutes } from './routes/audio.js'
+import { attachmentRoutes } from './routes/attachments.js'
+import { projectRoutes } from './routes/projects.js'
+import { knowledgeRoutes } from './routes/knowledge.js'
+import { memoryRoutes } from './routes/memories.js'
 import { sessionRoutes } from './routes/sessions.js'
 import { modelRoutes } from './routes/models.js'
 import { apiKeyRoutes } from './routes/api-keys.js'
+import { mailRoutes } from './routes/mail.js'
+import { calendarRoutes } from './routes/calendar.js'
+import { feedRoutes } from './routes/feeds.js'
+import { webhookRoutes } from './routes/webhooks.js'
+import { pluginRoutes } from './routes/plugins.js'
+import { actionRoutes } from './routes/actions.js'
 import { systemPromptRoutes } from './routes/system-prompts.js'
+import { controllerPromptRoutes } from './routes/controller-prompts.js'
+import { experimentRoutes } from './routes/experiments.js'
 import { preferenceRoutes } from './routes/preferences.js'
+import { profileRoutes } from './routes/profile.js'
 import { toolRoutes } from './routes/tools.js'
 import { usageRoutes } from './routes/usage.js'
+import { auditRoutes } from './routes/audit.js'
+import { metricsRoutes } from './routes/metrics.js'
+import { inspectorRoutes } from './routes/inspector.js'
+import { retentionRoutes } from './routes/retention.js'
+import { maintenanceRoutes } from './routes/maintenance.js'
+import { windowRoutes } from './routes/windows.js'
 import { workflowRoutes } from './routes/workflows.js'
 import { openaiCompatRoutes } from './routes/openai-compat.js'
 import { authMiddleware } from './middleware/auth.js'
@@ -24,6 +43,9 @@ import { mcpRoutes } from './routes/mcps.js'
 import { telegramRoutes, telegramWebhookRoutes } from './routes/telegram.js'
 import { createRateLimiter } from './lib/rate-limit.js'
 import { mcpOAuthCallbackRoutes } from './routes/mcp-oauth-callback.js'
+import { remoteApprovalRoutes } from './routes/remote-approvals.js'
+import { workspaceRoutes } from './routes/workspaces.js'
+import { syncRoutes } from './routes/sync.js'
 
 type AppEnv = {
   Variables: {
@@ -32,143 +54,187 @@ type AppEnv = {
   }
 }
 
-const config = loadConfig()
+const baseConfig = loadEnvConfig()
+const config = applyWorkspace(baseConfig)
 
 if (config.workingDir) {
-  // Resolve DATABASE_URL to absolute before chdir so it doesn't get re-rooted
-  if (config.dbDialect === 'sqlite' && !path.isAbsolute(config.databaseUrl)) {
-    config.databaseUrl = path.resolve(process.cwd(), config.databaseUrl)
-  }
+  // Data paths are resolved against the server directory, so they stay put
   process.chdir(config.workingDir)
 }
 
 async function main() {
-  const runtime = await initRuntime(config)
-
-  const app = new Hono<AppEnv>()
-
-  // CORS — bearer-token auth is the gate; CORS stays open without credentials
-  // so the bearer-Authorization model works from any origin (Tauri has no fixed origin).
-  // The actual gate is authMiddleware below.
-  const allowedOrigins = config.allowedOrigins
-    .split(',')
-    .map((o) => o.trim())
-    .filter(Boolean)
-  app.use(
-    '*',
-    cors(
-      allowedOrigins.length > 0
-        ? { origin: allowedOrigins, credentials: true }
-        : { origin: '*', credentials: false },
-    ),
-  )
-  const requestLogger = honoLogger()
-  app.use('*', (c, next) => c.req.path === '/oauth/mcp/callback' ? next() : requestLogger(c, next))
-
-  // Inject runtime into context
-  app.use('*', async (c, next) => {
-    c.set('runtime', runtime)
-    await next()
+  let app = buildApp(await initRuntime(config))
+  const workspaces = new WorkspaceSwitcher(baseConfig, app.runtime, {
+    init: initRuntime,
+    shutdown: shutdownRuntime,
+    activate: (runtime) => { app = buildApp(runtime) },
   })
 
-  // Health check (no auth, IP-based rate limit)
-  app.use(
-    '/health',
-    createRateLimiter({
-      name: 'health',
-      limit: config.rateLimitHealthPerMin,
-      keyBy: 'ip',
-      trustProxy: config.trustProxy,
-    }),
-  )
-  app.get('/health', (c) => c.json({ status: 'ok', timestamp: Date.now() }))
-
-  // Telegram webhooks — server-to-server from Telegram, IP-based rate limit
-  app.use(
-    '/telegram/*',
-    createRateLimiter({
-      name: 'telegram',
-      limit: config.rateLimitTelegramPerMin,
-      keyBy: 'ip',
-      trustProxy: config.trustProxy,
-    }),
-  )
-  app.route('/telegram', telegramWebhookRoutes(runtime))
-
-  // MCP OAuth callback — public by design. One-time state is its sole authority.
-  app.use(
-    '/oauth/mcp/*',
-    createRateLimiter({
-      name: 'mcp-oauth-callback',
-      limit: config.rateLimitOAuthCallbackPerMin,
-      keyBy: 'ip',
-      trustProxy: config.trustProxy,
-    }),
-  )
-  app.route('/oauth/mcp', mcpOAuthCallbackRoutes(runtime))
-
-  // Pre-auth failure throttle, then auth + per-user rate limit for /api/*
-  app.use(
-    '/api/*',
-    createRateLimiter({
-      name: 'api-auth-failure',
-      limit: config.rateLimitAuthFailurePerMin,
-      keyBy: 'ip',
-      trustProxy: config.trustProxy,
-      skipSuccessfulRequests: true,
-    }),
-  )
-  app.use('/api/*', authMiddleware)
-  app.use(
-    '/api/*',
-    createRateLimiter({
-      name: 'api',
-      limit: config.rateLimitApiPerMin,
-      keyBy: 'user',
-      trustProxy: config.trustProxy,
-    }),
-  )
-
-  // API Routes
-  app.route('/api/audio', audioRoutes(runtime))
-  app.route('/api/chat', chatRoutes(runtime))
-  app.route('/api/sessions', sessionRoutes(runtime))
-  app.route('/api/models', modelRoutes(runtime))
-  app.route('/api/keys', apiKeyRoutes(runtime))
-  app.route('/api/system-prompts', systemPromptRoutes(runtime))
-  app.route('/api/preferences', preferenceRoutes(runtime))
-  app.route('/api/tools', toolRoutes(runtime))
-  app.route('/api/usage', usageRoutes(runtime))
-  app.route('/api/workflows', workflowRoutes(runtime))
-  app.route('/api/mcps', mcpRoutes(runtime))
-  app.route('/api/telegram', telegramRoutes(runtime))
-
-  // OpenAI-compatible endpoint — gated like /api/* with its own rate-limit bucket
-  app.use(
-    '/v1/*',
-    createRateLimiter({
-      name: 'inference-auth-failure',
-      limit: config.rateLimitAuthFailurePerMin,
-      keyBy: 'ip',
-      trustProxy: config.trustProxy,
-      skipSuccessfulRequests: true,
-    }),
-  )
-  app.use('/v1/*', authMiddleware)
-  app.use(
-    '/v1/*',
-    createRateLimiter({
-      name: 'inference',
-      limit: config.rateLimitInferencePerMin,
-      keyBy: 'user',
-      trustProxy: config.trustProxy,
-    }),
-  )
-  app.route('/v1', openaiCompatRoutes(runtime))
+  function buildApp(runtime: RuntimeContext): { runtime: RuntimeContext; hono: Hono<AppEnv> } {
+    const app = new Hono<AppEnv>()
+
+    // CORS — bearer-token auth is the gate; CORS stays open without credentials
+    // so the bearer-Authorization model works from any origin (Tauri has no fixed origin).
+    // The actual gate is authMiddleware below.
+    const allowedOrigins = config.allowedOrigins
+      .split(',')
+      .map((o) => o.trim())
+      .filter(Boolean)
+    app.use(
+      '*',
+      cors(
+        allowedOrigins.length > 0
+          ? { origin: allowedOrigins, credentials: true }
+          : { origin: '*', credentials: false },
+      ),
+    )
+    const requestLogger = honoLogger()
+    app.use('*', (c, next) => c.req.path === '/oauth/mcp/callback' ? next() : requestLogger(c, next))
+
+    // Inject runtime into context
+    app.use('*', async (c, next) => {
+      c.set('runtime', runtime)
+      await next()
+    })
+
+    // Health check (no auth, IP-based rate limit)
+    app.use(
+      '/health',
+      createRateLimiter({
+        name: 'health',
+        limit: config.rateLimitHealthPerMin,
+        keyBy: 'ip',
+        trustProxy: config.trustProxy,
+      }),
+    )
+    app.get('/health', (c) => c.json({ status: 'ok', timestamp: Date.now() }))
+
+    // Telegram webhooks — server-to-server from Telegram, IP-based rate limit
+    app.use(
+      '/telegram/*',
+      createRateLimiter({
+        name: 'telegram',
+        limit: config.rateLimitTelegramPerMin,
+        keyBy: 'ip',
+        trustProxy: config.trustProxy,
+      }),
+    )
+    app.route('/telegram', telegramWebhookRoutes(runtime))
+
+    // MCP OAuth callback — public by design. One-time state is its sole authority.
+    app.use(
+      '/oauth/mcp/*',
+      createRateLimiter({
+        name: 'mcp-oauth-callback',
+        limit: config.rateLimitOAuthCallbackPerMin,
+        keyBy: 'ip',
+        trustProxy: config.trustProxy,
+      }),
+    )
+    app.route('/oauth/mcp', mcpOAuthCallbackRoutes(runtime))
+
+    // Remote approval page — public by design. The signed link is its sole authority.
+    app.use(
+      '/remote-approvals/*',
+      createRateLimiter({
+        name: 'remote-approvals',
+        limit: config.rateLimitRemoteApprovalPerMin,
+        keyBy: 'ip',
+        trustProxy: config.trustProxy,
+      }),
+    )
+    app.route('/remote-approvals', remoteApprovalRoutes(runtime))
+
+    // Pre-auth failure throttle, then auth + per-user rate limit for /api/*
+    app.use(
+      '/api/*',
+      createRateLimiter({
+        name: 'api-auth-failure',
+        limit: config.rateLimitAuthFailurePerMin,
+        keyBy: 'ip',
+        trustProxy: config.trustProxy,
+        skipSuccessfulRequests: true,
+      }),
+    )
+    app.use('/api/*', authMiddleware)
+    app.use(
+      '/api/*',
+      createRateLimiter({
+        name: 'api',
+        limit: config.rateLimitApiPerMin,
+        keyBy: 'user',
+        trustProxy: config.trustProxy,
+      }),
+    )
+
+    // API Routes
+    app.route('/api/audio', audioRoutes(runtime))
+    app.route('/api/attachments', attachmentRoutes(runtime))
+    app.route('/api/projects', projectRoutes(runtime))
+    app.route('/api/knowledge', knowledgeRoutes(runtime))
+    app.route('/api/memories', memoryRoutes(runtime))
+    app.route('/api/chat', chatRoutes(runtime))
+    app.route('/api/sessions', sessionRoutes(runtime))
+    app.route('/api/models', modelRoutes(runtime))
+    app.route('/api/keys', apiKeyRoutes(runtime))
+    app.route('/api/mail', mailRoutes(runtime))
+    app.route('/api/calendar', calendarRoutes(runtime))
+    app.route('/api/feeds', feedRoutes(runtime))
+    app.route('/api/webhooks', webhookRoutes(runtime))
+    app.route('/api/plugins', pluginRoutes(runtime))
+    app.route('/api/actions', actionRoutes(runtime))
+    app.route('/api/system-prompts', systemPromptRoutes(runtime))
+    app.route('/api/controller-prompts', controllerPromptRoutes(runtime))
+    app.route('/api/experiments', experimentRoutes(runtime))
+    app.route('/api/preferences', preferenceRoutes(runtime))
+    app.route('/api/profile', profileRoutes(runtime))
+    app.route('/api/tools', toolRoutes(runtime))
+    app.route('/api/usage', usageRoutes(runtime))
+    app.route('/api/audit', auditRoutes(runtime))
+    app.route('/api/metrics', metricsRoutes(runtime))
+    app.route('/api/workflows', workflowRoutes(runtime))
+    app.route('/api/mcps', mcpRoutes(runtime))
+    app.route('/api/telegram', telegramRoutes(runtime))
+    app.route('/api/workspaces', workspaceRoutes(runtime, (name) => workspaces.switch(name)))
+    app.route('/api/sync', syncRoutes(runtime))
+    app.route('/api/inspector', inspectorRoutes(runtime))
+    app.route('/api/retention', retentionRoutes(runtime))
+    app.route('/api/maintenance', maintenanceRoutes(runtime))
+    app.route('/api/windows', windowRoutes(runtime))
+
+    // OpenAI-compatible endpoint — gated like /api/* with its own rate-limit bucket
+    app.use(
+      '/v1/*',
+      createRateLimiter({
+        name: 'inference-auth-failure',
+        limit: config.rateLimitAuthFailurePerMin,
+        keyBy: 'ip',
+        trustProxy: config.trustProxy,
+        skipSuccessfulRequests: true,
+      }),
+    )
+    app.use('/v1/*', authMiddleware)
+    app.use(
+      '/v1/*',
+      createRateLimiter({
+        name: 'inference',
+        limit: config.rateLimitInferencePerMin,
+        keyBy: 'user',
+        trustProxy: config.trustProxy,
+      }),
+    )
+    app.route('/v1', openaiCompatRoutes(runtime))
+
+    return { runtime, hono: app }
+  }
 
   // Start server
   const server = serve({
-    fetch: app.fetch,
+    // Requests that arrive during a workspace switch wait for the new runtime
+    fetch: async (request, env) => {
+      await workspaces.idle()
+      return app.hono.fetch(request, env)
+    },
     port: config.port,
     hostname: config.host,
   })
@@ -181,7 +247,8 @@ async function main() {
     shuttingDown = true
     logger.info({ signal: signalName }, 'Shutting down...')
     server.close()
-    await shutdownRuntime(runtime)
+    await workspaces.idle()
+    await shutdownRuntime(workspaces.runtime)
     process.exit(0)
   }
 
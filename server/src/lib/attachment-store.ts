@@ -0,0 +1,257 @@
+import { createHash } from 'crypto'
+import fs from 'fs/promises'
+import path from 'path'
+import type { Item, ItemContentBlock } from '../domain/types.js'
+import type { ItemRepository } from '../repositories/types.js'
+import { logger } from './logger.js'
+
+const HASH_RE = /^[a-f0-9]{64}$/
+
+const SIDECARS = ['extraction', 'embeddings', 'vision'] as const
+type Sidecar = (typeof SIDECARS)[number]
+
+/** Derives the copy of an image sent to vision models; null sends the original. */
+export interface ImagePreparer {
+  /** Changes when the settings do, so cached copies made under old ones are redone. */
+  key: string
+  prepare(data: Buffer, mediaType: string | undefined): Promise<{ data: Buffer; mediaType: string } | null>
+}
+
+interface VisionCopy {
+  key: string
+  /** Absent when the original is sent as is. */
+  media_type?: string
+  data?: string
+}
+
+/**
+ * Content-addressed file store for attachment payloads (images returned by
+ * tools, MCP resources). Items keep `{ hash, bytes, media_type }` and the
+ * base64 data is read back only when a turn is sent to the model, with
+ * images swapped for their vision copy when an ImagePreparer is set.
+ */
+export class AttachmentStore {
+  constructor(
+    readonly dir: string,
+    private readonly imagePreparer?: ImagePreparer,
+  ) {}
+
+  async put(data: Buffer): Promise<string> {
+    const hash = createHash('sha256').update(data).digest('hex')
+    const file = this.pathFor(hash)
+    try {
+      await fs.access(file)
+    } catch {
+      await fs.mkdir(path.dirname(file), { recursive: true })
+      // Write then rename so a crash never leaves a truncated blob under its hash
+      const tmp = `${file}.${process.pid}.tmp`
+      await fs.writeFile(tmp, data)
+      await fs.rename(tmp, file)
+    }
+    return hash
+  }
+
+  async get(hash: string): Promise<Buffer | null> {
+    if (!HASH_RE.test(hash)) return null
+    try {
+      return await fs.readFile(this.pathFor(hash))
+    } catch {
+      return null
+    }
+  }
+
+  /** Derived data kept next to a payload, such as the text extracted from a PDF. */
+  async putExtraction(hash: string, value: unknown): Promise<void> {
+    await this.putSidecar(hash, 'extraction', value)
+  }
+
+  async getExtraction<T>(hash: string): Promise<T | null> {
+    return this.getSidecar<T>(hash, 'extraction')
+  }
+
+  /** Embeddings of an extraction's chunks, used to search large attachments. */
+  async putEmbeddings(hash: string, value: unknown): Promise<void> {
+    await this.putSidecar(hash, 'embeddings', value)
+  }
+
+  async getEmbeddings<T>(hash: string): Promise<T | null> {
+    return this.getSidecar<T>(hash, 'embeddings')
+  }
+
+  /**
+   * The payload to send a model for an image: a resized copy when the
+   * preparer makes one, cached next to the original, which stays on disk.
+   */
+  async forVision(hash: string, data: Buffer, mediaType: string | undefined): Promise<{ data: Buffer; mediaType: string | undefined }> {
+    if (!this.imagePreparer) return { data, mediaType }
+    let copy = await this.getSidecar<VisionCopy>(hash, 'vision')
+    if (copy?.key !== this.imagePreparer.key) {
+      const prepared = await this.imagePreparer.prepare(data, mediaType)
+      copy = prepared
+        ? { key: this.imagePreparer.key, media_type: prepared.mediaType, data: prepared.data.toString('base64') }
+        : { key: this.imagePreparer.key }
+      await this.putSidecar(hash, 'vision', copy)
+    }
+    return copy.data ? { data: Buffer.from(copy.data, 'base64'), mediaType: copy.media_type } : { data, mediaType }
+  }
+
+  /** Move inline `data` into the store; blocks without data pass through. */
+  async externalize(blocks: ItemContentBlock[] | null | undefined): Promise<ItemContentBlock[] | null> {
+    if (!blocks) return null
+    const stored: ItemContentBlock[] = []
+    for (const block of blocks) {
+      if (block.data === undefined) {
+        stored.push(block)
+        continue
+      }
+      const data = Buffer.from(block.data, 'base64')
+      const { data: _inline, ...rest } = block
+      stored.push({ ...rest, hash: await this.put(data), bytes: data.length })
+    }
+    return stored
+  }
+
+  /**
+   * Load payloads back into history items before building LLM content. With
+   * `recentUserTurns`, only items from that many user messages back are
+   * loaded; older payloads are left on disk and named in a short note.
+   */
+  async hydrate(items: Item[], recentUserTurns?: number): Promise<Item[]> {
+    const from = recentUserTurns ? recentStart(items, recentUserTurns) : 0
+    const hydrated: Item[] = []
+    for (const [index, item] of items.entries()) {
+      if (!item.contentBlocks?.some(needsPayload)) {
+        hydrated.push(item)
+        continue
+      }
+      const blocks: ItemContentBlock[] = []
+      for (const block of item.contentBlocks) {
+        if (!needsPayload(block)) {
+          blocks.push(block)
+          continue
+        }
+        if (index < from) {
+          blocks.push({ type: 'text', text: `[${block.type} ${block.name ?? block.hash!.slice(0, 12)} from earlier in the conversation is not resent]` })
+          continue
+        }
+        const data = await this.get(block.hash)
+        if (data && block.type === 'image') {
+          const image = await this.forVision(block.hash, data, block.media_type)
+          blocks.push({ ...block, media_type: image.mediaType, data: image.data.toString('base64') })
+        } else if (data) {
+          blocks.push({ ...block, data: data.toString('base64') })
+        } else {
+          logger.warn({ itemId: item.id, hash: block.hash }, 'Attachment payload missing from store')
+          blocks.push({ type: 'text', text: `[attachment ${block.hash.slice(0, 12)} is no longer available]` })
+        }
+      }
+      hydrated.push({ ...item, contentBlocks: blocks })
+    }
+    return hydrated
+  }
+
+  /** Every stored payload with its last write time. */
+  async list(): Promise<Array<{ hash: string; bytes: number; modifiedAt: number }>> {
+    const blobs: Array<{ hash: string; bytes: number; modifiedAt: number }> = []
+    let prefixes: string[]
+    try {
+      prefixes = await fs.readdir(this.dir)
+    } catch {
+      return blobs
+    }
+    for (const prefix of prefixes) {
+      let files: string[]
+      try {
+        files = await fs.readdir(path.join(this.dir, prefix))
+      } catch {
+        continue
+      }
+      for (const hash of files.filter((name) => HASH_RE.test(name))) {
+        const stat = await fs.stat(this.pathFor(hash))
+        blobs.push({ hash, bytes: stat.size, modifiedAt: stat.mtimeMs })
+      }
+    }
+    return blobs
+  }
+
+  async remove(hash: string): Promise<void> {
+    if (!HASH_RE.test(hash)) return
+    await fs.rm(this.pathFor(hash), { force: true })
+    for (const kind of SIDECARS) {
+      await fs.rm(`${this.pathFor(hash)}.${kind}.json`, { force: true })
+    }
+  }
+
+  private async putSidecar(hash: string, kind: Sidecar, value: unknown): Promise<void> {
+    if (!HASH_RE.test(hash)) return
+    const file = `${this.pathFor(hash)}.${kind}.json`
+    const tmp = `${file}.${process.pid}.tmp`
+    await fs.mkdir(path.dirname(file), { recursive: true })
+    await fs.writeFile(tmp, JSON.stringify(value))
+    await fs.rename(tmp, file)
+  }
+
+  private async getSidecar<T>(hash: string, kind: Sidecar): Promise<T | null> {
+    if (!HASH_RE.test(hash)) return null
+    try {
+      return JSON.parse(await fs.readFile(`${this.pathFor(hash)}.${kind}.json`, 'utf8')) as T
+    } catch {
+      return null
+    }
+  }
+
+  private pathFor(hash: string): string {
+    return path.join(this.dir, hash.slice(0, 2), hash)
+  }
+}
+
+/** Documents and OCR'd images reach the model as their extracted text, so their payload stays on disk. */
+/** Index of the `userTurns`-th latest user message, or 0 when there are fewer. */
+function recentStart(items: Item[], userTurns: number): number {
+  let seen = 0
+  for (let index = items.length - 1; index >= 0; index--) {
+    if (items[index].type === 'message' && items[index].role === 'user' && ++seen === userTurns) return index
+  }
+  return 0
+}
+
+function needsPayload(block: ItemContentBlock): boolean {
+  return Boolean(block.hash) && block.data === undefined && block.type !== 'document' && block.type !== 'video' && block.chunks === undefined && !block.retrieval
+}
+
+/** Externalizes attachment data on write; reads return metadata only. */
+export function withAttachmentStore(items: ItemRepository, store: AttachmentStore): ItemRepository {
+  return {
+    ...items,
+    create: async (input) => items.create({ ...input, contentBlocks: await store.externalize(input.contentBlocks) }),
+  }
+}
+
+/** Hashes referenced by serialized content block arrays. */
+export function collectAttachmentHashes(serialized: Array<string | null>): string[] {
+  const hashes = new Set<string>()
+  for (const raw of serialized) {
+    if (!raw) continue
+    try {
+      for (const block of JSON.parse(raw) as ItemContentBlock[]) {
+        if (block.hash) hashes.add(block.hash)
+      }
+    } catch {
+      // Malformed rows reference nothing
+    }
+  }
+  return [...hashes]
+}
+
+/** One-off move of payloads written before the store existed. */
+export async function migrateInlineAttachments(items: ItemRepository, store: AttachmentStore): Promise<number> {
+  let moved = 0
+  for (const item of await items.listWithInlineAttachments()) {
+    await items.updateContentBlocks(item.id, await store.externalize(item.contentBlocks))
+    moved++
+  }
+  if (moved > 0) {
+    logger.info({ moved }, 'Moved inline attachment payloads to the attachment store')
+  }
+  return moved
+}
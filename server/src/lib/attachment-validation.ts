@@ -0,0 +1,226 @@
+import type { ItemContentBlock } from '../domain/types.js'
+import type { UploadKind } from '../repositories/types.js'
+import { readImageInfo } from './image-info.js'
+import { isPdf } from './pdf-text.js'
+
+/**
+ * Checks attachments as they arrive, before anything is stored or sent to a
+ * model: a size cap per kind, the type read from the file's own bytes rather
+ * than the declared one, and a cap on image dimensions.
+ */
+export type AttachmentKind = UploadKind | 'audio'
+
+export const MAX_ATTACHMENT_BYTES: Record<AttachmentKind, number> = {
+  audio: 20 * 1024 * 1024,
+  image: 20 * 1024 * 1024,
+  office: 50 * 1024 * 1024,
+  pdf: 32 * 1024 * 1024,
+  text: 8 * 1024 * 1024,
+  video: 200 * 1024 * 1024,
+}
+
+/** Detected types accepted per kind; text has no signature and is checked for binary content instead. */
+const ACCEPTED_TYPES: Record<Exclude<AttachmentKind, 'text'>, string[]> = {
+  // Audio-only recordings often come in video containers
+  audio: ['audio/aac', 'audio/flac', 'audio/mp4', 'audio/mpeg', 'audio/ogg', 'audio/wav', 'video/mp4', 'video/webm', 'video/x-matroska'],
+  // Formats vision providers reject are converted when the image is sent
+  image: ['image/gif', 'image/heic', 'image/jpeg', 'image/png', 'image/tiff', 'image/webp'],
+  office: ['application/zip'],
+  pdf: ['application/pdf'],
+  video: ['video/mp4', 'video/quicktime', 'video/webm', 'video/x-matroska', 'video/x-msvideo'],
+}
+
+const KIND_LABELS: Record<AttachmentKind, string> = {
+  audio: 'an audio file',
+  image: 'an image',
+  office: 'a Word, Excel or PowerPoint (Office Open XML) file',
+  pdf: 'a PDF',
+  text: 'a text file',
+  video: 'a video',
+}
+
+export type AttachmentErrorCode = 'empty' | 'too_large' | 'invalid_encoding' | 'unsupported_type' | 'type_mismatch' | 'image_too_large'
+
+export interface AttachmentErrorDetails {
+  kind: AttachmentKind
+  fileName?: string
+  /** Byte or pixel limit that was exceeded. */
+  limit?: number
+  actual?: number
+  declaredType?: string
+  detectedType?: string
+}
+
+export class AttachmentValidationError extends Error {
+  constructor(
+    readonly code: AttachmentErrorCode,
+    message: string,
+    readonly details: AttachmentErrorDetails,
+  ) {
+    super(message)
+  }
+
+  /** HTTP status for the error: 413 for oversized files, 400 otherwise. */
+  get status(): 400 | 413 {
+    return this.code === 'too_large' ? 413 : 400
+  }
+
+  toJSON(): { error: string; code: AttachmentErrorCode; details: AttachmentErrorDetails } {
+    return { error: this.message, code: this.code, details: this.details }
+  }
+}
+
+export interface AttachmentCheck {
+  kind: AttachmentKind
+  declaredType?: string
+  fileName?: string
+  /** Longest accepted image side in pixels; images are not measured without it. */
+  maxImageDimension?: number
+}
+
+export interface ValidatedAttachment {
+  data: Buffer
+  /** Type read from the content; null for text. */
+  mediaType: string | null
+  width?: number
+  height?: number
+}
+
+export function validateAttachment(bytes: ArrayBuffer | Uint8Array, check: AttachmentCheck): ValidatedAttachment {
+  const data = Buffer.from(bytes instanceof ArrayBuffer ? new Uint8Array(bytes) : bytes)
+  const details: AttachmentErrorDetails = {
+    kind: check.kind,
+    ...(check.fileName && { fileName: check.fileName }),
+    ...(check.declaredType && { declaredType: check.declaredType }),
+  }
+  const label = check.fileName || 'Attachment'
+
+  if (data.length === 0) throw new AttachmentValidationError('empty', `${label} is empty`, details)
+  const limit = MAX_ATTACHMENT_BYTES[check.kind]
+  if (data.length > limit) {
+    throw new AttachmentValidationError('too_large', `${label} is too large (${data.length} bytes, limit ${limit})`, {
+      ...details, limit, actual: data.length,
+    })
+  }
+
+  const detected = sniffMediaType(data)
+  if (check.kind === 'text') {
+    if (looksBinary(data)) {
+      throw new AttachmentValidationError('type_mismatch', `${label} is not ${KIND_LABELS.text}`, {
+        ...details, ...(detected && { detectedType: detected }),
+      })
+    }
+    return { data, mediaType: null }
+  }
+
+  if (!detected) {
+    throw new AttachmentValidationError('unsupported_type', `${label} is not ${KIND_LABELS[check.kind]}`, details)
+  }
+  if (!ACCEPTED_TYPES[check.kind].includes(detected)) {
+    const code = detected.startsWith(`${check.kind}/`) ? 'unsupported_type' : 'type_mismatch'
+    throw new AttachmentValidationError(code, `${label} is ${detected}, not ${KIND_LABELS[check.kind]}`, {
+      ...details, detectedType: detected,
+    })
+  }
+
+  if (check.kind !== 'image') return { data, mediaType: detected }
+  const info = readImageInfo(data)
+  if (info && check.maxImageDimension) {
+    const longest = Math.max(info.width, info.height)
+    if (longest > check.maxImageDimension) {
+      throw new AttachmentValidationError(
+        'image_too_large',
+        `${label} is ${info.width}×${info.height} pixels; the limit is ${check.maxImageDimension} on either side`,
+        { ...details, detectedType: detected, limit: check.maxImageDimension, actual: longest },
+      )
+    }
+  }
+  return { data, mediaType: detected, ...(info && { width: info.width, height: info.height }) }
+}
+
+/**
+ * Validates base64 payloads sent inline with a message. Each block's
+ * `media_type` is replaced by the detected one; blocks without data pass
+ * through.
+ */
+export function validateInlineBlocks(
+  blocks: ItemContentBlock[] | null | undefined,
+  options: { maxImageDimension?: number },
+): ItemContentBlock[] | null | undefined {
+  if (!blocks?.some((block) => block.data !== undefined)) return blocks
+  return blocks.map((block) => {
+    if (block.data === undefined) return block
+    const kind = inlineKind(block)
+    const data = decodeBase64(block.data)
+    if (!data) {
+      throw new AttachmentValidationError('invalid_encoding', `${block.name || 'Attachment'} is not valid base64`, {
+        kind, ...(block.name && { fileName: block.name }),
+      })
+    }
+    const validated = validateAttachment(data, {
+      kind, declaredType: block.media_type, fileName: block.name, maxImageDimension: options.maxImageDimension,
+    })
+    return {
+      ...block,
+      data: validated.data.toString('base64'),
+      ...(validated.mediaType && { media_type: validated.mediaType }),
+    }
+  })
+}
+
+/** Text has no signature, but a NUL byte near the start means binary content. */
+export function looksBinary(data: Buffer): boolean {
+  return data.subarray(0, 8192).includes(0)
+}
+
+/** The content type from the file's leading bytes, or null when none is recognized. */
+export function sniffMediaType(data: Buffer): string | null {
+  const ascii = (start: number, end: number) => data.toString('latin1', start, end)
+  if (data.length >= 8 && data.readUInt32BE(0) === 0x89504e47) return 'image/png'
+  if (data.length >= 3 && data[0] === 0xff && data[1] === 0xd8 && data[2] === 0xff) return 'image/jpeg'
+  if (ascii(0, 4) === 'GIF8') return 'image/gif'
+  if (ascii(0, 4) === 'RIFF') {
+    const form = ascii(8, 12)
+    if (form === 'WEBP') return 'image/webp'
+    if (form === 'WAVE') return 'audio/wav'
+    if (form === 'AVI ') return 'video/x-msvideo'
+  }
+  if (ascii(0, 4) === 'II*\0' || ascii(0, 4) === 'MM\0*') return 'image/tiff'
+  if (ascii(0, 4) === 'PK\x03\x04') return 'application/zip'
+  if (data.length >= 8 && data.readUInt32BE(0) === 0xd0cf11e0 && data.readUInt32BE(4) === 0xa1b11ae1) {
+    return 'application/x-ole-storage'
+  }
+  if (ascii(4, 8) === 'ftyp') {
+    const brand = ascii(8, 12)
+    if (brand === 'qt  ') return 'video/quicktime'
+    if (brand === 'M4A ' || brand === 'M4B ') return 'audio/mp4'
+    if (['heic', 'heix', 'mif1', 'msf1'].includes(brand)) return 'image/heic'
+    return 'video/mp4'
+  }
+  if (data.length >= 4 && data.readUInt32BE(0) === 0x1a45dfa3) {
+    return ascii(0, 64).includes('webm') ? 'video/webm' : 'video/x-matroska'
+  }
+  // PDFs may have junk before the header, so this looks further than the fixed signatures
+  if (isPdf(data)) return 'application/pdf'
+  if (ascii(0, 4) === 'OggS') return 'audio/ogg'
+  if (ascii(0, 4) === 'fLaC') return 'audio/flac'
+  if (ascii(0, 3) === 'ID3') return 'audio/mpeg'
+  // MPEG audio frame sync; layer bits of 00 mark AAC in ADTS framing
+  if (data.length >= 2 && data[0] === 0xff && (data[1] & 0xe0) === 0xe0) {
+    return (data[1] & 0x06) === 0 ? 'audio/aac' : 'audio/mpeg'
+  }
+  return null
+}
+
+function inlineKind(block: ItemContentBlock): AttachmentKind {
+  if (block.type === 'image' || block.type === 'video') return block.type
+  if (block.media_type === 'application/pdf') return 'pdf'
+  return block.media_type?.startsWith('application/vnd.openxmlformats-officedocument.') ? 'office' : 'text'
+}
+
+function decodeBase64(value: string): Buffer | null {
+  // Accept data URLs as well as bare base64
+  const encoded = value.replace(/^data:[^,]*;base64,/, '').replace(/\s+/g, '')
+  if (encoded.length % 4 !== 0 || !/^[A-Za-z0-9+/]*={0,2}$/.test(encoded)) return null
+  return Buffer.from(encoded, 'base64')
+}
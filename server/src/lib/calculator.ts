@@ -0,0 +1,155 @@
+/**
+ * Arithmetic without eval: numbers, + - * / ^, parentheses, postfix % (per
+ * cent), the constants pi and e, and a few math functions.
+ */
+
+const FUNCTIONS: Record<string, (...args: number[]) => number> = {
+  sqrt: Math.sqrt,
+  cbrt: Math.cbrt,
+  abs: Math.abs,
+  round: (value, digits = 0) => Math.round(value * 10 ** digits) / 10 ** digits,
+  floor: Math.floor,
+  ceil: Math.ceil,
+  ln: Math.log,
+  log: Math.log10,
+  log2: Math.log2,
+  exp: Math.exp,
+  sin: Math.sin,
+  cos: Math.cos,
+  tan: Math.tan,
+  asin: Math.asin,
+  acos: Math.acos,
+  atan: Math.atan,
+  min: Math.min,
+  max: Math.max,
+}
+
+const CONSTANTS: Record<string, number> = { pi: Math.PI, e: Math.E }
+const OPERATOR_ALIASES: Record<string, string> = { '**': '^', '×': '*', '÷': '/' }
+const MAX_EXPRESSION_LENGTH = 1000
+
+export class CalculatorError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'CalculatorError'
+  }
+}
+
+type Token = { type: 'number'; value: number } | { type: 'name'; value: string } | { type: 'op'; value: string }
+
+export function evaluateExpression(expression: string): number {
+  if (expression.length > MAX_EXPRESSION_LENGTH) throw new CalculatorError('Expression is too long')
+  const tokens = tokenize(expression)
+  let position = 0
+
+  const peek = () => tokens[position]
+  const isOp = (value: string) => peek()?.type === 'op' && peek()!.value === value
+  const expect = (value: string) => {
+    if (!isOp(value)) throw new CalculatorError(`Expected "${value}"`)
+    position++
+  }
+
+  // additive := term (("+" | "-") term)*
+  const additive = (): number => {
+    let value = term()
+    while (isOp('+') || isOp('-')) {
+      const op = tokens[position++].value
+      value = op === '+' ? value + term() : value - term()
+    }
+    return value
+  }
+  // term := unary (("*" | "/") unary)*
+  const term = (): number => {
+    let value = unary()
+    while (isOp('*') || isOp('/')) {
+      const op = tokens[position++].value
+      const right = unary()
+      if (op === '/' && right === 0) throw new CalculatorError('Division by zero')
+      value = op === '*' ? value * right : value / right
+    }
+    return value
+  }
+  // unary := ("-" | "+") unary | power
+  const unary = (): number => {
+    if (isOp('-')) {
+      position++
+      return -unary()
+    }
+    if (isOp('+')) {
+      position++
+      return unary()
+    }
+    return power()
+  }
+  // power := postfix ("^" unary)?  (right-associative, binds tighter than unary minus on its left)
+  const power = (): number => {
+    const base = postfix()
+    if (isOp('^')) {
+      position++
+      return base ** unary()
+    }
+    return base
+  }
+  // postfix := primary "%"*
+  const postfix = (): number => {
+    let value = primary()
+    while (isOp('%')) {
+      position++
+      value /= 100
+    }
+    return value
+  }
+  const primary = (): number => {
+    const token = tokens[position++]
+    if (!token) throw new CalculatorError('Unexpected end of expression')
+    if (token.type === 'number') return token.value
+    if (token.type === 'op' && token.value === '(') {
+      const value = additive()
+      expect(')')
+      return value
+    }
+    if (token.type === 'name') {
+      if (Object.hasOwn(FUNCTIONS, token.value)) {
+        const fn = FUNCTIONS[token.value]
+        expect('(')
+        const args = [additive()]
+        while (isOp(',')) {
+          position++
+          args.push(additive())
+        }
+        expect(')')
+        return fn(...args)
+      }
+      if (Object.hasOwn(CONSTANTS, token.value)) return CONSTANTS[token.value]
+      throw new CalculatorError(`Unknown name "${token.value}"`)
+    }
+    throw new CalculatorError(`Unexpected "${token.value}"`)
+  }
+
+  const result = additive()
+  if (position < tokens.length) throw new CalculatorError(`Unexpected "${tokens[position].value}"`)
+  if (!Number.isFinite(result)) throw new CalculatorError('Result is not a finite number')
+  return result
+}
+
+function tokenize(expression: string): Token[] {
+  const tokens: Token[] = []
+  // Thousands separators ("1,000") would clash with argument commas, so only "_" and spaces are allowed
+  const pattern = /\s*(?:(\d[\d_]*(?:\.\d+)?(?:e[+-]?\d+)?|\.\d+)|([a-z][a-z0-9]*)|(\*\*|[-+*/^%(),×÷]))/giy
+  let match: RegExpExecArray | null
+  let index = 0
+  while (index < expression.length) {
+    pattern.lastIndex = index
+    match = pattern.exec(expression)
+    if (!match) {
+      if (!expression.slice(index).trim()) break
+      throw new CalculatorError(`Unexpected character "${expression.slice(index).trim()[0]}"`)
+    }
+    index = pattern.lastIndex
+    if (match[1] !== undefined) tokens.push({ type: 'number', value: Number(match[1].replace(/_/g, '')) })
+    else if (match[2] !== undefined) tokens.push({ type: 'name', value: match[2].toLowerCase() })
+    else tokens.push({ type: 'op', value: OPERATOR_ALIASES[match[3]] ?? match[3] })
+  }
+  if (tokens.length === 0) throw new CalculatorError('Expression is empty')
+  return tokens
+}
@@ -1,4 +1,5 @@
 import { z } from 'zod'
+import { applyWorkspace } from './workspace.js'
 
 const boolFromEnv = z.preprocess((v) => {
   if (typeof v !== 'string') return v
@@ -11,32 +12,137 @@ const configSchema = z.object({
   host: z.string().default('localhost'),
   dbDialect: z.enum(['sqlite', 'postgres']).default('sqlite'),
   databaseUrl: z.string().default('./data/app.db'),
+  databaseKey: z.string().optional(),
+  databaseKeySource: z.enum(['env', 'keychain']).default('env'),
+  databaseBusyTimeoutMs: z.coerce.number().default(5000),
+  databasePoolSize: z.coerce.number().default(10),
   defaultModel: z.string().default('anthropic:claude-sonnet-4-20250514'),
   audioTranscriptionModel: z.string().optional(),
   telegramTranscriptionModel: z.string().optional(),
+  pdfOcrModel: z.string().optional(),
+  imageOcrModel: z.string().optional(),
+  tesseractBin: z.string().default('tesseract'),
+  attachmentInlineMaxChars: z.coerce.number().int().positive().default(12000),
+  attachmentEmbeddingModel: z.string().optional(),
+  whisperCppBin: z.string().default('whisper-cli'),
+  ffmpegBin: z.string().default('ffmpeg'),
+  visionImageMaxDimension: z.coerce.number().int().positive().default(1568),
+  visionImageMaxBytes: z.coerce.number().int().positive().default(3_750_000),
+  videoFrameCount: z.coerce.number().int().positive().max(32).default(8),
+  attachmentImageMaxDimension: z.coerce.number().int().positive().default(16384),
+  imageGenerationModel: z.string().optional(),
+  memoryModel: z.string().optional(),
+  titleModel: z.string().optional(),
+  routingModel: z.string().optional(),
+  routingQuickModel: z.string().optional(),
+  routingAgentModel: z.string().optional(),
+  routingCodingModel: z.string().optional(),
+  memoryEmbeddingModel: z.string().optional(),
+  embeddingMaintenanceIntervalMs: z.coerce.number().int().positive().default(15 * 60_000),
+  embeddingMaintenanceMaxDocuments: z.coerce.number().int().positive().default(100),
+  feedPollIntervalMs: z.coerce.number().int().positive().default(30 * 60_000),
+  digestTime: z.string().regex(/^([01]?\d|2[0-3]):[0-5]\d$/, 'DIGEST_TIME must be HH:MM').optional(),
+  digestAgent: z.string().optional(),
+  digestWebhookUrl: z.string().optional(),
   publicBaseUrl: z.string().optional(),
   encryptionKey: z.string().optional(),
   anthropicApiKey: z.string().optional(),
   openaiApiKey: z.string().optional(),
   ollamaBaseUrl: z.string().optional(),
   openrouterApiKey: z.string().optional(),
+  mockProviderScript: z.string().optional(),
+  stabilityApiKey: z.string().optional(),
+  githubToken: z.string().optional(),
+  githubApiUrl: z.string().default('https://api.github.com'),
+  notionToken: z.string().optional(),
+  jiraUrl: z.string().optional(),
+  jiraEmail: z.string().optional(),
+  jiraToken: z.string().optional(),
+  linearApiKey: z.string().optional(),
+  todoBackend: z.enum(['todoist', 'reminders']).optional(),
+  todoistApiToken: z.string().optional(),
+  obsidianVaultDir: z.string().optional(),
+  personalNotesBackend: z.enum(['obsidian', 'apple_notes']).optional(),
+  contactsBackend: z.enum(['macos']).optional(),
+  homeAssistantUrl: z.string().optional(),
+  homeAssistantToken: z.string().optional(),
+  mediaBackend: z.enum(['spotify', 'macos']).optional(),
+  spotifyClientId: z.string().optional(),
+  spotifyClientSecret: z.string().optional(),
+  spotifyRefreshToken: z.string().optional(),
   workingDir: z.string().optional(),
   agentsDir: z.string().default('./agents'),
   tasksDir: z.string().default('./data/tasks'),
   workspaceDir: z.string().default('./data/workspace'),
   sessionFilesDir: z.string().default('./data/sessions'),
+  attachmentsDir: z.string().default('./data/attachments'),
   inlineOutputLimitBytes: z.coerce.number().default(32 * 1024),
+  sessionInlineOutputBudgetChars: z.coerce.number().int().min(0).default(160_000),
+  // --- history loading (0 = no limit) ---
+  historyWindowTurns: z.coerce.number().int().min(0).default(0),
+  attachmentHistoryTurns: z.coerce.number().int().min(0).default(10),
   workflowsDir: z.string().default('./workflows'),
+  notesDir: z.string().default('./data/research-notes'),
+  projectsDir: z.string().default('./data/projects'),
+  pluginsDir: z.string().default('./data/plugins'),
+  // --- workspaces ---
+  workspace: z.string().optional(),
+  workspacesDir: z.string().default('./data/workspaces'),
+  trashRetentionDays: z.coerce.number().default(30),
+  // --- tool execution ---
+  toolPoolSize: z.coerce.number().int().min(1).default(16),
+  toolPoolPerSession: z.coerce.number().int().min(1).default(4),
+  maxConcurrentRuns: z.coerce.number().int().min(1).default(4),
+  conversationMaxConcurrentSends: z.coerce.number().int().min(1).default(1),
+  conversationMinSendIntervalMs: z.coerce.number().int().min(0).default(0),
+  shutdownGraceMs: z.coerce.number().int().min(0).default(5000),
+  stuckRunMultiple: z.coerce.number().min(0).default(3),
+  connectivityProbeHost: z.string().default('one.one.one.one'),
+  connectivityCheckMs: z.coerce.number().int().min(1000).default(15_000),
+  offlineModel: z.string().optional(),
+  // --- response verification ---
+  verifyResponses: boolFromEnv.default(false),
+  verifierModel: z.string().optional(),
+  // --- tool approvals ---
+  approvalTimeoutMs: z.coerce.number().default(0),
+  approvalEscalationMaxCalls: z.coerce.number().int().min(0).default(5),
+  approvalEscalationMaxMutations: z.coerce.number().int().min(0).default(500),
+  filesApprovalFreeRoots: z.string().default(''),
+  remoteApprovals: boolFromEnv.default(false),
+  remoteApprovalWebhookUrl: z.string().optional(),
+  remoteApprovalLinkTtlMs: z.coerce.number().default(24 * 60 * 60 * 1000),
+  // --- usage pricing ---
+  pricingCatalogUrl: z.string().default('https://raw.githubusercontent.com/BerriAI/litellm/main/model_prices_and_context_window.json'),
+  exchangeRateUrl: z.string().default('https://open.er-api.com/v6/latest/USD'),
+  // --- file-based sync ---
+  syncDir: z.string().optional(),
+  syncDeviceId: z.string().optional(),
+  syncIntervalMs: z.coerce.number().default(60_000),
+  // --- event streaming ---
+  eventBatchMs: z.coerce.number().default(16),
+  eventMaxPerSecond: z.coerce.number().default(60),
+  // --- local WebSocket bridge ---
+  wsBridgeEnabled: boolFromEnv.default(false),
+  wsBridgePort: z.coerce.number().default(3002),
+  // --- local Prometheus endpoint ---
+  prometheusEnabled: boolFromEnv.default(false),
+  prometheusPort: z.coerce.number().default(9464),
   // --- security / hardening ---
   allowedOrigins: z.string().default(''),
   trustProxy: boolFromEnv.default(false),
   enableShellTool: boolFromEnv.default(false),
+  enablePythonTool: boolFromEnv.default(false),
+  pythonBin: z.string().default('python3'),
+  pythonTimeoutMs: z.coerce.number().int().positive().default(30_000),
+  pythonMaxTimeoutMs: z.coerce.number().int().positive().default(300_000),
+  pythonMemoryMb: z.coerce.number().int().positive().default(1024),
   rateLimitAuthFailurePerMin: z.coerce.number().default(120),
   rateLimitApiPerMin: z.coerce.number().default(60),
   rateLimitInferencePerMin: z.coerce.number().default(60),
   rateLimitTelegramPerMin: z.coerce.number().default(600),
   rateLimitHealthPerMin: z.coerce.number().default(20),
   rateLimitOAuthCallbackPerMin: z.coerce.number().default(30),
+  rateLimitRemoteApprovalPerMin: z.coerce.number().default(30),
   // --- LLM observability ---
   langfuseEnabled: boolFromEnv.optional(),
   langfuseBaseUrl: z.string().default('https://cloud.langfuse.com'),
@@ -44,48 +150,164 @@ const configSchema = z.object({
   langfuseSecretKey: z.string().optional(),
   langfuseCaptureContent: boolFromEnv.default(false),
   langfuseMaxContentChars: z.coerce.number().default(20000),
+  // --- local debug traces ---
+  debugTraces: boolFromEnv.default(false),
+  tracesDir: z.string().default('./data/traces'),
+  debugTraceMaxBytes: z.coerce.number().default(256 * 1024),
+  debugTracesMaxTotalBytes: z.coerce.number().default(50 * 1024 * 1024),
+  debugTraceRetentionDays: z.coerce.number().default(7),
+  // --- audit log ---
+  auditLog: boolFromEnv.default(false),
+  auditDir: z.string().default('./data/audit'),
 })
 
 export type AppConfig = z.infer<typeof configSchema>
 
 export function loadConfig(): AppConfig {
+  return applyWorkspace(loadEnvConfig())
+}
+
+/** The config as the environment gives it, before a workspace's paths are applied. */
+export function loadEnvConfig(): AppConfig {
   const config = configSchema.parse({
     nodeEnv: process.env.NODE_ENV,
     port: process.env.PORT,
     host: process.env.HOST,
     dbDialect: process.env.DB_DIALECT,
     databaseUrl: process.env.DATABASE_URL,
+    databaseKey: process.env.DATABASE_KEY || undefined,
+    databaseKeySource: process.env.DATABASE_KEY_SOURCE || undefined,
+    databaseBusyTimeoutMs: process.env.DATABASE_BUSY_TIMEOUT_MS,
+    databasePoolSize: process.env.DATABASE_POOL_SIZE,
     defaultModel: process.env.DEFAULT_MODEL,
     audioTranscriptionModel: process.env.AUDIO_TRANSCRIPTION_MODEL,
     telegramTranscriptionModel: process.env.TELEGRAM_TRANSCRIPTION_MODEL,
+    pdfOcrModel: process.env.PDF_OCR_MODEL,
+    imageOcrModel: process.env.IMAGE_OCR_MODEL,
+    tesseractBin: process.env.TESSERACT_BIN,
+    attachmentInlineMaxChars: process.env.ATTACHMENT_INLINE_MAX_CHARS,
+    attachmentEmbeddingModel: process.env.ATTACHMENT_EMBEDDING_MODEL,
+    whisperCppBin: process.env.WHISPER_CPP_BIN,
+    ffmpegBin: process.env.FFMPEG_BIN,
+    visionImageMaxDimension: process.env.VISION_IMAGE_MAX_DIMENSION,
+    visionImageMaxBytes: process.env.VISION_IMAGE_MAX_BYTES,
+    videoFrameCount: process.env.VIDEO_FRAME_COUNT,
+    attachmentImageMaxDimension: process.env.ATTACHMENT_IMAGE_MAX_DIMENSION,
+    imageGenerationModel: process.env.IMAGE_GENERATION_MODEL || undefined,
+    memoryModel: process.env.MEMORY_MODEL || undefined,
+    titleModel: process.env.TITLE_MODEL || undefined,
+    routingModel: process.env.ROUTING_MODEL || undefined,
+    routingQuickModel: process.env.ROUTING_QUICK_MODEL || undefined,
+    routingAgentModel: process.env.ROUTING_AGENT_MODEL || undefined,
+    routingCodingModel: process.env.ROUTING_CODING_MODEL || undefined,
+    memoryEmbeddingModel: process.env.MEMORY_EMBEDDING_MODEL || undefined,
+    embeddingMaintenanceIntervalMs: process.env.EMBEDDING_MAINTENANCE_INTERVAL_MS,
+    embeddingMaintenanceMaxDocuments: process.env.EMBEDDING_MAINTENANCE_MAX_DOCUMENTS,
+    feedPollIntervalMs: process.env.FEED_POLL_INTERVAL_MS,
+    digestTime: process.env.DIGEST_TIME || undefined,
+    digestAgent: process.env.DIGEST_AGENT || undefined,
+    digestWebhookUrl: process.env.DIGEST_WEBHOOK_URL || undefined,
     publicBaseUrl: process.env.PUBLIC_BASE_URL,
     encryptionKey: process.env.ENCRYPTION_KEY,
     anthropicApiKey: process.env.ANTHROPIC_API_KEY,
     openaiApiKey: process.env.OPENAI_API_KEY,
     ollamaBaseUrl: process.env.OLLAMA_BASE_URL,
     openrouterApiKey: process.env.OPENROUTER_API_KEY,
+    mockProviderScript: process.env.MOCK_PROVIDER_SCRIPT,
+    stabilityApiKey: process.env.STABILITY_API_KEY || undefined,
+    githubToken: process.env.GITHUB_TOKEN || undefined,
+    githubApiUrl: process.env.GITHUB_API_URL || undefined,
+    notionToken: process.env.NOTION_TOKEN || undefined,
+    jiraUrl: process.env.JIRA_URL || undefined,
+    jiraEmail: process.env.JIRA_EMAIL || undefined,
+    jiraToken: process.env.JIRA_TOKEN || undefined,
+    linearApiKey: process.env.LINEAR_API_KEY || undefined,
+    todoBackend: process.env.TODO_BACKEND || undefined,
+    todoistApiToken: process.env.TODOIST_API_TOKEN || undefined,
+    obsidianVaultDir: process.env.OBSIDIAN_VAULT_DIR || undefined,
+    personalNotesBackend: process.env.PERSONAL_NOTES_BACKEND || undefined,
+    contactsBackend: process.env.CONTACTS_BACKEND || undefined,
+    homeAssistantUrl: process.env.HOME_ASSISTANT_URL || undefined,
+    homeAssistantToken: process.env.HOME_ASSISTANT_TOKEN || undefined,
+    mediaBackend: process.env.MEDIA_BACKEND || undefined,
+    spotifyClientId: process.env.SPOTIFY_CLIENT_ID || undefined,
+    spotifyClientSecret: process.env.SPOTIFY_CLIENT_SECRET || undefined,
+    spotifyRefreshToken: process.env.SPOTIFY_REFRESH_TOKEN || undefined,
     workingDir: process.env.WORKING_DIR,
     agentsDir: process.env.AGENTS_DIR,
     tasksDir: process.env.TASKS_DIR,
     workspaceDir: process.env.WORKSPACE_DIR,
     sessionFilesDir: process.env.SESSION_FILES_DIR,
+    attachmentsDir: process.env.ATTACHMENTS_DIR,
     inlineOutputLimitBytes: process.env.INLINE_OUTPUT_LIMIT_BYTES,
+    sessionInlineOutputBudgetChars: process.env.SESSION_INLINE_OUTPUT_BUDGET_CHARS,
+    historyWindowTurns: process.env.HISTORY_WINDOW_TURNS,
+    attachmentHistoryTurns: process.env.ATTACHMENT_HISTORY_TURNS,
     workflowsDir: process.env.WORKFLOWS_DIR,
+    notesDir: process.env.NOTES_DIR,
+    projectsDir: process.env.PROJECTS_DIR,
+    pluginsDir: process.env.PLUGINS_DIR,
+    workspace: process.env.WORKSPACE || undefined,
+    workspacesDir: process.env.WORKSPACES_DIR,
+    trashRetentionDays: process.env.TRASH_RETENTION_DAYS,
+    toolPoolSize: process.env.TOOL_POOL_SIZE,
+    toolPoolPerSession: process.env.TOOL_POOL_PER_SESSION,
+    maxConcurrentRuns: process.env.MAX_CONCURRENT_RUNS,
+    conversationMaxConcurrentSends: process.env.CONVERSATION_MAX_CONCURRENT_SENDS,
+    conversationMinSendIntervalMs: process.env.CONVERSATION_MIN_SEND_INTERVAL_MS,
+    shutdownGraceMs: process.env.SHUTDOWN_GRACE_MS,
+    stuckRunMultiple: process.env.STUCK_RUN_MULTIPLE,
+    connectivityProbeHost: process.env.CONNECTIVITY_PROBE_HOST || undefined,
+    connectivityCheckMs: process.env.CONNECTIVITY_CHECK_MS,
+    offlineModel: process.env.OFFLINE_MODEL || undefined,
+    verifyResponses: process.env.VERIFY_RESPONSES,
+    verifierModel: process.env.VERIFIER_MODEL || undefined,
+    approvalTimeoutMs: process.env.APPROVAL_TIMEOUT_MS,
+    approvalEscalationMaxCalls: process.env.APPROVAL_ESCALATION_MAX_CALLS,
+    approvalEscalationMaxMutations: process.env.APPROVAL_ESCALATION_MAX_MUTATIONS,
+    filesApprovalFreeRoots: process.env.FILES_APPROVAL_FREE_ROOTS,
+    remoteApprovals: process.env.REMOTE_APPROVALS,
+    remoteApprovalWebhookUrl: process.env.REMOTE_APPROVAL_WEBHOOK_URL || undefined,
+    remoteApprovalLinkTtlMs: process.env.REMOTE_APPROVAL_LINK_TTL_MS,
+    pricingCatalogUrl: process.env.PRICING_CATALOG_URL || undefined,
+    exchangeRateUrl: process.env.EXCHANGE_RATE_URL || undefined,
+    syncDir: process.env.SYNC_DIR || undefined,
+    syncDeviceId: process.env.SYNC_DEVICE_ID || undefined,
+    syncIntervalMs: process.env.SYNC_INTERVAL_MS,
+    eventBatchMs: process.env.EVENT_BATCH_MS,
+    eventMaxPerSecond: process.env.EVENT_MAX_PER_SECOND,
+    wsBridgeEnabled: process.env.WS_BRIDGE_ENABLED,
+    wsBridgePort: process.env.WS_BRIDGE_PORT,
+    prometheusEnabled: process.env.PROMETHEUS_ENABLED,
+    prometheusPort: process.env.PROMETHEUS_PORT,
     allowedOrigins: process.env.ALLOWED_ORIGINS,
     trustProxy: process.env.TRUST_PROXY,
     enableShellTool: process.env.ENABLE_SHELL_TOOL,
+    enablePythonTool: process.env.ENABLE_PYTHON_TOOL,
+    pythonBin: process.env.PYTHON_BIN,
+    pythonTimeoutMs: process.env.PYTHON_TIMEOUT_MS,
+    pythonMaxTimeoutMs: process.env.PYTHON_MAX_TIMEOUT_MS,
+    pythonMemoryMb: process.env.PYTHON_MEMORY_MB,
     rateLimitAuthFailurePerMin: process.env.RATE_LIMIT_AUTH_FAILURE_PER_MIN,
     rateLimitApiPerMin: process.env.RATE_LIMIT_API_PER_MIN,
     rateLimitInferencePerMin: process.env.RATE_LIMIT_INFERENCE_PER_MIN,
     rateLimitTelegramPerMin: process.env.RATE_LIMIT_TELEGRAM_PER_MIN,
     rateLimitHealthPerMin: process.env.RATE_LIMIT_HEALTH_PER_MIN,
     rateLimitOAuthCallbackPerMin: process.env.RATE_LIMIT_OAUTH_CALLBACK_PER_MIN,
+    rateLimitRemoteApprovalPerMin: process.env.RATE_LIMIT_REMOTE_APPROVAL_PER_MIN,
     langfuseEnabled: process.env.LANGFUSE_ENABLED,
     langfuseBaseUrl: process.env.LANGFUSE_BASE_URL,
     langfusePublicKey: process.env.LANGFUSE_PUBLIC_KEY,
     langfuseSecretKey: process.env.LANGFUSE_SECRET_KEY,
     langfuseCaptureContent: process.env.LANGFUSE_CAPTURE_CONTENT,
     langfuseMaxContentChars: process.env.LANGFUSE_MAX_CONTENT_CHARS,
+    debugTraces: process.env.DEBUG_TRACES,
+    tracesDir: process.env.TRACES_DIR,
+    debugTraceMaxBytes: process.env.DEBUG_TRACE_MAX_BYTES,
+    debugTracesMaxTotalBytes: process.env.DEBUG_TRACES_MAX_TOTAL_BYTES,
+    debugTraceRetentionDays: process.env.DEBUG_TRACE_RETENTION_DAYS,
+    auditLog: process.env.AUDIT_LOG,
+    auditDir: process.env.AUDIT_DIR,
   })
 
   if (
@@ -95,5 +317,9 @@ export function loadConfig(): AppConfig {
     throw new Error('DB_DIALECT=postgres requires DATABASE_URL to be a postgres:// or postgresql:// connection string')
   }
 
+  if ((config.databaseKey || config.databaseKeySource === 'keychain') && config.dbDialect !== 'sqlite') {
+    throw new Error('DATABASE_KEY and DATABASE_KEY_SOURCE only apply to DB_DIALECT=sqlite; use storage-level encryption for postgres')
+  }
+
   return config
 }
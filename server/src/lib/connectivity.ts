@@ -0,0 +1,111 @@
+import { promises as dns } from 'node:dns'
+import { logger } from './logger.js'
+
+/** Providers that run on this machine and keep working without a connection. */
+const LOCAL_PROVIDERS = new Set(['ollama', 'mock'])
+
+const NETWORK_ERROR = /ENOTFOUND|EAI_AGAIN|ENETUNREACH|ENETDOWN|EHOSTUNREACH|ECONNREFUSED|ECONNRESET|ETIMEDOUT|getaddrinfo|fetch failed|network/i
+
+export interface ConnectivityOptions {
+  /** Host resolved through DNS to tell whether the machine is online. */
+  probeHost: string
+  /** A result is trusted this long; while offline, the probe repeats at this interval. */
+  checkMs: number
+  /** Model runs switch to while offline, e.g. `ollama:llama3.2`. */
+  offlineModel?: string
+  /** Replaces the DNS probe; rejects when offline. */
+  probe?: () => Promise<void>
+}
+
+/** Whether a thrown error looks like the network failing, rather than the provider answering with an error. */
+export function isNetworkError(err: unknown): boolean {
+  if (typeof err !== 'object' || err === null) return false
+  const { message, code, cause } = err as { message?: unknown; code?: unknown; cause?: unknown }
+  if (typeof code === 'string' && NETWORK_ERROR.test(code)) return true
+  if (typeof message === 'string' && NETWORK_ERROR.test(message)) return true
+  return cause !== undefined && cause !== err && isNetworkError(cause)
+}
+
+export function isLocalProvider(provider: string): boolean {
+  return LOCAL_PROVIDERS.has(provider)
+}
+
+/**
+ * Tracks whether the machine can reach the internet, so runs fail fast (or
+ * move to a local model) instead of ending in a connection error, and
+ * messages queued while offline are sent once the connection returns.
+ */
+export class ConnectivityMonitor {
+  private online = true
+  private checkedAt = 0
+  private probing: Promise<boolean> | null = null
+  private timer: NodeJS.Timeout | null = null
+  private readonly listeners = new Set<() => void>()
+
+  constructor(private readonly options: ConnectivityOptions) {}
+
+  get offlineModel(): string | undefined {
+    return this.options.offlineModel
+  }
+
+  /** The last known state, probing again when it is older than `checkMs`. */
+  async isOnline(): Promise<boolean> {
+    if (Date.now() - this.checkedAt < this.options.checkMs) return this.online
+    return this.check()
+  }
+
+  /** Probes now; concurrent callers share one probe. */
+  check(): Promise<boolean> {
+    this.probing ??= this.probe().finally(() => {
+      this.probing = null
+    })
+    return this.probing
+  }
+
+  /** Called each time the connection comes back. Returns an unsubscribe function. */
+  onOnline(listener: () => void): () => void {
+    this.listeners.add(listener)
+    return () => this.listeners.delete(listener)
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer)
+      this.timer = null
+    }
+  }
+
+  private async probe(): Promise<boolean> {
+    let online: boolean
+    try {
+      await (this.options.probe ?? (() => resolveHost(this.options.probeHost, this.options.checkMs)))()
+      online = true
+    } catch {
+      online = false
+    }
+    const changed = online !== this.online
+    this.online = online
+    this.checkedAt = Date.now()
+    if (!changed) return online
+
+    if (online) {
+      logger.info('Network connection restored')
+      this.stop()
+      for (const listener of this.listeners) listener()
+    } else {
+      logger.warn({ probeHost: this.options.probeHost }, 'Network connection lost')
+      // Poll, so queued messages go out without waiting for the next run
+      this.timer = setInterval(() => {
+        void this.check()
+      }, this.options.checkMs)
+      this.timer.unref()
+    }
+    return online
+  }
+}
+
+/** Asks the DNS servers directly; the system resolver may answer from its cache or the hosts file. */
+async function resolveHost(host: string, timeoutMs: number): Promise<void> {
+  const resolver = new dns.Resolver({ timeout: Math.min(timeoutMs, 3000), tries: 1 })
+  await resolver.resolve(host)
+}
@@ -0,0 +1,89 @@
+import { randomBytes } from 'crypto'
+import { statSync } from 'fs'
+import path from 'path'
+import Database from 'better-sqlite3'
+import type { AppConfig } from './config.js'
+import type { Keychain } from './keychain.js'
+import { logger } from './logger.js'
+import { applyDatabaseKey, encryptDatabaseFile, rekeyDatabase } from '../repositories/sqlite/index.js'
+
+type DatabaseKeyConfig = Pick<AppConfig, 'databaseUrl' | 'databaseKey' | 'databaseKeySource'>
+
+const KEYCHAIN_LABEL = 'AI Agent database key'
+
+/** One keychain entry per database file, so each workspace keeps its own key. */
+export function databaseKeyAccount(databaseUrl: string): string {
+  return `database-key:${path.resolve(databaseUrl)}`
+}
+
+export function generateDatabaseKey(): string {
+  return randomBytes(32).toString('base64url')
+}
+
+/**
+ * The key to open the sqlite database with: DATABASE_KEY when set, otherwise
+ * the keychain's entry when DATABASE_KEY_SOURCE=keychain. On first run, with
+ * no database file yet, a key is generated and saved so the new database
+ * starts out encrypted. An existing database without a saved key is refused
+ * rather than opened as plaintext; encrypt-db migrates it.
+ */
+export async function resolveDatabaseKey(config: DatabaseKeyConfig, keychain: Keychain): Promise<string | undefined> {
+  if (config.databaseKey) return config.databaseKey
+  if (config.databaseKeySource !== 'keychain') return undefined
+  const account = databaseKeyAccount(config.databaseUrl)
+  const stored = await keychain.get(account)
+  if (stored) return stored
+  if (databaseExists(config.databaseUrl)) {
+    throw new Error('DATABASE_KEY_SOURCE=keychain but the keychain has no key for this database; run `bun run encrypt-db` to encrypt it')
+  }
+  const key = generateDatabaseKey()
+  await keychain.set(account, key, KEYCHAIN_LABEL)
+  logger.info({ path: config.databaseUrl }, 'Generated a database key and saved it in the OS keychain')
+  return key
+}
+
+/**
+ * Encrypts a plaintext database, or rotates the key of an encrypted one
+ * (`oldKey`, or the keychain's entry). With DATABASE_KEY_SOURCE=keychain the
+ * new key is DATABASE_KEY or a generated one, and it is saved before the
+ * file changes; the previous entry is put back if the change fails.
+ */
+export async function encryptDatabase(config: DatabaseKeyConfig, keychain: Keychain, oldKey?: string): Promise<{ rotated: boolean }> {
+  const useKeychain = config.databaseKeySource === 'keychain'
+  if (!config.databaseKey && !useKeychain) {
+    throw new Error('Set DATABASE_KEY, or DATABASE_KEY_SOURCE=keychain to keep a generated key in the OS keychain')
+  }
+  const account = databaseKeyAccount(config.databaseUrl)
+  const saved = useKeychain ? await keychain.get(account) : null
+  const previous = oldKey ?? saved ?? undefined
+  const key = config.databaseKey ?? generateDatabaseKey()
+  if (useKeychain) await keychain.set(account, key, KEYCHAIN_LABEL)
+  try {
+    if (previous) {
+      const sqlite = new Database(config.databaseUrl, { fileMustExist: true })
+      try {
+        applyDatabaseKey(sqlite, previous)
+        rekeyDatabase(sqlite, key)
+      } finally {
+        sqlite.close()
+      }
+    } else {
+      encryptDatabaseFile(config.databaseUrl, key)
+    }
+  } catch (err) {
+    if (useKeychain) {
+      if (saved) await keychain.set(account, saved, KEYCHAIN_LABEL)
+      else await keychain.delete(account)
+    }
+    throw err
+  }
+  return { rotated: Boolean(previous) }
+}
+
+function databaseExists(file: string): boolean {
+  try {
+    return statSync(file).size > 0
+  } catch {
+    return false
+  }
+}
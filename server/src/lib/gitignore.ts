@@ -0,0 +1,83 @@
+/**
+ * The subset of .gitignore syntax folder ingestion needs: comments, `!`
+ * negation, trailing `/` for directories only, leading or inner `/` to
+ * anchor a pattern to its file's directory, and `*`, `?`, `**` globs.
+ * Character classes and escapes are not supported.
+ */
+interface IgnoreRule {
+  regex: RegExp
+  negated: boolean
+  directoryOnly: boolean
+}
+
+export class IgnoreRules {
+  private constructor(private readonly rules: IgnoreRule[]) {}
+
+  static empty(): IgnoreRules {
+    return new IgnoreRules([])
+  }
+
+  /** Adds the patterns of an ignore file in `base`, a directory relative to the walk root ('' for the root). */
+  with(content: string, base = ''): IgnoreRules {
+    const rules = content.split(/\r?\n/).map((line) => compileRule(line, base)).filter((rule): rule is IgnoreRule => rule !== null)
+    return rules.length > 0 ? new IgnoreRules([...this.rules, ...rules]) : this
+  }
+
+  /** Whether a path relative to the walk root is ignored; the last matching pattern wins. */
+  ignores(relativePath: string, isDirectory: boolean): boolean {
+    let ignored = false
+    for (const rule of this.rules) {
+      if (rule.directoryOnly && !isDirectory) continue
+      if (rule.regex.test(relativePath)) ignored = !rule.negated
+    }
+    return ignored
+  }
+}
+
+function compileRule(line: string, base: string): IgnoreRule | null {
+  let pattern = line.replace(/\s+$/, '')
+  if (!pattern || pattern.startsWith('#')) return null
+  const negated = pattern.startsWith('!')
+  if (negated) pattern = pattern.slice(1)
+  const directoryOnly = pattern.endsWith('/')
+  if (directoryOnly) pattern = pattern.replace(/\/+$/, '')
+  if (!pattern) return null
+
+  // A slash anywhere but the end anchors the pattern to the ignore file's directory
+  const anchored = pattern.includes('/')
+  pattern = pattern.replace(/^\/+/, '')
+  const prefix = base ? `${escapeRegex(base)}/` : ''
+  const body = globToRegex(pattern)
+  const regex = anchored
+    ? new RegExp(`^${prefix}${body}$`)
+    : new RegExp(`^${prefix}(?:.*/)?${body}$`)
+  return { regex, negated, directoryOnly }
+}
+
+function globToRegex(glob: string): string {
+  let out = ''
+  for (let i = 0; i < glob.length; i++) {
+    const char = glob[i]
+    if (char === '*' && glob[i + 1] === '*') {
+      // `**/` matches any number of directories, a trailing `**` everything below
+      if (glob[i + 2] === '/') {
+        out += '(?:.*/)?'
+        i += 2
+      } else {
+        out += '.*'
+        i += 1
+      }
+    } else if (char === '*') {
+      out += '[^/]*'
+    } else if (char === '?') {
+      out += '[^/]'
+    } else {
+      out += escapeRegex(char)
+    }
+  }
+  return out
+}
+
+function escapeRegex(text: string): string {
+  return text.replace(/[.*+?^${}()|[\]\\]/g, '\\$&')
+}
@@ -0,0 +1,56 @@
+/**
+ * Reads an image's format and pixel size from its header, without decoding
+ * it. Covers the formats vision providers accept; anything else is null.
+ */
+export interface ImageInfo {
+  mediaType: 'image/png' | 'image/jpeg' | 'image/gif' | 'image/webp'
+  width: number
+  height: number
+}
+
+export function readImageInfo(data: Buffer): ImageInfo | null {
+  if (data.length >= 24 && data.readUInt32BE(0) === 0x89504e47 && data.toString('latin1', 12, 16) === 'IHDR') {
+    return { mediaType: 'image/png', width: data.readUInt32BE(16), height: data.readUInt32BE(20) }
+  }
+  if (data.length >= 10 && data.toString('latin1', 0, 4) === 'GIF8') {
+    return { mediaType: 'image/gif', width: data.readUInt16LE(6), height: data.readUInt16LE(8) }
+  }
+  if (data.length >= 4 && data[0] === 0xff && data[1] === 0xd8) return readJpeg(data)
+  if (data.length >= 30 && data.toString('latin1', 0, 4) === 'RIFF' && data.toString('latin1', 8, 12) === 'WEBP') {
+    return readWebp(data)
+  }
+  return null
+}
+
+function readJpeg(data: Buffer): ImageInfo | null {
+  let offset = 2
+  while (offset + 9 < data.length) {
+    if (data[offset] !== 0xff) return null
+    const marker = data[offset + 1]
+    if (marker === 0xff) {
+      offset++
+      continue
+    }
+    // SOF0–SOF15 carry the frame size; C4 (DHT), C8 (JPG) and CC (DAC) share the range but do not
+    if (marker >= 0xc0 && marker <= 0xcf && marker !== 0xc4 && marker !== 0xc8 && marker !== 0xcc) {
+      return { mediaType: 'image/jpeg', width: data.readUInt16BE(offset + 7), height: data.readUInt16BE(offset + 5) }
+    }
+    offset += 2 + data.readUInt16BE(offset + 2)
+  }
+  return null
+}
+
+function readWebp(data: Buffer): ImageInfo | null {
+  const chunk = data.toString('latin1', 12, 16)
+  if (chunk === 'VP8 ') {
+    return { mediaType: 'image/webp', width: data.readUInt16LE(26) & 0x3fff, height: data.readUInt16LE(28) & 0x3fff }
+  }
+  if (chunk === 'VP8L') {
+    const bits = data.readUInt32LE(21)
+    return { mediaType: 'image/webp', width: (bits & 0x3fff) + 1, height: ((bits >>> 14) & 0x3fff) + 1 }
+  }
+  if (chunk === 'VP8X') {
+    return { mediaType: 'image/webp', width: data.readUIntLE(24, 3) + 1, height: data.readUIntLE(27, 3) + 1 }
+  }
+  return null
+}
@@ -0,0 +1,73 @@
+import { execFile } from 'child_process'
+
+/** Service name the app's entries are stored under. */
+export const KEYCHAIN_SERVICE = 'ai-agent'
+
+/** Secrets kept by the operating system rather than in files or the environment. */
+export interface Keychain {
+  get(account: string): Promise<string | null>
+  set(account: string, value: string, label: string): Promise<void>
+  delete(account: string): Promise<void>
+}
+
+export class KeychainError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'KeychainError'
+  }
+}
+
+/** Runs a command with `input` on stdin; rejects with the exit code on failure. */
+export type KeychainCommandRunner = (command: string, args: string[], input?: string) => Promise<string>
+
+const runCommand: KeychainCommandRunner = (command, args, input) =>
+  new Promise((resolve, reject) => {
+    const child = execFile(command, args, { timeout: 30_000, maxBuffer: 1024 * 1024 }, (err, stdout, stderr) => {
+      if (err) reject(Object.assign(new KeychainError(`${command} failed: ${(stderr || err.message).trim()}`), { exitCode: err.code }))
+      else resolve(stdout)
+    })
+    child.stdin?.end(input ?? '')
+  })
+
+/**
+ * The macOS login keychain through `security`, or the Secret Service
+ * (GNOME Keyring, KWallet) through `secret-tool` on Linux. Other platforms
+ * get a keychain whose every call fails, so callers can fall back to
+ * DATABASE_KEY.
+ */
+export function systemKeychain(platform: NodeJS.Platform = process.platform, run: KeychainCommandRunner = runCommand): Keychain {
+  if (platform === 'darwin') {
+    // security exits 44 when no item matches
+    return {
+      get: (account) => run('security', ['find-generic-password', '-s', KEYCHAIN_SERVICE, '-a', account, '-w'])
+        .then((out) => out.trim() || null, (err) => notFound(err, 44)),
+      set: async (account, value, label) => {
+        await run('security', ['add-generic-password', '-U', '-s', KEYCHAIN_SERVICE, '-a', account, '-l', label, '-w', value])
+      },
+      delete: (account) => run('security', ['delete-generic-password', '-s', KEYCHAIN_SERVICE, '-a', account])
+        .then(() => undefined, (err) => notFound(err, 44).then(() => undefined)),
+    }
+  }
+  if (platform === 'linux') {
+    // secret-tool exits 1 with no output when no item matches; the value goes over stdin
+    return {
+      get: (account) => run('secret-tool', ['lookup', 'service', KEYCHAIN_SERVICE, 'account', account])
+        .then((out) => out.trim() || null, (err) => notFound(err, 1)),
+      set: async (account, value, label) => {
+        await run('secret-tool', ['store', `--label=${label}`, 'service', KEYCHAIN_SERVICE, 'account', account], value)
+      },
+      delete: async (account) => {
+        await run('secret-tool', ['clear', 'service', KEYCHAIN_SERVICE, 'account', account])
+      },
+    }
+  }
+  const unsupported = async (): Promise<never> => {
+    throw new KeychainError(`No supported OS keychain on ${platform}; set DATABASE_KEY instead`)
+  }
+  return { get: unsupported, set: unsupported, delete: unsupported }
+}
+
+async function notFound(err: unknown, exitCode: number): Promise<null> {
+  if ((err as { exitCode?: unknown }).exitCode === exitCode) return null
+  throw err
+}
@@ -0,0 +1,177 @@
+import path from 'path'
+import { child, children, descendants, parseXml, textContent, type XmlElement } from './xml.js'
+import { readZip, type ZipArchive } from './zip.js'
+
+/**
+ * Minimal Office Open XML reader for attachment text. Word documents keep
+ * headings, list items and tables; spreadsheets become one Markdown table per
+ * sheet using cached cell values; presentations become one section per slide
+ * with speaker notes. Formatting, images and charts are dropped.
+ */
+export type OfficeFormat = 'docx' | 'xlsx' | 'pptx'
+
+export interface OfficeContent {
+  /** Markdown sections: the whole document, or one per sheet or slide. */
+  sections: string[]
+}
+
+interface Relationship {
+  target: string
+  type: string
+}
+
+/** Upper bound for a single inflated part, so a small archive cannot expand without bound. */
+const MAX_PART_BYTES = 256 * 1024 * 1024
+
+/** Inline markup whose text is not part of the visible content. */
+const SKIPPED_TEXT = new Set(['delText', 'instrText', 'pPr', 'rPh'])
+
+export function extractOfficeContent(data: Buffer, format: OfficeFormat): OfficeContent {
+  const zip = readZip(data, MAX_PART_BYTES)
+  if (format === 'docx') return { sections: [docxText(zip)] }
+  if (format === 'xlsx') return { sections: xlsxSheets(zip) }
+  return { sections: pptxSlides(zip) }
+}
+
+function docxText(zip: ZipArchive): string {
+  const document = part(zip, 'word/document.xml')
+  if (!document) throw new Error('Not a Word document: word/document.xml is missing')
+  return blocks(document, docxParagraph).join('\n\n')
+}
+
+function docxParagraph(paragraph: XmlElement): string {
+  const text = runText(paragraph).trim()
+  if (!text) return ''
+  const properties = child(paragraph, 'pPr')
+  const style = properties && child(properties, 'pStyle')?.attrs['w:val']
+  const heading = style?.match(/^Heading(\d)$/i)
+  if (heading) return `${'#'.repeat(Number(heading[1]))} ${text}`
+  if (style === 'Title') return `# ${text}`
+  if (properties && child(properties, 'numPr')) return `- ${text}`
+  return text
+}
+
+function xlsxSheets(zip: ZipArchive): string[] {
+  const workbook = part(zip, 'xl/workbook.xml')
+  if (!workbook) throw new Error('Not an Excel workbook: xl/workbook.xml is missing')
+  const relationships = readRelationships(zip, 'xl/workbook.xml')
+  const sharedStrings = descendants(part(zip, 'xl/sharedStrings.xml'), 'si').map(runText)
+
+  return descendants(workbook, 'sheet').map((sheet) => {
+    const target = relationships.get(sheet.attrs['r:id'])?.target
+    const rows: string[][] = []
+    for (const row of descendants(target ? part(zip, target) : null, 'row')) {
+      const cells: string[] = []
+      for (const cell of children(row, 'c')) {
+        const column = cell.attrs.r ? columnIndex(cell.attrs.r) : cells.length
+        while (cells.length < column) cells.push('')
+        cells[column] = cellValue(cell, sharedStrings)
+      }
+      if (cells.some(Boolean)) rows.push(cells)
+    }
+    const title = `## Sheet: ${sheet.attrs.name ?? 'Untitled'}`
+    return rows.length ? `${title}\n\n${markdownTable(rows)}` : `${title}\n\n(empty)`
+  })
+}
+
+function cellValue(cell: XmlElement, sharedStrings: string[]): string {
+  const value = textContent(child(cell, 'v'))
+  switch (cell.attrs.t) {
+    case 's':
+      return sharedStrings[Number(value)] ?? ''
+    case 'inlineStr':
+      return runText(child(cell, 'is') ?? cell)
+    case 'b':
+      return value === '1' ? 'TRUE' : 'FALSE'
+    default:
+      return value
+  }
+}
+
+function pptxSlides(zip: ZipArchive): string[] {
+  const presentation = part(zip, 'ppt/presentation.xml')
+  if (!presentation) throw new Error('Not a PowerPoint presentation: ppt/presentation.xml is missing')
+  const relationships = readRelationships(zip, 'ppt/presentation.xml')
+
+  return descendants(presentation, 'sldId').map((slideId, index) => {
+    const target = relationships.get(slideId.attrs['r:id'])?.target
+    const slide = target ? part(zip, target) : null
+    const sections = [`## Slide ${index + 1}`, ...blocks(slide, (paragraph) => runText(paragraph).trim())]
+
+    const notesTarget = target
+      ? [...readRelationships(zip, target).values()].find((rel) => rel.type.endsWith('/notesSlide'))?.target
+      : undefined
+    // Notes slides also repeat the slide image and number; only the body placeholder holds the notes
+    const notes = descendants(notesTarget ? part(zip, notesTarget) : null, 'sp')
+      .filter((shape) => descendants(shape, 'ph').some((placeholder) => placeholder.attrs.type === 'body'))
+      .flatMap((shape) => blocks(shape, (paragraph) => runText(paragraph).trim()))
+    if (notes.length) sections.push(`Notes:\n${notes.join('\n')}`)
+    return sections.join('\n\n')
+  })
+}
+
+/** Paragraphs and tables in document order; tables become Markdown with one line per row. */
+function blocks(element: XmlElement | null, paragraph: (element: XmlElement) => string): string[] {
+  const out: string[] = []
+  for (const node of element?.children ?? []) {
+    if (typeof node === 'string') continue
+    if (node.name === 'tbl') {
+      const rows = children(node, 'tr').map((row) => children(row, 'tc').map((cell) => blocks(cell, paragraph).join(' ')))
+      if (rows.length) out.push(markdownTable(rows))
+    } else if (node.name === 'p') {
+      const text = paragraph(node)
+      if (text) out.push(text)
+    } else {
+      out.push(...blocks(node, paragraph))
+    }
+  }
+  return out
+}
+
+export function markdownTable(rows: string[][]): string {
+  const width = Math.max(...rows.map((row) => row.length))
+  const line = (row: string[]) => `| ${Array.from({ length: width }, (_, i) => escapeCell(row[i] ?? '')).join(' | ')} |`
+  return [line(rows[0]), `|${' --- |'.repeat(width)}`, ...rows.slice(1).map(line)].join('\n')
+}
+
+function escapeCell(value: string): string {
+  return value.replace(/\|/g, '\\|').replace(/\s*\n\s*/g, ' ').trim()
+}
+
+/** `B12` → 1 */
+function columnIndex(reference: string): number {
+  let index = 0
+  for (const letter of reference.match(/^[A-Z]+/i)?.[0].toUpperCase() ?? 'A') index = index * 26 + letter.charCodeAt(0) - 64
+  return index - 1
+}
+
+function runText(element: XmlElement): string {
+  let text = ''
+  for (const node of element.children) {
+    if (typeof node === 'string') continue
+    if (node.name === 't') text += textContent(node)
+    else if (node.name === 'tab') text += '\t'
+    else if (node.name === 'br' || node.name === 'cr') text += '\n'
+    else if (!SKIPPED_TEXT.has(node.name)) text += runText(node)
+  }
+  return text
+}
+
+function readRelationships(zip: ZipArchive, partName: string): Map<string, Relationship> {
+  const dir = path.posix.dirname(partName)
+  const rels = part(zip, path.posix.join(dir, '_rels', `${path.posix.basename(partName)}.rels`))
+  const relationships = new Map<string, Relationship>()
+  for (const rel of descendants(rels, 'Relationship')) {
+    if (rel.attrs.TargetMode === 'External' || !rel.attrs.Target) continue
+    const target = rel.attrs.Target.startsWith('/')
+      ? rel.attrs.Target.slice(1)
+      : path.posix.normalize(path.posix.join(dir, rel.attrs.Target))
+    relationships.set(rel.attrs.Id, { target, type: rel.attrs.Type ?? '' })
+  }
+  return relationships
+}
+
+function part(zip: ZipArchive, name: string): XmlElement | null {
+  const data = zip.read(name)
+  return data ? parseXml(data.toString('utf8')) : null
+}
@@ -0,0 +1,19 @@
+import { execFile } from 'child_process'
+
+export type OsaScriptRunner = (script: string, args: string[], signal?: AbortSignal) => Promise<string>
+
+export class OsaScriptError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'OsaScriptError'
+  }
+}
+
+/** Runs JavaScript for Automation; arguments go through argv, never into the script text. */
+export const runOsaScript: OsaScriptRunner = (script, args, signal) =>
+  new Promise((resolve, reject) => {
+    execFile('osascript', ['-l', 'JavaScript', '-e', script, ...args], { signal, timeout: 30_000, maxBuffer: 16 * 1024 * 1024 }, (err, stdout, stderr) => {
+      if (err) reject(new OsaScriptError(`osascript failed: ${(stderr || err.message).trim()}`))
+      else resolve(stdout.trim())
+    })
+  })
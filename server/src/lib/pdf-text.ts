@@ -0,0 +1,457 @@
+import { inflateSync } from 'zlib'
+
+/**
+ * Minimal PDF reader for attachment text. It walks the page tree, inflates
+ * content streams and reads text-showing operators, mapping glyph codes
+ * through each font's ToUnicode CMap when one exists. Layout is approximate
+ * (a line break per text line) and encrypted PDFs are not supported.
+ */
+export interface PdfContent {
+  /** Text of each page in document order; empty for pages without a text layer. */
+  pages: string[]
+  /** Embedded JPEG images in object order, typically the pages of a scanned document. */
+  images: Buffer[]
+}
+
+interface PdfObject {
+  body: string
+  stream: Buffer | null
+}
+
+type CMap = { codeBytes: number; map: Map<number, string> }
+
+const REF_RE = /^(\d+)\s+\d+\s+R/
+/** Inflated size allowed per stream and per document, so a small Flate stream cannot expand without bound. */
+const MAX_STREAM_BYTES = 16 * 1024 * 1024
+const MAX_DECODED_BYTES = 64 * 1024 * 1024
+
+export function isPdf(data: Uint8Array): boolean {
+  return Buffer.from(data.subarray(0, 1024)).toString('latin1').includes('%PDF-')
+}
+
+export function extractPdfContent(data: Buffer): PdfContent {
+  const objects = parseObjects(data)
+  const pages = pageObjects(objects).map((page) => pageText(objects, page))
+  const images: Buffer[] = []
+  for (const object of objects.values()) {
+    if (object.stream && /\/Subtype\s*\/Image\b/.test(object.body) && filters(object.body).includes('DCTDecode')) {
+      images.push(object.stream)
+    }
+  }
+  return { pages, images }
+}
+
+/** Splits text into chunks of at most `maxChars`, preferring paragraph and line boundaries. */
+export function chunkText(text: string, maxChars = 4000): string[] {
+  const chunks: string[] = []
+  let current = ''
+  const flush = () => {
+    if (current.trim()) chunks.push(current.trim())
+    current = ''
+  }
+  for (const paragraph of text.split(/\n{2,}/)) {
+    if (current && current.length + paragraph.length + 2 > maxChars) flush()
+    if (paragraph.length <= maxChars) {
+      current = current ? `${current}\n\n${paragraph}` : paragraph
+      continue
+    }
+    for (const line of paragraph.split('\n')) {
+      if (current && current.length + line.length + 1 > maxChars) flush()
+      for (let start = 0; start < line.length; start += maxChars) {
+        const piece = line.slice(start, start + maxChars)
+        if (current && current.length + piece.length + 1 > maxChars) flush()
+        current = current ? `${current}\n${piece}` : piece
+      }
+    }
+  }
+  flush()
+  return chunks
+}
+
+// ---------------------------------------------------------------------------
+// Objects
+// ---------------------------------------------------------------------------
+
+function parseObjects(data: Buffer): Map<number, PdfObject> {
+  const text = data.toString('latin1')
+  const objects = new Map<number, PdfObject>()
+  let budget = MAX_DECODED_BYTES
+  const header = /(\d+)\s+\d+\s+obj\b/g
+  let match: RegExpExecArray | null
+  while ((match = header.exec(text))) {
+    const start = match.index + match[0].length
+    const end = text.indexOf('endobj', start)
+    if (end === -1) break
+    const streamAt = text.indexOf('stream', start)
+    if (streamAt !== -1 && streamAt < end) {
+      let dataStart = streamAt + 'stream'.length
+      if (text[dataStart] === '\r') dataStart++
+      if (text[dataStart] === '\n') dataStart++
+      const body = text.slice(start, streamAt)
+      // Trust a direct /Length; otherwise the data runs up to `endstream`, less the line break before it
+      const length = Number(dictValue(body, 'Length'))
+      const exact = Number.isInteger(length) && length >= 0 && text.startsWith('endstream', skipEol(text, dataStart + length))
+      const dataEnd = text.indexOf('endstream', exact ? dataStart + length : dataStart)
+      if (dataEnd === -1) break
+      const raw = exact ? data.subarray(dataStart, dataStart + length) : trimEol(data.subarray(dataStart, dataEnd))
+      const stream = decodeStream(body, raw, Math.min(MAX_STREAM_BYTES, budget))
+      if (stream && stream !== raw) budget -= stream.length
+      objects.set(Number(match[1]), { body, stream })
+      header.lastIndex = dataEnd
+    } else {
+      objects.set(Number(match[1]), { body: text.slice(start, end), stream: null })
+      header.lastIndex = end
+    }
+  }
+
+  // PDF 1.5+ packs most dictionaries, page objects included, into object streams
+  for (const object of [...objects.values()]) {
+    if (!object.stream || !/\/Type\s*\/ObjStm\b/.test(object.body)) continue
+    const first = Number(dictValue(object.body, 'First'))
+    const decoded = object.stream.toString('latin1')
+    const offsets = decoded.slice(0, first).trim().split(/\s+/).map(Number)
+    for (let i = 0; i + 1 < offsets.length; i += 2) {
+      const from = first + offsets[i + 1]
+      const to = i + 3 < offsets.length ? first + offsets[i + 3] : decoded.length
+      if (!objects.has(offsets[i])) objects.set(offsets[i], { body: decoded.slice(from, to), stream: null })
+    }
+  }
+  return objects
+}
+
+function filters(body: string): string[] {
+  const value = dictValue(body, 'Filter')
+  return value ? [...value.matchAll(/\/(\w+)/g)].map((m) => m[1]) : []
+}
+
+/** A stream that inflates past `maxBytes` is treated as undecodable. */
+function decodeStream(body: string, raw: Buffer, maxBytes: number): Buffer | null {
+  const applied = filters(body)
+  if (applied.length === 0 || applied[0] === 'DCTDecode') return raw
+  if (applied.length !== 1 || applied[0] !== 'FlateDecode') return null
+  try {
+    return inflateSync(raw, { maxOutputLength: Math.max(1, maxBytes) })
+  } catch {
+    return null
+  }
+}
+
+function skipEol(text: string, index: number): number {
+  while (text[index] === '\r' || text[index] === '\n') index++
+  return index
+}
+
+function trimEol(raw: Buffer): Buffer {
+  let end = raw.length
+  while (end > 0 && (raw[end - 1] === 0x0a || raw[end - 1] === 0x0d)) end--
+  return raw.subarray(0, end)
+}
+
+/** Raw text of a dictionary entry: a nested dictionary, array, reference, or single token. */
+function dictValue(body: string, key: string): string | null {
+  const match = new RegExp(`/${key}(?![\\w.#-])\\s*`).exec(body)
+  if (!match) return null
+  const start = match.index + match[0].length
+  const rest = body.slice(start)
+  if (rest.startsWith('<<')) return balanced(rest, '<<', '>>')
+  if (rest.startsWith('[')) return balanced(rest, '[', ']')
+  const ref = REF_RE.exec(rest)
+  if (ref) return ref[0]
+  return /^\/?[^\s/<>[\]()]*/.exec(rest)?.[0] ?? null
+}
+
+function balanced(text: string, open: string, close: string): string {
+  let depth = 0
+  for (let i = 0; i < text.length; i++) {
+    if (text.startsWith(open, i)) {
+      depth++
+      i += open.length - 1
+    } else if (text.startsWith(close, i)) {
+      depth--
+      i += close.length - 1
+      if (depth === 0) return text.slice(0, i + 1)
+    }
+  }
+  return text
+}
+
+/** Follows an indirect reference to the object's body; direct values are returned as is. */
+function resolve(objects: Map<number, PdfObject>, value: string | null): string | null {
+  if (!value) return null
+  const ref = REF_RE.exec(value)
+  return ref ? objects.get(Number(ref[1]))?.body ?? null : value
+}
+
+function refs(value: string): number[] {
+  return [...value.matchAll(/(\d+)\s+\d+\s+R/g)].map((m) => Number(m[1]))
+}
+
+// ---------------------------------------------------------------------------
+// Pages
+// ---------------------------------------------------------------------------
+
+interface Page {
+  object: PdfObject
+  resources: string | null
+}
+
+function pageObjects(objects: Map<number, PdfObject>): Page[] {
+  const catalog = [...objects.values()].find((o) => /\/Type\s*\/Catalog\b/.test(o.body))
+  const root = catalog ? REF_RE.exec(dictValue(catalog.body, 'Pages') ?? '') : null
+  if (!root) {
+    return [...objects.entries()]
+      .filter(([, o]) => /\/Type\s*\/Page\b/.test(o.body))
+      .sort(([a], [b]) => a - b)
+      .map(([, object]) => ({ object, resources: resolve(objects, dictValue(object.body, 'Resources')) }))
+  }
+
+  const pages: Page[] = []
+  const seen = new Set<number>()
+  const walk = (id: number, inherited: string | null) => {
+    const node = objects.get(id)
+    if (!node || seen.has(id)) return
+    seen.add(id)
+    const resources = resolve(objects, dictValue(node.body, 'Resources')) ?? inherited
+    const kids = dictValue(node.body, 'Kids')
+    if (kids) {
+      for (const kid of refs(resolve(objects, kids) ?? '')) walk(kid, resources)
+    } else if (/\/Type\s*\/Page\b/.test(node.body)) {
+      pages.push({ object: node, resources })
+    }
+  }
+  walk(Number(root[1]), null)
+  return pages
+}
+
+function pageText(objects: Map<number, PdfObject>, page: Page): string {
+  const contents = dictValue(page.object.body, 'Contents')
+  if (!contents) return ''
+  const streams: Buffer[] = []
+  for (const id of refs(contents)) {
+    const target = objects.get(id)
+    if (target?.stream) streams.push(target.stream)
+    // A reference may point at an array of content streams
+    else if (target) for (const inner of refs(target.body)) {
+      const stream = objects.get(inner)?.stream
+      if (stream) streams.push(stream)
+    }
+  }
+
+  const fonts = new Map<string, CMap>()
+  const fontDict = resolve(objects, dictValue(page.resources ?? '', 'Font'))
+  for (const [, name, id] of (fontDict ?? '').matchAll(/\/([^\s/<>[\]()]+)\s*(\d+)\s+\d+\s+R/g)) {
+    const font = objects.get(Number(id))
+    const cmapRef = font ? REF_RE.exec(dictValue(font.body, 'ToUnicode') ?? '') : null
+    const cmap = cmapRef ? objects.get(Number(cmapRef[1]))?.stream : null
+    if (cmap) fonts.set(name, parseCMap(cmap.toString('latin1')))
+  }
+
+  return showText(Buffer.concat(streams).toString('latin1'), fonts)
+    .replace(/[ \t]+\n/g, '\n')
+    .replace(/\n{3,}/g, '\n\n')
+    .trim()
+}
+
+function parseCMap(source: string): CMap {
+  const map = new Map<number, string>()
+  let codeBytes = 1
+  const hex = (value: string) => parseInt(value, 16)
+  const unicode = (value: string) => {
+    const units: number[] = []
+    for (let i = 0; i + 4 <= value.length; i += 4) units.push(hex(value.slice(i, i + 4)))
+    return value.length <= 2 ? String.fromCharCode(hex(value)) : String.fromCharCode(...units)
+  }
+
+  const range = /<([0-9a-fA-F]+)>/.exec(source.slice(source.indexOf('begincodespacerange')))
+  if (range) codeBytes = Math.max(1, range[1].length / 2)
+
+  for (const [, block] of source.matchAll(/beginbfchar([\s\S]*?)endbfchar/g)) {
+    for (const [, src, dst] of block.matchAll(/<([0-9a-fA-F]+)>\s*<([0-9a-fA-F]*)>/g)) map.set(hex(src), unicode(dst))
+  }
+  for (const [, block] of source.matchAll(/beginbfrange([\s\S]*?)endbfrange/g)) {
+    for (const [, lo, hi, dst, list] of block.matchAll(/<([0-9a-fA-F]+)>\s*<([0-9a-fA-F]+)>\s*(?:<([0-9a-fA-F]+)>|\[([^\]]*)\])/g)) {
+      const from = hex(lo)
+      const to = Math.min(hex(hi), from + 0xffff)
+      if (dst !== undefined) {
+        const base = unicode(dst)
+        const last = base.charCodeAt(base.length - 1)
+        for (let code = from; code <= to; code++) map.set(code, base.slice(0, -1) + String.fromCharCode(last + code - from))
+      } else {
+        const targets = [...list.matchAll(/<([0-9a-fA-F]+)>/g)].map((m) => unicode(m[1]))
+        targets.forEach((target, i) => map.set(from + i, target))
+      }
+    }
+  }
+  return { codeBytes, map }
+}
+
+// ---------------------------------------------------------------------------
+// Content streams
+// ---------------------------------------------------------------------------
+
+type Operand = string | number | Operand[] | { name: string }
+
+function showText(content: string, fonts: Map<string, CMap>): string {
+  let out = ''
+  let font: CMap | undefined
+  let lastY: number | null = null
+  const stack: Operand[][] = [[]]
+  const operands = () => stack[0]
+  const push = (value: Operand) => stack[stack.length - 1].push(value)
+  const newline = () => {
+    if (out && !out.endsWith('\n')) out += '\n'
+  }
+  const write = (raw: string) => {
+    out += decodeString(raw, font)
+  }
+
+  let i = 0
+  while (i < content.length) {
+    const ch = content[i]
+    if (/\s/.test(ch)) {
+      i++
+    } else if (ch === '%') {
+      while (i < content.length && content[i] !== '\n' && content[i] !== '\r') i++
+    } else if (ch === '(') {
+      const [value, next] = readLiteral(content, i)
+      push(value)
+      i = next
+    } else if (ch === '<' && content[i + 1] !== '<') {
+      const end = content.indexOf('>', i)
+      const digits = content.slice(i + 1, end === -1 ? content.length : end).replace(/\s+/g, '')
+      const padded = digits.length % 2 ? `${digits}0` : digits
+      push(Buffer.from(padded, 'hex').toString('latin1'))
+      i = end === -1 ? content.length : end + 1
+    } else if (ch === '[') {
+      stack.push([])
+      i++
+    } else if (ch === ']') {
+      const array = stack.length > 1 ? stack.pop()! : []
+      push(array)
+      i++
+    } else if (ch === '<' || ch === '>' || ch === '{' || ch === '}') {
+      i += content[i + 1] === ch ? 2 : 1
+    } else if (ch === '/') {
+      const name = /^\/[^\s/<>[\]()%{}]*/.exec(content.slice(i, i + 128))![0]
+      push({ name: name.slice(1) })
+      i += name.length
+    } else if (/[-+.\d]/.test(ch)) {
+      const number = /^[-+]?(\d*\.?\d*)/.exec(content.slice(i, i + 32))![0]
+      push(Number(number) || 0)
+      i += Math.max(number.length, 1)
+    } else {
+      const op = /^[A-Za-z'"*]+/.exec(content.slice(i, i + 16))?.[0] ?? ch
+      i += op.length
+      const args = operands()
+      const last = args[args.length - 1]
+      switch (op) {
+        case 'Tf': {
+          const name = args.find((a): a is { name: string } => typeof a === 'object' && !Array.isArray(a))
+          font = name ? fonts.get(name.name) : undefined
+          break
+        }
+        case 'Tj':
+          if (typeof last === 'string') write(last)
+          break
+        case "'":
+        case '"':
+          newline()
+          if (typeof last === 'string') write(last)
+          break
+        case 'TJ':
+          if (Array.isArray(last)) {
+            for (const part of last) {
+              if (typeof part === 'string') write(part)
+              else if (typeof part === 'number' && part < -180 && !out.endsWith(' ')) out += ' '
+            }
+          }
+          break
+        case 'Td':
+        case 'TD':
+          if (typeof args[1] === 'number' && args[1] !== 0) newline()
+          else if (typeof args[0] === 'number' && args[0] > 0 && !out.endsWith(' ')) out += ' '
+          break
+        case 'T*':
+          newline()
+          break
+        case 'Tm': {
+          const y = args[5]
+          if (typeof y === 'number') {
+            if (lastY !== null && y !== lastY) newline()
+            else if (!out.endsWith(' ')) out += ' '
+            lastY = y
+          }
+          break
+        }
+        case 'ET':
+          if (!out.endsWith('\n') && !out.endsWith(' ')) out += ' '
+          break
+        case 'ID': {
+          // Inline image data runs until EI; skip it
+          const end = content.slice(i).search(/\sEI(?=\s|$)/)
+          i = end === -1 ? content.length : i + end + 3
+          break
+        }
+      }
+      stack.length = 1
+      stack[0] = []
+    }
+  }
+  return out
+}
+
+function readLiteral(content: string, start: number): [string, number] {
+  let out = ''
+  let depth = 0
+  let i = start
+  while (i < content.length) {
+    const ch = content[i]
+    if (ch === '\\') {
+      const next = content[i + 1]
+      const escapes: Record<string, string> = { n: '\n', r: '\r', t: '\t', b: '\b', f: '\f', '(': '(', ')': ')', '\\': '\\' }
+      if (next in escapes) {
+        out += escapes[next]
+        i += 2
+      } else if (/[0-7]/.test(next)) {
+        const octal = /^[0-7]{1,3}/.exec(content.slice(i + 1, i + 4))![0]
+        out += String.fromCharCode(parseInt(octal, 8) & 0xff)
+        i += 1 + octal.length
+      } else if (next === '\r' || next === '\n') {
+        i += content[i + 2] === '\n' && next === '\r' ? 3 : 2
+      } else {
+        i++
+      }
+      continue
+    }
+    if (ch === '(') {
+      depth++
+      if (depth > 1) out += ch
+    } else if (ch === ')') {
+      depth--
+      if (depth === 0) return [out, i + 1]
+      out += ch
+    } else {
+      out += ch
+    }
+    i++
+  }
+  return [out, i]
+}
+
+function decodeString(raw: string, font: CMap | undefined): string {
+  if (font) {
+    let out = ''
+    for (let i = 0; i + font.codeBytes <= raw.length; i += font.codeBytes) {
+      let code = 0
+      for (let b = 0; b < font.codeBytes; b++) code = (code << 8) | raw.charCodeAt(i + b)
+      out += font.map.get(code) ?? (font.codeBytes === 1 ? raw[i] : '')
+    }
+    return out
+  }
+  if (raw.startsWith('\xfe\xff')) {
+    let out = ''
+    for (let i = 2; i + 1 < raw.length; i += 2) out += String.fromCharCode((raw.charCodeAt(i) << 8) | raw.charCodeAt(i + 1))
+    return out
+  }
+  return raw.replace(/[\x00-\x08\x0b-\x1f]/g, '')
+}
@@ -1,22 +1,45 @@
 import fs from 'fs/promises'
 import path from 'path'
-import { fileURLToPath } from 'node:url'
-
-// server/src/lib → server/
-const SERVER_ROOT = path.resolve(path.dirname(fileURLToPath(import.meta.url)), '../../')
+import { resolveServerPath } from './server-root.js'
 
 import { openDatabase } from '../repositories/factory.js'
 import type { DrizzleInstance } from '../repositories/sqlite/index.js'
 import { ProviderRegistryImpl } from '../providers/registry.js'
+import { MockProvider } from '../providers/mock.js'
 import { ToolRegistryImpl } from '../tools/registry.js'
 import { AgentEventEmitter } from '../events/emitter.js'
 import { registerFileTools } from '../tools/files.js'
 import { registerShellTools } from '../tools/shell.js'
+import { registerPythonTools } from '../tools/python.js'
+import { registerTerminalTools } from '../tools/terminal.js'
 import { registerWebTools } from '../tools/web.js'
 import { registerSearchTools } from '../tools/search.js'
 import { registerNoteTools } from '../tools/notes.js'
 import { registerToolOutputTools } from '../tools/tool-outputs.js'
+import { registerAttachmentTools } from '../tools/attachments.js'
+import { registerArtifactTools } from '../tools/artifacts.js'
+import { registerImageTools } from '../tools/image.js'
+import { registerProjectTools } from '../tools/projects.js'
+import { registerMemoryTools } from '../tools/memory.js'
+import { registerEntityTools } from '../tools/entities.js'
+import { registerGitHubTools } from '../tools/github.js'
+import { registerNotionTools } from '../tools/notion.js'
+import { registerIssueTrackerTools } from '../tools/issue-trackers.js'
+import { registerTodoTools } from '../tools/todos.js'
+import { registerPersonalNoteTools } from '../tools/personal-notes.js'
+import { registerContactTools } from '../tools/contacts.js'
+import { registerMailTools } from '../tools/mail.js'
+import { registerCalendarTools } from '../tools/calendar.js'
+import { registerWebhookTools } from '../tools/webhooks.js'
+import { registerHomeAssistantTools } from '../tools/home-assistant.js'
+import { registerMediaTools } from '../tools/media.js'
+import { registerHistoryTools } from '../tools/history.js'
+import { registerKnowledgeTools } from '../tools/knowledge.js'
+import { registerFeedTools } from '../tools/feeds.js'
+import { registerBrowserTools } from '../tools/browser.js'
+import { registerFileSearchTools } from '../tools/file-search.js'
 import { registerPreferenceTools } from '../tools/preferences.js'
+import { registerScratchpadTools } from '../tools/scratchpad.js'
 import { registerDelegateTools } from '../tools/delegate.js'
 import { loadAgentDefinitions } from '../agents/loader.js'
 import { AgentDefinitionRegistryImpl } from '../agents/registry.js'
@@ -24,7 +47,9 @@ import type { AgentDefinitionRegistry } from '../agents/registry.js'
 import { registerTaskTools } from '../tools/tasks.js'
 import { TaskRunner } from '../tasks/runner.js'
 import { TelegramTaskBridge } from '../services/telegram-task-bridge.js'
+import { DailyDigest, registerDigestAction } from '../services/daily-digest.js'
 import { registerThinkTool } from '../tools/think.js'
+import { registerUtilityTools } from '../tools/utilities.js'
 import { logger } from './logger.js'
 import type { AppConfig } from './config.js'
 import { createLangfuseObservability } from '../observability/langfuse.js'
@@ -33,6 +58,12 @@ import type { ProviderRegistry } from '../providers/types.js'
 import type { ToolExecutor } from '../tools/types.js'
 import type { EventSink, EventSource } from '../events/types.js'
 import type { InterceptHandler } from '../orchestrator/types.js'
+import { ApprovalHistory } from '../orchestrator/approval-history.js'
+import { ToolPool } from '../orchestrator/tool-pool.js'
+import { InlineOutputBudget } from '../orchestrator/output.js'
+import { PrivacyVault } from '../orchestrator/privacy.js'
+import { RunScheduler } from '../orchestrator/run-scheduler.js'
+import { ServerShutdownError } from '../orchestrator/errors.js'
 import type { WorkflowRegistry } from '../workflows/types.js'
 import type { WorkflowRunRepository } from '../repositories/types.js'
 import { WorkflowRegistryImpl } from '../workflows/registry.js'
@@ -40,6 +71,57 @@ import { WorkflowExecutor } from '../workflows/executor.js'
 import { registerWorkflowTools } from '../workflows/tool.js'
 import { loadWorkflowDefinitions } from '../workflows/loader.js'
 import { McpManager } from '../mcp/manager.js'
+import { SyncEngine } from '../services/sync.js'
+import { TrashPurger } from '../services/trash.js'
+import { StuckRunWatchdog } from '../services/stuck-runs.js'
+import { OfflineQueue } from '../services/offline-queue.js'
+import { TranscriptionJobs } from '../services/transcription-jobs.js'
+import { RetentionMaintenance } from '../services/retention.js'
+import { ApprovalTimeouts } from '../services/approval-timeouts.js'
+import { MemoryExtractor } from '../services/memory.js'
+import { ConversationTitler } from '../services/conversation-title.js'
+import { EntityExtractor } from '../services/entities.js'
+import { KnowledgeIndexer } from '../services/knowledge-base.js'
+import { FeedPoller } from '../services/feeds.js'
+import { InstructionFiles } from '../services/instruction-files.js'
+import { EmbeddingMaintenance } from '../services/embedding-maintenance.js'
+import { UsageTracker } from '../usage/tracker.js'
+import { BudgetMonitor } from '../usage/budget.js'
+import { PricingRegistry } from '../usage/pricing.js'
+import { AuditLog } from '../observability/audit-log.js'
+import { DebugTraceRecorder } from '../observability/debug-traces.js'
+import { Metrics, PrometheusEndpoint } from '../observability/metrics.js'
+import { AttachmentStore, migrateInlineAttachments, withAttachmentStore } from './attachment-store.js'
+import { SecretRedactor, withSecretTracking } from './secrets.js'
+import { ConnectivityMonitor } from './connectivity.js'
+import { ResponseVerifier } from '../orchestrator/verifier.js'
+import { GuardrailEngine } from '../orchestrator/guardrails.js'
+import { createVisionImagePreparer } from '../services/vision-images.js'
+import { WebSocketBridge } from '../services/ws-bridge.js'
+import { RemoteApprovalRelay } from '../services/remote-approvals.js'
+import { WindowClaims } from '../services/window-routing.js'
+import { GitHubClient } from '../services/github.js'
+import { NotionClient } from '../services/notion.js'
+import { JiraTracker, LinearTracker } from '../services/issue-trackers.js'
+import { RemindersBackend, TodoistBackend } from '../services/todos.js'
+import { AppleNotes, ObsidianVault } from '../services/personal-notes.js'
+import { MacContacts } from '../services/contacts.js'
+import { MAIL_ACCOUNT_KEY, MailService, loadMailAccount } from '../services/mail.js'
+import { CALDAV_ACCOUNTS_KEY, CalendarService, loadCalDavAccounts } from '../services/caldav.js'
+import { WebhookService } from '../services/webhooks.js'
+import { PluginManager } from '../services/plugins.js'
+import { ModelCatalog } from '../services/model-catalog.js'
+import { ActionRegistry } from '../services/actions.js'
+import { registerBuiltinActions } from '../services/builtin-actions.js'
+import { HomeAssistantClient } from '../services/home-assistant.js'
+import { MacMediaPlayer, SpotifyPlayer } from '../services/media.js'
+import { TerminalManager } from '../services/terminals.js'
+import { BrowserExtensions } from '../services/browser-extension.js'
+import { TurnKeys } from '../services/turn-keys.js'
+import { ConversationThrottle } from '../services/conversation-throttle.js'
+import { ControllerPromptStore } from '../services/controller-prompts.js'
+import { PromptExperiments } from '../services/experiments.js'
+import type { PreparedSessionTurn } from '../services/session-runner.js'
 import type {
   UserRepository,
   SessionRepository,
@@ -65,6 +147,22 @@ export interface RuntimeContext {
     preferences: PreferenceRepository
     mcp: import('../repositories/types.js').McpRepository
     telegram: import('../repositories/types.js').TelegramRepository
+    sync: import('../repositories/types.js').SyncRepository
+    usage: import('../repositories/types.js').UsageRepository
+    approvalHistory: import('../repositories/types.js').ApprovalHistoryRepository
+    modelPrices: import('../repositories/types.js').ModelPriceRepository
+    uploads: import('../repositories/types.js').UploadRepository
+    memories: import('../repositories/types.js').MemoryRepository
+    knowledge: import('../repositories/types.js').KnowledgeSourceRepository
+    scratchpad: import('../repositories/types.js').ScratchpadRepository
+    entities: import('../repositories/types.js').EntityRepository
+    feeds: import('../repositories/types.js').FeedRepository
+    retention: import('../repositories/types.js').RetentionRepository
+    messageRevisions: import('../repositories/types.js').MessageRevisionRepository
+    maintenance: import('../repositories/types.js').MaintenanceRepository
+    orphans: import('../repositories/types.js').OrphanRepository
+    controllerPrompts: import('../repositories/types.js').ControllerPromptRepository
+    experiments: import('../repositories/types.js').ExperimentRepository
   }
   providers: ProviderRegistry
   tools: ToolExecutor
@@ -74,7 +172,26 @@ export interface RuntimeContext {
   sessionFilesRoot: string
   tasksDir: string
   notesDir: string
+  /** Manifests of folders ingested as projects, per user. */
+  projectsDir: string
+  /** ASSISTANT.md files of the workspace and linked projects, injected into controller prompts. */
+  instructions: InstructionFiles
   inlineOutputLimitBytes: number
+  inlineOutputBudget: InlineOutputBudget
+  /** Strips API keys and other credentials from tool output, approval previews and debug traces. */
+  secrets: SecretRedactor
+  /** Placeholder mappings of conversations in privacy mode. */
+  privacy: PrivacyVault
+  connectivity: ConnectivityMonitor
+  /** Set when VERIFY_RESPONSES is on. */
+  verifier?: ResponseVerifier
+  /** Evaluates the guardrail_policy preference for every run. */
+  guardrails: GuardrailEngine
+  /** Editable controller prompts, versioned in the database; runs read them when they start. */
+  controllerPrompts: ControllerPromptStore
+  /** The running prompt or model A/B test, which also resolves each session's controller prompts. */
+  experiments: PromptExperiments
+  attachments: AttachmentStore
   db: DrizzleInstance
   closeDatabase: () => Promise<void>
   agentDefinitions: AgentDefinitionRegistry
@@ -96,6 +213,76 @@ export interface RuntimeContext {
   telegramTaskBridge: TelegramTaskBridge | null
   /** Optional Langfuse/OpenTelemetry-backed LLM observability. */
   observability: LLMObservability
+  /** Persists token usage and cost for every LLM call. */
+  usage: UsageTracker
+  /** Weekly/monthly spend caps and threshold alerts. */
+  budget: BudgetMonitor
+  /** Per-model token prices used to cost usage; editable and refreshable. */
+  pricing: PricingRegistry
+  /** Models each provider lists, with their capabilities, for the picker and routing. */
+  modelCatalog: ModelCatalog
+  /** Persists how each approval-gated tool call was decided. */
+  approvalHistory: ApprovalHistory
+  /** Local LLM request/response capture, toggled by the debug_traces preference. */
+  debugTraces: DebugTraceRecorder
+  /** Append-only record of tool calls, approvals and LLM call metadata, toggled by the audit_log preference. */
+  auditLog: AuditLog
+  /** LLM/tool latency, token and queue-depth metrics for performance debugging. */
+  metrics: Metrics
+  /** Bounds parallel tool batches to TOOL_POOL_SIZE calls at once, TOOL_POOL_PER_SESSION per conversation. */
+  toolPool: ToolPool
+  /** Runs at most MAX_CONCURRENT_RUNS agent runs at once and one per conversation; the rest queue. */
+  runScheduler: RunScheduler
+  /** Turns started under client idempotency keys, so retried sends do not start a second run. */
+  turnKeys: TurnKeys<PreparedSessionTurn>
+  /** Turns away sends to a conversation still handling one, or sent too soon after the last. */
+  conversationThrottle: ConversationThrottle
+  /** Loopback Prometheus scrape endpoint — null unless PROMETHEUS_ENABLED. */
+  prometheus: PrometheusEndpoint | null
+  /** File-based device sync — null unless SYNC_DIR is configured. */
+  sync: SyncEngine | null
+  /** Purges sessions left in the trash past TRASH_RETENTION_DAYS. */
+  trashPurger: TrashPurger | null
+  /** Fails runs and approval waits that stopped moving; see STUCK_RUN_MULTIPLE. */
+  stuckRuns: StuckRunWatchdog | null
+  /** Messages sent while offline, started when the connection returns. */
+  offlineQueue: OfflineQueue | null
+  /** Background transcription of uploaded audio attachments. */
+  transcriptions: TranscriptionJobs | null
+  /** Applies the retention_policy preference once a day. */
+  retention: RetentionMaintenance | null
+  /** Reminds about, then expires, approvals left unanswered past their timeout. */
+  approvalTimeouts: ApprovalTimeouts | null
+  /** Saves facts and preferences from finished runs — null unless MEMORY_MODEL is set. */
+  memoryExtractor: MemoryExtractor | null
+  /** Names conversations after their first exchange — null unless TITLE_MODEL is set. */
+  conversationTitler: ConversationTitler | null
+  /** Learns people, organizations and recurring meetings from email and calendar tool results. */
+  entityExtractor: EntityExtractor | null
+  /** Reads and embeds registered knowledge sources in the background. */
+  knowledgeIndexer: KnowledgeIndexer | null
+  /** Fetches subscribed RSS and Atom feeds every FEED_POLL_INTERVAL_MS. */
+  feedPoller: FeedPoller | null
+  /** Writes a morning briefing every day at DIGEST_TIME — null unless it is set. */
+  dailyDigest: DailyDigest | null
+  /** Re-indexes changed knowledge sources and projects and embeds what has no vectors yet. */
+  embeddingMaintenance: EmbeddingMaintenance | null
+  /** Signed approve/deny links for approvals — null unless REMOTE_APPROVALS is set. */
+  remoteApprovals: RemoteApprovalRelay | null
+  /** Localhost WebSocket API — null unless WS_BRIDGE_ENABLED is set. */
+  wsBridge: WebSocketBridge | null
+  /** Browser extensions registered on the bridge, answering browser.get_active_tab — null unless WS_BRIDGE_ENABLED is set. */
+  browserExtensions: BrowserExtensions | null
+  /** Persistent shells behind terminal.* — null unless ENABLE_SHELL_TOOL is set. */
+  terminals: TerminalManager | null
+  /** Which app window owns which conversation's events. */
+  windows: WindowClaims
+  /** Endpoints webhook.post may send to; the list is cached for the tool's approval trigger. */
+  webhooks: WebhookService
+  /** Installed plugins and the tools, prompts and MCP servers they provide. */
+  plugins: PluginManager
+  /** Operations the command palette and global hotkeys run: new conversation, run playbook, export, summarize. */
+  actions: ActionRegistry
 }
 
 export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
@@ -104,7 +291,7 @@ export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
     const dbDir = path.dirname(config.databaseUrl)
     await fs.mkdir(dbDir, { recursive: true })
   }
-  logger.info({ path: config.databaseUrl }, 'Opening database')
+  logger.info({ path: config.databaseUrl, workspace: config.workspace }, 'Opening database')
 
   // 2. Create database connection and repositories (dialect-aware)
   const opened = await openDatabase(config)
@@ -113,6 +300,25 @@ export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
   const db = opened.db as DrizzleInstance
   const repos = opened.repositories
 
+  // 3. Attachment payloads live on disk; every item write goes through the store
+  const attachments = new AttachmentStore(
+    resolveServerPath(config.attachmentsDir),
+    createVisionImagePreparer({
+      ffmpegBin: config.ffmpegBin,
+      maxDimension: config.visionImageMaxDimension,
+      maxBytes: config.visionImageMaxBytes,
+    }),
+  )
+  await migrateInlineAttachments(repos.items, attachments)
+  repos.items = withAttachmentStore(repos.items, attachments)
+
+  // Known secret values: configured keys now, stored keys whenever they are read
+  const secrets = new SecretRedactor()
+  for (const [name, value] of Object.entries(config)) {
+    if (typeof value === 'string' && /key|token|secret|password/i.test(name)) secrets.register(value)
+  }
+  repos.apiKeys = withSecretTracking(repos.apiKeys, secrets)
+
   if (!config.encryptionKey) {
     if (config.nodeEnv === 'production') {
       throw new Error('ENCRYPTION_KEY is required in production. Generate one with: openssl rand -base64 32')
@@ -122,45 +328,79 @@ export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
   const observability = await createLangfuseObservability(config)
 
   // 4. Create event emitter
-  const events = new AgentEventEmitter()
+  const events = new AgentEventEmitter({
+    batchMs: config.eventBatchMs,
+    maxEventsPerSecond: config.eventMaxPerSecond,
+  })
 
   // 5. Load agent definitions from markdown files
-  const agentsDir = path.isAbsolute(config.agentsDir)
-    ? config.agentsDir
-    : path.resolve(SERVER_ROOT, config.agentsDir)
+  const agentsDir = resolveServerPath(config.agentsDir)
   const agentDefs = await loadAgentDefinitions(agentsDir)
   const agentDefinitions = new AgentDefinitionRegistryImpl(agentsDir, agentDefs)
   logger.info({ count: agentDefs.length, dir: agentsDir }, 'Agent definitions loaded')
 
   // 6. Create tool registry and register all tools
   const tools = new ToolRegistryImpl()
-  const tasksDir = path.isAbsolute(config.tasksDir)
-    ? config.tasksDir
-    : path.resolve(SERVER_ROOT, config.tasksDir)
-  const workspaceDir = path.isAbsolute(config.workspaceDir)
-    ? config.workspaceDir
-    : path.resolve(SERVER_ROOT, config.workspaceDir)
-  const notesDir = path.resolve(SERVER_ROOT, './data/research-notes')
-  const sessionFilesRoot = path.isAbsolute(config.sessionFilesDir)
-    ? config.sessionFilesDir
-    : path.resolve(SERVER_ROOT, config.sessionFilesDir)
+  const tasksDir = resolveServerPath(config.tasksDir)
+  const workspaceDir = resolveServerPath(config.workspaceDir)
+  const notesDir = resolveServerPath(config.notesDir)
+  const projectsDir = resolveServerPath(config.projectsDir)
+  const sessionFilesRoot = resolveServerPath(config.sessionFilesDir)
   await fs.mkdir(sessionFilesRoot, { recursive: true })
+  const inlineOutputBudget = new InlineOutputBudget(config.sessionInlineOutputBudgetChars)
+  const privacy = new PrivacyVault(repos.preferences)
+  const connectivity = new ConnectivityMonitor({
+    probeHost: config.connectivityProbeHost,
+    checkMs: config.connectivityCheckMs,
+    offlineModel: config.offlineModel,
+  })
+  const verifier = config.verifyResponses ? new ResponseVerifier({ model: config.verifierModel }) : undefined
+  const guardrails = new GuardrailEngine(repos.preferences, repos.usage)
+  const controllerPrompts = new ControllerPromptStore(repos.controllerPrompts)
+  await controllerPrompts.seed()
+  const experiments = new PromptExperiments({
+    preferences: repos.preferences,
+    experiments: repos.experiments,
+    agents: repos.agents,
+    usage: repos.usage,
+    prompts: controllerPrompts,
+  })
 
-  registerFileTools(tools, { sessionFilesRoot, notesDir })
+  registerFileTools(tools, {
+    sessionFilesRoot,
+    notesDir,
+    approvalFreeRoots: config.filesApprovalFreeRoots.split(',').map((root) => root.trim()).filter(Boolean),
+  })
+  let terminals: TerminalManager | null = null
   if (config.enableShellTool) {
     registerShellTools(tools)
+    terminals = new TerminalManager()
+    registerTerminalTools(tools, terminals)
   } else {
-    logger.warn('shell.exec disabled — set ENABLE_SHELL_TOOL=true to enable')
+    logger.warn('shell.exec and terminal.* disabled — set ENABLE_SHELL_TOOL=true to enable')
+  }
+  if (config.enablePythonTool) {
+    registerPythonTools(tools, {
+      sessionFilesRoot,
+      notesDir,
+      pythonBin: config.pythonBin,
+      timeoutMs: config.pythonTimeoutMs,
+      maxTimeoutMs: config.pythonMaxTimeoutMs,
+      memoryMb: config.pythonMemoryMb,
+    })
   }
   registerWebTools(tools)
   registerSearchTools(tools, { sessionFilesRoot, notesDir })
   registerToolOutputTools(tools, repos.toolOutputs)
   registerPreferenceTools(tools, repos.preferences)
+  registerScratchpadTools(tools, repos.scratchpad)
   registerThinkTool(tools)
+  registerUtilityTools(tools, { exchangeRateUrl: config.exchangeRateUrl })
 
   // Task management tools — files stored in data/tasks/, outputs in data/workspace/
   registerTaskTools(tools, tasksDir, workspaceDir, agentDefinitions)
   registerNoteTools(tools, { notesDir, sessionFilesRoot })
+  registerArtifactTools(tools, { sessionFilesRoot })
 
   // Intercept handlers — populated by register*Tools functions that provide orchestrator_intercept tools
   const interceptHandlers = new Map<string, InterceptHandler>()
@@ -195,6 +435,10 @@ export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
     providers.registerFromKey('openrouter', config.openrouterApiKey)
     envRegistered.push('openrouter')
   }
+  if (config.mockProviderScript) {
+    providers.register('mock', MockProvider.fromFile(config.mockProviderScript))
+    envRegistered.push('mock')
+  }
 
   if (envRegistered.length > 0) {
     logger.info({ providers: envRegistered }, 'Providers registered from environment')
@@ -207,10 +451,93 @@ export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
     logger.warn('No LLM providers configured. Set keys via .env or PUT /api/keys/:provider')
   }
 
+  // Attachment search embeds queries, so it registers once providers exist
+  registerAttachmentTools(tools, { items: repos.items, attachments, config, providers })
+  registerProjectTools(tools, { sessions: repos.sessions, attachments, config, providers, sessionFilesRoot, projectsDir })
+  registerMemoryTools(tools, { sessions: repos.sessions, memories: repos.memories, preferences: repos.preferences, config, providers })
+  registerEntityTools(tools, { sessions: repos.sessions, entities: repos.entities, preferences: repos.preferences, config, providers })
+  registerHistoryTools(tools, { items: repos.items, sessions: repos.sessions, config, providers })
+  registerKnowledgeTools(tools, { knowledge: repos.knowledge, sessions: repos.sessions, attachments, config, providers })
+  registerFeedTools(tools, { feeds: repos.feeds, sessions: repos.sessions })
+  // The companion browser extension reaches the server through the WebSocket bridge
+  const browserExtensions = config.wsBridgeEnabled ? new BrowserExtensions() : null
+  if (browserExtensions) registerBrowserTools(tools, { extensions: browserExtensions, sessions: repos.sessions })
+  // Without an embedding model this would only repeat keyword search
+  if (config.attachmentEmbeddingModel) {
+    registerFileSearchTools(tools, {
+      knowledge: repos.knowledge,
+      sessions: repos.sessions,
+      attachments,
+      config,
+      providers,
+      sessionFilesRoot,
+      projectsDir,
+    })
+  }
+  if (config.imageGenerationModel) {
+    registerImageTools(tools, { sessionFilesRoot, config, providers })
+  }
+  if (config.githubToken) {
+    registerGitHubTools(tools, new GitHubClient(config.githubToken, config.githubApiUrl))
+  }
+  if (config.notionToken) {
+    registerNotionTools(tools, new NotionClient(config.notionToken))
+  }
+  if (config.jiraUrl && config.jiraToken) {
+    registerIssueTrackerTools(tools, new JiraTracker(config.jiraUrl, config.jiraToken, config.jiraEmail))
+  }
+  if (config.linearApiKey) {
+    registerIssueTrackerTools(tools, new LinearTracker(config.linearApiKey))
+  }
+  if (config.todoBackend === 'reminders') {
+    if (process.platform === 'darwin') registerTodoTools(tools, new RemindersBackend())
+    else logger.warn('TODO_BACKEND=reminders needs macOS — todos.* tools disabled')
+  } else if (config.todoistApiToken) {
+    registerTodoTools(tools, new TodoistBackend(config.todoistApiToken))
+  }
+  if (config.personalNotesBackend === 'apple_notes') {
+    if (process.platform === 'darwin') registerPersonalNoteTools(tools, new AppleNotes())
+    else logger.warn('PERSONAL_NOTES_BACKEND=apple_notes needs macOS — personal_notes.* tools disabled')
+  } else if (config.obsidianVaultDir) {
+    registerPersonalNoteTools(tools, new ObsidianVault(config.obsidianVaultDir))
+  }
+  if (config.contactsBackend === 'macos') {
+    if (process.platform === 'darwin') registerContactTools(tools, new MacContacts())
+    else logger.warn('CONTACTS_BACKEND=macos needs macOS — contacts.search disabled')
+  }
+  if (config.homeAssistantUrl && config.homeAssistantToken) {
+    registerHomeAssistantTools(tools, new HomeAssistantClient(config.homeAssistantUrl, config.homeAssistantToken))
+  }
+  if (config.mediaBackend === 'macos') {
+    if (process.platform === 'darwin') registerMediaTools(tools, new MacMediaPlayer())
+    else logger.warn('MEDIA_BACKEND=macos needs macOS — media.control disabled')
+  } else if (config.spotifyRefreshToken) {
+    if (config.spotifyClientId && config.spotifyClientSecret) {
+      registerMediaTools(tools, new SpotifyPlayer(config.spotifyClientId, config.spotifyClientSecret, config.spotifyRefreshToken))
+    } else {
+      logger.warn('SPOTIFY_REFRESH_TOKEN needs SPOTIFY_CLIENT_ID and SPOTIFY_CLIENT_SECRET — media.control disabled')
+    }
+  }
+  // The IMAP/SMTP account is saved through /api/mail/account; the tools appear once it exists
+  if (await repos.apiKeys.getByProvider(MAIL_ACCOUNT_KEY).catch(() => null)) {
+    registerMailTools(tools, new MailService(() => loadMailAccount(repos.apiKeys)))
+  }
+  // CalDAV accounts are saved through /api/calendar/accounts; the tools appear once one exists
+  if (await repos.apiKeys.getByProvider(CALDAV_ACCOUNTS_KEY).catch(() => null)) {
+    registerCalendarTools(tools, new CalendarService(() => loadCalDavAccounts(repos.apiKeys)))
+  }
+  // Webhook endpoints are registered through /api/webhooks; webhook.post appears once one exists
+  const webhooks = new WebhookService(repos.apiKeys)
+  if ((await webhooks.refresh().catch(() => [])).length > 0) registerWebhookTools(tools, webhooks)
+  // Installed plugins register their tools; MCP-backed ones were connected with the other MCP servers
+  const plugins = new PluginManager(
+    resolveServerPath(config.pluginsDir),
+    { tools, apiKeys: repos.apiKeys, systemPrompts: repos.systemPrompts, mcps, redactor: secrets },
+  )
+  await plugins.loadAll()
+
   // 7. Build workflow subsystem (two-phase: registry first, executor after providers)
-  const workflowsDir = path.isAbsolute(config.workflowsDir)
-    ? config.workflowsDir
-    : path.resolve(SERVER_ROOT, config.workflowsDir)
+  const workflowsDir = resolveServerPath(config.workflowsDir)
   const workflowDefs = await loadWorkflowDefinitions(workflowsDir)
   const workflowRegistry = new WorkflowRegistryImpl()
   for (const def of workflowDefs) {
@@ -232,6 +559,14 @@ export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
       interceptHandlers,
       sessionFilesRoot,
       inlineOutputLimitBytes: config.inlineOutputLimitBytes,
+      inlineOutputBudget,
+      redactor: secrets,
+      privacy,
+      connectivity,
+      verifier,
+      guardrails,
+      prompts: experiments,
+      attachments,
       defaultModel: config.defaultModel,
     })
     registerWorkflowTools(tools, workflowRegistry, executor, interceptHandlers)
@@ -239,6 +574,51 @@ export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
     logger.info({ count: workflowDefs.length }, 'Workflow subsystem initialized')
   }
 
+  const budget = new BudgetMonitor(repos.usage, repos.preferences, events, repos.sessions)
+  const pricing = new PricingRegistry(repos.modelPrices, config.pricingCatalogUrl, fetch, repos.preferences)
+  try {
+    await pricing.load()
+  } catch (err) {
+    logger.warn({ err }, 'Failed to load model prices; using the bundled table')
+  }
+  const modelCatalog = new ModelCatalog(repos.modelCatalog, providers)
+  try {
+    await modelCatalog.load()
+  } catch (err) {
+    logger.warn({ err }, 'Failed to load the model catalog')
+  }
+  // Listings come from the network; startup does not wait for them
+  const staleListings = modelCatalog.stale()
+  if (staleListings.length > 0) {
+    void Promise.all(staleListings.map((provider) => modelCatalog.refresh(provider)))
+      .catch((err) => logger.warn({ err }, 'Model catalog refresh failed'))
+  }
+  const debugTraces = new DebugTraceRecorder({
+    dir: resolveServerPath(config.tracesDir),
+    preferences: repos.preferences,
+    defaultEnabled: config.debugTraces,
+    maxTraceBytes: config.debugTraceMaxBytes,
+    maxTotalBytes: config.debugTracesMaxTotalBytes,
+    retentionDays: config.debugTraceRetentionDays,
+    redactor: secrets,
+  })
+  const auditLog = new AuditLog({
+    dir: resolveServerPath(config.auditDir),
+    preferences: repos.preferences,
+    sessions: repos.sessions,
+    defaultEnabled: config.auditLog,
+  })
+  const metrics = new Metrics()
+  metrics.gauge('event_subscribers', 'Open event subscriptions (UI streams, bridge clients, internal consumers).', () =>
+    events.queueStats().subscribers)
+  metrics.gauge('event_queue_depth', 'Events waiting for slow subscribers: total and deepest single queue.', () => {
+    const stats = events.queueStats()
+    return [
+      { labels: { stat: 'total' }, value: stats.queued },
+      { labels: { stat: 'max' }, value: stats.maxQueued },
+    ]
+  })
+
   // 8. Return RuntimeContext
   const runtime: RuntimeContext = {
     repositories: {
@@ -253,6 +633,22 @@ export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
       preferences: repos.preferences,
       mcp: repos.mcp,
       telegram: repos.telegram,
+      sync: repos.sync,
+      usage: repos.usage,
+      approvalHistory: repos.approvalHistory,
+      modelPrices: repos.modelPrices,
+      uploads: repos.uploads,
+      memories: repos.memories,
+      knowledge: repos.knowledge,
+      scratchpad: repos.scratchpad,
+      entities: repos.entities,
+      feeds: repos.feeds,
+      retention: repos.retention,
+      messageRevisions: repos.messageRevisions,
+      maintenance: repos.maintenance,
+      orphans: repos.orphans,
+      controllerPrompts: repos.controllerPrompts,
+      experiments: repos.experiments,
     },
     providers,
     tools,
@@ -262,7 +658,18 @@ export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
     sessionFilesRoot,
     tasksDir,
     notesDir,
+    projectsDir,
+    instructions: new InstructionFiles({ sessions: repos.sessions, sessionFilesRoot, projectsDir, workspaceDir }),
     inlineOutputLimitBytes: config.inlineOutputLimitBytes,
+    inlineOutputBudget,
+    secrets,
+    privacy,
+    connectivity,
+    verifier,
+    guardrails,
+    controllerPrompts,
+    experiments,
+    attachments,
     db,
     closeDatabase: opened.close,
     agentDefinitions,
@@ -273,6 +680,44 @@ export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
     taskRunner: null,
     telegramTaskBridge: null,
     observability,
+    usage: new UsageTracker(repos.usage, repos.sessions, budget, pricing),
+    budget,
+    pricing,
+    modelCatalog,
+    approvalHistory: new ApprovalHistory(repos.approvalHistory, repos.sessions),
+    debugTraces,
+    auditLog,
+    metrics,
+    toolPool: new ToolPool({ size: config.toolPoolSize, perSession: config.toolPoolPerSession }),
+    runScheduler: new RunScheduler({ maxRuns: config.maxConcurrentRuns }),
+    turnKeys: new TurnKeys(),
+    conversationThrottle: new ConversationThrottle(repos.agents, {
+      maxConcurrent: config.conversationMaxConcurrentSends,
+      minIntervalMs: config.conversationMinSendIntervalMs,
+    }),
+    prometheus: null,
+    sync: null,
+    trashPurger: null,
+    stuckRuns: null,
+    offlineQueue: null,
+    transcriptions: null,
+    retention: null,
+    approvalTimeouts: null,
+    memoryExtractor: null,
+    conversationTitler: null,
+    entityExtractor: null,
+    knowledgeIndexer: null,
+    feedPoller: null,
+    dailyDigest: null,
+    embeddingMaintenance: null,
+    remoteApprovals: null,
+    wsBridge: null,
+    browserExtensions,
+    terminals,
+    windows: new WindowClaims(events),
+    webhooks,
+    plugins,
+    actions: new ActionRegistry(),
   }
 
   runtime.taskRunner = new TaskRunner(runtime, { tasksDir, notesDir })
@@ -280,6 +725,95 @@ export async function initRuntime(config: AppConfig): Promise<RuntimeContext> {
   runtime.telegramTaskBridge = new TelegramTaskBridge(runtime, { tasksDir })
   runtime.telegramTaskBridge.start()
 
+  if (config.syncDir) {
+    runtime.sync = new SyncEngine({
+      repository: repos.sync,
+      preferences: repos.preferences,
+      users: repos.users,
+      dir: path.resolve(config.syncDir),
+      deviceId: config.syncDeviceId,
+      intervalMs: config.syncIntervalMs,
+    })
+    await runtime.sync.start()
+    logger.info({ dir: config.syncDir }, 'File sync enabled')
+  }
+
+  runtime.trashPurger = new TrashPurger(runtime)
+  runtime.trashPurger.start()
+  runtime.transcriptions = new TranscriptionJobs(runtime)
+  runtime.retention = new RetentionMaintenance(runtime)
+  runtime.retention.start()
+  runtime.approvalTimeouts = new ApprovalTimeouts(runtime)
+  runtime.approvalTimeouts.start()
+  runtime.stuckRuns = new StuckRunWatchdog(runtime)
+  runtime.stuckRuns.start()
+  runtime.offlineQueue = new OfflineQueue(runtime)
+  await runtime.offlineQueue.start()
+  if (config.memoryModel) {
+    runtime.memoryExtractor = new MemoryExtractor(runtime)
+    runtime.memoryExtractor.start()
+  }
+  if (config.titleModel) {
+    runtime.conversationTitler = new ConversationTitler(runtime)
+    runtime.conversationTitler.start()
+  }
+  runtime.entityExtractor = new EntityExtractor(runtime)
+  runtime.entityExtractor.start()
+  runtime.knowledgeIndexer = new KnowledgeIndexer(runtime)
+  await runtime.knowledgeIndexer.start()
+  runtime.feedPoller = new FeedPoller(runtime, { intervalMs: config.feedPollIntervalMs })
+  runtime.feedPoller.start()
+  runtime.embeddingMaintenance = new EmbeddingMaintenance(runtime, {
+    intervalMs: config.embeddingMaintenanceIntervalMs,
+    maxDocuments: config.embeddingMaintenanceMaxDocuments,
+  })
+  runtime.embeddingMaintenance.start()
+  registerBuiltinActions(runtime.actions, runtime)
+  if (config.digestTime) {
+    runtime.dailyDigest = new DailyDigest(runtime, {
+      time: config.digestTime,
+      agent: config.digestAgent,
+      webhookUrl: config.digestWebhookUrl,
+    })
+    registerDigestAction(runtime.actions, runtime.dailyDigest)
+    runtime.dailyDigest.start()
+  }
+
+  if (config.remoteApprovals) {
+    if (!config.publicBaseUrl || !config.encryptionKey) {
+      logger.warn('REMOTE_APPROVALS needs PUBLIC_BASE_URL and ENCRYPTION_KEY — remote approvals disabled')
+    } else {
+      runtime.remoteApprovals = new RemoteApprovalRelay(runtime, {
+        baseUrl: config.publicBaseUrl,
+        secret: config.encryptionKey,
+        webhookUrl: config.remoteApprovalWebhookUrl,
+        linkTtlMs: config.remoteApprovalLinkTtlMs,
+      })
+      runtime.remoteApprovals.start()
+    }
+  }
+
+  runtime.debugTraces.start()
+  runtime.auditLog.start(runtime.events)
+  runtime.metrics.gauge('agents_in_flight', 'Agent runs currently executing.', () => runtime.agentAbortControllers.size)
+  runtime.metrics.gauge('tool_pool_active', 'Parallel tool calls currently running.', () => runtime.toolPool.active)
+  runtime.metrics.gauge('tool_pool_queued', 'Parallel tool calls waiting for a free slot.', () => runtime.toolPool.queued)
+  runtime.metrics.gauge('agent_runs_active', 'Agent runs currently executing.', () => runtime.runScheduler.active)
+  runtime.metrics.gauge('agent_runs_queued', 'Agent runs waiting for a free slot or for their conversation.', () => runtime.runScheduler.queued)
+  runtime.metrics.start(runtime.events)
+
+  if (config.prometheusEnabled) {
+    // Loopback only: the scrape endpoint is unauthenticated
+    runtime.prometheus = new PrometheusEndpoint(runtime.metrics, { port: config.prometheusPort, host: '127.0.0.1' })
+    await runtime.prometheus.start()
+  }
+
+  if (config.wsBridgeEnabled) {
+    // Loopback only: the bridge can drive the agent, so it is never exposed on the network
+    runtime.wsBridge = new WebSocketBridge(runtime, { port: config.wsBridgePort, host: '127.0.0.1' })
+    await runtime.wsBridge.start()
+  }
+
   return runtime
 }
 
@@ -289,19 +823,48 @@ export async function shutdownRuntime(runtime: RuntimeContext): Promise<void> {
   // Abort in-flight workflow runs
   runtime.telegramTaskBridge?.stop()
   runtime.taskRunner?.stop()
+  runtime.sync?.stop()
+  runtime.trashPurger?.stop()
+  runtime.retention?.stop()
+  runtime.approvalTimeouts?.stop()
+  runtime.stuckRuns?.stop()
+  runtime.offlineQueue?.stop()
+  runtime.connectivity.stop()
+  runtime.memoryExtractor?.stop()
+  runtime.conversationTitler?.stop()
+  runtime.entityExtractor?.stop()
+  runtime.knowledgeIndexer?.stop()
+  runtime.feedPoller?.stop()
+  runtime.dailyDigest?.stop()
+  runtime.embeddingMaintenance?.stop()
+  runtime.instructions.stop()
+  runtime.remoteApprovals?.stop()
+  runtime.prometheus?.stop()
+  runtime.wsBridge?.stop()
+  runtime.terminals?.closeAll()
   runtime.workflows?.executor.abortAll()
-  await runtime.mcps.shutdown()
 
-  // Signal all in-flight agent runs to abort
+  // Signal all in-flight agent runs to abort, then give them a moment to record
+  // where they stopped while the database and audit log are still open
+  const reason = new ServerShutdownError()
   for (const controller of runtime.agentAbortControllers.values()) {
-    controller.abort()
+    controller.abort(reason)
+  }
+  runtime.shutdownController.abort(reason)
+  const settled = await untilRunsSettle(runtime.agentAbortControllers, runtime.config.shutdownGraceMs)
+  if (!settled) {
+    logger.warn({ runs: runtime.agentAbortControllers.size }, 'Agent runs still in flight after the shutdown grace period')
   }
   runtime.agentAbortControllers.clear()
-  runtime.shutdownController.abort()
+
+  runtime.debugTraces.stop()
+  runtime.auditLog.stop()
+  runtime.metrics.stop()
+  await runtime.mcps.shutdown()
 
   // Mark any running/waiting agents as failed so they don't appear stuck on restart
   try {
-    await runtime.repositories.agents.failRunningOrWaiting('Server shutdown')
+    await runtime.repositories.agents.failRunningOrWaiting('Server shutdown', 'interrupted')
   } catch {
     // Best-effort — don't block shutdown
   }
@@ -311,3 +874,15 @@ export async function shutdownRuntime(runtime: RuntimeContext): Promise<void> {
 
   logger.info('Runtime shutdown complete')
 }
+
+const SETTLE_POLL_MS = 50
+
+/** Resolves true once every run has dropped its abort handle, or false after `graceMs`. */
+async function untilRunsSettle(runs: Map<string, AbortController>, graceMs: number): Promise<boolean> {
+  const deadline = Date.now() + graceMs
+  while (runs.size > 0) {
+    if (Date.now() >= deadline) return false
+    await new Promise((resolve) => setTimeout(resolve, SETTLE_POLL_MS))
+  }
+  return true
+}
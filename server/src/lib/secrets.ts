@@ -0,0 +1,156 @@
+import type { ApiKeyRecord, ApiKeyRepository } from '../repositories/types.js'
+
+export const REDACTED = '[REDACTED]'
+
+/** Credential formats with a documented prefix, matched anywhere in text. */
+const TOKEN_PATTERNS: RegExp[] = [
+  /\bsk-(?:ant-|proj-|or-v1-)?[A-Za-z0-9_-]{16,}/g, // Anthropic, OpenAI, OpenRouter
+  /\bpk-lf-[A-Za-z0-9-]{16,}/g, // Langfuse public key
+  /\bgh[pousr]_[A-Za-z0-9]{30,}/g, // GitHub
+  /\bgithub_pat_[A-Za-z0-9_]{30,}/g,
+  /\bxox[abposr]-[A-Za-z0-9-]{10,}/g, // Slack
+  /\b(?:AKIA|ASIA)[0-9A-Z]{16}\b/g, // AWS access key id
+  /\bAIza[0-9A-Za-z_-]{35}/g, // Google
+  /\b\d{8,10}:AA[0-9A-Za-z_-]{33}/g, // Telegram bot
+  /\b(?:pk|sk|rk)_(?:live|test)_[A-Za-z0-9]{16,}/g, // Stripe
+  /\blin_api_[A-Za-z0-9]{32,}/g, // Linear
+  /\b(?:secret|ntn)_[A-Za-z0-9]{32,}/g, // Notion
+  /\beyJ[A-Za-z0-9_-]{8,}\.eyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}/g, // JWT
+  /-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----/g,
+]
+
+/** The value after a credential-like name: `password=…`, `"api_key": "…"`, `Authorization: Bearer …`. */
+const ASSIGNMENT_PATTERN = /(\b[\w-]*(?:api[_-]?key|access[_-]?key|private[_-]?key|signing[_-]?key|token|secret|password|passwd|passphrase|authorization|credential)s?["']?\s*[:=]\s*(?:(?:Bearer|Basic|Token)\s+)?)(["']?)([^\s"',;}]{6,})\2/gi
+
+/** Values that follow a credential-like name without being one, e.g. `max_tokens: 4096`. */
+const NOT_A_SECRET = /^(?:null|true|false|undefined|none|\d+|\[REDACTED\])$/i
+
+const BEARER_PATTERN = /\b(Bearer\s+)([A-Za-z0-9._~+/-]{16,}=*)/g
+const URL_CREDENTIALS_PATTERN = /(\b[a-z][a-z0-9+.-]*:\/\/[^\s:@/]+:)([^\s@/]+)(@)/gi
+
+/** `.env` and shell lines: an uppercase variable name assigned a value. */
+const ENV_ASSIGNMENT_PATTERN = /^(\s*(?:export\s+)?[A-Z][A-Z0-9_]*\s*=\s*["']?)([A-Za-z0-9_+/=.-]{16,})/gm
+const ENV_VALUE_MIN_BITS = 3.5
+/**
+ * Bare alphanumeric runs. IDs with separators (`msg_…`, `chatcmpl-…`) look
+ * just as random, so only unbroken runs are judged, and with a high bar.
+ */
+const HIGH_ENTROPY_CANDIDATE = /\b[A-Za-z0-9]{32,}\b/g
+const HIGH_ENTROPY_MIN_BITS = 4.5
+/** Longer runs are encoded payloads rather than keys, and are left alone. */
+const MAX_KEY_CHARS = 256
+
+/** Known values shorter than this would redact ordinary words. */
+const MIN_KNOWN_SECRET_CHARS = 8
+
+const SENSITIVE_KEY = /api[_-]?key|authorization|bearer|token|password|secret|credential/i
+
+/**
+ * Redacts credential-shaped text using only the fixed patterns and entropy
+ * heuristics, for callers without a SecretRedactor.
+ */
+export function redactSecrets(text: string): string {
+  let result = text
+  for (const pattern of TOKEN_PATTERNS) result = result.replace(pattern, REDACTED)
+  result = result.replace(ASSIGNMENT_PATTERN, (match, name: string, quote: string, value: string) =>
+    NOT_A_SECRET.test(value) ? match : `${name}${quote}${REDACTED}${quote}`)
+  result = result.replace(BEARER_PATTERN, `$1${REDACTED}`)
+  result = result.replace(URL_CREDENTIALS_PATTERN, `$1${REDACTED}$3`)
+  result = result.replace(ENV_ASSIGNMENT_PATTERN, (match, name: string, value: string) =>
+    looksRandom(value, ENV_VALUE_MIN_BITS) ? `${name}${REDACTED}` : match)
+  return result.replace(HIGH_ENTROPY_CANDIDATE, (match) =>
+    match.length <= MAX_KEY_CHARS && looksRandom(match, HIGH_ENTROPY_MIN_BITS) ? REDACTED : match)
+}
+
+/**
+ * Strips secrets from text before it reaches model context, traces or
+ * stored previews. On top of the fixed patterns it knows the literal values
+ * of configured keys and of everything read from the API key store, so a
+ * password that has no recognizable shape is still caught.
+ */
+export class SecretRedactor {
+  private readonly known = new Set<string>()
+
+  register(value: string | null | undefined): void {
+    const trimmed = value?.trim()
+    if (trimmed && trimmed.length >= MIN_KNOWN_SECRET_CHARS) this.known.add(trimmed)
+  }
+
+  /** A stored key; JSON account settings register their credential-like fields instead of the whole blob. */
+  registerStored(value: string): void {
+    let parsed: unknown
+    try {
+      parsed = JSON.parse(value)
+    } catch {
+      this.register(value)
+      return
+    }
+    if (typeof parsed === 'string') this.register(parsed)
+    else collectSensitive(parsed, (secret) => this.register(secret))
+  }
+
+  redact(text: string): string {
+    let result = text
+    // Longest first, so a value containing another is replaced whole
+    for (const secret of [...this.known].sort((a, b) => b.length - a.length)) {
+      if (result.includes(secret)) result = result.split(secret).join(REDACTED)
+    }
+    return redactSecrets(result)
+  }
+
+  /** Redacts every string in a JSON-like value, and the whole value of credential-like keys. */
+  redactValue<T>(value: T): T {
+    return this.walk(value) as T
+  }
+
+  private walk(value: unknown): unknown {
+    if (typeof value === 'string') return this.redact(value)
+    if (Array.isArray(value)) return value.map((item) => this.walk(item))
+    if (value && typeof value === 'object') {
+      const out: Record<string, unknown> = {}
+      for (const [key, raw] of Object.entries(value)) {
+        out[key] = typeof raw === 'string' && SENSITIVE_KEY.test(key) && !NOT_A_SECRET.test(raw) && raw !== '' ? REDACTED : this.walk(raw)
+      }
+      return out
+    }
+    return value
+  }
+}
+
+/** Registers every key the wrapped repository reads or stores, so it is redacted wherever it shows up. */
+export function withSecretTracking(repo: ApiKeyRepository, redactor: SecretRedactor): ApiKeyRepository {
+  const track = (record: ApiKeyRecord | null) => {
+    // The repository hands back the decrypted value under this name
+    if (record) redactor.registerStored(record.encryptedKey)
+    return record
+  }
+  return {
+    getByProvider: async (provider) => track(await repo.getByProvider(provider)),
+    upsert: async (provider, plaintextKey) => track(await repo.upsert(provider, plaintextKey))!,
+    delete: (provider) => repo.delete(provider),
+  }
+}
+
+function collectSensitive(value: unknown, add: (secret: string) => void): void {
+  if (Array.isArray(value)) {
+    for (const item of value) collectSensitive(item, add)
+  } else if (value && typeof value === 'object') {
+    for (const [key, raw] of Object.entries(value)) {
+      if (typeof raw === 'string' && SENSITIVE_KEY.test(key)) add(raw)
+      else collectSensitive(raw, add)
+    }
+  }
+}
+
+/** Letters and digits spread near-randomly (Shannon entropy per character), as generated keys are. */
+function looksRandom(text: string, minBits: number): boolean {
+  if (!/[a-z]/i.test(text) || !/\d/.test(text)) return false
+  const counts = new Map<string, number>()
+  for (const char of text) counts.set(char, (counts.get(char) ?? 0) + 1)
+  let entropy = 0
+  for (const count of counts.values()) {
+    const p = count / text.length
+    entropy -= p * Math.log2(p)
+  }
+  return entropy >= minBits
+}
@@ -0,0 +1,10 @@
+import path from 'path'
+import { fileURLToPath } from 'node:url'
+
+// server/src/lib → server/
+export const SERVER_ROOT = path.resolve(path.dirname(fileURLToPath(import.meta.url)), '../../')
+
+/** Relative paths in the config are relative to the server directory, whatever the working directory. */
+export function resolveServerPath(value: string): string {
+  return path.isAbsolute(value) ? value : path.resolve(SERVER_ROOT, value)
+}
@@ -0,0 +1,213 @@
+import { createHash } from 'crypto'
+import { EventEmitter } from 'events'
+import type { IncomingMessage } from 'http'
+import type { Duplex } from 'stream'
+
+/**
+ * Minimal RFC 6455 server side: text messages, fragmentation, ping/pong and
+ * close. Enough for the local bridge without pulling in a dependency;
+ * extensions (compression) and binary messages are not supported.
+ */
+
+const ACCEPT_GUID = '258EAFA5-E914-47DA-95CA-C5AB0DC85B11'
+const MAX_MESSAGE_BYTES = 1024 * 1024
+
+const OPCODE = {
+  CONTINUATION: 0x0,
+  TEXT: 0x1,
+  BINARY: 0x2,
+  CLOSE: 0x8,
+  PING: 0x9,
+  PONG: 0xa,
+} as const
+
+const CLOSE_CODE = {
+  NORMAL: 1000,
+  PROTOCOL_ERROR: 1002,
+  UNSUPPORTED_DATA: 1003,
+  TOO_BIG: 1009,
+} as const
+
+class ProtocolError extends Error {
+  constructor(readonly code: number, message: string) {
+    super(message)
+  }
+}
+
+interface Frame {
+  fin: boolean
+  opcode: number
+  payload: Buffer
+  length: number
+}
+
+export interface WebSocketConnection {
+  on(event: 'message', listener: (text: string) => void): this
+  on(event: 'close', listener: () => void): this
+}
+
+export class WebSocketConnection extends EventEmitter {
+  private buffer = Buffer.alloc(0)
+  private fragments: Buffer[] = []
+  private fragmentBytes = 0
+  private closed = false
+
+  constructor(private readonly socket: Duplex) {
+    super()
+    socket.on('data', (chunk: Buffer) => this.onData(chunk))
+    socket.on('close', () => this.finish())
+    socket.on('error', () => this.finish())
+  }
+
+  get isOpen(): boolean {
+    return !this.closed
+  }
+
+  send(text: string): void {
+    if (this.closed) return
+    this.socket.write(encodeFrame(OPCODE.TEXT, Buffer.from(text, 'utf-8')))
+  }
+
+  close(code: number = CLOSE_CODE.NORMAL, reason = ''): void {
+    if (this.closed) return
+    const payload = Buffer.alloc(2 + Buffer.byteLength(reason))
+    payload.writeUInt16BE(code, 0)
+    payload.write(reason, 2)
+    this.socket.end(encodeFrame(OPCODE.CLOSE, payload))
+    this.finish()
+  }
+
+  private onData(chunk: Buffer): void {
+    this.buffer = Buffer.concat([this.buffer, chunk])
+    try {
+      for (let frame = decodeFrame(this.buffer); frame; frame = decodeFrame(this.buffer)) {
+        this.buffer = this.buffer.subarray(frame.length)
+        this.handleFrame(frame)
+        if (this.closed) return
+      }
+    } catch (err) {
+      if (err instanceof ProtocolError) {
+        this.close(err.code, err.message)
+      } else {
+        this.socket.destroy()
+        this.finish()
+      }
+    }
+  }
+
+  private handleFrame(frame: Frame): void {
+    switch (frame.opcode) {
+      case OPCODE.TEXT:
+      case OPCODE.CONTINUATION: {
+        if ((frame.opcode === OPCODE.TEXT) !== (this.fragments.length === 0)) {
+          throw new ProtocolError(CLOSE_CODE.PROTOCOL_ERROR, 'unexpected continuation frame')
+        }
+        this.fragmentBytes += frame.payload.length
+        if (this.fragmentBytes > MAX_MESSAGE_BYTES) {
+          throw new ProtocolError(CLOSE_CODE.TOO_BIG, 'message too large')
+        }
+        this.fragments.push(frame.payload)
+        if (frame.fin) {
+          const text = Buffer.concat(this.fragments).toString('utf-8')
+          this.fragments = []
+          this.fragmentBytes = 0
+          this.emit('message', text)
+        }
+        break
+      }
+      case OPCODE.BINARY:
+        throw new ProtocolError(CLOSE_CODE.UNSUPPORTED_DATA, 'binary messages are not supported')
+      case OPCODE.PING:
+        this.socket.write(encodeFrame(OPCODE.PONG, frame.payload))
+        break
+      case OPCODE.PONG:
+        break
+      case OPCODE.CLOSE:
+        this.close(CLOSE_CODE.NORMAL)
+        break
+      default:
+        throw new ProtocolError(CLOSE_CODE.PROTOCOL_ERROR, `unknown opcode ${frame.opcode}`)
+    }
+  }
+
+  private finish(): void {
+    if (this.closed) return
+    this.closed = true
+    this.emit('close')
+  }
+}
+
+/** Complete the handshake for an HTTP upgrade request; null (and a 400) when it is not a valid one. */
+export function acceptWebSocket(req: IncomingMessage, socket: Duplex): WebSocketConnection | null {
+  const key = req.headers['sec-websocket-key']
+  if (
+    req.headers.upgrade?.toLowerCase() !== 'websocket' ||
+    req.headers['sec-websocket-version'] !== '13' ||
+    typeof key !== 'string'
+  ) {
+    socket.end('HTTP/1.1 400 Bad Request\r\nConnection: close\r\n\r\n')
+    return null
+  }
+
+  const accept = createHash('sha1').update(key + ACCEPT_GUID).digest('base64')
+  socket.write([
+    'HTTP/1.1 101 Switching Protocols',
+    'Upgrade: websocket',
+    'Connection: Upgrade',
+    `Sec-WebSocket-Accept: ${accept}`,
+    '',
+    '',
+  ].join('\r\n'))
+  return new WebSocketConnection(socket)
+}
+
+export function encodeFrame(opcode: number, payload: Buffer): Buffer {
+  let header: Buffer
+  if (payload.length < 126) {
+    header = Buffer.from([0x80 | opcode, payload.length])
+  } else if (payload.length < 65536) {
+    header = Buffer.alloc(4)
+    header[0] = 0x80 | opcode
+    header[1] = 126
+    header.writeUInt16BE(payload.length, 2)
+  } else {
+    header = Buffer.alloc(10)
+    header[0] = 0x80 | opcode
+    header[1] = 127
+    header.writeBigUInt64BE(BigInt(payload.length), 2)
+  }
+  return Buffer.concat([header, payload])
+}
+
+/** Parse one client frame from the front of `buffer`; null until it is complete. */
+export function decodeFrame(buffer: Buffer): Frame | null {
+  if (buffer.length < 2) return null
+  const fin = (buffer[0] & 0x80) !== 0
+  const opcode = buffer[0] & 0x0f
+  if ((buffer[1] & 0x80) === 0) {
+    throw new ProtocolError(CLOSE_CODE.PROTOCOL_ERROR, 'client frames must be masked')
+  }
+
+  let length = buffer[1] & 0x7f
+  let offset = 2
+  if (length === 126) {
+    if (buffer.length < 4) return null
+    length = buffer.readUInt16BE(2)
+    offset = 4
+  } else if (length === 127) {
+    if (buffer.length < 10) return null
+    const long = buffer.readBigUInt64BE(2)
+    if (long > BigInt(MAX_MESSAGE_BYTES)) throw new ProtocolError(CLOSE_CODE.TOO_BIG, 'message too large')
+    length = Number(long)
+    offset = 10
+  }
+  if (length > MAX_MESSAGE_BYTES) throw new ProtocolError(CLOSE_CODE.TOO_BIG, 'message too large')
+  if (buffer.length < offset + 4 + length) return null
+
+  const mask = buffer.subarray(offset, offset + 4)
+  const payload = Buffer.alloc(length)
+  for (let i = 0; i < length; i++) {
+    payload[i] = buffer[offset + 4 + i] ^ mask[i % 4]
+  }
+  return { fin, opcode, payload, length: offset + 4 + length }
+}
@@ -0,0 +1,152 @@
+import fs from 'fs'
+import path from 'path'
+import type { AppConfig } from './config.js'
+import { resolveServerPath } from './server-root.js'
+
+export const DEFAULT_WORKSPACE = 'default'
+
+const WORKSPACE_NAME_RE = /^[a-z0-9][a-z0-9_-]{0,63}$/i
+const ACTIVE_FILE = 'active'
+
+/**
+ * Workspaces keep separate stores for unrelated contexts (e.g. work vs
+ * personal). Each non-default workspace gets its own sqlite file, task,
+ * notes, and session-file roots under `<workspacesDir>/<name>/`. Because API
+ * keys, MCP credentials, and preferences live in the database, they are
+ * scoped to the workspace automatically.
+ *
+ * The default workspace keeps the paths configured in the environment so
+ * existing installs are unaffected. The workspaces dir and the sqlite file
+ * are resolved against the server directory, like every other data dir, so
+ * a workspace's paths never depend on the working directory.
+ */
+export function applyWorkspace(config: AppConfig): AppConfig {
+  const workspacesDir = resolveServerPath(config.workspacesDir)
+  const name = config.workspace ?? readActiveWorkspace(workspacesDir) ?? DEFAULT_WORKSPACE
+  assertWorkspaceName(name)
+
+  if (name === DEFAULT_WORKSPACE) {
+    return { ...config, workspace: name, workspacesDir, databaseUrl: sqlitePath(config) }
+  }
+
+  if (config.dbDialect !== 'sqlite') {
+    throw new Error('Named workspaces require DB_DIALECT=sqlite; use a separate DATABASE_URL per postgres deployment')
+  }
+
+  const root = path.join(workspacesDir, name)
+  return {
+    ...config,
+    workspace: name,
+    workspacesDir,
+    databaseUrl: path.join(root, 'app.db'),
+    tasksDir: path.join(root, 'tasks'),
+    workspaceDir: path.join(root, 'workspace'),
+    sessionFilesDir: path.join(root, 'sessions'),
+    attachmentsDir: path.join(root, 'attachments'),
+    notesDir: path.join(root, 'research-notes'),
+    projectsDir: path.join(root, 'projects'),
+    tracesDir: path.join(root, 'traces'),
+    auditDir: path.join(root, 'audit'),
+  }
+}
+
+function sqlitePath(config: AppConfig): string {
+  if (config.dbDialect !== 'sqlite' || config.databaseUrl === ':memory:') return config.databaseUrl
+  return resolveServerPath(config.databaseUrl)
+}
+
+export function assertWorkspaceName(name: string): void {
+  if (!WORKSPACE_NAME_RE.test(name)) {
+    throw new Error(`Invalid workspace name '${name}': use letters, digits, '-' or '_' (max 64 chars)`)
+  }
+}
+
+export function listWorkspaces(workspacesDir: string): string[] {
+  let entries: fs.Dirent[] = []
+  try {
+    entries = fs.readdirSync(workspacesDir, { withFileTypes: true })
+  } catch {
+    // No named workspaces yet
+  }
+  const names = entries
+    .filter((entry) => entry.isDirectory() && WORKSPACE_NAME_RE.test(entry.name))
+    .map((entry) => entry.name)
+    .filter((name) => name !== DEFAULT_WORKSPACE)
+    .sort()
+  return [DEFAULT_WORKSPACE, ...names]
+}
+
+export function readActiveWorkspace(workspacesDir: string): string | null {
+  try {
+    const value = fs.readFileSync(path.join(workspacesDir, ACTIVE_FILE), 'utf-8').trim()
+    return value || null
+  } catch {
+    return null
+  }
+}
+
+/** Persist the workspace to open on the next start. */
+export function writeActiveWorkspace(workspacesDir: string, name: string): void {
+  assertWorkspaceName(name)
+  fs.mkdirSync(path.join(workspacesDir, name), { recursive: true })
+  fs.writeFileSync(path.join(workspacesDir, ACTIVE_FILE), `${name}\n`, 'utf-8')
+}
+
+export interface WorkspaceHost<R> {
+  init(config: AppConfig): Promise<R>
+  shutdown(runtime: R): Promise<void>
+  /** Called with each runtime once it serves requests. */
+  activate(runtime: R): void
+}
+
+/**
+ * Switches the running server between workspaces: the current runtime is
+ * shut down (its database closed, background services stopped) and one is
+ * built from the base config for the new workspace. If that fails, the
+ * previous workspace is opened again. Switches run one at a time.
+ */
+export class WorkspaceSwitcher<R extends { config: AppConfig }> {
+  private queue: Promise<unknown> = Promise.resolve()
+
+  /** `baseConfig` is the config before any workspace was applied. */
+  constructor(
+    private readonly baseConfig: AppConfig,
+    private current: R,
+    private readonly host: WorkspaceHost<R>,
+  ) {}
+
+  get runtime(): R {
+    return this.current
+  }
+
+  /** Resolves once no switch is in progress. */
+  idle(): Promise<void> {
+    return this.queue.then(() => undefined)
+  }
+
+  switch(name: string): Promise<R> {
+    const run = this.queue.then(() => this.run(name))
+    this.queue = run.catch(() => undefined)
+    return run
+  }
+
+  private async run(name: string): Promise<R> {
+    assertWorkspaceName(name)
+    if (name === this.current.config.workspace) return this.current
+    const config = applyWorkspace({ ...this.baseConfig, workspace: name })
+    const previous = this.current
+    await this.host.shutdown(previous)
+    let next: R
+    try {
+      next = await this.host.init(config)
+    } catch (err) {
+      this.current = await this.host.init(previous.config)
+      this.host.activate(this.current)
+      throw err
+    }
+    writeActiveWorkspace(config.workspacesDir, name)
+    this.current = next
+    this.host.activate(next)
+    return next
+  }
+}
@@ -0,0 +1,70 @@
+/** Minimal XML reader: elements keep local names only, so namespace prefixes never matter. */
+export interface XmlElement {
+  /** Local name, without the namespace prefix. */
+  name: string
+  attrs: Record<string, string>
+  children: Array<XmlElement | string>
+}
+
+const ENTITIES: Record<string, string> = { amp: '&', apos: "'", gt: '>', lt: '<', quot: '"' }
+
+export function child(element: XmlElement, name: string): XmlElement | undefined {
+  return children(element, name)[0]
+}
+
+export function children(element: XmlElement, name: string): XmlElement[] {
+  return element.children.filter((node): node is XmlElement => typeof node !== 'string' && node.name === name)
+}
+
+export function descendants(element: XmlElement | null, name: string, found: XmlElement[] = []): XmlElement[] {
+  for (const node of element?.children ?? []) {
+    if (typeof node === 'string') continue
+    if (node.name === name) found.push(node)
+    descendants(node, name, found)
+  }
+  return found
+}
+
+export function textContent(element: XmlElement | undefined): string {
+  return element?.children.map((node) => (typeof node === 'string' ? node : textContent(node))).join('') ?? ''
+}
+
+export function parseXml(xml: string): XmlElement {
+  const root: XmlElement = { name: '#document', attrs: {}, children: [] }
+  const stack = [root]
+  const tokens = /<!--[\s\S]*?-->|<!\[CDATA\[([\s\S]*?)\]\]>|<[?!][^>]*>|<(\/?)([^\s/>]+)([^>]*?)(\/?)>|([^<]+)/g
+  for (const [, cdata, closing, tag, attrs, selfClosing, text] of xml.matchAll(tokens)) {
+    const parent = stack[stack.length - 1]
+    if (cdata !== undefined) {
+      parent.children.push(cdata)
+    } else if (text !== undefined) {
+      parent.children.push(decodeEntities(text))
+    } else if (tag && closing) {
+      const name = localName(tag)
+      while (stack.length > 1 && stack.pop()!.name !== name) { /* unwind unclosed elements */ }
+    } else if (tag) {
+      const element: XmlElement = { name: localName(tag), attrs: parseAttributes(attrs), children: [] }
+      parent.children.push(element)
+      if (!selfClosing) stack.push(element)
+    }
+  }
+  return root
+}
+
+function parseAttributes(source: string): Record<string, string> {
+  const attrs: Record<string, string> = {}
+  for (const [, name, , value] of source.matchAll(/([^\s=]+)\s*=\s*(["'])([\s\S]*?)\2/g)) attrs[name] = decodeEntities(value)
+  return attrs
+}
+
+function localName(name: string): string {
+  return name.slice(name.indexOf(':') + 1)
+}
+
+function decodeEntities(text: string): string {
+  return text.replace(/&(#x[0-9a-f]+|#\d+|\w+);/gi, (entity, code: string) => {
+    if (code[0] !== '#') return ENTITIES[code] ?? entity
+    const point = code[1] === 'x' || code[1] === 'X' ? parseInt(code.slice(2), 16) : parseInt(code.slice(1), 10)
+    return Number.isFinite(point) && point <= 0x10ffff ? String.fromCodePoint(point) : entity
+  })
+}
@@ -0,0 +1,72 @@
+import { inflateRawSync } from 'zlib'
+
+/**
+ * Minimal ZIP reader for Office documents. It reads the central directory
+ * and inflates entries on demand; stored and deflated entries are supported,
+ * ZIP64 and encrypted archives are not.
+ */
+export interface ZipArchive {
+  names(): string[]
+  /** The entry's contents, or null when the archive has no such entry. */
+  read(name: string): Buffer | null
+}
+
+interface ZipEntry {
+  method: number
+  compressedSize: number
+  size: number
+  offset: number
+}
+
+const END_OF_CENTRAL_DIRECTORY = 0x06054b50
+const CENTRAL_DIRECTORY_HEADER = 0x02014b50
+const LOCAL_FILE_HEADER = 0x04034b50
+
+export function isZip(data: Uint8Array): boolean {
+  return data.length >= 4 && data[0] === 0x50 && data[1] === 0x4b && data[2] === 0x03 && data[3] === 0x04
+}
+
+/** `maxEntryBytes` caps each inflated entry, so a small archive cannot expand without bound. */
+export function readZip(data: Buffer, maxEntryBytes: number): ZipArchive {
+  const entries = new Map<string, ZipEntry>()
+  const end = findEndOfCentralDirectory(data)
+  const count = data.readUInt16LE(end + 10)
+  let offset = data.readUInt32LE(end + 16)
+  for (let i = 0; i < count; i++) {
+    if (offset + 46 > data.length || data.readUInt32LE(offset) !== CENTRAL_DIRECTORY_HEADER) {
+      throw new Error('Corrupt ZIP central directory')
+    }
+    const nameLength = data.readUInt16LE(offset + 28)
+    const name = data.toString('utf8', offset + 46, offset + 46 + nameLength)
+    entries.set(name, {
+      method: data.readUInt16LE(offset + 10),
+      compressedSize: data.readUInt32LE(offset + 20),
+      size: data.readUInt32LE(offset + 24),
+      offset: data.readUInt32LE(offset + 42),
+    })
+    offset += 46 + nameLength + data.readUInt16LE(offset + 30) + data.readUInt16LE(offset + 32)
+  }
+
+  return {
+    names: () => [...entries.keys()],
+    read(name) {
+      const entry = entries.get(name)
+      if (!entry) return null
+      if (entry.size > maxEntryBytes) throw new Error(`ZIP entry ${name} is too large (${entry.size} bytes)`)
+      if (data.readUInt32LE(entry.offset) !== LOCAL_FILE_HEADER) throw new Error(`Corrupt ZIP entry ${name}`)
+      const start = entry.offset + 30 + data.readUInt16LE(entry.offset + 26) + data.readUInt16LE(entry.offset + 28)
+      const body = data.subarray(start, start + entry.compressedSize)
+      if (entry.method === 0) return body
+      if (entry.method === 8) return inflateRawSync(body, { maxOutputLength: maxEntryBytes })
+      throw new Error(`Unsupported ZIP compression method ${entry.method} for ${name}`)
+    },
+  }
+}
+
+function findEndOfCentralDirectory(data: Buffer): number {
+  // The record is 22 bytes plus a comment of up to 64 KiB
+  for (let offset = data.length - 22; offset >= Math.max(0, data.length - 22 - 0xffff); offset--) {
+    if (data.readUInt32LE(offset) === END_OF_CENTRAL_DIRECTORY) return offset
+  }
+  throw new Error('Not a ZIP archive')
+}
@@ -198,6 +198,13 @@ export class McpManager {
       }))
   }
 
+  /** Round-trips a ping to a connected server; throws when it does not answer. */
+  async ping(id: string, signal?: AbortSignal): Promise<void> {
+    const client = this.clients.get(id)
+    if (!client) throw new Error('Not connected')
+    await client.ping({ signal })
+  }
+
   async connectServer(userId: string, id: string): Promise<McpServerRecord> {
     await this.repository.updateServer(userId, id, { enabled: true, updatedAt: Date.now() })
     await this.connect(userId, id)
@@ -0,0 +1,243 @@
+import fs from 'fs/promises'
+import path from 'path'
+import { UNPACED } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentErrorCode, type AgentEvent, type EventSource } from '../events/types.js'
+import { logger } from '../lib/logger.js'
+import type { PreferenceRepository, SessionRepository } from '../repositories/types.js'
+import type { GenerationTraceRecord, TraceSink } from './debug-traces.js'
+
+export const AUDIT_LOG_PREFERENCE_KEY = 'audit_log'
+
+const DAY_MS = 24 * 60 * 60 * 1000
+const AUDIT_FILE_RE = /^(\d{4}-\d{2}-\d{2})\.jsonl$/
+const OUTPUT_PREVIEW_CHARS = 2000
+
+const AUDITED_EVENTS = [
+  EVENT_TYPES.TOOL_STARTED,
+  EVENT_TYPES.TOOL_COMPLETED,
+  EVENT_TYPES.TOOL_APPROVED,
+  EVENT_TYPES.TOOL_DENIED,
+  EVENT_TYPES.TOOL_APPROVAL_EXPIRED,
+]
+
+export const AUDIT_ENTRY_KINDS = ['tool_call', 'tool_result', 'approval', 'llm_call'] as const
+export type AuditEntryKind = (typeof AUDIT_ENTRY_KINDS)[number]
+
+interface AuditEntryBase {
+  at: number
+  userId: string | null
+  sessionId: string
+  agentId: string
+}
+
+export type AuditEntry = AuditEntryBase & (
+  | { kind: 'tool_call'; callId: string; tool: string; args: Record<string, unknown> }
+  | {
+    kind: 'tool_result'
+    callId: string
+    tool: string
+    success: boolean
+    durationMs: number
+    errorCode: AgentErrorCode | null
+    outputBytes: number
+    /** First OUTPUT_PREVIEW_CHARS characters; the full output stays in the session. */
+    output: string
+  }
+  | { kind: 'approval'; callId: string; tool: string; decision: 'approved' | 'denied' | 'expired' }
+  | {
+    kind: 'llm_call'
+    provider: string
+    model: string
+    turnNumber: number
+    stream: boolean
+    durationMs: number
+    inputTokens: number | null
+    outputTokens: number | null
+    error: string | null
+  }
+)
+
+/** Distributes Omit over the AuditEntry union so each kind keeps its own fields. */
+type NewAuditEntry = AuditEntry extends infer E ? E extends AuditEntry ? Omit<E, 'at' | 'userId'> : never : never
+
+export interface AuditQuery {
+  userId: string
+  /** Inclusive, epoch milliseconds. */
+  from?: number
+  /** Exclusive, epoch milliseconds. */
+  to?: number
+  kinds?: AuditEntryKind[]
+}
+
+export interface AuditLogOptions {
+  dir: string
+  preferences: PreferenceRepository
+  sessions: SessionRepository
+  /** Used when the `audit_log` preference is unset. */
+  defaultEnabled: boolean
+}
+
+/**
+ * Append-only record of what agents did on this machine: every tool call and
+ * result, every approval decision, and the metadata (never the content) of
+ * every LLM request. One JSONL file per UTC day; nothing here deletes them.
+ * Like debug traces it is toggled at runtime through a preference.
+ */
+export class AuditLog implements TraceSink {
+  private readonly sessionOwners = new Map<string, string | null>()
+  private iterator: AsyncIterator<AgentEvent> | null = null
+  private writing: Promise<void> = Promise.resolve()
+
+  constructor(private readonly options: AuditLogOptions) {}
+
+  get dir(): string {
+    return this.options.dir
+  }
+
+  /** Follow tool and approval events until stop(). */
+  start(events: EventSource): void {
+    if (this.iterator) return
+    const iterator = events.subscribe({ types: AUDITED_EVENTS }, UNPACED)[Symbol.asyncIterator]()
+    this.iterator = iterator
+    void (async () => {
+      for (let next = await iterator.next(); !next.done; next = await iterator.next()) {
+        await this.recordEvent(next.value)
+      }
+    })()
+  }
+
+  stop(): void {
+    void this.iterator?.return?.()
+    this.iterator = null
+  }
+
+  async isEnabled(): Promise<boolean> {
+    const value = await this.options.preferences.get(AUDIT_LOG_PREFERENCE_KEY)
+    return value === null ? this.options.defaultEnabled : value === 'true'
+  }
+
+  /** TraceSink hook: one llm_call entry per provider request. */
+  async record(trace: GenerationTraceRecord): Promise<void> {
+    await this.append({
+      kind: 'llm_call',
+      sessionId: trace.agent.sessionId,
+      agentId: trace.agent.id,
+      provider: trace.provider,
+      model: trace.model,
+      turnNumber: trace.turnNumber,
+      stream: trace.stream,
+      durationMs: trace.durationMs,
+      inputTokens: trace.response?.usage.input_tokens ?? null,
+      outputTokens: trace.response?.usage.output_tokens ?? null,
+      error: trace.error ?? null,
+    })
+  }
+
+  async recordEvent(event: AgentEvent): Promise<void> {
+    const base = { sessionId: event.session_id, agentId: event.agent_id }
+    switch (event.type) {
+      case EVENT_TYPES.TOOL_STARTED:
+        return this.append({ ...base, kind: 'tool_call', callId: event.payload.callId, tool: event.payload.name, args: event.payload.args })
+      case EVENT_TYPES.TOOL_COMPLETED:
+        return this.append({
+          ...base,
+          kind: 'tool_result',
+          callId: event.payload.callId,
+          tool: event.payload.name,
+          success: event.payload.success,
+          durationMs: event.payload.durationMs,
+          errorCode: event.payload.errorCode ?? null,
+          outputBytes: Buffer.byteLength(event.payload.output),
+          output: event.payload.output.slice(0, OUTPUT_PREVIEW_CHARS),
+        })
+      case EVENT_TYPES.TOOL_APPROVED:
+      case EVENT_TYPES.TOOL_DENIED:
+        return this.append({
+          ...base,
+          kind: 'approval',
+          callId: event.payload.callId,
+          tool: event.payload.name,
+          decision: event.type === EVENT_TYPES.TOOL_APPROVED ? 'approved' : 'denied',
+        })
+      case EVENT_TYPES.TOOL_APPROVAL_EXPIRED:
+        return this.append({ ...base, kind: 'approval', callId: event.payload.callId, tool: event.payload.name, decision: 'expired' })
+    }
+  }
+
+  /** Entries owned by `query.userId`, oldest first. */
+  async *query(query: AuditQuery): AsyncGenerator<AuditEntry> {
+    for (const file of await this.files(query.from, query.to)) {
+      let content: string
+      try {
+        content = await fs.readFile(path.join(this.options.dir, file), 'utf-8')
+      } catch {
+        continue
+      }
+      for (const line of content.split('\n')) {
+        if (!line) continue
+        let entry: AuditEntry
+        try {
+          entry = JSON.parse(line) as AuditEntry
+        } catch {
+          // A torn final line from a crash mid-write; skip it
+          continue
+        }
+        if (entry.userId !== query.userId) continue
+        if (query.from !== undefined && entry.at < query.from) continue
+        if (query.to !== undefined && entry.at >= query.to) continue
+        if (query.kinds && !query.kinds.includes(entry.kind)) continue
+        yield entry
+      }
+    }
+  }
+
+  /** Resolves once every entry appended so far is on disk. */
+  flush(): Promise<void> {
+    return this.writing
+  }
+
+  private async append(entry: NewAuditEntry): Promise<void> {
+    try {
+      if (!(await this.isEnabled())) return
+      const at = Date.now()
+      const line = JSON.stringify({ at, userId: await this.resolveOwner(entry.sessionId), ...entry }) + '\n'
+      const file = path.join(this.options.dir, `${new Date(at).toISOString().slice(0, 10)}.jsonl`)
+      // Chain writes so lines land in the order they were recorded
+      this.writing = this.writing.then(async () => {
+        await fs.mkdir(this.options.dir, { recursive: true })
+        await fs.appendFile(file, line, 'utf-8')
+      }).catch((err) => {
+        logger.warn({ err }, 'Failed to append audit entry')
+      })
+      await this.writing
+    } catch (err) {
+      // Auditing must never fail a run
+      logger.warn({ err, sessionId: entry.sessionId }, 'Failed to record audit entry')
+    }
+  }
+
+  private async files(from?: number, to?: number): Promise<string[]> {
+    let names: string[]
+    try {
+      names = await fs.readdir(this.options.dir)
+    } catch {
+      return []
+    }
+    return names
+      .filter((name) => {
+        const match = AUDIT_FILE_RE.exec(name)
+        if (!match) return false
+        const dayStart = Date.parse(`${match[1]}T00:00:00Z`)
+        return (from === undefined || dayStart + DAY_MS > from) && (to === undefined || dayStart < to)
+      })
+      .sort()
+  }
+
+  private async resolveOwner(sessionId: string): Promise<string | null> {
+    if (this.sessionOwners.has(sessionId)) return this.sessionOwners.get(sessionId) ?? null
+    const session = await this.options.sessions.getById(sessionId)
+    const owner = session?.userId ?? null
+    if (session) this.sessionOwners.set(sessionId, owner)
+    return owner
+  }
+}
@@ -0,0 +1,208 @@
+import fs from 'fs/promises'
+import path from 'path'
+import type { Agent } from '../domain/types.js'
+import { logger } from '../lib/logger.js'
+import type { SecretRedactor } from '../lib/secrets.js'
+import type { LLMRequest, LLMResponse } from '../providers/types.js'
+import type { PreferenceRepository } from '../repositories/types.js'
+
+export const DEBUG_TRACES_PREFERENCE_KEY = 'debug_traces'
+
+const DAY_MS = 24 * 60 * 60 * 1000
+const PRUNE_INTERVAL_MS = 60 * 60 * 1000
+const TRACE_FILE_RE = /^\d+-[A-Za-z0-9_-]+-t\d+\.json$/
+const SESSION_DIR_RE = /^[A-Za-z0-9_-]+$/
+
+export interface GenerationTraceRecord {
+  agent: Agent
+  turnNumber: number
+  provider: string
+  model: string
+  stream: boolean
+  request: LLMRequest
+  response?: LLMResponse
+  error?: string
+  durationMs: number
+}
+
+/** Persists exactly what the model saw and returned for each LLM call. */
+export interface TraceSink {
+  record(trace: GenerationTraceRecord): Promise<void>
+}
+
+export interface DebugTraceOptions {
+  dir: string
+  preferences: PreferenceRepository
+  /** Used when the `debug_traces` preference is unset. */
+  defaultEnabled: boolean
+  maxTraceBytes: number
+  maxTotalBytes: number
+  retentionDays: number
+  /** Traces are meant to be attached to bug reports, so keys are stripped before writing. */
+  redactor?: SecretRedactor
+}
+
+export interface TraceFileInfo {
+  file: string
+  bytes: number
+  createdAt: number
+}
+
+/**
+ * Local trace capture for bug reports. Unlike Langfuse this never leaves the
+ * machine, and it is toggled at runtime through a preference so it can be
+ * switched on in a production install without a restart.
+ */
+export class DebugTraceRecorder implements TraceSink {
+  private timer: NodeJS.Timeout | null = null
+
+  constructor(private readonly options: DebugTraceOptions) {}
+
+  get dir(): string {
+    return this.options.dir
+  }
+
+  start(): void {
+    if (this.timer) return
+    void this.prune()
+    this.timer = setInterval(() => {
+      void this.prune()
+    }, PRUNE_INTERVAL_MS)
+    this.timer.unref()
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer)
+      this.timer = null
+    }
+  }
+
+  async isEnabled(): Promise<boolean> {
+    const value = await this.options.preferences.get(DEBUG_TRACES_PREFERENCE_KEY)
+    return value === null ? this.options.defaultEnabled : value === 'true'
+  }
+
+  async record(trace: GenerationTraceRecord): Promise<void> {
+    try {
+      if (!(await this.isEnabled())) return
+      const createdAt = Date.now()
+      const redact = <T>(value: T): T => (this.options.redactor ? this.options.redactor.redactValue(value) : value)
+      const body = fitToSize({
+        agentId: trace.agent.id,
+        sessionId: trace.agent.sessionId,
+        parentId: trace.agent.parentId,
+        depth: trace.agent.depth,
+        turnNumber: trace.turnNumber,
+        provider: trace.provider,
+        model: trace.model,
+        stream: trace.stream,
+        durationMs: trace.durationMs,
+        createdAt,
+        request: redact(trace.request),
+        response: redact(trace.response ?? null),
+        error: redact(trace.error ?? null),
+      }, this.options.maxTraceBytes)
+
+      const dir = path.join(this.options.dir, trace.agent.sessionId)
+      await fs.mkdir(dir, { recursive: true })
+      await fs.writeFile(path.join(dir, `${createdAt}-${trace.agent.id}-t${trace.turnNumber}.json`), body, 'utf-8')
+    } catch (err) {
+      // Tracing must never fail a run
+      logger.warn({ err, agentId: trace.agent.id }, 'Failed to write debug trace')
+    }
+  }
+
+  async list(sessionId: string): Promise<TraceFileInfo[]> {
+    if (!SESSION_DIR_RE.test(sessionId)) return []
+    const dir = path.join(this.options.dir, sessionId)
+    let files: string[]
+    try {
+      files = await fs.readdir(dir)
+    } catch {
+      return []
+    }
+    const traces: TraceFileInfo[] = []
+    for (const file of files.filter((name) => TRACE_FILE_RE.test(name)).sort()) {
+      const stat = await fs.stat(path.join(dir, file))
+      traces.push({ file, bytes: stat.size, createdAt: Number(file.slice(0, file.indexOf('-'))) })
+    }
+    return traces
+  }
+
+  async read(sessionId: string, file: string): Promise<string | null> {
+    if (!SESSION_DIR_RE.test(sessionId) || !TRACE_FILE_RE.test(file)) return null
+    try {
+      return await fs.readFile(path.join(this.options.dir, sessionId, file), 'utf-8')
+    } catch {
+      return null
+    }
+  }
+
+  async removeSession(sessionId: string): Promise<void> {
+    if (!SESSION_DIR_RE.test(sessionId)) return
+    await fs.rm(path.join(this.options.dir, sessionId), { recursive: true, force: true })
+  }
+
+  /** Drop traces past the retention window, then the oldest until under the total cap. */
+  async prune(now = Date.now()): Promise<number> {
+    let removed = 0
+    try {
+      const all = await this.listAll()
+      const cutoff = now - this.options.retentionDays * DAY_MS
+      let total = all.reduce((sum, trace) => sum + trace.bytes, 0)
+      for (const trace of all) {
+        const expired = this.options.retentionDays > 0 && trace.createdAt < cutoff
+        if (!expired && total <= this.options.maxTotalBytes) continue
+        await fs.rm(trace.path, { force: true })
+        total -= trace.bytes
+        removed++
+      }
+      if (removed > 0) {
+        logger.info({ removed }, 'Pruned debug traces')
+      }
+    } catch (err) {
+      logger.warn({ err }, 'Debug trace prune failed')
+    }
+    return removed
+  }
+
+  private async listAll(): Promise<Array<TraceFileInfo & { path: string }>> {
+    let sessions: string[]
+    try {
+      sessions = await fs.readdir(this.options.dir)
+    } catch {
+      return []
+    }
+    const all: Array<TraceFileInfo & { path: string }> = []
+    for (const sessionId of sessions) {
+      for (const trace of await this.list(sessionId)) {
+        all.push({ ...trace, path: path.join(this.options.dir, sessionId, trace.file) })
+      }
+    }
+    return all.sort((a, b) => a.createdAt - b.createdAt)
+  }
+}
+
+/**
+ * Serialize, truncating long strings until the result fits `maxBytes`. The
+ * structure is kept intact so a truncated trace still shows every message.
+ */
+export function fitToSize(value: unknown, maxBytes: number): string {
+  let body = JSON.stringify(value, null, 2)
+  for (let limit = 16_384; Buffer.byteLength(body) > maxBytes && limit >= 64; limit = Math.floor(limit / 2)) {
+    body = JSON.stringify(truncateStrings(value, limit), null, 2)
+  }
+  return body
+}
+
+function truncateStrings(value: unknown, limit: number): unknown {
+  if (typeof value === 'string') {
+    return value.length > limit ? `${value.slice(0, limit)}… [truncated ${value.length - limit} chars]` : value
+  }
+  if (Array.isArray(value)) return value.map((entry) => truncateStrings(entry, limit))
+  if (value && typeof value === 'object') {
+    return Object.fromEntries(Object.entries(value).map(([key, entry]) => [key, truncateStrings(entry, limit)]))
+  }
+  return value
+}
@@ -2,6 +2,7 @@ import type { NodeSDK } from '@opentelemetry/sdk-node'
 import type { propagateAttributes, startActiveObservation } from '@langfuse/tracing'
 import type { AppConfig } from '../lib/config.js'
 import { logger } from '../lib/logger.js'
+import { redactSecrets } from '../lib/secrets.js'
 import type { LLMMessage, LLMResponse } from '../providers/types.js'
 import type {
   AgentTraceContext,
@@ -10,12 +11,6 @@ import type {
   ToolTraceContext,
 } from './types.js'
 
-const SECRET_PATTERNS = [
-  /\b(sk-[A-Za-z0-9_-]{12,})\b/g,
-  /\b(pk-[A-Za-z0-9_-]{12,})\b/g,
-  /\b(api[_-]?key|authorization|bearer|token|password|secret)\s*[:=]\s*["']?[^"'\s,}]+/gi,
-]
-
 class NoopObservability implements LLMObservability {
   readonly enabled = false
 
@@ -352,7 +347,7 @@ function summarizeShape(value: unknown): unknown {
 }
 
 function scrub(value: unknown, maxChars: number): unknown {
-  if (typeof value === 'string') return truncate(redact(value), maxChars)
+  if (typeof value === 'string') return truncate(redactSecrets(value), maxChars)
   if (Array.isArray(value)) return value.map((item) => scrub(item, maxChars))
   if (value && typeof value === 'object') {
     const out: Record<string, unknown> = {}
@@ -370,17 +365,6 @@ function scrub(value: unknown, maxChars: number): unknown {
   return value
 }
 
-function redact(value: string): string {
-  let result = value
-  for (const pattern of SECRET_PATTERNS) {
-    result = result.replace(pattern, (match) => {
-      const [prefix] = match.split(/[:=]/)
-      return prefix && prefix !== match ? `${prefix}: [REDACTED]` : '[REDACTED]'
-    })
-  }
-  return result
-}
-
 function truncate(value: string, maxChars: number): string {
   return value.length <= maxChars ? value : `${value.slice(0, maxChars)}...[truncated ${value.length - maxChars} chars]`
 }
@@ -0,0 +1,275 @@
+import http from 'node:http'
+import { UNPACED } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent, type EventSource } from '../events/types.js'
+import { logger } from '../lib/logger.js'
+import { classifyError } from '../orchestrator/errors.js'
+import type { GenerationTraceRecord, TraceSink } from './debug-traces.js'
+
+const METRIC_PREFIX = 'assistant_'
+
+/** Seconds. LLM turns range from sub-second completions to multi-minute tool-heavy streams. */
+export const LATENCY_BUCKETS = [0.05, 0.1, 0.25, 0.5, 1, 2.5, 5, 10, 30, 60, 120, 300]
+
+type Labels = Record<string, string>
+
+export interface CounterSeries {
+  labels: Labels
+  value: number
+}
+
+export interface HistogramSeries {
+  labels: Labels
+  count: number
+  sum: number
+  /** Cumulative counts per upper bound, as in the Prometheus exposition format. */
+  buckets: Array<{ le: number; count: number }>
+  /** Estimated from the buckets; null before the first observation. */
+  p50: number | null
+  p95: number | null
+}
+
+export type MetricFamily =
+  | { name: string; help: string; type: 'counter' | 'gauge'; series: CounterSeries[] }
+  | { name: string; help: string; type: 'histogram'; series: HistogramSeries[] }
+
+export interface MetricsSnapshot {
+  collectedAt: number
+  uptimeSeconds: number
+  metrics: MetricFamily[]
+}
+
+const seriesKey = (labels: Labels) => JSON.stringify(Object.entries(labels).sort(([a], [b]) => a.localeCompare(b)))
+
+class Counter {
+  private readonly series = new Map<string, CounterSeries>()
+
+  constructor(readonly name: string, readonly help: string) {}
+
+  inc(labels: Labels, by = 1): void {
+    if (by === 0) return
+    const key = seriesKey(labels)
+    const entry = this.series.get(key)
+    if (entry) entry.value += by
+    else this.series.set(key, { labels, value: by })
+  }
+
+  collect(): CounterSeries[] {
+    return [...this.series.values()].map((s) => ({ ...s }))
+  }
+}
+
+class Histogram {
+  private readonly series = new Map<string, { labels: Labels; counts: number[]; sum: number; count: number }>()
+
+  constructor(readonly name: string, readonly help: string, readonly bounds: number[]) {}
+
+  observe(labels: Labels, value: number): void {
+    const key = seriesKey(labels)
+    let entry = this.series.get(key)
+    if (!entry) {
+      entry = { labels, counts: new Array<number>(this.bounds.length).fill(0), sum: 0, count: 0 }
+      this.series.set(key, entry)
+    }
+    const index = this.bounds.findIndex((bound) => value <= bound)
+    if (index >= 0) entry.counts[index]++
+    entry.sum += value
+    entry.count++
+  }
+
+  collect(): HistogramSeries[] {
+    return [...this.series.values()].map(({ labels, counts, sum, count }) => {
+      let cumulative = 0
+      const buckets = this.bounds.map((le, i) => ({ le, count: (cumulative += counts[i]) }))
+      return { labels, count, sum, buckets, p50: quantile(buckets, count, 0.5), p95: quantile(buckets, count, 0.95) }
+    })
+  }
+}
+
+/** Linear interpolation inside the bucket holding the q-th observation, like histogram_quantile(). */
+export function quantile(buckets: HistogramSeries['buckets'], count: number, q: number): number | null {
+  if (count === 0) return null
+  const rank = q * count
+  let lowerBound = 0
+  let lowerCount = 0
+  for (const { le, count: upTo } of buckets) {
+    if (upTo >= rank) {
+      const inBucket = upTo - lowerCount
+      return inBucket === 0 ? le : lowerBound + (le - lowerBound) * ((rank - lowerCount) / inBucket)
+    }
+    lowerBound = le
+    lowerCount = upTo
+  }
+  // Beyond the largest bound; the best honest answer is that bound
+  return buckets[buckets.length - 1]?.le ?? null
+}
+
+/**
+ * In-process metrics for performance debugging: LLM and tool latency,
+ * prompt-cache effectiveness and queue depths. Nothing leaves the process;
+ * read it via GET /api/metrics, the bridge's `get_metrics` command, or the
+ * optional Prometheus endpoint.
+ */
+export class Metrics implements TraceSink {
+  private readonly startedAt = Date.now()
+  private readonly llmDuration = new Histogram('llm_request_duration_seconds', 'LLM request latency by provider, model and outcome.', LATENCY_BUCKETS)
+  private readonly llmTokens = new Counter('llm_tokens_total', 'Tokens by provider, model and kind (input, output, cache_read, cache_write).')
+  private readonly toolDuration = new Histogram('tool_duration_seconds', 'Tool execution time by tool and outcome.', LATENCY_BUCKETS)
+  private readonly gauges: Array<{ name: string; help: string; collect: () => CounterSeries[] }> = []
+  private iterator: AsyncIterator<AgentEvent> | null = null
+
+  /** Register a gauge sampled at collection time, e.g. a queue length. */
+  gauge(name: string, help: string, collect: () => number | CounterSeries[]): void {
+    this.gauges.push({
+      name,
+      help,
+      collect: () => {
+        const value = collect()
+        return typeof value === 'number' ? [{ labels: {}, value }] : value
+      },
+    })
+  }
+
+  /** Follow tool completions until stop(). */
+  start(events: EventSource): void {
+    if (this.iterator) return
+    const iterator = events.subscribe({ types: [EVENT_TYPES.TOOL_COMPLETED] }, UNPACED)[Symbol.asyncIterator]()
+    this.iterator = iterator
+    void (async () => {
+      for (let next = await iterator.next(); !next.done; next = await iterator.next()) {
+        this.recordEvent(next.value)
+      }
+    })()
+  }
+
+  stop(): void {
+    void this.iterator?.return?.()
+    this.iterator = null
+  }
+
+  /** TraceSink hook: one observation per provider request. */
+  async record(trace: GenerationTraceRecord): Promise<void> {
+    const labels = { provider: trace.provider, model: trace.model }
+    this.llmDuration.observe({ ...labels, outcome: trace.error ? classifyError(trace.error) : 'ok' }, trace.durationMs / 1000)
+    const usage = trace.response?.usage
+    if (!usage) return
+    this.llmTokens.inc({ ...labels, kind: 'input' }, usage.input_tokens)
+    this.llmTokens.inc({ ...labels, kind: 'output' }, usage.output_tokens)
+    this.llmTokens.inc({ ...labels, kind: 'cache_read' }, usage.cache_read_input_tokens ?? 0)
+    this.llmTokens.inc({ ...labels, kind: 'cache_write' }, usage.cache_creation_input_tokens ?? 0)
+  }
+
+  recordEvent(event: AgentEvent): void {
+    if (event.type !== EVENT_TYPES.TOOL_COMPLETED) return
+    const outcome = event.payload.success ? 'ok' : event.payload.errorCode ?? 'tool_failed'
+    this.toolDuration.observe({ tool: event.payload.name, outcome }, event.payload.durationMs / 1000)
+  }
+
+  snapshot(): MetricsSnapshot {
+    const families: MetricFamily[] = [
+      { name: this.llmDuration.name, help: this.llmDuration.help, type: 'histogram', series: this.llmDuration.collect() },
+      { name: this.llmTokens.name, help: this.llmTokens.help, type: 'counter', series: this.llmTokens.collect() },
+      {
+        name: 'prompt_cache_hit_ratio',
+        help: 'Share of prompt tokens served from the provider cache, by provider and model.',
+        type: 'gauge',
+        series: this.cacheHitRatios(),
+      },
+      { name: this.toolDuration.name, help: this.toolDuration.help, type: 'histogram', series: this.toolDuration.collect() },
+    ]
+    for (const gauge of this.gauges) {
+      let series: CounterSeries[]
+      try {
+        series = gauge.collect()
+      } catch (err) {
+        logger.warn({ err, gauge: gauge.name }, 'Failed to sample gauge')
+        continue
+      }
+      families.push({ name: gauge.name, help: gauge.help, type: 'gauge', series })
+    }
+    const now = Date.now()
+    return { collectedAt: now, uptimeSeconds: Math.round((now - this.startedAt) / 1000), metrics: families }
+  }
+
+  /** Prometheus text exposition format (version 0.0.4). */
+  toPrometheus(): string {
+    const lines: string[] = []
+    for (const family of this.snapshot().metrics) {
+      const name = METRIC_PREFIX + family.name
+      lines.push(`# HELP ${name} ${family.help}`, `# TYPE ${name} ${family.type}`)
+      if (family.type === 'histogram') {
+        for (const s of family.series) {
+          for (const bucket of s.buckets) lines.push(`${name}_bucket${formatLabels({ ...s.labels, le: String(bucket.le) })} ${bucket.count}`)
+          lines.push(`${name}_bucket${formatLabels({ ...s.labels, le: '+Inf' })} ${s.count}`)
+          lines.push(`${name}_sum${formatLabels(s.labels)} ${s.sum}`)
+          lines.push(`${name}_count${formatLabels(s.labels)} ${s.count}`)
+        }
+      } else {
+        for (const s of family.series) lines.push(`${name}${formatLabels(s.labels)} ${s.value}`)
+      }
+    }
+    return lines.join('\n') + '\n'
+  }
+
+  // Anthropic reports input_tokens net of cached reads and writes, so the
+  // denominator is the sum of all three
+  private cacheHitRatios(): CounterSeries[] {
+    const totals = new Map<string, { labels: Labels; read: number; all: number }>()
+    for (const { labels, value } of this.llmTokens.collect()) {
+      if (labels.kind === 'output') continue
+      const key = `${labels.provider}\u0000${labels.model}`
+      const entry = totals.get(key) ?? { labels: { provider: labels.provider, model: labels.model }, read: 0, all: 0 }
+      entry.all += value
+      if (labels.kind === 'cache_read') entry.read += value
+      totals.set(key, entry)
+    }
+    return [...totals.values()]
+      .filter((t) => t.all > 0)
+      .map((t) => ({ labels: t.labels, value: t.read / t.all }))
+  }
+}
+
+function formatLabels(labels: Labels): string {
+  const pairs = Object.entries(labels).map(([key, value]) =>
+    `${key}="${value.replace(/\\/g, '\\\\').replace(/"/g, '\\"').replace(/\n/g, '\\n')}"`)
+  return pairs.length > 0 ? `{${pairs.join(',')}}` : ''
+}
+
+export interface PrometheusEndpointOptions {
+  port: number
+  host: string
+}
+
+/** Serves GET /metrics for a local Prometheus scraper. Unauthenticated, so loopback only. */
+export class PrometheusEndpoint {
+  private server: http.Server | null = null
+
+  constructor(
+    private readonly metrics: Metrics,
+    private readonly options: PrometheusEndpointOptions,
+  ) {}
+
+  async start(): Promise<void> {
+    if (this.server) return
+    const server = http.createServer((req, res) => {
+      if (req.method !== 'GET' || req.url?.split('?')[0] !== '/metrics') {
+        res.writeHead(404).end()
+        return
+      }
+      res.writeHead(200, { 'Content-Type': 'text/plain; version=0.0.4; charset=utf-8' }).end(this.metrics.toPrometheus())
+    })
+    await new Promise<void>((resolve, reject) => {
+      server.once('error', reject)
+      server.listen(this.options.port, this.options.host, () => {
+        server.off('error', reject)
+        resolve()
+      })
+    })
+    this.server = server
+    logger.info({ port: this.options.port, host: this.options.host }, 'Prometheus metrics endpoint listening')
+  }
+
+  stop(): void {
+    this.server?.close()
+    this.server = null
+  }
+}
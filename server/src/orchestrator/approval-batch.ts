@@ -0,0 +1,75 @@
+import { randomUUID } from 'node:crypto'
+import type { ApprovalBatch } from '../domain/types.js'
+import type { ToolPreview } from '../tools/types.js'
+
+/** Thresholds past which a batch of approvals is escalated; 0 turns a check off. */
+export interface ApprovalEscalation {
+  /** Escalate when more than this many calls in one step need approval. */
+  maxCalls: number
+  /** Escalate when the batch's previews add up to more than this many mutations. */
+  maxMutations: number
+}
+
+export interface ProposedCall {
+  callId: string
+  name: string
+  preview?: ToolPreview
+}
+
+/**
+ * Decide whether the calls one step left waiting for approval are approved
+ * one by one or only as a whole. Null keeps the per-call prompts; a single
+ * call is never escalated.
+ */
+export function escalateApprovalBatch(
+  calls: ProposedCall[],
+  limits: ApprovalEscalation | undefined,
+): ApprovalBatch | null {
+  if (!limits || calls.length < 2) return null
+  const mutations = calls.reduce((total, call) => total + countMutations(call.preview), 0)
+  const reason = limits.maxCalls > 0 && calls.length > limits.maxCalls
+    ? 'calls'
+    : limits.maxMutations > 0 && mutations > limits.maxMutations
+      ? 'mutations'
+      : null
+  if (!reason) return null
+  return {
+    id: randomUUID(),
+    callIds: calls.map((call) => call.callId),
+    mutations,
+    reason,
+    summary: summarizeBatch(calls, mutations),
+  }
+}
+
+/**
+ * What one call would change, per its preview: changed lines for file diffs,
+ * one per message, one per field. Calls without a structured change count once.
+ */
+export function countMutations(preview: ToolPreview | undefined): number {
+  const change = preview?.change
+  if (!change) return 1
+  switch (change.kind) {
+    case 'file': {
+      const changed = change.diff
+        .split('\n')
+        .filter((line) => (line.startsWith('+') && !line.startsWith('+++')) || (line.startsWith('-') && !line.startsWith('---')))
+      return Math.max(1, changed.length)
+    }
+    case 'message':
+      return 1
+    case 'fields':
+      return Math.max(1, change.changes.length)
+  }
+}
+
+function summarizeBatch(calls: ProposedCall[], mutations: number): string {
+  const byTool = new Map<string, number>()
+  for (const call of calls) byTool.set(call.name, (byTool.get(call.name) ?? 0) + 1)
+  const tools = [...byTool].map(([name, count]) => (count > 1 ? `${name} ×${count}` : name)).join(', ')
+  const files = new Set(
+    calls.flatMap((call) => (call.preview?.change?.kind === 'file' ? [call.preview.change.path] : [])),
+  )
+  const fileNote = files.size > 0 ? ` across ${files.size} file${files.size === 1 ? '' : 's'}` : ''
+  return `${calls.length} calls (${tools}), ${mutations} change${mutations === 1 ? '' : 's'}${fileNote}`
+}
@@ -0,0 +1,65 @@
+import { logger } from '../lib/logger.js'
+import { TOOL_CATEGORIES, type ToolCategory, type ToolMetadata } from '../tools/types.js'
+import { matchApprovalTrigger } from './approval-rules.js'
+
+export const APPROVAL_CATEGORY_DEFAULTS_PREFERENCE_KEY = 'approval_category_defaults'
+
+export type CategoryApprovalAction = 'ask' | 'allow'
+
+/** Per-category default; a category left out falls back to the built-in one. */
+export type CategoryApprovalDefaults = Partial<Record<ToolCategory, CategoryApprovalAction>>
+
+/**
+ * Read-only tools run without asking and destructive ones always ask.
+ * Mutating tools keep their own `requires_approval`, as do tools that
+ * declare no category (e.g. MCP tools).
+ */
+export const BUILT_IN_CATEGORY_DEFAULTS: CategoryApprovalDefaults = {
+  read_only: 'allow',
+  destructive: 'ask',
+}
+
+/**
+ * Whether a call needs approval when no rule or per-tool override decides:
+ * the global per-category policy, then the tool's arg triggers, then the
+ * built-in category default, then the tool's own flag.
+ */
+export function defaultApprovalAction(
+  meta: Pick<ToolMetadata, 'requires_approval' | 'category' | 'approval_triggers'> | undefined,
+  policy: CategoryApprovalDefaults,
+  args: Record<string, unknown> = {},
+): CategoryApprovalAction {
+  const category = meta?.category
+  const chosen = category ? policy[category] : undefined
+  if (chosen) return chosen
+  if (meta?.approval_triggers) return matchApprovalTrigger(meta.approval_triggers, args) ? 'ask' : 'allow'
+  const builtIn = category ? BUILT_IN_CATEGORY_DEFAULTS[category] : undefined
+  return builtIn ?? (meta?.requires_approval ? 'ask' : 'allow')
+}
+
+export function parseCategoryDefaults(raw: string | null): CategoryApprovalDefaults {
+  if (!raw) return {}
+  try {
+    return normalizeCategoryDefaults(JSON.parse(raw))
+  } catch {
+    logger.warn('Ignoring malformed approval_category_defaults preference')
+    return {}
+  }
+}
+
+/** Validate user input; throws with a message suitable for a 400. `null` clears a category. */
+export function normalizeCategoryDefaults(input: unknown): CategoryApprovalDefaults {
+  if (!input || typeof input !== 'object' || Array.isArray(input)) {
+    throw new Error('defaults must be an object keyed by category')
+  }
+  const defaults: CategoryApprovalDefaults = {}
+  for (const [key, value] of Object.entries(input)) {
+    if (!TOOL_CATEGORIES.includes(key as ToolCategory)) {
+      throw new Error(`category must be one of: ${TOOL_CATEGORIES.join(', ')}`)
+    }
+    if (value === null) continue
+    if (value !== 'ask' && value !== 'allow') throw new Error(`${key} must be 'ask', 'allow' or null`)
+    defaults[key as ToolCategory] = value
+  }
+  return defaults
+}
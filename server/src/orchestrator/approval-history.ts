@@ -0,0 +1,68 @@
+import type { Agent } from '../domain/types.js'
+import type {
+  ApprovalHistoryRepository,
+  ApprovalOutcome,
+  ApprovalRecord,
+  SessionRepository,
+} from '../repositories/types.js'
+import { logger } from '../lib/logger.js'
+
+export interface ApprovalDecision {
+  agent: Agent
+  callId: string
+  toolName: string
+  args: Record<string, unknown>
+  /** Args the approver substituted before approving. */
+  amendedArgs?: Record<string, unknown> | null
+  outcome: ApprovalOutcome
+  scope?: ApprovalRecord['scope']
+  ruleId?: string | null
+  /** When the call was proposed; omitted for decisions made on the spot. */
+  requestedAt?: number
+}
+
+/** Records how every approval-gated tool call was decided. */
+export interface ApprovalSink {
+  record(decision: ApprovalDecision): Promise<void>
+}
+
+export class ApprovalHistory implements ApprovalSink {
+  private readonly sessionOwners = new Map<string, string | null>()
+
+  constructor(
+    private readonly history: ApprovalHistoryRepository,
+    private readonly sessions: SessionRepository,
+  ) {}
+
+  async record(decision: ApprovalDecision): Promise<void> {
+    const { agent } = decision
+    const decidedAt = Date.now()
+    try {
+      await this.history.record({
+        userId: await this.resolveOwner(agent.sessionId),
+        sessionId: agent.sessionId,
+        agentId: agent.id,
+        callId: decision.callId,
+        toolName: decision.toolName,
+        args: decision.args,
+        amendedArgs: decision.amendedArgs ?? null,
+        outcome: decision.outcome,
+        scope: decision.scope ?? null,
+        ruleId: decision.ruleId ?? null,
+        requestedAt: decision.requestedAt ?? decidedAt,
+        decidedAt,
+      })
+    } catch (err) {
+      // History is for accountability after the fact; it must never block a decision
+      logger.warn({ err, agentId: agent.id, callId: decision.callId }, 'Failed to record approval decision')
+    }
+  }
+
+  private async resolveOwner(sessionId: string): Promise<string | null> {
+    if (this.sessionOwners.has(sessionId)) return this.sessionOwners.get(sessionId) ?? null
+    const session = await this.sessions.getById(sessionId)
+    const owner = session?.userId ?? null
+    this.sessionOwners.set(sessionId, owner)
+    return owner
+  }
+}
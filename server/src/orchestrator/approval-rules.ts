@@ -0,0 +1,163 @@
+import os from 'os'
+import path from 'path'
+import { logger } from '../lib/logger.js'
+
+export const APPROVAL_RULES_PREFERENCE_KEY = 'approval_rules'
+
+export const APPROVAL_RULE_ACTIONS = ['allow', 'deny', 'ask'] as const
+export type ApprovalRuleAction = (typeof APPROVAL_RULE_ACTIONS)[number]
+
+export const ARG_PREDICATE_OPS = ['equals', 'glob', 'regex', 'under', 'exists'] as const
+export type ArgPredicateOp = (typeof ARG_PREDICATE_OPS)[number]
+
+export interface ArgPredicate {
+  /** Dot path into the tool arguments, e.g. `path` or `options.cwd`. */
+  arg: string
+  op: ArgPredicateOp
+  /** Required for every op except `exists`. `under` takes a directory; `~` expands to the home directory. */
+  value?: string
+  /** Inverts the test, e.g. `under` + `not` for "outside this directory". */
+  not?: boolean
+}
+
+/** Declared by a tool: calls whose args match `when` need approval, for `reason`. */
+export interface ApprovalTrigger {
+  /** Every predicate must hold; an empty list always matches. */
+  when: ArgPredicate[]
+  /** Shown with the approval prompt, e.g. "The path is outside drafts/." */
+  reason: string
+}
+
+export interface ApprovalRule {
+  id: string
+  /** Tool name, `*` matching any run of characters (`files.*`, `*`). */
+  tool: string
+  /** Every predicate must hold for the rule to apply. */
+  when: ArgPredicate[]
+  action: ApprovalRuleAction
+  /** Shown to the model when the rule denies a call. */
+  description: string | null
+}
+
+/**
+ * Rules are checked in list order and the first match wins, so specific
+ * exceptions go above broad ones. No match falls back to the per-tool
+ * overrides and the tool's own default.
+ */
+export function matchApprovalRule(
+  rules: ApprovalRule[],
+  toolName: string,
+  args: Record<string, unknown>,
+): ApprovalRule | null {
+  return rules.find((rule) => matchesToolCall(rule.tool, rule.when, toolName, args)) ?? null
+}
+
+/** Whether a call is to a tool matching `pattern` with args meeting every predicate. */
+export function matchesToolCall(
+  pattern: string,
+  when: ArgPredicate[],
+  toolName: string,
+  args: Record<string, unknown>,
+): boolean {
+  return globToRegExp(pattern).test(toolName) && when.every((p) => holds(p, args))
+}
+
+/** The first of a tool's approval triggers that these args set off. */
+export function matchApprovalTrigger(
+  triggers: ApprovalTrigger[],
+  args: Record<string, unknown>,
+): ApprovalTrigger | null {
+  return triggers.find((trigger) => trigger.when.every((p) => holds(p, args))) ?? null
+}
+
+export function parseApprovalRules(raw: string | null): ApprovalRule[] {
+  if (!raw) return []
+  try {
+    const parsed = JSON.parse(raw) as unknown
+    if (!Array.isArray(parsed)) throw new Error('not an array')
+    return parsed.map((rule) => normalizeApprovalRule(rule as Partial<ApprovalRule>))
+  } catch {
+    logger.warn('Ignoring malformed approval_rules preference')
+    return []
+  }
+}
+
+/** Validate user input; throws with a message suitable for a 400. */
+export function normalizeApprovalRule(input: Partial<ApprovalRule>): ApprovalRule {
+  if (typeof input.id !== 'string' || !input.id) throw new Error('id is required')
+  if (typeof input.tool !== 'string' || !input.tool.trim()) throw new Error('tool must be a non-empty name or pattern')
+  if (!APPROVAL_RULE_ACTIONS.includes(input.action as ApprovalRuleAction)) {
+    throw new Error(`action must be one of: ${APPROVAL_RULE_ACTIONS.join(', ')}`)
+  }
+  const when = input.when ?? []
+  if (!Array.isArray(when)) throw new Error('when must be an array of predicates')
+  return {
+    id: input.id,
+    tool: input.tool.trim(),
+    when: when.map(normalizePredicate),
+    action: input.action as ApprovalRuleAction,
+    description: typeof input.description === 'string' && input.description.trim() ? input.description.trim() : null,
+  }
+}
+
+/** Validate one arg predicate; throws with a message suitable for a 400. */
+export function normalizePredicate(input: Partial<ArgPredicate>): ArgPredicate {
+  if (typeof input?.arg !== 'string' || !input.arg) throw new Error('predicate arg is required')
+  if (!ARG_PREDICATE_OPS.includes(input.op as ArgPredicateOp)) {
+    throw new Error(`predicate op must be one of: ${ARG_PREDICATE_OPS.join(', ')}`)
+  }
+  if (input.not !== undefined && typeof input.not !== 'boolean') throw new Error('predicate not must be a boolean')
+  const not = input.not ? { not: true } : {}
+  if (input.op === 'exists') return { arg: input.arg, op: 'exists', ...not }
+  if (typeof input.value !== 'string') throw new Error(`predicate '${input.op}' needs a string value`)
+  if (input.op === 'regex') {
+    try {
+      new RegExp(input.value)
+    } catch {
+      throw new Error(`invalid regex: ${input.value}`)
+    }
+  }
+  return { arg: input.arg, op: input.op as ArgPredicateOp, value: input.value, ...not }
+}
+
+function holds(predicate: ArgPredicate, args: Record<string, unknown>): boolean {
+  return test(predicate, args) !== Boolean(predicate.not)
+}
+
+function test(predicate: ArgPredicate, args: Record<string, unknown>): boolean {
+  const raw = predicate.arg.split('.').reduce<unknown>(
+    (value, key) => (value !== null && typeof value === 'object' ? (value as Record<string, unknown>)[key] : undefined),
+    args,
+  )
+  if (predicate.op === 'exists') return raw !== undefined && raw !== null
+  if (raw === undefined || raw === null) return false
+  const actual = typeof raw === 'string' ? raw : JSON.stringify(raw)
+  const expected = predicate.value ?? ''
+  switch (predicate.op) {
+    case 'equals':
+      return actual === expected
+    case 'glob':
+      return globToRegExp(expected).test(actual)
+    case 'regex':
+      return new RegExp(expected).test(actual)
+    case 'under':
+      return isUnder(actual, expected)
+  }
+}
+
+function globToRegExp(pattern: string): RegExp {
+  const escaped = pattern.split('*').map((part) => part.replace(/[.+?^${}()|[\]\\]/g, '\\$&')).join('.*')
+  return new RegExp(`^${escaped}$`)
+}
+
+// Normalizing first means `~/Projects/../.ssh` is not under `~/Projects`
+function isUnder(candidate: string, dir: string): boolean {
+  const expand = (p: string) => {
+    const normalized = path.normalize(p === '~' || p.startsWith('~/') ? path.join(os.homedir(), p.slice(1)) : p)
+    return normalized.length > 1 ? normalized.replace(/[/\\]+$/, '') : normalized
+  }
+  const target = expand(candidate)
+  const base = expand(dir)
+  if (path.isAbsolute(target) !== path.isAbsolute(base)) return false
+  return target === base || target.startsWith(base.endsWith(path.sep) ? base : base + path.sep)
+}
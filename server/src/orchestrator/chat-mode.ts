@@ -0,0 +1,27 @@
+import type { PreferenceRepository } from '../repositories/types.js'
+
+/**
+ * How a conversation's messages are answered: `agent` runs the controller
+ * loop with planning and tools, `chat` streams one direct provider reply
+ * over the history. Keyed `conversation_mode:<sessionId>`; unset is `agent`.
+ */
+export const CONVERSATION_MODE_PREFIX = 'conversation_mode:'
+
+export const CONVERSATION_MODES = ['agent', 'chat'] as const
+export type ConversationMode = (typeof CONVERSATION_MODES)[number]
+
+export function isConversationMode(value: unknown): value is ConversationMode {
+  return typeof value === 'string' && (CONVERSATION_MODES as readonly string[]).includes(value)
+}
+
+export async function getConversationMode(preferences: PreferenceRepository, sessionId: string): Promise<ConversationMode> {
+  const stored = await preferences.get(`${CONVERSATION_MODE_PREFIX}${sessionId}`)
+  return isConversationMode(stored) ? stored : 'agent'
+}
+
+/** null drops the conversation's setting, so it goes back to agent mode. */
+export async function setConversationMode(preferences: PreferenceRepository, sessionId: string, mode: ConversationMode | null): Promise<void> {
+  const key = `${CONVERSATION_MODE_PREFIX}${sessionId}`
+  if (mode === null) await preferences.delete(key)
+  else await preferences.set(key, mode)
+}
@@ -1,12 +1,33 @@
 import type { OrchestratorDeps, RunResult } from './types.js'
+import type { Agent, Item } from '../domain/types.js'
 import { deliverOne, completeAgent } from '../domain/agent.js'
 import { runAgent } from './runner.js'
 import { materializeTextOutput, materializeToolOutput } from './output.js'
+import { withToolProgress } from './progress.js'
+import { classifyToolError } from './errors.js'
+import { stepDeadline, toolCallLimits } from '../tools/registry.js'
 import { EVENT_TYPES } from '../events/types.js'
 import { AgentLock } from '../lib/agent-lock.js'
 
 const agentLock = new AgentLock()
 
+/**
+ * Returned by the locked sections when the agent is ready to run again. The
+ * state transition to 'running' happens under the lock; the (potentially
+ * minutes-long) resumed run happens after it is released, so unrelated
+ * deliveries are not queued behind LLM calls. A concurrent delivery to the
+ * same agent sees status 'running' and is rejected as before.
+ */
+interface ResumeAfterRelease {
+  resume: string
+}
+
+type LockedOutcome = RunResult | ResumeAfterRelease
+
+async function finishOutsideLock(outcome: LockedOutcome, deps: OrchestratorDeps): Promise<RunResult> {
+  return 'resume' in outcome ? runAndPropagateUp(outcome.resume, deps) : outcome
+}
+
 // ---------------------------------------------------------------------------
 // Deliver an external result to a waiting agent
 // ---------------------------------------------------------------------------
@@ -19,11 +40,13 @@ export async function deliverResult(
   deps: OrchestratorDeps,
 ): Promise<RunResult> {
   const release = await agentLock.acquire(agentId)
+  let outcome: LockedOutcome
   try {
-    return await deliverResultLocked(agentId, callId, output, isError, deps)
+    outcome = await deliverResultLocked(agentId, callId, output, isError, deps)
   } finally {
     release()
   }
+  return finishOutsideLock(outcome, deps)
 }
 
 async function deliverResultLocked(
@@ -32,7 +55,7 @@ async function deliverResultLocked(
   output: string,
   isError: boolean,
   deps: OrchestratorDeps,
-): Promise<RunResult> {
+): Promise<LockedOutcome> {
   const agent = await deps.agents.getById(agentId)
   if (!agent) throw new Error(`Agent not found: ${agentId}`)
   if (agent.status !== 'waiting') {
@@ -47,6 +70,8 @@ async function deliverResultLocked(
   const storedOutput = await materializeTextOutput(output, {
     sessionFilesRoot: deps.sessionFilesRoot,
     inlineLimitBytes: deps.inlineOutputLimitBytes,
+    sessionBudget: deps.inlineOutputBudget,
+    redactor: deps.redactor,
     sessionId: agent.sessionId,
     agentId,
     callId,
@@ -81,8 +106,9 @@ async function deliverResultLocked(
     }
   }
 
-  // Resume the agent and propagate completion up the parent chain iteratively
-  return runAndPropagateUp(agentId, deps)
+  // Resume the agent (after releasing the lock) and propagate completion up
+  // the parent chain iteratively
+  return { resume: agentId }
 }
 
 // ---------------------------------------------------------------------------
@@ -91,19 +117,82 @@ async function deliverResultLocked(
 
 export type ApprovalScope = 'once' | 'conversation' | 'always'
 
+/** Replacement args by callId, from an approver who edited the proposed call. */
+export type AmendedArgs = Record<string, Record<string, unknown>>
+
+/** The amended args were rejected; nothing was approved or run. */
+export class ApprovalAmendmentError extends Error {}
+
+/** An escalated batch was only partly approved; nothing was approved or run. */
+export class ApprovalBatchError extends Error {}
+
+/** Denial feedback past this length is cut; it goes into the model's context verbatim. */
+const MAX_DENIAL_FEEDBACK_CHARS = 4000
+
 export async function deliverApproval(
   agentId: string,
   callId: string,
   decision: 'approved' | 'denied',
   deps: OrchestratorDeps,
   scope?: ApprovalScope,
+  amendedArgs?: Record<string, unknown>,
+  feedback?: string,
+): Promise<RunResult> {
+  return deliverApprovals(
+    agentId,
+    [callId],
+    decision,
+    deps,
+    scope,
+    amendedArgs ? { [callId]: amendedArgs } : undefined,
+    feedback,
+  )
+}
+
+/**
+ * Apply one decision to several of an agent's pending approvals at once. The
+ * agent resumes once, after every approved call has run. Denying stops the
+ * agent, and any of its approvals not listed are cancelled with it — unless
+ * the denial carries feedback, which goes back to the model as the calls'
+ * result so it can change course and carry on. Listed calls can be approved
+ * with amended args, which must pass the tool's schema; the model is told what
+ * ran instead of what it proposed. Calls from an escalated batch can only be
+ * approved together with the rest of their batch.
+ */
+export async function deliverApprovals(
+  agentId: string,
+  callIds: string[],
+  decision: 'approved' | 'denied',
+  deps: OrchestratorDeps,
+  scope?: ApprovalScope,
+  amendedArgs?: AmendedArgs,
+  feedback?: string,
 ): Promise<RunResult> {
   const release = await agentLock.acquire(agentId)
+  const untrack = trackAbort(agentId, deps)
+  let outcome: LockedOutcome
   try {
-    return await deliverApprovalLocked(agentId, callId, decision, deps, scope)
+    outcome = await deliverApprovalLocked(agentId, callIds, decision, deps, scope, amendedArgs, feedback)
   } finally {
+    untrack()
     release()
   }
+  return finishOutsideLock(outcome, deps)
+}
+
+/**
+ * Approved calls run outside any run, so nothing else would let cancelling the
+ * agent reach them. Registers an abort handle for the agent unless a run
+ * already holds one; the returned function removes it again.
+ */
+function trackAbort(agentId: string, deps: OrchestratorDeps): () => void {
+  const controllers = deps.agentAbortControllers
+  if (!controllers || controllers.has(agentId)) return () => {}
+  const controller = new AbortController()
+  controllers.set(agentId, controller)
+  return () => {
+    if (controllers.get(agentId) === controller) controllers.delete(agentId)
+  }
 }
 
 const APPROVAL_PREFIX_SESSION = 'tool_approval_session:'
@@ -111,51 +200,47 @@ const APPROVAL_PREFIX_GLOBAL = 'tool_approval_global:'
 
 async function deliverApprovalLocked(
   agentId: string,
-  callId: string,
+  callIds: string[],
   decision: 'approved' | 'denied',
   deps: OrchestratorDeps,
   scope?: ApprovalScope,
-): Promise<RunResult> {
+  amendedArgs: AmendedArgs = {},
+  feedback?: string,
+): Promise<LockedOutcome> {
   const agent = await deps.agents.getById(agentId)
   if (!agent) throw new Error(`Agent not found: ${agentId}`)
   if (agent.status !== 'waiting') {
     throw new Error(`Agent ${agentId} is not waiting (status: ${agent.status})`)
   }
+  if (callIds.length === 0) throw new Error('No callIds given')
 
-  const waitEntry = agent.waitingFor.find((w) => w.callId === callId)
-  if (!waitEntry) {
-    throw new Error(`Agent ${agentId} is not waiting for callId '${callId}'`)
-  }
+  const waitEntries = [...new Set(callIds)].map((callId) => {
+    const waitEntry = agent.waitingFor.find((w) => w.callId === callId)
+    if (!waitEntry) {
+      throw new Error(`Agent ${agentId} is not waiting for callId '${callId}'`)
+    }
+    return waitEntry
+  })
+  validateAmendedArgs(amendedArgs, waitEntries, decision, deps)
+  if (decision === 'approved') requireWholeBatches(agent, callIds)
 
-  if (decision === 'denied') {
-    // Write denial as an error output
-    await deps.items.create({
-      agentId,
-      type: 'function_call_output',
-      callId,
-      output: 'Tool execution denied by user',
-      isError: true,
-      turnNumber: agent.turnCount,
-    })
+  const items = await deps.items.listByAgent(agentId)
+  const requestedAt = (callId: string) =>
+    items.find((i) => i.type === 'function_call' && i.callId === callId)?.createdAt
 
-    deps.events.emit({
-      type: EVENT_TYPES.TOOL_DENIED,
-      agent_id: agentId,
-      session_id: agent.sessionId,
-      payload: { callId, name: waitEntry.name },
-      timestamp: Date.now(),
-    })
+  if (decision === 'denied') {
+    const note = normalizeDenialFeedback(feedback)
 
-    // Write denial outputs for any remaining pending approvals in this agent
-    const remainingWaits = agent.waitingFor.filter(
-      (w) => w.callId !== callId && w.type === 'approval',
+    // Write denials as error outputs; approvals not listed are cancelled with the batch
+    const pendingApprovals = agent.waitingFor.filter(
+      (w) => callIds.includes(w.callId) || w.type === 'approval',
     )
-    for (const remaining of remainingWaits) {
+    for (const pending of pendingApprovals) {
       await deps.items.create({
         agentId,
         type: 'function_call_output',
-        callId: remaining.callId,
-        output: 'Tool execution denied by user (batch cancelled)',
+        callId: pending.callId,
+        output: denialOutput(callIds.includes(pending.callId), note),
         isError: true,
         turnNumber: agent.turnCount,
       })
@@ -163,12 +248,39 @@ async function deliverApprovalLocked(
         type: EVENT_TYPES.TOOL_DENIED,
         agent_id: agentId,
         session_id: agent.sessionId,
-        payload: { callId: remaining.callId, name: remaining.name },
+        payload: { callId: pending.callId, name: pending.name, ...(note ? { feedback: note } : {}) },
         timestamp: Date.now(),
       })
+      await deps.approvals?.record({
+        agent,
+        callId: pending.callId,
+        toolName: pending.name,
+        args: pending.args ?? {},
+        outcome: 'denied',
+        requestedAt: requestedAt(pending.callId),
+      })
     }
 
-    // Complete the agent — denial means "stop", not "try again"
+    // With feedback the denial is an instruction: hand it to the model and
+    // resume once nothing else is outstanding
+    if (note) {
+      const updated = pendingApprovals.reduce((current, pending) => deliverOne(current, pending.callId), agent)
+      await deps.agents.update(agentId, {
+        status: updated.status,
+        waitingFor: updated.waitingFor,
+      })
+      if (updated.status === 'running') {
+        return { resume: agentId }
+      }
+      return {
+        agentId,
+        status: updated.status,
+        waitingFor: updated.waitingFor,
+        turnCount: updated.turnCount,
+      }
+    }
+
+    // Complete the agent — a bare denial means "stop", not "try again"
     const stoppingMessage =
       "Okay, stopping since the tool request wasn't approved. Let me know how you'd like to continue."
 
@@ -212,31 +324,223 @@ async function deliverApprovalLocked(
   }
 
   // Approved: persist approval override if scope is broader than "once"
+  const toolNames = [...new Set(waitEntries.map((w) => w.name))]
   if (scope && scope !== 'once') {
-    const toolName = waitEntry.name
-    try {
-      if (scope === 'conversation') {
-        const key = `${APPROVAL_PREFIX_SESSION}${agent.sessionId}:${toolName}`
-        await deps.preferences.set(key, 'false')
-      } else if (scope === 'always') {
-        const key = `${APPROVAL_PREFIX_GLOBAL}${toolName}`
-        await deps.preferences.set(key, 'false')
+    for (const toolName of toolNames) {
+      try {
+        if (scope === 'conversation') {
+          const key = `${APPROVAL_PREFIX_SESSION}${agent.sessionId}:${toolName}`
+          await deps.preferences.set(key, 'false')
+        } else if (scope === 'always') {
+          const key = `${APPROVAL_PREFIX_GLOBAL}${toolName}`
+          await deps.preferences.set(key, 'false')
+        }
+      } catch {
+        // Non-fatal — approval still proceeds, just won't be remembered
       }
-    } catch {
-      // Non-fatal — approval still proceeds, just won't be remembered
     }
   }
 
+  // The user just said not to ask again for these tools, so their other calls
+  // waiting in the same batch are approved along with the listed ones
+  const approved = [...waitEntries]
+  if (scope === 'conversation' || scope === 'always') {
+    approved.push(...agent.waitingFor.filter(
+      (w) => w.type === 'approval' && !callIds.includes(w.callId) && toolNames.includes(w.name),
+    ))
+  }
+
+  // The approved calls run one after another under one step budget
+  const deadline = stepDeadline(agent.config)
+  let updated = agent
+  for (const wait of approved) {
+    deps.events.emit({
+      type: EVENT_TYPES.TOOL_APPROVED,
+      agent_id: agentId,
+      session_id: agent.sessionId,
+      payload: { callId: wait.callId, name: wait.name },
+      timestamp: Date.now(),
+    })
+    await deps.approvals?.record({
+      agent,
+      callId: wait.callId,
+      toolName: wait.name,
+      args: wait.args ?? {},
+      amendedArgs: amendedArgs[wait.callId] ?? null,
+      outcome: 'approved',
+      scope: scope ?? 'once',
+      requestedAt: requestedAt(wait.callId),
+    })
+    await executeApprovedCall(agent, wait.callId, items, deps, amendedArgs[wait.callId], deadline)
+    updated = deliverOne(updated, wait.callId)
+  }
+
+  // Remove from waitingFor
+  await deps.agents.update(agentId, {
+    status: updated.status,
+    waitingFor: updated.waitingFor,
+  })
+
+  if (updated.status === 'running') {
+    return { resume: agentId }
+  }
+
+  return {
+    agentId,
+    status: updated.status,
+    waitingFor: updated.waitingFor,
+    turnCount: updated.turnCount,
+  }
+}
+
+function normalizeDenialFeedback(feedback: string | undefined): string | null {
+  const trimmed = feedback?.trim()
+  if (!trimmed) return null
+  return trimmed.length > MAX_DENIAL_FEEDBACK_CHARS ? `${trimmed.slice(0, MAX_DENIAL_FEEDBACK_CHARS)}…` : trimmed
+}
+
+function denialOutput(listed: boolean, feedback: string | null): string {
+  if (!feedback) {
+    return listed ? 'Tool execution denied by user' : 'Tool execution denied by user (batch cancelled)'
+  }
+  const reason = listed ? 'Tool execution denied by user' : 'Tool execution cancelled along with a denied call'
+  return `${reason}. The user's feedback:\n${feedback}\n\n` +
+    'Adjust your plan to this feedback and continue. Do not repeat the denied call unchanged.'
+}
+
+/** Escalated batches run entirely or not at all, so each one must be approved in full. */
+function requireWholeBatches(agent: Agent, callIds: string[]): void {
+  for (const wait of agent.waitingFor) {
+    if (!wait.batch || !callIds.includes(wait.callId)) continue
+    const missing = agent.waitingFor.filter(
+      (w) => w.batch?.id === wait.batch!.id && !callIds.includes(w.callId),
+    )
+    if (missing.length > 0) {
+      throw new ApprovalBatchError(
+        `${wait.callId} belongs to a batch of ${wait.batch.callIds.length} calls that must be approved together; ` +
+          `also list ${missing.map((w) => w.callId).join(', ')}`,
+      )
+    }
+  }
+}
+
+/** Reject amendments for calls not being approved, or that the tool's schema refuses. */
+function validateAmendedArgs(
+  amendedArgs: AmendedArgs,
+  waitEntries: Array<{ callId: string; name: string }>,
+  decision: 'approved' | 'denied',
+  deps: OrchestratorDeps,
+): void {
+  for (const [callId, args] of Object.entries(amendedArgs)) {
+    if (decision !== 'approved') {
+      throw new ApprovalAmendmentError('Args can only be amended when approving')
+    }
+    const wait = waitEntries.find((w) => w.callId === callId)
+    if (!wait) {
+      throw new ApprovalAmendmentError(`Amended args given for '${callId}', which is not being approved`)
+    }
+    if (typeof args !== 'object' || args === null || Array.isArray(args)) {
+      throw new ApprovalAmendmentError(`Amended args for '${callId}' must be an object`)
+    }
+    const validation = deps.tools.validateArgs(wait.name, args)
+    if (!validation.valid) {
+      throw new ApprovalAmendmentError(`Amended args for ${wait.name} are invalid: ${(validation.errors ?? []).join('; ')}`)
+    }
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Expire an approval nobody answered in time
+// ---------------------------------------------------------------------------
+
+/**
+ * Settle an unanswered approval with an `approval_expired` error output.
+ * Unlike a denial the agent carries on, so the controller can ask the user
+ * what to do. Null when the call was answered in the meantime.
+ */
+export async function expireApproval(
+  agentId: string,
+  callId: string,
+  timeoutMs: number,
+  deps: OrchestratorDeps,
+): Promise<RunResult | null> {
+  const release = await agentLock.acquire(agentId)
+  let outcome: LockedOutcome | null
+  try {
+    outcome = await expireApprovalLocked(agentId, callId, timeoutMs, deps)
+  } finally {
+    release()
+  }
+  return outcome ? finishOutsideLock(outcome, deps) : null
+}
+
+async function expireApprovalLocked(
+  agentId: string,
+  callId: string,
+  timeoutMs: number,
+  deps: OrchestratorDeps,
+): Promise<LockedOutcome | null> {
+  const agent = await deps.agents.getById(agentId)
+  if (!agent || agent.status !== 'waiting') return null
+  const wait = agent.waitingFor.find((w) => w.callId === callId && w.type === 'approval')
+  if (!wait) return null
+
+  await deps.items.create({
+    agentId,
+    type: 'function_call_output',
+    callId,
+    output: `approval_expired: Nobody approved ${wait.name} within ${Math.round(timeoutMs / 1000)}s, so it did not run. ` +
+      'Ask the user whether to go ahead before calling it again.',
+    isError: true,
+    turnNumber: agent.turnCount,
+  })
+
   deps.events.emit({
-    type: EVENT_TYPES.TOOL_APPROVED,
+    type: EVENT_TYPES.TOOL_APPROVAL_EXPIRED,
     agent_id: agentId,
     session_id: agent.sessionId,
-    payload: { callId, name: waitEntry.name },
+    payload: { callId, name: wait.name, timeoutMs, parentId: agent.parentId, depth: agent.depth },
     timestamp: Date.now(),
   })
 
+  const call = (await deps.items.listByAgent(agentId)).find((i) => i.type === 'function_call' && i.callId === callId)
+  await deps.approvals?.record({
+    agent,
+    callId,
+    toolName: wait.name,
+    args: wait.args ?? {},
+    outcome: 'expired',
+    requestedAt: call?.createdAt,
+  })
+
+  const updated = deliverOne(agent, callId)
+  await deps.agents.update(agentId, {
+    status: updated.status,
+    waitingFor: updated.waitingFor,
+  })
+
+  if (updated.status === 'running') {
+    return { resume: agentId }
+  }
+
+  return {
+    agentId,
+    status: updated.status,
+    waitingFor: updated.waitingFor,
+    turnCount: updated.turnCount,
+  }
+}
+
+/** Run one approved call from its saved function_call item and record the output. */
+async function executeApprovedCall(
+  agent: Agent,
+  callId: string,
+  items: Item[],
+  deps: OrchestratorDeps,
+  amendedArgs?: Record<string, unknown>,
+  deadline?: number,
+): Promise<void> {
   // Find the function_call item to get tool name and args
-  const items = await deps.items.listByAgent(agentId)
   const callItem = items.find(
     (i) => i.type === 'function_call' && i.callId === callId,
   )
@@ -245,14 +549,14 @@ async function deliverApprovalLocked(
   }
 
   const toolName = callItem.name!
-  const toolArgs: Record<string, unknown> = callItem.arguments
+  const toolArgs: Record<string, unknown> = amendedArgs ?? (callItem.arguments
     ? JSON.parse(callItem.arguments)
-    : {}
+    : {})
 
   // Emit TOOL_STARTED so the UI shows activity
   deps.events.emit({
     type: EVENT_TYPES.TOOL_STARTED,
-    agent_id: agentId,
+    agent_id: agent.id,
     session_id: agent.sessionId,
     payload: { callId, name: toolName, args: toolArgs, parentId: agent.parentId, depth: agent.depth },
     timestamp: Date.now(),
@@ -260,28 +564,41 @@ async function deliverApprovalLocked(
 
   // Execute the tool
   const startMs = Date.now()
-  const result = await deps.tools.execute(toolName, toolArgs, {
-    agent_id: agentId,
-    session_id: agent.sessionId,
-    signal: AbortSignal.timeout(agent.config.tool_execution_timeout_ms),
-    events: deps.events,
-  })
+  const limits = toolCallLimits(
+    agent.config.tool_execution_timeout_ms,
+    deps.agentAbortControllers?.get(agent.id)?.signal,
+    deadline,
+  )
+  const result = await withToolProgress(deps.events, agent, callId, toolName, (reportProgress) =>
+    deps.tools.execute(toolName, toolArgs, {
+      agent_id: agent.id,
+      session_id: agent.sessionId,
+      ...limits,
+      events: deps.events,
+      reportProgress,
+    }),
+  )
   const durationMs = Date.now() - startMs
 
   const outputStr = await materializeToolOutput(result, {
     sessionFilesRoot: deps.sessionFilesRoot,
     inlineLimitBytes: deps.inlineOutputLimitBytes,
+    sessionBudget: deps.inlineOutputBudget,
+    redactor: deps.redactor,
     sessionId: agent.sessionId,
-    agentId,
+    agentId: agent.id,
     callId,
     toolName,
   })
 
   await deps.items.create({
-    agentId,
+    agentId: agent.id,
     type: 'function_call_output',
     callId,
-    output: outputStr,
+    // The model should know the call it proposed is not the one that ran
+    output: amendedArgs
+      ? `Note: the user edited the arguments before approving. The call ran with ${JSON.stringify(amendedArgs)}\n\n${outputStr}`
+      : outputStr,
     isError: !result.ok,
     turnNumber: agent.turnCount,
     durationMs,
@@ -289,29 +606,11 @@ async function deliverApprovalLocked(
 
   deps.events.emit({
     type: EVENT_TYPES.TOOL_COMPLETED,
-    agent_id: agentId,
+    agent_id: agent.id,
     session_id: agent.sessionId,
-    payload: { callId, name: toolName, success: result.ok, output: outputStr, durationMs, parentId: agent.parentId, depth: agent.depth },
+    payload: { callId, name: toolName, success: result.ok, output: outputStr, durationMs, errorCode: classifyToolError(result, limits.signal), parentId: agent.parentId, depth: agent.depth },
     timestamp: Date.now(),
   })
-
-  // Remove from waitingFor
-  const updated = deliverOne(agent, callId)
-  await deps.agents.update(agentId, {
-    status: updated.status,
-    waitingFor: updated.waitingFor,
-  })
-
-  if (updated.status === 'running') {
-    return runAndPropagateUp(agentId, deps)
-  }
-
-  return {
-    agentId,
-    status: updated.status,
-    waitingFor: updated.waitingFor,
-    turnCount: updated.turnCount,
-  }
 }
 
 // ---------------------------------------------------------------------------
@@ -322,7 +621,7 @@ async function propagateDenialUp(
   child: { id: string; parentId: string | null; sourceCallId: string | null; sessionId: string; depth: number },
   denialResult: string,
   deps: OrchestratorDeps,
-): Promise<RunResult> {
+): Promise<LockedOutcome> {
   let currentChild = child
 
   while (currentChild.parentId && currentChild.sourceCallId) {
@@ -352,6 +651,7 @@ async function propagateDenialUp(
         success: false,
         output: denialResult,
         durationMs: 0,
+        errorCode: 'approval_denied',
         parentId: parent.parentId ?? null,
         depth: parent.depth,
       },
@@ -366,8 +666,9 @@ async function propagateDenialUp(
     })
 
     if (updated.status === 'running') {
-      // Parent is ready to resume — use the normal propagation path
-      return runAndPropagateUp(parent.id, deps)
+      // Parent is ready to resume — use the normal propagation path once the
+      // caller has released the lock
+      return { resume: parent.id }
     }
 
     if (updated.status !== 'waiting') {
@@ -439,6 +740,8 @@ async function runAndPropagateUp(
     const delegateOutput = await materializeTextOutput(rawDelegateOutput, {
       sessionFilesRoot: deps.sessionFilesRoot,
       inlineLimitBytes: deps.inlineOutputLimitBytes,
+      sessionBudget: deps.inlineOutputBudget,
+      redactor: deps.redactor,
       sessionId: parent.sessionId,
       agentId: parent.id,
       callId: agent.sourceCallId,
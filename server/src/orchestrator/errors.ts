@@ -0,0 +1,91 @@
+import type { AgentErrorCode } from '../domain/types.js'
+import type { ToolResult } from '../tools/types.js'
+import { BudgetExceededError, SpendCapExceededError } from '../usage/budget.js'
+
+/** The run's outcome could not be saved; nothing of it was kept. */
+export class RunPersistenceError extends Error {
+  constructor(cause: unknown) {
+    super(`Could not save the run's result: ${cause instanceof Error ? cause.message : String(cause)}`, { cause })
+    this.name = 'RunPersistenceError'
+  }
+}
+
+/** Abort reason for runs stopped because the server is shutting down. */
+export class ServerShutdownError extends Error {
+  constructor() {
+    super('Server shutdown')
+    this.name = 'ServerShutdownError'
+  }
+}
+
+/** Abort reason for runs the watchdog found making no progress. */
+export class StalledRunError extends Error {
+  constructor(idleMs: number) {
+    super(`Run made no progress for ${Math.round(idleMs / 1000)}s and was stopped`)
+    this.name = 'StalledRunError'
+  }
+}
+
+/** The machine is offline and the run's provider needs the network. */
+export class OfflineError extends Error {
+  constructor() {
+    super('No network connection: the provider cannot be reached while this machine is offline')
+    this.name = 'OfflineError'
+  }
+}
+
+/**
+ * Map a thrown error to an AgentErrorCode. Provider SDK errors and
+ * ProviderError expose the HTTP status on the error; other errors may only
+ * carry it in the message ("OpenRouter 429: ..."), so both are checked.
+ */
+export function classifyError(err: unknown): AgentErrorCode {
+  if (err instanceof BudgetExceededError || err instanceof SpendCapExceededError) return 'budget_exceeded'
+  if (err instanceof RunPersistenceError) return 'persistence_failed'
+  if (err instanceof ServerShutdownError) return 'interrupted'
+  if (err instanceof StalledRunError) return 'stalled'
+  if (err instanceof OfflineError) return 'offline'
+
+  const message = err instanceof Error ? err.message : String(err)
+  const status = statusOf(err) ?? statusInMessage(message)
+
+  if (status === 401 || status === 403) return 'provider_auth'
+  if (status === 429) return 'rate_limited'
+  if (/context[ _](length|window)|too many tokens|prompt is too long|maximum context/i.test(message)) {
+    return 'context_overflow'
+  }
+  if (status !== null && status >= 500) return 'provider_unavailable'
+  if (/api[ _-]?key|unauthorized|authentication/i.test(message) || /^Provider ".*" not found/.test(message)) {
+    return 'provider_auth'
+  }
+  if (/rate limit/i.test(message)) return 'rate_limited'
+  if (/ECONNREFUSED|ECONNRESET|ENOTFOUND|ETIMEDOUT|fetch failed|overloaded/i.test(message)) {
+    return 'provider_unavailable'
+  }
+  return 'unknown'
+}
+
+/**
+ * Code for a failed tool result; undefined when the call succeeded. Pass the
+ * tool's signal when it carries a timeout, since tools report the abort
+ * without saying why.
+ */
+export function classifyToolError(
+  result: Pick<ToolResult, 'ok' | 'error'>,
+  signal?: AbortSignal,
+): AgentErrorCode | undefined {
+  if (result.ok) return undefined
+  const timedOut = (signal?.reason as { name?: string } | undefined)?.name === 'TimeoutError'
+  return timedOut || /timed out|timeout/i.test(result.error ?? '') ? 'tool_timeout' : 'tool_failed'
+}
+
+function statusOf(err: unknown): number | null {
+  if (typeof err !== 'object' || err === null) return null
+  const status = (err as { status?: unknown }).status
+  return typeof status === 'number' ? status : null
+}
+
+function statusInMessage(message: string): number | null {
+  const match = /^(?:[\w ]+ )?([45]\d\d)\b/.exec(message)
+  return match ? Number(match[1]) : null
+}
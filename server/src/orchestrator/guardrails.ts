@@ -0,0 +1,216 @@
+import type { Agent, Item } from '../domain/types.js'
+import type { GuardrailTrigger } from '../events/types.js'
+import { logger } from '../lib/logger.js'
+import type { PreferenceRepository, UsageRepository } from '../repositories/types.js'
+import { matchesToolCall, normalizePredicate, type ArgPredicate } from './approval-rules.js'
+import type { ControllerAction } from './types.js'
+
+export const GUARDRAIL_POLICY_PREFERENCE_KEY = 'guardrail_policy'
+
+export const GUARDRAIL_SEVERITIES = ['recoverable', 'fatal'] as const
+export type GuardrailSeverity = (typeof GUARDRAIL_SEVERITIES)[number]
+
+/** Where a content rule looks: the user's request, what the model says, or the args it calls tools with. */
+export const CONTENT_SCOPES = ['request', 'response', 'tool_args'] as const
+export type ContentScope = (typeof CONTENT_SCOPES)[number]
+
+export interface ForbiddenToolRule {
+  /** Tool name, `*` matching any run of characters (`shell.*`). */
+  tool: string
+  /** Every predicate must hold; an empty list forbids the tool outright. */
+  when: ArgPredicate[]
+  /** Shown to the user when the rule stops a run. */
+  reason: string
+  severity: GuardrailSeverity
+}
+
+export interface ContentRule {
+  /** Name of the category, e.g. `credentials` or `medical advice`. */
+  category: string
+  /** Case-insensitive regular expressions; any match puts the text in the category. */
+  patterns: string[]
+  scopes: ContentScope[]
+  severity: GuardrailSeverity
+}
+
+export interface GuardrailPolicy {
+  /** Tool calls in a row that may fail before the run stops; null disables it. */
+  maxConsecutiveFailures: { limit: number; severity: GuardrailSeverity } | null
+  /** USD the conversation may spend on one request, sub-agents included; null disables it. */
+  costCeiling: { maxUsd: number; severity: GuardrailSeverity } | null
+  forbiddenTools: ForbiddenToolRule[]
+  contentRules: ContentRule[]
+}
+
+export const DEFAULT_GUARDRAIL_POLICY: GuardrailPolicy = {
+  maxConsecutiveFailures: null,
+  costCeiling: null,
+  forbiddenTools: [],
+  contentRules: [],
+}
+
+export function parseGuardrailPolicy(raw: string | null): GuardrailPolicy {
+  if (!raw) return { ...DEFAULT_GUARDRAIL_POLICY }
+  try {
+    return normalizeGuardrailPolicy(JSON.parse(raw) as Partial<GuardrailPolicy>)
+  } catch {
+    logger.warn('Ignoring malformed guardrail_policy preference')
+    return { ...DEFAULT_GUARDRAIL_POLICY }
+  }
+}
+
+/** Validate user input; throws with a message suitable for a 400. */
+export function normalizeGuardrailPolicy(input: Partial<GuardrailPolicy>): GuardrailPolicy {
+  const forbiddenTools = input.forbiddenTools ?? []
+  if (!Array.isArray(forbiddenTools)) throw new Error('forbiddenTools must be an array')
+  const contentRules = input.contentRules ?? []
+  if (!Array.isArray(contentRules)) throw new Error('contentRules must be an array')
+
+  const failures = input.maxConsecutiveFailures ?? null
+  if (failures !== null && (!Number.isInteger(failures.limit) || failures.limit < 1)) {
+    throw new Error('maxConsecutiveFailures.limit must be a positive integer')
+  }
+  const ceiling = input.costCeiling ?? null
+  if (ceiling !== null && (typeof ceiling.maxUsd !== 'number' || !Number.isFinite(ceiling.maxUsd) || ceiling.maxUsd <= 0)) {
+    throw new Error('costCeiling.maxUsd must be a positive number')
+  }
+
+  return {
+    maxConsecutiveFailures: failures && { limit: failures.limit, severity: severity(failures.severity, 'maxConsecutiveFailures') },
+    costCeiling: ceiling && { maxUsd: ceiling.maxUsd, severity: severity(ceiling.severity, 'costCeiling') },
+    forbiddenTools: forbiddenTools.map((rule: Partial<ForbiddenToolRule>, i) => {
+      if (typeof rule?.tool !== 'string' || !rule.tool.trim()) throw new Error(`forbiddenTools[${i}].tool must be a non-empty name or pattern`)
+      const when = rule.when ?? []
+      if (!Array.isArray(when)) throw new Error(`forbiddenTools[${i}].when must be an array of predicates`)
+      return {
+        tool: rule.tool.trim(),
+        when: when.map(normalizePredicate),
+        reason: typeof rule.reason === 'string' && rule.reason.trim() ? rule.reason.trim() : `${rule.tool.trim()} is not allowed`,
+        severity: severity(rule.severity, `forbiddenTools[${i}]`),
+      }
+    }),
+    contentRules: contentRules.map((rule: Partial<ContentRule>, i) => {
+      if (typeof rule?.category !== 'string' || !rule.category.trim()) throw new Error(`contentRules[${i}].category is required`)
+      if (!Array.isArray(rule.patterns) || rule.patterns.length === 0) throw new Error(`contentRules[${i}].patterns must be a non-empty array`)
+      for (const pattern of rule.patterns) {
+        try {
+          new RegExp(pattern, 'i')
+        } catch {
+          throw new Error(`contentRules[${i}] has an invalid regex: ${String(pattern)}`)
+        }
+      }
+      const scopes = rule.scopes ?? [...CONTENT_SCOPES]
+      if (!Array.isArray(scopes) || scopes.length === 0 || scopes.some((scope) => !CONTENT_SCOPES.includes(scope))) {
+        throw new Error(`contentRules[${i}].scopes must list some of: ${CONTENT_SCOPES.join(', ')}`)
+      }
+      return {
+        category: rule.category.trim(),
+        patterns: rule.patterns,
+        scopes: [...new Set(scopes)],
+        severity: severity(rule.severity, `contentRules[${i}]`),
+      }
+    }),
+  }
+}
+
+function severity(value: unknown, name: string): GuardrailSeverity {
+  if (value === undefined) return 'recoverable'
+  if (!GUARDRAIL_SEVERITIES.includes(value as GuardrailSeverity)) {
+    throw new Error(`${name}.severity must be one of: ${GUARDRAIL_SEVERITIES.join(', ')}`)
+  }
+  return value as GuardrailSeverity
+}
+
+/**
+ * Where the current request starts: the latest user message, or the latest
+ * reply to a question the run paused on. Failure streaks and spend count
+ * from here, so answering a recoverable stop lets the run carry on.
+ */
+export function requestStart(items: Item[]): { index: number; item: Item | undefined } {
+  const questions = new Set(items.filter((item) => item.type === 'message' && item.role === 'assistant' && item.callId).map((item) => item.callId))
+  for (let i = items.length - 1; i >= 0; i--) {
+    const item = items[i]
+    if (item.type === 'message' && item.role === 'user') return { index: i, item }
+    if (item.type === 'function_call_output' && questions.has(item.callId)) return { index: i, item }
+  }
+  return { index: -1, item: undefined }
+}
+
+/** Checks that need no model output: the failure streak, the spend and the request itself. */
+export function evaluateTurn(policy: GuardrailPolicy, items: Item[], spentUsd: number): GuardrailTrigger | null {
+  const start = requestStart(items)
+
+  if (policy.maxConsecutiveFailures) {
+    const { limit, severity } = policy.maxConsecutiveFailures
+    let failures = 0
+    for (let i = items.length - 1; i > start.index; i--) {
+      if (items[i].type !== 'function_call_output') continue
+      if (!items[i].isError) break
+      failures++
+    }
+    if (failures >= limit) {
+      return { guardrail: 'consecutive_failures', severity, reason: `${failures} tool calls in a row failed (limit ${limit})` }
+    }
+  }
+
+  if (policy.costCeiling && spentUsd >= policy.costCeiling.maxUsd) {
+    const { maxUsd, severity } = policy.costCeiling
+    return { guardrail: 'cost_ceiling', severity, reason: `This request has cost $${spentUsd.toFixed(4)}, reaching the $${maxUsd} ceiling` }
+  }
+
+  // A reply to an earlier stop is the user's go-ahead; the request it answered is not checked again
+  if (start.item?.type === 'message' && start.item.content) {
+    return matchContent(policy, 'request', start.item.content)
+  }
+  return null
+}
+
+/** Checks on what the model chose to do, before any of it runs. */
+export function evaluateAction(policy: GuardrailPolicy, action: ControllerAction): GuardrailTrigger | null {
+  if (action.action === 'next_step') {
+    const calls = action.tools ?? (action.tool ? [{ tool: action.tool, args: action.args ?? {} }] : [])
+    for (const call of calls) {
+      const rule = policy.forbiddenTools.find((candidate) => matchesToolCall(candidate.tool, candidate.when, call.tool, call.args))
+      if (rule) return { guardrail: 'forbidden_tool', severity: rule.severity, reason: `Call to ${call.tool} blocked: ${rule.reason}` }
+      const hit = matchContent(policy, 'tool_args', JSON.stringify(call.args))
+      if (hit) return { ...hit, reason: `${hit.reason} in the arguments of ${call.tool}` }
+    }
+  }
+  const said = action.action === 'ask_user' ? action.question : action.message
+  return said ? matchContent(policy, 'response', said) : null
+}
+
+function matchContent(policy: GuardrailPolicy, scope: ContentScope, text: string): GuardrailTrigger | null {
+  const rule = policy.contentRules.find((candidate) =>
+    candidate.scopes.includes(scope) && candidate.patterns.some((pattern) => new RegExp(pattern, 'i').test(text)))
+  if (!rule) return null
+  const where = scope === 'request' ? 'The request' : scope === 'response' ? 'The reply' : 'Content'
+  return { guardrail: 'content', severity: rule.severity, reason: `${where} falls under the blocked category "${rule.category}"` }
+}
+
+/** Evaluates the stored policy for each turn of every run; see evaluateTurn and evaluateAction. */
+export class GuardrailEngine {
+  constructor(
+    private readonly preferences: Pick<PreferenceRepository, 'get'>,
+    private readonly usage: Pick<UsageRepository, 'list'>,
+  ) {}
+
+  async policy(): Promise<GuardrailPolicy> {
+    return parseGuardrailPolicy(await this.preferences.get(GUARDRAIL_POLICY_PREFERENCE_KEY))
+  }
+
+  async checkTurn(agent: Pick<Agent, 'sessionId' | 'createdAt'>, items: Item[]): Promise<GuardrailTrigger | null> {
+    const policy = await this.policy()
+    let spentUsd = 0
+    if (policy.costCeiling) {
+      const from = requestStart(items).item?.createdAt ?? agent.createdAt
+      const records = await this.usage.list({ sessionId: agent.sessionId, from })
+      spentUsd = records.reduce((sum, record) => sum + record.costUsd, 0)
+    }
+    return evaluateTurn(policy, items, spentUsd)
+  }
+
+  async checkAction(action: ControllerAction): Promise<GuardrailTrigger | null> {
+    return evaluateAction(await this.policy(), action)
+  }
+}
@@ -0,0 +1,97 @@
+import type { PreferenceRepository } from '../repositories/types.js'
+import { parseUserProfile, USER_PROFILE_PREFERENCE_KEY, type UserProfile } from './user-profile.js'
+
+/** Per-conversation override of the profile's language, keyed `response_language:<sessionId>`. */
+export const RESPONSE_LANGUAGE_PREFIX = 'response_language:'
+
+/** Same limit as the profile's short fields. */
+const MAX_LANGUAGE_CHARS = 200
+
+export interface ResponseLanguageSetting {
+  /** Free-form language name, e.g. `Polish`; null leaves it to the user's messages. */
+  language: string | null
+  source: 'conversation' | 'profile' | 'none'
+}
+
+/** The conversation's language, else the profile's. Pass the profile when it has already been read. */
+export async function resolveResponseLanguage(
+  preferences: PreferenceRepository,
+  sessionId: string,
+  profile?: UserProfile,
+): Promise<ResponseLanguageSetting> {
+  const conversation = await preferences.get(`${RESPONSE_LANGUAGE_PREFIX}${sessionId}`)
+  if (conversation) return { language: conversation, source: 'conversation' }
+  const { language } = profile ?? parseUserProfile(await preferences.get(USER_PROFILE_PREFERENCE_KEY))
+  return language ? { language, source: 'profile' } : { language: null, source: 'none' }
+}
+
+/** Validate user input; throws with a message suitable for a 400. Blank becomes null. */
+export function normalizeLanguage(value: unknown): string | null {
+  if (value === undefined || value === null) return null
+  if (typeof value !== 'string') throw new Error('language must be a string')
+  const trimmed = value.trim()
+  if (trimmed.length > MAX_LANGUAGE_CHARS) throw new Error(`language is too long (${MAX_LANGUAGE_CHARS} characters at most)`)
+  return trimmed || null
+}
+
+const SCRIPTS: Array<[RegExp, string]> = [
+  [/[가-힯]/g, 'Korean'],
+  [/[぀-ヿ]/g, 'Japanese'],
+  [/[一-鿿]/g, 'Chinese'],
+  [/[؀-ۿ]/g, 'Arabic'],
+  [/[֐-׿]/g, 'Hebrew'],
+  [/[Ͱ-Ͽ]/g, 'Greek'],
+  [/[฀-๿]/g, 'Thai'],
+  [/[ऀ-ॿ]/g, 'Hindi'],
+  [/[Ѐ-ӿ]/g, 'Russian'],
+]
+
+/** Common short words, chosen so each list has few members shared with the others. */
+const STOPWORDS: Record<string, string[]> = {
+  English: ['the', 'and', 'is', 'are', 'you', 'what', 'how', 'this', 'that', 'with', 'for', 'of', 'can', 'please', 'my', 'it', 'do', 'i'],
+  Polish: ['jest', 'nie', 'się', 'jak', 'czy', 'co', 'na', 'że', 'mi', 'mnie', 'proszę', 'jestem', 'możesz', 'dla', 'ale', 'tak', 'jaki', 'jaka'],
+  German: ['der', 'die', 'das', 'und', 'ist', 'nicht', 'ich', 'du', 'wie', 'was', 'ein', 'eine', 'mit', 'für', 'bitte', 'kannst', 'auf', 'zu'],
+  French: ['le', 'les', 'et', 'est', 'je', 'tu', 'vous', 'pas', 'qui', 'une', 'des', 'pour', 'avec', 'comment', 'dans', 'sur', 'ce', 'quel', 'à', 'au', 'mais'],
+  Spanish: ['el', 'los', 'las', 'y', 'qué', 'cómo', 'por', 'con', 'del', 'puedes', 'estoy', 'está', 'muy', 'pero', 'cuál', 'es'],
+  Italian: ['il', 'gli', 'è', 'non', 'che', 'come', 'per', 'sono', 'della', 'puoi', 'cosa', 'questo', 'anche', 'ciao', 'mi'],
+  Portuguese: ['os', 'não', 'você', 'uma', 'um', 'isso', 'obrigado', 'muito', 'como', 'para', 'com', 'está', 'é'],
+  Dutch: ['het', 'een', 'niet', 'ik', 'je', 'wat', 'hoe', 'van', 'voor', 'dat', 'kun', 'op', 'en', 'de'],
+}
+
+const DIACRITICS: Record<string, RegExp> = {
+  Polish: /[ąęłśźżćń]/,
+  German: /[äöüß]/,
+  Spanish: /[ñ¿¡]/,
+  Portuguese: /[ãõ]/,
+  French: /[œêëîïû]/,
+}
+
+/**
+ * Best guess at the language of a message, or null when it is too short or
+ * ambiguous to tell. Scripts decide outright; Latin text is scored on common
+ * words and language-specific letters.
+ */
+export function detectLanguage(text: string): string | null {
+  const letters = text.match(/\p{L}/gu)?.length ?? 0
+  for (const [pattern, language] of SCRIPTS) {
+    if (letters && (text.match(pattern)?.length ?? 0) / letters > 0.3) {
+      return language === 'Russian' && /[іїєґ]/i.test(text) ? 'Ukrainian' : language
+    }
+  }
+  const lower = text.toLowerCase()
+  const words = lower.match(/\p{L}+/gu) ?? []
+  if (words.length < 3) return null
+  const scores = Object.entries(STOPWORDS).map(([language, stopwords]) => {
+    const hits = words.filter((word) => stopwords.includes(word)).length
+    return { language, score: hits + (DIACRITICS[language]?.test(lower) ? 2 : 0) }
+  }).sort((a, b) => b.score - a.score)
+  const [best, second] = scores
+  return best.score >= 2 && best.score > second.score ? best.language : null
+}
+
+/** The Additional Instructions line for a set language, or for one detected from the latest message. */
+export function responseLanguageInstruction(language: string, detected: boolean): string {
+  return detected
+    ? `Reply in ${language}, the language of the user's latest message.`
+    : `Reply in ${language}, unless the user asks for a different language.`
+}
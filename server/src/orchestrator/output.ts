@@ -2,12 +2,57 @@ import type { ToolResult } from '../tools/types.js'
 import fs from 'fs/promises'
 import path from 'path'
 import { getSessionFilesDir } from '../tools/path-policy.js'
+import type { SecretRedactor } from '../lib/secrets.js'
 
 const MAX_OUTPUT_BYTES = 32 * 1024 // 32 KB
 
+/** First line of the reference that replaces an oversized tool output in history. */
+export const ARTIFACT_REFERENCE_HEADER = 'Output exceeded inline limit and was saved as:'
+
+const MEMOIZED_NOTE = '(Same result as an identical earlier call in this conversation; the tool was not run again.)'
+
+/** Why an output that fit the per-output limit was saved as an artifact anyway. */
+export const SESSION_INLINE_BUDGET_EXCEEDED = 'session_inline_budget_exceeded'
+
+/** Sessions whose totals are kept; the least recently charged is dropped past this. */
+const MAX_BUDGET_SESSIONS = 1000
+
+/**
+ * Counts the tool-output characters each session has put inline into its
+ * history. Each output is capped on its own, but enough of them in one
+ * conversation still crowd out the rest of the context; once a session's
+ * total passes the budget, every later output is saved as an artifact.
+ * Totals live in memory, so a restart gives each session a fresh budget.
+ */
+export class InlineOutputBudget {
+  private readonly used = new Map<string, number>()
+
+  /** A budget of 0 or less never forces an output out. */
+  constructor(private readonly limitChars: number) {}
+
+  /** Adds `chars` to the session's total; false once the total is over budget. */
+  charge(sessionId: string, chars: number): boolean {
+    if (this.limitChars <= 0) return true
+    const total = (this.used.get(sessionId) ?? 0) + chars
+    // Re-insert so Map order tracks recency
+    this.used.delete(sessionId)
+    this.used.set(sessionId, total)
+    if (this.used.size > MAX_BUDGET_SESSIONS) this.used.delete(this.used.keys().next().value!)
+    return total <= this.limitChars
+  }
+
+  forget(sessionId: string): void {
+    this.used.delete(sessionId)
+  }
+}
+
 export interface OutputMaterializationOptions {
   sessionFilesRoot: string
   inlineLimitBytes?: number
+  /** Session-wide inline total; output that would take it past budget is persisted. */
+  sessionBudget?: InlineOutputBudget
+  /** Applied before the output is inlined or persisted, so neither copy holds a secret. */
+  redactor?: SecretRedactor
   sessionId: string
   agentId: string
   callId: string
@@ -25,38 +70,41 @@ export async function materializeToolOutput(
   options: OutputMaterializationOptions,
 ): Promise<string> {
   if (!result.ok) {
-    return result.error ?? 'Unknown error'
+    const error = result.error ?? 'Unknown error'
+    return options.redactor ? options.redactor.redact(error) : error
   }
   if (result.output === undefined || result.output === null) {
     return '(no output)'
   }
 
   const serialized = serializeOutput(result.output)
-  return materializeTextOutput(serialized.body, {
+  const text = await materializeTextOutput(serialized.body, {
     ...options,
     extension: serialized.extension,
   })
+  // Tells a controller that lost track of a result that it already has it
+  return result.memoized ? `${text}\n\n${MEMOIZED_NOTE}` : text
 }
 
 export async function materializeTextOutput(
   text: string,
   options: OutputMaterializationOptions & { extension?: string },
 ): Promise<string> {
+  if (options.redactor) text = options.redactor.redact(text)
   const byteLength = Buffer.byteLength(text, 'utf-8')
-  const shouldPersist = options.persistEvenWhenInline || byteLength > (options.inlineLimitBytes ?? MAX_OUTPUT_BYTES)
+  const overLimit = byteLength > (options.inlineLimitBytes ?? MAX_OUTPUT_BYTES)
+  // Only output that would otherwise go inline counts against the session
+  const overBudget = !overLimit && options.sessionBudget !== undefined && !options.sessionBudget.charge(options.sessionId, text.length)
+  const shouldPersist = options.persistEvenWhenInline || overLimit || overBudget
 
   if (shouldPersist) {
     const artifactRef = await writeArtifact(text, options)
-    if (byteLength > (options.inlineLimitBytes ?? MAX_OUTPUT_BYTES)) {
-      return artifactReference(artifactRef, text, byteLength)
+    if (overLimit || overBudget) {
+      return artifactReference(artifactRef, text, byteLength, overBudget ? SESSION_INLINE_BUDGET_EXCEEDED : undefined)
     }
   }
 
-  if (byteLength <= (options.inlineLimitBytes ?? MAX_OUTPUT_BYTES)) {
-    return text
-  }
-
-  throw new Error('Large output was not persisted')
+  return text
 }
 
 function serializeOutput(output: unknown): { body: string; extension: string } {
@@ -81,18 +129,45 @@ async function writeArtifact(
   ].filter(Boolean).join('-') || `output-${Date.now()}`
   const artifactPath = path.join(dir, `${filename}.${options.extension ?? 'txt'}`)
 
-  await fs.writeFile(artifactPath, text.endsWith('\n') ? text : `${text}\n`, 'utf-8')
+  // Write then rename so a crash never leaves a truncated artifact that later reads trust
+  const tmp = `${artifactPath}.${process.pid}.tmp`
+  await fs.writeFile(tmp, text.endsWith('\n') ? text : `${text}\n`, 'utf-8')
+  await fs.rename(tmp, artifactPath)
   return `artifact://${agentPart}/${path.basename(artifactPath)}`
 }
 
-function artifactReference(artifactRef: string, text: string, byteLength: number): string {
+/**
+ * Checks a spilled JSON output before it is read back. One that does not parse
+ * was cut short by a crash mid-write; it is renamed to `<name>.corrupt` so
+ * later reads and existence checks treat it as missing instead of as data.
+ * Returns the quarantined path, or null when the file is sound.
+ */
+export async function quarantineTruncatedOutput(fsPath: string): Promise<string | null> {
+  let text: string
+  try {
+    text = await fs.readFile(fsPath, 'utf-8')
+  } catch {
+    return null
+  }
+  try {
+    JSON.parse(text)
+    return null
+  } catch {
+    const quarantined = `${fsPath}.corrupt`
+    await fs.rename(fsPath, quarantined)
+    return quarantined
+  }
+}
+
+function artifactReference(artifactRef: string, text: string, byteLength: number, reason?: string): string {
   const lineCount = text.length === 0 ? 0 : text.split(/\r\n|\r|\n/).length
   return [
-    'Output exceeded inline limit and was saved as:',
+    ARTIFACT_REFERENCE_HEADER,
     artifactRef,
     '',
     `bytes: ${byteLength}`,
     `lines: ${lineCount}`,
+    ...(reason ? [`reason: ${reason}`] : []),
   ].join('\n')
 }
 
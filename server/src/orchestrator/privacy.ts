@@ -0,0 +1,205 @@
+import { logger } from '../lib/logger.js'
+import type { LLMContentBlock, LLMMessage, LLMRequest, LLMResponse } from '../providers/types.js'
+import type { PreferenceRepository } from '../repositories/types.js'
+
+/** Default for conversations without their own setting; `true` turns privacy mode on everywhere. */
+export const PRIVACY_MODE_PREFERENCE_KEY = 'privacy_mode'
+/** Per-conversation override, `true` or `false`, keyed `privacy_mode:<sessionId>`. */
+export const PRIVACY_MODE_PREFIX = 'privacy_mode:'
+/** Placeholder → original value for one conversation, kept locally and never sent to a provider. */
+export const PII_MAPPING_PREFIX = 'pii_mapping:'
+
+export type PiiKind = 'EMAIL' | 'PHONE' | 'PERSON'
+
+const EMAIL_PATTERN = /\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b/g
+/** Digit groups split by spaces, dots or dashes, with an optional country code and area code in parentheses. */
+const PHONE_PATTERN = /(?<![\w.+-])(?:\+\d{1,3}[\s.-]?)?(?:\(\d{2,4}\)[\s.-]?)?\d{2,4}(?:[\s.-]\d{2,4}){1,4}(?![\w-])/g
+const DATE_OR_ADDRESS = /^\d{4}-\d{2}-\d{2}|^\d{1,3}(?:\.\d{1,3}){3}$/
+const NAME = String.raw`\p{Lu}[\p{L}'’-]+(?: \p{Lu}[\p{L}'’-]+){1,2}`
+/** Names that come with an address or in a field that holds one: `Jane Doe <jane@…>`, `"author": "Jane Doe"`, `From: Jane Doe`. */
+const NAME_PATTERNS = [
+  new RegExp(String.raw`"?(${NAME})"?\s*<[^<>\s]+@[^<>\s]+>`, 'gu'),
+  new RegExp(String.raw`"(?:name|full_?name|display_?name|author|sender|recipient|organizer|attendee|contact)"\s*:\s*"(${NAME})"`, 'giu'),
+  new RegExp(String.raw`^\s*(?:From|To|Cc|Bcc|Author|Name|Organizer|Attendee):[ \t]*(${NAME})\b`, 'gmu'),
+]
+const PLACEHOLDER_PATTERN = /\[(?:EMAIL|PHONE|PERSON)_\d+\]/g
+/** The start of a placeholder cut off at the end of a streamed chunk. */
+const PARTIAL_PLACEHOLDER = /\[[A-Z]{0,6}(?:_\d{0,6})?$/
+
+export async function isPrivacyMode(preferences: PreferenceRepository, sessionId: string): Promise<boolean> {
+  const session = await preferences.get(`${PRIVACY_MODE_PREFIX}${sessionId}`)
+  if (session !== null) return session === 'true'
+  return (await preferences.get(PRIVACY_MODE_PREFERENCE_KEY)) === 'true'
+}
+
+/** null drops the conversation's own setting, so it follows the default again. */
+export async function setPrivacyMode(preferences: PreferenceRepository, sessionId: string, enabled: boolean | null): Promise<void> {
+  const key = `${PRIVACY_MODE_PREFIX}${sessionId}`
+  if (enabled === null) await preferences.delete(key)
+  else await preferences.set(key, String(enabled))
+}
+
+/**
+ * One conversation's pseudonyms. A value keeps its placeholder for the whole
+ * conversation, so the model can refer back to `[EMAIL_1]` turns later and
+ * its answer can be mapped back to the real value.
+ */
+export class PiiMapping {
+  private readonly originals = new Map<string, string>()
+  private readonly placeholders = new Map<string, string>()
+  private readonly counts: Record<PiiKind, number> = { EMAIL: 0, PHONE: 0, PERSON: 0 }
+  changed = false
+
+  constructor(entries: Record<string, string> = {}) {
+    for (const [placeholder, original] of Object.entries(entries)) this.add(placeholder, original)
+  }
+
+  toJSON(): Record<string, string> {
+    return Object.fromEntries(this.originals)
+  }
+
+  /** Finds new emails, phone numbers and names in the text and gives each a placeholder. */
+  learn(text: string): void {
+    for (const match of text.matchAll(EMAIL_PATTERN)) this.placeholderFor('EMAIL', match[0])
+    for (const match of text.matchAll(PHONE_PATTERN)) {
+      const digits = match[0].replace(/\D/g, '').length
+      if (digits >= 9 && digits <= 15 && !DATE_OR_ADDRESS.test(match[0])) this.placeholderFor('PHONE', match[0].trim())
+    }
+    for (const pattern of NAME_PATTERNS) {
+      for (const match of text.matchAll(pattern)) this.placeholderFor('PERSON', match[1])
+    }
+  }
+
+  /** Replaces every known value; nothing new is detected here. */
+  pseudonymize(text: string): string {
+    let result = text
+    // Longest first, so `Jane Doe` is not left as `[PERSON_2] Doe` by a shorter value
+    for (const original of [...this.placeholders.keys()].sort((a, b) => b.length - a.length)) {
+      if (result.includes(original)) result = result.split(original).join(this.placeholders.get(original)!)
+    }
+    return result
+  }
+
+  reidentify(text: string): string {
+    return text.replace(PLACEHOLDER_PATTERN, (placeholder) => this.originals.get(placeholder) ?? placeholder)
+  }
+
+  private placeholderFor(kind: PiiKind, original: string): string {
+    const existing = this.placeholders.get(original)
+    if (existing) return existing
+    const placeholder = `[${kind}_${this.counts[kind] + 1}]`
+    this.add(placeholder, original)
+    this.changed = true
+    return placeholder
+  }
+
+  private add(placeholder: string, original: string): void {
+    const kind = placeholder.match(/^\[([A-Z]+)_(\d+)\]$/)
+    if (!kind || !(kind[1] in this.counts)) return
+    this.originals.set(placeholder, original)
+    this.placeholders.set(original, placeholder)
+    this.counts[kind[1] as PiiKind] = Math.max(this.counts[kind[1] as PiiKind], Number(kind[2]))
+  }
+}
+
+/**
+ * Privacy mode: emails, phone numbers and names found in tool outputs are
+ * replaced with placeholders before a request leaves for the provider, and
+ * the provider's answer is mapped back before it is shown or stored. Items
+ * keep the real values; only what the model sees is pseudonymized. Mappings
+ * are cached per conversation so parallel sub-agents share numbering.
+ */
+export class PrivacyVault {
+  private readonly mappings = new Map<string, PiiMapping>()
+
+  constructor(private readonly preferences: PreferenceRepository) {}
+
+  /** The conversation's mapping while privacy mode is on for it, else null. */
+  async forSession(sessionId: string): Promise<PiiMapping | null> {
+    if (!(await isPrivacyMode(this.preferences, sessionId))) return null
+    const cached = this.mappings.get(sessionId)
+    if (cached) return cached
+    const mapping = new PiiMapping(parseMapping(await this.preferences.get(`${PII_MAPPING_PREFIX}${sessionId}`)))
+    // Another run of the conversation may have loaded it meanwhile
+    const loaded = this.mappings.get(sessionId) ?? mapping
+    this.mappings.set(sessionId, loaded)
+    return loaded
+  }
+
+  /** Learns from the request's tool outputs, then swaps known values for placeholders in everything but the system prompt. */
+  async pseudonymizeRequest(sessionId: string, mapping: PiiMapping, request: LLMRequest): Promise<LLMRequest> {
+    for (const message of request.messages) {
+      if (message.role === 'tool') mapping.learn(messageText(message))
+    }
+    if (mapping.changed) {
+      mapping.changed = false
+      await this.preferences.set(`${PII_MAPPING_PREFIX}${sessionId}`, JSON.stringify(mapping))
+    }
+    return {
+      ...request,
+      messages: request.messages.map((message) => message.role === 'system' ? message : mapStrings(message, (text) => mapping.pseudonymize(text))),
+    }
+  }
+
+  /** Drops the conversation's mapping, which holds the very values privacy mode hides. */
+  async forget(sessionId: string): Promise<void> {
+    this.mappings.delete(sessionId)
+    await this.preferences.delete(`${PII_MAPPING_PREFIX}${sessionId}`)
+  }
+}
+
+export function reidentifyResponse(mapping: PiiMapping, response: LLMResponse): LLMResponse {
+  return mapStrings(response, (text) => mapping.reidentify(text))
+}
+
+/** Re-identifies streamed text, holding back a placeholder split across chunks until it is complete. */
+export class StreamReidentifier {
+  private pending = ''
+
+  constructor(private readonly mapping: PiiMapping) {}
+
+  push(chunk: string): string {
+    const text = this.pending + chunk
+    const partial = text.match(PARTIAL_PLACEHOLDER)
+    this.pending = partial ? partial[0] : ''
+    return this.mapping.reidentify(partial ? text.slice(0, partial.index) : text)
+  }
+
+  flush(): string {
+    const rest = this.mapping.reidentify(this.pending)
+    this.pending = ''
+    return rest
+  }
+}
+
+function messageText(message: LLMMessage): string {
+  if (typeof message.content === 'string') return message.content
+  return message.content.map((block: LLMContentBlock) => block.text ?? '').join('\n')
+}
+
+/** Copies a JSON-like value with every string passed through `fn`; base64 image data is left alone. */
+function mapStrings<T>(value: T, fn: (text: string) => string): T {
+  const walk = (node: unknown, key?: string): unknown => {
+    if (typeof node === 'string') return key === 'data' ? node : fn(node)
+    if (Array.isArray(node)) return node.map((item) => walk(item))
+    if (node && typeof node === 'object') {
+      return Object.fromEntries(Object.entries(node).map(([k, v]) => [k, walk(v, k)]))
+    }
+    return node
+  }
+  return walk(value) as T
+}
+
+function parseMapping(raw: string | null): Record<string, string> {
+  if (!raw) return {}
+  try {
+    const parsed = JSON.parse(raw) as unknown
+    if (parsed && typeof parsed === 'object' && !Array.isArray(parsed)) {
+      return Object.fromEntries(Object.entries(parsed).filter((entry): entry is [string, string] => typeof entry[1] === 'string'))
+    }
+  } catch {
+    // Fall through
+  }
+  logger.warn('Ignoring malformed PII mapping preference')
+  return {}
+}
@@ -0,0 +1,89 @@
+import type { Agent, Plan } from '../domain/types.js'
+import { EVENT_TYPES, type EventSink, type RunProgress } from '../events/types.js'
+import type { ToolProgressUpdate } from '../tools/types.js'
+
+export const TOOL_HEARTBEAT_INTERVAL_MS = 5_000
+/** Tool-reported updates closer together than this wait for the next heartbeat. */
+const MIN_REPORT_GAP_MS = 250
+
+/**
+ * Run a tool call while emitting tool:progress heartbeats, so the UI has
+ * something between tool:started and tool:completed. `fn` receives the
+ * callback to pass as ToolContext.reportProgress; the latest reported values
+ * ride along on every later heartbeat.
+ */
+export async function withToolProgress<T>(
+  events: EventSink,
+  agent: Agent,
+  callId: string,
+  name: string,
+  fn: (reportProgress: (update: ToolProgressUpdate) => void) => Promise<T>,
+  intervalMs = TOOL_HEARTBEAT_INTERVAL_MS,
+): Promise<T> {
+  const startedAt = Date.now()
+  let latest: ToolProgressUpdate = {}
+  let lastEmittedAt = 0
+  let done = false
+
+  const emit = () => {
+    lastEmittedAt = Date.now()
+    events.emit({
+      type: EVENT_TYPES.TOOL_PROGRESS,
+      agent_id: agent.id,
+      session_id: agent.sessionId,
+      payload: {
+        callId,
+        name,
+        elapsedMs: lastEmittedAt - startedAt,
+        ...latest,
+        parentId: agent.parentId,
+        depth: agent.depth,
+      },
+      timestamp: lastEmittedAt,
+    })
+  }
+
+  const reportProgress = (update: ToolProgressUpdate) => {
+    if (done) return
+    latest = {
+      progress: typeof update.progress === 'number' && Number.isFinite(update.progress)
+        ? Math.min(1, Math.max(0, update.progress))
+        : latest.progress,
+      message: update.message ?? latest.message,
+    }
+    if (Date.now() - lastEmittedAt >= MIN_REPORT_GAP_MS) emit()
+  }
+
+  const timer = setInterval(emit, intervalMs)
+  timer.unref()
+  try {
+    return await fn(reportProgress)
+  } finally {
+    done = true
+    clearInterval(timer)
+  }
+}
+
+/**
+ * Where a run stands after a step. Plan steps are counted when the agent
+ * keeps a plan; otherwise the run's own finished steps are, and what is left
+ * is unknown.
+ */
+export function runProgress(
+  plan: Plan | null,
+  run: { stepsCompleted: number; startedAt: number; turnsUsed: number; maxTurns: number },
+  now = Date.now(),
+): RunProgress {
+  const planned = plan && plan.steps.length > 0 ? plan.steps : null
+  return {
+    stepsCompleted: planned
+      ? planned.filter((step) => step.status === 'completed' || step.status === 'skipped').length
+      : run.stepsCompleted,
+    stepsRemaining: planned
+      ? planned.filter((step) => step.status === 'pending' || step.status === 'running').length
+      : null,
+    elapsedMs: now - run.startedAt,
+    turnsUsed: run.turnsUsed,
+    maxTurns: run.maxTurns,
+  }
+}
@@ -1,5 +1,8 @@
 import type { LLMMessage, LLMContentBlock } from '../providers/types.js'
 import type { AgentResponseFormat, Item } from '../domain/types.js'
+import { renderUserProfile, type UserProfile } from './user-profile.js'
+import { detectLanguage, responseLanguageInstruction } from './language.js'
+import type { InstructionFile } from '../services/instruction-files.js'
 
 // ---------------------------------------------------------------------------
 // Base prompt — uses JSON markers for providers without native tool calling
@@ -68,6 +71,25 @@ When citing sources, include real source names and URLs. Do not emit provider-in
 
 Be precise and efficient. Execute tools when needed, respond when the task is done.`
 
+// ---------------------------------------------------------------------------
+// Editable copies — the constants above are the defaults runs fall back to
+// ---------------------------------------------------------------------------
+
+export const CONTROLLER_PROMPT_NAMES = ['controller_base', 'controller_anthropic', 'controller_openai'] as const
+export type ControllerPromptName = (typeof CONTROLLER_PROMPT_NAMES)[number]
+export type ControllerPrompts = Record<ControllerPromptName, string>
+
+/** What stored prompts are seeded from and reset to, and what runs use without a store. */
+export const DEFAULT_CONTROLLER_PROMPTS: ControllerPrompts = {
+  controller_base: CONTROLLER_PROMPT_BASE,
+  controller_anthropic: CONTROLLER_PROMPT_ANTHROPIC,
+  controller_openai: CONTROLLER_PROMPT_OPENAI,
+}
+
+export function isControllerPromptName(name: string): name is ControllerPromptName {
+  return (CONTROLLER_PROMPT_NAMES as readonly string[]).includes(name)
+}
+
 // ---------------------------------------------------------------------------
 // Build tool list string for inclusion in system prompt
 // ---------------------------------------------------------------------------
@@ -103,9 +125,11 @@ function itemToMessages(items: Item[]): LLMMessage[] {
       case 'message': {
         const role = item.role ?? 'user'
         if (role === 'system') continue // system messages handled separately
+        const text = (item.content ?? '') + attachedText(item)
+        const images = attachedImages(item)
         messages.push({
           role: role as 'user' | 'assistant',
-          content: item.content ?? '',
+          content: images.length > 0 ? [{ type: 'text', text }, ...images] : text,
         })
         break
       }
@@ -182,6 +206,34 @@ function itemToMessages(items: Item[]): LLMMessage[] {
   return messages
 }
 
+/** Extracted document and image text, inlined the way the client inlines attached text files. */
+function attachedText(item: Item): string {
+  return (item.contentBlocks ?? [])
+    .filter((block) => block.chunks?.length || block.retrieval)
+    .map((block) => {
+      const label = block.type === 'image'
+        ? `Attached image: ${block.name ?? 'image'} (text read from image)`
+        : block.type === 'video'
+          ? `Attached video: ${block.name ?? 'video'} (audio transcript)`
+          : `Attached file: ${block.name ?? 'document'}`
+      if (block.retrieval) {
+        return `\n\n[${label} — too large to include; use attachments.search to read the relevant parts]\n`
+      }
+      return `\n\n[${label}]\n\`\`\`\n${block.chunks!.join('\n\n')}\n\`\`\`\n`
+    })
+    .join('')
+}
+
+/** Hydrated images, such as sampled video frames, each preceded by its name so the model can tell them apart. */
+function attachedImages(item: Item): LLMContentBlock[] {
+  return (item.contentBlocks ?? [])
+    .filter((block) => block.type === 'image' && block.data !== undefined)
+    .flatMap((block): LLMContentBlock[] => [
+      ...(block.name ? [{ type: 'text' as const, text: `[${block.name}]` }] : []),
+      { type: 'image', media_type: block.media_type, data: block.data },
+    ])
+}
+
 function extraPromptInstructions(items: Item[]): string[] {
   const seen = new Set<string>()
   const instructions: string[] = []
@@ -197,6 +249,14 @@ function extraPromptInstructions(items: Item[]): string[] {
   return instructions
 }
 
+function languageInstruction(language: string | null | undefined, history: Item[]): string[] {
+  if (language) return [responseLanguageInstruction(language, false)]
+  if (language === null) return []
+  const latest = [...history].reverse().find((item) => item.type === 'message' && item.role === 'user')
+  const detected = latest?.content ? detectLanguage(latest.content) : null
+  return detected ? [responseLanguageInstruction(detected, true)] : []
+}
+
 function responseFormatInstruction(format: AgentResponseFormat = 'markdown'): string {
   switch (format) {
     case 'telegram_html':
@@ -225,6 +285,15 @@ export interface BuildMessagesConfig {
   agentTask: string
   customSystemPrompt?: string
   responseFormat?: AgentResponseFormat
+  userProfile?: UserProfile | null
+  /** ASSISTANT.md files of the workspace and the conversation's linked projects. */
+  instructionFiles?: InstructionFile[]
+  /**
+   * Language replies must be in. Unset falls back to the language of the
+   * latest user message when it can be told; null adds no instruction, as for
+   * sub-agents whose replies go to their parent.
+   */
+  responseLanguage?: string | null
 }
 
 export function buildControllerMessages(
@@ -238,6 +307,18 @@ export function buildControllerMessages(
   // System message: controller prompt + task + available tools
   let systemContent = systemPrompt
 
+  // The profile sits right after the fixed prompt so the prefix shared by every run stays cacheable
+  const profile = renderUserProfile(config.userProfile)
+  if (profile) {
+    systemContent += `\n\n## About the User\n${profile}`
+  }
+  const stablePrefixLength = systemContent.length
+
+  if (config.instructionFiles?.length) {
+    const files = config.instructionFiles.map((file) => `From ${file.path}:\n${file.content}`)
+    systemContent += `\n\n## Workspace Instructions\n${files.join('\n\n')}`
+  }
+
   if (config.agentTask) {
     systemContent += `\n\n## Current Task\n${config.agentTask}`
   }
@@ -248,6 +329,7 @@ export function buildControllerMessages(
 
   const additionalInstructions = [
     responseFormatInstruction(config.responseFormat),
+    ...languageInstruction(config.responseLanguage, history),
     ...(config.customSystemPrompt ? [config.customSystemPrompt] : []),
     ...extraPromptInstructions(history),
   ]
@@ -255,7 +337,7 @@ export function buildControllerMessages(
     systemContent += `\n\n## Additional Instructions\n${additionalInstructions.join('\n\n')}`
   }
 
-  messages.push({ role: 'system', content: systemContent })
+  messages.push({ role: 'system', content: systemContent, stable_prefix_length: stablePrefixLength })
 
   // Append conversation history
   const historyMessages = itemToMessages(history)
@@ -264,4 +346,28 @@ export function buildControllerMessages(
   return messages
 }
 
+// ---------------------------------------------------------------------------
+// Chat mode
+// ---------------------------------------------------------------------------
+
+/** System prompt for chat mode: a direct answer, no controller protocol and no tools. */
+export const CHAT_SYSTEM_PROMPT = `You are a helpful assistant. Answer the user's latest message directly, using the conversation so far.
+You cannot use tools or look anything up in this mode; when a request needs that, say so briefly.`
+
+/**
+ * Messages for a chat-mode reply. Tool calls and outputs from earlier agent
+ * turns are left out: providers reject them without tool definitions, and
+ * the answers built from them are already in the history.
+ */
+export function buildChatMessages(
+  history: Item[],
+  config: Omit<BuildMessagesConfig, 'useNativeFunctionCalling' | 'agentTask'>,
+): LLMMessage[] {
+  return buildControllerMessages(CHAT_SYSTEM_PROMPT, '', history.filter((item) => item.type === 'message'), {
+    ...config,
+    useNativeFunctionCalling: true,
+    agentTask: '',
+  })
+}
+
 export { buildToolListString }
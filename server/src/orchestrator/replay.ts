@@ -0,0 +1,182 @@
+import type { Agent, AgentConfig, Item } from '../domain/types.js'
+import type { LLMProvider, LLMRequest, LLMResponse, LLMStreamEvent } from '../providers/types.js'
+import type {
+  ToolBatchResult,
+  ToolCall,
+  ToolContext,
+  ToolExecutor,
+  ToolMetadata,
+  ToolPreview,
+  ToolResult,
+  ValidationResult,
+} from '../tools/types.js'
+
+export const RUN_CAPTURE_VERSION = 1
+
+/**
+ * Everything a run got from the outside world, in the order it got it:
+ * model responses and tool results. Replaying one through `runAgent` with
+ * `ReplayProvider` and `ReplayTools` re-runs the parsing, planning and
+ * history-building in between without a provider or a live tool.
+ */
+export interface RunCapture {
+  version: typeof RUN_CAPTURE_VERSION
+  task: string
+  config: AgentConfig
+  responses: LLMResponse[]
+  toolCalls: CapturedToolCall[]
+}
+
+export interface CapturedToolCall {
+  name: string
+  args: Record<string, unknown>
+  result: ToolResult
+}
+
+/** The fields of a debug trace file (see DebugTraceRecorder) a capture is built from. */
+export interface CapturedTrace {
+  agentId: string
+  turnNumber: number
+  createdAt: number
+  response: LLMResponse | null
+}
+
+/**
+ * Builds a capture for one agent from its debug trace files and stored
+ * items. Failed provider calls are left out, since the run retried or gave
+ * up on them. Tool outputs are kept as the text the run stored, which is
+ * also what it fed back to the model.
+ */
+export function buildRunCapture(agent: Agent, traces: CapturedTrace[], items: Item[]): RunCapture {
+  const responses = traces
+    .filter((trace) => trace.agentId === agent.id && trace.response)
+    .sort((a, b) => a.createdAt - b.createdAt || a.turnNumber - b.turnNumber)
+    .map((trace) => trace.response!)
+
+  const outputs = new Map(
+    items.filter((item) => item.type === 'function_call_output' && item.callId).map((item) => [item.callId!, item]),
+  )
+  const toolCalls: CapturedToolCall[] = []
+  for (const call of [...items].sort((a, b) => a.sequence - b.sequence)) {
+    if (call.type !== 'function_call' || !call.name || !call.callId) continue
+    const output = outputs.get(call.callId)
+    if (!output) continue
+    toolCalls.push({
+      name: call.name,
+      args: parseArgs(call.arguments),
+      result: output.isError
+        ? { ok: false, error: output.output ?? 'Unknown error' }
+        : { ok: true, output: output.output ?? undefined },
+    })
+  }
+
+  return { version: RUN_CAPTURE_VERSION, task: agent.task, config: agent.config, responses, toolCalls }
+}
+
+/** Answers each request with the next recorded response; running past the end fails the run. */
+export class ReplayProvider implements LLMProvider {
+  /** Every request the run sent, for comparing prompts across changes. */
+  readonly requests: LLMRequest[] = []
+  private next = 0
+
+  constructor(private readonly responses: LLMResponse[]) {}
+
+  get remaining(): number {
+    return this.responses.length - this.next
+  }
+
+  async generate(request: LLMRequest): Promise<LLMResponse> {
+    this.requests.push(request)
+    const response = this.responses[this.next]
+    if (!response) {
+      throw new Error(`Replay has no response for request ${this.next + 1}; the capture holds ${this.responses.length}`)
+    }
+    this.next++
+    return response
+  }
+
+  async *stream(request: LLMRequest): AsyncIterable<LLMStreamEvent> {
+    const response = await this.generate(request)
+    if (typeof response.content === 'string' && response.content) {
+      yield { type: 'text_delta', text: response.content }
+    }
+    yield { type: 'done', response }
+  }
+}
+
+/**
+ * Serves recorded tool results in call order, taking tool schemas from a
+ * real executor so the prompt matches the one the run saw. Recorded calls
+ * were already approved, so every tool is reported as needing no approval.
+ * A call with no recorded result, or with different args, is noted in
+ * `divergences` rather than thrown, so one run shows every difference.
+ */
+export class ReplayTools implements ToolExecutor {
+  readonly divergences: string[] = []
+  private readonly pending: CapturedToolCall[]
+
+  constructor(private readonly base: ToolExecutor, toolCalls: CapturedToolCall[]) {
+    this.pending = [...toolCalls]
+  }
+
+  /** Recorded calls the run never made. */
+  get unused(): CapturedToolCall[] {
+    return [...this.pending]
+  }
+
+  async execute(name: string, args: Record<string, unknown>, _ctx: ToolContext): Promise<ToolResult> {
+    const index = this.pending.findIndex((call) => call.name === name)
+    if (index === -1) {
+      this.divergences.push(`${name} was called but has no recorded result`)
+      return { ok: false, error: `Replay has no recorded result for ${name}` }
+    }
+    const [recorded] = this.pending.splice(index, 1)
+    if (JSON.stringify(recorded.args) !== JSON.stringify(args)) {
+      this.divergences.push(`${name} was called with ${JSON.stringify(args)}, recorded with ${JSON.stringify(recorded.args)}`)
+    }
+    return recorded.result
+  }
+
+  async executeBatch(calls: ToolCall[], ctx: ToolContext): Promise<ToolBatchResult> {
+    const results = []
+    for (const call of calls) {
+      results.push({ call_id: call.call_id, ...(await this.execute(call.name, call.args, ctx)) })
+    }
+    return { results, all_ok: results.every((r) => r.ok) }
+  }
+
+  getMetadata(name: string): ToolMetadata | undefined {
+    const metadata = this.base.getMetadata(name)
+    return metadata && withoutApproval(metadata)
+  }
+
+  listMetadata(): ToolMetadata[] {
+    return this.base.listMetadata().map(withoutApproval)
+  }
+
+  unregister(name: string): boolean {
+    return this.base.unregister(name)
+  }
+
+  validateArgs(name: string, args: unknown): ValidationResult {
+    return this.base.validateArgs(name, args)
+  }
+
+  getPreview(name: string, args: Record<string, unknown>, ctx: ToolContext): ToolPreview | undefined {
+    return this.base.getPreview(name, args, ctx)
+  }
+}
+
+function withoutApproval(metadata: ToolMetadata): ToolMetadata {
+  return { ...metadata, requires_approval: false, category: undefined, approval_triggers: undefined }
+}
+
+function parseArgs(raw: string | null): Record<string, unknown> {
+  if (!raw) return {}
+  try {
+    const parsed: unknown = JSON.parse(raw)
+    return parsed && typeof parsed === 'object' && !Array.isArray(parsed) ? parsed as Record<string, unknown> : {}
+  } catch {
+    return {}
+  }
+}
@@ -0,0 +1,110 @@
+export interface RunSchedulerOptions {
+  /** Agent runs executing at once, across all conversations. */
+  maxRuns: number
+}
+
+interface Waiter {
+  sessionId: string
+  start: () => void
+  onPosition?: (position: number) => void
+  /** Last position reported, so unchanged positions are not reported again. */
+  position: number
+}
+
+/**
+ * Caps how many agent runs execute at once and never runs two of the same
+ * conversation together, so their writes cannot interleave. Runs over the
+ * limit queue in arrival order; a queued run whose conversation is busy lets
+ * later runs of other conversations go first.
+ */
+export class RunScheduler {
+  private readonly maxRuns: number
+  private readonly running = new Set<string>()
+  private readonly waiting: Waiter[] = []
+
+  constructor(options: RunSchedulerOptions) {
+    this.maxRuns = Math.max(1, Math.floor(options.maxRuns))
+  }
+
+  get active(): number {
+    return this.running.size
+  }
+
+  get queued(): number {
+    return this.waiting.length
+  }
+
+  /**
+   * Runs `task` once the conversation is idle and a slot is free. While it
+   * waits, `onPosition` hears its 1-based place in the queue each time it
+   * changes; an abort while queued rejects with the signal's reason.
+   */
+  async run<T>(
+    sessionId: string,
+    task: () => Promise<T>,
+    options: { signal?: AbortSignal; onPosition?: (position: number) => void } = {},
+  ): Promise<T> {
+    await this.acquire(sessionId, options.signal, options.onPosition)
+    try {
+      return await task()
+    } finally {
+      this.release(sessionId)
+    }
+  }
+
+  private acquire(sessionId: string, signal?: AbortSignal, onPosition?: (position: number) => void): Promise<void> {
+    if (signal?.aborted) return Promise.reject(signal.reason)
+    // A run of the same conversation already queued goes first, so sends keep their order
+    if (this.hasRoom(sessionId) && !this.waiting.some((w) => w.sessionId === sessionId)) {
+      this.running.add(sessionId)
+      return Promise.resolve()
+    }
+    return new Promise<void>((resolve, reject) => {
+      const abort = () => {
+        const index = this.waiting.indexOf(waiter)
+        if (index !== -1) this.waiting.splice(index, 1)
+        this.reportPositions()
+        reject(signal!.reason)
+      }
+      const waiter: Waiter = {
+        sessionId,
+        onPosition,
+        position: 0,
+        start: () => {
+          signal?.removeEventListener('abort', abort)
+          resolve()
+        },
+      }
+      this.waiting.push(waiter)
+      signal?.addEventListener('abort', abort, { once: true })
+      this.reportPositions()
+    })
+  }
+
+  private release(sessionId: string): void {
+    this.running.delete(sessionId)
+    for (let i = 0; i < this.waiting.length && this.running.size < this.maxRuns; ) {
+      const waiter = this.waiting[i]
+      if (!this.hasRoom(waiter.sessionId)) {
+        i++
+        continue
+      }
+      this.waiting.splice(i, 1)
+      this.running.add(waiter.sessionId)
+      waiter.start()
+    }
+    this.reportPositions()
+  }
+
+  private hasRoom(sessionId: string): boolean {
+    return this.running.size < this.maxRuns && !this.running.has(sessionId)
+  }
+
+  private reportPositions(): void {
+    this.waiting.forEach((waiter, index) => {
+      if (waiter.position === index + 1) return
+      waiter.position = index + 1
+      waiter.onPosition?.(waiter.position)
+    })
+  }
+}
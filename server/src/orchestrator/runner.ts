@@ -9,11 +9,15 @@ import type {
   StepExecutionOutcome,
   ToolCallSpec,
 } from './types.js'
-import type { Item, WaitingFor } from '../domain/types.js'
-import type { LLMProvider, LLMRequest, LLMToolDefinition, LLMResponse } from '../providers/types.js'
-import type { ToolCall } from '../tools/types.js'
+import type { Agent, Item, WaitingFor } from '../domain/types.js'
+import type { UsagePhase } from '../repositories/types.js'
+import type { LLMMessage, LLMProvider, LLMRequest, LLMToolDefinition, LLMResponse } from '../providers/types.js'
+import type { ToolCall, ToolExecutor, ToolMetadata, ToolPreview } from '../tools/types.js'
+import { stepDeadline, toolCallLimits } from '../tools/registry.js'
 import { startAgent, completeAgent, failAgent, cancelAgent, waitForMany } from '../domain/agent.js'
+import { logger } from '../lib/logger.js'
 import { splitModelId } from '../lib/model.js'
+import { isLocalProvider, isNetworkError } from '../lib/connectivity.js'
 import {
   parseControllerAction,
   mapToolCallsToAction,
@@ -21,15 +25,38 @@ import {
   controllerOutputSchema,
 } from './parsing.js'
 import {
-  CONTROLLER_PROMPT_BASE,
-  CONTROLLER_PROMPT_ANTHROPIC,
-  CONTROLLER_PROMPT_OPENAI,
+  DEFAULT_CONTROLLER_PROMPTS,
+  buildChatMessages,
   buildControllerMessages,
   buildToolListString,
+  type BuildMessagesConfig,
+  type ControllerPrompts,
 } from './prompts.js'
 import { materializeTextOutput, materializeToolOutput } from './output.js'
+import { classifyError, classifyToolError, OfflineError, RunPersistenceError, ServerShutdownError, StalledRunError } from './errors.js'
+import {
+  APPROVAL_RULES_PREFERENCE_KEY,
+  matchApprovalRule,
+  matchApprovalTrigger,
+  parseApprovalRules,
+  type ApprovalRule,
+} from './approval-rules.js'
+import { escalateApprovalBatch } from './approval-batch.js'
+import {
+  APPROVAL_CATEGORY_DEFAULTS_PREFERENCE_KEY,
+  defaultApprovalAction,
+  parseCategoryDefaults,
+} from './approval-categories.js'
 import { hydrateToolArgs } from './hydration.js'
-import { EVENT_TYPES } from '../events/types.js'
+import { parseUserProfile, USER_PROFILE_PREFERENCE_KEY } from './user-profile.js'
+import { resolveResponseLanguage } from './language.js'
+import { getConversationMode } from './chat-mode.js'
+import { reidentifyResponse, StreamReidentifier, type PiiMapping } from './privacy.js'
+import { runProgress, withToolProgress } from './progress.js'
+import { EVENT_TYPES, type GuardrailTrigger } from '../events/types.js'
+import { TextCoalescer } from '../events/text-coalescer.js'
+import { SpendCapExceededError } from '../usage/budget.js'
+import { estimatePromptComposition } from '../usage/attribution.js'
 
 const DEFAULT_MAX_TURNS = 50
 const MAX_AGENT_DEPTH = 5
@@ -43,6 +70,26 @@ export async function runAgent(
   agentId: string,
   deps: OrchestratorDeps,
   options?: RunOptions,
+): Promise<RunResult> {
+  if (!deps.runScheduler) return runAgentNow(agentId, deps, options)
+  const agent = await deps.agents.getById(agentId)
+  if (!agent) throw new Error(`Agent not found: ${agentId}`)
+  return deps.runScheduler.run(agent.sessionId, () => runAgentNow(agentId, deps, options), {
+    signal: options?.signal,
+    onPosition: (position) => deps.events.emit({
+      type: EVENT_TYPES.RUN_QUEUED,
+      agent_id: agentId,
+      session_id: agent.sessionId,
+      payload: { position, parentId: agent.parentId, depth: agent.depth },
+      timestamp: Date.now(),
+    }),
+  })
+}
+
+async function runAgentNow(
+  agentId: string,
+  deps: OrchestratorDeps,
+  options?: RunOptions,
 ): Promise<RunResult> {
   // Load agent
   let agent = await deps.agents.getById(agentId)
@@ -76,6 +123,12 @@ export async function runAgent(
     timestamp: Date.now(),
   })
 
+  // Read once per run so every turn sends the same system prompt
+  const userProfile = parseUserProfile(await deps.preferences.get(USER_PROFILE_PREFERENCE_KEY))
+  const controllerPrompts = await deps.prompts?.active(agent.sessionId)
+  const { language: responseLanguage } = await resolveResponseLanguage(deps.preferences, agent.sessionId, userProfile)
+  const chatMode = agent.depth === 0 && (options?.mode ?? await getConversationMode(deps.preferences, agent.sessionId)) === 'chat'
+
   const ctx: RunContext = {
     agents: deps.agents,
     items: deps.items,
@@ -88,10 +141,36 @@ export async function runAgent(
     agentDefinitions: deps.agentDefinitions,
     sessionFilesRoot: deps.sessionFilesRoot,
     inlineOutputLimitBytes: deps.inlineOutputLimitBytes,
+    inlineOutputBudget: deps.inlineOutputBudget,
+    redactor: deps.redactor,
+    privacy: deps.privacy,
+    connectivity: deps.connectivity,
+    verifier: deps.verifier,
+    guardrails: deps.guardrails,
     interceptHandlers: deps.interceptHandlers,
     observability: deps.observability,
+    usage: deps.usage,
+    spendCaps: deps.spendCaps,
+    traces: deps.traces,
+    audit: deps.audit,
+    metrics: deps.metrics,
+    approvals: deps.approvals,
+    approvalEscalation: deps.approvalEscalation,
+    attachments: deps.attachments,
+    historyWindow: deps.historyWindow,
+    instructions: deps.instructions,
+    toolPool: deps.toolPool,
+    agentAbortControllers: deps.agentAbortControllers,
+    prompts: deps.prompts,
+    userProfile,
+    responseLanguage,
+    controllerPrompts,
+    chatMode,
     agent,
     turnNumber: 0,
+    maxTurns,
+    startedAt: Date.now(),
+    stepsCompleted: 0,
     signal,
     stream: options?.stream ?? false,
   }
@@ -117,48 +196,38 @@ export async function runAgent(
         timestamp: Date.now(),
       })
 
-      // 1. Build messages
-      const useNativeTools = isNativeToolProvider(ctx.agent.config.provider)
-      const systemPrompt = selectSystemPrompt(ctx.agent.config.provider)
-      const allToolMetadata = ctx.tools.listMetadata()
-      const baseToolMetadata = allToolMetadata.filter((t) => !t.name.startsWith('mcp.'))
-      const allowedTools = ctx.agent.config.allowed_tools
-      let toolMetadata = allowedTools
-        ? baseToolMetadata.filter((t) => allowedTools.includes(t.name))
-        : baseToolMetadata
-      toolMetadata = filterToolMetadataForProvider(ctx.agent.config.provider, toolMetadata)
-
-      if (ctx.agent.config.tools?.length) {
-        toolMetadata = [...toolMetadata, ...ctx.agent.config.tools]
-      }
-
-      if (ctx.agent.depth >= MAX_AGENT_DEPTH) {
-        toolMetadata = toolMetadata.filter((t) => t.name !== 'delegate')
-      }
-      const toolListStr = buildToolListString(toolMetadata)
+      // A capped provider switches the run to its fallback before the prompt is built
+      if (ctx.spendCaps) await applySpendCaps(ctx)
+      // Offline, fail now (or move to the local model) rather than on a connection error
+      if (ctx.connectivity) await applyConnectivity(ctx)
 
+      // 1. Build messages
       // Load this agent's own items. Root agents already have complete context:
       // user messages, tool call/output pairs, and delegate results (which summarise
       // child agent work). Using listBySession would include child agent items after
       // the root's items, breaking chronological order on follow-up messages and
       // burying the user's latest message mid-history.
-      const items = await ctx.items.listByAgent(agentId)
-      const messages = buildControllerMessages(systemPrompt, toolListStr, items, {
-        useNativeFunctionCalling: useNativeTools,
-        agentTask: ctx.agent.task,
-        customSystemPrompt: ctx.agent.config.system_prompt,
-        responseFormat: ctx.agent.config.response_format ?? 'markdown',
-      })
+      const historyWindow = ctx.historyWindow
+      const stored = historyWindow?.userTurns
+        ? await ctx.items.listRecentByAgent(agentId, historyWindow.userTurns)
+        : await ctx.items.listByAgent(agentId)
+      const items = ctx.attachments ? await ctx.attachments.hydrate(stored, historyWindow?.attachmentUserTurns) : stored
+
+      // Failure streaks, spend and the request are checked before any more is spent on the turn
+      const turnStop = ctx.guardrails ? await ctx.guardrails.checkTurn(ctx.agent, stored) : null
+      if (turnStop) return await stopForGuardrail(ctx, turnStop)
+
+      const instructionFiles = ctx.instructions ? await ctx.instructions.forSession(ctx.agent.sessionId) : []
+      const promptContext = {
+        userProfile: ctx.userProfile,
+        instructionFiles,
+        responseLanguage: ctx.responseLanguage ?? undefined,
+      }
+      const { messages, tools: toolDefs, useNativeTools } = ctx.chatMode
+        ? buildChatPrompt(ctx.agent, items, promptContext)
+        : buildControllerPrompt(ctx.agent, ctx.tools, items, { ...promptContext, controllerPrompts: ctx.controllerPrompts })
 
       // 2. Call LLM provider
-      const toolDefs: LLMToolDefinition[] | undefined = useNativeTools
-        ? toolMetadata.map((t) => ({
-            name: t.name,
-            description: t.description,
-            parameters: t.parameters,
-          }))
-        : undefined
-
       // Strip provider prefix ("anthropic:claude-3" → "claude-3")
       const { model: modelName } = splitModelId(ctx.agent.config.model)
 
@@ -167,10 +236,23 @@ export async function runAgent(
         messages,
         tools: toolDefs,
         structured_output: !useNativeTools ? controllerOutputSchema() : undefined,
-        max_tokens: ctx.agent.config.max_output_tokens ?? DEFAULT_MAX_OUTPUT_TOKENS,
+        max_tokens: maxOutputTokens(ctx.agent),
         signal,
       }
 
+      deps.events.emit({
+        type: EVENT_TYPES.PROMPT_COMPOSITION,
+        agent_id: agentId,
+        session_id: ctx.agent.sessionId,
+        payload: {
+          turn: ctx.turnNumber,
+          composition: estimatePromptComposition(llmRequest),
+          parentId: ctx.agent.parentId,
+          depth: ctx.agent.depth,
+        },
+        timestamp: Date.now(),
+      })
+
       // Only stream for native tool providers — non-native providers output raw JSON
       // which is meaningless to stream character-by-character to the UI.
       const llmResponse: LLMResponse = await traceGenerationTurn(ctx, deps, modelName, llmRequest, useNativeTools, agentId)
@@ -220,7 +302,9 @@ export async function runAgent(
         timestamp: Date.now(),
       })
 
-      // 5. Execute action
+      // 5. Execute action, unless it breaks a guardrail
+      const actionStop = ctx.guardrails ? await ctx.guardrails.checkAction(action) : null
+      if (actionStop) return await stopForGuardrail(ctx, actionStop)
       const outcome = await executeAction(ctx, action, llmResponse)
 
       deps.events.emit({
@@ -231,23 +315,38 @@ export async function runAgent(
         timestamp: Date.now(),
       })
 
-      // 6. Save turn count
-      await ctx.agents.update(agentId, { turnCount: ctx.turnNumber })
-
-      // 7. Check outcome
+      // 6. Check outcome
       if (outcome.type === 'complete') {
+        // A root answer is checked against the turn's persisted tool outputs before it is saved
+        const verification = ctx.verifier && ctx.agent.depth === 0 && outcome.message !== undefined
+          ? await ctx.verifier.verify(ctx, outcome.response, latestMessage(items, 'user')?.createdAt ?? 0) ?? undefined
+          : undefined
+
+        // Closing message, turn count and final state land together or not at all
         const completed = completeAgent(ctx.agent, outcome.response)
-        ctx.agent = await ctx.agents.update(agentId, {
-          status: 'completed',
-          result: completed.result,
-          completedAt: completed.completedAt,
-        })
+        const message = outcome.message === undefined ? undefined : {
+          agentId,
+          type: 'message' as const,
+          role: 'assistant' as const,
+          content: outcome.message,
+          turnNumber: ctx.turnNumber,
+        }
+        try {
+          ctx.agent = await ctx.agents.finish(agentId, {
+            status: 'completed',
+            result: completed.result,
+            turnCount: ctx.turnNumber,
+            completedAt: completed.completedAt,
+          }, message)
+        } catch (err) {
+          throw new RunPersistenceError(err)
+        }
 
         deps.events.emit({
           type: EVENT_TYPES.AGENT_COMPLETED,
           agent_id: agentId,
           session_id: ctx.agent.sessionId,
-          payload: { result: outcome.response, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+          payload: { result: outcome.response, verification, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
           timestamp: Date.now(),
         })
 
@@ -256,9 +355,13 @@ export async function runAgent(
           status: 'completed',
           result: outcome.response,
           turnCount: ctx.turnNumber,
+          verification,
         }
       }
 
+      // 7. Save turn count
+      await ctx.agents.update(agentId, { turnCount: ctx.turnNumber })
+
       if (outcome.type === 'waiting') {
         return {
           agentId,
@@ -280,7 +383,7 @@ export async function runAgent(
         type: EVENT_TYPES.AGENT_FAILED,
         agent_id: agentId,
         session_id: ctx.agent.sessionId,
-        payload: { error: 'Agent cancelled', parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+        payload: { error: 'Agent cancelled', errorCode: 'cancelled', parentId: ctx.agent.parentId, depth: ctx.agent.depth },
         timestamp: Date.now(),
       })
 
@@ -288,16 +391,18 @@ export async function runAgent(
         agentId,
         status: 'cancelled',
         error: 'Agent cancelled',
+        errorCode: 'cancelled',
         turnCount: ctx.turnNumber,
       }
     }
 
     // Max turns exceeded
     const errorMsg = `Agent reached maximum turn limit (${maxTurns})`
-    const failed = failAgent(ctx.agent, errorMsg)
+    const failed = failAgent(ctx.agent, errorMsg, 'max_turns')
     ctx.agent = await ctx.agents.update(agentId, {
       status: 'failed',
       error: errorMsg,
+      errorCode: failed.errorCode,
       completedAt: failed.completedAt,
     })
 
@@ -305,7 +410,7 @@ export async function runAgent(
       type: EVENT_TYPES.AGENT_FAILED,
       agent_id: agentId,
       session_id: ctx.agent.sessionId,
-      payload: { error: errorMsg, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+      payload: { error: errorMsg, errorCode: 'max_turns', parentId: ctx.agent.parentId, depth: ctx.agent.depth },
       timestamp: Date.now(),
     })
 
@@ -313,9 +418,15 @@ export async function runAgent(
       agentId,
       status: 'failed',
       error: errorMsg,
+      errorCode: 'max_turns',
       turnCount: ctx.turnNumber,
     }
-    } catch (err) {
+    } catch (caught) {
+    // Nobody asked a run stopped by shutdown or the watchdog to stop, so it fails rather than being cancelled
+    const stopped = signal.reason instanceof ServerShutdownError || signal.reason instanceof StalledRunError
+    let err = stopped ? signal.reason : caught
+    // The connection may have dropped since it was last checked
+    if (!stopped && ctx.connectivity && isNetworkError(err) && !(await ctx.connectivity.check())) err = new OfflineError()
     const errorMsg = err instanceof Error ? err.message : String(err)
 
     // Agent may have been cancelled externally while a tool/LLM call was in flight.
@@ -323,7 +434,7 @@ export async function runAgent(
     // Check signal.aborted as the canonical source: AbortError messages vary by runtime
     // ("The operation was aborted." in Bun/Node) and ctx.agent is an in-memory snapshot
     // that won't reflect the DB update made by the cancel route.
-    if (ctx.agent.status === 'cancelled' || signal.aborted || errorMsg === 'Agent run aborted') {
+    if (!stopped && (ctx.agent.status === 'cancelled' || signal.aborted || errorMsg === 'Agent run aborted')) {
       const refreshed = await ctx.agents.getById(agentId)
       if (refreshed && refreshed.status !== 'cancelled') {
         const cancelled = cancelAgent(refreshed)
@@ -337,7 +448,7 @@ export async function runAgent(
         type: EVENT_TYPES.AGENT_FAILED,
         agent_id: agentId,
         session_id: ctx.agent.sessionId,
-        payload: { error: 'Agent cancelled', parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+        payload: { error: 'Agent cancelled', errorCode: 'cancelled', parentId: ctx.agent.parentId, depth: ctx.agent.depth },
         timestamp: Date.now(),
       })
 
@@ -345,14 +456,17 @@ export async function runAgent(
         agentId,
         status: 'cancelled',
         error: 'Agent cancelled',
+        errorCode: 'cancelled',
         turnCount: ctx.turnNumber,
       }
     }
 
-    const failed = failAgent(ctx.agent, errorMsg)
+    const errorCode = classifyError(err)
+    const failed = failAgent(ctx.agent, errorMsg, errorCode)
     await ctx.agents.update(agentId, {
       status: 'failed',
       error: errorMsg,
+      errorCode,
       completedAt: failed.completedAt,
     })
 
@@ -360,7 +474,7 @@ export async function runAgent(
       type: EVENT_TYPES.AGENT_FAILED,
       agent_id: agentId,
       session_id: ctx.agent.sessionId,
-      payload: { error: errorMsg, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+      payload: { error: errorMsg, errorCode, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
       timestamp: Date.now(),
     })
 
@@ -368,6 +482,7 @@ export async function runAgent(
         agentId,
         status: 'failed',
         error: errorMsg,
+        errorCode,
         turnCount: ctx.turnNumber,
       }
     }
@@ -413,24 +528,70 @@ async function traceGenerationTurn(
   ctx: RunContext,
   deps: OrchestratorDeps,
   modelName: string,
-  request: LLMRequest,
+  llmRequest: LLMRequest,
   useNativeTools: boolean,
   agentId: string,
 ): Promise<LLMResponse> {
+  // In privacy mode the provider and every trace see placeholders; the answer is mapped back on return
+  const mapping = ctx.privacy ? await ctx.privacy.forSession(ctx.agent.sessionId) : null
+  const request = mapping && ctx.privacy
+    ? await ctx.privacy.pseudonymizeRequest(ctx.agent.sessionId, mapping, llmRequest)
+    : llmRequest
   const generate = () => (ctx.stream && useNativeTools)
-    ? streamLLMTurn(ctx.provider, request, deps, agentId, ctx.agent.sessionId, ctx.agent.parentId, ctx.agent.sourceCallId, ctx.agent.depth)
+    ? streamLLMTurn(ctx.provider, request, deps, agentId, ctx.agent.sessionId, ctx.agent.parentId, ctx.agent.sourceCallId, ctx.agent.depth, mapping)
     : ctx.provider.generate(request)
 
-  return ctx.observability
-    ? ctx.observability.traceGeneration({
-        agent: ctx.agent,
-        turnNumber: ctx.turnNumber,
-        provider: ctx.agent.config.provider,
-        model: modelName,
-        request,
-        stream: ctx.stream && useNativeTools,
-      }, generate)
-    : generate()
+  const trace = {
+    agent: ctx.agent,
+    turnNumber: ctx.turnNumber,
+    provider: ctx.agent.config.provider,
+    model: modelName,
+    request,
+    stream: ctx.stream && useNativeTools,
+  }
+  const startedAt = Date.now()
+  let response: LLMResponse
+  try {
+    response = await (ctx.observability
+      ? ctx.observability.traceGeneration(trace, generate)
+      : generate())
+  } catch (err) {
+    const failed = {
+      ...trace,
+      error: err instanceof Error ? err.message : String(err),
+      durationMs: Date.now() - startedAt,
+    }
+    await ctx.traces?.record(failed)
+    await ctx.audit?.record(failed)
+    await ctx.metrics?.record(failed)
+    throw err
+  }
+  const finished = { ...trace, response, durationMs: Date.now() - startedAt }
+  await ctx.traces?.record(finished)
+  await ctx.audit?.record(finished)
+  await ctx.metrics?.record(finished)
+
+  await ctx.usage?.record({
+    agent: ctx.agent,
+    provider: ctx.agent.config.provider,
+    model: modelName,
+    usage: response.usage,
+    phase: usagePhase(response, useNativeTools),
+    request,
+  })
+
+  return mapping ? reidentifyResponse(mapping, response) : response
+}
+
+/** Turns that pick tool calls drive the loop; the rest answer (or ask) the user. */
+function usagePhase(response: LLMResponse, useNativeTools: boolean): UsagePhase {
+  if (response.tool_calls && response.tool_calls.length > 0) return 'controller'
+  if (useNativeTools) return 'responder'
+  try {
+    return parseControllerAction(response.content).action === 'next_step' ? 'controller' : 'responder'
+  } catch {
+    return 'controller'
+  }
 }
 
 async function streamLLMTurn(
@@ -442,29 +603,38 @@ async function streamLLMTurn(
   parentId?: string | null,
   sourceCallId?: string | null,
   depth?: number,
+  mapping?: PiiMapping | null,
 ): Promise<LLMResponse> {
   const streamIter = provider.stream(request)
+  const reidentifier = mapping ? new StreamReidentifier(mapping) : null
   let finalResponse: LLMResponse | null = null
+  // Emit to event bus — the SSE handler consumes TEXT_DELTA events in order
+  // with all other events, ensuring correct interleaving without concurrent writes.
+  // Deltas are coalesced first so a fast provider does not emit one event per token.
+  const text = new TextCoalescer((chunk) => deps.events.emit({
+    type: EVENT_TYPES.TEXT_DELTA,
+    agent_id: agentId,
+    session_id: sessionId,
+    payload: { text: chunk, parentId: parentId ?? null, sourceCallId: sourceCallId ?? null, depth: depth ?? 0 },
+    timestamp: Date.now(),
+  }))
 
-  for await (const event of streamIter) {
-    switch (event.type) {
-      case 'text_delta':
-        // Emit to event bus — the SSE handler consumes TEXT_DELTA events in order
-        // with all other events, ensuring correct interleaving without concurrent writes.
-        deps.events.emit({
-          type: EVENT_TYPES.TEXT_DELTA,
-          agent_id: agentId,
-          session_id: sessionId,
-          payload: { text: event.text, parentId: parentId ?? null, sourceCallId: sourceCallId ?? null, depth: depth ?? 0 },
-          timestamp: Date.now(),
-        })
-        break
-      case 'done':
-        finalResponse = event.response
-        break
-      case 'error':
-        throw new Error(`LLM stream error: ${event.error}`)
+  try {
+    for await (const event of streamIter) {
+      switch (event.type) {
+        case 'text_delta':
+          text.push(reidentifier ? reidentifier.push(event.text) : event.text)
+          break
+        case 'done':
+          finalResponse = event.response
+          break
+        case 'error':
+          throw new Error(`LLM stream error: ${event.error}`)
+      }
     }
+  } finally {
+    if (reidentifier) text.push(reidentifier.flush())
+    text.flush()
   }
 
   if (!finalResponse) {
@@ -481,25 +651,155 @@ async function streamLLMTurn(
   return finalResponse
 }
 
+// ---------------------------------------------------------------------------
+// Spending caps
+// ---------------------------------------------------------------------------
+
+async function applySpendCaps(ctx: RunContext): Promise<void> {
+  const { model, caps } = await ctx.spendCaps!.resolveModel(ctx.agent, ctx.agent.config.model)
+  if (model === ctx.agent.config.model) return
+  if (!ctx.providers) throw new SpendCapExceededError(ctx.agent.config.model, caps)
+
+  const from = ctx.agent.config.model
+  ctx.provider = ctx.providers.resolve(model)
+  ctx.agent = { ...ctx.agent, config: { ...ctx.agent.config, model, provider: splitModelId(model).provider } }
+  logger.info({ agentId: ctx.agent.id, from, to: model }, 'Spending cap reached; switching to fallback model')
+  ctx.events.emit({
+    type: EVENT_TYPES.BUDGET_FALLBACK,
+    agent_id: ctx.agent.id,
+    session_id: ctx.agent.sessionId,
+    payload: {
+      from,
+      to: model,
+      caps: caps.map(({ scope, period, limitUsd, spentUsd }) => ({ scope, period, limitUsd, spentUsd })),
+      parentId: ctx.agent.parentId,
+      depth: ctx.agent.depth,
+    },
+    timestamp: Date.now(),
+  })
+}
+
+/** Offline, a run on a remote provider moves to the configured local model, or fails with OfflineError. */
+async function applyConnectivity(ctx: RunContext): Promise<void> {
+  const connectivity = ctx.connectivity!
+  if (isLocalProvider(ctx.agent.config.provider) || await connectivity.isOnline()) return
+  const model = connectivity.offlineModel
+  if (!model || !ctx.providers) throw new OfflineError()
+
+  const from = ctx.agent.config.model
+  ctx.provider = ctx.providers.resolve(model)
+  ctx.agent = { ...ctx.agent, config: { ...ctx.agent.config, model, provider: splitModelId(model).provider } }
+  logger.info({ agentId: ctx.agent.id, from, to: model }, 'Offline; switching to local model')
+  ctx.events.emit({
+    type: EVENT_TYPES.OFFLINE_FALLBACK,
+    agent_id: ctx.agent.id,
+    session_id: ctx.agent.sessionId,
+    payload: { from, to: model, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+    timestamp: Date.now(),
+  })
+}
+
+// ---------------------------------------------------------------------------
+// Controller prompt
+// ---------------------------------------------------------------------------
+
+export interface ControllerPrompt {
+  messages: LLMMessage[]
+  /** Native tool definitions; undefined when tools are listed in the system prompt instead. */
+  tools?: LLMToolDefinition[]
+  useNativeTools: boolean
+}
+
+/** Builds the prompt a controller turn sends for this agent and history. */
+export function buildControllerPrompt(
+  agent: Pick<Agent, 'task' | 'depth' | 'config'>,
+  tools: Pick<ToolExecutor, 'listMetadata'>,
+  items: Item[],
+  context: Pick<BuildMessagesConfig, 'userProfile' | 'instructionFiles' | 'responseLanguage'> & { controllerPrompts?: ControllerPrompts } = {},
+): ControllerPrompt {
+  const { controllerPrompts = DEFAULT_CONTROLLER_PROMPTS, ...messageContext } = context
+  const useNativeTools = isNativeToolProvider(agent.config.provider)
+  const systemPrompt = selectSystemPrompt(agent.config.provider, controllerPrompts)
+  const baseToolMetadata = tools.listMetadata().filter((t) => !t.name.startsWith('mcp.'))
+  const allowedTools = agent.config.allowed_tools
+  let toolMetadata = allowedTools
+    ? baseToolMetadata.filter((t) => allowedTools.includes(t.name))
+    : baseToolMetadata
+  toolMetadata = filterToolMetadataForProvider(agent.config.provider, toolMetadata)
+
+  if (agent.config.tools?.length) {
+    toolMetadata = [...toolMetadata, ...agent.config.tools]
+  }
+
+  if (agent.depth >= MAX_AGENT_DEPTH) {
+    toolMetadata = toolMetadata.filter((t) => t.name !== 'delegate')
+  }
+
+  const messages = buildControllerMessages(systemPrompt, buildToolListString(toolMetadata), items, {
+    useNativeFunctionCalling: useNativeTools,
+    agentTask: agent.task,
+    customSystemPrompt: agent.config.system_prompt,
+    responseFormat: agent.config.response_format ?? 'markdown',
+    ...messageContext,
+    // Sub-agents answer their parent, not the user
+    ...(agent.depth > 0 ? { responseLanguage: null } : {}),
+  })
+
+  return {
+    messages,
+    tools: useNativeTools
+      ? toolMetadata.map((t) => ({
+          name: t.name,
+          description: t.description,
+          parameters: t.parameters,
+        }))
+      : undefined,
+    useNativeTools,
+  }
+}
+
+/**
+ * Builds the prompt a chat-mode turn sends: the history and no tools. The
+ * reply is plain text like a native-tool provider's final answer, so it
+ * streams and completes the run in one turn.
+ */
+export function buildChatPrompt(
+  agent: Pick<Agent, 'config'>,
+  items: Item[],
+  context: Pick<BuildMessagesConfig, 'userProfile' | 'instructionFiles' | 'responseLanguage'> = {},
+): ControllerPrompt {
+  const messages = buildChatMessages(items, {
+    customSystemPrompt: agent.config.system_prompt,
+    responseFormat: agent.config.response_format ?? 'markdown',
+    ...context,
+  })
+  return { messages, useNativeTools: true }
+}
+
+export function maxOutputTokens(agent: Pick<Agent, 'config'>): number {
+  return agent.config.max_output_tokens ?? DEFAULT_MAX_OUTPUT_TOKENS
+}
+
 // ---------------------------------------------------------------------------
 // Provider detection
 // ---------------------------------------------------------------------------
 
 function isNativeToolProvider(provider: string): boolean {
-  const native = ['anthropic', 'openai', 'deepseek', 'ollama', 'openrouter']
+  const native = ['anthropic', 'openai', 'deepseek', 'ollama', 'openrouter', 'mock']
   return native.includes(provider.toLowerCase())
 }
 
-function selectSystemPrompt(provider: string): string {
+function selectSystemPrompt(provider: string, prompts: ControllerPrompts): string {
   switch (provider.toLowerCase()) {
     case 'anthropic':
-      return CONTROLLER_PROMPT_ANTHROPIC
+      return prompts.controller_anthropic
     case 'openai':
     case 'deepseek':
     case 'openrouter':
-      return CONTROLLER_PROMPT_OPENAI
+    case 'mock':
+      return prompts.controller_openai
     default:
-      return CONTROLLER_PROMPT_BASE
+      return prompts.controller_base
   }
 }
 
@@ -529,6 +829,8 @@ async function executePendingApprovedTools(ctx: RunContext): Promise<void> {
     (i) => i.callId && !outputCallIds.has(i.callId),
   )
 
+  // Approved calls finish the step that proposed them, so they share one step budget
+  const deadline = stepDeadline(ctx.agent.config)
   for (const callItem of pendingCalls) {
     const toolName = callItem.name!
     const toolArgs: Record<string, unknown> = callItem.arguments
@@ -545,13 +847,17 @@ async function executePendingApprovedTools(ctx: RunContext): Promise<void> {
     })
 
     const startMs = Date.now()
+    const limits = toolCallLimits(ctx.agent.config.tool_execution_timeout_ms, ctx.signal, deadline)
     const result = await traceToolExecution(ctx, callId, toolName, toolArgs, () =>
-      ctx.tools.execute(toolName, toolArgs, {
-        agent_id: ctx.agent.id,
-        session_id: ctx.agent.sessionId,
-        signal: ctx.signal,
-        events: ctx.events,
-      }),
+      withToolProgress(ctx.events, ctx.agent, callId, toolName, (reportProgress) =>
+        ctx.tools.execute(toolName, toolArgs, {
+          agent_id: ctx.agent.id,
+          session_id: ctx.agent.sessionId,
+          ...limits,
+          events: ctx.events,
+          reportProgress,
+        }),
+      ),
       true,
     )
     const durationMs = Date.now() - startMs
@@ -559,6 +865,8 @@ async function executePendingApprovedTools(ctx: RunContext): Promise<void> {
     const outputStr = await materializeToolOutput(result, {
       sessionFilesRoot: ctx.sessionFilesRoot,
       inlineLimitBytes: ctx.inlineOutputLimitBytes,
+      sessionBudget: ctx.inlineOutputBudget,
+      redactor: ctx.redactor,
       sessionId: ctx.agent.sessionId,
       agentId: ctx.agent.id,
       callId,
@@ -590,7 +898,7 @@ async function executePendingApprovedTools(ctx: RunContext): Promise<void> {
       type: EVENT_TYPES.TOOL_COMPLETED,
       agent_id: ctx.agent.id,
       session_id: ctx.agent.sessionId,
-      payload: { callId, name: toolName, success: result.ok, output: outputStr, durationMs, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+      payload: { callId, name: toolName, success: result.ok, output: outputStr, durationMs, errorCode: classifyToolError(result, limits.signal), parentId: ctx.agent.parentId, depth: ctx.agent.depth },
       timestamp: Date.now(),
     })
   }
@@ -610,15 +918,8 @@ async function executeAction(
       return executeFlatStep(ctx, action, llmResponse)
 
     case 'complete':
-      // Save the completion message as an assistant item
-      await ctx.items.create({
-        agentId: ctx.agent.id,
-        type: 'message',
-        role: 'assistant',
-        content: action.message,
-        turnNumber: ctx.turnNumber,
-      })
-      return { type: 'complete', response: action.message }
+      // The completion message is saved with the agent's final state
+      return { type: 'complete', response: action.message, message: action.message }
 
     case 'guardrail_stop': {
       const msg = action.message ?? `Stopped: ${action.reason}`
@@ -629,10 +930,11 @@ async function executeAction(
         content: msg,
         turnNumber: ctx.turnNumber,
       })
-      const failed = failAgent(ctx.agent, `Guardrail stop: ${action.reason}`)
+      const failed = failAgent(ctx.agent, `Guardrail stop: ${action.reason}`, 'guardrail_stop')
       ctx.agent = await ctx.agents.update(ctx.agent.id, {
         status: 'failed',
-        error: `Guardrail stop: ${action.reason}`,
+        error: failed.error,
+        errorCode: failed.errorCode,
         completedAt: failed.completedAt,
       })
       return { type: 'complete', response: msg }
@@ -666,6 +968,7 @@ async function executeFlatStep(
   })
 
   let outcome: StepExecutionOutcome
+  ctx.stepDeadline = stepDeadline(ctx.agent.config)
 
   switch (stepType) {
     case 'tool': {
@@ -695,15 +998,8 @@ async function executeFlatStep(
     }
 
     case 'respond': {
-      // Assistant message — save and complete
-      await ctx.items.create({
-        agentId: ctx.agent.id,
-        type: 'message',
-        role: 'assistant',
-        content: action.message!,
-        turnNumber: ctx.turnNumber,
-      })
-      outcome = { type: 'complete', response: action.message! }
+      // Assistant message — saved with the agent's final state
+      outcome = { type: 'complete', response: action.message!, message: action.message! }
       break
     }
 
@@ -715,12 +1011,26 @@ async function executeFlatStep(
     default:
       throw new Error(`Cannot infer step type from next_step action fields`)
   }
+  ctx.stepDeadline = undefined
+  ctx.stepsCompleted++
 
   ctx.events.emit({
     type: EVENT_TYPES.STEP_COMPLETED,
     agent_id: ctx.agent.id,
     session_id: ctx.agent.sessionId,
-    payload: { stepType: stepType ?? 'unknown', turn: ctx.turnNumber, outcomeType: outcome.type, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+    payload: {
+      stepType: stepType ?? 'unknown',
+      turn: ctx.turnNumber,
+      outcomeType: outcome.type,
+      progress: runProgress(ctx.agent.plan, {
+        stepsCompleted: ctx.stepsCompleted,
+        startedAt: ctx.startedAt,
+        turnsUsed: ctx.turnNumber,
+        maxTurns: ctx.maxTurns,
+      }),
+      parentId: ctx.agent.parentId,
+      depth: ctx.agent.depth,
+    },
     timestamp: Date.now(),
   })
 
@@ -734,26 +1044,102 @@ async function executeFlatStep(
 const APPROVAL_PREFIX_SESSION = 'tool_approval_session:'
 const APPROVAL_PREFIX_GLOBAL = 'tool_approval_global:'
 
+type ApprovalResolution =
+  | { action: 'allow' | 'ask'; rule?: ApprovalRule; reason?: string }
+  | { action: 'deny'; rule: ApprovalRule }
+
 async function resolveRequiresApproval(
   ctx: RunContext,
   toolName: string,
-  defaultRequiresApproval: boolean,
-): Promise<boolean> {
+  args: Record<string, unknown>,
+  meta: ToolMetadata | undefined,
+): Promise<ApprovalResolution> {
   try {
-    // 1. Session-scoped override (highest priority)
+    // 1. Policy rules (first match wins)
+    const rules = parseApprovalRules(await ctx.preferences.get(APPROVAL_RULES_PREFERENCE_KEY))
+    const rule = matchApprovalRule(rules, toolName, args)
+    if (rule?.action === 'deny') return { action: 'deny', rule }
+    if (rule) return { action: rule.action, rule }
+
+    // 2. Session-scoped override
     const sessionKey = `${APPROVAL_PREFIX_SESSION}${ctx.agent.sessionId}:${toolName}`
     const sessionOverride = await ctx.preferences.get(sessionKey)
-    if (sessionOverride !== null) return sessionOverride === 'true'
+    if (sessionOverride !== null) return { action: sessionOverride === 'true' ? 'ask' : 'allow' }
 
-    // 2. Global override
+    // 3. Global override
     const globalKey = `${APPROVAL_PREFIX_GLOBAL}${toolName}`
     const globalOverride = await ctx.preferences.get(globalKey)
-    if (globalOverride !== null) return globalOverride === 'true'
+    if (globalOverride !== null) return { action: globalOverride === 'true' ? 'ask' : 'allow' }
+
+    // 4. Category policy, the tool's arg triggers, then category/tool defaults
+    const categoryPolicy = parseCategoryDefaults(await ctx.preferences.get(APPROVAL_CATEGORY_DEFAULTS_PREFERENCE_KEY))
+    const action = defaultApprovalAction(meta, categoryPolicy, args)
+    const trigger = action === 'ask' && meta?.approval_triggers ? matchApprovalTrigger(meta.approval_triggers, args) : null
+    return trigger ? { action, reason: trigger.reason } : { action }
   } catch {
     // Fall through to default on any error
   }
 
-  return defaultRequiresApproval
+  return { action: meta?.requires_approval ? 'ask' : 'allow' }
+}
+
+function approvalDescription(name: string, reason?: string): string {
+  return reason ? `Approve execution of ${name}? ${reason}` : `Approve execution of ${name}?`
+}
+
+/**
+ * Decisions made without prompting go into the approval history too: rule
+ * hits, and approval-gated tools let through by a remembered override.
+ */
+async function recordAutoDecision(
+  ctx: RunContext,
+  callId: string,
+  name: string,
+  args: Record<string, unknown>,
+  approval: ApprovalResolution,
+  defaultRequiresApproval: boolean,
+): Promise<void> {
+  if (approval.action === 'ask') return
+  if (approval.action === 'allow' && !approval.rule && !defaultRequiresApproval) return
+  await ctx.approvals?.record({
+    agent: ctx.agent,
+    callId,
+    toolName: name,
+    args,
+    outcome: approval.action === 'deny' ? 'auto_denied' : 'auto_approved',
+    ruleId: approval.rule?.id ?? null,
+  })
+}
+
+/** Record a call refused by a deny rule. The run continues so the model can take another route. */
+async function recordPolicyDenial(ctx: RunContext, callId: string, name: string, rule: ApprovalRule): Promise<void> {
+  await ctx.items.create({
+    agentId: ctx.agent.id,
+    type: 'function_call_output',
+    callId,
+    output: `Tool execution denied by approval policy${rule.description ? `: ${rule.description}` : ` (rule for ${rule.tool})`}`,
+    isError: true,
+    turnNumber: ctx.turnNumber,
+  })
+  ctx.events.emit({
+    type: EVENT_TYPES.TOOL_DENIED,
+    agent_id: ctx.agent.id,
+    session_id: ctx.agent.sessionId,
+    payload: { callId, name },
+    timestamp: Date.now(),
+  })
+}
+
+/** The tool's preview of a call awaiting approval (e.g. a diff), shown instead of raw args. */
+function previewToolCall(ctx: RunContext, callId: string, name: string, args: Record<string, unknown>): ToolPreview | undefined {
+  try {
+    const preview = ctx.tools.getPreview(name, args, { agent_id: ctx.agent.id, session_id: ctx.agent.sessionId, signal: ctx.signal })
+    // Previews are stored with the waiting state and shown to the user
+    return preview && ctx.redactor ? ctx.redactor.redactValue(preview) : preview
+  } catch (err) {
+    logger.warn({ err, tool: name, callId }, 'Tool preview failed')
+    return undefined
+  }
 }
 
 // ---------------------------------------------------------------------------
@@ -799,9 +1185,23 @@ async function executeTool(
   const lastOutputId = await getLastOutputId(ctx)
   const hydratedArgs = hydrateToolArgs(name, args, lastOutputId)
 
-  // Check if tool requires approval (with override resolution)
-  const needsApproval = await resolveRequiresApproval(ctx, name, meta?.requires_approval ?? false)
-  if (needsApproval) {
+  // Check if tool requires approval (policy rules, then overrides)
+  const approval = await resolveRequiresApproval(ctx, name, hydratedArgs, meta)
+  await recordAutoDecision(ctx, callId, name, hydratedArgs, approval, meta?.requires_approval ?? false)
+  if (approval.action === 'deny') {
+    await ctx.items.create({
+      agentId: ctx.agent.id,
+      type: 'function_call',
+      callId,
+      name,
+      arguments: JSON.stringify(hydratedArgs),
+      saveOutput: save ?? null,
+      turnNumber: ctx.turnNumber,
+    })
+    await recordPolicyDenial(ctx, callId, name, approval.rule)
+    return { type: 'continue' }
+  }
+  if (approval.action === 'ask') {
     // Save the function_call item
     await ctx.items.create({
       agentId: ctx.agent.id,
@@ -819,7 +1219,7 @@ async function executeTool(
       type: 'approval',
       name,
       args: hydratedArgs,
-      description: `Approve execution of ${name}?`,
+      description: approvalDescription(name, approval.reason),
     }
 
     const waiting = waitForMany(ctx.agent, [waitEntry])
@@ -832,7 +1232,14 @@ async function executeTool(
       type: EVENT_TYPES.TOOL_PROPOSED,
       agent_id: ctx.agent.id,
       session_id: ctx.agent.sessionId,
-      payload: { callId, name, args: hydratedArgs, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+      payload: {
+        callId,
+        name,
+        args: hydratedArgs,
+        preview: previewToolCall(ctx, callId, name, hydratedArgs),
+        parentId: ctx.agent.parentId,
+        depth: ctx.agent.depth,
+      },
       timestamp: Date.now(),
     })
 
@@ -868,19 +1275,25 @@ async function executeTool(
   })
 
   const startMs = Date.now()
+  const limits = toolCallLimits(ctx.agent.config.tool_execution_timeout_ms, ctx.signal, ctx.stepDeadline)
   const result = await traceToolExecution(ctx, callId, name, hydratedArgs, () =>
-    ctx.tools.execute(name, hydratedArgs, {
-      agent_id: ctx.agent.id,
-      session_id: ctx.agent.sessionId,
-      signal: ctx.signal,
-      events: ctx.events,
-    }),
+    withToolProgress(ctx.events, ctx.agent, callId, name, (reportProgress) =>
+      ctx.tools.execute(name, hydratedArgs, {
+        agent_id: ctx.agent.id,
+        session_id: ctx.agent.sessionId,
+        ...limits,
+        events: ctx.events,
+        reportProgress,
+      }),
+    ),
   )
   const durationMs = Date.now() - startMs
 
   const outputStr = await materializeToolOutput(result, {
     sessionFilesRoot: ctx.sessionFilesRoot,
     inlineLimitBytes: ctx.inlineOutputLimitBytes,
+    sessionBudget: ctx.inlineOutputBudget,
+    redactor: ctx.redactor,
     sessionId: ctx.agent.sessionId,
     agentId: ctx.agent.id,
     callId,
@@ -913,7 +1326,7 @@ async function executeTool(
     type: EVENT_TYPES.TOOL_COMPLETED,
     agent_id: ctx.agent.id,
     session_id: ctx.agent.sessionId,
-    payload: { callId, name, success: result.ok, output: outputStr, durationMs, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+    payload: { callId, name, success: result.ok, output: outputStr, durationMs, errorCode: classifyToolError(result, limits.signal), parentId: ctx.agent.parentId, depth: ctx.agent.depth },
     timestamp: Date.now(),
   })
 
@@ -930,38 +1343,52 @@ async function executeToolBatch(
 ): Promise<StepExecutionOutcome> {
   const lastOutputId = await getLastOutputId(ctx)
 
-  // Separate orchestrator-intercepted tools, tools needing approval, and directly executable ones
+  // Hydrate args once so approval rules see what the tool will receive
+  const hydratedMap = new Map<string, Record<string, unknown>>()
+  for (const spec of specs) {
+    hydratedMap.set(spec.callId, hydrateToolArgs(spec.tool, spec.args, lastOutputId))
+  }
+
+  // Separate orchestrator-intercepted tools, denied tools, tools needing approval, and directly executable ones
   const interceptedSpecs: Array<ToolCallSpec & { callId: string }> = []
-  const needsApproval: Array<ToolCallSpec & { callId: string }> = []
+  const denied: Array<{ spec: ToolCallSpec & { callId: string }; rule: ApprovalRule }> = []
+  const needsApproval: Array<ToolCallSpec & { callId: string; reason?: string }> = []
   const canExecute: Array<ToolCallSpec & { callId: string }> = []
 
   for (const spec of specs) {
     const meta = ctx.tools.getMetadata(spec.tool)
     if (meta?.orchestrator_intercept) {
       interceptedSpecs.push(spec)
-    } else if (await resolveRequiresApproval(ctx, spec.tool, meta?.requires_approval ?? false)) {
-      needsApproval.push(spec)
+      continue
+    }
+    const approval = await resolveRequiresApproval(ctx, spec.tool, hydratedMap.get(spec.callId)!, meta)
+    await recordAutoDecision(ctx, spec.callId, spec.tool, hydratedMap.get(spec.callId)!, approval, meta?.requires_approval ?? false)
+    if (approval.action === 'deny') {
+      denied.push({ spec, rule: approval.rule })
+    } else if (approval.action === 'ask') {
+      needsApproval.push({ ...spec, reason: approval.reason })
     } else {
       canExecute.push(spec)
     }
   }
 
-  // Hydrate args once and save function_call items for ALL tools
-  const hydratedMap = new Map<string, Record<string, unknown>>()
+  // Save function_call items for ALL tools
   for (const spec of specs) {
-    const hydrated = hydrateToolArgs(spec.tool, spec.args, lastOutputId)
-    hydratedMap.set(spec.callId, hydrated)
     await ctx.items.create({
       agentId: ctx.agent.id,
       type: 'function_call',
       callId: spec.callId,
       name: spec.tool,
-      arguments: JSON.stringify(hydrated),
+      arguments: JSON.stringify(hydratedMap.get(spec.callId)),
       saveOutput: spec.save ?? null,
       turnNumber: ctx.turnNumber,
     })
   }
 
+  for (const { spec, rule } of denied) {
+    await recordPolicyDenial(ctx, spec.callId, spec.tool, rule)
+  }
+
   // Execute delegate fan-out in parallel, then run other intercepted tools
   // sequentially. Workflow-style intercepted tools can have external side
   // effects and waiting semantics, so they keep the conservative path.
@@ -1035,18 +1462,29 @@ async function executeToolBatch(
     }
 
     const startedAt = new Map<string, number>()
-    const settled = await Promise.allSettled(toolCalls.map(async (tc) => {
+    const callSignals = new Map<string, AbortSignal>()
+    const runCall = async (tc: ToolCall) => {
       startedAt.set(tc.call_id, Date.now())
+      // The timeout starts when the call runs, not while it waits for a pool slot
+      const limits = toolCallLimits(ctx.agent.config.tool_execution_timeout_ms, ctx.signal, ctx.stepDeadline)
+      callSignals.set(tc.call_id, limits.signal)
       const result = await traceToolExecution(ctx, tc.call_id, tc.name, tc.args, () =>
-        ctx.tools.execute(tc.name, tc.args, {
-          agent_id: ctx.agent.id,
-          session_id: ctx.agent.sessionId,
-          signal: ctx.signal,
-          events: ctx.events,
-        }),
+        withToolProgress(ctx.events, ctx.agent, tc.call_id, tc.name, (reportProgress) =>
+          ctx.tools.execute(tc.name, tc.args, {
+            agent_id: ctx.agent.id,
+            session_id: ctx.agent.sessionId,
+            ...limits,
+            events: ctx.events,
+            reportProgress,
+          }),
+        ),
       )
       return { call_id: tc.call_id, ...result }
-    }))
+    }
+    // Calls over the pool's limits wait for a slot; their duration starts when they run
+    const settled = await Promise.allSettled(toolCalls.map((tc) =>
+      ctx.toolPool ? ctx.toolPool.run(ctx.agent.sessionId, () => runCall(tc), ctx.signal) : runCall(tc),
+    ))
 
     const results = settled.map((settledResult, index) => {
       if (settledResult.status === 'fulfilled') return settledResult.value
@@ -1063,6 +1501,8 @@ async function executeToolBatch(
       const outputStr = await materializeToolOutput(res, {
         sessionFilesRoot: ctx.sessionFilesRoot,
         inlineLimitBytes: ctx.inlineOutputLimitBytes,
+        sessionBudget: ctx.inlineOutputBudget,
+        redactor: ctx.redactor,
         sessionId: ctx.agent.sessionId,
         agentId: ctx.agent.id,
         callId: res.call_id,
@@ -1092,7 +1532,7 @@ async function executeToolBatch(
         type: EVENT_TYPES.TOOL_COMPLETED,
         agent_id: ctx.agent.id,
         session_id: ctx.agent.sessionId,
-        payload: { callId: res.call_id, name: spec?.tool ?? 'unknown', success: res.ok, output: outputStr, durationMs, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+        payload: { callId: res.call_id, name: spec?.tool ?? 'unknown', success: res.ok, output: outputStr, durationMs, errorCode: classifyToolError(res, callSignals.get(res.call_id)), parentId: ctx.agent.parentId, depth: ctx.agent.depth },
         timestamp: Date.now(),
       })
     }
@@ -1100,20 +1540,44 @@ async function executeToolBatch(
 
   // If any tools need approval, park the agent
   if (needsApproval.length > 0) {
-    const waitEntries: WaitingFor[] = needsApproval.map((spec) => ({
-      callId: spec.callId,
+    const proposals = needsApproval.map((spec) => {
+      const args = hydratedMap.get(spec.callId) ?? spec.args
+      return {
+        callId: spec.callId,
+        name: spec.tool,
+        args,
+        reason: spec.reason,
+        preview: previewToolCall(ctx, spec.callId, spec.tool, args),
+      }
+    })
+    // Too many calls, or too much change, to approve one prompt at a time
+    const batch = escalateApprovalBatch(proposals, ctx.approvalEscalation) ?? undefined
+
+    const waitEntries: WaitingFor[] = proposals.map((proposal) => ({
+      callId: proposal.callId,
       type: 'approval' as const,
-      name: spec.tool,
-      args: hydratedMap.get(spec.callId) ?? spec.args,
-      description: `Approve execution of ${spec.tool}?`,
+      name: proposal.name,
+      args: proposal.args,
+      description: batch
+        ? `Approve the entire batch of ${batch.callIds.length} calls?`
+        : approvalDescription(proposal.name, proposal.reason),
+      ...(batch ? { batch } : {}),
     }))
 
-    for (const entry of waitEntries) {
+    for (const proposal of proposals) {
       ctx.events.emit({
         type: EVENT_TYPES.TOOL_PROPOSED,
         agent_id: ctx.agent.id,
         session_id: ctx.agent.sessionId,
-        payload: { callId: entry.callId, name: entry.name, args: entry.args ?? {}, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+        payload: {
+          callId: proposal.callId,
+          name: proposal.name,
+          args: proposal.args,
+          preview: proposal.preview,
+          ...(batch ? { batch } : {}),
+          parentId: ctx.agent.parentId,
+          depth: ctx.agent.depth,
+        },
         timestamp: Date.now(),
       })
     }
@@ -1182,6 +1646,58 @@ async function executeAskUser(
   return { type: 'waiting', waiting_for: waiting.waitingFor }
 }
 
+// ---------------------------------------------------------------------------
+// Guardrail stops
+// ---------------------------------------------------------------------------
+
+/**
+ * A recoverable stop pauses the run on a question to the user, and their
+ * reply resumes it; a fatal one ends it. Either way the reason is saved as
+ * the assistant's message, so it shows in the conversation.
+ */
+async function stopForGuardrail(ctx: RunContext, stop: GuardrailTrigger): Promise<RunResult> {
+  const agentId = ctx.agent.id
+  const lineage = { parentId: ctx.agent.parentId, depth: ctx.agent.depth }
+  ctx.events.emit({
+    type: EVENT_TYPES.GUARDRAIL_TRIGGERED,
+    agent_id: agentId,
+    session_id: ctx.agent.sessionId,
+    payload: { ...stop, turn: ctx.turnNumber, ...lineage },
+    timestamp: Date.now(),
+  })
+
+  if (stop.severity === 'recoverable') {
+    const outcome = await executeAskUser(ctx, `Paused by a guardrail: ${stop.reason}. Reply to continue.`)
+    await ctx.agents.update(agentId, { turnCount: ctx.turnNumber })
+    return { agentId, status: 'waiting', waitingFor: outcome.type === 'waiting' ? outcome.waiting_for : [], turnCount: ctx.turnNumber }
+  }
+
+  const error = `Guardrail stop: ${stop.reason}`
+  await ctx.items.create({
+    agentId,
+    type: 'message',
+    role: 'assistant',
+    content: `Stopped by a guardrail: ${stop.reason}.`,
+    turnNumber: ctx.turnNumber,
+  })
+  const failed = failAgent(ctx.agent, error, 'guardrail_stop')
+  ctx.agent = await ctx.agents.update(agentId, {
+    status: 'failed',
+    error,
+    errorCode: 'guardrail_stop',
+    turnCount: ctx.turnNumber,
+    completedAt: failed.completedAt,
+  })
+  ctx.events.emit({
+    type: EVENT_TYPES.AGENT_FAILED,
+    agent_id: agentId,
+    session_id: ctx.agent.sessionId,
+    payload: { error, errorCode: 'guardrail_stop', ...lineage },
+    timestamp: Date.now(),
+  })
+  return { agentId, status: 'failed', error, errorCode: 'guardrail_stop', turnCount: ctx.turnNumber }
+}
+
 // ---------------------------------------------------------------------------
 // Delegation — spawn and run a child agent
 // ---------------------------------------------------------------------------
@@ -1263,6 +1779,7 @@ export async function handleDelegation(
       model: childModel,
       provider: childProvider,
       ...(definition?.max_output_tokens ? { max_output_tokens: definition.max_output_tokens } : {}),
+      ...(definition?.step_timeout_ms ? { step_timeout_ms: definition.step_timeout_ms } : {}),
       ...(definition?.system_prompt ? { system_prompt: definition.system_prompt } : {}),
       ...(definition?.tools ? { allowed_tools: definition.tools } : {}),
     },
@@ -1303,6 +1820,8 @@ export async function handleDelegation(
     const output = await materializeTextOutput(rawOutput, {
       sessionFilesRoot: ctx.sessionFilesRoot,
       inlineLimitBytes: ctx.inlineOutputLimitBytes,
+      sessionBudget: ctx.inlineOutputBudget,
+      redactor: ctx.redactor,
       sessionId: ctx.agent.sessionId,
       agentId: ctx.agent.id,
       callId,
@@ -1368,7 +1887,7 @@ export async function handleDelegation(
     type: EVENT_TYPES.TOOL_COMPLETED,
     agent_id: ctx.agent.id,
     session_id: ctx.agent.sessionId,
-    payload: { callId, name: 'delegate', success: false, output: `Delegation failed: ${errorMsg}`, durationMs: Date.now() - startMs, parentId: ctx.agent.parentId, depth: ctx.agent.depth },
+    payload: { callId, name: 'delegate', success: false, output: `Delegation failed: ${errorMsg}`, durationMs: Date.now() - startMs, errorCode: 'tool_failed', parentId: ctx.agent.parentId, depth: ctx.agent.depth },
     timestamp: Date.now(),
   })
   return { type: 'continue' }
@@ -1391,8 +1910,27 @@ function buildDepsFromContext(ctx: RunContext): OrchestratorDeps {
     agentDefinitions: ctx.agentDefinitions,
     sessionFilesRoot: ctx.sessionFilesRoot,
     inlineOutputLimitBytes: ctx.inlineOutputLimitBytes,
+    inlineOutputBudget: ctx.inlineOutputBudget,
+    redactor: ctx.redactor,
+    privacy: ctx.privacy,
+    connectivity: ctx.connectivity,
+    verifier: ctx.verifier,
+    guardrails: ctx.guardrails,
     interceptHandlers: ctx.interceptHandlers,
     observability: ctx.observability,
+    usage: ctx.usage,
+    spendCaps: ctx.spendCaps,
+    traces: ctx.traces,
+    audit: ctx.audit,
+    metrics: ctx.metrics,
+    approvals: ctx.approvals,
+    approvalEscalation: ctx.approvalEscalation,
+    attachments: ctx.attachments,
+    historyWindow: ctx.historyWindow,
+    instructions: ctx.instructions,
+    prompts: ctx.prompts,
+    toolPool: ctx.toolPool,
+    agentAbortControllers: ctx.agentAbortControllers,
   }
 }
 
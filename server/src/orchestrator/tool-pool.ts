@@ -0,0 +1,99 @@
+export interface ToolPoolOptions {
+  /** Tool calls from parallel batches running at once, across all sessions. */
+  size: number
+  /** Of those, how many one session may hold, so a single wide batch cannot starve the others. */
+  perSession: number
+}
+
+interface Waiter {
+  sessionId: string
+  start: () => void
+}
+
+/**
+ * Caps how many calls from parallel tool batches run at once. Calls over the
+ * limit queue in arrival order; a queued call whose session is at its own
+ * limit lets later calls from other sessions go first.
+ */
+export class ToolPool {
+  private readonly size: number
+  private readonly perSession: number
+  private readonly running = new Map<string, number>()
+  private readonly waiting: Waiter[] = []
+  private activeCount = 0
+
+  constructor(options: ToolPoolOptions) {
+    this.size = Math.max(1, Math.floor(options.size))
+    this.perSession = Math.max(1, Math.min(Math.floor(options.perSession), this.size))
+  }
+
+  get active(): number {
+    return this.activeCount
+  }
+
+  get queued(): number {
+    return this.waiting.length
+  }
+
+  /** Runs `task` once a slot is free; rejects with the signal's reason if it aborts while queued. */
+  async run<T>(sessionId: string, task: () => Promise<T>, signal?: AbortSignal): Promise<T> {
+    await this.acquire(sessionId, signal)
+    try {
+      return await task()
+    } finally {
+      this.release(sessionId)
+    }
+  }
+
+  private acquire(sessionId: string, signal?: AbortSignal): Promise<void> {
+    if (signal?.aborted) return Promise.reject(signal.reason)
+    // Anyone still queued is blocked by their own session's limit, so a call with room can go ahead
+    if (this.hasRoom(sessionId)) {
+      this.take(sessionId)
+      return Promise.resolve()
+    }
+    return new Promise<void>((resolve, reject) => {
+      const abort = () => {
+        const index = this.waiting.indexOf(waiter)
+        if (index !== -1) this.waiting.splice(index, 1)
+        reject(signal!.reason)
+      }
+      const waiter: Waiter = {
+        sessionId,
+        start: () => {
+          signal?.removeEventListener('abort', abort)
+          resolve()
+        },
+      }
+      this.waiting.push(waiter)
+      signal?.addEventListener('abort', abort, { once: true })
+    })
+  }
+
+  private release(sessionId: string): void {
+    this.activeCount--
+    const count = (this.running.get(sessionId) ?? 1) - 1
+    if (count > 0) this.running.set(sessionId, count)
+    else this.running.delete(sessionId)
+
+    for (let i = 0; i < this.waiting.length && this.activeCount < this.size; ) {
+      const waiter = this.waiting[i]
+      if (!this.hasRoom(waiter.sessionId)) {
+        i++
+        continue
+      }
+      this.waiting.splice(i, 1)
+      this.take(waiter.sessionId)
+      waiter.start()
+    }
+  }
+
+  private hasRoom(sessionId: string): boolean {
+    return this.activeCount < this.size && (this.running.get(sessionId) ?? 0) < this.perSession
+  }
+
+  private take(sessionId: string): void {
+    this.activeCount++
+    this.running.set(sessionId, (this.running.get(sessionId) ?? 0) + 1)
+  }
+}
@@ -1,10 +1,28 @@
-import type { Agent, WaitingFor } from '../domain/types.js'
+import type { Agent, AgentErrorCode, WaitingFor } from '../domain/types.js'
 import type { LLMProvider, ProviderRegistry } from '../providers/types.js'
 import type { ToolExecutor } from '../tools/types.js'
 import type { AgentRepository, ItemRepository, ToolOutputRepository, PreferenceRepository } from '../repositories/types.js'
-import type { EventSink } from '../events/types.js'
+import type { EventSink, ResponseVerification } from '../events/types.js'
 import type { AgentDefinitionRegistry } from '../agents/registry.js'
 import type { LLMObservability } from '../observability/types.js'
+import type { UsageSink } from '../usage/tracker.js'
+import type { SpendCapGuard } from '../usage/budget.js'
+import type { TraceSink } from '../observability/debug-traces.js'
+import type { AttachmentStore } from '../lib/attachment-store.js'
+import type { ApprovalSink } from './approval-history.js'
+import type { ApprovalEscalation } from './approval-batch.js'
+import type { UserProfile } from './user-profile.js'
+import type { InstructionFile } from '../services/instruction-files.js'
+import type { ControllerPrompts } from './prompts.js'
+import type { ToolPool } from './tool-pool.js'
+import type { RunScheduler } from './run-scheduler.js'
+import type { InlineOutputBudget } from './output.js'
+import type { SecretRedactor } from '../lib/secrets.js'
+import type { PrivacyVault } from './privacy.js'
+import type { ConnectivityMonitor } from '../lib/connectivity.js'
+import type { ConversationMode } from './chat-mode.js'
+import type { ResponseVerifier } from './verifier.js'
+import type { GuardrailEngine } from './guardrails.js'
 
 export type ControllerAction =
   | { action: 'next_step'; thinking?: unknown; step_type?: string; tool?: string; tools?: ToolCallSpec[]; args?: Record<string, unknown>; message?: string; question?: string; context?: string; save?: boolean }
@@ -20,7 +38,8 @@ export interface ToolCallSpec {
 
 export type StepExecutionOutcome =
   | { type: 'continue' }
-  | { type: 'complete'; response: string }
+  /** `message` is the closing assistant message, saved together with the agent's final state. */
+  | { type: 'complete'; response: string; message?: string }
   | { type: 'waiting'; waiting_for: WaitingFor[] }
 
 /**
@@ -54,10 +73,70 @@ export interface OrchestratorDeps {
   sessionFilesRoot: string
   /** Inline output limit before output is replaced with an artifact reference. */
   inlineOutputLimitBytes?: number
+  /** Session-wide total of inline tool output; past it, outputs are saved as artifacts. */
+  inlineOutputBudget?: InlineOutputBudget
+  /** Strips secrets from tool output before it enters history, and from approval previews. */
+  redactor?: SecretRedactor
+  /** Pseudonymizes personal data in requests for conversations in privacy mode. */
+  privacy?: PrivacyVault
+  /** Offline, runs on remote providers fail fast or move to the configured local model. */
+  connectivity?: ConnectivityMonitor
+  /** Checks drafted final responses of root runs against the turn's persisted tool outputs. */
+  verifier?: ResponseVerifier
+  /** Configured guardrails, checked every turn; they pause or end runs that break them. */
+  guardrails?: GuardrailEngine
   /** Pluggable intercept handlers — keyed by tool name (e.g. 'delegate', 'workflow.run'). */
   interceptHandlers?: Map<string, InterceptHandler>
   /** Optional LLM observability sink. No-op when disabled. */
   observability?: LLMObservability
+  /** Token/cost accounting for each LLM call. */
+  usage?: UsageSink
+  /** Per-provider spending caps; may switch the run to a fallback model between calls. */
+  spendCaps?: SpendCapGuard
+  /** Local request/response capture, gated by the debug_traces setting. */
+  traces?: TraceSink
+  /** Append-only audit trail of LLM call metadata, gated by the audit_log setting. */
+  audit?: TraceSink
+  /** In-process latency and token metrics. */
+  metrics?: TraceSink
+  /** Outcome of every approval-gated tool call, for the approval history. */
+  approvals?: ApprovalSink
+  /** When a step's approvals are only granted as one batch. Unset never escalates. */
+  approvalEscalation?: ApprovalEscalation
+  /** Loads attachment payloads back into history before each LLM call. */
+  attachments?: AttachmentStore
+  /** How much history each controller turn loads. Unset loads all of it. */
+  historyWindow?: HistoryWindow
+  /** ASSISTANT.md instructions for the session, read before each controller turn. */
+  instructions?: InstructionSource
+  /** Stored controller prompts, read once at run start. Unset runs use the built-in defaults. */
+  prompts?: ControllerPromptSource
+  /** Bounds how many calls of parallel tool batches run at once. Unset runs a batch all at once. */
+  toolPool?: ToolPool
+  /**
+   * Queues runs over the global limit and serializes runs of one session.
+   * Not carried into delegated or workflow runs, which execute inside their
+   * parent's slot.
+   */
+  runScheduler?: RunScheduler
+  /** Abort handles for agent work in flight, by agent id; cancelling an agent aborts its entry. */
+  agentAbortControllers?: Map<string, AbortController>
+}
+
+export interface HistoryWindow {
+  /** Only items from this many user messages back are sent; 0 sends the whole history. */
+  userTurns: number
+  /** Attachment payloads are loaded only this many user messages back; 0 loads them all. */
+  attachmentUserTurns: number
+}
+
+export interface InstructionSource {
+  forSession(sessionId: string): Promise<InstructionFile[]>
+}
+
+export interface ControllerPromptSource {
+  /** The controller prompts a run of the session starts with; any the store lacks are the built-in defaults. */
+  active(sessionId?: string): Promise<ControllerPrompts>
 }
 
 export interface RunContext {
@@ -65,17 +144,52 @@ export interface RunContext {
   readonly items: ItemRepository
   readonly toolOutputs: ToolOutputRepository
   readonly preferences: PreferenceRepository
-  readonly provider: LLMProvider
+  /** Replaced when a spending cap switches the run to its fallback model. */
+  provider: LLMProvider
   readonly providers?: ProviderRegistry
   readonly tools: ToolExecutor
   readonly events: EventSink
   readonly agentDefinitions: AgentDefinitionRegistry
   readonly sessionFilesRoot: string
   readonly inlineOutputLimitBytes?: number
+  readonly inlineOutputBudget?: InlineOutputBudget
+  readonly redactor?: SecretRedactor
+  readonly privacy?: PrivacyVault
+  readonly connectivity?: ConnectivityMonitor
+  readonly verifier?: ResponseVerifier
+  readonly guardrails?: GuardrailEngine
   readonly interceptHandlers?: Map<string, InterceptHandler>
   readonly observability?: LLMObservability
+  readonly usage?: UsageSink
+  readonly spendCaps?: SpendCapGuard
+  readonly traces?: TraceSink
+  readonly audit?: TraceSink
+  readonly metrics?: TraceSink
+  readonly approvals?: ApprovalSink
+  readonly approvalEscalation?: ApprovalEscalation
+  readonly attachments?: AttachmentStore
+  readonly historyWindow?: HistoryWindow
+  readonly instructions?: InstructionSource
+  readonly prompts?: ControllerPromptSource
+  readonly toolPool?: ToolPool
+  readonly agentAbortControllers?: Map<string, AbortController>
+  /** The user_profile preference as it was when the run started. */
+  readonly userProfile?: UserProfile | null
+  /** The conversation's or profile's reply language as it was when the run started. */
+  readonly responseLanguage?: string | null
+  /** Controller prompts as they were when the run started; unset means the defaults. */
+  readonly controllerPrompts?: ControllerPrompts
+  /** Answer with one direct provider reply instead of the controller loop. */
+  readonly chatMode?: boolean
   agent: Agent
   turnNumber: number
+  /** Turn limit of this run. */
+  readonly maxTurns: number
+  readonly startedAt: number
+  /** Steps this run has finished, for the progress sent with step:completed. */
+  stepsCompleted: number
+  /** When the current step's tool calls must be done by; set while a step executes. */
+  stepDeadline?: number
   signal: AbortSignal
   stream: boolean
 }
@@ -84,6 +198,8 @@ export interface RunOptions {
   signal?: AbortSignal
   maxTurns?: number
   stream?: boolean
+  /** Overrides the conversation's mode for this run; sub-agents always run as agents. */
+  mode?: ConversationMode
 }
 
 export interface RunResult {
@@ -91,6 +207,8 @@ export interface RunResult {
   status: string
   result?: string
   error?: string
+  errorCode?: AgentErrorCode
   waitingFor?: WaitingFor[]
   turnCount: number
+  verification?: ResponseVerification
 }
@@ -0,0 +1,78 @@
+import { logger } from '../lib/logger.js'
+
+export const USER_PROFILE_PREFERENCE_KEY = 'user_profile'
+
+const MAX_FIELD_CHARS = 200
+const MAX_WRITING_STYLE_CHARS = 1000
+
+/** What the user told us about themselves; every field is optional. */
+export interface UserProfile {
+  name: string | null
+  /** IANA time zone, e.g. `Europe/Warsaw`. */
+  timezone: string | null
+  role: string | null
+  /** Free-form preferences for tone, length and formatting of replies. */
+  writingStyle: string | null
+  /** Language replies and titles are written in, e.g. `Polish`; conversations can override it. */
+  language: string | null
+}
+
+export const EMPTY_USER_PROFILE: UserProfile = {
+  name: null,
+  timezone: null,
+  role: null,
+  writingStyle: null,
+  language: null,
+}
+
+export function parseUserProfile(raw: string | null): UserProfile {
+  if (!raw) return { ...EMPTY_USER_PROFILE }
+  try {
+    return normalizeUserProfile(JSON.parse(raw) as Partial<UserProfile>)
+  } catch {
+    logger.warn('Ignoring malformed user_profile preference')
+    return { ...EMPTY_USER_PROFILE }
+  }
+}
+
+/** Validate user input; throws with a message suitable for a 400. Blank fields become null. */
+export function normalizeUserProfile(input: Partial<UserProfile>): UserProfile {
+  const field = (value: unknown, name: string, max: number): string | null => {
+    if (value === undefined || value === null) return null
+    if (typeof value !== 'string') throw new Error(`${name} must be a string`)
+    const trimmed = value.trim()
+    if (trimmed.length > max) throw new Error(`${name} is too long (${max} characters at most)`)
+    return trimmed || null
+  }
+  const timezone = field(input.timezone, 'timezone', MAX_FIELD_CHARS)
+  if (timezone) {
+    try {
+      new Intl.DateTimeFormat('en-US', { timeZone: timezone })
+    } catch {
+      throw new Error('timezone must be an IANA time zone such as Europe/Warsaw')
+    }
+  }
+  return {
+    name: field(input.name, 'name', MAX_FIELD_CHARS),
+    timezone,
+    role: field(input.role, 'role', MAX_FIELD_CHARS),
+    writingStyle: field(input.writingStyle, 'writingStyle', MAX_WRITING_STYLE_CHARS),
+    language: field(input.language, 'language', MAX_FIELD_CHARS),
+  }
+}
+
+/**
+ * The profile as a system prompt section, or null when nothing is set. The
+ * text only changes when the profile does, so it can sit in the cached
+ * prompt prefix; the current time is deliberately left out.
+ */
+export function renderUserProfile(profile: UserProfile | null | undefined): string | null {
+  if (!profile) return null
+  const lines = [
+    profile.name && `- Name: ${profile.name}`,
+    profile.role && `- Role: ${profile.role}`,
+    profile.timezone && `- Time zone: ${profile.timezone}`,
+    profile.writingStyle && `- Writing style preferences: ${profile.writingStyle}`,
+  ].filter((line): line is string => Boolean(line))
+  return lines.length > 0 ? lines.join('\n') : null
+}
@@ -0,0 +1,144 @@
+import type { ToolOutput } from '../domain/types.js'
+import type { ResponseVerification } from '../events/types.js'
+import { logger } from '../lib/logger.js'
+import { splitModelId } from '../lib/model.js'
+import type { LLMMessage, LLMRequest, LLMToolCall } from '../providers/types.js'
+import { reidentifyResponse } from './privacy.js'
+import type { RunContext } from './types.js'
+
+const EXTRACT_TOOL = 'tool_outputs.extract'
+/** Lookup rounds before the verifier must give its verdict. */
+const MAX_ROUNDS = 4
+const MAX_LISTED_OUTPUTS = 20
+/** Text outputs can't be addressed by path, so short ones are shown whole. */
+const MAX_INLINE_TEXT_CHARS = 2000
+const MAX_LOOKUP_CHARS = 4000
+const CONFIDENCE_LEVELS = ['high', 'medium', 'low'] as const
+
+const VERIFIER_PROMPT = `You check an assistant's draft reply against the tool outputs it was based on, before the reply is sent.
+
+Look up the facts the draft states (numbers, names, dates, quotes, items in lists) with ${EXTRACT_TOOL}, using the output ids and shapes listed. A few targeted lookups are enough; do not read everything.
+Ignore opinions, advice and general knowledge the outputs would not contain.
+
+When done, reply with JSON only:
+{"confidence": "high" | "medium" | "low", "unsupported_claims": ["<claim as stated in the draft>", ...]}
+- high: every fact you checked is backed by the outputs
+- medium: some details could not be confirmed
+- low: a claim contradicts the outputs, or key facts are missing from them`
+
+const VERDICT_NOW = 'Stop looking things up and give your verdict as JSON now.'
+
+export type VerifierContext = Pick<
+  RunContext,
+  'agent' | 'provider' | 'providers' | 'tools' | 'toolOutputs' | 'usage' | 'privacy' | 'redactor' | 'signal'
+>
+
+/**
+ * A second look at a drafted final response before it is saved: a model
+ * (VERIFIER_MODEL, or the run's own) checks its facts against the tool
+ * outputs persisted during the turn, through targeted extract lookups, and
+ * reports a confidence level with the claims it could not back up.
+ */
+export class ResponseVerifier {
+  constructor(private readonly options: { model?: string } = {}) {}
+
+  /** Null when the turn persisted no outputs to check against, or the check itself failed. */
+  async verify(ctx: VerifierContext, draft: string, since: number): Promise<ResponseVerification | null> {
+    if (!draft.trim()) return null
+    const outputs = (await ctx.toolOutputs.listByAgent(ctx.agent.id)).filter((output) => output.createdAt >= since)
+    if (outputs.length === 0) return null
+    try {
+      return await this.check(ctx, draft, outputs.slice(-MAX_LISTED_OUTPUTS))
+    } catch (err) {
+      if (ctx.signal.aborted) throw err
+      // The answer still goes out, just without an annotation
+      logger.warn({ err, agentId: ctx.agent.id }, 'Response verification failed')
+      return null
+    }
+  }
+
+  private async check(ctx: VerifierContext, draft: string, outputs: ToolOutput[]): Promise<ResponseVerification | null> {
+    const own = this.options.model && ctx.providers ? { modelId: this.options.model, providers: ctx.providers } : null
+    const modelId = own?.modelId ?? ctx.agent.config.model
+    const provider = own ? own.providers.resolve(own.modelId) : ctx.provider
+    const { provider: providerName, model } = splitModelId(modelId)
+    const extract = ctx.tools.getMetadata(EXTRACT_TOOL)
+    const tools = extract ? [{ name: extract.name, description: extract.description, parameters: extract.parameters }] : undefined
+    const mapping = ctx.privacy ? await ctx.privacy.forSession(ctx.agent.sessionId) : null
+
+    const messages: LLMMessage[] = [
+      { role: 'system', content: VERIFIER_PROMPT },
+      { role: 'user', content: `Draft reply:\n${draft}\n\nTool outputs of this turn:\n${outputs.map(describeOutput).join('\n')}` },
+    ]
+    for (let round = 1; round <= MAX_ROUNDS; round++) {
+      if (round === MAX_ROUNDS && messages.length > 2) messages.push({ role: 'user', content: VERDICT_NOW })
+      const request: LLMRequest = { model, messages, tools, temperature: 0, max_tokens: 1000, signal: ctx.signal }
+      // Same boundary as the run itself: in privacy mode the verifier only sees placeholders
+      const sent = mapping && ctx.privacy ? await ctx.privacy.pseudonymizeRequest(ctx.agent.sessionId, mapping, request) : request
+      const raw = await provider.generate(sent)
+      await ctx.usage?.record({ agent: ctx.agent, provider: providerName, model, usage: raw.usage, phase: 'verifier', request: sent })
+      const response = mapping ? reidentifyResponse(mapping, raw) : raw
+
+      if (!response.tool_calls?.length) return parseVerdict(response.content, outputs.length, modelId)
+      if (round === MAX_ROUNDS) break
+      messages.push({ role: 'assistant', content: response.content ?? '', tool_calls: response.tool_calls })
+      for (const call of response.tool_calls) {
+        messages.push({ role: 'tool', tool_call_id: call.call_id, content: await lookUp(ctx, outputs, call) })
+      }
+    }
+    logger.warn({ agentId: ctx.agent.id }, 'Verifier kept looking things up without a verdict')
+    return null
+  }
+}
+
+/** Runs one extract call, limited to the outputs under review; the verifier reads, it never acts. */
+async function lookUp(ctx: VerifierContext, outputs: ToolOutput[], call: LLMToolCall): Promise<string> {
+  if (call.name !== EXTRACT_TOOL) return `Error: only ${EXTRACT_TOOL} is available`
+  if (!outputs.some((output) => output.id === call.arguments.id)) return `Error: not an output of this turn: ${String(call.arguments.id)}`
+  const result = await ctx.tools.execute(EXTRACT_TOOL, call.arguments, {
+    agent_id: ctx.agent.id,
+    session_id: ctx.agent.sessionId,
+    signal: ctx.signal,
+  })
+  const text = result.ok ? JSON.stringify(result.output, null, 2) ?? 'null' : `Error: ${result.error}`
+  return truncate(ctx.redactor ? ctx.redactor.redact(text) : text, MAX_LOOKUP_CHARS)
+}
+
+/** One line per output: its id and enough of its shape to pick paths to look up. */
+export function describeOutput(output: ToolOutput): string {
+  const { data } = output
+  let shape: string
+  if (typeof data === 'string') {
+    shape = `text, ${data.length} characters:\n${truncate(data, MAX_INLINE_TEXT_CHARS)}`
+  } else if (Array.isArray(data)) {
+    const first = data[0]
+    const keys = typeof first === 'object' && first !== null && !Array.isArray(first) ? `, items with keys ${Object.keys(first).slice(0, 20).join(', ')}` : ''
+    shape = `array of ${data.length}${keys}`
+  } else if (typeof data === 'object' && data !== null) {
+    shape = `object with keys ${Object.keys(data).slice(0, 30).join(', ')}`
+  } else {
+    shape = `${typeof data}: ${String(data)}`
+  }
+  return `- ${output.id} (${output.toolName}): ${shape}`
+}
+
+export function parseVerdict(content: string | null | undefined, checkedOutputs: number, model: string): ResponseVerification | null {
+  const json = content?.match(/\{[\s\S]*\}/)?.[0]
+  if (!json) return null
+  let parsed: { confidence?: unknown; unsupported_claims?: unknown }
+  try {
+    parsed = JSON.parse(json) as typeof parsed
+  } catch {
+    return null
+  }
+  const confidence = CONFIDENCE_LEVELS.find((level) => level === parsed.confidence)
+  if (!confidence) return null
+  const unsupportedClaims = Array.isArray(parsed.unsupported_claims)
+    ? parsed.unsupported_claims.filter((claim): claim is string => typeof claim === 'string' && claim.trim() !== '')
+    : []
+  return { confidence, unsupportedClaims, checkedOutputs, model }
+}
+
+function truncate(text: string, maxChars: number): string {
+  return text.length <= maxChars ? text : `${text.slice(0, maxChars)}… [truncated]`
+}
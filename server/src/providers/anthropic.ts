@@ -10,6 +10,7 @@ import type {
   LLMToolDefinition,
   LLMToolCall,
   LLMContentBlock,
+  LLMModelInfo,
 } from './types.js'
 
 /** Replace dots with __ so tool names satisfy Anthropic's ^[a-zA-Z0-9_-]{1,128}$ requirement. */
@@ -68,11 +69,13 @@ function mapMessagesToAnthropic(
   messages: LLMMessage[],
 ): { system: Anthropic.TextBlockParam[] | undefined; messages: Anthropic.MessageParam[] } {
   let systemText: string | undefined
+  let stablePrefixLength: number | undefined
   const mapped: Anthropic.MessageParam[] = []
 
   for (const msg of messages) {
     if (msg.role === 'system') {
       systemText = typeof msg.content === 'string' ? msg.content : ''
+      stablePrefixLength = msg.stable_prefix_length
       continue
     }
 
@@ -189,11 +192,23 @@ function mapMessagesToAnthropic(
     }
   }
 
-  const system: Anthropic.TextBlockParam[] | undefined = systemText != null
-    ? [{ type: 'text', text: systemText }]
-    : undefined
+  return { system: systemText != null ? mapSystemToAnthropic(systemText, stablePrefixLength) : undefined, messages: mapped }
+}
 
-  return { system, messages: mapped }
+/**
+ * Splits the system prompt at the end of its stable prefix and puts a cache
+ * breakpoint there, so turns whose task or instructions differ still read the
+ * fixed prompt from cache.
+ */
+function mapSystemToAnthropic(text: string, stablePrefixLength: number | undefined): Anthropic.TextBlockParam[] {
+  if (!stablePrefixLength || stablePrefixLength <= 0) return [{ type: 'text', text }]
+  const prefix = text.slice(0, stablePrefixLength)
+  const rest = text.slice(stablePrefixLength)
+  return [
+    { type: 'text', text: prefix, cache_control: { type: 'ephemeral' } },
+    // Anthropic rejects empty text blocks
+    ...(rest ? [{ type: 'text' as const, text: rest }] : []),
+  ]
 }
 
 export function mapToolsToAnthropic(tools: LLMToolDefinition[]): Anthropic.ToolUnion[] {
@@ -212,6 +227,49 @@ export function mapToolsToAnthropic(tools: LLMToolDefinition[]): Anthropic.ToolU
   }))
 }
 
+/**
+ * Builds the Messages API request. Besides the automatic breakpoint that
+ * follows the conversation, the tool list and the system prompt's stable
+ * prefix get breakpoints of their own: the tools one sits on the last real
+ * tool, before the synthetic structured-output tool, so requests with and
+ * without a forced output format share the cached tools and system prompt.
+ */
+export function buildAnthropicParams(request: LLMRequest): Anthropic.MessageCreateParamsNonStreaming {
+  const { system, messages } = mapMessagesToAnthropic(request.messages)
+
+  let tools = request.tools?.length ? withCacheBreakpoint(mapToolsToAnthropic(request.tools)) : undefined
+  let toolChoice: Anthropic.MessageCreateParams['tool_choice'] = undefined
+
+  // For structured output, add a synthetic tool and force its use
+  if (request.structured_output) {
+    const structuredTool: Anthropic.Tool = {
+      name: '_structured_output',
+      description: 'Return the structured response in the required format.',
+      input_schema: stripUnsupportedSchemaKeywords(
+        request.structured_output,
+      ) as Anthropic.Tool.InputSchema,
+    }
+    tools = [...(tools ?? []), structuredTool]
+    toolChoice = { type: 'tool', name: '_structured_output' }
+  }
+
+  return {
+    model: request.model,
+    max_tokens: request.max_tokens ?? 4096,
+    messages,
+    cache_control: { type: 'ephemeral' },
+    ...(system && { system }),
+    ...(tools && { tools }),
+    ...(toolChoice && { tool_choice: toolChoice }),
+    ...(request.temperature !== undefined && { temperature: request.temperature }),
+  }
+}
+
+function withCacheBreakpoint(tools: Anthropic.ToolUnion[]): Anthropic.ToolUnion[] {
+  const last = tools.length - 1
+  return tools.map((tool, index) => (index === last ? { ...tool, cache_control: { type: 'ephemeral' } } : tool))
+}
+
 export class AnthropicProvider implements LLMProvider {
   private client: Anthropic
 
@@ -219,35 +277,31 @@ export class AnthropicProvider implements LLMProvider {
     this.client = new Anthropic({ apiKey })
   }
 
-  async generate(request: LLMRequest): Promise<LLMResponse> {
-    const { system, messages } = mapMessagesToAnthropic(request.messages)
-
-    let tools = request.tools ? mapToolsToAnthropic(request.tools) : undefined
-    let toolChoice: Anthropic.MessageCreateParams['tool_choice'] = undefined
-
-    // For structured output, add a synthetic tool and force its use
-    if (request.structured_output) {
-      const structuredTool: Anthropic.Tool = {
-        name: '_structured_output',
-        description: 'Return the structured response in the required format.',
-        input_schema: stripUnsupportedSchemaKeywords(
-          request.structured_output,
-        ) as Anthropic.Tool.InputSchema,
-      }
-      tools = [...(tools ?? []), structuredTool]
-      toolChoice = { type: 'tool', name: '_structured_output' }
-    }
+  async ping(signal?: AbortSignal): Promise<void> {
+    await this.client.models.list({ limit: 1 }, { signal })
+  }
 
-    const params: Anthropic.MessageCreateParams = {
-      model: request.model,
-      max_tokens: request.max_tokens ?? 4096,
-      messages,
-      cache_control: { type: 'ephemeral' },
-      ...(system && { system }),
-      ...(tools && { tools }),
-      ...(toolChoice && { tool_choice: toolChoice }),
-      ...(request.temperature !== undefined && { temperature: request.temperature }),
+  async listModels(signal?: AbortSignal): Promise<LLMModelInfo[]> {
+    const models: LLMModelInfo[] = []
+    for await (const model of this.client.models.list({ limit: 100 }, { signal })) {
+      // Limits are newer additions to the listing; older SDK types do not know them
+      const limits = model as { max_input_tokens?: number; max_tokens?: number }
+      models.push({
+        modelId: model.id,
+        displayName: model.display_name,
+        contextWindow: limits.max_input_tokens ?? null,
+        maxOutputTokens: limits.max_tokens ?? null,
+        // Every current Claude model reads images and calls tools; structured output goes through a forced tool
+        vision: true,
+        structuredOutputs: true,
+        tools: true,
+      })
     }
+    return models
+  }
+
+  async generate(request: LLMRequest): Promise<LLMResponse> {
+    const params = buildAnthropicParams(request)
 
     const response = await this.client.messages.create(params)
 
@@ -264,34 +318,7 @@ export class AnthropicProvider implements LLMProvider {
   }
 
   async *stream(request: LLMRequest): AsyncIterable<LLMStreamEvent> {
-    const { system, messages } = mapMessagesToAnthropic(request.messages)
-
-    let tools = request.tools ? mapToolsToAnthropic(request.tools) : undefined
-    let toolChoice: Anthropic.MessageCreateParams['tool_choice'] = undefined
-
-    if (request.structured_output) {
-      const structuredTool: Anthropic.Tool = {
-        name: '_structured_output',
-        description: 'Return the structured response in the required format.',
-        input_schema: stripUnsupportedSchemaKeywords(
-          request.structured_output,
-        ) as Anthropic.Tool.InputSchema,
-      }
-      tools = [...(tools ?? []), structuredTool]
-      toolChoice = { type: 'tool', name: '_structured_output' }
-    }
-
-    const params: Anthropic.MessageCreateParams = {
-      model: request.model,
-      max_tokens: request.max_tokens ?? 4096,
-      messages,
-      cache_control: { type: 'ephemeral' },
-      ...(system && { system }),
-      ...(tools && { tools }),
-      ...(toolChoice && { tool_choice: toolChoice }),
-      ...(request.temperature !== undefined && { temperature: request.temperature }),
-      stream: true,
-    }
+    const params: Anthropic.MessageCreateParamsStreaming = { ...buildAnthropicParams(request), stream: true }
 
     const stream = await this.client.messages.create(params)
 
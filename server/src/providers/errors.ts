@@ -0,0 +1,11 @@
+/**
+ * A provider request the provider answered with an error. Keeps the provider
+ * and HTTP status alongside the message, so classifyError does not have to
+ * read them back out of the text.
+ */
+export class ProviderError extends Error {
+  constructor(readonly provider: string, readonly status: number, message: string) {
+    super(message)
+    this.name = 'ProviderError'
+  }
+}
@@ -0,0 +1,95 @@
+import { readFileSync } from 'node:fs'
+import { z } from 'zod'
+import type { LLMMessage, LLMProvider, LLMRequest, LLMResponse, LLMStreamEvent } from './types.js'
+
+const mockRuleSchema = z.object({
+  /** The model's nth reply in the conversation: 1 + the assistant messages already in it. */
+  turn: z.number().int().positive().optional(),
+  /** Regular expression tested against the last user or tool message. */
+  match: z.string().optional(),
+  content: z.string().default(''),
+  tool_calls: z.array(z.object({
+    call_id: z.string().optional(),
+    name: z.string(),
+    arguments: z.record(z.unknown()).default({}),
+  })).default([]),
+})
+
+export const mockScriptSchema = z.object({
+  responses: z.array(mockRuleSchema).min(1),
+})
+
+export type MockScript = z.input<typeof mockScriptSchema>
+
+interface MockRule {
+  turn?: number
+  match?: RegExp
+  content: string
+  tool_calls: Array<{ call_id?: string; name: string; arguments: Record<string, unknown> }>
+}
+
+/**
+ * Scripted provider for running the whole stack without a network: each
+ * request gets the first rule whose `turn` and `match` both fit, and a rule
+ * with neither is the fallback. It keeps no state between calls, so
+ * concurrent sessions and retries get the same answers. Selected like any
+ * other provider, with a `mock:<anything>` model id.
+ */
+export class MockProvider implements LLMProvider {
+  private readonly rules: MockRule[]
+
+  constructor(script: MockScript) {
+    this.rules = mockScriptSchema.parse(script).responses.map((rule) => ({
+      ...rule,
+      match: rule.match === undefined ? undefined : new RegExp(rule.match, 'i'),
+    }))
+  }
+
+  /** Loads a JSON script, e.g. the one named by MOCK_PROVIDER_SCRIPT. */
+  static fromFile(path: string): MockProvider {
+    return new MockProvider(JSON.parse(readFileSync(path, 'utf-8')) as MockScript)
+  }
+
+  async generate(request: LLMRequest): Promise<LLMResponse> {
+    const turn = request.messages.filter((message) => message.role === 'assistant').length + 1
+    const last = [...request.messages].reverse().find((message) => message.role === 'user' || message.role === 'tool')
+    const text = last ? messageText(last) : ''
+    const rule = this.rules.find((candidate) =>
+      (candidate.turn === undefined || candidate.turn === turn) && (!candidate.match || candidate.match.test(text)))
+    if (!rule) {
+      throw new Error(`Mock script has no response for turn ${turn}`)
+    }
+    const toolCalls = rule.tool_calls.map((call, index) => ({
+      call_id: call.call_id ?? `mock_${turn}_${index + 1}`,
+      name: call.name,
+      arguments: call.arguments,
+    }))
+    return {
+      content: rule.content,
+      ...(toolCalls.length ? { tool_calls: toolCalls } : {}),
+      usage: { input_tokens: 0, output_tokens: 0 },
+      finish_reason: toolCalls.length ? 'tool_calls' : 'stop',
+    }
+  }
+
+  async *stream(request: LLMRequest): AsyncIterable<LLMStreamEvent> {
+    const response = await this.generate(request)
+    const content = response.content as string
+    // Word by word, so streaming consumers see more than one delta
+    for (const word of content.match(/\S+\s*/g) ?? []) {
+      yield { type: 'text_delta', text: word }
+    }
+    if (content) yield { type: 'text_done', text: content }
+    for (const call of response.tool_calls ?? []) {
+      yield { type: 'tool_call_done', call_id: call.call_id, name: call.name, arguments: JSON.stringify(call.arguments) }
+    }
+    yield { type: 'done', response }
+  }
+
+  async ping(): Promise<void> {}
+}
+
+function messageText(message: LLMMessage): string {
+  if (typeof message.content === 'string') return message.content
+  return message.content.map((block) => block.text ?? block.content ?? '').join('\n')
+}
@@ -1,4 +1,11 @@
 import { OpenAIProvider } from './openai.js'
+import { ProviderError } from './errors.js'
+import type { LLMModelInfo } from './types.js'
+
+interface OllamaShowResponse {
+  capabilities?: string[]
+  model_info?: Record<string, unknown>
+}
 
 /**
  * Ollama provider — reuses OpenAI-compatible API with a custom base URL.
@@ -6,8 +13,44 @@ import { OpenAIProvider } from './openai.js'
  */
 export class OllamaProvider extends OpenAIProvider {
   protected override readonly supportsHostedWebSearch = false
+  /** The native API root, without the OpenAI-compatible `/v1`. */
+  private readonly apiRoot: string
 
   constructor(baseUrl: string = 'http://localhost:11434/v1') {
     super('ollama', baseUrl)
+    this.apiRoot = baseUrl.replace(/\/+$/, '').replace(/\/v1$/, '')
+  }
+
+  /** Pulled models from /api/tags, with capabilities and context length from /api/show. */
+  override async listModels(signal?: AbortSignal): Promise<LLMModelInfo[]> {
+    const tags = await this.request<{ models?: Array<{ name: string }> }>('/api/tags', undefined, signal)
+    const models: LLMModelInfo[] = []
+    for (const { name } of tags.models ?? []) {
+      const show = await this.request<OllamaShowResponse>('/api/show', { model: name }, signal)
+      const capabilities = show.capabilities
+      const contextLength = Object.entries(show.model_info ?? {})
+        .find(([key, value]) => key.endsWith('.context_length') && typeof value === 'number')?.[1] as number | undefined
+      models.push({
+        modelId: name,
+        displayName: null,
+        contextWindow: contextLength ?? null,
+        maxOutputTokens: null,
+        vision: capabilities ? capabilities.includes('vision') : null,
+        // Ollama constrains any generating model's output to a schema
+        structuredOutputs: capabilities ? capabilities.includes('completion') : null,
+        tools: capabilities ? capabilities.includes('tools') : null,
+      })
+    }
+    return models
+  }
+
+  private async request<T>(route: string, body: unknown, signal?: AbortSignal): Promise<T> {
+    const res = await fetch(`${this.apiRoot}${route}`, {
+      method: body === undefined ? 'GET' : 'POST',
+      ...(body !== undefined && { headers: { 'Content-Type': 'application/json' }, body: JSON.stringify(body) }),
+      signal,
+    })
+    if (!res.ok) throw new ProviderError('ollama', res.status, `Ollama ${res.status}: ${await res.text()}`)
+    return await res.json() as T
   }
 }
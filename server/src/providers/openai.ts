@@ -10,6 +10,11 @@ import type {
   LLMToolDefinition,
   LLMToolCall,
   LLMContentBlock,
+  LLMEmbeddingRequest,
+  LLMEmbeddingResponse,
+  LLMImageGenerationRequest,
+  LLMImageGenerationResponse,
+  LLMModelInfo,
 } from './types.js'
 
 /**
@@ -169,6 +174,9 @@ export function resolveOpenAIModelForWebSearch(model: string): string {
   return 'gpt-5-search-api'
 }
 
+/** Ids in OpenAI's model listing that cannot hold a chat: embeddings, speech, images, moderation, legacy completions. */
+const NON_CHAT_MODEL = /embedding|tts|whisper|dall-e|moderation|transcribe|realtime|audio|image|davinci|babbage/
+
 export class OpenAIProvider implements LLMProvider {
   protected client: OpenAI
   protected readonly supportsHostedWebSearch: boolean = true
@@ -180,6 +188,20 @@ export class OpenAIProvider implements LLMProvider {
     })
   }
 
+  async ping(signal?: AbortSignal): Promise<void> {
+    await this.client.models.list({ signal })
+  }
+
+  /** OpenAI's listing gives ids only, so capabilities stay unknown; non-chat models are left out. */
+  async listModels(signal?: AbortSignal): Promise<LLMModelInfo[]> {
+    const models: LLMModelInfo[] = []
+    for await (const model of this.client.models.list({ signal })) {
+      if (NON_CHAT_MODEL.test(model.id)) continue
+      models.push({ modelId: model.id, displayName: null, contextWindow: null, maxOutputTokens: null, vision: null, structuredOutputs: null, tools: null })
+    }
+    return models
+  }
+
   async generate(request: LLMRequest): Promise<LLMResponse> {
     const messages = mapMessagesToOpenAI(request.messages)
     const tools = request.tools?.length ? mapToolsToOpenAI(request.tools) : undefined
@@ -246,16 +268,12 @@ export class OpenAIProvider implements LLMProvider {
     let fullText = ''
     const toolCallBuffers: Record<number, { call_id: string; name: string; args: string }> = {}
     let finishReason = ''
-    let completionTokens = 0
-    let promptTokens = 0
+    let usage: OpenAI.CompletionUsage | undefined
     const textSanitizer = new ProviderCitationStreamSanitizer()
 
     for await (const chunk of stream as AsyncIterable<OpenAI.ChatCompletionChunk>) {
       // Track usage if provided (some providers include it in chunks)
-      if (chunk.usage) {
-        promptTokens = chunk.usage.prompt_tokens
-        completionTokens = chunk.usage.completion_tokens
-      }
+      if (chunk.usage) usage = chunk.usage
 
       const choice = chunk.choices?.[0]
       if (!choice) continue
@@ -348,7 +366,7 @@ export class OpenAIProvider implements LLMProvider {
       content: sanitizedText,
       tool_calls: toolCalls.length > 0 ? toolCalls : undefined,
       companion_text: toolCalls.length > 0 && sanitizedText ? sanitizedText : undefined,
-      usage: { input_tokens: promptTokens, output_tokens: completionTokens },
+      usage: mapOpenAIUsage(usage),
       finish_reason: finishReason || 'stop',
     }
 
@@ -356,6 +374,42 @@ export class OpenAIProvider implements LLMProvider {
 
     yield { type: 'done', response: streamResponse }
   }
+
+  async embed(request: LLMEmbeddingRequest): Promise<LLMEmbeddingResponse> {
+    const response = await this.client.embeddings.create(
+      { model: request.model, input: request.input },
+      { signal: request.signal },
+    )
+    return { embeddings: [...response.data].sort((a, b) => a.index - b.index).map((entry) => entry.embedding) }
+  }
+
+  async generateImage(request: LLMImageGenerationRequest): Promise<LLMImageGenerationResponse> {
+    const response = await this.client.images.generate(
+      {
+        model: request.model,
+        prompt: request.prompt,
+        n: 1,
+        ...(request.size && { size: request.size as OpenAI.ImageGenerateParams['size'] }),
+        // gpt-image models always return base64 and reject response_format
+        ...(request.model.startsWith('dall-e') && { response_format: 'b64_json' as const }),
+      },
+      { signal: request.signal },
+    )
+    const image = response.data?.[0]
+    if (!image?.b64_json) throw new Error('Image generation response was empty')
+    return { data: image.b64_json, media_type: 'image/png', revised_prompt: image.revised_prompt }
+  }
+}
+
+/** `prompt_tokens` includes cached tokens; split them out the way Anthropic reports them. */
+function mapOpenAIUsage(usage: OpenAI.CompletionUsage | null | undefined): LLMResponse['usage'] {
+  const prompt = usage?.prompt_tokens ?? 0
+  const cached = Math.min(usage?.prompt_tokens_details?.cached_tokens ?? 0, prompt)
+  return {
+    input_tokens: prompt - cached,
+    output_tokens: usage?.completion_tokens ?? 0,
+    ...(cached > 0 && { cache_read_input_tokens: cached }),
+  }
 }
 
 function mapOpenAIResponse(response: OpenAI.ChatCompletion): LLMResponse {
@@ -364,10 +418,7 @@ function mapOpenAIResponse(response: OpenAI.ChatCompletion): LLMResponse {
   if (!choice) {
     return {
       content: '',
-      usage: {
-        input_tokens: response.usage?.prompt_tokens ?? 0,
-        output_tokens: response.usage?.completion_tokens ?? 0,
-      },
+      usage: mapOpenAIUsage(response.usage),
       finish_reason: 'stop',
     }
   }
@@ -388,10 +439,7 @@ function mapOpenAIResponse(response: OpenAI.ChatCompletion): LLMResponse {
     content: text,
     companion_text: toolCalls.length > 0 && text ? text : undefined,
     tool_calls: toolCalls.length > 0 ? toolCalls : undefined,
-    usage: {
-      input_tokens: response.usage?.prompt_tokens ?? 0,
-      output_tokens: response.usage?.completion_tokens ?? 0,
-    },
+    usage: mapOpenAIUsage(response.usage),
     finish_reason: choice.finish_reason ?? 'stop',
   }
 }
@@ -1,4 +1,5 @@
 import { logger } from '../lib/logger.js'
+import { ProviderError } from './errors.js'
 import { ProviderCitationStreamSanitizer, sanitizeProviderCitations } from '../lib/provider-citations.js'
 import type {
   LLMProvider,
@@ -9,10 +10,13 @@ import type {
   LLMToolDefinition,
   LLMAudioTranscriptionRequest,
   LLMAudioTranscriptionResponse,
+  LLMModelInfo,
 } from './types.js'
 
 const BASE_URL = 'https://openrouter.ai/api/v1/chat/completions'
 const AUDIO_TRANSCRIPTIONS_URL = 'https://openrouter.ai/api/v1/audio/transcriptions'
+const KEY_URL = 'https://openrouter.ai/api/v1/key'
+const MODELS_URL = 'https://openrouter.ai/api/v1/models'
 const DEFAULT_MAX_TOKENS = 12_000
 
 function buildTokenLimit(model: string, maxTokens?: number): Record<string, number> {
@@ -40,6 +44,18 @@ export class OpenRouterProvider implements LLMProvider {
     this.apiKey = apiKey
   }
 
+  async ping(signal?: AbortSignal): Promise<void> {
+    const res = await fetch(KEY_URL, { headers: { 'Authorization': `Bearer ${this.apiKey}` }, signal })
+    if (!res.ok) throw new ProviderError('openrouter', res.status, `OpenRouter ${res.status}: ${await res.text()}`)
+  }
+
+  async listModels(signal?: AbortSignal): Promise<LLMModelInfo[]> {
+    const res = await fetch(MODELS_URL, { headers: { 'Authorization': `Bearer ${this.apiKey}` }, signal })
+    if (!res.ok) throw new ProviderError('openrouter', res.status, `OpenRouter ${res.status}: ${await res.text()}`)
+    const json = await res.json() as { data?: OpenRouterModel[] }
+    return (json.data ?? []).map(mapModel)
+  }
+
   async transcribeAudio(request: LLMAudioTranscriptionRequest): Promise<LLMAudioTranscriptionResponse> {
     const body = buildAudioTranscriptionRequestBody(request)
     preflightAudioTranscriptionRequestBody(body)
@@ -58,7 +74,7 @@ export class OpenRouterProvider implements LLMProvider {
 
     if (!res.ok) {
       const err = await res.text()
-      throw new Error(`OpenRouter transcription ${res.status}: ${err}`)
+      throw new ProviderError('openrouter', res.status, `OpenRouter transcription ${res.status}: ${err}`)
     }
 
     const json = await res.json() as OpenRouterTranscriptionResponse
@@ -93,7 +109,7 @@ export class OpenRouterProvider implements LLMProvider {
 
     if (!res.ok) {
       const err = await res.text()
-      throw new Error(`OpenRouter ${res.status}: ${err}`)
+      throw new ProviderError('openrouter', res.status, `OpenRouter ${res.status}: ${err}`)
     }
 
     const json = (await res.json()) as ChatCompletionResponse
@@ -125,7 +141,7 @@ export class OpenRouterProvider implements LLMProvider {
 
     if (!res.ok) {
       const err = await res.text()
-      throw new Error(`OpenRouter ${res.status}: ${err}`)
+      throw new ProviderError('openrouter', res.status, `OpenRouter ${res.status}: ${err}`)
     }
 
     if (!res.body) {
@@ -135,15 +151,11 @@ export class OpenRouterProvider implements LLMProvider {
     let fullText = ''
     const toolCallBuffers: Record<number, { call_id: string; name: string; args: string }> = {}
     let finishReason = ''
-    let completionTokens = 0
-    let promptTokens = 0
+    let usage: ChatCompletionUsage | undefined
     const textSanitizer = new ProviderCitationStreamSanitizer()
 
     for await (const chunk of parseOpenRouterSSE(res.body)) {
-      if (chunk.usage) {
-        promptTokens = chunk.usage.prompt_tokens ?? promptTokens
-        completionTokens = chunk.usage.completion_tokens ?? completionTokens
-      }
+      if (chunk.usage) usage = { ...usage, ...chunk.usage }
 
       const choice = chunk.choices?.[0]
       if (!choice) continue
@@ -235,10 +247,7 @@ export class OpenRouterProvider implements LLMProvider {
       content: sanitizedText,
       tool_calls: toolCalls.length > 0 ? toolCalls : undefined,
       companion_text: toolCalls.length > 0 && sanitizedText ? sanitizedText : undefined,
-      usage: {
-        input_tokens: promptTokens,
-        output_tokens: completionTokens,
-      },
+      usage: mapUsage(usage),
       finish_reason: finishReason || 'stop',
     }
 
@@ -515,16 +524,47 @@ function isFunctionToolPayload(value: unknown): boolean {
 // Response mapping
 // ---------------------------------------------------------------------------
 
+interface OpenRouterModel {
+  id: string
+  name?: string
+  context_length?: number | null
+  architecture?: { input_modalities?: string[] }
+  top_provider?: { max_completion_tokens?: number | null }
+  supported_parameters?: string[]
+}
+
+function mapModel(model: OpenRouterModel): LLMModelInfo {
+  const params = model.supported_parameters
+  const modalities = model.architecture?.input_modalities
+  return {
+    modelId: model.id,
+    displayName: model.name ?? null,
+    contextWindow: model.context_length ?? null,
+    maxOutputTokens: model.top_provider?.max_completion_tokens ?? null,
+    vision: modalities ? modalities.includes('image') : null,
+    structuredOutputs: params ? params.includes('structured_outputs') || params.includes('response_format') : null,
+    tools: params ? params.includes('tools') : null,
+  }
+}
+
+/** `prompt_tokens` includes cached tokens; split them out the way Anthropic reports them. */
+function mapUsage(usage: ChatCompletionUsage | undefined): LLMResponse['usage'] {
+  const prompt = usage?.prompt_tokens ?? 0
+  const cached = Math.min(usage?.prompt_tokens_details?.cached_tokens ?? 0, prompt)
+  return {
+    input_tokens: prompt - cached,
+    output_tokens: usage?.completion_tokens ?? 0,
+    ...(cached > 0 && { cache_read_input_tokens: cached }),
+  }
+}
+
 function mapResponse(json: ChatCompletionResponse): LLMResponse {
   const choice = json.choices?.[0]
 
   if (!choice) {
     return {
       content: '',
-      usage: {
-        input_tokens: json.usage?.prompt_tokens ?? 0,
-        output_tokens: json.usage?.completion_tokens ?? 0,
-      },
+      usage: mapUsage(json.usage),
       finish_reason: 'stop',
     }
   }
@@ -541,10 +581,7 @@ function mapResponse(json: ChatCompletionResponse): LLMResponse {
     content: text,
     companion_text: toolCalls.length > 0 && text ? text : undefined,
     tool_calls: toolCalls.length > 0 ? toolCalls : undefined,
-    usage: {
-      input_tokens: json.usage?.prompt_tokens ?? 0,
-      output_tokens: json.usage?.completion_tokens ?? 0,
-    },
+    usage: mapUsage(json.usage),
     finish_reason: choice.finish_reason ?? 'stop',
   }
 }
@@ -568,7 +605,7 @@ interface ChatCompletionResponse {
     }
     finish_reason: string | null
   }>
-  usage?: { prompt_tokens: number; completion_tokens: number; total_tokens: number }
+  usage?: ChatCompletionUsage
 }
 
 interface ChatCompletionChunk {
@@ -589,7 +626,14 @@ interface ChatCompletionChunk {
     }
     finish_reason?: string | null
   }>
-  usage?: { prompt_tokens?: number; completion_tokens?: number; total_tokens?: number }
+  usage?: ChatCompletionUsage
+}
+
+interface ChatCompletionUsage {
+  prompt_tokens?: number
+  completion_tokens?: number
+  total_tokens?: number
+  prompt_tokens_details?: { cached_tokens?: number }
 }
 
 interface OpenRouterTranscriptionResponse {
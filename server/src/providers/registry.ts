@@ -78,6 +78,10 @@ export class ProviderRegistryImpl implements ProviderRegistry {
     return provider
   }
 
+  get(name: string): LLMProvider | undefined {
+    return this.providers.get(name)
+  }
+
   list(): string[] {
     return Array.from(this.providers.keys())
   }
@@ -2,6 +2,12 @@ export interface LLMProvider {
   generate(request: LLMRequest): Promise<LLMResponse>
   stream(request: LLMRequest): AsyncIterable<LLMStreamEvent>
   transcribeAudio?(request: LLMAudioTranscriptionRequest): Promise<LLMAudioTranscriptionResponse>
+  embed?(request: LLMEmbeddingRequest): Promise<LLMEmbeddingResponse>
+  generateImage?(request: LLMImageGenerationRequest): Promise<LLMImageGenerationResponse>
+  /** Cheapest authenticated call the provider offers; throws when the key or server is not usable. */
+  ping?(signal?: AbortSignal): Promise<void>
+  /** Every model the key can use, with whatever capabilities the provider's listing reports. */
+  listModels?(signal?: AbortSignal): Promise<LLMModelInfo[]>
 }
 
 export interface LLMRequest {
@@ -28,11 +34,56 @@ export interface LLMAudioTranscriptionResponse {
   usage?: unknown
 }
 
+export interface LLMEmbeddingRequest {
+  model: string
+  input: string[]
+  signal?: AbortSignal
+}
+
+export interface LLMEmbeddingResponse {
+  /** One vector per input, in input order. */
+  embeddings: number[][]
+}
+
+export interface LLMImageGenerationRequest {
+  model: string
+  prompt: string
+  /** Provider size string, e.g. `1024x1024`. */
+  size?: string
+  signal?: AbortSignal
+}
+
+export interface LLMImageGenerationResponse {
+  /** Base64 image data. */
+  data: string
+  media_type: string
+  /** The prompt the provider actually used, when it rewrites prompts. */
+  revised_prompt?: string
+}
+
+export interface LLMModelInfo {
+  /** The provider's id, as written after `provider:` in a model string. */
+  modelId: string
+  displayName: string | null
+  contextWindow: number | null
+  maxOutputTokens: number | null
+  /** Null when the listing does not say. */
+  vision: boolean | null
+  structuredOutputs: boolean | null
+  tools: boolean | null
+}
+
 export interface LLMMessage {
   role: 'system' | 'user' | 'assistant' | 'tool'
   content: string | LLMContentBlock[]
   tool_call_id?: string // for role='tool'
   tool_calls?: LLMToolCall[] // for role='assistant' with tool calls
+  /**
+   * For role='system': length of the leading part of `content` that is the
+   * same on every turn, so providers with explicit cache breakpoints can
+   * cache it apart from the per-run part that follows.
+   */
+  stable_prefix_length?: number
 }
 
 export interface LLMContentBlock {
@@ -84,5 +135,7 @@ export interface LLMToolCall {
 export interface ProviderRegistry {
   register(name: string, provider: LLMProvider): void
   resolve(model: string): LLMProvider
+  /** The provider registered under `name`, if any. */
+  get(name: string): LLMProvider | undefined
   list(): string[]
 }
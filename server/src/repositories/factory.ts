@@ -1,5 +1,7 @@
 import { logger } from '../lib/logger.js'
 import type { AppConfig } from '../lib/config.js'
+import { resolveDatabaseKey } from '../lib/database-key.js'
+import { systemKeychain, type Keychain } from '../lib/keychain.js'
 import { createDatabase, SQLiteRepositories, type DrizzleInstance as SqliteDb } from './sqlite/index.js'
 import { createPgDatabase, PostgresRepositories, type PgDrizzleInstance } from './postgres/index.js'
 import type {
@@ -15,6 +17,23 @@ import type {
   WorkflowRunRepository,
   McpRepository,
   TelegramRepository,
+  SyncRepository,
+  UsageRepository,
+  ApprovalHistoryRepository,
+  ModelPriceRepository,
+  ModelCatalogRepository,
+  UploadRepository,
+  MemoryRepository,
+  KnowledgeSourceRepository,
+  ScratchpadRepository,
+  ControllerPromptRepository,
+  ExperimentRepository,
+  EntityRepository,
+  FeedRepository,
+  RetentionRepository,
+  MessageRevisionRepository,
+  MaintenanceRepository,
+  OrphanRepository,
 } from './types.js'
 
 export interface RepositoryBundle {
@@ -30,6 +49,23 @@ export interface RepositoryBundle {
   workflowRuns: WorkflowRunRepository
   mcp: McpRepository
   telegram: TelegramRepository
+  sync: SyncRepository
+  usage: UsageRepository
+  approvalHistory: ApprovalHistoryRepository
+  modelPrices: ModelPriceRepository
+  modelCatalog: ModelCatalogRepository
+  uploads: UploadRepository
+  memories: MemoryRepository
+  knowledge: KnowledgeSourceRepository
+  scratchpad: ScratchpadRepository
+  controllerPrompts: ControllerPromptRepository
+  experiments: ExperimentRepository
+  entities: EntityRepository
+  feeds: FeedRepository
+  retention: RetentionRepository
+  messageRevisions: MessageRevisionRepository
+  maintenance: MaintenanceRepository
+  orphans: OrphanRepository
 }
 
 export interface OpenedDb {
@@ -37,14 +73,14 @@ export interface OpenedDb {
   /** Drizzle handle — type depends on dialect. SQLite is sync, Postgres is async. */
   db: SqliteDb | PgDrizzleInstance
   repositories: RepositoryBundle
-  /** Best-effort close — sqlite is a no-op, postgres ends the pool. */
+  /** Best-effort close — sqlite folds its WAL into the main file, postgres ends the pool. */
   close(): Promise<void>
 }
 
-export async function openDatabase(config: AppConfig): Promise<OpenedDb> {
+export async function openDatabase(config: AppConfig, keychain: Keychain = systemKeychain()): Promise<OpenedDb> {
   if (config.dbDialect === 'postgres') {
     logger.info({ url: redactDatabaseUrl(config.databaseUrl) }, 'Opening postgres database')
-    const opened = await createPgDatabase(config.databaseUrl)
+    const opened = await createPgDatabase(config.databaseUrl, { poolSize: config.databasePoolSize })
     const repos = new PostgresRepositories(opened.db, config.encryptionKey)
     return {
       dialect: 'postgres',
@@ -54,15 +90,22 @@ export async function openDatabase(config: AppConfig): Promise<OpenedDb> {
     }
   }
 
-  logger.info({ path: config.databaseUrl }, 'Opening sqlite database')
-  const db = createDatabase(config.databaseUrl)
+  const key = await resolveDatabaseKey(config, keychain)
+  logger.info({ path: config.databaseUrl, encrypted: Boolean(key) }, 'Opening sqlite database')
+  const db = createDatabase(config.databaseUrl, {
+    key,
+    busyTimeoutMs: config.databaseBusyTimeoutMs,
+  })
   const repos = new SQLiteRepositories(db, config.encryptionKey)
   return {
     dialect: 'sqlite',
     db,
     repositories: repos,
     close: async () => {
-      // better-sqlite3 has no async close path; rely on process exit
+      // Committed writes are already durable in the WAL; checkpointing just
+      // leaves a single clean file for the next start or a backup
+      db.$client.pragma('wal_checkpoint(TRUNCATE)')
+      db.$client.close()
     },
   }
 }
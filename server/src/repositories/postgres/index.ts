@@ -1,8 +1,9 @@
 import postgres from 'postgres'
 import { drizzle, type PostgresJsDatabase } from 'drizzle-orm/postgres-js'
-import { and, asc, desc, eq, isNull, max, or, sql, lte } from 'drizzle-orm'
+import { and, asc, count, desc, eq, gte, inArray, isNotNull, isNull, lt, max, ne, or, sql, lte } from 'drizzle-orm'
 import { v4 as uuid } from 'uuid'
 import { encrypt, decrypt, deriveKey } from '../../lib/crypto.js'
+import { collectAttachmentHashes } from '../../lib/attachment-store.js'
 import * as schema from '../../db/schema-pg.js'
 import type {
   AgentRepository,
@@ -29,6 +30,8 @@ import type {
   StoredTelegramConnection,
   StoredTelegramMessageLink,
   SystemPromptRecord,
+  SyncRecord,
+  SyncRepository,
   SystemPromptRepository,
   TelegramRepository,
   ToolOutputRepository,
@@ -37,12 +40,75 @@ import type {
   UpdateSessionInput,
   UpdateSystemPromptInput,
   UpdateWorkflowRunInput,
+  UsageQuery,
+  UsageRecord,
+  UsagePhase,
+  ToolContextShare,
+  UsageRepository,
+  ApprovalHistoryQuery,
+  ApprovalHistoryRepository,
+  ApprovalOutcome,
+  ApprovalRecord,
+  CreateApprovalRecordInput,
+  ModelPriceRecord,
+  ModelPriceRepository,
+  ModelCatalogEntry,
+  ModelCatalogRecord,
+  ModelCatalogRepository,
+  RecordUploadInput,
+  UploadKind,
+  UploadRecord,
+  UploadRepository,
+  CreateMemoryInput,
+  MemoryCategory,
+  MemoryKind,
+  MemoryRecord,
+  MessageSearchHit,
+  MemoryRepository,
+  UpdateMemoryInput,
+  CreateKnowledgeSourceInput,
+  KnowledgeDocument,
+  KnowledgeSource,
+  KnowledgeSourceKind,
+  KnowledgeSourceRepository,
+  KnowledgeSourceStatus,
+  UpdateKnowledgeSourceInput,
+  ScratchpadEntry,
+  ScratchpadRepository,
+  ControllerPromptRepository,
+  ControllerPromptVersion,
+  ExperimentRepository,
+  ExperimentSession,
+  CreateEntityInput,
+  EntityKind,
+  EntityRecord,
+  EntityRepository,
+  UpdateEntityInput,
+  CreateFeedInput,
+  Feed,
+  FeedItem,
+  FeedRepository,
+  NewFeedItem,
+  UpdateFeedInput,
+  ModelPriceSource,
+  UpsertModelPriceInput,
+  RetentionRepository,
+  InactiveSession,
+  MaintenanceRepository,
+  DatabaseMaintenanceReport,
+  OrphanRepository,
+  OrphanCounts,
+  MessageRevision,
+  MessageRevisionReason,
+  MessageRevisionRepository,
+  CreateUsageRecordInput,
   UserRepository,
   WorkflowRunRepository,
 } from '../types.js'
 import type {
   Agent,
   AgentConfig,
+  AgentErrorCode,
   AgentStatus,
   Item,
   ItemContentBlock,
@@ -56,6 +122,7 @@ import type {
   WaitingFor,
 } from '../../domain/types.js'
 import type { WorkflowRun, WorkflowRunStatus } from '../../workflows/types.js'
+import type { PromptComposition } from '../../events/payloads.js'
 
 export type PgDrizzleInstance = PostgresJsDatabase<typeof schema>
 type PgClient = postgres.Sql
@@ -77,6 +144,7 @@ function toAgent(row: typeof schema.agents.$inferSelect): Agent {
     waitingFor: JSON.parse(row.waitingFor ?? '[]') as WaitingFor[],
     result: row.result ?? null,
     error: row.error ?? null,
+    errorCode: (row.errorCode ?? null) as AgentErrorCode | null,
     turnCount: row.turnCount ?? 0,
     plan: row.plan ? (JSON.parse(row.plan) as Plan) : null,
     createdAt: row.createdAt ?? 0,
@@ -96,6 +164,7 @@ function toSession(row: typeof schema.sessions.$inferSelect): Session {
     title: row.title ?? null,
     summary: row.summary ?? null,
     status: (row.status ?? 'active') as SessionStatus,
+    deletedAt: row.deletedAt ?? null,
     createdAt: row.createdAt ?? 0,
     updatedAt: row.updatedAt ?? 0,
   }
@@ -296,6 +365,7 @@ function createSessionRepo(db: PgDrizzleInstance): SessionRepository {
         title: input.title ?? null,
         summary: null,
         status: 'active' as const,
+        deletedAt: null,
         createdAt: now,
         updatedAt: now,
       }
@@ -312,11 +382,28 @@ function createSessionRepo(db: PgDrizzleInstance): SessionRepository {
       const rows = await db
         .select()
         .from(schema.sessions)
-        .where(eq(schema.sessions.userId, userId))
+        .where(and(eq(schema.sessions.userId, userId), isNull(schema.sessions.deletedAt)))
         .orderBy(desc(schema.sessions.createdAt))
       return rows.map(toSession)
     },
 
+    async listTrashed(userId: string): Promise<Session[]> {
+      const rows = await db
+        .select()
+        .from(schema.sessions)
+        .where(and(eq(schema.sessions.userId, userId), isNotNull(schema.sessions.deletedAt)))
+        .orderBy(desc(schema.sessions.deletedAt))
+      return rows.map(toSession)
+    },
+
+    async listTrashedBefore(cutoff: number): Promise<Session[]> {
+      const rows = await db
+        .select()
+        .from(schema.sessions)
+        .where(lte(schema.sessions.deletedAt, cutoff))
+      return rows.map(toSession)
+    },
+
     async update(id: string, input: UpdateSessionInput): Promise<Session> {
       const updates: Record<string, unknown> = { updatedAt: Date.now() }
       if (input.rootAgentId !== undefined) updates.rootAgentId = input.rootAgentId
@@ -326,6 +413,7 @@ function createSessionRepo(db: PgDrizzleInstance): SessionRepository {
       if (input.title !== undefined) updates.title = input.title
       if (input.summary !== undefined) updates.summary = input.summary
       if (input.status !== undefined) updates.status = input.status
+      if (input.deletedAt !== undefined) updates.deletedAt = input.deletedAt
 
       await db.update(schema.sessions).set(updates as any).where(eq(schema.sessions.id, id))
       const rows = await db.select().from(schema.sessions).where(eq(schema.sessions.id, id)).limit(1)
@@ -348,6 +436,8 @@ function createSessionRepo(db: PgDrizzleInstance): SessionRepository {
         await tx.delete(schema.agents).where(eq(schema.agents.sessionId, id))
         await tx.delete(schema.workflowRuns).where(eq(schema.workflowRuns.sessionId, id))
         await tx.delete(schema.telegramMessageLinks).where(eq(schema.telegramMessageLinks.sessionId, id))
+        await tx.delete(schema.messageRevisions).where(eq(schema.messageRevisions.sessionId, id))
+        await tx.delete(schema.scratchpadEntries).where(eq(schema.scratchpadEntries.sessionId, id))
         await tx.delete(schema.sessions).where(eq(schema.sessions.id, id))
       })
     },
@@ -370,6 +460,7 @@ function createAgentRepo(db: PgDrizzleInstance): AgentRepository {
         waitingFor: '[]',
         result: null,
         error: null,
+        errorCode: null,
         turnCount: 0,
         plan: null,
         createdAt: now,
@@ -386,26 +477,42 @@ function createAgentRepo(db: PgDrizzleInstance): AgentRepository {
     },
 
     async update(id: string, input: UpdateAgentInput): Promise<Agent> {
-      const updates: Record<string, unknown> = { updatedAt: Date.now() }
-      if (input.status !== undefined) updates.status = input.status
-      if (input.waitingFor !== undefined) updates.waitingFor = JSON.stringify(input.waitingFor)
-      if (input.result !== undefined) updates.result = input.result
-      if (input.error !== undefined) updates.error = input.error
-      if (input.turnCount !== undefined) updates.turnCount = input.turnCount
-      if (input.plan !== undefined) updates.plan = input.plan ? JSON.stringify(input.plan) : null
-      if (input.completedAt !== undefined) updates.completedAt = input.completedAt
-
-      await db.update(schema.agents).set(updates as any).where(eq(schema.agents.id, id))
+      await db.update(schema.agents).set(agentUpdates(input) as any).where(eq(schema.agents.id, id))
       const rows = await db.select().from(schema.agents).where(eq(schema.agents.id, id)).limit(1)
       if (!rows[0]) throw new Error(`Agent not found: ${id}`)
       return toAgent(rows[0])
     },
 
-    async failRunningOrWaiting(error: string): Promise<void> {
+    async finish(id: string, input: UpdateAgentInput, message?: CreateItemInput): Promise<Agent> {
+      const row = await db.transaction(async (tx) => {
+        if (message) {
+          await tx.execute(sql`SELECT pg_advisory_xact_lock(hashtext(${message.agentId}))`)
+          const maxSeqResult = await tx
+            .select({ maxSeq: max(schema.items.sequence) })
+            .from(schema.items)
+            .where(eq(schema.items.agentId, message.agentId))
+          await tx.insert(schema.items).values(itemRow(message, (maxSeqResult[0]?.maxSeq ?? -1) + 1))
+        }
+        const rows = await tx.update(schema.agents).set(agentUpdates(input) as any).where(eq(schema.agents.id, id)).returning()
+        if (!rows[0]) throw new Error(`Agent not found: ${id}`)
+        return rows[0]
+      })
+      return toAgent(row)
+    },
+
+    async failRunningOrWaiting(error: string, errorCode: AgentErrorCode): Promise<void> {
       const now = Date.now()
       await db.update(schema.agents)
-        .set({ status: 'failed', error, completedAt: now, updatedAt: now })
+        .set({ status: 'failed', error, errorCode, completedAt: now, updatedAt: now })
+        .where(or(eq(schema.agents.status, 'running'), eq(schema.agents.status, 'waiting'))!)
+    },
+
+    async listRunningOrWaiting(): Promise<Agent[]> {
+      const rows = await db
+        .select()
+        .from(schema.agents)
         .where(or(eq(schema.agents.status, 'running'), eq(schema.agents.status, 'waiting'))!)
+      return rows.map(toAgent)
     },
 
     async findWaitingForCall(callId: string): Promise<Agent | null> {
@@ -447,37 +554,51 @@ function createAgentRepo(db: PgDrizzleInstance): AgentRepository {
   }
 }
 
+function agentUpdates(input: UpdateAgentInput): Record<string, unknown> {
+  const updates: Record<string, unknown> = { updatedAt: Date.now() }
+  if (input.status !== undefined) updates.status = input.status
+  if (input.waitingFor !== undefined) updates.waitingFor = JSON.stringify(input.waitingFor)
+  if (input.result !== undefined) updates.result = input.result
+  if (input.error !== undefined) updates.error = input.error
+  if (input.errorCode !== undefined) updates.errorCode = input.errorCode
+  if (input.turnCount !== undefined) updates.turnCount = input.turnCount
+  if (input.plan !== undefined) updates.plan = input.plan ? JSON.stringify(input.plan) : null
+  if (input.completedAt !== undefined) updates.completedAt = input.completedAt
+  if (input.config !== undefined) updates.config = JSON.stringify(input.config)
+  return updates
+}
+
+function itemRow(input: CreateItemInput, sequence: number) {
+  return {
+    id: uuid(),
+    agentId: input.agentId,
+    sequence,
+    type: input.type,
+    role: input.role ?? null,
+    content: input.content ?? null,
+    callId: input.callId ?? null,
+    name: input.name ?? null,
+    arguments: input.arguments ?? null,
+    output: input.output ?? null,
+    contentBlocks: input.contentBlocks ? JSON.stringify(input.contentBlocks) : null,
+    isError: input.isError ?? null,
+    saveOutput: input.saveOutput ?? null,
+    turnNumber: input.turnNumber,
+    durationMs: input.durationMs ?? null,
+    createdAt: Date.now(),
+  }
+}
+
 function createItemRepo(db: PgDrizzleInstance): ItemRepository {
   return {
     async create(input: CreateItemInput): Promise<Item> {
-      const now = Date.now()
-      const id = uuid()
-
       const row = await db.transaction(async (tx) => {
         await tx.execute(sql`SELECT pg_advisory_xact_lock(hashtext(${input.agentId}))`)
         const maxSeqResult = await tx
           .select({ maxSeq: max(schema.items.sequence) })
           .from(schema.items)
           .where(eq(schema.items.agentId, input.agentId))
-        const nextSequence = (maxSeqResult[0]?.maxSeq ?? -1) + 1
-        const insertRow = {
-          id,
-          agentId: input.agentId,
-          sequence: nextSequence,
-          type: input.type,
-          role: input.role ?? null,
-          content: input.content ?? null,
-          callId: input.callId ?? null,
-          name: input.name ?? null,
-          arguments: input.arguments ?? null,
-          output: input.output ?? null,
-          contentBlocks: input.contentBlocks ? JSON.stringify(input.contentBlocks) : null,
-          isError: input.isError ?? null,
-          saveOutput: input.saveOutput ?? null,
-          turnNumber: input.turnNumber,
-          durationMs: input.durationMs ?? null,
-          createdAt: now,
-        }
+        const insertRow = itemRow(input, (maxSeqResult[0]?.maxSeq ?? -1) + 1)
         await tx.insert(schema.items).values(insertRow)
         return insertRow
       })
@@ -499,6 +620,29 @@ function createItemRepo(db: PgDrizzleInstance): ItemRepository {
       return rows.map(toItem)
     },
 
+    async listRecentByAgent(agentId: string, userTurns: number): Promise<Item[]> {
+      const [start] = await db
+        .select({ sequence: schema.items.sequence })
+        .from(schema.items)
+        .where(and(eq(schema.items.agentId, agentId), eq(schema.items.type, 'message'), eq(schema.items.role, 'user')))
+        .orderBy(desc(schema.items.sequence))
+        .limit(1)
+        .offset(Math.max(userTurns - 1, 0))
+      if (!start) return this.listByAgent(agentId)
+      const rows = await db
+        .select()
+        .from(schema.items)
+        .where(and(
+          eq(schema.items.agentId, agentId),
+          or(
+            gte(schema.items.sequence, start.sequence),
+            and(eq(schema.items.type, 'message'), eq(schema.items.role, 'system')),
+          ),
+        ))
+        .orderBy(asc(schema.items.sequence))
+      return rows.map(toItem)
+    },
+
     async listBySession(sessionId: string): Promise<Item[]> {
       const rows = await db
         .select({ item: schema.items })
@@ -517,6 +661,61 @@ function createItemRepo(db: PgDrizzleInstance): ItemRepository {
         .limit(1)
       return rows[0] ? toItem(rows[0]) : null
     },
+
+    async listWithInlineAttachments(): Promise<Item[]> {
+      const rows = await db
+        .select()
+        .from(schema.items)
+        .where(sql`${schema.items.contentBlocks} LIKE '%"data":%'`)
+      return rows.map(toItem)
+    },
+
+    async updateContentBlocks(id: string, contentBlocks: ItemContentBlock[] | null): Promise<void> {
+      await db.update(schema.items)
+        .set({ contentBlocks: contentBlocks ? JSON.stringify(contentBlocks) : null })
+        .where(eq(schema.items.id, id))
+    },
+
+    async searchMessages(userId: string, terms: string[], options = {}): Promise<MessageSearchHit[]> {
+      if (terms.length === 0) return []
+      // Terms are letters and digits only, so joining them cannot inject tsquery syntax
+      const query = terms.join(' | ')
+      const rows = await db.execute<MessageSearchRow>(sql`
+        SELECT items.id AS item_id, agents.session_id, sessions.title AS session_title,
+          items.role, items.content, items.created_at
+        FROM items
+        JOIN agents ON agents.id = items.agent_id
+        JOIN sessions ON sessions.id = agents.session_id
+        WHERE items.type = 'message'
+          AND to_tsvector('simple', items.content) @@ to_tsquery('simple', ${query})
+          AND sessions.user_id = ${userId} AND sessions.deleted_at IS NULL
+          AND sessions.id != ${options.excludeSessionId ?? ''}
+          AND agents.depth = 0 AND items.role IN ('user', 'assistant')
+        ORDER BY ts_rank(to_tsvector('simple', items.content), to_tsquery('simple', ${query})) DESC
+        LIMIT ${options.limit ?? 20}
+      `)
+      return rows.map(toMessageSearchHit)
+    },
+  }
+}
+
+interface MessageSearchRow {
+  item_id: string
+  session_id: string
+  session_title: string | null
+  role: 'user' | 'assistant'
+  content: string
+  created_at: number | string
+}
+
+function toMessageSearchHit(row: MessageSearchRow): MessageSearchHit {
+  return {
+    itemId: row.item_id,
+    sessionId: row.session_id,
+    sessionTitle: row.session_title,
+    role: row.role,
+    content: row.content,
+    createdAt: Number(row.created_at),
   }
 }
 
@@ -689,11 +888,12 @@ function createPreferenceRepo(db: PgDrizzleInstance): PreferenceRepository {
     },
 
     async set(key: string, value: string): Promise<void> {
+      const updatedAt = Date.now()
       await db.insert(schema.preferences)
-        .values({ key, value })
+        .values({ key, value, updatedAt })
         .onConflictDoUpdate({
           target: schema.preferences.key,
-          set: { value },
+          set: { value, updatedAt },
         })
     },
 
@@ -1058,8 +1258,1031 @@ function createTelegramRepo(db: PgDrizzleInstance): TelegramRepository {
   }
 }
 
-export async function createPgDatabase(url: string): Promise<{ db: PgDrizzleInstance; close: () => Promise<void> }> {
-  const client = postgres(url, { max: 10, onnotice: () => {} })
+// --- Usage ---
+
+function toMessageRevision(row: typeof schema.messageRevisions.$inferSelect): MessageRevision {
+  return {
+    id: row.id,
+    itemId: row.itemId,
+    sessionId: row.sessionId,
+    revision: row.revision,
+    role: (row.role as ItemRole) ?? null,
+    content: row.content ?? null,
+    contentBlocks: row.contentBlocks ? (JSON.parse(row.contentBlocks) as ItemContentBlock[]) : null,
+    reason: row.reason as MessageRevisionReason,
+    createdAt: row.createdAt,
+  }
+}
+
+function toUsageRecord(row: typeof schema.usageRecords.$inferSelect): UsageRecord {
+  return {
+    id: row.id,
+    userId: row.userId ?? null,
+    sessionId: row.sessionId ?? null,
+    agentId: row.agentId ?? null,
+    provider: row.provider,
+    model: row.model,
+    phase: (row.phase as UsagePhase | null) ?? null,
+    inputTokens: row.inputTokens,
+    outputTokens: row.outputTokens,
+    cacheReadTokens: row.cacheReadTokens,
+    cacheWriteTokens: row.cacheWriteTokens,
+    costUsd: row.costUsd,
+    cacheSavingsUsd: row.cacheSavingsUsd,
+    toolContext: row.toolContext ? (JSON.parse(row.toolContext) as Record<string, ToolContextShare>) : null,
+    promptComposition: row.promptComposition ? (JSON.parse(row.promptComposition) as PromptComposition) : null,
+    createdAt: row.createdAt,
+  }
+}
+
+function createMessageRevisionRepo(db: PgDrizzleInstance): MessageRevisionRepository {
+  return {
+    async revise(item, sessionId, reason, next): Promise<MessageRevision> {
+      const row = await db.transaction(async (tx) => {
+        const [latest] = await tx
+          .select({ revision: max(schema.messageRevisions.revision) })
+          .from(schema.messageRevisions)
+          .where(eq(schema.messageRevisions.itemId, item.id))
+        const insertRow = {
+          id: uuid(),
+          itemId: item.id,
+          sessionId,
+          revision: (latest?.revision ?? 0) + 1,
+          role: item.role,
+          content: item.content,
+          contentBlocks: item.contentBlocks ? JSON.stringify(item.contentBlocks) : null,
+          reason,
+          createdAt: Date.now(),
+        }
+        await tx.insert(schema.messageRevisions).values(insertRow)
+        await tx.update(schema.items)
+          .set({
+            content: next.content,
+            contentBlocks: next.contentBlocks ? JSON.stringify(next.contentBlocks) : null,
+            updatedAt: insertRow.createdAt,
+          })
+          .where(eq(schema.items.id, item.id))
+        return insertRow
+      })
+      return toMessageRevision(row)
+    },
+
+    async listByItem(itemId: string): Promise<MessageRevision[]> {
+      const rows = await db
+        .select()
+        .from(schema.messageRevisions)
+        .where(eq(schema.messageRevisions.itemId, itemId))
+        .orderBy(asc(schema.messageRevisions.revision))
+      return rows.map(toMessageRevision)
+    },
+
+    async getById(id: string): Promise<MessageRevision | null> {
+      const rows = await db
+        .select()
+        .from(schema.messageRevisions)
+        .where(eq(schema.messageRevisions.id, id))
+        .limit(1)
+      return rows.length > 0 ? toMessageRevision(rows[0]) : null
+    },
+  }
+}
+
+function createUsageRepo(db: PgDrizzleInstance): UsageRepository {
+  return {
+    async record(input: CreateUsageRecordInput): Promise<UsageRecord> {
+      const row = {
+        id: uuid(),
+        userId: input.userId ?? null,
+        sessionId: input.sessionId ?? null,
+        agentId: input.agentId ?? null,
+        provider: input.provider,
+        model: input.model,
+        phase: input.phase ?? null,
+        inputTokens: input.inputTokens,
+        outputTokens: input.outputTokens,
+        cacheReadTokens: input.cacheReadTokens ?? 0,
+        cacheWriteTokens: input.cacheWriteTokens ?? 0,
+        costUsd: input.costUsd,
+        cacheSavingsUsd: input.cacheSavingsUsd ?? 0,
+        toolContext: input.toolContext ? JSON.stringify(input.toolContext) : null,
+        promptComposition: input.promptComposition ? JSON.stringify(input.promptComposition) : null,
+        createdAt: Date.now(),
+      }
+      await db.insert(schema.usageRecords).values(row)
+      return toUsageRecord(row)
+    },
+
+    async list(query: UsageQuery): Promise<UsageRecord[]> {
+      const conditions = []
+      if (query.userId) conditions.push(eq(schema.usageRecords.userId, query.userId))
+      if (query.sessionId) conditions.push(eq(schema.usageRecords.sessionId, query.sessionId))
+      if (query.from !== undefined) conditions.push(gte(schema.usageRecords.createdAt, query.from))
+      if (query.to !== undefined) conditions.push(lt(schema.usageRecords.createdAt, query.to))
+
+      const rows = await db
+        .select()
+        .from(schema.usageRecords)
+        .where(conditions.length > 0 ? and(...conditions) : undefined)
+        .orderBy(asc(schema.usageRecords.createdAt))
+      return rows.map(toUsageRecord)
+    },
+  }
+}
+
+// --- Approval history ---
+
+function toApprovalRecord(row: typeof schema.approvalRecords.$inferSelect): ApprovalRecord {
+  return {
+    id: row.id,
+    userId: row.userId ?? null,
+    sessionId: row.sessionId,
+    agentId: row.agentId,
+    callId: row.callId,
+    toolName: row.toolName,
+    args: JSON.parse(row.args) as Record<string, unknown>,
+    amendedArgs: row.amendedArgs ? JSON.parse(row.amendedArgs) as Record<string, unknown> : null,
+    outcome: row.outcome as ApprovalOutcome,
+    scope: (row.scope as ApprovalRecord['scope']) ?? null,
+    ruleId: row.ruleId ?? null,
+    requestedAt: row.requestedAt,
+    decidedAt: row.decidedAt,
+    latencyMs: row.latencyMs,
+  }
+}
+
+function createApprovalHistoryRepo(db: PgDrizzleInstance): ApprovalHistoryRepository {
+  return {
+    async record(input: CreateApprovalRecordInput): Promise<ApprovalRecord> {
+      const row = {
+        id: uuid(),
+        userId: input.userId ?? null,
+        sessionId: input.sessionId,
+        agentId: input.agentId,
+        callId: input.callId,
+        toolName: input.toolName,
+        args: JSON.stringify(input.args),
+        amendedArgs: input.amendedArgs ? JSON.stringify(input.amendedArgs) : null,
+        outcome: input.outcome,
+        scope: input.scope ?? null,
+        ruleId: input.ruleId ?? null,
+        requestedAt: input.requestedAt,
+        decidedAt: input.decidedAt,
+        latencyMs: Math.max(0, input.decidedAt - input.requestedAt),
+      }
+      await db.insert(schema.approvalRecords).values(row)
+      return toApprovalRecord(row)
+    },
+
+    async list(query: ApprovalHistoryQuery): Promise<ApprovalRecord[]> {
+      const conditions = []
+      if (query.userId) conditions.push(eq(schema.approvalRecords.userId, query.userId))
+      if (query.sessionId) conditions.push(eq(schema.approvalRecords.sessionId, query.sessionId))
+      if (query.toolName) conditions.push(eq(schema.approvalRecords.toolName, query.toolName))
+      if (query.outcome) conditions.push(eq(schema.approvalRecords.outcome, query.outcome))
+      if (query.from !== undefined) conditions.push(gte(schema.approvalRecords.decidedAt, query.from))
+      if (query.to !== undefined) conditions.push(lt(schema.approvalRecords.decidedAt, query.to))
+
+      const rows = await db
+        .select()
+        .from(schema.approvalRecords)
+        .where(conditions.length > 0 ? and(...conditions) : undefined)
+        .orderBy(desc(schema.approvalRecords.decidedAt))
+        .limit(query.limit ?? 500)
+      return rows.map(toApprovalRecord)
+    },
+  }
+}
+
+// --- Model pricing ---
+
+function toModelPriceRecord(row: typeof schema.modelPrices.$inferSelect): ModelPriceRecord {
+  return {
+    modelId: row.modelId,
+    input: row.input,
+    output: row.output,
+    cacheRead: row.cacheRead ?? null,
+    cacheWrite: row.cacheWrite ?? null,
+    source: row.source as ModelPriceSource,
+    updatedAt: row.updatedAt,
+  }
+}
+
+function createModelPriceRepo(db: PgDrizzleInstance): ModelPriceRepository {
+  return {
+    async list(): Promise<ModelPriceRecord[]> {
+      const rows = await db.select().from(schema.modelPrices).orderBy(asc(schema.modelPrices.modelId))
+      return rows.map(toModelPriceRecord)
+    },
+
+    async seed(prices: UpsertModelPriceInput[]): Promise<number> {
+      if (prices.length === 0) return 0
+      const updatedAt = Date.now()
+      const inserted = await db.insert(schema.modelPrices)
+        .values(prices.map((price) => ({ ...price, updatedAt })))
+        .onConflictDoNothing()
+        .returning({ modelId: schema.modelPrices.modelId })
+      return inserted.length
+    },
+
+    async upsert(input: UpsertModelPriceInput): Promise<ModelPriceRecord> {
+      const row = { ...input, cacheRead: input.cacheRead ?? null, cacheWrite: input.cacheWrite ?? null, updatedAt: Date.now() }
+      await db.insert(schema.modelPrices)
+        .values(row)
+        .onConflictDoUpdate({
+          target: schema.modelPrices.modelId,
+          set: {
+            input: row.input,
+            output: row.output,
+            cacheRead: row.cacheRead,
+            cacheWrite: row.cacheWrite,
+            source: row.source,
+            updatedAt: row.updatedAt,
+          },
+        })
+      return toModelPriceRecord(row)
+    },
+
+    async delete(modelId: string): Promise<boolean> {
+      const deleted = await db.delete(schema.modelPrices)
+        .where(eq(schema.modelPrices.modelId, modelId))
+        .returning({ modelId: schema.modelPrices.modelId })
+      return deleted.length > 0
+    },
+  }
+}
+
+// --- Model catalog ---
+
+function toModelCatalogRecord(row: typeof schema.modelCatalog.$inferSelect): ModelCatalogRecord {
+  return {
+    provider: row.provider,
+    modelId: row.modelId,
+    displayName: row.displayName ?? null,
+    contextWindow: row.contextWindow ?? null,
+    maxOutputTokens: row.maxOutputTokens ?? null,
+    vision: row.vision ?? null,
+    structuredOutputs: row.structuredOutputs ?? null,
+    tools: row.tools ?? null,
+    fetchedAt: row.fetchedAt,
+  }
+}
+
+function createModelCatalogRepo(db: PgDrizzleInstance): ModelCatalogRepository {
+  return {
+    async list(): Promise<ModelCatalogRecord[]> {
+      const rows = await db.select().from(schema.modelCatalog)
+        .orderBy(asc(schema.modelCatalog.provider), asc(schema.modelCatalog.modelId))
+      return rows.map(toModelCatalogRecord)
+    },
+
+    async replace(provider: string, models: ModelCatalogEntry[]): Promise<void> {
+      const fetchedAt = Date.now()
+      await db.transaction(async (tx) => {
+        await tx.delete(schema.modelCatalog).where(eq(schema.modelCatalog.provider, provider))
+        if (models.length === 0) return
+        await tx.insert(schema.modelCatalog)
+          .values(models.map((model) => ({ ...model, provider, fetchedAt })))
+          .onConflictDoNothing()
+      })
+    },
+  }
+}
+
+// --- Uploads ---
+
+function toUploadRecord(row: typeof schema.uploads.$inferSelect): UploadRecord {
+  return { ...row, kind: row.kind as UploadKind }
+}
+
+/** Matches serialized content blocks that reference a payload; hashes are hex, so nothing needs escaping. */
+function hashPattern(hash: string): string {
+  return `%"hash":"${hash}"%`
+}
+
+function createUploadRepo(db: PgDrizzleInstance): UploadRepository {
+  const byKey = (userId: string, hash: string) => and(eq(schema.uploads.userId, userId), eq(schema.uploads.hash, hash))
+
+  return {
+    async record(input: RecordUploadInput): Promise<UploadRecord> {
+      const now = Date.now()
+      const [row] = await db.insert(schema.uploads)
+        .values({ ...input, createdAt: now, lastUsedAt: now })
+        .onConflictDoUpdate({ target: [schema.uploads.userId, schema.uploads.hash], set: { lastUsedAt: now } })
+        .returning()
+      return toUploadRecord(row)
+    },
+
+    async get(userId: string, hash: string): Promise<UploadRecord | null> {
+      const [row] = await db.select().from(schema.uploads).where(byKey(userId, hash)).limit(1)
+      return row ? toUploadRecord(row) : null
+    },
+
+    async listByUser(userId: string): Promise<UploadRecord[]> {
+      const rows = await db.select().from(schema.uploads)
+        .where(eq(schema.uploads.userId, userId))
+        .orderBy(desc(schema.uploads.lastUsedAt))
+      return rows.map(toUploadRecord)
+    },
+
+    async touch(userId: string, hash: string): Promise<void> {
+      await db.update(schema.uploads).set({ lastUsedAt: Date.now() }).where(byKey(userId, hash))
+    },
+
+    async delete(userId: string, hash: string): Promise<boolean> {
+      const deleted = await db.delete(schema.uploads).where(byKey(userId, hash)).returning({ hash: schema.uploads.hash })
+      return deleted.length > 0
+    },
+
+    async countMessageReferences(userId: string, hash: string): Promise<number> {
+      const [row] = await db.select({ count: count() }).from(schema.items)
+        .innerJoin(schema.agents, eq(schema.agents.id, schema.items.agentId))
+        .innerJoin(schema.sessions, eq(schema.sessions.id, schema.agents.sessionId))
+        .where(and(eq(schema.sessions.userId, userId), sql`${schema.items.contentBlocks} LIKE ${hashPattern(hash)}`))
+      return row?.count ?? 0
+    },
+
+    async countAllReferences(hash: string): Promise<number> {
+      const [uploads] = await db.select({ count: count() }).from(schema.uploads).where(eq(schema.uploads.hash, hash))
+      const [items] = await db.select({ count: count() }).from(schema.items)
+        .where(sql`${schema.items.contentBlocks} LIKE ${hashPattern(hash)}`)
+      const [revisions] = await db.select({ count: count() }).from(schema.messageRevisions)
+        .where(sql`${schema.messageRevisions.contentBlocks} LIKE ${hashPattern(hash)}`)
+      return (uploads?.count ?? 0) + (items?.count ?? 0) + (revisions?.count ?? 0)
+    },
+  }
+}
+
+// --- Memories ---
+
+function toMemoryRecord(row: typeof schema.memories.$inferSelect): MemoryRecord {
+  return {
+    ...row,
+    kind: row.kind as MemoryKind,
+    category: row.category as MemoryCategory,
+    embedding: row.embedding ? JSON.parse(row.embedding) as number[] : null,
+  }
+}
+
+function createMemoryRepo(db: PgDrizzleInstance): MemoryRepository {
+  return {
+    async create(input: CreateMemoryInput): Promise<MemoryRecord> {
+      const now = Date.now()
+      const [row] = await db.insert(schema.memories).values({
+        id: uuid(),
+        userId: input.userId,
+        content: input.content,
+        kind: input.kind,
+        category: input.category ?? 'other',
+        confidence: input.confidence,
+        embedding: input.embedding ? JSON.stringify(input.embedding) : null,
+        embeddingModel: input.embeddingModel ?? null,
+        sourceSessionId: input.sourceSessionId ?? null,
+        sourceItemId: input.sourceItemId ?? null,
+        createdAt: now,
+        updatedAt: now,
+      }).returning()
+      return toMemoryRecord(row)
+    },
+
+    async getById(id: string): Promise<MemoryRecord | null> {
+      const [row] = await db.select().from(schema.memories).where(eq(schema.memories.id, id)).limit(1)
+      return row ? toMemoryRecord(row) : null
+    },
+
+    async listByUser(userId: string): Promise<MemoryRecord[]> {
+      const rows = await db.select().from(schema.memories)
+        .where(eq(schema.memories.userId, userId))
+        .orderBy(desc(schema.memories.updatedAt))
+      return rows.map(toMemoryRecord)
+    },
+
+    async update(id: string, input: UpdateMemoryInput): Promise<MemoryRecord | null> {
+      const updates: Record<string, unknown> = { updatedAt: Date.now() }
+      if (input.content !== undefined) updates.content = input.content
+      if (input.kind !== undefined) updates.kind = input.kind
+      if (input.category !== undefined) updates.category = input.category
+      if (input.confidence !== undefined) updates.confidence = input.confidence
+      if (input.embedding !== undefined) updates.embedding = input.embedding ? JSON.stringify(input.embedding) : null
+      if (input.embeddingModel !== undefined) updates.embeddingModel = input.embeddingModel
+      if (input.sourceSessionId !== undefined) updates.sourceSessionId = input.sourceSessionId
+      if (input.sourceItemId !== undefined) updates.sourceItemId = input.sourceItemId
+      if (input.disabled !== undefined) updates.disabled = input.disabled
+      const [row] = await db.update(schema.memories).set(updates).where(eq(schema.memories.id, id)).returning()
+      return row ? toMemoryRecord(row) : null
+    },
+
+    async delete(id: string): Promise<boolean> {
+      const deleted = await db.delete(schema.memories).where(eq(schema.memories.id, id)).returning({ id: schema.memories.id })
+      return deleted.length > 0
+    },
+  }
+}
+
+// --- Knowledge base ---
+
+function toKnowledgeSource(row: typeof schema.knowledgeSources.$inferSelect): KnowledgeSource {
+  return {
+    ...row,
+    kind: row.kind as KnowledgeSourceKind,
+    status: row.status as KnowledgeSourceStatus,
+    documents: JSON.parse(row.documents) as KnowledgeDocument[],
+  }
+}
+
+function createKnowledgeSourceRepo(db: PgDrizzleInstance): KnowledgeSourceRepository {
+  return {
+    async create(input: CreateKnowledgeSourceInput): Promise<KnowledgeSource> {
+      const now = Date.now()
+      const [row] = await db.insert(schema.knowledgeSources).values({
+        id: uuid(),
+        userId: input.userId,
+        kind: input.kind,
+        location: input.location,
+        name: input.name,
+        status: 'pending',
+        error: null,
+        documents: '[]',
+        indexedAt: null,
+        createdAt: now,
+        updatedAt: now,
+      }).returning()
+      return toKnowledgeSource(row)
+    },
+
+    async getById(id: string): Promise<KnowledgeSource | null> {
+      const [row] = await db.select().from(schema.knowledgeSources).where(eq(schema.knowledgeSources.id, id)).limit(1)
+      return row ? toKnowledgeSource(row) : null
+    },
+
+    async listByUser(userId: string): Promise<KnowledgeSource[]> {
+      const rows = await db.select().from(schema.knowledgeSources)
+        .where(eq(schema.knowledgeSources.userId, userId))
+        .orderBy(asc(schema.knowledgeSources.createdAt))
+      return rows.map(toKnowledgeSource)
+    },
+
+    async listByStatus(statuses: KnowledgeSourceStatus[]): Promise<KnowledgeSource[]> {
+      if (statuses.length === 0) return []
+      const rows = await db.select().from(schema.knowledgeSources)
+        .where(inArray(schema.knowledgeSources.status, statuses))
+        .orderBy(asc(schema.knowledgeSources.createdAt))
+      return rows.map(toKnowledgeSource)
+    },
+
+    async update(id: string, input: UpdateKnowledgeSourceInput): Promise<KnowledgeSource | null> {
+      const updates: Record<string, unknown> = { updatedAt: Date.now() }
+      if (input.name !== undefined) updates.name = input.name
+      if (input.status !== undefined) updates.status = input.status
+      if (input.error !== undefined) updates.error = input.error
+      if (input.documents !== undefined) updates.documents = JSON.stringify(input.documents)
+      if (input.indexedAt !== undefined) updates.indexedAt = input.indexedAt
+      const [row] = await db.update(schema.knowledgeSources).set(updates).where(eq(schema.knowledgeSources.id, id)).returning()
+      return row ? toKnowledgeSource(row) : null
+    },
+
+    async delete(id: string): Promise<boolean> {
+      const deleted = await db.delete(schema.knowledgeSources).where(eq(schema.knowledgeSources.id, id)).returning({ id: schema.knowledgeSources.id })
+      return deleted.length > 0
+    },
+
+    async listDocumentHashes(): Promise<string[]> {
+      const rows = await db.select({ documents: schema.knowledgeSources.documents }).from(schema.knowledgeSources)
+      return [...new Set(rows.flatMap((row) => (JSON.parse(row.documents) as KnowledgeDocument[]).map((doc) => doc.hash)))]
+    },
+  }
+}
+
+// --- Entities ---
+
+function toEntityRecord(row: typeof schema.entities.$inferSelect): EntityRecord {
+  return {
+    ...row,
+    kind: row.kind as EntityKind,
+    details: JSON.parse(row.details) as Record<string, string>,
+    embedding: row.embedding ? JSON.parse(row.embedding) as number[] : null,
+  }
+}
+
+function createEntityRepo(db: PgDrizzleInstance): EntityRepository {
+  return {
+    async create(input: CreateEntityInput): Promise<EntityRecord> {
+      const now = Date.now()
+      const [row] = await db.insert(schema.entities).values({
+        id: uuid(),
+        userId: input.userId,
+        kind: input.kind,
+        key: input.key,
+        name: input.name,
+        email: input.email ?? null,
+        details: JSON.stringify(input.details ?? {}),
+        mentions: 1,
+        embedding: null,
+        embeddingModel: null,
+        lastSeenAt: now,
+        createdAt: now,
+        updatedAt: now,
+      }).returning()
+      return toEntityRecord(row)
+    },
+
+    async getById(id: string): Promise<EntityRecord | null> {
+      const [row] = await db.select().from(schema.entities).where(eq(schema.entities.id, id)).limit(1)
+      return row ? toEntityRecord(row) : null
+    },
+
+    async findByKey(userId: string, kind: EntityKind, key: string): Promise<EntityRecord | null> {
+      const [row] = await db.select().from(schema.entities)
+        .where(and(eq(schema.entities.userId, userId), eq(schema.entities.kind, kind), eq(schema.entities.key, key)))
+        .limit(1)
+      return row ? toEntityRecord(row) : null
+    },
+
+    async listByUser(userId: string): Promise<EntityRecord[]> {
+      const rows = await db.select().from(schema.entities)
+        .where(eq(schema.entities.userId, userId))
+        .orderBy(desc(schema.entities.lastSeenAt))
+      return rows.map(toEntityRecord)
+    },
+
+    async update(id: string, input: UpdateEntityInput): Promise<EntityRecord | null> {
+      const updates: Record<string, unknown> = { updatedAt: Date.now() }
+      if (input.name !== undefined) updates.name = input.name
+      if (input.email !== undefined) updates.email = input.email
+      if (input.details !== undefined) updates.details = JSON.stringify(input.details)
+      if (input.mentions !== undefined) updates.mentions = input.mentions
+      if (input.embedding !== undefined) updates.embedding = input.embedding ? JSON.stringify(input.embedding) : null
+      if (input.embeddingModel !== undefined) updates.embeddingModel = input.embeddingModel
+      if (input.lastSeenAt !== undefined) updates.lastSeenAt = input.lastSeenAt
+      const [row] = await db.update(schema.entities).set(updates).where(eq(schema.entities.id, id)).returning()
+      return row ? toEntityRecord(row) : null
+    },
+
+    async delete(id: string): Promise<boolean> {
+      const deleted = await db.delete(schema.entities).where(eq(schema.entities.id, id)).returning({ id: schema.entities.id })
+      return deleted.length > 0
+    },
+  }
+}
+
+// --- Feeds ---
+
+function createFeedRepo(db: PgDrizzleInstance): FeedRepository {
+  return {
+    async create(input: CreateFeedInput): Promise<Feed> {
+      const now = Date.now()
+      const [row] = await db.insert(schema.feeds).values({
+        id: uuid(),
+        userId: input.userId,
+        url: input.url,
+        title: input.title,
+        siteUrl: input.siteUrl ?? null,
+        etag: null,
+        lastModified: null,
+        error: null,
+        fetchedAt: null,
+        createdAt: now,
+        updatedAt: now,
+      }).returning()
+      return row
+    },
+
+    async getById(id: string): Promise<Feed | null> {
+      const [row] = await db.select().from(schema.feeds).where(eq(schema.feeds.id, id)).limit(1)
+      return row ?? null
+    },
+
+    async findByUrl(userId: string, url: string): Promise<Feed | null> {
+      const [row] = await db.select().from(schema.feeds)
+        .where(and(eq(schema.feeds.userId, userId), eq(schema.feeds.url, url)))
+        .limit(1)
+      return row ?? null
+    },
+
+    async listByUser(userId: string): Promise<Feed[]> {
+      return db.select().from(schema.feeds)
+        .where(eq(schema.feeds.userId, userId))
+        .orderBy(asc(schema.feeds.createdAt))
+    },
+
+    async listAll(): Promise<Feed[]> {
+      return db.select().from(schema.feeds).orderBy(asc(schema.feeds.createdAt))
+    },
+
+    async update(id: string, input: UpdateFeedInput): Promise<Feed | null> {
+      const updates: Record<string, unknown> = { updatedAt: Date.now() }
+      if (input.title !== undefined) updates.title = input.title
+      if (input.siteUrl !== undefined) updates.siteUrl = input.siteUrl
+      if (input.etag !== undefined) updates.etag = input.etag
+      if (input.lastModified !== undefined) updates.lastModified = input.lastModified
+      if (input.error !== undefined) updates.error = input.error
+      if (input.fetchedAt !== undefined) updates.fetchedAt = input.fetchedAt
+      const [row] = await db.update(schema.feeds).set(updates).where(eq(schema.feeds.id, id)).returning()
+      return row ?? null
+    },
+
+    async delete(id: string): Promise<boolean> {
+      return db.transaction(async (tx) => {
+        await tx.delete(schema.feedItems).where(eq(schema.feedItems.feedId, id))
+        const deleted = await tx.delete(schema.feeds).where(eq(schema.feeds.id, id)).returning({ id: schema.feeds.id })
+        return deleted.length > 0
+      })
+    },
+
+    async addItems(feedId: string, items: NewFeedItem[]): Promise<number> {
+      if (items.length === 0) return 0
+      const createdAt = Date.now()
+      const inserted = await db.insert(schema.feedItems)
+        .values(items.map((item) => ({ ...item, id: uuid(), feedId, createdAt })))
+        .onConflictDoNothing()
+        .returning({ id: schema.feedItems.id })
+      return inserted.length
+    },
+
+    async getItem(id: string): Promise<FeedItem | null> {
+      const [row] = await db.select().from(schema.feedItems).where(eq(schema.feedItems.id, id)).limit(1)
+      return row ?? null
+    },
+
+    async listItems(feedIds: string[], options: { since?: number; limit: number }): Promise<FeedItem[]> {
+      if (feedIds.length === 0) return []
+      const conditions = [inArray(schema.feedItems.feedId, feedIds)]
+      if (options.since !== undefined) conditions.push(gte(schema.feedItems.publishedAt, options.since))
+      return db.select().from(schema.feedItems)
+        .where(and(...conditions))
+        .orderBy(desc(schema.feedItems.publishedAt))
+        .limit(options.limit)
+    },
+
+    async pruneItems(feedId: string, keep: number): Promise<number> {
+      const stale = await db.select({ id: schema.feedItems.id }).from(schema.feedItems)
+        .where(eq(schema.feedItems.feedId, feedId))
+        .orderBy(desc(schema.feedItems.publishedAt))
+        .offset(keep)
+      if (stale.length === 0) return 0
+      const deleted = await db.delete(schema.feedItems)
+        .where(inArray(schema.feedItems.id, stale.map((row) => row.id)))
+        .returning({ id: schema.feedItems.id })
+      return deleted.length
+    },
+  }
+}
+
+// --- Scratchpad ---
+
+function createScratchpadRepo(db: PgDrizzleInstance): ScratchpadRepository {
+  const entry = (sessionId: string, key: string) =>
+    and(eq(schema.scratchpadEntries.sessionId, sessionId), eq(schema.scratchpadEntries.key, key))
+
+  return {
+    async get(sessionId: string, key: string): Promise<ScratchpadEntry | null> {
+      const [row] = await db.select().from(schema.scratchpadEntries).where(entry(sessionId, key)).limit(1)
+      return row ?? null
+    },
+
+    async list(sessionId: string): Promise<ScratchpadEntry[]> {
+      return db.select().from(schema.scratchpadEntries)
+        .where(eq(schema.scratchpadEntries.sessionId, sessionId))
+        .orderBy(asc(schema.scratchpadEntries.key))
+    },
+
+    async set(sessionId: string, key: string, value: string): Promise<ScratchpadEntry> {
+      const [row] = await db.insert(schema.scratchpadEntries)
+        .values({ sessionId, key, value, updatedAt: Date.now() })
+        .onConflictDoUpdate({
+          target: [schema.scratchpadEntries.sessionId, schema.scratchpadEntries.key],
+          set: { value, updatedAt: Date.now() },
+        })
+        .returning()
+      return row
+    },
+
+    async delete(sessionId: string, key: string): Promise<boolean> {
+      const deleted = await db.delete(schema.scratchpadEntries).where(entry(sessionId, key))
+        .returning({ key: schema.scratchpadEntries.key })
+      return deleted.length > 0
+    },
+  }
+}
+
+// --- Controller prompts ---
+
+function createControllerPromptRepo(db: PgDrizzleInstance): ControllerPromptRepository {
+  const table = schema.controllerPromptVersions
+
+  return {
+    async listActive(): Promise<ControllerPromptVersion[]> {
+      const rows = await db.select().from(table).orderBy(asc(table.name), desc(table.version))
+      return rows.filter((row, index) => index === 0 || rows[index - 1].name !== row.name)
+    },
+
+    async listVersions(name: string): Promise<ControllerPromptVersion[]> {
+      return db.select().from(table).where(eq(table.name, name)).orderBy(desc(table.version))
+    },
+
+    async addVersion(name: string, content: string): Promise<ControllerPromptVersion> {
+      return db.transaction(async (tx) => {
+        const [latest] = await tx.select({ version: max(table.version) }).from(table).where(eq(table.name, name))
+        const [row] = await tx.insert(table)
+          .values({ name, version: (latest?.version ?? 0) + 1, content, createdAt: Date.now() })
+          .returning()
+        return row
+      })
+    },
+  }
+}
+
+// --- Experiments ---
+
+function createExperimentRepo(db: PgDrizzleInstance): ExperimentRepository {
+  const table = schema.experimentSessions
+
+  return {
+    async assign(input: Omit<ExperimentSession, 'createdAt'>): Promise<ExperimentSession> {
+      const [row] = await db.insert(table).values({ ...input, createdAt: Date.now() }).returning()
+      return row
+    },
+
+    async getBySession(sessionId: string): Promise<ExperimentSession | null> {
+      const [row] = await db.select().from(table).where(eq(table.sessionId, sessionId))
+      return row ?? null
+    },
+
+    async listByExperiment(experimentId: string): Promise<ExperimentSession[]> {
+      return db.select().from(table).where(eq(table.experimentId, experimentId)).orderBy(asc(table.createdAt))
+    },
+  }
+}
+
+// --- Orphans ---
+
+function createOrphanRepo(db: PgDrizzleInstance): OrphanRepository {
+  const where = {
+    toolOutputs: sql`NOT EXISTS (SELECT 1 FROM ${schema.items} WHERE ${schema.items.callId} = ${schema.toolOutputs.callId})`,
+    agents: and(
+      inArray(schema.agents.status, ['completed', 'failed', 'cancelled']),
+      sql`NOT EXISTS (SELECT 1 FROM ${schema.items} WHERE ${schema.items.agentId} = ${schema.agents.id})`,
+      sql`NOT EXISTS (SELECT 1 FROM agents child WHERE child.parent_id = ${schema.agents.id})`,
+      sql`NOT EXISTS (SELECT 1 FROM ${schema.toolOutputs} WHERE ${schema.toolOutputs.agentId} = ${schema.agents.id})`,
+      sql`NOT EXISTS (SELECT 1 FROM ${schema.workflowRuns} WHERE ${schema.workflowRuns.triggerAgentId} = ${schema.agents.id})`,
+    ),
+    usageRecords: and(
+      isNotNull(schema.usageRecords.sessionId),
+      sql`NOT EXISTS (SELECT 1 FROM ${schema.sessions} WHERE ${schema.sessions.id} = ${schema.usageRecords.sessionId})`,
+    ),
+    messageRevisions: sql`NOT EXISTS (SELECT 1 FROM ${schema.items} WHERE ${schema.items.id} = ${schema.messageRevisions.itemId})`,
+  }
+
+  return {
+    async count(): Promise<OrphanCounts> {
+      const n = (rows: Array<{ n: number }>) => rows[0]?.n ?? 0
+      return {
+        toolOutputs: n(await db.select({ n: count() }).from(schema.toolOutputs).where(where.toolOutputs)),
+        agents: n(await db.select({ n: count() }).from(schema.agents).where(where.agents)),
+        usageRecords: n(await db.select({ n: count() }).from(schema.usageRecords).where(where.usageRecords)),
+        messageRevisions: n(await db.select({ n: count() }).from(schema.messageRevisions).where(where.messageRevisions)),
+      }
+    },
+
+    async purge(): Promise<OrphanCounts> {
+      return db.transaction(async (tx) => ({
+        toolOutputs: (await tx.delete(schema.toolOutputs).where(where.toolOutputs).returning({ id: schema.toolOutputs.id })).length,
+        agents: (await tx.delete(schema.agents).where(where.agents).returning({ id: schema.agents.id })).length,
+        usageRecords: (await tx.delete(schema.usageRecords).where(where.usageRecords).returning({ id: schema.usageRecords.id })).length,
+        messageRevisions: (await tx.delete(schema.messageRevisions).where(where.messageRevisions).returning({ id: schema.messageRevisions.id })).length,
+      }))
+    },
+
+    async listSessionIds(): Promise<string[]> {
+      return (await db.select({ id: schema.sessions.id }).from(schema.sessions)).map((row) => row.id)
+    },
+
+    async listAttachmentHashes(): Promise<string[]> {
+      const rows = [
+        ...await db.select({ blocks: schema.items.contentBlocks }).from(schema.items)
+          .where(sql`${schema.items.contentBlocks} LIKE '%"hash":%'`),
+        ...await db.select({ blocks: schema.messageRevisions.contentBlocks }).from(schema.messageRevisions)
+          .where(sql`${schema.messageRevisions.contentBlocks} LIKE '%"hash":%'`),
+      ]
+      const library = await db.selectDistinct({ hash: schema.uploads.hash }).from(schema.uploads)
+      return [...new Set([...collectAttachmentHashes(rows.map((row) => row.blocks)), ...library.map((row) => row.hash)])]
+    },
+  }
+}
+
+function createMaintenanceRepo(db: PgDrizzleInstance): MaintenanceRepository {
+  const sizeBytes = async () => {
+    const rows = await db.execute<{ size: string }>(sql`SELECT pg_database_size(current_database())::text AS size`)
+    return Number(rows[0]?.size ?? 0)
+  }
+
+  return {
+    async run(): Promise<DatabaseMaintenanceReport> {
+      const sizeBytesBefore = await sizeBytes()
+      // Full-text search in postgres lives in GIN indexes
+      const ginIndexes = await db.execute<{ indexname: string }>(sql`
+        SELECT indexname FROM pg_indexes
+        WHERE schemaname = current_schema() AND indexdef ILIKE '%USING gin%'
+      `)
+      for (const { indexname } of ginIndexes) {
+        await db.execute(sql.raw(`REINDEX INDEX "${indexname.replace(/"/g, '""')}"`))
+      }
+      await db.execute(sql`VACUUM (ANALYZE)`)
+      return {
+        dialect: 'postgres',
+        sizeBytesBefore,
+        sizeBytesAfter: await sizeBytes(),
+        reindexed: ginIndexes.map((index) => index.indexname),
+        integrity: null,
+      }
+    },
+
+    sizeBytes,
+  }
+}
+
+function createRetentionRepo(db: PgDrizzleInstance): RetentionRepository {
+  const staleOutputs = (before: number, placeholder: string) => and(
+    eq(schema.items.type, 'function_call_output'),
+    lt(schema.items.createdAt, before),
+    isNotNull(schema.items.output),
+    ne(schema.items.output, placeholder),
+  )
+
+  return {
+    async listInactiveSessions(before: number): Promise<InactiveSession[]> {
+      const rows = await db
+        .select({
+          id: schema.sessions.id,
+          title: schema.sessions.title,
+          updatedAt: schema.sessions.updatedAt,
+          latestItemAt: max(schema.items.createdAt),
+          itemCount: count(schema.items.id),
+        })
+        .from(schema.sessions)
+        .leftJoin(schema.agents, eq(schema.agents.sessionId, schema.sessions.id))
+        .leftJoin(schema.items, eq(schema.items.agentId, schema.agents.id))
+        .where(lt(schema.sessions.updatedAt, before))
+        .groupBy(schema.sessions.id)
+      return rows
+        .map((row) => ({
+          id: row.id,
+          title: row.title,
+          lastActivityAt: Math.max(row.updatedAt, row.latestItemAt ?? 0),
+          itemCount: row.itemCount,
+        }))
+        .filter((session) => session.lastActivityAt < before)
+    },
+
+    async countToolOutputs(before: number, placeholder: string): Promise<number> {
+      const [stored] = await db
+        .select({ n: count() })
+        .from(schema.toolOutputs)
+        .where(lt(schema.toolOutputs.createdAt, before))
+      const [inline] = await db
+        .select({ n: count() })
+        .from(schema.items)
+        .where(staleOutputs(before, placeholder))
+      return (stored?.n ?? 0) + (inline?.n ?? 0)
+    },
+
+    async purgeToolOutputs(before: number, placeholder: string): Promise<number> {
+      return db.transaction(async (tx) => {
+        const stored = await tx
+          .delete(schema.toolOutputs)
+          .where(lt(schema.toolOutputs.createdAt, before))
+          .returning({ id: schema.toolOutputs.id })
+        const inline = await tx
+          .update(schema.items)
+          .set({ output: placeholder })
+          .where(staleOutputs(before, placeholder))
+          .returning({ id: schema.items.id })
+        return stored.length + inline.length
+      })
+    },
+
+    async countUsage(before: number): Promise<number> {
+      const [row] = await db
+        .select({ n: count() })
+        .from(schema.usageRecords)
+        .where(lt(schema.usageRecords.createdAt, before))
+      return row?.n ?? 0
+    },
+
+    async purgeUsage(before: number): Promise<number> {
+      const rows = await db
+        .delete(schema.usageRecords)
+        .where(lt(schema.usageRecords.createdAt, before))
+        .returning({ id: schema.usageRecords.id })
+      return rows.length
+    },
+  }
+}
+
+/** Preference keys that hold per-device sync state and must never replicate. */
+const LOCAL_PREFERENCE_PREFIX = 'sync:'
+
+function createSyncRepo(db: PgDrizzleInstance): SyncRepository {
+  const sessions = createSessionRepo(db)
+
+  const rowExists = async (kind: 'session' | 'agent' | 'item', id: string): Promise<boolean> => {
+    switch (kind) {
+      case 'session':
+        return (await db.select({ id: schema.sessions.id }).from(schema.sessions).where(eq(schema.sessions.id, id)).limit(1)).length > 0
+      case 'agent':
+        return (await db.select({ id: schema.agents.id }).from(schema.agents).where(eq(schema.agents.id, id)).limit(1)).length > 0
+      case 'item':
+        return (await db.select({ id: schema.items.id }).from(schema.items).where(eq(schema.items.id, id)).limit(1)).length > 0
+    }
+  }
+
+  return {
+    async listChangesSince(since: number): Promise<SyncRecord[]> {
+      const sessionRows = await db.select().from(schema.sessions)
+        .where(sql`${schema.sessions.updatedAt} >= ${since}`)
+        .orderBy(asc(schema.sessions.createdAt))
+      const agentRows = await db.select().from(schema.agents)
+        .where(sql`${schema.agents.updatedAt} >= ${since}`)
+        .orderBy(asc(schema.agents.createdAt))
+      const itemRows = await db.select().from(schema.items)
+        .where(sql`coalesce(${schema.items.updatedAt}, ${schema.items.createdAt}) >= ${since}`)
+        .orderBy(asc(schema.items.createdAt), asc(schema.items.sequence))
+      const preferenceRows = (await db.select().from(schema.preferences)
+        .where(sql`${schema.preferences.updatedAt} >= ${since}`))
+        .filter((row) => !row.key.startsWith(LOCAL_PREFERENCE_PREFIX))
+
+      return [
+        ...sessionRows.map((row) => ({ kind: 'session' as const, id: row.id, updatedAt: row.updatedAt ?? 0, data: row })),
+        ...agentRows.map((row) => ({ kind: 'agent' as const, id: row.id, updatedAt: row.updatedAt ?? 0, data: row })),
+        ...itemRows.map((row) => ({ kind: 'item' as const, id: row.id, updatedAt: row.updatedAt ?? row.createdAt, data: row })),
+        ...preferenceRows.map((row) => ({ kind: 'preference' as const, id: row.key, updatedAt: row.updatedAt ?? 0, data: row })),
+      ]
+    },
+
+    async applyRecord(record: SyncRecord, localUserId: string): Promise<boolean> {
+      switch (record.kind) {
+        case 'session': {
+          const [existing] = await db.select().from(schema.sessions).where(eq(schema.sessions.id, record.id)).limit(1)
+          if (existing && (existing.updatedAt ?? 0) >= record.updatedAt) return false
+          if (record.deleted) {
+            if (existing) await sessions.delete(record.id)
+            return Boolean(existing)
+          }
+          const row = { ...(record.data as typeof schema.sessions.$inferSelect), userId: localUserId }
+          await db.insert(schema.sessions).values(row)
+            .onConflictDoUpdate({ target: schema.sessions.id, set: row })
+          return true
+        }
+        case 'agent': {
+          const [existing] = await db.select().from(schema.agents).where(eq(schema.agents.id, record.id)).limit(1)
+          if (existing && (existing.updatedAt ?? 0) >= record.updatedAt) return false
+          const row = record.data as typeof schema.agents.$inferSelect
+          if (!row.sessionId || !(await rowExists('session', row.sessionId))) return false
+          await db.insert(schema.agents).values(row)
+            .onConflictDoUpdate({ target: schema.agents.id, set: row })
+          return true
+        }
+        case 'item': {
+          const [existing] = await db.select().from(schema.items).where(eq(schema.items.id, record.id)).limit(1)
+          if (existing && (existing.updatedAt ?? existing.createdAt) >= record.updatedAt) return false
+          const row = record.data as typeof schema.items.$inferSelect
+          if (!row.agentId || !(await rowExists('agent', row.agentId))) return false
+          await db.insert(schema.items).values(row)
+            .onConflictDoUpdate({ target: schema.items.id, set: row })
+          return true
+        }
+        case 'preference': {
+          if (record.id.startsWith(LOCAL_PREFERENCE_PREFIX)) return false
+          const [existing] = await db.select().from(schema.preferences).where(eq(schema.preferences.key, record.id)).limit(1)
+          if (existing && (existing.updatedAt ?? 0) >= record.updatedAt) return false
+          if (record.deleted) {
+            await db.delete(schema.preferences).where(eq(schema.preferences.key, record.id))
+            return Boolean(existing)
+          }
+          const row = record.data as typeof schema.preferences.$inferSelect
+          await db.insert(schema.preferences).values(row)
+            .onConflictDoUpdate({ target: schema.preferences.key, set: { value: row.value, updatedAt: row.updatedAt } })
+          return true
+        }
+      }
+    },
+  }
+}
+
+export interface CreatePgDatabaseOptions {
+  poolSize?: number
+  /** Seconds to wait when opening a new connection to the server. Queries that find every pooled connection busy queue without a limit. */
+  connectTimeoutSeconds?: number
+}
+
+export async function createPgDatabase(
+  url: string,
+  options: CreatePgDatabaseOptions = {},
+): Promise<{ db: PgDrizzleInstance; close: () => Promise<void> }> {
+  const client = postgres(url, {
+    max: options.poolSize ?? 10,
+    connect_timeout: options.connectTimeoutSeconds ?? 30,
+    onnotice: () => {},
+  })
   await ensurePgSchema(client)
   const db = drizzle(client, { schema })
   return { db, close: async () => { await client.end() } }
@@ -1078,6 +2301,23 @@ export class PostgresRepositories {
   workflowRuns: WorkflowRunRepository
   mcp: McpRepository
   telegram: TelegramRepository
+  sync: SyncRepository
+  usage: UsageRepository
+  approvalHistory: ApprovalHistoryRepository
+  modelPrices: ModelPriceRepository
+  modelCatalog: ModelCatalogRepository
+  uploads: UploadRepository
+  memories: MemoryRepository
+  knowledge: KnowledgeSourceRepository
+  scratchpad: ScratchpadRepository
+  controllerPrompts: ControllerPromptRepository
+  experiments: ExperimentRepository
+  entities: EntityRepository
+  feeds: FeedRepository
+  retention: RetentionRepository
+  messageRevisions: MessageRevisionRepository
+  maintenance: MaintenanceRepository
+  orphans: OrphanRepository
 
   constructor(db: PgDrizzleInstance, encryptionKey?: string) {
     const encKey = encryptionKey ? deriveKey(encryptionKey) : null
@@ -1093,6 +2333,23 @@ export class PostgresRepositories {
     this.workflowRuns = createWorkflowRunRepo(db)
     this.mcp = createMcpRepo(db, encKey)
     this.telegram = createTelegramRepo(db)
+    this.sync = createSyncRepo(db)
+    this.usage = createUsageRepo(db)
+    this.approvalHistory = createApprovalHistoryRepo(db)
+    this.modelPrices = createModelPriceRepo(db)
+    this.modelCatalog = createModelCatalogRepo(db)
+    this.uploads = createUploadRepo(db)
+    this.memories = createMemoryRepo(db)
+    this.knowledge = createKnowledgeSourceRepo(db)
+    this.scratchpad = createScratchpadRepo(db)
+    this.controllerPrompts = createControllerPromptRepo(db)
+    this.experiments = createExperimentRepo(db)
+    this.entities = createEntityRepo(db)
+    this.feeds = createFeedRepo(db)
+    this.retention = createRetentionRepo(db)
+    this.messageRevisions = createMessageRevisionRepo(db)
+    this.maintenance = createMaintenanceRepo(db)
+    this.orphans = createOrphanRepo(db)
   }
 }
 
@@ -1115,6 +2372,7 @@ export async function ensurePgSchema(client: PgClient): Promise<void> {
       title TEXT,
       summary TEXT,
       status TEXT DEFAULT 'active',
+      deleted_at BIGINT,
       created_at BIGINT,
       updated_at BIGINT
     );
@@ -1130,6 +2388,7 @@ export async function ensurePgSchema(client: PgClient): Promise<void> {
       waiting_for TEXT DEFAULT '[]',
       result TEXT,
       error TEXT,
+      error_code TEXT,
       turn_count INTEGER DEFAULT 0,
       plan TEXT,
       created_at BIGINT,
@@ -1152,7 +2411,8 @@ export async function ensurePgSchema(client: PgClient): Promise<void> {
       save_output BOOLEAN,
       turn_number INTEGER,
       duration_ms INTEGER,
-      created_at BIGINT NOT NULL
+      created_at BIGINT NOT NULL,
+      updated_at BIGINT
     );
     CREATE TABLE IF NOT EXISTS api_keys (
       id TEXT PRIMARY KEY,
@@ -1184,7 +2444,8 @@ export async function ensurePgSchema(client: PgClient): Promise<void> {
     );
     CREATE TABLE IF NOT EXISTS preferences (
       key TEXT PRIMARY KEY,
-      value TEXT NOT NULL
+      value TEXT NOT NULL,
+      updated_at BIGINT
     );
     CREATE TABLE IF NOT EXISTS workflow_runs (
       id TEXT PRIMARY KEY,
@@ -1284,10 +2545,192 @@ export async function ensurePgSchema(client: PgClient): Promise<void> {
       created_at BIGINT NOT NULL,
       PRIMARY KEY (connection_id, telegram_update_id)
     );
+    CREATE TABLE IF NOT EXISTS message_revisions (
+      id TEXT PRIMARY KEY,
+      item_id TEXT NOT NULL,
+      session_id TEXT NOT NULL,
+      revision INTEGER NOT NULL,
+      role TEXT,
+      content TEXT,
+      content_blocks TEXT,
+      reason TEXT NOT NULL,
+      created_at BIGINT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS usage_records (
+      id TEXT PRIMARY KEY,
+      user_id TEXT,
+      session_id TEXT,
+      agent_id TEXT,
+      provider TEXT NOT NULL,
+      model TEXT NOT NULL,
+      phase TEXT,
+      input_tokens INTEGER NOT NULL,
+      output_tokens INTEGER NOT NULL,
+      cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+      cache_write_tokens INTEGER NOT NULL DEFAULT 0,
+      cost_usd DOUBLE PRECISION NOT NULL DEFAULT 0,
+      cache_savings_usd DOUBLE PRECISION NOT NULL DEFAULT 0,
+      tool_context TEXT,
+      prompt_composition TEXT,
+      created_at BIGINT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS approval_records (
+      id TEXT PRIMARY KEY,
+      user_id TEXT,
+      session_id TEXT NOT NULL,
+      agent_id TEXT NOT NULL,
+      call_id TEXT NOT NULL,
+      tool_name TEXT NOT NULL,
+      args TEXT NOT NULL,
+      amended_args TEXT,
+      outcome TEXT NOT NULL,
+      scope TEXT,
+      rule_id TEXT,
+      requested_at BIGINT NOT NULL,
+      decided_at BIGINT NOT NULL,
+      latency_ms INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS model_prices (
+      model_id TEXT PRIMARY KEY,
+      input DOUBLE PRECISION NOT NULL,
+      output DOUBLE PRECISION NOT NULL,
+      cache_read DOUBLE PRECISION,
+      cache_write DOUBLE PRECISION,
+      source TEXT NOT NULL,
+      updated_at BIGINT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS model_catalog (
+      provider TEXT NOT NULL,
+      model_id TEXT NOT NULL,
+      display_name TEXT,
+      context_window INTEGER,
+      max_output_tokens INTEGER,
+      vision BOOLEAN,
+      structured_outputs BOOLEAN,
+      tools BOOLEAN,
+      fetched_at BIGINT NOT NULL,
+      PRIMARY KEY (provider, model_id)
+    );
+    CREATE TABLE IF NOT EXISTS uploads (
+      user_id TEXT NOT NULL,
+      hash TEXT NOT NULL,
+      name TEXT NOT NULL,
+      media_type TEXT NOT NULL,
+      kind TEXT NOT NULL,
+      bytes BIGINT NOT NULL,
+      created_at BIGINT NOT NULL,
+      last_used_at BIGINT NOT NULL,
+      PRIMARY KEY (user_id, hash)
+    );
+    CREATE TABLE IF NOT EXISTS memories (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL,
+      content TEXT NOT NULL,
+      kind TEXT NOT NULL,
+      category TEXT NOT NULL DEFAULT 'other',
+      confidence DOUBLE PRECISION NOT NULL,
+      embedding TEXT,
+      embedding_model TEXT,
+      source_session_id TEXT,
+      source_item_id TEXT,
+      disabled BOOLEAN NOT NULL DEFAULT FALSE,
+      created_at BIGINT NOT NULL,
+      updated_at BIGINT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS knowledge_sources (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL,
+      kind TEXT NOT NULL,
+      location TEXT NOT NULL,
+      name TEXT NOT NULL,
+      status TEXT NOT NULL,
+      error TEXT,
+      documents TEXT NOT NULL,
+      indexed_at BIGINT,
+      created_at BIGINT NOT NULL,
+      updated_at BIGINT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS entities (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL,
+      kind TEXT NOT NULL,
+      key TEXT NOT NULL,
+      name TEXT NOT NULL,
+      email TEXT,
+      details TEXT NOT NULL,
+      mentions INTEGER NOT NULL,
+      embedding TEXT,
+      embedding_model TEXT,
+      last_seen_at BIGINT NOT NULL,
+      created_at BIGINT NOT NULL,
+      updated_at BIGINT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS scratchpad_entries (
+      session_id TEXT NOT NULL,
+      key TEXT NOT NULL,
+      value TEXT NOT NULL,
+      updated_at BIGINT NOT NULL,
+      PRIMARY KEY (session_id, key)
+    );
+    CREATE TABLE IF NOT EXISTS controller_prompt_versions (
+      name TEXT NOT NULL,
+      version INTEGER NOT NULL,
+      content TEXT NOT NULL,
+      created_at BIGINT NOT NULL,
+      PRIMARY KEY (name, version)
+    );
+    CREATE TABLE IF NOT EXISTS experiment_sessions (
+      session_id TEXT PRIMARY KEY,
+      experiment_id TEXT NOT NULL,
+      variant TEXT NOT NULL,
+      created_at BIGINT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS feeds (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL,
+      url TEXT NOT NULL,
+      title TEXT NOT NULL,
+      site_url TEXT,
+      etag TEXT,
+      last_modified TEXT,
+      error TEXT,
+      fetched_at BIGINT,
+      created_at BIGINT NOT NULL,
+      updated_at BIGINT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS feed_items (
+      id TEXT PRIMARY KEY,
+      feed_id TEXT NOT NULL,
+      guid TEXT NOT NULL,
+      title TEXT NOT NULL,
+      link TEXT,
+      author TEXT,
+      summary TEXT NOT NULL,
+      content TEXT NOT NULL,
+      published_at BIGINT NOT NULL,
+      created_at BIGINT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS uploads_hash_idx ON uploads(hash);
+    CREATE INDEX IF NOT EXISTS memories_user_id_idx ON memories(user_id, updated_at);
+    CREATE INDEX IF NOT EXISTS knowledge_sources_user_id_idx ON knowledge_sources(user_id, created_at);
+    CREATE INDEX IF NOT EXISTS knowledge_sources_status_idx ON knowledge_sources(status);
+    CREATE UNIQUE INDEX IF NOT EXISTS entities_user_kind_key_idx ON entities(user_id, kind, key);
+    CREATE UNIQUE INDEX IF NOT EXISTS feeds_user_url_idx ON feeds(user_id, url);
+    CREATE UNIQUE INDEX IF NOT EXISTS feed_items_feed_guid_idx ON feed_items(feed_id, guid);
+    CREATE INDEX IF NOT EXISTS feed_items_feed_published_idx ON feed_items(feed_id, published_at);
+    CREATE INDEX IF NOT EXISTS experiment_sessions_experiment_idx ON experiment_sessions(experiment_id);
     CREATE INDEX IF NOT EXISTS agents_session_id_idx ON agents(session_id);
     CREATE INDEX IF NOT EXISTS agents_status_idx ON agents(status);
+    CREATE INDEX IF NOT EXISTS usage_records_user_created_idx ON usage_records(user_id, created_at);
+    CREATE INDEX IF NOT EXISTS usage_records_session_id_idx ON usage_records(session_id);
+    CREATE INDEX IF NOT EXISTS approval_records_user_decided_idx ON approval_records(user_id, decided_at);
+    CREATE INDEX IF NOT EXISTS approval_records_session_id_idx ON approval_records(session_id);
+    CREATE INDEX IF NOT EXISTS approval_records_tool_name_idx ON approval_records(tool_name);
+    CREATE INDEX IF NOT EXISTS message_revisions_item_id_idx ON message_revisions(item_id, revision);
+    CREATE INDEX IF NOT EXISTS message_revisions_session_id_idx ON message_revisions(session_id);
     CREATE INDEX IF NOT EXISTS items_agent_id_sequence_idx ON items(agent_id, sequence);
     CREATE INDEX IF NOT EXISTS items_call_id_idx ON items(call_id);
+    CREATE INDEX IF NOT EXISTS items_content_fts_idx ON items USING gin (to_tsvector('simple', content)) WHERE type = 'message';
     CREATE INDEX IF NOT EXISTS workflow_runs_session_id_idx ON workflow_runs(session_id);
     CREATE INDEX IF NOT EXISTS workflow_runs_status_idx ON workflow_runs(status);
     CREATE INDEX IF NOT EXISTS mcp_servers_name_idx ON mcp_servers(name);
@@ -1306,6 +2749,20 @@ export async function ensurePgSchema(client: PgClient): Promise<void> {
   `)
   await client.unsafe(`ALTER TABLE mcp_servers ADD COLUMN IF NOT EXISTS user_id TEXT REFERENCES users(id)`)
   await client.unsafe(`ALTER TABLE mcp_servers ADD COLUMN IF NOT EXISTS auth_mode TEXT NOT NULL DEFAULT 'auto'`)
+  await client.unsafe(`ALTER TABLE preferences ADD COLUMN IF NOT EXISTS updated_at BIGINT`)
+  await client.unsafe(`ALTER TABLE sessions ADD COLUMN IF NOT EXISTS deleted_at BIGINT`)
+  await client.unsafe(`ALTER TABLE agents ADD COLUMN IF NOT EXISTS error_code TEXT`)
+  await client.unsafe(`ALTER TABLE items ADD COLUMN IF NOT EXISTS updated_at BIGINT`)
+  await client.unsafe(`ALTER TABLE approval_records ADD COLUMN IF NOT EXISTS amended_args TEXT`)
+  await client.unsafe(`ALTER TABLE usage_records ADD COLUMN IF NOT EXISTS cache_savings_usd DOUBLE PRECISION NOT NULL DEFAULT 0`)
+  await client.unsafe(`ALTER TABLE usage_records ADD COLUMN IF NOT EXISTS phase TEXT`)
+  await client.unsafe(`ALTER TABLE usage_records ADD COLUMN IF NOT EXISTS tool_context TEXT`)
+  await client.unsafe(`ALTER TABLE usage_records ADD COLUMN IF NOT EXISTS prompt_composition TEXT`)
+  await client.unsafe(`ALTER TABLE model_prices ADD COLUMN IF NOT EXISTS cache_read DOUBLE PRECISION`)
+  await client.unsafe(`ALTER TABLE model_prices ADD COLUMN IF NOT EXISTS cache_write DOUBLE PRECISION`)
+  await client.unsafe(`ALTER TABLE memories ADD COLUMN IF NOT EXISTS source_item_id TEXT`)
+  await client.unsafe(`ALTER TABLE memories ADD COLUMN IF NOT EXISTS disabled BOOLEAN NOT NULL DEFAULT FALSE`)
+  await client.unsafe(`ALTER TABLE memories ADD COLUMN IF NOT EXISTS category TEXT NOT NULL DEFAULT 'other'`)
   await client.unsafe(`UPDATE mcp_servers SET user_id = (SELECT id FROM users ORDER BY created_at ASC LIMIT 1) WHERE user_id IS NULL`)
   await client.unsafe(`UPDATE mcp_servers SET auth_mode = CASE WHEN transport = 'stdio' THEN 'none' WHEN bearer_token IS NOT NULL AND bearer_token != '' THEN 'bearer' ELSE 'auto' END WHERE auth_mode = 'auto'`)
   const orphaned = await client<{ count: number }[]>`SELECT COUNT(*)::int AS count FROM mcp_servers WHERE user_id IS NULL`
@@ -1,9 +1,11 @@
 import Database from 'better-sqlite3'
 import { drizzle, type BetterSQLite3Database } from 'drizzle-orm/better-sqlite3'
-import { eq, and, desc, asc, sql, max, isNull, or, lte } from 'drizzle-orm'
+import { eq, and, desc, asc, sql, max, isNull, isNotNull, or, lte, gte, lt, ne, count, inArray } from 'drizzle-orm'
 import { v4 as uuid } from 'uuid'
 import { createHash } from 'crypto'
+import { renameSync, rmSync } from 'fs'
 import { encrypt, decrypt, deriveKey } from '../../lib/crypto.js'
+import { collectAttachmentHashes } from '../../lib/attachment-store.js'
 
 import * as schema from '../../db/schema.js'
 import type {
@@ -41,10 +43,75 @@ import type {
   TelegramRepository,
   StoredTelegramConnection,
   StoredTelegramMessageLink,
+  SyncRecord,
+  SyncRepository,
+  UsageRecord,
+  UsagePhase,
+  ToolContextShare,
+  UsageRepository,
+  ApprovalHistoryQuery,
+  ApprovalHistoryRepository,
+  ApprovalOutcome,
+  ApprovalRecord,
+  CreateApprovalRecordInput,
+  ModelPriceRecord,
+  ModelPriceRepository,
+  ModelCatalogEntry,
+  ModelCatalogRecord,
+  ModelCatalogRepository,
+  RecordUploadInput,
+  UploadKind,
+  UploadRecord,
+  UploadRepository,
+  CreateMemoryInput,
+  MemoryCategory,
+  MemoryKind,
+  MemoryRecord,
+  MessageSearchHit,
+  MemoryRepository,
+  UpdateMemoryInput,
+  CreateKnowledgeSourceInput,
+  KnowledgeDocument,
+  KnowledgeSource,
+  KnowledgeSourceKind,
+  KnowledgeSourceRepository,
+  KnowledgeSourceStatus,
+  UpdateKnowledgeSourceInput,
+  ScratchpadEntry,
+  ScratchpadRepository,
+  ControllerPromptRepository,
+  ControllerPromptVersion,
+  ExperimentRepository,
+  ExperimentSession,
+  CreateEntityInput,
+  EntityKind,
+  EntityRecord,
+  EntityRepository,
+  UpdateEntityInput,
+  CreateFeedInput,
+  Feed,
+  FeedItem,
+  FeedRepository,
+  NewFeedItem,
+  UpdateFeedInput,
+  ModelPriceSource,
+  UpsertModelPriceInput,
+  RetentionRepository,
+  InactiveSession,
+  MaintenanceRepository,
+  DatabaseMaintenanceReport,
+  OrphanRepository,
+  OrphanCounts,
+  MessageRevision,
+  MessageRevisionReason,
+  MessageRevisionRepository,
+  CreateUsageRecordInput,
+  UsageQuery,
 } from '../types.js'
 import type {
   Agent,
   AgentConfig,
+  AgentErrorCode,
   AgentStatus,
   Item,
   ItemContentBlock,
@@ -58,8 +125,9 @@ import type {
   WaitingFor,
 } from '../../domain/types.js'
 import type { WorkflowRun, WorkflowRunStatus } from '../../workflows/types.js'
+import type { PromptComposition } from '../../events/payloads.js'
 
-export type DrizzleInstance = BetterSQLite3Database<typeof schema>
+export type DrizzleInstance = BetterSQLite3Database<typeof schema> & { $client: Database.Database }
 
 // --- Helpers ---
 
@@ -76,6 +144,7 @@ function toAgent(row: typeof schema.agents.$inferSelect): Agent {
     waitingFor: JSON.parse(row.waitingFor ?? '[]') as WaitingFor[],
     result: row.result ?? null,
     error: row.error ?? null,
+    errorCode: (row.errorCode ?? null) as AgentErrorCode | null,
     turnCount: row.turnCount ?? 0,
     plan: row.plan ? (JSON.parse(row.plan) as Plan) : null,
     createdAt: row.createdAt ?? 0,
@@ -95,6 +164,7 @@ function toSession(row: typeof schema.sessions.$inferSelect): Session {
     title: row.title ?? null,
     summary: row.summary ?? null,
     status: (row.status ?? 'active') as SessionStatus,
+    deletedAt: row.deletedAt ?? null,
     createdAt: row.createdAt ?? 0,
     updatedAt: row.updatedAt ?? 0,
   }
@@ -305,6 +375,7 @@ function createSessionRepo(db: DrizzleInstance): SessionRepository {
         title: input.title ?? null,
         summary: null,
         status: 'active' as const,
+        deletedAt: null,
         createdAt: now,
         updatedAt: now,
       }
@@ -326,12 +397,31 @@ function createSessionRepo(db: DrizzleInstance): SessionRepository {
       const rows = db
         .select()
         .from(schema.sessions)
-        .where(eq(schema.sessions.userId, userId))
+        .where(and(eq(schema.sessions.userId, userId), isNull(schema.sessions.deletedAt)))
         .orderBy(desc(schema.sessions.createdAt))
         .all()
       return rows.map(toSession)
     },
 
+    async listTrashed(userId: string): Promise<Session[]> {
+      const rows = db
+        .select()
+        .from(schema.sessions)
+        .where(and(eq(schema.sessions.userId, userId), isNotNull(schema.sessions.deletedAt)))
+        .orderBy(desc(schema.sessions.deletedAt))
+        .all()
+      return rows.map(toSession)
+    },
+
+    async listTrashedBefore(cutoff: number): Promise<Session[]> {
+      const rows = db
+        .select()
+        .from(schema.sessions)
+        .where(lte(schema.sessions.deletedAt, cutoff))
+        .all()
+      return rows.map(toSession)
+    },
+
     async update(id: string, input: UpdateSessionInput): Promise<Session> {
       const now = Date.now()
       const updates: Record<string, unknown> = { updatedAt: now }
@@ -342,6 +432,7 @@ function createSessionRepo(db: DrizzleInstance): SessionRepository {
       if (input.title !== undefined) updates.title = input.title
       if (input.summary !== undefined) updates.summary = input.summary
       if (input.status !== undefined) updates.status = input.status
+      if (input.deletedAt !== undefined) updates.deletedAt = input.deletedAt
 
       db.update(schema.sessions).set(updates).where(eq(schema.sessions.id, id)).run()
 
@@ -371,6 +462,8 @@ function createSessionRepo(db: DrizzleInstance): SessionRepository {
         tx.delete(schema.agents).where(eq(schema.agents.sessionId, id)).run()
         tx.delete(schema.workflowRuns).where(eq(schema.workflowRuns.sessionId, id)).run()
         tx.delete(schema.telegramMessageLinks).where(eq(schema.telegramMessageLinks.sessionId, id)).run()
+        tx.delete(schema.messageRevisions).where(eq(schema.messageRevisions.sessionId, id)).run()
+        tx.delete(schema.scratchpadEntries).where(eq(schema.scratchpadEntries.sessionId, id)).run()
         tx.delete(schema.sessions).where(eq(schema.sessions.id, id)).run()
       })
     },
@@ -394,6 +487,7 @@ function createAgentRepo(db: DrizzleInstance): AgentRepository {
         waitingFor: '[]',
         result: null,
         error: null,
+        errorCode: null,
         turnCount: 0,
         plan: null,
         createdAt: now,
@@ -410,28 +504,37 @@ function createAgentRepo(db: DrizzleInstance): AgentRepository {
     },
 
     async update(id: string, input: UpdateAgentInput): Promise<Agent> {
-      const now = Date.now()
-      const updates: Record<string, unknown> = { updatedAt: now }
-      if (input.status !== undefined) updates.status = input.status
-      if (input.waitingFor !== undefined) updates.waitingFor = JSON.stringify(input.waitingFor)
-      if (input.result !== undefined) updates.result = input.result
-      if (input.error !== undefined) updates.error = input.error
-      if (input.turnCount !== undefined) updates.turnCount = input.turnCount
-      if (input.plan !== undefined) updates.plan = input.plan ? JSON.stringify(input.plan) : null
-      if (input.completedAt !== undefined) updates.completedAt = input.completedAt
-
-      db.update(schema.agents).set(updates).where(eq(schema.agents.id, id)).run()
+      db.update(schema.agents).set(agentUpdates(input)).where(eq(schema.agents.id, id)).run()
 
       const rows = db.select().from(schema.agents).where(eq(schema.agents.id, id)).limit(1).all()
       if (rows.length === 0) throw new Error(`Agent not found: ${id}`)
       return toAgent(rows[0])
     },
 
-    async failRunningOrWaiting(error: string): Promise<void> {
+    async finish(id: string, input: UpdateAgentInput, message?: CreateItemInput): Promise<Agent> {
+      db.transaction((tx) => {
+        if (message) {
+          const maxSeqResult = tx
+            .select({ maxSeq: max(schema.items.sequence) })
+            .from(schema.items)
+            .where(eq(schema.items.agentId, message.agentId))
+            .all()
+          tx.insert(schema.items).values(itemRow(message, (maxSeqResult[0]?.maxSeq ?? -1) + 1)).run()
+        }
+        const { changes } = tx.update(schema.agents).set(agentUpdates(input)).where(eq(schema.agents.id, id)).run()
+        if (changes === 0) throw new Error(`Agent not found: ${id}`)
+      })
+
+      const rows = db.select().from(schema.agents).where(eq(schema.agents.id, id)).limit(1).all()
+      return toAgent(rows[0])
+    },
+
+    async failRunningOrWaiting(error: string, errorCode: AgentErrorCode): Promise<void> {
       db.update(schema.agents)
         .set({
           status: 'failed',
           error,
+          errorCode,
           completedAt: Date.now(),
           updatedAt: Date.now(),
         })
@@ -444,6 +547,15 @@ function createAgentRepo(db: DrizzleInstance): AgentRepository {
         .run()
     },
 
+    async listRunningOrWaiting(): Promise<Agent[]> {
+      const rows = db
+        .select()
+        .from(schema.agents)
+        .where(or(eq(schema.agents.status, 'running'), eq(schema.agents.status, 'waiting'))!)
+        .all()
+      return rows.map(toAgent)
+    },
+
     async findWaitingForCall(callId: string): Promise<Agent | null> {
       const rows = db
         .select()
@@ -494,12 +606,44 @@ function createAgentRepo(db: DrizzleInstance): AgentRepository {
   }
 }
 
+function agentUpdates(input: UpdateAgentInput): Record<string, unknown> {
+  const updates: Record<string, unknown> = { updatedAt: Date.now() }
+  if (input.status !== undefined) updates.status = input.status
+  if (input.waitingFor !== undefined) updates.waitingFor = JSON.stringify(input.waitingFor)
+  if (input.result !== undefined) updates.result = input.result
+  if (input.error !== undefined) updates.error = input.error
+  if (input.errorCode !== undefined) updates.errorCode = input.errorCode
+  if (input.turnCount !== undefined) updates.turnCount = input.turnCount
+  if (input.plan !== undefined) updates.plan = input.plan ? JSON.stringify(input.plan) : null
+  if (input.completedAt !== undefined) updates.completedAt = input.completedAt
+  if (input.config !== undefined) updates.config = JSON.stringify(input.config)
+  return updates
+}
+
+function itemRow(input: CreateItemInput, sequence: number) {
+  return {
+    id: uuid(),
+    agentId: input.agentId,
+    sequence,
+    type: input.type,
+    role: input.role ?? null,
+    content: input.content ?? null,
+    callId: input.callId ?? null,
+    name: input.name ?? null,
+    arguments: input.arguments ?? null,
+    output: input.output ?? null,
+    contentBlocks: input.contentBlocks ? JSON.stringify(input.contentBlocks) : null,
+    isError: input.isError != null ? (input.isError ? 1 : 0) : null,
+    saveOutput: input.saveOutput != null ? (input.saveOutput ? 1 : 0) : null,
+    turnNumber: input.turnNumber,
+    durationMs: input.durationMs ?? null,
+    createdAt: Date.now(),
+  }
+}
+
 function createItemRepo(db: DrizzleInstance): ItemRepository {
   return {
     async create(input: CreateItemInput): Promise<Item> {
-      const now = Date.now()
-      const id = uuid()
-
       // Atomic: SELECT MAX(sequence) + INSERT in a single transaction to avoid
       // duplicate sequence numbers when concurrent awaits interleave on the event loop.
       const row = db.transaction((tx) => {
@@ -508,26 +652,7 @@ function createItemRepo(db: DrizzleInstance): ItemRepository {
           .from(schema.items)
           .where(eq(schema.items.agentId, input.agentId))
           .all()
-        const nextSequence = (maxSeqResult[0]?.maxSeq ?? -1) + 1
-
-        const insertRow = {
-          id,
-          agentId: input.agentId,
-          sequence: nextSequence,
-          type: input.type,
-          role: input.role ?? null,
-          content: input.content ?? null,
-          callId: input.callId ?? null,
-          name: input.name ?? null,
-          arguments: input.arguments ?? null,
-          output: input.output ?? null,
-          contentBlocks: input.contentBlocks ? JSON.stringify(input.contentBlocks) : null,
-          isError: input.isError != null ? (input.isError ? 1 : 0) : null,
-          saveOutput: input.saveOutput != null ? (input.saveOutput ? 1 : 0) : null,
-          turnNumber: input.turnNumber,
-          durationMs: input.durationMs ?? null,
-          createdAt: now,
-        }
+        const insertRow = itemRow(input, (maxSeqResult[0]?.maxSeq ?? -1) + 1)
         tx.insert(schema.items).values(insertRow).run()
         return insertRow
       })
@@ -555,6 +680,31 @@ function createItemRepo(db: DrizzleInstance): ItemRepository {
       return rows.map(toItem)
     },
 
+    async listRecentByAgent(agentId: string, userTurns: number): Promise<Item[]> {
+      const [start] = db
+        .select({ sequence: schema.items.sequence })
+        .from(schema.items)
+        .where(and(eq(schema.items.agentId, agentId), eq(schema.items.type, 'message'), eq(schema.items.role, 'user')))
+        .orderBy(desc(schema.items.sequence))
+        .limit(1)
+        .offset(Math.max(userTurns - 1, 0))
+        .all()
+      if (!start) return this.listByAgent(agentId)
+      const rows = db
+        .select()
+        .from(schema.items)
+        .where(and(
+          eq(schema.items.agentId, agentId),
+          or(
+            gte(schema.items.sequence, start.sequence),
+            and(eq(schema.items.type, 'message'), eq(schema.items.role, 'system')),
+          ),
+        ))
+        .orderBy(asc(schema.items.sequence))
+        .all()
+      return rows.map(toItem)
+    },
+
     async listBySession(sessionId: string): Promise<Item[]> {
       // Load items from ALL agents in the session (root + subagents),
       // ordered by depth so root items come first, then creation time, then sequence.
@@ -579,6 +729,63 @@ function createItemRepo(db: DrizzleInstance): ItemRepository {
         .all()
       return rows.length > 0 ? toItem(rows[0]) : null
     },
+
+    async listWithInlineAttachments(): Promise<Item[]> {
+      const rows = db
+        .select()
+        .from(schema.items)
+        .where(sql`${schema.items.contentBlocks} LIKE '%"data":%'`)
+        .all()
+      return rows.map(toItem)
+    },
+
+    async updateContentBlocks(id: string, contentBlocks: ItemContentBlock[] | null): Promise<void> {
+      db.update(schema.items)
+        .set({ contentBlocks: contentBlocks ? JSON.stringify(contentBlocks) : null })
+        .where(eq(schema.items.id, id))
+        .run()
+    },
+
+    async searchMessages(userId: string, terms: string[], options = {}): Promise<MessageSearchHit[]> {
+      if (terms.length === 0) return []
+      // Quoted terms are matched literally, so FTS5 operators in a query are inert
+      const match = terms.map((term) => `"${term.replace(/"/g, '""')}"`).join(' OR ')
+      const rows = db.all<MessageSearchRow>(sql`
+        SELECT items.id AS item_id, agents.session_id, sessions.title AS session_title,
+          items.role, items.content, items.created_at
+        FROM items_fts
+        JOIN items ON items.rowid = items_fts.rowid
+        JOIN agents ON agents.id = items.agent_id
+        JOIN sessions ON sessions.id = agents.session_id
+        WHERE items_fts MATCH ${match}
+          AND sessions.user_id = ${userId} AND sessions.deleted_at IS NULL
+          AND sessions.id != ${options.excludeSessionId ?? ''}
+          AND agents.depth = 0 AND items.type = 'message' AND items.role IN ('user', 'assistant')
+        ORDER BY bm25(items_fts)
+        LIMIT ${options.limit ?? 20}
+      `)
+      return rows.map(toMessageSearchHit)
+    },
+  }
+}
+
+interface MessageSearchRow {
+  item_id: string
+  session_id: string
+  session_title: string | null
+  role: 'user' | 'assistant'
+  content: string
+  created_at: number
+}
+
+function toMessageSearchHit(row: MessageSearchRow): MessageSearchHit {
+  return {
+    itemId: row.item_id,
+    sessionId: row.session_id,
+    sessionTitle: row.session_title,
+    role: row.role,
+    content: row.content,
+    createdAt: row.created_at,
   }
 }
 
@@ -813,11 +1020,12 @@ function createPreferenceRepo(db: DrizzleInstance): PreferenceRepository {
     },
 
     async set(key: string, value: string): Promise<void> {
+      const updatedAt = Date.now()
       db.insert(schema.preferences)
-        .values({ key, value })
+        .values({ key, value, updatedAt })
         .onConflictDoUpdate({
           target: schema.preferences.key,
-          set: { value },
+          set: { value, updatedAt },
         })
         .run()
     },
@@ -1228,177 +1436,1262 @@ function createTelegramRepo(db: DrizzleInstance): TelegramRepository {
   }
 }
 
-// --- Public API ---
+// --- Usage ---
 
-export function createDatabase(url: string): DrizzleInstance {
-  const sqlite = new Database(url)
-  sqlite.pragma('journal_mode = WAL')
-  sqlite.pragma('foreign_keys = ON')
+function toMessageRevision(row: typeof schema.messageRevisions.$inferSelect): MessageRevision {
+  return {
+    id: row.id,
+    itemId: row.itemId,
+    sessionId: row.sessionId,
+    revision: row.revision,
+    role: (row.role as ItemRole) ?? null,
+    content: row.content ?? null,
+    contentBlocks: row.contentBlocks ? (JSON.parse(row.contentBlocks) as ItemContentBlock[]) : null,
+    reason: row.reason as MessageRevisionReason,
+    createdAt: row.createdAt,
+  }
+}
 
-  // Auto-create tables if they don't exist
-  sqlite.exec(`
-    CREATE TABLE IF NOT EXISTS users (
-      id TEXT PRIMARY KEY,
-      email TEXT UNIQUE,
-      api_key_hash TEXT UNIQUE NOT NULL,
-      created_at INTEGER NOT NULL,
-      updated_at INTEGER NOT NULL
-    );
-    CREATE TABLE IF NOT EXISTS sessions (
-      id TEXT PRIMARY KEY,
-      user_id TEXT REFERENCES users(id),
-      root_agent_id TEXT,
-      parent_session_id TEXT REFERENCES sessions(id),
-      forked_from_item_id TEXT REFERENCES items(id),
-      source TEXT,
-      title TEXT,
-      summary TEXT,
-      status TEXT DEFAULT 'active',
-      created_at INTEGER,
-      updated_at INTEGER
-    );
-    CREATE TABLE IF NOT EXISTS agents (
-      id TEXT PRIMARY KEY,
-      session_id TEXT REFERENCES sessions(id),
-      parent_id TEXT REFERENCES agents(id),
-      source_call_id TEXT,
-      depth INTEGER DEFAULT 0,
-      task TEXT NOT NULL,
-      config TEXT,
-      status TEXT DEFAULT 'pending',
-      waiting_for TEXT DEFAULT '[]',
-      result TEXT,
-      error TEXT,
-      turn_count INTEGER DEFAULT 0,
-      plan TEXT,
-      created_at INTEGER,
-      updated_at INTEGER,
-      completed_at INTEGER
-    );
-    CREATE TABLE IF NOT EXISTS items (
-      id TEXT PRIMARY KEY,
-      agent_id TEXT REFERENCES agents(id),
-      sequence INTEGER NOT NULL,
-      type TEXT NOT NULL,
-      role TEXT,
-      content TEXT,
-      call_id TEXT,
-      name TEXT,
-      arguments TEXT,
-      output TEXT,
-      is_error INTEGER,
-      save_output INTEGER,
-      turn_number INTEGER,
-      duration_ms INTEGER,
-      created_at INTEGER NOT NULL
-    );
-    CREATE TABLE IF NOT EXISTS api_keys (
-      id TEXT PRIMARY KEY,
-      provider TEXT NOT NULL,
-      api_key TEXT NOT NULL,
-      created_at INTEGER NOT NULL
-    );
-    CREATE TABLE IF NOT EXISTS models (
-      id TEXT PRIMARY KEY,
-      provider TEXT NOT NULL,
-      model_name TEXT NOT NULL,
-      enabled INTEGER DEFAULT 1,
-      created_at INTEGER NOT NULL
-    );
-    CREATE TABLE IF NOT EXISTS system_prompts (
-      id TEXT PRIMARY KEY,
-      name TEXT NOT NULL,
-      content TEXT NOT NULL,
-      created_at INTEGER,
-      updated_at INTEGER
-    );
-    CREATE TABLE IF NOT EXISTS tool_outputs (
-      id TEXT PRIMARY KEY,
-      agent_id TEXT REFERENCES agents(id),
-      call_id TEXT,
-      tool_name TEXT NOT NULL,
-      data TEXT,
-      created_at INTEGER NOT NULL
-    );
-    CREATE TABLE IF NOT EXISTS preferences (
-      key TEXT PRIMARY KEY,
-      value TEXT NOT NULL
-    );
-    CREATE TABLE IF NOT EXISTS workflow_runs (
-      id TEXT PRIMARY KEY,
-      workflow_name TEXT NOT NULL,
-      session_id TEXT NOT NULL REFERENCES sessions(id),
-      trigger_agent_id TEXT REFERENCES agents(id),
-      trigger_call_id TEXT,
-      status TEXT NOT NULL DEFAULT 'pending',
-      input TEXT,
-      output TEXT,
-      error TEXT,
-      started_at INTEGER,
-      completed_at INTEGER,
-      created_at INTEGER NOT NULL,
-      updated_at INTEGER NOT NULL
-    );
-    CREATE TABLE IF NOT EXISTS mcp_servers (
-      id TEXT PRIMARY KEY,
-      user_id TEXT NOT NULL REFERENCES users(id),
-      auth_mode TEXT NOT NULL DEFAULT 'auto',
-      name TEXT NOT NULL,
-      transport TEXT NOT NULL,
-      command TEXT,
-      args TEXT,
-      env TEXT,
-      cwd TEXT,
-      url TEXT,
-      bearer_token TEXT,
-      enabled INTEGER NOT NULL DEFAULT 0,
-      status TEXT NOT NULL DEFAULT 'disabled',
-      error TEXT,
-      created_at INTEGER NOT NULL,
-      updated_at INTEGER NOT NULL
-    );
-    CREATE TABLE IF NOT EXISTS mcp_tools (
-      id TEXT PRIMARY KEY,
-      server_id TEXT NOT NULL REFERENCES mcp_servers(id) ON DELETE CASCADE,
-      remote_name TEXT NOT NULL,
-      registered_name TEXT NOT NULL,
-      description TEXT,
-      input_schema TEXT,
-      enabled_for_new_sessions INTEGER NOT NULL DEFAULT 1,
-      created_at INTEGER NOT NULL,
-      updated_at INTEGER NOT NULL
-    );
-    CREATE TABLE IF NOT EXISTS mcp_oauth_credentials (
-      server_id TEXT PRIMARY KEY REFERENCES mcp_servers(id) ON DELETE CASCADE,
-      user_id TEXT NOT NULL REFERENCES users(id),
-      resource_url TEXT NOT NULL,
-      tokens TEXT,
-      client_information TEXT,
-      discovery TEXT,
-      created_at INTEGER NOT NULL,
-      updated_at INTEGER NOT NULL
-    );
-    CREATE TABLE IF NOT EXISTS mcp_oauth_sessions (
-      id TEXT PRIMARY KEY,
-      server_id TEXT NOT NULL REFERENCES mcp_servers(id) ON DELETE CASCADE,
-      user_id TEXT NOT NULL REFERENCES users(id),
-      state_hash TEXT UNIQUE NOT NULL,
-      code_verifier TEXT NOT NULL,
-      status TEXT NOT NULL,
-      error TEXT,
-      expires_at INTEGER NOT NULL,
-      consumed_at INTEGER,
-      created_at INTEGER NOT NULL,
-      updated_at INTEGER NOT NULL
-    );
-    CREATE TABLE IF NOT EXISTS telegram_connections (
-      id TEXT PRIMARY KEY,
-      user_id TEXT NOT NULL REFERENCES users(id),
-      bot_token TEXT NOT NULL,
-      bot_username TEXT,
-      allowed_telegram_user_id TEXT NOT NULL,
-      webhook_path_secret TEXT NOT NULL,
-      webhook_header_secret TEXT NOT NULL,
+function toUsageRecord(row: typeof schema.usageRecords.$inferSelect): UsageRecord {
+  return {
+    id: row.id,
+    userId: row.userId ?? null,
+    sessionId: row.sessionId ?? null,
+    agentId: row.agentId ?? null,
+    provider: row.provider,
+    model: row.model,
+    phase: (row.phase as UsagePhase | null) ?? null,
+    inputTokens: row.inputTokens,
+    outputTokens: row.outputTokens,
+    cacheReadTokens: row.cacheReadTokens,
+    cacheWriteTokens: row.cacheWriteTokens,
+    costUsd: row.costUsd,
+    cacheSavingsUsd: row.cacheSavingsUsd,
+    toolContext: row.toolContext ? (JSON.parse(row.toolContext) as Record<string, ToolContextShare>) : null,
+    promptComposition: row.promptComposition ? (JSON.parse(row.promptComposition) as PromptComposition) : null,
+    createdAt: row.createdAt,
+  }
+}
+
+function createMessageRevisionRepo(db: DrizzleInstance): MessageRevisionRepository {
+  return {
+    async revise(item, sessionId, reason, next): Promise<MessageRevision> {
+      const row = db.transaction((tx) => {
+        const latest = tx
+          .select({ revision: max(schema.messageRevisions.revision) })
+          .from(schema.messageRevisions)
+          .where(eq(schema.messageRevisions.itemId, item.id))
+          .all()
+        const insertRow = {
+          id: uuid(),
+          itemId: item.id,
+          sessionId,
+          revision: (latest[0]?.revision ?? 0) + 1,
+          role: item.role,
+          content: item.content,
+          contentBlocks: item.contentBlocks ? JSON.stringify(item.contentBlocks) : null,
+          reason,
+          createdAt: Date.now(),
+        }
+        tx.insert(schema.messageRevisions).values(insertRow).run()
+        tx.update(schema.items)
+          .set({
+            content: next.content,
+            contentBlocks: next.contentBlocks ? JSON.stringify(next.contentBlocks) : null,
+            updatedAt: insertRow.createdAt,
+          })
+          .where(eq(schema.items.id, item.id))
+          .run()
+        return insertRow
+      })
+      return toMessageRevision(row)
+    },
+
+    async listByItem(itemId: string): Promise<MessageRevision[]> {
+      const rows = db
+        .select()
+        .from(schema.messageRevisions)
+        .where(eq(schema.messageRevisions.itemId, itemId))
+        .orderBy(asc(schema.messageRevisions.revision))
+        .all()
+      return rows.map(toMessageRevision)
+    },
+
+    async getById(id: string): Promise<MessageRevision | null> {
+      const rows = db
+        .select()
+        .from(schema.messageRevisions)
+        .where(eq(schema.messageRevisions.id, id))
+        .limit(1)
+        .all()
+      return rows.length > 0 ? toMessageRevision(rows[0]) : null
+    },
+  }
+}
+
+function createUsageRepo(db: DrizzleInstance): UsageRepository {
+  return {
+    async record(input: CreateUsageRecordInput): Promise<UsageRecord> {
+      const row = {
+        id: uuid(),
+        userId: input.userId ?? null,
+        sessionId: input.sessionId ?? null,
+        agentId: input.agentId ?? null,
+        provider: input.provider,
+        model: input.model,
+        phase: input.phase ?? null,
+        inputTokens: input.inputTokens,
+        outputTokens: input.outputTokens,
+        cacheReadTokens: input.cacheReadTokens ?? 0,
+        cacheWriteTokens: input.cacheWriteTokens ?? 0,
+        costUsd: input.costUsd,
+        cacheSavingsUsd: input.cacheSavingsUsd ?? 0,
+        toolContext: input.toolContext ? JSON.stringify(input.toolContext) : null,
+        promptComposition: input.promptComposition ? JSON.stringify(input.promptComposition) : null,
+        createdAt: Date.now(),
+      }
+      db.insert(schema.usageRecords).values(row).run()
+      return toUsageRecord(row)
+    },
+
+    async list(query: UsageQuery): Promise<UsageRecord[]> {
+      const conditions = []
+      if (query.userId) conditions.push(eq(schema.usageRecords.userId, query.userId))
+      if (query.sessionId) conditions.push(eq(schema.usageRecords.sessionId, query.sessionId))
+      if (query.from !== undefined) conditions.push(gte(schema.usageRecords.createdAt, query.from))
+      if (query.to !== undefined) conditions.push(lt(schema.usageRecords.createdAt, query.to))
+
+      const rows = db
+        .select()
+        .from(schema.usageRecords)
+        .where(conditions.length > 0 ? and(...conditions) : undefined)
+        .orderBy(asc(schema.usageRecords.createdAt))
+        .all()
+      return rows.map(toUsageRecord)
+    },
+  }
+}
+
+// --- Approval history ---
+
+function toApprovalRecord(row: typeof schema.approvalRecords.$inferSelect): ApprovalRecord {
+  return {
+    id: row.id,
+    userId: row.userId ?? null,
+    sessionId: row.sessionId,
+    agentId: row.agentId,
+    callId: row.callId,
+    toolName: row.toolName,
+    args: JSON.parse(row.args) as Record<string, unknown>,
+    amendedArgs: row.amendedArgs ? JSON.parse(row.amendedArgs) as Record<string, unknown> : null,
+    outcome: row.outcome as ApprovalOutcome,
+    scope: (row.scope as ApprovalRecord['scope']) ?? null,
+    ruleId: row.ruleId ?? null,
+    requestedAt: row.requestedAt,
+    decidedAt: row.decidedAt,
+    latencyMs: row.latencyMs,
+  }
+}
+
+function createApprovalHistoryRepo(db: DrizzleInstance): ApprovalHistoryRepository {
+  return {
+    async record(input: CreateApprovalRecordInput): Promise<ApprovalRecord> {
+      const row = {
+        id: uuid(),
+        userId: input.userId ?? null,
+        sessionId: input.sessionId,
+        agentId: input.agentId,
+        callId: input.callId,
+        toolName: input.toolName,
+        args: JSON.stringify(input.args),
+        amendedArgs: input.amendedArgs ? JSON.stringify(input.amendedArgs) : null,
+        outcome: input.outcome,
+        scope: input.scope ?? null,
+        ruleId: input.ruleId ?? null,
+        requestedAt: input.requestedAt,
+        decidedAt: input.decidedAt,
+        latencyMs: Math.max(0, input.decidedAt - input.requestedAt),
+      }
+      db.insert(schema.approvalRecords).values(row).run()
+      return toApprovalRecord(row)
+    },
+
+    async list(query: ApprovalHistoryQuery): Promise<ApprovalRecord[]> {
+      const conditions = []
+      if (query.userId) conditions.push(eq(schema.approvalRecords.userId, query.userId))
+      if (query.sessionId) conditions.push(eq(schema.approvalRecords.sessionId, query.sessionId))
+      if (query.toolName) conditions.push(eq(schema.approvalRecords.toolName, query.toolName))
+      if (query.outcome) conditions.push(eq(schema.approvalRecords.outcome, query.outcome))
+      if (query.from !== undefined) conditions.push(gte(schema.approvalRecords.decidedAt, query.from))
+      if (query.to !== undefined) conditions.push(lt(schema.approvalRecords.decidedAt, query.to))
+
+      const rows = db
+        .select()
+        .from(schema.approvalRecords)
+        .where(conditions.length > 0 ? and(...conditions) : undefined)
+        .orderBy(desc(schema.approvalRecords.decidedAt))
+        .limit(query.limit ?? 500)
+        .all()
+      return rows.map(toApprovalRecord)
+    },
+  }
+}
+
+// --- Model pricing ---
+
+function toModelPriceRecord(row: typeof schema.modelPrices.$inferSelect): ModelPriceRecord {
+  return {
+    modelId: row.modelId,
+    input: row.input,
+    output: row.output,
+    cacheRead: row.cacheRead ?? null,
+    cacheWrite: row.cacheWrite ?? null,
+    source: row.source as ModelPriceSource,
+    updatedAt: row.updatedAt,
+  }
+}
+
+function createModelPriceRepo(db: DrizzleInstance): ModelPriceRepository {
+  return {
+    async list(): Promise<ModelPriceRecord[]> {
+      return db.select().from(schema.modelPrices).orderBy(asc(schema.modelPrices.modelId)).all().map(toModelPriceRecord)
+    },
+
+    async seed(prices: UpsertModelPriceInput[]): Promise<number> {
+      const updatedAt = Date.now()
+      return db.transaction((tx) => {
+        let added = 0
+        for (const price of prices) {
+          added += tx.insert(schema.modelPrices).values({ ...price, updatedAt }).onConflictDoNothing().run().changes
+        }
+        return added
+      })
+    },
+
+    async upsert(input: UpsertModelPriceInput): Promise<ModelPriceRecord> {
+      const row = { ...input, cacheRead: input.cacheRead ?? null, cacheWrite: input.cacheWrite ?? null, updatedAt: Date.now() }
+      db.insert(schema.modelPrices)
+        .values(row)
+        .onConflictDoUpdate({
+          target: schema.modelPrices.modelId,
+          set: {
+            input: row.input,
+            output: row.output,
+            cacheRead: row.cacheRead,
+            cacheWrite: row.cacheWrite,
+            source: row.source,
+            updatedAt: row.updatedAt,
+          },
+        })
+        .run()
+      return toModelPriceRecord(row)
+    },
+
+    async delete(modelId: string): Promise<boolean> {
+      return db.delete(schema.modelPrices).where(eq(schema.modelPrices.modelId, modelId)).run().changes > 0
+    },
+  }
+}
+
+// --- Model catalog ---
+
+function toModelCatalogRecord(row: typeof schema.modelCatalog.$inferSelect): ModelCatalogRecord {
+  const flag = (value: number | null) => (value != null ? Boolean(value) : null)
+  return {
+    provider: row.provider,
+    modelId: row.modelId,
+    displayName: row.displayName ?? null,
+    contextWindow: row.contextWindow ?? null,
+    maxOutputTokens: row.maxOutputTokens ?? null,
+    vision: flag(row.vision),
+    structuredOutputs: flag(row.structuredOutputs),
+    tools: flag(row.tools),
+    fetchedAt: row.fetchedAt,
+  }
+}
+
+function createModelCatalogRepo(db: DrizzleInstance): ModelCatalogRepository {
+  const flag = (value: boolean | null) => (value != null ? (value ? 1 : 0) : null)
+  return {
+    async list(): Promise<ModelCatalogRecord[]> {
+      return db.select().from(schema.modelCatalog)
+        .orderBy(asc(schema.modelCatalog.provider), asc(schema.modelCatalog.modelId))
+        .all()
+        .map(toModelCatalogRecord)
+    },
+
+    async replace(provider: string, models: ModelCatalogEntry[]): Promise<void> {
+      const fetchedAt = Date.now()
+      db.transaction((tx) => {
+        tx.delete(schema.modelCatalog).where(eq(schema.modelCatalog.provider, provider)).run()
+        for (const model of models) {
+          tx.insert(schema.modelCatalog).values({
+            ...model,
+            provider,
+            vision: flag(model.vision),
+            structuredOutputs: flag(model.structuredOutputs),
+            tools: flag(model.tools),
+            fetchedAt,
+          }).onConflictDoNothing().run()
+        }
+      })
+    },
+  }
+}
+
+// --- Uploads ---
+
+function toUploadRecord(row: typeof schema.uploads.$inferSelect): UploadRecord {
+  return { ...row, kind: row.kind as UploadKind }
+}
+
+/** Matches serialized content blocks that reference a payload; hashes are hex, so nothing needs escaping. */
+function hashPattern(hash: string): string {
+  return `%"hash":"${hash}"%`
+}
+
+function createUploadRepo(db: DrizzleInstance): UploadRepository {
+  const byKey = (userId: string, hash: string) => and(eq(schema.uploads.userId, userId), eq(schema.uploads.hash, hash))
+
+  return {
+    async record(input: RecordUploadInput): Promise<UploadRecord> {
+      const now = Date.now()
+      db.insert(schema.uploads)
+        .values({ ...input, createdAt: now, lastUsedAt: now })
+        .onConflictDoUpdate({ target: [schema.uploads.userId, schema.uploads.hash], set: { lastUsedAt: now } })
+        .run()
+      return toUploadRecord(db.select().from(schema.uploads).where(byKey(input.userId, input.hash)).get()!)
+    },
+
+    async get(userId: string, hash: string): Promise<UploadRecord | null> {
+      const row = db.select().from(schema.uploads).where(byKey(userId, hash)).get()
+      return row ? toUploadRecord(row) : null
+    },
+
+    async listByUser(userId: string): Promise<UploadRecord[]> {
+      return db.select().from(schema.uploads)
+        .where(eq(schema.uploads.userId, userId))
+        .orderBy(desc(schema.uploads.lastUsedAt))
+        .all()
+        .map(toUploadRecord)
+    },
+
+    async touch(userId: string, hash: string): Promise<void> {
+      db.update(schema.uploads).set({ lastUsedAt: Date.now() }).where(byKey(userId, hash)).run()
+    },
+
+    async delete(userId: string, hash: string): Promise<boolean> {
+      return db.delete(schema.uploads).where(byKey(userId, hash)).run().changes > 0
+    },
+
+    async countMessageReferences(userId: string, hash: string): Promise<number> {
+      const row = db.select({ count: count() }).from(schema.items)
+        .innerJoin(schema.agents, eq(schema.agents.id, schema.items.agentId))
+        .innerJoin(schema.sessions, eq(schema.sessions.id, schema.agents.sessionId))
+        .where(and(eq(schema.sessions.userId, userId), sql`${schema.items.contentBlocks} LIKE ${hashPattern(hash)}`))
+        .get()
+      return row?.count ?? 0
+    },
+
+    async countAllReferences(hash: string): Promise<number> {
+      const uploads = db.select({ count: count() }).from(schema.uploads).where(eq(schema.uploads.hash, hash)).get()
+      const items = db.select({ count: count() }).from(schema.items)
+        .where(sql`${schema.items.contentBlocks} LIKE ${hashPattern(hash)}`).get()
+      const revisions = db.select({ count: count() }).from(schema.messageRevisions)
+        .where(sql`${schema.messageRevisions.contentBlocks} LIKE ${hashPattern(hash)}`).get()
+      return (uploads?.count ?? 0) + (items?.count ?? 0) + (revisions?.count ?? 0)
+    },
+  }
+}
+
+// --- Memories ---
+
+function toMemoryRecord(row: typeof schema.memories.$inferSelect): MemoryRecord {
+  return {
+    ...row,
+    kind: row.kind as MemoryKind,
+    category: row.category as MemoryCategory,
+    embedding: row.embedding ? JSON.parse(row.embedding) as number[] : null,
+    disabled: row.disabled === 1,
+  }
+}
+
+function createMemoryRepo(db: DrizzleInstance): MemoryRepository {
+  return {
+    async create(input: CreateMemoryInput): Promise<MemoryRecord> {
+      const now = Date.now()
+      const row = {
+        id: uuid(),
+        userId: input.userId,
+        content: input.content,
+        kind: input.kind,
+        category: input.category ?? 'other',
+        confidence: input.confidence,
+        embedding: input.embedding ? JSON.stringify(input.embedding) : null,
+        embeddingModel: input.embeddingModel ?? null,
+        sourceSessionId: input.sourceSessionId ?? null,
+        sourceItemId: input.sourceItemId ?? null,
+        disabled: 0,
+        createdAt: now,
+        updatedAt: now,
+      }
+      db.insert(schema.memories).values(row).run()
+      return toMemoryRecord(row)
+    },
+
+    async getById(id: string): Promise<MemoryRecord | null> {
+      const row = db.select().from(schema.memories).where(eq(schema.memories.id, id)).get()
+      return row ? toMemoryRecord(row) : null
+    },
+
+    async listByUser(userId: string): Promise<MemoryRecord[]> {
+      return db.select().from(schema.memories)
+        .where(eq(schema.memories.userId, userId))
+        .orderBy(desc(schema.memories.updatedAt))
+        .all()
+        .map(toMemoryRecord)
+    },
+
+    async update(id: string, input: UpdateMemoryInput): Promise<MemoryRecord | null> {
+      const updates: Record<string, unknown> = { updatedAt: Date.now() }
+      if (input.content !== undefined) updates.content = input.content
+      if (input.kind !== undefined) updates.kind = input.kind
+      if (input.category !== undefined) updates.category = input.category
+      if (input.confidence !== undefined) updates.confidence = input.confidence
+      if (input.embedding !== undefined) updates.embedding = input.embedding ? JSON.stringify(input.embedding) : null
+      if (input.embeddingModel !== undefined) updates.embeddingModel = input.embeddingModel
+      if (input.sourceSessionId !== undefined) updates.sourceSessionId = input.sourceSessionId
+      if (input.sourceItemId !== undefined) updates.sourceItemId = input.sourceItemId
+      if (input.disabled !== undefined) updates.disabled = input.disabled ? 1 : 0
+      db.update(schema.memories).set(updates).where(eq(schema.memories.id, id)).run()
+      return this.getById(id)
+    },
+
+    async delete(id: string): Promise<boolean> {
+      return db.delete(schema.memories).where(eq(schema.memories.id, id)).run().changes > 0
+    },
+  }
+}
+
+// --- Knowledge base ---
+
+function toKnowledgeSource(row: typeof schema.knowledgeSources.$inferSelect): KnowledgeSource {
+  return {
+    ...row,
+    kind: row.kind as KnowledgeSourceKind,
+    status: row.status as KnowledgeSourceStatus,
+    documents: JSON.parse(row.documents) as KnowledgeDocument[],
+  }
+}
+
+function createKnowledgeSourceRepo(db: DrizzleInstance): KnowledgeSourceRepository {
+  return {
+    async create(input: CreateKnowledgeSourceInput): Promise<KnowledgeSource> {
+      const now = Date.now()
+      const row = {
+        id: uuid(),
+        userId: input.userId,
+        kind: input.kind,
+        location: input.location,
+        name: input.name,
+        status: 'pending',
+        error: null,
+        documents: '[]',
+        indexedAt: null,
+        createdAt: now,
+        updatedAt: now,
+      }
+      db.insert(schema.knowledgeSources).values(row).run()
+      return toKnowledgeSource(row)
+    },
+
+    async getById(id: string): Promise<KnowledgeSource | null> {
+      const row = db.select().from(schema.knowledgeSources).where(eq(schema.knowledgeSources.id, id)).get()
+      return row ? toKnowledgeSource(row) : null
+    },
+
+    async listByUser(userId: string): Promise<KnowledgeSource[]> {
+      return db.select().from(schema.knowledgeSources)
+        .where(eq(schema.knowledgeSources.userId, userId))
+        .orderBy(asc(schema.knowledgeSources.createdAt))
+        .all()
+        .map(toKnowledgeSource)
+    },
+
+    async listByStatus(statuses: KnowledgeSourceStatus[]): Promise<KnowledgeSource[]> {
+      if (statuses.length === 0) return []
+      return db.select().from(schema.knowledgeSources)
+        .where(inArray(schema.knowledgeSources.status, statuses))
+        .orderBy(asc(schema.knowledgeSources.createdAt))
+        .all()
+        .map(toKnowledgeSource)
+    },
+
+    async update(id: string, input: UpdateKnowledgeSourceInput): Promise<KnowledgeSource | null> {
+      const updates: Record<string, unknown> = { updatedAt: Date.now() }
+      if (input.name !== undefined) updates.name = input.name
+      if (input.status !== undefined) updates.status = input.status
+      if (input.error !== undefined) updates.error = input.error
+      if (input.documents !== undefined) updates.documents = JSON.stringify(input.documents)
+      if (input.indexedAt !== undefined) updates.indexedAt = input.indexedAt
+      db.update(schema.knowledgeSources).set(updates).where(eq(schema.knowledgeSources.id, id)).run()
+      return this.getById(id)
+    },
+
+    async delete(id: string): Promise<boolean> {
+      return db.delete(schema.knowledgeSources).where(eq(schema.knowledgeSources.id, id)).run().changes > 0
+    },
+
+    async listDocumentHashes(): Promise<string[]> {
+      const rows = db.select({ documents: schema.knowledgeSources.documents }).from(schema.knowledgeSources).all()
+      return [...new Set(rows.flatMap((row) => (JSON.parse(row.documents) as KnowledgeDocument[]).map((doc) => doc.hash)))]
+    },
+  }
+}
+
+// --- Entities ---
+
+function toEntityRecord(row: typeof schema.entities.$inferSelect): EntityRecord {
+  return {
+    ...row,
+    kind: row.kind as EntityKind,
+    details: JSON.parse(row.details) as Record<string, string>,
+    embedding: row.embedding ? JSON.parse(row.embedding) as number[] : null,
+  }
+}
+
+function createEntityRepo(db: DrizzleInstance): EntityRepository {
+  return {
+    async create(input: CreateEntityInput): Promise<EntityRecord> {
+      const now = Date.now()
+      const row = {
+        id: uuid(),
+        userId: input.userId,
+        kind: input.kind,
+        key: input.key,
+        name: input.name,
+        email: input.email ?? null,
+        details: JSON.stringify(input.details ?? {}),
+        mentions: 1,
+        embedding: null,
+        embeddingModel: null,
+        lastSeenAt: now,
+        createdAt: now,
+        updatedAt: now,
+      }
+      db.insert(schema.entities).values(row).run()
+      return toEntityRecord(row)
+    },
+
+    async getById(id: string): Promise<EntityRecord | null> {
+      const row = db.select().from(schema.entities).where(eq(schema.entities.id, id)).get()
+      return row ? toEntityRecord(row) : null
+    },
+
+    async findByKey(userId: string, kind: EntityKind, key: string): Promise<EntityRecord | null> {
+      const row = db.select().from(schema.entities)
+        .where(and(eq(schema.entities.userId, userId), eq(schema.entities.kind, kind), eq(schema.entities.key, key)))
+        .get()
+      return row ? toEntityRecord(row) : null
+    },
+
+    async listByUser(userId: string): Promise<EntityRecord[]> {
+      return db.select().from(schema.entities)
+        .where(eq(schema.entities.userId, userId))
+        .orderBy(desc(schema.entities.lastSeenAt))
+        .all()
+        .map(toEntityRecord)
+    },
+
+    async update(id: string, input: UpdateEntityInput): Promise<EntityRecord | null> {
+      const updates: Record<string, unknown> = { updatedAt: Date.now() }
+      if (input.name !== undefined) updates.name = input.name
+      if (input.email !== undefined) updates.email = input.email
+      if (input.details !== undefined) updates.details = JSON.stringify(input.details)
+      if (input.mentions !== undefined) updates.mentions = input.mentions
+      if (input.embedding !== undefined) updates.embedding = input.embedding ? JSON.stringify(input.embedding) : null
+      if (input.embeddingModel !== undefined) updates.embeddingModel = input.embeddingModel
+      if (input.lastSeenAt !== undefined) updates.lastSeenAt = input.lastSeenAt
+      db.update(schema.entities).set(updates).where(eq(schema.entities.id, id)).run()
+      return this.getById(id)
+    },
+
+    async delete(id: string): Promise<boolean> {
+      return db.delete(schema.entities).where(eq(schema.entities.id, id)).run().changes > 0
+    },
+  }
+}
+
+// --- Feeds ---
+
+function createFeedRepo(db: DrizzleInstance): FeedRepository {
+  return {
+    async create(input: CreateFeedInput): Promise<Feed> {
+      const now = Date.now()
+      const row = {
+        id: uuid(),
+        userId: input.userId,
+        url: input.url,
+        title: input.title,
+        siteUrl: input.siteUrl ?? null,
+        etag: null,
+        lastModified: null,
+        error: null,
+        fetchedAt: null,
+        createdAt: now,
+        updatedAt: now,
+      }
+      db.insert(schema.feeds).values(row).run()
+      return row
+    },
+
+    async getById(id: string): Promise<Feed | null> {
+      return db.select().from(schema.feeds).where(eq(schema.feeds.id, id)).get() ?? null
+    },
+
+    async findByUrl(userId: string, url: string): Promise<Feed | null> {
+      return db.select().from(schema.feeds)
+        .where(and(eq(schema.feeds.userId, userId), eq(schema.feeds.url, url)))
+        .get() ?? null
+    },
+
+    async listByUser(userId: string): Promise<Feed[]> {
+      return db.select().from(schema.feeds)
+        .where(eq(schema.feeds.userId, userId))
+        .orderBy(asc(schema.feeds.createdAt))
+        .all()
+    },
+
+    async listAll(): Promise<Feed[]> {
+      return db.select().from(schema.feeds).orderBy(asc(schema.feeds.createdAt)).all()
+    },
+
+    async update(id: string, input: UpdateFeedInput): Promise<Feed | null> {
+      const updates: Record<string, unknown> = { updatedAt: Date.now() }
+      if (input.title !== undefined) updates.title = input.title
+      if (input.siteUrl !== undefined) updates.siteUrl = input.siteUrl
+      if (input.etag !== undefined) updates.etag = input.etag
+      if (input.lastModified !== undefined) updates.lastModified = input.lastModified
+      if (input.error !== undefined) updates.error = input.error
+      if (input.fetchedAt !== undefined) updates.fetchedAt = input.fetchedAt
+      db.update(schema.feeds).set(updates).where(eq(schema.feeds.id, id)).run()
+      return this.getById(id)
+    },
+
+    async delete(id: string): Promise<boolean> {
+      return db.transaction((tx) => {
+        tx.delete(schema.feedItems).where(eq(schema.feedItems.feedId, id)).run()
+        return tx.delete(schema.feeds).where(eq(schema.feeds.id, id)).run().changes > 0
+      })
+    },
+
+    async addItems(feedId: string, items: NewFeedItem[]): Promise<number> {
+      const createdAt = Date.now()
+      return db.transaction((tx) => {
+        let added = 0
+        for (const item of items) {
+          added += tx.insert(schema.feedItems)
+            .values({ ...item, id: uuid(), feedId, createdAt })
+            .onConflictDoNothing()
+            .run().changes
+        }
+        return added
+      })
+    },
+
+    async getItem(id: string): Promise<FeedItem | null> {
+      return db.select().from(schema.feedItems).where(eq(schema.feedItems.id, id)).get() ?? null
+    },
+
+    async listItems(feedIds: string[], options: { since?: number; limit: number }): Promise<FeedItem[]> {
+      if (feedIds.length === 0) return []
+      const conditions = [inArray(schema.feedItems.feedId, feedIds)]
+      if (options.since !== undefined) conditions.push(gte(schema.feedItems.publishedAt, options.since))
+      return db.select().from(schema.feedItems)
+        .where(and(...conditions))
+        .orderBy(desc(schema.feedItems.publishedAt))
+        .limit(options.limit)
+        .all()
+    },
+
+    async pruneItems(feedId: string, keep: number): Promise<number> {
+      const stale = db.select({ id: schema.feedItems.id }).from(schema.feedItems)
+        .where(eq(schema.feedItems.feedId, feedId))
+        .orderBy(desc(schema.feedItems.publishedAt))
+        .limit(-1)
+        .offset(keep)
+        .all()
+      if (stale.length === 0) return 0
+      return db.delete(schema.feedItems).where(inArray(schema.feedItems.id, stale.map((row) => row.id))).run().changes
+    },
+  }
+}
+
+// --- Scratchpad ---
+
+function createScratchpadRepo(db: DrizzleInstance): ScratchpadRepository {
+  const entry = (sessionId: string, key: string) =>
+    and(eq(schema.scratchpadEntries.sessionId, sessionId), eq(schema.scratchpadEntries.key, key))
+
+  return {
+    async get(sessionId: string, key: string): Promise<ScratchpadEntry | null> {
+      return db.select().from(schema.scratchpadEntries).where(entry(sessionId, key)).get() ?? null
+    },
+
+    async list(sessionId: string): Promise<ScratchpadEntry[]> {
+      return db.select().from(schema.scratchpadEntries)
+        .where(eq(schema.scratchpadEntries.sessionId, sessionId))
+        .orderBy(asc(schema.scratchpadEntries.key))
+        .all()
+    },
+
+    async set(sessionId: string, key: string, value: string): Promise<ScratchpadEntry> {
+      const row = { sessionId, key, value, updatedAt: Date.now() }
+      db.insert(schema.scratchpadEntries)
+        .values(row)
+        .onConflictDoUpdate({
+          target: [schema.scratchpadEntries.sessionId, schema.scratchpadEntries.key],
+          set: { value: row.value, updatedAt: row.updatedAt },
+        })
+        .run()
+      return row
+    },
+
+    async delete(sessionId: string, key: string): Promise<boolean> {
+      return db.delete(schema.scratchpadEntries).where(entry(sessionId, key)).run().changes > 0
+    },
+  }
+}
+
+// --- Controller prompts ---
+
+function createControllerPromptRepo(db: DrizzleInstance): ControllerPromptRepository {
+  const table = schema.controllerPromptVersions
+
+  return {
+    async listActive(): Promise<ControllerPromptVersion[]> {
+      const rows = db.select().from(table).orderBy(asc(table.name), desc(table.version)).all()
+      return rows.filter((row, index) => index === 0 || rows[index - 1].name !== row.name)
+    },
+
+    async listVersions(name: string): Promise<ControllerPromptVersion[]> {
+      return db.select().from(table).where(eq(table.name, name)).orderBy(desc(table.version)).all()
+    },
+
+    async addVersion(name: string, content: string): Promise<ControllerPromptVersion> {
+      return db.transaction((tx) => {
+        const latest = tx.select({ version: max(table.version) }).from(table).where(eq(table.name, name)).get()
+        const row = { name, version: (latest?.version ?? 0) + 1, content, createdAt: Date.now() }
+        tx.insert(table).values(row).run()
+        return row
+      })
+    },
+  }
+}
+
+// --- Experiments ---
+
+function createExperimentRepo(db: DrizzleInstance): ExperimentRepository {
+  const table = schema.experimentSessions
+
+  return {
+    async assign(input: Omit<ExperimentSession, 'createdAt'>): Promise<ExperimentSession> {
+      const row = { ...input, createdAt: Date.now() }
+      db.insert(table).values(row).run()
+      return row
+    },
+
+    async getBySession(sessionId: string): Promise<ExperimentSession | null> {
+      return db.select().from(table).where(eq(table.sessionId, sessionId)).get() ?? null
+    },
+
+    async listByExperiment(experimentId: string): Promise<ExperimentSession[]> {
+      return db.select().from(table).where(eq(table.experimentId, experimentId)).orderBy(asc(table.createdAt)).all()
+    },
+  }
+}
+
+// --- Orphans ---
+
+function createOrphanRepo(db: DrizzleInstance): OrphanRepository {
+  const where = {
+    toolOutputs: sql`NOT EXISTS (SELECT 1 FROM ${schema.items} WHERE ${schema.items.callId} = ${schema.toolOutputs.callId})`,
+    agents: and(
+      inArray(schema.agents.status, ['completed', 'failed', 'cancelled']),
+      sql`NOT EXISTS (SELECT 1 FROM ${schema.items} WHERE ${schema.items.agentId} = ${schema.agents.id})`,
+      sql`NOT EXISTS (SELECT 1 FROM agents child WHERE child.parent_id = ${schema.agents.id})`,
+      sql`NOT EXISTS (SELECT 1 FROM ${schema.toolOutputs} WHERE ${schema.toolOutputs.agentId} = ${schema.agents.id})`,
+      sql`NOT EXISTS (SELECT 1 FROM ${schema.workflowRuns} WHERE ${schema.workflowRuns.triggerAgentId} = ${schema.agents.id})`,
+    ),
+    usageRecords: and(
+      isNotNull(schema.usageRecords.sessionId),
+      sql`NOT EXISTS (SELECT 1 FROM ${schema.sessions} WHERE ${schema.sessions.id} = ${schema.usageRecords.sessionId})`,
+    ),
+    messageRevisions: sql`NOT EXISTS (SELECT 1 FROM ${schema.items} WHERE ${schema.items.id} = ${schema.messageRevisions.itemId})`,
+  }
+
+  return {
+    async count(): Promise<OrphanCounts> {
+      const n = (rows: Array<{ n: number }>) => rows[0]?.n ?? 0
+      return {
+        toolOutputs: n(db.select({ n: count() }).from(schema.toolOutputs).where(where.toolOutputs).all()),
+        agents: n(db.select({ n: count() }).from(schema.agents).where(where.agents).all()),
+        usageRecords: n(db.select({ n: count() }).from(schema.usageRecords).where(where.usageRecords).all()),
+        messageRevisions: n(db.select({ n: count() }).from(schema.messageRevisions).where(where.messageRevisions).all()),
+      }
+    },
+
+    async purge(): Promise<OrphanCounts> {
+      return db.transaction((tx) => ({
+        toolOutputs: tx.delete(schema.toolOutputs).where(where.toolOutputs).run().changes,
+        agents: tx.delete(schema.agents).where(where.agents).run().changes,
+        usageRecords: tx.delete(schema.usageRecords).where(where.usageRecords).run().changes,
+        messageRevisions: tx.delete(schema.messageRevisions).where(where.messageRevisions).run().changes,
+      }))
+    },
+
+    async listSessionIds(): Promise<string[]> {
+      return db.select({ id: schema.sessions.id }).from(schema.sessions).all().map((row) => row.id)
+    },
+
+    async listAttachmentHashes(): Promise<string[]> {
+      const rows = [
+        ...db.select({ blocks: schema.items.contentBlocks }).from(schema.items)
+          .where(sql`${schema.items.contentBlocks} LIKE '%"hash":%'`).all(),
+        ...db.select({ blocks: schema.messageRevisions.contentBlocks }).from(schema.messageRevisions)
+          .where(sql`${schema.messageRevisions.contentBlocks} LIKE '%"hash":%'`).all(),
+      ]
+      const library = db.selectDistinct({ hash: schema.uploads.hash }).from(schema.uploads).all()
+      return [...new Set([...collectAttachmentHashes(rows.map((row) => row.blocks)), ...library.map((row) => row.hash)])]
+    },
+  }
+}
+
+// --- Maintenance ---
+
+function createMaintenanceRepo(db: DrizzleInstance): MaintenanceRepository {
+  const sizeBytes = () => {
+    const pages = db.get<{ page_count: number }>(sql.raw('PRAGMA page_count'))
+    const pageSize = db.get<{ page_size: number }>(sql.raw('PRAGMA page_size'))
+    return pages.page_count * pageSize.page_size
+  }
+
+  return {
+    async run(): Promise<DatabaseMaintenanceReport> {
+      const sizeBytesBefore = sizeBytes()
+      db.run(sql.raw('PRAGMA wal_checkpoint(TRUNCATE)'))
+
+      db.run(sql.raw('REINDEX'))
+      db.run(sql.raw('VACUUM'))
+      // After VACUUM, which may renumber the rowids that external-content indexes point at
+      const ftsTables = db.all<{ name: string }>(sql.raw(
+        `SELECT name FROM sqlite_master WHERE type = 'table' AND sql LIKE 'CREATE VIRTUAL TABLE%USING fts%'`,
+      ))
+      for (const { name } of ftsTables) {
+        const quoted = `"${name.replace(/"/g, '""')}"`
+        db.run(sql.raw(`INSERT INTO ${quoted}(${quoted}) VALUES('rebuild')`))
+      }
+      db.run(sql.raw('ANALYZE'))
+
+      const problems = db.all<{ quick_check: string }>(sql.raw('PRAGMA quick_check'))
+      return {
+        dialect: 'sqlite',
+        sizeBytesBefore,
+        sizeBytesAfter: sizeBytes(),
+        reindexed: ftsTables.map((table) => table.name),
+        integrity: problems[0]?.quick_check ?? 'ok',
+      }
+    },
+
+    async sizeBytes(): Promise<number> {
+      return sizeBytes()
+    },
+  }
+}
+
+// --- Retention ---
+
+function createRetentionRepo(db: DrizzleInstance): RetentionRepository {
+  const staleOutputs = (before: number, placeholder: string) => and(
+    eq(schema.items.type, 'function_call_output'),
+    lt(schema.items.createdAt, before),
+    isNotNull(schema.items.output),
+    ne(schema.items.output, placeholder),
+  )
+
+  return {
+    async listInactiveSessions(before: number): Promise<InactiveSession[]> {
+      const rows = db
+        .select({
+          id: schema.sessions.id,
+          title: schema.sessions.title,
+          updatedAt: schema.sessions.updatedAt,
+          latestItemAt: max(schema.items.createdAt),
+          itemCount: count(schema.items.id),
+        })
+        .from(schema.sessions)
+        .leftJoin(schema.agents, eq(schema.agents.sessionId, schema.sessions.id))
+        .leftJoin(schema.items, eq(schema.items.agentId, schema.agents.id))
+        .where(lt(schema.sessions.updatedAt, before))
+        .groupBy(schema.sessions.id)
+        .all()
+      return rows
+        .map((row) => ({
+          id: row.id,
+          title: row.title,
+          lastActivityAt: Math.max(row.updatedAt, row.latestItemAt ?? 0),
+          itemCount: row.itemCount,
+        }))
+        .filter((session) => session.lastActivityAt < before)
+    },
+
+    async countToolOutputs(before: number, placeholder: string): Promise<number> {
+      const stored = db
+        .select({ n: count() })
+        .from(schema.toolOutputs)
+        .where(lt(schema.toolOutputs.createdAt, before))
+        .all()
+      const inline = db
+        .select({ n: count() })
+        .from(schema.items)
+        .where(staleOutputs(before, placeholder))
+        .all()
+      return (stored[0]?.n ?? 0) + (inline[0]?.n ?? 0)
+    },
+
+    async purgeToolOutputs(before: number, placeholder: string): Promise<number> {
+      return db.transaction((tx) => {
+        const stored = tx.delete(schema.toolOutputs).where(lt(schema.toolOutputs.createdAt, before)).run().changes
+        const inline = tx
+          .update(schema.items)
+          .set({ output: placeholder })
+          .where(staleOutputs(before, placeholder))
+          .run().changes
+        return stored + inline
+      })
+    },
+
+    async countUsage(before: number): Promise<number> {
+      const rows = db
+        .select({ n: count() })
+        .from(schema.usageRecords)
+        .where(lt(schema.usageRecords.createdAt, before))
+        .all()
+      return rows[0]?.n ?? 0
+    },
+
+    async purgeUsage(before: number): Promise<number> {
+      return db.delete(schema.usageRecords).where(lt(schema.usageRecords.createdAt, before)).run().changes
+    },
+  }
+}
+
+// --- Sync ---
+
+/** Preference keys that hold per-device sync state and must never replicate. */
+const LOCAL_PREFERENCE_PREFIX = 'sync:'
+
+function createSyncRepo(db: DrizzleInstance): SyncRepository {
+  const sessions = createSessionRepo(db)
+
+  return {
+    async listChangesSince(since: number): Promise<SyncRecord[]> {
+      const sessionRows = db.select().from(schema.sessions)
+        .where(sql`${schema.sessions.updatedAt} >= ${since}`)
+        .orderBy(asc(schema.sessions.createdAt))
+        .all()
+      const agentRows = db.select().from(schema.agents)
+        .where(sql`${schema.agents.updatedAt} >= ${since}`)
+        .orderBy(asc(schema.agents.createdAt))
+        .all()
+      const itemRows = db.select().from(schema.items)
+        .where(sql`coalesce(${schema.items.updatedAt}, ${schema.items.createdAt}) >= ${since}`)
+        .orderBy(asc(schema.items.createdAt), asc(schema.items.sequence))
+        .all()
+      const preferenceRows = db.select().from(schema.preferences)
+        .where(sql`${schema.preferences.updatedAt} >= ${since}`)
+        .all()
+        .filter((row) => !row.key.startsWith(LOCAL_PREFERENCE_PREFIX))
+
+      return [
+        ...sessionRows.map((row) => ({ kind: 'session' as const, id: row.id, updatedAt: row.updatedAt ?? 0, data: row })),
+        ...agentRows.map((row) => ({ kind: 'agent' as const, id: row.id, updatedAt: row.updatedAt ?? 0, data: row })),
+        ...itemRows.map((row) => ({ kind: 'item' as const, id: row.id, updatedAt: row.updatedAt ?? row.createdAt, data: row })),
+        ...preferenceRows.map((row) => ({ kind: 'preference' as const, id: row.key, updatedAt: row.updatedAt ?? 0, data: row })),
+      ]
+    },
+
+    async applyRecord(record: SyncRecord, localUserId: string): Promise<boolean> {
+      switch (record.kind) {
+        case 'session': {
+          const existing = db.select().from(schema.sessions).where(eq(schema.sessions.id, record.id)).limit(1).all()[0]
+          if (existing && (existing.updatedAt ?? 0) >= record.updatedAt) return false
+          if (record.deleted) {
+            if (existing) await sessions.delete(record.id)
+            return Boolean(existing)
+          }
+          const data = record.data as typeof schema.sessions.$inferSelect
+          const row = {
+            ...data,
+            userId: localUserId,
+            parentSessionId: data.parentSessionId && rowExists(db, 'session', data.parentSessionId)
+              ? data.parentSessionId
+              : null,
+            forkedFromItemId: data.forkedFromItemId && rowExists(db, 'item', data.forkedFromItemId)
+              ? data.forkedFromItemId
+              : null,
+          }
+          db.insert(schema.sessions).values(row)
+            .onConflictDoUpdate({ target: schema.sessions.id, set: row })
+            .run()
+          return true
+        }
+        case 'agent': {
+          const existing = db.select().from(schema.agents).where(eq(schema.agents.id, record.id)).limit(1).all()[0]
+          if (existing && (existing.updatedAt ?? 0) >= record.updatedAt) return false
+          const data = record.data as typeof schema.agents.$inferSelect
+          if (!data.sessionId || !rowExists(db, 'session', data.sessionId)) return false
+          const row = {
+            ...data,
+            parentId: data.parentId && rowExists(db, 'agent', data.parentId) ? data.parentId : null,
+          }
+          db.insert(schema.agents).values(row)
+            .onConflictDoUpdate({ target: schema.agents.id, set: row })
+            .run()
+          return true
+        }
+        case 'item': {
+          const existing = db.select().from(schema.items).where(eq(schema.items.id, record.id)).limit(1).all()[0]
+          if (existing && (existing.updatedAt ?? existing.createdAt) >= record.updatedAt) return false
+          const data = record.data as typeof schema.items.$inferSelect
+          if (!data.agentId || !rowExists(db, 'agent', data.agentId)) return false
+          db.insert(schema.items).values(data)
+            .onConflictDoUpdate({ target: schema.items.id, set: data })
+            .run()
+          return true
+        }
+        case 'preference': {
+          if (record.id.startsWith(LOCAL_PREFERENCE_PREFIX)) return false
+          const existing = db.select().from(schema.preferences).where(eq(schema.preferences.key, record.id)).limit(1).all()[0]
+          if (existing && (existing.updatedAt ?? 0) >= record.updatedAt) return false
+          if (record.deleted) {
+            db.delete(schema.preferences).where(eq(schema.preferences.key, record.id)).run()
+            return Boolean(existing)
+          }
+          const data = record.data as typeof schema.preferences.$inferSelect
+          db.insert(schema.preferences).values(data)
+            .onConflictDoUpdate({ target: schema.preferences.key, set: { value: data.value, updatedAt: data.updatedAt } })
+            .run()
+          return true
+        }
+      }
+    },
+  }
+}
+
+function rowExists(db: DrizzleInstance, kind: 'session' | 'agent' | 'item', id: string): boolean {
+  switch (kind) {
+    case 'session':
+      return db.select({ id: schema.sessions.id }).from(schema.sessions).where(eq(schema.sessions.id, id)).limit(1).all().length > 0
+    case 'agent':
+      return db.select({ id: schema.agents.id }).from(schema.agents).where(eq(schema.agents.id, id)).limit(1).all().length > 0
+    case 'item':
+      return db.select({ id: schema.items.id }).from(schema.items).where(eq(schema.items.id, id)).limit(1).all().length > 0
+  }
+}
+
+// --- Public API ---
+
+export interface CreateDatabaseOptions {
+  /** SQLCipher passphrase. Requires a better-sqlite3 build linked against an encrypting SQLite. */
+  key?: string
+  /**
+   * How long a statement waits on a lock held by another connection (the
+   * encrypt-db script, a backup tool, a second server process) before failing
+   * with SQLITE_BUSY.
+   */
+  busyTimeoutMs?: number
+}
+
+export function createDatabase(url: string, options: CreateDatabaseOptions = {}): DrizzleInstance {
+  const sqlite = new Database(url, { timeout: options.busyTimeoutMs ?? 5000 })
+  if (options.key) {
+    applyDatabaseKey(sqlite, options.key)
+  }
+  sqlite.pragma('journal_mode = WAL')
+  // WAL makes NORMAL durable across application crashes; only power loss can
+  // roll back the most recent commits.
+  sqlite.pragma('synchronous = NORMAL')
+  sqlite.pragma('foreign_keys = ON')
+
+  // Auto-create tables if they don't exist
+  sqlite.exec(`
+    CREATE TABLE IF NOT EXISTS users (
+      id TEXT PRIMARY KEY,
+      email TEXT UNIQUE,
+      api_key_hash TEXT UNIQUE NOT NULL,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS sessions (
+      id TEXT PRIMARY KEY,
+      user_id TEXT REFERENCES users(id),
+      root_agent_id TEXT,
+      parent_session_id TEXT REFERENCES sessions(id),
+      forked_from_item_id TEXT REFERENCES items(id),
+      source TEXT,
+      title TEXT,
+      summary TEXT,
+      status TEXT DEFAULT 'active',
+      deleted_at INTEGER,
+      created_at INTEGER,
+      updated_at INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS agents (
+      id TEXT PRIMARY KEY,
+      session_id TEXT REFERENCES sessions(id),
+      parent_id TEXT REFERENCES agents(id),
+      source_call_id TEXT,
+      depth INTEGER DEFAULT 0,
+      task TEXT NOT NULL,
+      config TEXT,
+      status TEXT DEFAULT 'pending',
+      waiting_for TEXT DEFAULT '[]',
+      result TEXT,
+      error TEXT,
+      error_code TEXT,
+      turn_count INTEGER DEFAULT 0,
+      plan TEXT,
+      created_at INTEGER,
+      updated_at INTEGER,
+      completed_at INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS items (
+      id TEXT PRIMARY KEY,
+      agent_id TEXT REFERENCES agents(id),
+      sequence INTEGER NOT NULL,
+      type TEXT NOT NULL,
+      role TEXT,
+      content TEXT,
+      call_id TEXT,
+      name TEXT,
+      arguments TEXT,
+      output TEXT,
+      is_error INTEGER,
+      save_output INTEGER,
+      turn_number INTEGER,
+      duration_ms INTEGER,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS api_keys (
+      id TEXT PRIMARY KEY,
+      provider TEXT NOT NULL,
+      api_key TEXT NOT NULL,
+      created_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS models (
+      id TEXT PRIMARY KEY,
+      provider TEXT NOT NULL,
+      model_name TEXT NOT NULL,
+      enabled INTEGER DEFAULT 1,
+      created_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS system_prompts (
+      id TEXT PRIMARY KEY,
+      name TEXT NOT NULL,
+      content TEXT NOT NULL,
+      created_at INTEGER,
+      updated_at INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS tool_outputs (
+      id TEXT PRIMARY KEY,
+      agent_id TEXT REFERENCES agents(id),
+      call_id TEXT,
+      tool_name TEXT NOT NULL,
+      data TEXT,
+      created_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS preferences (
+      key TEXT PRIMARY KEY,
+      value TEXT NOT NULL,
+      updated_at INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS workflow_runs (
+      id TEXT PRIMARY KEY,
+      workflow_name TEXT NOT NULL,
+      session_id TEXT NOT NULL REFERENCES sessions(id),
+      trigger_agent_id TEXT REFERENCES agents(id),
+      trigger_call_id TEXT,
+      status TEXT NOT NULL DEFAULT 'pending',
+      input TEXT,
+      output TEXT,
+      error TEXT,
+      started_at INTEGER,
+      completed_at INTEGER,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS mcp_servers (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL REFERENCES users(id),
+      auth_mode TEXT NOT NULL DEFAULT 'auto',
+      name TEXT NOT NULL,
+      transport TEXT NOT NULL,
+      command TEXT,
+      args TEXT,
+      env TEXT,
+      cwd TEXT,
+      url TEXT,
+      bearer_token TEXT,
+      enabled INTEGER NOT NULL DEFAULT 0,
+      status TEXT NOT NULL DEFAULT 'disabled',
+      error TEXT,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS mcp_tools (
+      id TEXT PRIMARY KEY,
+      server_id TEXT NOT NULL REFERENCES mcp_servers(id) ON DELETE CASCADE,
+      remote_name TEXT NOT NULL,
+      registered_name TEXT NOT NULL,
+      description TEXT,
+      input_schema TEXT,
+      enabled_for_new_sessions INTEGER NOT NULL DEFAULT 1,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS mcp_oauth_credentials (
+      server_id TEXT PRIMARY KEY REFERENCES mcp_servers(id) ON DELETE CASCADE,
+      user_id TEXT NOT NULL REFERENCES users(id),
+      resource_url TEXT NOT NULL,
+      tokens TEXT,
+      client_information TEXT,
+      discovery TEXT,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS mcp_oauth_sessions (
+      id TEXT PRIMARY KEY,
+      server_id TEXT NOT NULL REFERENCES mcp_servers(id) ON DELETE CASCADE,
+      user_id TEXT NOT NULL REFERENCES users(id),
+      state_hash TEXT UNIQUE NOT NULL,
+      code_verifier TEXT NOT NULL,
+      status TEXT NOT NULL,
+      error TEXT,
+      expires_at INTEGER NOT NULL,
+      consumed_at INTEGER,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS telegram_connections (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL REFERENCES users(id),
+      bot_token TEXT NOT NULL,
+      bot_username TEXT,
+      allowed_telegram_user_id TEXT NOT NULL,
+      webhook_path_secret TEXT NOT NULL,
+      webhook_header_secret TEXT NOT NULL,
       webhook_url TEXT,
       status TEXT NOT NULL DEFAULT 'disconnected',
       last_error TEXT,
@@ -1421,8 +2714,189 @@ export function createDatabase(url: string): DrizzleInstance {
       created_at INTEGER NOT NULL,
       PRIMARY KEY (connection_id, telegram_update_id)
     );
+    CREATE TABLE IF NOT EXISTS message_revisions (
+      id TEXT PRIMARY KEY,
+      item_id TEXT NOT NULL,
+      session_id TEXT NOT NULL,
+      revision INTEGER NOT NULL,
+      role TEXT,
+      content TEXT,
+      content_blocks TEXT,
+      reason TEXT NOT NULL,
+      created_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS usage_records (
+      id TEXT PRIMARY KEY,
+      user_id TEXT,
+      session_id TEXT,
+      agent_id TEXT,
+      provider TEXT NOT NULL,
+      model TEXT NOT NULL,
+      phase TEXT,
+      input_tokens INTEGER NOT NULL,
+      output_tokens INTEGER NOT NULL,
+      cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+      cache_write_tokens INTEGER NOT NULL DEFAULT 0,
+      cost_usd REAL NOT NULL DEFAULT 0,
+      cache_savings_usd REAL NOT NULL DEFAULT 0,
+      tool_context TEXT,
+      prompt_composition TEXT,
+      created_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS approval_records (
+      id TEXT PRIMARY KEY,
+      user_id TEXT,
+      session_id TEXT NOT NULL,
+      agent_id TEXT NOT NULL,
+      call_id TEXT NOT NULL,
+      tool_name TEXT NOT NULL,
+      args TEXT NOT NULL,
+      amended_args TEXT,
+      outcome TEXT NOT NULL,
+      scope TEXT,
+      rule_id TEXT,
+      requested_at INTEGER NOT NULL,
+      decided_at INTEGER NOT NULL,
+      latency_ms INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS model_prices (
+      model_id TEXT PRIMARY KEY,
+      input REAL NOT NULL,
+      output REAL NOT NULL,
+      cache_read REAL,
+      cache_write REAL,
+      source TEXT NOT NULL,
+      updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS model_catalog (
+      provider TEXT NOT NULL,
+      model_id TEXT NOT NULL,
+      display_name TEXT,
+      context_window INTEGER,
+      max_output_tokens INTEGER,
+      vision INTEGER,
+      structured_outputs INTEGER,
+      tools INTEGER,
+      fetched_at INTEGER NOT NULL,
+      PRIMARY KEY (provider, model_id)
+    );
+    CREATE TABLE IF NOT EXISTS uploads (
+      user_id TEXT NOT NULL,
+      hash TEXT NOT NULL,
+      name TEXT NOT NULL,
+      media_type TEXT NOT NULL,
+      kind TEXT NOT NULL,
+      bytes INTEGER NOT NULL,
+      created_at INTEGER NOT NULL,
+      last_used_at INTEGER NOT NULL,
+      PRIMARY KEY (user_id, hash)
+    );
+    CREATE TABLE IF NOT EXISTS memories (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL,
+      content TEXT NOT NULL,
+      kind TEXT NOT NULL,
+      category TEXT NOT NULL DEFAULT 'other',
+      confidence REAL NOT NULL,
+      embedding TEXT,
+      embedding_model TEXT,
+      source_session_id TEXT,
+      source_item_id TEXT,
+      disabled INTEGER NOT NULL DEFAULT 0,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS knowledge_sources (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL,
+      kind TEXT NOT NULL,
+      location TEXT NOT NULL,
+      name TEXT NOT NULL,
+      status TEXT NOT NULL,
+      error TEXT,
+      documents TEXT NOT NULL,
+      indexed_at INTEGER,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS entities (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL,
+      kind TEXT NOT NULL,
+      key TEXT NOT NULL,
+      name TEXT NOT NULL,
+      email TEXT,
+      details TEXT NOT NULL,
+      mentions INTEGER NOT NULL,
+      embedding TEXT,
+      embedding_model TEXT,
+      last_seen_at INTEGER NOT NULL,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS scratchpad_entries (
+      session_id TEXT NOT NULL,
+      key TEXT NOT NULL,
+      value TEXT NOT NULL,
+      updated_at INTEGER NOT NULL,
+      PRIMARY KEY (session_id, key)
+    );
+    CREATE TABLE IF NOT EXISTS controller_prompt_versions (
+      name TEXT NOT NULL,
+      version INTEGER NOT NULL,
+      content TEXT NOT NULL,
+      created_at INTEGER NOT NULL,
+      PRIMARY KEY (name, version)
+    );
+    CREATE TABLE IF NOT EXISTS experiment_sessions (
+      session_id TEXT PRIMARY KEY,
+      experiment_id TEXT NOT NULL,
+      variant TEXT NOT NULL,
+      created_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS feeds (
+      id TEXT PRIMARY KEY,
+      user_id TEXT NOT NULL,
+      url TEXT NOT NULL,
+      title TEXT NOT NULL,
+      site_url TEXT,
+      etag TEXT,
+      last_modified TEXT,
+      error TEXT,
+      fetched_at INTEGER,
+      created_at INTEGER NOT NULL,
+      updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS feed_items (
+      id TEXT PRIMARY KEY,
+      feed_id TEXT NOT NULL,
+      guid TEXT NOT NULL,
+      title TEXT NOT NULL,
+      link TEXT,
+      author TEXT,
+      summary TEXT NOT NULL,
+      content TEXT NOT NULL,
+      published_at INTEGER NOT NULL,
+      created_at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS uploads_hash_idx ON uploads(hash);
+    CREATE INDEX IF NOT EXISTS memories_user_id_idx ON memories(user_id, updated_at);
+    CREATE INDEX IF NOT EXISTS knowledge_sources_user_id_idx ON knowledge_sources(user_id, created_at);
+    CREATE INDEX IF NOT EXISTS knowledge_sources_status_idx ON knowledge_sources(status);
+    CREATE UNIQUE INDEX IF NOT EXISTS entities_user_kind_key_idx ON entities(user_id, kind, key);
+    CREATE UNIQUE INDEX IF NOT EXISTS feeds_user_url_idx ON feeds(user_id, url);
+    CREATE UNIQUE INDEX IF NOT EXISTS feed_items_feed_guid_idx ON feed_items(feed_id, guid);
+    CREATE INDEX IF NOT EXISTS feed_items_feed_published_idx ON feed_items(feed_id, published_at);
+    CREATE INDEX IF NOT EXISTS experiment_sessions_experiment_idx ON experiment_sessions(experiment_id);
     CREATE INDEX IF NOT EXISTS agents_session_id_idx ON agents(session_id);
     CREATE INDEX IF NOT EXISTS agents_status_idx ON agents(status);
+    CREATE INDEX IF NOT EXISTS usage_records_user_created_idx ON usage_records(user_id, created_at);
+    CREATE INDEX IF NOT EXISTS usage_records_session_id_idx ON usage_records(session_id);
+    CREATE INDEX IF NOT EXISTS approval_records_user_decided_idx ON approval_records(user_id, decided_at);
+    CREATE INDEX IF NOT EXISTS approval_records_session_id_idx ON approval_records(session_id);
+    CREATE INDEX IF NOT EXISTS approval_records_tool_name_idx ON approval_records(tool_name);
+    CREATE INDEX IF NOT EXISTS message_revisions_item_id_idx ON message_revisions(item_id, revision);
+    CREATE INDEX IF NOT EXISTS message_revisions_session_id_idx ON message_revisions(session_id);
     CREATE INDEX IF NOT EXISTS items_agent_id_sequence_idx ON items(agent_id, sequence);
     CREATE INDEX IF NOT EXISTS items_call_id_idx ON items(call_id);
     CREATE INDEX IF NOT EXISTS workflow_runs_session_id_idx ON workflow_runs(session_id);
@@ -1442,6 +2916,24 @@ export function createDatabase(url: string): DrizzleInstance {
       ON telegram_message_links(connection_id, telegram_chat_id, telegram_message_id);
   `)
 
+  // Full-text index over message text for history.search, kept in step with items by triggers.
+  // A database that predates it is indexed once when the table is first created.
+  const hadItemsFts = sqlite.prepare(`SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'items_fts'`).get()
+  sqlite.exec(`
+    CREATE VIRTUAL TABLE IF NOT EXISTS items_fts USING fts5(content, content = 'items', content_rowid = 'rowid');
+    CREATE TRIGGER IF NOT EXISTS items_fts_insert AFTER INSERT ON items BEGIN
+      INSERT INTO items_fts(rowid, content) VALUES (new.rowid, new.content);
+    END;
+    CREATE TRIGGER IF NOT EXISTS items_fts_delete AFTER DELETE ON items BEGIN
+      INSERT INTO items_fts(items_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
+    END;
+    CREATE TRIGGER IF NOT EXISTS items_fts_update AFTER UPDATE OF content ON items BEGIN
+      INSERT INTO items_fts(items_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
+      INSERT INTO items_fts(rowid, content) VALUES (new.rowid, new.content);
+    END;
+  `)
+  if (!hadItemsFts) sqlite.exec(`INSERT INTO items_fts(items_fts) VALUES ('rebuild')`)
+
   // Incremental migrations — ADD COLUMN IF NOT EXISTS (SQLite has no such syntax, so try/catch)
   try { sqlite.exec(`ALTER TABLE items ADD COLUMN content_blocks TEXT;`) } catch { /* already exists */ }
   try { sqlite.exec(`ALTER TABLE workflow_runs ADD COLUMN steps TEXT;`) } catch { /* already exists */ }
@@ -1450,6 +2942,20 @@ export function createDatabase(url: string): DrizzleInstance {
   try { sqlite.exec(`ALTER TABLE sessions ADD COLUMN source TEXT;`) } catch { /* already exists */ }
   try { sqlite.exec(`ALTER TABLE mcp_servers ADD COLUMN user_id TEXT REFERENCES users(id);`) } catch { /* already exists */ }
   try { sqlite.exec(`ALTER TABLE mcp_servers ADD COLUMN auth_mode TEXT NOT NULL DEFAULT 'auto';`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE preferences ADD COLUMN updated_at INTEGER;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE sessions ADD COLUMN deleted_at INTEGER;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE agents ADD COLUMN error_code TEXT;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE approval_records ADD COLUMN amended_args TEXT;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE usage_records ADD COLUMN cache_savings_usd REAL NOT NULL DEFAULT 0;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE usage_records ADD COLUMN phase TEXT;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE usage_records ADD COLUMN tool_context TEXT;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE usage_records ADD COLUMN prompt_composition TEXT;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE model_prices ADD COLUMN cache_read REAL;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE model_prices ADD COLUMN cache_write REAL;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE memories ADD COLUMN source_item_id TEXT;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE memories ADD COLUMN disabled INTEGER NOT NULL DEFAULT 0;`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE memories ADD COLUMN category TEXT NOT NULL DEFAULT 'other';`) } catch { /* already exists */ }
+  try { sqlite.exec(`ALTER TABLE items ADD COLUMN updated_at INTEGER;`) } catch { /* already exists */ }
   sqlite.exec(`
     UPDATE mcp_servers
     SET user_id = (SELECT id FROM users ORDER BY created_at ASC LIMIT 1)
@@ -1479,6 +2985,94 @@ export function createDatabase(url: string): DrizzleInstance {
   return drizzle(sqlite, { schema })
 }
 
+/**
+ * Unlock an encrypted database. Must run before any other statement touches
+ * the file. Fails closed when the linked SQLite has no cipher support, since
+ * `PRAGMA key` is silently ignored by stock builds.
+ */
+export function applyDatabaseKey(sqlite: Database.Database, key: string): void {
+  sqlite.pragma(`key = ${quoteSqlString(key)}`)
+  if (!hasCipherSupport(sqlite)) {
+    throw new Error('DATABASE_KEY is set but the sqlite driver was built without encryption support (SQLCipher or SQLite3MultipleCiphers)')
+  }
+  try {
+    sqlite.prepare('SELECT count(*) FROM sqlite_master').get()
+  } catch {
+    throw new Error('Unable to open sqlite database: DATABASE_KEY is wrong or the file is not encrypted')
+  }
+}
+
+/**
+ * Encrypt a plaintext database file with `key`. SQLCipher cannot rekey a
+ * plaintext file in place, so the data is exported into an encrypted copy
+ * beside it, which then replaces the original.
+ */
+export function encryptDatabaseFile(file: string, key: string): void {
+  const copy = `${file}.encrypting`
+  rmSync(copy, { force: true })
+  const plain = new Database(file, { fileMustExist: true })
+  try {
+    if (!hasCipherSupport(plain)) {
+      throw new Error('The sqlite driver was built without encryption support (SQLCipher or SQLite3MultipleCiphers)')
+    }
+    plain.prepare('SELECT count(*) FROM sqlite_master').get()
+    plain.pragma('wal_checkpoint(TRUNCATE)')
+    const userVersion = plain.pragma('user_version', { simple: true }) as number
+    plain.prepare('ATTACH DATABASE ? AS encrypted KEY ?').run(copy, key)
+    plain.prepare("SELECT sqlcipher_export('encrypted')").get()
+    plain.pragma(`encrypted.user_version = ${Number(userVersion)}`)
+    plain.exec('DETACH DATABASE encrypted')
+  } catch (err) {
+    plain.close()
+    rmSync(copy, { force: true })
+    throw err
+  }
+  plain.close()
+
+  const check = new Database(copy, { fileMustExist: true })
+  try {
+    applyDatabaseKey(check, key)
+  } catch (err) {
+    check.close()
+    rmSync(copy, { force: true })
+    throw err
+  }
+  check.close()
+  renameSync(copy, file)
+  rmSync(`${file}-wal`, { force: true })
+  rmSync(`${file}-shm`, { force: true })
+}
+
+/**
+ * Change the key of a database that is already encrypted. WAL must be off
+ * while the pages are rewritten, so it is switched back on afterwards.
+ */
+export function rekeyDatabase(sqlite: Database.Database, newKey: string): void {
+  if (!hasCipherSupport(sqlite)) {
+    throw new Error('The sqlite driver was built without encryption support (SQLCipher or SQLite3MultipleCiphers)')
+  }
+  sqlite.pragma('wal_checkpoint(TRUNCATE)')
+  sqlite.pragma('journal_mode = DELETE')
+  sqlite.pragma(`rekey = ${quoteSqlString(newKey)}`)
+  sqlite.pragma('journal_mode = WAL')
+}
+
+export function hasCipherSupport(sqlite: Database.Database): boolean {
+  // SQLCipher exposes cipher_version; SQLite3MultipleCiphers exposes cipher.
+  for (const pragma of ['cipher_version', 'cipher']) {
+    try {
+      if (sqlite.pragma(pragma, { simple: true })) return true
+    } catch {
+      // Unknown pragmas are ignored or rejected depending on the build
+    }
+  }
+  return false
+}
+
+function quoteSqlString(value: string): string {
+  return `'${value.replace(/'/g, "''")}'`
+}
+
 export class SQLiteRepositories {
   users: UserRepository
   sessions: SessionRepository
@@ -1492,6 +3086,23 @@ export class SQLiteRepositories {
   workflowRuns: WorkflowRunRepository
   mcp: McpRepository
   telegram: TelegramRepository
+  sync: SyncRepository
+  usage: UsageRepository
+  approvalHistory: ApprovalHistoryRepository
+  modelPrices: ModelPriceRepository
+  modelCatalog: ModelCatalogRepository
+  uploads: UploadRepository
+  memories: MemoryRepository
+  knowledge: KnowledgeSourceRepository
+  scratchpad: ScratchpadRepository
+  controllerPrompts: ControllerPromptRepository
+  experiments: ExperimentRepository
+  entities: EntityRepository
+  feeds: FeedRepository
+  retention: RetentionRepository
+  messageRevisions: MessageRevisionRepository
+  maintenance: MaintenanceRepository
+  orphans: OrphanRepository
 
   constructor(db: DrizzleInstance, encryptionKey?: string) {
     const encKey = encryptionKey ? deriveKey(encryptionKey) : null
@@ -1507,5 +3118,22 @@ export class SQLiteRepositories {
     this.workflowRuns = createWorkflowRunRepo(db)
     this.mcp = createMcpRepo(db, encKey)
     this.telegram = createTelegramRepo(db)
+    this.sync = createSyncRepo(db)
+    this.usage = createUsageRepo(db)
+    this.approvalHistory = createApprovalHistoryRepo(db)
+    this.modelPrices = createModelPriceRepo(db)
+    this.modelCatalog = createModelCatalogRepo(db)
+    this.uploads = createUploadRepo(db)
+    this.memories = createMemoryRepo(db)
+    this.knowledge = createKnowledgeSourceRepo(db)
+    this.scratchpad = createScratchpadRepo(db)
+    this.controllerPrompts = createControllerPromptRepo(db)
+    this.experiments = createExperimentRepo(db)
+    this.entities = createEntityRepo(db)
+    this.feeds = createFeedRepo(db)
+    this.retention = createRetentionRepo(db)
+    this.messageRevisions = createMessageRevisionRepo(db)
+    this.maintenance = createMaintenanceRepo(db)
+    this.orphans = createOrphanRepo(db)
   }
 }
@@ -1,6 +1,7 @@
 import type {
   Agent,
   AgentConfig,
+  AgentErrorCode,
   AgentStatus,
   Item,
   ItemContentBlock,
@@ -14,6 +15,7 @@ import type {
   WaitingFor,
 } from '../domain/types.js'
 import type { WorkflowRun, WorkflowRunStatus } from '../workflows/types.js'
+import type { PromptComposition } from '../events/payloads.js'
 
 // --- Input types (omit auto-generated fields) ---
 
@@ -31,9 +33,12 @@ export interface UpdateAgentInput {
   waitingFor?: WaitingFor[]
   result?: string | null
   error?: string | null
+  errorCode?: AgentErrorCode | null
   turnCount?: number
   plan?: Plan | null
   completedAt?: number | null
+  /** Replaces the whole config, e.g. when a routed follow-up runs on another model. */
+  config?: AgentConfig
 }
 
 export interface CreateSessionInput {
@@ -52,6 +57,7 @@ export interface UpdateSessionInput {
   parentSessionId?: string | null
   forkedFromItemId?: string | null
   source?: string | null
+  deletedAt?: number | null
 }
 
 export interface CreateUserInput {
@@ -142,7 +148,14 @@ export interface AgentRepository {
   create(input: CreateAgentInput): Promise<Agent>
   getById(id: string): Promise<Agent | null>
   update(id: string, input: UpdateAgentInput): Promise<Agent>
-  failRunningOrWaiting(error: string): Promise<void>
+  /**
+   * Records how a run ended: applies `input` and saves the closing `message`
+   * in one transaction, so a failure leaves neither behind.
+   */
+  finish(id: string, input: UpdateAgentInput, message?: CreateItemInput): Promise<Agent>
+  failRunningOrWaiting(error: string, errorCode: AgentErrorCode): Promise<void>
+  /** Agents that are running or waiting, across all sessions. */
+  listRunningOrWaiting(): Promise<Agent[]>
   findWaitingForCall(callId: string): Promise<Agent | null>
   findRootAgent(sessionId: string): Promise<Agent | null>
   listBySession(sessionId: string): Promise<Agent[]>
@@ -153,15 +166,44 @@ export interface ItemRepository {
   create(input: CreateItemInput): Promise<Item>
   getById(id: string): Promise<Item | null>
   listByAgent(agentId: string): Promise<Item[]>
+  /**
+   * The agent's items from its `userTurns`-th latest user message on, plus
+   * its earlier system messages, which carry prompt instructions. Returns
+   * everything when the agent has fewer user messages.
+   */
+  listRecentByAgent(agentId: string, userTurns: number): Promise<Item[]>
   listBySession(sessionId: string): Promise<Item[]>
   getOutputByCallId(callId: string): Promise<Item | null>
+  /** Items whose content blocks still carry base64 payloads inline. */
+  listWithInlineAttachments(): Promise<Item[]>
+  updateContentBlocks(id: string, contentBlocks: ItemContentBlock[] | null): Promise<void>
+  /**
+   * The user's conversation messages containing any of `terms`, best
+   * full-text match first. Only user and assistant messages of top-level
+   * runs in live sessions are searched.
+   */
+  searchMessages(userId: string, terms: string[], options?: { excludeSessionId?: string; limit?: number }): Promise<MessageSearchHit[]>
+}
+
+export interface MessageSearchHit {
+  itemId: string
+  sessionId: string
+  sessionTitle: string | null
+  role: 'user' | 'assistant'
+  content: string
+  createdAt: number
 }
 
 export interface SessionRepository {
   create(input: CreateSessionInput): Promise<Session>
   getById(id: string): Promise<Session | null>
+  /** Live sessions only; trashed sessions are listed via listTrashed. */
   listByUser(userId: string): Promise<Session[]>
+  listTrashed(userId: string): Promise<Session[]>
+  /** Trashed sessions (any user) whose deletedAt is at or before `cutoff`. */
+  listTrashedBefore(cutoff: number): Promise<Session[]>
   update(id: string, input: UpdateSessionInput): Promise<Session>
+  /** Permanently remove the session and everything it owns. */
   delete(id: string): Promise<void>
 }
 
@@ -448,3 +490,664 @@ export interface TelegramRepository {
   getSessionHeadLink(connectionId: string, sessionId: string): Promise<StoredTelegramMessageLink | null>
   createMessageLink(input: CreateStoredTelegramMessageLinkInput): Promise<void>
 }
+
+// --- File-based sync ---
+
+export type SyncRecordKind = 'session' | 'agent' | 'item' | 'preference'
+
+export interface SyncRecord {
+  kind: SyncRecordKind
+  /** Row id, or the preference key. */
+  id: string
+  /** Last-writer-wins clock: updatedAt, or createdAt for items never edited. */
+  updatedAt: number
+  deleted?: boolean
+  /** Raw row in schema field names; null for tombstones. */
+  data: Record<string, unknown> | null
+}
+
+export interface SyncRepository {
+  /** Rows changed at or after `since`, ordered so parents precede children. */
+  listChangesSince(since: number): Promise<SyncRecord[]>
+  /**
+   * Apply a remote record when it is newer than the local row. Sessions are
+   * re-owned by `localUserId`. Returns false when skipped (stale or orphaned).
+   */
+  applyRecord(record: SyncRecord, localUserId: string): Promise<boolean>
+}
+
+// --- Message revisions ---
+
+export type MessageRevisionReason = 'edit' | 'regenerate' | 'restore'
+
+/** A prior version of a message item, captured before it was overwritten. */
+export interface MessageRevision {
+  id: string
+  itemId: string
+  sessionId: string
+  /** 1-based, increasing per item. */
+  revision: number
+  role: ItemRole | null
+  content: string | null
+  contentBlocks: ItemContentBlock[] | null
+  /** Why the version was replaced. */
+  reason: MessageRevisionReason
+  createdAt: number
+}
+
+export interface MessageRevisionRepository {
+  /** Snapshot `item` as the next revision, then overwrite its content — atomically. */
+  revise(
+    item: Item,
+    sessionId: string,
+    reason: MessageRevisionReason,
+    next: { content: string | null; contentBlocks: ItemContentBlock[] | null },
+  ): Promise<MessageRevision>
+  listByItem(itemId: string): Promise<MessageRevision[]>
+  getById(id: string): Promise<MessageRevision | null>
+}
+
+// --- Orphans ---
+
+/** Rows whose owner no longer exists. */
+export interface OrphanCounts {
+  /** Stored tool outputs with no matching function call item. */
+  toolOutputs: number
+  /** Finished agents that never recorded an item and own nothing. */
+  agents: number
+  /** Usage rows attributed to a session that is gone. */
+  usageRecords: number
+  /** Revisions of messages that are gone. */
+  messageRevisions: number
+}
+
+export interface OrphanRepository {
+  count(): Promise<OrphanCounts>
+  purge(): Promise<OrphanCounts>
+  listSessionIds(): Promise<string[]>
+  /** Attachment hashes still referenced by items or message revisions. */
+  listAttachmentHashes(): Promise<string[]>
+}
+
+// --- Maintenance ---
+
+export interface DatabaseMaintenanceReport {
+  dialect: 'sqlite' | 'postgres'
+  sizeBytesBefore: number
+  sizeBytesAfter: number
+  /** Full-text indexes rebuilt; empty while the schema has none. */
+  reindexed: string[]
+  /** 'ok' or the first problem reported; null where the dialect has no cheap check. */
+  integrity: string | null
+}
+
+export interface MaintenanceRepository {
+  /** VACUUM, ANALYZE, and rebuild full-text indexes. Blocks writers while it runs. */
+  run(): Promise<DatabaseMaintenanceReport>
+  sizeBytes(): Promise<number>
+}
+
+// --- Retention ---
+
+export interface InactiveSession {
+  id: string
+  title: string | null
+  /** Later of the session's updatedAt and its newest item. */
+  lastActivityAt: number
+  itemCount: number
+}
+
+export interface RetentionRepository {
+  /** Sessions (live or trashed, any user) with no activity at or after `before`. */
+  listInactiveSessions(before: number): Promise<InactiveSession[]>
+  /** Stored tool outputs plus function_call_output items created before `before` that still hold content. */
+  countToolOutputs(before: number, placeholder: string): Promise<number>
+  /** Delete stored tool outputs and replace inline item outputs with `placeholder`. */
+  purgeToolOutputs(before: number, placeholder: string): Promise<number>
+  countUsage(before: number): Promise<number>
+  purgeUsage(before: number): Promise<number>
+}
+
+// --- Usage ---
+
+/**
+ * What an LLM call was for: `controller` turns choose the next tool calls,
+ * `responder` turns write the reply (or a question) for the user, and `title`
+ * names the conversation.
+ */
+export type UsagePhase = 'controller' | 'responder' | 'title' | 'memory' | 'verifier' | 'routing' | 'summary'
+
+/** A tool's estimated slice of one call's prompt. */
+export interface ToolContextShare {
+  tokens: number
+  costUsd: number
+}
+
+export interface UsageRecord {
+  id: string
+  userId: string | null
+  sessionId: string | null
+  agentId: string | null
+  provider: string
+  model: string
+  /** Null for calls recorded before phases were tracked. */
+  phase: UsagePhase | null
+  inputTokens: number
+  outputTokens: number
+  cacheReadTokens: number
+  cacheWriteTokens: number
+  costUsd: number
+  /** Saved by caching versus billing cached tokens at the input rate; negative when writes cost more. */
+  cacheSavingsUsd: number
+  /** Prompt tokens and cost attributed to each tool's results in the prompt; null when none were. */
+  toolContext: Record<string, ToolContextShare> | null
+  /** Estimated make-up of the prompt; null for calls recorded without it. */
+  promptComposition: PromptComposition | null
+  createdAt: number
+}
+
+export interface CreateUsageRecordInput {
+  userId?: string | null
+  sessionId?: string | null
+  agentId?: string | null
+  provider: string
+  model: string
+  phase?: UsagePhase | null
+  inputTokens: number
+  outputTokens: number
+  cacheReadTokens?: number
+  cacheWriteTokens?: number
+  costUsd: number
+  cacheSavingsUsd?: number
+  toolContext?: Record<string, ToolContextShare> | null
+  promptComposition?: PromptComposition | null
+}
+
+export interface UsageQuery {
+  userId?: string
+  sessionId?: string
+  /** Inclusive lower bound (epoch ms). */
+  from?: number
+  /** Exclusive upper bound (epoch ms). */
+  to?: number
+}
+
+export interface UsageRepository {
+  record(input: CreateUsageRecordInput): Promise<UsageRecord>
+  /** Matching records, oldest first. */
+  list(query: UsageQuery): Promise<UsageRecord[]>
+}
+
+// --- Approval history ---
+
+/**
+ * How an approval request ended. `auto_*` are decided by a policy rule or a
+ * remembered "don't ask again" without prompting; `expired` means nobody
+ * answered within the approval timeout; `cancelled` means the run was stopped
+ * while the request was still pending.
+ */
+export type ApprovalOutcome = 'approved' | 'denied' | 'auto_approved' | 'auto_denied' | 'expired' | 'cancelled'
+
+export interface ApprovalRecord {
+  id: string
+  userId: string | null
+  sessionId: string
+  agentId: string
+  callId: string
+  toolName: string
+  args: Record<string, unknown>
+  /** What actually ran when the approver edited the args first; `args` keeps the proposal. */
+  amendedArgs: Record<string, unknown> | null
+  outcome: ApprovalOutcome
+  /** How far a manual approval reaches: once, the conversation, or always. */
+  scope: 'once' | 'conversation' | 'always' | null
+  /** The approval rule that decided, if one did. */
+  ruleId: string | null
+  requestedAt: number
+  decidedAt: number
+  /** decidedAt - requestedAt; 0 for automatic decisions. */
+  latencyMs: number
+}
+
+export interface CreateApprovalRecordInput {
+  userId?: string | null
+  sessionId: string
+  agentId: string
+  callId: string
+  toolName: string
+  args: Record<string, unknown>
+  amendedArgs?: Record<string, unknown> | null
+  outcome: ApprovalOutcome
+  scope?: ApprovalRecord['scope']
+  ruleId?: string | null
+  requestedAt: number
+  decidedAt: number
+}
+
+export interface ApprovalHistoryQuery {
+  userId?: string
+  sessionId?: string
+  toolName?: string
+  outcome?: ApprovalOutcome
+  /** Inclusive lower bound on decidedAt (epoch ms). */
+  from?: number
+  /** Exclusive upper bound on decidedAt (epoch ms). */
+  to?: number
+  limit?: number
+}
+
+export interface ApprovalHistoryRepository {
+  record(input: CreateApprovalRecordInput): Promise<ApprovalRecord>
+  /** Matching records, newest first. */
+  list(query: ApprovalHistoryQuery): Promise<ApprovalRecord[]>
+}
+
+// --- Model pricing ---
+
+/** Where a price came from; refreshes from the remote catalog never overwrite `user` rows. */
+export type ModelPriceSource = 'bundled' | 'remote' | 'user'
+
+export interface ModelPriceRecord {
+  /** Normalized model id (see `normalizeModelId`). */
+  modelId: string
+  /** USD per million input tokens. */
+  input: number
+  /** USD per million output tokens. */
+  output: number
+  /** USD per million cached prompt tokens; null bills them as input. */
+  cacheRead: number | null
+  /** USD per million tokens written to the cache; null bills them as input. */
+  cacheWrite: number | null
+  source: ModelPriceSource
+  updatedAt: number
+}
+
+export interface UpsertModelPriceInput {
+  modelId: string
+  input: number
+  output: number
+  cacheRead?: number | null
+  cacheWrite?: number | null
+  source: ModelPriceSource
+}
+
+export interface ModelPriceRepository {
+  /** All prices, ordered by model id. */
+  list(): Promise<ModelPriceRecord[]>
+  /** Inserts rows that are missing and leaves existing ones alone; returns how many were added. */
+  seed(prices: UpsertModelPriceInput[]): Promise<number>
+  upsert(input: UpsertModelPriceInput): Promise<ModelPriceRecord>
+  delete(modelId: string): Promise<boolean>
+}
+
+// --- Model catalog ---
+
+/** A model a provider's listing reported, with what it can do; null where the listing does not say. */
+export interface ModelCatalogEntry {
+  /** The provider's id, as written after `provider:` in a model string. */
+  modelId: string
+  displayName: string | null
+  contextWindow: number | null
+  maxOutputTokens: number | null
+  vision: boolean | null
+  structuredOutputs: boolean | null
+  tools: boolean | null
+}
+
+export interface ModelCatalogRecord extends ModelCatalogEntry {
+  provider: string
+  fetchedAt: number
+}
+
+export interface ModelCatalogRepository {
+  /** All cached models, ordered by provider and model id. */
+  list(): Promise<ModelCatalogRecord[]>
+  /** Swaps a provider's cached models for a fresh listing. */
+  replace(provider: string, models: ModelCatalogEntry[]): Promise<void>
+}
+
+// --- Uploads ---
+
+/** Which extraction an upload carries: a PDF, text or Office `document`, an OCR'd `image`, or a sampled `video`. */
+export type UploadKind = 'pdf' | 'text' | 'office' | 'image' | 'video'
+
+/** A file in a user's attachment library; payloads are shared by hash across users. */
+export interface UploadRecord {
+  userId: string
+  hash: string
+  name: string
+  mediaType: string
+  kind: UploadKind
+  bytes: number
+  createdAt: number
+  lastUsedAt: number
+}
+
+export interface RecordUploadInput {
+  userId: string
+  hash: string
+  name: string
+  mediaType: string
+  kind: UploadKind
+  bytes: number
+}
+
+export interface UploadRepository {
+  /** Adds a file to the user's library; uploading it again only marks it as used. */
+  record(input: RecordUploadInput): Promise<UploadRecord>
+  get(userId: string, hash: string): Promise<UploadRecord | null>
+  /** Most recently used first. */
+  listByUser(userId: string): Promise<UploadRecord[]>
+  touch(userId: string, hash: string): Promise<void>
+  delete(userId: string, hash: string): Promise<boolean>
+  /** Messages in the user's sessions that reference the payload. */
+  countMessageReferences(userId: string, hash: string): Promise<number>
+  /** Library entries, messages and revisions of any user that still need the payload. */
+  countAllReferences(hash: string): Promise<number>
+}
+
+// --- Memories ---
+
+/** A `fact` about the user and their world, or a `preference` for how the assistant should act. */
+export type MemoryKind = 'fact' | 'preference'
+/** What a memory is about, so users can keep whole topics out of memory. */
+export type MemoryCategory = 'work' | 'personal' | 'health' | 'financial' | 'relationships' | 'location' | 'other'
+
+/** Something durable about a user, remembered across conversations. */
+export interface MemoryRecord {
+  id: string
+  userId: string
+  content: string
+  kind: MemoryKind
+  category: MemoryCategory
+  /** 0–1, as judged by the extraction model; restating a memory keeps the higher value. */
+  confidence: number
+  embedding: number[] | null
+  /** Model that produced `embedding`; vectors from different models are not compared. */
+  embeddingModel: string | null
+  /** Conversation the memory was last learned from. */
+  sourceSessionId: string | null
+  /** User message in that conversation the memory was learned from, when known. */
+  sourceItemId: string | null
+  /** Disabled memories are kept, so they are not learned again, but never recalled. */
+  disabled: boolean
+  createdAt: number
+  updatedAt: number
+}
+
+export interface CreateMemoryInput {
+  userId: string
+  content: string
+  kind: MemoryKind
+  /** Defaults to 'other'. */
+  category?: MemoryCategory
+  confidence: number
+  embedding?: number[] | null
+  embeddingModel?: string | null
+  sourceSessionId?: string | null
+  sourceItemId?: string | null
+}
+
+export interface UpdateMemoryInput {
+  content?: string
+  kind?: MemoryKind
+  category?: MemoryCategory
+  confidence?: number
+  embedding?: number[] | null
+  embeddingModel?: string | null
+  sourceSessionId?: string | null
+  sourceItemId?: string | null
+  disabled?: boolean
+}
+
+export interface MemoryRepository {
+  create(input: CreateMemoryInput): Promise<MemoryRecord>
+  getById(id: string): Promise<MemoryRecord | null>
+  /** Most recently updated first. */
+  listByUser(userId: string): Promise<MemoryRecord[]>
+  update(id: string, input: UpdateMemoryInput): Promise<MemoryRecord | null>
+  delete(id: string): Promise<boolean>
+}
+
+// --- Knowledge base ---
+
+export type KnowledgeSourceKind = 'folder' | 'url' | 'document'
+export type KnowledgeSourceStatus = 'pending' | 'indexing' | 'ready' | 'failed'
+
+export interface KnowledgeDocument {
+  /** Path within a folder source, a document's file name, or the fetched URL. */
+  path: string
+  title: string
+  /** Attachment store hash of the document; its chunks live in the extraction. */
+  hash: string
+  chunks: number
+}
+
+export interface KnowledgeSource {
+  id: string
+  userId: string
+  kind: KnowledgeSourceKind
+  /** Absolute folder or file path, or URL. */
+  location: string
+  name: string
+  status: KnowledgeSourceStatus
+  /** Why the last indexing attempt failed. */
+  error: string | null
+  documents: KnowledgeDocument[]
+  indexedAt: number | null
+  createdAt: number
+  updatedAt: number
+}
+
+export interface CreateKnowledgeSourceInput {
+  userId: string
+  kind: KnowledgeSourceKind
+  location: string
+  name: string
+}
+
+export interface UpdateKnowledgeSourceInput {
+  name?: string
+  status?: KnowledgeSourceStatus
+  error?: string | null
+  documents?: KnowledgeDocument[]
+  indexedAt?: number | null
+}
+
+export interface KnowledgeSourceRepository {
+  /** New sources start out pending, with no documents. */
+  create(input: CreateKnowledgeSourceInput): Promise<KnowledgeSource>
+  getById(id: string): Promise<KnowledgeSource | null>
+  /** Oldest first. */
+  listByUser(userId: string): Promise<KnowledgeSource[]>
+  /** Any user's sources in one of the given states, oldest first. */
+  listByStatus(statuses: KnowledgeSourceStatus[]): Promise<KnowledgeSource[]>
+  update(id: string, input: UpdateKnowledgeSourceInput): Promise<KnowledgeSource | null>
+  delete(id: string): Promise<boolean>
+  /** Attachment hashes of every indexed document, kept by orphan cleanup. */
+  listDocumentHashes(): Promise<string[]>
+}
+
+// --- Entities ---
+
+export type EntityKind = 'person' | 'organization' | 'meeting'
+
+/** Someone or something the user deals with, learned from email and calendar tool results. */
+export interface EntityRecord {
+  id: string
+  userId: string
+  kind: EntityKind
+  /** Identity within the kind: a lowercased email address, a domain, or a recurring event ID. */
+  key: string
+  name: string
+  email: string | null
+  /** Short descriptive fields, e.g. organization, recurrence, attendees. */
+  details: Record<string, string>
+  /** Tool results the entity appeared in. */
+  mentions: number
+  embedding: number[] | null
+  embeddingModel: string | null
+  lastSeenAt: number
+  createdAt: number
+  updatedAt: number
+}
+
+export interface CreateEntityInput {
+  userId: string
+  kind: EntityKind
+  key: string
+  name: string
+  email?: string | null
+  details?: Record<string, string>
+}
+
+export interface UpdateEntityInput {
+  name?: string
+  email?: string | null
+  details?: Record<string, string>
+  mentions?: number
+  embedding?: number[] | null
+  embeddingModel?: string | null
+  lastSeenAt?: number
+}
+
+export interface EntityRepository {
+  /** New entities start with one mention. */
+  create(input: CreateEntityInput): Promise<EntityRecord>
+  getById(id: string): Promise<EntityRecord | null>
+  findByKey(userId: string, kind: EntityKind, key: string): Promise<EntityRecord | null>
+  /** Most recently seen first. */
+  listByUser(userId: string): Promise<EntityRecord[]>
+  update(id: string, input: UpdateEntityInput): Promise<EntityRecord | null>
+  delete(id: string): Promise<boolean>
+}
+
+// --- Scratchpad ---
+
+export interface ScratchpadEntry {
+  sessionId: string
+  key: string
+  value: string
+  updatedAt: number
+}
+
+/** Working notes the agent keeps for one conversation; removed with the session. */
+export interface ScratchpadRepository {
+  get(sessionId: string, key: string): Promise<ScratchpadEntry | null>
+  /** Ordered by key. */
+  list(sessionId: string): Promise<ScratchpadEntry[]>
+  /** Creates or replaces the entry. */
+  set(sessionId: string, key: string, value: string): Promise<ScratchpadEntry>
+  delete(sessionId: string, key: string): Promise<boolean>
+}
+
+// --- Feeds ---
+
+/** An RSS or Atom feed the user subscribed to; FeedPoller refreshes it in the background. */
+export interface Feed {
+  id: string
+  userId: string
+  url: string
+  title: string
+  /** Website the feed belongs to, from its channel link. */
+  siteUrl: string | null
+  /** Validators from the last response, sent back for conditional requests. */
+  etag: string | null
+  lastModified: string | null
+  /** Why the last poll failed; cleared by the next successful one. */
+  error: string | null
+  fetchedAt: number | null
+  createdAt: number
+  updatedAt: number
+}
+
+export interface CreateFeedInput {
+  userId: string
+  url: string
+  title: string
+  siteUrl?: string | null
+}
+
+export interface UpdateFeedInput {
+  title?: string
+  siteUrl?: string | null
+  etag?: string | null
+  lastModified?: string | null
+  error?: string | null
+  fetchedAt?: number | null
+}
+
+export interface FeedItem {
+  id: string
+  feedId: string
+  /** The entry's guid or id, falling back to its link. */
+  guid: string
+  title: string
+  link: string | null
+  author: string | null
+  /** Plain text, a few hundred characters at most. */
+  summary: string
+  /** Full plain text of the entry as the feed carries it. */
+  content: string
+  /** When the entry was published; when it was first seen if the feed has no date. */
+  publishedAt: number
+  createdAt: number
+}
+
+export type NewFeedItem = Omit<FeedItem, 'id' | 'feedId' | 'createdAt'>
+
+export interface FeedRepository {
+  create(input: CreateFeedInput): Promise<Feed>
+  getById(id: string): Promise<Feed | null>
+  findByUrl(userId: string, url: string): Promise<Feed | null>
+  /** Oldest first. */
+  listByUser(userId: string): Promise<Feed[]>
+  /** Every user's feeds, oldest first. */
+  listAll(): Promise<Feed[]>
+  update(id: string, input: UpdateFeedInput): Promise<Feed | null>
+  /** Removes the feed with its items. */
+  delete(id: string): Promise<boolean>
+  /** Stores entries whose guid the feed has not had before; returns how many were new. */
+  addItems(feedId: string, items: NewFeedItem[]): Promise<number>
+  getItem(id: string): Promise<FeedItem | null>
+  /** Items of the given feeds, newest first. */
+  listItems(feedIds: string[], options: { since?: number; limit: number }): Promise<FeedItem[]>
+  /** Keeps only the newest `keep` items of a feed; returns how many were deleted. */
+  pruneItems(feedId: string, keep: number): Promise<number>
+}
+
+// --- Controller prompts ---
+
+export interface ControllerPromptVersion {
+  name: string
+  version: number
+  content: string
+  createdAt: number
+}
+
+/** Every saved text of each controller prompt; the highest version is the one in use. */
+export interface ControllerPromptRepository {
+  /** The highest version of each prompt that has one. */
+  listActive(): Promise<ControllerPromptVersion[]>
+  /** Newest first. */
+  listVersions(name: string): Promise<ControllerPromptVersion[]>
+  /** Saves `content` as the prompt's next version. */
+  addVersion(name: string, content: string): Promise<ControllerPromptVersion>
+}
+
+// --- Experiments ---
+
+export interface ExperimentSession {
+  sessionId: string
+  experimentId: string
+  variant: string
+  createdAt: number
+}
+
+/** Which variant of a prompt experiment each enrolled session runs with. */
+export interface ExperimentRepository {
+  assign(input: Omit<ExperimentSession, 'createdAt'>): Promise<ExperimentSession>
+  getBySession(sessionId: string): Promise<ExperimentSession | null>
+  /** Oldest first. */
+  listByExperiment(experimentId: string): Promise<ExperimentSession[]>
+}
@@ -0,0 +1,28 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { ActionError } from '../services/actions.js'
+
+type ActionRouteEnv = { Variables: { userId: string } }
+
+export function actionRoutes(runtime: RuntimeContext): Hono<ActionRouteEnv> {
+  const app = new Hono<ActionRouteEnv>()
+
+  // GET / — Actions for the command palette and hotkeys, with their argument schemas
+  app.get('/', (c) => c.json({ actions: runtime.actions.list() }))
+
+  // POST /:id — Run an action: { args }
+  app.post('/:id', async (c) => {
+    const { id } = c.req.param()
+    if (!runtime.actions.has(id)) return c.json({ error: `Unknown action: ${id}` }, 404)
+    try {
+      const body = await c.req.json<{ args?: unknown }>().catch(() => ({} as { args?: unknown }))
+      const result = await runtime.actions.execute(id, body.args, { userId: c.get('userId'), signal: c.req.raw.signal })
+      return c.json({ result })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof ActionError ? 400 : 500)
+    }
+  })
+
+  return app
+}
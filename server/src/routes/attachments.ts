@@ -0,0 +1,214 @@
+import { Hono } from 'hono'
+import { bodyLimit } from 'hono/body-limit'
+import { AttachmentValidationError, validateAttachment } from '../lib/attachment-validation.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { attachFromLibrary, listLibrary, recordUpload, removeFromLibrary } from '../services/attachment-library.js'
+import { extractImageText, ImageInputError, MAX_IMAGE_BYTES } from '../services/image-ocr.js'
+import { extractOfficeDocument, MAX_OFFICE_BYTES, OfficeInputError } from '../services/office-extraction.js'
+import { extractPdf, MAX_PDF_BYTES, PdfInputError } from '../services/pdf-extraction.js'
+import { extractTextFile, MAX_TEXT_BYTES, TextInputError } from '../services/text-extraction.js'
+import { extractVideo, MAX_VIDEO_BYTES, VideoInputError } from '../services/video-extraction.js'
+
+type AttachmentsEnv = { Variables: { userId: string } }
+
+export function attachmentRoutes(runtime: RuntimeContext): Hono<AttachmentsEnv> {
+  const app = new Hono<AttachmentsEnv>()
+
+  // POST /pdf — Store a PDF and extract its text for use as message context
+  app.post(
+    '/pdf',
+    bodyLimit({
+      maxSize: MAX_PDF_BYTES + 1024 * 1024,
+      onError: (c) => c.json({ error: 'PDF upload is too large' }, 413),
+    }),
+    async (c) => {
+      try {
+        const body = await c.req.parseBody()
+        const file = body.file
+        if (!(file instanceof File)) {
+          return c.json({ error: 'A multipart PDF file is required' }, 400)
+        }
+
+        const upload = validateAttachment(await file.arrayBuffer(), { kind: 'pdf', declaredType: file.type, fileName: file.name })
+        const result = await extractPdf(runtime, {
+          bytes: upload.data,
+          fileName: file.name,
+          signal: c.req.raw.signal,
+        })
+        await recordUpload(runtime, {
+          userId: c.get('userId'), hash: result.hash, name: file.name, mediaType: 'application/pdf', kind: 'pdf', bytes: result.bytes,
+        })
+        return c.json(result)
+      } catch (err) {
+        if (err instanceof AttachmentValidationError) return c.json(err.toJSON(), err.status)
+        const message = err instanceof Error ? err.message : String(err)
+        return c.json({ error: message }, err instanceof PdfInputError ? 400 : 500)
+      }
+    },
+  )
+
+  // POST /text — Store a text file and chunk it like an extracted PDF
+  app.post(
+    '/text',
+    bodyLimit({
+      maxSize: MAX_TEXT_BYTES + 1024 * 1024,
+      onError: (c) => c.json({ error: 'Text upload is too large' }, 413),
+    }),
+    async (c) => {
+      try {
+        const body = await c.req.parseBody()
+        const file = body.file
+        if (!(file instanceof File)) {
+          return c.json({ error: 'A multipart text file is required' }, 400)
+        }
+
+        const upload = validateAttachment(await file.arrayBuffer(), { kind: 'text', declaredType: file.type, fileName: file.name })
+        const result = await extractTextFile(runtime, { bytes: upload.data, fileName: file.name })
+        await recordUpload(runtime, {
+          userId: c.get('userId'), hash: result.hash, name: file.name, mediaType: 'text/plain', kind: 'text', bytes: result.bytes,
+        })
+        return c.json(result)
+      } catch (err) {
+        if (err instanceof AttachmentValidationError) return c.json(err.toJSON(), err.status)
+        const message = err instanceof Error ? err.message : String(err)
+        return c.json({ error: message }, err instanceof TextInputError ? 400 : 500)
+      }
+    },
+  )
+
+  // POST /office — Store a Word, Excel or PowerPoint file and convert it to Markdown text and tables
+  app.post(
+    '/office',
+    bodyLimit({
+      maxSize: MAX_OFFICE_BYTES + 1024 * 1024,
+      onError: (c) => c.json({ error: 'Office document upload is too large' }, 413),
+    }),
+    async (c) => {
+      try {
+        const body = await c.req.parseBody()
+        const file = body.file
+        if (!(file instanceof File)) {
+          return c.json({ error: 'A multipart Office document is required' }, 400)
+        }
+
+        const upload = validateAttachment(await file.arrayBuffer(), { kind: 'office', declaredType: file.type, fileName: file.name })
+        // The content only shows it is a ZIP; which Office format comes from the declared type or name
+        const result = await extractOfficeDocument(runtime, { bytes: upload.data, mimeType: file.type, fileName: file.name })
+        await recordUpload(runtime, {
+          userId: c.get('userId'), hash: result.hash, name: file.name, mediaType: result.mediaType!, kind: 'office', bytes: result.bytes,
+        })
+        return c.json(result)
+      } catch (err) {
+        if (err instanceof AttachmentValidationError) return c.json(err.toJSON(), err.status)
+        const message = err instanceof Error ? err.message : String(err)
+        return c.json({ error: message }, err instanceof OfficeInputError ? 400 : 500)
+      }
+    },
+  )
+
+  // POST /image — Store an image and read its text with IMAGE_OCR_MODEL
+  app.post(
+    '/image',
+    bodyLimit({
+      maxSize: MAX_IMAGE_BYTES + 1024 * 1024,
+      onError: (c) => c.json({ error: 'Image upload is too large' }, 413),
+    }),
+    async (c) => {
+      try {
+        const body = await c.req.parseBody()
+        const file = body.file
+        if (!(file instanceof File)) {
+          return c.json({ error: 'A multipart image file is required' }, 400)
+        }
+
+        const upload = validateAttachment(await file.arrayBuffer(), {
+          kind: 'image', declaredType: file.type, fileName: file.name, maxImageDimension: runtime.config.attachmentImageMaxDimension,
+        })
+        const result = await extractImageText(runtime, {
+          bytes: upload.data,
+          mediaType: upload.mediaType!,
+          fileName: file.name,
+          signal: c.req.raw.signal,
+        })
+        await recordUpload(runtime, {
+          userId: c.get('userId'), hash: result.hash, name: file.name, mediaType: result.mediaType, kind: 'image', bytes: result.bytes,
+        })
+        return c.json(result)
+      } catch (err) {
+        if (err instanceof AttachmentValidationError) return c.json(err.toJSON(), err.status)
+        const message = err instanceof Error ? err.message : String(err)
+        return c.json({ error: message }, err instanceof ImageInputError ? 400 : 500)
+      }
+    },
+  )
+
+  // POST /video — Store a video, sample frames for vision models and transcribe its audio track
+  app.post(
+    '/video',
+    bodyLimit({
+      maxSize: MAX_VIDEO_BYTES + 1024 * 1024,
+      onError: (c) => c.json({ error: 'Video upload is too large' }, 413),
+    }),
+    async (c) => {
+      try {
+        const body = await c.req.parseBody()
+        const file = body.file
+        if (!(file instanceof File)) {
+          return c.json({ error: 'A multipart video file is required' }, 400)
+        }
+
+        const upload = validateAttachment(await file.arrayBuffer(), { kind: 'video', declaredType: file.type, fileName: file.name })
+        const result = await extractVideo(runtime, {
+          bytes: upload.data,
+          mimeType: upload.mediaType!,
+          fileName: file.name,
+          signal: c.req.raw.signal,
+        })
+        await recordUpload(runtime, {
+          userId: c.get('userId'), hash: result.hash, name: file.name, mediaType: result.mediaType, kind: 'video', bytes: result.bytes,
+        })
+        return c.json(result)
+      } catch (err) {
+        if (err instanceof AttachmentValidationError) return c.json(err.toJSON(), err.status)
+        const message = err instanceof Error ? err.message : String(err)
+        return c.json({ error: message }, err instanceof VideoInputError ? 400 : 500)
+      }
+    },
+  )
+
+  // GET /library — Files the user has uploaded, one per distinct payload
+  app.get('/library', async (c) => {
+    try {
+      return c.json({ attachments: await listLibrary(runtime, c.get('userId')) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST /library/:hash/attach — Reuse an uploaded file on a new message without re-uploading
+  app.post('/library/:hash/attach', async (c) => {
+    try {
+      const attachment = await attachFromLibrary(runtime, c.get('userId'), c.req.param('hash'))
+      if (!attachment) return c.json({ error: 'Attachment not found' }, 404)
+      return c.json(attachment)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // DELETE /library/:hash — Remove a file from the library; the payload goes once nothing references it
+  app.delete('/library/:hash', async (c) => {
+    try {
+      const result = await removeFromLibrary(runtime, c.get('userId'), c.req.param('hash'))
+      if (!result.removed) return c.json({ error: 'Attachment not found' }, 404)
+      return c.json({ ok: true, purged: result.purged })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
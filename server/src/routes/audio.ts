@@ -1,5 +1,6 @@
 import { Hono } from 'hono'
 import { bodyLimit } from 'hono/body-limit'
+import { AttachmentValidationError, validateAttachment } from '../lib/attachment-validation.js'
 import type { RuntimeContext } from '../lib/runtime.js'
 import {
   AudioInputError,
@@ -26,19 +27,65 @@ export function audioRoutes(runtime: RuntimeContext): Hono<AudioEnv> {
           return c.json({ error: 'A multipart audio file is required' }, 400)
         }
 
+        const upload = validateAttachment(await file.arrayBuffer(), { kind: 'audio', declaredType: file.type, fileName: file.name })
         const result = await transcribeAudio(runtime, {
-          bytes: await file.arrayBuffer(),
-          mimeType: file.type,
+          bytes: upload.data,
+          mimeType: audioType(upload.mediaType, file.type),
           fileName: file.name,
           signal: c.req.raw.signal,
         })
         return c.json(result)
       } catch (err) {
+        if (err instanceof AttachmentValidationError) return c.json(err.toJSON(), err.status)
         const message = err instanceof Error ? err.message : String(err)
         return c.json({ error: message }, err instanceof AudioInputError ? 400 : 500)
       }
     },
   )
 
+  // POST /transcriptions/jobs — Start transcribing in the background; progress arrives as
+  // transcription:* events (scoped to `sessionId` when given) or by polling the job.
+  app.post(
+    '/transcriptions/jobs',
+    bodyLimit({
+      maxSize: MAX_AUDIO_BYTES + 1024 * 1024,
+      onError: (c) => c.json({ error: 'Audio upload is too large' }, 413),
+    }),
+    async (c) => {
+      try {
+        if (!runtime.transcriptions) return c.json({ error: 'Transcription jobs are not available' }, 503)
+        const body = await c.req.parseBody()
+        const file = body.file
+        if (!(file instanceof File)) {
+          return c.json({ error: 'A multipart audio file is required' }, 400)
+        }
+
+        const upload = validateAttachment(await file.arrayBuffer(), { kind: 'audio', declaredType: file.type, fileName: file.name })
+        const job = runtime.transcriptions.start(c.get('userId'), {
+          bytes: upload.data,
+          mimeType: audioType(upload.mediaType, file.type),
+          fileName: file.name,
+        }, typeof body.sessionId === 'string' && body.sessionId ? body.sessionId : undefined)
+        return c.json(job, 202)
+      } catch (err) {
+        if (err instanceof AttachmentValidationError) return c.json(err.toJSON(), err.status)
+        const message = err instanceof Error ? err.message : String(err)
+        return c.json({ error: message }, err instanceof AudioInputError ? 400 : 500)
+      }
+    },
+  )
+
+  // GET /transcriptions/jobs/:id — Status, progress and, once done, the transcript
+  app.get('/transcriptions/jobs/:id', (c) => {
+    const job = runtime.transcriptions?.get(c.get('userId'), c.req.param('id'))
+    if (!job) return c.json({ error: 'Transcription job not found' }, 404)
+    return c.json(job)
+  })
+
   return app
 }
+
+/** The detected type names the audio format, except for video containers, where the declared type is more specific. */
+function audioType(detected: string | null, declared: string): string {
+  return detected?.startsWith('audio/') ? detected : declared
+}
@@ -0,0 +1,82 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { AUDIT_ENTRY_KINDS, type AuditEntry, type AuditEntryKind } from '../observability/audit-log.js'
+
+type AuditEnv = { Variables: { userId: string } }
+
+const DEFAULT_LIMIT = 500
+const MAX_LIMIT = 5000
+
+/** Accepts epoch milliseconds or anything `Date.parse` understands (e.g. 2025-01-31). */
+function parseTimestamp(value: string | undefined): number | undefined | null {
+  if (value === undefined || value === '') return undefined
+  const numeric = Number(value)
+  if (Number.isFinite(numeric)) return numeric
+  const parsed = Date.parse(value)
+  return Number.isNaN(parsed) ? null : parsed
+}
+
+function parseKinds(value: string | undefined): AuditEntryKind[] | undefined | null {
+  if (!value) return undefined
+  const kinds = value.split(',').map((k) => k.trim()).filter(Boolean)
+  return kinds.every((k) => (AUDIT_ENTRY_KINDS as readonly string[]).includes(k)) ? kinds as AuditEntryKind[] : null
+}
+
+export function auditRoutes(runtime: RuntimeContext): Hono<AuditEnv> {
+  const app = new Hono<AuditEnv>()
+
+  // GET /?from=&to=&kind=tool_call,approval&limit= — Most recent entries, newest first
+  app.get('/', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const from = parseTimestamp(c.req.query('from'))
+      const to = parseTimestamp(c.req.query('to'))
+      if (from === null || to === null) {
+        return c.json({ error: 'from/to must be an ISO date or epoch milliseconds' }, 400)
+      }
+      const kinds = parseKinds(c.req.query('kind'))
+      if (kinds === null) {
+        return c.json({ error: `kind must be a comma-separated list of: ${AUDIT_ENTRY_KINDS.join(', ')}` }, 400)
+      }
+      const limit = Math.min(Math.max(Number(c.req.query('limit')) || DEFAULT_LIMIT, 1), MAX_LIMIT)
+
+      const entries: AuditEntry[] = []
+      for await (const entry of runtime.auditLog.query({ userId, from, to, kinds })) {
+        entries.push(entry)
+        if (entries.length > limit) entries.shift()
+      }
+      return c.json({ enabled: await runtime.auditLog.isEnabled(), entries: entries.reverse() })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /export.jsonl?from=&to= — Every entry in range, oldest first, one JSON object per line
+  app.get('/export.jsonl', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const from = parseTimestamp(c.req.query('from'))
+      const to = parseTimestamp(c.req.query('to'))
+      if (from === null || to === null) {
+        return c.json({ error: 'from/to must be an ISO date or epoch milliseconds' }, 400)
+      }
+
+      let body = ''
+      for await (const entry of runtime.auditLog.query({ userId, from, to })) {
+        body += JSON.stringify(entry) + '\n'
+      }
+
+      const date = new Date().toISOString().slice(0, 10)
+      return c.body(body, 200, {
+        'Content-Type': 'application/x-ndjson; charset=utf-8',
+        'Content-Disposition': `attachment; filename="audit-${date}.jsonl"`,
+      })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
@@ -0,0 +1,86 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { ToolRegistryImpl } from '../tools/registry.js'
+import { CALENDAR_TOOL_NAMES, registerCalendarTools } from '../tools/calendar.js'
+import {
+  type CalDavAccount,
+  type CalendarInfo,
+  CalendarService,
+  calDavAccountView,
+  loadCalDavAccounts,
+  normalizeCalDavAccount,
+  saveCalDavAccounts,
+  verifyCalDavAccount,
+} from '../services/caldav.js'
+
+export function calendarRoutes(runtime: RuntimeContext): Hono {
+  const app = new Hono()
+  const apiKeys = runtime.repositories.apiKeys
+
+  // GET /accounts — Configured CalDAV accounts, never their passwords
+  app.get('/accounts', async (c) => {
+    try {
+      const accounts = await loadCalDavAccounts(apiKeys)
+      return c.json({ accounts: accounts.map(calDavAccountView) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /accounts/:name — Discover the account's calendars, store it encrypted and enable the calendar.* tools
+  app.put('/accounts/:name', async (c) => {
+    const name = c.req.param('name')
+    let accounts: CalDavAccount[]
+    let account: CalDavAccount
+    try {
+      accounts = await loadCalDavAccounts(apiKeys).catch(() => [])
+      const existing = accounts.find((entry) => entry.name === name) ?? null
+      account = normalizeCalDavAccount(name, await c.req.json<Record<string, unknown>>(), existing)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    let calendars: CalendarInfo[]
+    try {
+      calendars = await verifyCalDavAccount(account, fetch, AbortSignal.timeout(30_000))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      await saveCalDavAccounts(apiKeys, [...accounts.filter((entry) => entry.name !== name), account])
+      const registry = runtime.tools as ToolRegistryImpl
+      if (!registry.getMetadata('calendar.list_events')) {
+        registerCalendarTools(registry, new CalendarService(() => loadCalDavAccounts(apiKeys)))
+      }
+      return c.json({ account: calDavAccountView(account), calendars })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // DELETE /accounts/:name — Forget an account; the tools go away with the last one
+  app.delete('/accounts/:name', async (c) => {
+    const name = c.req.param('name')
+    try {
+      const accounts = await loadCalDavAccounts(apiKeys)
+      if (!accounts.some((entry) => entry.name === name)) {
+        return c.json({ error: 'Calendar account not found' }, 404)
+      }
+      const remaining = accounts.filter((entry) => entry.name !== name)
+      await saveCalDavAccounts(apiKeys, remaining)
+      if (remaining.length === 0) {
+        const registry = runtime.tools as ToolRegistryImpl
+        for (const toolName of CALENDAR_TOOL_NAMES) registry.unregister(toolName)
+      }
+      return c.json({ ok: true })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
@@ -1,18 +1,35 @@
 import { Hono } from 'hono'
 import { streamSSE } from 'hono/streaming'
 import { randomUUID } from 'node:crypto'
+import { AttachmentValidationError } from '../lib/attachment-validation.js'
 import { logger } from '../lib/logger.js'
 import type { RuntimeContext } from '../lib/runtime.js'
 import type { AgentResponseFormat, Item } from '../domain/types.js'
 import { runAgent } from '../orchestrator/runner.js'
-import { deliverResult, deliverApproval } from '../orchestrator/delivery.js'
-import { EVENT_TYPES } from '../events/types.js'
+import {
+  deliverResult,
+  deliverApprovals,
+  ApprovalAmendmentError,
+  ApprovalBatchError,
+  type AmendedArgs,
+} from '../orchestrator/delivery.js'
+import { classifyError } from '../orchestrator/errors.js'
+import { CONVERSATION_MODES, isConversationMode, type ConversationMode } from '../orchestrator/chat-mode.js'
+import type { RunResult } from '../orchestrator/types.js'
+import { EVENT_TYPES, toWireEvent, type AgentEvent, type ChatStreamDone } from '../events/types.js'
 import { cancelAgent } from '../domain/index.js'
 import {
   buildDeps,
   formatAssistantOutput,
   prepareSessionTurn,
 } from '../services/session-runner.js'
+import { BudgetExceededError } from '../usage/budget.js'
+import { ImageInputError } from '../services/image-ocr.js'
+import { TurnKeyError } from '../services/turn-keys.js'
+import { ConversationBusyError } from '../services/conversation-throttle.js'
+import { PdfInputError } from '../services/pdf-extraction.js'
+import { ProjectInputError } from '../services/projects.js'
+import { VideoInputError } from '../services/video-extraction.js'
 
 // ---------------------------------------------------------------------------
 // Routes
@@ -37,11 +54,23 @@ export function chatRoutes(runtime: RuntimeContext) {
         response_format?: AgentResponseFormat
         tools?: string[]
         mcpServerIds?: string[]
+        projects?: string[]
         stream?: boolean
         temperature?: number
         maxTokens?: number
+        overrideBudget?: boolean
+        /** Same as the Idempotency-Key header; a retry with the same key joins the first attempt's run. */
+        idempotencyKey?: string
+        /** Offline, save the message and start the run when the connection returns instead of failing. */
+        queueIfOffline?: boolean
+        /** 'chat' answers this message directly without planning or tools; unset follows the conversation's mode. */
+        mode?: ConversationMode
       }>()
 
+      if (body.mode !== undefined && !isConversationMode(body.mode)) {
+        return c.json({ error: `mode must be one of: ${CONVERSATION_MODES.join(', ')}` }, 400)
+      }
+
       const userId = c.get('userId') as string
       const prepared = await prepareSessionTurn(runtime, {
         userId,
@@ -53,8 +82,11 @@ export function chatRoutes(runtime: RuntimeContext) {
         systemPrompt: body.systemPrompt,
         responseFormat: body.responseFormat ?? body.response_format ?? 'markdown',
         mcpServerIds: body.mcpServerIds,
+        projects: body.projects,
         allowedTools: body.tools,
         maxTokens: body.maxTokens,
+        overrideBudget: body.overrideBudget,
+        idempotencyKey: c.req.header('Idempotency-Key') ?? body.idempotencyKey,
       })
 
       if (prepared.status === 'active') {
@@ -64,10 +96,15 @@ export function chatRoutes(runtime: RuntimeContext) {
           status: prepared.agent.status,
           output: prepared.output,
           waitingFor: prepared.agent.waitingFor.length > 0 ? prepared.agent.waitingFor : undefined,
+          ...(prepared.duplicate ? { duplicate: true } : {}),
         }, 202)
       }
 
       const { agent, sessionId, model } = prepared
+      if (body.queueIfOffline && runtime.offlineQueue && await runtime.offlineQueue.shouldQueue(model)) {
+        await runtime.offlineQueue.enqueue(agent.id)
+        return c.json({ id: agent.id, sessionId, status: 'queued', errorCode: 'offline' }, 202)
+      }
       const deps = buildDeps(runtime, model)
 
       // 5. Streaming vs non-streaming
@@ -87,10 +124,11 @@ export function chatRoutes(runtime: RuntimeContext) {
             agentAbort.signal,
           ])
 
-          const resultPromise = runAgent(agent!.id, deps, { stream: true, signal }).catch((err) => ({
+          const resultPromise = runAgent(agent!.id, deps, { stream: true, signal, mode: body.mode }).catch((err): RunResult => ({
             agentId: agent!.id,
-            status: 'failed' as const,
+            status: 'failed',
             error: err instanceof Error ? err.message : String(err),
+            errorCode: classifyError(err),
             turnCount: 0,
           }))
 
@@ -107,6 +145,7 @@ export function chatRoutes(runtime: RuntimeContext) {
               case EVENT_TYPES.COMPANION_TEXT: return 'text_delta'
               case EVENT_TYPES.TOOL_STARTED: return 'tool_start'
               case EVENT_TYPES.TOOL_COMPLETED: return 'tool_end'
+              case EVENT_TYPES.TOOL_PROGRESS: return 'tool_progress'
               case EVENT_TYPES.TOOL_PROPOSED: return 'approval'
               case EVENT_TYPES.TOOL_APPROVED: return 'tool_approved'
               case EVENT_TYPES.TOOL_DENIED: return 'tool_denied'
@@ -118,17 +157,12 @@ export function chatRoutes(runtime: RuntimeContext) {
             }
           }
 
-          const writeEvent = async (event: { type: string; agent_id: string; payload: unknown }) => {
+          const writeEvent = async (event: AgentEvent) => {
             const sseEvent = mapEventToSSE(event)
             if (!sseEvent) return
             await stream.writeSSE({
               event: sseEvent,
-              data: JSON.stringify({
-                type: sseEvent,
-                agentId: event.agent_id,
-                sessionId,
-                ...event.payload as Record<string, unknown>,
-              }),
+              data: JSON.stringify({ ...toWireEvent(event, sessionId), type: sseEvent }),
               id: randomUUID(),
             })
           }
@@ -180,11 +214,13 @@ export function chatRoutes(runtime: RuntimeContext) {
               id: agent!.id,
               sessionId,
               status: result.status,
-              result: 'result' in result ? result.result : undefined,
-              error: 'error' in result ? result.error : undefined,
-              waitingFor: 'waitingFor' in result ? result.waitingFor : undefined,
+              result: result.result,
+              error: result.error,
+              errorCode: result.errorCode,
+              waitingFor: result.waitingFor,
               turnCount: result.turnCount,
-            }),
+              verification: result.verification,
+            } satisfies ChatStreamDone),
             id: randomUUID(),
           })
 
@@ -197,6 +233,7 @@ export function chatRoutes(runtime: RuntimeContext) {
       runtime.agentAbortControllers.set(agent.id, agentAbort)
       const result = await runAgent(agent.id, deps, {
         signal: AbortSignal.any([c.req.raw.signal, runtime.shutdownController.signal, agentAbort.signal]),
+        mode: body.mode,
       })
       runtime.agentAbortControllers.delete(agent.id)
 
@@ -221,13 +258,26 @@ export function chatRoutes(runtime: RuntimeContext) {
         output,
         usage: { turnCount: result.turnCount },
         error: result.error,
+        errorCode: result.errorCode,
+        verification: result.verification,
+        routing: prepared.routing,
       }, 200)
     } catch (err) {
+      if (err instanceof BudgetExceededError) {
+        return c.json({ error: err.message, errorCode: 'budget_exceeded', budget: err.statuses }, 402)
+      }
+      if (err instanceof ConversationBusyError) {
+        if (err.busy.retryAfterMs !== undefined) c.header('Retry-After', String(Math.ceil(err.busy.retryAfterMs / 1000)))
+        return c.json({ error: err.message, errorCode: 'conversation_busy', busy: err.busy }, 429)
+      }
+      if (err instanceof AttachmentValidationError) return c.json(err.toJSON(), err.status)
       logger.error(err, 'POST /completions failed')
       const message = err instanceof Error ? err.message : String(err)
-      const status = message.startsWith('Unknown agent:') || message.startsWith('Session not found:')
-        ? 400
-        : 500
+      const status = message.startsWith('Session not found:')
+        ? 404
+        : err instanceof PdfInputError || err instanceof ImageInputError || err instanceof VideoInputError || err instanceof ProjectInputError || err instanceof TurnKeyError || message.startsWith('Unknown agent:')
+          ? 400
+          : 500
       return c.json({ error: message }, status)
     }
   })
@@ -282,6 +332,8 @@ export function chatRoutes(runtime: RuntimeContext) {
         output,
         usage: { turnCount: result.turnCount },
         error: result.error,
+        errorCode: result.errorCode,
+        verification: result.verification,
       }, 200)
     } catch (err) {
       const message = err instanceof Error ? err.message : String(err)
@@ -289,28 +341,43 @@ export function chatRoutes(runtime: RuntimeContext) {
     }
   })
 
-  // POST /agents/:agentId/approve — Approve/deny pending tool
+  // POST /agents/:agentId/approve — Approve/deny one pending tool, or several via callIds
   app.post('/agents/:agentId/approve', async (c) => {
     try {
       const { agentId } = c.req.param()
       const body = await c.req.json<{
-        callId: string
+        callId?: string
+        callIds?: string[]
         decision: 'approved' | 'denied'
         scope?: 'once' | 'conversation' | 'always'
+        /** Replacement args by callId, for calls the approver edited */
+        amendedArgs?: AmendedArgs
+        /** Why the calls were denied; the agent continues with it instead of stopping */
+        feedback?: string
       }>()
 
+      const callIds = body.callIds ?? (body.callId ? [body.callId] : [])
+      if (callIds.length === 0 || !callIds.every((id) => typeof id === 'string')) {
+        return c.json({ error: 'callId or a non-empty callIds array is required' }, 400)
+      }
+      if (body.feedback !== undefined && (typeof body.feedback !== 'string' || body.decision !== 'denied')) {
+        return c.json({ error: 'feedback must be a string and is only accepted with a denial' }, 400)
+      }
+
       const agent = await runtime.repositories.agents.getById(agentId)
       if (!agent) {
         return c.json({ error: `Agent not found: ${agentId}` }, 404)
       }
 
       const deps = buildDeps(runtime, agent.config.model)
-      const result = await deliverApproval(
+      const result = await deliverApprovals(
         agentId,
-        body.callId,
+        callIds,
         body.decision,
         deps,
         body.scope,
+        body.amendedArgs,
+        body.feedback,
       )
 
       // After runAndPropagateUp, the result may be from a parent agent.
@@ -339,10 +406,13 @@ export function chatRoutes(runtime: RuntimeContext) {
         output,
         usage: { turnCount: result.turnCount },
         error: result.error,
+        errorCode: result.errorCode,
+        verification: result.verification,
       }, 200)
     } catch (err) {
       const message = err instanceof Error ? err.message : String(err)
-      return c.json({ error: message }, 500)
+      const invalid = err instanceof ApprovalAmendmentError || err instanceof ApprovalBatchError
+      return c.json({ error: message }, invalid ? 400 : 500)
     }
   })
 
@@ -363,6 +433,7 @@ export function chatRoutes(runtime: RuntimeContext) {
         waitingFor: agent.waitingFor.length > 0 ? agent.waitingFor : undefined,
         result: agent.result,
         error: agent.error,
+        errorCode: agent.errorCode,
         turnCount: agent.turnCount,
       })
     } catch (err) {
@@ -398,6 +469,22 @@ export function chatRoutes(runtime: RuntimeContext) {
         completedAt: updated.completedAt,
       })
 
+      // Approvals still pending are abandoned with the run
+      const abandoned = agent.status === 'waiting' ? agent.waitingFor.filter((w) => w.type === 'approval') : []
+      if (abandoned.length > 0) {
+        const items = await runtime.repositories.items.listByAgent(agentId)
+        for (const wait of abandoned) {
+          await runtime.approvalHistory.record({
+            agent,
+            callId: wait.callId,
+            toolName: wait.name,
+            args: wait.args ?? {},
+            outcome: 'cancelled',
+            requestedAt: items.find((i) => i.type === 'function_call' && i.callId === wait.callId)?.createdAt,
+          })
+        }
+      }
+
       return c.json({ id: agentId, status: 'cancelled' })
     } catch (err) {
       const message = err instanceof Error ? err.message : String(err)
@@ -405,9 +492,34 @@ export function chatRoutes(runtime: RuntimeContext) {
     }
   })
 
-  // GET /agents/:agentId/events — SSE stream for agent events
+  // GET /agents/:agentId/events/replay?since=<ms> — Buffered events for the agent's session
+  app.get('/agents/:agentId/events/replay', async (c) => {
+    try {
+      const { agentId } = c.req.param()
+      const agent = await runtime.repositories.agents.getById(agentId)
+      if (!agent) {
+        return c.json({ error: `Agent not found: ${agentId}` }, 404)
+      }
+      const sinceParam = c.req.query('since')
+      const since = sinceParam ? Number(sinceParam) : 0
+      if (!Number.isFinite(since)) {
+        return c.json({ error: 'since must be a millisecond timestamp' }, 400)
+      }
+
+      const events = runtime.events.replay({ session_id: agent.sessionId }, since)
+      return c.json(events.map((event) => toWireEvent(event, agent.sessionId)))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /agents/:agentId/events?types=a,b&window=id — SSE stream for agent events, optionally
+  // by type. With `window`, events stop while another window has claimed the conversation.
   app.get('/agents/:agentId/events', async (c) => {
     const { agentId } = c.req.param()
+    const types = c.req.query('types')?.split(',').map((type) => type.trim()).filter(Boolean) ?? []
+    const windowId = c.req.query('window')
 
     const agent = await runtime.repositories.agents.getById(agentId)
     if (!agent) {
@@ -422,21 +534,25 @@ export function chatRoutes(runtime: RuntimeContext) {
     const breakAgentId = rootAgent?.id ?? agentId
 
     return streamSSE(c, async (stream) => {
+      // Terminal events are always subscribed so the stream knows when to end
       const eventStream = runtime.events.subscribe({
         session_id: agent.sessionId,
+        types: types.length > 0
+          ? [...types, EVENT_TYPES.AGENT_COMPLETED, EVENT_TYPES.AGENT_FAILED]
+          : undefined,
       })
 
       for await (const event of eventStream) {
-        await stream.writeSSE({
-          event: event.type,
-          data: JSON.stringify({
-            type: event.type,
-            agentId: event.agent_id,
-            sessionId: agent.sessionId,
-            ...event.payload as Record<string, unknown>,
-          }),
-          id: randomUUID(),
-        })
+        if (
+          (types.length === 0 || types.includes(event.type)) &&
+          runtime.windows.routes(event.session_id, windowId, event.type)
+        ) {
+          await stream.writeSSE({
+            event: event.type,
+            data: JSON.stringify(toWireEvent(event, agent.sessionId)),
+            id: randomUUID(),
+          })
+        }
 
         // End the stream when the root agent is done (not just the requested agent)
         if (
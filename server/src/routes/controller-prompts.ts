@@ -0,0 +1,60 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { isControllerPromptName } from '../orchestrator/prompts.js'
+
+export function controllerPromptRoutes(runtime: RuntimeContext): Hono {
+  const app = new Hono()
+  const store = runtime.controllerPrompts
+
+  // GET / — The prompt each run starts with, with its version
+  app.get('/', async (c) => {
+    try {
+      return c.json(await store.list())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /:name/versions — Every saved text, newest first
+  app.get('/:name/versions', async (c) => {
+    try {
+      const { name } = c.req.param()
+      if (!isControllerPromptName(name)) return c.json({ error: `Controller prompt not found: ${name}` }, 404)
+      return c.json(await store.versions(name))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /:name — Save a new version; runs started after this use it
+  app.put('/:name', async (c) => {
+    try {
+      const { name } = c.req.param()
+      if (!isControllerPromptName(name)) return c.json({ error: `Controller prompt not found: ${name}` }, 404)
+      const body = await c.req.json<{ content?: unknown }>()
+      if (typeof body.content !== 'string' || !body.content.trim()) {
+        return c.json({ error: 'content is required' }, 400)
+      }
+      return c.json(await store.update(name, body.content))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST /:name/reset — Go back to the built-in default
+  app.post('/:name/reset', async (c) => {
+    try {
+      const { name } = c.req.param()
+      if (!isControllerPromptName(name)) return c.json({ error: `Controller prompt not found: ${name}` }, 404)
+      return c.json(await store.reset(name))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
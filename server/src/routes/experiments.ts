@@ -0,0 +1,60 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { normalizeExperimentInput } from '../services/experiments.js'
+
+export function experimentRoutes(runtime: RuntimeContext): Hono {
+  const app = new Hono()
+
+  // GET / — The current experiment with per-variant results, or null
+  app.get('/', async (c) => {
+    try {
+      return c.json(await runtime.experiments.results())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST / — Start an experiment, replacing the current one
+  app.post('/', async (c) => {
+    let input: ReturnType<typeof normalizeExperimentInput>
+    try {
+      input = normalizeExperimentInput(await c.req.json<{ name?: unknown; variants?: unknown }>())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      return c.json(await runtime.experiments.start(input), 201)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST /stop — Stop enrolling new sessions; results stay available
+  app.post('/stop', async (c) => {
+    try {
+      const experiment = await runtime.experiments.stop()
+      if (!experiment) return c.json({ error: 'No experiment to stop' }, 404)
+      return c.json(experiment)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /sessions/:sessionId — The variant a session was enrolled in
+  app.get('/sessions/:sessionId', async (c) => {
+    try {
+      const tag = await runtime.repositories.experiments.getBySession(c.req.param('sessionId'))
+      if (!tag) return c.json({ error: 'Session is not in an experiment' }, 404)
+      return c.json(tag)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
@@ -0,0 +1,69 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { FeedInputError, refreshFeed, subscribeFeed } from '../services/feeds.js'
+import type { Feed } from '../repositories/types.js'
+
+type FeedsEnv = { Variables: { userId: string } }
+
+export function feedRoutes(runtime: RuntimeContext): Hono<FeedsEnv> {
+  const app = new Hono<FeedsEnv>()
+  const repo = runtime.repositories.feeds
+
+  const owned = async (userId: string, id: string): Promise<Feed | null> => {
+    const feed = await repo.getById(id)
+    return feed && feed.userId === userId ? feed : null
+  }
+
+  // GET / — The user's feed subscriptions with the last poll's outcome
+  app.get('/', async (c) => {
+    try {
+      return c.json({ feeds: await repo.listByUser(c.get('userId')) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST / — Subscribe to a feed, or to the feed a web page links to
+  app.post('/', async (c) => {
+    try {
+      const body = await c.req.json<{ url?: string; title?: string }>()
+      if (!body.url) {
+        return c.json({ error: 'url is required' }, 400)
+      }
+      const { feed, added, existing } = await subscribeFeed(runtime, c.get('userId'), { url: body.url, title: body.title })
+      return c.json({ feed, items: added }, existing ? 200 : 201)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof FeedInputError ? 400 : 500)
+    }
+  })
+
+  // POST /:id/refresh — Poll one feed now instead of waiting for the next round
+  app.post('/:id/refresh', async (c) => {
+    try {
+      const feed = await owned(c.get('userId'), c.req.param('id'))
+      if (!feed) return c.json({ error: 'Feed not found' }, 404)
+      const added = await refreshFeed(runtime, feed)
+      return c.json({ feed: await repo.getById(feed.id), items: added })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof FeedInputError ? 502 : 500)
+    }
+  })
+
+  // DELETE /:id — Unsubscribe; the feed's stored items are deleted with it
+  app.delete('/:id', async (c) => {
+    try {
+      const feed = await owned(c.get('userId'), c.req.param('id'))
+      if (!feed) return c.json({ error: 'Feed not found' }, 404)
+      await repo.delete(feed.id)
+      return c.json({ ok: true })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
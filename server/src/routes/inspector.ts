@@ -0,0 +1,77 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { getInspectorSession, listInspectorSessions } from '../services/inspector.js'
+
+type InspectorEnv = { Variables: { userId: string } }
+
+export function inspectorRoutes(runtime: RuntimeContext): Hono<InspectorEnv> {
+  const app = new Hono<InspectorEnv>()
+
+  // GET /sessions — Live and trashed sessions with agent/turn/usage counts
+  app.get('/sessions', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      return c.json(await listInspectorSessions(runtime, userId))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /sessions/:id — Agent tree with plans, per-turn steps and results, and phases
+  app.get('/sessions/:id', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const { id } = c.req.param()
+      const inspected = await getInspectorSession(runtime, userId, id)
+      if (!inspected) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      return c.json(inspected)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /sessions/:id/traces — Debug traces captured for the session
+  app.get('/sessions/:id/traces', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session || session.userId !== userId) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      return c.json({
+        enabled: await runtime.debugTraces.isEnabled(),
+        traces: await runtime.debugTraces.list(id),
+      })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /sessions/:id/traces/:file — Raw trace JSON, suitable for attaching to a bug report
+  app.get('/sessions/:id/traces/:file', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const { id, file } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session || session.userId !== userId) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      const body = await runtime.debugTraces.read(id, file)
+      if (body === null) {
+        return c.json({ error: `Trace not found: ${file}` }, 404)
+      }
+      return c.body(body, 200, { 'Content-Type': 'application/json; charset=utf-8' })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
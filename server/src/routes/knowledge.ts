@@ -0,0 +1,80 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { addKnowledgeSource, KnowledgeInputError } from '../services/knowledge-base.js'
+import type { KnowledgeSource } from '../repositories/types.js'
+
+type KnowledgeEnv = { Variables: { userId: string } }
+
+export function knowledgeRoutes(runtime: RuntimeContext): Hono<KnowledgeEnv> {
+  const app = new Hono<KnowledgeEnv>()
+  const repo = runtime.repositories.knowledge
+
+  const owned = async (userId: string, id: string): Promise<KnowledgeSource | null> => {
+    const source = await repo.getById(id)
+    return source && source.userId === userId ? source : null
+  }
+
+  // GET / — The user's knowledge sources with their indexing status
+  app.get('/', async (c) => {
+    try {
+      const sources = await repo.listByUser(c.get('userId'))
+      return c.json({ sources: sources.map(summarize) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST / — Register a folder, document path or URL; it is indexed in the background
+  app.post('/', async (c) => {
+    try {
+      const body = await c.req.json<{ location?: string; name?: string }>()
+      if (!body.location) {
+        return c.json({ error: 'location is required' }, 400)
+      }
+      const source = await addKnowledgeSource(runtime, c.get('userId'), { location: body.location, name: body.name })
+      runtime.knowledgeIndexer?.enqueue(source.id)
+      return c.json({ source: summarize(source) }, 202)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof KnowledgeInputError ? 400 : 500)
+    }
+  })
+
+  // POST /:id/reindex — Read the source again, e.g. after its files or page changed
+  app.post('/:id/reindex', async (c) => {
+    try {
+      const source = await owned(c.get('userId'), c.req.param('id'))
+      if (!source) return c.json({ error: 'Knowledge source not found' }, 404)
+      const pending = await repo.update(source.id, { status: 'pending', error: null })
+      runtime.knowledgeIndexer?.enqueue(source.id)
+      return c.json({ source: summarize(pending ?? source) }, 202)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // DELETE /:id — Remove a source; its stored documents go with the next orphan cleanup
+  app.delete('/:id', async (c) => {
+    try {
+      const source = await owned(c.get('userId'), c.req.param('id'))
+      if (!source) return c.json({ error: 'Knowledge source not found' }, 404)
+      await repo.delete(source.id)
+      return c.json({ ok: true })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
+
+function summarize({ documents, ...source }: KnowledgeSource) {
+  return {
+    ...source,
+    documents: documents.length,
+    chunks: documents.reduce((total, document) => total + document.chunks, 0),
+  }
+}
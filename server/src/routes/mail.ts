@@ -0,0 +1,75 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { ToolRegistryImpl } from '../tools/registry.js'
+import { MAIL_TOOL_NAMES, registerMailTools } from '../tools/mail.js'
+import {
+  MAIL_ACCOUNT_KEY,
+  type MailAccount,
+  MailService,
+  loadMailAccount,
+  mailAccountView,
+  normalizeMailAccount,
+  verifyMailAccount,
+} from '../services/mail.js'
+
+export function mailRoutes(runtime: RuntimeContext): Hono {
+  const app = new Hono()
+  const apiKeys = runtime.repositories.apiKeys
+
+  // GET /account — The configured account, never the password
+  app.get('/account', async (c) => {
+    try {
+      const account = await loadMailAccount(apiKeys)
+      return c.json({ configured: account !== null, account: account ? mailAccountView(account) : null })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /account — Check the IMAP login, store the account encrypted and enable the mail.* tools
+  app.put('/account', async (c) => {
+    let account: MailAccount
+    try {
+      account = normalizeMailAccount(await c.req.json<Record<string, unknown>>(), await loadMailAccount(apiKeys).catch(() => null))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      await verifyMailAccount(account, AbortSignal.timeout(30_000))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      await apiKeys.upsert(MAIL_ACCOUNT_KEY, JSON.stringify(account))
+      const registry = runtime.tools as ToolRegistryImpl
+      if (!registry.getMetadata('mail.list')) {
+        registerMailTools(registry, new MailService(() => loadMailAccount(apiKeys)))
+      }
+      return c.json({ configured: true, account: mailAccountView(account) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // DELETE /account — Forget the account and remove the mail.* tools
+  app.delete('/account', async (c) => {
+    try {
+      if (!(await apiKeys.getByProvider(MAIL_ACCOUNT_KEY))) {
+        return c.json({ error: 'No mail account configured' }, 404)
+      }
+      await apiKeys.delete(MAIL_ACCOUNT_KEY)
+      const registry = runtime.tools as ToolRegistryImpl
+      for (const name of MAIL_TOOL_NAMES) registry.unregister(name)
+      return c.json({ ok: true })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
@@ -0,0 +1,67 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { MaintenanceInProgressError, runDbMaintenance } from '../services/db-maintenance.js'
+import { EmbeddingMaintenanceInProgressError } from '../services/embedding-maintenance.js'
+import { runHealthCheck } from '../services/health-check.js'
+import { cleanupOrphans } from '../services/orphans.js'
+
+type MaintenanceRouteEnv = { Variables: { userId: string } }
+
+export function maintenanceRoutes(runtime: RuntimeContext): Hono<MaintenanceRouteEnv> {
+  const app = new Hono<MaintenanceRouteEnv>()
+
+  // POST /db — VACUUM/ANALYZE, rebuild full-text indexes, report sizes and health
+  app.post('/db', async (c) => {
+    try {
+      return c.json(await runDbMaintenance(runtime))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof MaintenanceInProgressError ? err.status : 500)
+    }
+  })
+
+  // GET /health — Check provider keys, mail and calendar logins, MCP servers, and artifact stores
+  app.get('/health', async (c) => {
+    try {
+      return c.json(await runHealthCheck(runtime, c.get('userId'), c.req.raw.signal))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST /embeddings — Re-index changed knowledge sources and projects, embed what lacks vectors
+  app.post('/embeddings', async (c) => {
+    try {
+      if (!runtime.embeddingMaintenance) {
+        return c.json({ error: 'Embedding maintenance is not running' }, 503)
+      }
+      return c.json(await runtime.embeddingMaintenance.run())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof EmbeddingMaintenanceInProgressError ? 409 : 500)
+    }
+  })
+
+  // GET /orphans — Report data whose owner no longer exists
+  app.get('/orphans', async (c) => {
+    try {
+      return c.json(await cleanupOrphans(runtime))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST /orphans/cleanup — Remove everything the report lists
+  app.post('/orphans/cleanup', async (c) => {
+    try {
+      return c.json(await cleanupOrphans(runtime, { remove: true }))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
@@ -0,0 +1,151 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { MemoryCategory, MemoryKind, MemoryRecord } from '../repositories/types.js'
+import { editMemory, forgetMemory } from '../services/memory.js'
+import {
+  MEMORY_CATEGORIES,
+  MEMORY_SETTINGS_PREFERENCE_KEY,
+  normalizeMemorySettings,
+  readMemorySettings,
+  type MemorySettings,
+} from '../services/memory-settings.js'
+
+type MemoryEnv = { Variables: { userId: string } }
+type MemoryEdit = { content?: string; kind?: MemoryKind; category?: MemoryCategory; disabled?: boolean }
+
+/** Longest excerpt of the source message returned with a memory. */
+const EXCERPT_CHARS = 200
+
+export function memoryRoutes(runtime: RuntimeContext): Hono<MemoryEnv> {
+  const app = new Hono<MemoryEnv>()
+  const store = { config: runtime.config, providers: runtime.providers, memories: runtime.repositories.memories }
+
+  // GET / — Everything remembered about the user, with where each memory was learned
+  app.get('/', async (c) => {
+    try {
+      const memories = await runtime.repositories.memories.listByUser(c.get('userId'))
+      const titles = new Map<string, string | null>()
+      const results = []
+      for (const memory of memories) {
+        results.push(await describe(runtime, memory, titles))
+      }
+      return c.json({ memories: results })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /settings — Memory on/off, excluded categories and low-confidence expiry
+  app.get('/settings', async (c) => {
+    try {
+      return c.json({ settings: await readMemorySettings(runtime.repositories.preferences), categories: MEMORY_CATEGORIES })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /settings — Replace the settings; omitted fields go back to their defaults
+  app.put('/settings', async (c) => {
+    let settings: MemorySettings
+    try {
+      settings = normalizeMemorySettings(await c.req.json<Partial<MemorySettings>>())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      await runtime.repositories.preferences.set(MEMORY_SETTINGS_PREFERENCE_KEY, JSON.stringify(settings))
+      return c.json({ settings, categories: MEMORY_CATEGORIES })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PATCH /:id — Correct a memory's wording, kind or category, or disable it without forgetting it
+  app.patch('/:id', async (c) => {
+    let input: MemoryEdit
+    try {
+      const body = await c.req.json<Record<string, unknown>>()
+      input = parseEdit(body)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      const memory = await editMemory(store, c.get('userId'), c.req.param('id'), input, c.req.raw.signal)
+      if (!memory) return c.json({ error: 'Memory not found' }, 404)
+      return c.json({ memory: await describe(runtime, memory, new Map()) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // DELETE /:id — Forget a memory; it may be learned again if the user restates it
+  app.delete('/:id', async (c) => {
+    try {
+      const removed = await forgetMemory(store, c.get('userId'), c.req.param('id'))
+      if (!removed) return c.json({ error: 'Memory not found' }, 404)
+      return c.json({ ok: true })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
+
+function parseEdit(body: Record<string, unknown>): MemoryEdit {
+  const input: MemoryEdit = {}
+  if (body.content !== undefined) {
+    if (typeof body.content !== 'string' || !body.content.trim()) throw new Error('content must be a non-empty string')
+    input.content = body.content
+  }
+  if (body.kind !== undefined) {
+    if (body.kind !== 'fact' && body.kind !== 'preference') throw new Error('kind must be "fact" or "preference"')
+    input.kind = body.kind
+  }
+  if (body.category !== undefined) {
+    const category = MEMORY_CATEGORIES.find((value) => value === body.category)
+    if (!category) throw new Error(`category must be one of: ${MEMORY_CATEGORIES.join(', ')}`)
+    input.category = category
+  }
+  if (body.disabled !== undefined) {
+    if (typeof body.disabled !== 'boolean') throw new Error('disabled must be a boolean')
+    input.disabled = body.disabled
+  }
+  if (Object.keys(input).length === 0) throw new Error('Nothing to update: pass content, kind, category or disabled')
+  return input
+}
+
+/** A memory without its embedding, plus the conversation and message it came from. */
+async function describe(
+  runtime: RuntimeContext,
+  { embedding: _embedding, embeddingModel: _model, sourceSessionId, sourceItemId, ...memory }: MemoryRecord,
+  titles: Map<string, string | null>,
+) {
+  let sessionTitle: string | null = null
+  if (sourceSessionId) {
+    if (!titles.has(sourceSessionId)) {
+      const session = await runtime.repositories.sessions.getById(sourceSessionId)
+      titles.set(sourceSessionId, session && !session.deletedAt ? session.title ?? null : null)
+    }
+    sessionTitle = titles.get(sourceSessionId) ?? null
+  }
+  const item = sourceItemId ? await runtime.repositories.items.getById(sourceItemId) : null
+  return {
+    ...memory,
+    source: sourceSessionId
+      ? {
+          sessionId: sourceSessionId,
+          sessionTitle,
+          itemId: sourceItemId,
+          excerpt: item?.content ? item.content.trim().slice(0, EXCERPT_CHARS) : null,
+        }
+      : null,
+  }
+}
@@ -0,0 +1,23 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+
+export function metricsRoutes(runtime: RuntimeContext): Hono {
+  const app = new Hono()
+
+  // GET /?format=json|prometheus — In-process metrics snapshot
+  app.get('/', (c) => {
+    try {
+      if (c.req.query('format') === 'prometheus') {
+        return c.body(runtime.metrics.toPrometheus(), 200, {
+          'Content-Type': 'text/plain; version=0.0.4; charset=utf-8',
+        })
+      }
+      return c.json(runtime.metrics.snapshot())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
@@ -15,6 +15,20 @@ export function modelRoutes(runtime: RuntimeContext): Hono {
     }
   })
 
+  // GET /catalog — Models the configured providers list, with capabilities; ?refresh=1 refetches first
+  app.get('/catalog', async (c) => {
+    try {
+      const refresh = c.req.query('refresh')
+      const result = refresh === '1' || refresh === 'true'
+        ? await runtime.modelCatalog.refresh(c.req.query('provider') || undefined)
+        : null
+      return c.json({ models: runtime.modelCatalog.list(c.req.query('provider') || undefined), ...(result && { errors: result.errors }) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
   // POST / — Add model
   app.post('/', async (c) => {
     try {
@@ -15,7 +15,7 @@ import { stream } from 'hono/streaming'
 import { randomUUID } from 'node:crypto'
 import type { RuntimeContext } from '../lib/runtime.js'
 import { runAgent } from '../orchestrator/runner.js'
-import { EVENT_TYPES } from '../events/types.js'
+import { EVENT_TYPES, toWireEvent } from '../events/types.js'
 
 // ---------------------------------------------------------------------------
 // OpenAI-compatible types (subset we care about)
@@ -159,6 +159,15 @@ export function openaiCompatRoutes(runtime: RuntimeContext): Hono<OAIAppEnv> {
         sessionFilesRoot: runtime.sessionFilesRoot,
         inlineOutputLimitBytes: runtime.inlineOutputLimitBytes,
         interceptHandlers: runtime.interceptHandlers,
+        attachments: runtime.attachments,
+        historyWindow: {
+          userTurns: runtime.config.historyWindowTurns,
+          attachmentUserTurns: runtime.config.attachmentHistoryTurns,
+        },
+        instructions: runtime.instructions,
+        toolPool: runtime.toolPool,
+        runScheduler: runtime.runScheduler,
+        agentAbortControllers: runtime.agentAbortControllers,
       }
 
       const completionId = `chatcmpl-${randomUUID()}`
@@ -245,14 +254,7 @@ export function openaiCompatRoutes(runtime: RuntimeContext): Hono<OAIAppEnv> {
             ]
 
             if (forwardableEvents.includes(event.type)) {
-              const eventData = {
-                type: event.type,
-                agentId: event.agent_id,
-                sessionId: event.session_id,
-                timestamp: event.timestamp,
-                ...(payload ?? {}),
-              }
-              await s.write(`data: ${JSON.stringify(eventData)}\n\n`)
+              await s.write(`data: ${JSON.stringify(toWireEvent(event))}\n\n`)
             }
 
             if (
@@ -0,0 +1,58 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { PluginError } from '../services/plugin-manifest.js'
+
+type PluginRouteEnv = { Variables: { userId: string } }
+
+export function pluginRoutes(runtime: RuntimeContext): Hono<PluginRouteEnv> {
+  const app = new Hono<PluginRouteEnv>()
+
+  // GET / — Installed plugins, what they provide and which secrets they still need
+  app.get('/', (c) => c.json({ plugins: runtime.plugins.list() }))
+
+  // POST / — Install or upgrade a plugin from a local folder or a URL: { source }
+  app.post('/', async (c) => {
+    try {
+      const body = await c.req.json<{ source?: unknown }>()
+      if (typeof body.source !== 'string' || !body.source.trim()) {
+        return c.json({ error: 'source must be a folder path or URL' }, 400)
+      }
+      return c.json({ plugin: await runtime.plugins.install(c.get('userId'), body.source.trim()) }, 201)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof PluginError ? 400 : 500)
+    }
+  })
+
+  // PUT /:name/secrets — Save secrets the plugin declares: { values: { NAME: "..." } }; an empty value removes one
+  app.put('/:name/secrets', async (c) => {
+    const name = c.req.param('name')
+    if (!runtime.plugins.get(name)) return c.json({ error: `Plugin not found: ${name}` }, 404)
+    try {
+      const body = await c.req.json<{ values?: unknown }>()
+      if (!body.values || typeof body.values !== 'object' || Array.isArray(body.values)) {
+        return c.json({ error: 'values must be an object' }, 400)
+      }
+      return c.json({ plugin: await runtime.plugins.setSecrets(c.get('userId'), name, body.values as Record<string, unknown>) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof PluginError ? 400 : 500)
+    }
+  })
+
+  // DELETE /:name — Uninstall, removing its tools, prompts, secrets and MCP server
+  app.delete('/:name', async (c) => {
+    try {
+      const { name } = c.req.param()
+      if (!(await runtime.plugins.uninstall(c.get('userId'), name))) {
+        return c.json({ error: `Plugin not found: ${name}` }, 404)
+      }
+      return c.json({ ok: true })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
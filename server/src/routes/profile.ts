@@ -0,0 +1,43 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import {
+  normalizeUserProfile,
+  parseUserProfile,
+  USER_PROFILE_PREFERENCE_KEY,
+  type UserProfile,
+} from '../orchestrator/user-profile.js'
+
+export function profileRoutes(runtime: RuntimeContext): Hono {
+  const app = new Hono()
+
+  // GET / — The profile shown to the agent in every run
+  app.get('/', async (c) => {
+    try {
+      const profile = parseUserProfile(await runtime.repositories.preferences.get(USER_PROFILE_PREFERENCE_KEY))
+      return c.json({ profile })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT / — Replace the profile; blank fields are cleared
+  app.put('/', async (c) => {
+    let profile: UserProfile
+    try {
+      profile = normalizeUserProfile(await c.req.json<Partial<UserProfile>>())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      await runtime.repositories.preferences.set(USER_PROFILE_PREFERENCE_KEY, JSON.stringify(profile))
+      return c.json({ profile })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
@@ -0,0 +1,55 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { deleteProject, ingestProject, linkProjects, listProjects, ProjectInputError } from '../services/projects.js'
+
+type ProjectsEnv = { Variables: { userId: string } }
+
+export function projectRoutes(runtime: RuntimeContext): Hono<ProjectsEnv> {
+  const app = new Hono<ProjectsEnv>()
+
+  // GET / — Folders the user has ingested as projects
+  app.get('/', async (c) => {
+    try {
+      return c.json({ projects: await listProjects(runtime, c.get('userId')) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST / — Index a local folder as a project, optionally linking it to a conversation for project.search
+  app.post('/', async (c) => {
+    try {
+      const body = await c.req.json<{ path?: string; name?: string; sessionId?: string }>()
+      if (!body.path) {
+        return c.json({ error: 'path is required' }, 400)
+      }
+      const userId = c.get('userId')
+      if (body.sessionId) {
+        const session = await runtime.repositories.sessions.getById(body.sessionId)
+        if (!session || session.userId !== userId) return c.json({ error: 'Session not found' }, 404)
+      }
+
+      const project = await ingestProject(runtime, userId, { root: body.path, name: body.name, signal: c.req.raw.signal })
+      const linked = body.sessionId ? await linkProjects(runtime, body.sessionId, [project.name]) : []
+      return c.json({ project, linked })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof ProjectInputError ? 400 : 500)
+    }
+  })
+
+  // DELETE /:name — Forget a project; conversations linked to it stop searching it
+  app.delete('/:name', async (c) => {
+    try {
+      const removed = await deleteProject(runtime, c.get('userId'), c.req.param('name'))
+      if (!removed) return c.json({ error: 'Project not found' }, 404)
+      return c.json({ ok: true })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
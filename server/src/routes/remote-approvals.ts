@@ -0,0 +1,110 @@
+import { Hono } from 'hono'
+import type { Context } from 'hono'
+import type { Agent, WaitingFor } from '../domain/types.js'
+import { escapeHtml } from '../lib/html.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+import { deliverApprovals } from '../orchestrator/delivery.js'
+import { buildDeps } from '../services/session-runner.js'
+import type { RemoteApprovalToken } from '../services/remote-approvals.js'
+
+const PAGE_CSP = "default-src 'none'; style-src 'unsafe-inline'; base-uri 'none'; form-action 'self'; frame-ancestors 'none'"
+const MAX_ARGS_CHARS = 2000
+
+/**
+ * Public approve/deny page behind the signed links the remote approval relay
+ * sends out. GET only renders, so link previews in chat apps cannot decide
+ * anything; the decision is a form POST back to the same URL.
+ */
+export function remoteApprovalRoutes(runtime: RuntimeContext): Hono {
+  const app = new Hono()
+
+  app.get('/:token', async (c) => {
+    const pending = await findPending(runtime, c.req.param('token'))
+    if (!pending.ok) return approvalHtml(c, pending.title, pending.message, pending.status)
+
+    const { wait, sessionTitle } = pending
+    const args = JSON.stringify(wait.args ?? {}, null, 2)
+    const body = [
+      wait.batch
+        ? `<p>A batch of <strong>${wait.batch.callIds.length} calls</strong> is waiting for approval${sessionTitle ? ` in “${escapeHtml(sessionTitle)}”` : ''}: ${escapeHtml(wait.batch.summary)}. It runs entirely or not at all.</p><p>First call: <strong>${escapeHtml(wait.name)}</strong></p>`
+        : `<p><strong>${escapeHtml(wait.name)}</strong> is waiting for approval${sessionTitle ? ` in “${escapeHtml(sessionTitle)}”` : ''}.</p>`,
+      wait.description && !wait.batch ? `<p>${escapeHtml(wait.description)}</p>` : '',
+      `<pre>${escapeHtml(args.length > MAX_ARGS_CHARS ? `${args.slice(0, MAX_ARGS_CHARS)}\n…` : args)}</pre>`,
+      '<form method="post"><label for="feedback">Feedback if denying (optional)</label><textarea id="feedback" name="feedback" rows="3" maxlength="4000" placeholder="What should it do instead?"></textarea>',
+      `<button name="decision" value="approved">${wait.batch ? `Run entire batch (${wait.batch.callIds.length} calls)` : 'Approve once'}</button> <button name="decision" value="denied">Deny</button></form>`,
+    ].join('')
+    return approvalHtml(c, 'Approval needed', body, 200, true)
+  })
+
+  app.post('/:token', async (c) => {
+    const pending = await findPending(runtime, c.req.param('token'))
+    if (!pending.ok) return approvalHtml(c, pending.title, pending.message, pending.status)
+
+    const form = await c.req.parseBody()
+    const decision = form.decision
+    if (decision !== 'approved' && decision !== 'denied') {
+      return approvalHtml(c, 'Approval not recorded', 'Choose approve or deny.', 400)
+    }
+
+    const feedback = decision === 'denied' && typeof form.feedback === 'string' ? form.feedback : undefined
+
+    const { token, agent, wait } = pending
+    // A link to an escalated batch decides the whole batch, as the page said
+    const callIds = wait.batch
+      ? agent.waitingFor.filter((w) => w.batch?.id === wait.batch!.id).map((w) => w.callId)
+      : [token.callId]
+    // The run continues in the background; the phone only needs to know the decision landed
+    void deliverApprovals(token.agentId, callIds, decision, buildDeps(runtime, agent.config.model), undefined, undefined, feedback)
+      .catch((err) => logger.warn({ err, agentId: token.agentId, callId: token.callId }, 'Remote approval failed'))
+
+    return approvalHtml(
+      c,
+      decision === 'approved' ? 'Approved' : 'Denied',
+      decision === 'approved'
+        ? 'The task continues. You can close this page.'
+        : feedback?.trim()
+          ? 'The tool will not run; the task continues with your feedback. You can close this page.'
+          : 'The tool will not run. You can close this page.',
+      200,
+    )
+  })
+
+  return app
+}
+
+type PendingLookup =
+  | {
+      ok: true
+      token: RemoteApprovalToken
+      agent: Agent
+      wait: WaitingFor
+      sessionTitle: string | null
+    }
+  | { ok: false; title: string; message: string; status: 404 | 410 }
+
+async function findPending(runtime: RuntimeContext, raw: string): Promise<PendingLookup> {
+  const token = runtime.remoteApprovals?.verify(raw) ?? null
+  if (!token) {
+    return { ok: false, title: 'Link not valid', message: 'This approval link is invalid or has expired. Open the app to answer it.', status: 404 }
+  }
+  const agent = await runtime.repositories.agents.getById(token.agentId)
+  const wait = agent?.status === 'waiting'
+    ? agent.waitingFor.find((w) => w.type === 'approval' && w.callId === token.callId)
+    : undefined
+  if (!agent || !wait) {
+    return { ok: false, title: 'Already answered', message: 'This tool call is no longer waiting for approval.', status: 410 }
+  }
+  const session = await runtime.repositories.sessions.getById(agent.sessionId)
+  return { ok: true, token, agent, wait, sessionTitle: session?.title ?? null }
+}
+
+function approvalHtml(c: Context, title: string, body: string, status: 200 | 400 | 404 | 410, rawBody = false) {
+  c.header('Cache-Control', 'no-store, max-age=0')
+  c.header('Content-Security-Policy', PAGE_CSP)
+  c.header('Referrer-Policy', 'no-referrer')
+  c.header('X-Content-Type-Options', 'nosniff')
+  c.header('X-Frame-Options', 'DENY')
+  const content = rawBody ? body : `<p>${escapeHtml(body)}</p>`
+  return c.html(`<!doctype html><html lang="en"><head><meta charset="utf-8"><meta name="viewport" content="width=device-width"><title>${escapeHtml(title)}</title><style>body{font:16px system-ui;margin:2rem;max-width:40rem}h1{font-size:1.5rem}pre{background:#f4f4f4;padding:.75rem;overflow:auto;white-space:pre-wrap}label{display:block;font-size:.9rem;margin-top:1rem}textarea{font:inherit;width:100%;box-sizing:border-box;margin-top:.25rem}button{font:inherit;padding:.6rem 1.2rem;margin-top:.5rem}</style></head><body><main><h1>${escapeHtml(title)}</h1>${content}</main></body></html>`, status)
+}
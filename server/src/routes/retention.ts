@@ -0,0 +1,57 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import {
+  normalizeRetentionPolicy,
+  RETENTION_PREFERENCE_KEY,
+  type RetentionPolicy,
+} from '../services/retention.js'
+
+export function retentionRoutes(runtime: RuntimeContext): Hono {
+  const app = new Hono()
+
+  // GET / — Current policy and a preview of what the next run would delete
+  app.get('/', async (c) => {
+    try {
+      if (!runtime.retention) {
+        return c.json({ error: 'Retention maintenance is not running' }, 503)
+      }
+      return c.json(await runtime.retention.preview())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT / — Replace the policy; responds with the new preview
+  app.put('/', async (c) => {
+    let policy: RetentionPolicy
+    try {
+      policy = normalizeRetentionPolicy(await c.req.json<Partial<RetentionPolicy>>())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      await runtime.repositories.preferences.set(RETENTION_PREFERENCE_KEY, JSON.stringify(policy))
+      return c.json(runtime.retention ? await runtime.retention.preview() : { policy })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST /run — Apply the policy now
+  app.post('/run', async (c) => {
+    try {
+      if (!runtime.retention) {
+        return c.json({ error: 'Retention maintenance is not running' }, 503)
+      }
+      return c.json(await runtime.retention.run())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
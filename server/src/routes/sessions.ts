@@ -1,8 +1,32 @@
-import { Hono } from 'hono'
+import { Hono, type MiddlewareHandler } from 'hono'
 import { logger } from '../lib/logger.js'
-import { splitModelId } from '../lib/model.js'
 import type { RuntimeContext } from '../lib/runtime.js'
-import { deleteSessionFiles } from '../tools/path-policy.js'
+import { purgeSession } from '../services/trash.js'
+import { listAgentArtifacts, readAgentArtifact } from '../services/agent-artifacts.js'
+import { getConversationStats } from '../services/conversation-stats.js'
+import { listRoutingDecisions } from '../services/model-routing.js'
+import { applyConversationTitle, writeConversationTitle } from '../services/conversation-title.js'
+import { buildRunReport, isRunReportFormat, renderRunReport, reportFileName, RUN_REPORT_FORMATS } from '../services/run-report.js'
+import { listPendingApprovals } from '../services/pending-approvals.js'
+import {
+  APPROVAL_TIMEOUT_PREFIX,
+  normalizeApprovalTimeout,
+  resolveApprovalTimeout,
+} from '../services/approval-timeouts.js'
+import { isIncognito, setIncognito } from '../services/memory-settings.js'
+import {
+  normalizeLanguage,
+  resolveResponseLanguage,
+  RESPONSE_LANGUAGE_PREFIX,
+} from '../orchestrator/language.js'
+import { isPrivacyMode, setPrivacyMode } from '../orchestrator/privacy.js'
+import { CONVERSATION_MODES, getConversationMode, isConversationMode, setConversationMode } from '../orchestrator/chat-mode.js'
+import {
+  listMessageRevisions,
+  MessageRevisionError,
+  restoreMessageRevision,
+  reviseMessage,
+} from '../services/message-history.js'
 
 type SessionEnv = { Variables: { userId: string } }
 
@@ -21,6 +45,56 @@ export function sessionRoutes(runtime: RuntimeContext): Hono<SessionEnv> {
     }
   })
 
+  // GET /trash — List trashed sessions
+  app.get('/trash', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const sessions = await runtime.repositories.sessions.listTrashed(userId)
+      return c.json({ sessions, retentionDays: runtime.config.trashRetentionDays })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // DELETE /trash — Permanently delete everything in the trash
+  app.delete('/trash', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const trashed = await runtime.repositories.sessions.listTrashed(userId)
+      for (const session of trashed) {
+        await purgeSession(runtime, session.id)
+      }
+      return c.json({ ok: true, purged: trashed.length })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // A trashed session reads as gone until it is restored; only restore and
+  // delete still reach it. Its messages stay with it in the trash.
+  const hideTrashed: MiddlewareHandler<SessionEnv> = async (c, next) => {
+    const id = c.req.param('id')
+    if (!id) return next()
+    const reachesTrash = (c.req.method === 'DELETE' && c.req.path.endsWith(`/${id}`))
+      || (c.req.method === 'POST' && c.req.path.endsWith(`/${id}/restore`))
+    if (!reachesTrash) {
+      try {
+        const session = await runtime.repositories.sessions.getById(id)
+        if (session && session.deletedAt !== null) {
+          return c.json({ error: `Session not found: ${id}` }, 404)
+        }
+      } catch (err) {
+        const message = err instanceof Error ? err.message : String(err)
+        return c.json({ error: message }, 500)
+      }
+    }
+    await next()
+  }
+  app.use('/:id', hideTrashed)
+  app.use('/:id/*', hideTrashed)
+
   // GET /:id — Get session with its agents
   app.get('/:id', async (c) => {
     try {
@@ -44,6 +118,296 @@ export function sessionRoutes(runtime: RuntimeContext): Hono<SessionEnv> {
     }
   })
 
+  // GET /:id/stats — Message, tool, turn, latency and cost totals
+  app.get('/:id/stats', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const stats = await getConversationStats(runtime, id)
+      if (!stats) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      return c.json(stats)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /:id/approvals — Tool calls awaiting approval, across all agents in the session
+  app.get('/:id/approvals', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const approvals = await listPendingApprovals(runtime, id)
+      if (!approvals) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      return c.json({ approvals })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /:id/approval-timeout — Effective approval window for the conversation
+  app.get('/:id/approval-timeout', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      return c.json(await resolveApprovalTimeout(runtime.repositories.preferences, id, runtime.config.approvalTimeoutMs))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /:id/approval-timeout — { timeoutMs } overrides the default; null goes back to it
+  app.put('/:id/approval-timeout', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      const body = await c.req.json<{ timeoutMs?: unknown }>()
+      const key = `${APPROVAL_TIMEOUT_PREFIX}${id}`
+      if (body.timeoutMs === null) {
+        await runtime.repositories.preferences.delete(key)
+      } else {
+        let timeoutMs: number
+        try {
+          timeoutMs = normalizeApprovalTimeout(body.timeoutMs)
+        } catch (err) {
+          return c.json({ error: err instanceof Error ? err.message : String(err) }, 400)
+        }
+        await runtime.repositories.preferences.set(key, String(timeoutMs))
+      }
+      return c.json(await resolveApprovalTimeout(runtime.repositories.preferences, id, runtime.config.approvalTimeoutMs))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /:id/incognito — Whether memory is off for this conversation
+  app.get('/:id/incognito', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      return c.json({ incognito: await isIncognito(runtime.repositories.preferences, id) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /:id/incognito — { incognito } stops learning from and recalling memories in this conversation
+  app.put('/:id/incognito', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      const body = await c.req.json<{ incognito?: unknown }>()
+      if (typeof body.incognito !== 'boolean') {
+        return c.json({ error: 'incognito must be a boolean' }, 400)
+      }
+      await setIncognito(runtime.repositories.preferences, id, body.incognito)
+      return c.json({ incognito: body.incognito })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /:id/privacy — Whether personal data in tool outputs is pseudonymized for the model
+  app.get('/:id/privacy', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      return c.json({ privacy: await isPrivacyMode(runtime.repositories.preferences, id) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /:id/privacy — { privacy } overrides the privacy_mode default; null goes back to it
+  app.put('/:id/privacy', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      const body = await c.req.json<{ privacy?: unknown }>()
+      if (typeof body.privacy !== 'boolean' && body.privacy !== null) {
+        return c.json({ error: 'privacy must be a boolean or null' }, 400)
+      }
+      await setPrivacyMode(runtime.repositories.preferences, id, body.privacy)
+      return c.json({ privacy: await isPrivacyMode(runtime.repositories.preferences, id) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /:id/mode — Whether messages run the agent loop or get a direct chat reply
+  app.get('/:id/mode', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      return c.json({ mode: await getConversationMode(runtime.repositories.preferences, id) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /:id/mode — { mode } is 'agent' or 'chat'; null goes back to agent mode
+  app.put('/:id/mode', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      const body = await c.req.json<{ mode?: unknown }>()
+      if (!isConversationMode(body.mode) && body.mode !== null) {
+        return c.json({ error: `mode must be one of: ${CONVERSATION_MODES.join(', ')}, or null` }, 400)
+      }
+      await setConversationMode(runtime.repositories.preferences, id, body.mode)
+      return c.json({ mode: await getConversationMode(runtime.repositories.preferences, id) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /:id/routing — Which model each message sent with model `auto` went to, and why
+  app.get('/:id/routing', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      return c.json({ decisions: await listRoutingDecisions(runtime.repositories.preferences, id) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /:id/language — Language replies and titles use, and where it comes from
+  app.get('/:id/language', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      return c.json(await resolveResponseLanguage(runtime.repositories.preferences, id))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /:id/language — { language } overrides the profile's; null goes back to it
+  app.put('/:id/language', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      const body = await c.req.json<{ language?: unknown }>()
+      let language: string | null
+      try {
+        language = normalizeLanguage(body.language)
+      } catch (err) {
+        return c.json({ error: err instanceof Error ? err.message : String(err) }, 400)
+      }
+      const key = `${RESPONSE_LANGUAGE_PREFIX}${id}`
+      if (language) await runtime.repositories.preferences.set(key, language)
+      else await runtime.repositories.preferences.delete(key)
+      return c.json(await resolveResponseLanguage(runtime.repositories.preferences, id))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /:id/artifacts — Files the agent created for download
+  app.get('/:id/artifacts', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const session = await runtime.repositories.sessions.getById(id)
+      if (!session) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      return c.json({ artifacts: await listAgentArtifacts(runtime.sessionFilesRoot, id) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /:id/artifacts/:name — Download an agent-created file
+  app.get('/:id/artifacts/:name', async (c) => {
+    try {
+      const { id, name } = c.req.param()
+      const found = await readAgentArtifact(runtime.sessionFilesRoot, id, name)
+      if (!found) {
+        return c.json({ error: `Artifact not found: ${name}` }, 404)
+      }
+      return c.body(new Uint8Array(found.data), 200, {
+        'Content-Type': found.artifact.mediaType,
+        'Content-Length': String(found.data.length),
+        'Content-Disposition': `attachment; filename*=UTF-8''${encodeURIComponent(found.artifact.name)}`,
+      })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /:id/report?format=markdown|html|conversation — Shareable account of how the conversation's result was produced, or the conversation itself as one HTML page
+  app.get('/:id/report', async (c) => {
+    try {
+      const { id } = c.req.param()
+      const format = c.req.query('format') ?? 'markdown'
+      if (!isRunReportFormat(format)) {
+        return c.json({ error: `format must be one of: ${RUN_REPORT_FORMATS.join(', ')}` }, 400)
+      }
+      const report = await buildRunReport(runtime, c.get('userId') as string, id, format)
+      if (!report) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      const body = renderRunReport(report, format, (text) => runtime.secrets.redact(text))
+      return c.body(body, 200, {
+        'Content-Type': format === 'markdown' ? 'text/markdown; charset=utf-8' : 'text/html; charset=utf-8',
+        'Content-Disposition': `attachment; filename*=UTF-8''${encodeURIComponent(reportFileName(report, format))}`,
+      })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
   // PATCH /:id — Update title, status
   app.patch('/:id', async (c) => {
     try {
@@ -90,54 +454,97 @@ export function sessionRoutes(runtime: RuntimeContext): Hono<SessionEnv> {
         return c.json({ error: 'No messages in session to generate title from' }, 400)
       }
 
-      const conversationText = messages
-        .map((m) => `${m.role}: ${typeof m.content === 'string' ? m.content : JSON.stringify(m.content)}`)
-        .join('\n')
-        .slice(0, 2000) // limit context size
+      const title = await writeConversationTitle(runtime, session, messages, runtime.config.titleModel ?? runtime.config.defaultModel)
+      await applyConversationTitle(runtime, session, title)
+
+      return c.json({ title })
+    } catch (err) {
+      logger.error(err, 'POST /:id/generate-title failed')
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
 
-      const model = runtime.config.defaultModel
-      const provider = runtime.providers.resolve(model)
+  // POST /:id/restore — Move a session out of the trash
+  app.post('/:id/restore', async (c) => {
+    try {
+      const { id } = c.req.param()
 
-      const { model: modelName } = splitModelId(model)
+      const existing = await runtime.repositories.sessions.getById(id)
+      if (!existing) {
+        return c.json({ error: `Session not found: ${id}` }, 404)
+      }
+      if (existing.deletedAt === null) {
+        return c.json({ error: `Session is not in the trash: ${id}` }, 409)
+      }
 
-      const response = await provider.generate({
-        model: modelName,
-        messages: [
-          {
-            role: 'user',
-            content: `Generate a short title (max 6 words) for this conversation. Return ONLY the title, no quotes or extra text.\n\n${conversationText}`,
-          },
-        ],
-        temperature: 0.3,
-        max_tokens: 30,
-      })
+      const restored = await runtime.repositories.sessions.update(id, { deletedAt: null })
+      return c.json(restored)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
 
-      const title = (typeof response.content === 'string' ? response.content : String(response.content)).trim()
+  // PATCH /:id/messages/:itemId — Edit a message; the old content becomes a revision
+  app.patch('/:id/messages/:itemId', async (c) => {
+    try {
+      const { id, itemId } = c.req.param()
+      const body = await c.req.json<{ content?: string }>()
+      if (typeof body.content !== 'string') {
+        return c.json({ error: 'content is required' }, 400)
+      }
+      const revision = await reviseMessage(runtime, id, itemId, { content: body.content }, 'edit')
+      const item = await runtime.repositories.items.getById(itemId)
+      return c.json({ item, revision })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof MessageRevisionError ? err.status : 500)
+    }
+  })
 
-      // Update the session title
-      await runtime.repositories.sessions.update(id, { title })
+  // GET /:id/messages/:itemId/revisions — Prior versions, oldest first
+  app.get('/:id/messages/:itemId/revisions', async (c) => {
+    try {
+      const { id, itemId } = c.req.param()
+      return c.json(await listMessageRevisions(runtime, id, itemId))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof MessageRevisionError ? err.status : 500)
+    }
+  })
 
-      return c.json({ title })
+  // POST /:id/messages/:itemId/revisions/:revisionId/restore
+  app.post('/:id/messages/:itemId/revisions/:revisionId/restore', async (c) => {
+    try {
+      const { id, itemId, revisionId } = c.req.param()
+      const revision = await restoreMessageRevision(runtime, id, itemId, revisionId)
+      const item = await runtime.repositories.items.getById(itemId)
+      return c.json({ item, revision })
     } catch (err) {
-      logger.error(err, 'POST /:id/generate-title failed')
       const message = err instanceof Error ? err.message : String(err)
-      return c.json({ error: message }, 500)
+      return c.json({ error: message }, err instanceof MessageRevisionError ? err.status : 500)
     }
   })
 
-  // DELETE /:id — Delete session
+  // DELETE /:id — Move session to the trash (?permanent=true skips it)
   app.delete('/:id', async (c) => {
     try {
       const { id } = c.req.param()
+      const permanent = c.req.query('permanent') === 'true'
 
       const existing = await runtime.repositories.sessions.getById(id)
       if (!existing) {
         return c.json({ error: `Session not found: ${id}` }, 404)
       }
 
-      await runtime.repositories.sessions.delete(id)
-      await deleteSessionFiles(runtime.sessionFilesRoot, id)
-      return c.json({ ok: true })
+      if (permanent) {
+        await purgeSession(runtime, id)
+        return c.json({ ok: true, permanent: true })
+      }
+
+      await runtime.repositories.sessions.update(id, { deletedAt: Date.now() })
+      return c.json({ ok: true, permanent: false })
     } catch (err) {
       const message = err instanceof Error ? err.message : String(err)
       return c.json({ error: message }, 500)
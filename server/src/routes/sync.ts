@@ -0,0 +1,30 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+
+export function syncRoutes(runtime: RuntimeContext): Hono {
+  const app = new Hono()
+
+  // GET / — Sync status
+  app.get('/', (c) => {
+    if (!runtime.sync) {
+      return c.json({ enabled: false })
+    }
+    return c.json({ enabled: true, ...runtime.sync.status() })
+  })
+
+  // POST /run — Export local changes and merge peer logs now
+  app.post('/run', async (c) => {
+    try {
+      if (!runtime.sync) {
+        return c.json({ error: 'Sync is not configured; set SYNC_DIR' }, 400)
+      }
+      const result = await runtime.sync.runOnce()
+      return c.json(result)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
@@ -1,8 +1,48 @@
+import { randomUUID } from 'node:crypto'
 import { Hono } from 'hono'
 import type { RuntimeContext } from '../lib/runtime.js'
+import {
+  APPROVAL_RULES_PREFERENCE_KEY,
+  normalizeApprovalRule,
+  parseApprovalRules,
+  type ApprovalRule,
+} from '../orchestrator/approval-rules.js'
+import {
+  APPROVAL_CATEGORY_DEFAULTS_PREFERENCE_KEY,
+  BUILT_IN_CATEGORY_DEFAULTS,
+  normalizeCategoryDefaults,
+  parseCategoryDefaults,
+  type CategoryApprovalDefaults,
+} from '../orchestrator/approval-categories.js'
+import {
+  GUARDRAIL_POLICY_PREFERENCE_KEY,
+  normalizeGuardrailPolicy,
+  parseGuardrailPolicy,
+  type GuardrailPolicy,
+} from '../orchestrator/guardrails.js'
+import type { ApprovalOutcome } from '../repositories/types.js'
 
-export function toolRoutes(runtime: RuntimeContext): Hono {
-  const app = new Hono()
+type ToolEnv = { Variables: { userId: string } }
+
+const APPROVAL_OUTCOMES: ApprovalOutcome[] = ['approved', 'denied', 'auto_approved', 'auto_denied', 'expired', 'cancelled']
+const DEFAULT_HISTORY_LIMIT = 200
+const MAX_HISTORY_LIMIT = 5000
+
+/** Accepts epoch milliseconds or anything `Date.parse` understands (e.g. 2025-01-31). */
+function parseTimestamp(value: string | undefined): number | undefined | null {
+  if (value === undefined || value === '') return undefined
+  const numeric = Number(value)
+  if (Number.isFinite(numeric)) return numeric
+  const parsed = Date.parse(value)
+  return Number.isNaN(parsed) ? null : parsed
+}
+
+export function toolRoutes(runtime: RuntimeContext): Hono<ToolEnv> {
+  const app = new Hono<ToolEnv>()
+  const preferences = runtime.repositories.preferences
+
+  const loadRules = async () => parseApprovalRules(await preferences.get(APPROVAL_RULES_PREFERENCE_KEY))
+  const saveRules = (rules: ApprovalRule[]) => preferences.set(APPROVAL_RULES_PREFERENCE_KEY, JSON.stringify(rules))
 
   // GET / — List all tools with metadata
   app.get('/', (c) => {
@@ -15,5 +55,191 @@ export function toolRoutes(runtime: RuntimeContext): Hono {
     }
   })
 
+  // GET /approval-rules — Policy rules in evaluation order
+  app.get('/approval-rules', async (c) => {
+    try {
+      return c.json({ rules: await loadRules() })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /approval-rules — Replace the whole list (used to reorder)
+  app.put('/approval-rules', async (c) => {
+    let rules: ApprovalRule[]
+    try {
+      const body = await c.req.json<{ rules?: Array<Partial<ApprovalRule>> }>()
+      if (!Array.isArray(body.rules)) throw new Error('rules must be an array')
+      rules = body.rules.map((rule) => normalizeApprovalRule({ ...rule, id: rule.id ?? randomUUID() }))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      await saveRules(rules)
+      return c.json({ rules })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST /approval-rules — Add a rule; `position` inserts it (default: last)
+  app.post('/approval-rules', async (c) => {
+    let rule: ApprovalRule
+    let position: number | undefined
+    try {
+      const body = await c.req.json<Partial<ApprovalRule> & { position?: number }>()
+      rule = normalizeApprovalRule({ ...body, id: randomUUID() })
+      position = body.position
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      const rules = await loadRules()
+      rules.splice(Number.isInteger(position) ? Math.max(0, position!) : rules.length, 0, rule)
+      await saveRules(rules)
+      return c.json(rule, 201)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /approval-rules/:id — Update a rule in place
+  app.put('/approval-rules/:id', async (c) => {
+    const id = c.req.param('id')
+    try {
+      const rules = await loadRules()
+      const index = rules.findIndex((rule) => rule.id === id)
+      if (index < 0) return c.json({ error: 'Rule not found' }, 404)
+      let rule: ApprovalRule
+      try {
+        rule = normalizeApprovalRule({ ...rules[index], ...(await c.req.json<Partial<ApprovalRule>>()), id })
+      } catch (err) {
+        const message = err instanceof Error ? err.message : String(err)
+        return c.json({ error: message }, 400)
+      }
+      rules[index] = rule
+      await saveRules(rules)
+      return c.json(rule)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // DELETE /approval-rules/:id
+  app.delete('/approval-rules/:id', async (c) => {
+    const id = c.req.param('id')
+    try {
+      const rules = await loadRules()
+      const remaining = rules.filter((rule) => rule.id !== id)
+      if (remaining.length === rules.length) return c.json({ error: 'Rule not found' }, 404)
+      await saveRules(remaining)
+      return c.json({ ok: true })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /approval-categories — Global approval default per tool category, and the built-ins underneath
+  app.get('/approval-categories', async (c) => {
+    try {
+      const defaults = parseCategoryDefaults(await preferences.get(APPROVAL_CATEGORY_DEFAULTS_PREFERENCE_KEY))
+      return c.json({ defaults, builtIn: BUILT_IN_CATEGORY_DEFAULTS })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /approval-categories — Merge { category: 'ask' | 'allow' | null }; null restores the built-in
+  app.put('/approval-categories', async (c) => {
+    let changes: CategoryApprovalDefaults
+    let cleared: string[]
+    try {
+      const body = await c.req.json<{ defaults?: Record<string, unknown> }>()
+      changes = normalizeCategoryDefaults(body.defaults)
+      cleared = Object.entries(body.defaults ?? {}).filter(([, value]) => value === null).map(([key]) => key)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      const current = parseCategoryDefaults(await preferences.get(APPROVAL_CATEGORY_DEFAULTS_PREFERENCE_KEY))
+      const defaults = Object.fromEntries(
+        Object.entries({ ...current, ...changes }).filter(([key]) => !cleared.includes(key)),
+      ) as CategoryApprovalDefaults
+      await preferences.set(APPROVAL_CATEGORY_DEFAULTS_PREFERENCE_KEY, JSON.stringify(defaults))
+      return c.json({ defaults, builtIn: BUILT_IN_CATEGORY_DEFAULTS })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /guardrails — Guardrail policy checked on every turn of every run
+  app.get('/guardrails', async (c) => {
+    try {
+      return c.json({ policy: parseGuardrailPolicy(await preferences.get(GUARDRAIL_POLICY_PREFERENCE_KEY)) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /guardrails — Replace the guardrail policy
+  app.put('/guardrails', async (c) => {
+    let policy: GuardrailPolicy
+    try {
+      policy = normalizeGuardrailPolicy(await c.req.json<Partial<GuardrailPolicy>>())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      await preferences.set(GUARDRAIL_POLICY_PREFERENCE_KEY, JSON.stringify(policy))
+      return c.json({ policy })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /approval-history?tool=&sessionId=&outcome=&from=&to=&limit= — Approval decisions, newest first
+  app.get('/approval-history', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const from = parseTimestamp(c.req.query('from'))
+      const to = parseTimestamp(c.req.query('to'))
+      if (from === null || to === null) {
+        return c.json({ error: 'from/to must be an ISO date or epoch milliseconds' }, 400)
+      }
+      const outcome = c.req.query('outcome') || undefined
+      if (outcome && !APPROVAL_OUTCOMES.includes(outcome as ApprovalOutcome)) {
+        return c.json({ error: `outcome must be one of: ${APPROVAL_OUTCOMES.join(', ')}` }, 400)
+      }
+      const limit = Math.min(Math.max(Number(c.req.query('limit')) || DEFAULT_HISTORY_LIMIT, 1), MAX_HISTORY_LIMIT)
+
+      const records = await runtime.repositories.approvalHistory.list({
+        userId,
+        sessionId: c.req.query('sessionId') || undefined,
+        toolName: c.req.query('tool') || undefined,
+        outcome: outcome as ApprovalOutcome | undefined,
+        from,
+        to,
+        limit,
+      })
+      return c.json({ records })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
   return app
 }
@@ -1,8 +1,47 @@
 import { Hono } from 'hono'
 import type { RuntimeContext } from '../lib/runtime.js'
+import { buildUsageDashboard, buildUsageReport, USAGE_GROUP_BY, type UsageBucket, type UsageGroupBy } from '../usage/report.js'
+import { buildToolCostReport } from '../usage/attribution.js'
+import { estimateRunCost } from '../usage/estimate.js'
+import { parseUserProfile, USER_PROFILE_PREFERENCE_KEY } from '../orchestrator/user-profile.js'
+import { usageToCsv } from '../usage/csv.js'
+import { BUDGET_PREFERENCE_KEY, normalizeBudget, type BudgetSettings } from '../usage/budget.js'
+import { normalizeModelPrice, PriceCatalogError, type ModelPrice } from '../usage/pricing.js'
+import {
+  convertToolCostReport,
+  convertUsageDashboard,
+  convertUsageReport,
+  CURRENCY_PREFERENCE_KEY,
+  DEFAULT_CURRENCY,
+  displayCurrency,
+  ExchangeRateError,
+  fetchExchangeRate,
+  normalizeCurrencySettings,
+  parseCurrencySettings,
+  type CurrencySettings,
+  type DisplayCurrency,
+} from '../usage/currency.js'
 
-export function usageRoutes(runtime: RuntimeContext): Hono {
-  const app = new Hono()
+type UsageEnv = { Variables: { userId: string } }
+
+/** Accepts epoch milliseconds or anything `Date.parse` understands (e.g. 2025-01-31). */
+function parseTimestamp(value: string | undefined): number | undefined | null {
+  if (value === undefined || value === '') return undefined
+  const numeric = Number(value)
+  if (Number.isFinite(numeric)) return numeric
+  const parsed = Date.parse(value)
+  return Number.isNaN(parsed) ? null : parsed
+}
+
+export function usageRoutes(runtime: RuntimeContext): Hono<UsageEnv> {
+  const app = new Hono<UsageEnv>()
+
+  const currencySettings = async (): Promise<CurrencySettings> =>
+    parseCurrencySettings(await runtime.repositories.preferences.get(CURRENCY_PREFERENCE_KEY))
+
+  // Reports show costs in the display currency; `?currency=USD` asks for the stored amounts
+  const reportCurrency = async (requested: string | undefined): Promise<DisplayCurrency> =>
+    displayCurrency(requested?.toUpperCase() === 'USD' ? DEFAULT_CURRENCY : await currencySettings())
 
   // GET / — Return basic usage stats
   app.get('/', async (c) => {
@@ -36,5 +75,346 @@ export function usageRoutes(runtime: RuntimeContext): Hono {
     }
   })
 
+  // GET /report?from=&to=&group_by=day|week|model|conversation|phase&currency=
+  // `from` is inclusive, `to` exclusive. Buckets are shaped for charting.
+  app.get('/report', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const groupBy = (c.req.query('group_by') ?? 'day') as UsageGroupBy
+      if (!USAGE_GROUP_BY.includes(groupBy)) {
+        return c.json({ error: `group_by must be one of: ${USAGE_GROUP_BY.join(', ')}` }, 400)
+      }
+      const from = parseTimestamp(c.req.query('from'))
+      const to = parseTimestamp(c.req.query('to'))
+      if (from === null || to === null) {
+        return c.json({ error: 'from/to must be an ISO date or epoch milliseconds' }, 400)
+      }
+
+      const records = await runtime.repositories.usage.list({ userId, from, to })
+      const currency = await reportCurrency(c.req.query('currency'))
+      return c.json(convertUsageReport(buildUsageReport(records, groupBy, { from, to }), currency))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /dashboard?from=&to=&conversations=&currency= — Daily, per-model, per-conversation and per-phase
+  // aggregates with cache-hit ratios, in one response
+  app.get('/dashboard', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const from = parseTimestamp(c.req.query('from'))
+      const to = parseTimestamp(c.req.query('to'))
+      if (from === null || to === null) {
+        return c.json({ error: 'from/to must be an ISO date or epoch milliseconds' }, 400)
+      }
+      const limit = c.req.query('conversations') === undefined ? undefined : Number(c.req.query('conversations'))
+      if (limit !== undefined && (!Number.isInteger(limit) || limit < 0)) {
+        return c.json({ error: 'conversations must be a non-negative integer' }, 400)
+      }
+
+      const records = await runtime.repositories.usage.list({ userId, from, to })
+      const dashboard = buildUsageDashboard(records, { from, to, conversationLimit: limit })
+      const conversations: Array<UsageBucket & { title: string | null }> = []
+      for (const bucket of dashboard.conversations) {
+        const session = await runtime.repositories.sessions.getById(bucket.key)
+        conversations.push({ ...bucket, title: session?.title ?? null })
+      }
+      const currency = await reportCurrency(c.req.query('currency'))
+      return c.json(convertUsageDashboard({ ...dashboard, conversations }, currency))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /tools?from=&to=&sessionId=&currency= — Prompt tokens and cost attributed to each tool's results
+  app.get('/tools', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const from = parseTimestamp(c.req.query('from'))
+      const to = parseTimestamp(c.req.query('to'))
+      if (from === null || to === null) {
+        return c.json({ error: 'from/to must be an ISO date or epoch milliseconds' }, 400)
+      }
+
+      const records = await runtime.repositories.usage.list({ userId, sessionId: c.req.query('sessionId') || undefined, from, to })
+      const currency = await reportCurrency(c.req.query('currency'))
+      return c.json(convertToolCostReport(buildToolCostReport(records, { from, to }), currency))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /composition?sessionId= — What each recorded prompt of a session was made of
+  app.get('/composition', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const sessionId = c.req.query('sessionId')
+      if (!sessionId) return c.json({ error: 'sessionId is required' }, 400)
+
+      const records = await runtime.repositories.usage.list({ userId, sessionId })
+      return c.json({
+        sessionId,
+        calls: records
+          .filter((r) => r.promptComposition)
+          .map((r) => ({
+            createdAt: r.createdAt,
+            agentId: r.agentId,
+            phase: r.phase,
+            model: `${r.provider}:${r.model}`,
+            promptTokens: r.inputTokens + r.cacheReadTokens + r.cacheWriteTokens,
+            composition: r.promptComposition,
+          })),
+      })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST /estimate — Projected prompt size and cost of the next controller turn
+  // Body: { sessionId, model?, message? } — `message` is a draft not yet sent.
+  app.post('/estimate', async (c) => {
+    try {
+      const body = await c.req.json<{ sessionId?: unknown; model?: unknown; message?: unknown }>().catch(() => ({}) as Record<string, unknown>)
+      if (typeof body.sessionId !== 'string' || !body.sessionId) {
+        return c.json({ error: 'sessionId is required' }, 400)
+      }
+      if (body.model !== undefined && (typeof body.model !== 'string' || !body.model.trim())) {
+        return c.json({ error: 'model must be a non-empty string' }, 400)
+      }
+      if (body.message !== undefined && typeof body.message !== 'string') {
+        return c.json({ error: 'message must be a string' }, 400)
+      }
+
+      const session = await runtime.repositories.sessions.getById(body.sessionId)
+      if (!session) return c.json({ error: `Session not found: ${body.sessionId}` }, 404)
+      const agent = await runtime.repositories.agents.findRootAgent(session.id)
+      if (!agent) return c.json({ error: 'Session has no agent to estimate yet' }, 404)
+
+      const stored = await runtime.repositories.items.listByAgent(agent.id)
+      const items = await runtime.attachments.hydrate(stored)
+      return c.json({
+        sessionId: session.id,
+        ...estimateRunCost(agent, runtime.tools, items, runtime.pricing, {
+          model: (body.model as string | undefined)?.trim(),
+          draft: (body.message as string | undefined) || undefined,
+          userProfile: parseUserProfile(await runtime.repositories.preferences.get(USER_PROFILE_PREFERENCE_KEY)),
+          instructionFiles: await runtime.instructions.forSession(session.id),
+        }),
+      })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /export.csv?from=&to= — Per-call usage rows for expense reporting
+  app.get('/export.csv', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      const from = parseTimestamp(c.req.query('from'))
+      const to = parseTimestamp(c.req.query('to'))
+      if (from === null || to === null) {
+        return c.json({ error: 'from/to must be an ISO date or epoch milliseconds' }, 400)
+      }
+
+      const records = await runtime.repositories.usage.list({ userId, from, to })
+      const titles = new Map<string, string | null>()
+      for (const sessionId of new Set(records.map((r) => r.sessionId))) {
+        if (!sessionId) continue
+        const session = await runtime.repositories.sessions.getById(sessionId)
+        titles.set(sessionId, session?.title ?? null)
+      }
+
+      const date = new Date().toISOString().slice(0, 10)
+      return c.body(usageToCsv(records, titles), 200, {
+        'Content-Type': 'text/csv; charset=utf-8',
+        'Content-Disposition': `attachment; filename="usage-${date}.csv"`,
+      })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /budget — Budget settings, current spend per period, and spend against each cap
+  app.get('/budget', async (c) => {
+    try {
+      const userId = c.get('userId') as string
+      return c.json({
+        settings: await runtime.budget.settings(),
+        status: await runtime.budget.status(userId),
+        caps: await runtime.budget.capStatus(userId),
+      })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /budget — Replace budget settings
+  app.put('/budget', async (c) => {
+    let settings: BudgetSettings
+    try {
+      settings = normalizeBudget(await c.req.json<Partial<BudgetSettings>>())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      await runtime.repositories.preferences.set(BUDGET_PREFERENCE_KEY, JSON.stringify(settings))
+      const userId = c.get('userId') as string
+      return c.json({ settings, status: await runtime.budget.status(userId), caps: await runtime.budget.capStatus(userId) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // GET /currency — Display currency, exchange rate and locale for usage reports
+  app.get('/currency', async (c) => {
+    try {
+      return c.json(await currencySettings())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /currency — { code, rate?, locale? }; without a rate it is fetched from EXCHANGE_RATE_URL
+  app.put('/currency', async (c) => {
+    let settings: CurrencySettings
+    try {
+      settings = normalizeCurrencySettings(await c.req.json<Partial<CurrencySettings>>())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      if (settings.rate === 0) {
+        settings = { ...settings, rate: await fetchExchangeRate(settings.code, runtime.config.exchangeRateUrl), source: 'remote' }
+      }
+      settings = { ...settings, rateUpdatedAt: Date.now() }
+      await runtime.repositories.preferences.set(CURRENCY_PREFERENCE_KEY, JSON.stringify(settings))
+      return c.json(settings)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof ExchangeRateError ? 502 : 500)
+    }
+  })
+
+  // POST /currency/refresh — Re-fetch the rate for the current display currency
+  app.post('/currency/refresh', async (c) => {
+    try {
+      const current = await currencySettings()
+      if (current.code === 'USD') return c.json(current)
+      const settings: CurrencySettings = {
+        ...current,
+        rate: await fetchExchangeRate(current.code, runtime.config.exchangeRateUrl),
+        source: 'remote',
+        rateUpdatedAt: Date.now(),
+      }
+      await runtime.repositories.preferences.set(CURRENCY_PREFERENCE_KEY, JSON.stringify(settings))
+      return c.json(settings)
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof ExchangeRateError ? 502 : 500)
+    }
+  })
+
+  // GET /pricing — Per-model prices (USD per million tokens), where each came from, and backend prices
+  app.get('/pricing', (c) => {
+    try {
+      return c.json({
+        prices: runtime.pricing.list(),
+        backends: runtime.pricing.backends(),
+        catalogUrl: runtime.config.pricingCatalogUrl || null,
+      })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /pricing/backends/:provider — Price every model of a provider (self-hosted or
+  // OpenAI-compatible) at one rate; wins over per-model prices and Ollama's $0
+  app.put('/pricing/backends/:provider', async (c) => {
+    let price: ModelPrice
+    try {
+      price = normalizeModelPrice(await c.req.json<Partial<ModelPrice>>())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      const provider = c.req.param('provider').toLowerCase()
+      return c.json({ provider, price: await runtime.pricing.setBackend(provider, price) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // DELETE /pricing/backends/:provider — Go back to per-model prices for this provider
+  app.delete('/pricing/backends/:provider', async (c) => {
+    try {
+      if (!(await runtime.pricing.resetBackend(c.req.param('provider')))) {
+        return c.json({ error: 'No backend price for this provider' }, 404)
+      }
+      return c.json({ ok: true })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /pricing/:model — Set a model's price by hand; refreshes leave it alone
+  app.put('/pricing/:model', async (c) => {
+    let price: ModelPrice
+    try {
+      price = normalizeModelPrice(await c.req.json<Partial<ModelPrice>>())
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    try {
+      return c.json(await runtime.pricing.set(c.req.param('model'), price))
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // DELETE /pricing/:model — Drop an edited price; bundled models revert to the bundled price
+  app.delete('/pricing/:model', async (c) => {
+    try {
+      const price = await runtime.pricing.reset(c.req.param('model'))
+      if (price === undefined) return c.json({ error: 'No price for this model' }, 404)
+      return c.json({ price })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST /pricing/refresh — Pull the remote catalog ({ url? } overrides PRICING_CATALOG_URL)
+  app.post('/pricing/refresh', async (c) => {
+    try {
+      const body = await c.req.json<{ url?: unknown }>().catch(() => ({}) as { url?: unknown })
+      if (body.url !== undefined && (typeof body.url !== 'string' || !/^https?:\/\//.test(body.url))) {
+        return c.json({ error: 'url must be an http(s) URL' }, 400)
+      }
+      const result = await runtime.pricing.refresh(body.url as string | undefined)
+      return c.json({ ...result, prices: runtime.pricing.list() })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, err instanceof PriceCatalogError ? 502 : 500)
+    }
+  })
+
   return app
 }
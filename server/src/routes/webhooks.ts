@@ -0,0 +1,84 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { ToolRegistryImpl } from '../tools/registry.js'
+import { registerWebhookTools } from '../tools/webhooks.js'
+import {
+  type WebhookEndpoint,
+  loadWebhookEndpoints,
+  normalizeWebhookEndpoint,
+  saveWebhookEndpoints,
+  webhookEndpointView,
+} from '../services/webhooks.js'
+
+export function webhookRoutes(runtime: RuntimeContext): Hono {
+  const app = new Hono()
+  const apiKeys = runtime.repositories.apiKeys
+
+  // GET / — Registered endpoints, without header values
+  app.get('/', async (c) => {
+    try {
+      const endpoints = await loadWebhookEndpoints(apiKeys)
+      return c.json({ endpoints: endpoints.map(webhookEndpointView) })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // PUT /:name — Register or change an endpoint and enable webhook.post
+  app.put('/:name', async (c) => {
+    const name = c.req.param('name')
+    let body: Record<string, unknown>
+    try {
+      body = await c.req.json<Record<string, unknown>>()
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 400)
+    }
+    return runtime.webhooks.locked(async () => {
+      let endpoints: WebhookEndpoint[]
+      let endpoint: WebhookEndpoint
+      try {
+        endpoints = await loadWebhookEndpoints(apiKeys).catch(() => [])
+        const existing = endpoints.find((entry) => entry.name === name) ?? null
+        endpoint = normalizeWebhookEndpoint(name, body, existing)
+      } catch (err) {
+        const message = err instanceof Error ? err.message : String(err)
+        return c.json({ error: message }, 400)
+      }
+      try {
+        await saveWebhookEndpoints(apiKeys, [...endpoints.filter((entry) => entry.name !== name), endpoint])
+        await runtime.webhooks.refresh()
+        const registry = runtime.tools as ToolRegistryImpl
+        if (!registry.getMetadata('webhook.post')) registerWebhookTools(registry, runtime.webhooks)
+        return c.json({ endpoint: webhookEndpointView(endpoint) })
+      } catch (err) {
+        const message = err instanceof Error ? err.message : String(err)
+        return c.json({ error: message }, 500)
+      }
+    })
+  })
+
+  // DELETE /:name — Forget an endpoint; the tool goes away with the last one
+  app.delete('/:name', async (c) => {
+    const name = c.req.param('name')
+    return runtime.webhooks.locked(async () => {
+      try {
+        const endpoints = await loadWebhookEndpoints(apiKeys)
+        if (!endpoints.some((entry) => entry.name === name)) {
+          return c.json({ error: 'Webhook endpoint not found' }, 404)
+        }
+        const remaining = endpoints.filter((entry) => entry.name !== name)
+        await saveWebhookEndpoints(apiKeys, remaining)
+        await runtime.webhooks.refresh()
+        if (remaining.length === 0) (runtime.tools as ToolRegistryImpl).unregister('webhook.post')
+        return c.json({ ok: true })
+      } catch (err) {
+        const message = err instanceof Error ? err.message : String(err)
+        return c.json({ error: message }, 500)
+      }
+    })
+  })
+
+  return app
+}
@@ -0,0 +1,87 @@
+import { Hono } from 'hono'
+import { streamSSE } from 'hono/streaming'
+import { randomUUID } from 'node:crypto'
+import { toWireEvent } from '../events/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+
+const WINDOW_ID_RE = /^[A-Za-z0-9_-]{1,64}$/
+
+export function windowRoutes(runtime: RuntimeContext): Hono {
+  const app = new Hono()
+
+  const ownsSession = async (userId: string, sessionId: string): Promise<boolean> => {
+    const session = await runtime.repositories.sessions.getById(sessionId)
+    return session?.userId === userId
+  }
+
+  // GET /:windowId/claims — Conversations this window currently owns
+  app.get('/:windowId/claims', (c) => {
+    const { windowId } = c.req.param()
+    if (!WINDOW_ID_RE.test(windowId)) return c.json({ error: 'Invalid window id' }, 400)
+    return c.json({ windowId, sessionIds: runtime.windows.claimedBy(windowId) })
+  })
+
+  // POST /:windowId/claims — Route a conversation's events to this window only
+  app.post('/:windowId/claims', async (c) => {
+    try {
+      const { windowId } = c.req.param()
+      if (!WINDOW_ID_RE.test(windowId)) return c.json({ error: 'Invalid window id' }, 400)
+      const body = await c.req.json<{ sessionId?: string }>()
+      if (!body.sessionId) return c.json({ error: 'sessionId is required' }, 400)
+      if (!(await ownsSession(c.get('userId') as string, body.sessionId))) {
+        return c.json({ error: `Session not found: ${body.sessionId}` }, 404)
+      }
+      const previousWindowId = runtime.windows.claim(body.sessionId, windowId)
+      return c.json({ sessionId: body.sessionId, windowId, previousWindowId })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // DELETE /:windowId/claims/:sessionId — Hand the conversation back to every window
+  app.delete('/:windowId/claims/:sessionId', (c) => {
+    const { windowId, sessionId } = c.req.param()
+    return c.json({ sessionId, released: runtime.windows.release(sessionId, windowId) })
+  })
+
+  // GET /:windowId/events?types=a,b — SSE stream of the events routed to this window.
+  // Closing the stream releases the window's claims.
+  app.get('/:windowId/events', async (c) => {
+    const { windowId } = c.req.param()
+    if (!WINDOW_ID_RE.test(windowId)) return c.json({ error: 'Invalid window id' }, 400)
+    const userId = c.get('userId') as string
+    const types = c.req.query('types')?.split(',').map((type) => type.trim()).filter(Boolean) ?? []
+
+    return streamSSE(c, async (stream) => {
+      const iterator = runtime.events
+        .subscribe({ types: types.length > 0 ? types : undefined })[Symbol.asyncIterator]()
+      stream.onAbort(() => {
+        runtime.windows.releaseWindow(windowId)
+        void iterator.return?.()
+      })
+
+      const owned = new Map<string, boolean>()
+      for (let next = await iterator.next(); !next.done; next = await iterator.next()) {
+        const event = next.value
+        if (!runtime.windows.routes(event.session_id, windowId, event.type)) continue
+        let mine = owned.get(event.session_id)
+        if (mine === undefined) {
+          const session = await runtime.repositories.sessions.getById(event.session_id)
+          // Unknown sessions are not cached; they may be created after this lookup
+          if (!session) continue
+          mine = session.userId === userId
+          owned.set(event.session_id, mine)
+        }
+        if (!mine) continue
+        await stream.writeSSE({
+          event: event.type,
+          data: JSON.stringify(toWireEvent(event)),
+          id: randomUUID(),
+        })
+      }
+    })
+  })
+
+  return app
+}
@@ -0,0 +1,56 @@
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { assertWorkspaceName, DEFAULT_WORKSPACE, listWorkspaces } from '../lib/workspace.js'
+
+/** `switchWorkspace` rebuilds the runtime for another workspace and resolves once it serves requests. */
+export function workspaceRoutes(runtime: RuntimeContext, switchWorkspace: (name: string) => Promise<RuntimeContext>): Hono {
+  const app = new Hono()
+
+  // GET / — List workspaces and the one this process is serving
+  app.get('/', (c) => {
+    try {
+      return c.json({
+        active: runtime.config.workspace,
+        workspaces: listWorkspaces(runtime.config.workspacesDir),
+      })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  // POST /switch — Close this workspace and open another in its place
+  app.post('/switch', async (c) => {
+    let name: string | undefined
+    try {
+      name = (await c.req.json<{ name?: string }>()).name
+    } catch {
+      return c.json({ error: 'Invalid JSON body' }, 400)
+    }
+    if (!name) {
+      return c.json({ error: 'name is required' }, 400)
+    }
+    if (process.env.WORKSPACE) {
+      return c.json({ error: 'WORKSPACE is pinned by the environment; unset it to switch at runtime' }, 409)
+    }
+    try {
+      assertWorkspaceName(name)
+    } catch (err) {
+      return c.json({ error: err instanceof Error ? err.message : String(err) }, 400)
+    }
+    if (name !== DEFAULT_WORKSPACE && runtime.config.dbDialect !== 'sqlite') {
+      return c.json({ error: 'Named workspaces require DB_DIALECT=sqlite' }, 400)
+    }
+
+    const previous = runtime.config.workspace
+    try {
+      const next = await switchWorkspace(name)
+      return c.json({ active: next.config.workspace, previous })
+    } catch (err) {
+      const message = err instanceof Error ? err.message : String(err)
+      return c.json({ error: message }, 500)
+    }
+  })
+
+  return app
+}
@@ -0,0 +1,40 @@
+import 'dotenv/config'
+import { loadConfig } from '../lib/config.js'
+import { encryptDatabase } from '../lib/database-key.js'
+import { systemKeychain } from '../lib/keychain.js'
+
+/**
+ * Encrypt an existing plaintext sqlite database with DATABASE_KEY, or with a
+ * generated key kept in the OS keychain when DATABASE_KEY_SOURCE=keychain.
+ * The server must be stopped, since the file is replaced by an encrypted copy.
+ * To rotate the key of an already-encrypted file, also set OLD_DATABASE_KEY
+ * (in keychain mode the saved key is the old one).
+ */
+async function main() {
+  const config = loadConfig()
+  if (config.dbDialect !== 'sqlite') {
+    console.error('encrypt-db only applies to DB_DIALECT=sqlite.')
+    process.exit(1)
+  }
+  if (!config.databaseKey && config.databaseKeySource !== 'keychain') {
+    console.error('Set DATABASE_KEY to the passphrase the database should be encrypted with, or DATABASE_KEY_SOURCE=keychain.')
+    process.exit(1)
+  }
+
+  const { rotated } = await encryptDatabase(config, systemKeychain(), process.env.OLD_DATABASE_KEY || undefined)
+
+  console.log()
+  console.log(rotated ? 'Database key rotated.' : 'Database encrypted.')
+  console.log(`  Path: ${config.databaseUrl}`)
+  console.log()
+  if (config.databaseKeySource === 'keychain') {
+    console.log('The key is saved in the OS keychain. Losing that entry makes the database unreadable.')
+  } else {
+    console.log('Keep DATABASE_KEY set for every server start. Losing it makes the database unreadable.')
+  }
+}
+
+main().catch((err) => {
+  console.error('Failed:', err instanceof Error ? err.message : err)
+  process.exit(1)
+})
@@ -0,0 +1,64 @@
+import 'dotenv/config'
+import { writeFile } from 'node:fs/promises'
+import { loadConfig } from '../lib/config.js'
+import { SecretRedactor } from '../lib/secrets.js'
+import { openDatabase } from '../repositories/factory.js'
+import { buildRunReport, isRunReportFormat, renderRunReport, RUN_REPORT_FORMATS, type RunReportFormat } from '../services/run-report.js'
+
+const USAGE = `Usage: export-report <sessionId> [--format ${RUN_REPORT_FORMATS.join('|')}] [--out <file>]`
+
+async function main() {
+  const args = process.argv.slice(2)
+  let sessionId: string | undefined
+  let format: RunReportFormat = 'markdown'
+  let out: string | undefined
+  for (let i = 0; i < args.length; i++) {
+    const arg = args[i]
+    if (arg === '--format') {
+      const value = args[++i] ?? ''
+      if (!isRunReportFormat(value)) {
+        console.error(USAGE)
+        process.exit(1)
+      }
+      format = value
+    } else if (arg === '--out') {
+      out = args[++i]
+    } else if (!sessionId) {
+      sessionId = arg
+    }
+  }
+  if (!sessionId) {
+    console.error(USAGE)
+    process.exit(1)
+  }
+
+  const config = loadConfig()
+  const opened = await openDatabase(config)
+
+  try {
+    const session = await opened.repositories.sessions.getById(sessionId)
+    const report = session ? await buildRunReport(opened, session.userId, sessionId, format) : null
+    if (!report) {
+      console.error(`Session not found: ${sessionId}`)
+      process.exit(1)
+    }
+    const secrets = new SecretRedactor()
+    for (const [name, value] of Object.entries(config)) {
+      if (typeof value === 'string' && /key|token|secret|password/i.test(name)) secrets.register(value)
+    }
+    const rendered = renderRunReport(report, format, (text) => secrets.redact(text))
+    if (out) {
+      await writeFile(out, rendered)
+      console.log(`Wrote ${out}`)
+    } else {
+      process.stdout.write(rendered)
+    }
+  } finally {
+    await opened.close()
+  }
+}
+
+main().catch((err) => {
+  console.error('Failed:', err instanceof Error ? err.message : err)
+  process.exit(1)
+})
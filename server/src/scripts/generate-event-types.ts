@@ -0,0 +1,49 @@
+import fs from 'fs/promises'
+import path from 'path'
+import { fileURLToPath } from 'url'
+
+/**
+ * Copy events/payloads.ts into the frontend so both sides compile against the
+ * same event payloads. `--check` only reports whether the copy is stale.
+ */
+
+const serverRoot = path.resolve(path.dirname(fileURLToPath(import.meta.url)), '../..')
+const SOURCE = path.join(serverRoot, 'src/events/payloads.ts')
+const TARGET = path.join(serverRoot, '../src/lib/types/server-events.ts')
+const BANNER = '// Generated from server/src/events/payloads.ts by `bun run gen:event-types`. Do not edit.\n\n'
+
+export async function renderEventTypes(): Promise<string> {
+  const source = await fs.readFile(SOURCE, 'utf-8')
+  if (/^import\s/m.test(source)) {
+    throw new Error('events/payloads.ts must not import anything; the frontend copy has to stand alone')
+  }
+  return BANNER + source
+}
+
+async function main() {
+  const rendered = await renderEventTypes()
+  const current = await fs.readFile(TARGET, 'utf-8').catch(() => null)
+
+  if (process.argv.includes('--check')) {
+    if (current !== rendered) {
+      console.error(`${path.relative(process.cwd(), TARGET)} is out of date. Run \`bun run gen:event-types\`.`)
+      process.exit(1)
+    }
+    console.log('Event types are up to date.')
+    return
+  }
+
+  if (current === rendered) {
+    console.log('Event types are up to date.')
+    return
+  }
+  await fs.writeFile(TARGET, rendered, 'utf-8')
+  console.log(`Wrote ${path.relative(process.cwd(), TARGET)}`)
+}
+
+if (process.argv[1] && path.resolve(process.argv[1]) === fileURLToPath(import.meta.url)) {
+  main().catch((err) => {
+    console.error('Failed to generate event types:', err)
+    process.exit(1)
+  })
+}
@@ -0,0 +1,34 @@
+import 'dotenv/config'
+import { loadConfig } from '../lib/config.js'
+import { CONTROLLER_PROMPT_NAMES, isControllerPromptName } from '../orchestrator/prompts.js'
+import { openDatabase } from '../repositories/factory.js'
+import { ControllerPromptStore } from '../services/controller-prompts.js'
+
+async function main() {
+  const names = process.argv.slice(2)
+  const unknown = names.filter((name) => !isControllerPromptName(name))
+  if (unknown.length > 0) {
+    console.error(`Unknown prompt: ${unknown.join(', ')}. Choose from ${CONTROLLER_PROMPT_NAMES.join(', ')}.`)
+    process.exit(1)
+  }
+
+  const config = loadConfig()
+  const opened = await openDatabase(config)
+
+  try {
+    const store = new ControllerPromptStore(opened.repositories.controllerPrompts)
+    await store.seed()
+    // No names resets every prompt
+    for (const name of names.length > 0 ? names.filter(isControllerPromptName) : CONTROLLER_PROMPT_NAMES) {
+      const state = await store.reset(name)
+      console.log(`${name}: default text at version ${state.version}`)
+    }
+  } finally {
+    await opened.close()
+  }
+}
+
+main().catch((err) => {
+  console.error('Failed:', err instanceof Error ? err.message : err)
+  process.exit(1)
+})
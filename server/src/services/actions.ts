@@ -0,0 +1,103 @@
+/** A JSON Schema property an action argument is checked against; the palette builds its prompt from it. */
+export interface ActionParameter {
+  type: 'string' | 'number' | 'integer' | 'boolean' | 'object' | 'array'
+  description?: string
+  enum?: string[]
+}
+
+export interface ActionParameters {
+  type: 'object'
+  properties: Record<string, ActionParameter>
+  required?: string[]
+}
+
+/** What clients list: enough to show the action in a palette and bind it to a hotkey. */
+export interface ActionDefinition {
+  /** Namespaced by area like tools, e.g. `conversation.new`. */
+  id: string
+  label: string
+  description?: string
+  /** Suggested default hotkey, e.g. `Mod+Shift+N`; clients let the user rebind it. */
+  shortcut?: string
+  parameters: ActionParameters
+}
+
+export interface ActionContext {
+  userId: string
+  signal: AbortSignal
+}
+
+export interface ActionHandler extends Omit<ActionDefinition, 'parameters'> {
+  /** A function when choices change while the server runs, such as the agent or workflow names. */
+  parameters: ActionParameters | (() => ActionParameters)
+  handle(args: Record<string, unknown>, ctx: ActionContext): Promise<unknown>
+}
+
+/** Bad arguments or a missing target; the message is suitable for a 400. */
+export class ActionError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'ActionError'
+  }
+}
+
+/**
+ * Operations clients offer outside a conversation turn — in the command
+ * palette, behind global hotkeys — registered once on the server so every
+ * client lists and runs the same set.
+ */
+export class ActionRegistry {
+  private handlers = new Map<string, ActionHandler>()
+
+  register(handler: ActionHandler): void {
+    if (this.handlers.has(handler.id)) throw new Error(`Action already registered: ${handler.id}`)
+    this.handlers.set(handler.id, handler)
+  }
+
+  unregister(id: string): boolean {
+    return this.handlers.delete(id)
+  }
+
+  has(id: string): boolean {
+    return this.handlers.has(id)
+  }
+
+  list(): ActionDefinition[] {
+    return [...this.handlers.values()].map(({ handle: _handle, parameters, ...definition }) => ({
+      ...definition,
+      parameters: typeof parameters === 'function' ? parameters() : parameters,
+    }))
+  }
+
+  /** Throws ActionError for an unknown action or arguments that do not fit its schema. */
+  async execute(id: string, args: unknown, ctx: ActionContext): Promise<unknown> {
+    const handler = this.handlers.get(id)
+    if (!handler) throw new ActionError(`Unknown action: ${id}`)
+    const input = args ?? {}
+    if (typeof input !== 'object' || Array.isArray(input)) throw new ActionError('args must be an object')
+    const parameters = typeof handler.parameters === 'function' ? handler.parameters() : handler.parameters
+    const problems = validateActionArgs(parameters, input as Record<string, unknown>)
+    if (problems.length) throw new ActionError(`Invalid arguments for ${id}: ${problems.join('; ')}`)
+    return handler.handle(input as Record<string, unknown>, ctx)
+  }
+}
+
+export function validateActionArgs(parameters: ActionParameters, args: Record<string, unknown>): string[] {
+  const problems: string[] = []
+  for (const name of parameters.required ?? []) {
+    if (args[name] === undefined || args[name] === null || args[name] === '') problems.push(`${name} is required`)
+  }
+  for (const [name, value] of Object.entries(args)) {
+    const parameter = parameters.properties[name]
+    if (!parameter) {
+      problems.push(`${name} is not an argument of this action`)
+      continue
+    }
+    if (value === undefined || value === null) continue
+    const actual = Array.isArray(value) ? 'array' : typeof value
+    const fits = parameter.type === 'integer' ? Number.isInteger(value) : actual === parameter.type
+    if (!fits) problems.push(`${name} must be ${/^[aeiou]/.test(parameter.type) ? 'an' : 'a'} ${parameter.type}`)
+    else if (parameter.enum && !parameter.enum.includes(value as string)) problems.push(`${name} must be one of: ${parameter.enum.join(', ')}`)
+  }
+  return problems
+}
@@ -0,0 +1,154 @@
+import fs from 'fs/promises'
+import path from 'path'
+import { getSessionFilesDir } from '../tools/path-policy.js'
+
+export const MAX_ARTIFACT_BYTES = 10 * 1024 * 1024
+
+/** Session artifacts subdirectory for files the agent writes on purpose, next to spilled tool outputs. */
+const FILES_DIR = 'files'
+/** Per-file metadata, kept beside the files so listings stay plain directory reads. */
+const METADATA_DIR = '.meta'
+
+const MEDIA_TYPES: Record<string, string> = {
+  '.csv': 'text/csv',
+  '.html': 'text/html',
+  '.ics': 'text/calendar',
+  '.json': 'application/json',
+  '.md': 'text/markdown',
+  '.pdf': 'application/pdf',
+  '.png': 'image/png',
+  '.svg': 'image/svg+xml',
+  '.tsv': 'text/tab-separated-values',
+  '.txt': 'text/plain',
+  '.vcf': 'text/vcard',
+  '.xml': 'application/xml',
+  '.zip': 'application/zip',
+}
+
+export class ArtifactInputError extends Error {}
+
+export interface AgentArtifact {
+  /** Managed path, readable with files.read. */
+  ref: string
+  name: string
+  mediaType: string
+  bytes: number
+  createdAt: number
+  /** How the file was made, e.g. the prompt behind a generated image. */
+  metadata?: Record<string, unknown>
+}
+
+export interface WriteArtifactInput {
+  name: string
+  content: string
+  /** `base64` for binary files; text is written as UTF-8. */
+  encoding?: 'utf-8' | 'base64'
+  metadata?: Record<string, unknown>
+}
+
+/**
+ * Saves a file the agent produced for the user to download. Names are
+ * reduced to a single safe file name; an existing file of the same name is
+ * kept and the new one gets a numbered name instead.
+ */
+export async function writeAgentArtifact(
+  sessionFilesRoot: string,
+  sessionId: string,
+  input: WriteArtifactInput,
+): Promise<AgentArtifact> {
+  const name = safeFileName(input.name)
+  if (input.encoding && input.encoding !== 'utf-8' && input.encoding !== 'base64') {
+    throw new ArtifactInputError(`Unsupported encoding: ${input.encoding}`)
+  }
+  const data = input.encoding === 'base64' ? Buffer.from(input.content, 'base64') : Buffer.from(input.content, 'utf-8')
+  if (data.length === 0) throw new ArtifactInputError('Artifact content is empty')
+  if (data.length > MAX_ARTIFACT_BYTES) throw new ArtifactInputError(`Artifact is too large (${data.length} bytes)`)
+
+  const dir = filesDir(sessionFilesRoot, sessionId)
+  await fs.mkdir(dir, { recursive: true })
+  const { name: base, ext } = path.parse(name)
+  for (let attempt = 1; ; attempt++) {
+    const candidate = attempt === 1 ? name : `${base} (${attempt})${ext}`
+    try {
+      await fs.writeFile(path.join(dir, candidate), data, { flag: 'wx' })
+      if (input.metadata) {
+        await fs.mkdir(path.join(dir, METADATA_DIR), { recursive: true })
+        await fs.writeFile(metadataPath(dir, candidate), JSON.stringify(input.metadata, null, 2))
+      }
+      return { ...describe(candidate, data.length, Date.now()), ...(input.metadata && { metadata: input.metadata }) }
+    } catch (err) {
+      if ((err as NodeJS.ErrnoException).code !== 'EEXIST' || attempt >= 100) throw err
+    }
+  }
+}
+
+/** Files the agent has written in a session, newest first. */
+export async function listAgentArtifacts(sessionFilesRoot: string, sessionId: string): Promise<AgentArtifact[]> {
+  const dir = filesDir(sessionFilesRoot, sessionId)
+  let names: string[]
+  try {
+    names = await fs.readdir(dir)
+  } catch {
+    return []
+  }
+  const artifacts: AgentArtifact[] = []
+  for (const name of names) {
+    const stat = await fs.stat(path.join(dir, name)).catch(() => null)
+    if (!stat?.isFile()) continue
+    const metadata = await readMetadata(dir, name)
+    artifacts.push({ ...describe(name, stat.size, stat.mtimeMs), ...(metadata && { metadata }) })
+  }
+  return artifacts.sort((a, b) => b.createdAt - a.createdAt)
+}
+
+/** The file's contents, or null when the session has no artifact of that name. */
+export async function readAgentArtifact(
+  sessionFilesRoot: string,
+  sessionId: string,
+  name: string,
+): Promise<{ artifact: AgentArtifact; data: Buffer } | null> {
+  if (name !== path.basename(name) || name === '.' || name === '..') return null
+  const file = path.join(filesDir(sessionFilesRoot, sessionId), name)
+  try {
+    const [data, stat] = await Promise.all([fs.readFile(file), fs.stat(file)])
+    return { artifact: describe(name, data.length, stat.mtimeMs), data }
+  } catch {
+    return null
+  }
+}
+
+export function artifactMediaType(name: string): string {
+  return MEDIA_TYPES[path.extname(name).toLowerCase()] ?? 'application/octet-stream'
+}
+
+function safeFileName(name: string): string {
+  const cleaned = path.posix.basename(String(name ?? '').replace(/\\/g, '/'))
+    // `%` would be read back as percent-encoding in artifact:// paths
+    .replace(/[\u0000-\u001f<>:"|?*%]+/g, '-')
+    .trim()
+    // No dotfiles, which also keeps names clear of the metadata directory
+    .replace(/^\.+/, '')
+    .slice(-120)
+  if (!cleaned) throw new ArtifactInputError('A file name is required')
+  return cleaned
+}
+
+async function readMetadata(dir: string, name: string): Promise<Record<string, unknown> | null> {
+  try {
+    return JSON.parse(await fs.readFile(metadataPath(dir, name), 'utf-8')) as Record<string, unknown>
+  } catch {
+    return null
+  }
+}
+
+function metadataPath(dir: string, name: string): string {
+  return path.join(dir, METADATA_DIR, `${name}.json`)
+}
+
+function filesDir(sessionFilesRoot: string, sessionId: string): string {
+  return path.join(getSessionFilesDir(sessionFilesRoot, sessionId), 'artifacts', FILES_DIR)
+}
+
+function describe(name: string, bytes: number, createdAt: number): AgentArtifact {
+  return { ref: `artifact://${FILES_DIR}/${name}`, name, mediaType: artifactMediaType(name), bytes, createdAt }
+}
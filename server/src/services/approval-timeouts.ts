@@ -0,0 +1,135 @@
+import { UNPACED } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+import { expireApproval } from '../orchestrator/delivery.js'
+import type { PreferenceRepository } from '../repositories/types.js'
+import { buildDeps } from './session-runner.js'
+
+/** Per-conversation override, keyed `approval_timeout_ms:<sessionId>`; '0' waits indefinitely. */
+export const APPROVAL_TIMEOUT_PREFIX = 'approval_timeout_ms:'
+
+/** Shorter windows would expire before anyone could reasonably react. */
+export const MIN_APPROVAL_TIMEOUT_MS = 5_000
+
+const REMINDER_FRACTIONS = [0.5, 0.9]
+
+export interface ApprovalTimeoutSetting {
+  timeoutMs: number
+  source: 'conversation' | 'default'
+}
+
+export async function resolveApprovalTimeout(
+  preferences: PreferenceRepository,
+  sessionId: string,
+  defaultTimeoutMs: number,
+): Promise<ApprovalTimeoutSetting> {
+  const raw = await preferences.get(`${APPROVAL_TIMEOUT_PREFIX}${sessionId}`)
+  const value = raw === null ? NaN : Number(raw)
+  if (Number.isFinite(value) && value >= 0) return { timeoutMs: value, source: 'conversation' }
+  return { timeoutMs: Math.max(0, defaultTimeoutMs), source: 'default' }
+}
+
+/** Validate user input; throws with a message suitable for a 400. */
+export function normalizeApprovalTimeout(value: unknown): number {
+  if (typeof value !== 'number' || !Number.isInteger(value) || value < 0) {
+    throw new Error('timeoutMs must be a non-negative integer (0 waits indefinitely)')
+  }
+  if (value > 0 && value < MIN_APPROVAL_TIMEOUT_MS) {
+    throw new Error(`timeoutMs must be 0 or at least ${MIN_APPROVAL_TIMEOUT_MS}`)
+  }
+  return value
+}
+
+/**
+ * Arms a timer for every proposed tool call that needs approval. Reminders go
+ * out at 50% and 90% of the window; at the end the call expires and the agent
+ * resumes with an `approval_expired` error. Timers live in memory, so calls
+ * already pending when the server starts wait until someone answers them.
+ */
+export class ApprovalTimeouts {
+  private readonly timers = new Map<string, NodeJS.Timeout[]>()
+  private iterator: AsyncIterator<AgentEvent> | null = null
+
+  constructor(private readonly runtime: RuntimeContext) {}
+
+  start(): void {
+    if (this.iterator) return
+    const iterator = this.runtime.events.subscribe({
+      types: [EVENT_TYPES.TOOL_PROPOSED, EVENT_TYPES.TOOL_APPROVED, EVENT_TYPES.TOOL_DENIED],
+    }, UNPACED)[Symbol.asyncIterator]()
+    this.iterator = iterator
+    void (async () => {
+      for (let next = await iterator.next(); !next.done; next = await iterator.next()) {
+        const event = next.value
+        if (event.type === EVENT_TYPES.TOOL_PROPOSED) {
+          await this.arm(event)
+        } else if (event.type === EVENT_TYPES.TOOL_APPROVED || event.type === EVENT_TYPES.TOOL_DENIED) {
+          this.clear(event.payload.callId)
+        }
+      }
+    })()
+  }
+
+  stop(): void {
+    void this.iterator?.return?.()
+    this.iterator = null
+    for (const callId of [...this.timers.keys()]) this.clear(callId)
+  }
+
+  /** Calls with a running timer. */
+  get pending(): number {
+    return this.timers.size
+  }
+
+  private async arm(event: Extract<AgentEvent, { type: typeof EVENT_TYPES.TOOL_PROPOSED }>): Promise<void> {
+    const { callId, name, parentId, depth } = event.payload
+    let timeoutMs: number
+    try {
+      ({ timeoutMs } = await resolveApprovalTimeout(
+        this.runtime.repositories.preferences,
+        event.session_id,
+        this.runtime.config.approvalTimeoutMs,
+      ))
+    } catch (err) {
+      logger.warn({ err, callId }, 'Could not resolve approval timeout')
+      return
+    }
+    if (timeoutMs <= 0) return
+    this.clear(callId)
+
+    const timers = REMINDER_FRACTIONS.map((fraction) => {
+      const elapsedMs = Math.round(timeoutMs * fraction)
+      return setTimeout(() => {
+        this.runtime.events.emit({
+          type: EVENT_TYPES.TOOL_APPROVAL_REMINDER,
+          agent_id: event.agent_id,
+          session_id: event.session_id,
+          payload: { callId, name, elapsedMs, remainingMs: timeoutMs - elapsedMs, timeoutMs, parentId, depth },
+          timestamp: Date.now(),
+        })
+      }, elapsedMs)
+    })
+    timers.push(setTimeout(() => {
+      this.clear(callId)
+      void this.expire(event.agent_id, callId, timeoutMs)
+    }, timeoutMs))
+    for (const timer of timers) timer.unref()
+    this.timers.set(callId, timers)
+  }
+
+  private async expire(agentId: string, callId: string, timeoutMs: number): Promise<void> {
+    try {
+      const agent = await this.runtime.repositories.agents.getById(agentId)
+      if (!agent) return
+      await expireApproval(agentId, callId, timeoutMs, buildDeps(this.runtime, agent.config.model))
+    } catch (err) {
+      logger.warn({ err, agentId, callId }, 'Failed to expire approval')
+    }
+  }
+
+  private clear(callId: string): void {
+    for (const timer of this.timers.get(callId) ?? []) clearTimeout(timer)
+    this.timers.delete(callId)
+  }
+}
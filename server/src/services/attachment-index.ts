@@ -0,0 +1,228 @@
+import type { ItemContentBlock } from '../domain/types.js'
+import { logger } from '../lib/logger.js'
+import { splitModelId } from '../lib/model.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { ItemRepository } from '../repositories/types.js'
+
+/** Chunks per embedding request, to stay under provider input limits. */
+const EMBEDDING_BATCH_SIZE = 64
+
+const DEFAULT_SEARCH_LIMIT = 5
+const MAX_SEARCH_LIMIT = 20
+
+interface StoredEmbeddings {
+  model: string
+  vectors: number[][]
+}
+
+export interface SearchSource {
+  name: string
+  hash: string
+  chunks: string[]
+  vectors?: number[][]
+}
+
+export interface AttachmentSearchResult {
+  attachment: string
+  hash: string
+  /** Index of the chunk within the attachment's extracted text. */
+  chunk: number
+  score: number
+  text: string
+}
+
+export type AttachmentIndexRuntime = Pick<RuntimeContext, 'attachments' | 'config' | 'providers'>
+
+export type AttachmentSearchDeps = AttachmentIndexRuntime & { items: ItemRepository }
+
+/**
+ * Keeps attachments longer than ATTACHMENT_INLINE_MAX_CHARS out of the
+ * message: their chunks are embedded for attachments.search and the block is
+ * marked for retrieval instead of carrying the text.
+ */
+export async function prepareLargeAttachments(
+  runtime: AttachmentIndexRuntime,
+  blocks: ItemContentBlock[] | null,
+  signal?: AbortSignal,
+): Promise<ItemContentBlock[] | null> {
+  if (!blocks) return null
+  const prepared: ItemContentBlock[] = []
+  for (const block of blocks) {
+    const size = block.chunks?.reduce((total, chunk) => total + chunk.length, 0) ?? 0
+    if (!block.hash || size <= runtime.config.attachmentInlineMaxChars) {
+      prepared.push(block)
+      continue
+    }
+    await indexAttachment(runtime, block.hash, block.chunks!, signal)
+    const { chunks: _chunks, ...rest } = block
+    prepared.push({ ...rest, retrieval: true })
+  }
+  return prepared
+}
+
+/**
+ * Embeds an attachment's chunks with ATTACHMENT_EMBEDDING_MODEL. Without a
+ * model, or when embedding fails, search falls back to keyword ranking.
+ */
+export async function indexAttachment(
+  runtime: AttachmentIndexRuntime,
+  hash: string,
+  chunks: string[],
+  signal?: AbortSignal,
+): Promise<void> {
+  const modelId = runtime.config.attachmentEmbeddingModel?.trim()
+  if (!modelId) return
+  const stored = await runtime.attachments.getEmbeddings<StoredEmbeddings>(hash)
+  if (stored?.model === modelId && stored.vectors.length === chunks.length) return
+
+  try {
+    const vectors = await embed(runtime, modelId, chunks, signal)
+    if (vectors) await runtime.attachments.putEmbeddings(hash, { model: modelId, vectors } satisfies StoredEmbeddings)
+  } catch (err) {
+    logger.warn({ hash, model: modelId, err: err instanceof Error ? err.message : String(err) }, 'Attachment embedding failed')
+  }
+}
+
+/** True when ATTACHMENT_EMBEDDING_MODEL is set and the attachment has no vectors from it yet. */
+export async function needsEmbedding(runtime: AttachmentIndexRuntime, hash: string, chunks: number): Promise<boolean> {
+  const modelId = runtime.config.attachmentEmbeddingModel?.trim()
+  if (!modelId || chunks === 0) return false
+  const stored = await runtime.attachments.getEmbeddings<StoredEmbeddings>(hash)
+  return stored?.model !== modelId || stored.vectors.length !== chunks
+}
+
+/** Ranks the chunks of every attachment sent in a session against a query. */
+export async function searchAttachments(
+  deps: AttachmentSearchDeps,
+  sessionId: string,
+  query: string,
+  options: { attachment?: string; limit?: number; signal?: AbortSignal } = {},
+): Promise<AttachmentSearchResult[]> {
+  let sources = await sessionAttachments(deps, sessionId)
+  if (options.attachment) {
+    sources = sources.filter((source) => source.name === options.attachment || source.hash === options.attachment)
+    if (sources.length === 0) throw new Error(`No attachment named ${options.attachment} in this session`)
+  }
+  if (sources.length === 0) throw new Error('No searchable attachments in this session')
+  return rankChunks(deps, sources, query, options)
+}
+
+/**
+ * The chunks most relevant to a query: by embedding similarity when every
+ * source is embedded with the configured model, by keyword otherwise.
+ */
+export async function rankChunks(
+  runtime: AttachmentIndexRuntime,
+  sources: SearchSource[],
+  query: string,
+  options: { limit?: number; signal?: AbortSignal } = {},
+): Promise<AttachmentSearchResult[]> {
+  const limit = Math.min(Math.max(1, Math.floor(options.limit ?? DEFAULT_SEARCH_LIMIT)), MAX_SEARCH_LIMIT)
+  const results = (await semanticScores(runtime, sources, query, options.signal)) ?? keywordScores(sources, query)
+  return results
+    .filter((result) => result.score > 0)
+    .sort((a, b) => b.score - a.score)
+    .slice(0, limit)
+}
+
+async function sessionAttachments(deps: AttachmentSearchDeps, sessionId: string): Promise<SearchSource[]> {
+  const sources = new Map<string, SearchSource>()
+  for (const item of await deps.items.listBySession(sessionId)) {
+    if (item.type !== 'message' || item.role !== 'user') continue
+    for (const block of item.contentBlocks ?? []) {
+      if (!block.hash || sources.has(block.hash) || !(block.retrieval || block.chunks?.length)) continue
+      const extraction = await deps.attachments.getExtraction<{ chunks?: string[] }>(block.hash)
+      if (!extraction?.chunks?.length) continue
+      sources.set(block.hash, { name: block.name ?? block.hash.slice(0, 12), hash: block.hash, chunks: extraction.chunks })
+    }
+  }
+  return [...sources.values()]
+}
+
+/** Cosine similarity when every attachment has embeddings from the configured model; null otherwise. */
+async function semanticScores(
+  deps: AttachmentIndexRuntime,
+  sources: SearchSource[],
+  query: string,
+  signal?: AbortSignal,
+): Promise<AttachmentSearchResult[] | null> {
+  const modelId = deps.config.attachmentEmbeddingModel?.trim()
+  if (!modelId) return null
+  for (const source of sources) {
+    const stored = await deps.attachments.getEmbeddings<StoredEmbeddings>(source.hash)
+    if (stored?.model !== modelId || stored.vectors.length !== source.chunks.length) return null
+    source.vectors = stored.vectors
+  }
+
+  let queryVector: number[] | undefined
+  try {
+    queryVector = (await embed(deps, modelId, [query], signal))?.[0]
+  } catch (err) {
+    logger.warn({ model: modelId, err: err instanceof Error ? err.message : String(err) }, 'Query embedding failed')
+  }
+  if (!queryVector) return null
+
+  return sources.flatMap((source) => source.chunks.map((text, chunk) => ({
+    attachment: source.name,
+    hash: source.hash,
+    chunk,
+    score: cosine(queryVector, source.vectors![chunk]),
+    text,
+  })))
+}
+
+/** TF-IDF over the query's terms, with chunks as documents. */
+function keywordScores(sources: SearchSource[], query: string): AttachmentSearchResult[] {
+  const terms = [...new Set(tokenize(query))]
+  const chunks = sources.flatMap((source) => source.chunks.map((text, chunk) => ({ source, chunk, text, tokens: tokenize(text) })))
+  const idf = new Map(terms.map((term) => {
+    const df = chunks.filter((entry) => entry.tokens.includes(term)).length
+    return [term, Math.log(1 + chunks.length / (df || 1))]
+  }))
+
+  return chunks.map(({ source, chunk, text, tokens }) => {
+    let score = 0
+    for (const term of terms) {
+      const tf = tokens.filter((token) => token === term).length
+      if (tf > 0) score += (1 + Math.log(tf)) * idf.get(term)!
+    }
+    return { attachment: source.name, hash: source.hash, chunk, score, text }
+  })
+}
+
+/** Embeds texts in batches; null when the provider has no embedding support. */
+export async function embed(
+  runtime: Pick<RuntimeContext, 'providers'>,
+  modelId: string,
+  input: string[],
+  signal?: AbortSignal,
+): Promise<number[][] | null> {
+  const provider = runtime.providers.resolve(modelId)
+  if (!provider.embed) {
+    logger.warn({ model: modelId }, 'Provider does not support embeddings')
+    return null
+  }
+  const { model } = splitModelId(modelId)
+  const vectors: number[][] = []
+  for (let start = 0; start < input.length; start += EMBEDDING_BATCH_SIZE) {
+    const response = await provider.embed({ model, input: input.slice(start, start + EMBEDDING_BATCH_SIZE), signal })
+    vectors.push(...response.embeddings)
+  }
+  return vectors
+}
+
+export function tokenize(text: string): string[] {
+  return text.toLowerCase().match(/[\p{L}\p{N}]+/gu) ?? []
+}
+
+export function cosine(a: number[], b: number[]): number {
+  let dot = 0
+  let normA = 0
+  let normB = 0
+  for (let i = 0; i < a.length; i++) {
+    dot += a[i] * b[i]
+    normA += a[i] * a[i]
+    normB += b[i] * b[i]
+  }
+  return normA && normB ? dot / Math.sqrt(normA * normB) : 0
+}
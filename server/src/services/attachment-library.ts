@@ -0,0 +1,66 @@
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+import type { RecordUploadInput, UploadRecord } from '../repositories/types.js'
+import type { ImageExtraction } from './image-ocr.js'
+import type { PdfExtraction } from './pdf-extraction.js'
+import type { VideoExtraction } from './video-extraction.js'
+
+export interface LibraryEntry extends Omit<UploadRecord, 'userId'> {
+  /** Messages in the user's conversations that include the file. */
+  references: number
+}
+
+export interface LibraryAttachment {
+  upload: Omit<UploadRecord, 'userId'>
+  /** The same payload the upload route returned, so clients build the attachment without re-uploading. */
+  extraction: PdfExtraction | ImageExtraction | VideoExtraction
+}
+
+type LibraryRuntime = Pick<RuntimeContext, 'attachments' | 'repositories'>
+
+/** Adds an uploaded file to the user's library; failures never fail the upload itself. */
+export async function recordUpload(runtime: LibraryRuntime, input: RecordUploadInput): Promise<void> {
+  try {
+    await runtime.repositories.uploads.record(input)
+  } catch (err) {
+    logger.warn({ hash: input.hash, err: err instanceof Error ? err.message : String(err) }, 'Failed to record upload')
+  }
+}
+
+/** Every file the user has uploaded, one entry per distinct payload. */
+export async function listLibrary(runtime: LibraryRuntime, userId: string): Promise<LibraryEntry[]> {
+  const uploads = await runtime.repositories.uploads.listByUser(userId)
+  return Promise.all(uploads.map(async ({ userId: _userId, ...upload }) => ({
+    ...upload,
+    references: await runtime.repositories.uploads.countMessageReferences(userId, upload.hash),
+  })))
+}
+
+/** Looks up a library file for a new message and marks it as used; null when it is gone. */
+export async function attachFromLibrary(runtime: LibraryRuntime, userId: string, hash: string): Promise<LibraryAttachment | null> {
+  const upload = await runtime.repositories.uploads.get(userId, hash)
+  if (!upload) return null
+  const extraction = await runtime.attachments.getExtraction<PdfExtraction | ImageExtraction | VideoExtraction>(hash)
+  if (!extraction) return null
+  await runtime.repositories.uploads.touch(userId, hash)
+  const { userId: _userId, ...rest } = upload
+  return { upload: { ...rest, lastUsedAt: Date.now() }, extraction }
+}
+
+/**
+ * Drops a file from the user's library. The payload is deleted right away
+ * once no library entry, message or revision of any user references it;
+ * otherwise it stays until the last reference goes and orphan cleanup runs.
+ */
+export async function removeFromLibrary(
+  runtime: LibraryRuntime,
+  userId: string,
+  hash: string,
+): Promise<{ removed: boolean; purged: boolean }> {
+  const removed = await runtime.repositories.uploads.delete(userId, hash)
+  if (!removed) return { removed, purged: false }
+  const references = await runtime.repositories.uploads.countAllReferences(hash)
+  if (references > 0) return { removed, purged: false }
+  await runtime.attachments.remove(hash)
+  return { removed, purged: true }
+}
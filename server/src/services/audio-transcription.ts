@@ -1,7 +1,9 @@
+import { MAX_ATTACHMENT_BYTES } from '../lib/attachment-validation.js'
 import type { RuntimeContext } from '../lib/runtime.js'
 import { splitModelId } from '../lib/model.js'
+import { transcribeWithWhisperCpp, WHISPER_CPP_PROVIDER } from './whisper-cpp.js'
 
-export const MAX_AUDIO_BYTES = 20 * 1024 * 1024
+export const MAX_AUDIO_BYTES = MAX_ATTACHMENT_BYTES.audio
 
 const MIME_FORMATS: Record<string, string> = {
   'audio/aac': 'aac',
@@ -39,6 +41,8 @@ export interface AudioTranscriptionInput {
   mimeType?: string
   fileName?: string
   signal?: AbortSignal
+  /** Called with 0–100 while a local whisper.cpp transcription runs. */
+  onProgress?: (percent: number) => void
 }
 
 export function inferAudioFormat(mimeType?: string, fileName?: string): string {
@@ -61,7 +65,7 @@ export function isSupportedAudioFile(mimeType?: string, fileName?: string): bool
 }
 
 export async function transcribeAudio(
-  runtime: RuntimeContext,
+  runtime: Pick<RuntimeContext, 'config' | 'providers' | 'shutdownController'>,
   input: AudioTranscriptionInput,
 ): Promise<{ text: string; usage?: unknown }> {
   const size = input.bytes.byteLength
@@ -76,6 +80,23 @@ export async function transcribeAudio(
   }
 
   const { provider, model } = splitModelId(transcriptionModel)
+  if (provider === WHISPER_CPP_PROVIDER) {
+    const raw = await transcribeWithWhisperCpp(
+      input.bytes instanceof ArrayBuffer ? new Uint8Array(input.bytes) : input.bytes,
+      format,
+      {
+        bin: runtime.config.whisperCppBin,
+        ffmpegBin: runtime.config.ffmpegBin,
+        modelPath: model,
+        signal: input.signal ?? runtime.shutdownController.signal,
+        onProgress: input.onProgress,
+      },
+    )
+    const text = raw.trim()
+    if (!text) throw new Error('Audio transcription response was empty')
+    return { text }
+  }
+
   const transcriptionProvider = runtime.providers.resolve(transcriptionModel)
   if (!transcriptionProvider.transcribeAudio) {
     throw new Error(`Provider "${provider}" does not support audio transcription`)
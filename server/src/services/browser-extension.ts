@@ -0,0 +1,168 @@
+import { randomUUID } from 'node:crypto'
+
+/** How long an extension gets to answer before the request fails. */
+const REQUEST_TIMEOUT_MS = 10_000
+/** Page text beyond this is cut before it reaches a conversation. */
+export const MAX_PAGE_TEXT_CHARS = 50_000
+
+export interface BrowserPage {
+  url: string
+  title: string
+  /** Text the user selected on the page, if any. */
+  selection?: string
+  /** Readable text of the page, when the extension sent it. */
+  text?: string
+  /** The page text was longer than MAX_PAGE_TEXT_CHARS and was cut. */
+  truncated?: boolean
+}
+
+/** Request the server sends an extension; answered with `browser_result`. */
+export interface BrowserRequest {
+  type: 'request'
+  id: string
+  method: 'get_active_tab'
+  params: Record<string, unknown>
+}
+
+export class BrowserExtensionError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'BrowserExtensionError'
+  }
+}
+
+/** One connected extension, as seen by the transport that carries its messages. */
+export interface BrowserExtensionConnection {
+  /** Hands an extension's answer to the request waiting for it; false when none is. */
+  resolve(requestId: string, answer: { ok: boolean; result?: unknown; error?: string }): boolean
+  disconnect(): void
+}
+
+interface Connection {
+  userId: string
+  send: (request: BrowserRequest) => void
+}
+
+interface PendingRequest {
+  connection: Connection
+  resolve: (value: unknown) => void
+  reject: (err: Error) => void
+  timer: NodeJS.Timeout
+}
+
+/**
+ * Companion browser extensions connected over the WebSocket bridge. An
+ * extension registers once per socket; requests go to the user's most
+ * recently connected one and are matched to answers by id, the way browser
+ * native messaging pairs a request with its response.
+ */
+export class BrowserExtensions {
+  private readonly connections: Connection[] = []
+  private readonly pending = new Map<string, PendingRequest>()
+
+  constructor(private readonly timeoutMs = REQUEST_TIMEOUT_MS) {}
+
+  connect(userId: string, send: (request: BrowserRequest) => void): BrowserExtensionConnection {
+    const connection: Connection = { userId, send }
+    this.connections.push(connection)
+    return {
+      resolve: (requestId, answer) => {
+        const request = this.pending.get(requestId)
+        // Only the extension that was asked may answer
+        if (!request || request.connection !== connection) return false
+        this.settle(requestId)
+        if (answer.ok) request.resolve(answer.result)
+        else request.reject(new BrowserExtensionError(`Browser extension: ${answer.error || 'request failed'}`))
+        return true
+      },
+      disconnect: () => {
+        const index = this.connections.indexOf(connection)
+        if (index !== -1) this.connections.splice(index, 1)
+        for (const [id, request] of this.pending) {
+          if (request.connection !== connection) continue
+          this.settle(id)
+          request.reject(new BrowserExtensionError('The browser extension disconnected before answering'))
+        }
+      },
+    }
+  }
+
+  isConnected(userId: string): boolean {
+    return this.connections.some((connection) => connection.userId === userId)
+  }
+
+  /** The tab the user is looking at; page text only when asked, since it can be long. */
+  async getActiveTab(userId: string, options: { includeText?: boolean } = {}, signal?: AbortSignal): Promise<BrowserPage> {
+    const result = await this.request(userId, 'get_active_tab', { includeText: options.includeText ?? false }, signal)
+    const page = parseBrowserPage(result)
+    if (!options.includeText) {
+      delete page.text
+      delete page.truncated
+    }
+    return page
+  }
+
+  private request(userId: string, method: BrowserRequest['method'], params: Record<string, unknown>, signal?: AbortSignal): Promise<unknown> {
+    const connection = this.connections.filter((candidate) => candidate.userId === userId).at(-1)
+    if (!connection) {
+      return Promise.reject(new BrowserExtensionError('No browser extension is connected; open the browser with the extension signed in'))
+    }
+    const id = randomUUID()
+    return new Promise((resolve, reject) => {
+      const abort = () => {
+        this.settle(id)
+        reject(new BrowserExtensionError('Cancelled'))
+      }
+      const timer = setTimeout(() => {
+        this.settle(id)
+        reject(new BrowserExtensionError(`The browser extension did not answer within ${Math.round(this.timeoutMs / 1000)}s`))
+      }, this.timeoutMs)
+      this.pending.set(id, {
+        connection,
+        resolve: (value) => {
+          signal?.removeEventListener('abort', abort)
+          resolve(value)
+        },
+        reject: (err) => {
+          signal?.removeEventListener('abort', abort)
+          reject(err)
+        },
+        timer,
+      })
+      signal?.addEventListener('abort', abort, { once: true })
+      connection.send({ type: 'request', id, method, params })
+    })
+  }
+
+  private settle(id: string): void {
+    const request = this.pending.get(id)
+    if (!request) return
+    clearTimeout(request.timer)
+    this.pending.delete(id)
+  }
+}
+
+/** Validates a page sent by an extension, cutting overlong page text. */
+export function parseBrowserPage(value: unknown): BrowserPage {
+  const raw = value && typeof value === 'object' ? value as Record<string, unknown> : {}
+  const url = typeof raw.url === 'string' ? raw.url.trim() : ''
+  if (!url) throw new BrowserExtensionError('page.url is required')
+  const title = typeof raw.title === 'string' && raw.title.trim() ? raw.title.trim() : url
+  const page: BrowserPage = { url, title }
+  if (typeof raw.selection === 'string' && raw.selection.trim()) page.selection = raw.selection.trim()
+  if (typeof raw.text === 'string' && raw.text.trim()) {
+    const text = raw.text.trim()
+    page.text = text.slice(0, MAX_PAGE_TEXT_CHARS)
+    if (text.length > MAX_PAGE_TEXT_CHARS) page.truncated = true
+  }
+  return page
+}
+
+/** The user message for a page sent from the browser: the instruction, then the page and what was selected. */
+export function formatBrowserPage(page: BrowserPage, instruction?: string): string {
+  const parts = [instruction?.trim() || (page.selection ? 'Here is a selection from a page I am reading.' : 'Here is a page I am reading.')]
+  parts.push(`Page: ${page.title}\nURL: ${page.url}`)
+  if (page.selection) parts.push(`Selected text:\n${page.selection.split('\n').map((line) => `> ${line}`).join('\n')}`)
+  if (page.text) parts.push(`Page text${page.truncated ? ' (cut short)' : ''}:\n${page.text}`)
+  return parts.join('\n\n')
+}
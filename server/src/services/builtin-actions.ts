@@ -0,0 +1,171 @@
+import type { Session } from '../domain/types.js'
+import { logger } from '../lib/logger.js'
+import { splitModelId } from '../lib/model.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { runAgent } from '../orchestrator/runner.js'
+import { ActionError, type ActionRegistry } from './actions.js'
+import { PLACEHOLDER_TITLE } from './conversation-title.js'
+import { buildRunReport, renderRunReport, reportFileName, RUN_REPORT_FORMATS, type RunReportFormat } from './run-report.js'
+import { buildDeps, prepareSessionTurn } from './session-runner.js'
+
+const MAX_SUMMARY_TRANSCRIPT_CHARS = 12_000
+
+/**
+ * The actions every client gets: start a conversation with an agent, run a
+ * workflow (playbook), export a conversation and summarize one. Names of
+ * agents and workflows are read when the actions are listed, so new
+ * definitions show up without a restart.
+ */
+export function registerBuiltinActions(registry: ActionRegistry, runtime: RuntimeContext): void {
+  registry.register({
+    id: 'conversation.new',
+    label: 'New conversation',
+    description: 'Start a conversation with an agent; with a message, its first turn starts right away',
+    shortcut: 'Mod+Shift+N',
+    parameters: () => ({
+      type: 'object',
+      properties: {
+        agent: { type: 'string', description: 'Agent (persona) that answers', enum: runtime.agentDefinitions.list().map((agent) => agent.name) },
+        message: { type: 'string', description: 'First message to send' },
+        model: { type: 'string', description: 'Model to use instead of the agent\'s' },
+      },
+    }),
+    async handle(args, ctx) {
+      const agent = args.agent as string | undefined
+      const model = args.model as string | undefined
+      const message = (args.message as string | undefined)?.trim()
+      if (!message) {
+        // The agent applies from the first send, which names it again
+        const session = await runtime.repositories.sessions.create({ userId: ctx.userId, title: PLACEHOLDER_TITLE })
+        return { sessionId: session.id, agentId: null, status: 'created', agent: agent ?? null }
+      }
+      const prepared = await prepareSessionTurn(runtime, { userId: ctx.userId, agent, model, input: message })
+      const abort = new AbortController()
+      runtime.agentAbortControllers.set(prepared.agent.id, abort)
+      void runAgent(prepared.agent.id, buildDeps(runtime, prepared.model), {
+        stream: true,
+        signal: AbortSignal.any([runtime.shutdownController.signal, abort.signal]),
+      })
+        .catch((err) => logger.warn({ err, agentId: prepared.agent.id }, 'Action run failed'))
+        .finally(() => runtime.agentAbortControllers.delete(prepared.agent.id))
+      return { sessionId: prepared.sessionId, agentId: prepared.agent.id, status: 'running', agent: agent ?? null }
+    },
+  })
+
+  const workflows = runtime.workflows
+  if (workflows) {
+    registry.register({
+      id: 'workflow.run',
+      label: 'Run playbook',
+      description: 'Run a workflow; progress arrives as workflow events on the conversation',
+      parameters: () => ({
+        type: 'object',
+        properties: {
+          workflow: { type: 'string', description: 'Workflow to run', enum: workflows.registry.list().map((workflow) => workflow.name) },
+          input: { type: 'object', description: 'Input the workflow expects' },
+          sessionId: { type: 'string', description: 'Conversation to run it in; a new one when left out' },
+        },
+        required: ['workflow'],
+      }),
+      async handle(args, ctx) {
+        const definition = workflows.registry.get(args.workflow as string)!
+        const parsed = definition.inputSchema.safeParse(args.input ?? {})
+        if (!parsed.success) {
+          throw new ActionError(`Invalid input for ${definition.name}: ${parsed.error.issues.map((issue) => `${issue.path.join('.') || 'input'} ${issue.message}`).join('; ')}`)
+        }
+        const sessionId = args.sessionId
+          ? (await ownedSession(runtime, ctx.userId, args.sessionId as string)).id
+          : (await runtime.repositories.sessions.create({ userId: ctx.userId, title: `Playbook: ${definition.name}` })).id
+        const { run, execution } = await workflows.executor.start(definition, parsed.data, {
+          sessionId,
+          signal: runtime.shutdownController.signal,
+        })
+        execution.catch((err) => logger.warn({ err, runId: run.id }, 'Playbook run failed'))
+        return { runId: run.id, sessionId, status: run.status }
+      },
+    })
+  }
+
+  registry.register({
+    id: 'conversation.export',
+    label: 'Export conversation',
+    description: 'The conversation as a file: a Markdown or HTML run report, or a self-contained page to share',
+    shortcut: 'Mod+Shift+E',
+    parameters: {
+      type: 'object',
+      properties: {
+        sessionId: { type: 'string', description: 'Conversation to export' },
+        format: { type: 'string', description: 'markdown or html run report, or conversation for the page to share', enum: [...RUN_REPORT_FORMATS] },
+      },
+      required: ['sessionId'],
+    },
+    async handle(args, ctx) {
+      const sessionId = args.sessionId as string
+      const format = (args.format as RunReportFormat | undefined) ?? 'markdown'
+      const report = await buildRunReport(runtime, ctx.userId, sessionId, format)
+      if (!report) throw new ActionError(`Session not found: ${sessionId}`)
+      return {
+        fileName: reportFileName(report, format),
+        contentType: format === 'markdown' ? 'text/markdown' : 'text/html',
+        content: renderRunReport(report, format, (text) => runtime.secrets.redact(text)),
+      }
+    },
+  })
+
+  registry.register({
+    id: 'conversation.summarize',
+    label: 'Summarize conversation',
+    description: 'Write a short summary of the conversation and keep it with it',
+    parameters: {
+      type: 'object',
+      properties: { sessionId: { type: 'string', description: 'Conversation to summarize' } },
+      required: ['sessionId'],
+    },
+    async handle(args, ctx) {
+      const session = await ownedSession(runtime, ctx.userId, args.sessionId as string)
+      const summary = await summarizeConversation(runtime, session, ctx.signal)
+      await runtime.repositories.sessions.update(session.id, { summary })
+      return { sessionId: session.id, summary }
+    },
+  })
+}
+
+/**
+ * A few sentences on what the conversation was about and where it ended,
+ * written by the title model (TITLE_MODEL, else DEFAULT_MODEL).
+ */
+async function summarizeConversation(
+  runtime: Pick<RuntimeContext, 'config' | 'providers' | 'repositories' | 'usage'>,
+  session: Session,
+  signal?: AbortSignal,
+): Promise<string> {
+  const root = await runtime.repositories.agents.findRootAgent(session.id)
+  const messages = (root ? await runtime.repositories.items.listByAgent(root.id) : [])
+    .filter((item) => item.type === 'message' && (item.role === 'user' || item.role === 'assistant') && item.content?.trim())
+  if (messages.length === 0) throw new ActionError('The conversation has no messages to summarize')
+  // Keep the end of long conversations; that is where they landed
+  const transcript = messages.map((item) => `${item.role}: ${item.content!.trim()}`).join('\n\n').slice(-MAX_SUMMARY_TRANSCRIPT_CHARS)
+
+  const modelId = runtime.config.titleModel ?? runtime.config.defaultModel
+  const { provider, model } = splitModelId(modelId)
+  const response = await runtime.providers.resolve(modelId).generate({
+    model,
+    messages: [{
+      role: 'user',
+      content: `Summarize this conversation in at most four sentences: what the user wanted, what was found or done, and anything left open. Write in the language of the conversation and return only the summary.\n\n${transcript}`,
+    }],
+    temperature: 0.2,
+    max_tokens: 300,
+    signal,
+  })
+  if (root) await runtime.usage.record({ agent: root, provider, model, usage: response.usage, phase: 'summary' })
+  const summary = response.content?.trim()
+  if (!summary) throw new Error('The model returned an empty summary')
+  return summary
+}
+
+async function ownedSession(runtime: Pick<RuntimeContext, 'repositories'>, userId: string, sessionId: string): Promise<Session> {
+  const session = await runtime.repositories.sessions.getById(sessionId)
+  if (!session || session.userId !== userId) throw new ActionError(`Session not found: ${sessionId}`)
+  return session
+}
@@ -0,0 +1,324 @@
+import type { ApiKeyRepository } from '../repositories/types.js'
+import { child, descendants, parseXml, textContent, type XmlElement } from '../lib/xml.js'
+import { parseEvents, type CalendarEvent } from './icalendar.js'
+
+/** All CalDAV accounts live in one encrypted api_keys entry under this name. */
+export const CALDAV_ACCOUNTS_KEY = 'caldav'
+
+const REQUEST_TIMEOUT_MS = 30_000
+const MAX_EVENTS = 250
+
+export interface CalDavAccount {
+  /** Picked by the calendar.* `account` argument, e.g. "icloud" or "work". */
+  name: string
+  /** Server or principal URL: https://caldav.icloud.com, https://caldav.fastmail.com/dav/, https://cloud.example.com/remote.php/dav. */
+  url: string
+  username: string
+  /** An app-specific password for iCloud and Fastmail. */
+  password: string
+}
+
+export type CalDavAccountView = Omit<CalDavAccount, 'password'>
+
+export interface CalendarInfo {
+  account: string
+  /** Collection URL; pass as `calendar` to the calendar.* tools. */
+  id: string
+  name: string
+  color: string | null
+}
+
+export interface CalendarEventEntry extends CalendarEvent {
+  account: string
+  calendar: string
+  /** The event resource; pass to calendar.delete_event. */
+  url: string
+}
+
+export class CalDavError extends Error {
+  constructor(message: string, readonly status?: number) {
+    super(message)
+    this.name = 'CalDavError'
+  }
+}
+
+/** Validates an account from the settings UI; throws with a message suitable for a 400. A missing password keeps the saved one. */
+export function normalizeCalDavAccount(name: string, input: Record<string, unknown>, existing: CalDavAccount | null): CalDavAccount {
+  if (!/^[\w .-]{1,40}$/.test(name)) throw new Error('name must be 1-40 letters, digits, spaces, dots or dashes')
+  const url = typeof input.url === 'string' ? input.url.trim() : ''
+  if (!/^https?:\/\/[^/]+/.test(url)) throw new Error('url must be an http(s) CalDAV server URL')
+  const username = typeof input.username === 'string' ? input.username.trim() : ''
+  if (!username) throw new Error('username is required')
+  const password = typeof input.password === 'string' && input.password ? input.password : existing?.password
+  if (!password) throw new Error('password is required')
+  return { name, url, username, password }
+}
+
+export function calDavAccountView(account: CalDavAccount): CalDavAccountView {
+  const { password: _password, ...view } = account
+  return view
+}
+
+export async function loadCalDavAccounts(apiKeys: ApiKeyRepository): Promise<CalDavAccount[]> {
+  const record = await apiKeys.getByProvider(CALDAV_ACCOUNTS_KEY)
+  if (!record) return []
+  try {
+    return JSON.parse(record.encryptedKey) as CalDavAccount[]
+  } catch {
+    throw new CalDavError('Stored CalDAV accounts are unreadable; save them again')
+  }
+}
+
+export async function saveCalDavAccounts(apiKeys: ApiKeyRepository, accounts: CalDavAccount[]): Promise<void> {
+  if (accounts.length === 0) await apiKeys.delete(CALDAV_ACCOUNTS_KEY)
+  else await apiKeys.upsert(CALDAV_ACCOUNTS_KEY, JSON.stringify(accounts))
+}
+
+/** One CalDAV account: discovery (RFC 6764/4791), calendar listing, time-range queries, PUT and DELETE. */
+export class CalDavClient {
+  private home: URL | null = null
+
+  constructor(
+    private readonly account: CalDavAccount,
+    private readonly fetchImpl: typeof fetch = fetch,
+  ) {}
+
+  async calendars(signal?: AbortSignal): Promise<CalendarInfo[]> {
+    const home = await this.calendarHome(signal)
+    const { responses } = await this.propfind(home, 1, '<D:resourcetype/><D:displayname/><C:supported-calendar-component-set/><A:calendar-color/>', signal)
+    return responses
+      .filter(({ props }) => {
+        const type = child(props, 'resourcetype')
+        if (!type || !child(type, 'calendar')) return false
+        const components = descendants(child(props, 'supported-calendar-component-set') ?? null, 'comp')
+        return components.length === 0 || components.some((component) => component.attrs.name === 'VEVENT')
+      })
+      .map(({ href, props }) => ({
+        account: this.account.name,
+        id: href.href,
+        name: textContent(child(props, 'displayname')).trim() || decodeURIComponent(href.pathname.split('/').filter(Boolean).at(-1) ?? href.pathname),
+        color: textContent(child(props, 'calendar-color')).trim() || null,
+      }))
+  }
+
+  /** Events overlapping [from, to); recurring events come back as their occurrences when the server can expand them. */
+  async events(calendar: CalendarInfo, from: Date, to: Date, signal?: AbortSignal): Promise<CalendarEventEntry[]> {
+    const url = this.ownUrl(calendar.id)
+    const range = `start="${caldavTime(from)}" end="${caldavTime(to)}"`
+    const body = `<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><D:getetag/><C:calendar-data><C:expand ${range}/></C:calendar-data></D:prop>
+  <C:filter><C:comp-filter name="VCALENDAR"><C:comp-filter name="VEVENT"><C:time-range ${range}/></C:comp-filter></C:comp-filter></C:filter>
+</C:calendar-query>`
+    const response = await this.request('REPORT', url, { body, depth: 1, signal })
+    return parseMultistatus(await response.text(), url).flatMap(({ href, props }) =>
+      parseEvents(textContent(child(props, 'calendar-data'))).map((event) => ({
+        ...event,
+        account: this.account.name,
+        calendar: calendar.name,
+        url: href.href,
+      })),
+    )
+  }
+
+  /** Stores a new event resource and returns its URL. */
+  async createEvent(calendar: CalendarInfo, uid: string, ics: string, signal?: AbortSignal): Promise<string> {
+    const base = this.ownUrl(calendar.id)
+    const url = new URL(`${encodeURIComponent(uid)}.ics`, base.href.endsWith('/') ? base : `${base.href}/`)
+    await this.request('PUT', url, {
+      body: ics,
+      contentType: 'text/calendar; charset=utf-8',
+      headers: { 'If-None-Match': '*' },
+      signal,
+    })
+    return url.href
+  }
+
+  async deleteEvent(eventUrl: string, signal?: AbortSignal): Promise<void> {
+    await this.calendarHome(signal)
+    await this.request('DELETE', this.ownUrl(eventUrl), { signal })
+  }
+
+  private async calendarHome(signal?: AbortSignal): Promise<URL> {
+    if (this.home) return this.home
+    const start = new URL(this.account.url)
+    let principal = await this.findPrincipal(start, signal)
+    if (!principal) principal = await this.findPrincipal(new URL('/.well-known/caldav', start), signal)
+    if (!principal) throw new CalDavError(`No CalDAV principal found at ${this.account.url}; check the server URL`)
+    const { responses } = await this.propfind(principal, 0, '<C:calendar-home-set/>', signal)
+    const href = textContent(child(child(responses[0]?.props ?? emptyElement(), 'calendar-home-set') ?? emptyElement(), 'href')).trim()
+    if (!href) throw new CalDavError(`${this.account.url} did not report a calendar home`)
+    this.home = new URL(href, principal)
+    return this.home
+  }
+
+  private async findPrincipal(url: URL, signal?: AbortSignal): Promise<URL | null> {
+    try {
+      const { responses, base } = await this.propfind(url, 0, '<D:current-user-principal/>', signal)
+      const href = textContent(child(child(responses[0]?.props ?? emptyElement(), 'current-user-principal') ?? emptyElement(), 'href')).trim()
+      return href ? new URL(href, base) : null
+    } catch (err) {
+      if (err instanceof CalDavError && err.status === 401) throw err
+      return null
+    }
+  }
+
+  private async propfind(url: URL, depth: 0 | 1, props: string, signal?: AbortSignal): Promise<{ responses: DavResponse[]; base: URL }> {
+    const body = `<?xml version="1.0" encoding="utf-8"?>
+<D:propfind xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav" xmlns:A="http://apple.com/ns/ical/"><D:prop>${props}</D:prop></D:propfind>`
+    const response = await this.request('PROPFIND', url, { body, depth, signal })
+    // Redirects (e.g. /.well-known/caldav) move the base that relative hrefs resolve against
+    const base = response.url ? new URL(response.url) : url
+    return { responses: parseMultistatus(await response.text(), base), base }
+  }
+
+  private async request(
+    method: string,
+    url: URL,
+    options: { body?: string; depth?: 0 | 1; contentType?: string; headers?: Record<string, string>; signal?: AbortSignal },
+  ): Promise<Response> {
+    const signal = options.signal ? AbortSignal.any([options.signal, AbortSignal.timeout(REQUEST_TIMEOUT_MS)]) : AbortSignal.timeout(REQUEST_TIMEOUT_MS)
+    const response = await this.fetchImpl(url, {
+      method,
+      headers: {
+        Authorization: `Basic ${Buffer.from(`${this.account.username}:${this.account.password}`, 'utf-8').toString('base64')}`,
+        ...(options.body !== undefined && { 'Content-Type': options.contentType ?? 'application/xml; charset=utf-8' }),
+        ...(options.depth !== undefined && { Depth: String(options.depth) }),
+        ...options.headers,
+      },
+      body: options.body,
+      signal,
+    })
+    if (response.status === 401) throw new CalDavError(`CalDAV login for "${this.account.name}" was rejected; check the username and app password`, 401)
+    if (!response.ok) {
+      throw new CalDavError(`CalDAV ${method} ${url.pathname} failed: ${response.status} ${response.statusText}`.trim(), response.status)
+    }
+    return response
+  }
+
+  /** Credentials only go to the server that reported the calendar home. */
+  private ownUrl(value: string): URL {
+    const url = new URL(value)
+    const home = this.home ?? new URL(this.account.url)
+    if (url.origin !== home.origin) throw new CalDavError(`${value} is not on this account's CalDAV server`)
+    return url
+  }
+}
+
+/** calendar.* over every configured CalDAV account, one chosen with `account` when there are several. */
+export class CalendarService {
+  constructor(
+    private readonly loadAccounts: () => Promise<CalDavAccount[]>,
+    private readonly fetchImpl: typeof fetch = fetch,
+  ) {}
+
+  async listCalendars(accountName?: string, signal?: AbortSignal): Promise<CalendarInfo[]> {
+    const clients = await this.clients(accountName)
+    return (await Promise.all(clients.map(({ client }) => client.calendars(signal)))).flat()
+  }
+
+  async listEvents(
+    options: { account?: string; calendar?: string; from: Date; to: Date; query?: string },
+    signal?: AbortSignal,
+  ): Promise<{ events: CalendarEventEntry[]; truncated: boolean }> {
+    const clients = await this.clients(options.account)
+    const batches = await Promise.all(clients.map(async ({ client }) => {
+      const calendars = filterCalendars(await client.calendars(signal), options.calendar)
+      return (await Promise.all(calendars.map((calendar) => client.events(calendar, options.from, options.to, signal)))).flat()
+    }))
+    const query = options.query?.trim().toLowerCase()
+    const events = batches.flat()
+      .filter((event) => !query || [event.summary, event.description, event.location, ...(event.attendees ?? []).flatMap((attendee) => [attendee.email, attendee.displayName])]
+        .some((field) => field?.toLowerCase().includes(query)))
+      .sort((a, b) => startMillis(a) - startMillis(b))
+    return { events: events.slice(0, MAX_EVENTS), truncated: events.length > MAX_EVENTS }
+  }
+
+  async createEvent(
+    options: { account?: string; calendar?: string; uid: string; ics: string },
+    signal?: AbortSignal,
+  ): Promise<{ account: string; calendar: string; url: string }> {
+    const { account, client } = await this.single(options.account)
+    const calendars = filterCalendars(await client.calendars(signal), options.calendar)
+    if (calendars.length === 0) throw new CalDavError(`Account "${account.name}" has no event calendars`)
+    const url = await client.createEvent(calendars[0], options.uid, options.ics, signal)
+    return { account: account.name, calendar: calendars[0].name, url }
+  }
+
+  async deleteEvent(options: { account?: string; url: string }, signal?: AbortSignal): Promise<void> {
+    const { client } = await this.single(options.account)
+    await client.deleteEvent(options.url, signal)
+  }
+
+  /** The account that new events go to: the named one, or the only one. */
+  async organizer(accountName?: string): Promise<string | null> {
+    const { account } = await this.single(accountName)
+    return account.username.includes('@') ? account.username : null
+  }
+
+  private async single(accountName?: string): Promise<{ account: CalDavAccount; client: CalDavClient }> {
+    const clients = await this.clients(accountName)
+    if (clients.length > 1) {
+      throw new CalDavError(`Several calendar accounts are configured (${clients.map(({ account }) => account.name).join(', ')}); pass account`)
+    }
+    return clients[0]
+  }
+
+  private async clients(accountName?: string): Promise<Array<{ account: CalDavAccount; client: CalDavClient }>> {
+    const accounts = await this.loadAccounts()
+    if (accounts.length === 0) throw new CalDavError('No calendar account is configured')
+    const chosen = accountName ? accounts.filter((account) => account.name.toLowerCase() === accountName.toLowerCase()) : accounts
+    if (chosen.length === 0) {
+      throw new CalDavError(`Unknown calendar account "${accountName}"; configured: ${accounts.map((account) => account.name).join(', ')}`)
+    }
+    return chosen.map((account) => ({ account, client: new CalDavClient(account, this.fetchImpl) }))
+  }
+}
+
+/** Discovers the calendars once; used to check an account before it is saved. */
+export async function verifyCalDavAccount(account: CalDavAccount, fetchImpl: typeof fetch = fetch, signal?: AbortSignal): Promise<CalendarInfo[]> {
+  return new CalDavClient(account, fetchImpl).calendars(signal)
+}
+
+interface DavResponse {
+  href: URL
+  props: XmlElement
+}
+
+/** The 200 propstat of each <response>, with hrefs resolved against the request URL. */
+function parseMultistatus(xml: string, base: URL): DavResponse[] {
+  return descendants(parseXml(xml), 'response').flatMap((response) => {
+    const href = textContent(child(response, 'href')).trim()
+    if (!href) return []
+    const props: XmlElement = { name: 'prop', attrs: {}, children: [] }
+    for (const propstat of descendants(response, 'propstat')) {
+      if (!/\s2\d\d\s/.test(` ${textContent(child(propstat, 'status'))} `)) continue
+      props.children.push(...(child(propstat, 'prop')?.children ?? []))
+    }
+    return [{ href: new URL(href, base), props }]
+  })
+}
+
+function filterCalendars(calendars: CalendarInfo[], wanted?: string): CalendarInfo[] {
+  if (!wanted?.trim()) return calendars
+  const needle = wanted.trim().toLowerCase()
+  const matches = calendars.filter((calendar) => calendar.id === wanted || calendar.name.toLowerCase() === needle)
+  if (matches.length === 0) {
+    throw new CalDavError(`No calendar named "${wanted}"; available: ${calendars.map((calendar) => calendar.name).join(', ')}`)
+  }
+  return matches
+}
+
+function startMillis(event: CalendarEvent): number {
+  const value = 'date' in event.start ? `${event.start.date}T00:00:00Z` : event.start.dateTime
+  const parsed = Date.parse(value)
+  return Number.isNaN(parsed) ? 0 : parsed
+}
+
+function caldavTime(date: Date): string {
+  return date.toISOString().replace(/[-:]/g, '').replace(/\.\d{3}/, '')
+}
+
+function emptyElement(): XmlElement {
+  return { name: '', attrs: {}, children: [] }
+}
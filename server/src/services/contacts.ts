@@ -0,0 +1,59 @@
+import { runOsaScript, type OsaScriptRunner } from '../lib/osascript.js'
+
+const DEFAULT_LIMIT = 5
+const MAX_LIMIT = 25
+
+export interface ContactPoint {
+  /** "work", "home", "mobile"… as the address book labels it. */
+  label: string | null
+  value: string
+}
+
+export interface Contact {
+  id: string
+  name: string
+  organization: string | null
+  emails: ContactPoint[]
+  phones: ContactPoint[]
+}
+
+/** The user's address book behind contacts.search. */
+export interface ContactsDirectory {
+  readonly name: string
+  search(query: string, limit: number | undefined, signal?: AbortSignal): Promise<Contact[]>
+}
+
+const CONTACTS_SEARCH_SCRIPT = `
+function run(argv) {
+  const query = argv[0], limit = Number(argv[1])
+  const people = Application('Contacts').people.whose({ _or: [
+    { name: { _contains: query } },
+    { nickname: { _contains: query } },
+    { organization: { _contains: query } },
+  ] })
+  const label = (raw) => raw ? raw.replace(/^_\\$!<(.*)>!\\$_$/, '$1').toLowerCase() : null
+  const out = []
+  for (let i = 0; i < people.length && i < limit; i++) {
+    const person = people[i]
+    out.push({
+      id: person.id(),
+      name: person.name(),
+      organization: person.organization() || null,
+      emails: person.emails().map((email) => ({ label: label(email.label()), value: email.value() })),
+      phones: person.phones().map((phone) => ({ label: label(phone.label()), value: phone.value() })),
+    })
+  }
+  return JSON.stringify(out)
+}`
+
+/** macOS Contacts through osascript; matches names, nicknames and companies. */
+export class MacContacts implements ContactsDirectory {
+  readonly name = 'macOS Contacts'
+
+  constructor(private readonly run: OsaScriptRunner = runOsaScript) {}
+
+  async search(query: string, limit: number | undefined, signal?: AbortSignal): Promise<Contact[]> {
+    const clamped = Math.min(Math.max(1, Math.floor(limit ?? DEFAULT_LIMIT)), MAX_LIMIT)
+    return JSON.parse(await this.run(CONTACTS_SEARCH_SCRIPT, [query.trim(), String(clamped)], signal)) as Contact[]
+  }
+}
@@ -0,0 +1,80 @@
+import {
+  CONTROLLER_PROMPT_NAMES,
+  DEFAULT_CONTROLLER_PROMPTS,
+  type ControllerPromptName,
+  type ControllerPrompts,
+} from '../orchestrator/prompts.js'
+import type { ControllerPromptSource } from '../orchestrator/types.js'
+import type { ControllerPromptRepository, ControllerPromptVersion } from '../repositories/types.js'
+
+export interface ControllerPromptState {
+  name: ControllerPromptName
+  version: number
+  content: string
+  /** The text is the built-in default. */
+  isDefault: boolean
+  updatedAt: number
+}
+
+/**
+ * The controller prompts runs use, kept in the database so they can be
+ * tuned without a rebuild. Each prompt starts as its built-in default at
+ * version 1; every edit or reset saves a new version, so earlier texts can
+ * be looked up and restored. Runs read the set once when they start, so a
+ * change applies from the next run on.
+ */
+export class ControllerPromptStore implements ControllerPromptSource {
+  constructor(private readonly repo: ControllerPromptRepository) {}
+
+  /** Saves the default as version 1 of any prompt without a stored text. */
+  async seed(): Promise<void> {
+    const stored = new Set((await this.repo.listActive()).map((row) => row.name))
+    for (const name of CONTROLLER_PROMPT_NAMES) {
+      if (!stored.has(name)) await this.repo.addVersion(name, DEFAULT_CONTROLLER_PROMPTS[name])
+    }
+  }
+
+  async active(): Promise<ControllerPrompts> {
+    const prompts = { ...DEFAULT_CONTROLLER_PROMPTS }
+    for (const row of await this.repo.listActive()) {
+      if (row.name in prompts) prompts[row.name as ControllerPromptName] = row.content
+    }
+    return prompts
+  }
+
+  async list(): Promise<ControllerPromptState[]> {
+    const rows = new Map((await this.repo.listActive()).map((row) => [row.name, row]))
+    return CONTROLLER_PROMPT_NAMES.map((name) => {
+      const row = rows.get(name)
+      return row ? state(name, row) : { name, version: 0, content: DEFAULT_CONTROLLER_PROMPTS[name], isDefault: true, updatedAt: 0 }
+    })
+  }
+
+  /** Newest first. */
+  versions(name: ControllerPromptName): Promise<ControllerPromptVersion[]> {
+    return this.repo.listVersions(name)
+  }
+
+  /** Saves `content` as the prompt's next version; saving the current text again adds nothing. */
+  async update(name: ControllerPromptName, content: string): Promise<ControllerPromptState> {
+    if (!content.trim()) throw new Error('A controller prompt cannot be empty')
+    const [current] = await this.repo.listVersions(name)
+    if (current?.content === content) return state(name, current)
+    return state(name, await this.repo.addVersion(name, content))
+  }
+
+  /** Goes back to the built-in default, as a new version so the edited text stays in the history. */
+  reset(name: ControllerPromptName): Promise<ControllerPromptState> {
+    return this.update(name, DEFAULT_CONTROLLER_PROMPTS[name])
+  }
+}
+
+function state(name: ControllerPromptName, row: ControllerPromptVersion): ControllerPromptState {
+  return {
+    name,
+    version: row.version,
+    content: row.content,
+    isDefault: row.content === DEFAULT_CONTROLLER_PROMPTS[name],
+    updatedAt: row.createdAt,
+  }
+}
@@ -0,0 +1,83 @@
+import type { Item, ItemRole } from '../domain/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+
+export interface ToolCallCount {
+  name: string
+  calls: number
+  errors: number
+}
+
+export interface ConversationStats {
+  sessionId: string
+  /** Messages in the visible conversation (root agent only). */
+  messages: Record<ItemRole, number>
+  /** Tool calls across every agent in the session, most used first. */
+  tools: ToolCallCount[]
+  agentCount: number
+  agentTurns: number
+  /** Mean time from a user message to the assistant's reply; null before the first reply. */
+  averageResponseMs: number | null
+  costUsd: number
+}
+
+export async function getConversationStats(
+  runtime: RuntimeContext,
+  sessionId: string,
+): Promise<ConversationStats | null> {
+  const { sessions, agents, items, usage } = runtime.repositories
+  const session = await sessions.getById(sessionId)
+  if (!session) return null
+
+  const sessionAgents = await agents.listBySession(sessionId)
+  const root = sessionAgents.find((agent) => agent.parentId === null)
+  const sessionItems = await items.listBySession(sessionId)
+  const rootItems = sessionItems
+    .filter((item) => item.agentId === root?.id)
+    .sort((a, b) => a.sequence - b.sequence)
+
+  const messages: Record<ItemRole, number> = { system: 0, user: 0, assistant: 0 }
+  for (const item of rootItems) {
+    if (item.type === 'message' && item.role) messages[item.role]++
+  }
+
+  const failedCalls = new Set(
+    sessionItems
+      .filter((item) => item.type === 'function_call_output' && item.isError && item.callId)
+      .map((item) => item.callId!),
+  )
+  const tools = new Map<string, ToolCallCount>()
+  for (const item of sessionItems) {
+    if (item.type !== 'function_call' || !item.name) continue
+    const entry = tools.get(item.name) ?? { name: item.name, calls: 0, errors: 0 }
+    entry.calls++
+    if (item.callId && failedCalls.has(item.callId)) entry.errors++
+    tools.set(item.name, entry)
+  }
+
+  const records = await usage.list({ sessionId })
+  return {
+    sessionId,
+    messages,
+    tools: [...tools.values()].sort((a, b) => b.calls - a.calls || a.name.localeCompare(b.name)),
+    agentCount: sessionAgents.length,
+    agentTurns: sessionAgents.reduce((sum, agent) => sum + agent.turnCount, 0),
+    averageResponseMs: averageResponseMs(rootItems),
+    costUsd: Math.round(records.reduce((sum, record) => sum + record.costUsd, 0) * 1_000_000) / 1_000_000,
+  }
+}
+
+function averageResponseMs(items: Item[]): number | null {
+  const latencies: number[] = []
+  let pendingSince: number | null = null
+  for (const item of items) {
+    if (item.type !== 'message') continue
+    if (item.role === 'user') {
+      pendingSince ??= item.createdAt
+    } else if (item.role === 'assistant' && pendingSince !== null) {
+      latencies.push(item.createdAt - pendingSince)
+      pendingSince = null
+    }
+  }
+  if (latencies.length === 0) return null
+  return Math.round(latencies.reduce((sum, ms) => sum + ms, 0) / latencies.length)
+}
@@ -0,0 +1,128 @@
+import type { Agent } from '../domain/types.js'
+import type { AgentRepository } from '../repositories/types.js'
+
+export interface ConversationThrottleOptions {
+  /** Sends of one conversation in flight at once: being prepared, or their run still going. */
+  maxConcurrent: number
+  /** Least time between two sends to one conversation. */
+  minIntervalMs: number
+}
+
+/** Why a send was turned away, and the message the conversation is busy with. */
+export interface ConversationBusy {
+  reason: 'concurrent' | 'too_soon'
+  sessionId: string
+  /** The user message of the send in the way; null while it is still being prepared. */
+  messageId: string | null
+  agentId: string | null
+  /** When sending again would be accepted, if that is known. */
+  retryAfterMs?: number
+}
+
+export class ConversationBusyError extends Error {
+  constructor(readonly busy: ConversationBusy) {
+    super(busy.reason === 'too_soon'
+      ? `Conversation ${busy.sessionId} received a message moments ago; retry in ${busy.retryAfterMs} ms`
+      : `Conversation ${busy.sessionId} is still handling a message`)
+  }
+}
+
+interface Send {
+  agentId: string | null
+  messageId: string | null
+}
+
+interface ConversationSends {
+  inFlight: Send[]
+  last: Send | null
+  lastAdmittedAt: number
+}
+
+/** An admitted send: report what it started, or release it when it started nothing. */
+export interface SendTicket {
+  started(agentId: string, messageId: string | null): void
+  release(): void
+}
+
+/**
+ * Turns away sends that would pile onto a conversation already handling
+ * one, such as an accidental double-send, before anything is saved or a
+ * second worker is started. A send counts as in flight from admission until
+ * its run stops running: it completes, fails or waits on the user.
+ */
+export class ConversationThrottle {
+  private readonly conversations = new Map<string, ConversationSends>()
+
+  constructor(
+    private readonly agents: Pick<AgentRepository, 'getById'>,
+    private readonly options: ConversationThrottleOptions,
+    private readonly now: () => number = Date.now,
+  ) {}
+
+  /** Throws ConversationBusyError when the conversation cannot take another send yet. */
+  async admit(sessionId: string): Promise<SendTicket> {
+    await this.settle(sessionId)
+    // Nothing is awaited from here on, so two sends cannot both pass the checks
+    const now = this.now()
+    const conversation = this.conversations.get(sessionId) ?? { inFlight: [], last: null, lastAdmittedAt: 0 }
+    const [active] = conversation.inFlight
+    if (conversation.inFlight.length >= Math.max(1, this.options.maxConcurrent)) {
+      throw new ConversationBusyError({ reason: 'concurrent', sessionId, messageId: active.messageId, agentId: active.agentId })
+    }
+    const wait = conversation.lastAdmittedAt + this.options.minIntervalMs - now
+    if (wait > 0) {
+      throw new ConversationBusyError({
+        reason: 'too_soon',
+        sessionId,
+        messageId: conversation.last?.messageId ?? null,
+        agentId: conversation.last?.agentId ?? null,
+        retryAfterMs: wait,
+      })
+    }
+
+    const send: Send = { agentId: null, messageId: null }
+    conversation.inFlight.push(send)
+    conversation.last = send
+    conversation.lastAdmittedAt = now
+    this.conversations.set(sessionId, conversation)
+    this.prune(now)
+    return {
+      started: (agentId, messageId) => {
+        send.agentId = agentId
+        send.messageId = messageId
+      },
+      release: () => this.release(sessionId, send),
+    }
+  }
+
+  /** Drops sends whose run has stopped; runs end in many places, so their state is read rather than reported. */
+  private async settle(sessionId: string): Promise<void> {
+    const conversation = this.conversations.get(sessionId)
+    if (!conversation) return
+    for (const send of [...conversation.inFlight]) {
+      if (!send.agentId) continue
+      const agent = await this.agents.getById(send.agentId)
+      if (!agent || !isInFlight(agent)) this.release(sessionId, send)
+    }
+  }
+
+  private release(sessionId: string, send: Send): void {
+    const conversation = this.conversations.get(sessionId)
+    if (!conversation) return
+    const index = conversation.inFlight.indexOf(send)
+    if (index !== -1) conversation.inFlight.splice(index, 1)
+  }
+
+  private prune(now: number): void {
+    for (const [sessionId, conversation] of this.conversations) {
+      if (conversation.inFlight.length === 0 && now - conversation.lastAdmittedAt >= this.options.minIntervalMs) {
+        this.conversations.delete(sessionId)
+      }
+    }
+  }
+}
+
+// A queued (offline) send stays pending until it runs
+function isInFlight(agent: Pick<Agent, 'status'>): boolean {
+  return agent.status === 'running' || agent.status === 'pending'
+}
@@ -0,0 +1,141 @@
+import { UNPACED } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import type { Item, Session } from '../domain/types.js'
+import { logger } from '../lib/logger.js'
+import { splitModelId } from '../lib/model.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { detectLanguage, resolveResponseLanguage } from '../orchestrator/language.js'
+
+/** Title a conversation starts with when its first message is not plain text. */
+export const PLACEHOLDER_TITLE = 'New conversation'
+const MAX_TITLE_CHARS = 80
+const MAX_TRANSCRIPT_CHARS = 2000
+
+type TitleRuntime = Pick<RuntimeContext, 'config' | 'providers' | 'repositories' | 'usage' | 'events'>
+
+/**
+ * Asks `modelId` for a title for the conversation's messages, written in the
+ * reply language, else the language the user wrote in. Usage is recorded on
+ * the root agent.
+ */
+export async function writeConversationTitle(
+  runtime: Omit<TitleRuntime, 'events'>,
+  session: Session,
+  messages: Item[],
+  modelId: string,
+  signal?: AbortSignal,
+): Promise<string> {
+  const transcript = messages.map((item) => `${item.role}: ${item.content?.trim() ?? ''}`).join('\n').slice(0, MAX_TRANSCRIPT_CHARS)
+  const { language } = await resolveResponseLanguage(runtime.repositories.preferences, session.id)
+  const firstUserMessage = messages.find((item) => item.role === 'user')?.content
+  const titleLanguage = language ?? (firstUserMessage ? detectLanguage(firstUserMessage) : null)
+
+  const { provider, model } = splitModelId(modelId)
+  const response = await runtime.providers.resolve(modelId).generate({
+    model,
+    messages: [{
+      role: 'user',
+      content: `Generate a short title (max 6 words) for this conversation, written in ${titleLanguage ?? 'the language of the conversation'}. Return ONLY the title, no quotes or extra text.\n\n${transcript}`,
+    }],
+    temperature: 0.3,
+    max_tokens: 30,
+    signal,
+  })
+  const rootAgent = session.rootAgentId ? await runtime.repositories.agents.getById(session.rootAgentId) : null
+  if (rootAgent) await runtime.usage.record({ agent: rootAgent, provider, model, usage: response.usage, phase: 'title' })
+  return cleanTitle(response.content)
+}
+
+/** Saves the title and tells every window about it. */
+export async function applyConversationTitle(runtime: Pick<TitleRuntime, 'repositories' | 'events'>, session: Session, title: string): Promise<void> {
+  await runtime.repositories.sessions.update(session.id, { title })
+  runtime.events.emit({
+    type: EVENT_TYPES.CONVERSATION_UPDATED,
+    agent_id: session.rootAgentId ?? 'session',
+    session_id: session.id,
+    payload: { title },
+    timestamp: Date.now(),
+  })
+}
+
+/**
+ * Names a conversation after its first exchange with TITLE_MODEL. Only
+ * conversations still carrying the title they were created with are named,
+ * so a title the user chose is never replaced. Returns the new title, or
+ * null when there was nothing to do.
+ */
+export async function generateConversationTitle(
+  runtime: TitleRuntime,
+  input: { sessionId: string; agentId: string; signal?: AbortSignal },
+): Promise<string | null> {
+  const modelId = runtime.config.titleModel?.trim()
+  if (!modelId) return null
+  const session = await runtime.repositories.sessions.getById(input.sessionId)
+  if (!session) return null
+  const messages = (await runtime.repositories.items.listByAgent(input.agentId))
+    .filter((item) => item.type === 'message' && (item.role === 'user' || item.role === 'assistant') && item.content?.trim())
+  const firstExchange = messages.filter((item) => item.role === 'user').length === 1 && messages.some((item) => item.role === 'assistant')
+  if (!firstExchange || !isUntitled(session, messages[0])) return null
+
+  const title = await writeConversationTitle(runtime, session, messages, modelId, input.signal)
+  if (!title) return null
+  // The user may have renamed the conversation while the title was generated
+  const current = await runtime.repositories.sessions.getById(input.sessionId)
+  if (!current || current.title !== session.title) return null
+  await applyConversationTitle(runtime, current, title)
+  return title
+}
+
+/**
+ * Titles conversations when their root agent finishes its first turn, so
+ * clients no longer have to ask for one. Started only when TITLE_MODEL is set.
+ */
+export class ConversationTitler {
+  private iterator: AsyncIterator<AgentEvent> | null = null
+
+  constructor(private readonly runtime: RuntimeContext) {}
+
+  start(): void {
+    if (this.iterator) return
+    const iterator = this.runtime.events.subscribe({ types: [EVENT_TYPES.AGENT_COMPLETED] }, UNPACED)[Symbol.asyncIterator]()
+    this.iterator = iterator
+    void (async () => {
+      for (let next = await iterator.next(); !next.done; next = await iterator.next()) {
+        const event = next.value
+        if (event.type !== EVENT_TYPES.AGENT_COMPLETED || event.payload.depth > 0) continue
+        await this.title(event.agent_id, event.session_id)
+      }
+    })()
+  }
+
+  stop(): void {
+    void this.iterator?.return?.()
+    this.iterator = null
+  }
+
+  private async title(agentId: string, sessionId: string): Promise<void> {
+    try {
+      const title = await generateConversationTitle(this.runtime, {
+        sessionId,
+        agentId,
+        signal: this.runtime.shutdownController.signal,
+      })
+      if (title) logger.info({ sessionId, title }, 'Conversation titled')
+    } catch (err) {
+      logger.warn({ err, sessionId, agentId }, 'Conversation title generation failed')
+    }
+  }
+}
+
+/** Still the title it was created with: none, the placeholder, or the start of the first message. */
+function isUntitled(session: Session, firstMessage: Item | undefined): boolean {
+  const title = session.title?.trim()
+  if (!title || title === PLACEHOLDER_TITLE) return true
+  return firstMessage?.role === 'user' && title === firstMessage.content!.slice(0, 100).trim()
+}
+
+function cleanTitle(content: string | null | undefined): string {
+  const line = (content ?? '').trim().split('\n')[0] ?? ''
+  const title = line.replace(/^(?:title:\s*)/i, '').replace(/^["'“”]+|["'“”.]+$/g, '').trim()
+  return title.length > MAX_TITLE_CHARS ? `${title.slice(0, MAX_TITLE_CHARS - 1).trimEnd()}…` : title
+}
@@ -0,0 +1,226 @@
+import { EVENT_TYPES } from '../events/types.js'
+import { logger } from '../lib/logger.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { runAgent } from '../orchestrator/runner.js'
+import type { ActionRegistry } from './actions.js'
+import { buildDeps, prepareSessionTurn } from './session-runner.js'
+
+/** Preference holding the id of a user's digest conversation, suffixed with the user id. */
+export const DIGEST_SESSION_PREFERENCE = 'digest_session'
+/** Preference holding the local date (YYYY-MM-DD) of a user's last digest, suffixed with the user id. */
+export const DIGEST_LAST_RUN_PREFERENCE = 'digest_last_run'
+export const DIGEST_TITLE = 'Daily digest'
+/** Read-only tools the briefing draws on; those not configured are left out. */
+export const DIGEST_TOOLS = ['calendar.list_events', 'mail.list', 'mail.read', 'todos.list', 'tasks.list']
+const MAX_NOTIFICATION_CHARS = 280
+
+const DIGEST_INSTRUCTIONS = [
+  'Write the user\'s morning briefing. Look up, with the tools you have:',
+  '- today\'s calendar events, with times and who attends;',
+  '- unread email worth attention, one line per message or thread;',
+  '- open todos and background tasks, overdue and due today first.',
+  'Skip a section whose tool is missing or fails, and say so in one line. Do not reply to, change or create anything.',
+  'Return markdown: a one-sentence overview first, then a short section per source.',
+].join('\n')
+
+export interface DigestResult {
+  sessionId: string
+  /** Local date the briefing is for, YYYY-MM-DD. */
+  date: string
+  status: 'completed' | 'failed' | 'skipped'
+  briefing: string | null
+  error?: string
+}
+
+/** Body POSTed to DIGEST_WEBHOOK_URL when a briefing is ready. */
+export interface DigestNotification {
+  title: string
+  message: string
+  sessionId: string
+  date: string
+}
+
+/** `HH:MM` as hours and minutes; null when it is not a valid 24-hour time. */
+export function parseDigestTime(value: string): { hour: number; minute: number } | null {
+  const match = /^([01]?\d|2[0-3]):([0-5]\d)$/.exec(value.trim())
+  return match ? { hour: Number(match[1]), minute: Number(match[2]) } : null
+}
+
+/** The next moment, in server local time, the clock reads `time` — today if still ahead, else tomorrow. */
+export function nextDigestAt(time: { hour: number; minute: number }, now: number): number {
+  const next = new Date(now)
+  next.setHours(time.hour, time.minute, 0, 0)
+  if (next.getTime() <= now) next.setDate(next.getDate() + 1)
+  return next.getTime()
+}
+
+export function localDate(now: number): string {
+  const date = new Date(now)
+  return `${date.getFullYear()}-${String(date.getMonth() + 1).padStart(2, '0')}-${String(date.getDate()).padStart(2, '0')}`
+}
+
+/**
+ * Runs the digest agent every day at DIGEST_TIME (server local time) for
+ * each user: it reads calendar, mail and todos through the usual tools and
+ * posts a morning briefing into one long-lived "Daily digest" conversation,
+ * then notifies the user. A server that was down at the set time catches up
+ * when it starts, once per day.
+ */
+export class DailyDigest {
+  private timer: NodeJS.Timeout | null = null
+  private running: Promise<DigestResult[]> | null = null
+  private readonly time: { hour: number; minute: number }
+
+  constructor(
+    private readonly runtime: RuntimeContext,
+    private readonly options: { time: string; agent?: string; webhookUrl?: string; fetchImpl?: typeof fetch },
+  ) {
+    const time = parseDigestTime(options.time)
+    if (!time) throw new Error(`DIGEST_TIME must be HH:MM, got "${options.time}"`)
+    this.time = time
+  }
+
+  start(now = Date.now()): void {
+    if (this.timer) return
+    const today = new Date(now)
+    today.setHours(this.time.hour, this.time.minute, 0, 0)
+    // Missed today's run while the server was down
+    if (today.getTime() <= now) void this.runAll(now)
+    this.schedule(now)
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearTimeout(this.timer)
+      this.timer = null
+    }
+  }
+
+  /** Briefs every user who has not had today's digest yet. A run already under way is joined. */
+  runAll(now = Date.now()): Promise<DigestResult[]> {
+    this.running ??= this.briefAll(now).finally(() => {
+      this.running = null
+    })
+    return this.running
+  }
+
+  /** Writes today's briefing for one user now, whether or not one was written already. */
+  async run(userId: string, now = Date.now()): Promise<DigestResult> {
+    const date = localDate(now)
+    const { preferences } = this.runtime.repositories
+    await preferences.set(`${DIGEST_LAST_RUN_PREFERENCE}:${userId}`, date)
+    const sessionId = await this.digestSession(userId)
+
+    const available = new Set(this.runtime.tools.listMetadata().map((tool) => tool.name))
+    const prepared = await prepareSessionTurn(this.runtime, {
+      userId,
+      sessionId,
+      agent: this.options.agent,
+      input: `Morning briefing for ${new Date(now).toLocaleDateString('en-US', { weekday: 'long', year: 'numeric', month: 'long', day: 'numeric' })}`,
+      instructions: DIGEST_INSTRUCTIONS,
+      responseFormat: 'markdown',
+      allowedTools: DIGEST_TOOLS.filter((name) => available.has(name)),
+    })
+    // Yesterday's briefing is still being written or waits on the user
+    if (prepared.status === 'active') return { sessionId, date, status: 'skipped', briefing: null }
+
+    const abort = new AbortController()
+    this.runtime.agentAbortControllers.set(prepared.agent.id, abort)
+    let result: Awaited<ReturnType<typeof runAgent>>
+    try {
+      result = await runAgent(prepared.agent.id, buildDeps(this.runtime, prepared.model), {
+        signal: AbortSignal.any([this.runtime.shutdownController.signal, abort.signal]),
+      })
+    } finally {
+      this.runtime.agentAbortControllers.delete(prepared.agent.id)
+    }
+    if (result.status !== 'completed') {
+      return { sessionId, date, status: 'failed', briefing: null, error: result.error ?? `Digest agent finished with status: ${result.status}` }
+    }
+
+    const briefing = result.result ?? ''
+    await this.notify(prepared.agent.id, sessionId, date, briefing)
+    return { sessionId, date, status: 'completed', briefing }
+  }
+
+  private schedule(now: number): void {
+    const at = nextDigestAt(this.time, now)
+    this.timer = setTimeout(() => {
+      this.timer = null
+      void this.runAll().finally(() => this.schedule(Date.now()))
+    }, at - now)
+    this.timer.unref()
+  }
+
+  private async briefAll(now: number): Promise<DigestResult[]> {
+    const results: DigestResult[] = []
+    const date = localDate(now)
+    for (const user of await this.runtime.repositories.users.list()) {
+      try {
+        if (await this.runtime.repositories.preferences.get(`${DIGEST_LAST_RUN_PREFERENCE}:${user.id}`) === date) continue
+        const result = await this.run(user.id, now)
+        if (result.status === 'failed') logger.warn({ userId: user.id, error: result.error }, 'Daily digest failed')
+        else if (result.status === 'completed') logger.info({ userId: user.id, sessionId: result.sessionId }, 'Daily digest written')
+        results.push(result)
+      } catch (err) {
+        logger.warn({ err, userId: user.id }, 'Daily digest failed')
+      }
+    }
+    return results
+  }
+
+  /** The user's digest conversation, created on first use and again if it was deleted. */
+  private async digestSession(userId: string): Promise<string> {
+    const { preferences, sessions } = this.runtime.repositories
+    const key = `${DIGEST_SESSION_PREFERENCE}:${userId}`
+    const saved = await preferences.get(key)
+    const existing = saved ? await sessions.getById(saved) : null
+    if (existing && existing.userId === userId && !existing.deletedAt) return existing.id
+    const session = await sessions.create({ userId, title: DIGEST_TITLE })
+    await preferences.set(key, session.id)
+    return session.id
+  }
+
+  private async notify(agentId: string, sessionId: string, date: string, briefing: string): Promise<void> {
+    const excerpt = excerptOf(briefing)
+    this.runtime.events.emit({
+      type: EVENT_TYPES.DIGEST_READY,
+      agent_id: agentId,
+      session_id: sessionId,
+      payload: { date, excerpt },
+      timestamp: Date.now(),
+    })
+    if (!this.options.webhookUrl) return
+    const notification: DigestNotification = { title: `${DIGEST_TITLE} · ${date}`, message: excerpt, sessionId, date }
+    try {
+      const response = await (this.options.fetchImpl ?? fetch)(this.options.webhookUrl, {
+        method: 'POST',
+        headers: { 'content-type': 'application/json' },
+        body: JSON.stringify(notification),
+        signal: AbortSignal.timeout(10_000),
+      })
+      if (!response.ok) logger.warn({ status: response.status, sessionId }, 'Digest webhook rejected the notification')
+    } catch (err) {
+      logger.warn({ err, sessionId }, 'Failed to send digest notification')
+    }
+  }
+}
+
+/** Adds `digest.run`, which writes today's briefing on demand. */
+export function registerDigestAction(registry: ActionRegistry, digest: DailyDigest): void {
+  registry.register({
+    id: 'digest.run',
+    label: 'Run daily digest',
+    description: 'Write today\'s morning briefing now, in the Daily digest conversation',
+    parameters: { type: 'object', properties: {} },
+    handle: (_args, ctx) => digest.run(ctx.userId),
+  })
+}
+
+/** The briefing's first line of prose, flattened from markdown, for a notification body. */
+function excerptOf(briefing: string): string {
+  const lines = briefing.split('\n').map((text) => text.trim()).filter(Boolean)
+  const opening = lines.find((text) => !text.startsWith('#')) ?? lines[0] ?? ''
+  const line = opening.replace(/^[#>*\-\s]+/, '').replace(/[*_`]/g, '').trim()
+  return line.length > MAX_NOTIFICATION_CHARS ? `${line.slice(0, MAX_NOTIFICATION_CHARS - 1).trimEnd()}…` : line
+}
@@ -0,0 +1,105 @@
+import fs from 'fs/promises'
+import path from 'path'
+import { EVENT_TYPES } from '../events/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+import type { DatabaseMaintenanceReport } from '../repositories/types.js'
+
+export interface ArtifactStoreSize {
+  name: 'sessions' | 'attachments' | 'tasks' | 'notes' | 'traces'
+  path: string
+  bytes: number
+  files: number
+}
+
+export interface DatabaseHealth {
+  status: 'ok' | 'degraded'
+  warnings: string[]
+}
+
+export interface DbMaintenanceResult {
+  database: DatabaseMaintenanceReport & { reclaimedBytes: number }
+  artifacts: ArtifactStoreSize[]
+  totalBytes: number
+  health: DatabaseHealth
+  durationMs: number
+  completedAt: number
+}
+
+export class MaintenanceInProgressError extends Error {
+  readonly status = 409
+  constructor() {
+    super('Database maintenance is already running')
+  }
+}
+
+let running = false
+
+/**
+ * VACUUM/ANALYZE the database, rebuild full-text indexes, and measure the
+ * on-disk stores. Emits maintenance:completed when done. Writers are blocked
+ * for the duration on sqlite, so this only runs when asked.
+ */
+export async function runDbMaintenance(runtime: RuntimeContext): Promise<DbMaintenanceResult> {
+  if (running) throw new MaintenanceInProgressError()
+  running = true
+  try {
+    const startedAt = Date.now()
+    const report = await runtime.repositories.maintenance.run()
+    const artifacts = await Promise.all([
+      measureStore('sessions', runtime.sessionFilesRoot),
+      measureStore('attachments', runtime.attachments.dir),
+      measureStore('tasks', runtime.tasksDir),
+      measureStore('notes', runtime.notesDir),
+      measureStore('traces', runtime.debugTraces.dir),
+    ])
+
+    const warnings: string[] = []
+    if (report.integrity !== null && report.integrity !== 'ok') {
+      warnings.push(`Integrity check failed: ${report.integrity}`)
+    }
+    const result: DbMaintenanceResult = {
+      database: { ...report, reclaimedBytes: Math.max(0, report.sizeBytesBefore - report.sizeBytesAfter) },
+      artifacts,
+      totalBytes: report.sizeBytesAfter + artifacts.reduce((sum, store) => sum + store.bytes, 0),
+      health: { status: warnings.length > 0 ? 'degraded' : 'ok', warnings },
+      durationMs: Date.now() - startedAt,
+      completedAt: Date.now(),
+    }
+
+    logger.info({ database: result.database, health: result.health }, 'Database maintenance completed')
+    runtime.events.emit({
+      type: EVENT_TYPES.MAINTENANCE_COMPLETED,
+      agent_id: 'maintenance',
+      session_id: 'maintenance',
+      timestamp: result.completedAt,
+      payload: result,
+    })
+    return result
+  } finally {
+    running = false
+  }
+}
+
+async function measureStore(name: ArtifactStoreSize['name'], root: string): Promise<ArtifactStoreSize> {
+  const size = { name, path: root, bytes: 0, files: 0 }
+  const walk = async (dir: string): Promise<void> => {
+    let entries
+    try {
+      entries = await fs.readdir(dir, { withFileTypes: true })
+    } catch {
+      return
+    }
+    for (const entry of entries) {
+      const full = path.join(dir, entry.name)
+      if (entry.isDirectory()) {
+        await walk(full)
+      } else if (entry.isFile()) {
+        size.bytes += (await fs.stat(full)).size
+        size.files++
+      }
+    }
+  }
+  await walk(root)
+  return size
+}
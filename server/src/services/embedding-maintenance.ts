@@ -0,0 +1,252 @@
+import { createHash } from 'crypto'
+import fs from 'fs/promises'
+import path from 'path'
+import { EVENT_TYPES, type EventPayloads } from '../events/types.js'
+import { logger } from '../lib/logger.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { KnowledgeSource } from '../repositories/types.js'
+import { embed, indexAttachment, needsEmbedding } from './attachment-index.js'
+import type { KnowledgeIndexRuntime } from './knowledge-base.js'
+import { memoryEmbeddingModel } from './memory.js'
+import type { PdfExtraction } from './pdf-extraction.js'
+import { getProject, ingestProject, listProjects, walkProject, type Project } from './projects.js'
+
+/** Pause between embedding requests so a backlog does not use up the provider's rate limit. */
+const EMBED_PAUSE_MS = 200
+
+export type EmbeddingMaintenanceResult = Omit<EventPayloads['embeddings:completed'], 'durationMs' | 'completedAt'>
+
+type MaintenanceRuntime = KnowledgeIndexRuntime
+  & Pick<RuntimeContext, 'repositories' | 'events' | 'projectsDir' | 'knowledgeIndexer'>
+
+export class EmbeddingMaintenanceInProgressError extends Error {
+  constructor() {
+    super('Embedding maintenance is already running')
+  }
+}
+
+interface IndexedFile {
+  path: string
+  hash: string
+}
+
+/**
+ * Keeps semantic search fresh without blocking requests. Each run re-indexes
+ * knowledge sources and projects whose files changed since they were indexed
+ * (a newer modification time, confirmed by content hash), then embeds
+ * documents and memories that have no vectors from the current model, at
+ * most `maxDocuments` documents per run. Conversations are not covered:
+ * history.search embeds its candidates at query time.
+ */
+export class EmbeddingMaintenance {
+  private timer: NodeJS.Timeout | null = null
+  private running = false
+
+  constructor(
+    private readonly runtime: MaintenanceRuntime,
+    private readonly options: { intervalMs: number; maxDocuments: number; pauseMs?: number },
+  ) {}
+
+  start(): void {
+    if (this.timer) return
+    void this.runScheduled()
+    this.timer = setInterval(() => {
+      void this.runScheduled()
+    }, this.options.intervalMs)
+    this.timer.unref()
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer)
+      this.timer = null
+    }
+  }
+
+  /** Throws EmbeddingMaintenanceInProgressError while another run is going. */
+  async run(): Promise<EmbeddingMaintenanceResult> {
+    if (this.running) throw new EmbeddingMaintenanceInProgressError()
+    this.running = true
+    const startedAt = Date.now()
+    const result: EmbeddingMaintenanceResult = { knowledgeSources: 0, projects: 0, documents: 0, memories: 0, throttled: false }
+    try {
+      await this.refreshKnowledgeSources(result)
+      await this.refreshProjects(result)
+      await this.embedDocuments(result)
+      await this.embedMemories(result)
+    } finally {
+      this.running = false
+    }
+
+    this.runtime.events.emit({
+      type: EVENT_TYPES.EMBEDDINGS_COMPLETED,
+      agent_id: 'embeddings',
+      session_id: 'embeddings',
+      timestamp: Date.now(),
+      payload: { ...result, durationMs: Date.now() - startedAt, completedAt: Date.now() },
+    })
+    return result
+  }
+
+  private async refreshKnowledgeSources(result: EmbeddingMaintenanceResult): Promise<void> {
+    const repo = this.runtime.repositories.knowledge
+    // A web page has no cheap change check; reindexing it is left to the user
+    const sources = (await repo.listByStatus(['ready'])).filter((source) => source.kind !== 'url')
+    for (const [index, source] of sources.entries()) {
+      this.signal.throwIfAborted()
+      if (await knowledgeSourceChanged(source)) {
+        await repo.update(source.id, { status: 'pending', error: null })
+        this.runtime.knowledgeIndexer?.enqueue(source.id)
+        result.knowledgeSources++
+      }
+      this.progress('knowledge', index + 1, sources.length)
+    }
+  }
+
+  private async refreshProjects(result: EmbeddingMaintenanceResult): Promise<void> {
+    const projects: Array<{ userId: string; project: Project }> = []
+    for (const user of await this.runtime.repositories.users.list()) {
+      for (const summary of await listProjects(this.runtime, user.id)) {
+        const project = await getProject(this.runtime, user.id, summary.name)
+        if (project) projects.push({ userId: user.id, project })
+      }
+    }
+    for (const [index, { userId, project }] of projects.entries()) {
+      this.signal.throwIfAborted()
+      if (await folderChanged(project.root, project.files, project.indexedAt)) {
+        try {
+          // Unchanged files keep their stored vectors, so only edits are embedded again
+          await ingestProject(this.runtime, userId, { root: project.root, name: project.name, signal: this.signal })
+          result.projects++
+        } catch (err) {
+          if (this.signal.aborted) throw err
+          logger.warn({ project: project.name, err: err instanceof Error ? err.message : String(err) }, 'Project re-index failed')
+        }
+      }
+      this.progress('projects', index + 1, projects.length)
+    }
+  }
+
+  private async embedDocuments(result: EmbeddingMaintenanceResult): Promise<void> {
+    const hashes = new Set<string>()
+    for (const source of await this.runtime.repositories.knowledge.listByStatus(['ready'])) {
+      for (const document of source.documents) hashes.add(document.hash)
+    }
+    for (const user of await this.runtime.repositories.users.list()) {
+      for (const summary of await listProjects(this.runtime, user.id)) {
+        for (const file of (await getProject(this.runtime, user.id, summary.name))?.files ?? []) hashes.add(file.hash)
+      }
+    }
+
+    const pending: Array<{ hash: string; chunks: string[] }> = []
+    for (const hash of hashes) {
+      const extraction = await this.runtime.attachments.getExtraction<PdfExtraction>(hash)
+      if (extraction && await needsEmbedding(this.runtime, hash, extraction.chunks.length)) {
+        pending.push({ hash, chunks: extraction.chunks })
+      }
+    }
+    const batch = pending.slice(0, this.options.maxDocuments)
+    result.throttled = pending.length > batch.length
+    for (const [index, { hash, chunks }] of batch.entries()) {
+      this.signal.throwIfAborted()
+      await indexAttachment(this.runtime, hash, chunks, this.signal)
+      if (!(await needsEmbedding(this.runtime, hash, chunks.length))) result.documents++
+      this.progress('documents', index + 1, batch.length)
+      await this.pause()
+    }
+  }
+
+  private async embedMemories(result: EmbeddingMaintenanceResult): Promise<void> {
+    const modelId = memoryEmbeddingModel(this.runtime.config)
+    if (!modelId) return
+    const repo = this.runtime.repositories.memories
+    for (const user of await this.runtime.repositories.users.list()) {
+      this.signal.throwIfAborted()
+      const stale = (await repo.listByUser(user.id)).filter((memory) => memory.embeddingModel !== modelId || !memory.embedding)
+      if (stale.length === 0) continue
+      try {
+        const vectors = await embed(this.runtime, modelId, stale.map((memory) => memory.content), this.signal)
+        if (!vectors) return
+        for (const [index, memory] of stale.entries()) {
+          await repo.update(memory.id, { embedding: vectors[index], embeddingModel: modelId })
+          result.memories++
+          this.progress('memories', index + 1, stale.length)
+        }
+      } catch (err) {
+        if (this.signal.aborted) throw err
+        logger.warn({ model: modelId, err: err instanceof Error ? err.message : String(err) }, 'Memory re-embedding failed')
+      }
+      await this.pause()
+    }
+  }
+
+  private get signal(): AbortSignal {
+    return this.runtime.shutdownController.signal
+  }
+
+  private progress(phase: EventPayloads['embeddings:progress']['phase'], done: number, total: number): void {
+    this.runtime.events.emit({
+      type: EVENT_TYPES.EMBEDDINGS_PROGRESS,
+      agent_id: 'embeddings',
+      session_id: 'embeddings',
+      timestamp: Date.now(),
+      payload: { phase, done, total },
+    })
+  }
+
+  private async pause(): Promise<void> {
+    const ms = this.options.pauseMs ?? EMBED_PAUSE_MS
+    if (ms > 0) await new Promise((resolve) => setTimeout(resolve, ms))
+  }
+
+  private async runScheduled(): Promise<void> {
+    if (this.running) return
+    try {
+      const result = await this.run()
+      if (result.knowledgeSources > 0 || result.projects > 0 || result.documents > 0 || result.memories > 0) {
+        logger.info(result, 'Embedding index refreshed')
+      }
+    } catch (err) {
+      if (!this.signal.aborted) logger.warn({ err }, 'Embedding maintenance failed')
+    }
+  }
+}
+
+async function knowledgeSourceChanged(source: KnowledgeSource): Promise<boolean> {
+  const indexedAt = source.indexedAt ?? 0
+  if (source.kind === 'folder') return folderChanged(source.location, source.documents, indexedAt)
+  const document = source.documents[0]
+  return !document || fileChanged(source.location, document.hash, indexedAt)
+}
+
+/**
+ * True when an indexed file was removed or edited, or a file newer than the
+ * index appeared. Files the index left out (binary, empty) only count once
+ * they change, so they do not trigger a re-index on every run.
+ */
+async function folderChanged(root: string, files: IndexedFile[], indexedAt: number): Promise<boolean> {
+  const indexed = new Map(files.map((file) => [file.path, file.hash]))
+  const { paths } = await walkProject(root)
+  const current = new Set(paths)
+  for (const file of files) {
+    if (!current.has(file.path)) return true
+  }
+  for (const relative of paths) {
+    const hash = indexed.get(relative)
+    const absolute = path.join(root, relative)
+    if (hash ? await fileChanged(absolute, hash, indexedAt) : await modifiedSince(absolute, indexedAt)) return true
+  }
+  return false
+}
+
+async function fileChanged(file: string, hash: string, indexedAt: number): Promise<boolean> {
+  // The modification time is a cheap first check; the content hash decides
+  if (!(await modifiedSince(file, indexedAt))) return false
+  const data = await fs.readFile(file).catch(() => null)
+  return !data || createHash('sha256').update(data).digest('hex') !== hash
+}
+
+async function modifiedSince(file: string, since: number): Promise<boolean> {
+  const stat = await fs.stat(file).catch(() => null)
+  return !stat || stat.mtimeMs > since
+}
@@ -0,0 +1,368 @@
+import fs from 'fs/promises'
+import { UNPACED } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import { logger } from '../lib/logger.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { ARTIFACT_REFERENCE_HEADER } from '../orchestrator/output.js'
+import type { EntityKind, EntityRecord, EntityRepository, PreferenceRepository, SessionRepository } from '../repositories/types.js'
+import { resolveManagedFilePath } from '../tools/path-policy.js'
+import { cosine, embed, tokenize } from './attachment-index.js'
+import { memoryEmbeddingModel, type MemoryStoreDeps } from './memory.js'
+import { memoryBlockedReason } from './memory-settings.js'
+
+/** Tool names (usually MCP tools, e.g. mcp.gmail_1a2b3c4d.search) whose results are read for entities. */
+const INTEGRATION_TOOL_PATTERN = /gmail|gcal|calendar/i
+const EMAIL_PATTERN = /[\w.+-]+@(?:[a-z0-9-]+\.)+[a-z]{2,}/gi
+/** `Name <address>` as in From, To and Cc headers. */
+const NAMED_ADDRESS_PATTERN = /(?:"([^"\n]{1,80})"|([^\s"<>,:;][^"<>,:;\n]{0,79}?))\s*<([\w.+-]+@(?:[a-z0-9-]+\.)+[a-z]{2,})>/gi
+const AUTOMATED_SENDER = /^(?:no-?reply|do-?not-?reply|notifications?|mailer-daemon|postmaster|bounces?)(?:[+.-]|$)/i
+/** Calendar groups and rooms have addresses too, but are not people. */
+const NON_PERSON_DOMAINS = /(?:^|\.)(?:group|resource)\.calendar\.google\.com$/i
+/** Personal mailbox providers say nothing about where someone works. */
+const FREE_MAIL_DOMAINS = new Set([
+  'gmail.com', 'googlemail.com', 'outlook.com', 'hotmail.com', 'live.com', 'msn.com', 'yahoo.com', 'icloud.com',
+  'me.com', 'mac.com', 'aol.com', 'proton.me', 'protonmail.com', 'gmx.com', 'gmx.de', 'fastmail.com', 'hey.com',
+  'yandex.com', 'zoho.com', 'wp.pl', 'o2.pl', 'onet.pl', 'interia.pl',
+])
+const MAX_ENTITIES_PER_RESULT = 100
+const MAX_ATTENDEES = 10
+const DEFAULT_LOOKUP_LIMIT = 5
+const MAX_LOOKUP_LIMIT = 20
+
+export interface ExtractedEntity {
+  kind: EntityKind
+  key: string
+  /** Undefined when only the address is known; an existing name is then kept. */
+  name?: string
+  email?: string
+  details?: Record<string, string>
+}
+
+export interface EntityLookupResult {
+  id: string
+  kind: EntityKind
+  name: string
+  email: string | null
+  details: Record<string, string>
+  mentions: number
+  lastSeenAt: number
+  score: number
+}
+
+export type EntityStoreDeps = Pick<MemoryStoreDeps, 'config' | 'providers'> & { entities: EntityRepository }
+export type EntityToolDeps = EntityStoreDeps & { sessions: SessionRepository; preferences: PreferenceRepository }
+
+export function isIntegrationTool(name: string): boolean {
+  return INTEGRATION_TOOL_PATTERN.test(name)
+}
+
+/**
+ * People, organizations and recurring meetings in an email or calendar tool
+ * result. JSON results are walked for attendee-style objects and recurring
+ * events; every string is also scanned for `Name <address>` headers and bare
+ * addresses. Automated senders and calendar rooms are skipped, and an
+ * organization is only inferred from a company domain.
+ */
+export function extractEntities(output: unknown): ExtractedEntity[] {
+  const found = new Map<string, ExtractedEntity>()
+  const add = (entity: ExtractedEntity) => {
+    const id = `${entity.kind}:${entity.key}`
+    const existing = found.get(id)
+    if (existing) {
+      existing.name ??= entity.name
+      existing.details = { ...existing.details, ...entity.details }
+    } else if (found.size < MAX_ENTITIES_PER_RESULT) {
+      found.set(id, { ...entity })
+    }
+  }
+  const addPerson = (address: string, name?: string) => {
+    const email = address.toLowerCase()
+    const [local, domain] = email.split('@')
+    if (AUTOMATED_SENDER.test(local) || NON_PERSON_DOMAINS.test(domain)) return
+    const organization = organizationOf(domain)
+    const cleanName = name?.trim().replace(/^['"]|['"]$/g, '').trim()
+    add({
+      kind: 'person',
+      key: email,
+      name: cleanName && !cleanName.includes('@') ? cleanName : undefined,
+      email,
+      details: organization ? { organization: organization.name } : {},
+    })
+    if (organization) add({ kind: 'organization', key: organization.domain, name: organization.name, details: { domain: organization.domain } })
+  }
+
+  const scan = (text: string) => {
+    const named = new Set<string>()
+    for (const match of text.matchAll(NAMED_ADDRESS_PATTERN)) {
+      addPerson(match[3], match[1] ?? match[2])
+      named.add(match[3].toLowerCase())
+    }
+    for (const match of text.matchAll(EMAIL_PATTERN)) {
+      if (!named.has(match[0].toLowerCase())) addPerson(match[0])
+    }
+  }
+
+  const visit = (value: unknown, depth: number) => {
+    if (depth > 12 || value === null || value === undefined) return
+    if (typeof value === 'string') {
+      // MCP tools often return JSON as text
+      const parsed = parseJson(value)
+      if (parsed !== undefined) visit(parsed, depth + 1)
+      else scan(value)
+      return
+    }
+    if (Array.isArray(value)) {
+      for (const entry of value) visit(entry, depth + 1)
+      return
+    }
+    if (typeof value !== 'object') return
+    const record = value as Record<string, unknown>
+    if (typeof record.email === 'string' && record.resource !== true && record.self !== true) {
+      const name = typeof record.displayName === 'string' ? record.displayName : typeof record.name === 'string' ? record.name : undefined
+      if (isEmail(record.email)) addPerson(record.email, name)
+    }
+    const meeting = recurringMeeting(record)
+    if (meeting) add(meeting)
+    for (const [key, entry] of Object.entries(record)) {
+      if (key === 'email' && typeof entry === 'string') continue
+      visit(entry, depth + 1)
+    }
+  }
+
+  visit(output, 0)
+  return [...found.values()]
+}
+
+/**
+ * Stores entities for a user: a known one gains a mention, takes a newly
+ * learned name and merged details, and is embedded again when it changed.
+ */
+export async function recordEntities(
+  deps: EntityStoreDeps,
+  userId: string,
+  entities: ExtractedEntity[],
+  signal?: AbortSignal,
+): Promise<EntityRecord[]> {
+  const stored: EntityRecord[] = []
+  for (const entity of entities) {
+    const existing = await deps.entities.findByKey(userId, entity.kind, entity.key)
+    if (!existing) {
+      stored.push(await deps.entities.create({
+        userId,
+        kind: entity.kind,
+        key: entity.key,
+        name: entity.name ?? entity.email ?? entity.key,
+        email: entity.email ?? null,
+        details: entity.details ?? {},
+      }))
+      continue
+    }
+    const name = entity.name ?? existing.name
+    const details = { ...existing.details, ...entity.details }
+    const changed = name !== existing.name || JSON.stringify(details) !== JSON.stringify(existing.details)
+    const record = await deps.entities.update(existing.id, {
+      name,
+      details,
+      mentions: existing.mentions + 1,
+      lastSeenAt: Date.now(),
+      ...(changed && { embedding: null, embeddingModel: null }),
+    })
+    if (record) stored.push(record)
+  }
+  await embedEntities(deps, stored.filter((entity) => !entity.embedding), signal)
+  return stored
+}
+
+/**
+ * A user's entities best matching a name or description, e.g. "Sarah" or
+ * "design review". Each is scored by the share of query terms it contains,
+ * plus embedding similarity when every candidate is embedded with the
+ * configured model. Ties go to the entity seen more often.
+ */
+export async function lookupEntities(
+  deps: EntityStoreDeps,
+  userId: string,
+  query: string,
+  options: { kind?: EntityKind; limit?: number; signal?: AbortSignal } = {},
+): Promise<EntityLookupResult[]> {
+  const limit = Math.min(Math.max(1, Math.floor(options.limit ?? DEFAULT_LOOKUP_LIMIT)), MAX_LOOKUP_LIMIT)
+  const entities = (await deps.entities.listByUser(userId)).filter((entity) => !options.kind || entity.kind === options.kind)
+  if (entities.length === 0) return []
+
+  const keyword = keywordScores(entities, query)
+  const semantic = await semanticScores(deps, entities, query, options.signal)
+  return entities
+    .map((entity, index) => ({
+      id: entity.id,
+      kind: entity.kind,
+      name: entity.name,
+      email: entity.email,
+      details: entity.details,
+      mentions: entity.mentions,
+      lastSeenAt: entity.lastSeenAt,
+      score: keyword[index] + (semantic?.[index] ?? 0),
+    }))
+    .filter((result) => result.score > 0)
+    .sort((a, b) => b.score - a.score || b.mentions - a.mentions)
+    .slice(0, limit)
+}
+
+/** The text an entity is embedded and matched by. */
+export function entityText(entity: Pick<EntityRecord, 'kind' | 'name' | 'email' | 'details'>): string {
+  const details = Object.entries(entity.details).map(([key, value]) => `${key}: ${value}`)
+  return [`${entity.name} (${entity.kind})`, entity.email, ...details].filter(Boolean).join('\n')
+}
+
+async function embedEntities(deps: EntityStoreDeps, entities: EntityRecord[], signal?: AbortSignal): Promise<void> {
+  const modelId = memoryEmbeddingModel(deps.config)
+  if (!modelId || entities.length === 0) return
+  try {
+    const vectors = await embed(deps, modelId, entities.map(entityText), signal)
+    if (!vectors) return
+    for (const [index, entity] of entities.entries()) {
+      const updated = await deps.entities.update(entity.id, { embedding: vectors[index], embeddingModel: modelId })
+      if (updated) Object.assign(entity, { embedding: updated.embedding, embeddingModel: updated.embeddingModel })
+    }
+  } catch (err) {
+    if (signal?.aborted) throw err
+    logger.warn({ model: modelId, err: err instanceof Error ? err.message : String(err) }, 'Entity embedding failed')
+  }
+}
+
+async function semanticScores(
+  deps: EntityStoreDeps,
+  entities: EntityRecord[],
+  query: string,
+  signal?: AbortSignal,
+): Promise<number[] | null> {
+  const modelId = memoryEmbeddingModel(deps.config)
+  if (!modelId || entities.some((entity) => !entity.embedding || entity.embeddingModel !== modelId)) return null
+  let queryVector: number[] | undefined
+  try {
+    queryVector = (await embed(deps, modelId, [query], signal))?.[0]
+  } catch (err) {
+    logger.warn({ model: modelId, err: err instanceof Error ? err.message : String(err) }, 'Entity query embedding failed')
+  }
+  if (!queryVector) return null
+  return entities.map((entity) => Math.max(0, cosine(queryVector, entity.embedding!)))
+}
+
+/** Share of the query's terms found in each entity's name, address or details. */
+function keywordScores(entities: EntityRecord[], query: string): number[] {
+  const terms = [...new Set(tokenize(query))]
+  if (terms.length === 0) return entities.map(() => 0)
+  return entities.map((entity) => {
+    const tokens = new Set(tokenize(entityText(entity)))
+    return terms.filter((term) => tokens.has(term)).length / terms.length
+  })
+}
+
+function recurringMeeting(event: Record<string, unknown>): ExtractedEntity | null {
+  if (typeof event.summary !== 'string' || !event.summary.trim()) return null
+  const recurringId = typeof event.recurringEventId === 'string' ? event.recurringEventId : null
+  const rules = Array.isArray(event.recurrence) ? event.recurrence.filter((rule): rule is string => typeof rule === 'string') : []
+  if (!recurringId && rules.length === 0) return null
+  const key = recurringId ?? (typeof event.id === 'string' ? event.id : null)
+  if (!key) return null
+
+  const details: Record<string, string> = {}
+  const rule = rules.find((entry) => entry.startsWith('RRULE:'))
+  if (rule) details.recurrence = rule.slice('RRULE:'.length)
+  const start = (event.start as { dateTime?: unknown } | undefined)?.dateTime
+  if (typeof start === 'string') details.time = start
+  const organizer = (event.organizer as { email?: unknown } | undefined)?.email
+  if (typeof organizer === 'string' && isEmail(organizer)) details.organizer = organizer.toLowerCase()
+  const attendees = Array.isArray(event.attendees)
+    ? event.attendees
+      .filter((attendee) => attendee && typeof attendee === 'object' && (attendee as { resource?: unknown }).resource !== true)
+      .map((attendee) => (attendee as { email?: unknown }).email)
+      .filter((email): email is string => typeof email === 'string' && isEmail(email))
+    : []
+  if (attendees.length > 0) details.attendees = attendees.slice(0, MAX_ATTENDEES).map((email) => email.toLowerCase()).join(', ')
+  return { kind: 'meeting', key, name: event.summary.trim(), details }
+}
+
+/** The company behind an email domain, or null for personal mailbox providers. */
+function organizationOf(domain: string): { domain: string; name: string } | null {
+  const labels = domain.split('.')
+  if (labels.length < 2) return null
+  // acme.com, but mail.acme.co.uk -> acme.co.uk
+  const secondLevel = labels.length >= 3 && labels.at(-1)!.length === 2 && labels.at(-2)!.length <= 3
+  const base = labels.slice(secondLevel ? -3 : -2)
+  const registrable = base.join('.')
+  if (FREE_MAIL_DOMAINS.has(registrable)) return null
+  const name = base[0].charAt(0).toUpperCase() + base[0].slice(1)
+  return { domain: registrable, name }
+}
+
+function isEmail(value: string): boolean {
+  return new RegExp(`^${EMAIL_PATTERN.source}$`, 'i').test(value.trim())
+}
+
+function parseJson(text: string): unknown {
+  const trimmed = text.trim()
+  if (!trimmed.startsWith('{') && !trimmed.startsWith('[')) return undefined
+  try {
+    return JSON.parse(trimmed)
+  } catch {
+    return undefined
+  }
+}
+
+/**
+ * Learns entities from every successful email and calendar tool call, one
+ * result at a time in the background. Results too large to inline are read
+ * back from the artifact they were saved to.
+ */
+export class EntityExtractor {
+  private iterator: AsyncIterator<AgentEvent> | null = null
+
+  constructor(private readonly runtime: Pick<RuntimeContext, 'config' | 'providers' | 'repositories' | 'events' | 'sessionFilesRoot' | 'notesDir' | 'shutdownController'>) {}
+
+  start(): void {
+    if (this.iterator) return
+    const iterator = this.runtime.events.subscribe({ types: [EVENT_TYPES.TOOL_COMPLETED] }, UNPACED)[Symbol.asyncIterator]()
+    this.iterator = iterator
+    void (async () => {
+      for (let next = await iterator.next(); !next.done; next = await iterator.next()) {
+        const event = next.value
+        if (event.type !== EVENT_TYPES.TOOL_COMPLETED || !event.payload.success || !isIntegrationTool(event.payload.name)) continue
+        await this.extract(event.session_id, event.payload.output)
+      }
+    })()
+  }
+
+  stop(): void {
+    void this.iterator?.return?.()
+    this.iterator = null
+  }
+
+  private async extract(sessionId: string, output: string): Promise<void> {
+    try {
+      const session = await this.runtime.repositories.sessions.getById(sessionId)
+      if (!session) return
+      // Learned entities are memory too, so the same switches apply
+      if (await memoryBlockedReason(this.runtime.repositories.preferences, sessionId)) return
+      const entities = extractEntities(await this.fullOutput(sessionId, output))
+      if (entities.length === 0) return
+      const deps = { config: this.runtime.config, providers: this.runtime.providers, entities: this.runtime.repositories.entities }
+      await recordEntities(deps, session.userId, entities, this.runtime.shutdownController.signal)
+      logger.debug({ sessionId, count: entities.length }, 'Entities updated')
+    } catch (err) {
+      if (!this.runtime.shutdownController.signal.aborted) logger.warn({ err, sessionId }, 'Entity extraction failed')
+    }
+  }
+
+  private async fullOutput(sessionId: string, output: string): Promise<string> {
+    if (!output.startsWith(ARTIFACT_REFERENCE_HEADER)) return output
+    const ref = output.split('\n')[1]?.trim()
+    if (!ref) return output
+    const file = resolveManagedFilePath(ref, {
+      sessionFilesRoot: this.runtime.sessionFilesRoot,
+      notesDir: this.runtime.notesDir,
+      sessionId,
+      access: 'read',
+    })
+    return fs.readFile(file.fsPath, 'utf-8')
+  }
+}
@@ -0,0 +1,216 @@
+import { randomUUID } from 'node:crypto'
+import { logger } from '../lib/logger.js'
+import { isControllerPromptName, type ControllerPrompts } from '../orchestrator/prompts.js'
+import type { ControllerPromptSource } from '../orchestrator/types.js'
+import type {
+  AgentRepository,
+  ExperimentRepository,
+  PreferenceRepository,
+  UsageRepository,
+} from '../repositories/types.js'
+
+export const PROMPT_EXPERIMENT_PREFERENCE_KEY = 'prompt_experiment'
+
+export interface ExperimentVariant {
+  name: string
+  /** Model the arm's sessions run on; unset keeps the agent's usual model. */
+  model?: string
+  /** Controller prompts the arm replaces; the rest are the stored prompts. */
+  prompts?: Partial<ControllerPrompts>
+}
+
+export interface PromptExperiment {
+  id: string
+  name: string
+  variants: [ExperimentVariant, ExperimentVariant]
+  startedAt: number
+  /** Set once stopped: new sessions are no longer enrolled, and the results stay readable. */
+  stoppedAt: number | null
+}
+
+export interface VariantResults {
+  variant: string
+  sessions: number
+  completed: number
+  /** Failed or cancelled. */
+  failed: number
+  /** Still running or waiting for input; left out of the rates and averages. */
+  unfinished: number
+  /** Share of finished sessions that completed; null before any finished. */
+  successRate: number | null
+  /** Turns of every agent in the session, averaged over finished sessions. */
+  averageTurns: number | null
+  averageCostUsd: number | null
+  totalCostUsd: number
+}
+
+export interface ExperimentResults {
+  experiment: PromptExperiment
+  variants: VariantResults[]
+}
+
+interface PromptExperimentsOptions {
+  preferences: PreferenceRepository
+  experiments: ExperimentRepository
+  agents: AgentRepository
+  usage: UsageRepository
+  /** The prompts sessions outside the experiment get, and the base the variants override. */
+  prompts: ControllerPromptSource
+}
+
+/** Validate user input. Throws with a message suitable for a 400. */
+export function normalizeExperimentInput(input: { name?: unknown; variants?: unknown }): Pick<PromptExperiment, 'name' | 'variants'> {
+  if (typeof input.name !== 'string' || !input.name.trim()) throw new Error('name is required')
+  if (!Array.isArray(input.variants) || input.variants.length !== 2) throw new Error('variants must list exactly two variants')
+  const variants = input.variants.map((raw: unknown, index): ExperimentVariant => {
+    const variant = (raw ?? {}) as Record<string, unknown>
+    if (typeof variant.name !== 'string' || !variant.name.trim()) throw new Error(`variants[${index}].name is required`)
+    const normalized: ExperimentVariant = { name: variant.name.trim() }
+    if (variant.model !== undefined) {
+      if (typeof variant.model !== 'string' || !variant.model.includes(':')) {
+        throw new Error(`variants[${index}].model must be a provider:model id`)
+      }
+      normalized.model = variant.model
+    }
+    if (variant.prompts !== undefined) {
+      if (typeof variant.prompts !== 'object' || variant.prompts === null) throw new Error(`variants[${index}].prompts must be an object`)
+      const prompts: Partial<ControllerPrompts> = {}
+      for (const [name, content] of Object.entries(variant.prompts)) {
+        if (!isControllerPromptName(name)) throw new Error(`variants[${index}].prompts has an unknown prompt: ${name}`)
+        if (typeof content !== 'string' || !content.trim()) throw new Error(`variants[${index}].prompts.${name} cannot be empty`)
+        prompts[name] = content
+      }
+      if (Object.keys(prompts).length > 0) normalized.prompts = prompts
+    }
+    return normalized
+  }) as [ExperimentVariant, ExperimentVariant]
+  if (variants[0].name === variants[1].name) throw new Error('variants need different names')
+  return { name: input.name.trim(), variants }
+}
+
+/**
+ * A/B comparison of controller prompts or models. While an experiment runs,
+ * new sessions alternate between its two variants and keep theirs for every
+ * later run, so each arm's success rate, turn count and cost can be compared.
+ * One experiment is kept at a time; starting another replaces it.
+ */
+export class PromptExperiments implements ControllerPromptSource {
+  constructor(private readonly options: PromptExperimentsOptions) {}
+
+  async current(): Promise<PromptExperiment | null> {
+    const raw = await this.options.preferences.get(PROMPT_EXPERIMENT_PREFERENCE_KEY)
+    if (!raw) return null
+    try {
+      return JSON.parse(raw) as PromptExperiment
+    } catch {
+      logger.warn('Ignoring malformed prompt_experiment preference')
+      return null
+    }
+  }
+
+  async start(input: { name?: unknown; variants?: unknown }): Promise<PromptExperiment> {
+    const experiment: PromptExperiment = {
+      id: randomUUID(),
+      ...normalizeExperimentInput(input),
+      startedAt: Date.now(),
+      stoppedAt: null,
+    }
+    await this.options.preferences.set(PROMPT_EXPERIMENT_PREFERENCE_KEY, JSON.stringify(experiment))
+    return experiment
+  }
+
+  async stop(): Promise<PromptExperiment | null> {
+    const experiment = await this.current()
+    if (!experiment || experiment.stoppedAt !== null) return experiment
+    const stopped = { ...experiment, stoppedAt: Date.now() }
+    await this.options.preferences.set(PROMPT_EXPERIMENT_PREFERENCE_KEY, JSON.stringify(stopped))
+    return stopped
+  }
+
+  /**
+   * Puts a new session in the next arm and returns that variant. Returns null
+   * when no experiment is running, or when it compares models and the caller
+   * picked one, since overriding that choice would skew both arms.
+   */
+  async enroll(sessionId: string, options: { modelPinned: boolean }): Promise<ExperimentVariant | null> {
+    const experiment = await this.current()
+    if (!experiment || experiment.stoppedAt !== null) return null
+    if (options.modelPinned && experiment.variants.some((variant) => variant.model)) return null
+    const enrolled = await this.options.experiments.listByExperiment(experiment.id)
+    const variant = experiment.variants[enrolled.length % 2]
+    await this.options.experiments.assign({ sessionId, experimentId: experiment.id, variant: variant.name })
+    return variant
+  }
+
+  async active(sessionId?: string): Promise<ControllerPrompts> {
+    const prompts = await this.options.prompts.active(sessionId)
+    if (!sessionId) return prompts
+    const variant = await this.variantFor(sessionId)
+    return variant?.prompts ? { ...prompts, ...variant.prompts } : prompts
+  }
+
+  /** The arm the session runs in, while its experiment is the current one. */
+  async variantFor(sessionId: string): Promise<ExperimentVariant | null> {
+    const tag = await this.options.experiments.getBySession(sessionId)
+    if (!tag) return null
+    const experiment = await this.current()
+    if (!experiment || experiment.id !== tag.experimentId) return null
+    return experiment.variants.find((variant) => variant.name === tag.variant) ?? null
+  }
+
+  /** Per-variant outcome of the current experiment; a session counts by how its latest run ended. */
+  async results(): Promise<ExperimentResults | null> {
+    const experiment = await this.current()
+    if (!experiment) return null
+    const byVariant = new Map(experiment.variants.map((variant) => [variant.name, emptyResults(variant.name)]))
+    const turns = new Map<string, number>()
+    for (const tag of await this.options.experiments.listByExperiment(experiment.id)) {
+      const results = byVariant.get(tag.variant)
+      const agents = await this.options.agents.listBySession(tag.sessionId)
+      const root = agents.find((agent) => agent.parentId === null)
+      // Purged sessions, and ones whose first run never started, have nothing to count
+      if (!results || !root) continue
+      results.sessions++
+      if (root.status === 'completed') results.completed++
+      else if (root.status === 'failed' || root.status === 'cancelled') results.failed++
+      else {
+        results.unfinished++
+        continue
+      }
+      const cost = (await this.options.usage.list({ sessionId: tag.sessionId })).reduce((sum, record) => sum + record.costUsd, 0)
+      results.totalCostUsd += cost
+      turns.set(tag.variant, (turns.get(tag.variant) ?? 0) + agents.reduce((sum, agent) => sum + agent.turnCount, 0))
+    }
+    return {
+      experiment,
+      variants: [...byVariant.values()].map((results) => {
+        const finished = results.completed + results.failed
+        return {
+          ...results,
+          successRate: finished ? results.completed / finished : null,
+          averageTurns: finished ? (turns.get(results.variant) ?? 0) / finished : null,
+          averageCostUsd: finished ? roundUsd(results.totalCostUsd / finished) : null,
+          totalCostUsd: roundUsd(results.totalCostUsd),
+        }
+      }),
+    }
+  }
+}
+
+function emptyResults(variant: string): VariantResults {
+  return {
+    variant,
+    sessions: 0,
+    completed: 0,
+    failed: 0,
+    unfinished: 0,
+    successRate: null,
+    averageTurns: null,
+    averageCostUsd: null,
+    totalCostUsd: 0,
+  }
+}
+
+function roundUsd(value: number): number {
+  return Math.round(value * 1_000_000) / 1_000_000
+}
@@ -0,0 +1,343 @@
+import { createHash } from 'crypto'
+import { logger } from '../lib/logger.js'
+import { child, children, parseXml, textContent, type XmlElement } from '../lib/xml.js'
+import type { Feed, FeedRepository, NewFeedItem } from '../repositories/types.js'
+import { decodeHtmlEntities, extractReadableHtmlBody } from '../tools/web.js'
+
+/** Feeds larger than this are refused rather than parsed. */
+const MAX_FEED_BYTES = 5 * 1024 * 1024
+const FEED_FETCH_TIMEOUT_MS = 30_000
+/** Older items beyond this many per feed are dropped after each poll. */
+export const MAX_ITEMS_PER_FEED = 500
+const SUMMARY_CHARS = 300
+const MAX_CONTENT_CHARS = 50_000
+const ACCEPT = 'application/rss+xml, application/atom+xml, application/rdf+xml;q=0.9, application/xml;q=0.9, text/xml;q=0.9, text/html;q=0.5, */*;q=0.1'
+
+export class FeedInputError extends Error {}
+
+export interface ParsedFeed {
+  title: string
+  siteUrl: string | null
+  /** Entries without a date have `publishedAt` set to null. */
+  items: Array<Omit<NewFeedItem, 'publishedAt'> & { publishedAt: number | null }>
+}
+
+type FeedStore = { repositories: { feeds: FeedRepository } }
+
+/**
+ * Subscribes the user to a feed. `url` may be the feed itself or a web page
+ * that advertises one with <link rel="alternate">. The feed is fetched once
+ * so a bad URL fails here rather than in the background.
+ */
+export async function subscribeFeed(
+  runtime: FeedStore,
+  userId: string,
+  input: { url: string; title?: string },
+  fetchImpl: typeof fetch = fetch,
+  signal?: AbortSignal,
+): Promise<{ feed: Feed; added: number; existing: boolean }> {
+  const repo = runtime.repositories.feeds
+  const requested = parseHttpUrl(input.url)
+  const known = await repo.findByUrl(userId, requested)
+  if (known) return { feed: known, added: 0, existing: true }
+
+  let fetched = await fetchFeed(requested, {}, fetchImpl, signal)
+  let url = requested
+  if (fetched.kind === 'html') {
+    const discovered = discoverFeedUrl(fetched.text, fetched.url)
+    if (!discovered) throw new FeedInputError(`${requested} is a web page without an RSS or Atom feed link`)
+    url = discovered
+    const existing = await repo.findByUrl(userId, url)
+    if (existing) return { feed: existing, added: 0, existing: true }
+    fetched = await fetchFeed(url, {}, fetchImpl, signal)
+  }
+  if (fetched.kind !== 'feed') throw new FeedInputError(`${url} is not an RSS or Atom feed`)
+
+  const feed = await repo.create({
+    userId,
+    url,
+    title: input.title?.trim() || fetched.feed.title || new URL(url).hostname,
+    siteUrl: fetched.feed.siteUrl,
+  })
+  const added = await storeItems(repo, feed.id, fetched.feed)
+  const updated = await repo.update(feed.id, { etag: fetched.etag, lastModified: fetched.lastModified, fetchedAt: Date.now() })
+  return { feed: updated ?? feed, added, existing: false }
+}
+
+/** Fetches a feed again, sending the validators of the last response; returns how many items were new. */
+export async function refreshFeed(
+  runtime: FeedStore,
+  feed: Feed,
+  fetchImpl: typeof fetch = fetch,
+  signal?: AbortSignal,
+): Promise<number> {
+  const repo = runtime.repositories.feeds
+  try {
+    const fetched = await fetchFeed(feed.url, { etag: feed.etag, lastModified: feed.lastModified }, fetchImpl, signal)
+    if (fetched.kind === 'not_modified') {
+      await repo.update(feed.id, { error: null, fetchedAt: Date.now() })
+      return 0
+    }
+    if (fetched.kind !== 'feed') throw new FeedInputError(`${feed.url} no longer serves an RSS or Atom feed`)
+    const added = await storeItems(repo, feed.id, fetched.feed)
+    await repo.update(feed.id, {
+      siteUrl: fetched.feed.siteUrl ?? feed.siteUrl,
+      etag: fetched.etag,
+      lastModified: fetched.lastModified,
+      error: null,
+      fetchedAt: Date.now(),
+    })
+    return added
+  } catch (err) {
+    if (signal?.aborted) throw err
+    const message = err instanceof Error ? err.message : String(err)
+    await repo.update(feed.id, { error: message, fetchedAt: Date.now() })
+    throw err
+  }
+}
+
+/**
+ * Polls every subscribed feed on an interval so scheduled agents read
+ * fresh items from the database instead of fetching sources themselves.
+ * Feeds are fetched one at a time; a failing feed records its error and
+ * the others carry on.
+ */
+export class FeedPoller {
+  private timer: NodeJS.Timeout | null = null
+  private running: Promise<number> | null = null
+  private readonly controller = new AbortController()
+
+  constructor(
+    private readonly runtime: FeedStore,
+    private readonly options: { intervalMs: number; fetchImpl?: typeof fetch },
+  ) {}
+
+  start(): void {
+    if (this.timer) return
+    void this.pollAll()
+    this.timer = setInterval(() => {
+      void this.pollAll()
+    }, this.options.intervalMs)
+    this.timer.unref()
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer)
+      this.timer = null
+    }
+    this.controller.abort()
+  }
+
+  /** Refreshes every feed; returns how many new items were stored. A poll already under way is joined. */
+  pollAll(): Promise<number> {
+    this.running ??= this.poll().finally(() => {
+      this.running = null
+    })
+    return this.running
+  }
+
+  private async poll(): Promise<number> {
+    let added = 0
+    let feeds: Feed[]
+    try {
+      feeds = await this.runtime.repositories.feeds.listAll()
+    } catch (err) {
+      logger.warn({ err }, 'Could not list feeds to poll')
+      return 0
+    }
+    for (const feed of feeds) {
+      if (this.controller.signal.aborted) break
+      try {
+        added += await refreshFeed(this.runtime, feed, this.options.fetchImpl, this.controller.signal)
+      } catch (err) {
+        if (this.controller.signal.aborted) break
+        logger.warn({ feedId: feed.id, url: feed.url, err: err instanceof Error ? err.message : String(err) }, 'Feed poll failed')
+      }
+    }
+    if (added > 0) logger.info({ feeds: feeds.length, added }, 'Feeds polled')
+    return added
+  }
+}
+
+/** Reads RSS 2.0, RSS 1.0 (RDF) and Atom; null when the document is none of them. */
+export function parseFeed(xml: string, baseUrl?: string): ParsedFeed | null {
+  const document = parseXml(xml)
+  const root = document.children.find((node): node is XmlElement => typeof node !== 'string')
+  if (!root) return null
+
+  if (root.name === 'feed') {
+    return {
+      title: inlineText(textContent(child(root, 'title'))),
+      siteUrl: atomLink(root, baseUrl),
+      items: children(root, 'entry').map((entry) => {
+        const link = atomLink(entry, baseUrl)
+        const title = inlineText(textContent(child(entry, 'title')))
+        const summary = htmlText(textContent(child(entry, 'summary')))
+        const content = htmlText(textContent(child(entry, 'content'))) || summary
+        return item({
+          guid: textContent(child(entry, 'id')).trim(),
+          title,
+          link,
+          author: inlineText(textContent(child(child(entry, 'author') ?? child(root, 'author') ?? entry, 'name'))) || null,
+          summary,
+          content,
+          date: textContent(child(entry, 'published')) || textContent(child(entry, 'updated')),
+        })
+      }),
+    }
+  }
+
+  if (root.name === 'rss' || root.name === 'RDF') {
+    const channel = child(root, 'channel')
+    if (!channel) return null
+    // RSS 1.0 puts items beside the channel, RSS 2.0 inside it
+    const entries = root.name === 'RDF' ? children(root, 'item') : children(channel, 'item')
+    return {
+      title: inlineText(textContent(child(channel, 'title'))),
+      siteUrl: resolveUrl(textContent(child(channel, 'link')), baseUrl),
+      items: entries.map((entry) => {
+        const link = resolveUrl(textContent(child(entry, 'link')), baseUrl)
+        const summary = htmlText(textContent(child(entry, 'description')))
+        return item({
+          guid: textContent(child(entry, 'guid')).trim() || (entry.attrs['rdf:about'] ?? ''),
+          title: inlineText(textContent(child(entry, 'title'))),
+          link,
+          author: inlineText(textContent(child(entry, 'creator')) || textContent(child(entry, 'author'))) || null,
+          summary,
+          content: htmlText(textContent(child(entry, 'encoded'))) || summary,
+          date: textContent(child(entry, 'pubDate')) || textContent(child(entry, 'date')),
+        })
+      }),
+    }
+  }
+  return null
+}
+
+/** The first RSS or Atom feed a web page links to, resolved against the page URL. */
+export function discoverFeedUrl(html: string, pageUrl: string): string | null {
+  for (const [tag] of html.matchAll(/<link\b[^>]*>/gi)) {
+    const attrs = Object.fromEntries(
+      [...tag.matchAll(/([\w-]+)\s*=\s*(["'])([\s\S]*?)\2/g)].map(([, name, , value]) => [name.toLowerCase(), decodeHtmlEntities(value)]),
+    )
+    const rel = (attrs.rel ?? '').toLowerCase().split(/\s+/)
+    if (!rel.includes('alternate') || !/^application\/(rss|atom)\+xml$/i.test(attrs.type ?? '') || !attrs.href) continue
+    return resolveUrl(attrs.href, pageUrl)
+  }
+  return null
+}
+
+type Fetched =
+  | { kind: 'feed'; url: string; feed: ParsedFeed; etag: string | null; lastModified: string | null }
+  | { kind: 'html'; url: string; text: string }
+  | { kind: 'not_modified' }
+  | { kind: 'other'; url: string }
+
+async function fetchFeed(
+  url: string,
+  validators: { etag?: string | null; lastModified?: string | null },
+  fetchImpl: typeof fetch,
+  signal?: AbortSignal,
+): Promise<Fetched> {
+  const timeout = AbortSignal.timeout(FEED_FETCH_TIMEOUT_MS)
+  const headers: Record<string, string> = { Accept: ACCEPT }
+  if (validators.etag) headers['If-None-Match'] = validators.etag
+  if (validators.lastModified) headers['If-Modified-Since'] = validators.lastModified
+  let response: Response
+  try {
+    response = await fetchImpl(url, { headers, signal: signal ? AbortSignal.any([signal, timeout]) : timeout, redirect: 'follow' })
+  } catch (err) {
+    if (signal?.aborted) throw err
+    throw new FeedInputError(`Fetching ${url} failed: ${err instanceof Error ? err.message : String(err)}`)
+  }
+  if (response.status === 304) return { kind: 'not_modified' }
+  if (!response.ok) throw new FeedInputError(`Fetching ${url} failed with HTTP ${response.status}`)
+  const declared = Number(response.headers.get('content-length') ?? 0)
+  if (declared > MAX_FEED_BYTES) throw new FeedInputError(`${url} is too large (${declared} bytes)`)
+  const data = Buffer.from(await response.arrayBuffer())
+  if (data.length > MAX_FEED_BYTES) throw new FeedInputError(`${url} is too large (${data.length} bytes)`)
+
+  const finalUrl = response.url || url
+  const text = data.toString('utf-8')
+  const contentType = response.headers.get('content-type') ?? ''
+  const feed = /<(rss|feed|rdf:RDF)\b/i.test(text.slice(0, 4096)) ? parseFeed(text, finalUrl) : null
+  if (feed) {
+    return { kind: 'feed', url: finalUrl, feed, etag: response.headers.get('etag'), lastModified: response.headers.get('last-modified') }
+  }
+  if (contentType.includes('html') || /^\s*<(!doctype html|html)\b/i.test(text)) return { kind: 'html', url: finalUrl, text }
+  return { kind: 'other', url: finalUrl }
+}
+
+async function storeItems(repo: FeedRepository, feedId: string, feed: ParsedFeed): Promise<number> {
+  const now = Date.now()
+  // Undated entries keep the feed's order by counting back from now
+  const items = feed.items.map((entry, index) => ({ ...entry, publishedAt: entry.publishedAt ?? now - index }))
+  const added = await repo.addItems(feedId, items)
+  await repo.pruneItems(feedId, MAX_ITEMS_PER_FEED)
+  return added
+}
+
+function item(entry: {
+  guid: string
+  title: string
+  link: string | null
+  author: string | null
+  summary: string
+  content: string
+  date: string
+}): ParsedFeed['items'][number] {
+  const published = Date.parse(entry.date.trim())
+  const summarySource = entry.summary || entry.content
+  return {
+    guid: entry.guid || entry.link || createHash('sha1').update(`${entry.title}\n${entry.date}\n${summarySource}`).digest('hex'),
+    title: entry.title || truncate(summarySource, 80) || '(untitled)',
+    link: entry.link,
+    author: entry.author,
+    summary: truncate(summarySource, SUMMARY_CHARS),
+    content: entry.content.slice(0, MAX_CONTENT_CHARS),
+    publishedAt: Number.isFinite(published) ? published : null,
+  }
+}
+
+function atomLink(element: XmlElement, baseUrl?: string): string | null {
+  const links = children(element, 'link')
+  const alternate = links.find((link) => (link.attrs.rel ?? 'alternate') === 'alternate') ?? links[0]
+  return alternate ? resolveUrl(alternate.attrs.href ?? '', baseUrl) : null
+}
+
+function resolveUrl(href: string, baseUrl?: string): string | null {
+  if (!href.trim()) return null
+  try {
+    return new URL(href.trim(), baseUrl).toString()
+  } catch {
+    return null
+  }
+}
+
+function parseHttpUrl(input: string): string {
+  let url: URL
+  try {
+    url = new URL(input.trim())
+  } catch {
+    throw new FeedInputError(`Invalid URL: ${input}`)
+  }
+  if (url.protocol !== 'http:' && url.protocol !== 'https:') throw new FeedInputError('Only http(s) feeds are supported')
+  return url.toString()
+}
+
+/** Feed text is often HTML, escaped once more inside the XML. */
+function htmlText(value: string): string {
+  return /<[a-z!/][^>]*>|&[#\w]+;/i.test(value) ? extractReadableHtmlBody(value) : value.trim()
+}
+
+function inlineText(value: string): string {
+  return htmlText(value).replace(/\s+/g, ' ').trim()
+}
+
+function truncate(text: string, max: number): string {
+  const flat = text.replace(/\s+/g, ' ').trim()
+  if (flat.length <= max) return flat
+  const cut = flat.slice(0, max - 1)
+  const space = cut.lastIndexOf(' ')
+  return `${space > max / 2 ? cut.slice(0, space) : cut}…`
+}
@@ -0,0 +1,74 @@
+import path from 'path'
+import type { KnowledgeSourceRepository } from '../repositories/types.js'
+import { rankChunks, type SearchSource } from './attachment-index.js'
+import type { PdfExtraction } from './pdf-extraction.js'
+import { getProject, linkedProjects, type ProjectSearchDeps } from './projects.js'
+
+export interface FileSearchResult {
+  /** Absolute path of the file the passage came from. */
+  path: string
+  /** Project or knowledge source the file was indexed through. */
+  source: string
+  /** Index of the chunk within the file's text. */
+  chunk: number
+  score: number
+  text: string
+}
+
+export type FileSearchDeps = ProjectSearchDeps & { knowledge: KnowledgeSourceRepository }
+
+/**
+ * Ranks the chunks of every indexed local file the conversation can see —
+ * its linked projects and the user's folder and document knowledge sources —
+ * by embedding similarity to the query. `under` narrows the search to one
+ * folder. Files indexed before an embedding model was configured fall back
+ * to keyword ranking until they are re-indexed.
+ */
+export async function searchLocalFiles(
+  deps: FileSearchDeps,
+  sessionId: string,
+  query: string,
+  options: { under?: string; limit?: number; signal?: AbortSignal } = {},
+): Promise<FileSearchResult[]> {
+  const session = await deps.sessions.getById(sessionId)
+  if (!session) throw new Error(`Session not found: ${sessionId}`)
+  const folder = options.under?.trim()
+  if (folder && !path.isAbsolute(folder)) throw new Error('under must be an absolute folder path')
+  const under = folder ? path.resolve(folder) : null
+
+  // Keyed by absolute path so a folder both linked and in the knowledge base is searched once
+  const files = new Map<string, { source: string; hash: string }>()
+  for (const name of await linkedProjects(deps, sessionId)) {
+    const project = await getProject(deps, session.userId, name)
+    for (const file of project?.files ?? []) {
+      const absolute = path.join(project!.root, file.path)
+      if (!files.has(absolute)) files.set(absolute, { source: name, hash: file.hash })
+    }
+  }
+  for (const source of await deps.knowledge.listByUser(session.userId)) {
+    if (source.status !== 'ready' || source.kind === 'url') continue
+    for (const document of source.documents) {
+      const absolute = source.kind === 'folder' ? path.join(source.location, document.path) : source.location
+      if (!files.has(absolute)) files.set(absolute, { source: source.name, hash: document.hash })
+    }
+  }
+
+  const sources: SearchSource[] = []
+  const found: Array<{ path: string; source: string }> = []
+  for (const [absolute, file] of files) {
+    if (under && absolute !== under && !absolute.startsWith(under + path.sep)) continue
+    const extraction = await deps.attachments.getExtraction<PdfExtraction>(file.hash)
+    if (!extraction?.chunks.length) continue
+    // Search sources are named by position so results map back to their file
+    sources.push({ name: String(found.length), hash: file.hash, chunks: extraction.chunks })
+    found.push({ path: absolute, source: file.source })
+  }
+  if (sources.length === 0) {
+    throw new Error(under
+      ? `No indexed files under ${under}`
+      : 'No indexed local files: link a project to this conversation or add a folder to the knowledge base')
+  }
+
+  const results = await rankChunks(deps, sources, query, options)
+  return results.map(({ attachment, chunk, score, text }) => ({ ...found[Number(attachment)], chunk, score, text }))
+}
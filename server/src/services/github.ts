@@ -0,0 +1,213 @@
+export const DEFAULT_GITHUB_API_URL = 'https://api.github.com'
+
+/** Issue and comment bodies are cut here; the full text stays a link away. */
+const MAX_BODY_CHARS = 8_000
+const MAX_COMMENTS = 30
+const REPO_PATTERN = /^[\w.-]+\/[\w.-]+$/
+
+export type GitHubSearchType = 'issues' | 'code' | 'repositories'
+
+export interface GitHubIssueSummary {
+  number: number
+  title: string
+  kind: 'issue' | 'pull'
+  state: string
+  author: string | null
+  labels: string[]
+  comments: number
+  url: string
+  updatedAt: string
+}
+
+export interface GitHubComment {
+  id: number
+  author: string | null
+  body: string
+  createdAt: string
+  url: string
+}
+
+export interface GitHubIssue extends GitHubIssueSummary {
+  body: string
+  assignees: string[]
+  createdAt: string
+  closedAt: string | null
+  /** Set for pull requests only. */
+  pull?: { head: string; base: string; draft: boolean; merged: boolean; mergeable: boolean | null; changedFiles: number }
+  commentList: GitHubComment[]
+}
+
+export class GitHubError extends Error {
+  constructor(message: string, readonly status: number) {
+    super(message)
+    this.name = 'GitHubError'
+  }
+}
+
+/** Checks an "owner/name" argument; throws with a message for the agent. */
+export function parseRepo(value: unknown): string {
+  const repo = typeof value === 'string' ? value.trim().replace(/^https:\/\/github\.com\//, '').replace(/\.git$/, '') : ''
+  if (!REPO_PATTERN.test(repo)) throw new Error('repo must be "owner/name"')
+  return repo
+}
+
+/**
+ * Minimal REST client for the GitHub tools, authenticated with a personal
+ * access token. apiUrl points at GitHub Enterprise when set.
+ */
+export class GitHubClient {
+  private readonly apiUrl: string
+
+  constructor(
+    private readonly token: string,
+    apiUrl = DEFAULT_GITHUB_API_URL,
+    private readonly fetchImpl: typeof fetch = fetch,
+  ) {
+    this.apiUrl = apiUrl.replace(/\/+$/, '')
+  }
+
+  async listIssues(
+    repo: string,
+    options: { state?: 'open' | 'closed' | 'all'; kind?: 'issue' | 'pull' | 'all'; labels?: string[]; limit?: number } = {},
+    signal?: AbortSignal,
+  ): Promise<GitHubIssueSummary[]> {
+    const limit = clampLimit(options.limit)
+    const params = new URLSearchParams({ state: options.state ?? 'open', sort: 'updated', per_page: String(Math.min(100, limit * 2)) })
+    if (options.labels?.length) params.set('labels', options.labels.join(','))
+    const rows = await this.request<RawIssue[]>('GET', `/repos/${repo}/issues?${params}`, undefined, signal)
+    // The issues endpoint returns pull requests too
+    return rows
+      .map(toSummary)
+      .filter((issue) => !options.kind || options.kind === 'all' || issue.kind === options.kind)
+      .slice(0, limit)
+  }
+
+  async getIssue(repo: string, number: number, signal?: AbortSignal): Promise<GitHubIssue> {
+    const raw = await this.request<RawIssue>('GET', `/repos/${repo}/issues/${number}`, undefined, signal)
+    const comments = raw.comments > 0
+      ? await this.request<RawComment[]>('GET', `/repos/${repo}/issues/${number}/comments?per_page=${MAX_COMMENTS}`, undefined, signal)
+      : []
+    const issue: GitHubIssue = {
+      ...toSummary(raw),
+      body: truncate(raw.body ?? ''),
+      assignees: (raw.assignees ?? []).map((user) => user.login),
+      createdAt: raw.created_at,
+      closedAt: raw.closed_at ?? null,
+      commentList: comments.map(toComment),
+    }
+    if (raw.pull_request) {
+      const pull = await this.request<RawPull>('GET', `/repos/${repo}/pulls/${number}`, undefined, signal)
+      issue.pull = {
+        head: pull.head.label,
+        base: pull.base.label,
+        draft: pull.draft ?? false,
+        merged: pull.merged ?? false,
+        mergeable: pull.mergeable ?? null,
+        changedFiles: pull.changed_files ?? 0,
+      }
+    }
+    return issue
+  }
+
+  async search(type: GitHubSearchType, query: string, limit?: number, signal?: AbortSignal): Promise<{ total: number; results: unknown[] }> {
+    const params = new URLSearchParams({ q: query, per_page: String(clampLimit(limit)) })
+    const found = await this.request<{ total_count: number; items: Array<Record<string, unknown>> }>('GET', `/search/${type}?${params}`, undefined, signal)
+    const results = found.items.map((item) => {
+      if (type === 'issues') return toSummary(item as unknown as RawIssue)
+      if (type === 'code') {
+        const repository = item.repository as { full_name: string }
+        return { repo: repository.full_name, path: item.path, url: item.html_url }
+      }
+      return {
+        repo: item.full_name,
+        description: item.description ?? null,
+        stars: item.stargazers_count,
+        language: item.language ?? null,
+        url: item.html_url,
+        updatedAt: item.updated_at,
+      }
+    })
+    return { total: found.total_count, results }
+  }
+
+  async comment(repo: string, number: number, body: string, signal?: AbortSignal): Promise<GitHubComment> {
+    return toComment(await this.request<RawComment>('POST', `/repos/${repo}/issues/${number}/comments`, { body }, signal))
+  }
+
+  async createIssue(repo: string, input: { title: string; body?: string; labels?: string[] }, signal?: AbortSignal): Promise<GitHubIssueSummary> {
+    return toSummary(await this.request<RawIssue>('POST', `/repos/${repo}/issues`, input, signal))
+  }
+
+  private async request<T>(method: string, path: string, body: unknown, signal?: AbortSignal): Promise<T> {
+    const response = await this.fetchImpl(`${this.apiUrl}${path}`, {
+      method,
+      headers: {
+        Accept: 'application/vnd.github+json',
+        Authorization: `Bearer ${this.token}`,
+        'X-GitHub-Api-Version': '2022-11-28',
+        ...(body !== undefined && { 'Content-Type': 'application/json' }),
+      },
+      body: body !== undefined ? JSON.stringify(body) : undefined,
+      signal,
+    })
+    if (!response.ok) {
+      const detail = await response.json().catch(() => null) as { message?: string } | null
+      const message = detail?.message ?? response.statusText
+      if (response.status === 404) throw new GitHubError(`Not found on GitHub (or the token cannot see it): ${path.split('?')[0]}`, 404)
+      throw new GitHubError(`GitHub ${method} ${path.split('?')[0]} failed (${response.status}): ${message}`, response.status)
+    }
+    return response.json() as Promise<T>
+  }
+}
+
+interface RawUser { login: string }
+interface RawIssue {
+  number: number
+  title: string
+  state: string
+  user?: RawUser | null
+  labels?: Array<string | { name?: string }>
+  assignees?: RawUser[]
+  comments: number
+  html_url: string
+  body?: string | null
+  created_at: string
+  updated_at: string
+  closed_at?: string | null
+  pull_request?: unknown
+}
+interface RawComment { id: number; user?: RawUser | null; body?: string | null; created_at: string; html_url: string }
+interface RawPull {
+  head: { label: string }
+  base: { label: string }
+  draft?: boolean
+  merged?: boolean
+  mergeable?: boolean | null
+  changed_files?: number
+}
+
+function toSummary(raw: RawIssue): GitHubIssueSummary {
+  return {
+    number: raw.number,
+    title: raw.title,
+    kind: raw.pull_request ? 'pull' : 'issue',
+    state: raw.state,
+    author: raw.user?.login ?? null,
+    labels: (raw.labels ?? []).map((label) => (typeof label === 'string' ? label : label.name ?? '')).filter(Boolean),
+    comments: raw.comments,
+    url: raw.html_url,
+    updatedAt: raw.updated_at,
+  }
+}
+
+function toComment(raw: RawComment): GitHubComment {
+  return { id: raw.id, author: raw.user?.login ?? null, body: truncate(raw.body ?? ''), createdAt: raw.created_at, url: raw.html_url }
+}
+
+function truncate(text: string): string {
+  return text.length > MAX_BODY_CHARS ? `${text.slice(0, MAX_BODY_CHARS)}\n[truncated]` : text
+}
+
+function clampLimit(limit: number | undefined): number {
+  return Math.min(Math.max(1, Math.floor(limit ?? 10)), 50)
+}
@@ -0,0 +1,130 @@
+import fs from 'fs/promises'
+import path from 'path'
+import { randomUUID } from 'node:crypto'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+import { loadMailAccount, verifyMailAccount } from './mail.js'
+import { loadCalDavAccounts, verifyCalDavAccount } from './caldav.js'
+
+/** How long one check may take before it counts as failed. */
+const CHECK_TIMEOUT_MS = 10_000
+
+export type HealthCheckKind = 'provider' | 'mail' | 'calendar' | 'mcp' | 'storage'
+
+export interface HealthCheck {
+  kind: HealthCheckKind
+  /** Provider, account, server or store name. */
+  name: string
+  /** `skipped` when there is no way to check it without a real request. */
+  status: 'ok' | 'failed' | 'skipped'
+  detail: string | null
+  durationMs: number
+}
+
+export interface HealthReport {
+  status: 'ok' | 'degraded'
+  checks: HealthCheck[]
+  checkedAt: number
+}
+
+type HealthRuntime = Pick<RuntimeContext, 'providers' | 'repositories' | 'mcps' | 'sessionFilesRoot' | 'attachments' | 'notesDir'>
+
+/**
+ * Checks everything a run may lean on — provider keys, mail and calendar
+ * logins, MCP servers, and the stores artifacts are written to — so settings
+ * can show what is broken before a run trips over it. Checks run side by
+ * side and each is bounded, so one hung server cannot stall the report.
+ */
+export async function runHealthCheck(runtime: HealthRuntime, userId: string, signal?: AbortSignal): Promise<HealthReport> {
+  const pending: Array<Promise<HealthCheck>> = []
+  const check = (kind: HealthCheckKind, name: string, probe: (signal: AbortSignal) => Promise<string | void>) => {
+    pending.push(runCheck(kind, name, probe, signal))
+  }
+
+  for (const name of runtime.providers.list()) {
+    const provider = runtime.providers.get(name)
+    const ping = provider?.ping?.bind(provider)
+    if (ping) {
+      check('provider', name, ping)
+    } else {
+      pending.push(Promise.resolve(skipped('provider', name, 'This provider has no check that does not spend tokens')))
+    }
+  }
+
+  const mail = await loadMailAccount(runtime.repositories.apiKeys).catch(() => null)
+  if (mail) check('mail', mail.username, (s) => verifyMailAccount(mail, s))
+
+  const calendars = await loadCalDavAccounts(runtime.repositories.apiKeys).catch(() => [])
+  for (const account of calendars) {
+    check('calendar', account.name, async (s) => {
+      const found = await verifyCalDavAccount(account, fetch, s)
+      return `${found.length} calendar${found.length === 1 ? '' : 's'}`
+    })
+  }
+
+  for (const server of await runtime.mcps.listServers(userId)) {
+    if (!server.enabled) continue
+    if (server.authStatus === 'required' || server.authStatus === 'pending') {
+      pending.push(Promise.resolve(failed('mcp', server.name, 'Needs authorization')))
+    } else if (server.status !== 'connected') {
+      pending.push(Promise.resolve(failed('mcp', server.name, server.error ?? `Server is ${server.status}`)))
+    } else {
+      check('mcp', server.name, (s) => runtime.mcps.ping(server.id, s))
+    }
+  }
+
+  check('storage', 'sessions', () => probeWritable(runtime.sessionFilesRoot))
+  check('storage', 'attachments', () => probeWritable(runtime.attachments.dir))
+  check('storage', 'notes', () => probeWritable(runtime.notesDir))
+
+  const checks = await Promise.all(pending)
+  const report: HealthReport = {
+    status: checks.some((c) => c.status === 'failed') ? 'degraded' : 'ok',
+    checks,
+    checkedAt: Date.now(),
+  }
+  if (report.status === 'degraded') {
+    logger.warn({ failed: checks.filter((c) => c.status === 'failed').map((c) => `${c.kind}:${c.name}`) }, 'Health check found problems')
+  }
+  return report
+}
+
+async function runCheck(
+  kind: HealthCheckKind,
+  name: string,
+  probe: (signal: AbortSignal) => Promise<string | void>,
+  signal?: AbortSignal,
+): Promise<HealthCheck> {
+  const startedAt = Date.now()
+  const timeout = AbortSignal.timeout(CHECK_TIMEOUT_MS)
+  const combined = signal ? AbortSignal.any([signal, timeout]) : timeout
+  try {
+    // Race the abort too, since not every client stops when its signal fires
+    const detail = await Promise.race([
+      probe(combined),
+      new Promise<never>((_, reject) => combined.addEventListener('abort', () => reject(combined.reason), { once: true })),
+    ])
+    return { kind, name, status: 'ok', detail: detail || null, durationMs: Date.now() - startedAt }
+  } catch (err) {
+    const detail = timeout.aborted
+      ? `No answer within ${CHECK_TIMEOUT_MS / 1000}s`
+      : err instanceof Error ? err.message : String(err)
+    return { kind, name, status: 'failed', detail, durationMs: Date.now() - startedAt }
+  }
+}
+
+function failed(kind: HealthCheckKind, name: string, detail: string): HealthCheck {
+  return { kind, name, status: 'failed', detail, durationMs: 0 }
+}
+
+function skipped(kind: HealthCheckKind, name: string, detail: string): HealthCheck {
+  return { kind, name, status: 'skipped', detail, durationMs: 0 }
+}
+
+/** Writes and removes a throwaway file, creating the directory if needed. */
+async function probeWritable(dir: string): Promise<void> {
+  await fs.mkdir(dir, { recursive: true })
+  const probe = path.join(dir, `.health-${randomUUID()}`)
+  await fs.writeFile(probe, '')
+  await fs.rm(probe, { force: true })
+}
@@ -0,0 +1,107 @@
+import { logger } from '../lib/logger.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { ItemRepository, MessageSearchHit, SessionRepository } from '../repositories/types.js'
+import { cosine, embed, tokenize } from './attachment-index.js'
+
+const DEFAULT_SEARCH_LIMIT = 5
+const MAX_SEARCH_LIMIT = 20
+/** Full-text matches considered for each search before embeddings re-rank them. */
+const CANDIDATE_LIMIT = 50
+const MAX_QUERY_TERMS = 16
+const SNIPPET_CHARS = 400
+/** Reciprocal rank fusion constant; damps the lead of the very top ranks. */
+const RRF_K = 60
+
+export interface HistorySearchResult {
+  sessionId: string
+  title: string | null
+  itemId: string
+  role: 'user' | 'assistant'
+  /** ISO timestamp of the message, so the model can answer "last month" questions. */
+  date: string
+  snippet: string
+  score: number
+}
+
+export type HistorySearchDeps = Pick<RuntimeContext, 'config' | 'providers'> & {
+  items: ItemRepository
+  sessions: SessionRepository
+}
+
+/**
+ * Messages from the user's other conversations relevant to a query. Matches
+ * come from the full-text index; with ATTACHMENT_EMBEDDING_MODEL set they
+ * are re-ranked by combining keyword rank with embedding similarity.
+ */
+export async function searchHistory(
+  deps: HistorySearchDeps,
+  sessionId: string,
+  query: string,
+  options: { limit?: number; signal?: AbortSignal } = {},
+): Promise<HistorySearchResult[]> {
+  const session = await deps.sessions.getById(sessionId)
+  if (!session) throw new Error(`Session not found: ${sessionId}`)
+  const limit = Math.min(Math.max(1, Math.floor(options.limit ?? DEFAULT_SEARCH_LIMIT)), MAX_SEARCH_LIMIT)
+  const terms = [...new Set(tokenize(query))].slice(0, MAX_QUERY_TERMS)
+  const hits = await deps.items.searchMessages(session.userId, terms, { excludeSessionId: sessionId, limit: CANDIDATE_LIMIT })
+  if (hits.length === 0) return []
+
+  const snippets = hits.map((hit) => snippet(hit.content, terms))
+  const similarities = await semanticScores(deps, query, snippets, options.signal)
+  const keywordRank = (index: number) => 1 / (RRF_K + index + 1)
+  let scores = hits.map((_, index) => keywordRank(index))
+  if (similarities) {
+    const bySimilarity = similarities.map((_, index) => index).sort((a, b) => similarities[b] - similarities[a])
+    const semanticRank = new Map(bySimilarity.map((index, rank) => [index, 1 / (RRF_K + rank + 1)]))
+    scores = scores.map((score, index) => score + semanticRank.get(index)!)
+  }
+
+  return hits
+    .map((hit, index) => toResult(hit, snippets[index], scores[index]))
+    .sort((a, b) => b.score - a.score)
+    .slice(0, limit)
+}
+
+/** The part of a message around its first matching term, trimmed to SNIPPET_CHARS. */
+export function snippet(content: string, terms: string[]): string {
+  const text = content.replace(/\s+/g, ' ').trim()
+  if (text.length <= SNIPPET_CHARS) return text
+  const lower = text.toLowerCase()
+  const positions = terms.map((term) => lower.indexOf(term)).filter((position) => position >= 0)
+  const first = positions.length > 0 ? Math.min(...positions) : 0
+  const start = Math.max(0, Math.min(first - SNIPPET_CHARS / 4, text.length - SNIPPET_CHARS))
+  const body = text.slice(start, start + SNIPPET_CHARS).trim()
+  return `${start > 0 ? '…' : ''}${body}${start + SNIPPET_CHARS < text.length ? '…' : ''}`
+}
+
+/** Cosine similarity of each snippet to the query; null without a usable embedding model. */
+async function semanticScores(
+  deps: HistorySearchDeps,
+  query: string,
+  snippets: string[],
+  signal?: AbortSignal,
+): Promise<number[] | null> {
+  const modelId = deps.config.attachmentEmbeddingModel?.trim()
+  if (!modelId) return null
+  try {
+    const vectors = await embed(deps, modelId, [query, ...snippets], signal)
+    if (!vectors) return null
+    const [queryVector, ...snippetVectors] = vectors
+    return snippetVectors.map((vector) => cosine(queryVector, vector))
+  } catch (err) {
+    logger.warn({ model: modelId, err: err instanceof Error ? err.message : String(err) }, 'History embedding failed')
+    return null
+  }
+}
+
+function toResult(hit: MessageSearchHit, text: string, score: number): HistorySearchResult {
+  return {
+    sessionId: hit.sessionId,
+    title: hit.sessionTitle,
+    itemId: hit.itemId,
+    role: hit.role,
+    date: new Date(hit.createdAt).toISOString(),
+    snippet: text,
+    score,
+  }
+}
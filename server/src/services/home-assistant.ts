@@ -0,0 +1,156 @@
+const DEFAULT_LIMIT = 50
+const MAX_LIMIT = 200
+const SLUG_PATTERN = /^[a-z0-9_]+$/
+const ENTITY_ID_PATTERN = /^[a-z0-9_]+\.[a-z0-9_]+$/
+/** Attributes worth showing in a list; a single entity returns all of them. */
+const SUMMARY_ATTRIBUTES = ['device_class', 'unit_of_measurement', 'current_position', 'brightness', 'temperature', 'current_temperature', 'hvac_action']
+/** One line per entity with its area, so "the office lights" resolves without the registry APIs (WebSocket-only). */
+const AREAS_TEMPLATE = '{% for s in states %}{{ s.entity_id }}\t{{ area_name(s.entity_id) or "" }}\n{% endfor %}'
+
+export interface HomeAssistantState {
+  entity_id: string
+  name: string
+  state: string
+  area?: string
+  attributes: Record<string, unknown>
+  last_changed: string
+}
+
+export class HomeAssistantError extends Error {
+  constructor(message: string, readonly status: number) {
+    super(message)
+    this.name = 'HomeAssistantError'
+  }
+}
+
+export function isEntityId(value: string): boolean {
+  return ENTITY_ID_PATTERN.test(value)
+}
+
+/** Home Assistant's REST API with a long-lived access token. */
+export class HomeAssistantClient {
+  private readonly baseUrl: string
+
+  constructor(
+    baseUrl: string,
+    private readonly token: string,
+    private readonly fetchImpl: typeof fetch = fetch,
+  ) {
+    this.baseUrl = baseUrl.replace(/\/+$/, '')
+  }
+
+  async state(entityId: string, signal?: AbortSignal): Promise<HomeAssistantState> {
+    if (!isEntityId(entityId)) throw new HomeAssistantError(`Invalid entity_id "${entityId}"; expected e.g. cover.garage_door`, 400)
+    const raw = await this.request<RawState>('GET', `/api/states/${entityId}`, undefined, signal)
+    const areas = await this.areas(signal)
+    return toState(raw, areas.get(raw.entity_id), true)
+  }
+
+  /** Entities filtered by domain, area and a name search, sorted by entity_id. */
+  async states(
+    options: { domain?: string; area?: string; search?: string; limit?: number } = {},
+    signal?: AbortSignal,
+  ): Promise<{ states: HomeAssistantState[]; total: number }> {
+    const limit = Math.min(Math.max(1, Math.floor(options.limit ?? DEFAULT_LIMIT)), MAX_LIMIT)
+    const [raw, areas] = await Promise.all([
+      this.request<RawState[]>('GET', '/api/states', undefined, signal),
+      this.areas(signal),
+    ])
+    const area = options.area?.trim().toLowerCase()
+    const terms = (options.search ?? '').toLowerCase().split(/\s+/).filter(Boolean)
+    const matching = raw
+      .map((entry) => toState(entry, areas.get(entry.entity_id), false))
+      .filter((state) => !options.domain || state.entity_id.startsWith(`${options.domain}.`))
+      .filter((state) => !area || state.area?.toLowerCase() === area)
+      .filter((state) => {
+        const haystack = `${state.entity_id} ${state.name} ${state.area ?? ''}`.toLowerCase()
+        return terms.every((term) => haystack.includes(term))
+      })
+      .sort((a, b) => a.entity_id.localeCompare(b.entity_id))
+    return { states: matching.slice(0, limit), total: matching.length }
+  }
+
+  /** Calls e.g. light.turn_off; returns the states Home Assistant reports as changed. */
+  async callService(
+    domain: string,
+    service: string,
+    data: Record<string, unknown>,
+    signal?: AbortSignal,
+  ): Promise<HomeAssistantState[]> {
+    if (!SLUG_PATTERN.test(domain) || !SLUG_PATTERN.test(service)) {
+      throw new HomeAssistantError('domain and service must be lowercase names such as "light" and "turn_off"', 400)
+    }
+    const changed = await this.request<RawState[]>('POST', `/api/services/${domain}/${service}`, data, signal)
+    return changed.map((entry) => toState(entry, undefined, false))
+  }
+
+  /** entity_id → area name; empty when templates are unavailable. */
+  private async areas(signal?: AbortSignal): Promise<Map<string, string>> {
+    const areas = new Map<string, string>()
+    let text: string
+    try {
+      text = await this.request<string>('POST', '/api/template', { template: AREAS_TEMPLATE }, signal, 'text')
+    } catch (err) {
+      if (err instanceof HomeAssistantError && err.status === 401) throw err
+      return areas
+    }
+    for (const line of text.split('\n')) {
+      const [entityId, area] = line.split('\t')
+      if (entityId && area?.trim()) areas.set(entityId.trim(), area.trim())
+    }
+    return areas
+  }
+
+  private async request<T>(method: string, path: string, body: unknown, signal?: AbortSignal, as: 'json' | 'text' = 'json'): Promise<T> {
+    let response: Response
+    try {
+      response = await this.fetchImpl(`${this.baseUrl}${path}`, {
+        method,
+        headers: {
+          Authorization: `Bearer ${this.token}`,
+          ...(body !== undefined && { 'Content-Type': 'application/json' }),
+        },
+        body: body !== undefined ? JSON.stringify(body) : undefined,
+        signal,
+      })
+    } catch (err) {
+      if (signal?.aborted) throw err
+      throw new HomeAssistantError(`Home Assistant at ${this.baseUrl} is unreachable: ${err instanceof Error ? err.message : String(err)}`, 0)
+    }
+    if (!response.ok) {
+      const detail = (await response.text().catch(() => '')).trim()
+      if (response.status === 401) throw new HomeAssistantError('Home Assistant rejected the access token', 401)
+      if (response.status === 404 && path.startsWith('/api/states/')) {
+        throw new HomeAssistantError(`No entity ${path.slice('/api/states/'.length)}; list entities to find its id`, 404)
+      }
+      if (response.status === 400 && path.startsWith('/api/services/')) {
+        throw new HomeAssistantError(`Home Assistant refused ${path.slice('/api/services/'.length).replace('/', '.')}: ${detail || response.statusText}`, 400)
+      }
+      throw new HomeAssistantError(`Home Assistant ${method} ${path} failed (${response.status}): ${detail || response.statusText}`, response.status)
+    }
+    return (as === 'text' ? response.text() : response.json()) as Promise<T>
+  }
+}
+
+interface RawState {
+  entity_id: string
+  state: string
+  attributes?: Record<string, unknown>
+  last_changed?: string
+}
+
+function toState(raw: RawState, area: string | undefined, allAttributes: boolean): HomeAssistantState {
+  const attributes = raw.attributes ?? {}
+  const name = typeof attributes.friendly_name === 'string' ? attributes.friendly_name : raw.entity_id
+  const { friendly_name: _name, ...rest } = attributes
+  return {
+    entity_id: raw.entity_id,
+    name,
+    state: raw.state,
+    ...(area && { area }),
+    attributes: allAttributes
+      ? rest
+      : Object.fromEntries(SUMMARY_ATTRIBUTES.filter((key) => rest[key] !== undefined && rest[key] !== null).map((key) => [key, rest[key]])),
+    last_changed: raw.last_changed ?? '',
+  }
+}
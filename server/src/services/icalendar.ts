@@ -0,0 +1,257 @@
+import { randomUUID } from 'node:crypto'
+
+/**
+ * An event in the shape Google Calendar returns, so agents (and entity
+ * learning, which reads organizer, attendees and recurrence) see the same
+ * fields whichever backend the calendar lives on.
+ */
+export interface CalendarEvent {
+  /** The iCalendar UID; an expanded occurrence appends its start, like Google's instance ids. */
+  id: string
+  summary: string | null
+  description: string | null
+  location: string | null
+  status: string | null
+  /** `dateTime` with an offset for timed events, `date` for all-day ones. */
+  start: EventTime
+  end: EventTime | null
+  organizer?: { email: string; displayName?: string }
+  attendees?: Array<{ email: string; displayName?: string; responseStatus: string; resource?: true }>
+  recurrence?: string[]
+  /** Set on occurrences of a recurring event: the series' id. */
+  recurringEventId?: string
+}
+
+export type EventTime = { dateTime: string; timeZone?: string } | { date: string }
+
+export interface NewCalendarEvent {
+  summary: string
+  /** An instant for timed events; all-day events use `allDay` with start and end dates. */
+  start: Date
+  end: Date
+  allDay?: { start: string; end: string }
+  location?: string
+  description?: string
+  attendees?: string[]
+  organizer?: string
+}
+
+interface Property {
+  name: string
+  params: Record<string, string>
+  value: string
+}
+
+interface Component {
+  name: string
+  properties: Property[]
+  components: Component[]
+}
+
+const PARTSTAT: Record<string, string> = {
+  'NEEDS-ACTION': 'needsAction',
+  ACCEPTED: 'accepted',
+  DECLINED: 'declined',
+  TENTATIVE: 'tentative',
+  DELEGATED: 'needsAction',
+}
+
+/** Every VEVENT in an iCalendar object, including expanded occurrences. */
+export function parseEvents(ics: string): CalendarEvent[] {
+  const root = parseComponents(ics)
+  const events: CalendarEvent[] = []
+  for (const calendar of root.components) {
+    for (const component of calendar.components) {
+      if (component.name === 'VEVENT') events.push(toEvent(component))
+    }
+  }
+  return events
+}
+
+/** A VCALENDAR holding one VEVENT; timed events are written in UTC so no VTIMEZONE is needed. */
+export function buildEvent(event: NewCalendarEvent, now = new Date()): { uid: string; ics: string } {
+  const uid = `${randomUUID()}@ai-assistant`
+  const lines = [
+    'BEGIN:VCALENDAR',
+    'VERSION:2.0',
+    'PRODID:-//ai-assistant//CalDAV//EN',
+    'BEGIN:VEVENT',
+    `UID:${uid}`,
+    `DTSTAMP:${utcStamp(now)}`,
+    ...(event.allDay
+      ? [`DTSTART;VALUE=DATE:${event.allDay.start.replace(/-/g, '')}`, `DTEND;VALUE=DATE:${event.allDay.end.replace(/-/g, '')}`]
+      : [`DTSTART:${utcStamp(event.start)}`, `DTEND:${utcStamp(event.end)}`]),
+    `SUMMARY:${escapeText(event.summary)}`,
+    ...(event.location ? [`LOCATION:${escapeText(event.location)}`] : []),
+    ...(event.description ? [`DESCRIPTION:${escapeText(event.description)}`] : []),
+    ...(event.organizer && event.attendees?.length ? [`ORGANIZER:mailto:${event.organizer}`] : []),
+    ...(event.attendees ?? []).map((email) => `ATTENDEE;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:${email}`),
+    'END:VEVENT',
+    'END:VCALENDAR',
+  ]
+  return { uid, ics: `${lines.map(foldLine).join('\r\n')}\r\n` }
+}
+
+function toEvent(component: Component): CalendarEvent {
+  const get = (name: string) => component.properties.find((property) => property.name === name)
+  const text = (name: string) => {
+    const property = get(name)
+    return property ? unescapeText(property.value) : null
+  }
+  const uid = get('UID')?.value ?? randomUUID()
+  const startProperty = get('DTSTART')
+  const start = startProperty ? eventTime(startProperty) : { date: '' }
+  const endProperty = get('DTEND')
+  const duration = get('DURATION')?.value
+  const recurrenceId = get('RECURRENCE-ID')
+
+  const event: CalendarEvent = {
+    id: recurrenceId ? `${uid}_${recurrenceId.value.replace(/[^0-9TZ]/g, '')}` : uid,
+    summary: text('SUMMARY'),
+    description: text('DESCRIPTION'),
+    location: text('LOCATION'),
+    status: get('STATUS')?.value.toLowerCase() ?? null,
+    start,
+    end: endProperty ? eventTime(endProperty) : duration ? addDuration(start, duration) : null,
+  }
+  const organizer = get('ORGANIZER')
+  if (organizer && mailto(organizer.value)) {
+    event.organizer = { email: mailto(organizer.value)!, ...(organizer.params.CN && { displayName: organizer.params.CN }) }
+  }
+  const attendees = component.properties.filter((property) => property.name === 'ATTENDEE' && mailto(property.value))
+  if (attendees.length > 0) {
+    event.attendees = attendees.map((attendee) => ({
+      email: mailto(attendee.value)!,
+      ...(attendee.params.CN && { displayName: attendee.params.CN }),
+      responseStatus: PARTSTAT[attendee.params.PARTSTAT?.toUpperCase() ?? 'NEEDS-ACTION'] ?? 'needsAction',
+      ...(/^(ROOM|RESOURCE)$/i.test(attendee.params.CUTYPE ?? '') && { resource: true as const }),
+    }))
+  }
+  const rules = component.properties.filter((property) => /^(RRULE|EXRULE|RDATE|EXDATE)$/.test(property.name))
+  if (rules.length > 0) event.recurrence = rules.map((rule) => `${rule.name}:${rule.value}`)
+  if (recurrenceId) event.recurringEventId = uid
+  return event
+}
+
+function parseComponents(ics: string): Component {
+  const root: Component = { name: '#root', properties: [], components: [] }
+  const stack = [root]
+  const unfolded = ics.replace(/\r?\n[ \t]/g, '')
+  for (const line of unfolded.split(/\r?\n/)) {
+    if (!line.trim()) continue
+    const property = parseProperty(line)
+    if (!property) continue
+    const current = stack[stack.length - 1]
+    if (property.name === 'BEGIN') {
+      const component: Component = { name: property.value.toUpperCase(), properties: [], components: [] }
+      current.components.push(component)
+      stack.push(component)
+    } else if (property.name === 'END') {
+      if (stack.length > 1) stack.pop()
+    } else {
+      current.properties.push(property)
+    }
+  }
+  return root
+}
+
+/** NAME;PARAM=value;PARAM="quoted:value":value */
+function parseProperty(line: string): Property | null {
+  const match = line.match(/^([A-Za-z0-9-]+)((?:;[A-Za-z0-9-]+=(?:"[^"]*"|[^";:]*)(?:,(?:"[^"]*"|[^";:,]*))*)*):(.*)$/)
+  if (!match) return null
+  const params: Record<string, string> = {}
+  for (const [, key, value] of match[2].matchAll(/;([A-Za-z0-9-]+)=("[^"]*"|[^";:]*)/g)) {
+    params[key.toUpperCase()] = value.replace(/^"|"$/g, '')
+  }
+  return { name: match[1].toUpperCase(), params, value: match[3] }
+}
+
+function eventTime(property: Property): EventTime {
+  const value = property.value.trim()
+  const date = value.match(/^(\d{4})(\d{2})(\d{2})$/)
+  if (date || property.params.VALUE === 'DATE') {
+    return { date: `${value.slice(0, 4)}-${value.slice(4, 6)}-${value.slice(6, 8)}` }
+  }
+  const time = value.match(/^(\d{4})(\d{2})(\d{2})T(\d{2})(\d{2})(\d{2})(Z?)$/)
+  if (!time) return { dateTime: value }
+  const [, year, month, day, hour, minute, second, utc] = time
+  const local = `${year}-${month}-${day}T${hour}:${minute}:${second}`
+  if (utc) return { dateTime: `${local}Z` }
+  const zone = property.params.TZID
+  if (!zone) return { dateTime: local }
+  const offset = zoneOffset(Date.UTC(+year, +month - 1, +day, +hour, +minute, +second), zone)
+  return offset === null ? { dateTime: local, timeZone: zone } : { dateTime: `${local}${offset}`, timeZone: zone }
+}
+
+/** "+02:00" for a wall-clock time in an IANA zone, or null for names Intl does not know (e.g. Windows zones). */
+function zoneOffset(wallClockAsUtc: number, zone: string): string | null {
+  let format: Intl.DateTimeFormat
+  try {
+    format = new Intl.DateTimeFormat('en-US', {
+      timeZone: zone, hourCycle: 'h23', year: 'numeric', month: 'numeric', day: 'numeric', hour: 'numeric', minute: 'numeric', second: 'numeric',
+    })
+  } catch {
+    return null
+  }
+  const offsetAt = (instant: number) => {
+    const parts = Object.fromEntries(format.formatToParts(new Date(instant)).map((part) => [part.type, Number(part.value)]))
+    return (Date.UTC(parts.year, parts.month - 1, parts.day, parts.hour, parts.minute, parts.second) - instant) / 60_000
+  }
+  // Offset at the guessed instant, then again at the corrected one for DST edges
+  const minutes = offsetAt(wallClockAsUtc - offsetAt(wallClockAsUtc) * 60_000)
+  const pad = (value: number) => String(value).padStart(2, '0')
+  return `${minutes < 0 ? '-' : '+'}${pad(Math.floor(Math.abs(minutes) / 60))}:${pad(Math.abs(minutes) % 60)}`
+}
+
+/** DTSTART plus a DURATION such as PT1H30M or P1D. */
+function addDuration(start: EventTime, duration: string): EventTime | null {
+  const match = duration.match(/^([+-])?P(?:(\d+)W)?(?:(\d+)D)?(?:T(?:(\d+)H)?(?:(\d+)M)?(?:(\d+)S)?)?$/)
+  if (!match) return null
+  const [, sign, weeks = '0', days = '0', hours = '0', minutes = '0', seconds = '0'] = match
+  const millis = (sign === '-' ? -1 : 1) * ((+weeks * 7 + +days) * 86_400 + +hours * 3600 + +minutes * 60 + +seconds) * 1000
+  if ('date' in start) {
+    return { date: new Date(Date.parse(`${start.date}T00:00:00Z`) + millis).toISOString().slice(0, 10) }
+  }
+  const parsed = Date.parse(start.dateTime)
+  if (Number.isNaN(parsed)) return null
+  const offset = start.dateTime.match(/([+-]\d{2}:\d{2}|Z)$/)?.[1]
+  if (!offset) return { ...start, dateTime: new Date(Date.parse(`${start.dateTime}Z`) + millis).toISOString().slice(0, 19) }
+  if (offset === 'Z') return { ...start, dateTime: `${new Date(parsed + millis).toISOString().slice(0, 19)}Z` }
+  // Keep the zone's offset from the start; a DST change mid-event is rare enough to ignore
+  const offsetMillis = (offset[0] === '-' ? -1 : 1) * (+offset.slice(1, 3) * 60 + +offset.slice(4, 6)) * 60_000
+  return { ...start, dateTime: `${new Date(parsed + millis + offsetMillis).toISOString().slice(0, 19)}${offset}` }
+}
+
+function mailto(value: string): string | null {
+  const address = value.replace(/^mailto:/i, '').trim()
+  return address.includes('@') ? address : null
+}
+
+function unescapeText(value: string): string {
+  return value.replace(/\\([nN,;\\])/g, (_, char: string) => (char === 'n' || char === 'N' ? '\n' : char))
+}
+
+function escapeText(value: string): string {
+  return value.replace(/\\/g, '\\\\').replace(/;/g, '\\;').replace(/,/g, '\\,').replace(/\r?\n/g, '\\n')
+}
+
+function utcStamp(date: Date): string {
+  return date.toISOString().replace(/[-:]/g, '').replace(/\.\d{3}/, '')
+}
+
+/** Lines longer than 75 octets continue on the next line after a space (RFC 5545 §3.1). */
+function foldLine(line: string): string {
+  const bytes = Buffer.from(line, 'utf-8')
+  if (bytes.length <= 75) return line
+  const chunks: string[] = []
+  let current = ''
+  for (const char of line) {
+    if (Buffer.byteLength(current + char, 'utf-8') > (chunks.length === 0 ? 75 : 74)) {
+      chunks.push(current)
+      current = ''
+    }
+    current += char
+  }
+  chunks.push(current)
+  return chunks.join('\r\n ')
+}
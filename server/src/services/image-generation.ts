@@ -0,0 +1,88 @@
+import { splitModelId } from '../lib/model.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+
+/** Model id prefix that routes generation to Stability AI, e.g. `stability:core`. */
+export const STABILITY_PROVIDER = 'stability'
+
+const STABILITY_API_URL = 'https://api.stability.ai/v2beta/stable-image/generate'
+
+export type ImageOrientation = 'square' | 'landscape' | 'portrait'
+
+export const IMAGE_ORIENTATIONS: ImageOrientation[] = ['square', 'landscape', 'portrait']
+
+export interface ImageGenerationInput {
+  prompt: string
+  orientation?: ImageOrientation
+  signal?: AbortSignal
+}
+
+export interface GeneratedImage {
+  data: Buffer
+  mediaType: string
+  model: string
+  /** The prompt the provider actually used, when it rewrites prompts. */
+  revisedPrompt?: string
+}
+
+type GenerationRuntime = Pick<RuntimeContext, 'config' | 'providers'>
+
+/** Generates one image with IMAGE_GENERATION_MODEL: an OpenAI Images model or a `stability:` endpoint. */
+export async function generateImage(runtime: GenerationRuntime, input: ImageGenerationInput): Promise<GeneratedImage> {
+  const modelId = runtime.config.imageGenerationModel?.trim()
+  if (!modelId) throw new Error('IMAGE_GENERATION_MODEL is not configured')
+  const prompt = input.prompt.trim()
+  if (!prompt) throw new Error('prompt is required')
+  const orientation = input.orientation ?? 'square'
+
+  const { provider, model } = splitModelId(modelId)
+  if (provider === STABILITY_PROVIDER) {
+    const apiKey = runtime.config.stabilityApiKey?.trim()
+    if (!apiKey) throw new Error('STABILITY_API_KEY is not configured')
+    const data = await generateWithStability(apiKey, model, prompt, orientation, input.signal)
+    return { data, mediaType: 'image/png', model: modelId }
+  }
+
+  const imageProvider = runtime.providers.resolve(modelId)
+  if (!imageProvider.generateImage) {
+    throw new Error(`Provider "${provider}" does not support image generation`)
+  }
+  const response = await imageProvider.generateImage({ model, prompt, size: openAISize(model, orientation), signal: input.signal })
+  return {
+    data: Buffer.from(response.data, 'base64'),
+    mediaType: response.media_type,
+    model: modelId,
+    ...(response.revised_prompt && { revisedPrompt: response.revised_prompt }),
+  }
+}
+
+/** DALL·E 3 and the gpt-image models support different non-square sizes. */
+export function openAISize(model: string, orientation: ImageOrientation): string {
+  if (orientation === 'square') return '1024x1024'
+  const long = model.startsWith('dall-e') ? 1792 : 1536
+  return orientation === 'landscape' ? `${long}x1024` : `1024x${long}`
+}
+
+async function generateWithStability(
+  apiKey: string,
+  model: string,
+  prompt: string,
+  orientation: ImageOrientation,
+  signal?: AbortSignal,
+): Promise<Buffer> {
+  const form = new FormData()
+  form.append('prompt', prompt)
+  form.append('output_format', 'png')
+  form.append('aspect_ratio', orientation === 'square' ? '1:1' : orientation === 'landscape' ? '16:9' : '9:16')
+
+  const response = await fetch(`${STABILITY_API_URL}/${encodeURIComponent(model)}`, {
+    method: 'POST',
+    headers: { Authorization: `Bearer ${apiKey}`, Accept: 'image/*' },
+    body: form,
+    signal,
+  })
+  if (!response.ok) {
+    const detail = (await response.text().catch(() => '')).slice(0, 500)
+    throw new Error(`Stability image generation failed (${response.status})${detail ? `: ${detail}` : ''}`)
+  }
+  return Buffer.from(await response.arrayBuffer())
+}
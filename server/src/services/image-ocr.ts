@@ -0,0 +1,83 @@
+import type { ItemContentBlock } from '../domain/types.js'
+import { MAX_ATTACHMENT_BYTES } from '../lib/attachment-validation.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { chunkText } from '../lib/pdf-text.js'
+import { isOcrImageType, ocrImage, TESSERACT_OCR } from './ocr.js'
+
+export const MAX_IMAGE_BYTES = MAX_ATTACHMENT_BYTES.image
+
+export class ImageInputError extends Error {}
+
+export interface ImageExtractionInput {
+  bytes: ArrayBuffer | Uint8Array
+  mediaType: string
+  fileName?: string
+  signal?: AbortSignal
+}
+
+export interface ImageExtraction {
+  /** SHA-256 of the image in the attachment store; reference it from an `image` content block. */
+  hash: string
+  bytes: number
+  mediaType: string
+  /** Text read from the image; empty when it contains none. */
+  chunks: string[]
+}
+
+type ExtractionRuntime = Pick<RuntimeContext, 'attachments' | 'config' | 'providers' | 'shutdownController'>
+
+/**
+ * Stores an image attachment and the text read from it with IMAGE_OCR_MODEL,
+ * so screenshots are usable by models without vision. Re-uploading the same
+ * image returns the stored text.
+ */
+export async function extractImageText(runtime: ExtractionRuntime, input: ImageExtractionInput): Promise<ImageExtraction> {
+  const data = Buffer.from(input.bytes instanceof ArrayBuffer ? new Uint8Array(input.bytes) : input.bytes)
+  if (data.length === 0) throw new ImageInputError('Image file is empty')
+  if (data.length > MAX_IMAGE_BYTES) throw new ImageInputError(`Image file is too large (${data.length} bytes)`)
+  if (!isOcrImageType(input.mediaType)) {
+    throw new ImageInputError(`Unsupported image type: ${input.mediaType || input.fileName || 'unknown'}`)
+  }
+  const ocrModel = runtime.config.imageOcrModel?.trim()
+  if (!ocrModel) throw new ImageInputError('IMAGE_OCR_MODEL is not configured')
+
+  const hash = await runtime.attachments.put(data)
+  const stored = await runtime.attachments.getExtraction<ImageExtraction>(hash)
+  if (stored) return stored
+
+  // Tesseract reads best at full resolution; vision models get the resized copy
+  const image = ocrModel === TESSERACT_OCR ? { data, mediaType: input.mediaType } : await runtime.attachments.forVision(hash, data, input.mediaType)
+  const text = await ocrImage(runtime, ocrModel, { data: image.data, mediaType: image.mediaType ?? input.mediaType }, input.signal)
+  const extraction: ImageExtraction = { hash, bytes: data.length, mediaType: input.mediaType, chunks: text ? chunkText(text) : [] }
+  await runtime.attachments.putExtraction(hash, extraction)
+  return extraction
+}
+
+/**
+ * Fills in the OCR text of uploaded `image` blocks sent with a user message.
+ * Blocks that carry their own payload are left as they are.
+ */
+export async function resolveImageBlocks(
+  runtime: Pick<RuntimeContext, 'attachments'>,
+  blocks: ItemContentBlock[] | null,
+): Promise<ItemContentBlock[] | null> {
+  if (!blocks) return null
+  const resolved: ItemContentBlock[] = []
+  for (const block of blocks) {
+    if (block.type !== 'image' || block.data !== undefined) {
+      resolved.push(block)
+      continue
+    }
+    const extraction = block.hash ? await runtime.attachments.getExtraction<ImageExtraction>(block.hash) : null
+    if (!extraction) throw new ImageInputError(`Unknown image attachment: ${block.name ?? block.hash ?? 'unnamed'}`)
+    resolved.push({
+      type: 'image',
+      name: block.name,
+      media_type: extraction.mediaType,
+      hash: extraction.hash,
+      bytes: extraction.bytes,
+      chunks: extraction.chunks,
+    })
+  }
+  return resolved
+}
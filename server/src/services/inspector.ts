@@ -0,0 +1,264 @@
+import type { Agent, Item, Plan, Session, WaitingFor } from '../domain/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { UsageRecord } from '../repositories/types.js'
+
+/**
+ * Read-only views over what an agent actually did, for the debugging panel.
+ * Everything here is derived from persisted rows; nothing is recomputed.
+ */
+
+export type InspectorPhase =
+  | 'queued'
+  | 'running'
+  | 'awaiting_tool'
+  | 'awaiting_approval'
+  | 'awaiting_agent'
+  | 'awaiting_human'
+  | 'awaiting_workflow'
+  | 'completed'
+  | 'failed'
+  | 'cancelled'
+
+export interface InspectorUsage {
+  requests: number
+  inputTokens: number
+  outputTokens: number
+  costUsd: number
+}
+
+export interface InspectorSessionSummary {
+  id: string
+  title: string | null
+  status: Session['status']
+  deletedAt: number | null
+  rootAgentId: string | null
+  rootPhase: InspectorPhase | null
+  agentCount: number
+  turnCount: number
+  usage: InspectorUsage
+  createdAt: number
+  updatedAt: number
+}
+
+export interface InspectorStep {
+  callId: string | null
+  name: string | null
+  arguments: unknown
+  /** Null while the call is still pending. */
+  result: string | null
+  isError: boolean | null
+  durationMs: number | null
+  /** Sub-agent spawned by this call, if it delegated. */
+  childAgentId: string | null
+}
+
+export interface InspectorTurn {
+  turn: number
+  input: string | null
+  reasoning: string[]
+  steps: InspectorStep[]
+  response: string | null
+}
+
+export interface InspectorAgent {
+  id: string
+  parentId: string | null
+  sourceCallId: string | null
+  depth: number
+  task: string
+  model: string
+  provider: string
+  phase: InspectorPhase
+  plan: Plan | null
+  waitingFor: WaitingFor[]
+  result: string | null
+  error: string | null
+  turnCount: number
+  usage: InspectorUsage
+  turns: InspectorTurn[]
+  createdAt: number
+  completedAt: number | null
+}
+
+/** One user message on the root agent and the turns it produced. */
+export interface InspectorExchange {
+  itemId: string
+  content: string | null
+  createdAt: number
+  fromTurn: number
+  toTurn: number
+}
+
+export interface InspectorSession {
+  session: Session
+  exchanges: InspectorExchange[]
+  agents: InspectorAgent[]
+  usage: InspectorUsage
+}
+
+export function agentPhase(agent: Agent): InspectorPhase {
+  switch (agent.status) {
+    case 'pending':
+      return 'queued'
+    case 'waiting':
+      return `awaiting_${agent.waitingFor[0]?.type ?? 'tool'}`
+    default:
+      return agent.status
+  }
+}
+
+export async function listInspectorSessions(
+  runtime: RuntimeContext,
+  userId: string,
+): Promise<InspectorSessionSummary[]> {
+  const { sessions, agents, usage } = runtime.repositories
+  const all = [
+    ...await sessions.listByUser(userId),
+    ...await sessions.listTrashed(userId),
+  ].sort((a, b) => b.updatedAt - a.updatedAt)
+
+  const summaries: InspectorSessionSummary[] = []
+  for (const session of all) {
+    const sessionAgents = await agents.listBySession(session.id)
+    const root = sessionAgents.find((agent) => agent.parentId === null) ?? null
+    summaries.push({
+      id: session.id,
+      title: session.title,
+      status: session.status,
+      deletedAt: session.deletedAt,
+      rootAgentId: root?.id ?? null,
+      rootPhase: root ? agentPhase(root) : null,
+      agentCount: sessionAgents.length,
+      turnCount: sessionAgents.reduce((sum, agent) => sum + agent.turnCount, 0),
+      usage: sumUsage(await usage.list({ sessionId: session.id })),
+      createdAt: session.createdAt,
+      updatedAt: session.updatedAt,
+    })
+  }
+  return summaries
+}
+
+export async function getInspectorSession(
+  runtime: Pick<RuntimeContext, 'repositories'>,
+  userId: string,
+  sessionId: string,
+): Promise<InspectorSession | null> {
+  const { sessions, agents, items, usage } = runtime.repositories
+  const session = await sessions.getById(sessionId)
+  if (!session || session.userId !== userId) return null
+
+  const sessionAgents = (await agents.listBySession(sessionId))
+    .sort((a, b) => a.depth - b.depth || a.createdAt - b.createdAt)
+  const records = await usage.list({ sessionId })
+  const childByCall = new Map(
+    sessionAgents
+      .filter((agent) => agent.sourceCallId)
+      .map((agent) => [agent.sourceCallId!, agent.id]),
+  )
+
+  const inspected: InspectorAgent[] = []
+  let exchanges: InspectorExchange[] = []
+  for (const agent of sessionAgents) {
+    const agentItems = await items.listByAgent(agent.id)
+    inspected.push({
+      id: agent.id,
+      parentId: agent.parentId,
+      sourceCallId: agent.sourceCallId,
+      depth: agent.depth,
+      task: agent.task,
+      model: agent.config.model,
+      provider: agent.config.provider,
+      phase: agentPhase(agent),
+      plan: agent.plan,
+      waitingFor: agent.waitingFor,
+      result: agent.result,
+      error: agent.error,
+      turnCount: agent.turnCount,
+      usage: sumUsage(records.filter((record) => record.agentId === agent.id)),
+      turns: buildTurns(agentItems, childByCall),
+      createdAt: agent.createdAt,
+      completedAt: agent.completedAt,
+    })
+    if (agent.parentId === null) exchanges = buildExchanges(agentItems)
+  }
+
+  return { session, exchanges, agents: inspected, usage: sumUsage(records) }
+}
+
+function buildTurns(items: Item[], childByCall: Map<string, string>): InspectorTurn[] {
+  const turns = new Map<number, InspectorTurn>()
+  const outputs = new Map(
+    items
+      .filter((item) => item.type === 'function_call_output' && item.callId)
+      .map((item) => [item.callId!, item]),
+  )
+
+  for (const item of items) {
+    let turn = turns.get(item.turnNumber)
+    if (!turn) {
+      turn = { turn: item.turnNumber, input: null, reasoning: [], steps: [], response: null }
+      turns.set(item.turnNumber, turn)
+    }
+    switch (item.type) {
+      case 'message':
+        if (item.role === 'user') turn.input = item.content
+        if (item.role === 'assistant') turn.response = item.content
+        break
+      case 'reasoning':
+        if (item.content) turn.reasoning.push(item.content)
+        break
+      case 'function_call': {
+        const output = item.callId ? outputs.get(item.callId) : undefined
+        turn.steps.push({
+          callId: item.callId,
+          name: item.name,
+          arguments: parseArguments(item.arguments),
+          result: output?.output ?? null,
+          isError: output?.isError ?? null,
+          durationMs: output?.durationMs ?? item.durationMs,
+          childAgentId: item.callId ? childByCall.get(item.callId) ?? null : null,
+        })
+        break
+      }
+      case 'function_call_output':
+        break
+    }
+  }
+  return [...turns.values()].sort((a, b) => a.turn - b.turn)
+}
+
+function buildExchanges(items: Item[]): InspectorExchange[] {
+  const lastTurn = items.reduce((max, item) => Math.max(max, item.turnNumber), 0)
+  const userMessages = items.filter((item) => item.type === 'message' && item.role === 'user')
+  return userMessages.map((item, index) => {
+    const next = userMessages[index + 1]
+    return {
+      itemId: item.id,
+      content: item.content,
+      createdAt: item.createdAt,
+      fromTurn: item.turnNumber,
+      toTurn: next ? Math.max(item.turnNumber, next.turnNumber - 1) : lastTurn,
+    }
+  })
+}
+
+function parseArguments(raw: string | null): unknown {
+  if (raw === null) return null
+  try {
+    return JSON.parse(raw)
+  } catch {
+    return raw
+  }
+}
+
+function sumUsage(records: UsageRecord[]): InspectorUsage {
+  const totals = { requests: 0, inputTokens: 0, outputTokens: 0, costUsd: 0 }
+  for (const record of records) {
+    totals.requests++
+    totals.inputTokens += record.inputTokens
+    totals.outputTokens += record.outputTokens
+    totals.costUsd += record.costUsd
+  }
+  totals.costUsd = Math.round(totals.costUsd * 1_000_000) / 1_000_000
+  return totals
+}
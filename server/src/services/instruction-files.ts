@@ -0,0 +1,93 @@
+import { watch, type FSWatcher } from 'fs'
+import fs from 'fs/promises'
+import path from 'path'
+import { logger } from '../lib/logger.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { SessionRepository } from '../repositories/types.js'
+import { getProject, linkedProjects } from './projects.js'
+
+export const INSTRUCTIONS_FILE = 'ASSISTANT.md'
+/** A longer file is cut here; instructions are meant to be a page, not a manual. */
+const MAX_INSTRUCTIONS_CHARS = 16_000
+
+export interface InstructionFile {
+  path: string
+  content: string
+}
+
+export type InstructionFilesDeps = Pick<RuntimeContext, 'projectsDir' | 'sessionFilesRoot'> & {
+  sessions: SessionRepository
+  /** The active workspace's shared folder. */
+  workspaceDir: string
+}
+
+/**
+ * Reads ASSISTANT.md from the workspace folder and from the root of every
+ * project linked to a conversation. Contents are cached per folder and a
+ * watcher drops the cached copy when the file is created, edited or removed,
+ * so the next turn picks up the change.
+ */
+export class InstructionFiles {
+  private readonly cache = new Map<string, string | null>()
+  private readonly watchers = new Map<string, FSWatcher>()
+  private stopped = false
+
+  constructor(private readonly deps: InstructionFilesDeps) {}
+
+  /** Workspace instructions first, then each linked project's, skipping folders without a file. */
+  async forSession(sessionId: string): Promise<InstructionFile[]> {
+    const dirs = [this.deps.workspaceDir]
+    const session = await this.deps.sessions.getById(sessionId)
+    if (session) {
+      for (const name of await linkedProjects(this.deps, sessionId)) {
+        const project = await getProject(this.deps, session.userId, name)
+        if (project) dirs.push(project.root)
+      }
+    }
+
+    const files: InstructionFile[] = []
+    for (const dir of new Set(dirs)) {
+      const content = await this.read(dir)
+      if (content) files.push({ path: path.join(dir, INSTRUCTIONS_FILE), content })
+    }
+    return files
+  }
+
+  stop(): void {
+    this.stopped = true
+    for (const watcher of this.watchers.values()) watcher.close()
+    this.watchers.clear()
+    this.cache.clear()
+  }
+
+  private async read(dir: string): Promise<string | null> {
+    if (this.cache.has(dir)) return this.cache.get(dir)!
+    // Without a watcher a cached copy could go stale, so the file is read again next time
+    const watched = this.watch(dir)
+    const raw = await fs.readFile(path.join(dir, INSTRUCTIONS_FILE), 'utf-8').catch(() => null)
+    const content = raw?.trim() ? raw.trim().slice(0, MAX_INSTRUCTIONS_CHARS) : null
+    if (watched) this.cache.set(dir, content)
+    return content
+  }
+
+  private watch(dir: string): boolean {
+    if (this.watchers.has(dir)) return true
+    if (this.stopped) return false
+    try {
+      const watcher = watch(dir, { persistent: false }, (_event, name) => {
+        if (!name || name === INSTRUCTIONS_FILE) this.cache.delete(dir)
+      })
+      watcher.on('error', (err) => {
+        logger.warn({ dir, err: err.message }, 'Stopped watching instructions file')
+        watcher.close()
+        this.watchers.delete(dir)
+        this.cache.delete(dir)
+      })
+      this.watchers.set(dir, watcher)
+      return true
+    } catch {
+      // Missing folder: nothing to watch yet
+      return false
+    }
+  }
+}
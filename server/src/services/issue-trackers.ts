@@ -0,0 +1,347 @@
+const LINEAR_API_URL = 'https://api.linear.app/graphql'
+const MAX_DESCRIPTION_CHARS = 8_000
+const MAX_COMMENTS = 20
+const LINEAR_SUMMARY_FIELDS = 'identifier title url updatedAt priorityLabel state { name } assignee { name }'
+
+export type IssueTrackerName = 'jira' | 'linear'
+
+export interface TrackerIssueSummary {
+  key: string
+  title: string
+  status: string | null
+  assignee: string | null
+  priority: string | null
+  url: string
+  updatedAt: string
+}
+
+export interface TrackerIssue extends TrackerIssueSummary {
+  description: string
+  reporter: string | null
+  labels: string[]
+  createdAt: string
+  comments: Array<{ author: string | null; body: string; createdAt: string }>
+}
+
+export interface CreateTrackerIssueInput {
+  /** Jira project key or Linear team key. */
+  project: string
+  title: string
+  description?: string
+  /** Jira issue type; ignored by Linear. Defaults to Task. */
+  type?: string
+  /** Jira only; Linear labels are set in Linear. */
+  labels?: string[]
+}
+
+export interface UpdateTrackerIssueInput {
+  title?: string
+  description?: string
+  /** Target status by name, e.g. "In Progress". */
+  status?: string
+  comment?: string
+}
+
+/** One issue tracker behind the jira.* or linear.* tools. */
+export interface IssueTracker {
+  readonly name: IssueTrackerName
+  search(query: string, options: { project?: string; limit?: number }, signal?: AbortSignal): Promise<TrackerIssueSummary[]>
+  getIssue(key: string, signal?: AbortSignal): Promise<TrackerIssue>
+  createIssue(input: CreateTrackerIssueInput, signal?: AbortSignal): Promise<TrackerIssueSummary>
+  updateIssue(key: string, input: UpdateTrackerIssueInput, signal?: AbortSignal): Promise<TrackerIssueSummary>
+}
+
+export class IssueTrackerError extends Error {
+  constructor(message: string, readonly status: number) {
+    super(message)
+    this.name = 'IssueTrackerError'
+  }
+}
+
+/**
+ * Jira Cloud (email + API token) or Data Center (personal access token, no
+ * email). Uses REST API v2 so descriptions and comments stay plain text.
+ */
+export class JiraTracker implements IssueTracker {
+  readonly name = 'jira' as const
+  private readonly baseUrl: string
+
+  constructor(
+    baseUrl: string,
+    private readonly token: string,
+    private readonly email?: string,
+    private readonly fetchImpl: typeof fetch = fetch,
+  ) {
+    this.baseUrl = baseUrl.replace(/\/+$/, '')
+  }
+
+  async search(query: string, options: { project?: string; limit?: number }, signal?: AbortSignal): Promise<TrackerIssueSummary[]> {
+    const params = new URLSearchParams({
+      jql: toJql(query, options.project),
+      maxResults: String(clampLimit(options.limit)),
+      fields: 'summary,status,assignee,priority,updated',
+    })
+    // Cloud retired /search in favour of /search/jql; Data Center only has /search
+    const path = this.email ? '/rest/api/2/search/jql' : '/rest/api/2/search'
+    const found = await this.request<{ issues: RawJiraIssue[] }>('GET', `${path}?${params}`, undefined, signal)
+    return found.issues.map((issue) => this.toSummary(issue))
+  }
+
+  async getIssue(key: string, signal?: AbortSignal): Promise<TrackerIssue> {
+    const fields = 'summary,status,assignee,reporter,priority,labels,description,created,updated,comment'
+    const raw = await this.request<RawJiraIssue>('GET', `/rest/api/2/issue/${encodeURIComponent(key)}?fields=${fields}`, undefined, signal)
+    const comments = raw.fields.comment?.comments ?? []
+    return {
+      ...this.toSummary(raw),
+      description: truncate(raw.fields.description ?? ''),
+      reporter: raw.fields.reporter?.displayName ?? null,
+      labels: raw.fields.labels ?? [],
+      createdAt: raw.fields.created ?? '',
+      comments: comments.slice(-MAX_COMMENTS).map((comment) => ({
+        author: comment.author?.displayName ?? null,
+        body: truncate(comment.body ?? ''),
+        createdAt: comment.created,
+      })),
+    }
+  }
+
+  async createIssue(input: CreateTrackerIssueInput, signal?: AbortSignal): Promise<TrackerIssueSummary> {
+    const created = await this.request<{ key: string }>('POST', '/rest/api/2/issue', {
+      fields: {
+        project: { key: input.project },
+        summary: input.title,
+        issuetype: { name: input.type ?? 'Task' },
+        ...(input.description && { description: input.description }),
+        ...(input.labels?.length && { labels: input.labels }),
+      },
+    }, signal)
+    return this.getIssue(created.key, signal)
+  }
+
+  async updateIssue(key: string, input: UpdateTrackerIssueInput, signal?: AbortSignal): Promise<TrackerIssueSummary> {
+    const path = `/rest/api/2/issue/${encodeURIComponent(key)}`
+    const fields = {
+      ...(input.title !== undefined && { summary: input.title }),
+      ...(input.description !== undefined && { description: input.description }),
+    }
+    if (Object.keys(fields).length > 0) await this.request('PUT', path, { fields }, signal)
+    if (input.status) {
+      // Statuses change through workflow transitions, not field updates
+      const { transitions } = await this.request<{ transitions: Array<{ id: string; name: string; to?: { name: string } }> }>('GET', `${path}/transitions`, undefined, signal)
+      const wanted = input.status.toLowerCase()
+      const transition = transitions.find((item) => item.to?.name.toLowerCase() === wanted || item.name.toLowerCase() === wanted)
+      if (!transition) {
+        const available = transitions.map((item) => item.to?.name ?? item.name).join(', ') || 'none'
+        throw new IssueTrackerError(`Cannot move ${key} to "${input.status}"; available: ${available}`, 400)
+      }
+      await this.request('POST', `${path}/transitions`, { transition: { id: transition.id } }, signal)
+    }
+    if (input.comment) await this.request('POST', `${path}/comment`, { body: input.comment }, signal)
+    return this.getIssue(key, signal)
+  }
+
+  private toSummary(raw: RawJiraIssue): TrackerIssueSummary {
+    return {
+      key: raw.key,
+      title: raw.fields.summary ?? '',
+      status: raw.fields.status?.name ?? null,
+      assignee: raw.fields.assignee?.displayName ?? null,
+      priority: raw.fields.priority?.name ?? null,
+      url: `${this.baseUrl}/browse/${raw.key}`,
+      updatedAt: raw.fields.updated ?? '',
+    }
+  }
+
+  private async request<T>(method: string, path: string, body: unknown, signal?: AbortSignal): Promise<T> {
+    const authorization = this.email
+      ? `Basic ${Buffer.from(`${this.email}:${this.token}`).toString('base64')}`
+      : `Bearer ${this.token}`
+    const response = await this.fetchImpl(`${this.baseUrl}${path}`, {
+      method,
+      headers: {
+        Accept: 'application/json',
+        Authorization: authorization,
+        ...(body !== undefined && { 'Content-Type': 'application/json' }),
+      },
+      body: body !== undefined ? JSON.stringify(body) : undefined,
+      signal,
+    })
+    if (!response.ok) {
+      const detail = await response.json().catch(() => null) as { errorMessages?: string[]; errors?: Record<string, string> } | null
+      const messages = [...(detail?.errorMessages ?? []), ...Object.entries(detail?.errors ?? {}).map(([field, message]) => `${field}: ${message}`)]
+      throw new IssueTrackerError(`Jira ${method} ${path.split('?')[0]} failed (${response.status}): ${messages.join('; ') || response.statusText}`, response.status)
+    }
+    // Updates and transitions answer 204 No Content
+    return (response.status === 204 ? undefined : await response.json()) as T
+  }
+}
+
+/** Linear's GraphQL API with a personal API key. Projects are team keys, e.g. "ENG". */
+export class LinearTracker implements IssueTracker {
+  readonly name = 'linear' as const
+
+  constructor(
+    private readonly apiKey: string,
+    private readonly fetchImpl: typeof fetch = fetch,
+  ) {}
+
+  async search(query: string, options: { project?: string; limit?: number }, signal?: AbortSignal): Promise<TrackerIssueSummary[]> {
+    const data = await this.request<{ searchIssues: { nodes: RawLinearIssue[] } }>(`
+      query Search($term: String!, $first: Int!, $filter: IssueFilter) {
+        searchIssues(term: $term, first: $first, filter: $filter) { nodes { ${LINEAR_SUMMARY_FIELDS} } }
+      }`, {
+      term: query,
+      first: clampLimit(options.limit),
+      filter: options.project ? { team: { key: { eq: options.project.toUpperCase() } } } : null,
+    }, signal)
+    return data.searchIssues.nodes.map(toLinearSummary)
+  }
+
+  async getIssue(key: string, signal?: AbortSignal): Promise<TrackerIssue> {
+    const data = await this.request<{ issue: RawLinearIssue | null }>(`
+      query Issue($id: String!) {
+        issue(id: $id) {
+          ${LINEAR_SUMMARY_FIELDS}
+          description createdAt creator { name } labels { nodes { name } }
+          comments(first: ${MAX_COMMENTS}) { nodes { body createdAt user { name } } }
+        }
+      }`, { id: key }, signal)
+    if (!data.issue) throw new IssueTrackerError(`Linear issue not found: ${key}`, 404)
+    const issue = data.issue
+    return {
+      ...toLinearSummary(issue),
+      description: truncate(issue.description ?? ''),
+      reporter: issue.creator?.name ?? null,
+      labels: issue.labels?.nodes.map((label) => label.name) ?? [],
+      createdAt: issue.createdAt ?? '',
+      comments: (issue.comments?.nodes ?? []).map((comment) => ({
+        author: comment.user?.name ?? null,
+        body: truncate(comment.body),
+        createdAt: comment.createdAt,
+      })),
+    }
+  }
+
+  async createIssue(input: CreateTrackerIssueInput, signal?: AbortSignal): Promise<TrackerIssueSummary> {
+    const teams = await this.request<{ teams: { nodes: Array<{ id: string }> } }>(`
+      query Team($key: String!) { teams(filter: { key: { eq: $key } }) { nodes { id } } }`, { key: input.project.toUpperCase() }, signal)
+    const teamId = teams.teams.nodes[0]?.id
+    if (!teamId) throw new IssueTrackerError(`Linear team not found: ${input.project}`, 404)
+    const data = await this.request<{ issueCreate: { success: boolean; issue: RawLinearIssue } }>(`
+      mutation Create($input: IssueCreateInput!) { issueCreate(input: $input) { success issue { ${LINEAR_SUMMARY_FIELDS} } } }`, {
+      input: { teamId, title: input.title, ...(input.description && { description: input.description }) },
+    }, signal)
+    return toLinearSummary(data.issueCreate.issue)
+  }
+
+  async updateIssue(key: string, input: UpdateTrackerIssueInput, signal?: AbortSignal): Promise<TrackerIssueSummary> {
+    const update: Record<string, unknown> = {
+      ...(input.title !== undefined && { title: input.title }),
+      ...(input.description !== undefined && { description: input.description }),
+    }
+    const current = await this.request<{ issue: { id: string; team: { states: { nodes: Array<{ id: string; name: string }> } } } | null }>(`
+      query States($id: String!) { issue(id: $id) { id team { states { nodes { id name } } } } }`, { id: key }, signal)
+    if (!current.issue) throw new IssueTrackerError(`Linear issue not found: ${key}`, 404)
+    if (input.status) {
+      const states = current.issue.team.states.nodes
+      const state = states.find((item) => item.name.toLowerCase() === input.status!.toLowerCase())
+      if (!state) throw new IssueTrackerError(`Cannot move ${key} to "${input.status}"; available: ${states.map((item) => item.name).join(', ')}`, 400)
+      update.stateId = state.id
+    }
+    let issue: RawLinearIssue | null = null
+    if (Object.keys(update).length > 0) {
+      const data = await this.request<{ issueUpdate: { issue: RawLinearIssue } }>(`
+        mutation Update($id: String!, $input: IssueUpdateInput!) { issueUpdate(id: $id, input: $input) { success issue { ${LINEAR_SUMMARY_FIELDS} } } }`, {
+        id: current.issue.id,
+        input: update,
+      }, signal)
+      issue = data.issueUpdate.issue
+    }
+    if (input.comment) {
+      await this.request(`
+        mutation Comment($input: CommentCreateInput!) { commentCreate(input: $input) { success } }`, {
+        input: { issueId: current.issue.id, body: input.comment },
+      }, signal)
+    }
+    return issue ? toLinearSummary(issue) : this.getIssue(key, signal)
+  }
+
+  private async request<T>(query: string, variables: Record<string, unknown>, signal?: AbortSignal): Promise<T> {
+    const response = await this.fetchImpl(LINEAR_API_URL, {
+      method: 'POST',
+      headers: { Authorization: this.apiKey, 'Content-Type': 'application/json' },
+      body: JSON.stringify({ query, variables }),
+      signal,
+    })
+    const payload = await response.json().catch(() => null) as { data?: T; errors?: Array<{ message: string }> } | null
+    if (!response.ok || payload?.errors?.length || !payload?.data) {
+      const message = payload?.errors?.map((error) => error.message).join('; ') || response.statusText
+      throw new IssueTrackerError(`Linear request failed (${response.status}): ${message}`, response.status)
+    }
+    return payload.data
+  }
+}
+
+interface RawJiraUser { displayName?: string }
+interface RawJiraIssue {
+  key: string
+  fields: {
+    summary?: string
+    status?: { name: string }
+    assignee?: RawJiraUser | null
+    reporter?: RawJiraUser | null
+    priority?: { name: string } | null
+    labels?: string[]
+    description?: string | null
+    created?: string
+    updated?: string
+    comment?: { comments: Array<{ author?: RawJiraUser; body?: string; created: string }> }
+  }
+}
+interface RawLinearIssue {
+  identifier: string
+  title: string
+  url: string
+  updatedAt: string
+  priorityLabel?: string | null
+  state?: { name: string } | null
+  assignee?: { name: string } | null
+  description?: string | null
+  createdAt?: string
+  creator?: { name: string } | null
+  labels?: { nodes: Array<{ name: string }> }
+  comments?: { nodes: Array<{ body: string; createdAt: string; user?: { name: string } | null }> }
+}
+
+function toLinearSummary(raw: RawLinearIssue): TrackerIssueSummary {
+  return {
+    key: raw.identifier,
+    title: raw.title,
+    status: raw.state?.name ?? null,
+    assignee: raw.assignee?.name ?? null,
+    priority: raw.priorityLabel ?? null,
+    url: raw.url,
+    updatedAt: raw.updatedAt,
+  }
+}
+
+/** Plain words become a text search; anything that already looks like JQL is used as-is. */
+export function toJql(query: string, project?: string): string {
+  const trimmed = query.trim()
+  const isJql = /[=~<>]|\bORDER BY\b|\b(?:not\s+)?in\s*\(|\bis\s+(?:not\s+)?(?:empty|null)\b/i.test(trimmed)
+  const order = isJql ? trimmed.match(/\bORDER BY\s+.*$/i)?.[0] : undefined
+  const filter = order ? trimmed.slice(0, -order.length).trim() : trimmed
+  const clauses = [
+    project ? `project = "${project.replace(/"/g, '')}"` : '',
+    filter ? (isJql ? `(${filter})` : `text ~ "${filter.replace(/["\\]/g, ' ')}"`) : '',
+  ].filter(Boolean)
+  return `${clauses.join(' AND ')} ${order ?? 'ORDER BY updated DESC'}`.trim()
+}
+
+function truncate(text: string): string {
+  return text.length > MAX_DESCRIPTION_CHARS ? `${text.slice(0, MAX_DESCRIPTION_CHARS)}\n[truncated]` : text
+}
+
+function clampLimit(limit: number | undefined): number {
+  return Math.min(Math.max(1, Math.floor(limit ?? 10)), 50)
+}
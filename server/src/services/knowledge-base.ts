@@ -0,0 +1,260 @@
+import fs from 'fs/promises'
+import path from 'path'
+import { looksBinary, MAX_ATTACHMENT_BYTES } from '../lib/attachment-validation.js'
+import { logger } from '../lib/logger.js'
+import { isPdf } from '../lib/pdf-text.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { isZip } from '../lib/zip.js'
+import type {
+  KnowledgeDocument,
+  KnowledgeSource,
+  KnowledgeSourceKind,
+  KnowledgeSourceRepository,
+  SessionRepository,
+} from '../repositories/types.js'
+import { decodeHtmlEntities, extractReadableHtmlBody } from '../tools/web.js'
+import { indexAttachment, rankChunks, type AttachmentIndexRuntime, type SearchSource } from './attachment-index.js'
+import { extractOfficeDocument, inferOfficeFormat, OfficeInputError } from './office-extraction.js'
+import { extractPdf, PdfInputError, type PdfExtraction } from './pdf-extraction.js'
+import { walkProject } from './projects.js'
+import { extractTextFile, TextInputError } from './text-extraction.js'
+
+/** Pages larger than this are refused rather than downloaded in full. */
+const MAX_URL_BYTES = MAX_ATTACHMENT_BYTES.pdf
+const URL_FETCH_TIMEOUT_MS = 30_000
+
+export class KnowledgeInputError extends Error {}
+
+export interface KnowledgeSearchResult {
+  sourceId: string
+  source: string
+  title: string
+  /** URL for web pages, otherwise the file's absolute path. */
+  location: string
+  /** Ready-made reference for the answer: the title followed by the location. */
+  citation: string
+  /** Index of the chunk within the document's text. */
+  chunk: number
+  score: number
+  text: string
+}
+
+type KnowledgeStore = { repositories: { knowledge: KnowledgeSourceRepository } }
+export type KnowledgeIndexRuntime = AttachmentIndexRuntime & Pick<RuntimeContext, 'shutdownController'>
+export type KnowledgeSearchDeps = AttachmentIndexRuntime & { knowledge: KnowledgeSourceRepository; sessions: SessionRepository }
+
+/**
+ * Registers a folder, document or URL for the knowledge base. The source
+ * starts out pending; KnowledgeIndexer reads and embeds it in the background.
+ */
+export async function addKnowledgeSource(
+  runtime: KnowledgeStore,
+  userId: string,
+  input: { location: string; name?: string },
+): Promise<KnowledgeSource> {
+  const location = input.location.trim()
+  let kind: KnowledgeSourceKind
+  let defaultName: string
+  if (/^https?:\/\//i.test(location)) {
+    let url: URL
+    try {
+      url = new URL(location)
+    } catch {
+      throw new KnowledgeInputError(`Invalid URL: ${location}`)
+    }
+    kind = 'url'
+    defaultName = `${url.hostname}${url.pathname === '/' ? '' : url.pathname}`
+  } else {
+    if (!location || !path.isAbsolute(location)) throw new KnowledgeInputError('An absolute path or http(s) URL is required')
+    const stat = await fs.stat(location).catch(() => null)
+    if (!stat) throw new KnowledgeInputError(`Not found: ${location}`)
+    kind = stat.isDirectory() ? 'folder' : 'document'
+    defaultName = path.basename(location)
+  }
+  const name = input.name?.trim() || defaultName
+  if (name.length > 200) throw new KnowledgeInputError('Name is too long (200 characters at most)')
+  return runtime.repositories.knowledge.create({ userId, kind, location, name })
+}
+
+/** Reads a source, stores each document's text and embeds its chunks; returns the documents indexed. */
+export async function indexKnowledgeSource(
+  runtime: KnowledgeIndexRuntime,
+  source: Pick<KnowledgeSource, 'kind' | 'location'>,
+  signal?: AbortSignal,
+): Promise<KnowledgeDocument[]> {
+  const documents: KnowledgeDocument[] = []
+  const add = async (docPath: string, title: string, extraction: PdfExtraction) => {
+    await indexAttachment(runtime, extraction.hash, extraction.chunks, signal)
+    documents.push({ path: docPath, title, hash: extraction.hash, chunks: extraction.chunks.length })
+  }
+
+  if (source.kind === 'url') {
+    const page = await fetchPage(source.location, signal)
+    await add(source.location, page.title, await extractDocument(runtime, page.fileName, page.bytes, signal))
+  } else if (source.kind === 'document') {
+    const data = await fs.readFile(source.location)
+    const fileName = path.basename(source.location)
+    await add(fileName, fileName, await extractDocument(runtime, fileName, data, signal))
+  } else {
+    const { paths } = await walkProject(source.location)
+    for (const relative of paths) {
+      signal?.throwIfAborted()
+      const data = await fs.readFile(path.join(source.location, relative)).catch(() => null)
+      if (!data) continue
+      try {
+        await add(relative, relative, await extractDocument(runtime, relative, data, signal))
+      } catch (err) {
+        // A folder keeps whatever it can read; binary and unsupported files are left out
+        if (err instanceof KnowledgeInputError || err instanceof TextInputError
+          || err instanceof PdfInputError || err instanceof OfficeInputError) continue
+        throw err
+      }
+    }
+  }
+  if (documents.length === 0) throw new KnowledgeInputError(`No readable documents in ${source.location}`)
+  return documents
+}
+
+/** The passages of a user's ready sources most relevant to a query. */
+export async function searchKnowledge(
+  deps: KnowledgeSearchDeps,
+  sessionId: string,
+  query: string,
+  options: { source?: string; limit?: number; signal?: AbortSignal } = {},
+): Promise<KnowledgeSearchResult[]> {
+  const session = await deps.sessions.getById(sessionId)
+  if (!session) throw new Error(`Session not found: ${sessionId}`)
+  let sources = (await deps.knowledge.listByUser(session.userId)).filter((source) => source.status === 'ready')
+  if (options.source) {
+    sources = sources.filter((source) => source.id === options.source || source.name === options.source)
+    if (sources.length === 0) throw new Error(`No indexed knowledge source named ${options.source}`)
+  }
+  if (sources.length === 0) throw new Error('The knowledge base has no indexed sources')
+
+  // Search sources are named by position so results map back to their document
+  const documents: Array<{ source: KnowledgeSource; document: KnowledgeDocument }> = []
+  const searchSources: SearchSource[] = []
+  for (const source of sources) {
+    for (const document of source.documents) {
+      const extraction = await deps.attachments.getExtraction<PdfExtraction>(document.hash)
+      if (!extraction?.chunks.length) continue
+      searchSources.push({ name: String(documents.length), hash: document.hash, chunks: extraction.chunks })
+      documents.push({ source, document })
+    }
+  }
+  if (searchSources.length === 0) throw new Error('The knowledge base has no indexed documents')
+
+  const results = await rankChunks(deps, searchSources, query, options)
+  return results.map(({ attachment, chunk, score, text }) => {
+    const { source, document } = documents[Number(attachment)]
+    const location = source.kind === 'folder' ? path.join(source.location, document.path) : source.location
+    return {
+      sourceId: source.id,
+      source: source.name,
+      title: document.title,
+      location,
+      citation: document.title === location ? location : `${document.title} — ${location}`,
+      chunk,
+      score,
+      text,
+    }
+  })
+}
+
+async function extractDocument(
+  runtime: KnowledgeIndexRuntime,
+  fileName: string,
+  data: Buffer,
+  signal?: AbortSignal,
+): Promise<PdfExtraction> {
+  if (isPdf(data)) return extractPdf(runtime, { bytes: data, fileName, signal })
+  if (inferOfficeFormat(undefined, fileName) && isZip(data)) return extractOfficeDocument(runtime, { bytes: data, fileName })
+  if (looksBinary(data)) throw new KnowledgeInputError(`Unsupported file: ${fileName}`)
+  return extractTextFile(runtime, { bytes: data, fileName })
+}
+
+async function fetchPage(url: string, signal?: AbortSignal): Promise<{ fileName: string; title: string; bytes: Buffer }> {
+  const timeout = AbortSignal.timeout(URL_FETCH_TIMEOUT_MS)
+  const response = await fetch(url, { signal: signal ? AbortSignal.any([signal, timeout]) : timeout, redirect: 'follow' })
+  if (!response.ok) throw new KnowledgeInputError(`Fetching ${url} failed with HTTP ${response.status}`)
+  const declared = Number(response.headers.get('content-length') ?? 0)
+  if (declared > MAX_URL_BYTES) throw new KnowledgeInputError(`${url} is too large (${declared} bytes)`)
+  const data = Buffer.from(await response.arrayBuffer())
+  if (data.length > MAX_URL_BYTES) throw new KnowledgeInputError(`${url} is too large (${data.length} bytes)`)
+
+  const fileName = decodeURIComponent(new URL(response.url || url).pathname.split('/').pop() || '') || 'index.html'
+  const contentType = response.headers.get('content-type') ?? ''
+  if (!contentType.includes('html')) return { fileName, title: url, bytes: data }
+
+  const html = data.toString('utf-8')
+  const title = decodeHtmlEntities(html.match(/<title[^>]*>([\s\S]*?)<\/title>/i)?.[1] ?? '').replace(/\s+/g, ' ').trim()
+  return { fileName: `${fileName}.txt`, title: title || url, bytes: Buffer.from(extractReadableHtmlBody(html)) }
+}
+
+/**
+ * Indexes knowledge sources one at a time in the background. Sources still
+ * pending or interrupted mid-index when the server stopped are picked up
+ * again on start.
+ */
+export class KnowledgeIndexer {
+  private readonly queue: string[] = []
+  private running: Promise<void> | null = null
+  private readonly controller = new AbortController()
+
+  constructor(private readonly runtime: KnowledgeIndexRuntime & KnowledgeStore) {}
+
+  async start(): Promise<void> {
+    try {
+      for (const source of await this.runtime.repositories.knowledge.listByStatus(['pending', 'indexing'])) this.enqueue(source.id)
+    } catch (err) {
+      logger.warn({ err }, 'Could not resume knowledge indexing')
+    }
+  }
+
+  stop(): void {
+    this.queue.length = 0
+    this.controller.abort()
+  }
+
+  enqueue(id: string): void {
+    if (this.controller.signal.aborted || this.queue.includes(id)) return
+    this.queue.push(id)
+    this.running ??= this.drain().finally(() => {
+      this.running = null
+      // Anything enqueued while the drain was finishing still needs a run
+      if (this.queue.length > 0) this.enqueue(this.queue.shift()!)
+    })
+  }
+
+  /** Resolves once the queue is empty. */
+  async idle(): Promise<void> {
+    while (this.running) await this.running
+  }
+
+  private async drain(): Promise<void> {
+    while (this.queue.length > 0 && !this.controller.signal.aborted) {
+      await this.index(this.queue.shift()!)
+    }
+  }
+
+  private async index(id: string): Promise<void> {
+    const repo = this.runtime.repositories.knowledge
+    const source = await repo.getById(id)
+    if (!source) return
+    await repo.update(id, { status: 'indexing', error: null })
+    const signal = AbortSignal.any([this.controller.signal, this.runtime.shutdownController.signal])
+    try {
+      const documents = await indexKnowledgeSource(this.runtime, source, signal)
+      // Deleting a source while it indexes leaves nothing to update
+      if (await repo.update(id, { status: 'ready', documents, indexedAt: Date.now() })) {
+        logger.info({ sourceId: id, documents: documents.length }, 'Knowledge source indexed')
+      }
+    } catch (err) {
+      // Stopped mid-index: the source stays `indexing` and resumes on the next start
+      if (signal.aborted) return
+      const message = err instanceof Error ? err.message : String(err)
+      logger.warn({ sourceId: id, err: message }, 'Knowledge source indexing failed')
+      await repo.update(id, { status: 'failed', error: message })
+    }
+  }
+}
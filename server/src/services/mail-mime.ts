@@ -0,0 +1,207 @@
+import { randomUUID } from 'node:crypto'
+
+/** Long enough for a long thread; quoted history past this rarely matters to the agent. */
+const MAX_TEXT_CHARS = 40_000
+
+export interface ParsedMessage {
+  headers: Map<string, string[]>
+  text: string
+  truncated: boolean
+  attachments: Array<{ filename: string; contentType: string; size: number }>
+}
+
+export interface OutgoingMessage {
+  from: string
+  fromName?: string
+  to: string[]
+  cc?: string[]
+  subject: string
+  body: string
+  inReplyTo?: string
+  references?: string
+}
+
+interface MimePart {
+  headers: Map<string, string[]>
+  /** Raw bytes of the body as a latin1 string, so decoding can pick the charset. */
+  body: string
+}
+
+/** Parses an RFC 5322 message into headers, readable text and an attachment list. */
+export function parseMessage(raw: Buffer): ParsedMessage {
+  const root = splitPart(raw.toString('latin1'))
+  const collected = { plain: [] as string[], html: [] as string[], attachments: [] as ParsedMessage['attachments'] }
+  walk(root, collected)
+  const text = collected.plain.length > 0 ? collected.plain.join('\n\n') : collected.html.map(htmlToText).join('\n\n')
+  return {
+    headers: root.headers,
+    text: text.slice(0, MAX_TEXT_CHARS),
+    truncated: text.length > MAX_TEXT_CHARS,
+    attachments: collected.attachments,
+  }
+}
+
+/** Parses only a header block, e.g. from BODY[HEADER.FIELDS (...)]. */
+export function parseHeaders(raw: Buffer): Map<string, string[]> {
+  return splitPart(raw.toString('latin1')).headers
+}
+
+export function header(headers: Map<string, string[]>, name: string): string | null {
+  return headers.get(name.toLowerCase())?.[0] ?? null
+}
+
+/** A plain-text UTF-8 message ready for SMTP DATA or IMAP APPEND, with CRLF line endings. */
+export function buildMessage(message: OutgoingMessage, now = new Date()): { raw: string; messageId: string } {
+  const domain = message.from.split('@')[1] ?? 'localhost'
+  const messageId = `<${randomUUID()}@${domain}>`
+  const lines = [
+    `From: ${message.fromName ? `${encodeWord(message.fromName)} <${message.from}>` : message.from}`,
+    `To: ${message.to.join(', ')}`,
+    ...(message.cc && message.cc.length > 0 ? [`Cc: ${message.cc.join(', ')}`] : []),
+    `Subject: ${encodeWord(message.subject)}`,
+    `Date: ${now.toUTCString().replace('GMT', '+0000')}`,
+    `Message-ID: ${messageId}`,
+    ...(message.inReplyTo ? [`In-Reply-To: ${message.inReplyTo}`] : []),
+    ...(message.references ? [`References: ${message.references}`] : []),
+    'MIME-Version: 1.0',
+    'Content-Type: text/plain; charset=utf-8',
+    'Content-Transfer-Encoding: base64',
+    '',
+    ...(Buffer.from(message.body.replace(/\r?\n/g, '\r\n'), 'utf-8').toString('base64').match(/.{1,76}/g) ?? ['']),
+  ]
+  return { raw: `${lines.join('\r\n')}\r\n`, messageId }
+}
+
+/** Loose check that keeps header injection and obvious typos out. */
+export function isEmailAddress(value: string): boolean {
+  return /^[^\s@<>,;"]+@[^\s@<>,;"]+\.[^\s@<>,;"]+$/.test(value)
+}
+
+/** "Ana <ana@example.com>" → "ana@example.com". */
+export function addressOf(value: string): string {
+  return (value.match(/<([^>]+)>/)?.[1] ?? value).trim()
+}
+
+function walk(part: MimePart, collected: { plain: string[]; html: string[]; attachments: ParsedMessage['attachments'] }): void {
+  const { type, params } = parseParams(header(part.headers, 'content-type') ?? 'text/plain')
+  const disposition = parseParams(header(part.headers, 'content-disposition') ?? '')
+  if (type.startsWith('multipart/') && params.boundary) {
+    for (const child of splitMultipart(part.body, params.boundary)) walk(child, collected)
+    return
+  }
+  const filename = disposition.params.filename ?? params.name
+  const bytes = decodeTransfer(part.body, header(part.headers, 'content-transfer-encoding'))
+  if (disposition.type === 'attachment' || filename || !type.startsWith('text/')) {
+    collected.attachments.push({ filename: filename ? decodeWords(filename) : '(unnamed)', contentType: type, size: bytes.length })
+    return
+  }
+  const text = decodeCharset(bytes, params.charset)
+  if (type === 'text/html') collected.html.push(text)
+  else collected.plain.push(text)
+}
+
+function splitPart(raw: string): MimePart {
+  const match = raw.match(/\r?\n\r?\n/)
+  const headerBlock = match ? raw.slice(0, match.index) : raw
+  const body = match ? raw.slice(match.index! + match[0].length) : ''
+  const headers = new Map<string, string[]>()
+  // Headers may carry raw UTF-8 (RFC 6532)
+  const unfolded = Buffer.from(headerBlock, 'latin1').toString('utf-8').replace(/\r?\n[ \t]+/g, ' ')
+  for (const line of unfolded.split(/\r?\n/)) {
+    const colon = line.indexOf(':')
+    if (colon <= 0) continue
+    const name = line.slice(0, colon).trim().toLowerCase()
+    headers.set(name, [...(headers.get(name) ?? []), decodeWords(line.slice(colon + 1).trim())])
+  }
+  return { headers, body }
+}
+
+function splitMultipart(body: string, boundary: string): MimePart[] {
+  const parts: MimePart[] = []
+  const delimiter = `--${boundary}`
+  const sections = body.split(new RegExp(`\\r?\\n?${escapeRegExp(delimiter)}`))
+  // sections[0] is the preamble; a section starting with "--" is the closing delimiter
+  for (const section of sections.slice(1)) {
+    if (section.startsWith('--')) break
+    parts.push(splitPart(section.replace(/^[ \t]*\r?\n/, '')))
+  }
+  return parts
+}
+
+function parseParams(value: string): { type: string; params: Record<string, string> } {
+  const [type, ...rest] = value.split(';')
+  const params: Record<string, string> = {}
+  for (const match of rest.join(';').matchAll(/([\w*-]+)\s*=\s*(?:"((?:[^"\\]|\\.)*)"|([^;\s]+))/g)) {
+    const key = match[1].toLowerCase()
+    const raw = match[2] !== undefined ? match[2].replace(/\\(.)/g, '$1') : match[3]
+    // RFC 2231: filename*=utf-8''na%C3%AFve.pdf
+    if (key.endsWith('*')) {
+      const encoded = raw.match(/^([^']*)'[^']*'(.*)$/)
+      params[key.slice(0, -1)] = encoded
+        ? decodeCharset(Buffer.from(encoded[2].replace(/%([0-9A-Fa-f]{2})/g, (_, hex: string) => String.fromCharCode(parseInt(hex, 16))), 'latin1'), encoded[1])
+        : raw
+    } else if (!(key in params)) {
+      params[key] = raw
+    }
+  }
+  return { type: type.trim().toLowerCase(), params }
+}
+
+function decodeTransfer(body: string, encoding: string | null): Buffer {
+  switch (encoding?.trim().toLowerCase()) {
+    case 'base64':
+      return Buffer.from(body.replace(/[^A-Za-z0-9+/=]/g, ''), 'base64')
+    case 'quoted-printable':
+      return Buffer.from(
+        body.replace(/=\r?\n/g, '').replace(/=([0-9A-Fa-f]{2})/g, (_, hex: string) => String.fromCharCode(parseInt(hex, 16))),
+        'latin1',
+      )
+    default:
+      return Buffer.from(body, 'latin1')
+  }
+}
+
+function decodeCharset(bytes: Buffer, charset: string | undefined): string {
+  try {
+    return new TextDecoder(charset?.trim() || 'utf-8').decode(bytes)
+  } catch {
+    return bytes.toString('utf-8')
+  }
+}
+
+/** RFC 2047 encoded words: =?utf-8?B?...?= and =?iso-8859-2?Q?...?=. */
+function decodeWords(value: string): string {
+  return value
+    .replace(/(=\?[^?]+\?[BbQq]\?[^?]*\?=)\s+(?==\?[^?]+\?[BbQq]\?[^?]*\?=)/g, '$1')
+    .replace(/=\?([^?]+)\?([BbQq])\?([^?]*)\?=/g, (_, charset: string, encoding: string, text: string) => {
+      const bytes = encoding.toUpperCase() === 'B'
+        ? Buffer.from(text, 'base64')
+        : Buffer.from(text.replace(/_/g, ' ').replace(/=([0-9A-Fa-f]{2})/g, (__, hex: string) => String.fromCharCode(parseInt(hex, 16))), 'latin1')
+      return decodeCharset(bytes, charset.split('*')[0])
+    })
+}
+
+function encodeWord(value: string): string {
+  return /^[\x20-\x7e]*$/.test(value) ? value : `=?UTF-8?B?${Buffer.from(value, 'utf-8').toString('base64')}?=`
+}
+
+function htmlToText(html: string): string {
+  return html
+    .replace(/<(script|style)[\s\S]*?<\/\1>/gi, '')
+    .replace(/<br\s*\/?>/gi, '\n')
+    .replace(/<\/(p|div|tr|li|h[1-6])>/gi, '\n')
+    .replace(/<[^>]+>/g, '')
+    .replace(/&nbsp;/g, ' ')
+    .replace(/&lt;/g, '<')
+    .replace(/&gt;/g, '>')
+    .replace(/&quot;/g, '"')
+    .replace(/&#39;/g, "'")
+    .replace(/&amp;/g, '&')
+    .replace(/[ \t]+/g, ' ')
+    .replace(/\n\s*\n\s*\n+/g, '\n\n')
+    .trim()
+}
+
+function escapeRegExp(value: string): string {
+  return value.replace(/[.*+?^${}()|[\]\\]/g, '\\$&')
+}
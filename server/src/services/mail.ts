@@ -0,0 +1,604 @@
+import net from 'node:net'
+import tls from 'node:tls'
+import { once } from 'node:events'
+import os from 'node:os'
+import type { ApiKeyRepository } from '../repositories/types.js'
+import { addressOf, buildMessage, header, isEmailAddress, parseHeaders, parseMessage, type OutgoingMessage } from './mail-mime.js'
+
+/** The account lives in the encrypted api_keys store under this name, never in plain config. */
+export const MAIL_ACCOUNT_KEY = 'mail'
+
+const SOCKET_TIMEOUT_MS = 30_000
+const DEFAULT_LIMIT = 20
+const MAX_LIMIT = 100
+/** Messages larger than this are read partially; attachments are listed, not downloaded. */
+const MAX_FETCH_BYTES = 5 * 1024 * 1024
+const LIST_HEADER_FIELDS = 'FROM TO CC SUBJECT DATE MESSAGE-ID'
+
+export type MailSecurity = 'tls' | 'starttls' | 'none'
+
+export interface MailServer {
+  host: string
+  port: number
+  /** `tls` for 993/465, `starttls` for 143/587, `none` only for local bridges. */
+  security: MailSecurity
+}
+
+export interface MailAccount {
+  imap: MailServer
+  smtp: MailServer
+  username: string
+  password: string
+  /** Sender address; defaults to the username. */
+  from: string
+  fromName?: string
+  /** Keep a copy in the Sent mailbox after sending; off for servers that do it themselves (Gmail, Outlook). */
+  saveSentCopy: boolean
+}
+
+export interface MailSummary {
+  /** `<mailbox>#<uid>`, stable while the mailbox keeps its UIDVALIDITY. */
+  id: string
+  from: string | null
+  to: string | null
+  subject: string | null
+  date: string | null
+  unread: boolean
+}
+
+export interface MailMessage extends MailSummary {
+  cc: string | null
+  messageId: string | null
+  text: string
+  truncated: boolean
+  attachments: Array<{ filename: string; contentType: string; size: number }>
+}
+
+export interface MailDraftInput {
+  to: string[]
+  cc?: string[]
+  bcc?: string[]
+  subject?: string
+  body: string
+  /** Message to reply to; fills recipients, subject and threading headers. */
+  replyToId?: string
+}
+
+export class MailError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'MailError'
+  }
+}
+
+/** The account as shown to clients: everything but the password. */
+export type MailAccountView = Omit<MailAccount, 'password'>
+
+/**
+ * Validates an account from the settings UI; throws with a message suitable
+ * for a 400. A missing password keeps the saved one.
+ */
+export function normalizeMailAccount(input: Record<string, unknown>, existing: MailAccount | null): MailAccount {
+  const server = (value: unknown, name: string, defaultPort: number): MailServer => {
+    const raw = (value ?? {}) as Record<string, unknown>
+    const host = typeof raw.host === 'string' ? raw.host.trim() : ''
+    if (!host) throw new Error(`${name}.host is required`)
+    const security = raw.security ?? 'tls'
+    if (security !== 'tls' && security !== 'starttls' && security !== 'none') throw new Error(`${name}.security must be tls, starttls or none`)
+    const port = raw.port === undefined ? defaultPort : Number(raw.port)
+    if (!Number.isInteger(port) || port <= 0 || port > 65535) throw new Error(`${name}.port must be a port number`)
+    return { host, port, security }
+  }
+  const username = typeof input.username === 'string' ? input.username.trim() : ''
+  if (!username) throw new Error('username is required')
+  const password = typeof input.password === 'string' && input.password ? input.password : existing?.password
+  if (!password) throw new Error('password is required')
+  const from = typeof input.from === 'string' && input.from.trim() ? input.from.trim() : username
+  if (!isEmailAddress(from)) throw new Error('from must be an email address (set it when the username is not one)')
+  return {
+    imap: server(input.imap, 'imap', 993),
+    smtp: server(input.smtp, 'smtp', 465),
+    username,
+    password,
+    from,
+    ...(typeof input.fromName === 'string' && input.fromName.trim() && { fromName: input.fromName.trim() }),
+    saveSentCopy: input.saveSentCopy !== false,
+  }
+}
+
+export function mailAccountView(account: MailAccount): MailAccountView {
+  const { password: _password, ...view } = account
+  return view
+}
+
+export async function loadMailAccount(apiKeys: ApiKeyRepository): Promise<MailAccount | null> {
+  const record = await apiKeys.getByProvider(MAIL_ACCOUNT_KEY)
+  if (!record) return null
+  try {
+    return JSON.parse(record.encryptedKey) as MailAccount
+  } catch {
+    throw new MailError('Stored mail account is unreadable; save it again')
+  }
+}
+
+/** List, read, draft and send through one IMAP/SMTP account. Each call opens its own connection. */
+export class MailService {
+  constructor(private readonly loadAccount: () => Promise<MailAccount | null>) {}
+
+  async list(options: { mailbox?: string; query?: string; unreadOnly?: boolean; limit?: number }, signal?: AbortSignal): Promise<MailSummary[]> {
+    const mailbox = options.mailbox?.trim() || 'INBOX'
+    const limit = Math.min(Math.max(1, Math.floor(options.limit ?? DEFAULT_LIMIT)), MAX_LIMIT)
+    return this.withImap(signal, async (imap) => {
+      await imap.command(['EXAMINE', quote(mailbox)])
+      const criteria: ImapArg[] = []
+      if (options.unreadOnly) criteria.push('UNSEEN')
+      if (options.query?.trim()) criteria.push('TEXT', imapString(options.query.trim()))
+      const charset: ImapArg[] = criteria.some((arg) => typeof arg === 'object') ? ['CHARSET', 'UTF-8'] : []
+      const search = await imap.command(['UID SEARCH', ...charset, ...(criteria.length > 0 ? criteria : ['ALL'])])
+      const uids = search
+        .filter((line) => /^\* SEARCH\b/i.test(line.text))
+        .flatMap((line) => line.text.slice(8).trim().split(/\s+/).filter(Boolean).map(Number))
+        .sort((a, b) => b - a)
+        .slice(0, limit)
+      if (uids.length === 0) return []
+      const fetched = await imap.command(['UID FETCH', uids.join(','), `(UID FLAGS BODY.PEEK[HEADER.FIELDS (${LIST_HEADER_FIELDS})])`])
+      return fetchResults(fetched)
+        .map((fields) => summarize(mailbox, fields, parseHeaders(literalField(fields, 'BODY['))))
+        .sort((a, b) => parseId(b.id).uid - parseId(a.id).uid)
+    })
+  }
+
+  async read(id: string, signal?: AbortSignal): Promise<MailMessage> {
+    const { mailbox, uid } = parseId(id)
+    return this.withImap(signal, async (imap) => {
+      await imap.command(['EXAMINE', quote(mailbox)])
+      const fetched = fetchResults(await imap.command(['UID FETCH', String(uid), `(UID FLAGS BODY.PEEK[]<0.${MAX_FETCH_BYTES}>)`]))
+      if (fetched.length === 0) throw new MailError(`Message not found: ${id}`)
+      const parsed = parseMessage(literalField(fetched[0], 'BODY['))
+      return {
+        ...summarize(mailbox, fetched[0], parsed.headers),
+        cc: header(parsed.headers, 'cc'),
+        messageId: header(parsed.headers, 'message-id'),
+        text: parsed.text,
+        truncated: parsed.truncated,
+        attachments: parsed.attachments,
+      }
+    })
+  }
+
+  /** Saves to the Drafts mailbox for the user to review and send from their own client. */
+  async draft(input: MailDraftInput, signal?: AbortSignal): Promise<{ id: string | null; mailbox: string; to: string[]; subject: string }> {
+    const account = await this.requireAccount()
+    const message = await this.compose(account, input, signal)
+    return this.withImap(signal, async (imap) => {
+      const mailbox = await imap.specialMailbox('\\Drafts', 'Drafts')
+      const uid = await imap.append(mailbox, ['\\Draft', '\\Seen'], message.raw)
+      return { id: uid ? `${mailbox}#${uid}` : null, mailbox, to: message.to, subject: message.subject }
+    }, account)
+  }
+
+  async send(input: MailDraftInput, signal?: AbortSignal): Promise<{ messageId: string; to: string[]; subject: string; savedTo: string | null }> {
+    const account = await this.requireAccount()
+    const message = await this.compose(account, input, signal)
+    await sendSmtp(account, account.from, [...message.to, ...(input.cc ?? []), ...(input.bcc ?? [])], message.raw, signal)
+    let savedTo: string | null = null
+    if (account.saveSentCopy) {
+      // The message is already out; a failed copy only means no Sent entry
+      savedTo = await this.withImap(signal, async (imap) => {
+        const mailbox = await imap.specialMailbox('\\Sent', 'Sent')
+        await imap.append(mailbox, ['\\Seen'], message.raw)
+        return mailbox
+      }, account).catch(() => null)
+    }
+    return { messageId: message.messageId, to: message.to, subject: message.subject, savedTo }
+  }
+
+  private async compose(account: MailAccount, input: MailDraftInput, signal?: AbortSignal): Promise<{ raw: string; messageId: string; to: string[]; subject: string }> {
+    let to = input.to
+    let subject = input.subject?.trim() ?? ''
+    let threading: Pick<OutgoingMessage, 'inReplyTo' | 'references'> = {}
+    if (input.replyToId) {
+      const original = await this.read(input.replyToId, signal)
+      if (to.length === 0) {
+        const replyTo = original.from ? addressOf(original.from) : null
+        if (replyTo) to = [replyTo]
+      }
+      if (!subject) subject = /^re:/i.test(original.subject ?? '') ? original.subject! : `Re: ${original.subject ?? ''}`.trim()
+      if (original.messageId) threading = { inReplyTo: original.messageId, references: original.messageId }
+    }
+    if (to.length === 0) throw new MailError('At least one recipient is required')
+    const built = buildMessage({ from: account.from, fromName: account.fromName, to, cc: input.cc, subject, body: input.body, ...threading })
+    return { ...built, to, subject }
+  }
+
+  private async requireAccount(): Promise<MailAccount> {
+    const account = await this.loadAccount()
+    if (!account) throw new MailError('No mail account is configured')
+    return account
+  }
+
+  private async withImap<T>(signal: AbortSignal | undefined, action: (imap: ImapSession) => Promise<T>, account?: MailAccount): Promise<T> {
+    const imap = await ImapSession.open(account ?? await this.requireAccount(), signal)
+    try {
+      return await action(imap)
+    } finally {
+      await imap.close()
+    }
+  }
+}
+
+/** Logs in and out again; used to check an account before it is saved. */
+export async function verifyMailAccount(account: MailAccount, signal?: AbortSignal): Promise<void> {
+  const imap = await ImapSession.open(account, signal)
+  await imap.close()
+}
+
+// --- Sockets ---
+
+/** Line and byte reads over a socket that can be upgraded with STARTTLS. */
+class MailSocket {
+  private buffer = Buffer.alloc(0)
+  private waiters: Array<() => void> = []
+  private failure: Error | null = null
+
+  private constructor(private socket: net.Socket, private readonly label: string) {
+    this.attach(socket)
+  }
+
+  static async open(server: MailServer, label: string, signal?: AbortSignal): Promise<MailSocket> {
+    signal?.throwIfAborted()
+    const socket = server.security === 'tls'
+      ? tls.connect({ host: server.host, port: server.port, servername: server.host })
+      : net.connect({ host: server.host, port: server.port })
+    try {
+      await once(socket, server.security === 'tls' ? 'secureConnect' : 'connect', { signal })
+    } catch (err) {
+      socket.destroy()
+      throw new MailError(`${label} connection to ${server.host}:${server.port} failed: ${err instanceof Error ? err.message : String(err)}`)
+    }
+    const mailSocket = new MailSocket(socket, label)
+    signal?.addEventListener('abort', () => mailSocket.destroy(new MailError(`${label} request aborted`)), { once: true })
+    return mailSocket
+  }
+
+  async readLine(): Promise<string> {
+    for (;;) {
+      const end = this.buffer.indexOf('\r\n')
+      if (end >= 0) {
+        const line = this.buffer.subarray(0, end).toString('utf-8')
+        this.buffer = this.buffer.subarray(end + 2)
+        return line
+      }
+      await this.wait()
+    }
+  }
+
+  async readBytes(length: number): Promise<Buffer> {
+    while (this.buffer.length < length) await this.wait()
+    const bytes = this.buffer.subarray(0, length)
+    this.buffer = this.buffer.subarray(length)
+    return bytes
+  }
+
+  write(data: string | Buffer): void {
+    this.socket.write(data)
+  }
+
+  async startTls(host: string): Promise<void> {
+    const plain = this.socket
+    plain.removeAllListeners('data').removeAllListeners('error').removeAllListeners('close').removeAllListeners('timeout')
+    const secure = tls.connect({ socket: plain, servername: host })
+    await once(secure, 'secureConnect')
+    this.socket = secure
+    this.attach(secure)
+  }
+
+  destroy(error?: Error): void {
+    this.failure ??= error ?? new MailError(`${this.label} connection closed`)
+    this.socket.destroy()
+    this.wake()
+  }
+
+  private attach(socket: net.Socket): void {
+    socket.setTimeout(SOCKET_TIMEOUT_MS)
+    socket.on('data', (chunk: Buffer) => {
+      this.buffer = Buffer.concat([this.buffer, chunk])
+      this.wake()
+    })
+    socket.on('timeout', () => this.destroy(new MailError(`${this.label} server stopped responding`)))
+    socket.on('error', (err) => this.destroy(new MailError(`${this.label} connection error: ${err.message}`)))
+    socket.on('close', () => this.destroy())
+  }
+
+  private wait(): Promise<void> {
+    if (this.failure) return Promise.reject(this.failure)
+    return new Promise((resolve) => this.waiters.push(resolve))
+  }
+
+  private wake(): void {
+    const waiters = this.waiters
+    this.waiters = []
+    for (const resolve of waiters) resolve()
+  }
+}
+
+// --- IMAP (RFC 9051 subset, IMAP4rev1 compatible) ---
+
+/** A command argument: a raw token, or a string sent as a literal because it is not plain ASCII. */
+type ImapArg = string | { literal: Buffer }
+
+interface ImapLine {
+  /** Response text with each literal replaced by \0. */
+  text: string
+  literals: Buffer[]
+}
+
+type ImapValue = string | Buffer | null | ImapValue[]
+
+class ImapSession {
+  private tagCounter = 0
+
+  private constructor(private readonly socket: MailSocket) {}
+
+  static async open(account: MailAccount, signal?: AbortSignal): Promise<ImapSession> {
+    const socket = await MailSocket.open(account.imap, 'IMAP', signal)
+    const session = new ImapSession(socket)
+    try {
+      const greeting = await socket.readLine()
+      if (!/^\* (OK|PREAUTH)/i.test(greeting)) throw new MailError(`IMAP server refused the connection: ${greeting}`)
+      if (account.imap.security === 'starttls') {
+        await session.command(['STARTTLS'])
+        await socket.startTls(account.imap.host)
+      }
+      if (!/^\* PREAUTH/i.test(greeting)) {
+        await session.command(['LOGIN', imapString(account.username), imapString(account.password)], 'LOGIN')
+      }
+      return session
+    } catch (err) {
+      socket.destroy()
+      throw err
+    }
+  }
+
+  /** Sends one command and returns its untagged responses; NO/BAD become MailErrors. */
+  async command(args: ImapArg[], label = typeof args[0] === 'string' ? args[0] : 'command'): Promise<ImapLine[]> {
+    const tag = `A${++this.tagCounter}`
+    let pending = `${tag}`
+    for (const arg of args) {
+      if (typeof arg === 'string') {
+        pending += ` ${arg}`
+        continue
+      }
+      this.socket.write(`${pending} {${arg.literal.length}}\r\n`)
+      await this.awaitContinuation(tag)
+      this.socket.write(arg.literal)
+      pending = ''
+    }
+    this.socket.write(`${pending}\r\n`)
+
+    const untagged: ImapLine[] = []
+    for (;;) {
+      const line = await this.readResponse()
+      if (line.text.startsWith(`${tag} `)) {
+        const status = line.text.slice(tag.length + 1)
+        if (!/^OK\b/i.test(status)) throw new MailError(`IMAP ${label} failed: ${status.replace(/^(NO|BAD)\s*/i, '')}`)
+        untagged.push(line)
+        return untagged
+      }
+      untagged.push(line)
+    }
+  }
+
+  async append(mailbox: string, flags: string[], message: string): Promise<number | null> {
+    const responses = await this.command(['APPEND', quote(mailbox), `(${flags.join(' ')})`, { literal: Buffer.from(message, 'utf-8') }], 'APPEND')
+    const uid = responses.at(-1)?.text.match(/\[APPENDUID \d+ (\d+)\]/i)?.[1]
+    return uid ? Number(uid) : null
+  }
+
+  /** Finds the mailbox flagged with a special use (RFC 6154), falling back to a conventional name. */
+  async specialMailbox(flag: string, fallback: string): Promise<string> {
+    const lines = await this.command(['LIST', '""', '"*"'])
+    for (const line of lines) {
+      if (!/^\* LIST /i.test(line.text)) continue
+      const [flags, , name] = parseValues(line.text.slice(7), line.literals)
+      if (Array.isArray(flags) && flags.some((value) => typeof value === 'string' && value.toLowerCase() === flag.toLowerCase())) {
+        return valueText(name) ?? fallback
+      }
+    }
+    return fallback
+  }
+
+  async close(): Promise<void> {
+    try {
+      await this.command(['LOGOUT'])
+    } catch {
+      // The server may drop the connection before the tagged OK
+    }
+    this.socket.destroy()
+  }
+
+  private async awaitContinuation(tag: string): Promise<void> {
+    for (;;) {
+      const line = await this.readResponse()
+      if (line.text.startsWith('+')) return
+      if (line.text.startsWith(`${tag} `)) throw new MailError(`IMAP command rejected: ${line.text.slice(tag.length + 1)}`)
+    }
+  }
+
+  /** One response, pulling in any {n} literals it announces. */
+  private async readResponse(): Promise<ImapLine> {
+    let text = ''
+    const literals: Buffer[] = []
+    for (;;) {
+      const line = await this.socket.readLine()
+      const literal = line.match(/\{(\d+)\}$/)
+      if (!literal) return { text: text + line, literals }
+      text += `${line.slice(0, -literal[0].length)}\0`
+      literals.push(await this.socket.readBytes(Number(literal[1])))
+    }
+  }
+}
+
+function fetchResults(lines: ImapLine[]): Array<Map<string, ImapValue>> {
+  return lines
+    .filter((line) => /^\* \d+ FETCH /i.test(line.text))
+    .map((line) => {
+      const [list] = parseValues(line.text.replace(/^\* \d+ FETCH /i, ''), line.literals)
+      const fields = new Map<string, ImapValue>()
+      if (Array.isArray(list)) {
+        for (let index = 0; index + 1 < list.length; index += 2) {
+          fields.set(String(valueText(list[index])).toUpperCase(), list[index + 1])
+        }
+      }
+      return fields
+    })
+}
+
+function literalField(fields: Map<string, ImapValue>, prefix: string): Buffer {
+  for (const [key, value] of fields) {
+    if (!key.startsWith(prefix)) continue
+    if (Buffer.isBuffer(value)) return value
+    if (typeof value === 'string') return Buffer.from(value, 'utf-8')
+  }
+  return Buffer.alloc(0)
+}
+
+function summarize(mailbox: string, fields: Map<string, ImapValue>, headers: Map<string, string[]>): MailSummary {
+  const flags = fields.get('FLAGS')
+  return {
+    id: `${mailbox}#${valueText(fields.get('UID') ?? null)}`,
+    from: header(headers, 'from'),
+    to: header(headers, 'to'),
+    subject: header(headers, 'subject'),
+    date: header(headers, 'date'),
+    unread: !(Array.isArray(flags) && flags.some((flag) => typeof flag === 'string' && flag.toLowerCase() === '\\seen')),
+  }
+}
+
+/** Parses IMAP data items: atoms, "quoted", literals, NIL and (lists). Atoms keep [sections] whole. */
+function parseValues(text: string, literals: Buffer[]): ImapValue[] {
+  let position = 0
+  let literalIndex = 0
+  const parseList = (): ImapValue[] => {
+    const values: ImapValue[] = []
+    for (;;) {
+      while (text[position] === ' ') position++
+      const char = text[position]
+      if (char === undefined || char === ')') {
+        position++
+        return values
+      }
+      if (char === '(') {
+        position++
+        values.push(parseList())
+      } else if (char === '"') {
+        let value = ''
+        position++
+        while (position < text.length && text[position] !== '"') {
+          if (text[position] === '\\') position++
+          value += text[position++]
+        }
+        position++
+        values.push(value)
+      } else if (char === '\0') {
+        position++
+        values.push(literals[literalIndex++] ?? Buffer.alloc(0))
+      } else {
+        let value = ''
+        let depth = 0
+        while (position < text.length) {
+          const current = text[position]
+          if (depth === 0 && (current === ' ' || current === ')' || current === '(' || current === '\0')) break
+          if (current === '[') depth++
+          if (current === ']') depth--
+          value += current
+          position++
+        }
+        values.push(value.toUpperCase() === 'NIL' ? null : value)
+      }
+    }
+  }
+  return parseList()
+}
+
+function valueText(value: ImapValue | undefined): string | null {
+  if (value === null || value === undefined || Array.isArray(value)) return null
+  return Buffer.isBuffer(value) ? value.toString('utf-8') : value
+}
+
+function quote(value: string): string {
+  return `"${value.replace(/\\/g, '\\\\').replace(/"/g, '\\"')}"`
+}
+
+/** Quoted when plain ASCII, otherwise sent as a literal. */
+function imapString(value: string): ImapArg {
+  return /^[\x20-\x7e]*$/.test(value) ? quote(value) : { literal: Buffer.from(value, 'utf-8') }
+}
+
+function parseId(id: string): { mailbox: string; uid: number } {
+  const hash = id.lastIndexOf('#')
+  const uid = Number(id.slice(hash + 1))
+  if (hash <= 0 || !Number.isInteger(uid) || uid <= 0) throw new MailError(`Invalid message id "${id}"; use an id from mail.list`)
+  return { mailbox: id.slice(0, hash), uid }
+}
+
+// --- SMTP (RFC 5321 with AUTH PLAIN/LOGIN) ---
+
+async function sendSmtp(account: MailAccount, from: string, recipients: string[], message: string, signal?: AbortSignal): Promise<void> {
+  const socket = await MailSocket.open(account.smtp, 'SMTP', signal)
+  try {
+    await expectReply(socket, [220])
+    let capabilities = await ehlo(socket)
+    if (account.smtp.security === 'starttls') {
+      socket.write('STARTTLS\r\n')
+      await expectReply(socket, [220])
+      await socket.startTls(account.smtp.host)
+      capabilities = await ehlo(socket)
+    }
+    const auth = capabilities.find((line) => /^AUTH\b/i.test(line))?.toUpperCase() ?? ''
+    if (/\bPLAIN\b/.test(auth) || !/\bLOGIN\b/.test(auth)) {
+      socket.write(`AUTH PLAIN ${Buffer.from(`\0${account.username}\0${account.password}`, 'utf-8').toString('base64')}\r\n`)
+      await expectReply(socket, [235], 'AUTH')
+    } else {
+      socket.write('AUTH LOGIN\r\n')
+      await expectReply(socket, [334], 'AUTH')
+      socket.write(`${Buffer.from(account.username, 'utf-8').toString('base64')}\r\n`)
+      await expectReply(socket, [334], 'AUTH')
+      socket.write(`${Buffer.from(account.password, 'utf-8').toString('base64')}\r\n`)
+      await expectReply(socket, [235], 'AUTH')
+    }
+    socket.write(`MAIL FROM:<${from}>\r\n`)
+    await expectReply(socket, [250], 'MAIL FROM')
+    for (const recipient of recipients) {
+      socket.write(`RCPT TO:<${recipient}>\r\n`)
+      await expectReply(socket, [250, 251], `RCPT TO ${recipient}`)
+    }
+    socket.write('DATA\r\n')
+    await expectReply(socket, [354], 'DATA')
+    // Dot-stuffing: a line starting with "." gets a second one
+    socket.write(`${message.replace(/^\./gm, '..')}.\r\n`)
+    await expectReply(socket, [250], 'DATA')
+    socket.write('QUIT\r\n')
+    await expectReply(socket, [221]).catch(() => undefined)
+  } finally {
+    socket.destroy()
+  }
+}
+
+async function ehlo(socket: MailSocket): Promise<string[]> {
+  socket.write(`EHLO ${os.hostname() || 'localhost'}\r\n`)
+  return (await expectReply(socket, [250], 'EHLO')).slice(1)
+}
+
+async function expectReply(socket: MailSocket, codes: number[], step = 'greeting'): Promise<string[]> {
+  const lines: string[] = []
+  for (;;) {
+    const line = await socket.readLine()
+    lines.push(line.slice(4))
+    if (line[3] !== '-') {
+      const code = Number(line.slice(0, 3))
+      if (!codes.includes(code)) throw new MailError(`SMTP ${step} failed: ${line}`)
+      return lines
+    }
+  }
+}
@@ -0,0 +1,194 @@
+import { runOsaScript, type OsaScriptRunner } from '../lib/osascript.js'
+
+const SPOTIFY_API_URL = 'https://api.spotify.com/v1'
+const SPOTIFY_TOKEN_URL = 'https://accounts.spotify.com/api/token'
+/** Refresh the access token this long before Spotify says it expires. */
+const TOKEN_MARGIN_MS = 60_000
+
+export type MediaBackendName = 'spotify' | 'macos'
+export type MediaAction = 'play' | 'pause' | 'toggle' | 'next' | 'previous'
+export const MEDIA_ACTIONS: MediaAction[] = ['play', 'pause', 'toggle', 'next', 'previous']
+
+export interface NowPlaying {
+  state: 'playing' | 'paused' | 'stopped'
+  track: string | null
+  artist: string | null
+  album: string | null
+  /** Seconds. */
+  duration: number | null
+  position: number | null
+  /** The Spotify device or macOS app that is playing. */
+  player: string | null
+}
+
+/** Whatever plays the user's music, behind media.control. */
+export interface MediaPlayer {
+  readonly name: MediaBackendName
+  nowPlaying(signal?: AbortSignal): Promise<NowPlaying>
+  control(action: MediaAction, signal?: AbortSignal): Promise<void>
+}
+
+export class MediaPlayerError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'MediaPlayerError'
+  }
+}
+
+const STOPPED: NowPlaying = { state: 'stopped', track: null, artist: null, album: null, duration: null, position: null, player: null }
+
+/**
+ * Spotify's Web API for whichever device is active, authorized by a refresh
+ * token from the user's own Spotify app (user-read-playback-state and
+ * user-modify-playback-state scopes). Control needs Spotify Premium.
+ */
+export class SpotifyPlayer implements MediaPlayer {
+  readonly name = 'spotify' as const
+  private accessToken: { value: string; expiresAt: number } | null = null
+
+  constructor(
+    private readonly clientId: string,
+    private readonly clientSecret: string,
+    private readonly refreshToken: string,
+    private readonly fetchImpl: typeof fetch = fetch,
+  ) {}
+
+  async nowPlaying(signal?: AbortSignal): Promise<NowPlaying> {
+    const state = await this.request<RawSpotifyState | undefined>('GET', '/me/player', signal)
+    if (!state?.device) return STOPPED
+    const item = state.item
+    return {
+      state: state.is_playing ? 'playing' : item ? 'paused' : 'stopped',
+      track: item?.name ?? null,
+      artist: item?.artists?.map((artist) => artist.name).join(', ') || item?.show?.name || null,
+      album: item?.album?.name ?? null,
+      duration: item?.duration_ms ? Math.round(item.duration_ms / 1000) : null,
+      position: state.progress_ms != null ? Math.round(state.progress_ms / 1000) : null,
+      player: state.device.name,
+    }
+  }
+
+  async control(action: MediaAction, signal?: AbortSignal): Promise<void> {
+    if (action === 'toggle') {
+      const current = await this.nowPlaying(signal)
+      action = current.state === 'playing' ? 'pause' : 'play'
+    }
+    const [method, path] = ({
+      play: ['PUT', '/me/player/play'],
+      pause: ['PUT', '/me/player/pause'],
+      next: ['POST', '/me/player/next'],
+      previous: ['POST', '/me/player/previous'],
+    } as const)[action]
+    await this.request(method, path, signal)
+  }
+
+  private async token(signal?: AbortSignal): Promise<string> {
+    if (this.accessToken && this.accessToken.expiresAt > Date.now()) return this.accessToken.value
+    const response = await this.fetchImpl(SPOTIFY_TOKEN_URL, {
+      method: 'POST',
+      headers: {
+        Authorization: `Basic ${Buffer.from(`${this.clientId}:${this.clientSecret}`).toString('base64')}`,
+        'Content-Type': 'application/x-www-form-urlencoded',
+      },
+      body: new URLSearchParams({ grant_type: 'refresh_token', refresh_token: this.refreshToken }).toString(),
+      signal,
+    })
+    if (!response.ok) {
+      const detail = await response.text().catch(() => '')
+      throw new MediaPlayerError(`Spotify refused the refresh token (${response.status}): ${detail.slice(0, 200) || response.statusText}`)
+    }
+    const body = await response.json() as { access_token: string; expires_in: number }
+    this.accessToken = { value: body.access_token, expiresAt: Date.now() + body.expires_in * 1000 - TOKEN_MARGIN_MS }
+    return body.access_token
+  }
+
+  private async request<T>(method: string, path: string, signal?: AbortSignal): Promise<T> {
+    const response = await this.fetchImpl(`${SPOTIFY_API_URL}${path}`, {
+      method,
+      headers: { Authorization: `Bearer ${await this.token(signal)}` },
+      signal,
+    })
+    if (!response.ok) {
+      const detail = await response.text().catch(() => '')
+      const reason = (() => {
+        try {
+          return (JSON.parse(detail) as { error?: { reason?: string } }).error?.reason
+        } catch {
+          return undefined
+        }
+      })()
+      if (reason === 'NO_ACTIVE_DEVICE' || response.status === 404) {
+        throw new MediaPlayerError('No active Spotify device; start playback in a Spotify app first')
+      }
+      if (reason === 'PREMIUM_REQUIRED') throw new MediaPlayerError('Controlling Spotify playback needs a Premium account')
+      // Forget an access token Spotify no longer accepts so the next call refreshes it
+      if (response.status === 401) this.accessToken = null
+      throw new MediaPlayerError(`Spotify ${method} ${path} failed (${response.status}): ${detail.slice(0, 200) || response.statusText}`)
+    }
+    const text = await response.text()
+    return (text ? JSON.parse(text) : undefined) as T
+  }
+}
+
+/** Picks the playing app, else the first running one, among Music and Spotify. */
+const MAC_MEDIA_SCRIPT = `
+function run(argv) {
+  const action = argv[0]
+  const apps = ['Music', 'Spotify'].filter((name) => Application(name).running())
+  if (apps.length === 0) return JSON.stringify({ state: 'stopped', player: null })
+  const name = apps.find((app) => Application(app).playerState() === 'playing') || apps[0]
+  const app = Application(name)
+  if (action === 'play') app.play()
+  else if (action === 'pause') app.pause()
+  else if (action === 'toggle') app.playpause()
+  else if (action === 'next') app.nextTrack()
+  else if (action === 'previous') app.previousTrack()
+  const state = app.playerState()
+  const out = { state: state === 'playing' ? 'playing' : state === 'paused' ? 'paused' : 'stopped', player: name }
+  if (out.state !== 'stopped') {
+    const track = app.currentTrack
+    const duration = track.duration()
+    out.track = track.name() || null
+    out.artist = track.artist() || null
+    out.album = track.album() || null
+    // Spotify reports milliseconds, Music seconds
+    out.duration = duration ? Math.round(name === 'Spotify' ? duration / 1000 : duration) : null
+    out.position = Math.round(app.playerPosition())
+  }
+  return JSON.stringify(out)
+}`
+
+/** Music or Spotify on this Mac, through osascript; needs Automation access for each app. */
+export class MacMediaPlayer implements MediaPlayer {
+  readonly name = 'macos' as const
+
+  constructor(private readonly run: OsaScriptRunner = runOsaScript) {}
+
+  async nowPlaying(signal?: AbortSignal): Promise<NowPlaying> {
+    return this.script('status', signal)
+  }
+
+  async control(action: MediaAction, signal?: AbortSignal): Promise<void> {
+    const result = await this.script(action, signal)
+    if (!result.player) throw new MediaPlayerError('Neither Music nor Spotify is running')
+  }
+
+  private async script(action: MediaAction | 'status', signal?: AbortSignal): Promise<NowPlaying> {
+    const raw = JSON.parse(await this.run(MAC_MEDIA_SCRIPT, [action], signal)) as Partial<NowPlaying>
+    return { ...STOPPED, ...raw }
+  }
+}
+
+interface RawSpotifyState {
+  device?: { name: string }
+  is_playing: boolean
+  progress_ms?: number | null
+  item?: {
+    name: string
+    duration_ms?: number
+    artists?: Array<{ name: string }>
+    album?: { name: string }
+    /** Podcast episodes carry their show instead of artists. */
+    show?: { name: string }
+  } | null
+}
@@ -0,0 +1,109 @@
+import { logger } from '../lib/logger.js'
+import type { MemoryCategory, MemoryRepository, PreferenceRepository, UserRepository } from '../repositories/types.js'
+
+export const MEMORY_SETTINGS_PREFERENCE_KEY = 'memory_settings'
+/** Per-conversation flag, stored like the approval timeout override. */
+export const MEMORY_INCOGNITO_PREFIX = 'memory_incognito:'
+export const MEMORY_CATEGORIES: readonly MemoryCategory[] = ['work', 'personal', 'health', 'financial', 'relationships', 'location', 'other']
+
+const DAY_MS = 24 * 60 * 60 * 1000
+
+export interface MemorySettings {
+  /** Off stops both learning and recalling memories everywhere; stored memories are kept. */
+  enabled: boolean
+  /** Never stored, whether extracted or saved on request. */
+  excludedCategories: MemoryCategory[]
+  /** Memories below lowConfidenceThreshold not reinforced for this many days are deleted; null keeps them. */
+  expireLowConfidenceDays: number | null
+  lowConfidenceThreshold: number
+}
+
+export const DEFAULT_MEMORY_SETTINGS: MemorySettings = {
+  enabled: true,
+  excludedCategories: [],
+  expireLowConfidenceDays: null,
+  lowConfidenceThreshold: 0.7,
+}
+
+export function parseMemorySettings(raw: string | null): MemorySettings {
+  if (!raw) return { ...DEFAULT_MEMORY_SETTINGS }
+  try {
+    return normalizeMemorySettings(JSON.parse(raw) as Partial<MemorySettings>)
+  } catch {
+    logger.warn('Ignoring malformed memory_settings preference')
+    return { ...DEFAULT_MEMORY_SETTINGS }
+  }
+}
+
+/** Validate user input; omitted fields take their defaults. Throws with a message suitable for a 400. */
+export function normalizeMemorySettings(input: Partial<MemorySettings>): MemorySettings {
+  const settings = { ...DEFAULT_MEMORY_SETTINGS }
+  if (input.enabled !== undefined) {
+    if (typeof input.enabled !== 'boolean') throw new Error('enabled must be a boolean')
+    settings.enabled = input.enabled
+  }
+  if (input.excludedCategories !== undefined) {
+    if (!Array.isArray(input.excludedCategories) || input.excludedCategories.some((category) => !MEMORY_CATEGORIES.includes(category))) {
+      throw new Error(`excludedCategories must list categories from: ${MEMORY_CATEGORIES.join(', ')}`)
+    }
+    settings.excludedCategories = [...new Set(input.excludedCategories)]
+  }
+  if (input.expireLowConfidenceDays !== undefined && input.expireLowConfidenceDays !== null) {
+    const days = input.expireLowConfidenceDays
+    if (typeof days !== 'number' || !Number.isInteger(days) || days <= 0) {
+      throw new Error('expireLowConfidenceDays must be a positive integer or null')
+    }
+    settings.expireLowConfidenceDays = days
+  }
+  if (input.lowConfidenceThreshold !== undefined) {
+    const threshold = input.lowConfidenceThreshold
+    if (typeof threshold !== 'number' || !(threshold > 0 && threshold <= 1)) {
+      throw new Error('lowConfidenceThreshold must be a number above 0 and at most 1')
+    }
+    settings.lowConfidenceThreshold = threshold
+  }
+  return settings
+}
+
+export async function readMemorySettings(preferences: PreferenceRepository): Promise<MemorySettings> {
+  return parseMemorySettings(await preferences.get(MEMORY_SETTINGS_PREFERENCE_KEY))
+}
+
+export async function isIncognito(preferences: PreferenceRepository, sessionId: string): Promise<boolean> {
+  return (await preferences.get(`${MEMORY_INCOGNITO_PREFIX}${sessionId}`)) === 'true'
+}
+
+export async function setIncognito(preferences: PreferenceRepository, sessionId: string, incognito: boolean): Promise<void> {
+  const key = `${MEMORY_INCOGNITO_PREFIX}${sessionId}`
+  if (incognito) await preferences.set(key, 'true')
+  else await preferences.delete(key)
+}
+
+/**
+ * Why nothing may be learned from or recalled into a conversation, or null
+ * when memory is available. The reason is shown to the agent as-is.
+ */
+export async function memoryBlockedReason(preferences: PreferenceRepository, sessionId: string): Promise<string | null> {
+  if (!(await readMemorySettings(preferences)).enabled) return 'Memory is turned off in settings'
+  if (await isIncognito(preferences, sessionId)) return 'This conversation is incognito: nothing is remembered or recalled'
+  return null
+}
+
+/** Deletes memories that stayed below the confidence threshold for the configured number of days. */
+export async function expireLowConfidenceMemories(
+  deps: { users: UserRepository; memories: MemoryRepository },
+  settings: MemorySettings,
+  now = Date.now(),
+  options: { dryRun?: boolean } = {},
+): Promise<number> {
+  if (settings.expireLowConfidenceDays === null) return 0
+  const cutoff = now - settings.expireLowConfidenceDays * DAY_MS
+  let expired = 0
+  for (const user of await deps.users.list()) {
+    for (const memory of await deps.memories.listByUser(user.id)) {
+      if (memory.confidence >= settings.lowConfidenceThreshold || memory.updatedAt > cutoff) continue
+      if (options.dryRun || await deps.memories.delete(memory.id)) expired++
+    }
+  }
+  return expired
+}
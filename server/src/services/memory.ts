@@ -0,0 +1,371 @@
+import { UNPACED } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import type { Item } from '../domain/types.js'
+import type { AppConfig } from '../lib/config.js'
+import { logger } from '../lib/logger.js'
+import { splitModelId } from '../lib/model.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { MemoryCategory, MemoryKind, MemoryRecord, MemoryRepository, PreferenceRepository, SessionRepository, UpdateMemoryInput } from '../repositories/types.js'
+import { cosine, embed, tokenize } from './attachment-index.js'
+import { MEMORY_CATEGORIES, memoryBlockedReason, readMemorySettings } from './memory-settings.js'
+
+/** Extracted memories the model is less sure of than this are dropped. */
+export const MIN_MEMORY_CONFIDENCE = 0.5
+/** Embedding similarity at which a new memory restates an existing one. */
+const DUPLICATE_SIMILARITY = 0.9
+/** Most memories come from what the user wrote, so long turns are cut from the end. */
+const MAX_TRANSCRIPT_CHARS = 12_000
+const MAX_MEMORIES_PER_RUN = 10
+const DEFAULT_SEARCH_LIMIT = 5
+const MAX_SEARCH_LIMIT = 20
+
+const EXTRACTION_PROMPT = `You maintain long-term memory for an assistant. Read the conversation turn below and list durable facts about the user (their work, projects, people, tools, circumstances) and preferences for how the assistant should respond.
+
+Only include what will still be true and useful in future conversations. Skip one-off requests, small talk, anything the assistant said about itself, and details that only matter to this task. Write each memory as a short standalone sentence about "the user". Rate confidence from 0 to 1: 1 when the user stated it plainly, lower when it is inferred. Give each memory the category it is about: ${MEMORY_CATEGORIES.join(', ')}.
+
+Reply with JSON only: {"memories": [{"content": "...", "kind": "fact" | "preference", "category": "work", "confidence": 0.9}]}. Reply {"memories": []} when there is nothing worth keeping.`
+
+const MEMORY_SCHEMA = {
+  type: 'object',
+  properties: {
+    memories: {
+      type: 'array',
+      items: {
+        type: 'object',
+        properties: {
+          content: { type: 'string' },
+          kind: { type: 'string', enum: ['fact', 'preference'] },
+          category: { type: 'string', enum: [...MEMORY_CATEGORIES] },
+          confidence: { type: 'number' },
+        },
+        required: ['content', 'kind', 'category', 'confidence'],
+        additionalProperties: false,
+      },
+    },
+  },
+  required: ['memories'],
+  additionalProperties: false,
+}
+
+export interface ExtractedMemory {
+  content: string
+  kind: MemoryKind
+  /** Defaults to 'other'. */
+  category?: MemoryCategory
+  confidence: number
+}
+
+export interface MemorySearchResult {
+  id: string
+  content: string
+  kind: MemoryKind
+  category: MemoryCategory
+  confidence: number
+  score: number
+  updatedAt: number
+}
+
+type MemoryRuntime = Pick<RuntimeContext, 'config' | 'providers' | 'repositories' | 'usage'>
+export type MemoryStoreDeps = Pick<RuntimeContext, 'config' | 'providers'> & { memories: MemoryRepository }
+export type MemoryToolDeps = MemoryStoreDeps & { sessions: SessionRepository; preferences: PreferenceRepository }
+
+/**
+ * Asks MEMORY_MODEL for durable facts and preferences in a finished run's
+ * turn and stores them for the session's user. Returns the memories created
+ * or reinforced; nothing happens without a model, with memory turned off or
+ * in an incognito conversation, and excluded categories are never stored.
+ */
+export async function extractMemories(
+  runtime: MemoryRuntime,
+  input: { userId: string; sessionId: string; agentId: string; signal?: AbortSignal },
+): Promise<MemoryRecord[]> {
+  const modelId = runtime.config.memoryModel?.trim()
+  if (!modelId) return []
+  if (await memoryBlockedReason(runtime.repositories.preferences, input.sessionId)) return []
+  const settings = await readMemorySettings(runtime.repositories.preferences)
+  const agent = await runtime.repositories.agents.getById(input.agentId)
+  if (!agent) return []
+  const messages = turnMessages(await runtime.repositories.items.listByAgent(input.agentId))
+  if (messages.length === 0) return []
+  const transcript = messages.map((item) => `${item.role}: ${item.content!.trim()}`).join('\n\n').slice(0, MAX_TRANSCRIPT_CHARS)
+  const exclusions = settings.excludedCategories.length > 0
+    ? `\n\nThe user does not want anything remembered in these categories; leave such facts out: ${settings.excludedCategories.join(', ')}.`
+    : ''
+
+  const { provider: providerName, model } = splitModelId(modelId)
+  const response = await runtime.providers.resolve(modelId).generate({
+    model,
+    messages: [{ role: 'user', content: `${EXTRACTION_PROMPT}${exclusions}\n\n${transcript}` }],
+    structured_output: MEMORY_SCHEMA,
+    temperature: 0,
+    max_tokens: 800,
+    signal: input.signal,
+  })
+  await runtime.usage.record({ agent, provider: providerName, model, usage: response.usage, phase: 'memory' })
+
+  const extracted = parseExtractedMemories(response.content)
+    .filter((memory) => memory.confidence >= MIN_MEMORY_CONFIDENCE)
+    // The model may not follow the exclusion, so it is enforced here too
+    .filter((memory) => !settings.excludedCategories.includes(memory.category ?? 'other'))
+    .slice(0, MAX_MEMORIES_PER_RUN)
+  if (extracted.length === 0) return []
+  const store = { config: runtime.config, providers: runtime.providers, memories: runtime.repositories.memories }
+  return rememberMemories(store, input.userId, extracted, {
+    sessionId: input.sessionId,
+    itemId: messages[0].id,
+    signal: input.signal,
+  })
+}
+
+/**
+ * Stores memories for a user, merging each into an existing one that says
+ * the same thing: identical wording, or embeddings from the same model at
+ * DUPLICATE_SIMILARITY or above. A merge keeps the higher confidence and,
+ * when the new memory is surer, its wording. A disabled memory stays
+ * disabled, so restating it does not bring it back.
+ */
+export async function rememberMemories(
+  deps: MemoryStoreDeps,
+  userId: string,
+  memories: ExtractedMemory[],
+  options: { sessionId?: string; itemId?: string; signal?: AbortSignal } = {},
+): Promise<MemoryRecord[]> {
+  const provenance = options.sessionId ? { sourceSessionId: options.sessionId, sourceItemId: options.itemId ?? null } : {}
+  const existing = await deps.memories.listByUser(userId)
+  const embeddingModel = memoryEmbeddingModel(deps.config)
+  let vectors: number[][] | null = null
+  if (embeddingModel) {
+    try {
+      vectors = await embed(deps, embeddingModel, memories.map((memory) => memory.content), options.signal)
+    } catch (err) {
+      logger.warn({ model: embeddingModel, err: err instanceof Error ? err.message : String(err) }, 'Memory embedding failed')
+    }
+  }
+
+  const stored: MemoryRecord[] = []
+  for (const [index, memory] of memories.entries()) {
+    const vector = vectors?.[index] ?? null
+    const embedding = vector ? { embedding: vector, embeddingModel } : {}
+    const duplicate = findDuplicate(existing, memory.content, vector, embeddingModel)
+    let record: MemoryRecord | null
+    if (duplicate) {
+      const surer = memory.confidence > duplicate.confidence
+      record = await deps.memories.update(duplicate.id, {
+        confidence: Math.max(memory.confidence, duplicate.confidence),
+        ...provenance,
+        ...(surer && { content: memory.content, kind: memory.kind, category: memory.category ?? duplicate.category, ...embedding }),
+      })
+      if (!record) continue
+      existing.splice(existing.indexOf(duplicate), 1, record)
+    } else {
+      record = await deps.memories.create({ userId, ...memory, ...embedding, ...provenance })
+      // Later memories in the same batch are checked against this one too
+      existing.push(record)
+    }
+    stored.push(record)
+  }
+  return stored
+}
+
+/**
+ * A user's enabled memories most relevant to a query: by embedding similarity
+ * when every one is embedded with the configured model, by keyword otherwise.
+ */
+export async function searchMemories(
+  deps: MemoryStoreDeps,
+  userId: string,
+  query: string,
+  options: { limit?: number; signal?: AbortSignal } = {},
+): Promise<MemorySearchResult[]> {
+  const limit = Math.min(Math.max(1, Math.floor(options.limit ?? DEFAULT_SEARCH_LIMIT)), MAX_SEARCH_LIMIT)
+  const memories = (await deps.memories.listByUser(userId)).filter((memory) => !memory.disabled)
+  if (memories.length === 0) return []
+
+  const scores = (await semanticScores(deps, memories, query, options.signal)) ?? keywordScores(memories, query)
+  return memories
+    .map((memory, index) => ({
+      id: memory.id,
+      content: memory.content,
+      kind: memory.kind,
+      category: memory.category,
+      confidence: memory.confidence,
+      score: scores[index],
+      updatedAt: memory.updatedAt,
+    }))
+    .filter((result) => result.score > 0)
+    .sort((a, b) => b.score - a.score)
+    .slice(0, limit)
+}
+
+/**
+ * Applies a user's correction to one of their memories. New wording is
+ * embedded again and, coming from the user, gets full confidence. Null when
+ * the memory does not exist or belongs to someone else.
+ */
+export async function editMemory(
+  deps: MemoryStoreDeps,
+  userId: string,
+  id: string,
+  input: { content?: string; kind?: MemoryKind; category?: MemoryCategory; disabled?: boolean },
+  signal?: AbortSignal,
+): Promise<MemoryRecord | null> {
+  const memory = await deps.memories.getById(id)
+  if (!memory || memory.userId !== userId) return null
+  const update: UpdateMemoryInput = {}
+  if (input.kind !== undefined) update.kind = input.kind
+  if (input.category !== undefined) update.category = input.category
+  if (input.disabled !== undefined) update.disabled = input.disabled
+  const content = input.content?.trim()
+  if (content && content !== memory.content) {
+    update.content = content
+    update.confidence = 1
+    update.embedding = null
+    update.embeddingModel = null
+    const embeddingModel = memoryEmbeddingModel(deps.config)
+    if (embeddingModel) {
+      try {
+        const vector = (await embed(deps, embeddingModel, [content], signal))?.[0]
+        if (vector) Object.assign(update, { embedding: vector, embeddingModel })
+      } catch (err) {
+        logger.warn({ model: embeddingModel, err: err instanceof Error ? err.message : String(err) }, 'Memory embedding failed')
+      }
+    }
+  }
+  return deps.memories.update(id, update)
+}
+
+/** Deletes one of the user's memories; false when it does not exist or belongs to someone else. */
+export async function forgetMemory(deps: Pick<MemoryStoreDeps, 'memories'>, userId: string, id: string): Promise<boolean> {
+  const memory = await deps.memories.getById(id)
+  if (!memory || memory.userId !== userId) return false
+  return deps.memories.delete(id)
+}
+
+/**
+ * Reads the model's reply; malformed entries are skipped, confidence is
+ * clamped to 0–1 and an unknown category becomes 'other'.
+ */
+export function parseExtractedMemories(content: unknown): ExtractedMemory[] {
+  let parsed = content
+  if (typeof content === 'string') {
+    try {
+      parsed = JSON.parse(content.trim().replace(/^```(?:json)?\s*|\s*```$/g, ''))
+    } catch {
+      return []
+    }
+  }
+  const entries = (parsed as { memories?: unknown } | null)?.memories
+  if (!Array.isArray(entries)) return []
+  return entries.flatMap((entry): ExtractedMemory[] => {
+    const { content: text, kind, category, confidence } = (entry ?? {}) as Record<string, unknown>
+    if (typeof text !== 'string' || !text.trim()) return []
+    if (kind !== 'fact' && kind !== 'preference') return []
+    const score = typeof confidence === 'number' && Number.isFinite(confidence) ? Math.min(1, Math.max(0, confidence)) : 0
+    const known = MEMORY_CATEGORIES.find((value) => value === category) ?? 'other'
+    return [{ content: text.trim(), kind, category: known, confidence: score }]
+  })
+}
+
+export function memoryEmbeddingModel(config: Pick<AppConfig, 'memoryEmbeddingModel' | 'attachmentEmbeddingModel'>): string | null {
+  return config.memoryEmbeddingModel?.trim() || config.attachmentEmbeddingModel?.trim() || null
+}
+
+/** The run's user and assistant messages from the latest user message on; empty without one. */
+function turnMessages(items: Item[]): Item[] {
+  const messages = items.filter((item) => item.type === 'message' && (item.role === 'user' || item.role === 'assistant') && item.content?.trim())
+  let start = messages.length - 1
+  while (start >= 0 && messages[start].role !== 'user') start--
+  return start < 0 ? [] : messages.slice(start)
+}
+
+function findDuplicate(
+  existing: MemoryRecord[],
+  content: string,
+  vector: number[] | null,
+  embeddingModel: string | null,
+): MemoryRecord | undefined {
+  const key = normalize(content)
+  return existing.find((memory) => normalize(memory.content) === key)
+    ?? (vector
+      ? existing.find((memory) => memory.embedding && memory.embeddingModel === embeddingModel
+        && cosine(vector, memory.embedding) >= DUPLICATE_SIMILARITY)
+      : undefined)
+}
+
+async function semanticScores(
+  deps: MemoryStoreDeps,
+  memories: MemoryRecord[],
+  query: string,
+  signal?: AbortSignal,
+): Promise<number[] | null> {
+  const modelId = memoryEmbeddingModel(deps.config)
+  if (!modelId || memories.some((memory) => !memory.embedding || memory.embeddingModel !== modelId)) return null
+  let queryVector: number[] | undefined
+  try {
+    queryVector = (await embed(deps, modelId, [query], signal))?.[0]
+  } catch (err) {
+    logger.warn({ model: modelId, err: err instanceof Error ? err.message : String(err) }, 'Memory query embedding failed')
+  }
+  if (!queryVector) return null
+  return memories.map((memory) => cosine(queryVector, memory.embedding!))
+}
+
+/** Share of the query's terms that appear in each memory. */
+function keywordScores(memories: MemoryRecord[], query: string): number[] {
+  const terms = [...new Set(tokenize(query))]
+  if (terms.length === 0) return memories.map(() => 0)
+  return memories.map((memory) => {
+    const tokens = new Set(tokenize(memory.content))
+    return terms.filter((term) => tokens.has(term)).length / terms.length
+  })
+}
+
+function normalize(text: string): string {
+  return text.toLowerCase().replace(/\s+/g, ' ').replace(/[.!]+$/, '').trim()
+}
+
+/**
+ * Extracts memories after every completed top-level run. Runs are handled
+ * one at a time in the background, so replies never wait on it and
+ * concurrent runs cannot store the same memory twice.
+ */
+export class MemoryExtractor {
+  private iterator: AsyncIterator<AgentEvent> | null = null
+
+  constructor(private readonly runtime: RuntimeContext) {}
+
+  start(): void {
+    if (this.iterator) return
+    const iterator = this.runtime.events.subscribe({ types: [EVENT_TYPES.AGENT_COMPLETED] }, UNPACED)[Symbol.asyncIterator]()
+    this.iterator = iterator
+    void (async () => {
+      for (let next = await iterator.next(); !next.done; next = await iterator.next()) {
+        const event = next.value
+        // Sub-agents report to their parent; the user's turn is the root run
+        if (event.type !== EVENT_TYPES.AGENT_COMPLETED || event.payload.depth > 0) continue
+        await this.extract(event.agent_id, event.session_id)
+      }
+    })()
+  }
+
+  stop(): void {
+    void this.iterator?.return?.()
+    this.iterator = null
+  }
+
+  private async extract(agentId: string, sessionId: string): Promise<void> {
+    try {
+      const session = await this.runtime.repositories.sessions.getById(sessionId)
+      if (!session) return
+      const stored = await extractMemories(this.runtime, {
+        userId: session.userId,
+        sessionId,
+        agentId,
+        signal: this.runtime.shutdownController.signal,
+      })
+      if (stored.length > 0) logger.info({ sessionId, agentId, count: stored.length }, 'Memories updated')
+    } catch (err) {
+      logger.warn({ err, sessionId, agentId }, 'Memory extraction failed')
+    }
+  }
+}
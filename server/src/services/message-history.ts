@@ -0,0 +1,75 @@
+import type { Item, ItemContentBlock } from '../domain/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { MessageRevision, MessageRevisionReason } from '../repositories/types.js'
+
+export class MessageRevisionError extends Error {
+  constructor(message: string, readonly status: 400 | 404 | 409) {
+    super(message)
+  }
+}
+
+async function findSessionItem(runtime: RuntimeContext, sessionId: string, itemId: string) {
+  const item = await runtime.repositories.items.getById(itemId)
+  const agent = item ? await runtime.repositories.agents.getById(item.agentId) : null
+  if (!item || !agent || agent.sessionId !== sessionId) {
+    throw new MessageRevisionError(`Message not found: ${itemId}`, 404)
+  }
+  return { item, agent }
+}
+
+/** Load a message item that belongs to `sessionId` and may be overwritten now. */
+async function loadEditableMessage(runtime: RuntimeContext, sessionId: string, itemId: string): Promise<Item> {
+  const { item, agent } = await findSessionItem(runtime, sessionId, itemId)
+  if (item.type !== 'message') {
+    throw new MessageRevisionError('Only message items can be revised', 400)
+  }
+  if (agent.status === 'running') {
+    throw new MessageRevisionError('Cannot revise a message while the agent is running', 409)
+  }
+  return item
+}
+
+/**
+ * Overwrite a message, keeping the previous content as a revision. Every
+ * path that changes message content (edit, regenerate, restore) goes through
+ * here so no version is ever lost.
+ */
+export async function reviseMessage(
+  runtime: RuntimeContext,
+  sessionId: string,
+  itemId: string,
+  next: { content: string | null; contentBlocks?: ItemContentBlock[] | null },
+  reason: MessageRevisionReason,
+): Promise<MessageRevision> {
+  const item = await loadEditableMessage(runtime, sessionId, itemId)
+  return runtime.repositories.messageRevisions.revise(item, sessionId, reason, {
+    content: next.content,
+    contentBlocks: next.contentBlocks ?? null,
+  })
+}
+
+export async function listMessageRevisions(
+  runtime: RuntimeContext,
+  sessionId: string,
+  itemId: string,
+): Promise<MessageRevision[]> {
+  await findSessionItem(runtime, sessionId, itemId)
+  return runtime.repositories.messageRevisions.listByItem(itemId)
+}
+
+/** Bring back an earlier version; the content it replaces becomes a revision too. */
+export async function restoreMessageRevision(
+  runtime: RuntimeContext,
+  sessionId: string,
+  itemId: string,
+  revisionId: string,
+): Promise<MessageRevision> {
+  const revision = await runtime.repositories.messageRevisions.getById(revisionId)
+  if (!revision || revision.itemId !== itemId) {
+    throw new MessageRevisionError(`Revision not found: ${revisionId}`, 404)
+  }
+  return reviseMessage(runtime, sessionId, itemId, {
+    content: revision.content,
+    contentBlocks: revision.contentBlocks,
+  }, 'restore')
+}
@@ -0,0 +1,90 @@
+import { logger } from '../lib/logger.js'
+import type { ProviderRegistry } from '../providers/types.js'
+import type { ModelCatalogRecord, ModelCatalogRepository } from '../repositories/types.js'
+
+/** Listings older than this are refetched in the background at startup. */
+export const MODEL_CATALOG_MAX_AGE_MS = 24 * 60 * 60 * 1000
+
+export interface ModelCatalogRefreshResult {
+  /** Models fetched, by provider. */
+  refreshed: Record<string, number>
+  /** Why a provider's listing failed; its cached models are kept. */
+  errors: Record<string, string>
+}
+
+/** What the catalog knows a model can do; null fields mean the provider's listing did not say. */
+export type ModelCapabilities = Pick<ModelCatalogRecord, 'contextWindow' | 'maxOutputTokens' | 'vision' | 'structuredOutputs' | 'tools'>
+
+/**
+ * Models each configured provider offers, from their model listings, kept in
+ * the `model_catalog` table and cached in memory so capability checks stay
+ * synchronous.
+ */
+export class ModelCatalog {
+  private models = new Map<string, ModelCatalogRecord>()
+
+  constructor(
+    private readonly repo: ModelCatalogRepository,
+    private readonly providers: Pick<ProviderRegistry, 'get' | 'list'>,
+  ) {}
+
+  async load(): Promise<void> {
+    this.models = new Map((await this.repo.list()).map((record) => [modelKey(record.provider, record.modelId), record]))
+  }
+
+  list(provider?: string): ModelCatalogRecord[] {
+    const models = [...this.models.values()]
+    return provider ? models.filter((model) => model.provider === provider) : models
+  }
+
+  /** Capabilities of a `provider:model` string, or null when the catalog has not seen it. */
+  capabilities(modelString: string): ModelCapabilities | null {
+    const separator = modelString.indexOf(':')
+    if (separator === -1) return null
+    return this.models.get(modelKey(modelString.slice(0, separator), modelString.slice(separator + 1))) ?? null
+  }
+
+  /** Providers whose listing is missing or older than `maxAgeMs`. */
+  stale(maxAgeMs = MODEL_CATALOG_MAX_AGE_MS, now = Date.now()): string[] {
+    return this.listable().filter((name) => {
+      const fetched = this.list(name).map((model) => model.fetchedAt)
+      return fetched.length === 0 || now - Math.min(...fetched) > maxAgeMs
+    })
+  }
+
+  /** Fetches listings from every provider that has one, or just `provider`. */
+  async refresh(provider?: string): Promise<ModelCatalogRefreshResult> {
+    const names = provider ? [provider] : this.listable()
+    const result: ModelCatalogRefreshResult = { refreshed: {}, errors: {} }
+    await Promise.all(names.map(async (name) => {
+      const source = this.providers.get(name)
+      if (!source?.listModels) {
+        result.errors[name] = 'Provider is not configured or cannot list its models'
+        return
+      }
+      try {
+        const models = await source.listModels(AbortSignal.timeout(30_000))
+        await this.repo.replace(name, models)
+        const fetchedAt = Date.now()
+        for (const key of [...this.models.keys()]) {
+          if (this.models.get(key)!.provider === name) this.models.delete(key)
+        }
+        for (const model of models) this.models.set(modelKey(name, model.modelId), { ...model, provider: name, fetchedAt })
+        result.refreshed[name] = models.length
+      } catch (err) {
+        result.errors[name] = err instanceof Error ? err.message : String(err)
+      }
+    }))
+    if (Object.keys(result.errors).length > 0) logger.warn(result, 'Some model listings could not be refreshed')
+    else logger.info(result, 'Refreshed model catalog')
+    return result
+  }
+
+  private listable(): string[] {
+    return this.providers.list().filter((name) => typeof this.providers.get(name)?.listModels === 'function')
+  }
+}
+
+function modelKey(provider: string, modelId: string): string {
+  return `${provider}:${modelId}`
+}
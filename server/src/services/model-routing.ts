@@ -0,0 +1,177 @@
+import type { Item } from '../domain/types.js'
+import type { AppConfig } from '../lib/config.js'
+import { logger } from '../lib/logger.js'
+import { splitModelId } from '../lib/model.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { LLMResponse } from '../providers/types.js'
+import type { PreferenceRepository } from '../repositories/types.js'
+
+/** Model id that has each message routed to a tier's model instead of running on a fixed one. */
+export const AUTO_MODEL = 'auto'
+/** Routing decisions of one conversation, oldest first, keyed `model_routing:<sessionId>`. */
+export const MODEL_ROUTING_PREFIX = 'model_routing:'
+const MAX_RECORDED_DECISIONS = 50
+const MAX_TRIAGE_INPUT_CHARS = 4000
+
+export const ROUTING_TIERS = ['quick', 'agent', 'coding'] as const
+export type RoutingTier = (typeof ROUTING_TIERS)[number]
+
+export interface RoutingDecision {
+  tier: RoutingTier
+  model: string
+  reason: string
+  /** `triage` when ROUTING_MODEL classified the message; `heuristic` when it is unset or failed. */
+  classifiedBy: 'triage' | 'heuristic'
+  decidedAt: number
+}
+
+export interface RoutedTurn {
+  decision: RoutingDecision
+  /** What the triage call used, for the caller to record once the turn's agent exists. */
+  usage?: { provider: string; model: string; usage: LLMResponse['usage'] }
+}
+
+const TRIAGE_PROMPT = `Classify the user's message so it goes to the right model. Tiers:
+- quick: answered directly from general knowledge or the conversation; no tools, research or actions
+- agent: needs several steps, tools, research, or acting on the user's mail, calendar, files or accounts
+- coding: writing, reviewing, debugging or explaining code
+Reply with JSON: {"tier": "quick" | "agent" | "coding", "reason": "<one short sentence>"}`
+
+const TRIAGE_SCHEMA = {
+  type: 'object',
+  properties: {
+    tier: { type: 'string', enum: [...ROUTING_TIERS] },
+    reason: { type: 'string' },
+  },
+  required: ['tier', 'reason'],
+  additionalProperties: false,
+}
+
+const CODE_SIGNS = /```|\b(?:function|class|def|import|const|stack ?trace|traceback|exception|segfault|compile[sr]?|refactor|regex|typescript|javascript|python|rust|golang|sql|git)\b|\b\w+\.(?:ts|tsx|js|py|rs|go|java|rb|cpp|sh)\b/i
+const TASK_SIGNS = /https?:\/\/|\b(?:search|look up|research|find|email|mail|send|schedule|book|remind|calendar|download|upload|compare|plan|then|after that|every)\b/i
+/** Longer requests usually carry several asks. */
+const QUICK_MAX_CHARS = 400
+
+/**
+ * Picks the model for a message sent with model `auto`: ROUTING_MODEL
+ * classifies it as a quick answer, an agent task or coding, and the tier's
+ * configured model runs it. Without a triage model, or when it fails, a
+ * keyword heuristic decides.
+ */
+export async function routeModel(
+  runtime: Pick<RuntimeContext, 'config' | 'providers' | 'modelCatalog'>,
+  input: string | Item[],
+  signal?: AbortSignal,
+): Promise<RoutedTurn> {
+  const routed = await classify(runtime, input, signal)
+  return { ...routed, decision: withVision(runtime, routed.decision, input) }
+}
+
+async function classify(
+  runtime: Pick<RuntimeContext, 'config' | 'providers' | 'modelCatalog'>,
+  input: string | Item[],
+  signal?: AbortSignal,
+): Promise<RoutedTurn> {
+  const text = triageText(input)
+  const triageModel = runtime.config.routingModel?.trim()
+  if (triageModel) {
+    try {
+      const { provider, model } = splitModelId(triageModel)
+      const response = await runtime.providers.resolve(triageModel).generate({
+        model,
+        messages: [
+          { role: 'system', content: TRIAGE_PROMPT },
+          { role: 'user', content: text.slice(0, MAX_TRIAGE_INPUT_CHARS) },
+        ],
+        // The prompt asks for JSON too, so models the catalog says cannot follow a schema still answer
+        ...(runtime.modelCatalog.capabilities(triageModel)?.structuredOutputs !== false && { structured_output: TRIAGE_SCHEMA }),
+        temperature: 0,
+        max_tokens: 150,
+        signal,
+      })
+      const usage = { provider, model, usage: response.usage }
+      const parsed = parseTriage(response.content)
+      if (parsed) return { decision: decide(runtime.config, parsed.tier, parsed.reason, 'triage'), usage }
+      logger.warn({ content: response.content }, 'Unreadable triage answer; routing by heuristic')
+      return { decision: classifyByHeuristic(runtime.config, input, text), usage }
+    } catch (err) {
+      if (signal?.aborted) throw err
+      logger.warn({ err }, 'Triage failed; routing by heuristic')
+    }
+  }
+  return { decision: classifyByHeuristic(runtime.config, input, text) }
+}
+
+/** The model a tier runs on; tiers without their own model use the agent tier's. */
+export function tierModel(config: Pick<AppConfig, 'defaultModel' | 'routingQuickModel' | 'routingAgentModel' | 'routingCodingModel'>, tier: RoutingTier): string {
+  const own = tier === 'quick' ? config.routingQuickModel : tier === 'coding' ? config.routingCodingModel : undefined
+  const model = own ?? config.routingAgentModel ?? (config.defaultModel !== AUTO_MODEL ? config.defaultModel : undefined)
+  if (!model) throw new Error(`Model "${AUTO_MODEL}" needs ROUTING_AGENT_MODEL, or a DEFAULT_MODEL other than "${AUTO_MODEL}"`)
+  return model
+}
+
+export async function listRoutingDecisions(preferences: PreferenceRepository, sessionId: string): Promise<RoutingDecision[]> {
+  const raw = await preferences.get(`${MODEL_ROUTING_PREFIX}${sessionId}`)
+  if (!raw) return []
+  try {
+    const parsed = JSON.parse(raw) as unknown
+    return Array.isArray(parsed) ? parsed as RoutingDecision[] : []
+  } catch {
+    logger.warn({ sessionId }, 'Ignoring malformed model_routing preference')
+    return []
+  }
+}
+
+/** Keeps the decision with the conversation, so it can be shown which model answered what and why. */
+export async function recordRoutingDecision(preferences: PreferenceRepository, sessionId: string, decision: RoutingDecision): Promise<void> {
+  const decisions = [...await listRoutingDecisions(preferences, sessionId), decision].slice(-MAX_RECORDED_DECISIONS)
+  await preferences.set(`${MODEL_ROUTING_PREFIX}${sessionId}`, JSON.stringify(decisions))
+}
+
+/**
+ * Images need a model that reads them: when the catalog says the tier's model
+ * does not, the first tier model it says does takes the message instead.
+ */
+function withVision(runtime: Pick<RuntimeContext, 'config' | 'modelCatalog'>, decision: RoutingDecision, input: string | Item[]): RoutingDecision {
+  // Images read as text reach the model as text
+  const images = Array.isArray(input) && input.some((item) => item.contentBlocks?.some((block) => block.type === 'image' && !block.chunks?.length))
+  if (!images || runtime.modelCatalog.capabilities(decision.model)?.vision !== false) return decision
+  const candidates = (['agent', 'quick', 'coding'] as const).map((tier) => tierModel(runtime.config, tier))
+  const model = candidates.find((candidate) => runtime.modelCatalog.capabilities(candidate)?.vision === true)
+  if (!model) return decision
+  return { ...decision, model, reason: `${decision.reason}; ${model} reads the attached images` }
+}
+
+function classifyByHeuristic(config: Parameters<typeof tierModel>[0], input: string | Item[], text: string): RoutingDecision {
+  const attachments = Array.isArray(input) && input.some((item) => item.contentBlocks?.length)
+  if (CODE_SIGNS.test(text)) return decide(config, 'coding', 'Mentions code or programming', 'heuristic')
+  if (attachments) return decide(config, 'agent', 'Comes with attachments to work through', 'heuristic')
+  if (TASK_SIGNS.test(text)) return decide(config, 'agent', 'Asks for research or an action', 'heuristic')
+  if (text.length > QUICK_MAX_CHARS) return decide(config, 'agent', 'Long request with several parts', 'heuristic')
+  return decide(config, 'quick', 'Short question', 'heuristic')
+}
+
+function decide(config: Parameters<typeof tierModel>[0], tier: RoutingTier, reason: string, classifiedBy: RoutingDecision['classifiedBy']): RoutingDecision {
+  return { tier, model: tierModel(config, tier), reason, classifiedBy, decidedAt: Date.now() }
+}
+
+function parseTriage(content: string | null | undefined): { tier: RoutingTier; reason: string } | null {
+  const json = content?.match(/\{[\s\S]*\}/)?.[0]
+  if (!json) return null
+  try {
+    const parsed = JSON.parse(json) as { tier?: unknown; reason?: unknown }
+    const tier = ROUTING_TIERS.find((candidate) => candidate === parsed.tier)
+    if (!tier) return null
+    return { tier, reason: typeof parsed.reason === 'string' && parsed.reason.trim() ? parsed.reason.trim() : `Classified as ${tier}` }
+  } catch {
+    return null
+  }
+}
+
+function triageText(input: string | Item[]): string {
+  if (typeof input === 'string') return input
+  return input
+    .filter((item) => (item.role ?? 'user') === 'user' && item.content)
+    .map((item) => item.content!)
+    .join('\n\n')
+}
@@ -0,0 +1,220 @@
+const NOTION_API_URL = 'https://api.notion.com/v1'
+const NOTION_VERSION = '2022-06-28'
+
+/** Notion rejects rich text longer than this in one text object. */
+const MAX_TEXT_CHARS = 2_000
+/** Blocks per append request allowed by the API. */
+const MAX_BLOCKS_PER_REQUEST = 100
+/** Reading stops here so a huge page cannot flood the context. */
+const MAX_READ_BLOCKS = 500
+
+export interface NotionPageSummary {
+  id: string
+  title: string
+  url: string
+  lastEditedAt: string
+  parent: string | null
+}
+
+export interface NotionPage extends NotionPageSummary {
+  /** The page's blocks as Markdown-like text; nested blocks are indented. */
+  content: string
+  truncated: boolean
+}
+
+type NotionBlock = Record<string, unknown> & { type: string }
+
+export class NotionError extends Error {
+  constructor(message: string, readonly status: number) {
+    super(message)
+    this.name = 'NotionError'
+  }
+}
+
+/** Accepts a page ID with or without dashes, or a Notion page URL. */
+export function parsePageId(value: unknown): string {
+  const text = typeof value === 'string' ? value.trim() : ''
+  const match = text.replace(/-/g, '').match(/([0-9a-f]{32})(?:\?|$)/i)
+  if (!match) throw new Error('page must be a Notion page ID or URL')
+  const id = match[1].toLowerCase()
+  return `${id.slice(0, 8)}-${id.slice(8, 12)}-${id.slice(12, 16)}-${id.slice(16, 20)}-${id.slice(20)}`
+}
+
+/**
+ * Turns simple Markdown into Notion blocks: headings, bullet and numbered
+ * lists, to-dos, quotes, fenced code and paragraphs. Inline formatting is
+ * kept as plain text.
+ */
+export function markdownToBlocks(markdown: string): NotionBlock[] {
+  const blocks: NotionBlock[] = []
+  const lines = markdown.replace(/\r\n/g, '\n').split('\n')
+  for (let index = 0; index < lines.length; index++) {
+    const line = lines[index]
+    const fence = line.match(/^```(\w*)\s*$/)
+    if (fence) {
+      const code: string[] = []
+      while (++index < lines.length && !/^```\s*$/.test(lines[index])) code.push(lines[index])
+      blocks.push(block('code', code.join('\n'), { language: fence[1] || 'plain text' }))
+      continue
+    }
+    if (!line.trim()) continue
+    let match: RegExpMatchArray | null
+    if ((match = line.match(/^(#{1,3})\s+(.*)$/))) blocks.push(block(`heading_${match[1].length}`, match[2]))
+    else if ((match = line.match(/^\s*[-*]\s+\[([ xX])\]\s+(.*)$/))) blocks.push(block('to_do', match[2], { checked: match[1] !== ' ' }))
+    else if ((match = line.match(/^\s*[-*]\s+(.*)$/))) blocks.push(block('bulleted_list_item', match[1]))
+    else if ((match = line.match(/^\s*\d+[.)]\s+(.*)$/))) blocks.push(block('numbered_list_item', match[1]))
+    else if ((match = line.match(/^>\s?(.*)$/))) blocks.push(block('quote', match[1]))
+    else blocks.push(block('paragraph', line))
+  }
+  return blocks
+}
+
+/** Minimal client for the Notion tools, authenticated with an internal integration token. */
+export class NotionClient {
+  constructor(
+    private readonly token: string,
+    private readonly fetchImpl: typeof fetch = fetch,
+  ) {}
+
+  async search(query: string, limit = 10, signal?: AbortSignal): Promise<NotionPageSummary[]> {
+    const found = await this.request<{ results: RawPage[] }>('POST', '/search', {
+      query,
+      filter: { property: 'object', value: 'page' },
+      sort: { direction: 'descending', timestamp: 'last_edited_time' },
+      page_size: Math.min(Math.max(1, Math.floor(limit)), 50),
+    }, signal)
+    return found.results.map(toSummary)
+  }
+
+  async readPage(pageId: string, signal?: AbortSignal): Promise<NotionPage> {
+    const page = toSummary(await this.request<RawPage>('GET', `/pages/${pageId}`, undefined, signal))
+    const lines: string[] = []
+    const state = { blocks: 0, truncated: false }
+    await this.collect(pageId, 0, lines, state, signal)
+    return { ...page, content: lines.join('\n'), truncated: state.truncated }
+  }
+
+  /** Adds blocks to the end of a page, in API-sized batches. */
+  async append(pageId: string, blocks: NotionBlock[], signal?: AbortSignal): Promise<number> {
+    for (let start = 0; start < blocks.length; start += MAX_BLOCKS_PER_REQUEST) {
+      await this.request('PATCH', `/blocks/${pageId}/children`, { children: blocks.slice(start, start + MAX_BLOCKS_PER_REQUEST) }, signal)
+    }
+    return blocks.length
+  }
+
+  async createPage(parentId: string, title: string, blocks: NotionBlock[], signal?: AbortSignal): Promise<NotionPageSummary> {
+    const page = toSummary(await this.request<RawPage>('POST', '/pages', {
+      parent: { page_id: parentId },
+      properties: { title: { title: richText(title) } },
+      children: blocks.slice(0, MAX_BLOCKS_PER_REQUEST),
+    }, signal))
+    if (blocks.length > MAX_BLOCKS_PER_REQUEST) await this.append(page.id, blocks.slice(MAX_BLOCKS_PER_REQUEST), signal)
+    return page
+  }
+
+  private async collect(
+    blockId: string,
+    depth: number,
+    lines: string[],
+    state: { blocks: number; truncated: boolean },
+    signal?: AbortSignal,
+  ): Promise<void> {
+    let cursor: string | null = null
+    do {
+      const params = new URLSearchParams({ page_size: '100', ...(cursor && { start_cursor: cursor }) })
+      const page: { results: NotionBlock[]; next_cursor: string | null; has_more: boolean } =
+        await this.request('GET', `/blocks/${blockId}/children?${params}`, undefined, signal)
+      for (const child of page.results) {
+        if (++state.blocks > MAX_READ_BLOCKS) {
+          state.truncated = true
+          return
+        }
+        const text = blockText(child)
+        if (text !== null) lines.push(`${'  '.repeat(depth)}${text}`)
+        // Child pages are read on their own; only inline nesting is followed
+        if (child.has_children && child.type !== 'child_page' && child.type !== 'child_database') {
+          await this.collect(String(child.id), depth + 1, lines, state, signal)
+          if (state.truncated) return
+        }
+      }
+      cursor = page.has_more ? page.next_cursor : null
+    } while (cursor)
+  }
+
+  private async request<T>(method: string, path: string, body: unknown, signal?: AbortSignal): Promise<T> {
+    const response = await this.fetchImpl(`${NOTION_API_URL}${path}`, {
+      method,
+      headers: {
+        Authorization: `Bearer ${this.token}`,
+        'Notion-Version': NOTION_VERSION,
+        ...(body !== undefined && { 'Content-Type': 'application/json' }),
+      },
+      body: body !== undefined ? JSON.stringify(body) : undefined,
+      signal,
+    })
+    if (!response.ok) {
+      const detail = await response.json().catch(() => null) as { message?: string } | null
+      if (response.status === 404) {
+        throw new NotionError('Not found in Notion; the page may not be shared with the integration', 404)
+      }
+      throw new NotionError(`Notion ${method} ${path.split('?')[0]} failed (${response.status}): ${detail?.message ?? response.statusText}`, response.status)
+    }
+    return response.json() as Promise<T>
+  }
+}
+
+interface RawRichText { plain_text?: string }
+interface RawPage {
+  id: string
+  url: string
+  last_edited_time: string
+  parent?: { type: string; page_id?: string; database_id?: string; workspace?: boolean }
+  properties?: Record<string, { type: string; title?: RawRichText[] }>
+}
+
+function toSummary(raw: RawPage): NotionPageSummary {
+  const titleProperty = Object.values(raw.properties ?? {}).find((property) => property.type === 'title')
+  return {
+    id: raw.id,
+    title: plain(titleProperty?.title) || 'Untitled',
+    url: raw.url,
+    lastEditedAt: raw.last_edited_time,
+    parent: raw.parent?.page_id ?? raw.parent?.database_id ?? null,
+  }
+}
+
+function blockText(raw: NotionBlock): string | null {
+  const data = (raw[raw.type] ?? {}) as { rich_text?: RawRichText[]; checked?: boolean; language?: string; title?: string }
+  const text = plain(data.rich_text)
+  switch (raw.type) {
+    case 'heading_1': return `# ${text}`
+    case 'heading_2': return `## ${text}`
+    case 'heading_3': return `### ${text}`
+    case 'bulleted_list_item': return `- ${text}`
+    case 'numbered_list_item': return `1. ${text}`
+    case 'to_do': return `- [${data.checked ? 'x' : ' '}] ${text}`
+    case 'quote':
+    case 'callout': return `> ${text}`
+    case 'code': return `\`\`\`${data.language ?? ''}\n${text}\n\`\`\``
+    case 'divider': return '---'
+    case 'child_page': return `[page: ${data.title ?? ''} (${String(raw.id)})]`
+    case 'child_database': return `[database: ${data.title ?? ''} (${String(raw.id)})]`
+    default: return text || null
+  }
+}
+
+function block(type: string, text: string, extra: Record<string, unknown> = {}): NotionBlock {
+  return { object: 'block', type, [type]: { rich_text: richText(text), ...extra } }
+}
+
+function richText(text: string): Array<{ type: 'text'; text: { content: string } }> {
+  const parts: Array<{ type: 'text'; text: { content: string } }> = []
+  for (let start = 0; start < text.length; start += MAX_TEXT_CHARS) {
+    parts.push({ type: 'text', text: { content: text.slice(start, start + MAX_TEXT_CHARS) } })
+  }
+  return parts
+}
+
+function plain(parts: RawRichText[] | undefined): string {
+  return (parts ?? []).map((part) => part.plain_text ?? '').join('')
+}
@@ -0,0 +1,74 @@
+import { execFile } from 'child_process'
+import fs from 'fs/promises'
+import os from 'os'
+import path from 'path'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { splitModelId } from '../lib/model.js'
+
+/** OCR model setting that reads images with a local tesseract install instead of a vision model. */
+export const TESSERACT_OCR = 'tesseract'
+
+const OCR_PROMPT = 'Transcribe all text in this image exactly as written. Return only the text, with no commentary. If the image contains no text, return nothing.'
+
+const IMAGE_EXTENSIONS: Record<string, string> = {
+  'image/png': 'png',
+  'image/jpeg': 'jpg',
+  'image/gif': 'gif',
+  'image/webp': 'webp',
+}
+
+export interface OcrImage {
+  data: Buffer
+  mediaType: string
+}
+
+export type OcrRuntime = Pick<RuntimeContext, 'config' | 'providers' | 'shutdownController'>
+
+/**
+ * Reads the text in an image. `ocrModel` is either `tesseract` or a
+ * `provider:model` id for a vision model.
+ */
+export async function ocrImage(runtime: OcrRuntime, ocrModel: string, image: OcrImage, signal?: AbortSignal): Promise<string> {
+  if (ocrModel === TESSERACT_OCR) {
+    return (await runTesseract(runtime.config.tesseractBin, image, signal ?? runtime.shutdownController.signal)).trim()
+  }
+
+  const { model } = splitModelId(ocrModel)
+  const response = await runtime.providers.resolve(ocrModel).generate({
+    model,
+    messages: [{
+      role: 'user',
+      content: [
+        { type: 'text', text: OCR_PROMPT },
+        { type: 'image', media_type: image.mediaType, data: image.data.toString('base64') },
+      ],
+    }],
+    temperature: 0,
+    signal: signal ?? runtime.shutdownController.signal,
+  })
+  return (typeof response.content === 'string' ? response.content : String(response.content ?? '')).trim()
+}
+
+export function isOcrImageType(mediaType: string): boolean {
+  return mediaType in IMAGE_EXTENSIONS
+}
+
+async function runTesseract(bin: string, image: OcrImage, signal: AbortSignal): Promise<string> {
+  const dir = await fs.mkdtemp(path.join(os.tmpdir(), 'ocr-'))
+  try {
+    const input = path.join(dir, `image.${IMAGE_EXTENSIONS[image.mediaType] ?? 'png'}`)
+    await fs.writeFile(input, image.data)
+    return await new Promise((resolve, reject) => {
+      execFile(bin, [input, 'stdout'], { signal, maxBuffer: 16 * 1024 * 1024 }, (err, stdout, stderr) => {
+        if (!err) return resolve(String(stdout))
+        if ((err as NodeJS.ErrnoException).code === 'ENOENT') {
+          return reject(new Error(`${bin} was not found; install it or set TESSERACT_BIN`))
+        }
+        const detail = String(stderr).trim().split('\n').slice(-3).join('\n')
+        reject(new Error(`${path.basename(bin)} failed${detail ? `: ${detail}` : ''}`))
+      })
+    })
+  } finally {
+    await fs.rm(dir, { recursive: true, force: true })
+  }
+}
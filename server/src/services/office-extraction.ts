@@ -0,0 +1,72 @@
+import { MAX_ATTACHMENT_BYTES } from '../lib/attachment-validation.js'
+import { chunkText } from '../lib/pdf-text.js'
+import { extractOfficeContent, type OfficeFormat } from '../lib/office-text.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { isZip } from '../lib/zip.js'
+import type { PdfExtraction } from './pdf-extraction.js'
+
+export const MAX_OFFICE_BYTES = MAX_ATTACHMENT_BYTES.office
+
+const OFFICE_MEDIA_TYPES: Record<OfficeFormat, string> = {
+  docx: 'application/vnd.openxmlformats-officedocument.wordprocessingml.document',
+  xlsx: 'application/vnd.openxmlformats-officedocument.spreadsheetml.sheet',
+  pptx: 'application/vnd.openxmlformats-officedocument.presentationml.presentation',
+}
+
+export class OfficeInputError extends Error {}
+
+export interface OfficeExtractionInput {
+  bytes: ArrayBuffer | Uint8Array
+  mimeType?: string
+  fileName?: string
+}
+
+export function inferOfficeFormat(mimeType?: string, fileName?: string): OfficeFormat | null {
+  const normalizedMime = mimeType?.toLowerCase().split(';', 1)[0]?.trim()
+  const byMime = (Object.keys(OFFICE_MEDIA_TYPES) as OfficeFormat[]).find((format) => OFFICE_MEDIA_TYPES[format] === normalizedMime)
+  if (byMime) return byMime
+  const extension = fileName?.toLowerCase().split('.').pop()
+  return extension && extension in OFFICE_MEDIA_TYPES ? extension as OfficeFormat : null
+}
+
+/**
+ * Stores a Word, Excel or PowerPoint attachment with its text converted to
+ * Markdown (tables included), in the same shape as a PDF extraction so it
+ * travels as a `document` block. `pages` counts sheets or slides.
+ */
+export async function extractOfficeDocument(
+  runtime: Pick<RuntimeContext, 'attachments'>,
+  input: OfficeExtractionInput,
+): Promise<PdfExtraction> {
+  const data = Buffer.from(input.bytes instanceof ArrayBuffer ? new Uint8Array(input.bytes) : input.bytes)
+  if (data.length === 0) throw new OfficeInputError('Office document is empty')
+  if (data.length > MAX_OFFICE_BYTES) throw new OfficeInputError(`Office document is too large (${data.length} bytes)`)
+  const format = inferOfficeFormat(input.mimeType, input.fileName)
+  if (!format) throw new OfficeInputError(`Unsupported Office document type: ${input.mimeType || input.fileName || 'unknown'}`)
+  // Legacy .doc/.xls/.ppt files are not ZIP archives
+  if (!isZip(data)) throw new OfficeInputError(`Not an Office Open XML file${input.fileName ? `: ${input.fileName}` : ''}`)
+
+  const hash = await runtime.attachments.put(data)
+  const stored = await runtime.attachments.getExtraction<PdfExtraction>(hash)
+  if (stored) return stored
+
+  let sections: string[]
+  try {
+    sections = extractOfficeContent(data, format).sections
+  } catch (err) {
+    throw new OfficeInputError(`Could not read ${input.fileName ?? 'the document'}: ${err instanceof Error ? err.message : String(err)}`)
+  }
+  const text = sections.join('\n\n')
+  if (!text.trim()) throw new OfficeInputError(`No text found in ${input.fileName ?? 'the document'}`)
+
+  const extraction: PdfExtraction = {
+    hash,
+    bytes: data.length,
+    pages: sections.length,
+    source: 'text',
+    mediaType: OFFICE_MEDIA_TYPES[format],
+    chunks: chunkText(text),
+  }
+  await runtime.attachments.putExtraction(hash, extraction)
+  return extraction
+}
@@ -0,0 +1,96 @@
+import { isLocalProvider } from '../lib/connectivity.js'
+import { logger } from '../lib/logger.js'
+import { splitModelId } from '../lib/model.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { runAgent } from '../orchestrator/runner.js'
+import { buildDeps } from './session-runner.js'
+
+/** Ids of pending agents whose messages wait for the connection, oldest first. */
+export const OFFLINE_QUEUE_PREFERENCE_KEY = 'offline_queue'
+
+/**
+ * Messages sent while offline with `queueIfOffline`. Their turn is saved and
+ * left pending; once the connection returns each run starts, in the order
+ * the messages were sent. The list is kept in preferences, so a restart
+ * while offline does not lose it.
+ */
+export class OfflineQueue {
+  private unsubscribe: (() => void) | null = null
+  private flushing: Promise<void> | null = null
+
+  constructor(private readonly runtime: RuntimeContext) {}
+
+  async start(): Promise<void> {
+    if (this.unsubscribe) return
+    this.unsubscribe = this.runtime.connectivity.onOnline(() => {
+      void this.flush()
+    })
+    // Messages left from before a restart go out now if the connection is already back
+    if ((await this.list()).length > 0 && await this.runtime.connectivity.check()) await this.flush()
+  }
+
+  stop(): void {
+    this.unsubscribe?.()
+    this.unsubscribe = null
+  }
+
+  /** Whether a run on this model should wait: offline, on a remote provider, with no local model to move to. */
+  async shouldQueue(model: string): Promise<boolean> {
+    const { connectivity } = this.runtime
+    if (isLocalProvider(splitModelId(model).provider) || connectivity.offlineModel) return false
+    return !(await connectivity.isOnline())
+  }
+
+  /** Parks the prepared turn; a follow-up's root agent was already set running, so it goes back to pending. */
+  async enqueue(agentId: string): Promise<void> {
+    await this.runtime.repositories.agents.update(agentId, { status: 'pending' })
+    const queued = await this.list()
+    if (queued.includes(agentId)) return
+    await this.save([...queued, agentId])
+  }
+
+  async list(): Promise<string[]> {
+    const raw = await this.runtime.repositories.preferences.get(OFFLINE_QUEUE_PREFERENCE_KEY)
+    if (!raw) return []
+    try {
+      const parsed = JSON.parse(raw) as unknown
+      return Array.isArray(parsed) ? parsed.filter((id): id is string => typeof id === 'string') : []
+    } catch {
+      logger.warn('Ignoring malformed offline_queue preference')
+      return []
+    }
+  }
+
+  /** Starts every queued run still pending; one already in progress is joined. */
+  flush(): Promise<void> {
+    this.flushing ??= this.flushNow().finally(() => {
+      this.flushing = null
+    })
+    return this.flushing
+  }
+
+  private async flushNow(): Promise<void> {
+    for (const agentId of await this.list()) {
+      // Dropped the connection again; the rest wait for the next reconnect
+      if (!(await this.runtime.connectivity.isOnline())) return
+      await this.save((await this.list()).filter((id) => id !== agentId))
+      const agent = await this.runtime.repositories.agents.getById(agentId)
+      // Cancelled or deleted while it waited
+      if (!agent || agent.status !== 'pending') continue
+      logger.info({ agentId, sessionId: agent.sessionId }, 'Sending message queued while offline')
+      const abort = new AbortController()
+      this.runtime.agentAbortControllers.set(agentId, abort)
+      void runAgent(agentId, buildDeps(this.runtime, agent.config.model), {
+        stream: true,
+        signal: AbortSignal.any([this.runtime.shutdownController.signal, abort.signal]),
+      })
+        .catch((err) => logger.warn({ err, agentId }, 'Queued run failed'))
+        .finally(() => this.runtime.agentAbortControllers.delete(agentId))
+    }
+  }
+
+  private async save(ids: string[]): Promise<void> {
+    if (ids.length === 0) await this.runtime.repositories.preferences.delete(OFFLINE_QUEUE_PREFERENCE_KEY)
+    else await this.runtime.repositories.preferences.set(OFFLINE_QUEUE_PREFERENCE_KEY, JSON.stringify(ids))
+  }
+}
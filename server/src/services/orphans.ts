@@ -0,0 +1,77 @@
+import fs from 'fs/promises'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+import type { OrphanCounts } from '../repositories/types.js'
+import { deleteSessionFiles } from '../tools/path-policy.js'
+import { listProjectHashes } from './projects.js'
+
+const SESSION_ID_RE = /^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$/
+/** Payloads are written before the item that references them; leave fresh ones alone. */
+const ATTACHMENT_GRACE_MS = 60 * 60 * 1000
+
+export interface OrphanReport {
+  database: OrphanCounts
+  /** Session file directories whose session no longer exists. */
+  sessionDirs: string[]
+  /** Debug trace directories whose session no longer exists. */
+  traceDirs: string[]
+  attachments: { count: number; bytes: number }
+  /** False for a dry run. */
+  removed: boolean
+}
+
+/**
+ * Find data left behind by crashes, manual edits, or older versions that
+ * did not cascade deletes. With `remove` unset this only reports.
+ */
+export async function cleanupOrphans(
+  runtime: RuntimeContext,
+  options: { remove?: boolean; now?: number } = {},
+): Promise<OrphanReport> {
+  const { orphans } = runtime.repositories
+  const remove = options.remove === true
+  const now = options.now ?? Date.now()
+
+  const sessionIds = new Set(await orphans.listSessionIds())
+  const sessionDirs = (await listDirs(runtime.sessionFilesRoot))
+    .filter((name) => SESSION_ID_RE.test(name) && !sessionIds.has(name))
+  const traceDirs = (await listDirs(runtime.debugTraces.dir))
+    .filter((name) => SESSION_ID_RE.test(name) && !sessionIds.has(name))
+
+  const referenced = new Set(await orphans.listAttachmentHashes())
+  // Video frames are only referenced from their video's extraction until a message includes them
+  for (const hash of [...referenced]) {
+    const extraction = await runtime.attachments.getExtraction<{ frames?: Array<{ hash: string }> }>(hash)
+    for (const frame of extraction?.frames ?? []) referenced.add(frame.hash)
+  }
+  // Project files are referenced from project manifests rather than the database
+  for (const hash of await listProjectHashes(runtime)) referenced.add(hash)
+  for (const hash of await runtime.repositories.knowledge.listDocumentHashes()) referenced.add(hash)
+  const blobs = (await runtime.attachments.list())
+    .filter((blob) => !referenced.has(blob.hash) && now - blob.modifiedAt > ATTACHMENT_GRACE_MS)
+
+  const report: OrphanReport = {
+    database: remove ? await orphans.purge() : await orphans.count(),
+    sessionDirs,
+    traceDirs,
+    attachments: { count: blobs.length, bytes: blobs.reduce((sum, blob) => sum + blob.bytes, 0) },
+    removed: remove,
+  }
+
+  if (remove) {
+    for (const sessionId of sessionDirs) await deleteSessionFiles(runtime.sessionFilesRoot, sessionId)
+    for (const sessionId of traceDirs) await runtime.debugTraces.removeSession(sessionId)
+    for (const blob of blobs) await runtime.attachments.remove(blob.hash)
+    logger.info(report, 'Removed orphaned data')
+  }
+  return report
+}
+
+async function listDirs(root: string): Promise<string[]> {
+  try {
+    const entries = await fs.readdir(root, { withFileTypes: true })
+    return entries.filter((entry) => entry.isDirectory()).map((entry) => entry.name)
+  } catch {
+    return []
+  }
+}
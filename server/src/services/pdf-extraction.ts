@@ -0,0 +1,102 @@
+import type { ItemContentBlock } from '../domain/types.js'
+import { MAX_ATTACHMENT_BYTES } from '../lib/attachment-validation.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { chunkText, extractPdfContent, isPdf } from '../lib/pdf-text.js'
+import { ocrImage } from './ocr.js'
+
+export const MAX_PDF_BYTES = MAX_ATTACHMENT_BYTES.pdf
+
+/** Scanned documents beyond this many page images are cut short rather than run through OCR. */
+const MAX_OCR_IMAGES = 20
+
+export class PdfInputError extends Error {}
+
+export interface PdfExtractionInput {
+  bytes: ArrayBuffer | Uint8Array
+  fileName?: string
+  signal?: AbortSignal
+}
+
+export interface PdfExtraction {
+  /** SHA-256 of the PDF in the attachment store; reference it from a `document` content block. */
+  hash: string
+  bytes: number
+  pages: number
+  /** `text` came from the PDF's own text layer, `ocr` from reading its page images. */
+  source: 'text' | 'ocr'
+  /** Set for plain-text and Office documents; PDFs leave it out. */
+  mediaType?: string
+  chunks: string[]
+}
+
+type ExtractionRuntime = Pick<RuntimeContext, 'attachments' | 'config' | 'providers' | 'shutdownController'>
+
+/**
+ * Stores a PDF and the text chunks extracted from it side by side in the
+ * attachment store. Re-uploading the same file returns the stored chunks.
+ */
+export async function extractPdf(runtime: ExtractionRuntime, input: PdfExtractionInput): Promise<PdfExtraction> {
+  const data = Buffer.from(input.bytes instanceof ArrayBuffer ? new Uint8Array(input.bytes) : input.bytes)
+  if (data.length === 0) throw new PdfInputError('PDF file is empty')
+  if (data.length > MAX_PDF_BYTES) throw new PdfInputError(`PDF file is too large (${data.length} bytes)`)
+  if (!isPdf(data)) throw new PdfInputError(`Not a PDF file${input.fileName ? `: ${input.fileName}` : ''}`)
+
+  const hash = await runtime.attachments.put(data)
+  const stored = await runtime.attachments.getExtraction<PdfExtraction>(hash)
+  if (stored) return stored
+
+  const content = extractPdfContent(data)
+  let text = content.pages.join('\n\n').trim()
+  let source: PdfExtraction['source'] = 'text'
+  if (!text) {
+    if (content.images.length === 0) throw new PdfInputError('PDF has no extractable text')
+    text = await ocrImages(runtime, content.images.slice(0, MAX_OCR_IMAGES), input.signal)
+    source = 'ocr'
+  }
+  if (!text) throw new PdfInputError('PDF has no extractable text')
+
+  const extraction: PdfExtraction = { hash, bytes: data.length, pages: content.pages.length, source, chunks: chunkText(text) }
+  await runtime.attachments.putExtraction(hash, extraction)
+  return extraction
+}
+
+/**
+ * Fills in the stored chunks of `document` blocks sent with a user message,
+ * so clients only need to pass the hash returned by the upload.
+ */
+export async function resolveDocumentBlocks(
+  runtime: Pick<RuntimeContext, 'attachments'>,
+  blocks: ItemContentBlock[] | null | undefined,
+): Promise<ItemContentBlock[] | null> {
+  if (!blocks) return null
+  const resolved: ItemContentBlock[] = []
+  for (const block of blocks) {
+    if (block.type !== 'document') {
+      resolved.push(block)
+      continue
+    }
+    const extraction = block.hash ? await runtime.attachments.getExtraction<PdfExtraction>(block.hash) : null
+    if (!extraction) throw new PdfInputError(`Unknown document attachment: ${block.name ?? block.hash ?? 'unnamed'}`)
+    resolved.push({
+      type: 'document',
+      name: block.name,
+      media_type: extraction.mediaType ?? 'application/pdf',
+      hash: extraction.hash,
+      bytes: extraction.bytes,
+      chunks: extraction.chunks,
+    })
+  }
+  return resolved
+}
+
+async function ocrImages(runtime: ExtractionRuntime, images: Buffer[], signal?: AbortSignal): Promise<string> {
+  const ocrModel = runtime.config.pdfOcrModel?.trim()
+  if (!ocrModel) throw new PdfInputError('PDF has no text layer and PDF_OCR_MODEL is not configured')
+
+  const pages: string[] = []
+  for (const image of images) {
+    const text = await ocrImage(runtime, ocrModel, { data: image, mediaType: 'image/jpeg' }, signal)
+    if (text) pages.push(text)
+  }
+  return pages.join('\n\n')
+}
@@ -0,0 +1,62 @@
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+import type { ApprovalBatch } from '../domain/types.js'
+import type { ToolPreview } from '../tools/types.js'
+
+export interface PendingApproval {
+  agentId: string
+  parentId: string | null
+  depth: number
+  callId: string
+  name: string
+  args: Record<string, unknown>
+  description: string | null
+  /** From the tool's preview hook (e.g. a diff), when it has one. */
+  preview: ToolPreview | null
+  /** Set when the call can only be approved with the rest of its batch. */
+  batch: ApprovalBatch | null
+}
+
+/**
+ * Every tool call awaiting approval in a session, across the root agent and
+ * its subagents, in the order each agent asked. Null when the session does
+ * not exist.
+ */
+export async function listPendingApprovals(
+  runtime: RuntimeContext,
+  sessionId: string,
+): Promise<PendingApproval[] | null> {
+  const session = await runtime.repositories.sessions.getById(sessionId)
+  if (!session) return null
+
+  const pending: PendingApproval[] = []
+  for (const agent of await runtime.repositories.agents.listBySession(sessionId)) {
+    if (agent.status !== 'waiting') continue
+    for (const wait of agent.waitingFor) {
+      if (wait.type !== 'approval') continue
+      const args = wait.args ?? {}
+      let preview: ToolPreview | null = null
+      try {
+        preview = runtime.tools.getPreview(wait.name, args, {
+          agent_id: agent.id,
+          session_id: sessionId,
+          signal: AbortSignal.timeout(5000),
+        }) ?? null
+      } catch (err) {
+        logger.warn({ err, tool: wait.name }, 'Tool preview failed')
+      }
+      pending.push({
+        agentId: agent.id,
+        parentId: agent.parentId,
+        depth: agent.depth,
+        callId: wait.callId,
+        name: wait.name,
+        args,
+        description: wait.description ?? null,
+        preview,
+        batch: wait.batch ?? null,
+      })
+    }
+  }
+  return pending
+}
@@ -0,0 +1,271 @@
+import fs from 'fs/promises'
+import path from 'path'
+import { escapeHtml } from '../lib/html.js'
+import { runOsaScript, type OsaScriptRunner } from '../lib/osascript.js'
+
+const DEFAULT_LIMIT = 10
+const MAX_LIMIT = 50
+/** Enough for long meeting notes; the agent can ask for a later slice. */
+const MAX_READ_CHARS = 40_000
+/** Stop walking huge vaults rather than stalling a turn. */
+const MAX_VAULT_FILES = 10_000
+const SNIPPET_CHARS = 200
+
+export type PersonalNotesBackendName = 'obsidian' | 'apple_notes'
+
+export interface PersonalNoteSummary {
+  /** Vault-relative path for Obsidian, the note's x-coredata ID for Apple Notes. */
+  id: string
+  title: string
+  folder: string | null
+  snippet: string
+  modifiedAt: string | null
+}
+
+export interface PersonalNote {
+  id: string
+  title: string
+  folder: string | null
+  content: string
+  truncated: boolean
+  modifiedAt: string | null
+}
+
+export interface CreatePersonalNoteInput {
+  title: string
+  /** Markdown; converted to simple HTML for Apple Notes. */
+  body: string
+  folder?: string
+}
+
+/** The user's own notes app behind the personal_notes.* tools. */
+export interface PersonalNotesBackend {
+  readonly name: PersonalNotesBackendName
+  search(query: string, options: { folder?: string; limit?: number }, signal?: AbortSignal): Promise<PersonalNoteSummary[]>
+  read(id: string, signal?: AbortSignal): Promise<PersonalNote>
+  create(input: CreatePersonalNoteInput, signal?: AbortSignal): Promise<PersonalNoteSummary>
+}
+
+export class PersonalNotesError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'PersonalNotesError'
+  }
+}
+
+/** Markdown files in an Obsidian vault folder. Paths never leave the vault and notes are never overwritten. */
+export class ObsidianVault implements PersonalNotesBackend {
+  readonly name = 'obsidian' as const
+  private readonly root: string
+
+  constructor(vaultDir: string) {
+    this.root = path.resolve(vaultDir)
+  }
+
+  async search(query: string, options: { folder?: string; limit?: number }, signal?: AbortSignal): Promise<PersonalNoteSummary[]> {
+    const terms = query.toLowerCase().split(/\s+/).filter(Boolean)
+    const start = options.folder ? this.resolve(options.folder) : this.root
+    const scored: Array<{ score: number; summary: PersonalNoteSummary }> = []
+    for (const file of await this.markdownFiles(start, signal)) {
+      const [content, stat] = await Promise.all([fs.readFile(file, 'utf-8'), fs.stat(file)])
+      const title = path.basename(file, '.md')
+      const haystack = `${title}\n${content}`.toLowerCase()
+      // Every term must appear; title hits rank above body hits
+      if (terms.some((term) => !haystack.includes(term))) continue
+      const score = terms.reduce((sum, term) => sum + (title.toLowerCase().includes(term) ? 10 : 0) + countOccurrences(haystack, term), 0)
+      scored.push({ score, summary: this.summarize(file, title, content, terms, stat.mtime) })
+    }
+    return scored
+      .sort((a, b) => b.score - a.score || (b.summary.modifiedAt ?? '').localeCompare(a.summary.modifiedAt ?? ''))
+      .slice(0, clampLimit(options.limit))
+      .map((entry) => entry.summary)
+  }
+
+  async read(id: string): Promise<PersonalNote> {
+    const file = this.resolve(id.endsWith('.md') ? id : `${id}.md`)
+    const [content, stat] = await Promise.all([fs.readFile(file, 'utf-8'), fs.stat(file)]).catch((err: NodeJS.ErrnoException) => {
+      throw err.code === 'ENOENT' ? new PersonalNotesError(`Note not found: ${id}`) : err
+    })
+    return {
+      id: this.relative(file),
+      title: path.basename(file, '.md'),
+      folder: this.folderOf(file),
+      content: content.slice(0, MAX_READ_CHARS),
+      truncated: content.length > MAX_READ_CHARS,
+      modifiedAt: stat.mtime.toISOString(),
+    }
+  }
+
+  async create(input: CreatePersonalNoteInput): Promise<PersonalNoteSummary> {
+    const dir = input.folder ? this.resolve(input.folder) : this.root
+    const file = path.join(dir, `${sanitizeTitle(input.title)}.md`)
+    await fs.mkdir(dir, { recursive: true })
+    try {
+      await fs.writeFile(file, input.body.endsWith('\n') ? input.body : `${input.body}\n`, { flag: 'wx' })
+    } catch (err) {
+      if ((err as NodeJS.ErrnoException).code === 'EEXIST') {
+        throw new PersonalNotesError(`A note named "${path.basename(file, '.md')}" already exists in ${this.folderOf(file) ?? 'the vault root'}`)
+      }
+      throw err
+    }
+    return this.summarize(file, path.basename(file, '.md'), input.body, [], new Date())
+  }
+
+  private async markdownFiles(dir: string, signal?: AbortSignal): Promise<string[]> {
+    const files: string[] = []
+    const pending = [dir]
+    while (pending.length > 0 && files.length < MAX_VAULT_FILES) {
+      signal?.throwIfAborted()
+      const current = pending.pop()!
+      let entries
+      try {
+        entries = await fs.readdir(current, { withFileTypes: true })
+      } catch (err) {
+        if ((err as NodeJS.ErrnoException).code === 'ENOENT') throw new PersonalNotesError(`Folder not found: ${this.relative(current)}`)
+        throw err
+      }
+      for (const entry of entries) {
+        // .obsidian holds settings and plugins, .trash deleted notes
+        if (entry.name.startsWith('.')) continue
+        const full = path.join(current, entry.name)
+        if (entry.isDirectory()) pending.push(full)
+        else if (entry.isFile() && entry.name.endsWith('.md')) files.push(full)
+      }
+    }
+    return files
+  }
+
+  private resolve(relativePath: string): string {
+    const resolved = path.resolve(this.root, relativePath.replace(/^\/+/, ''))
+    if (resolved !== this.root && !resolved.startsWith(this.root + path.sep)) {
+      throw new PersonalNotesError(`Path is outside the vault: ${relativePath}`)
+    }
+    return resolved
+  }
+
+  private relative(file: string): string {
+    return path.relative(this.root, file).split(path.sep).join('/')
+  }
+
+  private folderOf(file: string): string | null {
+    const folder = path.dirname(this.relative(file))
+    return folder === '.' ? null : folder
+  }
+
+  private summarize(file: string, title: string, content: string, terms: string[], mtime: Date): PersonalNoteSummary {
+    return { id: this.relative(file), title, folder: this.folderOf(file), snippet: snippet(content, terms), modifiedAt: mtime.toISOString() }
+  }
+}
+
+const NOTES_SEARCH_SCRIPT = `
+function run(argv) {
+  const app = Application('Notes')
+  const query = argv[0], limit = Number(argv[2])
+  const scope = argv[1] ? app.folders.byName(argv[1]).notes : app.notes
+  const matches = scope.whose({ _or: [{ name: { _contains: query } }, { plaintext: { _contains: query } }] })
+  const ids = matches.id(), names = matches.name(), modified = matches.modificationDate()
+  const out = []
+  for (let i = 0; i < ids.length && i < limit; i++) {
+    const note = matches[i]
+    out.push({ id: ids[i], title: names[i], folder: note.container().name(), text: note.plaintext().slice(0, 2000), modifiedAt: modified[i].toISOString() })
+  }
+  return JSON.stringify(out)
+}`
+
+const NOTES_READ_SCRIPT = `
+function run(argv) {
+  const note = Application('Notes').notes.byId(argv[0])
+  return JSON.stringify({ id: note.id(), title: note.name(), folder: note.container().name(), text: note.plaintext(), modifiedAt: note.modificationDate().toISOString() })
+}`
+
+const NOTES_CREATE_SCRIPT = `
+function run(argv) {
+  const app = Application('Notes')
+  const input = JSON.parse(argv[0])
+  const folder = input.folder ? app.folders.byName(input.folder) : app.defaultAccount().defaultFolder()
+  const note = app.Note({ body: input.html })
+  folder.notes.push(note)
+  return JSON.stringify({ id: note.id(), title: note.name(), folder: folder.name(), text: note.plaintext().slice(0, 2000), modifiedAt: note.modificationDate().toISOString() })
+}`
+
+/** Apple Notes on macOS, through osascript. */
+export class AppleNotes implements PersonalNotesBackend {
+  readonly name = 'apple_notes' as const
+
+  constructor(private readonly run: OsaScriptRunner = runOsaScript) {}
+
+  async search(query: string, options: { folder?: string; limit?: number }, signal?: AbortSignal): Promise<PersonalNoteSummary[]> {
+    const raw = JSON.parse(await this.run(NOTES_SEARCH_SCRIPT, [query.trim(), options.folder ?? '', String(clampLimit(options.limit))], signal)) as RawAppleNote[]
+    const terms = query.toLowerCase().split(/\s+/).filter(Boolean)
+    return raw.map((note) => ({ id: note.id, title: note.title, folder: note.folder, snippet: snippet(note.text, terms), modifiedAt: note.modifiedAt }))
+  }
+
+  async read(id: string, signal?: AbortSignal): Promise<PersonalNote> {
+    const note = JSON.parse(await this.run(NOTES_READ_SCRIPT, [id], signal)) as RawAppleNote
+    return {
+      id: note.id,
+      title: note.title,
+      folder: note.folder,
+      content: note.text.slice(0, MAX_READ_CHARS),
+      truncated: note.text.length > MAX_READ_CHARS,
+      modifiedAt: note.modifiedAt,
+    }
+  }
+
+  async create(input: CreatePersonalNoteInput, signal?: AbortSignal): Promise<PersonalNoteSummary> {
+    // Notes takes its title from the first line of the body
+    const html = `<h1>${escapeHtml(input.title)}</h1>${markdownToNotesHtml(input.body)}`
+    const note = JSON.parse(await this.run(NOTES_CREATE_SCRIPT, [JSON.stringify({ html, folder: input.folder })], signal)) as RawAppleNote
+    return { id: note.id, title: note.title, folder: note.folder, snippet: snippet(note.text, []), modifiedAt: note.modifiedAt }
+  }
+}
+
+interface RawAppleNote { id: string; title: string; folder: string; text: string; modifiedAt: string }
+
+/** Headings, bullets and paragraphs; other markdown stays as typed. */
+export function markdownToNotesHtml(markdown: string): string {
+  const html: string[] = []
+  let list: string[] = []
+  const flush = () => {
+    if (list.length > 0) html.push(`<ul>${list.map((item) => `<li>${item}</li>`).join('')}</ul>`)
+    list = []
+  }
+  for (const line of markdown.split('\n')) {
+    const bullet = line.match(/^\s*[-*]\s+(.*)$/)
+    if (bullet) {
+      list.push(escapeHtml(bullet[1]))
+      continue
+    }
+    flush()
+    const heading = line.match(/^(#{1,3})\s+(.*)$/)
+    if (heading) html.push(`<h${heading[1].length}>${escapeHtml(heading[2])}</h${heading[1].length}>`)
+    else html.push(line.trim() ? `<div>${escapeHtml(line)}</div>` : '<div><br></div>')
+  }
+  flush()
+  return html.join('')
+}
+
+function sanitizeTitle(title: string): string {
+  // Characters Obsidian refuses in note names
+  const cleaned = title.replace(/[\\/:*?"<>|#^[\]]/g, ' ').replace(/\s+/g, ' ').trim().slice(0, 120)
+  if (!cleaned || cleaned.startsWith('.')) throw new PersonalNotesError(`Unusable note title: ${title}`)
+  return cleaned
+}
+
+function snippet(content: string, terms: string[]): string {
+  const text = content.replace(/\s+/g, ' ').trim()
+  const lower = text.toLowerCase()
+  const hit = terms.map((term) => lower.indexOf(term)).filter((index) => index >= 0).sort((a, b) => a - b)[0] ?? 0
+  const start = Math.max(0, hit - SNIPPET_CHARS / 4)
+  return `${start > 0 ? '…' : ''}${text.slice(start, start + SNIPPET_CHARS)}${start + SNIPPET_CHARS < text.length ? '…' : ''}`
+}
+
+function countOccurrences(haystack: string, term: string): number {
+  let count = 0
+  for (let index = haystack.indexOf(term); index >= 0; index = haystack.indexOf(term, index + term.length)) count++
+  return count
+}
+
+function clampLimit(limit: number | undefined): number {
+  return Math.min(Math.max(1, Math.floor(limit ?? DEFAULT_LIMIT)), MAX_LIMIT)
+}
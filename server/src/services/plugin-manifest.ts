@@ -0,0 +1,154 @@
+import { z } from 'zod'
+import { TOOL_CATEGORIES } from '../tools/types.js'
+
+/** Every plugin folder, or the URL it is installed from, holds its manifest under this name. */
+export const PLUGIN_MANIFEST_FILE = 'plugin.json'
+
+/** `${secrets.NAME}` in backend settings is filled in with the secret the user saved. */
+const SECRET_REFERENCE = /\$\{secrets\.([A-Za-z0-9_]+)\}/g
+
+const relativeFile = z.string().min(1).refine(
+  (file) => !file.startsWith('/') && !/^[a-z]+:/i.test(file) && !file.split(/[\\/]/).includes('..'),
+  'must be a path inside the plugin folder',
+)
+
+const toolSchema = z.object({
+  name: z.string().regex(/^[a-z][a-z0-9_]{0,63}$/, 'must be lowercase letters, digits or underscores, starting with a letter'),
+  description: z.string().min(1).max(2000),
+  parameters: z.object({
+    type: z.literal('object'),
+    properties: z.record(z.record(z.unknown())).optional(),
+    required: z.array(z.string()).optional(),
+  }).passthrough(),
+  category: z.enum(TOOL_CATEGORIES).optional(),
+  /** Defaults to asking unless the tool is declared read-only. */
+  requires_approval: z.boolean().optional(),
+})
+
+const backendSchema = z.discriminatedUnion('type', [
+  /** Every call is POSTed as `{tool, args}`; the JSON answer is the result, or `{error}`. */
+  z.object({
+    type: z.literal('http'),
+    url: z.string().url().refine((url) => /^https?:/.test(url), 'must be an http(s) URL'),
+    headers: z.record(z.string()).default({}),
+  }),
+  /** A WebAssembly module in the plugin folder; see WasmPluginBackend for the exports it needs. */
+  z.object({ type: z.literal('wasm'), module: relativeFile }),
+  /** An MCP server, added to the installing user's servers; it lists its own tools. */
+  z.object({
+    type: z.literal('mcp'),
+    transport: z.enum(['stdio', 'streamable_http']),
+    command: z.string().optional(),
+    args: z.array(z.string()).default([]),
+    env: z.record(z.string()).default({}),
+    url: z.string().url().optional(),
+    bearerToken: z.string().optional(),
+  }),
+])
+
+const manifestSchema = z.object({
+  name: z.string().regex(/^[a-z][a-z0-9_-]{0,39}$/, 'must be 1-40 lowercase letters, digits, dashes or underscores, starting with a letter'),
+  version: z.string().regex(/^\d+\.\d+\.\d+(?:[-+][0-9A-Za-z.-]+)?$/, 'must be a semantic version such as 1.0.0'),
+  description: z.string().max(500).optional(),
+  backend: backendSchema,
+  tools: z.array(toolSchema).default([]),
+  prompts: z.array(z.object({
+    name: z.string().min(1).max(80),
+    content: z.string().min(1).optional(),
+    file: relativeFile.optional(),
+  }).refine((prompt) => (prompt.content === undefined) !== (prompt.file === undefined), 'needs exactly one of content or file')).default([]),
+  secrets: z.array(z.object({
+    name: z.string().regex(/^[A-Z][A-Z0-9_]*$/, 'must be UPPER_SNAKE_CASE'),
+    description: z.string().max(300).optional(),
+  })).default([]),
+}).superRefine((manifest, ctx) => {
+  const { backend } = manifest
+  if (backend.type !== 'mcp' && manifest.tools.length === 0) {
+    ctx.addIssue({ code: 'custom', path: ['tools'], message: `${backend.type === 'http' ? 'an' : 'a'} ${backend.type} backend must declare at least one tool` })
+  }
+  if (backend.type === 'mcp' && manifest.tools.length > 0) {
+    ctx.addIssue({ code: 'custom', path: ['tools'], message: 'an mcp backend lists its own tools; leave tools out' })
+  }
+  if (backend.type === 'mcp' && backend.transport === 'stdio' && !backend.command) {
+    ctx.addIssue({ code: 'custom', path: ['backend', 'command'], message: 'is required for the stdio transport' })
+  }
+  if (backend.type === 'mcp' && backend.transport === 'streamable_http' && !backend.url) {
+    ctx.addIssue({ code: 'custom', path: ['backend', 'url'], message: 'is required for the streamable_http transport' })
+  }
+  for (const [key, names] of [['tools', manifest.tools.map((tool) => tool.name)], ['prompts', manifest.prompts.map((prompt) => prompt.name)], ['secrets', manifest.secrets.map((secret) => secret.name)]] as const) {
+    const duplicate = names.find((name, index) => names.indexOf(name) !== index)
+    if (duplicate) ctx.addIssue({ code: 'custom', path: [key], message: `"${duplicate}" is declared twice` })
+  }
+  manifest.tools.forEach((tool, index) => {
+    const missing = (tool.parameters.required ?? []).filter((field) => !tool.parameters.properties?.[field])
+    if (missing.length) ctx.addIssue({ code: 'custom', path: ['tools', index, 'parameters', 'required'], message: `lists undefined properties: ${missing.join(', ')}` })
+  })
+  const declared = new Set(manifest.secrets.map((secret) => secret.name))
+  for (const name of secretReferences(backend)) {
+    if (!declared.has(name)) ctx.addIssue({ code: 'custom', path: ['backend'], message: `refers to undeclared secret ${name}` })
+  }
+})
+
+export type PluginManifest = z.infer<typeof manifestSchema>
+export type PluginBackendConfig = PluginManifest['backend']
+export type PluginToolManifest = PluginManifest['tools'][number]
+
+export class PluginError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'PluginError'
+  }
+}
+
+/** Validates a manifest; throws PluginError listing every problem, suitable for a 400. */
+export function parsePluginManifest(input: unknown): PluginManifest {
+  const parsed = manifestSchema.safeParse(input)
+  if (parsed.success) return parsed.data
+  const problems = parsed.error.issues.map((issue) => `${issue.path.length ? `${issue.path.join('.')} ` : ''}${issue.message}`)
+  throw new PluginError(`Invalid plugin manifest: ${problems.join('; ')}`)
+}
+
+/** Plugin tools are namespaced by plugin, as built-in tools are by area. */
+export function pluginToolName(plugin: string, tool: string): string {
+  return `${plugin}.${tool}`
+}
+
+/** Names of the secrets the backend settings refer to. */
+export function secretReferences(backend: PluginBackendConfig): string[] {
+  const names = new Set<string>()
+  for (const text of backendStrings(backend)) {
+    for (const match of text.matchAll(SECRET_REFERENCE)) names.add(match[1])
+  }
+  return [...names]
+}
+
+/** The backend settings with secret references filled in. */
+export function resolveBackendSecrets(backend: PluginBackendConfig, secrets: Record<string, string>): PluginBackendConfig {
+  const fill = (text: string) => text.replace(SECRET_REFERENCE, (_, name: string) => secrets[name] ?? '')
+  const fillAll = (record: Record<string, string>) => Object.fromEntries(Object.entries(record).map(([key, value]) => [key, fill(value)]))
+  switch (backend.type) {
+    case 'http':
+      return { ...backend, url: fill(backend.url), headers: fillAll(backend.headers) }
+    case 'mcp':
+      return {
+        ...backend,
+        args: backend.args.map(fill),
+        env: fillAll(backend.env),
+        ...(backend.url ? { url: fill(backend.url) } : {}),
+        ...(backend.bearerToken ? { bearerToken: fill(backend.bearerToken) } : {}),
+      }
+    default:
+      return backend
+  }
+}
+
+function backendStrings(backend: PluginBackendConfig): string[] {
+  switch (backend.type) {
+    case 'http':
+      return [backend.url, ...Object.values(backend.headers)]
+    case 'mcp':
+      return [...backend.args, ...Object.values(backend.env), backend.url ?? '', backend.bearerToken ?? '']
+    default:
+      return []
+  }
+}
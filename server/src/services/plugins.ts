@@ -0,0 +1,459 @@
+import { promises as fs } from 'node:fs'
+import path from 'node:path'
+import { Worker } from 'node:worker_threads'
+import { logger } from '../lib/logger.js'
+import type { SecretRedactor } from '../lib/secrets.js'
+import type { McpManager } from '../mcp/manager.js'
+import type { ApiKeyRepository, SystemPromptRepository } from '../repositories/types.js'
+import type { ToolRegistryImpl } from '../tools/registry.js'
+import { registerPluginTools } from '../tools/plugins.js'
+import {
+  type PluginManifest,
+  PLUGIN_MANIFEST_FILE,
+  PluginError,
+  parsePluginManifest,
+  pluginToolName,
+  resolveBackendSecrets,
+} from './plugin-manifest.js'
+
+/** Each plugin's secrets live in one encrypted api_keys entry under this prefix and its name. */
+export const PLUGIN_SECRETS_PREFIX = 'plugin_secrets:'
+/** MCP-backed plugins appear among the user's MCP servers under this prefix and their name. */
+export const PLUGIN_MCP_SERVER_PREFIX = 'plugin:'
+
+const FETCH_TIMEOUT_MS = 30_000
+const MAX_MANIFEST_BYTES = 256 * 1024
+const MAX_FILE_BYTES = 20 * 1024 * 1024
+const HTTP_CALL_TIMEOUT_MS = 60_000
+/** Longer answers are cut; a tool result this long would crowd the context anyway. */
+const MAX_HTTP_RESPONSE_CHARS = 32_000
+/** A WebAssembly call the tool's own deadline does not stop sooner is stopped here. */
+const WASM_CALL_TIMEOUT_MS = 60_000
+
+/** Runs one of a plugin's tools; the result is the tool output, a failure throws. */
+export interface PluginBackend {
+  call(tool: string, args: Record<string, unknown>, signal?: AbortSignal): Promise<unknown>
+  /** Releases what the backend holds, e.g. a worker thread. */
+  close?(): Promise<void>
+}
+
+export type PluginStatus = 'active' | 'needs_secrets' | 'error'
+
+export interface PluginView {
+  name: string
+  version: string
+  description: string | null
+  backend: PluginManifest['backend']['type']
+  /** Registered names, `<plugin>.<tool>`; empty for MCP backends, whose tools show under MCP servers. */
+  tools: string[]
+  prompts: string[]
+  secrets: Array<{ name: string; description: string | null; set: boolean }>
+  status: PluginStatus
+  error: string | null
+}
+
+interface LoadedPlugin {
+  manifest: PluginManifest
+  dir: string
+  status: PluginStatus
+  error: string | null
+  /** Names of the declared secrets that are saved. */
+  savedSecrets: string[]
+  /** Runs the plugin's tools while it is active; MCP backends have none. */
+  runner: PluginBackend | null
+}
+
+export interface PluginManagerDeps {
+  tools: ToolRegistryImpl
+  apiKeys: ApiKeyRepository
+  systemPrompts: SystemPromptRepository
+  mcps: Pick<McpManager, 'listServers' | 'createServer' | 'updateServer' | 'deleteServer'>
+  redactor: Pick<SecretRedactor, 'register'>
+  fetchImpl?: typeof fetch
+}
+
+/**
+ * Installs plugins into one folder per plugin under `pluginsDir` and
+ * registers what they provide: tools, prompts in the system prompt library,
+ * and for MCP backends a server of the installing user. A plugin whose
+ * declared secrets are not all saved stays installed but inactive.
+ */
+export class PluginManager {
+  private readonly plugins = new Map<string, LoadedPlugin>()
+  private readonly fetchImpl: typeof fetch
+
+  constructor(private readonly pluginsDir: string, private readonly deps: PluginManagerDeps) {
+    this.fetchImpl = deps.fetchImpl ?? fetch
+  }
+
+  /** Reads every installed plugin; an unreadable one is logged and skipped rather than stopping startup. */
+  async loadAll(): Promise<void> {
+    const entries = await fs.readdir(this.pluginsDir, { withFileTypes: true }).catch(() => [])
+    for (const entry of entries) {
+      if (!entry.isDirectory() || entry.name.startsWith('.')) continue
+      const dir = path.join(this.pluginsDir, entry.name)
+      try {
+        const manifest = parsePluginManifest(JSON.parse(await fs.readFile(path.join(dir, PLUGIN_MANIFEST_FILE), 'utf8')))
+        if (manifest.name !== entry.name) throw new PluginError(`Folder ${entry.name} holds plugin ${manifest.name}`)
+        await this.activate(manifest, dir)
+      } catch (err) {
+        logger.warn({ plugin: entry.name, err: err instanceof Error ? err.message : String(err) }, 'Plugin not loaded')
+      }
+    }
+    logger.info({ count: this.plugins.size, dir: this.pluginsDir }, 'Plugins loaded')
+  }
+
+  list(): PluginView[] {
+    return [...this.plugins.values()].map((plugin) => this.view(plugin)).sort((a, b) => a.name.localeCompare(b.name))
+  }
+
+  get(name: string): PluginView | null {
+    const plugin = this.plugins.get(name)
+    return plugin ? this.view(plugin) : null
+  }
+
+  /**
+   * Installs, or upgrades, a plugin from a local folder or from the URL of
+   * its manifest (or of the folder holding it). Throws PluginError when the
+   * source cannot be read or fails validation; nothing is changed then.
+   */
+  async install(userId: string, source: string): Promise<PluginView> {
+    const remote = /^https?:\/\//i.test(source)
+    const manifestLocation = remote
+      ? (source.endsWith('.json') ? source : `${source.replace(/\/+$/, '')}/${PLUGIN_MANIFEST_FILE}`)
+      : path.join(path.resolve(source), PLUGIN_MANIFEST_FILE)
+    const read = (file: string) => remote
+      ? this.download(new URL(file, manifestLocation).href, file === PLUGIN_MANIFEST_FILE ? MAX_MANIFEST_BYTES : MAX_FILE_BYTES)
+      : readLocal(path.join(path.dirname(manifestLocation), file))
+
+    let manifest: PluginManifest
+    try {
+      manifest = parsePluginManifest(JSON.parse((await read(PLUGIN_MANIFEST_FILE)).toString('utf8')))
+    } catch (err) {
+      if (err instanceof PluginError) throw err
+      throw new PluginError(`Could not read ${PLUGIN_MANIFEST_FILE} from ${source}: ${err instanceof Error ? err.message : String(err)}`)
+    }
+    const clash = this.deps.tools.listMetadata().find((tool) => tool.name.startsWith(`${manifest.name}.`) && !this.plugins.has(manifest.name))
+    if (clash) throw new PluginError(`Plugin name ${manifest.name} clashes with the built-in tool ${clash.name}`)
+
+    // Everything is fetched and written aside first, so a failed upgrade leaves the old version in place
+    const files = [
+      ...(manifest.backend.type === 'wasm' ? [manifest.backend.module] : []),
+      ...manifest.prompts.flatMap((prompt) => (prompt.file ? [prompt.file] : [])),
+    ]
+    const staging = path.join(this.pluginsDir, `.${manifest.name}-${Date.now()}`)
+    try {
+      await fs.mkdir(staging, { recursive: true })
+      await fs.writeFile(path.join(staging, PLUGIN_MANIFEST_FILE), JSON.stringify(manifest, null, 2))
+      for (const file of files) {
+        const data = await read(file).catch((err: unknown) => {
+          throw new PluginError(`Could not read ${file} from ${source}: ${err instanceof Error ? err.message : String(err)}`)
+        })
+        await fs.mkdir(path.dirname(path.join(staging, file)), { recursive: true })
+        await fs.writeFile(path.join(staging, file), data)
+      }
+      this.deactivate(manifest.name)
+      const dir = path.join(this.pluginsDir, manifest.name)
+      await fs.rm(dir, { recursive: true, force: true })
+      await fs.rename(staging, dir)
+      await this.installPrompts(manifest, dir)
+      await this.activate(manifest, dir, userId)
+    } finally {
+      await fs.rm(staging, { recursive: true, force: true })
+    }
+    logger.info({ plugin: manifest.name, version: manifest.version, source }, 'Plugin installed')
+    return this.get(manifest.name)!
+  }
+
+  /** Saves secrets for a plugin; an empty value removes one. Activates the plugin once all are set. */
+  async setSecrets(userId: string, name: string, values: Record<string, unknown>): Promise<PluginView> {
+    const plugin = this.plugins.get(name)
+    if (!plugin) throw new PluginError(`Plugin not found: ${name}`)
+    const declared = new Set(plugin.manifest.secrets.map((secret) => secret.name))
+    const secrets = await this.loadSecrets(name)
+    for (const [key, value] of Object.entries(values)) {
+      if (!declared.has(key)) throw new PluginError(`${name} declares no secret ${key}`)
+      if (typeof value !== 'string') throw new PluginError(`${key} must be a string`)
+      if (value) secrets[key] = value
+      else delete secrets[key]
+    }
+    if (Object.keys(secrets).length === 0) await this.deps.apiKeys.delete(`${PLUGIN_SECRETS_PREFIX}${name}`)
+    else await this.deps.apiKeys.upsert(`${PLUGIN_SECRETS_PREFIX}${name}`, JSON.stringify(secrets))
+    this.deactivate(name)
+    await this.activate(plugin.manifest, plugin.dir, userId)
+    return this.get(name)!
+  }
+
+  /** Removes the plugin with its tools, secrets, prompts and MCP server. Returns false when it was not installed. */
+  async uninstall(userId: string, name: string): Promise<boolean> {
+    const plugin = this.plugins.get(name)
+    if (!plugin) return false
+    this.deactivate(name)
+    this.plugins.delete(name)
+    const server = await this.findMcpServer(userId, name)
+    if (server) await this.deps.mcps.deleteServer(userId, server.id)
+    const prefix = promptName(name, '')
+    for (const prompt of await this.deps.systemPrompts.list()) {
+      if (prompt.name.startsWith(prefix)) await this.deps.systemPrompts.delete(prompt.id)
+    }
+    await this.deps.apiKeys.delete(`${PLUGIN_SECRETS_PREFIX}${name}`)
+    await fs.rm(plugin.dir, { recursive: true, force: true })
+    logger.info({ plugin: name }, 'Plugin uninstalled')
+    return true
+  }
+
+  /** `userId` is needed to add or change an MCP server; at startup those already exist. */
+  private async activate(manifest: PluginManifest, dir: string, userId?: string): Promise<void> {
+    const plugin: LoadedPlugin = { manifest, dir, status: 'needs_secrets', error: null, savedSecrets: [], runner: null }
+    this.plugins.set(manifest.name, plugin)
+    try {
+      const secrets = await this.loadSecrets(manifest.name)
+      for (const value of Object.values(secrets)) this.deps.redactor.register(value)
+      plugin.savedSecrets = manifest.secrets.filter((secret) => secrets[secret.name]).map((secret) => secret.name)
+      const ready = plugin.savedSecrets.length === manifest.secrets.length
+      const backend = resolveBackendSecrets(manifest.backend, secrets)
+      if (backend.type === 'mcp') {
+        if (userId) await this.syncMcpServer(userId, manifest.name, backend, ready)
+      } else if (ready) {
+        plugin.runner = backend.type === 'http'
+          ? new HttpPluginBackend(backend.url, backend.headers, this.fetchImpl)
+          : await WasmPluginBackend.load(path.join(dir, backend.module))
+        registerPluginTools(this.deps.tools, manifest, plugin.runner)
+      }
+      plugin.status = ready ? 'active' : 'needs_secrets'
+    } catch (err) {
+      plugin.status = 'error'
+      plugin.error = err instanceof Error ? err.message : String(err)
+      logger.warn({ plugin: manifest.name, err: plugin.error }, 'Plugin could not be activated')
+    }
+  }
+
+  private deactivate(name: string): void {
+    const plugin = this.plugins.get(name)
+    if (!plugin) return
+    for (const tool of plugin.manifest.tools) this.deps.tools.unregister(pluginToolName(name, tool.name))
+    void plugin.runner?.close?.()
+    plugin.runner = null
+  }
+
+  /** Missing secrets leave the server disabled rather than starting it half configured. */
+  private async syncMcpServer(userId: string, name: string, backend: Extract<PluginManifest['backend'], { type: 'mcp' }>, enabled: boolean): Promise<void> {
+    const input = {
+      name: `${PLUGIN_MCP_SERVER_PREFIX}${name}`,
+      transport: backend.transport,
+      command: backend.command ?? null,
+      args: backend.args,
+      env: backend.env,
+      url: backend.url ?? null,
+      bearerToken: backend.bearerToken ?? null,
+      enabled,
+    }
+    const existing = await this.findMcpServer(userId, name)
+    if (existing) await this.deps.mcps.updateServer(userId, existing.id, input)
+    else await this.deps.mcps.createServer(userId, input)
+  }
+
+  private async findMcpServer(userId: string, name: string) {
+    const servers = await this.deps.mcps.listServers(userId)
+    return servers.find((server) => server.name === `${PLUGIN_MCP_SERVER_PREFIX}${name}`) ?? null
+  }
+
+  /** Prompts go to the system prompt library as "<plugin>/<prompt>", replacing those of an earlier version. */
+  private async installPrompts(manifest: PluginManifest, dir: string): Promise<void> {
+    const existing = await this.deps.systemPrompts.list()
+    const prefix = promptName(manifest.name, '')
+    const wanted = new Set(manifest.prompts.map((prompt) => promptName(manifest.name, prompt.name)))
+    for (const prompt of existing) {
+      if (prompt.name.startsWith(prefix) && !wanted.has(prompt.name)) await this.deps.systemPrompts.delete(prompt.id)
+    }
+    for (const prompt of manifest.prompts) {
+      const name = promptName(manifest.name, prompt.name)
+      const content = prompt.content ?? await fs.readFile(path.join(dir, prompt.file!), 'utf8')
+      const current = existing.find((entry) => entry.name === name)
+      if (current) await this.deps.systemPrompts.update(current.id, { content })
+      else await this.deps.systemPrompts.create({ name, content })
+    }
+  }
+
+  private async loadSecrets(name: string): Promise<Record<string, string>> {
+    const record = await this.deps.apiKeys.getByProvider(`${PLUGIN_SECRETS_PREFIX}${name}`)
+    if (!record) return {}
+    try {
+      return JSON.parse(record.encryptedKey) as Record<string, string>
+    } catch {
+      throw new PluginError(`Stored secrets of ${name} are unreadable; save them again`)
+    }
+  }
+
+  private async download(url: string, maxBytes: number): Promise<Buffer> {
+    const response = await this.fetchImpl(url, { signal: AbortSignal.timeout(FETCH_TIMEOUT_MS) })
+    if (!response.ok) throw new Error(`${url} answered ${response.status}`)
+    const data = Buffer.from(await response.arrayBuffer())
+    if (data.length > maxBytes) throw new Error(`${url} is larger than ${maxBytes} bytes`)
+    return data
+  }
+
+  private view(plugin: LoadedPlugin): PluginView {
+    const { manifest } = plugin
+    const registered = manifest.tools.map((tool) => pluginToolName(manifest.name, tool.name))
+    return {
+      name: manifest.name,
+      version: manifest.version,
+      description: manifest.description ?? null,
+      backend: manifest.backend.type,
+      tools: plugin.status === 'active' ? registered.filter((tool) => this.deps.tools.getMetadata(tool)) : [],
+      prompts: manifest.prompts.map((prompt) => promptName(manifest.name, prompt.name)),
+      // Values are never shown, only whether each is saved
+      secrets: manifest.secrets.map((secret) => ({
+        name: secret.name,
+        description: secret.description ?? null,
+        set: plugin.savedSecrets.includes(secret.name),
+      })),
+      status: plugin.status,
+      error: plugin.error,
+    }
+  }
+}
+
+function promptName(plugin: string, prompt: string): string {
+  return `${plugin}/${prompt}`
+}
+
+async function readLocal(file: string): Promise<Buffer> {
+  const stat = await fs.stat(file)
+  if (stat.size > MAX_FILE_BYTES) throw new Error(`${file} is larger than ${MAX_FILE_BYTES} bytes`)
+  return fs.readFile(file)
+}
+
+/** Calls a plugin's web service. */
+export class HttpPluginBackend implements PluginBackend {
+  constructor(
+    private readonly url: string,
+    private readonly headers: Record<string, string>,
+    private readonly fetchImpl: typeof fetch = fetch,
+  ) {}
+
+  async call(tool: string, args: Record<string, unknown>, signal?: AbortSignal): Promise<unknown> {
+    const timeout = AbortSignal.timeout(HTTP_CALL_TIMEOUT_MS)
+    const response = await this.fetchImpl(this.url, {
+      method: 'POST',
+      headers: { ...this.headers, 'content-type': 'application/json' },
+      body: JSON.stringify({ tool, args }),
+      signal: signal ? AbortSignal.any([signal, timeout]) : timeout,
+    })
+    const text = (await response.text()).slice(0, MAX_HTTP_RESPONSE_CHARS)
+    let body: unknown = text
+    try {
+      body = text.trim() ? JSON.parse(text) : null
+    } catch {
+      // Plain text answers are the output as they are
+    }
+    const error = body && typeof body === 'object' && 'error' in body ? String((body as { error: unknown }).error) : null
+    if (!response.ok || error) throw new Error(error ?? `Plugin service answered ${response.status}${text.trim() ? `: ${text.trim()}` : ''}`)
+    return body && typeof body === 'object' && 'output' in body ? (body as { output: unknown }).output : body
+  }
+}
+
+/**
+ * Runs a plugin compiled to WebAssembly, with no imports, so it can compute
+ * but not reach files or the network. The module exports `memory`,
+ * `alloc(len) -> ptr` and `call(namePtr, nameLen, argsPtr, argsLen) -> i64`,
+ * whose result packs the pointer (high 32 bits) and length of a UTF-8 JSON
+ * answer: `{"output": ...}` or `{"error": "..."}`.
+ *
+ * The module runs in a worker thread, one call at a time, so a plugin that
+ * never returns cannot stall the server: on abort, the tool's deadline or
+ * `timeoutMs` the worker is terminated and the next call starts a fresh one.
+ */
+export class WasmPluginBackend implements PluginBackend {
+  private worker: Worker | null = null
+  private queue: Promise<unknown> = Promise.resolve()
+
+  private constructor(private readonly module: WebAssembly.Module, private readonly timeoutMs: number) {}
+
+  static async load(file: string, timeoutMs = WASM_CALL_TIMEOUT_MS): Promise<WasmPluginBackend> {
+    const module = await WebAssembly.compile(await fs.readFile(file)).catch((err: unknown) => {
+      throw new PluginError(`WebAssembly module ${path.basename(file)} could not be loaded: ${err instanceof Error ? err.message : String(err)}`)
+    })
+    // Checked without instantiating, which would run the module's start function here
+    const exports = new Map(WebAssembly.Module.exports(module).map((entry) => [entry.name, entry.kind]))
+    if (exports.get('memory') !== 'memory' || exports.get('alloc') !== 'function' || exports.get('call') !== 'function') {
+      throw new PluginError(`WebAssembly module ${path.basename(file)} must export memory, alloc and call`)
+    }
+    return new WasmPluginBackend(module, timeoutMs)
+  }
+
+  call(tool: string, args: Record<string, unknown>, signal?: AbortSignal): Promise<unknown> {
+    const run = this.queue.then(() => this.run(tool, args, signal))
+    this.queue = run.catch(() => undefined)
+    return run
+  }
+
+  async close(): Promise<void> {
+    const worker = this.worker
+    this.worker = null
+    await worker?.terminate()
+  }
+
+  private run(tool: string, args: Record<string, unknown>, signal?: AbortSignal): Promise<unknown> {
+    signal?.throwIfAborted()
+    if (!this.worker) {
+      this.worker = new Worker(WASM_WORKER_SOURCE, { eval: true, workerData: { module: this.module } })
+      // An idle plugin does not keep the process alive
+      this.worker.unref()
+    }
+    const worker = this.worker
+    return new Promise((resolve, reject) => {
+      const settle = (outcome: () => void, discard: boolean) => {
+        clearTimeout(timer)
+        signal?.removeEventListener('abort', onAbort)
+        worker.off('message', onMessage).off('error', onError).off('exit', onExit)
+        if (discard) {
+          if (this.worker === worker) this.worker = null
+          void worker.terminate()
+        }
+        outcome()
+      }
+      const onMessage = (message: { answer?: string; error?: string }) => settle(() => {
+        if (message.error !== undefined) return reject(new Error(message.error))
+        try {
+          const answer = JSON.parse(message.answer ?? '') as { output?: unknown; error?: unknown }
+          if (answer.error !== undefined) reject(new Error(String(answer.error)))
+          else resolve(answer.output ?? null)
+        } catch (err) {
+          reject(err)
+        }
+      }, false)
+      const onError = (err: Error) => settle(() => reject(new Error(`WebAssembly plugin failed: ${err.message}`)), true)
+      const onExit = (code: number) => settle(() => reject(new Error(`WebAssembly plugin stopped with exit code ${code}`)), true)
+      const onAbort = () => settle(() => reject(signal!.reason), true)
+      const timer = setTimeout(() => settle(() => reject(new Error(`WebAssembly plugin call timed out after ${this.timeoutMs}ms`)), true), this.timeoutMs)
+      signal?.addEventListener('abort', onAbort, { once: true })
+      worker.on('message', onMessage).once('error', onError).once('exit', onExit)
+      worker.postMessage({ tool, args: JSON.stringify(args) })
+    })
+  }
+}
+
+/** Instantiates the module once per worker and answers one call per message; traps come back as errors. */
+const WASM_WORKER_SOURCE = `
+const { parentPort, workerData } = require('node:worker_threads')
+const { exports } = new WebAssembly.Instance(workerData.module, {})
+const write = (text) => {
+  const bytes = Buffer.from(text, 'utf8')
+  const ptr = exports.alloc(bytes.length)
+  new Uint8Array(exports.memory.buffer, ptr, bytes.length).set(bytes)
+  return [ptr, bytes.length]
+}
+parentPort.on('message', ({ tool, args }) => {
+  try {
+    const [namePtr, nameLen] = write(tool)
+    const [argsPtr, argsLen] = write(args)
+    const packed = BigInt.asUintN(64, exports.call(namePtr, nameLen, argsPtr, argsLen))
+    const answer = Buffer.from(exports.memory.buffer, Number(packed >> 32n), Number(packed & 0xffffffffn)).toString('utf8')
+    parentPort.postMessage({ answer })
+  } catch (err) {
+    parentPort.postMessage({ error: err instanceof Error ? err.message : String(err) })
+  }
+})
+`
@@ -0,0 +1,275 @@
+import fs from 'fs/promises'
+import path from 'path'
+import { looksBinary } from '../lib/attachment-validation.js'
+import { IgnoreRules } from '../lib/gitignore.js'
+import { logger } from '../lib/logger.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { SessionRepository } from '../repositories/types.js'
+import { getSessionFilesDir } from '../tools/path-policy.js'
+import { indexAttachment, rankChunks, type AttachmentIndexRuntime, type SearchSource } from './attachment-index.js'
+import type { PdfExtraction } from './pdf-extraction.js'
+import { extractTextFile, TextInputError } from './text-extraction.js'
+
+/** Stops a folder drop of, say, a home directory from indexing all of it. */
+export const MAX_PROJECT_FILES = 2000
+/** Larger files are usually generated code or data dumps rather than source. */
+export const MAX_PROJECT_FILE_BYTES = 1024 * 1024
+
+/** Skipped in every project before its own ignore files apply; hidden files covers .git and .env. */
+const DEFAULT_IGNORES = [
+  '.*',
+  'node_modules/', 'bower_components/', 'vendor/', 'target/', 'dist/', 'build/', 'out/', 'coverage/',
+  '__pycache__/', 'venv/',
+  '*.lock', 'package-lock.json', '*.min.js', '*.min.css', '*.map',
+].join('\n')
+const IGNORE_FILES = ['.gitignore', '.ignore']
+/** Per-session list of linked project names, hidden like artifact metadata. */
+const SESSION_LINKS_FILE = '.projects.json'
+
+export class ProjectInputError extends Error {}
+
+export interface ProjectFile {
+  /** Relative to the project root, with `/` separators. */
+  path: string
+  hash: string
+  bytes: number
+  chunks: number
+}
+
+export interface Project {
+  name: string
+  /** Folder the project was ingested from. */
+  root: string
+  files: ProjectFile[]
+  /** True when the folder had more than MAX_PROJECT_FILES candidate files. */
+  truncated: boolean
+  indexedAt: number
+}
+
+export interface ProjectSummary extends Omit<Project, 'files'> {
+  files: number
+  bytes: number
+}
+
+export interface ProjectSearchResult {
+  project: string
+  path: string
+  hash: string
+  /** Index of the chunk within the file's text. */
+  chunk: number
+  score: number
+  text: string
+}
+
+type ProjectStore = Pick<RuntimeContext, 'projectsDir'>
+type ProjectLinks = Pick<RuntimeContext, 'sessionFilesRoot'>
+
+export type ProjectSearchDeps = AttachmentIndexRuntime & ProjectStore & ProjectLinks & { sessions: SessionRepository }
+
+/**
+ * Indexes the text files of a local folder as a named project: each file is
+ * stored and chunked like a text attachment and embedded for project.search.
+ * Ingesting under an existing name replaces that project.
+ */
+export async function ingestProject(
+  runtime: AttachmentIndexRuntime & ProjectStore,
+  userId: string,
+  input: { root: string; name?: string; signal?: AbortSignal },
+): Promise<ProjectSummary> {
+  const root = input.root.trim()
+  if (!root || !path.isAbsolute(root)) throw new ProjectInputError('An absolute folder path is required')
+  const stat = await fs.stat(root).catch(() => null)
+  if (!stat?.isDirectory()) throw new ProjectInputError(`Not a folder: ${root}`)
+  const name = assertProjectName(input.name?.trim() || path.basename(root))
+
+  const { paths, truncated } = await walkProject(root)
+  const files: ProjectFile[] = []
+  for (const relative of paths) {
+    input.signal?.throwIfAborted()
+    const data = await fs.readFile(path.join(root, relative)).catch(() => null)
+    if (!data || looksBinary(data)) continue
+
+    let extraction: PdfExtraction
+    try {
+      extraction = await extractTextFile(runtime, { bytes: data, fileName: relative })
+    } catch (err) {
+      // Empty and non-UTF-8 files are left out
+      if (err instanceof TextInputError) continue
+      throw err
+    }
+    await indexAttachment(runtime, extraction.hash, extraction.chunks, input.signal)
+    files.push({ path: relative, hash: extraction.hash, bytes: extraction.bytes, chunks: extraction.chunks.length })
+  }
+  if (files.length === 0) throw new ProjectInputError(`No text files to index in ${root}`)
+
+  const project: Project = { name, root, files, truncated, indexedAt: Date.now() }
+  const file = manifestPath(runtime, userId, name)
+  await fs.mkdir(path.dirname(file), { recursive: true })
+  await fs.writeFile(file, JSON.stringify(project, null, 2))
+  logger.info({ userId, project: name, files: files.length, truncated }, 'Project indexed')
+  return summarize(project)
+}
+
+/**
+ * Candidate files under `root`, relative and in walk order. Hidden files,
+ * common build and dependency directories, and anything matched by a
+ * .gitignore or .ignore file on the way down are skipped. Symlinks are not
+ * followed, so a link can neither leave the folder nor loop.
+ */
+export async function walkProject(root: string): Promise<{ paths: string[]; truncated: boolean }> {
+  const paths: string[] = []
+  let truncated = false
+
+  const visit = async (relativeDir: string, inherited: IgnoreRules): Promise<void> => {
+    const dir = path.join(root, relativeDir)
+    const entries = (await fs.readdir(dir, { withFileTypes: true }).catch(() => []))
+      .sort((a, b) => a.name.localeCompare(b.name))
+    let rules = inherited
+    for (const ignoreFile of IGNORE_FILES) {
+      if (!entries.some((entry) => entry.isFile() && entry.name === ignoreFile)) continue
+      rules = rules.with(await fs.readFile(path.join(dir, ignoreFile), 'utf-8'), relativeDir)
+    }
+
+    for (const entry of entries) {
+      if (truncated) return
+      const relative = relativeDir ? `${relativeDir}/${entry.name}` : entry.name
+      if (entry.isDirectory()) {
+        if (!rules.ignores(relative, true)) await visit(relative, rules)
+      } else if (entry.isFile() && !rules.ignores(relative, false)) {
+        const { size } = await fs.stat(path.join(dir, entry.name))
+        if (size === 0 || size > MAX_PROJECT_FILE_BYTES) continue
+        if (paths.length >= MAX_PROJECT_FILES) {
+          truncated = true
+          return
+        }
+        paths.push(relative)
+      }
+    }
+  }
+
+  await visit('', IgnoreRules.empty().with(DEFAULT_IGNORES))
+  return { paths, truncated }
+}
+
+export async function listProjects(runtime: ProjectStore, userId: string): Promise<ProjectSummary[]> {
+  const dir = path.join(runtime.projectsDir, userId)
+  const names = (await fs.readdir(dir).catch(() => [] as string[])).filter((name) => name.endsWith('.json'))
+  const projects = await Promise.all(names.map((name) => readProject(path.join(dir, name))))
+  return projects
+    .filter((project): project is Project => project !== null)
+    .map(summarize)
+    .sort((a, b) => a.name.localeCompare(b.name))
+}
+
+export async function getProject(runtime: ProjectStore, userId: string, name: string): Promise<Project | null> {
+  return readProject(manifestPath(runtime, userId, name))
+}
+
+/** Forgets a project; its stored files go with the next orphan cleanup unless something else references them. */
+export async function deleteProject(runtime: ProjectStore, userId: string, name: string): Promise<boolean> {
+  const file = manifestPath(runtime, userId, name)
+  if (!(await readProject(file))) return false
+  await fs.rm(file, { force: true })
+  return true
+}
+
+/** The given project names, once each is known to exist for the user. */
+export async function requireProjects(runtime: ProjectStore, userId: string, names: string[]): Promise<string[]> {
+  const unique = [...new Set(names)]
+  for (const name of unique) {
+    if (!(await getProject(runtime, userId, name))) throw new ProjectInputError(`Unknown project: ${name}`)
+  }
+  return unique
+}
+
+/** Makes projects searchable from a conversation; returns every project now linked to it. */
+export async function linkProjects(runtime: ProjectLinks, sessionId: string, names: string[]): Promise<string[]> {
+  const linked = await linkedProjects(runtime, sessionId)
+  const next = [...new Set([...linked, ...names])]
+  if (next.length === linked.length) return linked
+  const dir = getSessionFilesDir(runtime.sessionFilesRoot, sessionId)
+  await fs.mkdir(dir, { recursive: true })
+  await fs.writeFile(path.join(dir, SESSION_LINKS_FILE), JSON.stringify(next))
+  return next
+}
+
+export async function linkedProjects(runtime: ProjectLinks, sessionId: string): Promise<string[]> {
+  try {
+    const file = path.join(getSessionFilesDir(runtime.sessionFilesRoot, sessionId), SESSION_LINKS_FILE)
+    const names: unknown = JSON.parse(await fs.readFile(file, 'utf-8'))
+    return Array.isArray(names) ? names.filter((name): name is string => typeof name === 'string') : []
+  } catch {
+    return []
+  }
+}
+
+/** Ranks the chunks of the projects linked to a session against a query. */
+export async function searchProjects(
+  deps: ProjectSearchDeps,
+  sessionId: string,
+  query: string,
+  options: { project?: string; limit?: number; signal?: AbortSignal } = {},
+): Promise<ProjectSearchResult[]> {
+  const session = await deps.sessions.getById(sessionId)
+  if (!session) throw new Error(`Session not found: ${sessionId}`)
+  let names = await linkedProjects(deps, sessionId)
+  if (options.project) {
+    if (!names.includes(options.project)) throw new Error(`Project ${options.project} is not linked to this conversation`)
+    names = [options.project]
+  }
+  if (names.length === 0) throw new Error('No project is linked to this conversation')
+
+  // Source names are `<project>/<path>`; project names cannot contain a slash
+  const sources: SearchSource[] = []
+  for (const name of names) {
+    const project = await getProject(deps, session.userId, name)
+    for (const file of project?.files ?? []) {
+      const extraction = await deps.attachments.getExtraction<PdfExtraction>(file.hash)
+      if (extraction?.chunks.length) sources.push({ name: `${name}/${file.path}`, hash: file.hash, chunks: extraction.chunks })
+    }
+  }
+  if (sources.length === 0) throw new Error('The linked projects have no indexed files')
+
+  const results = await rankChunks(deps, sources, query, options)
+  return results.map(({ attachment, ...result }) => {
+    const slash = attachment.indexOf('/')
+    return { project: attachment.slice(0, slash), path: attachment.slice(slash + 1), ...result }
+  })
+}
+
+/** Attachment hashes referenced by any user's projects, kept by orphan cleanup. */
+export async function listProjectHashes(runtime: ProjectStore): Promise<string[]> {
+  const hashes = new Set<string>()
+  for (const userId of await fs.readdir(runtime.projectsDir).catch(() => [] as string[])) {
+    const dir = path.join(runtime.projectsDir, userId)
+    for (const name of await fs.readdir(dir).catch(() => [] as string[])) {
+      if (!name.endsWith('.json')) continue
+      const project = await readProject(path.join(dir, name))
+      for (const file of project?.files ?? []) hashes.add(file.hash)
+    }
+  }
+  return [...hashes]
+}
+
+function assertProjectName(name: string): string {
+  if (!name || name.length > 64 || /[/\\\u0000-\u001f]/.test(name) || name === '.' || name === '..') {
+    throw new ProjectInputError(`Invalid project name '${name}': use up to 64 characters without slashes`)
+  }
+  return name
+}
+
+function manifestPath(runtime: ProjectStore, userId: string, name: string): string {
+  return path.join(runtime.projectsDir, userId, `${encodeURIComponent(name)}.json`)
+}
+
+async function readProject(file: string): Promise<Project | null> {
+  try {
+    return JSON.parse(await fs.readFile(file, 'utf-8')) as Project
+  } catch {
+    return null
+  }
+}
+
+function summarize({ files, ...project }: Project): ProjectSummary {
+  return { ...project, files: files.length, bytes: files.reduce((total, file) => total + file.bytes, 0) }
+}
@@ -0,0 +1,220 @@
+import { spawn } from 'child_process'
+import fs from 'fs/promises'
+import os from 'os'
+import path from 'path'
+
+export const MAX_PYTHON_FILES = 20
+export const MAX_PYTHON_FILE_BYTES = 10 * 1024 * 1024
+const SCRIPT_NAME = 'main.py'
+/** How long output still in the pipes is read once the interpreter has exited. */
+const PIPE_DRAIN_MS = 1000
+
+/**
+ * Run before the user's code: resource limits, then (unless network access
+ * was granted) sockets that refuse to connect. Limits the platform does not
+ * support are skipped.
+ */
+const BOOTSTRAP = `
+import os, resource, runpy, socket, sys
+def limit(name, value):
+    try:
+        resource.setrlimit(getattr(resource, name), (value, value))
+    except (AttributeError, ValueError, OSError):
+        pass
+limit('RLIMIT_CPU', int(os.environ['SANDBOX_CPU_SECONDS']))
+limit('RLIMIT_AS', int(os.environ['SANDBOX_MEMORY_BYTES']))
+limit('RLIMIT_FSIZE', int(os.environ['SANDBOX_FILE_BYTES']))
+if os.environ.get('SANDBOX_NETWORK') != '1':
+    def blocked(*args, **kwargs):
+        raise OSError('Network access is disabled in this sandbox')
+    socket.socket.connect = blocked
+    socket.socket.connect_ex = blocked
+    socket.socket.sendto = blocked
+    socket.getaddrinfo = blocked
+    socket.create_connection = blocked
+for name in [n for n in os.environ if n.startswith('SANDBOX_')]:
+    del os.environ[name]
+sys.argv = ['${SCRIPT_NAME}']
+sys.path.insert(0, os.getcwd())
+runpy.run_path('${SCRIPT_NAME}', run_name='__main__')
+`
+
+export interface PythonSandboxOptions {
+  /** Interpreter to run (PYTHON_BIN). */
+  pythonBin: string
+  /** Address-space cap for the interpreter, in MB. */
+  memoryMb: number
+  maxOutputBytes: number
+}
+
+export interface PythonRunInput {
+  code: string
+  /** Copied into the working directory before the code runs. */
+  files?: Array<{ name: string; data: Buffer }>
+  network?: boolean
+  timeoutMs: number
+  signal?: AbortSignal
+}
+
+export interface PythonRunResult {
+  exitCode: number | null
+  timedOut: boolean
+  stdout: string
+  stderr: string
+  /** Whether stdout or stderr went past maxOutputBytes and was cut. */
+  truncated: boolean
+  /** Files the code created or changed in its working directory. */
+  files: Array<{ name: string; data: Buffer }>
+  /** Files left out for being too many or too large. */
+  skippedFiles: string[]
+  durationMs: number
+}
+
+/**
+ * Runs Python in a child process of its own: a fresh temporary working
+ * directory, an empty environment, the interpreter's isolated mode (no user
+ * site-packages, no PYTHON* variables), CPU, memory and file-size limits, and
+ * no network unless asked for. Network blocking happens inside the
+ * interpreter, so it keeps well-behaved code and libraries off the network
+ * rather than containing hostile code; the call's approval is the real gate.
+ */
+export async function runPython(options: PythonSandboxOptions, input: PythonRunInput): Promise<PythonRunResult> {
+  const dir = await fs.mkdtemp(path.join(os.tmpdir(), 'python-run-'))
+  const startedAt = Date.now()
+  try {
+    const inputs = new Map<string, Buffer>()
+    for (const file of input.files ?? []) {
+      const name = path.basename(file.name)
+      if (!name || name === SCRIPT_NAME || name.startsWith('.')) throw new Error(`Input file name not allowed: ${file.name}`)
+      await fs.writeFile(path.join(dir, name), file.data)
+      inputs.set(name, file.data)
+    }
+    await fs.writeFile(path.join(dir, SCRIPT_NAME), input.code)
+
+    const child = spawn(options.pythonBin, ['-I', '-c', BOOTSTRAP], {
+      cwd: dir,
+      env: {
+        PATH: process.env.PATH ?? '/usr/bin:/bin',
+        HOME: dir,
+        TMPDIR: dir,
+        LANG: 'C.UTF-8',
+        PYTHONIOENCODING: 'utf-8',
+        MPLBACKEND: 'Agg',
+        SANDBOX_CPU_SECONDS: String(Math.max(1, Math.ceil(input.timeoutMs / 1000))),
+        SANDBOX_MEMORY_BYTES: String(options.memoryMb * 1024 * 1024),
+        SANDBOX_FILE_BYTES: String(MAX_PYTHON_FILE_BYTES),
+        SANDBOX_NETWORK: input.network ? '1' : '0',
+      },
+      stdio: ['ignore', 'pipe', 'pipe'],
+      // A process group of its own, so the script's subprocesses can be killed with it
+      detached: true,
+    })
+
+    const stdout = new OutputBuffer(options.maxOutputBytes)
+    const stderr = new OutputBuffer(options.maxOutputBytes)
+    child.stdout.on('data', (chunk: Buffer) => stdout.push(chunk))
+    child.stderr.on('data', (chunk: Buffer) => stderr.push(chunk))
+
+    let timedOut = false
+    const kill = () => {
+      if (child.pid === undefined) return
+      try {
+        process.kill(-child.pid, 'SIGKILL')
+      } catch {
+        // The group is already gone
+      }
+    }
+    const timer = setTimeout(() => {
+      timedOut = true
+      kill()
+    }, input.timeoutMs)
+    input.signal?.addEventListener('abort', kill, { once: true })
+
+    // Pipes close once every process holding them is gone, which a background subprocess can put off forever
+    const closed = new Promise<void>((resolve) => child.once('close', () => resolve()))
+    let exitCode: number | null
+    try {
+      exitCode = await new Promise<number | null>((resolve, reject) => {
+        child.once('error', reject)
+        child.once('exit', (code) => resolve(code))
+      })
+    } finally {
+      clearTimeout(timer)
+      input.signal?.removeEventListener('abort', kill)
+    }
+    kill()
+    let drainTimer: NodeJS.Timeout | undefined
+    await Promise.race([closed, new Promise((resolve) => { drainTimer = setTimeout(resolve, PIPE_DRAIN_MS) })])
+    clearTimeout(drainTimer)
+    child.stdout.destroy()
+    child.stderr.destroy()
+    if (input.signal?.aborted) throw new Error('Python run aborted')
+
+    const { files, skipped } = await collectOutputs(dir, inputs)
+    return {
+      exitCode,
+      timedOut,
+      stdout: stdout.text(),
+      stderr: stderr.text(),
+      truncated: stdout.truncated || stderr.truncated,
+      files,
+      skippedFiles: skipped,
+      durationMs: Date.now() - startedAt,
+    }
+  } finally {
+    await fs.rm(dir, { recursive: true, force: true })
+  }
+}
+
+class OutputBuffer {
+  private chunks: Buffer[] = []
+  private bytes = 0
+  truncated = false
+
+  constructor(private readonly max: number) {}
+
+  push(chunk: Buffer): void {
+    const room = this.max - this.bytes
+    if (room <= 0) {
+      this.truncated = true
+      return
+    }
+    if (chunk.length > room) this.truncated = true
+    const kept = chunk.subarray(0, room)
+    this.chunks.push(kept)
+    this.bytes += kept.length
+  }
+
+  text(): string {
+    return Buffer.concat(this.chunks).toString('utf-8')
+  }
+}
+
+/** New or changed files anywhere under the working directory, named by their relative path. */
+async function collectOutputs(dir: string, inputs: Map<string, Buffer>): Promise<{ files: PythonRunResult['files']; skipped: string[] }> {
+  const files: PythonRunResult['files'] = []
+  const skipped: string[] = []
+  const walk = async (relative: string): Promise<void> => {
+    for (const entry of await fs.readdir(path.join(dir, relative), { withFileTypes: true })) {
+      const name = relative ? `${relative}/${entry.name}` : entry.name
+      // Caches and dotfiles are the interpreter's and libraries' own
+      if (entry.name.startsWith('.') || entry.name === '__pycache__') continue
+      if (entry.isDirectory()) {
+        await walk(name)
+        continue
+      }
+      if (!entry.isFile() || name === SCRIPT_NAME) continue
+      const stat = await fs.stat(path.join(dir, name))
+      if (stat.size > MAX_PYTHON_FILE_BYTES || files.length >= MAX_PYTHON_FILES) {
+        skipped.push(name)
+        continue
+      }
+      const data = await fs.readFile(path.join(dir, name))
+      const original = inputs.get(name)
+      if (original && original.equals(data)) continue
+      files.push({ name, data })
+    }
+  }
+  await walk('')
+  return { files, skipped }
+}
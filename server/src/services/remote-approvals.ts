@@ -0,0 +1,141 @@
+import { createHmac, timingSafeEqual } from 'node:crypto'
+import { UNPACED } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+
+export interface RemoteApprovalToken {
+  agentId: string
+  callId: string
+  expiresAt: number
+}
+
+export interface RemoteApprovalRelayOptions {
+  /** Public origin the links point at (PUBLIC_BASE_URL). */
+  baseUrl: string
+  /** Signs the links; derived from ENCRYPTION_KEY so restarts keep them valid. */
+  secret: string
+  /** Receives a JSON POST per approval request, e.g. a push gateway. */
+  webhookUrl?: string
+  linkTtlMs: number
+}
+
+/** Body POSTed to REMOTE_APPROVAL_WEBHOOK_URL for every call that needs approval. */
+export interface RemoteApprovalNotification {
+  title: string
+  message: string
+  url: string
+  sessionId: string
+  agentId: string
+  callId: string
+  tool: string
+  expiresAt: number
+}
+
+const encode = (value: string | Buffer) => Buffer.from(value).toString('base64url')
+
+function sign(body: string, secret: string): string {
+  return createHmac('sha256', `remote-approvals:${secret}`).update(body).digest('base64url')
+}
+
+export function signApprovalToken(token: RemoteApprovalToken, secret: string): string {
+  const body = encode(JSON.stringify({ a: token.agentId, c: token.callId, e: token.expiresAt }))
+  return `${body}.${sign(body, secret)}`
+}
+
+/** Null when the token is malformed, tampered with or past its expiry. */
+export function verifyApprovalToken(raw: string, secret: string, now = Date.now()): RemoteApprovalToken | null {
+  const [body, signature, ...rest] = raw.split('.')
+  if (!body || !signature || rest.length > 0 || raw.length > 1024) return null
+  const expected = Buffer.from(sign(body, secret))
+  const given = Buffer.from(signature)
+  if (expected.length !== given.length || !timingSafeEqual(expected, given)) return null
+  try {
+    const { a, c, e } = JSON.parse(Buffer.from(body, 'base64url').toString('utf8')) as { a?: unknown; c?: unknown; e?: unknown }
+    if (typeof a !== 'string' || typeof c !== 'string' || typeof e !== 'number') return null
+    if (e <= now) return null
+    return { agentId: a, callId: c, expiresAt: e }
+  } catch {
+    return null
+  }
+}
+
+/**
+ * Sends a signed approve/deny link for every proposed tool call to an optional
+ * webhook, so long tasks can be unblocked from a phone. The link opens the
+ * public /remote-approvals page; the signature is its only authority, so it
+ * names a single call and stops working once that call is answered.
+ */
+export class RemoteApprovalRelay {
+  private iterator: AsyncIterator<AgentEvent> | null = null
+  private readonly baseUrl: string
+
+  constructor(
+    private readonly runtime: RuntimeContext,
+    private readonly options: RemoteApprovalRelayOptions,
+  ) {
+    this.baseUrl = options.baseUrl.trim().replace(/\/+$/, '')
+  }
+
+  start(): void {
+    if (this.iterator || !this.options.webhookUrl) return
+    const iterator = this.runtime.events.subscribe({ types: [EVENT_TYPES.TOOL_PROPOSED] }, UNPACED)[Symbol.asyncIterator]()
+    this.iterator = iterator
+    void (async () => {
+      for (let next = await iterator.next(); !next.done; next = await iterator.next()) {
+        const event = next.value
+        if (event.type !== EVENT_TYPES.TOOL_PROPOSED) continue
+        // One link per escalated batch: the page for its first call decides all of them
+        const { batch, callId } = event.payload
+        if (batch && batch.callIds[0] !== callId) continue
+        await this.notify(event)
+      }
+    })()
+  }
+
+  stop(): void {
+    void this.iterator?.return?.()
+    this.iterator = null
+  }
+
+  linkFor(agentId: string, callId: string, now = Date.now()): { url: string; expiresAt: number } {
+    const expiresAt = now + this.options.linkTtlMs
+    const token = signApprovalToken({ agentId, callId, expiresAt }, this.options.secret)
+    return { url: `${this.baseUrl}/remote-approvals/${token}`, expiresAt }
+  }
+
+  verify(raw: string): RemoteApprovalToken | null {
+    return verifyApprovalToken(raw, this.options.secret)
+  }
+
+  private async notify(event: Extract<AgentEvent, { type: typeof EVENT_TYPES.TOOL_PROPOSED }>): Promise<void> {
+    const { callId, name, batch } = event.payload
+    try {
+      const session = await this.runtime.repositories.sessions.getById(event.session_id)
+      const { url, expiresAt } = this.linkFor(event.agent_id, callId)
+      const notification: RemoteApprovalNotification = {
+        title: 'Approval needed',
+        message: batch
+          ? `A batch of ${batch.callIds.length} calls is waiting for approval${session?.title ? ` in "${session.title}"` : ''}`
+          : session?.title ? `${name} is waiting for approval in "${session.title}"` : `${name} is waiting for approval`,
+        url,
+        sessionId: event.session_id,
+        agentId: event.agent_id,
+        callId,
+        tool: name,
+        expiresAt,
+      }
+      const response = await fetch(this.options.webhookUrl!, {
+        method: 'POST',
+        headers: { 'content-type': 'application/json' },
+        body: JSON.stringify(notification),
+        signal: AbortSignal.timeout(10_000),
+      })
+      if (!response.ok) {
+        logger.warn({ status: response.status, callId }, 'Remote approval webhook rejected the notification')
+      }
+    } catch (err) {
+      logger.warn({ err, callId }, 'Failed to send remote approval notification')
+    }
+  }
+}
@@ -0,0 +1,187 @@
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+import type { InactiveSession } from '../repositories/types.js'
+import { expireLowConfidenceMemories, readMemorySettings } from './memory-settings.js'
+import { purgeSession } from './trash.js'
+
+export const RETENTION_PREFERENCE_KEY = 'retention_policy'
+export const PURGED_OUTPUT_PLACEHOLDER = '[tool output removed by retention policy]'
+
+const DAY_MS = 24 * 60 * 60 * 1000
+const RUN_INTERVAL_MS = 24 * 60 * 60 * 1000
+const PREVIEW_SESSION_LIMIT = 50
+
+/** Each rule is disabled when null. Usage is kept forever unless usageDays is set. */
+export interface RetentionPolicy {
+  /** Delete conversations with no activity for this many months. */
+  sessionsMonths: number | null
+  /** Drop stored tool outputs and blank inline tool results older than this. */
+  toolOutputsDays: number | null
+  usageDays: number | null
+}
+
+export const DEFAULT_RETENTION: RetentionPolicy = {
+  sessionsMonths: null,
+  toolOutputsDays: null,
+  usageDays: null,
+}
+
+export interface RetentionCutoffs {
+  sessionsBefore: number | null
+  toolOutputsBefore: number | null
+  usageBefore: number | null
+}
+
+export interface RetentionPreview {
+  policy: RetentionPolicy
+  cutoffs: RetentionCutoffs
+  sessions: { count: number; items: number; examples: InactiveSession[] }
+  toolOutputs: number
+  usageRecords: number
+  /** Low-confidence memories past the expiry in the memory settings. */
+  memories: number
+}
+
+export interface RetentionResult {
+  sessions: number
+  toolOutputs: number
+  usageRecords: number
+  memories: number
+}
+
+export function parseRetentionPolicy(raw: string | null): RetentionPolicy {
+  if (!raw) return { ...DEFAULT_RETENTION }
+  try {
+    return normalizeRetentionPolicy(JSON.parse(raw) as Partial<RetentionPolicy>)
+  } catch {
+    logger.warn('Ignoring malformed retention_policy preference')
+    return { ...DEFAULT_RETENTION }
+  }
+}
+
+/** Validate user input; throws with a message suitable for a 400. */
+export function normalizeRetentionPolicy(input: Partial<RetentionPolicy>): RetentionPolicy {
+  const rule = (value: unknown, name: string): number | null => {
+    if (value === undefined || value === null) return null
+    if (typeof value !== 'number' || !Number.isInteger(value) || value <= 0) {
+      throw new Error(`${name} must be a positive integer or null`)
+    }
+    return value
+  }
+  return {
+    sessionsMonths: rule(input.sessionsMonths, 'sessionsMonths'),
+    toolOutputsDays: rule(input.toolOutputsDays, 'toolOutputsDays'),
+    usageDays: rule(input.usageDays, 'usageDays'),
+  }
+}
+
+export function retentionCutoffs(policy: RetentionPolicy, now: number): RetentionCutoffs {
+  let sessionsBefore: number | null = null
+  if (policy.sessionsMonths !== null) {
+    const date = new Date(now)
+    date.setUTCMonth(date.getUTCMonth() - policy.sessionsMonths)
+    sessionsBefore = date.getTime()
+  }
+  return {
+    sessionsBefore,
+    toolOutputsBefore: policy.toolOutputsDays !== null ? now - policy.toolOutputsDays * DAY_MS : null,
+    usageBefore: policy.usageDays !== null ? now - policy.usageDays * DAY_MS : null,
+  }
+}
+
+/**
+ * Daily maintenance task that applies the retention policy stored in the
+ * `retention_policy` preference, and memory expiry from `memory_settings`.
+ * With the default policy and settings it does nothing.
+ */
+export class RetentionMaintenance {
+  private timer: NodeJS.Timeout | null = null
+  private running = false
+
+  constructor(private readonly runtime: RuntimeContext) {}
+
+  start(): void {
+    if (this.timer) return
+    void this.runScheduled()
+    this.timer = setInterval(() => {
+      void this.runScheduled()
+    }, RUN_INTERVAL_MS)
+    this.timer.unref()
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer)
+      this.timer = null
+    }
+  }
+
+  async policy(): Promise<RetentionPolicy> {
+    return parseRetentionPolicy(await this.runtime.repositories.preferences.get(RETENTION_PREFERENCE_KEY))
+  }
+
+  async preview(now = Date.now()): Promise<RetentionPreview> {
+    const policy = await this.policy()
+    const cutoffs = retentionCutoffs(policy, now)
+    const { retention } = this.runtime.repositories
+
+    const sessions = cutoffs.sessionsBefore !== null
+      ? await retention.listInactiveSessions(cutoffs.sessionsBefore)
+      : []
+    return {
+      policy,
+      cutoffs,
+      sessions: {
+        count: sessions.length,
+        items: sessions.reduce((sum, session) => sum + session.itemCount, 0),
+        examples: sessions.slice(0, PREVIEW_SESSION_LIMIT),
+      },
+      toolOutputs: cutoffs.toolOutputsBefore !== null
+        ? await retention.countToolOutputs(cutoffs.toolOutputsBefore, PURGED_OUTPUT_PLACEHOLDER)
+        : 0,
+      usageRecords: cutoffs.usageBefore !== null ? await retention.countUsage(cutoffs.usageBefore) : 0,
+      memories: await this.expireMemories(now, true),
+    }
+  }
+
+  async run(now = Date.now()): Promise<RetentionResult> {
+    const cutoffs = retentionCutoffs(await this.policy(), now)
+    const { retention } = this.runtime.repositories
+    const result: RetentionResult = { sessions: 0, toolOutputs: 0, usageRecords: 0, memories: 0 }
+
+    if (cutoffs.sessionsBefore !== null) {
+      for (const session of await retention.listInactiveSessions(cutoffs.sessionsBefore)) {
+        await purgeSession(this.runtime, session.id)
+        result.sessions++
+      }
+    }
+    if (cutoffs.toolOutputsBefore !== null) {
+      result.toolOutputs = await retention.purgeToolOutputs(cutoffs.toolOutputsBefore, PURGED_OUTPUT_PLACEHOLDER)
+    }
+    if (cutoffs.usageBefore !== null) {
+      result.usageRecords = await retention.purgeUsage(cutoffs.usageBefore)
+    }
+    result.memories = await this.expireMemories(now, false)
+    return result
+  }
+
+  private async expireMemories(now: number, dryRun: boolean): Promise<number> {
+    const { users, memories, preferences } = this.runtime.repositories
+    return expireLowConfidenceMemories({ users, memories }, await readMemorySettings(preferences), now, { dryRun })
+  }
+
+  private async runScheduled(): Promise<void> {
+    if (this.running) return
+    this.running = true
+    try {
+      const result = await this.run()
+      if (result.sessions > 0 || result.toolOutputs > 0 || result.usageRecords > 0 || result.memories > 0) {
+        logger.info(result, 'Applied retention policy')
+      }
+    } catch (err) {
+      logger.warn({ err }, 'Retention maintenance failed')
+    } finally {
+      this.running = false
+    }
+  }
+}
@@ -0,0 +1,513 @@
+import type { Item, ItemContentBlock, PlanStep } from '../domain/types.js'
+import { escapeHtml } from '../lib/html.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { ApprovalRecord } from '../repositories/types.js'
+import { getInspectorSession, type InspectorAgent, type InspectorSession, type InspectorStep, type InspectorUsage } from './inspector.js'
+
+/** `conversation` is the chat itself as one HTML page to share, rather than an account of the run. */
+export const RUN_REPORT_FORMATS = ['markdown', 'html', 'conversation'] as const
+export type RunReportFormat = (typeof RUN_REPORT_FORMATS)[number]
+
+/** Tool arguments and outputs are cut to this many characters; the report is for reading, not replay. */
+const MAX_PREVIEW_CHARS = 1500
+/** The conversation page shows more of each tool call, collapsed. */
+const MAX_TRANSCRIPT_TOOL_CHARS = 4000
+
+export interface ReportImage {
+  mediaType: string
+  /** Base64 payload, embedded as a data URL. */
+  data: string
+  name: string | null
+}
+
+export interface ReportToolCall {
+  name: string
+  arguments: string
+  /** Null while the call had no result yet. */
+  output: string | null
+  isError: boolean
+  images: ReportImage[]
+}
+
+/** A message, or a run of consecutive tool calls shown as one collapsed section. */
+export type TranscriptEntry =
+  | { kind: 'message'; role: 'user' | 'assistant'; text: string; images: ReportImage[]; attachments: string[]; createdAt: number }
+  | { kind: 'tools'; calls: ReportToolCall[] }
+
+export interface RunReport {
+  session: InspectorSession
+  /** Approval decisions of the session, oldest first. */
+  approvals: ApprovalRecord[]
+  /** The main agent's messages and tool calls; only built for the conversation format. */
+  transcript: TranscriptEntry[] | null
+  generatedAt: number
+}
+
+type ReportBlock =
+  | { type: 'heading'; level: 1 | 2 | 3 | 4; text: string }
+  | { type: 'text'; text: string }
+  | { type: 'code'; text: string }
+  | { type: 'list'; items: string[] }
+  | { type: 'table'; header: string[]; rows: string[][] }
+  | { type: 'meta'; text: string }
+  | { type: 'message'; role: 'user' | 'assistant'; time: string; markdown: string; images: ReportImage[]; attachments: string[] }
+  | { type: 'tools'; calls: ReportToolCall[] }
+
+export function isRunReportFormat(value: string): value is RunReportFormat {
+  return (RUN_REPORT_FORMATS as readonly string[]).includes(value)
+}
+
+/**
+ * Images stored by hash are embedded only when `attachments` is given;
+ * without it they are listed by name like other files.
+ */
+export async function buildRunReport(
+  runtime: Pick<RuntimeContext, 'repositories'> & Partial<Pick<RuntimeContext, 'attachments'>>,
+  userId: string,
+  sessionId: string,
+  format: RunReportFormat = 'markdown',
+): Promise<RunReport | null> {
+  const session = await getInspectorSession(runtime, userId, sessionId)
+  if (!session) return null
+  const approvals = await runtime.repositories.approvalHistory.list({ sessionId })
+  const transcript = format === 'conversation' ? await buildTranscript(runtime, sessionId) : null
+  return { session, approvals: approvals.sort((a, b) => a.decidedAt - b.decidedAt), transcript, generatedAt: Date.now() }
+}
+
+/**
+ * A self-contained account of how a conversation's result was produced:
+ * the requests, each agent's plan and tool calls (with what they returned
+ * and how approvals went), the final response and what it cost. The
+ * conversation format is the chat instead, with tool calls collapsed under
+ * their names and images inline, to email or post as is. Pass `redact` to
+ * strip secrets before the report leaves the machine; it never touches
+ * image data.
+ */
+export function renderRunReport(report: RunReport, format: RunReportFormat, redact: (text: string) => string = (text) => text): string {
+  if (format === 'conversation') {
+    if (!report.transcript) throw new Error('Build the report with the conversation format to render it')
+    return renderHtml(transcriptBlocks(report, report.transcript, redact), reportTitle(report))
+  }
+  const blocks = reportBlocks(report)
+  return redact(format === 'html' ? renderHtml(blocks, reportTitle(report)) : renderMarkdown(blocks))
+}
+
+export function reportFileName(report: RunReport, format: RunReportFormat): string {
+  const slug = reportTitle(report).toLowerCase().replace(/[^a-z0-9]+/g, '-').replace(/^-+|-+$/g, '').slice(0, 60) || 'conversation'
+  if (format === 'conversation') return `${slug}.html`
+  return `${slug}-report.${format === 'html' ? 'html' : 'md'}`
+}
+
+/** The main agent's side of the conversation; sub-agent work shows as the delegate calls that did it. */
+async function buildTranscript(
+  runtime: Pick<RuntimeContext, 'repositories'> & Partial<Pick<RuntimeContext, 'attachments'>>,
+  sessionId: string,
+): Promise<TranscriptEntry[]> {
+  const root = await runtime.repositories.agents.findRootAgent(sessionId)
+  const items = root ? await runtime.repositories.items.listByAgent(root.id) : []
+
+  const entries: TranscriptEntry[] = []
+  const calls = new Map<string, ReportToolCall>()
+  for (const item of items) {
+    if (item.type === 'message' && (item.role === 'user' || item.role === 'assistant')) {
+      const { images, attachments } = await blockContents(runtime, item.contentBlocks)
+      if (!item.content?.trim() && images.length === 0 && attachments.length === 0) continue
+      entries.push({ kind: 'message', role: item.role, text: item.content ?? '', images, attachments, createdAt: item.createdAt })
+    } else if (item.type === 'function_call') {
+      const call: ReportToolCall = { name: item.name ?? 'tool', arguments: formatArguments(item.arguments), output: null, isError: false, images: [] }
+      if (item.callId) calls.set(item.callId, call)
+      const last = entries.at(-1)
+      if (last?.kind === 'tools') last.calls.push(call)
+      else entries.push({ kind: 'tools', calls: [call] })
+    } else if (item.type === 'function_call_output' && item.callId) {
+      const call = calls.get(item.callId)
+      if (!call) continue
+      call.output = item.output ?? ''
+      call.isError = item.isError === true
+      call.images = (await blockContents(runtime, item.contentBlocks)).images
+    }
+  }
+  return entries
+}
+
+function reportTitle(report: RunReport): string {
+  return report.session.session.title?.trim() || 'Conversation'
+}
+
+function reportBlocks(report: RunReport): ReportBlock[] {
+  const { session, exchanges, agents, usage } = report.session
+  const root = agents.find((agent) => agent.parentId === null)
+  const approvalsByCall = new Map(report.approvals.map((approval) => [approval.callId, approval]))
+  const blocks: ReportBlock[] = [
+    { type: 'heading', level: 1, text: reportTitle(report) },
+    {
+      type: 'list',
+      items: [
+        `Conversation: ${session.id}`,
+        `Started: ${formatTime(session.createdAt)}`,
+        `Report generated: ${formatTime(report.generatedAt)}`,
+        `Status: ${root?.phase ?? 'no runs'}`,
+        `Models: ${[...new Set(agents.map((agent) => agent.model))].join(', ') || 'none'}`,
+        `Cost: ${formatUsage(usage)}`,
+      ],
+    },
+  ]
+
+  if (exchanges.length > 0) {
+    blocks.push({ type: 'heading', level: 2, text: 'Requests' })
+    for (const exchange of exchanges) {
+      blocks.push({ type: 'text', text: `${formatTime(exchange.createdAt)} (turns ${exchange.fromTurn}–${exchange.toTurn})` })
+      blocks.push({ type: 'code', text: exchange.content ?? '' })
+    }
+  }
+
+  blocks.push({ type: 'heading', level: 2, text: 'Steps' })
+  for (const agent of agents) blocks.push(...agentBlocks(agent, approvalsByCall))
+
+  if (report.approvals.length > 0) {
+    blocks.push({ type: 'heading', level: 2, text: 'Approvals' })
+    blocks.push({
+      type: 'table',
+      header: ['Decided', 'Tool', 'Outcome', 'Scope', 'Waited'],
+      rows: report.approvals.map((approval) => [
+        formatTime(approval.decidedAt),
+        approval.toolName,
+        approval.outcome + (approval.amendedArgs ? ' (edited)' : ''),
+        approval.scope ?? '',
+        formatDuration(approval.latencyMs),
+      ]),
+    })
+  }
+
+  blocks.push({ type: 'heading', level: 2, text: 'Final response' })
+  const finalResponse = root ? latestResponse(root) : null
+  blocks.push(finalResponse ? { type: 'code', text: finalResponse } : { type: 'text', text: root?.error ? `Failed: ${root.error}` : 'No response yet.' })
+
+  blocks.push({ type: 'heading', level: 2, text: 'Cost' })
+  blocks.push({
+    type: 'table',
+    header: ['Agent', 'Model', 'Requests', 'Input tokens', 'Output tokens', 'Cost (USD)'],
+    rows: [
+      ...agents.map((agent) => [
+        agentLabel(agent),
+        agent.model,
+        String(agent.usage.requests),
+        String(agent.usage.inputTokens),
+        String(agent.usage.outputTokens),
+        agent.usage.costUsd.toFixed(4),
+      ]),
+      ['Total', '', String(usage.requests), String(usage.inputTokens), String(usage.outputTokens), usage.costUsd.toFixed(4)],
+    ],
+  })
+  return blocks
+}
+
+function transcriptBlocks(report: RunReport, transcript: TranscriptEntry[], redact: (text: string) => string): ReportBlock[] {
+  const blocks: ReportBlock[] = [
+    { type: 'heading', level: 1, text: reportTitle(report) },
+    { type: 'meta', text: `Started ${formatTime(report.session.session.createdAt)} · exported ${formatTime(report.generatedAt)}` },
+  ]
+  for (const entry of transcript) {
+    if (entry.kind === 'tools') {
+      blocks.push({
+        type: 'tools',
+        calls: entry.calls.map((call) => ({
+          ...call,
+          arguments: redact(truncate(call.arguments, MAX_TRANSCRIPT_TOOL_CHARS)),
+          output: call.output === null ? null : redact(truncate(call.output, MAX_TRANSCRIPT_TOOL_CHARS)),
+        })),
+      })
+      continue
+    }
+    blocks.push({
+      type: 'message',
+      role: entry.role,
+      time: formatTime(entry.createdAt),
+      markdown: redact(entry.text),
+      images: entry.images,
+      attachments: entry.attachments,
+    })
+  }
+  if (transcript.length === 0) blocks.push({ type: 'meta', text: 'No messages yet.' })
+  return blocks
+}
+
+function agentBlocks(agent: InspectorAgent, approvalsByCall: Map<string, ApprovalRecord>): ReportBlock[] {
+  const blocks: ReportBlock[] = [
+    { type: 'heading', level: 3, text: agentLabel(agent) },
+    { type: 'list', items: [`Task: ${truncate(agent.task, 300)}`, `Model: ${agent.model}`, `Status: ${agent.phase}`, `Turns: ${agent.turnCount}`] },
+  ]
+  if (agent.plan) {
+    blocks.push({ type: 'text', text: `Plan: ${agent.plan.goal}` })
+    blocks.push({ type: 'list', items: agent.plan.steps.map(planStepLine) })
+  }
+  for (const turn of agent.turns) {
+    if (turn.steps.length === 0) continue
+    blocks.push({ type: 'heading', level: 4, text: `Turn ${turn.turn}` })
+    for (const step of turn.steps) blocks.push(...stepBlocks(step, step.callId ? approvalsByCall.get(step.callId) : undefined))
+  }
+  if (agent.error) blocks.push({ type: 'text', text: `Error: ${agent.error}` })
+  return blocks
+}
+
+function stepBlocks(step: InspectorStep, approval: ApprovalRecord | undefined): ReportBlock[] {
+  const facts = [
+    step.result === null ? 'pending' : step.isError ? 'failed' : 'ok',
+    step.durationMs !== null ? formatDuration(step.durationMs) : null,
+    approval ? `approval: ${approval.outcome}` : null,
+    step.childAgentId ? `delegated to ${step.childAgentId}` : null,
+  ].filter(Boolean)
+  const blocks: ReportBlock[] = [
+    { type: 'text', text: `${step.name ?? 'tool'} — ${facts.join(', ')}` },
+    { type: 'code', text: truncate(typeof step.arguments === 'string' ? step.arguments : JSON.stringify(step.arguments, null, 2) ?? '', MAX_PREVIEW_CHARS) },
+  ]
+  if (step.result !== null) blocks.push({ type: 'code', text: truncate(step.result, MAX_PREVIEW_CHARS) })
+  return blocks
+}
+
+function latestResponse(agent: InspectorAgent): string | null {
+  for (let i = agent.turns.length - 1; i >= 0; i--) {
+    if (agent.turns[i].response) return agent.turns[i].response
+  }
+  return agent.result
+}
+
+function agentLabel(agent: InspectorAgent): string {
+  return agent.parentId === null ? `Main agent (${agent.id})` : `Sub-agent at depth ${agent.depth} (${agent.id})`
+}
+
+function planStepLine(step: PlanStep): string {
+  return `[${step.status}] ${step.description}${step.result ? ` — ${truncate(step.result, 200)}` : ''}`
+}
+
+function renderMarkdown(blocks: ReportBlock[]): string {
+  return blocks.map((block) => {
+    switch (block.type) {
+      case 'heading':
+        return `${'#'.repeat(block.level)} ${block.text}`
+      case 'text':
+        return block.text
+      case 'code': {
+        // A fence longer than any backtick run inside, so the content cannot close it
+        const longest = Math.max(2, ...[...block.text.matchAll(/`+/g)].map((match) => match[0].length))
+        const fence = '`'.repeat(longest + 1)
+        return `${fence}\n${block.text}\n${fence}`
+      }
+      case 'list':
+        return block.items.map((item) => `- ${item}`).join('\n')
+      case 'table': {
+        const row = (cells: string[]) => `| ${cells.map((cell) => cell.replace(/\|/g, '\\|').replace(/\n/g, ' ')).join(' | ')} |`
+        return [row(block.header), row(block.header.map(() => '---')), ...block.rows.map(row)].join('\n')
+      }
+      case 'meta':
+        return `_${block.text}_`
+      case 'message':
+        return `**${block.role === 'user' ? 'You' : 'Assistant'}** · ${block.time}\n\n${block.markdown}`
+      case 'tools':
+        return block.calls.map((call) => `- ${call.name}${call.isError ? ' (failed)' : ''}`).join('\n')
+    }
+  }).join('\n\n') + '\n'
+}
+
+const HTML_STYLE = `body{font:15px/1.5 system-ui,sans-serif;max-width:960px;margin:2rem auto;padding:0 1rem;color:#1f2328;background:#fff}
+pre{background:#f6f8fa;padding:.75rem;border-radius:6px;overflow-x:auto;white-space:pre-wrap;word-break:break-word}
+table{border-collapse:collapse;margin:.5rem 0}th,td{border:1px solid #d0d7de;padding:.25rem .6rem;text-align:left}
+h3{border-top:1px solid #d0d7de;padding-top:1rem}.meta{color:#656d76;font-size:.8rem}
+.message{margin:1rem 0;padding:.75rem 1rem;border-radius:10px}.message h3{border-top:none;padding-top:0}
+.user{background:#ddf4ff;margin-left:3rem}.assistant{background:#f6f8fa;margin-right:3rem}.assistant pre{background:#eaeef2}
+.message>p:first-of-type{margin-top:0}.message>:last-child{margin-bottom:0}
+code{background:#eaeef2;padding:0 .25rem;border-radius:4px;font-size:.85em}pre code{background:none;padding:0}
+details{margin:.5rem 0;border:1px solid #d0d7de;border-radius:8px;padding:.4rem .8rem;font-size:.9rem}
+summary{cursor:pointer;color:#656d76}details .failed{color:#cf222e}
+img{max-width:100%;border-radius:6px;display:block;margin:.5rem 0}
+.file{display:inline-block;border:1px solid #d0d7de;border-radius:6px;padding:0 .4rem;margin-right:.3rem;font-size:.85rem}`
+
+function renderHtml(blocks: ReportBlock[], title: string): string {
+  const body = blocks.map((block) => {
+    switch (block.type) {
+      case 'heading':
+        return `<h${block.level}>${escapeHtml(block.text)}</h${block.level}>`
+      case 'text':
+        return `<p>${escapeHtml(block.text)}</p>`
+      case 'code':
+        return `<pre>${escapeHtml(block.text)}</pre>`
+      case 'list':
+        return `<ul>${block.items.map((item) => `<li>${escapeHtml(item)}</li>`).join('')}</ul>`
+      case 'table':
+        return `<table><thead><tr>${block.header.map((cell) => `<th>${escapeHtml(cell)}</th>`).join('')}</tr></thead><tbody>${
+          block.rows.map((row) => `<tr>${row.map((cell) => `<td>${escapeHtml(cell)}</td>`).join('')}</tr>`).join('')
+        }</tbody></table>`
+      case 'meta':
+        return `<p class="meta">${escapeHtml(block.text)}</p>`
+      case 'message': {
+        const attachments = block.attachments.map((name) => `<span class="file">${escapeHtml(name)}</span>`).join('')
+        return `<div class="message ${block.role}">
+<div class="meta">${block.role === 'user' ? 'You' : 'Assistant'} · ${escapeHtml(block.time)}</div>
+${markdownToHtml(block.markdown)}${attachments ? `<p>${attachments}</p>` : ''}${block.images.map(renderImage).join('')}
+</div>`
+      }
+      case 'tools':
+        return renderToolCalls(block.calls)
+    }
+  }).join('\n')
+  return `<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>${escapeHtml(title)}</title>
+<style>${HTML_STYLE}</style>
+</head>
+<body>
+${body}
+</body>
+</html>
+`
+}
+
+function renderToolCalls(calls: ReportToolCall[]): string {
+  const failed = calls.filter((call) => call.isError).length
+  const names = [...new Set(calls.map((call) => call.name))].join(', ')
+  const summary = `${calls.length === 1 ? 'Used' : `Ran ${calls.length} tool calls:`} ${escapeHtml(names)}${failed ? ` <span class="failed">(${failed} failed)</span>` : ''}`
+  const details = calls.map((call) => {
+    const output = call.output === null
+      ? '<p class="meta">No result</p>'
+      : `<pre${call.isError ? ' class="failed"' : ''}>${escapeHtml(call.output)}</pre>`
+    return `<p><strong>${escapeHtml(call.name)}</strong></p><pre>${escapeHtml(call.arguments)}</pre>${output}${call.images.map(renderImage).join('')}`
+  }).join('\n')
+  return `<details><summary>${summary}</summary>\n${details}\n</details>`
+}
+
+// The data comes from stored content blocks, so it is escaped like any other text
+function renderImage(image: ReportImage): string {
+  return `<img src="data:${escapeHtml(image.mediaType)};base64,${escapeHtml(image.data)}" alt="${escapeHtml(image.name ?? 'Image')}">`
+}
+
+/**
+ * Enough Markdown for chat replies: fenced code, headings, lists, quotes,
+ * emphasis, inline code and links. Text is escaped first; only http(s) and
+ * mailto links survive, and remote images become links so the file stays
+ * self-contained.
+ */
+export function markdownToHtml(text: string): string {
+  const lines = text.replace(/\r\n?/g, '\n').split('\n')
+  const html: string[] = []
+  let paragraph: string[] = []
+  let list: { tag: 'ul' | 'ol'; items: string[] } | null = null
+  const flush = () => {
+    if (paragraph.length) html.push(`<p>${paragraph.map(renderInline).join('<br>')}</p>`)
+    paragraph = []
+    if (list) html.push(`<${list.tag}>${list.items.map((item) => `<li>${renderInline(item)}</li>`).join('')}</${list.tag}>`)
+    list = null
+  }
+
+  for (let i = 0; i < lines.length; i++) {
+    const line = lines[i]
+    const fence = line.match(/^\s*(`{3,}|~{3,})/)
+    if (fence) {
+      flush()
+      const code: string[] = []
+      while (++i < lines.length && !lines[i].trimStart().startsWith(fence[1])) code.push(lines[i])
+      html.push(`<pre><code>${escapeHtml(code.join('\n'))}</code></pre>`)
+      continue
+    }
+    const heading = line.match(/^(#{1,6})\s+(.*)$/)
+    const bullet = line.match(/^\s*[-*+]\s+(.*)$/)
+    const numbered = line.match(/^\s*\d+[.)]\s+(.*)$/)
+    if (!line.trim()) {
+      flush()
+    } else if (heading) {
+      flush()
+      // The page title is the only h1
+      const level = Math.min(heading[1].length + 1, 6)
+      html.push(`<h${level}>${renderInline(heading[2])}</h${level}>`)
+    } else if (/^\s*([-*_])(\s*\1){2,}\s*$/.test(line)) {
+      flush()
+      html.push('<hr>')
+    } else if (line.startsWith('>')) {
+      flush()
+      html.push(`<blockquote>${renderInline(line.replace(/^>\s?/, ''))}</blockquote>`)
+    } else if (bullet || numbered) {
+      const tag = bullet ? 'ul' : 'ol'
+      if (paragraph.length || list?.tag !== tag) flush()
+      list ??= { tag, items: [] }
+      list.items.push((bullet ?? numbered)![1])
+    } else if (list && /^\s+\S/.test(line)) {
+      list.items[list.items.length - 1] += ` ${line.trim()}`
+    } else {
+      if (list) flush()
+      paragraph.push(line)
+    }
+  }
+  flush()
+  return html.join('\n')
+}
+
+function renderInline(text: string): string {
+  const codes: string[] = []
+  let html = escapeHtml(text).replace(/`([^`]+)`/g, (_, code: string) => {
+    codes.push(`<code>${code}</code>`)
+    return `\u0000${codes.length - 1}\u0000`
+  })
+  html = html
+    .replace(/!?\[([^\]]*)\]\(((?:https?:|mailto:)[^)\s]+)\)/g, (_, label: string, url: string) => `<a href="${url}">${label || url}</a>`)
+    .replace(/\*\*([^*]+)\*\*|__([^_]+)__/g, (_, a?: string, b?: string) => `<strong>${a ?? b}</strong>`)
+    .replace(/(^|[^*\w])\*([^*\s][^*]*)\*(?!\w)|(^|\W)_([^_\s][^_]*)_(?!\w)/g, (_, p1?: string, a?: string, p2?: string, b?: string) => `${p1 ?? p2}<em>${a ?? b}</em>`)
+    .replace(/~~([^~]+)~~/g, '<del>$1</del>')
+  return html.replace(/\u0000(\d+)\u0000/g, (_, index: string) => codes[Number(index)])
+}
+
+async function blockContents(
+  runtime: Partial<Pick<RuntimeContext, 'attachments'>>,
+  blocks: ItemContentBlock[] | null,
+): Promise<{ images: ReportImage[]; attachments: string[] }> {
+  const images: ReportImage[] = []
+  const attachments: string[] = []
+  for (const block of blocks ?? []) {
+    if (block.type === 'text') continue
+    if (block.type === 'image') {
+      const image = await loadImage(runtime, block)
+      if (image) {
+        images.push(image)
+        continue
+      }
+    }
+    attachments.push(block.name ?? `${block.type} attachment`)
+  }
+  return { images, attachments }
+}
+
+// Sized down the same way as for the models, which keeps the file small enough to send
+async function loadImage(runtime: Partial<Pick<RuntimeContext, 'attachments'>>, block: ItemContentBlock): Promise<ReportImage | null> {
+  if (block.data) return { mediaType: block.media_type ?? 'image/png', data: block.data, name: block.name ?? null }
+  if (!block.hash || !runtime.attachments) return null
+  const data = await runtime.attachments.get(block.hash)
+  if (!data) return null
+  const image = await runtime.attachments.forVision(block.hash, data, block.media_type)
+  return { mediaType: image.mediaType ?? 'image/png', data: image.data.toString('base64'), name: block.name ?? null }
+}
+
+function formatArguments(raw: Item['arguments']): string {
+  if (!raw) return ''
+  try {
+    return JSON.stringify(JSON.parse(raw), null, 2)
+  } catch {
+    return raw
+  }
+}
+
+function truncate(text: string, maxChars: number): string {
+  return text.length <= maxChars ? text : `${text.slice(0, maxChars)}… (${text.length - maxChars} more characters)`
+}
+
+function formatTime(ms: number): string {
+  return new Date(ms).toISOString().replace('T', ' ').replace(/\.\d+Z$/, ' UTC')
+}
+
+function formatDuration(ms: number): string {
+  return ms < 1000 ? `${ms} ms` : `${(ms / 1000).toFixed(1)} s`
+}
+
+function formatUsage(usage: InspectorUsage): string {
+  return `$${usage.costUsd.toFixed(4)} over ${usage.requests} model calls (${usage.inputTokens} input, ${usage.outputTokens} output tokens)`
+}
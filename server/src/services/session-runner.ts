@@ -1,6 +1,14 @@
+import { validateInlineBlocks } from '../lib/attachment-validation.js'
 import type { RuntimeContext } from '../lib/runtime.js'
 import type { AgentConfig, AgentResponseFormat, Item } from '../domain/types.js'
 import type { OrchestratorDeps } from '../orchestrator/types.js'
+import { prepareLargeAttachments } from './attachment-index.js'
+import { resolveImageBlocks } from './image-ocr.js'
+import { resolveDocumentBlocks } from './pdf-extraction.js'
+import { AUTO_MODEL, recordRoutingDecision, routeModel, type RoutingDecision } from './model-routing.js'
+import { PLACEHOLDER_TITLE } from './conversation-title.js'
+import { linkProjects, requireProjects } from './projects.js'
+import { resolveVideoBlocks } from './video-extraction.js'
 
 export interface PrepareSessionTurnInput {
   userId: string
@@ -12,8 +20,14 @@ export interface PrepareSessionTurnInput {
   systemPrompt?: string
   responseFormat?: AgentResponseFormat
   mcpServerIds?: string[]
+  /** Projects to link to the session for project.search. */
+  projects?: string[]
   allowedTools?: string[]
   maxTokens?: number
+  /** Send even when a blocking usage budget has been exceeded. */
+  overrideBudget?: boolean
+  /** Client-chosen key for this send; a retry with the same key returns the first attempt's turn. */
+  idempotencyKey?: string
 }
 
 export interface PreparedSessionTurn {
@@ -22,6 +36,12 @@ export interface PreparedSessionTurn {
   model: string
   status: 'prepared' | 'active'
   output?: Item[]
+  /** Set when the send repeated an idempotency key; nothing new was saved or started. */
+  duplicate?: boolean
+  /** How the model was picked when the turn was sent with model `auto`. */
+  routing?: RoutingDecision
+  /** The user message this send saved. */
+  messageId?: string
 }
 
 export function resolveProvider(runtime: RuntimeContext, model: string) {
@@ -46,8 +66,34 @@ export function buildDeps(runtime: RuntimeContext, model: string): OrchestratorD
     agentDefinitions: runtime.agentDefinitions,
     sessionFilesRoot: runtime.sessionFilesRoot,
     inlineOutputLimitBytes: runtime.inlineOutputLimitBytes,
+    inlineOutputBudget: runtime.inlineOutputBudget,
+    redactor: runtime.secrets,
+    privacy: runtime.privacy,
+    connectivity: runtime.connectivity,
+    verifier: runtime.verifier,
+    guardrails: runtime.guardrails,
+    prompts: runtime.experiments,
     interceptHandlers: runtime.interceptHandlers,
     observability: runtime.observability,
+    usage: runtime.usage,
+    spendCaps: runtime.budget,
+    traces: runtime.debugTraces,
+    audit: runtime.auditLog,
+    metrics: runtime.metrics,
+    approvals: runtime.approvalHistory,
+    approvalEscalation: {
+      maxCalls: runtime.config.approvalEscalationMaxCalls,
+      maxMutations: runtime.config.approvalEscalationMaxMutations,
+    },
+    attachments: runtime.attachments,
+    historyWindow: {
+      userTurns: runtime.config.historyWindowTurns,
+      attachmentUserTurns: runtime.config.attachmentHistoryTurns,
+    },
+    instructions: runtime.instructions,
+    toolPool: runtime.toolPool,
+    runScheduler: runtime.runScheduler,
+    agentAbortControllers: runtime.agentAbortControllers,
   }
 }
 
@@ -64,6 +110,38 @@ export function formatAssistantOutput(items: Item[]): Item[] {
 export async function prepareSessionTurn(
   runtime: RuntimeContext,
   body: PrepareSessionTurnInput,
+): Promise<PreparedSessionTurn> {
+  if (body.idempotencyKey === undefined) return prepareTurn(runtime, body)
+  const { turn, duplicate } = await runtime.turnKeys.dedupe(body.userId, body.idempotencyKey, () => prepareTurn(runtime, body))
+  if (!duplicate) return turn
+
+  // Report the turn as it stands now, so a retry after the run finished still gets its output
+  const agent = await runtime.repositories.agents.getById(turn.agent.id) ?? turn.agent
+  const items = await runtime.repositories.items.listByAgent(agent.id)
+  return { ...turn, agent, status: 'active', output: formatAssistantOutput(items), duplicate: true }
+}
+
+/** Throws ConversationBusyError when the conversation is still busy with an earlier send. */
+async function prepareTurn(
+  runtime: RuntimeContext,
+  body: PrepareSessionTurnInput,
+): Promise<PreparedSessionTurn> {
+  // A send that starts a conversation has nothing to collide with
+  const ticket = body.sessionId ? await runtime.conversationThrottle.admit(body.sessionId) : null
+  try {
+    const prepared = await prepareAdmittedTurn(runtime, body)
+    if (prepared.status === 'prepared') ticket?.started(prepared.agent.id, prepared.messageId ?? null)
+    else ticket?.release()
+    return prepared
+  } catch (err) {
+    ticket?.release()
+    throw err
+  }
+}
+
+async function prepareAdmittedTurn(
+  runtime: RuntimeContext,
+  body: PrepareSessionTurnInput,
 ): Promise<PreparedSessionTurn> {
   await runtime.agentDefinitions.reload()
 
@@ -75,7 +153,22 @@ export async function prepareSessionTurn(
     throw new Error(`Unknown agent: "${requestedAgent}". Available: ${available}`)
   }
 
-  const model = body.model ?? agentDef?.model ?? runtime.config.defaultModel
+  let model = body.model ?? agentDef?.model ?? runtime.config.defaultModel
+
+  if (!body.overrideBudget) {
+    await runtime.budget.assertWithinBudget(body.userId)
+  }
+
+  // Check inline payloads and resolve attached documents, images and videos first so bad input fails before anything is created
+  const inputBlocks = Array.isArray(body.input)
+    ? await Promise.all(body.input.map(async (item) => {
+      const inline = validateInlineBlocks(item.contentBlocks, { maxImageDimension: runtime.config.attachmentImageMaxDimension })
+      // Videos expand into frame images, which carry their own payload and skip OCR
+      const blocks = await resolveVideoBlocks(runtime, await resolveImageBlocks(runtime, await resolveDocumentBlocks(runtime, inline)))
+      return prepareLargeAttachments(runtime, blocks)
+    }))
+    : []
+  const projects = body.projects?.length ? await requireProjects(runtime, body.userId, body.projects) : []
 
   let sessionId = body.sessionId
   if (!sessionId) {
@@ -83,18 +176,35 @@ export async function prepareSessionTurn(
       userId: body.userId,
       title: typeof body.input === 'string'
         ? body.input.slice(0, 100)
-        : 'New conversation',
+        : PLACEHOLDER_TITLE,
     })
     sessionId = session.id
+    const variant = await runtime.experiments.enroll(sessionId, { modelPinned: body.model !== undefined })
+    if (variant?.model) model = variant.model
   } else {
     const existing = await runtime.repositories.sessions.getById(sessionId)
-    if (!existing) {
+    // A trashed session takes no new turns until it is restored
+    if (!existing || existing.deletedAt !== null) {
       throw new Error(`Session not found: ${sessionId}`)
     }
   }
+  if (projects.length) await linkProjects(runtime, sessionId, projects)
 
   let agent = await runtime.repositories.agents.findRootAgent(sessionId)
 
+  // A busy conversation takes no new turn, so there is nothing to route
+  const busy = agent?.status === 'running' || agent?.status === 'waiting'
+  const routed = model === AUTO_MODEL && !busy ? await routeModel(runtime, body.input) : null
+  if (routed) {
+    model = routed.decision.model
+    // Each routed message runs on its own tier's model
+    if (agent && agent.config.model !== model) {
+      agent = await runtime.repositories.agents.update(agent.id, {
+        config: { ...agent.config, model, provider: extractProviderName(model) },
+      })
+    }
+  }
+
   if (agent && agent.status === 'pending') {
     // Reuse a pre-created root agent, e.g. for forked sessions whose history
     // was copied before the next user turn was appended.
@@ -103,6 +213,7 @@ export async function prepareSessionTurn(
       status: 'running',
       result: null,
       error: null,
+      errorCode: null,
       completedAt: null,
     })
     agent = (await runtime.repositories.agents.getById(agent.id))!
@@ -132,6 +243,7 @@ export async function prepareSessionTurn(
         : {}),
       max_tool_calls_per_step: 10,
       tool_execution_timeout_ms: 60_000,
+      ...(agentDef?.step_timeout_ms ? { step_timeout_ms: agentDef.step_timeout_ms } : {}),
       response_format: normalizeResponseFormat(body.responseFormat),
       ...(body.systemPrompt
         ? { system_prompt: body.systemPrompt }
@@ -157,17 +269,19 @@ export async function prepareSessionTurn(
     })
   }
 
+  let messageId: string | undefined
   if (typeof body.input === 'string') {
-    await runtime.repositories.items.create({
+    const created = await runtime.repositories.items.create({
       agentId: agent.id,
       type: 'message',
       role: 'user',
       content: body.input,
       turnNumber: agent.turnCount,
     })
+    messageId = created.id
   } else if (Array.isArray(body.input)) {
-    for (const item of body.input) {
-      await runtime.repositories.items.create({
+    for (const [index, item] of body.input.entries()) {
+      const created = await runtime.repositories.items.create({
         agentId: agent.id,
         type: item.type ?? 'message',
         role: item.role ?? 'user',
@@ -177,8 +291,10 @@ export async function prepareSessionTurn(
         arguments: item.arguments ?? null,
         output: item.output ?? null,
         isError: item.isError ?? null,
+        contentBlocks: inputBlocks[index],
         turnNumber: agent.turnCount,
       })
+      if (created.type === 'message' && created.role === 'user') messageId = created.id
     }
   }
 
@@ -192,10 +308,17 @@ export async function prepareSessionTurn(
     })
   }
 
+  if (routed) {
+    await recordRoutingDecision(runtime.repositories.preferences, sessionId, routed.decision)
+    if (routed.usage) await runtime.usage.record({ agent, ...routed.usage, phase: 'routing' })
+  }
+
   return {
     agent,
     sessionId,
     model,
     status: 'prepared',
+    messageId,
+    ...(routed ? { routing: routed.decision } : {}),
   }
 }
@@ -0,0 +1,106 @@
+import { failAgent } from '../domain/agent.js'
+import type { Agent, AgentErrorCode } from '../domain/types.js'
+import { EVENT_TYPES } from '../events/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+import { StalledRunError } from '../orchestrator/errors.js'
+import { resolveApprovalTimeout } from './approval-timeouts.js'
+
+const SWEEP_INTERVAL_MS = 60 * 1000
+
+/** Provider calls have no timeout of their own, so a run is never called stuck sooner than this. */
+const MIN_RUN_IDLE_MS = 10 * 60 * 1000
+
+type WatchdogRuntime = Pick<RuntimeContext, 'config' | 'repositories' | 'events' | 'agentAbortControllers'>
+
+/**
+ * Periodically looks for agents whose state says they are busy but that have
+ * not moved in STUCK_RUN_MULTIPLE times their timeouts: a running agent whose
+ * turn never ends, or one still waiting on an approval nobody will answer
+ * (approval timers live in memory, so a restart drops them). Live runs are
+ * aborted and record their own failure; runs that ignore the abort, or whose
+ * worker is gone, are failed here. A multiple of 0 disables the watchdog.
+ */
+export class StuckRunWatchdog {
+  private timer: NodeJS.Timeout | null = null
+
+  constructor(private readonly runtime: WatchdogRuntime) {}
+
+  start(): void {
+    if (this.timer || this.runtime.config.stuckRunMultiple <= 0) return
+    this.timer = setInterval(() => {
+      void this.sweep()
+    }, SWEEP_INTERVAL_MS)
+    this.timer.unref()
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer)
+      this.timer = null
+    }
+  }
+
+  /** Stops every agent past its limit; returns how many it acted on. */
+  async sweep(now = Date.now()): Promise<number> {
+    const multiple = this.runtime.config.stuckRunMultiple
+    if (multiple <= 0) return 0
+    let stopped = 0
+    try {
+      for (const agent of await this.runtime.repositories.agents.listRunningOrWaiting()) {
+        const idleMs = now - agent.updatedAt
+        if (agent.status === 'running') {
+          if (idleMs <= runLimitMs(agent, multiple)) continue
+          await this.stopRun(agent, idleMs)
+          stopped++
+        } else if (agent.waitingFor.length > 0 && agent.waitingFor.every((w) => w.type === 'approval')) {
+          const { timeoutMs } = await resolveApprovalTimeout(
+            this.runtime.repositories.preferences,
+            agent.sessionId,
+            this.runtime.config.approvalTimeoutMs,
+          )
+          if (timeoutMs <= 0 || idleMs <= timeoutMs * multiple) continue
+          await this.fail(agent, `Approval went unanswered for ${Math.round(idleMs / 1000)}s`, 'approval_expired')
+          stopped++
+        }
+      }
+      if (stopped > 0) logger.warn({ stopped }, 'Watchdog stopped stuck agents')
+    } catch (err) {
+      logger.warn({ err }, 'Stuck-run sweep failed')
+    }
+    return stopped
+  }
+
+  private async stopRun(agent: Agent, idleMs: number): Promise<void> {
+    const controller = this.runtime.agentAbortControllers.get(agent.id)
+    if (controller && !controller.signal.aborted) {
+      controller.abort(new StalledRunError(idleMs))
+      return
+    }
+    this.runtime.agentAbortControllers.delete(agent.id)
+    await this.fail(agent, new StalledRunError(idleMs).message, 'stalled')
+  }
+
+  private async fail(agent: Agent, error: string, errorCode: AgentErrorCode): Promise<void> {
+    const failed = failAgent(agent, error, errorCode)
+    await this.runtime.repositories.agents.update(agent.id, {
+      status: 'failed',
+      error,
+      errorCode,
+      completedAt: failed.completedAt,
+    })
+    this.runtime.events.emit({
+      type: EVENT_TYPES.AGENT_FAILED,
+      agent_id: agent.id,
+      session_id: agent.sessionId,
+      payload: { error, errorCode, parentId: agent.parentId, depth: agent.depth },
+      timestamp: Date.now(),
+    })
+  }
+}
+
+/** A turn may run its tools up to the step budget, or one tool timeout when no budget is set. */
+function runLimitMs(agent: Agent, multiple: number): number {
+  const stepMs = agent.config.step_timeout_ms ?? agent.config.tool_execution_timeout_ms
+  return Math.max(MIN_RUN_IDLE_MS, stepMs * multiple)
+}
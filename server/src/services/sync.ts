@@ -0,0 +1,253 @@
+import fs from 'fs/promises'
+import path from 'path'
+import { randomUUID } from 'node:crypto'
+import { logger } from '../lib/logger.js'
+import type {
+  PreferenceRepository,
+  SyncRecord,
+  SyncRecordKind,
+  SyncRepository,
+  UserRepository,
+} from '../repositories/types.js'
+
+const DEVICE_ID_KEY = 'sync:device_id'
+const EXPORT_CURSOR_KEY = 'sync:export_cursor'
+const PEER_OFFSET_PREFIX = 'sync:peer_offset:'
+const LOG_EXTENSION = '.jsonl'
+
+export interface SyncEngineOptions {
+  repository: SyncRepository
+  preferences: PreferenceRepository
+  users: UserRepository
+  /** Shared folder (Dropbox, iCloud Drive, Syncthing, ...). */
+  dir: string
+  /** Stable id for this device; generated and persisted when omitted. */
+  deviceId?: string
+  intervalMs?: number
+}
+
+export interface SyncLogEntry extends SyncRecord {
+  device: string
+}
+
+export interface SyncRunResult {
+  exported: number
+  imported: number
+  skipped: number
+}
+
+/**
+ * File-based sync between devices. Each device appends its local changes to
+ * `<dir>/<deviceId>.jsonl` and tails every other device's log, applying
+ * records with last-writer-wins per row. Logs are never rewritten, so the
+ * file-sync provider only ever sees appends and cannot produce conflicts.
+ */
+export class SyncEngine {
+  private running = false
+  private processing = false
+  private timer: NodeJS.Timeout | null = null
+  private deviceId: string | null
+  private readonly intervalMs: number
+  private lastRun: ({ at: number } & SyncRunResult) | null = null
+  private lastError: string | null = null
+
+  constructor(private readonly options: SyncEngineOptions) {
+    this.deviceId = options.deviceId ?? null
+    this.intervalMs = options.intervalMs ?? 60_000
+  }
+
+  async start(): Promise<void> {
+    if (this.running) return
+    this.running = true
+    this.schedule(0)
+  }
+
+  stop(): void {
+    this.running = false
+    if (this.timer) {
+      clearTimeout(this.timer)
+      this.timer = null
+    }
+  }
+
+  status(): {
+    dir: string
+    deviceId: string | null
+    running: boolean
+    lastRun: ({ at: number } & SyncRunResult) | null
+    lastError: string | null
+  } {
+    return {
+      dir: this.options.dir,
+      deviceId: this.deviceId,
+      running: this.running,
+      lastRun: this.lastRun,
+      lastError: this.lastError,
+    }
+  }
+
+  /** Hard deletes leave no row behind to export, so callers log a tombstone. */
+  async recordDeletion(kind: SyncRecordKind, id: string): Promise<void> {
+    const deviceId = await this.resolveDeviceId()
+    await fs.mkdir(this.options.dir, { recursive: true })
+    await this.append(deviceId, [{ kind, id, updatedAt: Date.now(), deleted: true, data: null, device: deviceId }])
+  }
+
+  async runOnce(): Promise<SyncRunResult> {
+    await fs.mkdir(this.options.dir, { recursive: true })
+    const deviceId = await this.resolveDeviceId()
+    const exported = await this.exportChanges(deviceId)
+    const { imported, skipped } = await this.importPeers(deviceId)
+    return { exported, imported, skipped }
+  }
+
+  private schedule(delayMs: number): void {
+    if (this.timer) clearTimeout(this.timer)
+    this.timer = setTimeout(() => {
+      void this.tick()
+    }, delayMs)
+  }
+
+  private async tick(): Promise<void> {
+    if (!this.running || this.processing) return
+    this.processing = true
+    try {
+      const result = await this.runOnce()
+      this.lastRun = { at: Date.now(), ...result }
+      this.lastError = null
+      if (result.exported > 0 || result.imported > 0) {
+        logger.info(result, 'Sync completed')
+      }
+    } catch (err) {
+      this.lastError = err instanceof Error ? err.message : String(err)
+      logger.warn({ err }, 'Sync failed')
+    } finally {
+      this.processing = false
+      if (this.running) this.schedule(this.intervalMs)
+    }
+  }
+
+  private async resolveDeviceId(): Promise<string> {
+    if (this.deviceId) return this.deviceId
+    const stored = await this.options.preferences.get(DEVICE_ID_KEY)
+    if (stored) {
+      this.deviceId = stored
+      return stored
+    }
+    const generated = randomUUID()
+    await this.options.preferences.set(DEVICE_ID_KEY, generated)
+    this.deviceId = generated
+    return generated
+  }
+
+  private async exportChanges(deviceId: string): Promise<number> {
+    const cursor = parseExportCursor(await this.options.preferences.get(EXPORT_CURSOR_KEY))
+    // The cursor is inclusive, so rows written later in its last millisecond
+    // are still found; the ones already sent at that clock are left out by id.
+    const sent = new Set(cursor.ids)
+    const records = await this.options.repository.listChangesSince(cursor.at)
+    const fresh = records.filter((record) => !(record.updatedAt === cursor.at && sent.has(recordKey(record))))
+    if (fresh.length > 0) {
+      await this.append(deviceId, fresh.map((record) => ({ ...record, device: deviceId })))
+    }
+    // Clocks ahead of ours (a peer's skewed rows) do not move the cursor, or local writes behind them would be missed
+    const now = Date.now()
+    const at = records.reduce((latest, record) => record.updatedAt <= now ? Math.max(latest, record.updatedAt) : latest, cursor.at)
+    const next: ExportCursor = {
+      at,
+      ids: records.filter((record) => record.updatedAt === at).map(recordKey),
+    }
+    await this.options.preferences.set(EXPORT_CURSOR_KEY, JSON.stringify(next))
+    return fresh.length
+  }
+
+  private async importPeers(deviceId: string): Promise<{ imported: number; skipped: number }> {
+    const localUserId = (await this.options.users.list())[0]?.id
+    if (!localUserId) return { imported: 0, skipped: 0 }
+
+    let imported = 0
+    let skipped = 0
+    const entries = await fs.readdir(this.options.dir)
+    for (const file of entries.sort()) {
+      if (!file.endsWith(LOG_EXTENSION)) continue
+      const peer = file.slice(0, -LOG_EXTENSION.length)
+      if (peer === deviceId) continue
+
+      const offsetKey = `${PEER_OFFSET_PREFIX}${peer}`
+      const offset = Number(await this.options.preferences.get(offsetKey) ?? 0)
+      const { entries: records, nextOffset } = await readLogFrom(path.join(this.options.dir, file), offset)
+
+      for (const record of records) {
+        try {
+          if (await this.options.repository.applyRecord(record, localUserId)) {
+            imported++
+          } else {
+            skipped++
+          }
+        } catch (err) {
+          skipped++
+          logger.warn({ err, peer, kind: record.kind, id: record.id }, 'Failed to apply sync record')
+        }
+      }
+
+      if (nextOffset !== offset) {
+        await this.options.preferences.set(offsetKey, String(nextOffset))
+      }
+    }
+    return { imported, skipped }
+  }
+
+  private async append(deviceId: string, entries: SyncLogEntry[]): Promise<void> {
+    const body = entries.map((entry) => JSON.stringify(entry)).join('\n') + '\n'
+    await fs.appendFile(path.join(this.options.dir, `${deviceId}${LOG_EXTENSION}`), body, 'utf-8')
+  }
+}
+
+/** Clock of the newest exported row, and the rows exported at exactly that clock. */
+interface ExportCursor {
+  at: number
+  ids: string[]
+}
+
+function recordKey(record: SyncRecord): string {
+  return `${record.kind}:${record.id}`
+}
+
+/** Earlier versions stored a bare timestamp; rows at it are re-sent once, which peers ignore. */
+function parseExportCursor(raw: string | null): ExportCursor {
+  if (!raw) return { at: 0, ids: [] }
+  try {
+    const parsed = JSON.parse(raw) as ExportCursor | number
+    if (typeof parsed === 'number') return { at: parsed, ids: [] }
+    return { at: Number(parsed.at) || 0, ids: Array.isArray(parsed.ids) ? parsed.ids.map(String) : [] }
+  } catch {
+    return { at: 0, ids: [] }
+  }
+}
+
+/**
+ * Read complete lines after `offset`. A trailing partial line (the peer's
+ * sync client may still be uploading) is left for the next pass.
+ */
+export async function readLogFrom(
+  filePath: string,
+  offset: number,
+): Promise<{ entries: SyncLogEntry[]; nextOffset: number }> {
+  const buffer = await fs.readFile(filePath)
+  if (buffer.length <= offset) return { entries: [], nextOffset: offset }
+
+  const lastNewline = buffer.lastIndexOf(0x0a)
+  if (lastNewline < offset) return { entries: [], nextOffset: offset }
+
+  const chunk = buffer.subarray(offset, lastNewline + 1).toString('utf-8')
+  const entries: SyncLogEntry[] = []
+  for (const line of chunk.split('\n')) {
+    if (!line.trim()) continue
+    try {
+      entries.push(JSON.parse(line) as SyncLogEntry)
+    } catch {
+      logger.warn({ filePath }, 'Skipping malformed sync log line')
+    }
+  }
+  return { entries, nextOffset: lastNewline + 1 }
+}
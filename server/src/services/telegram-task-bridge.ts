@@ -4,6 +4,7 @@ import { decrypt, deriveKey } from '../lib/crypto.js'
 import { logger } from '../lib/logger.js'
 import { listTasks, updateTask } from '../tasks/storage.js'
 import { EVENT_TYPES } from '../events/types.js'
+import { UNPACED } from '../events/emitter.js'
 import { formatTelegramHtml, splitTelegramText, telegramHtmlToPlainText } from './telegram-format.js'
 
 interface TelegramTaskBridgeOptions {
@@ -89,7 +90,7 @@ export class TelegramTaskBridge {
     try {
       for await (const event of this.runtime.events.subscribe({
         types: [EVENT_TYPES.TASK_COMPLETED, EVENT_TYPES.TASK_METADATA_UPDATED],
-      })) {
+      }, UNPACED)) {
         if (signal.aborted || !this.running) break
         const taskId = typeof event.payload === 'object' && event.payload && 'taskId' in event.payload
           ? String(event.payload.taskId)
@@ -2,6 +2,7 @@ import { randomBytes, randomUUID } from 'node:crypto'
 import type { RuntimeContext } from '../lib/runtime.js'
 import { decrypt, deriveKey, encrypt } from '../lib/crypto.js'
 import { buildDeps, prepareSessionTurn } from './session-runner.js'
+import { ConversationBusyError } from './conversation-throttle.js'
 import {
   isSupportedAudioFile,
   MAX_AUDIO_BYTES,
@@ -536,12 +537,16 @@ export class TelegramService {
       }
     }
 
+    // A conversation still busy with an earlier message gets the same answer as a running one
     const prepared = await prepareSessionTurn(this.runtime, {
       userId: connection.userId,
       sessionId: resolution.sessionId,
       agent: 'planner',
       input: content,
       responseFormat: 'telegram_html',
+    }).catch((err) => {
+      if (err instanceof ConversationBusyError) return { status: 'active' as const, sessionId: err.busy.sessionId }
+      throw err
     })
 
     if (prepared.status === 'active') {
@@ -0,0 +1,246 @@
+import { spawn, type ChildProcessWithoutNullStreams } from 'child_process'
+import { randomBytes } from 'crypto'
+import { existsSync, statSync } from 'fs'
+import { logger } from '../lib/logger.js'
+
+/** Keeps a conversation from accumulating shells it forgot about. */
+export const MAX_TERMINALS_PER_SESSION = 4
+const IDLE_TIMEOUT_MS = 30 * 60_000
+/** Unread output beyond this is dropped from the front. */
+const MAX_BUFFER_CHARS = 256 * 1024
+/** eval survives syntax errors in bash; dash exits on them, so bash is preferred when present. */
+const SHELL = existsSync('/bin/bash') ? '/bin/bash' : '/bin/sh'
+const SHELL_ARGS = SHELL === '/bin/bash' ? ['--noprofile', '--norc'] : []
+
+export class TerminalError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'TerminalError'
+  }
+}
+
+export interface TerminalInfo {
+  id: string
+  /** Directory the last finished command left the shell in. */
+  cwd: string
+  /** The command still running, if any. */
+  running: string | null
+  openedAt: number
+}
+
+export interface TerminalOutput {
+  /** Output since the last run or read; stdout and stderr interleaved. */
+  output: string
+  /** True while the command is still running; read again for the rest. */
+  running: boolean
+  /** Set once the command has finished. */
+  exit_code?: number
+  cwd: string
+  /** Output was dropped because nobody read it in time. */
+  dropped?: boolean
+}
+
+interface Terminal {
+  id: string
+  sessionId: string
+  process: ChildProcessWithoutNullStreams
+  cwd: string
+  openedAt: number
+  buffer: string
+  dropped: boolean
+  command: { text: string; marker: string; exitCode: number | null } | null
+  closed: boolean
+  /** Wakes readers waiting for output or completion. */
+  waiters: Set<() => void>
+  idleTimer: NodeJS.Timeout | null
+}
+
+/**
+ * Long-lived shells for terminal.* tools, one set per conversation. State
+ * such as the working directory, exported variables and activated
+ * environments carries over between commands. Each command runs with its
+ * stdin closed and is followed by a marker line carrying its exit status
+ * and the new working directory, which is how completion is detected.
+ */
+export class TerminalManager {
+  private readonly terminals = new Map<string, Terminal>()
+  private nextId = 1
+
+  open(sessionId: string, cwd?: string): TerminalInfo {
+    if (this.list(sessionId).length >= MAX_TERMINALS_PER_SESSION) {
+      throw new TerminalError(`At most ${MAX_TERMINALS_PER_SESSION} terminals per conversation; close one first`)
+    }
+    const dir = cwd ?? process.cwd()
+    if (!statSync(dir, { throwIfNoEntry: false })?.isDirectory()) throw new TerminalError(`Not a directory: ${dir}`)
+
+    const child = spawn(SHELL, SHELL_ARGS, {
+      cwd: dir,
+      env: { ...process.env, TERM: 'dumb', PAGER: 'cat', GIT_PAGER: 'cat' },
+      stdio: ['pipe', 'pipe', 'pipe'],
+    })
+    const terminal: Terminal = {
+      id: `t${this.nextId++}`,
+      sessionId,
+      process: child,
+      cwd: dir,
+      openedAt: Date.now(),
+      buffer: '',
+      dropped: false,
+      command: null,
+      closed: false,
+      waiters: new Set(),
+      idleTimer: null,
+    }
+    child.stdout.setEncoding('utf-8').on('data', (chunk: string) => this.append(terminal, chunk))
+    child.stderr.setEncoding('utf-8').on('data', (chunk: string) => this.append(terminal, chunk))
+    child.on('error', (err) => {
+      this.append(terminal, `\n[terminal error: ${err.message}]\n`)
+      this.markClosed(terminal, null)
+    })
+    child.on('close', (code) => this.markClosed(terminal, code))
+    // Writing to a shell that already exited is reported by run instead
+    child.stdin.on('error', () => {})
+    this.terminals.set(terminal.id, terminal)
+    this.touch(terminal)
+    return this.info(terminal)
+  }
+
+  list(sessionId: string): TerminalInfo[] {
+    return [...this.terminals.values()]
+      .filter((terminal) => terminal.sessionId === sessionId && !terminal.closed)
+      .map((terminal) => this.info(terminal))
+  }
+
+  /**
+   * Starts a command and waits up to `waitMs` for it to finish. A command
+   * that takes longer keeps running; its output is collected for `read`.
+   */
+  async run(sessionId: string, id: string, command: string, waitMs: number, signal?: AbortSignal): Promise<TerminalOutput> {
+    const terminal = this.get(sessionId, id)
+    if (terminal.closed) throw new TerminalError(`Terminal ${id} has exited; open a new one`)
+    if (terminal.command && terminal.command.exitCode === null) {
+      throw new TerminalError(`Terminal ${id} is still running "${terminal.command.text}"; read its output or close it`)
+    }
+    const marker = `__terminal_done_${randomBytes(8).toString('hex')}`
+    terminal.command = { text: command, marker, exitCode: null }
+    terminal.buffer = ''
+    terminal.dropped = false
+    // Quoting the whole command keeps a syntax error inside eval instead of breaking the shell's input;
+    // stderr shares the stdout pipe so it cannot arrive after the marker
+    const quoted = `'${command.replace(/'/g, `'\\''`)}'`
+    terminal.process.stdin.write(`eval ${quoted} < /dev/null 2>&1\nprintf '\\n%s %s %s\\n' '${marker}' "$?" "$PWD"\n`)
+    this.touch(terminal)
+    return this.collect(terminal, waitMs, signal)
+  }
+
+  /** Output produced since the last run or read, waiting up to `waitMs` for the command to finish. */
+  async read(sessionId: string, id: string, waitMs: number, signal?: AbortSignal): Promise<TerminalOutput> {
+    const terminal = this.get(sessionId, id)
+    this.touch(terminal)
+    return this.collect(terminal, waitMs, signal)
+  }
+
+  close(sessionId: string, id: string): boolean {
+    const terminal = this.terminals.get(id)
+    if (!terminal || terminal.sessionId !== sessionId) return false
+    this.kill(terminal)
+    return true
+  }
+
+  closeAll(): void {
+    for (const terminal of this.terminals.values()) this.kill(terminal)
+  }
+
+  private get(sessionId: string, id: string): Terminal {
+    const terminal = this.terminals.get(id)
+    if (!terminal || terminal.sessionId !== sessionId) throw new TerminalError(`No open terminal ${id}; open one with terminal.open`)
+    return terminal
+  }
+
+  private async collect(terminal: Terminal, waitMs: number, signal?: AbortSignal): Promise<TerminalOutput> {
+    const finished = () => terminal.closed || terminal.command === null || terminal.command.exitCode !== null
+    if (!finished() && waitMs > 0 && !signal?.aborted) {
+      await new Promise<void>((resolve) => {
+        const done = () => {
+          clearTimeout(timer)
+          signal?.removeEventListener('abort', done)
+          terminal.waiters.delete(check)
+          resolve()
+        }
+        const check = () => {
+          if (finished()) done()
+        }
+        const timer = setTimeout(done, waitMs)
+        signal?.addEventListener('abort', done, { once: true })
+        terminal.waiters.add(check)
+      })
+    }
+    const running = !finished()
+    // While running, an unfinished last line stays behind: it may be the start of the marker
+    const cut = running ? terminal.buffer.lastIndexOf('\n') + 1 : terminal.buffer.length
+    const output = terminal.buffer.slice(0, cut)
+    const dropped = terminal.dropped
+    terminal.buffer = terminal.buffer.slice(cut)
+    terminal.dropped = false
+    return {
+      output,
+      running,
+      ...(!running && terminal.command?.exitCode != null && { exit_code: terminal.command.exitCode }),
+      cwd: terminal.cwd,
+      ...(dropped && { dropped: true }),
+    }
+  }
+
+  private append(terminal: Terminal, chunk: string): void {
+    terminal.buffer += chunk
+    const command = terminal.command
+    if (command && command.exitCode === null) {
+      const match = new RegExp(`\\n?${command.marker} (\\d+) (.*)\\n`).exec(terminal.buffer)
+      if (match) {
+        command.exitCode = Number(match[1])
+        terminal.cwd = match[2]
+        terminal.buffer = terminal.buffer.slice(0, match.index) + terminal.buffer.slice(match.index + match[0].length)
+      }
+    }
+    if (terminal.buffer.length > MAX_BUFFER_CHARS) {
+      // Nobody read it in time; keep the newest output
+      terminal.buffer = terminal.buffer.slice(-MAX_BUFFER_CHARS)
+      terminal.dropped = true
+    }
+    for (const waiter of [...terminal.waiters]) waiter()
+  }
+
+  private markClosed(terminal: Terminal, code: number | null): void {
+    if (terminal.closed) return
+    terminal.closed = true
+    if (terminal.command && terminal.command.exitCode === null) terminal.command.exitCode = code ?? -1
+    if (terminal.idleTimer) clearTimeout(terminal.idleTimer)
+    // The last output stays readable until the terminal is looked up again
+    setTimeout(() => this.terminals.delete(terminal.id), 60_000).unref()
+    for (const waiter of [...terminal.waiters]) waiter()
+  }
+
+  private kill(terminal: Terminal): void {
+    this.terminals.delete(terminal.id)
+    if (terminal.idleTimer) clearTimeout(terminal.idleTimer)
+    if (!terminal.closed) terminal.process.kill('SIGKILL')
+  }
+
+  private touch(terminal: Terminal): void {
+    if (terminal.idleTimer) clearTimeout(terminal.idleTimer)
+    terminal.idleTimer = setTimeout(() => {
+      logger.info({ terminalId: terminal.id, sessionId: terminal.sessionId }, 'Closing idle terminal')
+      this.kill(terminal)
+    }, IDLE_TIMEOUT_MS)
+    terminal.idleTimer.unref()
+  }
+
+  private info(terminal: Terminal): TerminalInfo {
+    return {
+      id: terminal.id,
+      cwd: terminal.cwd,
+      running: terminal.command && terminal.command.exitCode === null ? terminal.command.text : null,
+      openedAt: terminal.openedAt,
+    }
+  }
+}
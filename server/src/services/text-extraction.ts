@@ -0,0 +1,39 @@
+import { MAX_ATTACHMENT_BYTES } from '../lib/attachment-validation.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { chunkText } from '../lib/pdf-text.js'
+import type { PdfExtraction } from './pdf-extraction.js'
+
+export const MAX_TEXT_BYTES = MAX_ATTACHMENT_BYTES.text
+
+export class TextInputError extends Error {}
+
+export interface TextExtractionInput {
+  bytes: ArrayBuffer | Uint8Array
+  fileName?: string
+}
+
+/**
+ * Stores a UTF-8 text attachment and its chunks, in the same shape as a PDF
+ * extraction so both travel as `document` blocks.
+ */
+export async function extractTextFile(runtime: Pick<RuntimeContext, 'attachments'>, input: TextExtractionInput): Promise<PdfExtraction> {
+  const data = Buffer.from(input.bytes instanceof ArrayBuffer ? new Uint8Array(input.bytes) : input.bytes)
+  if (data.length === 0) throw new TextInputError('Text file is empty')
+  if (data.length > MAX_TEXT_BYTES) throw new TextInputError(`Text file is too large (${data.length} bytes)`)
+
+  let text: string
+  try {
+    text = new TextDecoder('utf-8', { fatal: true }).decode(data)
+  } catch {
+    throw new TextInputError(`Not a UTF-8 text file${input.fileName ? `: ${input.fileName}` : ''}`)
+  }
+  if (!text.trim()) throw new TextInputError('Text file is empty')
+
+  const hash = await runtime.attachments.put(data)
+  const stored = await runtime.attachments.getExtraction<PdfExtraction>(hash)
+  if (stored) return stored
+
+  const extraction: PdfExtraction = { hash, bytes: data.length, pages: 1, source: 'text', mediaType: 'text/plain', chunks: chunkText(text) }
+  await runtime.attachments.putExtraction(hash, extraction)
+  return extraction
+}
@@ -0,0 +1,234 @@
+import { runOsaScript, type OsaScriptRunner } from '../lib/osascript.js'
+
+const TODOIST_API_URL = 'https://api.todoist.com/api/v1'
+const DEFAULT_LIMIT = 30
+const MAX_LIMIT = 200
+
+export type TodoBackendName = 'todoist' | 'reminders'
+
+export interface TodoItem {
+  id: string
+  title: string
+  notes: string
+  /** Todoist's due date or datetime, or an ISO timestamp from Reminders. */
+  due: string | null
+  /** 1 (normal) to 4 (urgent), as in Todoist. */
+  priority: number
+  project: string | null
+  url: string | null
+}
+
+export interface CreateTodoInput {
+  title: string
+  notes?: string
+  /** Todoist takes natural language ("tomorrow 5pm"); Reminders needs an ISO date. */
+  due?: string
+  priority?: number
+  /** Todoist project or Reminders list name; the inbox/default list when omitted. */
+  project?: string
+}
+
+/** The user's personal task manager behind the todos.* tools. */
+export interface TodoBackend {
+  readonly name: TodoBackendName
+  list(options: { project?: string; filter?: string; limit?: number }, signal?: AbortSignal): Promise<TodoItem[]>
+  create(input: CreateTodoInput, signal?: AbortSignal): Promise<TodoItem>
+  complete(id: string, signal?: AbortSignal): Promise<{ id: string; title: string | null }>
+}
+
+export class TodoBackendError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'TodoBackendError'
+  }
+}
+
+/** Todoist's unified API v1 with a personal API token. */
+export class TodoistBackend implements TodoBackend {
+  readonly name = 'todoist' as const
+  private projects: Map<string, string> | null = null
+
+  constructor(
+    private readonly token: string,
+    private readonly fetchImpl: typeof fetch = fetch,
+  ) {}
+
+  async list(options: { project?: string; filter?: string; limit?: number }, signal?: AbortSignal): Promise<TodoItem[]> {
+    const limit = clampLimit(options.limit)
+    const projects = await this.projectNames(signal)
+    const filter = options.filter?.trim()
+    const query = [filter ? `(${filter})` : '', options.project ? `#${options.project.trim()}` : ''].filter(Boolean).join(' & ')
+    const items: RawTodoistTask[] = []
+    let cursor: string | null = null
+    do {
+      const params = new URLSearchParams({ limit: String(Math.min(limit, 200)), ...(query && { query }), ...(cursor && { cursor }) })
+      const page: { results: RawTodoistTask[]; next_cursor: string | null } =
+        await this.request('GET', `${query ? '/tasks/filter' : '/tasks'}?${params}`, undefined, signal)
+      items.push(...page.results)
+      cursor = page.next_cursor
+    } while (cursor && items.length < limit)
+    return items.slice(0, limit).map((task) => toTodoistItem(task, projects))
+  }
+
+  async create(input: CreateTodoInput, signal?: AbortSignal): Promise<TodoItem> {
+    const projects = await this.projectNames(signal)
+    let projectId: string | undefined
+    if (input.project) {
+      projectId = [...projects].find(([, name]) => name.toLowerCase() === input.project!.toLowerCase())?.[0]
+      if (!projectId) throw new TodoBackendError(`Todoist project not found: ${input.project}`)
+    }
+    const task = await this.request<RawTodoistTask>('POST', '/tasks', {
+      content: input.title,
+      ...(input.notes && { description: input.notes }),
+      ...(input.due && { due_string: input.due }),
+      ...(input.priority && { priority: input.priority }),
+      ...(projectId && { project_id: projectId }),
+    }, signal)
+    return toTodoistItem(task, projects)
+  }
+
+  async complete(id: string, signal?: AbortSignal): Promise<{ id: string; title: string | null }> {
+    const task = await this.request<RawTodoistTask>('GET', `/tasks/${encodeURIComponent(id)}`, undefined, signal)
+    await this.request('POST', `/tasks/${encodeURIComponent(id)}/close`, undefined, signal)
+    return { id, title: task.content }
+  }
+
+  /** Project IDs to names, loaded once; the user rarely adds projects mid-session. */
+  private async projectNames(signal?: AbortSignal): Promise<Map<string, string>> {
+    if (!this.projects) {
+      const page = await this.request<{ results: Array<{ id: string; name: string }> }>('GET', '/projects?limit=200', undefined, signal)
+      this.projects = new Map(page.results.map((project) => [project.id, project.name]))
+    }
+    return this.projects
+  }
+
+  private async request<T>(method: string, path: string, body: unknown, signal?: AbortSignal): Promise<T> {
+    const response = await this.fetchImpl(`${TODOIST_API_URL}${path}`, {
+      method,
+      headers: {
+        Authorization: `Bearer ${this.token}`,
+        ...(body !== undefined && { 'Content-Type': 'application/json' }),
+      },
+      body: body !== undefined ? JSON.stringify(body) : undefined,
+      signal,
+    })
+    if (!response.ok) {
+      const detail = await response.text().catch(() => '')
+      if (response.status === 404) throw new TodoBackendError(`Todoist task not found: ${path.split('?')[0]}`)
+      throw new TodoBackendError(`Todoist ${method} ${path.split('?')[0]} failed (${response.status}): ${detail.slice(0, 300) || response.statusText}`)
+    }
+    return (response.status === 204 ? undefined : await response.json()) as T
+  }
+}
+
+const REMINDERS_LIST_SCRIPT = `
+function run(argv) {
+  const app = Application('Reminders')
+  const limit = Number(argv[1])
+  const lists = argv[0] ? [app.lists.byName(argv[0])] : app.lists()
+  const out = []
+  for (const list of lists) {
+    const open = list.reminders.whose({ completed: false })
+    const ids = open.id(), names = open.name(), bodies = open.body(), dues = open.dueDate(), priorities = open.priority()
+    for (let i = 0; i < ids.length && out.length < limit; i++) {
+      out.push({ id: ids[i], title: names[i], notes: bodies[i] || '', due: dues[i] ? dues[i].toISOString() : null, priority: priorities[i], list: list.name() })
+    }
+  }
+  return JSON.stringify(out)
+}`
+
+const REMINDERS_CREATE_SCRIPT = `
+function run(argv) {
+  const app = Application('Reminders')
+  const input = JSON.parse(argv[0])
+  const list = input.list ? app.lists.byName(input.list) : app.defaultList()
+  const props = { name: input.title }
+  if (input.notes) props.body = input.notes
+  if (input.due) props.dueDate = new Date(input.due)
+  if (input.priority) props.priority = input.priority
+  const reminder = app.Reminder(props)
+  list.reminders.push(reminder)
+  return JSON.stringify({ id: reminder.id(), title: reminder.name(), notes: input.notes || '', due: input.due || null, priority: input.priority || 0, list: list.name() })
+}`
+
+const REMINDERS_COMPLETE_SCRIPT = `
+function run(argv) {
+  const reminder = Application('Reminders').reminders.byId(argv[0])
+  reminder.completed = true
+  return JSON.stringify({ id: argv[0], title: reminder.name() })
+}`
+
+/** Apple Reminders on macOS, through osascript. Projects are Reminders lists. */
+export class RemindersBackend implements TodoBackend {
+  readonly name = 'reminders' as const
+
+  constructor(private readonly run: OsaScriptRunner = runOsaScript) {}
+
+  async list(options: { project?: string; limit?: number }, signal?: AbortSignal): Promise<TodoItem[]> {
+    const raw = JSON.parse(await this.run(REMINDERS_LIST_SCRIPT, [options.project ?? '', String(clampLimit(options.limit))], signal)) as RawReminder[]
+    return raw.map(toReminderItem)
+  }
+
+  async create(input: CreateTodoInput, signal?: AbortSignal): Promise<TodoItem> {
+    let due: string | undefined
+    if (input.due) {
+      const parsed = new Date(input.due)
+      if (Number.isNaN(parsed.getTime())) throw new TodoBackendError('Reminders needs due as an ISO date, e.g. 2026-10-20T17:00')
+      due = parsed.toISOString()
+    }
+    const payload = { title: input.title, notes: input.notes, due, priority: remindersPriority(input.priority), list: input.project }
+    return toReminderItem(JSON.parse(await this.run(REMINDERS_CREATE_SCRIPT, [JSON.stringify(payload)], signal)) as RawReminder)
+  }
+
+  async complete(id: string, signal?: AbortSignal): Promise<{ id: string; title: string | null }> {
+    return JSON.parse(await this.run(REMINDERS_COMPLETE_SCRIPT, [id], signal)) as { id: string; title: string | null }
+  }
+}
+
+interface RawTodoistTask {
+  id: string
+  content: string
+  description?: string
+  due?: { date: string; datetime?: string | null; string?: string } | null
+  priority?: number
+  project_id?: string
+}
+interface RawReminder { id: string; title: string; notes: string; due: string | null; priority: number; list: string }
+
+function toTodoistItem(task: RawTodoistTask, projects: Map<string, string>): TodoItem {
+  return {
+    id: task.id,
+    title: task.content,
+    notes: task.description ?? '',
+    due: task.due?.datetime ?? task.due?.date ?? null,
+    priority: task.priority ?? 1,
+    project: task.project_id ? projects.get(task.project_id) ?? null : null,
+    url: `https://app.todoist.com/app/task/${task.id}`,
+  }
+}
+
+function toReminderItem(raw: RawReminder): TodoItem {
+  return {
+    id: raw.id,
+    title: raw.title,
+    notes: raw.notes,
+    due: raw.due,
+    priority: fromRemindersPriority(raw.priority),
+    project: raw.list,
+    url: null,
+  }
+}
+
+/** Reminders uses 0 (none), 9 (low), 5 (medium) and 1 (high). */
+function remindersPriority(priority: number | undefined): number {
+  return ({ 4: 1, 3: 5, 2: 9 } as Record<number, number>)[priority ?? 1] ?? 0
+}
+
+function fromRemindersPriority(priority: number): number {
+  if (priority === 0) return 1
+  return priority <= 4 ? 4 : priority <= 6 ? 3 : 2
+}
+
+function clampLimit(limit: number | undefined): number {
+  return Math.min(Math.max(1, Math.floor(limit ?? DEFAULT_LIMIT)), MAX_LIMIT)
+}
@@ -0,0 +1,118 @@
+import { randomUUID } from 'node:crypto'
+import { EVENT_TYPES } from '../events/types.js'
+import { logger } from '../lib/logger.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import {
+  AudioInputError,
+  inferAudioFormat,
+  MAX_AUDIO_BYTES,
+  transcribeAudio,
+  type AudioTranscriptionInput,
+} from './audio-transcription.js'
+
+/** Finished jobs are kept this long for clients to collect the transcript. */
+const FINISHED_JOB_TTL_MS = 60 * 60 * 1000
+
+export interface TranscriptionJob {
+  id: string
+  status: 'running' | 'completed' | 'failed'
+  fileName: string | null
+  /** 0–100; stays at 0 for backends that do not report progress. */
+  progress: number
+  text: string | null
+  error: string | null
+  createdAt: number
+  completedAt: number | null
+}
+
+type StoredJob = TranscriptionJob & { userId: string; sessionId: string | null }
+
+type JobRuntime = Pick<RuntimeContext, 'config' | 'providers' | 'events' | 'shutdownController'>
+
+/**
+ * Transcribes audio attachments in the background as soon as they are
+ * uploaded, emitting transcription:* events so clients can show progress.
+ */
+export class TranscriptionJobs {
+  private readonly jobs = new Map<string, StoredJob>()
+
+  constructor(private readonly runtime: JobRuntime) {}
+
+  /** Validates the upload and starts transcribing; throws AudioInputError for bad input. */
+  start(userId: string, input: Omit<AudioTranscriptionInput, 'onProgress' | 'signal'>, sessionId?: string): TranscriptionJob {
+    const size = input.bytes.byteLength
+    if (size === 0) throw new AudioInputError('Audio file is empty')
+    if (size > MAX_AUDIO_BYTES) throw new AudioInputError(`Audio file is too large (${size} bytes)`)
+    inferAudioFormat(input.mimeType, input.fileName)
+
+    this.prune()
+    const job: StoredJob = {
+      id: randomUUID(),
+      userId,
+      sessionId: sessionId ?? null,
+      status: 'running',
+      fileName: input.fileName ?? null,
+      progress: 0,
+      text: null,
+      error: null,
+      createdAt: Date.now(),
+      completedAt: null,
+    }
+    this.jobs.set(job.id, job)
+    void this.run(job.id, input)
+    return publicJob(job)
+  }
+
+  get(userId: string, id: string): TranscriptionJob | null {
+    const job = this.jobs.get(id)
+    return job && job.userId === userId ? publicJob(job) : null
+  }
+
+  private async run(id: string, input: Omit<AudioTranscriptionInput, 'onProgress' | 'signal'>): Promise<void> {
+    const job = this.jobs.get(id)!
+    try {
+      const result = await transcribeAudio(this.runtime, {
+        ...input,
+        onProgress: (percent) => {
+          if (percent <= job.progress) return
+          job.progress = percent
+          this.runtime.events.emit({
+            type: EVENT_TYPES.TRANSCRIPTION_PROGRESS,
+            ...source(job),
+            payload: { jobId: job.id, fileName: job.fileName, progress: percent },
+          })
+        },
+      })
+      Object.assign(job, { status: 'completed', progress: 100, text: result.text, completedAt: Date.now() })
+      this.runtime.events.emit({
+        type: EVENT_TYPES.TRANSCRIPTION_COMPLETED,
+        ...source(job),
+        payload: { jobId: job.id, fileName: job.fileName, text: result.text },
+      })
+    } catch (err) {
+      const error = err instanceof Error ? err.message : String(err)
+      logger.warn({ jobId: id, err: error }, 'Audio transcription failed')
+      Object.assign(job, { status: 'failed', error, completedAt: Date.now() })
+      this.runtime.events.emit({
+        type: EVENT_TYPES.TRANSCRIPTION_FAILED,
+        ...source(job),
+        payload: { jobId: job.id, fileName: job.fileName, error },
+      })
+    }
+  }
+
+  private prune(now = Date.now()): void {
+    for (const [id, job] of this.jobs) {
+      if (job.completedAt !== null && now - job.completedAt > FINISHED_JOB_TTL_MS) this.jobs.delete(id)
+    }
+  }
+}
+
+/** Jobs are not agent runs; they report under the session the audio was attached in, if any. */
+function source(job: { sessionId: string | null }) {
+  return { agent_id: 'transcription', session_id: job.sessionId ?? 'transcription', timestamp: Date.now() }
+}
+
+function publicJob({ userId: _userId, sessionId: _sessionId, ...job }: StoredJob): TranscriptionJob {
+  return job
+}
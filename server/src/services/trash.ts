@@ -0,0 +1,60 @@
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+import { deleteSessionFiles } from '../tools/path-policy.js'
+
+const DAY_MS = 24 * 60 * 60 * 1000
+const PURGE_INTERVAL_MS = 60 * 60 * 1000
+
+/** Permanently delete a session, its files and traces, and tell sync peers it is gone. */
+export async function purgeSession(runtime: RuntimeContext, sessionId: string): Promise<void> {
+  await runtime.repositories.sessions.delete(sessionId)
+  await deleteSessionFiles(runtime.sessionFilesRoot, sessionId)
+  await runtime.debugTraces.removeSession(sessionId)
+  runtime.inlineOutputBudget.forget(sessionId)
+  await runtime.privacy.forget(sessionId)
+  await runtime.sync?.recordDeletion('session', sessionId)
+}
+
+/**
+ * Periodically purges sessions that have sat in the trash longer than
+ * TRASH_RETENTION_DAYS. A retention of 0 disables automatic purging.
+ */
+export class TrashPurger {
+  private timer: NodeJS.Timeout | null = null
+
+  constructor(private readonly runtime: RuntimeContext) {}
+
+  start(): void {
+    if (this.timer || this.runtime.config.trashRetentionDays <= 0) return
+    void this.purgeExpired()
+    this.timer = setInterval(() => {
+      void this.purgeExpired()
+    }, PURGE_INTERVAL_MS)
+    this.timer.unref()
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer)
+      this.timer = null
+    }
+  }
+
+  async purgeExpired(now = Date.now()): Promise<number> {
+    const cutoff = now - this.runtime.config.trashRetentionDays * DAY_MS
+    let purged = 0
+    try {
+      const expired = await this.runtime.repositories.sessions.listTrashedBefore(cutoff)
+      for (const session of expired) {
+        await purgeSession(this.runtime, session.id)
+        purged++
+      }
+      if (purged > 0) {
+        logger.info({ purged }, 'Purged expired sessions from trash')
+      }
+    } catch (err) {
+      logger.warn({ err }, 'Trash purge failed')
+    }
+    return purged
+  }
+}
@@ -0,0 +1,49 @@
+/** How long a client may retry a send with the same key and get the original turn back. */
+const DEFAULT_TTL_MS = 10 * 60 * 1000
+const MAX_KEY_LENGTH = 200
+
+interface Entry<T> {
+  turn: Promise<T>
+  expiresAt: number
+}
+
+export class TurnKeyError extends Error {}
+
+/**
+ * Remembers the turns started under client-supplied idempotency keys, so a
+ * retried send joins the turn the first attempt started instead of saving the
+ * message again and starting a second run. Keys are scoped to the user; a
+ * send that fails forgets its key so the retry can go through.
+ */
+export class TurnKeys<T> {
+  private readonly entries = new Map<string, Entry<T>>()
+
+  constructor(private readonly ttlMs = DEFAULT_TTL_MS) {}
+
+  /** Runs `start` for a new key; for a key seen before, waits for the turn that key started. */
+  async dedupe(userId: string, key: string, start: () => Promise<T>): Promise<{ turn: T; duplicate: boolean }> {
+    if (typeof key !== 'string' || !key || key.length > MAX_KEY_LENGTH) {
+      throw new TurnKeyError(`Idempotency key must be 1-${MAX_KEY_LENGTH} characters`)
+    }
+    this.prune()
+    const id = `${userId}\u0000${key}`
+    const existing = this.entries.get(id)
+    if (existing) return { turn: await existing.turn, duplicate: true }
+
+    const turn = start()
+    this.entries.set(id, { turn, expiresAt: Date.now() + this.ttlMs })
+    try {
+      return { turn: await turn, duplicate: false }
+    } catch (err) {
+      this.entries.delete(id)
+      throw err
+    }
+  }
+
+  private prune(): void {
+    const now = Date.now()
+    for (const [id, entry] of this.entries) {
+      if (entry.expiresAt <= now) this.entries.delete(id)
+    }
+  }
+}
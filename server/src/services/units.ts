@@ -0,0 +1,122 @@
+/** Linear units as factors to the dimension's base unit (metre, kilogram, litre, …). */
+const UNITS: Record<string, { dimension: string; factor: number; aliases: string[] }> = {
+  // length (metre)
+  mm: { dimension: 'length', factor: 0.001, aliases: ['millimeter', 'millimetre'] },
+  cm: { dimension: 'length', factor: 0.01, aliases: ['centimeter', 'centimetre'] },
+  m: { dimension: 'length', factor: 1, aliases: ['meter', 'metre'] },
+  km: { dimension: 'length', factor: 1000, aliases: ['kilometer', 'kilometre'] },
+  in: { dimension: 'length', factor: 0.0254, aliases: ['inch', 'inches', '"'] },
+  ft: { dimension: 'length', factor: 0.3048, aliases: ['foot', 'feet', "'"] },
+  yd: { dimension: 'length', factor: 0.9144, aliases: ['yard'] },
+  mi: { dimension: 'length', factor: 1609.344, aliases: ['mile'] },
+  nmi: { dimension: 'length', factor: 1852, aliases: ['nautical mile'] },
+  // mass (kilogram)
+  mg: { dimension: 'mass', factor: 1e-6, aliases: ['milligram'] },
+  g: { dimension: 'mass', factor: 0.001, aliases: ['gram'] },
+  kg: { dimension: 'mass', factor: 1, aliases: ['kilogram', 'kilo'] },
+  t: { dimension: 'mass', factor: 1000, aliases: ['tonne', 'metric ton'] },
+  oz: { dimension: 'mass', factor: 0.028349523125, aliases: ['ounce'] },
+  lb: { dimension: 'mass', factor: 0.45359237, aliases: ['lbs', 'pound'] },
+  st: { dimension: 'mass', factor: 6.35029318, aliases: ['stone'] },
+  // volume (litre)
+  ml: { dimension: 'volume', factor: 0.001, aliases: ['milliliter', 'millilitre'] },
+  cl: { dimension: 'volume', factor: 0.01, aliases: ['centiliter', 'centilitre'] },
+  l: { dimension: 'volume', factor: 1, aliases: ['liter', 'litre'] },
+  m3: { dimension: 'volume', factor: 1000, aliases: ['cubic meter', 'cubic metre'] },
+  tsp: { dimension: 'volume', factor: 0.00492892159375, aliases: ['teaspoon'] },
+  tbsp: { dimension: 'volume', factor: 0.01478676478125, aliases: ['tablespoon'] },
+  floz: { dimension: 'volume', factor: 0.0295735295625, aliases: ['fl oz', 'fluid ounce'] },
+  cup: { dimension: 'volume', factor: 0.2365882365, aliases: [] },
+  pt: { dimension: 'volume', factor: 0.473176473, aliases: ['pint'] },
+  qt: { dimension: 'volume', factor: 0.946352946, aliases: ['quart'] },
+  gal: { dimension: 'volume', factor: 3.785411784, aliases: ['gallon'] },
+  // area (square metre)
+  m2: { dimension: 'area', factor: 1, aliases: ['sqm', 'square meter', 'square metre'] },
+  km2: { dimension: 'area', factor: 1e6, aliases: ['square kilometer', 'square kilometre'] },
+  ft2: { dimension: 'area', factor: 0.09290304, aliases: ['sqft', 'square foot', 'square feet'] },
+  ha: { dimension: 'area', factor: 10_000, aliases: ['hectare'] },
+  acre: { dimension: 'area', factor: 4046.8564224, aliases: [] },
+  // speed (metre per second)
+  'm/s': { dimension: 'speed', factor: 1, aliases: ['mps'] },
+  'km/h': { dimension: 'speed', factor: 1000 / 3600, aliases: ['kph', 'kmh'] },
+  mph: { dimension: 'speed', factor: 0.44704, aliases: [] },
+  kn: { dimension: 'speed', factor: 1852 / 3600, aliases: ['knot', 'kt'] },
+  // time (second)
+  ms: { dimension: 'time', factor: 0.001, aliases: ['millisecond'] },
+  s: { dimension: 'time', factor: 1, aliases: ['sec', 'second'] },
+  min: { dimension: 'time', factor: 60, aliases: ['minute'] },
+  h: { dimension: 'time', factor: 3600, aliases: ['hr', 'hour'] },
+  d: { dimension: 'time', factor: 86_400, aliases: ['day'] },
+  wk: { dimension: 'time', factor: 604_800, aliases: ['week'] },
+  // data (byte)
+  b: { dimension: 'data', factor: 1, aliases: ['byte'] },
+  kb: { dimension: 'data', factor: 1000, aliases: ['kilobyte'] },
+  mb: { dimension: 'data', factor: 1e6, aliases: ['megabyte'] },
+  gb: { dimension: 'data', factor: 1e9, aliases: ['gigabyte'] },
+  tb: { dimension: 'data', factor: 1e12, aliases: ['terabyte'] },
+  kib: { dimension: 'data', factor: 1024, aliases: ['kibibyte'] },
+  mib: { dimension: 'data', factor: 1024 ** 2, aliases: ['mebibyte'] },
+  gib: { dimension: 'data', factor: 1024 ** 3, aliases: ['gibibyte'] },
+  tib: { dimension: 'data', factor: 1024 ** 4, aliases: ['tebibyte'] },
+  // energy (joule)
+  j: { dimension: 'energy', factor: 1, aliases: ['joule'] },
+  kj: { dimension: 'energy', factor: 1000, aliases: ['kilojoule'] },
+  cal: { dimension: 'energy', factor: 4.184, aliases: ['calorie'] },
+  kcal: { dimension: 'energy', factor: 4184, aliases: ['kilocalorie', 'food calorie'] },
+  wh: { dimension: 'energy', factor: 3600, aliases: ['watt hour'] },
+  kwh: { dimension: 'energy', factor: 3.6e6, aliases: ['kilowatt hour'] },
+}
+
+const TEMPERATURES: Record<string, { toKelvin: (value: number) => number; fromKelvin: (value: number) => number; aliases: string[] }> = {
+  c: { toKelvin: (value) => value + 273.15, fromKelvin: (value) => value - 273.15, aliases: ['°c', 'celsius', 'centigrade'] },
+  f: { toKelvin: (value) => (value - 32) * 5 / 9 + 273.15, fromKelvin: (value) => (value - 273.15) * 9 / 5 + 32, aliases: ['°f', 'fahrenheit'] },
+  k: { toKelvin: (value) => value, fromKelvin: (value) => value, aliases: ['kelvin'] },
+}
+
+export class UnitConversionError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'UnitConversionError'
+  }
+}
+
+const LINEAR_LOOKUP = buildLookup(UNITS)
+const TEMPERATURE_LOOKUP = buildLookup(TEMPERATURES)
+
+/** Converts between units of the same dimension; temperatures are handled as offsets, not factors. */
+export function convertUnits(value: number, from: string, to: string): { value: number; from: string; to: string; dimension: string } {
+  const fromKey = normalizeUnit(from)
+  const toKey = normalizeUnit(to)
+  const fromTemperature = find(TEMPERATURE_LOOKUP, fromKey)
+  const toTemperature = find(TEMPERATURE_LOOKUP, toKey)
+  if (fromTemperature && toTemperature) {
+    const result = TEMPERATURES[toTemperature].fromKelvin(TEMPERATURES[fromTemperature].toKelvin(value))
+    return { value: result, from: fromTemperature, to: toTemperature, dimension: 'temperature' }
+  }
+  const fromUnit = find(LINEAR_LOOKUP, fromKey)
+  const toUnit = find(LINEAR_LOOKUP, toKey)
+  if (!fromUnit && !fromTemperature) throw new UnitConversionError(`Unknown unit: ${from}`)
+  if (!toUnit && !toTemperature) throw new UnitConversionError(`Unknown unit: ${to}`)
+  if (!fromUnit || !toUnit || UNITS[fromUnit].dimension !== UNITS[toUnit].dimension) {
+    throw new UnitConversionError(`Cannot convert ${from} to ${to}: different kinds of quantity`)
+  }
+  return { value: value * UNITS[fromUnit].factor / UNITS[toUnit].factor, from: fromUnit, to: toUnit, dimension: UNITS[fromUnit].dimension }
+}
+
+function normalizeUnit(unit: string): string {
+  return unit.trim().toLowerCase().replace(/\s+/g, ' ').replace(/²/g, '2').replace(/³/g, '3')
+}
+
+/** Exact match first, then the singular ("miles", "hrs"). */
+function find(lookup: Map<string, string>, key: string): string | undefined {
+  return lookup.get(key) ?? (key.length > 2 && key.endsWith('s') ? lookup.get(key.slice(0, -1)) : undefined)
+}
+
+function buildLookup(table: Record<string, { aliases: string[] }>): Map<string, string> {
+  const lookup = new Map<string, string>()
+  for (const [key, { aliases }] of Object.entries(table)) {
+    lookup.set(key, key)
+    for (const alias of aliases) lookup.set(alias, key)
+  }
+  return lookup
+}
@@ -0,0 +1,212 @@
+import { execFile } from 'child_process'
+import fs from 'fs/promises'
+import os from 'os'
+import path from 'path'
+import type { ItemContentBlock } from '../domain/types.js'
+import { MAX_ATTACHMENT_BYTES } from '../lib/attachment-validation.js'
+import { logger } from '../lib/logger.js'
+import { chunkText } from '../lib/pdf-text.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { MAX_AUDIO_BYTES, transcribeAudio } from './audio-transcription.js'
+
+export const MAX_VIDEO_BYTES = MAX_ATTACHMENT_BYTES.video
+
+const VIDEO_EXTENSIONS: Record<string, string> = {
+  m4v: 'video/mp4',
+  mkv: 'video/x-matroska',
+  mov: 'video/quicktime',
+  mp4: 'video/mp4',
+  webm: 'video/webm',
+}
+
+export class VideoInputError extends Error {}
+
+export interface VideoExtractionInput {
+  bytes: ArrayBuffer | Uint8Array
+  mimeType?: string
+  fileName?: string
+  signal?: AbortSignal
+}
+
+export interface VideoFrame {
+  /** SHA-256 of the JPEG frame in the attachment store. */
+  hash: string
+  bytes: number
+  /** Position in the video, in seconds; null when the duration is unknown. */
+  at: number | null
+}
+
+export interface VideoExtraction {
+  /** SHA-256 of the video in the attachment store; reference it from a `video` content block. */
+  hash: string
+  bytes: number
+  mediaType: string
+  durationSeconds: number | null
+  frames: VideoFrame[]
+  /** Transcript of the audio track; empty without audio or AUDIO_TRANSCRIPTION_MODEL. */
+  chunks: string[]
+}
+
+type ExtractionRuntime = Pick<RuntimeContext, 'attachments' | 'config' | 'providers' | 'shutdownController'>
+
+export function inferVideoType(mimeType?: string, fileName?: string): string | null {
+  const normalizedMime = mimeType?.toLowerCase().split(';', 1)[0]?.trim()
+  if (normalizedMime?.startsWith('video/')) return normalizedMime
+  const extension = fileName?.toLowerCase().split('.').pop()
+  return (extension && VIDEO_EXTENSIONS[extension]) || null
+}
+
+/**
+ * Stores a video attachment together with VIDEO_FRAME_COUNT evenly spaced
+ * frames and a transcript of its audio track, so vision models can answer
+ * questions about screen recordings. Frames are stored as attachments of
+ * their own; re-uploading the same video returns the stored extraction.
+ */
+export async function extractVideo(runtime: ExtractionRuntime, input: VideoExtractionInput): Promise<VideoExtraction> {
+  const data = Buffer.from(input.bytes instanceof ArrayBuffer ? new Uint8Array(input.bytes) : input.bytes)
+  if (data.length === 0) throw new VideoInputError('Video file is empty')
+  if (data.length > MAX_VIDEO_BYTES) throw new VideoInputError(`Video file is too large (${data.length} bytes)`)
+  const mediaType = inferVideoType(input.mimeType, input.fileName)
+  if (!mediaType) throw new VideoInputError(`Unsupported video type: ${input.mimeType || input.fileName || 'unknown'}`)
+
+  const hash = await runtime.attachments.put(data)
+  const stored = await runtime.attachments.getExtraction<VideoExtraction>(hash)
+  if (stored) return stored
+
+  const signal = input.signal ?? runtime.shutdownController.signal
+  const dir = await fs.mkdtemp(path.join(os.tmpdir(), 'video-'))
+  try {
+    const video = path.join(dir, 'input')
+    await fs.writeFile(video, data)
+    const probe = parseVideoProbe(await ffmpeg(runtime.config.ffmpegBin, ['-hide_banner', '-nostdin', '-i', video], signal, true))
+    if (!probe.hasVideo) throw new VideoInputError(`No video stream found in ${input.fileName ?? 'the file'}`)
+
+    const frames: VideoFrame[] = []
+    for (const at of frameTimestamps(probe.durationSeconds, runtime.config.videoFrameCount)) {
+      const output = path.join(dir, `frame-${frames.length}.jpg`)
+      await ffmpeg(runtime.config.ffmpegBin, videoFrameArgs(video, output, at, runtime.config.visionImageMaxDimension), signal)
+      const frame = await fs.readFile(output).catch(() => null)
+      // Seeking past the last decodable frame writes nothing; keep the frames that exist
+      if (!frame?.length) continue
+      frames.push({ hash: await runtime.attachments.put(frame), bytes: frame.length, at: probe.durationSeconds === null ? null : at })
+    }
+    if (frames.length === 0) throw new VideoInputError('No frames could be read from the video')
+
+    const transcript = probe.hasAudio ? await transcribeTrack(runtime, video, path.join(dir, 'audio.m4a'), signal) : ''
+    const extraction: VideoExtraction = {
+      hash,
+      bytes: data.length,
+      mediaType,
+      durationSeconds: probe.durationSeconds,
+      frames,
+      chunks: transcript ? chunkText(transcript) : [],
+    }
+    await runtime.attachments.putExtraction(hash, extraction)
+    return extraction
+  } finally {
+    await fs.rm(dir, { recursive: true, force: true })
+  }
+}
+
+/**
+ * Expands uploaded `video` blocks sent with a user message into the
+ * transcript block followed by one `image` block per sampled frame.
+ */
+export async function resolveVideoBlocks(
+  runtime: Pick<RuntimeContext, 'attachments'>,
+  blocks: ItemContentBlock[] | null,
+): Promise<ItemContentBlock[] | null> {
+  if (!blocks) return null
+  const resolved: ItemContentBlock[] = []
+  for (const block of blocks) {
+    if (block.type !== 'video') {
+      resolved.push(block)
+      continue
+    }
+    const extraction = block.hash ? await runtime.attachments.getExtraction<VideoExtraction>(block.hash) : null
+    if (!extraction) throw new VideoInputError(`Unknown video attachment: ${block.name ?? block.hash ?? 'unnamed'}`)
+    const name = block.name ?? 'video'
+    resolved.push({
+      type: 'video',
+      name,
+      media_type: extraction.mediaType,
+      hash: extraction.hash,
+      bytes: extraction.bytes,
+      chunks: extraction.chunks,
+    })
+    for (const frame of extraction.frames) {
+      resolved.push({
+        type: 'image',
+        name: frame.at === null ? `${name} frame` : `${name} at ${formatTimestamp(frame.at)}`,
+        media_type: 'image/jpeg',
+        hash: frame.hash,
+        bytes: frame.bytes,
+      })
+    }
+  }
+  return resolved
+}
+
+export function parseVideoProbe(stderr: string): { durationSeconds: number | null; hasVideo: boolean; hasAudio: boolean } {
+  const duration = stderr.match(/Duration:\s*(\d+):(\d{2}):(\d{2}(?:\.\d+)?)/)
+  const durationSeconds = duration ? Number(duration[1]) * 3600 + Number(duration[2]) * 60 + Number(duration[3]) : null
+  return {
+    durationSeconds: durationSeconds || null,
+    hasVideo: /Stream #\S+.*: Video:/.test(stderr),
+    hasAudio: /Stream #\S+.*: Audio:/.test(stderr),
+  }
+}
+
+/** The middle of each of `count` equal segments; only the opening frame when the duration is unknown. */
+export function frameTimestamps(durationSeconds: number | null, count: number): number[] {
+  if (!durationSeconds) return [0]
+  const frames = Math.max(1, Math.floor(count))
+  return Array.from({ length: frames }, (_, i) => Math.round(durationSeconds * (i + 0.5) / frames * 100) / 100)
+}
+
+export function videoFrameArgs(input: string, output: string, at: number, maxDimension: number): string[] {
+  const scale = `scale='min(iw,${maxDimension})':'min(ih,${maxDimension})':force_original_aspect_ratio=decrease`
+  return ['-nostdin', '-y', '-ss', String(at), '-i', input, '-frames:v', '1', '-vf', scale, '-q:v', '3', output]
+}
+
+export function formatTimestamp(seconds: number): string {
+  const whole = Math.floor(seconds)
+  const hours = Math.floor(whole / 3600)
+  const minutes = Math.floor((whole % 3600) / 60)
+  const secs = String(whole % 60).padStart(2, '0')
+  return hours ? `${hours}:${String(minutes).padStart(2, '0')}:${secs}` : `${minutes}:${secs}`
+}
+
+/** Transcribes the audio track; a missing model or a failed transcription leaves the video without one. */
+async function transcribeTrack(runtime: ExtractionRuntime, video: string, output: string, signal: AbortSignal): Promise<string> {
+  if (!(runtime.config.audioTranscriptionModel?.trim() || runtime.config.telegramTranscriptionModel?.trim())) return ''
+  try {
+    // Mono 16 kHz AAC keeps an hour of speech under the transcription size limit
+    await ffmpeg(runtime.config.ffmpegBin, ['-nostdin', '-y', '-i', video, '-vn', '-ac', '1', '-ar', '16000', '-c:a', 'aac', '-b:a', '32k', output], signal)
+    const audio = await fs.readFile(output)
+    if (audio.length > MAX_AUDIO_BYTES) {
+      logger.warn({ bytes: audio.length }, 'Video audio track is too long to transcribe')
+      return ''
+    }
+    const { text } = await transcribeAudio(runtime, { bytes: audio, mimeType: 'audio/mp4', fileName: 'audio.m4a', signal })
+    return text
+  } catch (err) {
+    if (signal.aborted) throw err
+    logger.warn({ err: err instanceof Error ? err.message : String(err) }, 'Video audio transcription failed')
+    return ''
+  }
+}
+
+/** Runs ffmpeg and returns its stderr; `probe` tolerates the failure `ffmpeg -i` reports when no output is given. */
+function ffmpeg(bin: string, args: string[], signal: AbortSignal, probe = false): Promise<string> {
+  return new Promise((resolve, reject) => {
+    execFile(bin, args, { signal, maxBuffer: 16 * 1024 * 1024 }, (err, _stdout, stderr) => {
+      if (!err || (probe && typeof err.code === 'number')) return resolve(String(stderr))
+      if ((err as NodeJS.ErrnoException).code === 'ENOENT') {
+        return reject(new Error(`${bin} was not found; install it or set FFMPEG_BIN`))
+      }
+      const detail = String(stderr).trim().split('\n').slice(-3).join('\n')
+      reject(new Error(`${path.basename(bin)} failed${detail ? `: ${detail}` : ''}`))
+    })
+  })
+}
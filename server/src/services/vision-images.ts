@@ -0,0 +1,93 @@
+import { execFile } from 'child_process'
+import fs from 'fs/promises'
+import os from 'os'
+import path from 'path'
+import type { ImagePreparer } from '../lib/attachment-store.js'
+import { readImageInfo } from '../lib/image-info.js'
+import { logger } from '../lib/logger.js'
+
+const RESIZE_TIMEOUT_MS = 30_000
+// ffmpeg writes the image to a file; this only bounds its log output
+const RESIZE_MAX_BUFFER = 1024 * 1024
+
+export interface VisionImageOptions {
+  /** ffmpeg, used to resize and re-encode. */
+  ffmpegBin: string
+  /** Longest edge, in pixels, sent to a vision model. */
+  maxDimension: number
+  /** Largest payload sent to a vision model. */
+  maxBytes: number
+  /** How long one ffmpeg run may take before it is killed; defaults to 30 seconds. */
+  timeoutMs?: number
+}
+
+/**
+ * Shrinks images to provider-friendly dimensions and converts formats vision
+ * providers reject, so history images cost fewer tokens and stay under size
+ * limits. Images that already fit are sent untouched. PNGs stay PNG (text in
+ * screenshots survives better) unless that is still too large; everything
+ * else becomes JPEG.
+ */
+export function createVisionImagePreparer(options: VisionImageOptions): ImagePreparer {
+  return {
+    key: `${options.maxDimension}:${options.maxBytes}`,
+    async prepare(data) {
+      const info = readImageInfo(data)
+      if (info && Math.max(info.width, info.height) <= options.maxDimension && data.length <= options.maxBytes) {
+        return null
+      }
+
+      try {
+        if (info?.mediaType === 'image/png') {
+          const png = await resize(options, data, 'png')
+          if (png.length <= options.maxBytes) return { data: png, mediaType: 'image/png' }
+        }
+        const jpeg = await resize(options, data, 'jpg')
+        if (jpeg.length > options.maxBytes) {
+          logger.warn({ bytes: jpeg.length, maxBytes: options.maxBytes }, 'Resized image is still over the size limit')
+        }
+        return { data: jpeg, mediaType: 'image/jpeg' }
+      } catch (err) {
+        // The original still goes out; the provider decides whether it can take it
+        logger.warn({ err: err instanceof Error ? err.message : String(err) }, 'Image resize failed')
+        return null
+      }
+    },
+  }
+}
+
+export function visionResizeArgs(input: string, output: string, maxDimension: number): string[] {
+  const scale = `scale='min(iw,${maxDimension})':'min(ih,${maxDimension})':force_original_aspect_ratio=decrease`
+  return [
+    '-nostdin', '-y', '-i', input, '-frames:v', '1', '-vf', scale,
+    ...(output.endsWith('.jpg') ? ['-q:v', '3'] : []),
+    output,
+  ]
+}
+
+async function resize(options: VisionImageOptions, data: Buffer, extension: 'png' | 'jpg'): Promise<Buffer> {
+  const dir = await fs.mkdtemp(path.join(os.tmpdir(), 'vision-'))
+  try {
+    const input = path.join(dir, 'input')
+    const output = path.join(dir, `output.${extension}`)
+    await fs.writeFile(input, data)
+    await new Promise<void>((resolve, reject) => {
+      const timeout = options.timeoutMs ?? RESIZE_TIMEOUT_MS
+      const args = visionResizeArgs(input, output, options.maxDimension)
+      execFile(options.ffmpegBin, args, { timeout, maxBuffer: RESIZE_MAX_BUFFER }, (err, _stdout, stderr) => {
+        if (!err) return resolve()
+        if ((err as NodeJS.ErrnoException).code === 'ENOENT') {
+          return reject(new Error(`${options.ffmpegBin} was not found; install it or set FFMPEG_BIN`))
+        }
+        if (err.killed) {
+          return reject(new Error(`${path.basename(options.ffmpegBin)} did not finish within ${timeout}ms`))
+        }
+        const detail = String(stderr).trim().split('\n').slice(-3).join('\n')
+        reject(new Error(`${path.basename(options.ffmpegBin)} failed${detail ? `: ${detail}` : ''}`))
+      })
+    })
+    return await fs.readFile(output)
+  } finally {
+    await fs.rm(dir, { recursive: true, force: true })
+  }
+}
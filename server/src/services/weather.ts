@@ -0,0 +1,131 @@
+const GEOCODING_URL = 'https://geocoding-api.open-meteo.com/v1/search'
+const FORECAST_URL = 'https://api.open-meteo.com/v1/forecast'
+const MAX_FORECAST_DAYS = 16
+
+/** WMO weather interpretation codes as used by Open-Meteo. */
+const WEATHER_CODES: Record<number, string> = {
+  0: 'Clear sky',
+  1: 'Mainly clear',
+  2: 'Partly cloudy',
+  3: 'Overcast',
+  45: 'Fog',
+  48: 'Depositing rime fog',
+  51: 'Light drizzle',
+  53: 'Drizzle',
+  55: 'Dense drizzle',
+  56: 'Light freezing drizzle',
+  57: 'Freezing drizzle',
+  61: 'Light rain',
+  63: 'Rain',
+  65: 'Heavy rain',
+  66: 'Light freezing rain',
+  67: 'Freezing rain',
+  71: 'Light snow',
+  73: 'Snow',
+  75: 'Heavy snow',
+  77: 'Snow grains',
+  80: 'Light rain showers',
+  81: 'Rain showers',
+  82: 'Violent rain showers',
+  85: 'Light snow showers',
+  86: 'Snow showers',
+  95: 'Thunderstorm',
+  96: 'Thunderstorm with light hail',
+  99: 'Thunderstorm with hail',
+}
+
+export interface WeatherReport {
+  location: { name: string; country: string | null; latitude: number; longitude: number; timezone: string }
+  units: { temperature: string; wind: string; precipitation: string }
+  current: { time: string; temperature: number; feelsLike: number; humidity: number; windSpeed: number; conditions: string }
+  daily: Array<{ date: string; conditions: string; min: number; max: number; precipitation: number; precipitationChance: number | null }>
+}
+
+export class WeatherError extends Error {
+  constructor(message: string) {
+    super(message)
+    this.name = 'WeatherError'
+  }
+}
+
+/** Open-Meteo forecasts; free and keyless, so the tool works without setup. */
+export class OpenMeteoClient {
+  constructor(private readonly fetchImpl: typeof fetch = fetch) {}
+
+  async forecast(location: string, options: { days?: number; units?: 'metric' | 'imperial' }, signal?: AbortSignal): Promise<WeatherReport> {
+    const place = await this.geocode(location, signal)
+    const imperial = options.units === 'imperial'
+    const days = Math.min(Math.max(1, Math.floor(options.days ?? 3)), MAX_FORECAST_DAYS)
+    const params = new URLSearchParams({
+      latitude: String(place.latitude),
+      longitude: String(place.longitude),
+      timezone: 'auto',
+      forecast_days: String(days),
+      current: 'temperature_2m,apparent_temperature,relative_humidity_2m,wind_speed_10m,weather_code',
+      daily: 'weather_code,temperature_2m_min,temperature_2m_max,precipitation_sum,precipitation_probability_max',
+      ...(imperial && { temperature_unit: 'fahrenheit', wind_speed_unit: 'mph', precipitation_unit: 'inch' }),
+    })
+    const data = await this.get<RawForecast>(`${FORECAST_URL}?${params}`, signal)
+    return {
+      location: { name: place.name, country: place.country ?? null, latitude: place.latitude, longitude: place.longitude, timezone: data.timezone },
+      units: { temperature: imperial ? '°F' : '°C', wind: imperial ? 'mph' : 'km/h', precipitation: imperial ? 'in' : 'mm' },
+      current: {
+        time: data.current.time,
+        temperature: data.current.temperature_2m,
+        feelsLike: data.current.apparent_temperature,
+        humidity: data.current.relative_humidity_2m,
+        windSpeed: data.current.wind_speed_10m,
+        conditions: describe(data.current.weather_code),
+      },
+      daily: data.daily.time.map((date, index) => ({
+        date,
+        conditions: describe(data.daily.weather_code[index]),
+        min: data.daily.temperature_2m_min[index],
+        max: data.daily.temperature_2m_max[index],
+        precipitation: data.daily.precipitation_sum[index],
+        precipitationChance: data.daily.precipitation_probability_max[index] ?? null,
+      })),
+    }
+  }
+
+  private async geocode(location: string, signal?: AbortSignal): Promise<RawPlace> {
+    // "Paris, France" → search "Paris" and prefer a result in France
+    const [name, ...qualifiers] = location.split(',').map((part) => part.trim()).filter(Boolean)
+    if (!name) throw new WeatherError('location is required')
+    const params = new URLSearchParams({ name, count: '10', language: 'en', format: 'json' })
+    const data = await this.get<{ results?: RawPlace[] }>(`${GEOCODING_URL}?${params}`, signal)
+    const results = data.results ?? []
+    const wanted = qualifiers.map((part) => part.toLowerCase())
+    const match = results.find((place) => wanted.every((part) =>
+      [place.country, place.country_code, place.admin1].some((field) => field?.toLowerCase() === part))) ?? (wanted.length === 0 ? results[0] : undefined)
+    if (!match) throw new WeatherError(`Location not found: ${location}`)
+    return match
+  }
+
+  private async get<T>(url: string, signal?: AbortSignal): Promise<T> {
+    const response = await this.fetchImpl(url, { signal: signal ? AbortSignal.any([signal, AbortSignal.timeout(15_000)]) : AbortSignal.timeout(15_000) })
+    if (!response.ok) {
+      const detail = await response.text().catch(() => '')
+      throw new WeatherError(`Open-Meteo request failed (${response.status}): ${detail.slice(0, 300) || response.statusText}`)
+    }
+    return await response.json() as T
+  }
+}
+
+interface RawPlace { name: string; latitude: number; longitude: number; country?: string; country_code?: string; admin1?: string }
+interface RawForecast {
+  timezone: string
+  current: { time: string; temperature_2m: number; apparent_temperature: number; relative_humidity_2m: number; wind_speed_10m: number; weather_code: number }
+  daily: {
+    time: string[]
+    weather_code: number[]
+    temperature_2m_min: number[]
+    temperature_2m_max: number[]
+    precipitation_sum: number[]
+    precipitation_probability_max: Array<number | null>
+  }
+}
+
+function describe(code: number): string {
+  return WEATHER_CODES[code] ?? `Weather code ${code}`
+}
@@ -0,0 +1,182 @@
+import { AgentLock } from '../lib/agent-lock.js'
+import type { ApiKeyRepository } from '../repositories/types.js'
+
+/** All webhook endpoints live in one encrypted api_keys entry under this name; their URLs often embed a secret. */
+export const WEBHOOKS_KEY = 'webhooks'
+
+const REQUEST_TIMEOUT_MS = 30_000
+/** Longer responses are cut; automations rarely answer with more than a status. */
+const MAX_RESPONSE_CHARS = 4_000
+
+export interface WebhookEndpoint {
+  /** Picked by the webhook.post `endpoint` argument, e.g. "crm" or "zapier-leads". */
+  name: string
+  url: string
+  /** Tells the agent what the endpoint does and what payload it expects. */
+  description: string | null
+  /** Sent with every post, e.g. an Authorization header the receiver checks. */
+  headers: Record<string, string>
+  /** Set by the first post, which always asks for approval. Changing the URL clears it. */
+  confirmedAt: number | null
+  createdAt: number
+}
+
+export interface WebhookEndpointView extends Omit<WebhookEndpoint, 'headers'> {
+  /** Header names only; values are secrets. */
+  headers: string[]
+}
+
+export interface WebhookResult {
+  endpoint: string
+  status: number
+  /** Parsed when the receiver answered with JSON. */
+  response: unknown
+}
+
+export class WebhookError extends Error {
+  constructor(message: string, readonly status?: number) {
+    super(message)
+    this.name = 'WebhookError'
+  }
+}
+
+/** Validates an endpoint from the settings UI; throws with a message suitable for a 400. Missing headers keep the saved ones. */
+export function normalizeWebhookEndpoint(name: string, input: Record<string, unknown>, existing: WebhookEndpoint | null): WebhookEndpoint {
+  if (!/^[a-z0-9][a-z0-9_-]{0,39}$/i.test(name)) throw new Error('name must be 1-40 letters, digits, dashes or underscores')
+  const url = typeof input.url === 'string' ? input.url.trim() : ''
+  if (!/^https?:\/\/[^/]+/.test(url)) throw new Error('url must be an http(s) URL')
+  if (input.description !== undefined && input.description !== null && typeof input.description !== 'string') {
+    throw new Error('description must be a string')
+  }
+  const description = typeof input.description === 'string' && input.description.trim() ? input.description.trim().slice(0, 500) : null
+  let headers = existing?.headers ?? {}
+  if (input.headers !== undefined) {
+    if (!input.headers || typeof input.headers !== 'object' || Array.isArray(input.headers)) throw new Error('headers must be an object')
+    headers = {}
+    for (const [key, value] of Object.entries(input.headers)) {
+      if (!/^[\w-]+$/.test(key) || typeof value !== 'string') throw new Error(`header ${key} must be a name with a string value`)
+      headers[key] = value
+    }
+  }
+  return {
+    name,
+    url,
+    description,
+    headers,
+    // A new destination needs a fresh approval
+    confirmedAt: existing && existing.url === url ? existing.confirmedAt : null,
+    createdAt: existing?.createdAt ?? Date.now(),
+  }
+}
+
+export function webhookEndpointView(endpoint: WebhookEndpoint): WebhookEndpointView {
+  return { ...endpoint, headers: Object.keys(endpoint.headers) }
+}
+
+export async function loadWebhookEndpoints(apiKeys: ApiKeyRepository): Promise<WebhookEndpoint[]> {
+  const record = await apiKeys.getByProvider(WEBHOOKS_KEY)
+  if (!record) return []
+  try {
+    return JSON.parse(record.encryptedKey) as WebhookEndpoint[]
+  } catch {
+    throw new WebhookError('Stored webhook endpoints are unreadable; save them again')
+  }
+}
+
+export async function saveWebhookEndpoints(apiKeys: ApiKeyRepository, endpoints: WebhookEndpoint[]): Promise<void> {
+  if (endpoints.length === 0) await apiKeys.delete(WEBHOOKS_KEY)
+  else await apiKeys.upsert(WEBHOOKS_KEY, JSON.stringify(endpoints))
+}
+
+/**
+ * Posts JSON payloads to the endpoints the user registered. The tool reads
+ * `endpoints` synchronously for its description and approval trigger, so the
+ * list is cached and reloaded by `refresh` after every change.
+ */
+export class WebhookService {
+  private cached: WebhookEndpoint[] = []
+  private readonly lock = new AgentLock()
+
+  constructor(
+    private readonly apiKeys: ApiKeyRepository,
+    private readonly fetchImpl: typeof fetch = fetch,
+  ) {}
+
+  get endpoints(): WebhookEndpoint[] {
+    return this.cached
+  }
+
+  async refresh(): Promise<WebhookEndpoint[]> {
+    this.cached = await loadWebhookEndpoints(this.apiKeys)
+    return this.cached
+  }
+
+  /**
+   * Runs a read-modify-write of the stored list. The routes and the first-post
+   * confirmation share it, so neither overwrites the other's change.
+   */
+  async locked<T>(work: () => Promise<T>): Promise<T> {
+    const release = await this.lock.acquire(WEBHOOKS_KEY)
+    try {
+      return await work()
+    } finally {
+      release()
+    }
+  }
+
+  async post(name: string, payload: unknown, signal?: AbortSignal): Promise<WebhookResult> {
+    const endpoints = await this.refresh()
+    let endpoint = endpoints.find((entry) => entry.name === name)
+    if (!endpoint) {
+      throw new WebhookError(`Unknown endpoint "${name}"; registered: ${endpoints.map((entry) => entry.name).join(', ') || 'none'}`)
+    }
+    // The call got here, so this first post was approved; later ones go through without asking
+    if (endpoint.confirmedAt === null) endpoint = await this.confirm(endpoint)
+
+    const timeout = AbortSignal.timeout(REQUEST_TIMEOUT_MS)
+    let response: Response
+    try {
+      response = await this.fetchImpl(endpoint.url, {
+        method: 'POST',
+        headers: { ...endpoint.headers, 'content-type': 'application/json' },
+        body: JSON.stringify(payload),
+        signal: signal ? AbortSignal.any([signal, timeout]) : timeout,
+      })
+    } catch (err) {
+      throw new WebhookError(`Could not reach endpoint "${name}": ${err instanceof Error ? err.message : String(err)}`)
+    }
+    const text = (await response.text()).slice(0, MAX_RESPONSE_CHARS)
+    if (!response.ok) {
+      throw new WebhookError(`Endpoint "${name}" answered ${response.status}${text.trim() ? `: ${text.trim()}` : ''}`, response.status)
+    }
+    return { endpoint: name, status: response.status, response: parseResponse(text, response.headers.get('content-type')) }
+  }
+
+  /** Sets the endpoint's confirmedAt alone, as long as it still points where it did when the post started. */
+  private confirm(endpoint: WebhookEndpoint): Promise<WebhookEndpoint> {
+    return this.locked(async () => {
+      const endpoints = await loadWebhookEndpoints(this.apiKeys)
+      const current = endpoints.find((entry) => entry.name === endpoint.name)
+      if (!current || current.url !== endpoint.url) {
+        throw new WebhookError(`Endpoint "${endpoint.name}" was changed while the post waited; try again`)
+      }
+      if (current.confirmedAt !== null) return current
+      current.confirmedAt = Date.now()
+      await saveWebhookEndpoints(this.apiKeys, endpoints)
+      this.cached = endpoints
+      return current
+    })
+  }
+}
+
+function parseResponse(text: string, contentType: string | null): unknown {
+  if (!text.trim()) return null
+  if (contentType?.includes('json')) {
+    try {
+      return JSON.parse(text)
+    } catch {
+      // Cut short or not JSON after all; the text is still useful
+    }
+  }
+  return text
+}
@@ -0,0 +1,64 @@
+import { execFile } from 'child_process'
+import fs from 'fs/promises'
+import os from 'os'
+import path from 'path'
+
+/** Model id prefix that routes transcription to a local whisper.cpp build, e.g. `whispercpp:/models/ggml-base.bin`. */
+export const WHISPER_CPP_PROVIDER = 'whispercpp'
+
+export interface WhisperCppOptions {
+  /** whisper.cpp CLI (`whisper-cli`, or `main` in older builds). */
+  bin: string
+  /** ffmpeg, used to resample input to the 16 kHz mono WAV whisper.cpp expects. */
+  ffmpegBin: string
+  /** Path to a ggml model file. */
+  modelPath: string
+  signal?: AbortSignal
+  /** Called with 0–100 as whisper.cpp reports progress. */
+  onProgress?: (percent: number) => void
+}
+
+export function whisperCppArgs(modelPath: string, wavPath: string, outputBase: string): string[] {
+  return ['-m', modelPath, '-f', wavPath, '-l', 'auto', '-nt', '-otxt', '-of', outputBase, '-pp']
+}
+
+/** Reads the percentage from a `-pp` progress line such as `whisper_print_progress_callback: progress =  45%`. */
+export function parseWhisperProgress(output: string): number | null {
+  const matches = [...output.matchAll(/progress\s*=\s*(\d+)%/g)]
+  if (matches.length === 0) return null
+  return Math.min(100, Number(matches[matches.length - 1][1]))
+}
+
+export async function transcribeWithWhisperCpp(bytes: Uint8Array, format: string, options: WhisperCppOptions): Promise<string> {
+  const dir = await fs.mkdtemp(path.join(os.tmpdir(), 'whisper-'))
+  try {
+    const input = path.join(dir, `input.${format}`)
+    const wav = path.join(dir, 'audio.wav')
+    const outputBase = path.join(dir, 'transcript')
+    await fs.writeFile(input, bytes)
+
+    await run(options.ffmpegBin, ['-nostdin', '-y', '-i', input, '-ar', '16000', '-ac', '1', '-c:a', 'pcm_s16le', wav], options.signal)
+    options.onProgress?.(0)
+    await run(options.bin, whisperCppArgs(options.modelPath, wav, outputBase), options.signal, (chunk) => {
+      const percent = parseWhisperProgress(chunk)
+      if (percent !== null) options.onProgress?.(percent)
+    })
+    return await fs.readFile(`${outputBase}.txt`, 'utf8')
+  } finally {
+    await fs.rm(dir, { recursive: true, force: true })
+  }
+}
+
+function run(bin: string, args: string[], signal?: AbortSignal, onStderr?: (chunk: string) => void): Promise<void> {
+  return new Promise((resolve, reject) => {
+    const child = execFile(bin, args, { signal, maxBuffer: 16 * 1024 * 1024 }, (err, _stdout, stderr) => {
+      if (!err) return resolve()
+      if ((err as NodeJS.ErrnoException).code === 'ENOENT') {
+        return reject(new Error(`${bin} was not found; install it or set its path in the server config`))
+      }
+      const detail = String(stderr).trim().split('\n').slice(-3).join('\n')
+      reject(new Error(`${path.basename(bin)} failed${detail ? `: ${detail}` : ''}`))
+    })
+    if (onStderr) child.stderr?.on('data', (chunk) => onStderr(String(chunk)))
+  })
+}
@@ -0,0 +1,62 @@
+import { EVENT_TYPES, type EventSink } from '../events/types.js'
+
+/**
+ * Which app window owns which conversation, so a detached agent monitor and
+ * the main chat window do not both render the same run. Events for a claimed
+ * conversation go only to the claiming window; unclaimed conversations go to
+ * every window. Claims live in memory and end with the window's event stream.
+ */
+export class WindowClaims {
+  private readonly owners = new Map<string, string>()
+
+  constructor(private readonly events: EventSink) {}
+
+  /** Take over a conversation; returns the window that held it before, if any. */
+  claim(sessionId: string, windowId: string): string | null {
+    const previous = this.owners.get(sessionId) ?? null
+    if (previous === windowId) return previous
+    this.owners.set(sessionId, windowId)
+    this.announce(sessionId, windowId, previous)
+    return previous
+  }
+
+  /** Give a conversation back; a window cannot release another window's claim. */
+  release(sessionId: string, windowId: string): boolean {
+    if (this.owners.get(sessionId) !== windowId) return false
+    this.owners.delete(sessionId)
+    this.announce(sessionId, null, windowId)
+    return true
+  }
+
+  /** Drop every claim held by a window that went away. */
+  releaseWindow(windowId: string): string[] {
+    const released = this.claimedBy(windowId)
+    for (const sessionId of released) this.release(sessionId, windowId)
+    return released
+  }
+
+  ownerOf(sessionId: string): string | null {
+    return this.owners.get(sessionId) ?? null
+  }
+
+  claimedBy(windowId: string): string[] {
+    return [...this.owners].filter(([, owner]) => owner === windowId).map(([sessionId]) => sessionId)
+  }
+
+  /** Claim changes, renames and digests reach every window so each can update its view or notify. */
+  routes(sessionId: string, windowId: string | undefined, eventType?: string): boolean {
+    if (eventType === EVENT_TYPES.CONVERSATION_CLAIMED || eventType === EVENT_TYPES.CONVERSATION_UPDATED || eventType === EVENT_TYPES.DIGEST_READY) return true
+    const owner = this.owners.get(sessionId)
+    return owner === undefined || windowId === undefined || owner === windowId
+  }
+
+  private announce(sessionId: string, windowId: string | null, previousWindowId: string | null): void {
+    this.events.emit({
+      type: EVENT_TYPES.CONVERSATION_CLAIMED,
+      agent_id: 'window',
+      session_id: sessionId,
+      payload: { windowId, previousWindowId },
+      timestamp: Date.now(),
+    })
+  }
+}
@@ -0,0 +1,408 @@
+import { createHash, randomUUID } from 'node:crypto'
+import http, { type IncomingMessage } from 'node:http'
+import type { Duplex } from 'node:stream'
+import { toWireEvent, type AgentEvent } from '../events/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { logger } from '../lib/logger.js'
+import { acceptWebSocket, type WebSocketConnection } from '../lib/websocket.js'
+import { deliverApprovals, type AmendedArgs } from '../orchestrator/delivery.js'
+import { CONVERSATION_MODES, isConversationMode, type ConversationMode } from '../orchestrator/chat-mode.js'
+import { runAgent } from '../orchestrator/runner.js'
+import { formatBrowserPage, parseBrowserPage, type BrowserExtensionConnection, type BrowserPage } from './browser-extension.js'
+import { listPendingApprovals } from './pending-approvals.js'
+import { buildDeps, prepareSessionTurn, type PreparedSessionTurn } from './session-runner.js'
+import { ConversationBusyError, type ConversationBusy } from './conversation-throttle.js'
+import { ActionError } from './actions.js'
+
+/**
+ * Localhost WebSocket bridge for CLI clients, editor plugins and the like.
+ * Clients authenticate with the same API key as the HTTP API and exchange
+ * JSON messages:
+ *
+ *   → { id, type: 'subscribe', sessionId?, types? }     ← { id, type: 'response', ok, subscription }
+ *   → { id, type: 'unsubscribe', subscription }
+ *   → { id, type: 'send', input, sessionId?, model?, agent?, idempotencyKey?, mode? }
+ *   → { id, type: 'approve', agentId, callId | callIds, decision, scope?, amendedArgs?, feedback? }
+ *   → { id, type: 'approvals_list_pending', sessionId }   ← { id, type: 'response', ok, approvals }
+ *   → { id, type: 'approvals_history', tool?, sessionId?, from?, to?, limit? }  ← { id, type: 'response', ok, records }
+ *   → { id, type: 'get_metrics' }                          ← { id, type: 'response', ok, metrics }
+ *   → { id, type: 'models_list', provider?, refresh? }     ← { id, type: 'response', ok, models, errors? }
+ *   → { id, type: 'actions_list' }                         ← { id, type: 'response', ok, actions }
+ *   → { id, type: 'actions_execute', action, args? }       ← { id, type: 'response', ok, result }
+ *   ← { type: 'event', subscription, event: ServerEvent }  (see events/payloads.ts)
+ *
+ * `send` and `approve` answer as soon as the run starts; progress arrives as events.
+ *
+ * A companion browser extension uses the same socket:
+ *
+ *   → { id, type: 'browser_send', page: { url, title, selection?, text? }, instruction?, sessionId?, model?, agent?, types? }
+ *                                       ← { id, type: 'response', ok, agentId, sessionId, status, subscription }
+ *   → { id, type: 'browser_register' }  then answers the server's requests (browser.get_active_tab):
+ *   ← { type: 'request', id, method: 'get_active_tab', params: { includeText } }
+ *   → { type: 'browser_result', requestId, ok, result?: { url, title, selection?, text? }, error? }
+ *
+ * `browser_send` subscribes to the conversation it started, so the agent's
+ * results arrive on the returned subscription.
+ */
+
+export interface WebSocketBridgeOptions {
+  port: number
+  host: string
+}
+
+type BridgeCommand =
+  | { id?: string; type: 'subscribe'; sessionId?: string; types?: string[] }
+  | { id?: string; type: 'unsubscribe'; subscription: string }
+  | { id?: string; type: 'send'; input: string; sessionId?: string; model?: string; agent?: string; idempotencyKey?: string; mode?: ConversationMode }
+  | {
+      id?: string
+      type: 'approve'
+      agentId: string
+      callId?: string
+      callIds?: string[]
+      decision: 'approved' | 'denied'
+      scope?: 'once' | 'conversation' | 'always'
+      amendedArgs?: AmendedArgs
+      feedback?: string
+    }
+  | { id?: string; type: 'approvals_list_pending'; sessionId: string }
+  | { id?: string; type: 'approvals_history'; tool?: string; sessionId?: string; from?: number; to?: number; limit?: number }
+  | { id?: string; type: 'get_metrics' }
+  | { id?: string; type: 'models_list'; provider?: string; refresh?: boolean }
+  | { id?: string; type: 'actions_list' }
+  | { id?: string; type: 'actions_execute'; action: string; args?: Record<string, unknown> }
+  | {
+      id?: string
+      type: 'browser_send'
+      page: unknown
+      instruction?: string
+      sessionId?: string
+      model?: string
+      agent?: string
+      types?: string[]
+    }
+  | { id?: string; type: 'browser_register' }
+  | { id?: string; type: 'browser_result'; requestId: string; ok: boolean; result?: unknown; error?: string }
+
+class BridgeCommandError extends Error {}
+
+export class WebSocketBridge {
+  private server: http.Server | null = null
+  private readonly clients = new Set<BridgeClient>()
+
+  constructor(
+    private readonly runtime: RuntimeContext,
+    private readonly options: WebSocketBridgeOptions,
+  ) {}
+
+  async start(): Promise<void> {
+    if (this.server) return
+    const server = http.createServer((_req, res) => {
+      res.writeHead(426, { Upgrade: 'websocket' }).end()
+    })
+    server.on('upgrade', (req: IncomingMessage, socket: Duplex) => {
+      void this.accept(req, socket)
+    })
+    await new Promise<void>((resolve, reject) => {
+      server.once('error', reject)
+      server.listen(this.options.port, this.options.host, () => {
+        server.off('error', reject)
+        resolve()
+      })
+    })
+    this.server = server
+    logger.info({ port: this.options.port, host: this.options.host }, 'WebSocket bridge listening')
+  }
+
+  stop(): void {
+    for (const client of this.clients) client.close()
+    this.clients.clear()
+    this.server?.close()
+    this.server = null
+  }
+
+  private async accept(req: IncomingMessage, socket: Duplex): Promise<void> {
+    try {
+      const userId = await this.authenticate(req)
+      if (!userId) {
+        socket.end('HTTP/1.1 401 Unauthorized\r\nConnection: close\r\n\r\n')
+        return
+      }
+      const connection = acceptWebSocket(req, socket)
+      if (!connection) return
+      const client = new BridgeClient(this.runtime, connection, userId)
+      this.clients.add(client)
+      connection.on('close', () => {
+        client.close()
+        this.clients.delete(client)
+      })
+    } catch (err) {
+      logger.warn({ err }, 'WebSocket bridge handshake failed')
+      socket.destroy()
+    }
+  }
+
+  /** Bearer header for CLI clients; `?token=` for environments that cannot set headers. */
+  private async authenticate(req: IncomingMessage): Promise<string | null> {
+    const header = req.headers.authorization
+    const token = header?.startsWith('Bearer ')
+      ? header.slice(7)
+      : new URL(req.url ?? '/', 'http://localhost').searchParams.get('token')
+    if (!token) return null
+    const hash = createHash('sha256').update(token).digest('hex')
+    const user = await this.runtime.repositories.users.getByApiKeyHash(hash)
+    return user?.id ?? null
+  }
+}
+
+class BridgeClient {
+  private readonly subscriptions = new Map<string, AsyncIterator<AgentEvent>>()
+  private readonly ownedSessions = new Map<string, boolean>()
+  private browser: BrowserExtensionConnection | null = null
+
+  constructor(
+    private readonly runtime: RuntimeContext,
+    private readonly connection: WebSocketConnection,
+    private readonly userId: string,
+  ) {
+    connection.on('message', (text) => {
+      void this.handle(text)
+    })
+  }
+
+  close(): void {
+    this.browser?.disconnect()
+    this.browser = null
+    for (const iterator of this.subscriptions.values()) void iterator.return?.()
+    this.subscriptions.clear()
+    this.connection.close()
+  }
+
+  private async handle(text: string): Promise<void> {
+    let command: BridgeCommand
+    try {
+      command = JSON.parse(text) as BridgeCommand
+    } catch {
+      this.reply(undefined, { ok: false, error: 'Messages must be JSON' })
+      return
+    }
+    try {
+      this.reply(command.id, { ok: true, ...await this.execute(command) })
+    } catch (err) {
+      if (!(err instanceof BridgeCommandError)) {
+        logger.warn({ err, type: command.type }, 'WebSocket bridge command failed')
+      }
+      this.reply(command.id, { ok: false, error: err instanceof Error ? err.message : String(err) })
+    }
+  }
+
+  private async execute(command: BridgeCommand): Promise<Record<string, unknown>> {
+    switch (command.type) {
+      case 'subscribe': {
+        if (command.sessionId && !(await this.owns(command.sessionId))) {
+          throw new BridgeCommandError(`Session not found: ${command.sessionId}`)
+        }
+        return { subscription: this.subscribe(command.sessionId, command.types) }
+      }
+
+      case 'unsubscribe': {
+        const iterator = this.subscriptions.get(command.subscription)
+        this.subscriptions.delete(command.subscription)
+        await iterator?.return?.()
+        return { subscription: command.subscription }
+      }
+
+      case 'send': {
+        if (typeof command.input !== 'string' || !command.input.trim()) {
+          throw new BridgeCommandError('input is required')
+        }
+        if (command.mode !== undefined && !isConversationMode(command.mode)) {
+          throw new BridgeCommandError(`mode must be one of: ${CONVERSATION_MODES.join(', ')}`)
+        }
+        return this.startTurn(command.input, command)
+      }
+
+      case 'approve': {
+        const agent = await this.runtime.repositories.agents.getById(command.agentId)
+        if (!agent || !(await this.owns(agent.sessionId))) {
+          throw new BridgeCommandError(`Agent not found: ${command.agentId}`)
+        }
+        if (command.decision !== 'approved' && command.decision !== 'denied') {
+          throw new BridgeCommandError("decision must be 'approved' or 'denied'")
+        }
+        const callIds = command.callIds ?? (command.callId ? [command.callId] : [])
+        if (callIds.length === 0) {
+          throw new BridgeCommandError('callId or callIds is required')
+        }
+        if (command.feedback !== undefined && (typeof command.feedback !== 'string' || command.decision !== 'denied')) {
+          throw new BridgeCommandError('feedback must be a string and is only accepted with a denial')
+        }
+        void deliverApprovals(
+          agent.id,
+          callIds,
+          command.decision,
+          buildDeps(this.runtime, agent.config.model),
+          command.scope,
+          command.amendedArgs,
+          command.feedback,
+        ).catch((err) => logger.warn({ err, agentId: agent.id }, 'Bridge approval failed'))
+        return { agentId: agent.id, sessionId: agent.sessionId }
+      }
+
+      case 'approvals_list_pending': {
+        if (!command.sessionId || !(await this.owns(command.sessionId))) {
+          throw new BridgeCommandError(`Session not found: ${command.sessionId}`)
+        }
+        return { approvals: await listPendingApprovals(this.runtime, command.sessionId) ?? [] }
+      }
+
+      case 'approvals_history':
+        return {
+          records: await this.runtime.repositories.approvalHistory.list({
+            userId: this.userId,
+            toolName: command.tool,
+            sessionId: command.sessionId,
+            from: command.from,
+            to: command.to,
+            limit: Math.min(Math.max(command.limit ?? 200, 1), 5000),
+          }),
+        }
+
+      case 'get_metrics':
+        return { metrics: this.runtime.metrics.snapshot() }
+
+      case 'models_list': {
+        const result = command.refresh ? await this.runtime.modelCatalog.refresh(command.provider) : null
+        return { models: this.runtime.modelCatalog.list(command.provider), ...(result && { errors: result.errors }) }
+      }
+
+      case 'actions_list':
+        return { actions: this.runtime.actions.list() }
+
+      case 'actions_execute':
+        try {
+          return {
+            result: await this.runtime.actions.execute(command.action, command.args, {
+              userId: this.userId,
+              signal: this.runtime.shutdownController.signal,
+            }),
+          }
+        } catch (err) {
+          if (err instanceof ActionError) throw new BridgeCommandError(err.message)
+          throw err
+        }
+
+      case 'browser_send': {
+        let page: BrowserPage
+        try {
+          page = parseBrowserPage(command.page)
+        } catch (err) {
+          throw new BridgeCommandError(err instanceof Error ? err.message : String(err))
+        }
+        if (command.instruction !== undefined && typeof command.instruction !== 'string') {
+          throw new BridgeCommandError('instruction must be a string')
+        }
+        const started = await this.startTurn(formatBrowserPage(page, command.instruction), command)
+        const subscription = this.subscribe(started.sessionId, command.types)
+        return { ...started, subscription }
+      }
+
+      case 'browser_register': {
+        const extensions = this.runtime.browserExtensions
+        if (!extensions) throw new BridgeCommandError('Browser extensions are not enabled')
+        this.browser?.disconnect()
+        this.browser = extensions.connect(this.userId, (request) => this.connection.send(JSON.stringify(request)))
+        return {}
+      }
+
+      case 'browser_result': {
+        if (!this.browser) throw new BridgeCommandError('Send browser_register first')
+        if (!this.browser.resolve(command.requestId, { ok: command.ok === true, result: command.result, error: command.error })) {
+          throw new BridgeCommandError(`No pending request: ${command.requestId}`)
+        }
+        return { requestId: command.requestId }
+      }
+
+      default:
+        throw new BridgeCommandError(`Unknown command: ${(command as { type?: string }).type}`)
+    }
+  }
+
+  private subscribe(sessionId: string | undefined, types: string[] | undefined): string {
+    const subscription = randomUUID()
+    const iterator = this.runtime.events
+      .subscribe({ session_id: sessionId, types })[Symbol.asyncIterator]()
+    this.subscriptions.set(subscription, iterator)
+    void this.pump(subscription, iterator)
+    return subscription
+  }
+
+  /** Adds the input to the conversation and starts a run unless one is already active. */
+  private async startTurn(
+    input: string,
+    options: { sessionId?: string; model?: string; agent?: string; idempotencyKey?: string; mode?: ConversationMode },
+  ): Promise<{ agentId: string | null; sessionId: string; status: string; busy?: ConversationBusy }> {
+    if (options.sessionId && !(await this.owns(options.sessionId))) {
+      throw new BridgeCommandError(`Session not found: ${options.sessionId}`)
+    }
+    let prepared: PreparedSessionTurn
+    try {
+      prepared = await prepareSessionTurn(this.runtime, {
+        userId: this.userId,
+        sessionId: options.sessionId,
+        model: options.model,
+        agent: options.agent,
+        input,
+        idempotencyKey: options.idempotencyKey,
+      })
+    } catch (err) {
+      // Nothing was saved; the client can point at the message still being handled
+      if (err instanceof ConversationBusyError) return { agentId: err.busy.agentId, sessionId: err.busy.sessionId, status: 'busy', busy: err.busy }
+      throw err
+    }
+    this.ownedSessions.set(prepared.sessionId, true)
+    if (prepared.status === 'active') {
+      return { agentId: prepared.agent.id, sessionId: prepared.sessionId, status: prepared.agent.status }
+    }
+
+    const { agent, sessionId, model } = prepared
+    const abort = new AbortController()
+    this.runtime.agentAbortControllers.set(agent.id, abort)
+    void runAgent(agent.id, buildDeps(this.runtime, model), {
+      stream: true,
+      signal: AbortSignal.any([this.runtime.shutdownController.signal, abort.signal]),
+      mode: options.mode,
+    })
+      .catch((err) => logger.warn({ err, agentId: agent.id }, 'Bridge run failed'))
+      .finally(() => this.runtime.agentAbortControllers.delete(agent.id))
+    return { agentId: agent.id, sessionId, status: 'running' }
+  }
+
+  private async pump(subscription: string, iterator: AsyncIterator<AgentEvent>): Promise<void> {
+    for (let next = await iterator.next(); !next.done; next = await iterator.next()) {
+      const event = next.value
+      // Unscoped subscriptions still only see this user's sessions
+      if (!(await this.owns(event.session_id))) continue
+      this.connection.send(JSON.stringify({
+        type: 'event',
+        subscription,
+        event: toWireEvent(event),
+      }))
+    }
+  }
+
+  private async owns(sessionId: string): Promise<boolean> {
+    const cached = this.ownedSessions.get(sessionId)
+    if (cached !== undefined) return cached
+    const session = await this.runtime.repositories.sessions.getById(sessionId)
+    // Unknown ids are not cached; the session may be created after this lookup
+    if (!session) return false
+    const owned = session.userId === this.userId
+    this.ownedSessions.set(sessionId, owned)
+    return owned
+  }
+
+  private reply(id: string | undefined, body: Record<string, unknown>): void {
+    this.connection.send(JSON.stringify({ id, type: 'response', ...body }))
+  }
+}
@@ -231,6 +231,7 @@ export class TaskRunner {
       status: 'running',
       result: null,
       error: null,
+      errorCode: null,
       completedAt: null,
     })
 
@@ -299,7 +300,10 @@ export class TaskRunner {
     const dir = path.join(getSessionFilesDir(this.runtime.sessionFilesRoot, sessionId), 'artifacts', 'task-runner')
     await fs.mkdir(dir, { recursive: true })
     const filename = `${safePathPart(taskId)}-result.md`
-    await fs.writeFile(path.join(dir, filename), output.endsWith('\n') ? output : `${output}\n`, 'utf-8')
+    const file = path.join(dir, filename)
+    const tmp = `${file}.${process.pid}.tmp`
+    await fs.writeFile(tmp, output.endsWith('\n') ? output : `${output}\n`, 'utf-8')
+    await fs.rename(tmp, file)
     return `artifact://task-runner/${filename}`
   }
 
@@ -0,0 +1,103 @@
+import assert from 'node:assert/strict'
+import type { Item, Session } from '../domain/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { LLMRequest } from '../providers/types.js'
+import { ActionError, ActionRegistry } from '../services/actions.js'
+import { registerBuiltinActions } from '../services/builtin-actions.js'
+
+const ctx = { userId: 'u1', signal: new AbortController().signal }
+const failure = async (promise: Promise<unknown>) => {
+  try {
+    await promise
+  } catch (err) {
+    assert.ok(err instanceof ActionError)
+    return err.message
+  }
+  assert.fail('action ran')
+}
+
+// Arguments are checked against the schema before the handler runs
+const registry = new ActionRegistry()
+const personas = ['planner']
+const seen: Array<Record<string, unknown>> = []
+registry.register({
+  id: 'demo.greet',
+  label: 'Greet',
+  parameters: () => ({
+    type: 'object',
+    properties: { persona: { type: 'string', enum: [...personas] }, times: { type: 'integer' } },
+    required: ['persona'],
+  }),
+  handle: async (args) => { seen.push(args); return { greeted: args.persona } },
+})
+assert.throws(() => registry.register({ id: 'demo.greet', label: 'Again', parameters: { type: 'object', properties: {} }, handle: async () => null }), /already registered/)
+assert.deepEqual(await registry.execute('demo.greet', { persona: 'planner', times: 2 }, ctx), { greeted: 'planner' })
+assert.match(await failure(registry.execute('demo.greet', { times: 1.5, loud: true }, ctx)), /persona is required; times must be an integer; loud is not an argument/)
+assert.match(await failure(registry.execute('demo.greet', { persona: 'pirate' }, ctx)), /persona must be one of: planner/)
+assert.match(await failure(registry.execute('demo.greet', ['planner'], ctx)), /args must be an object/)
+assert.match(await failure(registry.execute('demo.missing', {}, ctx)), /Unknown action: demo.missing/)
+assert.equal(seen.length, 1)
+
+// Choices are read again each time the actions are listed
+personas.push('pirate')
+assert.deepEqual(registry.list()[0].parameters.properties.persona.enum, ['planner', 'pirate'])
+assert.deepEqual(await registry.execute('demo.greet', { persona: 'pirate' }, ctx), { greeted: 'pirate' })
+
+// Built-in actions, against in-memory stores
+const sessions = new Map<string, Session>()
+const requests: LLMRequest[] = []
+const recorded: string[] = []
+const runtime = {
+  config: { defaultModel: 'openai:gpt-4o-mini' },
+  agentDefinitions: { list: () => [{ name: 'planner' }, { name: 'researcher' }] },
+  workflows: null,
+  providers: {
+    resolve: () => ({
+      generate: async (request: LLMRequest) => {
+        requests.push(request)
+        return { content: ' The user planned a Lisbon weekend; flights are still open. ', usage: { input_tokens: 50, output_tokens: 12 }, finish_reason: 'stop' }
+      },
+    }),
+  },
+  repositories: {
+    sessions: {
+      create: async (input: Partial<Session>) => {
+        const session = { id: `s${sessions.size + 1}`, summary: null, ...input } as Session
+        sessions.set(session.id, session)
+        return session
+      },
+      getById: async (id: string) => sessions.get(id) ?? null,
+      update: async (id: string, input: Partial<Session>) => Object.assign(sessions.get(id)!, input),
+    },
+    agents: { findRootAgent: async (sessionId: string) => ({ id: `${sessionId}-root` }) },
+    items: {
+      listByAgent: async () => [
+        { type: 'message', role: 'user', content: 'Plan a weekend in Lisbon' },
+        { type: 'function_call', role: null, content: null },
+        { type: 'message', role: 'assistant', content: 'Here is a plan; I could not book flights yet.' },
+      ] as Item[],
+    },
+  },
+  usage: { record: async (context: { phase: string }) => { recorded.push(context.phase) } },
+} as unknown as RuntimeContext
+const builtins = new ActionRegistry()
+registerBuiltinActions(builtins, runtime)
+// Playbooks are only offered when workflows are loaded
+assert.deepEqual(builtins.list().map((action) => action.id), ['conversation.new', 'conversation.export', 'conversation.summarize'])
+assert.deepEqual(builtins.list()[0].parameters.properties.agent.enum, ['planner', 'researcher'])
+
+// A new conversation without a message waits for the first send
+const created = await builtins.execute('conversation.new', { agent: 'researcher' }, ctx) as { sessionId: string; status: string }
+assert.deepEqual(created, { sessionId: 's1', agentId: null, status: 'created', agent: 'researcher' })
+assert.equal(sessions.get('s1')?.userId, 'u1')
+
+// Summaries are kept with the conversation, which must be the user's
+const summarized = await builtins.execute('conversation.summarize', { sessionId: 's1' }, ctx)
+assert.deepEqual(summarized, { sessionId: 's1', summary: 'The user planned a Lisbon weekend; flights are still open.' })
+assert.equal(sessions.get('s1')?.summary, 'The user planned a Lisbon weekend; flights are still open.')
+assert.match(String(requests[0].messages[0].content), /user: Plan a weekend in Lisbon\n\nassistant: Here is a plan/)
+assert.deepEqual(recorded, ['summary'])
+assert.match(await failure(builtins.execute('conversation.summarize', { sessionId: 's1' }, { ...ctx, userId: 'u2' })), /Session not found: s1/)
+assert.match(await failure(builtins.execute('conversation.export', { sessionId: 's1', format: 'pdf' }, ctx)), /format must be one of: markdown, html, conversation/)
+
+console.log('action tests passed')
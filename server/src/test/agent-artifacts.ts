@@ -0,0 +1,54 @@
+import assert from 'node:assert/strict'
+import fs from 'fs/promises'
+import os from 'os'
+import path from 'path'
+import { ToolRegistryImpl } from '../tools/registry.js'
+import { registerArtifactTools } from '../tools/artifacts.js'
+import { registerFileTools } from '../tools/files.js'
+import { listAgentArtifacts, readAgentArtifact } from '../services/agent-artifacts.js'
+
+const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), 'agent-artifacts-test-'))
+const sessionFilesRoot = path.join(tmpDir, 'sessions')
+
+try {
+  const registry = new ToolRegistryImpl()
+  registerArtifactTools(registry, { sessionFilesRoot })
+  registerFileTools(registry, { sessionFilesRoot, notesDir: path.join(tmpDir, 'notes') })
+  const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+
+  const ics = 'BEGIN:VCALENDAR\nVERSION:2.0\nEND:VCALENDAR\n'
+  const written = await registry.execute('artifacts.write', { name: 'trip.ics', content: ics }, ctx)
+  assert.equal(written.ok, true)
+  assert.deepEqual({ ...(written.output as Record<string, unknown>), createdAt: 0 }, {
+    ref: 'artifact://files/trip.ics', name: 'trip.ics', mediaType: 'text/calendar', bytes: ics.length, createdAt: 0,
+  })
+
+  // The agent can read its own artifact back through the managed path
+  const read = await registry.execute('files.read', { path: 'artifact://files/trip.ics' }, ctx)
+  assert.equal(read.ok, true)
+  assert.match(JSON.stringify(read.output), /BEGIN:VCALENDAR/)
+
+  // Binary content, path components stripped from the name, and no silent overwrite
+  const png = Buffer.from([0x89, 0x50, 0x4e, 0x47])
+  const chart = await registry.execute('artifacts.write', { name: '../../charts/q3:chart.png', content: png.toString('base64'), encoding: 'base64' }, ctx)
+  assert.equal((chart.output as { name: string }).name, 'q3-chart.png')
+  const again = await registry.execute('artifacts.write', { name: 'trip.ics', content: 'second' }, ctx)
+  assert.equal((again.output as { name: string }).name, 'trip (2).ics')
+
+  const downloaded = await readAgentArtifact(sessionFilesRoot, 'session', 'q3-chart.png')
+  assert.deepEqual(downloaded?.data, png)
+  assert.equal(downloaded?.artifact.mediaType, 'image/png')
+  assert.equal(await readAgentArtifact(sessionFilesRoot, 'session', '../session/artifacts/files/trip.ics'), null)
+  assert.deepEqual((await listAgentArtifacts(sessionFilesRoot, 'session')).map((a) => a.name).sort(), ['q3-chart.png', 'trip (2).ics', 'trip.ics'])
+  assert.deepEqual(await listAgentArtifacts(sessionFilesRoot, 'other-session'), [])
+
+  const empty = await registry.execute('artifacts.write', { name: 'empty.csv', content: '' }, ctx)
+  assert.equal(empty.ok, false)
+  assert.match(String(empty.error), /empty/)
+  const unnamed = await registry.execute('artifacts.write', { name: '../', content: 'x' }, ctx)
+  assert.equal(unnamed.ok, false)
+
+  console.log('Agent artifact tests passed')
+} finally {
+  await fs.rm(tmpDir, { recursive: true, force: true })
+}
@@ -0,0 +1,52 @@
+import assert from 'node:assert/strict'
+import { createAgent, failAgent, resumeAgent, startAgent } from '../domain/agent.js'
+import { AGENT_ERROR_CODES } from '../events/payloads.js'
+import { classifyError, classifyToolError, OfflineError, RunPersistenceError, ServerShutdownError } from '../orchestrator/errors.js'
+import { ProviderError } from '../providers/errors.js'
+import { BudgetExceededError } from '../usage/budget.js'
+
+const withStatus = (status: number, message: string) => Object.assign(new Error(message), { status })
+
+// Provider SDK errors carry the HTTP status
+assert.equal(classifyError(withStatus(401, 'invalid x-api-key')), 'provider_auth')
+assert.equal(classifyError(withStatus(403, 'forbidden')), 'provider_auth')
+assert.equal(classifyError(withStatus(429, 'Too many requests')), 'rate_limited')
+assert.equal(classifyError(withStatus(529, 'Overloaded')), 'provider_unavailable')
+assert.equal(classifyError(withStatus(400, 'prompt is too long: 210000 tokens > 200000 maximum')), 'context_overflow')
+
+// Fetch-based providers put the status in the message
+assert.equal(classifyError(new Error('OpenRouter 429: slow down')), 'rate_limited')
+assert.equal(classifyError(new Error('OpenRouter 502: bad gateway')), 'provider_unavailable')
+assert.equal(classifyError(new Error('Provider "openai" not found. Registered providers: (none).')), 'provider_auth')
+assert.equal(classifyError(new Error('fetch failed')), 'provider_unavailable')
+assert.equal(classifyError(new ProviderError('openrouter', 429, 'Too many requests')), 'rate_limited')
+assert.equal(classifyError(new BudgetExceededError([])), 'budget_exceeded')
+assert.equal(classifyError(new RunPersistenceError(new Error('SQLITE_FULL'))), 'persistence_failed')
+assert.equal(classifyError(new ServerShutdownError()), 'interrupted')
+assert.equal(classifyError(new OfflineError()), 'offline')
+assert.equal(classifyError(new Error('Agent reached maximum turn limit (500)')), 'unknown')
+assert.equal(classifyError('boom'), 'unknown')
+
+// Tool results
+assert.equal(classifyToolError({ ok: true }), undefined)
+assert.equal(classifyToolError({ ok: false, error: 'Command timed out' }), 'tool_timeout')
+assert.equal(classifyToolError({ ok: false, error: 'ENOENT' }), 'tool_failed')
+const timedOut = AbortSignal.abort(new DOMException('signal timed out', 'TimeoutError'))
+assert.equal(classifyToolError({ ok: false, error: 'Request aborted' }, timedOut), 'tool_timeout')
+assert.equal(classifyToolError({ ok: false, error: 'Request aborted' }, AbortSignal.abort()), 'tool_failed')
+
+// The code is kept on the agent and cleared when it resumes
+const agent = startAgent(createAgent({
+  id: 'agent-1',
+  sessionId: 'session-1',
+  task: 'test',
+  config: { model: 'openai:gpt-4o', provider: 'openai', max_turns: 5, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 },
+}))
+const failed = failAgent(agent, 'Too many requests', 'rate_limited')
+assert.equal(failed.errorCode, 'rate_limited')
+assert.equal(failAgent(agent, 'boom').errorCode, 'unknown')
+assert.equal(resumeAgent(failed).errorCode, null)
+
+for (const code of AGENT_ERROR_CODES) assert.match(code, /^[a-z_]+$/)
+
+console.log('agent error tests passed')
@@ -0,0 +1,41 @@
+import assert from 'node:assert/strict'
+import { buildAnthropicParams } from '../providers/anthropic.js'
+import { buildControllerMessages } from '../orchestrator/prompts.js'
+import type { LLMToolDefinition } from '../providers/types.js'
+
+const tools: LLMToolDefinition[] = [
+  { name: 'web.fetch', description: 'Fetch a URL', parameters: { type: 'object', properties: {} } },
+  { name: 'files.read', description: 'Read a file', parameters: { type: 'object', properties: {} } },
+]
+const messages = (task: string) => buildControllerMessages('CONTROLLER', '', [], { useNativeFunctionCalling: true, agentTask: task })
+
+// The fixed prompt gets its own breakpoint; the task follows uncached
+const params = buildAnthropicParams({ model: 'claude-sonnet-4-5', messages: messages('Plan the offsite'), tools })
+const [prefix, rest] = params.system as Array<{ text: string; cache_control?: unknown }>
+assert.equal(prefix.text, 'CONTROLLER')
+assert.deepEqual(prefix.cache_control, { type: 'ephemeral' })
+assert.match(rest.text, /## Current Task\nPlan the offsite/)
+assert.equal(rest.cache_control, undefined)
+const other = buildAnthropicParams({ model: 'claude-sonnet-4-5', messages: messages('Draft an update'), tools })
+assert.deepEqual((other.system as typeof params.system)![0], prefix)
+
+// The tools breakpoint stays on the last real tool when an output format is forced
+const toolsOf = (request: typeof params) => request.tools as Array<{ name: string; cache_control?: unknown }>
+assert.deepEqual(toolsOf(params).map((tool) => tool.cache_control), [undefined, { type: 'ephemeral' }])
+const structured = buildAnthropicParams({
+  model: 'claude-sonnet-4-5',
+  messages: messages('Plan the offsite'),
+  tools,
+  structured_output: { type: 'object', properties: {} },
+})
+assert.deepEqual(toolsOf(structured).slice(0, 2), toolsOf(params))
+assert.equal(toolsOf(structured)[2].name, '_structured_output')
+assert.equal(toolsOf(structured)[2].cache_control, undefined)
+assert.deepEqual(structured.system, params.system)
+
+// System messages without a stable prefix stay a single block
+const plain = buildAnthropicParams({ model: 'claude-sonnet-4-5', messages: [{ role: 'system', content: 'Summarize' }, { role: 'user', content: 'x' }] })
+assert.deepEqual(plain.system, [{ type: 'text', text: 'Summarize' }])
+assert.equal(plain.tools, undefined)
+
+console.log('Anthropic cache breakpoint tests passed')
@@ -0,0 +1,99 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { AgentEventEmitter } from '../events/emitter.js'
+import { countMutations, escalateApprovalBatch } from '../orchestrator/approval-batch.js'
+import { ApprovalBatchError, deliverApprovals } from '../orchestrator/delivery.js'
+import type { OrchestratorDeps } from '../orchestrator/types.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { unifiedDiff } from '../tools/diff.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+import type { ToolPreview } from '../tools/types.js'
+
+// Mutations come from the previews: changed lines, one per message, one per field
+const filePreview = (path: string, before: string, after: string): ToolPreview => ({
+  summary: `Write ${path}`,
+  change: { kind: 'file', path, operation: 'overwrite', ...unifiedDiff(path, before, after) },
+})
+assert.equal(countMutations(filePreview('a.md', 'one\ntwo\n', 'one\n2\nthree\n')), 3)
+assert.equal(countMutations({ summary: 'Send', change: { kind: 'message', to: ['a@example.com'], subject: 's', body: 'b' } }), 1)
+assert.equal(countMutations({ summary: 'Update', change: { kind: 'fields', target: 'event', changes: [
+  { field: 'title', before: 'a', after: 'b' },
+  { field: 'start', before: 1, after: 2 },
+] } }), 2)
+assert.equal(countMutations(undefined), 1)
+
+const limits = { maxCalls: 3, maxMutations: 10 }
+const calls = (n: number) => Array.from({ length: n }, (_, i) => ({ callId: `c${i}`, name: 'shell.run' }))
+assert.equal(escalateApprovalBatch(calls(3), limits), null)
+assert.equal(escalateApprovalBatch(calls(4), undefined), null)
+assert.equal(escalateApprovalBatch(calls(4), { maxCalls: 0, maxMutations: 0 }), null, '0 turns the checks off')
+
+const byCount = escalateApprovalBatch(calls(4), limits)
+assert.equal(byCount?.reason, 'calls')
+assert.deepEqual(byCount?.callIds, ['c0', 'c1', 'c2', 'c3'])
+assert.equal(byCount?.summary, '4 calls (shell.run ×4), 4 changes')
+
+const big = Array.from({ length: 12 }, (_, i) => `line ${i}`).join('\n') + '\n'
+const bySize = escalateApprovalBatch([
+  { callId: 'w1', name: 'files.write', preview: filePreview('big.md', '', big) },
+  { callId: 'w2', name: 'shell.run' },
+], limits)
+assert.equal(bySize?.reason, 'mutations')
+assert.equal(bySize?.mutations, 13)
+assert.equal(bySize?.summary, '2 calls (files.write, shell.run), 13 changes across 1 file')
+assert.equal(escalateApprovalBatch([{ callId: 'w1', name: 'files.write', preview: filePreview('big.md', '', big) }], limits), null, 'a single call is never escalated')
+
+// An escalated batch is approved whole or not at all
+const dir = mkdtempSync(join(tmpdir(), 'approval-batch-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'batch.db')))
+  const tools = new ToolRegistryImpl()
+  const ran: string[] = []
+  tools.register({
+    metadata: { name: 'shell.run', description: 'shell.run', parameters: { type: 'object', properties: {} }, requires_approval: true },
+    async handle(args) {
+      ran.push(String(args.n))
+      return { ok: true, output: 'done' }
+    },
+  })
+  const deps = {
+    agents: repos.agents,
+    items: repos.items,
+    toolOutputs: repos.toolOutputs,
+    preferences: repos.preferences,
+    tools,
+    events: new AgentEventEmitter(),
+    sessionFilesRoot: join(dir, 'files'),
+  } as unknown as OrchestratorDeps
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Batch' })
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 5, tool_execution_timeout_ms: 1000 }
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Escalated', config })
+  const batch = escalateApprovalBatch(calls(4), limits)!
+  for (const callId of batch.callIds) {
+    await repos.items.create({ agentId: agent.id, type: 'function_call', callId, name: 'shell.run', arguments: JSON.stringify({ n: callId }), turnNumber: 1 })
+  }
+  await repos.agents.update(agent.id, {
+    status: 'waiting',
+    waitingFor: [
+      ...batch.callIds.map((callId) => ({ callId, type: 'approval' as const, name: 'shell.run', batch })),
+      { callId: 'child', type: 'agent' as const, name: 'delegate' },
+    ],
+  })
+  assert.deepEqual((await repos.agents.getById(agent.id))?.waitingFor[0].batch, batch, 'the batch survives storage')
+
+  await assert.rejects(deliverApprovals(agent.id, ['c0', 'c1'], 'approved', deps, 'once'), ApprovalBatchError)
+  assert.deepEqual(ran, [], 'a refused partial approval runs nothing')
+
+  const approved = await deliverApprovals(agent.id, ['c0', 'c1', 'c2', 'c3'], 'approved', deps, 'once')
+  assert.equal(approved.status, 'waiting')
+  assert.deepEqual(ran, ['c0', 'c1', 'c2', 'c3'])
+  assert.deepEqual((await repos.agents.getById(agent.id))?.waitingFor.map((w) => w.callId), ['child'])
+
+  console.log('approval batch tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,42 @@
+import assert from 'node:assert/strict'
+import os from 'node:os'
+import path from 'node:path'
+import {
+  defaultApprovalAction,
+  normalizeCategoryDefaults,
+  parseCategoryDefaults,
+} from '../orchestrator/approval-categories.js'
+import { registerFileTools } from '../tools/files.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+// Built-in defaults: read-only runs, destructive asks, mutating keeps the tool's flag
+assert.equal(defaultApprovalAction({ requires_approval: true, category: 'read_only' }, {}), 'allow')
+assert.equal(defaultApprovalAction({ requires_approval: false, category: 'destructive' }, {}), 'ask')
+assert.equal(defaultApprovalAction({ requires_approval: true, category: 'mutating' }, {}), 'ask')
+assert.equal(defaultApprovalAction({ requires_approval: false, category: 'mutating' }, {}), 'allow')
+assert.equal(defaultApprovalAction({ requires_approval: true }, { mutating: 'allow' }), 'ask', 'uncategorized tools keep their flag')
+assert.equal(defaultApprovalAction(undefined, {}), 'allow')
+
+// Global policy flips a whole category
+assert.equal(defaultApprovalAction({ requires_approval: true, category: 'mutating' }, { mutating: 'allow' }), 'allow')
+assert.equal(defaultApprovalAction({ requires_approval: false, category: 'read_only' }, { read_only: 'ask' }), 'ask')
+assert.equal(defaultApprovalAction({ requires_approval: true, category: 'destructive' }, { destructive: 'allow' }), 'allow')
+
+assert.deepEqual(normalizeCategoryDefaults({ read_only: 'ask', mutating: null }), { read_only: 'ask' })
+assert.throws(() => normalizeCategoryDefaults({ harmless: 'allow' }), /category must be one of/)
+assert.throws(() => normalizeCategoryDefaults({ mutating: 'deny' }), /must be 'ask', 'allow' or null/)
+assert.throws(() => normalizeCategoryDefaults(['ask']), /object keyed by category/)
+assert.deepEqual(parseCategoryDefaults('{"destructive":"allow"}'), { destructive: 'allow' })
+assert.deepEqual(parseCategoryDefaults('not json'), {})
+assert.deepEqual(parseCategoryDefaults(null), {})
+
+// File tools declare what they do
+const registry = new ToolRegistryImpl()
+const tmp = os.tmpdir()
+registerFileTools(registry, { sessionFilesRoot: path.join(tmp, 'sessions'), notesDir: path.join(tmp, 'notes') })
+assert.equal(registry.getMetadata('files.read')?.category, 'read_only')
+assert.equal(registry.getMetadata('files.list')?.category, 'read_only')
+assert.equal(registry.getMetadata('files.edit')?.category, 'mutating')
+assert.equal(registry.getMetadata('files.write')?.category, 'destructive')
+
+console.log('approval category tests passed')
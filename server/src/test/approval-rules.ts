@@ -0,0 +1,46 @@
+import assert from 'node:assert/strict'
+import os from 'node:os'
+import path from 'node:path'
+import { matchApprovalRule, normalizeApprovalRule, parseApprovalRules, type ApprovalRule } from '../orchestrator/approval-rules.js'
+
+const rules: ApprovalRule[] = [
+  normalizeApprovalRule({ id: 'ssh', tool: 'files.*', when: [{ arg: 'path', op: 'glob', value: '*.ssh*' }], action: 'deny' }),
+  normalizeApprovalRule({ id: 'projects', tool: 'files.read', when: [{ arg: 'path', op: 'under', value: '~/Projects' }], action: 'allow' }),
+  normalizeApprovalRule({ id: 'shell', tool: 'shell.run', action: 'ask', description: '  always confirm shell  ' }),
+  normalizeApprovalRule({
+    id: 'git',
+    tool: 'shell.*',
+    when: [{ arg: 'command', op: 'regex', value: '^git (status|log)\\b' }, { arg: 'options.cwd', op: 'exists' }],
+    action: 'allow',
+  }),
+]
+const home = os.homedir()
+const match = (tool: string, args: Record<string, unknown>) => matchApprovalRule(rules, tool, args)?.id ?? null
+
+assert.equal(match('files.read', { path: path.join(home, 'Projects', 'app', 'README.md') }), 'projects')
+assert.equal(match('files.read', { path: '~/Projects' }), 'projects', 'the directory itself counts')
+assert.equal(match('files.read', { path: path.join(home, 'ProjectsOld', 'x') }), null, 'sibling prefixes do not match')
+assert.equal(match('files.read', { path: '~/Projects/../Documents/tax.pdf' }), null, 'traversal is normalized away')
+assert.equal(match('files.read', { path: 'Projects/x' }), null, 'relative paths never fall under an absolute directory')
+assert.equal(match('files.write', { path: '~/Projects/x' }), null, 'the tool pattern must match')
+assert.equal(match('files.read', { path: '~/Projects/.ssh/id_rsa' }), 'ssh', 'first match wins')
+
+assert.equal(match('shell.run', { command: 'git status' }), 'shell')
+assert.equal(matchApprovalRule(rules, 'shell.run', {})?.description, 'always confirm shell')
+assert.equal(matchApprovalRule(rules.slice(3), 'shell.run', { command: 'git log -1', options: { cwd: '/tmp' } })?.id, 'git')
+assert.equal(matchApprovalRule(rules.slice(3), 'shell.run', { command: 'git push', options: { cwd: '/tmp' } }), null)
+assert.equal(matchApprovalRule(rules.slice(3), 'shell.run', { command: 'git log' }), null, 'every predicate must hold')
+
+// Non-string args compare as JSON
+const numeric = [normalizeApprovalRule({ id: 'n', tool: 'web.search', when: [{ arg: 'limit', op: 'equals', value: '5' }], action: 'allow' })]
+assert.equal(matchApprovalRule(numeric, 'web.search', { limit: 5 })?.id, 'n')
+
+// Validation
+assert.throws(() => normalizeApprovalRule({ id: 'x', tool: 'a', action: 'maybe' as never }), /action must be one of/)
+assert.throws(() => normalizeApprovalRule({ id: 'x', tool: '', action: 'allow' }), /tool/)
+assert.throws(() => normalizeApprovalRule({ id: 'x', tool: 'a', action: 'allow', when: [{ arg: 'p', op: 'under' }] }), /needs a string value/)
+assert.throws(() => normalizeApprovalRule({ id: 'x', tool: 'a', action: 'allow', when: [{ arg: 'p', op: 'regex', value: '(' }] }), /invalid regex/)
+assert.deepEqual(parseApprovalRules('not json'), [])
+assert.deepEqual(parseApprovalRules(JSON.stringify(rules)), rules)
+
+console.log('approval rule tests passed')
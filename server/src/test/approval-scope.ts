@@ -0,0 +1,157 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { AgentEventEmitter } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import { ApprovalHistory } from '../orchestrator/approval-history.js'
+import { ApprovalAmendmentError, deliverApproval, deliverApprovals } from '../orchestrator/delivery.js'
+import type { OrchestratorDeps } from '../orchestrator/types.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'approval-scope-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'approvals.db')))
+  const tools = new ToolRegistryImpl()
+  const ran: string[] = []
+  for (const name of ['shell.run', 'files.write']) {
+    tools.register({
+      metadata: { name, description: name, parameters: { type: 'object', properties: {} }, requires_approval: true },
+      async handle(args) {
+        ran.push(`${name}:${String(args.n)}`)
+        return { ok: true, output: 'done' }
+      },
+    })
+  }
+  const events = new AgentEventEmitter()
+  const emitted: AgentEvent[] = []
+  const iterator = events.subscribe({})[Symbol.asyncIterator]()
+  const deps = {
+    agents: repos.agents,
+    items: repos.items,
+    toolOutputs: repos.toolOutputs,
+    preferences: repos.preferences,
+    tools,
+    events,
+    sessionFilesRoot: join(dir, 'files'),
+    approvals: new ApprovalHistory(repos.approvalHistory, repos.sessions),
+  } as unknown as OrchestratorDeps
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Approvals' })
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 3, tool_execution_timeout_ms: 1000 }
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Batch', config })
+  const calls = [['c1', 'shell.run'], ['c2', 'shell.run'], ['c3', 'files.write']] as const
+  for (const [callId, name] of calls) {
+    await repos.items.create({ agentId: agent.id, type: 'function_call', callId, name, arguments: JSON.stringify({ n: callId }), turnNumber: 1 })
+  }
+  await repos.agents.update(agent.id, {
+    status: 'waiting',
+    waitingFor: calls.map(([callId, name]) => ({ callId, type: 'approval' as const, name })),
+  })
+
+  // Approving one shell call for the conversation settles the other pending shell call too
+  const result = await deliverApproval(agent.id, 'c1', 'approved', deps, 'conversation')
+  assert.equal(result.status, 'waiting')
+  assert.deepEqual(result.waitingFor?.map((w) => w.callId), ['c3'], 'unrelated tools still wait')
+  assert.deepEqual(ran, ['shell.run:c1', 'shell.run:c2'])
+  assert.equal(await repos.preferences.get(`tool_approval_session:${session.id}:shell.run`), 'false')
+
+  const outputs = (await repos.items.listByAgent(agent.id)).filter((i) => i.type === 'function_call_output')
+  assert.deepEqual(outputs.map((i) => i.callId), ['c1', 'c2'])
+
+  for (let next = await iterator.next(); !next.done; next = await iterator.next()) {
+    emitted.push(next.value)
+    if (emitted.filter((e) => e.type === EVENT_TYPES.TOOL_COMPLETED).length === 2) break
+  }
+  const approved = emitted.filter((e) => e.type === EVENT_TYPES.TOOL_APPROVED)
+  assert.deepEqual(approved.map((e) => e.type === EVENT_TYPES.TOOL_APPROVED && e.payload.callId), ['c1', 'c2'])
+  await iterator.return!()
+
+  const waitingAgent = async (task: string, ids: string[]) => {
+    const created = await repos.agents.create({ sessionId: session.id, task, config })
+    for (const callId of ids) {
+      await repos.items.create({ agentId: created.id, type: 'function_call', callId, name: 'files.write', arguments: JSON.stringify({ n: callId }), turnNumber: 1 })
+    }
+    await repos.agents.update(created.id, {
+      status: 'waiting',
+      waitingFor: ids.map((callId) => ({ callId, type: 'approval' as const, name: 'files.write' })),
+    })
+    return created
+  }
+
+  // Approving a listed subset runs exactly those calls
+  ran.length = 0
+  const batch = await waitingAgent('Approve batch', ['d1', 'd2', 'd3'])
+  const partial = await deliverApprovals(batch.id, ['d1', 'd2', 'd1'], 'approved', deps, 'once')
+  assert.deepEqual(partial.waitingFor?.map((w) => w.callId), ['d3'])
+  assert.deepEqual(ran, ['files.write:d1', 'files.write:d2'], 'duplicate ids run once')
+  await assert.rejects(deliverApprovals(batch.id, ['nope'], 'approved', deps), /not waiting for callId 'nope'/)
+
+  // Denying a batch stops the agent and cancels whatever was not listed
+  const denied = await waitingAgent('Deny batch', ['e1', 'e2', 'e3'])
+  const stopped = await deliverApprovals(denied.id, ['e1', 'e2'], 'denied', deps)
+  assert.equal(stopped.status, 'completed')
+  const deniedOutputs = (await repos.items.listByAgent(denied.id)).filter((i) => i.type === 'function_call_output')
+  assert.deepEqual(deniedOutputs.map((i) => [i.callId, i.output]), [
+    ['e1', 'Tool execution denied by user'],
+    ['e2', 'Tool execution denied by user'],
+    ['e3', 'Tool execution denied by user (batch cancelled)'],
+  ])
+
+  // Every decision lands in the approval history, queryable by tool, outcome and date
+  const history = await repos.approvalHistory.list({ userId: user.id })
+  assert.equal(history.length, 7)
+  const shell = await repos.approvalHistory.list({ toolName: 'shell.run' })
+  assert.deepEqual(shell.map((r) => [r.callId, r.outcome, r.scope]).sort(), [['c1', 'approved', 'conversation'], ['c2', 'approved', 'conversation']])
+  assert.deepEqual((await repos.approvalHistory.list({ outcome: 'denied', sessionId: session.id })).map((r) => r.callId).sort(), ['e1', 'e2', 'e3'])
+  assert.ok(history.every((r) => r.latencyMs >= 0 && r.requestedAt <= r.decidedAt))
+  assert.deepEqual(await repos.approvalHistory.list({ from: Date.now() + 60_000 }), [])
+  assert.equal((await repos.approvalHistory.list({ limit: 2 })).length, 2)
+
+  // Approving with edited args runs the edit, tells the model, and keeps both in the history
+  ran.length = 0
+  const amended = await waitingAgent('Amend', ['f1', 'f2'])
+  await assert.rejects(deliverApproval(amended.id, 'f1', 'denied', deps, 'once', { n: 'x' }), ApprovalAmendmentError)
+  await assert.rejects(deliverApprovals(amended.id, ['f1'], 'approved', deps, 'once', { f2: { n: 'x' } }), ApprovalAmendmentError)
+  await assert.rejects(
+    deliverApproval(amended.id, 'f1', 'approved', deps, 'once', [] as unknown as Record<string, unknown>),
+    ApprovalAmendmentError,
+  )
+  assert.deepEqual(ran, [], 'rejected amendments run nothing')
+  assert.equal((await repos.agents.getById(amended.id))?.waitingFor.length, 2)
+
+  await deliverApproval(amended.id, 'f1', 'approved', deps, 'once', { n: 'edited' })
+  assert.deepEqual(ran, ['files.write:edited'])
+  const amendedOutput = (await repos.items.listByAgent(amended.id)).find((i) => i.type === 'function_call_output' && i.callId === 'f1')
+  assert.match(String(amendedOutput?.output), /^Note: the user edited the arguments before approving\. The call ran with \{"n":"edited"\}/)
+  const record = (await repos.approvalHistory.list({ sessionId: session.id })).find((r) => r.callId === 'f1')
+  assert.deepEqual(record?.amendedArgs, { n: 'edited' })
+  assert.equal((await repos.approvalHistory.list({ sessionId: session.id })).filter((r) => r.amendedArgs).length, 1)
+
+  // Denying with feedback hands it to the model instead of stopping; other waits stay open
+  const guided = await repos.agents.create({ sessionId: session.id, task: 'Feedback', config })
+  for (const callId of ['g1', 'g2']) {
+    await repos.items.create({ agentId: guided.id, type: 'function_call', callId, name: 'files.write', arguments: '{}', turnNumber: 1 })
+  }
+  await repos.agents.update(guided.id, {
+    status: 'waiting',
+    waitingFor: [
+      { callId: 'g1', type: 'approval', name: 'files.write' },
+      { callId: 'g2', type: 'approval', name: 'files.write' },
+      { callId: 'g3', type: 'agent', name: 'delegate' },
+    ],
+  })
+  const redirected = await deliverApproval(guided.id, 'g1', 'denied', deps, undefined, undefined, '  Write it to notes/plan.md instead  ')
+  assert.equal(redirected.status, 'waiting', 'the agent is not completed')
+  assert.deepEqual(redirected.waitingFor?.map((w) => w.callId), ['g3'])
+  const guidedOutputs = (await repos.items.listByAgent(guided.id)).filter((i) => i.type === 'function_call_output')
+  assert.match(String(guidedOutputs.find((i) => i.callId === 'g1')?.output), /^Tool execution denied by user\. The user's feedback:\nWrite it to notes\/plan\.md instead\n/)
+  assert.match(String(guidedOutputs.find((i) => i.callId === 'g2')?.output), /cancelled along with a denied call/)
+  assert.ok(!(await repos.items.listByAgent(guided.id)).some((i) => i.type === 'message'), 'no canned stopping message')
+
+  console.log('approval scope tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
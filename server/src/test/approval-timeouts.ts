@@ -0,0 +1,101 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { setTimeout as sleep } from 'node:timers/promises'
+import { UNPACED, AgentEventEmitter } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { ApprovalHistory } from '../orchestrator/approval-history.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import {
+  APPROVAL_TIMEOUT_PREFIX,
+  ApprovalTimeouts,
+  normalizeApprovalTimeout,
+  resolveApprovalTimeout,
+} from '../services/approval-timeouts.js'
+
+assert.equal(normalizeApprovalTimeout(0), 0)
+assert.equal(normalizeApprovalTimeout(60_000), 60_000)
+assert.throws(() => normalizeApprovalTimeout(-1), /non-negative integer/)
+assert.throws(() => normalizeApprovalTimeout('60000'), /non-negative integer/)
+assert.throws(() => normalizeApprovalTimeout(100), /at least 5000/)
+
+const dir = mkdtempSync(join(tmpdir(), 'approval-timeouts-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'timeouts.db')))
+  const events = new AgentEventEmitter()
+  const runtime = {
+    events,
+    repositories: repos,
+    config: { approvalTimeoutMs: 0 },
+    providers: { resolve: () => ({}) },
+    approvalHistory: new ApprovalHistory(repos.approvalHistory, repos.sessions),
+    sessionFilesRoot: join(dir, 'files'),
+  } as unknown as RuntimeContext
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Timeouts' })
+  const config = { model: 'test:model', provider: 'test', max_turns: 1, max_tool_calls_per_step: 3, tool_execution_timeout_ms: 1000 }
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Wait', config })
+  for (const callId of ['a1', 'a2']) {
+    await repos.items.create({ agentId: agent.id, type: 'function_call', callId, name: 'files.write', arguments: '{}', turnNumber: 1 })
+  }
+  await repos.agents.update(agent.id, {
+    status: 'waiting',
+    waitingFor: ['a1', 'a2'].map((callId) => ({ callId, type: 'approval' as const, name: 'files.write' })),
+  })
+
+  // The conversation override wins over the default
+  assert.deepEqual(await resolveApprovalTimeout(repos.preferences, session.id, 0), { timeoutMs: 0, source: 'default' })
+  await repos.preferences.set(`${APPROVAL_TIMEOUT_PREFIX}${session.id}`, '200')
+  assert.deepEqual(await resolveApprovalTimeout(repos.preferences, session.id, 0), { timeoutMs: 200, source: 'conversation' })
+
+  const seen: AgentEvent[] = []
+  const iterator = events.subscribe({
+    types: [EVENT_TYPES.TOOL_APPROVAL_REMINDER, EVENT_TYPES.TOOL_APPROVAL_EXPIRED],
+  }, UNPACED)[Symbol.asyncIterator]()
+  void (async () => {
+    for (let next = await iterator.next(); !next.done; next = await iterator.next()) seen.push(next.value)
+  })()
+
+  const timeouts = new ApprovalTimeouts(runtime)
+  timeouts.start()
+  const propose = (callId: string) => events.emit({
+    type: EVENT_TYPES.TOOL_PROPOSED,
+    agent_id: agent.id,
+    session_id: session.id,
+    payload: { callId, name: 'files.write', args: {}, parentId: null, depth: 0 },
+    timestamp: Date.now(),
+  })
+  propose('a1')
+  propose('a2')
+  // Answering a call stops its clock
+  events.emit({ type: EVENT_TYPES.TOOL_APPROVED, agent_id: agent.id, session_id: session.id, payload: { callId: 'a2', name: 'files.write' }, timestamp: Date.now() })
+
+  await sleep(400)
+  timeouts.stop()
+  await iterator.return!()
+
+  const reminders = seen.filter((e) => e.type === EVENT_TYPES.TOOL_APPROVAL_REMINDER)
+  assert.deepEqual(reminders.map((e) => e.type === EVENT_TYPES.TOOL_APPROVAL_REMINDER && [e.payload.callId, e.payload.elapsedMs, e.payload.remainingMs]), [
+    ['a1', 100, 100],
+    ['a1', 180, 20],
+  ])
+  const expired = seen.filter((e) => e.type === EVENT_TYPES.TOOL_APPROVAL_EXPIRED)
+  assert.deepEqual(expired.map((e) => e.type === EVENT_TYPES.TOOL_APPROVAL_EXPIRED && e.payload.callId), ['a1'])
+  assert.equal(timeouts.pending, 0)
+
+  // The expired call gets a distinct error; the agent still waits on the other one
+  const after = await repos.agents.getById(agent.id)
+  assert.equal(after?.status, 'waiting')
+  assert.deepEqual(after?.waitingFor.map((w) => w.callId), ['a2'])
+  const output = (await repos.items.listByAgent(agent.id)).find((i) => i.type === 'function_call_output' && i.callId === 'a1')
+  assert.equal(output?.isError, true)
+  assert.match(output?.output ?? '', /^approval_expired: /)
+  assert.deepEqual((await repos.approvalHistory.list({ outcome: 'expired' })).map((r) => r.callId), ['a1'])
+
+  console.log('approval timeout tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,46 @@
+import assert from 'node:assert/strict'
+import os from 'node:os'
+import path from 'node:path'
+import { defaultApprovalAction } from '../orchestrator/approval-categories.js'
+import { matchApprovalRule, matchApprovalTrigger, normalizeApprovalRule } from '../orchestrator/approval-rules.js'
+import { outsideRootsTrigger, registerFileTools } from '../tools/files.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+// `not` inverts a predicate, so rules can say "outside this directory"
+const outsideDrafts = normalizeApprovalRule({ id: 'r1', tool: 'files.*', when: [{ arg: 'path', op: 'under', value: 'drafts', not: true }], action: 'ask' })
+assert.equal(outsideDrafts.when[0].not, true)
+assert.equal(matchApprovalRule([outsideDrafts], 'files.write', { path: 'drafts/a.md' }), null)
+assert.equal(matchApprovalRule([outsideDrafts], 'files.write', { path: 'drafts/../notes/a.md' })?.id, 'r1')
+assert.equal(matchApprovalRule([outsideDrafts], 'files.write', {})?.id, 'r1', 'a missing path is outside')
+assert.throws(() => normalizeApprovalRule({ tool: '*', when: [{ arg: 'path', op: 'exists', not: 'yes' as never }], action: 'ask' }))
+
+const triggers = outsideRootsTrigger(['./drafts/', ' ', 'notes/scratch'])!
+assert.equal(triggers[0].reason, 'The path is outside drafts/, notes/scratch/.')
+assert.equal(matchApprovalTrigger(triggers, { path: 'drafts/today.md' }), null)
+assert.equal(matchApprovalTrigger(triggers, { path: 'notes/scratch/x.md' }), null)
+assert.equal(matchApprovalTrigger(triggers, { path: 'notes/final.md' }), triggers[0])
+assert.equal(outsideRootsTrigger([]), undefined)
+assert.equal(outsideRootsTrigger(undefined), undefined)
+
+// Triggers replace the tool's flag and built-in default; a category policy still wins
+const write = { requires_approval: true, category: 'destructive' as const, approval_triggers: triggers }
+assert.equal(defaultApprovalAction(write, {}, { path: 'drafts/today.md' }), 'allow')
+assert.equal(defaultApprovalAction(write, {}, { path: 'secrets/keys.txt' }), 'ask')
+assert.equal(defaultApprovalAction(write, { destructive: 'ask' }, { path: 'drafts/today.md' }), 'ask')
+assert.equal(defaultApprovalAction(write, { destructive: 'allow' }, { path: 'secrets/keys.txt' }), 'allow')
+assert.equal(defaultApprovalAction({ requires_approval: false, approval_triggers: [] }, {}, {}), 'allow')
+
+// File tools pick the triggers up from their options
+const tmp = os.tmpdir()
+const plain = new ToolRegistryImpl()
+registerFileTools(plain, { sessionFilesRoot: path.join(tmp, 'sessions'), notesDir: path.join(tmp, 'notes') })
+assert.equal(plain.getMetadata('files.write')?.approval_triggers, undefined)
+
+const scoped = new ToolRegistryImpl()
+registerFileTools(scoped, { sessionFilesRoot: path.join(tmp, 'sessions'), notesDir: path.join(tmp, 'notes'), approvalFreeRoots: ['drafts'] })
+for (const name of ['files.write', 'files.edit', 'files.create', 'files.append']) {
+  assert.equal(scoped.getMetadata(name)?.approval_triggers?.[0].reason, 'The path is outside drafts/.', name)
+}
+assert.equal(scoped.getMetadata('files.read')?.approval_triggers, undefined)
+
+console.log('approval trigger tests passed')
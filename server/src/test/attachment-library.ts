@@ -0,0 +1,72 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { AttachmentStore } from '../lib/attachment-store.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { attachFromLibrary, listLibrary, recordUpload, removeFromLibrary } from '../services/attachment-library.js'
+import { extractTextFile } from '../services/text-extraction.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'attachment-library-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'library.db')))
+  const runtime = {
+    repositories: repos,
+    attachments: new AttachmentStore(join(dir, 'attachments')),
+  } as unknown as RuntimeContext
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+
+  const alice = await repos.users.create({ apiKeyHash: 'alice' })
+  const bob = await repos.users.create({ apiKeyHash: 'bob' })
+  const upload = async (userId: string, text: string, name: string) => {
+    const extraction = await extractTextFile(runtime, { bytes: Buffer.from(text), fileName: name })
+    await recordUpload(runtime, { userId, hash: extraction.hash, name, mediaType: 'text/plain', kind: 'text', bytes: extraction.bytes })
+    return extraction
+  }
+
+  // Uploading the same file twice keeps one entry and its original name
+  const notes = await upload(alice.id, 'Meeting notes', 'notes.txt')
+  await upload(alice.id, 'Meeting notes', 'notes-copy.txt')
+  const draft = await upload(alice.id, 'Draft', 'draft.txt')
+  await upload(bob.id, 'Meeting notes', 'bob.txt')
+
+  const session = await repos.sessions.create({ userId: alice.id, title: 'Planning' })
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Plan', config })
+  for (let i = 0; i < 2; i++) {
+    await repos.items.create({
+      agentId: agent.id, type: 'message', role: 'user', content: 'See attached', turnNumber: i,
+      contentBlocks: [{ type: 'document', hash: notes.hash, name: 'notes.txt', chunks: notes.chunks }],
+    })
+  }
+
+  const library = await listLibrary(runtime, alice.id)
+  assert.deepEqual(library.map((entry) => [entry.name, entry.references]).sort(), [['draft.txt', 0], ['notes.txt', 2]])
+  assert.equal((await listLibrary(runtime, bob.id))[0].references, 0, 'references only count the user\'s own messages')
+
+  // Attaching reuses the stored extraction without touching the payload
+  await new Promise((resolve) => setTimeout(resolve, 5))
+  const attached = await attachFromLibrary(runtime, alice.id, draft.hash)
+  assert.deepEqual(attached?.extraction, draft)
+  assert.equal(attached?.upload.name, 'draft.txt')
+  assert.equal(await attachFromLibrary(runtime, bob.id, draft.hash), null, 'other users cannot attach it')
+  assert.equal((await listLibrary(runtime, alice.id))[0].hash, draft.hash, 'most recently used first')
+
+  // Payloads go only once nothing references them
+  assert.deepEqual(await removeFromLibrary(runtime, alice.id, draft.hash), { removed: true, purged: true })
+  assert.equal(await runtime.attachments.get(draft.hash), null)
+  assert.equal(await runtime.attachments.getExtraction(draft.hash), null)
+
+  assert.deepEqual(await removeFromLibrary(runtime, alice.id, notes.hash), { removed: true, purged: false })
+  assert.deepEqual(await removeFromLibrary(runtime, bob.id, notes.hash), { removed: true, purged: false })
+  assert.ok(await runtime.attachments.get(notes.hash), 'messages still reference it')
+  assert.deepEqual(await removeFromLibrary(runtime, bob.id, notes.hash), { removed: false, purged: false })
+
+  // Library entries alone keep a payload out of orphan cleanup
+  const kept = await upload(bob.id, 'Only in the library', 'kept.txt')
+  assert.ok((await repos.orphans.listAttachmentHashes()).includes(kept.hash))
+
+  console.log('attachment library tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
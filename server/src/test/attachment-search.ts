@@ -0,0 +1,101 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { Item, ItemContentBlock } from '../domain/types.js'
+import { AttachmentStore } from '../lib/attachment-store.js'
+import { buildControllerMessages } from '../orchestrator/prompts.js'
+import type { LLMEmbeddingRequest } from '../providers/types.js'
+import type { ItemRepository } from '../repositories/types.js'
+import { prepareLargeAttachments, type AttachmentSearchDeps } from '../services/attachment-index.js'
+import { resolveDocumentBlocks } from '../services/pdf-extraction.js'
+import { extractTextFile, TextInputError } from '../services/text-extraction.js'
+import { registerAttachmentTools } from '../tools/attachments.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const TOPICS = ['invoice', 'kubernetes', 'holiday']
+
+/** Embeds text as the count of each topic word, so similarity follows topic. */
+function fakeEmbedding(text: string): number[] {
+  return TOPICS.map((topic) => text.toLowerCase().split(topic).length - 1)
+}
+
+const paragraph = (topic: string, n: number) => `Section ${n} talks about ${topic}. ${'Filler words only. '.repeat(200)}`
+const manual = [paragraph('invoice', 1), paragraph('kubernetes', 2), paragraph('holiday', 3)].join('\n\n')
+
+const dir = mkdtempSync(join(tmpdir(), 'attachment-search-'))
+try {
+  const attachments = new AttachmentStore(join(dir, 'attachments'))
+  const embedRequests: LLMEmbeddingRequest[] = []
+  const items: Item[] = []
+  const deps = (attachmentEmbeddingModel?: string): AttachmentSearchDeps => ({
+    attachments,
+    config: { attachmentInlineMaxChars: 1000, attachmentEmbeddingModel },
+    providers: {
+      resolve: () => ({
+        async embed(request: LLMEmbeddingRequest) {
+          embedRequests.push(request)
+          return { embeddings: request.input.map(fakeEmbedding) }
+        },
+      }),
+    },
+    items: { listBySession: async () => items } as unknown as ItemRepository,
+  }) as unknown as AttachmentSearchDeps
+
+  await assert.rejects(extractTextFile(deps(), { bytes: Buffer.from('   ') }), TextInputError)
+  await assert.rejects(extractTextFile(deps(), { bytes: Buffer.from([0xff, 0xfe, 0x00]), fileName: 'blob.bin' }), /Not a UTF-8 text file/)
+
+  const extracted = await extractTextFile(deps(), { bytes: Buffer.from(manual), fileName: 'manual.md' })
+  assert.equal(extracted.chunks.length, 3)
+  const small = await extractTextFile(deps(), { bytes: Buffer.from('Holiday rota: who covers which holiday.'), fileName: 'note.txt' })
+
+  // Large attachments lose their inline text and are embedded once; small ones stay inline
+  const resolved = await resolveDocumentBlocks(deps(), [
+    { type: 'document', hash: extracted.hash, name: 'manual.md' },
+    { type: 'document', hash: small.hash, name: 'note.txt' },
+  ])
+  assert.equal(resolved?.[0].media_type, 'text/plain')
+  const blocks = (await prepareLargeAttachments(deps('openai:text-embedding-3-small'), resolved)) as ItemContentBlock[]
+  assert.deepEqual([blocks[0].retrieval, blocks[0].chunks], [true, undefined])
+  assert.deepEqual(blocks[1].chunks, ['Holiday rota: who covers which holiday.'])
+  assert.deepEqual(embedRequests.map((request) => [request.model, request.input.length]), [['text-embedding-3-small', 3]])
+  await prepareLargeAttachments(deps('openai:text-embedding-3-small'), resolved)
+  assert.equal(embedRequests.length, 1, 'stored embeddings are reused')
+
+  items.push({
+    id: 'i1', agentId: 'a1', sequence: 1, type: 'message', role: 'user', content: 'How do I deploy?', contentBlocks: blocks,
+    callId: null, name: null, arguments: null, output: null, isError: null, saveOutput: null, turnNumber: 0, durationMs: null, createdAt: 0,
+  } as Item)
+  const messages = buildControllerMessages('system', '', items, { useNativeFunctionCalling: true, agentTask: '' })
+  assert.equal(
+    messages[1].content,
+    'How do I deploy?\n\n[Attached file: manual.md — too large to include; use attachments.search to read the relevant parts]\n'
+      + '\n\n[Attached file: note.txt]\n```\nHoliday rota: who covers which holiday.\n```\n',
+  )
+
+  const search = async (searchDeps: AttachmentSearchDeps, args: Record<string, unknown>) => {
+    const registry = new ToolRegistryImpl()
+    registerAttachmentTools(registry, searchDeps)
+    return registry.execute('attachments.search', args, { agent_id: 'a1', session_id: 's1', signal: new AbortController().signal })
+  }
+  type Results = { results: Array<{ attachment: string; chunk: number; text: string }> }
+
+  // Semantic ranking embeds the query with the configured model
+  const semantic = await search(deps('openai:text-embedding-3-small'), { query: 'kubernetes rollout', attachment: 'manual.md', limit: 1 })
+  assert.equal(semantic.ok, true)
+  const [top] = (semantic.output as Results).results
+  assert.deepEqual([top.attachment, top.chunk], ['manual.md', 1])
+  assert.match(top.text, /about kubernetes/)
+  assert.deepEqual(embedRequests.at(-1)?.input, ['kubernetes rollout'])
+
+  // Without a model, chunks are ranked by keyword and non-matching ones dropped
+  const keyword = await search(deps(), { query: 'Holiday' })
+  assert.deepEqual((keyword.output as Results).results.map((r) => [r.attachment, r.chunk]), [['note.txt', 0], ['manual.md', 2]])
+
+  const missing = await search(deps(), { query: 'anything', attachment: 'other.pdf' })
+  assert.deepEqual([missing.ok, missing.error], [false, 'No attachment named other.pdf in this session'])
+
+  console.log('attachment search tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,59 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, readdirSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { AttachmentStore, migrateInlineAttachments, withAttachmentStore } from '../lib/attachment-store.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'attachment-store-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'attachments.db')))
+  const store = new AttachmentStore(join(dir, 'attachments'))
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Screenshots' })
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Look', config })
+  const png = Buffer.from('not really a png').toString('base64')
+  const blocks = [{ type: 'text' as const, text: 'Screenshot' }, { type: 'image' as const, media_type: 'image/png', data: png }]
+
+  // Rows written before the store existed are migrated in place
+  const legacy = await repos.items.create({ agentId: agent.id, type: 'function_call_output', callId: 'c1', output: 'ok', contentBlocks: blocks, turnNumber: 1 })
+  assert.equal(await migrateInlineAttachments(repos.items, store), 1)
+  assert.equal(await migrateInlineAttachments(repos.items, store), 0)
+  const migrated = await repos.items.getById(legacy.id)
+  assert.equal(migrated?.contentBlocks?.[1].data, undefined)
+  assert.equal(migrated?.contentBlocks?.[1].bytes, 16)
+
+  // New writes never store the payload inline, and identical payloads share one file
+  const items = withAttachmentStore(repos.items, store)
+  await items.create({ agentId: agent.id, type: 'function_call_output', callId: 'c2', output: 'ok', contentBlocks: blocks, turnNumber: 2 })
+  const history = await items.listByAgent(agent.id)
+  assert.ok(history.every((item) => item.contentBlocks?.every((block) => block.data === undefined)))
+  assert.equal(history[0].contentBlocks?.[1].hash, history[1].contentBlocks?.[1].hash)
+  assert.equal(readdirSync(join(dir, 'attachments')).length, 1)
+
+  const hydrated = await store.hydrate(history)
+  assert.equal(hydrated[1].contentBlocks?.[1].data, png)
+  assert.equal(hydrated[1].contentBlocks?.[0].text, 'Screenshot')
+
+  // Long conversations load only recent items, keep system instructions, and resend only recent payloads
+  await items.create({ agentId: agent.id, type: 'message', role: 'system', content: 'Answer in Polish', turnNumber: 2 })
+  for (const [turn, text] of ['first', 'second', 'third'].entries()) {
+    await items.create({ agentId: agent.id, type: 'message', role: 'user', content: text, turnNumber: turn + 3 })
+    await items.create({ agentId: agent.id, type: 'function_call_output', callId: `w${turn}`, output: 'ok', contentBlocks: blocks, turnNumber: turn + 3 })
+  }
+  const recent = await items.listRecentByAgent(agent.id, 2)
+  assert.deepEqual(recent.map((item) => item.content ?? item.callId), ['Answer in Polish', 'second', 'w1', 'third', 'w2'])
+  assert.equal((await items.listRecentByAgent(agent.id, 10)).length, 9)
+  const windowed = await store.hydrate(recent, 1)
+  assert.match(windowed[2].contentBlocks?.[1].text ?? '', /not resent/)
+  assert.equal(windowed[4].contentBlocks?.[1].data, png)
+
+  rmSync(join(dir, 'attachments'), { recursive: true, force: true })
+  const missing = await store.hydrate(history)
+  assert.equal(missing[0].contentBlocks?.[1].type, 'text', 'missing payloads degrade to a placeholder')
+
+  console.log('attachment store tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,86 @@
+import assert from 'node:assert/strict'
+import {
+  AttachmentValidationError,
+  MAX_ATTACHMENT_BYTES,
+  sniffMediaType,
+  validateAttachment,
+  validateInlineBlocks,
+} from '../lib/attachment-validation.js'
+
+function png(width: number, height: number): Buffer {
+  const header = Buffer.alloc(24)
+  header.writeUInt32BE(0x89504e47, 0)
+  header.writeUInt32BE(0x0d0a1a0a, 4)
+  header.write('IHDR', 12, 'latin1')
+  header.writeUInt32BE(width, 16)
+  header.writeUInt32BE(height, 20)
+  return header
+}
+
+function rejection(fn: () => unknown): AttachmentValidationError {
+  try {
+    fn()
+  } catch (err) {
+    assert.ok(err instanceof AttachmentValidationError)
+    return err
+  }
+  assert.fail('expected a validation error')
+}
+
+assert.equal(sniffMediaType(png(1, 1)), 'image/png')
+assert.equal(sniffMediaType(Buffer.from([0xff, 0xd8, 0xff, 0xe0])), 'image/jpeg')
+assert.equal(sniffMediaType(Buffer.from('RIFF\0\0\0\0WEBPVP8 ', 'latin1')), 'image/webp')
+assert.equal(sniffMediaType(Buffer.from('RIFF\0\0\0\0WAVEfmt ', 'latin1')), 'audio/wav')
+assert.equal(sniffMediaType(Buffer.from('\n%PDF-1.7\n')), 'application/pdf')
+assert.equal(sniffMediaType(Buffer.from('PK\x03\x04rest', 'latin1')), 'application/zip')
+assert.equal(sniffMediaType(Buffer.from('\0\0\0\x18ftypqt  ', 'latin1')), 'video/quicktime')
+assert.equal(sniffMediaType(Buffer.from('\0\0\0\x18ftypM4A ', 'latin1')), 'audio/mp4')
+assert.equal(sniffMediaType(Buffer.from('\0\0\0\x18ftypisom', 'latin1')), 'video/mp4')
+assert.equal(sniffMediaType(Buffer.from('\x1a\x45\xdf\xa3\x9f\x42\x82\x84webm', 'latin1')), 'video/webm')
+assert.equal(sniffMediaType(Buffer.from('ID3\x04')), 'audio/mpeg')
+assert.equal(sniffMediaType(Buffer.from([0xff, 0xf1, 0x50])), 'audio/aac')
+assert.equal(sniffMediaType(Buffer.from('plain text')), null)
+
+// The detected type wins over the declared one
+const renamed = validateAttachment(Buffer.from([0xff, 0xd8, 0xff, 0xe0]), { kind: 'image', declaredType: 'image/png', fileName: 'photo.png' })
+assert.equal(renamed.mediaType, 'image/jpeg')
+assert.deepEqual(validateAttachment(png(640, 480), { kind: 'image', maxImageDimension: 1000 }), {
+  data: png(640, 480), mediaType: 'image/png', width: 640, height: 480,
+})
+
+const spoofed = rejection(() => validateAttachment(Buffer.from('%PDF-1.4'), { kind: 'image', declaredType: 'image/png', fileName: 'cat.png' }))
+assert.equal(spoofed.code, 'type_mismatch')
+assert.equal(spoofed.status, 400)
+assert.deepEqual(spoofed.toJSON(), {
+  error: 'cat.png is application/pdf, not an image',
+  code: 'type_mismatch',
+  details: { kind: 'image', fileName: 'cat.png', declaredType: 'image/png', detectedType: 'application/pdf' },
+})
+assert.equal(rejection(() => validateAttachment(Buffer.from('hello'), { kind: 'pdf' })).code, 'unsupported_type')
+assert.equal(rejection(() => validateAttachment(Buffer.from('\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1', 'latin1'), { kind: 'office' })).details.detectedType, 'application/x-ole-storage')
+assert.equal(rejection(() => validateAttachment(new Uint8Array(), { kind: 'text' })).code, 'empty')
+assert.equal(rejection(() => validateAttachment(png(1, 1), { kind: 'text', fileName: 'notes.txt' })).code, 'type_mismatch')
+assert.deepEqual(validateAttachment(Buffer.from('# Notes\n'), { kind: 'text' }).mediaType, null)
+
+const oversized = rejection(() => validateAttachment(Buffer.alloc(MAX_ATTACHMENT_BYTES.text + 1, 'a'), { kind: 'text', fileName: 'dump.log' }))
+assert.equal(oversized.code, 'too_large')
+assert.equal(oversized.status, 413)
+assert.equal(oversized.details.limit, MAX_ATTACHMENT_BYTES.text)
+
+const huge = rejection(() => validateAttachment(png(30000, 200), { kind: 'image', maxImageDimension: 16384 }))
+assert.equal(huge.code, 'image_too_large')
+assert.deepEqual([huge.details.limit, huge.details.actual], [16384, 30000])
+
+// Inline blocks: normalized payloads and detected types; invalid base64 never reaches the store
+const inline = validateInlineBlocks([
+  { type: 'text', text: 'see attached' },
+  { type: 'image', name: 'shot', media_type: 'image/gif', data: `data:image/gif;base64,${png(10, 10).toString('base64')}` },
+], { maxImageDimension: 100 })
+assert.deepEqual(inline?.[0], { type: 'text', text: 'see attached' })
+assert.deepEqual(inline?.[1], { type: 'image', name: 'shot', media_type: 'image/png', data: png(10, 10).toString('base64') })
+const unchanged = [{ type: 'document' as const, hash: 'a'.repeat(64) }]
+assert.equal(validateInlineBlocks(unchanged, {}), unchanged)
+assert.equal(rejection(() => validateInlineBlocks([{ type: 'image', data: 'not base64!' }], {})).code, 'invalid_encoding')
+assert.equal(rejection(() => validateInlineBlocks([{ type: 'image', data: png(500, 10).toString('base64') }], { maxImageDimension: 100 })).code, 'image_too_large')
+
+console.log('Attachment validation tests passed')
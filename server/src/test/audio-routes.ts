@@ -52,8 +52,19 @@ const unsupported = new FormData()
 unsupported.append('file', new File(['hello'], 'notes.txt', { type: 'text/plain' }))
 assert.equal((await authorized(unsupported)).status, 400)
 
+// The declared type is not trusted; the bytes have to look like audio
+const spoofed = new FormData()
+spoofed.append('file', new File([new Uint8Array([1, 2, 3])], 'voice.ogg', { type: 'audio/ogg' }))
+const rejected = await authorized(spoofed)
+assert.equal(rejected.status, 400)
+assert.deepEqual(await rejected.json(), {
+  error: 'voice.ogg is not an audio file',
+  code: 'unsupported_type',
+  details: { kind: 'audio', fileName: 'voice.ogg', declaredType: 'audio/ogg' },
+})
+
 const valid = new FormData()
-valid.append('file', new File([new Uint8Array([1, 2, 3])], 'voice.ogg', { type: 'audio/ogg' }))
+valid.append('file', new File([Buffer.from('OggS\0\x02')], 'voice.ogg', { type: 'audio/ogg' }))
 const response = await authorized(valid)
 assert.equal(response.status, 200)
 assert.deepEqual(await response.json(), { text: 'uploaded audio transcript' })
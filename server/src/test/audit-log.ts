@@ -0,0 +1,94 @@
+import assert from 'node:assert/strict'
+import { appendFileSync, mkdtempSync, readdirSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { Agent } from '../domain/types.js'
+import { AgentEventEmitter } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import { AuditLog, type AuditEntry, type AuditQuery } from '../observability/audit-log.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'audit-log-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'audit.db')))
+  const alice = await repos.users.create({ apiKeyHash: 'alice' })
+  const bob = await repos.users.create({ apiKeyHash: 'bob' })
+  const aliceSession = await repos.sessions.create({ userId: alice.id, title: 'Alice' })
+  const bobSession = await repos.sessions.create({ userId: bob.id, title: 'Bob' })
+
+  const audit = new AuditLog({
+    dir: join(dir, 'audit'),
+    preferences: repos.preferences,
+    sessions: repos.sessions,
+    defaultEnabled: false,
+  })
+  const collect = async (query: AuditQuery) => {
+    const entries: AuditEntry[] = []
+    for await (const entry of audit.query(query)) entries.push(entry)
+    return entries
+  }
+  const lineage = { parentId: null, depth: 0 }
+  const event = (sessionId: string, type: AgentEvent['type'], payload: unknown) =>
+    ({ type, agent_id: 'agent-1', session_id: sessionId, timestamp: Date.now(), payload }) as AgentEvent
+
+  await audit.recordEvent(event(aliceSession.id, EVENT_TYPES.TOOL_STARTED, { ...lineage, callId: 'c0', name: 'shell.run', args: {} }))
+  await audit.flush()
+  assert.deepEqual(readdirSync(dir).filter((f) => f === 'audit'), [], 'nothing is written while disabled')
+
+  await repos.preferences.set('audit_log', 'true')
+  await audit.recordEvent(event(aliceSession.id, EVENT_TYPES.TOOL_STARTED, { ...lineage, callId: 'c1', name: 'shell.run', args: { cmd: 'ls' } }))
+  await audit.recordEvent(event(aliceSession.id, EVENT_TYPES.TOOL_APPROVED, { callId: 'c1', name: 'shell.run' }))
+  await audit.recordEvent(event(aliceSession.id, EVENT_TYPES.TOOL_COMPLETED, {
+    ...lineage, callId: 'c1', name: 'shell.run', success: false, output: 'x'.repeat(5000), durationMs: 12, errorCode: 'tool_timeout',
+  }))
+  await audit.recordEvent(event(bobSession.id, EVENT_TYPES.TOOL_DENIED, { callId: 'c2', name: 'files.delete' }))
+  await audit.record({
+    agent: { id: 'agent-1', sessionId: aliceSession.id } as Agent,
+    turnNumber: 2,
+    provider: 'openai',
+    model: 'gpt-4o',
+    stream: true,
+    request: { model: 'gpt-4o', messages: [{ role: 'user', content: 'secret prompt' }] },
+    response: { content: 'secret answer', usage: { input_tokens: 10, output_tokens: 3 }, finish_reason: 'stop' },
+    durationMs: 40,
+  })
+  await audit.flush()
+
+  const entries = await collect({ userId: alice.id })
+  assert.deepEqual(entries.map((e) => e.kind), ['tool_call', 'approval', 'tool_result', 'llm_call'], 'in recording order')
+  assert.ok(entries.every((e) => e.userId === alice.id && e.sessionId === aliceSession.id))
+  const result = entries[2]
+  assert.ok(result.kind === 'tool_result')
+  assert.equal(result.outputBytes, 5000)
+  assert.equal(result.output.length, 2000, 'tool output is kept as a preview')
+  assert.equal(result.errorCode, 'tool_timeout')
+  const llm = entries[3]
+  assert.ok(llm.kind === 'llm_call')
+  assert.equal(llm.inputTokens, 10)
+  assert.ok(!JSON.stringify(llm).includes('secret'), 'prompt and completion content are never logged')
+
+  const [denied] = await collect({ userId: bob.id })
+  assert.ok(denied.kind === 'approval' && denied.decision === 'denied', 'entries are scoped to the session owner')
+
+  assert.deepEqual((await collect({ userId: alice.id, kinds: ['approval', 'llm_call'] })).map((e) => e.kind), ['approval', 'llm_call'])
+  assert.equal((await collect({ userId: alice.id, from: Date.now() + 1000 })).length, 0)
+  assert.equal((await collect({ userId: alice.id, to: entries[0].at })).length, 0, '`to` is exclusive')
+
+  // A torn line from a crash mid-write is skipped, not fatal
+  const [file] = readdirSync(join(dir, 'audit'))
+  appendFileSync(join(dir, 'audit', file), '{"at":1,"kind":"tool_')
+  assert.equal((await collect({ userId: alice.id })).length, 4)
+
+  // Subscribed to the event bus, tool events are recorded as they are emitted
+  const events = new AgentEventEmitter()
+  audit.start(events)
+  events.emit(event(bobSession.id, EVENT_TYPES.TOOL_STARTED, { ...lineage, callId: 'c3', name: 'web.fetch', args: {} }))
+  await new Promise((resolve) => setTimeout(resolve, 20))
+  await audit.flush()
+  audit.stop()
+  assert.deepEqual((await collect({ userId: bob.id })).map((e) => e.kind), ['approval', 'tool_call'])
+
+  console.log('audit log tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
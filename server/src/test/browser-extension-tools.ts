@@ -0,0 +1,80 @@
+import assert from 'node:assert/strict'
+import type { SessionRepository } from '../repositories/types.js'
+import { BrowserExtensions, MAX_PAGE_TEXT_CHARS, formatBrowserPage, parseBrowserPage, type BrowserRequest } from '../services/browser-extension.js'
+import { registerBrowserTools } from '../tools/browser.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const owners = new Map([['alice-session', 'alice'], ['bob-session', 'bob']])
+const sessions = { getById: async (id: string) => owners.has(id) ? { id, userId: owners.get(id) } : null } as unknown as SessionRepository
+const extensions = new BrowserExtensions(200)
+const registry = new ToolRegistryImpl()
+registerBrowserTools(registry, { extensions, sessions })
+const ctx = { agent_id: 'agent', session_id: 'alice-session', signal: new AbortController().signal }
+
+assert.equal(registry.getMetadata('browser.get_active_tab')?.requires_approval, false)
+assert.match(String((await registry.execute('browser.get_active_tab', {}, ctx)).error), /No browser extension is connected/)
+
+// The newest extension of the session's user answers; requests carry an id the answer must echo
+const requests: BrowserRequest[] = []
+const stale = extensions.connect('alice', () => assert.fail('older extension was asked'))
+const laptop = extensions.connect('alice', (request) => {
+  requests.push(request)
+  queueMicrotask(() => laptop.resolve(request.id, {
+    ok: true,
+    result: { url: 'https://example.com/post', title: ' A post ', selection: 'the key sentence', text: 'Full article text' },
+  }))
+})
+extensions.connect('bob', () => assert.fail("bob's extension was asked"))
+
+assert.deepEqual((await registry.execute('browser.get_active_tab', {}, ctx)).output, {
+  url: 'https://example.com/post',
+  title: 'A post',
+  selection: 'the key sentence',
+})
+assert.deepEqual((await registry.execute('browser.get_active_tab', { include_text: true }, ctx)).output, {
+  url: 'https://example.com/post',
+  title: 'A post',
+  selection: 'the key sentence',
+  text: 'Full article text',
+})
+assert.deepEqual(requests.map((request) => [request.method, request.params]), [
+  ['get_active_tab', { includeText: false }],
+  ['get_active_tab', { includeText: true }],
+])
+// Answers only count from the extension that was asked, and only once
+assert.equal(stale.resolve(requests[0].id, { ok: true, result: {} }), false)
+assert.equal(laptop.resolve(requests[0].id, { ok: true, result: {} }), false)
+
+// Errors, silence and disconnects all fail the tool instead of hanging the run
+laptop.disconnect()
+const failing = extensions.connect('alice', (request) => queueMicrotask(() => failing.resolve(request.id, { ok: false, error: 'no tab is focused' })))
+assert.equal((await registry.execute('browser.get_active_tab', {}, ctx)).error, 'Browser extension: no tab is focused')
+failing.disconnect()
+const silent = extensions.connect('alice', () => {})
+assert.match(String((await registry.execute('browser.get_active_tab', {}, ctx)).error), /did not answer/)
+const pending = registry.execute('browser.get_active_tab', {}, ctx)
+await new Promise((resolve) => setTimeout(resolve, 20))
+silent.disconnect()
+assert.match(String((await pending).error), /disconnected before answering/)
+const nonsense = extensions.connect('alice', (request) => queueMicrotask(() => nonsense.resolve(request.id, { ok: true, result: 'tab' })))
+assert.match(String((await registry.execute('browser.get_active_tab', {}, ctx)).error), /page.url is required/)
+nonsense.disconnect()
+stale.disconnect()
+assert.equal(extensions.isConnected('alice'), false)
+assert.equal(extensions.isConnected('bob'), true)
+
+// Pages sent from the browser become the user's message
+const long = parseBrowserPage({ url: 'https://example.com/long', title: '', text: 'x'.repeat(MAX_PAGE_TEXT_CHARS + 10) })
+assert.equal(long.title, 'https://example.com/long')
+assert.equal(long.text?.length, MAX_PAGE_TEXT_CHARS)
+assert.equal(long.truncated, true)
+assert.equal(
+  formatBrowserPage({ url: 'https://example.com/post', title: 'A post', selection: 'first line\nsecond line' }, 'Is this right?'),
+  'Is this right?\n\nPage: A post\nURL: https://example.com/post\n\nSelected text:\n> first line\n> second line',
+)
+assert.equal(
+  formatBrowserPage({ url: 'https://example.com/post', title: 'A post', text: 'Body' }),
+  'Here is a page I am reading.\n\nPage: A post\nURL: https://example.com/post\n\nPage text:\nBody',
+)
+
+console.log('Browser extension tool tests passed')
@@ -0,0 +1,217 @@
+import assert from 'node:assert/strict'
+import { matchApprovalTrigger } from '../orchestrator/approval-rules.js'
+import { CalendarService, normalizeCalDavAccount, type CalDavAccount } from '../services/caldav.js'
+import { extractEntities, isIntegrationTool } from '../services/entities.js'
+import { buildEvent, parseEvents } from '../services/icalendar.js'
+import { registerCalendarTools } from '../tools/calendar.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+const crlf = (lines: string[]) => lines.join('\r\n')
+
+function multistatus(responses: Array<{ href: string; props: string; status?: string }>): string {
+  return `<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav" xmlns:ical="http://apple.com/ns/ical/">
+${responses.map((response) => `<d:response><d:href>${response.href}</d:href><d:propstat><d:prop>${response.props}</d:prop><d:status>HTTP/1.1 ${response.status ?? '200 OK'}</d:status></d:propstat></d:response>`).join('\n')}
+</d:multistatus>`
+}
+
+const STANDUP = crlf([
+  'BEGIN:VCALENDAR',
+  'VERSION:2.0',
+  'BEGIN:VTIMEZONE',
+  'TZID:Europe/Warsaw',
+  'END:VTIMEZONE',
+  'BEGIN:VEVENT',
+  'UID:standup@example.com',
+  'RECURRENCE-ID;TZID=Europe/Warsaw:20261021T090000',
+  'DTSTART;TZID=Europe/Warsaw:20261021T090000',
+  'DURATION:PT30M',
+  'SUMMARY:Stand-up\\, daily',
+  'DESCRIPTION:Agenda:\\n- blockers\\n- plans for the rest of the week and anything els',
+  ' e worth raising',
+  'ORGANIZER;CN="Ana Nowak":mailto:ana@example.com',
+  'ATTENDEE;CN=Bob Stone;PARTSTAT=ACCEPTED:mailto:bob@acme.io',
+  'ATTENDEE;CUTYPE=ROOM;CN=Room 4:mailto:room4@resource.acme.io',
+  'BEGIN:VALARM',
+  'TRIGGER:-PT10M',
+  'SUMMARY:Reminder',
+  'END:VALARM',
+  'END:VEVENT',
+  'END:VCALENDAR',
+  '',
+])
+const OFFSITE = crlf([
+  'BEGIN:VCALENDAR',
+  'BEGIN:VEVENT',
+  'UID:offsite@example.com',
+  'DTSTART;VALUE=DATE:20261020',
+  'DTEND;VALUE=DATE:20261021',
+  'SUMMARY:Offsite',
+  'RRULE:FREQ=YEARLY',
+  'END:VEVENT',
+  'END:VCALENDAR',
+  '',
+])
+
+const requests: Array<{ method: string; url: string; headers: Record<string, string>; body: string }> = []
+const fetchImpl = (async (input: string | URL | Request, init?: RequestInit) => {
+  const url = String(input)
+  const headers = init?.headers as Record<string, string>
+  const body = String(init?.body ?? '')
+  requests.push({ method: init?.method ?? 'GET', url, headers, body })
+  if (headers.Authorization !== `Basic ${Buffer.from('ana@example.com:app-password').toString('base64')}`) {
+    return new Response('', { status: 401 })
+  }
+  const path = new URL(url).pathname
+  if (init?.method === 'PROPFIND' && body.includes('current-user-principal')) {
+    return new Response(multistatus([{ href: path, props: '<d:current-user-principal><d:href>/principals/ana/</d:href></d:current-user-principal>' }]), { status: 207 })
+  }
+  if (init?.method === 'PROPFIND' && body.includes('calendar-home-set')) {
+    return new Response(multistatus([{ href: path, props: '<cal:calendar-home-set><d:href>/calendars/ana/</d:href></cal:calendar-home-set>' }]), { status: 207 })
+  }
+  if (init?.method === 'PROPFIND' && path === '/calendars/ana/') {
+    return new Response(multistatus([
+      { href: '/calendars/ana/', props: '<d:resourcetype><d:collection/></d:resourcetype>' },
+      {
+        href: '/calendars/ana/work/',
+        props: '<d:resourcetype><d:collection/><cal:calendar/></d:resourcetype><d:displayname>Work</d:displayname><cal:supported-calendar-component-set><cal:comp name="VEVENT"/></cal:supported-calendar-component-set><ical:calendar-color>#3a87ad</ical:calendar-color>',
+      },
+      {
+        href: '/calendars/ana/tasks/',
+        props: '<d:resourcetype><d:collection/><cal:calendar/></d:resourcetype><d:displayname>Tasks</d:displayname><cal:supported-calendar-component-set><cal:comp name="VTODO"/></cal:supported-calendar-component-set>',
+      },
+    ]), { status: 207 })
+  }
+  if (init?.method === 'REPORT') {
+    return new Response(multistatus([
+      { href: '/calendars/ana/work/standup.ics', props: `<d:getetag>"1"</d:getetag><cal:calendar-data><![CDATA[${STANDUP}]]></cal:calendar-data>` },
+      { href: '/calendars/ana/work/offsite.ics', props: `<d:getetag>"2"</d:getetag><cal:calendar-data>${OFFSITE.replace(/&/g, '&amp;')}</cal:calendar-data>` },
+    ]), { status: 207 })
+  }
+  if (init?.method === 'PUT') return new Response(null, { status: 201 })
+  if (init?.method === 'DELETE') return new Response(null, { status: 204 })
+  return new Response('', { status: 404, statusText: 'Not Found' })
+}) as typeof fetch
+
+let accounts: CalDavAccount[] = [
+  normalizeCalDavAccount('fastmail', { url: 'https://dav.example.com/', username: 'ana@example.com', password: 'app-password' }, null),
+]
+assert.throws(() => normalizeCalDavAccount('work/../x', { url: 'https://dav.example.com/', username: 'a', password: 'b' }, null), /name must be/)
+assert.throws(() => normalizeCalDavAccount('x', { url: 'dav.example.com', username: 'a', password: 'b' }, null), /url must be/)
+assert.equal(normalizeCalDavAccount('fastmail', { url: 'https://dav.example.com/', username: 'ana@example.com' }, accounts[0]).password, 'app-password')
+
+const registry = new ToolRegistryImpl()
+registerCalendarTools(registry, new CalendarService(async () => accounts, fetchImpl))
+
+// Discovery: principal, then calendar home, then event calendars only
+const calendars = await registry.execute('calendar.list_calendars', {}, ctx)
+assert.deepEqual(calendars.output, {
+  calendars: [{ account: 'fastmail', id: 'https://dav.example.com/calendars/ana/work/', name: 'Work', color: '#3a87ad' }],
+})
+assert.deepEqual(requests.map((request) => [request.method, request.url, request.headers.Depth]), [
+  ['PROPFIND', 'https://dav.example.com/', '0'],
+  ['PROPFIND', 'https://dav.example.com/principals/ana/', '0'],
+  ['PROPFIND', 'https://dav.example.com/calendars/ana/', '1'],
+])
+
+// Events come back in Google's shape, soonest first, with zone offsets resolved
+requests.length = 0
+const listed = await registry.execute('calendar.list_events', { from: '2026-10-19', to: '2026-10-26' }, ctx)
+assert.equal(listed.ok, true)
+const report = requests.find((request) => request.method === 'REPORT')!
+assert.match(report.body, /<C:time-range start="20261019T000000Z" end="20261026T000000Z"\/>/)
+assert.match(report.body, /<C:expand start="20261019T000000Z" end="20261026T000000Z"\/>/)
+const { events, truncated } = listed.output as { events: Array<Record<string, unknown>>; truncated: boolean }
+assert.equal(truncated, false)
+assert.deepEqual(events.map((event) => event.summary), ['Offsite', 'Stand-up, daily'])
+assert.deepEqual(events[0].start, { date: '2026-10-20' })
+assert.deepEqual(events[0].recurrence, ['RRULE:FREQ=YEARLY'])
+assert.deepEqual(events[1], {
+  id: 'standup@example.com_20261021T090000',
+  summary: 'Stand-up, daily',
+  description: 'Agenda:\n- blockers\n- plans for the rest of the week and anything else worth raising',
+  location: null,
+  status: null,
+  start: { dateTime: '2026-10-21T09:00:00+02:00', timeZone: 'Europe/Warsaw' },
+  end: { dateTime: '2026-10-21T09:30:00+02:00', timeZone: 'Europe/Warsaw' },
+  organizer: { email: 'ana@example.com', displayName: 'Ana Nowak' },
+  attendees: [
+    { email: 'bob@acme.io', displayName: 'Bob Stone', responseStatus: 'accepted' },
+    { email: 'room4@resource.acme.io', displayName: 'Room 4', responseStatus: 'needsAction', resource: true },
+  ],
+  recurringEventId: 'standup@example.com',
+  account: 'fastmail',
+  calendar: 'Work',
+  url: 'https://dav.example.com/calendars/ana/work/standup.ics',
+})
+const filtered = await registry.execute('calendar.list_events', { from: '2026-10-19', to: '2026-10-26', query: 'bob stone' }, ctx)
+assert.deepEqual((filtered.output as { events: Array<{ summary: string }> }).events.map((event) => event.summary), ['Stand-up, daily'])
+assert.match(String((await registry.execute('calendar.list_events', { from: '2026-10-19', to: '2026-10-01' }, ctx)).error), /to must be after from/)
+assert.match(String((await registry.execute('calendar.list_events', { calendar: 'Home' }, ctx)).error), /No calendar named "Home"; available: Work/)
+
+// Entity learning reads these results like Google Calendar's
+assert.equal(isIntegrationTool('calendar.list_events'), true)
+const learned = extractEntities(listed.output)
+assert.ok(learned.some((entity) => entity.kind === 'meeting' && entity.key === 'standup@example.com' && entity.details?.attendees === 'bob@acme.io'))
+assert.ok(!learned.some((entity) => entity.key === 'room4@resource.acme.io'))
+
+// Creating: local time in the given zone stored as UTC; attendees need approval
+const createMetadata = registry.getMetadata('calendar.create_event')!
+assert.equal(matchApprovalTrigger(createMetadata.approval_triggers!, { summary: 'Focus', start: '09:00' }), null)
+assert.equal(matchApprovalTrigger(createMetadata.approval_triggers!, { summary: 'Sync', start: '09:00', attendees: ['bob@acme.io'] })?.reason, 'Attendees will be emailed an invitation.')
+requests.length = 0
+const created = await registry.execute('calendar.create_event', {
+  summary: 'Quarterly review; budget, hiring',
+  start: '2026-10-22 15:00',
+  time_zone: 'Europe/Warsaw',
+  attendees: ['bob@acme.io'],
+}, ctx)
+assert.equal(created.ok, true, created.error)
+const put = requests.find((request) => request.method === 'PUT')!
+const createdOutput = created.output as { id: string; url: string; calendar: string; start: unknown }
+assert.equal(put.url, createdOutput.url)
+assert.ok(put.url.startsWith('https://dav.example.com/calendars/ana/work/'))
+assert.equal(put.headers['If-None-Match'], '*')
+assert.equal(put.headers['Content-Type'], 'text/calendar; charset=utf-8')
+assert.match(put.body, /\r\nDTSTART:20261022T130000Z\r\nDTEND:20261022T140000Z\r\n/)
+assert.match(put.body, /\r\nSUMMARY:Quarterly review\\; budget\\, hiring\r\n/)
+assert.match(put.body, /\r\nORGANIZER:mailto:ana@example\.com\r\nATTENDEE;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:bob@acme\.io\r\n/)
+assert.equal(createdOutput.calendar, 'Work')
+assert.deepEqual(createdOutput.start, { dateTime: '2026-10-22T13:00:00.000Z' })
+
+requests.length = 0
+await registry.execute('calendar.create_event', { summary: 'Conference', start: '2026-10-24', end: '2026-10-25', all_day: true }, ctx)
+const allDay = requests.find((request) => request.method === 'PUT')!.body
+assert.match(allDay, /\r\nDTSTART;VALUE=DATE:20261024\r\nDTEND;VALUE=DATE:20261026\r\n/)
+assert.doesNotMatch(allDay, /ORGANIZER/)
+assert.match(String((await registry.execute('calendar.create_event', { summary: 'x', start: '10:00', end: '09:00' }, ctx)).error), /end must be after start/)
+assert.match(String((await registry.execute('calendar.create_event', { summary: 'x', start: '10:00', attendees: ['bob'] }, ctx)).error), /Invalid attendee address: bob/)
+
+// Deleting only reaches the account's own server
+assert.equal(registry.getMetadata('calendar.delete_event')?.requires_approval, true)
+assert.match(String((await registry.execute('calendar.delete_event', { url: 'https://evil.example.net/x.ics' }, ctx)).error), /not on this account's CalDAV server/)
+requests.length = 0
+const deleted = await registry.execute('calendar.delete_event', { url: 'https://dav.example.com/calendars/ana/work/standup.ics' }, ctx)
+assert.equal(deleted.ok, true)
+assert.deepEqual(requests.at(-1), {
+  method: 'DELETE',
+  url: 'https://dav.example.com/calendars/ana/work/standup.ics',
+  headers: requests.at(-1)!.headers,
+  body: '',
+})
+
+// Several accounts: writes must name one
+accounts = [...accounts, { ...accounts[0], name: 'icloud', password: 'wrong' }]
+assert.match(String((await registry.execute('calendar.create_event', { summary: 'x', start: '10:00' }, ctx)).error), /Several calendar accounts are configured \(fastmail, icloud\); pass account/)
+assert.match(String((await registry.execute('calendar.list_calendars', { account: 'icloud' }, ctx)).error), /CalDAV login for "icloud" was rejected/)
+assert.match(String((await registry.execute('calendar.list_calendars', { account: 'google' }, ctx)).error), /Unknown calendar account "google"; configured: fastmail, icloud/)
+accounts = []
+assert.match(String((await registry.execute('calendar.list_calendars', {}, ctx)).error), /No calendar account is configured/)
+
+// Long lines fold at 75 octets and parse back
+const long = buildEvent({ summary: 'Ż'.repeat(60), start: new Date('2026-10-22T13:00:00Z'), end: new Date('2026-10-22T14:00:00Z') })
+assert.ok(long.ics.split('\r\n').every((line) => Buffer.byteLength(line, 'utf-8') <= 75))
+assert.equal(parseEvents(long.ics)[0].summary, 'Ż'.repeat(60))
+
+console.log('Calendar tool tests passed')
@@ -0,0 +1,48 @@
+import assert from 'node:assert/strict'
+import type { Item } from '../domain/types.js'
+import { CONVERSATION_MODE_PREFIX, getConversationMode, isConversationMode, setConversationMode } from '../orchestrator/chat-mode.js'
+import { CHAT_SYSTEM_PROMPT } from '../orchestrator/prompts.js'
+import { buildChatPrompt } from '../orchestrator/runner.js'
+
+const stored = new Map<string, string>()
+const preferences = {
+  get: async (key: string) => stored.get(key) ?? null,
+  set: async (key: string, value: string) => { stored.set(key, value) },
+  delete: async (key: string) => { stored.delete(key) },
+}
+
+// Agent mode unless the conversation picks chat; unknown stored values fall back
+assert.equal(isConversationMode('chat'), true)
+assert.equal(isConversationMode('plan'), false)
+assert.equal(await getConversationMode(preferences, 's1'), 'agent')
+await setConversationMode(preferences, 's1', 'chat')
+assert.equal(await getConversationMode(preferences, 's1'), 'chat')
+assert.equal(await getConversationMode(preferences, 's2'), 'agent')
+stored.set(`${CONVERSATION_MODE_PREFIX}s2`, 'bogus')
+assert.equal(await getConversationMode(preferences, 's2'), 'agent')
+await setConversationMode(preferences, 's1', null)
+assert.equal(stored.has(`${CONVERSATION_MODE_PREFIX}s1`), false)
+
+// The chat prompt: no tools, no controller protocol, and earlier tool traffic left out
+const history = [
+  { type: 'message', role: 'user', content: 'What is 3 * 7?' },
+  { type: 'function_call', role: 'assistant', callId: 'c1', name: 'calculator.evaluate', arguments: '{"expression":"3 * 7"}' },
+  { type: 'function_call_output', role: null, callId: 'c1', output: '{"result": 21}' },
+  { type: 'reasoning', role: 'assistant', content: 'Easy.' },
+  { type: 'message', role: 'assistant', content: 'It is 21.' },
+  { type: 'message', role: 'user', content: 'And doubled?' },
+] as Item[]
+const prompt = buildChatPrompt({ config: { model: 'anthropic:x', provider: 'anthropic', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1, system_prompt: 'Be brief.' } }, history)
+assert.equal(prompt.tools, undefined)
+assert.equal(prompt.useNativeTools, true)
+const [system, ...rest] = prompt.messages
+assert.ok((system.content as string).startsWith(CHAT_SYSTEM_PROMPT))
+assert.ok((system.content as string).includes('Be brief.'))
+assert.ok(!(system.content as string).includes('## Current Task'))
+assert.deepEqual(rest.map((message) => [message.role, message.content]), [
+  ['user', 'What is 3 * 7?'],
+  ['assistant', 'It is 21.'],
+  ['user', 'And doubled?'],
+])
+
+console.log('chat mode tests passed')
@@ -0,0 +1,82 @@
+import assert from 'node:assert/strict'
+import { ConnectivityMonitor, isLocalProvider, isNetworkError } from '../lib/connectivity.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { OFFLINE_QUEUE_PREFERENCE_KEY, OfflineQueue } from '../services/offline-queue.js'
+
+// Connection failures, including ones wrapped by fetch, but not provider errors
+assert.equal(isNetworkError(Object.assign(new Error('getaddrinfo ENOTFOUND api.anthropic.com'), { code: 'ENOTFOUND' })), true)
+assert.equal(isNetworkError(new TypeError('fetch failed', { cause: Object.assign(new Error('connect'), { code: 'ENETUNREACH' }) })), true)
+assert.equal(isNetworkError(Object.assign(new Error('Overloaded'), { status: 529 })), false)
+assert.equal(isNetworkError('ENOTFOUND'), false)
+assert.equal(isLocalProvider('ollama'), true)
+assert.equal(isLocalProvider('anthropic'), false)
+
+// The probe result is reused until it goes stale; the first failure starts polling
+let reachable = true
+let probes = 0
+const monitor = new ConnectivityMonitor({
+  probeHost: 'example.com',
+  checkMs: 60_000,
+  probe: async () => {
+    probes++
+    if (!reachable) throw new Error('ENETUNREACH')
+  },
+})
+let reconnects = 0
+const unsubscribe = monitor.onOnline(() => reconnects++)
+assert.equal(await monitor.isOnline(), true)
+assert.equal(await monitor.isOnline(), true)
+assert.equal(probes, 1)
+reachable = false
+assert.equal(await monitor.isOnline(), true)
+const [first, second] = await Promise.all([monitor.check(), monitor.check()])
+assert.deepEqual([first, second, probes], [false, false, 2])
+assert.equal(await monitor.isOnline(), false)
+reachable = true
+assert.equal(await monitor.check(), true)
+assert.equal(reconnects, 1)
+unsubscribe()
+monitor.stop()
+
+// Offline messages wait in order; a remote model is only queued without a local fallback
+const stored = new Map<string, string>()
+const statuses = new Map<string, string>([['agent-1', 'running'], ['agent-2', 'pending']])
+let online = false
+const runtime = {
+  connectivity: { offlineModel: undefined, isOnline: async () => online, check: async () => online, onOnline: () => () => {} },
+  repositories: {
+    preferences: {
+      get: async (key: string) => stored.get(key) ?? null,
+      set: async (key: string, value: string) => { stored.set(key, value) },
+      delete: async (key: string) => { stored.delete(key) },
+    },
+    agents: {
+      getById: async (id: string) => (statuses.has(id) ? { id, sessionId: 's1', status: statuses.get(id), config: { model: 'anthropic:x' } } : null),
+      update: async (id: string, patch: { status: string }) => { statuses.set(id, patch.status) },
+    },
+  },
+} as unknown as RuntimeContext
+const queue = new OfflineQueue(runtime)
+assert.equal(await queue.shouldQueue('anthropic:claude'), true)
+assert.equal(await queue.shouldQueue('ollama:llama3.2'), false)
+await queue.enqueue('agent-1')
+await queue.enqueue('agent-2')
+await queue.enqueue('agent-1')
+assert.deepEqual(await queue.list(), ['agent-1', 'agent-2'])
+// A follow-up's root agent was already set running by the send; it waits as pending
+assert.equal(statuses.get('agent-1'), 'pending')
+
+// Still offline: nothing is sent
+await queue.flush()
+assert.deepEqual(await queue.list(), ['agent-1', 'agent-2'])
+
+// Back online: runs no longer pending (cancelled meanwhile) are dropped without starting
+online = true
+statuses.set('agent-1', 'cancelled')
+statuses.set('agent-2', 'cancelled')
+await queue.flush()
+assert.deepEqual(await queue.list(), [])
+assert.equal(stored.has(OFFLINE_QUEUE_PREFERENCE_KEY), false)
+assert.equal(await queue.shouldQueue('anthropic:claude'), false)
+
+console.log('connectivity tests passed')
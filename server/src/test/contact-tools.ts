@@ -0,0 +1,33 @@
+import assert from 'node:assert/strict'
+import { MacContacts } from '../services/contacts.js'
+import { registerContactTools } from '../tools/contacts.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+
+const calls: Array<{ script: string; args: string[] }> = []
+const registry = new ToolRegistryImpl()
+registerContactTools(registry, new MacContacts(async (script, args) => {
+  calls.push({ script, args })
+  return JSON.stringify([{
+    id: 'ABC:ABPerson',
+    name: 'Sarah Lee',
+    organization: 'Acme',
+    emails: [{ label: 'work', value: 'sarah@acme.com' }],
+    phones: [{ label: 'mobile', value: '+1 555 0100' }],
+  }])
+}))
+
+assert.equal(registry.getMetadata('contacts.search')?.category, 'read_only')
+const found = await registry.execute('contacts.search', { query: ' Sarah ', limit: 100 }, ctx)
+assert.deepEqual((found.output as { contacts: Array<{ emails: Array<{ value: string }> }> }).contacts[0].emails, [{ label: 'work', value: 'sarah@acme.com' }])
+// The name goes through argv, never into the script, and the limit is capped
+assert.deepEqual(calls[0].args, ['Sarah', '25'])
+assert.doesNotMatch(calls[0].script, /Sarah/)
+assert.match(String((await registry.execute('contacts.search', { query: '  ' }, ctx)).error), /query is required/)
+
+const failing = new ToolRegistryImpl()
+registerContactTools(failing, new MacContacts(async () => { throw new Error('osascript failed: Not authorized to send Apple events to Contacts') }))
+assert.match(String((await failing.execute('contacts.search', { query: 'Sarah' }, ctx)).error), /Not authorized/)
+
+console.log('Contact tool tests passed')
@@ -0,0 +1,61 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { DEFAULT_CONTROLLER_PROMPTS } from '../orchestrator/prompts.js'
+import { buildControllerPrompt } from '../orchestrator/runner.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { ControllerPromptStore } from '../services/controller-prompts.js'
+import type { Agent } from '../domain/types.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'controller-prompts-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'prompts.db')))
+  const store = new ControllerPromptStore(repos.controllerPrompts)
+
+  // Seeding stores each default as version 1, once
+  await store.seed()
+  await store.seed()
+  for (const prompt of await store.list()) {
+    assert.equal(prompt.version, 1)
+    assert.equal(prompt.isDefault, true)
+    assert.equal(prompt.content, DEFAULT_CONTROLLER_PROMPTS[prompt.name])
+  }
+  assert.deepEqual(await store.active(), DEFAULT_CONTROLLER_PROMPTS)
+
+  // An edit is the next version and what runs read from then on
+  const edited = await store.update('controller_base', 'You are a terse controller.')
+  assert.equal(edited.version, 2)
+  assert.equal(edited.isDefault, false)
+  assert.equal((await store.active()).controller_base, 'You are a terse controller.')
+  assert.equal((await store.active()).controller_openai, DEFAULT_CONTROLLER_PROMPTS.controller_openai)
+  assert.equal((await store.update('controller_base', 'You are a terse controller.')).version, 2)
+  await assert.rejects(store.update('controller_base', '  '), /cannot be empty/)
+
+  // Reset restores the default as a new version, keeping the edit in the history
+  const reset = await store.reset('controller_base')
+  assert.equal(reset.version, 3)
+  assert.equal(reset.isDefault, true)
+  assert.deepEqual((await store.versions('controller_base')).map((row) => row.version), [3, 2, 1])
+  assert.deepEqual(await store.active(), DEFAULT_CONTROLLER_PROMPTS)
+
+  // The prompt builder uses the text it is given, and the defaults otherwise
+  const agent = {
+    id: 'agent-1',
+    sessionId: 'session-1',
+    task: 'Say hi',
+    depth: 0,
+    config: { model: 'test', provider: 'anthropic', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 },
+  } as unknown as Agent
+  const system = (prompts?: typeof DEFAULT_CONTROLLER_PROMPTS) => {
+    const { messages } = buildControllerPrompt(agent, { listMetadata: () => [] }, [], { controllerPrompts: prompts })
+    const first = messages[0]
+    return typeof first.content === 'string' ? first.content : ''
+  }
+  assert.ok(system().includes(DEFAULT_CONTROLLER_PROMPTS.controller_anthropic))
+  assert.ok(system({ ...DEFAULT_CONTROLLER_PROMPTS, controller_anthropic: 'Edited controller.' }).includes('Edited controller.'))
+
+  console.log('controller prompt tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,45 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { getConversationStats } from '../services/conversation-stats.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'conversation-stats-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'stats.db')))
+  const runtime = { repositories: repos } as unknown as RuntimeContext
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Stats' })
+  const root = await repos.agents.create({ sessionId: session.id, task: 'Plan', config })
+  const child = await repos.agents.create({ sessionId: session.id, parentId: root.id, task: 'Search', config, depth: 1 })
+
+  await repos.items.create({ agentId: root.id, type: 'message', role: 'user', content: 'Find flights', turnNumber: 1 })
+  await repos.items.create({ agentId: root.id, type: 'function_call', callId: 'c1', name: 'web.search', arguments: '{}', turnNumber: 1 })
+  await repos.items.create({ agentId: root.id, type: 'function_call_output', callId: 'c1', output: 'failed', isError: true, turnNumber: 1 })
+  await repos.items.create({ agentId: child.id, type: 'message', role: 'user', content: 'Search', turnNumber: 1 })
+  await repos.items.create({ agentId: child.id, type: 'function_call', callId: 'c2', name: 'web.search', arguments: '{}', turnNumber: 1 })
+  await repos.items.create({ agentId: child.id, type: 'function_call', callId: 'c3', name: 'web.fetch', arguments: '{}', turnNumber: 1 })
+  await repos.items.create({ agentId: root.id, type: 'message', role: 'assistant', content: 'Here are flights', turnNumber: 2 })
+  await repos.usage.record({ userId: user.id, sessionId: session.id, provider: 'openai', model: 'gpt-4o', inputTokens: 1, outputTokens: 1, costUsd: 0.01 })
+  await repos.usage.record({ userId: user.id, sessionId: session.id, provider: 'openai', model: 'gpt-4o', inputTokens: 1, outputTokens: 1, costUsd: 0.02 })
+
+  const stats = await getConversationStats(runtime, session.id)
+  assert.ok(stats)
+  assert.deepEqual(stats.messages, { system: 0, user: 1, assistant: 1 }, 'sub-agent messages are not part of the conversation')
+  assert.deepEqual(stats.tools, [
+    { name: 'web.search', calls: 2, errors: 1 },
+    { name: 'web.fetch', calls: 1, errors: 0 },
+  ])
+  assert.equal(stats.agentCount, 2)
+  assert.ok(stats.averageResponseMs !== null && stats.averageResponseMs >= 0)
+  assert.equal(stats.costUsd, 0.03)
+  assert.equal(await getConversationStats(runtime, 'missing'), null)
+
+  console.log('conversation stats tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
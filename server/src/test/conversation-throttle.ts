@@ -0,0 +1,52 @@
+import assert from 'node:assert/strict'
+import type { Agent } from '../domain/types.js'
+import { ConversationBusyError, ConversationThrottle } from '../services/conversation-throttle.js'
+
+const statuses = new Map<string, Agent['status']>()
+const agents = { getById: async (id: string) => (statuses.has(id) ? { id, status: statuses.get(id) } as Agent : null) }
+let now = 10_000
+
+const busy = async (promise: Promise<unknown>) => {
+  const err = await promise.then(() => null, (caught: unknown) => caught)
+  assert.ok(err instanceof ConversationBusyError)
+  return err.busy
+}
+
+// A double-send is turned away while the first is still being prepared, and while its run goes on
+const throttle = new ConversationThrottle(agents, { maxConcurrent: 1, minIntervalMs: 0 }, () => now)
+const first = await throttle.admit('s1')
+assert.deepEqual(await busy(throttle.admit('s1')), { reason: 'concurrent', sessionId: 's1', messageId: null, agentId: null })
+statuses.set('agent-1', 'running')
+first.started('agent-1', 'msg-1')
+assert.deepEqual(await busy(throttle.admit('s1')), { reason: 'concurrent', sessionId: 's1', messageId: 'msg-1', agentId: 'agent-1' })
+
+// Other conversations are unaffected
+const other = await throttle.admit('s2')
+other.release()
+
+// Once the run stops running, or waits on the user, the conversation takes sends again
+statuses.set('agent-1', 'waiting')
+const second = await throttle.admit('s1')
+second.release()
+const third = await throttle.admit('s1')
+// A send that failed to prepare, or started nothing, frees its place at once
+third.release()
+await throttle.admit('s1')
+
+// A queued send stays in flight until it runs
+const queued = new ConversationThrottle(agents, { maxConcurrent: 2, minIntervalMs: 0 }, () => now)
+statuses.set('agent-2', 'pending')
+;(await queued.admit('s3')).started('agent-2', 'msg-2')
+await queued.admit('s3')
+assert.equal((await busy(queued.admit('s3'))).messageId, 'msg-2')
+
+// Sends too close together wait out the interval
+const paced = new ConversationThrottle(agents, { maxConcurrent: 5, minIntervalMs: 2000 }, () => now)
+statuses.set('agent-3', 'completed')
+;(await paced.admit('s4')).started('agent-3', 'msg-3')
+now += 500
+assert.deepEqual(await busy(paced.admit('s4')), { reason: 'too_soon', sessionId: 's4', messageId: 'msg-3', agentId: 'agent-3', retryAfterMs: 1500 })
+now += 1500
+await paced.admit('s4')
+
+console.log('conversation throttle tests passed')
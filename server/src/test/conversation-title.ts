@@ -0,0 +1,75 @@
+import assert from 'node:assert/strict'
+import type { Item, Session } from '../domain/types.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import type { LLMRequest } from '../providers/types.js'
+import { generateConversationTitle, PLACEHOLDER_TITLE } from '../services/conversation-title.js'
+
+const sessions = new Map<string, Session>()
+const items = new Map<string, Item[]>()
+const requests: LLMRequest[] = []
+const recorded: string[] = []
+const emitted: AgentEvent[] = []
+let reply = '"Weekend trip to Lisbon."'
+let titleModel: string | undefined = 'openai:gpt-4o-mini'
+
+const runtime = {
+  get config() { return { titleModel } },
+  providers: {
+    resolve: () => ({
+      generate: async (request: LLMRequest) => {
+        requests.push(request)
+        return { content: reply, usage: { input_tokens: 40, output_tokens: 6 }, finish_reason: 'stop' }
+      },
+    }),
+  },
+  repositories: {
+    sessions: {
+      getById: async (id: string) => (sessions.has(id) ? { ...sessions.get(id)! } : null),
+      update: async (id: string, input: Partial<Session>) => Object.assign(sessions.get(id)!, input),
+    },
+    items: { listByAgent: async (agentId: string) => items.get(agentId) ?? [] },
+    agents: { getById: async (id: string) => ({ id }) },
+    preferences: { get: async () => null },
+  },
+  usage: { record: async (context: { phase: string }) => { recorded.push(context.phase) } },
+  events: { emit: (event: AgentEvent) => { emitted.push(event) } },
+} as unknown as Parameters<typeof generateConversationTitle>[0]
+
+const message = (role: 'user' | 'assistant', content: string) => ({ type: 'message', role, content }) as Item
+const conversation = (id: string, title: string | null, ...messages: Item[]) => {
+  sessions.set(id, { id, title, rootAgentId: `${id}-root` } as Session)
+  items.set(`${id}-root`, messages)
+  return { sessionId: id, agentId: `${id}-root` }
+}
+
+// A conversation still titled with its first message is named after the first exchange
+const first = conversation('s1', 'Can you plan a weekend in Lisbon for me', message('user', 'Can you plan a weekend in Lisbon for me'), message('assistant', 'Here is a plan.'))
+assert.equal(await generateConversationTitle(runtime, first), 'Weekend trip to Lisbon')
+assert.equal(sessions.get('s1')?.title, 'Weekend trip to Lisbon')
+assert.match(String(requests[0].messages[0].content), /written in English[\s\S]*user: Can you plan a weekend in Lisbon for me\nassistant: Here is a plan\./)
+assert.deepEqual(recorded, ['title'])
+assert.equal(emitted[0].type, EVENT_TYPES.CONVERSATION_UPDATED)
+assert.deepEqual([emitted[0].session_id, emitted[0].payload], ['s1', { title: 'Weekend trip to Lisbon' }])
+
+// So is one that started with attachments, under the placeholder
+reply = 'Title: Invoice totals'
+const attached = conversation('s2', PLACEHOLDER_TITLE, message('user', 'Add these up'), message('assistant', 'The total is 42.'))
+assert.equal(await generateConversationTitle(runtime, attached), 'Invoice totals')
+
+// Renamed conversations, later turns and unanswered messages are left alone
+requests.length = 0
+assert.equal(await generateConversationTitle(runtime, conversation('s3', 'My trip', message('user', 'Plan a trip'), message('assistant', 'Sure.'))), null)
+assert.equal(await generateConversationTitle(runtime, conversation('s4', 'Hi', message('user', 'Hi'), message('assistant', 'Hello!'), message('user', 'Plan a trip'), message('assistant', 'Sure.'))), null)
+assert.equal(await generateConversationTitle(runtime, conversation('s5', 'Hi', message('user', 'Hi'))), null)
+assert.equal(requests.length, 0)
+
+// Long answers are cut down to size
+reply = 'A '.repeat(60)
+const long = await generateConversationTitle(runtime, conversation('s6', null, message('user', 'Hi'), message('assistant', 'Hello!')))
+assert.ok(long && long.length <= 80 && long.endsWith('…'))
+
+// Nothing runs without a title model
+titleModel = undefined
+assert.equal(await generateConversationTitle(runtime, conversation('s7', null, message('user', 'Hi'), message('assistant', 'Hello!'))), null)
+
+console.log('conversation title tests passed')
@@ -0,0 +1,112 @@
+import assert from 'node:assert/strict'
+import { createHash } from 'node:crypto'
+import fs from 'node:fs/promises'
+import os from 'node:os'
+import path from 'node:path'
+
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import { loadConfig } from '../lib/config.js'
+import { initRuntime, shutdownRuntime, type RuntimeContext } from '../lib/runtime.js'
+import type { LLMRequest } from '../providers/types.js'
+import { DailyDigest, DIGEST_TITLE, DIGEST_TOOLS, localDate, nextDigestAt, parseDigestTime, type DigestNotification } from '../services/daily-digest.js'
+
+// Times are read as a 24-hour clock in server local time
+assert.deepEqual(parseDigestTime('7:05'), { hour: 7, minute: 5 })
+assert.deepEqual(parseDigestTime(' 23:59 '), { hour: 23, minute: 59 })
+assert.equal(parseDigestTime('24:00'), null)
+assert.equal(parseDigestTime('7am'), null)
+const morning = new Date(2026, 2, 10, 6, 0).getTime()
+assert.equal(nextDigestAt({ hour: 7, minute: 30 }, morning), new Date(2026, 2, 10, 7, 30).getTime())
+assert.equal(nextDigestAt({ hour: 7, minute: 30 }, new Date(2026, 2, 10, 7, 30).getTime()), new Date(2026, 2, 11, 7, 30).getTime())
+assert.equal(localDate(morning), '2026-03-10')
+
+async function main() {
+  const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), 'daily-digest-test-'))
+  process.env.DATABASE_URL = path.join(tmpDir, 'test.db')
+  process.env.TASKS_DIR = path.join(tmpDir, 'tasks')
+  process.env.WORKSPACE_DIR = path.join(tmpDir, 'workspace')
+  process.env.SESSION_FILES_DIR = path.join(tmpDir, 'sessions')
+  delete process.env.DIGEST_TIME
+  delete process.env.ANTHROPIC_API_KEY
+  delete process.env.OPENAI_API_KEY
+  delete process.env.OLLAMA_BASE_URL
+  delete process.env.OPENROUTER_API_KEY
+  process.env.LANGFUSE_ENABLED = 'false'
+
+  let runtime: RuntimeContext | null = null
+  try {
+    runtime = await initRuntime(loadConfig())
+    const user = await runtime.repositories.users.create({
+      email: 'digest@test.local',
+      apiKeyHash: createHash('sha256').update('daily-digest-test-key').digest('hex'),
+    })
+
+    const requests: LLMRequest[] = []
+    const briefing = '## Today\n\n**Three meetings** and two unread emails need you.\n\n### Calendar\n- 09:00 Standup'
+    runtime.providers.register('openrouter', {
+      async generate(request: LLMRequest) {
+        requests.push(request)
+        return { content: briefing, usage: { input_tokens: 1, output_tokens: 1 }, finish_reason: 'stop' }
+      },
+      async *stream() {
+        yield { type: 'done' as const, response: { content: briefing, usage: { input_tokens: 1, output_tokens: 1 }, finish_reason: 'stop' } }
+      },
+    })
+    const emitted: AgentEvent[] = []
+    const emit = runtime.events.emit.bind(runtime.events)
+    runtime.events.emit = (event: AgentEvent) => {
+      emitted.push(event)
+      emit(event)
+    }
+    const posted: DigestNotification[] = []
+    const fetchImpl = (async (_url: string, init: RequestInit) => {
+      posted.push(JSON.parse(String(init.body)))
+      return new Response(null, { status: 204 })
+    }) as unknown as typeof fetch
+
+    const digest = new DailyDigest(runtime, { time: '07:30', webhookUrl: 'https://push.test/digest', fetchImpl })
+    const now = new Date(2026, 2, 10, 7, 30).getTime()
+
+    // The briefing lands in its own conversation and the user hears about it
+    const [first] = await digest.runAll(now)
+    assert.equal(first.status, 'completed')
+    assert.equal(first.date, '2026-03-10')
+    assert.equal(first.briefing, briefing)
+    const session = await runtime.repositories.sessions.getById(first.sessionId)
+    assert.equal(session?.title, DIGEST_TITLE)
+    assert.equal(session?.userId, user.id)
+    const agent = (await runtime.repositories.agents.findRootAgent(first.sessionId))!
+    assert.ok(agent.config.allowed_tools?.every((name) => DIGEST_TOOLS.includes(name)))
+    assert.ok(agent.config.allowed_tools?.includes('tasks.list'))
+    assert.match(JSON.stringify(requests[0].messages), /Morning briefing for Tuesday, March 10, 2026/)
+
+    const ready = emitted.find((event) => event.type === EVENT_TYPES.DIGEST_READY)
+    assert.equal(ready?.session_id, first.sessionId)
+    assert.deepEqual(ready?.payload, { date: '2026-03-10', excerpt: 'Three meetings and two unread emails need you.' })
+    assert.deepEqual(posted, [{ title: 'Daily digest · 2026-03-10', message: 'Three meetings and two unread emails need you.', sessionId: first.sessionId, date: '2026-03-10' }])
+
+    // Once a day: a second pass the same day does nothing
+    assert.deepEqual(await digest.runAll(now + 60_000), [])
+    assert.equal(requests.length, 1)
+
+    // The next day's briefing goes to the same conversation
+    const next = await digest.run(user.id, now + 24 * 60 * 60 * 1000)
+    assert.equal(next.status, 'completed')
+    assert.equal(next.sessionId, first.sessionId)
+    assert.equal(next.date, '2026-03-11')
+
+    // A deleted digest conversation is replaced
+    await runtime.repositories.sessions.update(first.sessionId, { deletedAt: Date.now() })
+    const replaced = await digest.run(user.id, now + 2 * 24 * 60 * 60 * 1000)
+    assert.notEqual(replaced.sessionId, first.sessionId)
+
+    assert.throws(() => new DailyDigest(runtime!, { time: 'noon' }), /DIGEST_TIME must be HH:MM/)
+
+    console.log('daily digest tests passed')
+  } finally {
+    if (runtime) await shutdownRuntime(runtime)
+    await fs.rm(tmpDir, { recursive: true, force: true })
+  }
+}
+
+main()
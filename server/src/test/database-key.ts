@@ -0,0 +1,107 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import Database from 'better-sqlite3'
+import { databaseKeyAccount, encryptDatabase, resolveDatabaseKey } from '../lib/database-key.js'
+import { KEYCHAIN_SERVICE, systemKeychain, type Keychain } from '../lib/keychain.js'
+import { applyDatabaseKey, hasCipherSupport } from '../repositories/sqlite/index.js'
+
+function memoryKeychain(): Keychain & { entries: Map<string, string> } {
+  const entries = new Map<string, string>()
+  return {
+    entries,
+    get: async (account) => entries.get(account) ?? null,
+    set: async (account, value) => void entries.set(account, value),
+    delete: async (account) => void entries.delete(account),
+  }
+}
+
+function plaintextDatabase(file: string): void {
+  const sqlite = new Database(file)
+  sqlite.exec("CREATE TABLE notes (body TEXT); INSERT INTO notes VALUES ('secret')")
+  sqlite.close()
+}
+
+function readNotes(file: string, key?: string): unknown {
+  const sqlite = new Database(file, { fileMustExist: true })
+  try {
+    if (key) applyDatabaseKey(sqlite, key)
+    return sqlite.prepare('SELECT body FROM notes').pluck().get()
+  } finally {
+    sqlite.close()
+  }
+}
+
+const dir = mkdtempSync(join(tmpdir(), 'database-key-'))
+try {
+  // DATABASE_KEY wins; without the keychain source nothing is looked up
+  const keychain = memoryKeychain()
+  const fresh = join(dir, 'fresh.db')
+  assert.equal(await resolveDatabaseKey({ databaseUrl: fresh, databaseKey: 'from-env', databaseKeySource: 'keychain' }, keychain), 'from-env')
+  assert.equal(await resolveDatabaseKey({ databaseUrl: fresh, databaseKey: undefined, databaseKeySource: 'env' }, keychain), undefined)
+  assert.equal(keychain.entries.size, 0)
+
+  // First run generates a key and keeps it; later runs read it back
+  const generated = await resolveDatabaseKey({ databaseUrl: fresh, databaseKey: undefined, databaseKeySource: 'keychain' }, keychain)
+  assert.match(generated ?? '', /^[A-Za-z0-9_-]{43}$/)
+  assert.equal(keychain.entries.get(databaseKeyAccount(fresh)), generated)
+  assert.equal(await resolveDatabaseKey({ databaseUrl: fresh, databaseKey: undefined, databaseKeySource: 'keychain' }, keychain), generated)
+
+  // An existing database without a saved key is not opened as plaintext
+  const existing = join(dir, 'existing.db')
+  plaintextDatabase(existing)
+  await assert.rejects(
+    resolveDatabaseKey({ databaseUrl: existing, databaseKey: undefined, databaseKeySource: 'keychain' }, keychain),
+    /keychain has no key for this database/,
+  )
+  await assert.rejects(encryptDatabase({ databaseUrl: existing, databaseKey: undefined, databaseKeySource: 'env' }, keychain), /Set DATABASE_KEY/)
+
+  const config = { databaseUrl: existing, databaseKey: undefined, databaseKeySource: 'keychain' as const }
+  if (hasCipherSupport(new Database(':memory:'))) {
+    // encrypt-db: the plaintext file is replaced by an encrypted copy keyed from the keychain
+    assert.deepEqual(await encryptDatabase(config, keychain), { rotated: false })
+    const key = keychain.entries.get(databaseKeyAccount(existing))!
+    assert.equal(readNotes(existing, key), 'secret')
+    assert.throws(() => readNotes(existing))
+    assert.throws(() => readNotes(existing, 'wrong'), /DATABASE_KEY is wrong/)
+
+    // Running it again rotates from the saved key
+    assert.deepEqual(await encryptDatabase(config, keychain), { rotated: true })
+    const rotated = keychain.entries.get(databaseKeyAccount(existing))!
+    assert.notEqual(rotated, key)
+    assert.equal(readNotes(existing, rotated), 'secret')
+  } else {
+    // Stock builds fail closed and leave the file and the keychain as they were
+    assert.throws(() => applyDatabaseKey(new Database(':memory:'), 'key'), /without encryption support/)
+    await assert.rejects(encryptDatabase(config, keychain), /without encryption support/)
+    assert.equal(readNotes(existing), 'secret')
+    assert.equal(keychain.entries.has(databaseKeyAccount(existing)), false)
+  }
+
+  // OS keychains: the value never reaches Linux argv, and a missing entry reads as null
+  const calls: Array<{ command: string; args: string[]; input?: string }> = []
+  const linux = systemKeychain('linux', async (command, args, input) => {
+    calls.push({ command, args, input })
+    if (args[0] === 'lookup') throw Object.assign(new Error('no match'), { exitCode: 1 })
+    return ''
+  })
+  await linux.set('database-key:/data/app.db', 's3cret', 'AI Agent database key')
+  assert.deepEqual(calls[0], {
+    command: 'secret-tool',
+    args: ['store', '--label=AI Agent database key', 'service', KEYCHAIN_SERVICE, 'account', 'database-key:/data/app.db'],
+    input: 's3cret',
+  })
+  assert.equal(await linux.get('database-key:/data/app.db'), null)
+  const mac = systemKeychain('darwin', async (_command, args) => {
+    if (args[0] === 'find-generic-password') return 's3cret\n'
+    throw Object.assign(new Error('failed'), { exitCode: 51 })
+  })
+  assert.equal(await mac.get('database-key:/data/app.db'), 's3cret')
+  await assert.rejects(mac.delete('database-key:/data/app.db'), /failed/)
+  await assert.rejects(systemKeychain('win32').get('x'), /set DATABASE_KEY instead/)
+
+  console.log('database key tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
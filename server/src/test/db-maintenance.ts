@@ -0,0 +1,49 @@
+import assert from 'node:assert/strict'
+import { mkdirSync, mkdtempSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { runDbMaintenance } from '../services/db-maintenance.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'db-maintenance-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'maintenance.db')))
+  const events: AgentEvent[] = []
+  mkdirSync(join(dir, 'sessions', 's1'), { recursive: true })
+  writeFileSync(join(dir, 'sessions', 's1', 'note.md'), 'hello')
+  const runtime = {
+    repositories: repos,
+    sessionFilesRoot: join(dir, 'sessions'),
+    attachments: { dir: join(dir, 'attachments') },
+    tasksDir: join(dir, 'tasks'),
+    notesDir: join(dir, 'notes'),
+    debugTraces: { dir: join(dir, 'traces') },
+    events: { emit: (event: AgentEvent) => events.push(event) },
+  } as unknown as RuntimeContext
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  for (let i = 0; i < 50; i++) {
+    await repos.sessions.create({ userId: user.id, title: `Session ${i}`.padEnd(2000, '.') })
+  }
+  for (const session of await repos.sessions.listByUser(user.id)) {
+    await repos.sessions.delete(session.id)
+  }
+
+  const result = await runDbMaintenance(runtime)
+  assert.equal(result.database.dialect, 'sqlite')
+  assert.equal(result.database.integrity, 'ok')
+  assert.equal(result.health.status, 'ok')
+  assert.ok(result.database.sizeBytesAfter <= result.database.sizeBytesBefore, 'VACUUM never grows the file')
+  assert.deepEqual(
+    result.artifacts.map((store) => [store.name, store.files, store.bytes]),
+    [['sessions', 1, 5], ['attachments', 0, 0], ['tasks', 0, 0], ['notes', 0, 0], ['traces', 0, 0]],
+  )
+  assert.equal(events.length, 1)
+  assert.equal(events[0].type, EVENT_TYPES.MAINTENANCE_COMPLETED)
+
+  console.log('db maintenance tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,62 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { Agent } from '../domain/types.js'
+import { DebugTraceRecorder, fitToSize } from '../observability/debug-traces.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'debug-traces-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'traces.db')))
+  const recorder = new DebugTraceRecorder({
+    dir: join(dir, 'traces'),
+    preferences: repos.preferences,
+    defaultEnabled: false,
+    maxTraceBytes: 4096,
+    maxTotalBytes: 10_000,
+    retentionDays: 7,
+  })
+  const agent = { id: 'agent-1', sessionId: 'session-1', parentId: null, depth: 0 } as Agent
+  const trace = (content: string) => ({
+    agent,
+    turnNumber: 1,
+    provider: 'openai',
+    model: 'gpt-4o',
+    stream: false,
+    request: { model: 'gpt-4o', messages: [{ role: 'user' as const, content }] },
+    response: { content: 'ok', usage: { input_tokens: 1, output_tokens: 1 }, finish_reason: 'stop' },
+    durationMs: 5,
+  })
+
+  await recorder.record(trace('hidden'))
+  assert.equal((await recorder.list('session-1')).length, 0, 'disabled by default')
+
+  await repos.preferences.set('debug_traces', 'true')
+  await recorder.record(trace('x'.repeat(20_000)))
+  const [written] = await recorder.list('session-1')
+  assert.ok(written.bytes <= 4096, 'oversized traces are truncated to the cap')
+  const body = JSON.parse((await recorder.read('session-1', written.file))!)
+  assert.equal(body.model, 'gpt-4o')
+  assert.match(body.request.messages[0].content, /truncated/)
+
+  assert.equal(await recorder.read('session-1', '../traces.db'), null, 'paths outside the trace dir are rejected')
+
+  // Total cap keeps the newest traces.
+  for (let i = 0; i < 4; i++) {
+    await new Promise((resolve) => setTimeout(resolve, 2))
+    await recorder.record(trace('y'.repeat(3000)))
+  }
+  await recorder.prune()
+  const kept = await recorder.list('session-1')
+  assert.ok(kept.reduce((sum, t) => sum + t.bytes, 0) <= 10_000)
+  assert.ok(!kept.some((t) => t.file === written.file), 'oldest trace pruned first')
+
+  // Retention drops everything older than the window.
+  assert.equal(await recorder.prune(Date.now() + 8 * 24 * 60 * 60 * 1000), kept.length)
+
+  assert.equal(fitToSize({ a: 'short' }, 1000), JSON.stringify({ a: 'short' }, null, 2))
+  console.log('debug trace tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
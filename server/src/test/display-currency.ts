@@ -0,0 +1,62 @@
+import assert from 'node:assert/strict'
+import type { UsageRecord } from '../repositories/types.js'
+import { buildToolCostReport } from '../usage/attribution.js'
+import {
+  convertToolCostReport,
+  convertUsageDashboard,
+  convertUsageReport,
+  ExchangeRateError,
+  fetchExchangeRate,
+  normalizeCurrencySettings,
+  parseCurrencySettings,
+} from '../usage/currency.js'
+import { buildUsageDashboard, buildUsageReport } from '../usage/report.js'
+
+// Settings validation
+assert.deepEqual(normalizeCurrencySettings({ code: 'eur', rate: 0.9, locale: 'de-de' }), {
+  code: 'EUR', rate: 0.9, locale: 'de-DE', source: 'manual', rateUpdatedAt: null,
+})
+assert.equal(normalizeCurrencySettings({ code: 'PLN' }).rate, 0, 'a missing rate is left for the caller to fetch')
+assert.equal(normalizeCurrencySettings({ code: 'USD', rate: 3 }).rate, 1)
+assert.throws(() => normalizeCurrencySettings({ code: 'euro' }), /ISO 4217/)
+assert.throws(() => normalizeCurrencySettings({ code: 'EUR', rate: -1 }), /rate/)
+assert.throws(() => normalizeCurrencySettings({ code: 'EUR', rate: 1, locale: 'not a locale!' }), /BCP 47/)
+assert.equal(parseCurrencySettings(null).code, 'USD')
+assert.equal(parseCurrencySettings('{"code":"bogus"}').code, 'USD', 'malformed settings fall back to USD')
+assert.deepEqual(parseCurrencySettings(JSON.stringify({ code: 'GBP', rate: 0.8, source: 'remote', rateUpdatedAt: 5 })), {
+  code: 'GBP', rate: 0.8, locale: null, source: 'remote', rateUpdatedAt: 5,
+})
+
+// Fetched rates
+const feed = (body: unknown, status = 200) => (async () => new Response(JSON.stringify(body), { status })) as typeof fetch
+assert.equal(await fetchExchangeRate('EUR', 'https://example.test/rates', feed({ rates: { EUR: 0.92 } })), 0.92)
+await assert.rejects(fetchExchangeRate('JPY', 'https://example.test/rates', feed({ rates: { EUR: 0.92 } })), /no rate for JPY/)
+await assert.rejects(fetchExchangeRate('EUR', 'https://example.test/rates', feed({}, 503)), ExchangeRateError)
+await assert.rejects(fetchExchangeRate('EUR', ''), /EXCHANGE_RATE_URL/)
+
+// Reports convert every amount and say which currency they are in
+const record = (overrides: Partial<UsageRecord>): UsageRecord => ({
+  id: 'r', userId: 'user', sessionId: 'session-a', agentId: 'agent', provider: 'openai', model: 'gpt-4o', phase: 'controller',
+  inputTokens: 100, outputTokens: 50, cacheReadTokens: 0, cacheWriteTokens: 0, costUsd: 0.5, cacheSavingsUsd: 0.1,
+  toolContext: { 'files.read': { tokens: 10, costUsd: 0.2 } }, promptComposition: null, createdAt: Date.UTC(2025, 0, 6), ...overrides,
+})
+const records = [record({}), record({ model: 'gpt-4o-mini', costUsd: 0.25, cacheSavingsUsd: 0 })]
+const eur = { code: 'EUR', rate: 0.9, locale: 'de-DE' }
+
+const report = convertUsageReport(buildUsageReport(records, 'model'), eur)
+assert.equal(report.totals.cost, 0.675)
+assert.equal(report.totals.cache_savings, 0.09)
+assert.equal(report.totals.input_tokens, 200, 'token counts are untouched')
+assert.deepEqual(report.buckets.map((b) => b.cost), [0.45, 0.225])
+assert.deepEqual(report.currency, eur)
+
+const dashboard = convertUsageDashboard(buildUsageDashboard(records), eur)
+assert.deepEqual(dashboard.daily[0].models, { 'openai:gpt-4o': 0.45, 'openai:gpt-4o-mini': 0.225 })
+assert.equal(dashboard.conversations[0].cost, 0.675)
+
+const tools = convertToolCostReport(buildToolCostReport(records), eur)
+assert.equal(tools.totalCost, 0.675)
+assert.equal(tools.tools[0].cost, 0.36)
+assert.equal(tools.integrations[0].cost, 0.36)
+
+console.log('display currency tests passed')
@@ -138,6 +138,33 @@ async function main() {
     assert(agentsBySession.length >= 1, 'listBySession returns at least 1 agent')
     assert(agentsBySession.some((a) => a.id === agent.id), 'listBySession includes created agent')
 
+    // finish applies the final state and closing message together
+    const finishAgent = await repos.agents.create({ sessionId: session.id, task: 'Finish task', config: agentConfig })
+    const finished = await repos.agents.finish(finishAgent.id, { status: 'completed', result: 'done', turnCount: 2 }, {
+      agentId: finishAgent.id,
+      type: 'message',
+      role: 'assistant',
+      content: 'done',
+      turnNumber: 2,
+    })
+    assert(finished.status === 'completed' && finished.turnCount === 2, 'finish applies the final state')
+    const finishItems = await repos.items.listByAgent(finishAgent.id)
+    assert(finishItems.length === 1 && finishItems[0].content === 'done', 'finish saves the closing message')
+    let finishFailed = false
+    try {
+      await repos.agents.finish('missing-agent', { status: 'completed' }, {
+        agentId: finishAgent.id,
+        type: 'message',
+        role: 'assistant',
+        content: 'lost',
+        turnNumber: 3,
+      })
+    } catch {
+      finishFailed = true
+    }
+    assert(finishFailed, 'finish fails for an unknown agent')
+    assert((await repos.items.listByAgent(finishAgent.id)).length === 1, 'a failed finish keeps no message')
+
     // Test child agent
     const childAgent = await repos.agents.create({
       sessionId: session.id,
@@ -0,0 +1,93 @@
+import assert from 'node:assert/strict'
+import { mkdirSync, mkdtempSync, rmSync, utimesSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import { AttachmentStore } from '../lib/attachment-store.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { LLMEmbeddingRequest } from '../providers/types.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { EmbeddingMaintenance, EmbeddingMaintenanceInProgressError } from '../services/embedding-maintenance.js'
+import { addKnowledgeSource, KnowledgeIndexer } from '../services/knowledge-base.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'embedding-maintenance-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'embeddings.db')))
+  const events: AgentEvent[] = []
+  const embedded: string[] = []
+  const config: { attachmentEmbeddingModel?: string } = {}
+  const runtime = {
+    repositories: repos,
+    attachments: new AttachmentStore(join(dir, 'attachments')),
+    config,
+    providers: {
+      resolve: () => ({
+        async embed(request: LLMEmbeddingRequest) {
+          embedded.push(...request.input)
+          return { embeddings: request.input.map((text) => [text.length, 1]) }
+        },
+      }),
+    },
+    events: { emit: (event: AgentEvent) => events.push(event) },
+    projectsDir: join(dir, 'projects'),
+    shutdownController: new AbortController(),
+    knowledgeIndexer: null as KnowledgeIndexer | null,
+  }
+  runtime.knowledgeIndexer = new KnowledgeIndexer(runtime as unknown as RuntimeContext)
+  const maintenance = new EmbeddingMaintenance(runtime as unknown as RuntimeContext, { intervalMs: 60_000, maxDocuments: 1, pauseMs: 0 })
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+
+  // Indexed before any embedding model was configured
+  const docs = join(dir, 'docs')
+  mkdirSync(docs)
+  writeFileSync(join(docs, 'a.md'), 'Alpha notes')
+  writeFileSync(join(docs, 'b.md'), 'Beta notes')
+  const source = await addKnowledgeSource(runtime, user.id, { location: docs })
+  runtime.knowledgeIndexer.enqueue(source.id)
+  await runtime.knowledgeIndexer.idle()
+  assert.equal((await repos.knowledge.getById(source.id))!.status, 'ready')
+  await repos.memories.create({ userId: user.id, content: 'Prefers metric units', kind: 'preference', confidence: 0.9 })
+
+  const unchanged = await maintenance.run()
+  assert.deepEqual(unchanged, { knowledgeSources: 0, projects: 0, documents: 0, memories: 0, throttled: false })
+
+  // Once a model is set, documents are embedded a few per run and memories catch up
+  config.attachmentEmbeddingModel = 'openai:embed'
+  const first = await maintenance.run()
+  assert.equal(first.documents, 1)
+  assert.equal(first.throttled, true)
+  assert.equal(first.memories, 1)
+  assert.ok(embedded.includes('Prefers metric units'))
+  const second = await maintenance.run()
+  assert.equal(second.documents, 1)
+  assert.equal(second.throttled, false)
+  assert.equal(second.memories, 0)
+  assert.equal((await maintenance.run()).documents, 0)
+
+  const phases = new Set(events.flatMap((event) => event.type === EVENT_TYPES.EMBEDDINGS_PROGRESS ? [event.payload.phase] : []))
+  assert.deepEqual(phases, new Set(['knowledge', 'documents', 'memories']))
+  assert.equal(events.filter((event) => event.type === EVENT_TYPES.EMBEDDINGS_COMPLETED).length, 4)
+
+  // A touched but identical file is left alone; an edited one is re-indexed
+  const later = new Date(Date.now() + 60_000)
+  utimesSync(join(docs, 'a.md'), later, later)
+  assert.equal((await maintenance.run()).knowledgeSources, 0)
+  writeFileSync(join(docs, 'b.md'), 'Beta notes, revised')
+  utimesSync(join(docs, 'b.md'), later, later)
+  const before = (await repos.knowledge.getById(source.id))!.documents
+  assert.equal((await maintenance.run()).knowledgeSources, 1)
+  await runtime.knowledgeIndexer.idle()
+  const after = (await repos.knowledge.getById(source.id))!
+  assert.equal(after.status, 'ready')
+  assert.notEqual(after.documents.find((doc) => doc.path === 'b.md')!.hash, before.find((doc) => doc.path === 'b.md')!.hash)
+
+  // Runs do not overlap
+  const running = maintenance.run()
+  await assert.rejects(maintenance.run(), EmbeddingMaintenanceInProgressError)
+  await running
+
+  runtime.knowledgeIndexer.stop()
+  console.log('Embedding maintenance tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,112 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { LLMEmbeddingRequest } from '../providers/types.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { extractEntities, isIntegrationTool, lookupEntities, recordEntities, type EntityStoreDeps } from '../services/entities.js'
+import { registerEntityTools } from '../tools/entities.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const gmailOutput = JSON.stringify({
+  messages: [
+    {
+      id: 'm1',
+      from: 'Sarah Chen <sarah.chen@acme.com>',
+      to: '"Jan Kowalski" <jan@gmail.com>, notifications@github.com',
+      subject: 'Design review notes',
+      snippet: 'Looping in ops@acme.com for the rollout.',
+    },
+  ],
+})
+const calendarOutput = {
+  items: [
+    {
+      id: 'ev1_20261020',
+      recurringEventId: 'ev1',
+      summary: 'Design review',
+      start: { dateTime: '2026-10-20T10:00:00+02:00' },
+      organizer: { email: 'sarah.chen@acme.com' },
+      attendees: [
+        { email: 'sarah.chen@acme.com', displayName: 'Sarah Chen' },
+        { email: 'jan@gmail.com', self: true },
+        { email: 'c_123@resource.calendar.google.com', resource: true },
+        { email: 'sara.lopez@globex.co.uk', displayName: 'Sara Lopez' },
+      ],
+    },
+  ],
+}
+
+assert.equal(isIntegrationTool('mcp.gmail_1a2b3c4d.search_messages'), true)
+assert.equal(isIntegrationTool('mcp.google_calendar_5e6f7a8b.list_events'), true)
+assert.equal(isIntegrationTool('web.fetch'), false)
+
+// People come from headers and bare addresses; automated senders are skipped
+const fromMail = extractEntities(gmailOutput)
+const people = fromMail.filter((entity) => entity.kind === 'person')
+assert.deepEqual(people.map((person) => [person.key, person.name]), [
+  ['sarah.chen@acme.com', 'Sarah Chen'],
+  ['jan@gmail.com', 'Jan Kowalski'],
+  ['ops@acme.com', undefined],
+])
+// Organizations only come from company domains
+assert.deepEqual(fromMail.filter((entity) => entity.kind === 'organization').map((org) => [org.key, org.name]), [['acme.com', 'Acme']])
+
+// Calendar events give recurring meetings and attendees, without rooms or the user
+const fromCalendar = extractEntities(calendarOutput)
+const meeting = fromCalendar.find((entity) => entity.kind === 'meeting')
+assert.equal(meeting?.key, 'ev1')
+assert.equal(meeting?.name, 'Design review')
+assert.equal(meeting?.details?.attendees, 'sarah.chen@acme.com, jan@gmail.com, sara.lopez@globex.co.uk')
+assert.equal(meeting?.details?.organizer, 'sarah.chen@acme.com')
+assert.ok(!fromCalendar.some((entity) => entity.key.includes('resource.calendar')))
+assert.ok(fromCalendar.some((entity) => entity.kind === 'organization' && entity.key === 'globex.co.uk'))
+
+const dir = mkdtempSync(join(tmpdir(), 'entities-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'entities.db')))
+  const embedded: string[] = []
+  const deps: EntityStoreDeps = {
+    entities: repos.entities,
+    config: { attachmentEmbeddingModel: 'openai:embed' },
+    providers: {
+      resolve: () => ({
+        async embed(request: LLMEmbeddingRequest) {
+          embedded.push(...request.input)
+          return { embeddings: request.input.map((text) => ['design', 'acme'].map((word) => text.toLowerCase().split(word).length - 1)) }
+        },
+      }),
+    },
+  } as unknown as EntityStoreDeps
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Mail' })
+
+  // Seen twice, an entity gains a mention and keeps its vector while unchanged
+  await recordEntities(deps, user.id, fromMail)
+  const stored = await recordEntities(deps, user.id, fromCalendar)
+  const sarah = stored.find((entity) => entity.key === 'sarah.chen@acme.com')!
+  assert.equal(sarah.mentions, 2)
+  assert.equal(sarah.embeddingModel, 'openai:embed')
+  assert.equal(embedded.filter((text) => text.startsWith('Sarah Chen')).length, 1)
+  const ops = await repos.entities.findByKey(user.id, 'person', 'ops@acme.com')
+  assert.equal(ops?.name, 'ops@acme.com')
+
+  // "Sarah" resolves to a concrete address
+  const sarahs = await lookupEntities(deps, user.id, 'Sarah', { kind: 'person' })
+  assert.deepEqual(sarahs.map((person) => person.email), ['sarah.chen@acme.com'])
+  const [review] = await lookupEntities(deps, user.id, 'design review', { kind: 'meeting' })
+  assert.equal(review.details.recurrence, undefined)
+  assert.equal(review.details.time, '2026-10-20T10:00:00+02:00')
+
+  // The tool looks up entities for the session's user
+  const registry = new ToolRegistryImpl()
+  registerEntityTools(registry, { ...deps, sessions: repos.sessions, preferences: repos.preferences })
+  const ctx = { agent_id: 'agent', session_id: session.id, signal: new AbortController().signal }
+  const found = await registry.execute('entities.lookup', { query: 'Sarah Chen', kind: 'person', limit: 1 }, ctx)
+  assert.equal((found.output as { results: Array<{ email: string }> }).results[0].email, 'sarah.chen@acme.com')
+  assert.match(String((await registry.execute('entities.lookup', { query: 'x', kind: 'place' }, ctx)).error), /kind must be/)
+
+  console.log('Entity tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,96 @@
+import assert from 'node:assert/strict'
+import { AgentEventEmitter, coalesceEvents } from '../events/emitter.js'
+import { TextCoalescer } from '../events/text-coalescer.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+
+function delta(text: string, agentId = 'agent', sourceCallId: string | null = null): AgentEvent {
+  return {
+    type: EVENT_TYPES.TEXT_DELTA,
+    agent_id: agentId,
+    session_id: 'session',
+    timestamp: Date.now(),
+    payload: { text, parentId: null, depth: 0, sourceCallId },
+  }
+}
+
+function progress(callId: string, elapsedMs: number): AgentEvent {
+  return {
+    type: EVENT_TYPES.TOOL_PROGRESS,
+    agent_id: 'agent',
+    session_id: 'session',
+    timestamp: Date.now(),
+    payload: { callId, name: 'slow.tool', elapsedMs, parentId: null, depth: 0 },
+  }
+}
+
+const textOf = (event: AgentEvent) => (event.type === EVENT_TYPES.TEXT_DELTA ? event.payload.text : null)
+
+// Merge rules
+const merged = coalesceEvents(delta('Hel'), delta('lo'))
+assert.ok(merged)
+assert.equal(textOf(merged), 'Hello')
+assert.equal(coalesceEvents(delta('a'), delta('b', 'other-agent')), null)
+assert.equal(coalesceEvents(delta('a', 'agent', 'call-1'), delta('b', 'agent', 'call-2')), null)
+assert.equal(coalesceEvents(delta('a'), progress('call-1', 5)), null)
+const latest = coalesceEvents(progress('call-1', 5), progress('call-1', 10))
+assert.ok(latest?.type === EVENT_TYPES.TOOL_PROGRESS)
+assert.equal(latest.payload.elapsedMs, 10)
+assert.equal(coalesceEvents(progress('call-1', 5), progress('call-2', 10)), null)
+
+// Deltas inside the batch window arrive as one event
+const paced = new AgentEventEmitter({ batchMs: 30, maxEventsPerSecond: 0 })
+const iterator = paced.subscribe({ session_id: 'session' })[Symbol.asyncIterator]()
+const first = iterator.next()
+paced.emit(delta('Hel'))
+paced.emit(delta('lo '))
+paced.emit(delta('world'))
+const batched = await first
+assert.equal(textOf(batched.value), 'Hello world')
+
+// Other events end a batch rather than being reordered around it
+paced.emit(delta('a'))
+paced.emit(progress('call-1', 1))
+paced.emit(delta('b'))
+assert.equal(textOf((await iterator.next()).value), 'a')
+assert.equal((await iterator.next()).value.type, EVENT_TYPES.TOOL_PROGRESS)
+assert.equal(textOf((await iterator.next()).value), 'b')
+await iterator.return!()
+
+// The rate cap spaces deliveries out and coalesces the backlog meanwhile
+const capped = new AgentEventEmitter({ batchMs: 0, maxEventsPerSecond: 20 })
+const cappedIterator = capped.subscribe({ session_id: 'session' })[Symbol.asyncIterator]()
+capped.emit(progress('call-1', 1))
+const startedAt = Date.now()
+await cappedIterator.next()
+for (let i = 2; i <= 5; i++) capped.emit(progress('call-1', i))
+const next = await cappedIterator.next()
+assert.ok(Date.now() - startedAt >= 40, 'second event waits for the rate window')
+assert.ok(next.value.type === EVENT_TYPES.TOOL_PROGRESS)
+assert.equal(next.value.payload.elapsedMs, 5, 'only the newest heartbeat is delivered')
+await cappedIterator.return!()
+
+// Unpaced emitters deliver every event as before
+const plain = new AgentEventEmitter()
+const plainIterator = plain.subscribe({ session_id: 'session' })[Symbol.asyncIterator]()
+plain.emit(delta('x'))
+plain.emit(delta('y'))
+assert.equal(textOf((await plainIterator.next()).value), 'x')
+assert.equal(textOf((await plainIterator.next()).value), 'y')
+await plainIterator.return!()
+
+// Streamed text is buffered at the source and flushes on a timer or size, whichever comes first
+const flushed: string[] = []
+const coalescer = new TextCoalescer((text) => flushed.push(text), { flushMs: 20, maxChars: 10 })
+coalescer.push('Hel')
+coalescer.push('lo')
+assert.deepEqual(flushed, [])
+await new Promise((resolve) => setTimeout(resolve, 40))
+assert.deepEqual(flushed, ['Hello'])
+coalescer.push('a long chunk')
+assert.deepEqual(flushed, ['Hello', 'a long chunk'], 'a full buffer does not wait for the timer')
+coalescer.push('tail')
+coalescer.flush()
+coalescer.flush()
+assert.deepEqual(flushed, ['Hello', 'a long chunk', 'tail'])
+
+console.log('event pacing tests passed')
@@ -0,0 +1,36 @@
+import assert from 'node:assert/strict'
+import fs from 'node:fs/promises'
+import path from 'node:path'
+import { fileURLToPath } from 'node:url'
+import { EVENT_SCHEMA_VERSION, EVENT_TYPES, toWireEvent, type AgentEvent } from '../events/types.js'
+import { renderEventTypes } from '../scripts/generate-event-types.js'
+
+const event: AgentEvent = {
+  type: EVENT_TYPES.TOOL_COMPLETED,
+  agent_id: 'agent-1',
+  session_id: 'session-1',
+  timestamp: 1234,
+  payload: { callId: 'call-1', name: 'web.search', success: true, output: 'ok', durationMs: 5, parentId: null, depth: 0 },
+}
+
+const wire = toWireEvent(event)
+assert.equal(wire.type, 'tool:completed')
+assert.equal(wire.v, EVENT_SCHEMA_VERSION)
+assert.equal(wire.agentId, 'agent-1')
+assert.equal(wire.sessionId, 'session-1')
+assert.equal(wire.timestamp, 1234)
+if (wire.type === 'tool:completed') {
+  assert.equal(wire.callId, 'call-1')
+  assert.equal(wire.durationMs, 5)
+}
+assert.equal(toWireEvent(event, 'override').sessionId, 'override')
+
+// The frontend copy must match the server source
+const generated = path.resolve(path.dirname(fileURLToPath(import.meta.url)), '../../../src/lib/types/server-events.ts')
+assert.equal(
+  await fs.readFile(generated, 'utf-8'),
+  await renderEventTypes(),
+  'src/lib/types/server-events.ts is stale; run `bun run gen:event-types`',
+)
+
+console.log('event payload tests passed')
@@ -0,0 +1,35 @@
+import assert from 'node:assert/strict'
+import { AgentEventEmitter } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+
+function delta(sessionId: string, timestamp: number, text: string): AgentEvent {
+  return { type: EVENT_TYPES.TEXT_DELTA, agent_id: 'agent', session_id: sessionId, timestamp, payload: { text, parentId: null, depth: 0 } }
+}
+
+const events = new AgentEventEmitter()
+events.emit(delta('a', 100, 'Hel'))
+events.emit(delta('a', 200, 'lo'))
+events.emit(delta('b', 150, 'other'))
+
+assert.deepEqual(events.replay({ session_id: 'a' }).map((e) => e.timestamp), [100, 200])
+assert.deepEqual(events.replay({ session_id: 'a' }, 200).map((e) => e.timestamp), [200], 'since is inclusive')
+assert.equal(events.replay({ session_id: 'a', types: [EVENT_TYPES.TOOL_STARTED] }).length, 0)
+assert.equal(events.replay({ session_id: 'missing' }).length, 0)
+
+// Oldest sessions are evicted first
+for (let i = 0; i < 60; i++) events.emit(delta(`s${i}`, i, 'x'))
+assert.equal(events.replay({ session_id: 'a' }).length, 0)
+assert.equal(events.replay({ session_id: 's59' }).length, 1)
+
+// Session-scoped subscribers only receive their session's events
+const scoped = new AgentEventEmitter()
+const received: string[] = []
+const iterator = scoped.subscribe({ session_id: 'mine', types: [EVENT_TYPES.TEXT_DELTA] })[Symbol.asyncIterator]()
+scoped.emit(delta('other', 1, 'no'))
+scoped.emit({ type: EVENT_TYPES.TOOL_STARTED, agent_id: 'agent', session_id: 'mine', timestamp: 2, payload: {} } as unknown as AgentEvent)
+scoped.emit(delta('mine', 3, 'yes'))
+received.push(((await iterator.next()).value as AgentEvent & { payload: { text: string } }).payload.text)
+await iterator.return?.()
+assert.deepEqual(received, ['yes'])
+
+console.log('event replay tests passed')
@@ -0,0 +1,171 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { FeedInputError, FeedPoller, parseFeed, subscribeFeed } from '../services/feeds.js'
+import { registerFeedTools } from '../tools/feeds.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const rss = (items: string) => `<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <channel>
+    <title>Example News</title>
+    <link>https://news.example.com/</link>
+    ${items}
+  </channel>
+</rss>`
+const launch = `<item>
+  <title>Rocket launch delayed</title>
+  <link>https://news.example.com/launch</link>
+  <guid isPermaLink="false">launch-1</guid>
+  <pubDate>Fri, 16 Oct 2026 09:00:00 GMT</pubDate>
+  <dc:creator>Ada</dc:creator>
+  <description>&lt;p&gt;Weather pushed the launch to &lt;b&gt;Monday&lt;/b&gt; morning.&lt;/p&gt;</description>
+  <content:encoded><![CDATA[<p>Weather pushed the launch to <b>Monday</b> morning.</p><p>Crews will try again at dawn.</p>]]></content:encoded>
+</item>`
+const budget = `<item>
+  <title>City passes budget</title>
+  <link>https://news.example.com/budget</link>
+  <pubDate>Sat, 17 Oct 2026 07:30:00 GMT</pubDate>
+  <description>The council approved next year's budget.</description>
+</item>`
+const atom = `<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title type="text">Dev Blog</title>
+  <link rel="self" href="https://blog.example.org/atom.xml"/>
+  <link href="/"/>
+  <author><name>Grace</name></author>
+  <entry>
+    <id>tag:blog.example.org,2026:post-7</id>
+    <title>Faster builds</title>
+    <link rel="alternate" href="/posts/faster-builds"/>
+    <updated>2026-10-15T12:00:00Z</updated>
+    <summary>How we cut build times in half.</summary>
+    <content type="xhtml"><div xmlns="http://www.w3.org/1999/xhtml"><p>How we cut build times in half.</p><p>Caching did most of it.</p></div></content>
+  </entry>
+</feed>`
+
+// Parsing: RSS 2.0 with HTML descriptions, Atom with relative links and a feed-level author
+const parsedRss = parseFeed(rss(launch))!
+assert.equal(parsedRss.title, 'Example News')
+assert.equal(parsedRss.siteUrl, 'https://news.example.com/')
+assert.deepEqual(parsedRss.items[0], {
+  guid: 'launch-1',
+  title: 'Rocket launch delayed',
+  link: 'https://news.example.com/launch',
+  author: 'Ada',
+  summary: 'Weather pushed the launch to Monday morning.',
+  content: 'Weather pushed the launch to Monday morning.\nCrews will try again at dawn.',
+  publishedAt: Date.parse('2026-10-16T09:00:00Z'),
+})
+const parsedAtom = parseFeed(atom, 'https://blog.example.org/atom.xml')!
+assert.equal(parsedAtom.siteUrl, 'https://blog.example.org/')
+assert.equal(parsedAtom.items[0].link, 'https://blog.example.org/posts/faster-builds')
+assert.equal(parsedAtom.items[0].author, 'Grace')
+assert.equal(parsedAtom.items[0].guid, 'tag:blog.example.org,2026:post-7')
+assert.match(parsedAtom.items[0].content, /Caching did most of it/)
+assert.equal(parseFeed('<html><body>Not a feed</body></html>'), null)
+
+let newsBody = rss(launch)
+const requests: Array<{ url: string; ifNoneMatch: string | null }> = []
+const fakeFetch = (async (input: string | URL | Request, init?: RequestInit) => {
+  const url = String(input)
+  const headers = new Headers(init?.headers)
+  requests.push({ url, ifNoneMatch: headers.get('if-none-match') })
+  const reply = (body: string, type: string, extra: Record<string, string> = {}) => {
+    const response = new Response(body, { headers: { 'content-type': type, ...extra } })
+    Object.defineProperty(response, 'url', { value: url })
+    return response
+  }
+  if (url === 'https://news.example.com/rss') {
+    if (headers.get('if-none-match') === `"${newsBody.length}"`) return new Response(null, { status: 304 })
+    return reply(newsBody, 'application/rss+xml', { etag: `"${newsBody.length}"` })
+  }
+  if (url === 'https://blog.example.org/') {
+    return reply('<!doctype html><html><head><link rel="alternate" type="application/atom+xml" href="/atom.xml"></head><body>Blog</body></html>', 'text/html')
+  }
+  if (url === 'https://blog.example.org/atom.xml') return reply(atom, 'application/atom+xml')
+  if (url === 'https://plain.example.net/') return reply('<!doctype html><html><body>No feeds here</body></html>', 'text/html')
+  return new Response('Not found', { status: 404 })
+}) as typeof fetch
+
+const dir = mkdtempSync(join(tmpdir(), 'feeds-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'feeds.db')))
+  const runtime = { repositories: repos }
+  const alice = await repos.users.create({ apiKeyHash: 'alice' })
+  const bob = await repos.users.create({ apiKeyHash: 'bob' })
+  const session = await repos.sessions.create({ userId: alice.id, title: 'Digest' })
+  const bobSession = await repos.sessions.create({ userId: bob.id, title: 'Bob' })
+
+  // Subscribing fetches the feed once; a web page is followed to the feed it links
+  const news = await subscribeFeed(runtime, alice.id, { url: 'https://news.example.com/rss' }, fakeFetch)
+  assert.equal(news.feed.title, 'Example News')
+  assert.equal(news.added, 1)
+  assert.equal(news.feed.etag, `"${newsBody.length}"`)
+  const again = await subscribeFeed(runtime, alice.id, { url: 'https://news.example.com/rss' }, fakeFetch)
+  assert.equal(again.existing, true)
+  assert.equal(again.feed.id, news.feed.id)
+  await assert.rejects(subscribeFeed(runtime, alice.id, { url: 'https://plain.example.net/' }, fakeFetch), /without an RSS or Atom feed link/)
+  await assert.rejects(subscribeFeed(runtime, alice.id, { url: 'ftp://example.com/feed' }, fakeFetch), FeedInputError)
+
+  const registry = new ToolRegistryImpl()
+  registerFeedTools(registry, { feeds: repos.feeds, sessions: repos.sessions, fetchImpl: fakeFetch })
+  const ctx = { agent_id: 'agent', session_id: session.id, signal: new AbortController().signal }
+  const blog = await registry.execute('feeds.subscribe', { url: 'https://blog.example.org/', title: 'Team blog' }, ctx)
+  assert.deepEqual(blog.output, {
+    feed: { id: (blog.output as { feed: { id: string } }).feed.id, title: 'Team blog', url: 'https://blog.example.org/atom.xml', site: 'https://blog.example.org/' },
+    items_stored: 1,
+  })
+
+  // Polling sends the stored validators and stores only entries not seen before
+  newsBody = rss(`${budget}\n${launch}`)
+  const poller = new FeedPoller(runtime, { intervalMs: 60_000, fetchImpl: fakeFetch })
+  requests.length = 0
+  assert.equal(await poller.pollAll(), 1)
+  assert.equal(requests.find((request) => request.url === 'https://news.example.com/rss')?.ifNoneMatch, `"${rss(launch).length}"`)
+  assert.equal(await poller.pollAll(), 0)
+
+  // Items come newest first with their feed's name; since narrows them to a window
+  const listed = await registry.execute('feeds.list_items', {}, ctx)
+  const items = (listed.output as { items: Array<{ id: string; feed: string; title: string; published: string; summary: string }> }).items
+  assert.deepEqual(items.map((item) => [item.feed, item.title]), [
+    ['Example News', 'City passes budget'],
+    ['Example News', 'Rocket launch delayed'],
+    ['Team blog', 'Faster builds'],
+  ])
+  assert.equal(items[0].published, '2026-10-17T07:30:00.000Z')
+  const recent = await registry.execute('feeds.list_items', { since: '2026-10-16T00:00:00Z', feed: 'example news' }, ctx)
+  assert.equal((recent.output as { items: unknown[] }).items.length, 2)
+  const one = await registry.execute('feeds.list_items', { limit: 1 }, ctx)
+  assert.equal((one.output as { truncated?: boolean }).truncated, true)
+  assert.match(String((await registry.execute('feeds.list_items', { since: 'yesterday' }, ctx)).error), /ISO 8601/)
+  assert.match(String((await registry.execute('feeds.list_items', { feed: 'Sports' }, ctx)).error), /No subscribed feed named Sports/)
+
+  const launchId = items[1].id
+  const read = await registry.execute('feeds.read_item', { id: launchId }, ctx)
+  assert.equal((read.output as { content: string }).content, 'Weather pushed the launch to Monday morning.\nCrews will try again at dawn.')
+  assert.equal((read.output as { author: string }).author, 'Ada')
+
+  // Another user's items stay private
+  const bobCtx = { ...ctx, session_id: bobSession.id }
+  assert.match(String((await registry.execute('feeds.read_item', { id: launchId }, bobCtx)).error), /Feed item not found/)
+  assert.match(String((await registry.execute('feeds.list_items', {}, bobCtx)).error), /No feeds are subscribed/)
+
+  // A failing feed records its error, which the listing reports
+  const gone = await repos.feeds.create({ userId: alice.id, url: 'https://gone.example.com/rss', title: 'Gone' })
+  await poller.pollAll()
+  assert.match((await repos.feeds.getById(gone.id))!.error ?? '', /HTTP 404/)
+  const withFailure = await registry.execute('feeds.list_items', {}, ctx)
+  assert.deepEqual((withFailure.output as { failing: unknown }).failing, [{ feed: 'Gone', error: 'Fetching https://gone.example.com/rss failed with HTTP 404' }])
+
+  // Unsubscribing removes the stored items too
+  assert.equal(await repos.feeds.delete(news.feed.id), true)
+  assert.equal(await repos.feeds.getItem(launchId), null)
+
+  poller.stop()
+  console.log('Feed tool tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
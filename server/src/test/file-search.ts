@@ -0,0 +1,85 @@
+import assert from 'node:assert/strict'
+import { mkdirSync, mkdtempSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { AttachmentStore } from '../lib/attachment-store.js'
+import type { LLMEmbeddingRequest } from '../providers/types.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { searchLocalFiles, type FileSearchDeps } from '../services/file-search.js'
+import { addKnowledgeSource, indexKnowledgeSource } from '../services/knowledge-base.js'
+import { ingestProject, linkProjects } from '../services/projects.js'
+import { registerFileSearchTools } from '../tools/file-search.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const TOPICS = ['retry', 'invoice', 'login']
+
+/** Embeds text by topic words, so "backoff" only finds retry code through the embedding. */
+function fakeEmbedding(text: string): number[] {
+  const lower = text.toLowerCase()
+  return TOPICS.map((topic) => lower.split(topic).length - 1 + (topic === 'retry' ? lower.split('backoff').length - 1 : 0))
+}
+
+const dir = mkdtempSync(join(tmpdir(), 'file-search-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'files.db')))
+  const attachments = new AttachmentStore(join(dir, 'attachments'))
+  const deps = {
+    knowledge: repos.knowledge,
+    sessions: repos.sessions,
+    attachments,
+    config: { attachmentEmbeddingModel: 'openai:embed' },
+    providers: {
+      resolve: () => ({
+        async embed(request: LLMEmbeddingRequest) {
+          return { embeddings: request.input.map(fakeEmbedding) }
+        },
+      }),
+    },
+    sessionFilesRoot: join(dir, 'sessions'),
+    projectsDir: join(dir, 'projects'),
+    shutdownController: new AbortController(),
+  } as unknown as FileSearchDeps
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Code' })
+
+  await assert.rejects(searchLocalFiles(deps, session.id, 'anything'), /No indexed local files/)
+
+  const repo = join(dir, 'repo')
+  mkdirSync(join(repo, 'src'), { recursive: true })
+  writeFileSync(join(repo, 'src', 'http.ts'), 'export function withRetry(fn) { /* retry with a delay */ }')
+  writeFileSync(join(repo, 'src', 'auth.ts'), 'export function login(user) { /* login flow */ }')
+  await ingestProject(deps, user.id, { root: repo, name: 'repo' })
+  await linkProjects(deps, session.id, ['repo'])
+
+  const docs = join(dir, 'docs')
+  mkdirSync(docs)
+  writeFileSync(join(docs, 'billing.md'), 'Every invoice is emailed on the first of the month.')
+  const source = await addKnowledgeSource({ repositories: repos }, user.id, { location: docs })
+  const documents = await indexKnowledgeSource(deps as never, source)
+  await repos.knowledge.update(source.id, { status: 'ready', documents })
+
+  // Matches by meaning: "backoff" never appears in the code
+  const [retry] = await searchLocalFiles(deps, session.id, 'exponential backoff', { limit: 1 })
+  assert.equal(retry.path, join(repo, 'src', 'http.ts'))
+  assert.equal(retry.source, 'repo')
+
+  // Knowledge-base folders are searched too, and `under` narrows to one folder
+  const [invoice] = await searchLocalFiles(deps, session.id, 'when are invoices sent', { limit: 1 })
+  assert.equal(invoice.path, join(docs, 'billing.md'))
+  assert.equal(invoice.source, 'docs')
+  const scoped = await searchLocalFiles(deps, session.id, 'invoice login', { under: join(repo, 'src') })
+  assert.deepEqual(scoped.map((result) => result.path), [join(repo, 'src', 'auth.ts')])
+  await assert.rejects(searchLocalFiles(deps, session.id, 'invoice', { under: 'src' }), /absolute/)
+  await assert.rejects(searchLocalFiles(deps, session.id, 'invoice', { under: join(dir, 'elsewhere') }), /No indexed files under/)
+
+  const registry = new ToolRegistryImpl()
+  registerFileSearchTools(registry, deps)
+  const ctx = { agent_id: 'agent', session_id: session.id, signal: new AbortController().signal }
+  const found = await registry.execute('files.semantic_search', { query: 'login form', under: repo }, ctx)
+  assert.equal(found.ok, true)
+  assert.ok((found.output as { results: unknown[] }).results.length > 0)
+
+  console.log('File search tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,86 @@
+import assert from 'node:assert/strict'
+import { GitHubClient, parseRepo } from '../services/github.js'
+import { registerGitHubTools } from '../tools/github.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const requests: Array<{ method: string; url: string; auth: string | null; body: unknown }> = []
+const issue = (number: number, extra: Record<string, unknown> = {}) => ({
+  number,
+  title: `Item ${number}`,
+  state: 'open',
+  user: { login: 'ana' },
+  labels: [{ name: 'bug' }],
+  comments: 0,
+  html_url: `https://github.com/acme/app/issues/${number}`,
+  body: 'Steps to reproduce',
+  created_at: '2026-10-01T00:00:00Z',
+  updated_at: '2026-10-02T00:00:00Z',
+  ...extra,
+})
+
+const fakeFetch = (async (input: string | URL | Request, init?: RequestInit) => {
+  const url = String(input)
+  const headers = new Headers(init?.headers)
+  requests.push({ method: init?.method ?? 'GET', url, auth: headers.get('authorization'), body: init?.body ? JSON.parse(String(init.body)) : undefined })
+  const path = new URL(url).pathname
+  const json = (body: unknown, status = 200) => new Response(JSON.stringify(body), { status, headers: { 'content-type': 'application/json' } })
+
+  if (path === '/api/v3/repos/acme/app/issues' && init?.method === 'POST') return json(issue(12), 201)
+  if (path === '/api/v3/repos/acme/app/issues') return json([issue(7), issue(8, { pull_request: {} })])
+  if (path === '/api/v3/repos/acme/app/issues/8') return json(issue(8, { pull_request: {}, comments: 1 }))
+  if (path === '/api/v3/repos/acme/app/issues/8/comments' && init?.method === 'POST') {
+    return json({ id: 99, user: { login: 'me' }, body: 'Thanks!', created_at: '2026-10-03T00:00:00Z', html_url: 'https://github.com/acme/app/pull/8#c99' }, 201)
+  }
+  if (path === '/api/v3/repos/acme/app/issues/8/comments') {
+    return json([{ id: 1, user: { login: 'ben' }, body: 'LGTM', created_at: '2026-10-02T00:00:00Z', html_url: 'https://github.com/acme/app/pull/8#c1' }])
+  }
+  if (path === '/api/v3/repos/acme/app/pulls/8') {
+    return json({ head: { label: 'ana:fix' }, base: { label: 'acme:main' }, draft: false, merged: false, mergeable: true, changed_files: 3 })
+  }
+  if (path === '/api/v3/search/code') {
+    return json({ total_count: 1, items: [{ path: 'src/config.ts', html_url: 'https://github.com/acme/app/blob/main/src/config.ts', repository: { full_name: 'acme/app' } }] })
+  }
+  return json({ message: 'Not Found' }, 404)
+}) as typeof fetch
+
+// Repositories may be given as URLs; anything else is refused
+assert.equal(parseRepo('https://github.com/acme/app.git'), 'acme/app')
+assert.throws(() => parseRepo('acme'), /owner\/name/)
+
+const registry = new ToolRegistryImpl()
+registerGitHubTools(registry, new GitHubClient('ghp_test', 'https://ghe.example.com/api/v3/', fakeFetch))
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+
+// Listing separates pull requests from issues and sends the token
+const pulls = await registry.execute('github.list_issues', { repo: 'acme/app', kind: 'pull', labels: ['bug'] }, ctx)
+assert.deepEqual((pulls.output as { issues: Array<{ number: number; kind: string }> }).issues.map((item) => [item.number, item.kind]), [[8, 'pull']])
+assert.equal(requests[0].auth, 'Bearer ghp_test')
+assert.match(requests[0].url, /labels=bug/)
+
+// Reading a pull request adds comments and branch state
+const read = await registry.execute('github.read_issue', { repo: 'acme/app', number: '#8' }, ctx)
+const pull = read.output as { commentList: Array<{ body: string }>; pull: { head: string; changedFiles: number } }
+assert.deepEqual(pull.commentList.map((comment) => comment.body), ['LGTM'])
+assert.deepEqual([pull.pull.head, pull.pull.changedFiles], ['ana:fix', 3])
+
+const code = await registry.execute('github.search', { query: 'repo:acme/app parseConfig', type: 'code' }, ctx)
+assert.deepEqual((code.output as { results: Array<{ path: string }> }).results.map((result) => result.path), ['src/config.ts'])
+
+// Writes ask first and show what will be posted
+assert.equal(registry.getMetadata('github.comment')?.requires_approval, true)
+assert.equal(registry.getMetadata('github.create_issue')?.requires_approval, true)
+assert.deepEqual(registry.getPreview('github.comment', { repo: 'acme/app', number: 8, body: 'Thanks!' }, ctx), {
+  summary: 'Comment on acme/app#8',
+  details: { body: 'Thanks!' },
+})
+const posted = await registry.execute('github.comment', { repo: 'acme/app', number: 8, body: 'Thanks!' }, ctx)
+assert.equal((posted.output as { comment: { id: number } }).comment.id, 99)
+const created = await registry.execute('github.create_issue', { repo: 'acme/app', title: 'Crash on start', labels: ['bug'] }, ctx)
+assert.equal((created.output as { issue: { number: number } }).issue.number, 12)
+assert.deepEqual(requests.at(-1)?.body, { title: 'Crash on start', labels: ['bug'] })
+
+// API failures come back as tool errors
+const missing = await registry.execute('github.read_issue', { repo: 'acme/other', number: 1 }, ctx)
+assert.match(String(missing.error), /Not found on GitHub/)
+
+console.log('GitHub tool tests passed')
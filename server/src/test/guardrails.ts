@@ -0,0 +1,100 @@
+import assert from 'node:assert/strict'
+import type { Item } from '../domain/types.js'
+import {
+  evaluateAction,
+  evaluateTurn,
+  GUARDRAIL_POLICY_PREFERENCE_KEY,
+  GuardrailEngine,
+  normalizeGuardrailPolicy,
+  parseGuardrailPolicy,
+} from '../orchestrator/guardrails.js'
+import type { UsageQuery, UsageRecord } from '../repositories/types.js'
+
+let sequence = 0
+const item = (fields: Partial<Item>): Item => ({
+  id: `item-${++sequence}`,
+  agentId: 'agent-1',
+  sequence,
+  type: 'message',
+  role: null,
+  content: null,
+  callId: null,
+  name: null,
+  arguments: null,
+  output: null,
+  contentBlocks: null,
+  isError: null,
+  saveOutput: null,
+  turnNumber: 1,
+  durationMs: null,
+  createdAt: sequence * 1000,
+  ...fields,
+})
+const failure = () => item({ type: 'function_call_output', isError: true, output: 'Error: boom' })
+
+const policy = normalizeGuardrailPolicy({
+  maxConsecutiveFailures: { limit: 3, severity: 'recoverable' },
+  costCeiling: { maxUsd: 0.5, severity: 'fatal' },
+  forbiddenTools: [
+    { tool: 'shell.*', when: [{ arg: 'command', op: 'regex', value: 'rm -rf' }], reason: 'no recursive deletes', severity: 'fatal' },
+  ],
+  contentRules: [{ category: 'credentials', patterns: ['password\\s*[:=]'], scopes: ['request', 'tool_args'], severity: 'recoverable' }],
+})
+
+// Validation: defaults are filled in, mistakes are reported
+assert.equal(policy.contentRules[0].severity, 'recoverable')
+assert.deepEqual(normalizeGuardrailPolicy({ forbiddenTools: [{ tool: 'mail.send' } as never] }).forbiddenTools[0], {
+  tool: 'mail.send', when: [], reason: 'mail.send is not allowed', severity: 'recoverable',
+})
+assert.throws(() => normalizeGuardrailPolicy({ maxConsecutiveFailures: { limit: 0, severity: 'fatal' } }), /positive integer/)
+assert.throws(() => normalizeGuardrailPolicy({ costCeiling: { maxUsd: 1, severity: 'severe' as never } }), /severity/)
+assert.throws(() => normalizeGuardrailPolicy({ contentRules: [{ category: 'x', patterns: ['('], scopes: ['request'], severity: 'fatal' }] }), /invalid regex/)
+assert.deepEqual(parseGuardrailPolicy('{"costCeiling": {"maxUsd": -1}}'), parseGuardrailPolicy(null))
+
+// A failure streak since the request stops the run; a success in between resets it
+const request = item({ role: 'user', content: 'Clean up the build folder' })
+const streak = [request, failure(), failure(), failure()]
+assert.deepEqual(evaluateTurn(policy, streak, 0), {
+  guardrail: 'consecutive_failures', severity: 'recoverable', reason: '3 tool calls in a row failed (limit 3)',
+})
+assert.equal(evaluateTurn(policy, [request, failure(), item({ type: 'function_call_output', output: 'ok' }), failure(), failure()], 0), null)
+
+// Replying to the pause starts a new count, and the request is not checked again
+const pause = item({ role: 'assistant', content: 'Paused by a guardrail', callId: 'pause-1' })
+const reply = item({ type: 'function_call_output', callId: 'pause-1', output: 'Keep going' })
+assert.equal(evaluateTurn(policy, [...streak, pause, reply, failure()], 0), null)
+
+// The spend of the request is held against the ceiling
+assert.equal(evaluateTurn(policy, [request], 0.5)?.guardrail, 'cost_ceiling')
+assert.equal(evaluateTurn(policy, [request], 0.5)?.severity, 'fatal')
+
+// Content rules apply only in their scopes
+const leaky = item({ role: 'user', content: 'My password: hunter2, log in for me' })
+assert.equal(evaluateTurn(policy, [leaky], 0)?.reason, 'The request falls under the blocked category "credentials"')
+assert.equal(evaluateTurn(policy, [leaky, item({ role: 'assistant', content: 'Paused', callId: 'p' }), item({ type: 'function_call_output', callId: 'p' })], 0), null)
+assert.equal(evaluateAction(policy, { action: 'complete', message: 'Your password: is safe' }), null)
+
+// Forbidden calls are caught anywhere in a batch, before anything runs
+assert.deepEqual(evaluateAction(policy, {
+  action: 'next_step',
+  tools: [{ tool: 'files.read', args: { path: 'a.txt' } }, { tool: 'shell.exec', args: { command: 'rm -rf /tmp/build' } }],
+}), { guardrail: 'forbidden_tool', severity: 'fatal', reason: 'Call to shell.exec blocked: no recursive deletes' })
+assert.equal(evaluateAction(policy, { action: 'next_step', tool: 'shell.exec', args: { command: 'ls' } }), null)
+assert.equal(
+  evaluateAction(policy, { action: 'next_step', tool: 'web.fill', args: { text: 'password=hunter2' } })?.reason,
+  'Content falls under the blocked category "credentials" in the arguments of web.fill',
+)
+
+// The engine reads the stored policy and the session's spend since the request
+const queries: UsageQuery[] = []
+const engine = new GuardrailEngine(
+  { get: async (key: string) => (key === GUARDRAIL_POLICY_PREFERENCE_KEY ? JSON.stringify(policy) : null) },
+  { list: async (query: UsageQuery) => { queries.push(query); return [{ costUsd: 0.3 }, { costUsd: 0.25 }] as UsageRecord[] } },
+)
+assert.equal((await engine.checkTurn({ sessionId: 's1', createdAt: 0 }, [request]))?.guardrail, 'cost_ceiling')
+assert.deepEqual(queries, [{ sessionId: 's1', from: request.createdAt }])
+assert.equal(await engine.checkAction({ action: 'ask_user', question: 'Which folder?' }), null)
+const unconfigured = new GuardrailEngine({ get: async () => null }, { list: async () => { throw new Error('not needed') } })
+assert.equal(await unconfigured.checkTurn({ sessionId: 's1', createdAt: 0 }, streak), null)
+
+console.log('guardrail tests passed')
@@ -0,0 +1,60 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { ProviderRegistryImpl } from '../providers/registry.js'
+import type { LLMProvider } from '../providers/types.js'
+import { runHealthCheck } from '../services/health-check.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'health-check-'))
+try {
+  const provider = (ping?: LLMProvider['ping']) => ({ generate: async () => { throw new Error('unused') }, ping }) as unknown as LLMProvider
+  const providers = new ProviderRegistryImpl()
+  providers.register('good', provider(async () => {}))
+  providers.register('bad', provider(async () => { throw new Error('401 invalid x-api-key') }))
+  providers.register('local', provider())
+
+  const pinged: string[] = []
+  writeFileSync(join(dir, 'notes'), 'a file where the notes directory should be')
+  const runtime = {
+    providers,
+    repositories: { apiKeys: { getByProvider: async () => null } },
+    mcps: {
+      listServers: async () => [
+        { id: 'm1', name: 'Shopping', enabled: true, status: 'connected', authStatus: 'not_required', error: null },
+        { id: 'm2', name: 'Docs', enabled: true, status: 'error', authStatus: 'not_required', error: 'Could not connect to the MCP server.' },
+        { id: 'm3', name: 'Drive', enabled: true, status: 'error', authStatus: 'required', error: null },
+        { id: 'm4', name: 'Old', enabled: false, status: 'disabled', authStatus: 'not_required', error: null },
+      ],
+      ping: async (id: string) => { pinged.push(id) },
+    },
+    sessionFilesRoot: join(dir, 'sessions'),
+    attachments: { dir: join(dir, 'attachments') },
+    notesDir: join(dir, 'notes'),
+  } as unknown as RuntimeContext
+
+  const report = await runHealthCheck(runtime, 'user-1')
+  const statusOf = (kind: string, name: string) => report.checks.find((c) => c.kind === kind && c.name === name)
+  assert.equal(report.status, 'degraded')
+  assert.equal(statusOf('provider', 'good')?.status, 'ok')
+  assert.equal(statusOf('provider', 'bad')?.status, 'failed')
+  assert.match(statusOf('provider', 'bad')?.detail ?? '', /invalid x-api-key/)
+  assert.equal(statusOf('provider', 'local')?.status, 'skipped')
+
+  // Only connected servers are pinged; the others report why they are down
+  assert.deepEqual(pinged, ['m1'])
+  assert.equal(statusOf('mcp', 'Shopping')?.status, 'ok')
+  assert.equal(statusOf('mcp', 'Docs')?.detail, 'Could not connect to the MCP server.')
+  assert.equal(statusOf('mcp', 'Drive')?.detail, 'Needs authorization')
+  assert.equal(statusOf('mcp', 'Old'), undefined)
+
+  assert.equal(statusOf('storage', 'sessions')?.status, 'ok')
+  assert.equal(statusOf('storage', 'attachments')?.status, 'ok')
+  assert.equal(statusOf('storage', 'notes')?.status, 'failed')
+  assert.equal(report.checks.filter((c) => c.kind === 'mail' || c.kind === 'calendar').length, 0, 'no accounts, no checks')
+
+  console.log('health check tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
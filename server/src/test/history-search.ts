@@ -0,0 +1,90 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { LLMEmbeddingRequest } from '../providers/types.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { searchHistory, snippet, type HistorySearchDeps } from '../services/history-search.js'
+import { registerHistoryTools } from '../tools/history.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+// Long messages are cut around the first match
+const long = `${'intro '.repeat(100)}we picked postgres for billing ${'outro '.repeat(100)}`
+const cut = snippet(long, ['postgres'])
+assert.ok(cut.length <= 402)
+assert.match(cut, /^….*we picked postgres for billing.*…$/)
+assert.equal(snippet('Short  message\n', ['short']), 'Short message')
+
+const dir = mkdtempSync(join(tmpdir(), 'history-search-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'history.db')))
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+  const alice = await repos.users.create({ apiKeyHash: 'alice' })
+  const bob = await repos.users.create({ apiKeyHash: 'bob' })
+
+  const conversation = async (userId: string, title: string, messages: Array<['user' | 'assistant', string]>) => {
+    const session = await repos.sessions.create({ userId, title })
+    const agent = await repos.agents.create({ sessionId: session.id, task: title, config })
+    for (const [role, content] of messages) {
+      await repos.items.create({ agentId: agent.id, type: 'message', role, content, turnNumber: 1 })
+    }
+    return { session, agent }
+  }
+
+  const database = await conversation(alice.id, 'Billing database', [
+    ['user', 'Should billing use Postgres or SQLite?'],
+    ['assistant', 'We decided on Postgres for billing because of concurrent writers.'],
+  ])
+  const trip = await conversation(alice.id, 'Lisbon trip', [['user', 'Find vegetarian restaurants in Lisbon']])
+  const current = await conversation(alice.id, 'Today', [['user', 'What did we decide about the billing database?']])
+  await conversation(bob.id, 'Bob billing', [['user', 'Bob wants billing on MySQL']])
+  const trashed = await conversation(alice.id, 'Old billing', [['user', 'Billing moved to Oracle']])
+  await repos.sessions.update(trashed.session.id, { deletedAt: Date.now() })
+  // Delegated work is not part of the conversation itself
+  const child = await repos.agents.create({ sessionId: trip.session.id, parentId: trip.agent.id, depth: 1, task: 'research', config })
+  await repos.items.create({ agentId: child.id, type: 'message', role: 'assistant', content: 'Billing notes from a sub-agent', turnNumber: 1 })
+
+  // Only the user's other live conversations are searched
+  const deps = { config: {}, providers: {}, items: repos.items, sessions: repos.sessions } as unknown as HistorySearchDeps
+  const results = await searchHistory(deps, current.session.id, 'billing database decision')
+  assert.deepEqual(new Set(results.map((result) => result.sessionId)), new Set([database.session.id]))
+  assert.equal(results.length, 2)
+  assert.equal(results[0].title, 'Billing database')
+  assert.match(results[0].date, /^\d{4}-\d{2}-\d{2}T/)
+  assert.deepEqual(await searchHistory(deps, current.session.id, 'kubernetes'), [])
+  assert.deepEqual(await searchHistory(deps, current.session.id, '"* AND ('), [])
+
+  // Embeddings re-rank keyword matches when a model is configured
+  const semantic = {
+    ...deps,
+    config: { attachmentEmbeddingModel: 'openai:embed' },
+    providers: {
+      resolve: () => ({
+        async embed(request: LLMEmbeddingRequest) {
+          return { embeddings: request.input.map((text) => ['decid', 'should'].map((topic) => text.toLowerCase().split(topic).length - 1)) }
+        },
+      }),
+    },
+  } as unknown as HistorySearchDeps
+  const [top] = await searchHistory(semantic, current.session.id, 'billing decided', { limit: 1 })
+  assert.equal(top.role, 'assistant')
+  assert.match(top.snippet, /We decided on Postgres/)
+
+  // The index follows deletes and survives maintenance renumbering rows
+  await repos.maintenance.run()
+  const registry = new ToolRegistryImpl()
+  registerHistoryTools(registry, deps)
+  const ctx = { agent_id: current.agent.id, session_id: current.session.id, signal: new AbortController().signal }
+  const found = await registry.execute('history.search', { query: 'vegetarian lisbon' }, ctx)
+  assert.equal(found.ok, true)
+  const [restaurant] = (found.output as { results: Array<{ sessionId: string; snippet: string }> }).results
+  assert.equal(restaurant.sessionId, trip.session.id)
+  assert.equal(restaurant.snippet, 'Find vegetarian restaurants in Lisbon')
+
+  await repos.sessions.delete(database.session.id)
+  assert.deepEqual(await searchHistory(deps, current.session.id, 'postgres'), [])
+
+  console.log('History search tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,113 @@
+import assert from 'node:assert/strict'
+import { HomeAssistantClient } from '../services/home-assistant.js'
+import { registerHomeAssistantTools } from '../tools/home-assistant.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+const requests: Array<{ method: string; path: string; auth: string | null; body: unknown }> = []
+const states = [
+  { entity_id: 'light.office_ceiling', state: 'on', attributes: { friendly_name: 'Office ceiling', brightness: 180, color_mode: 'brightness' }, last_changed: '2026-10-17T08:00:00+00:00' },
+  { entity_id: 'light.office_desk', state: 'off', attributes: { friendly_name: 'Desk lamp' }, last_changed: '2026-10-17T07:00:00+00:00' },
+  { entity_id: 'light.kitchen', state: 'on', attributes: { friendly_name: 'Kitchen' }, last_changed: '2026-10-17T06:00:00+00:00' },
+  { entity_id: 'cover.garage_door', state: 'closed', attributes: { friendly_name: 'Garage door', device_class: 'garage', current_position: 0 }, last_changed: '2026-10-16T22:10:00+00:00' },
+]
+let templatesEnabled = true
+
+const fakeFetch = (async (input: string | URL | Request, init?: RequestInit) => {
+  const url = new URL(String(input))
+  const headers = new Headers(init?.headers)
+  const body = init?.body ? JSON.parse(String(init.body)) as Record<string, unknown> : undefined
+  requests.push({ method: init?.method ?? 'GET', path: url.pathname, auth: headers.get('authorization'), body })
+  const json = (value: unknown, status = 200) => new Response(JSON.stringify(value), { status, headers: { 'content-type': 'application/json' } })
+  if (headers.get('authorization') !== 'Bearer ha-token') return new Response('401: Unauthorized', { status: 401 })
+
+  if (url.pathname === '/api/template') {
+    if (!templatesEnabled) return new Response('Template rendering is not allowed', { status: 403 })
+    assert.match(String(body?.template), /area_name\(s\.entity_id\)/)
+    return new Response('light.office_ceiling\tOffice\nlight.office_desk\tOffice\nlight.kitchen\tKitchen\ncover.garage_door\t\n')
+  }
+  if (url.pathname === '/api/states') return json(states)
+  if (url.pathname.startsWith('/api/states/')) {
+    const found = states.find((state) => state.entity_id === url.pathname.slice('/api/states/'.length))
+    return found ? json(found) : json({ message: 'Entity not found.' }, 404)
+  }
+  if (url.pathname === '/api/services/light/turn_off') {
+    const ids = [body?.entity_id].flat()
+    return json(states.filter((state) => ids.includes(state.entity_id)).map((state) => ({ ...state, state: 'off' })))
+  }
+  if (url.pathname === '/api/services/light/explode') return new Response('Service not found.', { status: 400 })
+  return json({ message: 'Not found' }, 404)
+}) as typeof fetch
+
+const registry = new ToolRegistryImpl()
+registerHomeAssistantTools(registry, new HomeAssistantClient('http://ha.local:8123/', 'ha-token', fakeFetch))
+
+// One entity: every attribute
+const garage = await registry.execute('home_assistant.states', { entity_id: 'cover.garage_door' }, ctx)
+assert.deepEqual(garage.output, {
+  entity_id: 'cover.garage_door',
+  name: 'Garage door',
+  state: 'closed',
+  attributes: { device_class: 'garage', current_position: 0 },
+  last_changed: '2026-10-16T22:10:00+00:00',
+})
+assert.deepEqual(requests.map((request) => [request.method, request.path, request.auth]), [
+  ['GET', '/api/states/cover.garage_door', 'Bearer ha-token'],
+  ['POST', '/api/template', 'Bearer ha-token'],
+])
+assert.match(String((await registry.execute('home_assistant.states', { entity_id: 'cover.shed' }, ctx)).error), /No entity cover\.shed/)
+assert.match(String((await registry.execute('home_assistant.states', { entity_id: '../config' }, ctx)).error), /Invalid entity_id/)
+
+// Lists: filtered by area and domain, with only the useful attributes
+const office = await registry.execute('home_assistant.states', { area: 'office', domain: 'light' }, ctx)
+assert.deepEqual(office.output, {
+  states: [
+    { entity_id: 'light.office_ceiling', name: 'Office ceiling', state: 'on', area: 'Office', attributes: { brightness: 180 }, last_changed: '2026-10-17T08:00:00+00:00' },
+    { entity_id: 'light.office_desk', name: 'Desk lamp', state: 'off', area: 'Office', attributes: {}, last_changed: '2026-10-17T07:00:00+00:00' },
+  ],
+  total: 2,
+})
+const searched = await registry.execute('home_assistant.states', { search: 'garage' }, ctx)
+assert.deepEqual((searched.output as { states: Array<{ entity_id: string }> }).states.map((state) => state.entity_id), ['cover.garage_door'])
+const limited = await registry.execute('home_assistant.states', { limit: 1 }, ctx)
+assert.equal((limited.output as { states: unknown[]; total: number }).total, 4)
+assert.equal((limited.output as { states: unknown[] }).states.length, 1)
+
+// Without template access, areas are simply absent
+templatesEnabled = false
+const noAreas = await registry.execute('home_assistant.states', { domain: 'light' }, ctx)
+assert.equal((noAreas.output as { states: Array<{ area?: string }> }).states.every((state) => state.area === undefined), true)
+templatesEnabled = true
+
+// Service calls need approval and return the changed states
+assert.equal(registry.getMetadata('home_assistant.call_service')?.requires_approval, true)
+assert.deepEqual(registry.getPreview('home_assistant.call_service', { domain: 'light', service: 'turn_off', entity_id: ['light.office_ceiling', 'light.office_desk'] }, ctx), {
+  summary: 'Home Assistant: light.turn_off on light.office_ceiling, light.office_desk',
+})
+requests.length = 0
+const turnedOff = await registry.execute('home_assistant.call_service', {
+  domain: 'light',
+  service: 'turn_off',
+  entity_id: ['light.office_ceiling', 'light.office_desk'],
+  data: { transition: 5 },
+}, ctx)
+assert.equal(turnedOff.ok, true)
+assert.deepEqual(requests[0], {
+  method: 'POST',
+  path: '/api/services/light/turn_off',
+  auth: 'Bearer ha-token',
+  body: { transition: 5, entity_id: ['light.office_ceiling', 'light.office_desk'] },
+})
+assert.deepEqual((turnedOff.output as { called: string; changed: Array<{ entity_id: string; state: string }> }).changed.map((state) => [state.entity_id, state.state]), [
+  ['light.office_ceiling', 'off'],
+  ['light.office_desk', 'off'],
+])
+assert.match(String((await registry.execute('home_assistant.call_service', { domain: 'light', service: 'explode', entity_id: ['light.kitchen'] }, ctx)).error), /Home Assistant refused light\.explode: Service not found\./)
+assert.match(String((await registry.execute('home_assistant.call_service', { domain: 'light/../x', service: 'turn_on' }, ctx)).error), /domain and service must be lowercase names/)
+assert.match(String((await registry.execute('home_assistant.call_service', { domain: 'light', service: 'turn_on', entity_id: ['office'] }, ctx)).error), /Invalid entity_id "office"/)
+
+const rejected = new ToolRegistryImpl()
+registerHomeAssistantTools(rejected, new HomeAssistantClient('http://ha.local:8123', 'stale', fakeFetch))
+assert.match(String((await rejected.execute('home_assistant.states', {}, ctx)).error), /rejected the access token/)
+
+console.log('Home Assistant tool tests passed')
@@ -0,0 +1,77 @@
+import assert from 'node:assert/strict'
+import fs from 'fs/promises'
+import os from 'os'
+import path from 'path'
+import type { AppConfig } from '../lib/config.js'
+import type { LLMImageGenerationRequest, ProviderRegistry } from '../providers/types.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+import { imageFileName, registerImageTools } from '../tools/image.js'
+import { listAgentArtifacts, readAgentArtifact } from '../services/agent-artifacts.js'
+import { generateImage, openAISize } from '../services/image-generation.js'
+
+const png = Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a])
+const requests: LLMImageGenerationRequest[] = []
+const providers = {
+  resolve: (id: string) => id.startsWith('anthropic:')
+    ? {}
+    : {
+        async generateImage(request: LLMImageGenerationRequest) {
+          requests.push(request)
+          return { data: png.toString('base64'), media_type: 'image/png', revised_prompt: `${request.prompt}, flat style` }
+        },
+      },
+} as unknown as ProviderRegistry
+const config = (imageGenerationModel?: string, stabilityApiKey?: string) =>
+  ({ imageGenerationModel, stabilityApiKey }) as AppConfig
+
+assert.equal(openAISize('dall-e-3', 'landscape'), '1792x1024')
+assert.equal(openAISize('gpt-image-1', 'portrait'), '1024x1536')
+assert.equal(openAISize('gpt-image-1', 'square'), '1024x1024')
+assert.equal(imageFileName('', 'A diagram of the OAuth flow!'), 'a-diagram-of-the-oauth-flow.png')
+assert.equal(imageFileName('cover.jpg', 'anything'), 'cover.png')
+assert.equal(imageFileName('', '???'), 'image.png')
+
+await assert.rejects(generateImage({ config: config(), providers }, { prompt: 'cat' }), /IMAGE_GENERATION_MODEL is not configured/)
+await assert.rejects(generateImage({ config: config('openai:gpt-image-1'), providers }, { prompt: '  ' }), /prompt is required/)
+await assert.rejects(generateImage({ config: config('anthropic:claude'), providers }, { prompt: 'cat' }), /does not support image generation/)
+await assert.rejects(generateImage({ config: config('stability:core'), providers }, { prompt: 'cat' }), /STABILITY_API_KEY is not configured/)
+
+const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), 'image-generation-test-'))
+const sessionFilesRoot = path.join(tmpDir, 'sessions')
+
+try {
+  const registry = new ToolRegistryImpl()
+  registerImageTools(registry, { sessionFilesRoot, config: config('openai:gpt-image-1'), providers })
+  const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+
+  const result = await registry.execute('image.generate', { prompt: 'Onboarding flow diagram', orientation: 'landscape' }, ctx)
+  assert.equal(result.ok, true)
+  assert.deepEqual(requests, [{ model: 'gpt-image-1', prompt: 'Onboarding flow diagram', size: '1536x1024', signal: ctx.signal }])
+  const artifact = result.output as { ref: string; name: string; metadata: Record<string, unknown> }
+  assert.equal(artifact.ref, 'artifact://files/onboarding-flow-diagram.png')
+  assert.deepEqual(artifact.metadata, {
+    prompt: 'Onboarding flow diagram',
+    revisedPrompt: 'Onboarding flow diagram, flat style',
+    model: 'openai:gpt-image-1',
+    orientation: 'landscape',
+  })
+
+  // The model sees the image it made, and the user can download it with the prompt on record
+  assert.deepEqual(result.content_blocks?.[1], { type: 'image', media_type: 'image/png', data: png.toString('base64') })
+  assert.deepEqual((await readAgentArtifact(sessionFilesRoot, 'session', artifact.name))?.data, png)
+  const [listed] = await listAgentArtifacts(sessionFilesRoot, 'session')
+  assert.deepEqual(listed.metadata, artifact.metadata)
+
+  // Unknown orientations fall back to square; requested names are kept
+  const named = await registry.execute('image.generate', { prompt: 'Logo', orientation: 'wide', name: 'logo.png' }, ctx)
+  assert.equal((named.output as { name: string }).name, 'logo.png')
+  assert.equal(requests[1].size, '1024x1024')
+
+  const failed = await registry.execute('image.generate', { prompt: '' }, ctx)
+  assert.equal(failed.ok, false)
+  assert.match(String(failed.error), /prompt is required/)
+
+  console.log('Image generation tests passed')
+} finally {
+  await fs.rm(tmpDir, { recursive: true, force: true })
+}
@@ -0,0 +1,78 @@
+import assert from 'node:assert/strict'
+import { chmodSync, mkdtempSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { Item } from '../domain/types.js'
+import { AttachmentStore } from '../lib/attachment-store.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { buildControllerMessages } from '../orchestrator/prompts.js'
+import type { LLMRequest } from '../providers/types.js'
+import { extractImageText, ImageInputError, resolveImageBlocks } from '../services/image-ocr.js'
+
+const png = Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x01])
+const screenshot = Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x02])
+
+const dir = mkdtempSync(join(tmpdir(), 'image-ocr-'))
+try {
+  // Stand-in for tesseract: `tesseract <image> stdout` prints the text it read
+  const tesseract = join(dir, 'tesseract')
+  writeFileSync(tesseract, '#!/bin/sh\n[ "$2" = stdout ] && echo "  read from $(basename "$1")  "\n')
+  chmodSync(tesseract, 0o755)
+
+  const ocrRequests: LLMRequest[] = []
+  const attachments = new AttachmentStore(join(dir, 'attachments'))
+  const runtime = (imageOcrModel?: string) => ({
+    attachments,
+    config: { imageOcrModel, tesseractBin: tesseract },
+    providers: {
+      resolve: () => ({
+        async generate(request: LLMRequest) {
+          ocrRequests.push(request)
+          return { content: ' Error: connection refused ', usage: { input_tokens: 1, output_tokens: 1 }, finish_reason: 'stop' }
+        },
+      }),
+    },
+    shutdownController: new AbortController(),
+  }) as unknown as RuntimeContext
+
+  await assert.rejects(extractImageText(runtime('tesseract'), { bytes: new Uint8Array(), mediaType: 'image/png' }), ImageInputError)
+  await assert.rejects(extractImageText(runtime('tesseract'), { bytes: png, mediaType: 'image/svg+xml' }), /Unsupported image type/)
+  await assert.rejects(extractImageText(runtime(), { bytes: png, mediaType: 'image/png' }), /IMAGE_OCR_MODEL/)
+
+  const local = await extractImageText(runtime('tesseract'), { bytes: png, mediaType: 'image/png', fileName: 'shot.png' })
+  assert.deepEqual(local.chunks, ['read from image.png'])
+  assert.equal(local.bytes, png.length)
+
+  // Vision models get the image inline; the stored text is reused afterwards
+  const vision = await extractImageText(runtime('openai:gpt-4o-mini'), { bytes: screenshot, mediaType: 'image/png' })
+  assert.deepEqual(vision.chunks, ['Error: connection refused'])
+  assert.equal(ocrRequests.length, 1)
+  assert.equal(ocrRequests[0].model, 'gpt-4o-mini')
+  const [, image] = ocrRequests[0].messages[0].content as Array<{ data?: string; media_type?: string }>
+  assert.deepEqual([image.media_type, image.data], ['image/png', screenshot.toString('base64')])
+  await extractImageText(runtime('openai:gpt-4o-mini'), { bytes: screenshot, mediaType: 'image/png' })
+  assert.equal(ocrRequests.length, 1)
+
+  // User messages reference the upload by hash; inline payloads pass through untouched
+  const inline = { type: 'image' as const, media_type: 'image/png', data: png.toString('base64') }
+  const blocks = await resolveImageBlocks(runtime(), [{ type: 'image', hash: vision.hash, name: 'error.png' }, inline])
+  assert.deepEqual(blocks, [
+    { type: 'image', name: 'error.png', media_type: 'image/png', hash: vision.hash, bytes: screenshot.length, chunks: ['Error: connection refused'] },
+    inline,
+  ])
+  await assert.rejects(resolveImageBlocks(runtime(), [{ type: 'image', hash: 'f'.repeat(64) }]), /Unknown image attachment/)
+
+  // The OCR text reaches the model as text and the image itself is never loaded
+  const item = {
+    id: 'i1', agentId: 'a1', sequence: 1, type: 'message', role: 'user', content: 'What broke?', contentBlocks: blocks!.slice(0, 1),
+    callId: null, name: null, arguments: null, output: null, isError: null, saveOutput: null, turnNumber: 0, durationMs: null, createdAt: 0,
+  } as Item
+  const [hydrated] = await attachments.hydrate([item])
+  assert.equal(hydrated.contentBlocks?.[0].data, undefined)
+  const messages = buildControllerMessages('system', '', [hydrated], { useNativeFunctionCalling: true, agentTask: '' })
+  assert.equal(messages[1].content, 'What broke?\n\n[Attached image: error.png (text read from image)]\n```\nError: connection refused\n```\n')
+
+  console.log('image ocr tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,64 @@
+import assert from 'node:assert/strict'
+import { mkdirSync, mkdtempSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { buildControllerMessages } from '../orchestrator/prompts.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { InstructionFiles, type InstructionFile } from '../services/instruction-files.js'
+import { linkProjects } from '../services/projects.js'
+
+async function eventually(check: () => Promise<boolean>, message: string): Promise<void> {
+  for (let i = 0; i < 50; i++) {
+    if (await check()) return
+    await new Promise((resolve) => setTimeout(resolve, 50))
+  }
+  assert.fail(message)
+}
+
+const dir = mkdtempSync(join(tmpdir(), 'instruction-files-'))
+const workspaceDir = join(dir, 'workspace')
+const projectRoot = join(dir, 'repo')
+const projectsDir = join(dir, 'projects')
+const sessionFilesRoot = join(dir, 'sessions')
+const repos = new SQLiteRepositories(createDatabase(join(dir, 'instructions.db')))
+const instructions = new InstructionFiles({ sessions: repos.sessions, sessionFilesRoot, projectsDir, workspaceDir })
+try {
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Release' })
+  const paths = (files: InstructionFile[]) => files.map((file) => file.path)
+
+  // A missing workspace folder has no instructions, and is read again once it exists
+  assert.deepEqual(await instructions.forSession(session.id), [])
+  mkdirSync(workspaceDir, { recursive: true })
+  writeFileSync(join(workspaceDir, 'ASSISTANT.md'), 'Answer in British English.\n')
+  assert.deepEqual(await instructions.forSession(session.id), [
+    { path: join(workspaceDir, 'ASSISTANT.md'), content: 'Answer in British English.' },
+  ])
+
+  // Linked projects add their own file after the workspace's
+  mkdirSync(projectRoot, { recursive: true })
+  writeFileSync(join(projectRoot, 'ASSISTANT.md'), 'Run bun test before proposing a commit.')
+  mkdirSync(join(projectsDir, user.id), { recursive: true })
+  writeFileSync(join(projectsDir, user.id, 'repo.json'), JSON.stringify({ name: 'repo', root: projectRoot, files: [], truncated: false, indexedAt: 0 }))
+  await linkProjects({ sessionFilesRoot }, session.id, ['repo'])
+  assert.deepEqual(paths(await instructions.forSession(session.id)), [join(workspaceDir, 'ASSISTANT.md'), join(projectRoot, 'ASSISTANT.md')])
+
+  // Edits and deletes reach the cache through the watcher
+  writeFileSync(join(workspaceDir, 'ASSISTANT.md'), 'Answer in Polish.')
+  await eventually(async () => (await instructions.forSession(session.id))[0].content === 'Answer in Polish.', 'edit was not picked up')
+  rmSync(join(projectRoot, 'ASSISTANT.md'))
+  await eventually(async () => (await instructions.forSession(session.id)).length === 1, 'delete was not picked up')
+
+  // The files are rendered into the controller system prompt
+  const [system] = buildControllerMessages('CONTROLLER', '', [], {
+    useNativeFunctionCalling: true,
+    agentTask: 'Ship it',
+    instructionFiles: await instructions.forSession(session.id),
+  })
+  assert.match(system.content as string, /## Workspace Instructions\nFrom .*ASSISTANT\.md:\nAnswer in Polish\./)
+
+  console.log('Instruction files tests passed')
+} finally {
+  instructions.stop()
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,105 @@
+import assert from 'node:assert/strict'
+import { JiraTracker, LinearTracker, toJql } from '../services/issue-trackers.js'
+import { registerIssueTrackerTools } from '../tools/issue-trackers.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const json = (value: unknown, status = 200) => new Response(JSON.stringify(value), { status, headers: { 'content-type': 'application/json' } })
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+
+// Plain words become a text search; JQL keeps its own ordering
+assert.equal(toJql('login crash', 'OPS'), 'project = "OPS" AND text ~ "login crash" ORDER BY updated DESC')
+assert.equal(toJql('status in (Open) ORDER BY priority DESC'), '(status in (Open)) ORDER BY priority DESC')
+assert.equal(toJql('the bug is back'), 'text ~ "the bug is back" ORDER BY updated DESC')
+
+// Jira: Cloud auth, search, transitions by status name and comments
+const jiraRequests: Array<{ method: string; path: string; auth: string | null; body: unknown }> = []
+let status = 'To Do'
+const jiraIssue = () => ({
+  key: 'OPS-42',
+  fields: {
+    summary: 'Login fails',
+    status: { name: status },
+    assignee: { displayName: 'Ana' },
+    priority: { name: 'High' },
+    labels: ['auth'],
+    description: 'Steps',
+    created: '2026-10-01T00:00:00.000+0000',
+    updated: '2026-10-02T00:00:00.000+0000',
+    comment: { comments: [{ author: { displayName: 'Ben' }, body: 'Seen too', created: '2026-10-02T00:00:00.000+0000' }] },
+  },
+})
+const jiraFetch = (async (input: string | URL | Request, init?: RequestInit) => {
+  const url = new URL(String(input))
+  const method = init?.method ?? 'GET'
+  jiraRequests.push({ method, path: url.pathname, auth: new Headers(init?.headers).get('authorization'), body: init?.body ? JSON.parse(String(init.body)) : undefined })
+  if (url.pathname === '/rest/api/2/search/jql') return json({ issues: [jiraIssue()] })
+  if (url.pathname === '/rest/api/2/issue/OPS-42/transitions' && method === 'GET') {
+    return json({ transitions: [{ id: '21', name: 'Start work', to: { name: 'In Progress' } }, { id: '31', name: 'Done', to: { name: 'Done' } }] })
+  }
+  if (url.pathname === '/rest/api/2/issue/OPS-42/transitions') {
+    status = 'In Progress'
+    return new Response(null, { status: 204 })
+  }
+  if (url.pathname === '/rest/api/2/issue/OPS-42/comment') return json({ id: '1' }, 201)
+  if (url.pathname === '/rest/api/2/issue/OPS-42' && method === 'PUT') return new Response(null, { status: 204 })
+  if (url.pathname === '/rest/api/2/issue/OPS-42') return json(jiraIssue())
+  if (url.pathname === '/rest/api/2/issue' && method === 'POST') return json({ errors: { issuetype: 'Specify a valid issue type' } }, 400)
+  return json({ errorMessages: ['Issue does not exist'] }, 404)
+}) as typeof fetch
+
+const jira = new ToolRegistryImpl()
+registerIssueTrackerTools(jira, new JiraTracker('https://acme.atlassian.net/', 'token', 'me@acme.com', jiraFetch))
+const found = await jira.execute('jira.search', { query: 'login', project: 'OPS' }, ctx)
+assert.deepEqual((found.output as { issues: unknown[] }).issues, [{
+  key: 'OPS-42',
+  title: 'Login fails',
+  status: 'To Do',
+  assignee: 'Ana',
+  priority: 'High',
+  url: 'https://acme.atlassian.net/browse/OPS-42',
+  updatedAt: '2026-10-02T00:00:00.000+0000',
+}])
+assert.equal(jiraRequests[0].auth, `Basic ${Buffer.from('me@acme.com:token').toString('base64')}`)
+const read = await jira.execute('jira.read_issue', { key: 'OPS-42' }, ctx)
+assert.deepEqual((read.output as { comments: Array<{ author: string }> }).comments.map((comment) => comment.author), ['Ben'])
+
+assert.equal(jira.getMetadata('jira.update_issue')?.requires_approval, true)
+assert.equal(jira.getPreview('jira.update_issue', { key: 'OPS-42', status: 'In Progress' }, ctx)?.summary, 'Update Jira OPS-42: status')
+const moved = await jira.execute('jira.update_issue', { key: 'OPS-42', status: 'in progress', comment: 'On it' }, ctx)
+assert.equal((moved.output as { issue: { status: string } }).issue.status, 'In Progress')
+assert.deepEqual(jiraRequests.filter((request) => request.method === 'POST').map((request) => request.body), [{ transition: { id: '21' } }, { body: 'On it' }])
+const stuck = await jira.execute('jira.update_issue', { key: 'OPS-42', status: 'Blocked' }, ctx)
+assert.match(String(stuck.error), /available: In Progress, Done/)
+const invalid = await jira.execute('jira.create_issue', { project: 'OPS', title: 'New', type: 'Story' }, ctx)
+assert.match(String(invalid.error), /issuetype: Specify a valid issue type/)
+
+// Linear: GraphQL with the API key, states resolved per team
+const linearQueries: Array<{ query: string; variables: Record<string, unknown> }> = []
+const linearIssue = { identifier: 'ENG-7', title: 'Flaky test', url: 'https://linear.app/acme/issue/ENG-7', updatedAt: '2026-10-02T00:00:00.000Z', priorityLabel: 'Urgent', state: { name: 'Todo' }, assignee: null }
+const linearFetch = (async (_input: string | URL | Request, init?: RequestInit) => {
+  assert.equal(new Headers(init?.headers).get('authorization'), 'lin_api_key')
+  const { query, variables } = JSON.parse(String(init?.body)) as { query: string; variables: Record<string, unknown> }
+  linearQueries.push({ query, variables })
+  if (query.includes('searchIssues')) return json({ data: { searchIssues: { nodes: [linearIssue] } } })
+  if (query.includes('teams(')) return json({ data: { teams: { nodes: variables.key === 'ENG' ? [{ id: 'team-1' }] : [] } } })
+  if (query.includes('issueCreate')) return json({ data: { issueCreate: { success: true, issue: { ...linearIssue, identifier: 'ENG-8', title: 'New' } } } })
+  if (query.includes('team { states')) return json({ data: { issue: { id: 'uuid-7', team: { states: { nodes: [{ id: 's1', name: 'Todo' }, { id: 's2', name: 'In Review' }] } } } } })
+  if (query.includes('issueUpdate')) return json({ data: { issueUpdate: { success: true, issue: { ...linearIssue, state: { name: 'In Review' } } } } })
+  return json({ errors: [{ message: 'Unexpected query' }] })
+}) as typeof fetch
+
+const linear = new ToolRegistryImpl()
+registerIssueTrackerTools(linear, new LinearTracker('lin_api_key', linearFetch))
+assert.equal('type' in (linear.getMetadata('linear.create_issue')!.parameters.properties as object), false)
+const search = await linear.execute('linear.search', { query: 'flaky', project: 'eng' }, ctx)
+assert.equal((search.output as { issues: Array<{ key: string }> }).issues[0].key, 'ENG-7')
+assert.deepEqual(linearQueries[0].variables.filter, { team: { key: { eq: 'ENG' } } })
+const created = await linear.execute('linear.create_issue', { project: 'ENG', title: 'New', description: 'Details' }, ctx)
+assert.equal((created.output as { issue: { key: string } }).issue.key, 'ENG-8')
+assert.deepEqual(linearQueries.at(-1)?.variables, { input: { teamId: 'team-1', title: 'New', description: 'Details' } })
+assert.match(String((await linear.execute('linear.create_issue', { project: 'OPS', title: 'New' }, ctx)).error), /team not found/)
+const updated = await linear.execute('linear.update_issue', { key: 'ENG-7', status: 'In Review' }, ctx)
+assert.equal((updated.output as { issue: { status: string } }).issue.status, 'In Review')
+assert.deepEqual(linearQueries.at(-1)?.variables, { id: 'uuid-7', input: { stateId: 's2' } })
+
+console.log('Issue tracker tool tests passed')
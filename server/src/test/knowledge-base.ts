@@ -0,0 +1,104 @@
+import assert from 'node:assert/strict'
+import { mkdirSync, mkdtempSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { AttachmentStore } from '../lib/attachment-store.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import {
+  addKnowledgeSource,
+  KnowledgeIndexer,
+  KnowledgeInputError,
+  searchKnowledge,
+  type KnowledgeSearchDeps,
+} from '../services/knowledge-base.js'
+import { registerKnowledgeTools } from '../tools/knowledge.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'knowledge-base-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'knowledge.db')))
+  const attachments = new AttachmentStore(join(dir, 'attachments'))
+  const runtime = {
+    repositories: repos,
+    attachments,
+    config: { attachmentInlineMaxChars: 1000 },
+    providers: {},
+    shutdownController: new AbortController(),
+  }
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+  const alice = await repos.users.create({ apiKeyHash: 'alice' })
+  const bob = await repos.users.create({ apiKeyHash: 'bob' })
+  const session = await repos.sessions.create({ userId: alice.id, title: 'Questions' })
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Answer', config })
+
+  const handbook = join(dir, 'handbook')
+  mkdirSync(join(handbook, 'policies'), { recursive: true })
+  writeFileSync(join(handbook, 'policies', 'travel.md'), 'Travel expenses are reimbursed within 30 days of submitting receipts.')
+  writeFileSync(join(handbook, 'onboarding.txt'), 'New hires get a laptop and a badge on their first day.')
+  writeFileSync(join(handbook, 'logo.png'), Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x00, 0x00, 0x00, 0x0d]))
+  const faq = join(dir, 'faq.md')
+  writeFileSync(faq, 'Parking is free in the east garage after 6pm.')
+
+  // Sources are named after their location unless given a name
+  await assert.rejects(addKnowledgeSource(runtime, alice.id, { location: 'relative/path' }), KnowledgeInputError)
+  await assert.rejects(addKnowledgeSource(runtime, alice.id, { location: join(dir, 'missing') }), /Not found/)
+  const folder = await addKnowledgeSource(runtime, alice.id, { location: handbook })
+  assert.equal(folder.kind, 'folder')
+  assert.equal(folder.name, 'handbook')
+  assert.equal(folder.status, 'pending')
+  const document = await addKnowledgeSource(runtime, alice.id, { location: faq, name: 'Office FAQ' })
+  assert.equal(document.kind, 'document')
+  const page = await addKnowledgeSource(runtime, bob.id, { location: 'https://example.com/' })
+  assert.equal(page.kind, 'url')
+  assert.equal(page.name, 'example.com')
+  await repos.knowledge.delete(page.id)
+  mkdirSync(join(dir, 'empty'))
+  const blank = await addKnowledgeSource(runtime, alice.id, { location: join(dir, 'empty') })
+
+  // Pending sources are picked up on start; unreadable files are left out of a folder
+  const indexer = new KnowledgeIndexer(runtime as never)
+  await indexer.start()
+  await indexer.idle()
+  const indexed = (await repos.knowledge.getById(folder.id))!
+  assert.equal(indexed.status, 'ready')
+  assert.deepEqual(indexed.documents.map((doc) => doc.path), ['onboarding.txt', 'policies/travel.md'])
+  assert.ok(indexed.indexedAt)
+  assert.equal((await repos.knowledge.getById(document.id))!.status, 'ready')
+  const failed = (await repos.knowledge.getById(blank.id))!
+  assert.equal(failed.status, 'failed')
+  assert.match(failed.error ?? '', /No readable documents/)
+
+  // Results carry a citation back to the file they came from
+  const deps = { knowledge: repos.knowledge, sessions: repos.sessions, attachments, config: {}, providers: {} } as unknown as KnowledgeSearchDeps
+  const [travel] = await searchKnowledge(deps, session.id, 'when are travel expenses reimbursed')
+  assert.equal(travel.source, 'handbook')
+  assert.equal(travel.location, join(handbook, 'policies/travel.md'))
+  assert.equal(travel.citation, `policies/travel.md — ${join(handbook, 'policies/travel.md')}`)
+  assert.match(travel.text, /30 days/)
+  const [parking] = await searchKnowledge(deps, session.id, 'parking', { source: 'Office FAQ' })
+  assert.equal(parking.citation, `faq.md — ${faq}`)
+  await assert.rejects(searchKnowledge(deps, session.id, 'parking', { source: 'nope' }), /No indexed knowledge source/)
+
+  // Other users' sources are not searched
+  const bobSession = await repos.sessions.create({ userId: bob.id, title: 'Bob' })
+  await assert.rejects(searchKnowledge(deps, bobSession.id, 'parking'), /no indexed sources/)
+
+  const registry = new ToolRegistryImpl()
+  registerKnowledgeTools(registry, deps)
+  const ctx = { agent_id: agent.id, session_id: session.id, signal: new AbortController().signal }
+  const found = await registry.execute('kb.search', { query: 'laptop first day' }, ctx)
+  assert.equal(found.ok, true)
+  const [laptop] = (found.output as { results: Array<{ title: string; citation: string }> }).results
+  assert.equal(laptop.title, 'onboarding.txt')
+
+  // Indexed documents stay referenced until their source is removed
+  const hashes = await repos.knowledge.listDocumentHashes()
+  assert.ok(indexed.documents.every((doc) => hashes.includes(doc.hash)))
+  await repos.knowledge.delete(folder.id)
+  assert.ok(!(await repos.knowledge.listDocumentHashes()).includes(indexed.documents[0].hash))
+
+  indexer.stop()
+  console.log('Knowledge base tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,238 @@
+import assert from 'node:assert/strict'
+import net from 'node:net'
+import { once } from 'node:events'
+import { MailService, normalizeMailAccount, verifyMailAccount, type MailAccount } from '../services/mail.js'
+import { registerMailTools } from '../tools/mail.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+const crlf = (lines: string[]) => lines.join('\r\n')
+
+const HEADERS: Record<number, string> = {
+  7: crlf(['From: Ana <ana@example.com>', 'To: me@example.com', 'Subject: Lunch?', 'Date: Thu, 15 Oct 2026 12:00:00 +0000', 'Message-ID: <msg7@example.com>', '', '']),
+  9: crlf(['From: Billing <billing@example.com>', 'To: me@example.com', 'Subject: =?UTF-8?Q?Invoice_for_Pa=C5=BAdziernik?=', 'Date: Fri, 16 Oct 2026 09:00:00 +0000', 'Message-ID: <msg9@example.com>', '', '']),
+}
+const MESSAGE_9 = crlf([
+  HEADERS[9].trimEnd(),
+  'MIME-Version: 1.0',
+  'Content-Type: multipart/mixed; boundary="outer"',
+  '',
+  '--outer',
+  'Content-Type: multipart/alternative; boundary=inner',
+  '',
+  '--inner',
+  'Content-Type: text/plain; charset=iso-8859-2',
+  'Content-Transfer-Encoding: quoted-printable',
+  '',
+  'Kwota: 120 z=B3, termin p=B3atno=B6ci 30 dni. To jest bardzo d=',
+  '=B3uga linia.',
+  '--inner',
+  'Content-Type: text/html; charset=utf-8',
+  '',
+  '<p>HTML version</p>',
+  '--inner--',
+  '--outer',
+  'Content-Type: application/pdf',
+  "Content-Disposition: attachment; filename*=utf-8''na%C3%AFve.pdf",
+  'Content-Transfer-Encoding: base64',
+  '',
+  Buffer.from('%PDF-1.4 fake').toString('base64'),
+  '--outer--',
+  '',
+])
+
+// --- A scripted IMAP server ---
+const imapCommands: string[] = []
+const appended: Array<{ mailbox: string; flags: string; message: string }> = []
+const imapServer = net.createServer((socket) => {
+  let buffer = Buffer.alloc(0)
+  let command = ''
+  let literal: number | null = null
+  socket.on('error', () => undefined)
+  socket.write('* OK fake IMAP ready\r\n')
+  socket.on('data', (chunk: Buffer) => {
+    buffer = Buffer.concat([buffer, chunk])
+    for (;;) {
+      if (literal !== null) {
+        if (buffer.length < literal) return
+        command += `\0${buffer.subarray(0, literal).toString('utf-8')}\0`
+        buffer = buffer.subarray(literal)
+        literal = null
+      }
+      const end = buffer.indexOf('\r\n')
+      if (end < 0) return
+      const line = buffer.subarray(0, end).toString('utf-8')
+      buffer = buffer.subarray(end + 2)
+      const announced = line.match(/\{(\d+)\}$/)
+      if (announced) {
+        command += line.slice(0, -announced[0].length)
+        literal = Number(announced[1])
+        socket.write('+ go ahead\r\n')
+        continue
+      }
+      respond(socket, command + line)
+      command = ''
+    }
+  })
+})
+
+function respond(socket: net.Socket, line: string): void {
+  imapCommands.push(line)
+  const [tag] = line.split(' ', 1)
+  const request = line.slice(tag.length + 1)
+  const ok = (text = 'done') => socket.write(`${tag} OK ${text}\r\n`)
+  if (request.startsWith('LOGIN')) {
+    if (request.includes('"wrong"')) socket.write(`${tag} NO [AUTHENTICATIONFAILED] Invalid credentials\r\n`)
+    else ok()
+  } else if (request.startsWith('EXAMINE')) {
+    socket.write('* 2 EXISTS\r\n')
+    ok('[READ-ONLY] EXAMINE completed')
+  } else if (request.startsWith('UID SEARCH')) {
+    socket.write(request.includes('TEXT') || request.includes('UNSEEN') ? '* SEARCH 9\r\n' : '* SEARCH 7 9\r\n')
+    ok()
+  } else if (request.startsWith('UID FETCH') && request.includes('HEADER.FIELDS')) {
+    const uids = request.split(' ')[2].split(',').map(Number).sort((a, b) => a - b)
+    for (const [index, uid] of uids.entries()) {
+      const headers = Buffer.from(HEADERS[uid])
+      socket.write(`* ${index + 1} FETCH (UID ${uid} FLAGS (${uid === 7 ? '\\Seen' : ''}) BODY[HEADER.FIELDS (FROM TO CC SUBJECT DATE MESSAGE-ID)] {${headers.length}}\r\n`)
+      socket.write(Buffer.concat([headers, Buffer.from(')\r\n')]))
+    }
+    ok()
+  } else if (request.startsWith('UID FETCH 9 ')) {
+    const raw = Buffer.from(MESSAGE_9, 'utf-8')
+    socket.write(`* 2 FETCH (UID 9 FLAGS () BODY[]<0> {${raw.length}}\r\n`)
+    socket.write(Buffer.concat([raw, Buffer.from(')\r\n')]))
+    ok()
+  } else if (request.startsWith('UID FETCH')) {
+    ok()
+  } else if (request.startsWith('LIST')) {
+    socket.write('* LIST (\\HasNoChildren) "/" "INBOX"\r\n')
+    socket.write('* LIST (\\HasNoChildren \\Drafts) "/" "[Gmail]/Drafts"\r\n')
+    socket.write('* LIST (\\HasNoChildren \\Sent) "/" "[Gmail]/Sent Mail"\r\n')
+    ok()
+  } else if (request.startsWith('APPEND')) {
+    const match = request.match(/^APPEND "([^"]+)" (\([^)]*\)) \0([\s\S]*)\0$/)
+    assert.ok(match, `unexpected APPEND: ${request}`)
+    appended.push({ mailbox: match[1], flags: match[2], message: match[3] })
+    ok(`[APPENDUID 1 ${100 + appended.length}] APPEND completed`)
+  } else if (request.startsWith('LOGOUT')) {
+    socket.write('* BYE\r\n')
+    ok()
+    socket.end()
+  } else {
+    socket.write(`${tag} BAD unknown command\r\n`)
+  }
+}
+
+// --- A scripted SMTP server ---
+const smtpSessions: Array<{ commands: string[]; data: string }> = []
+const smtpServer = net.createServer((socket) => {
+  const session = { commands: [] as string[], data: '' }
+  smtpSessions.push(session)
+  let inData = false
+  let buffer = ''
+  socket.on('error', () => undefined)
+  socket.write('220 fake SMTP ready\r\n')
+  socket.on('data', (chunk: Buffer) => {
+    buffer += chunk.toString('utf-8')
+    for (;;) {
+      if (inData) {
+        const end = buffer.indexOf('\r\n.\r\n')
+        if (end < 0) return
+        session.data = buffer.slice(0, end + 2)
+        buffer = buffer.slice(end + 5)
+        inData = false
+        socket.write('250 queued\r\n')
+        continue
+      }
+      const end = buffer.indexOf('\r\n')
+      if (end < 0) return
+      const line = buffer.slice(0, end)
+      buffer = buffer.slice(end + 2)
+      session.commands.push(line)
+      if (line.startsWith('EHLO')) socket.write('250-fake\r\n250-AUTH PLAIN LOGIN\r\n250 8BITMIME\r\n')
+      else if (line.startsWith('AUTH PLAIN')) socket.write('235 accepted\r\n')
+      else if (line === 'DATA') {
+        inData = true
+        socket.write('354 go ahead\r\n')
+      } else if (line === 'QUIT') {
+        socket.write('221 bye\r\n')
+        socket.end()
+      } else socket.write('250 ok\r\n')
+    }
+  })
+})
+
+imapServer.listen(0, '127.0.0.1')
+smtpServer.listen(0, '127.0.0.1')
+await Promise.all([once(imapServer, 'listening'), once(smtpServer, 'listening')])
+
+let account: MailAccount | null = normalizeMailAccount({
+  imap: { host: '127.0.0.1', port: (imapServer.address() as net.AddressInfo).port, security: 'none' },
+  smtp: { host: '127.0.0.1', port: (smtpServer.address() as net.AddressInfo).port, security: 'none' },
+  username: 'me@example.com',
+  password: 'secret',
+  fromName: 'Mé',
+}, null)
+assert.equal(account.from, 'me@example.com')
+assert.equal(account.saveSentCopy, true)
+// Saving again without a password keeps the stored one
+assert.equal(normalizeMailAccount({ ...account, password: undefined }, account).password, 'secret')
+assert.throws(() => normalizeMailAccount({ ...account, imap: { host: 'x', security: 'ssl' } }, account), /imap.security/)
+
+await assert.rejects(verifyMailAccount({ ...account, password: 'wrong' }), /IMAP LOGIN failed: \[AUTHENTICATIONFAILED\] Invalid credentials/)
+
+const registry = new ToolRegistryImpl()
+registerMailTools(registry, new MailService(async () => account))
+
+// Listing: newest first, encoded subjects decoded, unread from flags
+const listed = await registry.execute('mail.list', {}, ctx)
+assert.deepEqual((listed.output as { messages: Array<{ id: string; subject: string; unread: boolean }> }).messages.map((message) => [message.id, message.subject, message.unread]), [
+  ['INBOX#9', 'Invoice for Październik', true],
+  ['INBOX#7', 'Lunch?', false],
+])
+await registry.execute('mail.list', { query: 'faktura źródło', unread_only: true }, ctx)
+assert.ok(imapCommands.includes('A3 UID SEARCH CHARSET UTF-8 UNSEEN TEXT \0faktura źródło\0'))
+
+// Reading: charset and quoted-printable decoded, plain text preferred, attachments listed, not marked read
+const read = await registry.execute('mail.read', { id: 'INBOX#9' }, ctx)
+const message = read.output as { text: string; messageId: string; attachments: unknown[] }
+assert.equal(message.text, 'Kwota: 120 zł, termin płatności 30 dni. To jest bardzo długa linia.')
+assert.equal(message.messageId, '<msg9@example.com>')
+assert.deepEqual(message.attachments, [{ filename: 'naïve.pdf', contentType: 'application/pdf', size: 13 }])
+assert.ok(imapCommands.some((command) => command.includes('BODY.PEEK[]')))
+assert.match(String((await registry.execute('mail.read', { id: 'INBOX' }, ctx)).error), /Invalid message id/)
+
+// Drafting a reply: recipient, subject and threading come from the original
+assert.equal(registry.getMetadata('mail.draft')?.requires_approval, false)
+const drafted = await registry.execute('mail.draft', { reply_to_id: 'INBOX#9', body: 'Paid, thanks.' }, ctx)
+assert.deepEqual(drafted.output, { id: '[Gmail]/Drafts#101', mailbox: '[Gmail]/Drafts', to: ['billing@example.com'], subject: 'Re: Invoice for Październik' })
+assert.equal(appended[0].flags, '(\\Draft \\Seen)')
+assert.match(appended[0].message, /^From: =\?UTF-8\?B\?TcOp\?= <me@example\.com>\r\nTo: billing@example\.com\r\n/)
+assert.match(appended[0].message, /\r\nIn-Reply-To: <msg9@example\.com>\r\n/)
+assert.match(appended[0].message, /\r\n\r\nUGFpZCwgdGhhbmtzLg==\r\n$/)
+
+// Sending: approval, Bcc only in the envelope, a copy in Sent
+assert.equal(registry.getMetadata('mail.send')?.requires_approval, true)
+assert.equal(registry.getPreview('mail.send', { to: ['ana@example.com'], subject: 'Lunch', body: 'Yes' }, ctx)?.summary, 'Send email to ana@example.com: Lunch')
+const sent = await registry.execute('mail.send', { to: ['Ana <ana@example.com>'], bcc: ['boss@example.com'], subject: 'Lunch', body: 'Yes, 1pm.' }, ctx)
+assert.equal((sent.output as { savedTo: string }).savedTo, '[Gmail]/Sent Mail')
+const smtp = smtpSessions[0]
+assert.deepEqual(smtp.commands.filter((command) => /^(MAIL|RCPT)/.test(command)), [
+  'MAIL FROM:<me@example.com>',
+  'RCPT TO:<ana@example.com>',
+  'RCPT TO:<boss@example.com>',
+])
+assert.equal(smtp.commands.find((command) => command.startsWith('AUTH PLAIN')), `AUTH PLAIN ${Buffer.from('\0me@example.com\0secret').toString('base64')}`)
+assert.doesNotMatch(smtp.data, /boss@example\.com/)
+assert.equal(appended[1].mailbox, '[Gmail]/Sent Mail')
+
+assert.match(String((await registry.execute('mail.send', { to: ['not-an-address'], body: 'x' }, ctx)).error), /Invalid to address/)
+assert.match(String((await registry.execute('mail.send', { body: 'x' }, ctx)).error), /to is required unless replying/)
+
+account = null
+assert.match(String((await registry.execute('mail.list', {}, ctx)).error), /No mail account is configured/)
+
+imapServer.close()
+smtpServer.close()
+console.log('Mail tool tests passed')
@@ -25,11 +25,23 @@ try {
     nodeEnv: 'test', port: 3001, host: 'localhost', dbDialect: 'sqlite', databaseUrl: join(dir, 'routes.db'),
     defaultModel: 'openrouter:test', publicBaseUrl: 'http://localhost:3001', encryptionKey: 'route-encryption-key',
     agentsDir: './agents', tasksDir: join(dir, 'tasks'), workspaceDir: join(dir, 'workspace'),
-    sessionFilesDir: join(dir, 'sessions'), inlineOutputLimitBytes: 32768, workflowsDir: './workflows',
+    sessionFilesDir: join(dir, 'sessions'), attachmentsDir: join(dir, 'attachments'),
+    inlineOutputLimitBytes: 32768, workflowsDir: './workflows',
+    databaseBusyTimeoutMs: 5000, databasePoolSize: 10, notesDir: join(dir, 'notes'),
+    projectsDir: join(dir, 'projects'), workspacesDir: join(dir, 'workspaces'), trashRetentionDays: 30, approvalTimeoutMs: 0,
+    approvalEscalationMaxCalls: 5, approvalEscalationMaxMutations: 500, filesApprovalFreeRoots: '',
+    remoteApprovals: false, remoteApprovalLinkTtlMs: 86_400_000, syncIntervalMs: 60_000,
+    pricingCatalogUrl: '', exchangeRateUrl: '', whisperCppBin: 'whisper-cli', ffmpegBin: 'ffmpeg', tesseractBin: 'tesseract',
+    visionImageMaxDimension: 1568, visionImageMaxBytes: 3_750_000, videoFrameCount: 8, attachmentImageMaxDimension: 16384, attachmentInlineMaxChars: 12000,
+    eventBatchMs: 16, eventMaxPerSecond: 60, wsBridgeEnabled: false, wsBridgePort: 3002,
+    prometheusEnabled: false, prometheusPort: 9464,
     allowedOrigins: '', trustProxy: false, enableShellTool: false, rateLimitAuthFailurePerMin: 120,
     rateLimitApiPerMin: 200, rateLimitInferencePerMin: 60, rateLimitTelegramPerMin: 600,
-    rateLimitHealthPerMin: 20, rateLimitOAuthCallbackPerMin: 100,
+    rateLimitHealthPerMin: 20, rateLimitOAuthCallbackPerMin: 100, rateLimitRemoteApprovalPerMin: 100,
     langfuseBaseUrl: 'https://cloud.langfuse.com', langfuseCaptureContent: false, langfuseMaxContentChars: 20000,
+    debugTraces: false, tracesDir: join(dir, 'traces'), debugTraceMaxBytes: 262144,
+    debugTracesMaxTotalBytes: 52428800, debugTraceRetentionDays: 7,
+    auditLog: false, auditDir: join(dir, 'audit'),
   } satisfies AppConfig
   runtime = await initRuntime(config)
 
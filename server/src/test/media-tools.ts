@@ -0,0 +1,91 @@
+import assert from 'node:assert/strict'
+import { MacMediaPlayer, SpotifyPlayer } from '../services/media.js'
+import { registerMediaTools } from '../tools/media.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const json = (value: unknown, status = 200) => new Response(JSON.stringify(value), { status, headers: { 'content-type': 'application/json' } })
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+
+// Spotify: the refresh token is exchanged once, then playback calls use the access token
+const spotify: Array<{ method: string; path: string; auth: string | null }> = []
+let playing = true
+let device = true
+let premium = true
+const spotifyFetch = (async (input: string | URL | Request, init?: RequestInit) => {
+  const url = new URL(String(input))
+  const method = init?.method ?? 'GET'
+  const headers = new Headers(init?.headers)
+  spotify.push({ method, path: url.pathname, auth: headers.get('authorization') })
+  if (url.pathname === '/api/token') {
+    assert.equal(String(init?.body), 'grant_type=refresh_token&refresh_token=refresh')
+    return json({ access_token: 'access', token_type: 'Bearer', expires_in: 3600 })
+  }
+  assert.equal(headers.get('authorization'), 'Bearer access')
+  if (!device) return json({ error: { status: 404, message: 'Player command failed: No active device found', reason: 'NO_ACTIVE_DEVICE' } }, 404)
+  if (url.pathname === '/v1/me/player' && method === 'GET') {
+    return json({
+      device: { name: 'Desk speaker' },
+      is_playing: playing,
+      progress_ms: 61_400,
+      item: { name: 'Clair de Lune', duration_ms: 302_000, artists: [{ name: 'Claude Debussy' }, { name: 'Jean-Yves Thibaudet' }], album: { name: 'Suite bergamasque' } },
+    })
+  }
+  if (!premium) return json({ error: { status: 403, message: 'Player command failed: Premium required', reason: 'PREMIUM_REQUIRED' } }, 403)
+  if (url.pathname === '/v1/me/player/pause') { playing = false; return new Response(null, { status: 204 }) }
+  if (url.pathname === '/v1/me/player/play') { playing = true; return new Response(null, { status: 204 }) }
+  if (url.pathname === '/v1/me/player/next') return new Response(null, { status: 204 })
+  return json({ error: { status: 404, message: 'Not found' } }, 404)
+}) as typeof fetch
+
+const registry = new ToolRegistryImpl()
+registerMediaTools(registry, new SpotifyPlayer('client', 'secret', 'refresh', spotifyFetch))
+assert.equal(registry.getMetadata('media.control')?.requires_approval, false)
+const status = await registry.execute('media.control', { action: 'status' }, ctx)
+assert.deepEqual(status.output, {
+  state: 'playing',
+  track: 'Clair de Lune',
+  artist: 'Claude Debussy, Jean-Yves Thibaudet',
+  album: 'Suite bergamasque',
+  duration: 302,
+  position: 61,
+  player: 'Desk speaker',
+})
+assert.equal(spotify[0].auth, `Basic ${Buffer.from('client:secret').toString('base64')}`)
+
+// Toggle pauses what is playing, then plays again
+spotify.length = 0
+assert.deepEqual((await registry.execute('media.control', { action: 'toggle' }, ctx)).output, { done: 'toggle' })
+assert.equal(playing, false)
+assert.deepEqual(spotify.map((request) => `${request.method} ${request.path}`), ['GET /v1/me/player', 'PUT /v1/me/player/pause'])
+await registry.execute('media.control', { action: 'Toggle' }, ctx)
+assert.equal(playing, true)
+assert.equal((await registry.execute('media.control', { action: 'next' }, ctx)).ok, true)
+assert.equal(spotify.filter((request) => request.path === '/api/token').length, 0)
+
+assert.match(String((await registry.execute('media.control', { action: 'shuffle' }, ctx)).error), /action must be one of status, play, pause/)
+premium = false
+assert.match(String((await registry.execute('media.control', { action: 'pause' }, ctx)).error), /needs a Premium account/)
+device = false
+assert.match(String((await registry.execute('media.control', { action: 'play' }, ctx)).error), /No active Spotify device/)
+
+// macOS: the action goes through argv and the script reports the app it used
+const scripts: Array<{ script: string; args: string[] }> = []
+let running = true
+const mac = new ToolRegistryImpl()
+registerMediaTools(mac, new MacMediaPlayer(async (script, args) => {
+  scripts.push({ script, args })
+  if (!running) return JSON.stringify({ state: 'stopped', player: null })
+  return JSON.stringify({ state: 'paused', player: 'Music', track: 'So What', artist: 'Miles Davis', album: 'Kind of Blue', duration: 562, position: 12 })
+}))
+assert.deepEqual((await mac.execute('media.control', { action: 'status' }, ctx)).output, {
+  state: 'paused', track: 'So What', artist: 'Miles Davis', album: 'Kind of Blue', duration: 562, position: 12, player: 'Music',
+})
+assert.deepEqual((await mac.execute('media.control', { action: 'previous' }, ctx)).output, { done: 'previous' })
+assert.deepEqual(scripts.map((call) => call.args), [['status'], ['previous']])
+running = false
+assert.deepEqual((await mac.execute('media.control', { action: 'status' }, ctx)).output, {
+  state: 'stopped', track: null, artist: null, album: null, duration: null, position: null, player: null,
+})
+assert.match(String((await mac.execute('media.control', { action: 'play' }, ctx)).error), /Neither Music nor Spotify is running/)
+
+console.log('Media tool tests passed')
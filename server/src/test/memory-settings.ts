@@ -0,0 +1,105 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { LLMRequest } from '../providers/types.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { extractMemories, rememberMemories } from '../services/memory.js'
+import {
+  DEFAULT_MEMORY_SETTINGS,
+  expireLowConfidenceMemories,
+  MEMORY_SETTINGS_PREFERENCE_KEY,
+  memoryBlockedReason,
+  normalizeMemorySettings,
+  parseMemorySettings,
+  setIncognito,
+} from '../services/memory-settings.js'
+import { registerMemoryTools } from '../tools/memory.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const DAY_MS = 24 * 60 * 60 * 1000
+
+// Omitted fields take their defaults; bad values are refused
+assert.deepEqual(parseMemorySettings(null), DEFAULT_MEMORY_SETTINGS)
+assert.deepEqual(parseMemorySettings('not json'), DEFAULT_MEMORY_SETTINGS)
+assert.deepEqual(normalizeMemorySettings({ excludedCategories: ['health', 'health', 'financial'] }).excludedCategories, ['health', 'financial'])
+assert.throws(() => normalizeMemorySettings({ excludedCategories: ['secrets' as never] }), /excludedCategories/)
+assert.throws(() => normalizeMemorySettings({ expireLowConfidenceDays: 0 }), /expireLowConfidenceDays/)
+assert.throws(() => normalizeMemorySettings({ lowConfidenceThreshold: 2 }), /lowConfidenceThreshold/)
+
+const dir = mkdtempSync(join(tmpdir(), 'memory-settings-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'memory.db')))
+  const generateRequests: LLMRequest[] = []
+  const reply = JSON.stringify({
+    memories: [
+      { content: 'The user works at Acme.', kind: 'fact', category: 'work', confidence: 0.9 },
+      { content: 'The user has asthma.', kind: 'fact', category: 'health', confidence: 0.9 },
+    ],
+  })
+  const runtime = {
+    config: { memoryModel: 'openai:mini' },
+    repositories: repos,
+    providers: {
+      resolve: () => ({
+        async generate(request: LLMRequest) {
+          generateRequests.push(request)
+          return { content: reply, usage: { input_tokens: 10, output_tokens: 5 } }
+        },
+      }),
+    },
+    usage: { record: async () => {} },
+  } as unknown as RuntimeContext
+  const store = { config: {}, providers: runtime.providers, memories: repos.memories } as Parameters<typeof rememberMemories>[0]
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Chat' })
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Chat', config })
+  await repos.items.create({ agentId: agent.id, type: 'message', role: 'user', content: 'I work at Acme and have asthma', turnNumber: 1 })
+  const extract = () => extractMemories(runtime, { userId: user.id, sessionId: session.id, agentId: agent.id })
+
+  // Incognito conversations are neither learned from nor recalled into
+  await setIncognito(repos.preferences, session.id, true)
+  assert.match(String(await memoryBlockedReason(repos.preferences, session.id)), /incognito/)
+  assert.deepEqual(await extract(), [])
+  assert.equal(generateRequests.length, 0)
+  const registry = new ToolRegistryImpl()
+  registerMemoryTools(registry, { ...store, sessions: repos.sessions, preferences: repos.preferences })
+  const ctx = { agent_id: agent.id, session_id: session.id, signal: new AbortController().signal }
+  const search = await registry.execute('memory.search', { query: 'Acme' }, ctx)
+  assert.deepEqual((search.output as { results: unknown[] }).results, [])
+  assert.match(String((await registry.execute('memory.save', { content: 'The user likes tea.' }, ctx)).error), /incognito/)
+  await setIncognito(repos.preferences, session.id, false)
+  assert.equal(await memoryBlockedReason(repos.preferences, session.id), null)
+
+  // Turning memory off applies everywhere
+  await repos.preferences.set(MEMORY_SETTINGS_PREFERENCE_KEY, JSON.stringify({ enabled: false }))
+  assert.match(String(await memoryBlockedReason(repos.preferences, session.id)), /turned off/)
+  assert.deepEqual(await extract(), [])
+
+  // Excluded categories are left out of the prompt's results and refused on save
+  await repos.preferences.set(MEMORY_SETTINGS_PREFERENCE_KEY, JSON.stringify({ excludedCategories: ['health'] }))
+  const stored = await extract()
+  assert.deepEqual(stored.map((memory) => [memory.content, memory.category]), [['The user works at Acme.', 'work']])
+  assert.match(String(generateRequests[0].messages[0].content), /leave such facts out: health/)
+  const refused = await registry.execute('memory.save', { content: 'The user takes an inhaler.', category: 'health' }, ctx)
+  assert.match(String(refused.error), /excluded health/)
+
+  // Low-confidence memories expire once untouched for long enough; sure ones stay
+  const [unsure] = await rememberMemories(store, user.id, [{ content: 'The user may like jazz.', kind: 'preference', confidence: 0.55 }])
+  const settings = normalizeMemorySettings({ expireLowConfidenceDays: 30 })
+  const later = unsure.updatedAt + 31 * DAY_MS
+  assert.equal(await expireLowConfidenceMemories(repos, settings, unsure.updatedAt + DAY_MS), 0)
+  assert.equal(await expireLowConfidenceMemories(repos, settings, later, { dryRun: true }), 1)
+  assert.notEqual(await repos.memories.getById(unsure.id), null)
+  assert.equal(await expireLowConfidenceMemories(repos, settings, later), 1)
+  assert.equal(await repos.memories.getById(unsure.id), null)
+  assert.equal((await repos.memories.listByUser(user.id)).length, 1)
+  assert.equal(await expireLowConfidenceMemories(repos, DEFAULT_MEMORY_SETTINGS, later), 0)
+
+  console.log('Memory settings tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,156 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { LLMEmbeddingRequest, LLMRequest } from '../providers/types.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { editMemory, extractMemories, forgetMemory, parseExtractedMemories, rememberMemories, searchMemories } from '../services/memory.js'
+import { registerMemoryTools } from '../tools/memory.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+// Replies may be fenced; bad kinds are dropped, confidence is clamped and unknown categories become other
+assert.deepEqual(parseExtractedMemories('```json\n{"memories":[{"content":" The user lives in Kraków. ","kind":"fact","category":"location","confidence":1.4},{"content":"x","kind":"mood","confidence":0.9},{"content":"The user likes short answers","kind":"preference","category":"style"}]}\n```'), [
+  { content: 'The user lives in Kraków.', kind: 'fact', category: 'location', confidence: 1 },
+  { content: 'The user likes short answers', kind: 'preference', category: 'other', confidence: 0 },
+])
+assert.deepEqual(parseExtractedMemories('Nothing to remember.'), [])
+assert.deepEqual(parseExtractedMemories({ memories: 'none' }), [])
+
+const dir = mkdtempSync(join(tmpdir(), 'memory-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'memory.db')))
+  const generateRequests: LLMRequest[] = []
+  const usagePhases: string[] = []
+  let reply = ''
+  const runtime = {
+    config: { memoryModel: 'openai:mini', attachmentEmbeddingModel: 'openai:embed' },
+    repositories: repos,
+    providers: {
+      resolve: () => ({
+        async generate(request: LLMRequest) {
+          generateRequests.push(request)
+          return { content: reply, usage: { input_tokens: 10, output_tokens: 5 } }
+        },
+        async embed(request: LLMEmbeddingRequest) {
+          return { embeddings: request.input.map((text) => ['rust', 'tabs'].map((topic) => text.toLowerCase().split(topic).length - 1)) }
+        },
+      }),
+    },
+    usage: { record: async ({ phase }: { phase: string }) => { usagePhases.push(phase) } },
+  } as unknown as RuntimeContext
+  const store = { config: runtime.config, providers: runtime.providers, memories: repos.memories }
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+
+  const alice = await repos.users.create({ apiKeyHash: 'alice' })
+  const bob = await repos.users.create({ apiKeyHash: 'bob' })
+
+  // Same wording merges; the surer memory's wording wins
+  const [first] = await rememberMemories(store, alice.id, [{ content: 'The user has a dog named Pixel.', kind: 'fact', confidence: 0.6 }])
+  const [again] = await rememberMemories(store, alice.id, [{ content: 'the user has a dog named pixel', kind: 'fact', confidence: 0.9 }])
+  assert.equal(again.id, first.id)
+  assert.equal(again.confidence, 0.9)
+  assert.equal(again.content, 'the user has a dog named pixel')
+  const [weaker] = await rememberMemories(store, alice.id, [{ content: 'The user has a dog named Pixel', kind: 'fact', confidence: 0.5 }])
+  assert.equal(weaker.confidence, 0.9)
+  assert.equal(weaker.content, 'the user has a dog named pixel')
+
+  // Close embeddings merge even when worded differently, also within one batch
+  const batch = await rememberMemories(store, alice.id, [
+    { content: 'The user writes Rust at work.', kind: 'fact', confidence: 0.8 },
+    { content: 'The user codes in Rust professionally.', kind: 'fact', confidence: 0.7 },
+    { content: 'The user prefers tabs over spaces.', kind: 'preference', confidence: 0.9 },
+  ])
+  assert.equal(batch[1].id, batch[0].id)
+  assert.equal(batch[1].content, 'The user writes Rust at work.')
+  assert.notEqual(batch[2].id, batch[0].id)
+  assert.equal(batch[2].embeddingModel, 'openai:embed')
+  assert.deepEqual(batch[2].embedding, [0, 1])
+  assert.equal((await repos.memories.listByUser(alice.id)).length, 3)
+
+  // Memories belong to one user
+  await rememberMemories(store, bob.id, [{ content: 'The user prefers tabs over spaces.', kind: 'preference', confidence: 0.9 }])
+  assert.equal((await repos.memories.listByUser(bob.id)).length, 1)
+  assert.equal((await repos.memories.listByUser(alice.id)).length, 3)
+
+  // Extraction reads the latest turn and drops unsure memories
+  const session = await repos.sessions.create({ userId: bob.id, title: 'Trip' })
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Plan a trip', config })
+  await repos.items.create({ agentId: agent.id, type: 'message', role: 'user', content: 'Earlier question', turnNumber: 1 })
+  await repos.items.create({ agentId: agent.id, type: 'message', role: 'assistant', content: 'Earlier answer', turnNumber: 1 })
+  await repos.items.create({ agentId: agent.id, type: 'message', role: 'user', content: 'I am vegetarian, find restaurants in Lisbon', turnNumber: 2 })
+  await repos.items.create({ agentId: agent.id, type: 'message', role: 'assistant', content: 'Here are three places.', turnNumber: 2 })
+  reply = JSON.stringify({
+    memories: [
+      { content: 'The user is vegetarian.', kind: 'fact', confidence: 0.95 },
+      { content: 'The user may live in Lisbon.', kind: 'fact', confidence: 0.3 },
+    ],
+  })
+
+  const extracted = await extractMemories(runtime, { userId: bob.id, sessionId: session.id, agentId: agent.id })
+  assert.deepEqual(extracted.map((memory) => [memory.content, memory.sourceSessionId]), [['The user is vegetarian.', session.id]])
+  const source = await repos.items.getById(extracted[0].sourceItemId!)
+  assert.equal(source?.content, 'I am vegetarian, find restaurants in Lisbon')
+  assert.equal(generateRequests.length, 1)
+  assert.equal(generateRequests[0].model, 'mini')
+  const prompt = String(generateRequests[0].messages[0].content)
+  assert.match(prompt, /user: I am vegetarian/)
+  assert.doesNotMatch(prompt, /Earlier question/)
+  assert.deepEqual(usagePhases, ['memory'])
+  assert.equal((await repos.memories.listByUser(bob.id)).length, 2)
+
+  // Search ranks by embedding; memories without one fall back to keywords
+  const [rust] = await searchMemories(store, alice.id, 'Which language does the user know? Rust?', { limit: 1 })
+  assert.equal(rust.content, 'The user writes Rust at work.')
+  const keywordStore = { ...store, config: {} } as typeof store
+  assert.deepEqual((await searchMemories(keywordStore, alice.id, 'dog pixel')).map((memory) => memory.content), ['the user has a dog named pixel'])
+  assert.deepEqual(await searchMemories(keywordStore, alice.id, 'cats'), [])
+
+  // Edits come from the user: new wording is embedded again at full confidence
+  const [tabs] = await searchMemories(store, alice.id, 'tabs', { limit: 1 })
+  assert.equal(await editMemory(store, bob.id, tabs.id, { content: 'The user prefers spaces.' }), null)
+  const edited = await editMemory(store, alice.id, tabs.id, { content: ' The user prefers tabs, width 4. ' })
+  assert.equal(edited?.content, 'The user prefers tabs, width 4.')
+  assert.equal(edited?.confidence, 1)
+  assert.equal(edited?.embeddingModel, 'openai:embed')
+
+  // A disabled memory is never recalled, and restating it does not bring it back
+  await editMemory(store, alice.id, tabs.id, { disabled: true })
+  assert.deepEqual(await searchMemories(store, alice.id, 'tabs'), [])
+  const [restated] = await rememberMemories(store, alice.id, [{ content: 'The user prefers tabs, width 4.', kind: 'preference', confidence: 0.9 }])
+  assert.equal(restated.id, tabs.id)
+  assert.equal(restated.disabled, true)
+  await editMemory(store, alice.id, tabs.id, { disabled: false })
+  assert.equal((await searchMemories(store, alice.id, 'tabs'))[0].id, tabs.id)
+
+  // Only the owner can forget a memory
+  assert.equal(await forgetMemory(store, bob.id, rust.id), false)
+  assert.equal(await forgetMemory(store, alice.id, rust.id), true)
+  assert.equal(await forgetMemory(store, alice.id, rust.id), false)
+
+  // Tools act for the session's user; memory.forget asks first
+  const registry = new ToolRegistryImpl()
+  registerMemoryTools(registry, { ...keywordStore, sessions: repos.sessions, preferences: repos.preferences })
+  const ctx = { agent_id: agent.id, session_id: session.id, signal: new AbortController().signal }
+  const saved = await registry.execute('memory.save', { content: 'The user prefers metric units.', kind: 'preference' }, ctx)
+  assert.equal(saved.ok, true)
+  const { id: savedId } = saved.output as { id: string }
+  assert.equal((await repos.memories.getById(savedId))?.confidence, 1)
+  assert.equal((await repos.memories.getById(savedId))?.sourceSessionId, session.id)
+  const found = await registry.execute('memory.search', { query: 'metric units' }, ctx)
+  assert.equal((found.output as { results: Array<{ id: string }> }).results[0].id, savedId)
+  assert.equal(registry.getMetadata('memory.forget')?.requires_approval, true)
+  const foreign = await registry.execute('memory.forget', { id: batch[2].id }, ctx)
+  assert.match(String(foreign.error), /Memory not found/)
+  assert.equal((await registry.execute('memory.forget', { id: savedId }, ctx)).ok, true)
+  assert.equal(await repos.memories.getById(savedId), null)
+
+  // Nothing runs without a memory model
+  const disabled = { ...runtime, config: { attachmentEmbeddingModel: 'openai:embed' } } as unknown as RuntimeContext
+  assert.deepEqual(await extractMemories(disabled, { userId: bob.id, sessionId: session.id, agentId: agent.id }), [])
+  assert.equal(generateRequests.length, 1)
+
+  console.log('Memory tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,66 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import {
+  listMessageRevisions,
+  MessageRevisionError,
+  restoreMessageRevision,
+  reviseMessage,
+} from '../services/message-history.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'message-revisions-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'revisions.db')))
+  const runtime = { repositories: repos } as unknown as RuntimeContext
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id })
+  const other = await repos.sessions.create({ userId: user.id })
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Chat', config })
+  const message = await repos.items.create({ agentId: agent.id, type: 'message', role: 'user', content: 'first draft', turnNumber: 1 })
+  const call = await repos.items.create({ agentId: agent.id, type: 'function_call', callId: 'c1', name: 'web', turnNumber: 1 })
+
+  const first = await reviseMessage(runtime, session.id, message.id, { content: 'second draft' }, 'edit')
+  assert.equal(first.revision, 1)
+  assert.equal(first.content, 'first draft')
+  assert.equal((await repos.items.getById(message.id))?.content, 'second draft')
+
+  await reviseMessage(runtime, session.id, message.id, { content: 'third draft' }, 'regenerate')
+  const revisions = await listMessageRevisions(runtime, session.id, message.id)
+  assert.deepEqual(revisions.map((r) => [r.revision, r.content, r.reason]), [
+    [1, 'first draft', 'edit'],
+    [2, 'second draft', 'regenerate'],
+  ])
+
+  // Restoring keeps the version it replaces.
+  const restored = await restoreMessageRevision(runtime, session.id, message.id, revisions[0].id)
+  assert.equal(restored.content, 'third draft')
+  assert.equal(restored.reason, 'restore')
+  assert.equal((await repos.items.getById(message.id))?.content, 'first draft')
+  assert.equal((await listMessageRevisions(runtime, session.id, message.id)).length, 3)
+
+  await assert.rejects(
+    reviseMessage(runtime, other.id, message.id, { content: 'x' }, 'edit'),
+    (err) => err instanceof MessageRevisionError && err.status === 404,
+  )
+  await assert.rejects(
+    reviseMessage(runtime, session.id, call.id, { content: 'x' }, 'edit'),
+    (err) => err instanceof MessageRevisionError && err.status === 400,
+  )
+  await repos.agents.update(agent.id, { status: 'running' })
+  await assert.rejects(
+    reviseMessage(runtime, session.id, message.id, { content: 'x' }, 'edit'),
+    (err) => err instanceof MessageRevisionError && err.status === 409,
+  )
+
+  await repos.sessions.delete(session.id)
+  assert.equal((await repos.messageRevisions.listByItem(message.id)).length, 0, 'revisions go with the session')
+
+  console.log('message revision tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,82 @@
+import assert from 'node:assert/strict'
+import type { Agent } from '../domain/types.js'
+import { AgentEventEmitter, UNPACED } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import { Metrics, quantile } from '../observability/metrics.js'
+
+const agent = { id: 'agent-1', sessionId: 'session-1' } as Agent
+const trace = (durationMs: number, extra: { error?: string; cacheRead?: number } = {}) => ({
+  agent,
+  turnNumber: 1,
+  provider: 'anthropic',
+  model: 'claude-sonnet',
+  stream: true,
+  request: { model: 'claude-sonnet', messages: [] },
+  ...(extra.error
+    ? { error: extra.error }
+    : { response: { content: 'ok', usage: { input_tokens: 100, output_tokens: 20, cache_read_input_tokens: extra.cacheRead }, finish_reason: 'stop' } }),
+  durationMs,
+})
+const toolDone = (name: string, durationMs: number, success = true): AgentEvent => ({
+  type: EVENT_TYPES.TOOL_COMPLETED,
+  agent_id: 'agent-1',
+  session_id: 'session-1',
+  timestamp: Date.now(),
+  payload: { callId: 'c', name, success, output: '', durationMs, parentId: null, depth: 0, ...(!success && { errorCode: 'tool_timeout' as const }) },
+})
+
+const metrics = new Metrics()
+await metrics.record(trace(400))
+await metrics.record(trace(800, { cacheRead: 300 }))
+await metrics.record(trace(1200, { error: 'Anthropic 429: rate limited' }))
+metrics.recordEvent(toolDone('shell.run', 30))
+metrics.recordEvent(toolDone('shell.run', 90_000, false))
+metrics.gauge('agents_in_flight', 'Agent runs currently executing.', () => 2)
+
+const families = new Map(metrics.snapshot().metrics.map((f) => [f.name, f]))
+const llm = families.get('llm_request_duration_seconds')!
+assert.equal(llm.type, 'histogram')
+const ok = llm.type === 'histogram' ? llm.series.find((s) => s.labels.outcome === 'ok')! : null
+assert.equal(ok?.count, 2)
+assert.ok(Math.abs(ok!.sum - 1.2) < 1e-9)
+const limited = llm.type === 'histogram' ? llm.series.find((s) => s.labels.outcome === 'rate_limited') : null
+assert.equal(limited?.count, 1, 'failed calls are labelled with their error code')
+
+const tokens = families.get('llm_tokens_total')!
+const tokensOf = (kind: string) => tokens.type === 'counter' ? tokens.series.find((s) => s.labels.kind === kind)?.value : undefined
+assert.equal(tokensOf('input'), 200)
+assert.equal(tokensOf('cache_read'), 300)
+assert.equal(tokensOf('cache_write'), undefined, 'zero increments create no series')
+
+const ratio = families.get('prompt_cache_hit_ratio')!
+assert.equal(ratio.type === 'gauge' ? ratio.series[0].value : null, 300 / 500)
+
+const tools = families.get('tool_duration_seconds')!
+assert.deepEqual(tools.type === 'histogram' ? tools.series.map((s) => s.labels.outcome) : [], ['ok', 'tool_timeout'])
+assert.equal(families.get('agents_in_flight')?.type, 'gauge')
+
+// Quantiles interpolate within buckets and clamp past the largest bound
+const buckets = [{ le: 1, count: 50 }, { le: 2, count: 100 }]
+assert.equal(quantile(buckets, 100, 0.5), 1)
+assert.equal(quantile(buckets, 100, 0.75), 1.5)
+assert.equal(quantile(buckets, 120, 0.99), 2)
+assert.equal(quantile(buckets, 0, 0.5), null)
+
+const text = metrics.toPrometheus()
+assert.match(text, /^# TYPE assistant_llm_request_duration_seconds histogram$/m)
+assert.match(text, /^assistant_llm_request_duration_seconds_bucket\{provider="anthropic",model="claude-sonnet",outcome="ok",le="0.5"\} 1$/m)
+assert.match(text, /^assistant_llm_request_duration_seconds_bucket\{.*outcome="ok",le="\+Inf"\} 2$/m)
+assert.match(text, /^assistant_tool_duration_seconds_count\{tool="shell.run",outcome="tool_timeout"\} 1$/m)
+assert.match(text, /^assistant_agents_in_flight 2$/m)
+
+// Subscriber queue depth is visible while a consumer lags
+const events = new AgentEventEmitter()
+const iterator = events.subscribe({ session_id: 'session-1' }, UNPACED)[Symbol.asyncIterator]()
+events.emit(toolDone('a', 1))
+events.emit(toolDone('b', 1))
+assert.deepEqual(events.queueStats(), { subscribers: 1, queued: 2, maxQueued: 2 })
+await iterator.next()
+await iterator.return!()
+assert.deepEqual(events.queueStats(), { subscribers: 0, queued: 0, maxQueued: 0 })
+
+console.log('metrics tests passed')
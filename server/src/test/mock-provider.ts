@@ -0,0 +1,90 @@
+import assert from 'node:assert/strict'
+import { createHash } from 'node:crypto'
+import fs from 'node:fs/promises'
+import os from 'node:os'
+import path from 'node:path'
+import { Hono } from 'hono'
+
+import { EVENT_TYPES } from '../events/types.js'
+import { loadConfig } from '../lib/config.js'
+import { initRuntime, shutdownRuntime, type RuntimeContext } from '../lib/runtime.js'
+import { MockProvider, type MockScript } from '../providers/mock.js'
+import type { LLMMessage, LLMStreamEvent } from '../providers/types.js'
+import { chatRoutes } from '../routes/chat.js'
+
+const script: MockScript = {
+  responses: [
+    { turn: 1, match: 'times', tool_calls: [{ name: 'calculator.evaluate', arguments: { expression: '3 * 7' } }] },
+    { turn: 2, match: '"result": 21', content: '3 times 7 is 21.' },
+    { turn: 1, match: '^hello', content: 'Hi there.' },
+  ],
+}
+
+// Rules: turn and match must both fit; without a fallback an unscripted turn fails
+const provider = new MockProvider(script)
+const ask = (...messages: LLMMessage[]) => provider.generate({ model: 'mock:test', messages })
+const first = await ask({ role: 'user', content: 'What is 3 times 7?' })
+assert.deepEqual(first.tool_calls, [{ call_id: 'mock_1_1', name: 'calculator.evaluate', arguments: { expression: '3 * 7' } }])
+assert.equal(first.finish_reason, 'tool_calls')
+assert.equal((await ask({ role: 'user', content: 'Hello!' })).content, 'Hi there.')
+await assert.rejects(ask({ role: 'user', content: 'Something else' }), /no response for turn 1/)
+const fallback = new MockProvider({ responses: [...script.responses, { content: 'Fallback.' }] })
+assert.equal((await fallback.generate({ model: 'mock:test', messages: [{ role: 'user', content: 'Something else' }] })).content, 'Fallback.')
+assert.throws(() => new MockProvider({ responses: [] }))
+
+const streamed: LLMStreamEvent[] = []
+for await (const event of provider.stream({ model: 'mock:test', messages: [{ role: 'user', content: 'hello' }] })) streamed.push(event)
+assert.deepEqual(streamed.map((event) => event.type), ['text_delta', 'text_delta', 'text_done', 'done'])
+
+// Full stack: the chat route runs the planner on the mock provider, picked by model id
+const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), 'mock-provider-test-'))
+process.env.DATABASE_URL = path.join(tmpDir, 'test.db')
+process.env.TASKS_DIR = path.join(tmpDir, 'tasks')
+process.env.WORKSPACE_DIR = path.join(tmpDir, 'workspace')
+process.env.SESSION_FILES_DIR = path.join(tmpDir, 'sessions')
+process.env.ENCRYPTION_KEY = 'test-encryption-key'
+process.env.MOCK_PROVIDER_SCRIPT = path.join(tmpDir, 'script.json')
+delete process.env.ANTHROPIC_API_KEY
+delete process.env.OPENAI_API_KEY
+delete process.env.OLLAMA_BASE_URL
+delete process.env.OPENROUTER_API_KEY
+process.env.LANGFUSE_ENABLED = 'false'
+await fs.writeFile(process.env.MOCK_PROVIDER_SCRIPT, JSON.stringify(script))
+
+let runtime: RuntimeContext | null = null
+try {
+  runtime = await initRuntime(loadConfig())
+  assert.ok(runtime.providers.list().includes('mock'))
+  const user = await runtime.repositories.users.create({
+    email: 'mock-provider@test.local',
+    apiKeyHash: createHash('sha256').update('mock-provider-test-key').digest('hex'),
+  })
+  const app = new Hono<{ Variables: { userId: string } }>()
+  app.use('*', async (c, next) => {
+    c.set('userId', user.id)
+    await next()
+  })
+  app.route('/api/chat', chatRoutes(runtime))
+
+  const res = await app.request('/api/chat/completions', {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ model: 'mock:scripted', agent: 'planner', input: 'What is 3 times 7?' }),
+  })
+  assert.equal(res.status, 200)
+  const body = await res.json() as { sessionId: string; status: string; output: Array<{ content: string }> }
+  assert.equal(body.status, 'completed')
+  assert.equal(body.output.at(-1)?.content, '3 times 7 is 21.')
+
+  const events = runtime.events.replay({ session_id: body.sessionId })
+  const types = events.map((event) => event.type)
+  for (const type of [EVENT_TYPES.AGENT_STARTED, EVENT_TYPES.TOOL_STARTED, EVENT_TYPES.TOOL_COMPLETED, EVENT_TYPES.AGENT_COMPLETED]) {
+    assert.ok(types.includes(type), `expected ${type}`)
+  }
+  assert.ok(types.indexOf(EVENT_TYPES.TOOL_COMPLETED) < types.indexOf(EVENT_TYPES.AGENT_COMPLETED))
+
+  console.log('mock provider tests passed')
+} finally {
+  if (runtime) await shutdownRuntime(runtime)
+  await fs.rm(tmpDir, { recursive: true, force: true })
+}
@@ -0,0 +1,78 @@
+import assert from 'node:assert/strict'
+import type { LLMModelInfo, LLMProvider } from '../providers/types.js'
+import type { ModelCatalogRecord, ModelCatalogRepository } from '../repositories/types.js'
+import { MODEL_CATALOG_MAX_AGE_MS, ModelCatalog } from '../services/model-catalog.js'
+
+const model = (modelId: string, capabilities: Partial<LLMModelInfo> = {}): LLMModelInfo => ({
+  modelId,
+  displayName: null,
+  contextWindow: null,
+  maxOutputTokens: null,
+  vision: null,
+  structuredOutputs: null,
+  tools: null,
+  ...capabilities,
+})
+
+// An in-memory table behind the catalog
+let rows: ModelCatalogRecord[] = []
+const repo: ModelCatalogRepository = {
+  list: async () => [...rows].sort((a, b) => a.provider.localeCompare(b.provider) || a.modelId.localeCompare(b.modelId)),
+  replace: async (provider, models) => {
+    rows = [...rows.filter((row) => row.provider !== provider), ...models.map((entry) => ({ ...entry, provider, fetchedAt: Date.now() }))]
+  },
+}
+
+let openrouterDown = false
+const listings: Record<string, Partial<LLMProvider>> = {
+  anthropic: { listModels: async () => [model('claude-sonnet-4-5', { displayName: 'Claude Sonnet 4.5', contextWindow: 200_000, vision: true, structuredOutputs: true, tools: true })] },
+  openrouter: {
+    listModels: async () => {
+      if (openrouterDown) throw new Error('OpenRouter 503: unavailable')
+      return [model('meta-llama/llama-3.1-8b', { vision: false, tools: true }), model('openai/gpt-4o', { vision: true })]
+    },
+  },
+  // Providers without a listing are left out
+  mock: {},
+}
+const providers = {
+  get: (name: string) => listings[name] as LLMProvider | undefined,
+  list: () => Object.keys(listings),
+}
+
+// Everything listable is stale until fetched
+const catalog = new ModelCatalog(repo, providers)
+await catalog.load()
+assert.deepEqual(catalog.stale(), ['anthropic', 'openrouter'])
+assert.equal(catalog.capabilities('anthropic:claude-sonnet-4-5'), null)
+
+// A refresh caches every listing, in memory and in the table
+const first = await catalog.refresh()
+assert.deepEqual(first, { refreshed: { anthropic: 1, openrouter: 2 }, errors: {} })
+assert.equal(catalog.capabilities('anthropic:claude-sonnet-4-5')?.contextWindow, 200_000)
+assert.equal(catalog.capabilities('openrouter:meta-llama/llama-3.1-8b')?.vision, false)
+assert.equal(catalog.capabilities('gpt-4o'), null)
+assert.deepEqual(catalog.list('openrouter').map((entry) => entry.modelId), ['meta-llama/llama-3.1-8b', 'openai/gpt-4o'])
+assert.deepEqual(catalog.stale(), [])
+assert.deepEqual(catalog.stale(MODEL_CATALOG_MAX_AGE_MS, Date.now() + MODEL_CATALOG_MAX_AGE_MS + 1), ['anthropic', 'openrouter'])
+
+// A failed listing keeps what was cached and says why
+openrouterDown = true
+const failed = await catalog.refresh('openrouter')
+assert.deepEqual(failed, { refreshed: {}, errors: { openrouter: 'OpenRouter 503: unavailable' } })
+assert.equal(catalog.list('openrouter').length, 2)
+assert.deepEqual((await catalog.refresh('mock')).errors, { mock: 'Provider is not configured or cannot list its models' })
+
+// A fresh listing replaces the old one, dropping models the provider no longer offers
+openrouterDown = false
+listings.openrouter = { listModels: async () => [model('openai/gpt-4o', { vision: true })] }
+await catalog.refresh('openrouter')
+assert.deepEqual(catalog.list('openrouter').map((entry) => entry.modelId), ['openai/gpt-4o'])
+
+// The cache comes back from the table on restart
+const restarted = new ModelCatalog(repo, providers)
+await restarted.load()
+assert.deepEqual(restarted.list().map((entry) => `${entry.provider}:${entry.modelId}`), ['anthropic:claude-sonnet-4-5', 'openrouter:openai/gpt-4o'])
+assert.equal(restarted.capabilities('anthropic:claude-sonnet-4-5')?.vision, true)
+
+console.log('model catalog tests passed')
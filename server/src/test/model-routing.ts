@@ -0,0 +1,84 @@
+import assert from 'node:assert/strict'
+import type { Item } from '../domain/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import type { LLMRequest } from '../providers/types.js'
+import { listRoutingDecisions, MODEL_ROUTING_PREFIX, recordRoutingDecision, routeModel, tierModel } from '../services/model-routing.js'
+
+const tiers = {
+  defaultModel: 'auto',
+  routingQuickModel: 'openai:gpt-4o-mini',
+  routingAgentModel: 'anthropic:claude-sonnet',
+  routingCodingModel: undefined as string | undefined,
+}
+const capabilities = new Map<string, { vision?: boolean; structuredOutputs?: boolean }>()
+const runtime = (routingModel: string | undefined, reply: () => Promise<string>) => ({
+  config: { ...tiers, routingModel },
+  modelCatalog: { capabilities: (model: string) => capabilities.get(model) ?? null },
+  providers: {
+    resolve: () => ({
+      generate: async (request: LLMRequest) => {
+        requests.push(request)
+        return { content: await reply(), usage: { input_tokens: 10, output_tokens: 5 }, finish_reason: 'stop' }
+      },
+    }),
+  },
+}) as unknown as Pick<RuntimeContext, 'config' | 'providers' | 'modelCatalog'>
+const requests: LLMRequest[] = []
+
+// Tier models: coding falls back to the agent tier, which falls back to a concrete default
+assert.equal(tierModel(tiers, 'quick'), 'openai:gpt-4o-mini')
+assert.equal(tierModel(tiers, 'coding'), 'anthropic:claude-sonnet')
+assert.equal(tierModel({ ...tiers, routingAgentModel: undefined, defaultModel: 'ollama:llama3.2' }, 'agent'), 'ollama:llama3.2')
+assert.throws(() => tierModel({ ...tiers, routingAgentModel: undefined }, 'agent'), /ROUTING_AGENT_MODEL/)
+
+// The triage model decides, and its usage is handed back for recording
+const triaged = await routeModel(runtime('openai:gpt-4o-mini', async () => '{"tier":"coding","reason":"Debugging a stack trace"}'), 'Why does this crash?')
+assert.deepEqual({ ...triaged.decision, decidedAt: 0 }, { tier: 'coding', model: 'anthropic:claude-sonnet', reason: 'Debugging a stack trace', classifiedBy: 'triage', decidedAt: 0 })
+assert.deepEqual(triaged.usage, { provider: 'openai', model: 'gpt-4o-mini', usage: { input_tokens: 10, output_tokens: 5 } })
+assert.equal(requests[0].messages[1].content, 'Why does this crash?')
+
+// Without a triage model, or when it fails, keywords decide
+const heuristic = async (input: string | Item[]) => (await routeModel(runtime(undefined, async () => ''), input)).decision.tier
+assert.equal(await heuristic('What is the capital of Peru?'), 'quick')
+assert.equal(await heuristic('Fix the regex in parser.ts'), 'coding')
+assert.equal(await heuristic('Find flights to Lima and email me the cheapest'), 'agent')
+assert.equal(await heuristic([{ role: 'user', content: 'What is in this?', contentBlocks: [{ type: 'image' }] }] as unknown as Item[]), 'agent')
+const failed = await routeModel(runtime('openai:gpt-4o-mini', async () => { throw new Error('down') }), 'Hi there')
+assert.deepEqual([failed.decision.tier, failed.decision.classifiedBy, failed.usage], ['quick', 'heuristic', undefined])
+const unreadable = await routeModel(runtime('openai:gpt-4o-mini', async () => '{"tier":"expert"}'), 'Hi there')
+assert.equal(unreadable.decision.classifiedBy, 'heuristic')
+assert.ok(unreadable.usage)
+
+// The catalog's capabilities steer routing: no schema for a triage model that cannot follow one,
+// and images go to a tier model that reads them
+capabilities.set('openai:gpt-4o-mini', { vision: false, structuredOutputs: false })
+capabilities.set('anthropic:claude-sonnet', { vision: true, structuredOutputs: true })
+requests.length = 0
+await routeModel(runtime('openai:gpt-4o-mini', async () => '{"tier":"quick","reason":"Greeting"}'), 'Hi there')
+assert.equal(requests[0].structured_output, undefined)
+const pictured = await routeModel(runtime('openai:gpt-4o-mini', async () => '{"tier":"quick","reason":"Asks what a photo shows"}'), [
+  { role: 'user', content: 'What is this?', contentBlocks: [{ type: 'image' }] },
+] as unknown as Item[])
+assert.deepEqual([pictured.decision.tier, pictured.decision.model], ['quick', 'anthropic:claude-sonnet'])
+assert.match(pictured.decision.reason, /reads the attached images/)
+const readAsText = await routeModel(runtime('openai:gpt-4o-mini', async () => '{"tier":"quick","reason":"Scanned note"}'), [
+  { role: 'user', content: 'Summarize', contentBlocks: [{ type: 'image', chunks: ['Buy milk'] }] },
+] as unknown as Item[])
+assert.equal(readAsText.decision.model, 'openai:gpt-4o-mini')
+capabilities.clear()
+
+// Decisions are kept with the conversation, the latest 50
+const stored = new Map<string, string>()
+const preferences = {
+  get: async (key: string) => stored.get(key) ?? null,
+  set: async (key: string, value: string) => { stored.set(key, value) },
+  delete: async (key: string) => { stored.delete(key) },
+}
+assert.deepEqual(await listRoutingDecisions(preferences, 's1'), [])
+for (let i = 0; i < 52; i++) await recordRoutingDecision(preferences, 's1', { ...triaged.decision, decidedAt: i })
+const decisions = await listRoutingDecisions(preferences, 's1')
+assert.deepEqual([decisions.length, decisions[0].decidedAt, decisions[49].decidedAt], [50, 2, 51])
+stored.set(`${MODEL_ROUTING_PREFIX}s2`, 'not json')
+assert.deepEqual(await listRoutingDecisions(preferences, 's2'), [])
+
+console.log('model routing tests passed')
@@ -0,0 +1,92 @@
+import assert from 'node:assert/strict'
+import { markdownToBlocks, NotionClient, parsePageId } from '../services/notion.js'
+import { registerNotionTools } from '../tools/notion.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const PAGE = '0123456789abcdef0123456789abcdef'
+const PAGE_ID = '01234567-89ab-cdef-0123-456789abcdef'
+const text = (content: string) => [{ plain_text: content }]
+const page = (id: string, title: string) => ({
+  id,
+  url: `https://www.notion.so/${id.replace(/-/g, '')}`,
+  last_edited_time: '2026-10-01T00:00:00.000Z',
+  parent: { type: 'workspace', workspace: true },
+  properties: { Name: { type: 'title', title: text(title) } },
+})
+
+const requests: Array<{ method: string; path: string; body: unknown }> = []
+const fakeFetch = (async (input: string | URL | Request, init?: RequestInit) => {
+  const url = new URL(String(input))
+  const method = init?.method ?? 'GET'
+  const body = init?.body ? JSON.parse(String(init.body)) : undefined
+  requests.push({ method, path: url.pathname, body })
+  assert.equal(new Headers(init?.headers).get('notion-version'), '2022-06-28')
+  const json = (value: unknown, status = 200) => new Response(JSON.stringify(value), { status, headers: { 'content-type': 'application/json' } })
+
+  if (url.pathname === '/v1/search') return json({ results: [page(PAGE_ID, 'Meeting notes')] })
+  if (url.pathname === `/v1/pages/${PAGE_ID}`) return json(page(PAGE_ID, 'Meeting notes'))
+  if (url.pathname === '/v1/pages' && method === 'POST') return json(page('fedcba98-7654-3210-fedc-ba9876543210', body.properties.title.title[0].text.content))
+  if (url.pathname === `/v1/blocks/${PAGE_ID}/children` && method === 'GET') {
+    if (!url.searchParams.get('start_cursor')) {
+      return json({
+        results: [
+          { id: 'b1', type: 'heading_2', heading_2: { rich_text: text('Agenda') }, has_children: false },
+          { id: 'b2', type: 'bulleted_list_item', bulleted_list_item: { rich_text: text('Budget') }, has_children: true },
+        ],
+        has_more: true,
+        next_cursor: 'c2',
+      })
+    }
+    return json({ results: [{ id: 'b3', type: 'to_do', to_do: { rich_text: text('Send recap'), checked: true }, has_children: false }], has_more: false, next_cursor: null })
+  }
+  if (url.pathname === '/v1/blocks/b2/children') {
+    return json({ results: [{ id: 'b4', type: 'paragraph', paragraph: { rich_text: text('Q4 numbers') }, has_children: false }], has_more: false, next_cursor: null })
+  }
+  if (url.pathname === `/v1/blocks/${PAGE_ID}/children` && method === 'PATCH') return json({ results: body.children })
+  return json({ message: 'Could not find page' }, 404)
+}) as typeof fetch
+
+// Page IDs come dashed, undashed or inside URLs
+assert.equal(parsePageId(PAGE), PAGE_ID)
+assert.equal(parsePageId(`https://www.notion.so/team/Meeting-notes-${PAGE}?pvs=4`), PAGE_ID)
+assert.throws(() => parsePageId('meeting notes'), /page must be/)
+
+// Markdown becomes Notion blocks
+assert.deepEqual(markdownToBlocks('# Summary\n\n- [x] done\n- item\n1. first\n> quote\n```ts\nconst a = 1\n```\nPlain').map((block) => block.type), [
+  'heading_1', 'to_do', 'bulleted_list_item', 'numbered_list_item', 'quote', 'code', 'paragraph',
+])
+const [long] = markdownToBlocks('x'.repeat(4_500))
+assert.equal((long.paragraph as { rich_text: unknown[] }).rich_text.length, 3)
+
+const registry = new ToolRegistryImpl()
+registerNotionTools(registry, new NotionClient('secret_test', fakeFetch))
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+
+const found = await registry.execute('notion.search', { query: 'meeting' }, ctx)
+assert.deepEqual((found.output as { pages: Array<{ id: string; title: string }> }).pages, [
+  { id: PAGE_ID, title: 'Meeting notes', url: `https://www.notion.so/${PAGE}`, lastEditedAt: '2026-10-01T00:00:00.000Z', parent: null },
+])
+
+// Reading follows pagination and nested blocks
+const read = await registry.execute('notion.read_page', { page: PAGE }, ctx)
+assert.equal((read.output as { content: string }).content, '## Agenda\n- Budget\n  Q4 numbers\n- [x] Send recap')
+
+// Writes ask first and preview the content
+assert.equal(registry.getMetadata('notion.append')?.requires_approval, true)
+assert.equal(registry.getMetadata('notion.create_page')?.requires_approval, true)
+assert.equal(registry.getPreview('notion.create_page', { parent: PAGE, title: 'Recap', content: 'Hi' }, ctx)?.summary, 'Create Notion page "Recap"')
+const appended = await registry.execute('notion.append', { page: PAGE, content: '## Follow-ups\n- Call Ana' }, ctx)
+assert.equal((appended.output as { blocksAdded: number }).blocksAdded, 2)
+const created = await registry.execute('notion.create_page', { parent: PAGE, title: 'Recap', content: 'Decisions' }, ctx)
+assert.equal((created.output as { page: { title: string } }).page.title, 'Recap')
+assert.deepEqual(requests.at(-1)?.body, {
+  parent: { page_id: PAGE_ID },
+  properties: { title: { title: [{ type: 'text', text: { content: 'Recap' } }] } },
+  children: [{ object: 'block', type: 'paragraph', paragraph: { rich_text: [{ type: 'text', text: { content: 'Decisions' } }] } }],
+})
+
+// Pages not shared with the integration explain themselves
+const missing = await registry.execute('notion.read_page', { page: 'ffffffffffffffffffffffffffffffff' }, ctx)
+assert.match(String(missing.error), /not be shared with the integration/)
+
+console.log('Notion tool tests passed')
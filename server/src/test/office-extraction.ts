@@ -0,0 +1,126 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { deflateRawSync } from 'node:zlib'
+import { AttachmentStore } from '../lib/attachment-store.js'
+import { extractOfficeContent, markdownTable } from '../lib/office-text.js'
+import { parseXml } from '../lib/xml.js'
+import { readZip } from '../lib/zip.js'
+import { extractOfficeDocument, inferOfficeFormat, OfficeInputError } from '../services/office-extraction.js'
+
+/** Builds a ZIP with deflated entries; the reader does not check CRCs, so they are left as zero. */
+function zip(files: Record<string, string>): Buffer {
+  const locals: Buffer[] = []
+  const centrals: Buffer[] = []
+  let offset = 0
+  for (const [name, content] of Object.entries(files)) {
+    const nameBytes = Buffer.from(name)
+    const raw = Buffer.from(content)
+    const body = deflateRawSync(raw)
+    const local = Buffer.alloc(30)
+    local.writeUInt32LE(0x04034b50, 0)
+    local.writeUInt16LE(8, 8)
+    local.writeUInt32LE(body.length, 18)
+    local.writeUInt32LE(raw.length, 22)
+    local.writeUInt16LE(nameBytes.length, 26)
+    const central = Buffer.alloc(46)
+    central.writeUInt32LE(0x02014b50, 0)
+    central.writeUInt16LE(8, 10)
+    central.writeUInt32LE(body.length, 20)
+    central.writeUInt32LE(raw.length, 24)
+    central.writeUInt16LE(nameBytes.length, 28)
+    central.writeUInt32LE(offset, 42)
+    locals.push(local, nameBytes, body)
+    centrals.push(central, nameBytes)
+    offset += local.length + nameBytes.length + body.length
+  }
+  const directory = Buffer.concat(centrals)
+  const end = Buffer.alloc(22)
+  end.writeUInt32LE(0x06054b50, 0)
+  end.writeUInt16LE(Object.keys(files).length, 8)
+  end.writeUInt16LE(Object.keys(files).length, 10)
+  end.writeUInt32LE(directory.length, 12)
+  end.writeUInt32LE(offset, 16)
+  return Buffer.concat([...locals, directory, end])
+}
+
+const rels = (entries: Array<[string, string, string?]>) =>
+  `<?xml version="1.0"?><Relationships>${entries.map(([id, target, type = 'x/other']) => `<Relationship Id="${id}" Type="${type}" Target="${target}"/>`).join('')}</Relationships>`
+
+assert.deepEqual(readZip(zip({ 'a.txt': 'hello' }), 1024).read('a.txt')?.toString(), 'hello')
+assert.throws(() => readZip(zip({ 'big.txt': 'x'.repeat(100) }), 10).read('big.txt'), /too large/)
+assert.equal(readZip(zip({ 'a.txt': 'hello' }), 1024).read('missing.txt'), null)
+assert.deepEqual(parseXml('<a:b x="1 &amp; 2"><![CDATA[<raw>]]>&lt;&#x41;</a:b>').children, [
+  { name: 'b', attrs: { x: '1 & 2' }, children: ['<raw>', '<A'] },
+])
+assert.equal(markdownTable([['Name', 'Note'], ['a|b', 'two\nlines', 'extra']]), '| Name | Note |  |\n| --- | --- | --- |\n| a\\|b | two lines | extra |')
+
+assert.equal(inferOfficeFormat(undefined, 'Budget.XLSX'), 'xlsx')
+assert.equal(inferOfficeFormat('application/vnd.openxmlformats-officedocument.wordprocessingml.document', 'download'), 'docx')
+assert.equal(inferOfficeFormat('application/msword', 'old.doc'), null)
+
+// Word: headings, list items and tables keep their structure; tracked deletions and field codes are dropped
+const docx = zip({
+  'word/document.xml': `<?xml version="1.0"?><w:document xmlns:w="w"><w:body>
+    <w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>Quarterly plan</w:t></w:r></w:p>
+    <w:p><w:r><w:t xml:space="preserve">Ship the </w:t></w:r><w:del><w:r><w:delText>old </w:delText></w:r></w:del><w:r><w:t>beta</w:t><w:tab/><w:t>soon</w:t></w:r></w:p>
+    <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/></w:numPr></w:pPr><w:r><w:instrText>PAGE</w:instrText><w:t>Hire two engineers</w:t></w:r></w:p>
+    <w:tbl><w:tr><w:tc><w:p><w:r><w:t>Owner</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>Due</w:t></w:r></w:p></w:tc></w:tr>
+    <w:tr><w:tc><w:p><w:r><w:t>Ana</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>Q3</w:t></w:r></w:p></w:tc></w:tr></w:tbl>
+    <w:sectPr/></w:body></w:document>`,
+})
+assert.deepEqual(extractOfficeContent(docx, 'docx').sections, [
+  '## Quarterly plan\n\nShip the beta\tsoon\n\n- Hire two engineers\n\n| Owner | Due |\n| --- | --- |\n| Ana | Q3 |',
+])
+
+// Excel: one table per sheet, with shared strings, inline strings, booleans and gaps between columns
+const xlsx = zip({
+  'xl/workbook.xml': '<workbook><sheets><sheet name="Budget" sheetId="1" r:id="rId1"/><sheet name="Empty" sheetId="2" r:id="rId2"/></sheets></workbook>',
+  'xl/_rels/workbook.xml.rels': rels([['rId1', 'worksheets/sheet1.xml'], ['rId2', '/xl/worksheets/sheet2.xml']]),
+  'xl/sharedStrings.xml': '<sst><si><t>Item</t></si><si><r><t>Cost</t></r><r><t xml:space="preserve"> (EUR)</t></r></si><si><t>Laptop</t><rPh><t>ラップトップ</t></rPh></si></sst>',
+  'xl/worksheets/sheet1.xml': `<worksheet><sheetData>
+    <row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c><c r="D1" t="inlineStr"><is><t>Approved</t></is></c></row>
+    <row r="2"><c r="A2" t="s"><v>2</v></c><c r="B2"><f>SUM(1,2)</f><v>1299.5</v></c><c r="D2" t="b"><v>1</v></c></row>
+    <row r="3"><c r="A3"/></row>
+  </sheetData></worksheet>`,
+  'xl/worksheets/sheet2.xml': '<worksheet><sheetData/></worksheet>',
+})
+assert.deepEqual(extractOfficeContent(xlsx, 'xlsx').sections, [
+  '## Sheet: Budget\n\n| Item | Cost (EUR) |  | Approved |\n| --- | --- | --- | --- |\n| Laptop | 1299.5 |  | TRUE |',
+  '## Sheet: Empty\n\n(empty)',
+])
+
+// PowerPoint: slides in presentation order with tables and speaker notes, but not the notes page number
+const pptx = zip({
+  'ppt/presentation.xml': '<p:presentation><p:sldIdLst><p:sldId id="257" r:id="rId3"/><p:sldId id="256" r:id="rId2"/></p:sldIdLst></p:presentation>',
+  'ppt/_rels/presentation.xml.rels': rels([['rId2', 'slides/slide1.xml'], ['rId3', 'slides/slide2.xml']]),
+  'ppt/slides/slide1.xml': '<p:sld><p:cSld><p:spTree><p:sp><p:txBody><a:p><a:r><a:t>Roadmap</a:t></a:r></a:p><a:p><a:r><a:t>Line one</a:t></a:r><a:br/><a:r><a:t>Line two</a:t></a:r></a:p></p:txBody></p:sp></p:spTree></p:cSld></p:sld>',
+  'ppt/slides/slide2.xml': '<p:sld><p:cSld><p:spTree><p:graphicFrame><a:graphic><a:graphicData><a:tbl><a:tr><a:tc><a:txBody><a:p><a:r><a:t>Goal</a:t></a:r></a:p></a:txBody></a:tc></a:tr><a:tr><a:tc><a:txBody><a:p><a:r><a:t>Grow</a:t></a:r></a:p></a:txBody></a:tc></a:tr></a:tbl></a:graphicData></a:graphic></p:graphicFrame></p:spTree></p:cSld></p:sld>',
+  'ppt/slides/_rels/slide2.xml.rels': rels([['rId1', '../notesSlides/notesSlide1.xml', 'http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesSlide']]),
+  'ppt/notesSlides/notesSlide1.xml': '<p:notes><p:cSld><p:spTree><p:sp><p:nvSpPr><p:nvPr><p:ph type="body"/></p:nvPr></p:nvSpPr><p:txBody><a:p><a:r><a:t>Mention hiring</a:t></a:r></a:p></p:txBody></p:sp><p:sp><p:nvSpPr><p:nvPr><p:ph type="sldNum"/></p:nvPr></p:nvSpPr><p:txBody><a:p><a:fld><a:t>2</a:t></a:fld></a:p></p:txBody></p:sp></p:spTree></p:cSld></p:notes>',
+})
+assert.deepEqual(extractOfficeContent(pptx, 'pptx').sections, [
+  '## Slide 1\n\n| Goal |\n| --- |\n| Grow |\n\nNotes:\nMention hiring',
+  '## Slide 2\n\nRoadmap\n\nLine one\nLine two',
+])
+
+const dir = mkdtempSync(join(tmpdir(), 'office-extraction-'))
+try {
+  const runtime = { attachments: new AttachmentStore(join(dir, 'attachments')) }
+
+  await assert.rejects(extractOfficeDocument(runtime, { bytes: new Uint8Array(), fileName: 'empty.docx' }), OfficeInputError)
+  await assert.rejects(extractOfficeDocument(runtime, { bytes: Buffer.from('plain'), fileName: 'notes.txt' }), /Unsupported Office document type/)
+  await assert.rejects(extractOfficeDocument(runtime, { bytes: Buffer.from('\xd0\xcf\x11\xe0', 'latin1'), fileName: 'legacy.docx' }), /Not an Office Open XML file/)
+  await assert.rejects(extractOfficeDocument(runtime, { bytes: zip({ 'other.xml': '<a/>' }), fileName: 'broken.pptx' }), /Could not read broken.pptx/)
+
+  const extraction = await extractOfficeDocument(runtime, { bytes: xlsx, fileName: 'budget.xlsx' })
+  assert.equal(extraction.pages, 2)
+  assert.equal(extraction.mediaType, 'application/vnd.openxmlformats-officedocument.spreadsheetml.sheet')
+  assert.match(extraction.chunks[0], /\| Laptop \| 1299\.5 \|/)
+  assert.deepEqual(await runtime.attachments.getExtraction(extraction.hash), extraction)
+
+  console.log('office extraction tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
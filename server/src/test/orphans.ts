@@ -0,0 +1,65 @@
+import assert from 'node:assert/strict'
+import { existsSync, mkdirSync, mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { AttachmentStore } from '../lib/attachment-store.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { cleanupOrphans } from '../services/orphans.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'orphans-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'orphans.db')))
+  const attachments = new AttachmentStore(join(dir, 'attachments'))
+  const runtime = {
+    repositories: repos,
+    sessionFilesRoot: join(dir, 'sessions'),
+    projectsDir: join(dir, 'projects'),
+    attachments,
+    debugTraces: { dir: join(dir, 'traces'), removeSession: async (id: string) => rmSync(join(dir, 'traces', id), { recursive: true }) },
+  } as unknown as RuntimeContext
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Kept' })
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Plan', config })
+  await repos.items.create({ agentId: agent.id, type: 'function_call', callId: 'c1', name: 'web.fetch', arguments: '{}', turnNumber: 1 })
+  await repos.toolOutputs.save({ agentId: agent.id, callId: 'c1', toolName: 'web.fetch', data: {} })
+  await repos.toolOutputs.save({ agentId: agent.id, callId: 'gone', toolName: 'web.fetch', data: {} })
+  const empty = await repos.agents.create({ sessionId: session.id, task: 'Never ran', config })
+  await repos.agents.update(empty.id, { status: 'failed' })
+  await repos.agents.create({ sessionId: session.id, task: 'Queued', config })
+  await repos.usage.record({ userId: user.id, sessionId: session.id, provider: 'openai', model: 'gpt-4o', inputTokens: 1, outputTokens: 1, costUsd: 0 })
+  await repos.usage.record({ userId: user.id, sessionId: '0b7c6a1e-0000-4000-8000-000000000000', provider: 'openai', model: 'gpt-4o', inputTokens: 1, outputTokens: 1, costUsd: 0 })
+
+  const strayDir = '0b7c6a1e-0000-4000-8000-000000000000'
+  mkdirSync(join(dir, 'sessions', session.id), { recursive: true })
+  mkdirSync(join(dir, 'sessions', strayDir), { recursive: true })
+  mkdirSync(join(dir, 'sessions', 'shared'), { recursive: true })
+  mkdirSync(join(dir, 'traces', strayDir), { recursive: true })
+  await attachments.put(Buffer.from('unreferenced'))
+
+  const later = Date.now() + 2 * 60 * 60 * 1000
+  const report = await cleanupOrphans(runtime, { now: later })
+  assert.deepEqual(report.database, { toolOutputs: 1, agents: 1, usageRecords: 1, messageRevisions: 0 })
+  assert.deepEqual(report.sessionDirs, [strayDir], 'only session-shaped directories are considered')
+  assert.deepEqual(report.traceDirs, [strayDir])
+  assert.equal(report.attachments.count, 1)
+  assert.equal(report.removed, false)
+  assert.equal((await cleanupOrphans(runtime)).attachments.count, 0, 'fresh payloads are within the grace period')
+
+  const removed = await cleanupOrphans(runtime, { remove: true, now: later })
+  assert.deepEqual(removed.database, report.database)
+  assert.equal(existsSync(join(dir, 'sessions', strayDir)), false)
+  assert.equal(existsSync(join(dir, 'sessions', session.id)), true)
+  assert.equal(existsSync(join(dir, 'traces', strayDir)), false)
+  assert.equal((await attachments.list()).length, 0)
+  assert.equal(await repos.agents.getById(empty.id), null)
+
+  const after = await cleanupOrphans(runtime, { now: later })
+  assert.deepEqual(after.database, { toolOutputs: 0, agents: 0, usageRecords: 0, messageRevisions: 0 })
+
+  console.log('orphan cleanup tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,126 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { deflateSync } from 'node:zlib'
+import type { Item } from '../domain/types.js'
+import { AttachmentStore } from '../lib/attachment-store.js'
+import { chunkText, extractPdfContent } from '../lib/pdf-text.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { buildControllerMessages } from '../orchestrator/prompts.js'
+import type { LLMRequest } from '../providers/types.js'
+import { extractPdf, PdfInputError, resolveDocumentBlocks } from '../services/pdf-extraction.js'
+
+type PdfPart = string | { dict: string; stream: Buffer }
+
+function pdf(objects: PdfPart[]): Buffer {
+  const parts: Buffer[] = [Buffer.from('%PDF-1.4\n')]
+  objects.forEach((object, index) => {
+    parts.push(Buffer.from(`${index + 1} 0 obj\n`))
+    if (typeof object === 'string') {
+      parts.push(Buffer.from(object))
+    } else {
+      parts.push(Buffer.from(`<< ${object.dict} /Length ${object.stream.length} >>\nstream\n`), object.stream, Buffer.from('\nendstream'))
+    }
+    parts.push(Buffer.from('\nendobj\n'))
+  })
+  parts.push(Buffer.from('trailer\n<< /Root 1 0 R >>\n%%EOF\n'))
+  return Buffer.concat(parts)
+}
+
+const textPdf = pdf([
+  '<< /Type /Catalog /Pages 2 0 R >>',
+  '<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /Resources << /Font << /F1 5 0 R /F2 7 0 R >> >> >>',
+  '<< /Type /Page /Parent 2 0 R /Contents 6 0 R >>',
+  '<< /Type /Page /Parent 2 0 R /Contents [8 0 R] >>',
+  '<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>',
+  { dict: '/Filter /FlateDecode', stream: deflateSync('BT /F1 12 Tf 72 720 Td (Hello, \\(PDF\\)!) Tj 0 -14 Td [(Second) -250 (line)] TJ ET') },
+  '<< /Type /Font /Subtype /Type0 /ToUnicode 9 0 R >>',
+  { dict: '', stream: Buffer.from('BT /F2 12 Tf <00480069> Tj ET') },
+  { dict: '', stream: Buffer.from('1 begincodespacerange <0000> <FFFF> endcodespacerange 1 beginbfchar <0048> <0048> endbfchar 1 beginbfrange <0069> <006A> <0069> endbfrange') },
+])
+
+const jpeg = Buffer.from([0xff, 0xd8, 0xff, 0xe0, 0x00, 0x10, 0xff, 0xd9])
+const scannedPdf = pdf([
+  '<< /Type /Catalog /Pages 2 0 R >>',
+  '<< /Type /Pages /Kids [3 0 R] /Count 1 >>',
+  '<< /Type /Page /Parent 2 0 R /Contents 4 0 R /Resources << /XObject << /Im0 5 0 R >> >> >>',
+  { dict: '', stream: Buffer.from('q 612 0 0 792 0 0 cm /Im0 Do Q') },
+  { dict: '/Type /XObject /Subtype /Image /Width 1 /Height 1 /Filter /DCTDecode', stream: jpeg },
+])
+
+// Text layers, page order, escapes, TJ spacing and ToUnicode mapping
+assert.deepEqual(extractPdfContent(textPdf), { pages: ['Hello, (PDF)!\nSecond line', 'Hi'], images: [] })
+assert.deepEqual(extractPdfContent(scannedPdf), { pages: [''], images: [jpeg] })
+
+// A stream that inflates past the cap is skipped rather than expanded in full
+const bombPdf = pdf([
+  '<< /Type /Catalog /Pages 2 0 R >>',
+  '<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /Resources << /Font << /F1 7 0 R >> >> >>',
+  '<< /Type /Page /Parent 2 0 R /Contents 5 0 R >>',
+  '<< /Type /Page /Parent 2 0 R /Contents 6 0 R >>',
+  { dict: '/Filter /FlateDecode', stream: deflateSync(Buffer.alloc(64 * 1024 * 1024, 0x20)) },
+  { dict: '/Filter /FlateDecode', stream: deflateSync('BT /F1 12 Tf (Still read) Tj ET') },
+  '<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>',
+])
+assert.ok(bombPdf.length < 1024 * 1024)
+assert.deepEqual(extractPdfContent(bombPdf), { pages: ['', 'Still read'], images: [] })
+
+assert.deepEqual(chunkText(`${'a'.repeat(10)}\n\n${'b'.repeat(10)}`, 15), ['a'.repeat(10), 'b'.repeat(10)])
+assert.deepEqual(chunkText('x'.repeat(25), 10), ['x'.repeat(10), 'x'.repeat(10), 'x'.repeat(5)])
+
+const dir = mkdtempSync(join(tmpdir(), 'pdf-extraction-'))
+try {
+  const ocrRequests: LLMRequest[] = []
+  const runtime = (pdfOcrModel?: string) => ({
+    attachments: new AttachmentStore(join(dir, 'attachments')),
+    config: { pdfOcrModel },
+    providers: {
+      resolve: () => ({
+        async generate(request: LLMRequest) {
+          ocrRequests.push(request)
+          return { content: 'Scanned text', usage: { input_tokens: 1, output_tokens: 1 }, finish_reason: 'stop' }
+        },
+      }),
+    },
+    shutdownController: new AbortController(),
+  }) as unknown as RuntimeContext
+
+  const extracted = await extractPdf(runtime(), { bytes: textPdf, fileName: 'notes.pdf' })
+  assert.equal(extracted.source, 'text')
+  assert.equal(extracted.pages, 2)
+  assert.deepEqual(extracted.chunks, ['Hello, (PDF)!\nSecond line\n\nHi'])
+  assert.equal(await runtime().attachments.get(extracted.hash).then((data) => data?.length), textPdf.length)
+
+  await assert.rejects(extractPdf(runtime(), { bytes: Buffer.from('plain text'), fileName: 'notes.txt' }), PdfInputError)
+  await assert.rejects(extractPdf(runtime(), { bytes: scannedPdf }), /PDF_OCR_MODEL/)
+
+  // Scanned pages go through the OCR model once; the stored chunks are reused afterwards
+  const scanned = await extractPdf(runtime('openai:gpt-4o-mini'), { bytes: scannedPdf })
+  assert.deepEqual([scanned.source, scanned.chunks], ['ocr', ['Scanned text']])
+  assert.equal(ocrRequests.length, 1)
+  assert.equal(ocrRequests[0].model, 'gpt-4o-mini')
+  const [, image] = ocrRequests[0].messages[0].content as Array<{ data?: string }>
+  assert.equal(image.data, jpeg.toString('base64'))
+  await extractPdf(runtime('openai:gpt-4o-mini'), { bytes: scannedPdf })
+  assert.equal(ocrRequests.length, 1)
+
+  // User messages reference the upload by hash; the chunks are filled in from the store
+  const blocks = await resolveDocumentBlocks(runtime(), [{ type: 'document', hash: extracted.hash, name: 'notes.pdf' }])
+  assert.deepEqual(blocks?.[0].chunks, extracted.chunks)
+  await assert.rejects(resolveDocumentBlocks(runtime(), [{ type: 'document', hash: 'f'.repeat(64) }]), /Unknown document/)
+
+  // The extracted text is inlined into the message, and the PDF itself is never loaded
+  const item = {
+    id: 'i1', agentId: 'a1', sequence: 1, type: 'message', role: 'user', content: 'Summarise this', contentBlocks: blocks,
+    callId: null, name: null, arguments: null, output: null, isError: null, saveOutput: null, turnNumber: 0, durationMs: null, createdAt: 0,
+  } as Item
+  const [hydrated] = await runtime().attachments.hydrate([item])
+  assert.equal(hydrated.contentBlocks?.[0].data, undefined)
+  const messages = buildControllerMessages('system', '', [hydrated], { useNativeFunctionCalling: true, agentTask: '' })
+  assert.equal(messages[1].content, 'Summarise this\n\n[Attached file: notes.pdf]\n```\nHello, (PDF)!\nSecond line\n\nHi\n```\n')
+
+  console.log('pdf extraction tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,64 @@
+import assert from 'node:assert/strict'
+import fs from 'fs/promises'
+import os from 'os'
+import path from 'path'
+import { AppleNotes, ObsidianVault, markdownToNotesHtml } from '../services/personal-notes.js'
+import { registerPersonalNoteTools } from '../tools/personal-notes.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+
+// Obsidian: search ranks title hits first, skips .obsidian and stays inside the vault
+const vault = await fs.mkdtemp(path.join(os.tmpdir(), 'obsidian-vault-test-'))
+await fs.mkdir(path.join(vault, 'Meetings'), { recursive: true })
+await fs.mkdir(path.join(vault, '.obsidian'), { recursive: true })
+await fs.writeFile(path.join(vault, 'Meetings', 'Budget review.md'), '# Budget review\n\nAgreed to cut the travel budget.\n')
+await fs.writeFile(path.join(vault, 'Travel.md'), 'Trip ideas. Budget is tight this year.\n')
+await fs.writeFile(path.join(vault, '.obsidian', 'budget.md'), 'plugin settings budget')
+await fs.writeFile(path.join(vault, 'Recipes.md'), 'Pasta\n')
+
+const registry = new ToolRegistryImpl()
+registerPersonalNoteTools(registry, new ObsidianVault(vault))
+const found = await registry.execute('personal_notes.search', { query: 'budget' }, ctx)
+assert.deepEqual((found.output as { notes: Array<{ id: string; folder: string | null }> }).notes.map((note) => [note.id, note.folder]), [
+  ['Meetings/Budget review.md', 'Meetings'],
+  ['Travel.md', null],
+])
+const inFolder = await registry.execute('personal_notes.search', { query: 'budget', folder: 'Meetings' }, ctx)
+assert.equal((inFolder.output as { notes: unknown[] }).notes.length, 1)
+assert.match(String((await registry.execute('personal_notes.search', { query: 'budget', folder: '../' }, ctx)).error), /outside the vault/)
+
+const read = await registry.execute('personal_notes.read', { id: 'Meetings/Budget review' }, ctx)
+assert.equal((read.output as { content: string }).content, '# Budget review\n\nAgreed to cut the travel budget.\n')
+assert.match(String((await registry.execute('personal_notes.read', { id: '../../etc/passwd' }, ctx)).error), /outside the vault/)
+assert.match(String((await registry.execute('personal_notes.read', { id: 'Missing' }, ctx)).error), /Note not found/)
+
+assert.equal(registry.getMetadata('personal_notes.create')?.category, 'mutating')
+assert.equal(registry.getPreview('personal_notes.create', { title: 'Standup', body: 'x', folder: 'Meetings' }, ctx)?.summary, 'Create note "Standup" in Obsidian vault / Meetings')
+const created = await registry.execute('personal_notes.create', { title: 'Standup: 10/17', body: '- Ship it', folder: 'Meetings/2026' }, ctx)
+assert.equal((created.output as { note: { id: string } }).note.id, 'Meetings/2026/Standup 10 17.md')
+assert.equal(await fs.readFile(path.join(vault, 'Meetings', '2026', 'Standup 10 17.md'), 'utf-8'), '- Ship it\n')
+// Existing notes are never overwritten
+const again = await registry.execute('personal_notes.create', { title: 'Standup: 10/17', body: 'Other', folder: 'Meetings/2026' }, ctx)
+assert.match(String(again.error), /already exists/)
+assert.equal(await fs.readFile(path.join(vault, 'Meetings', '2026', 'Standup 10 17.md'), 'utf-8'), '- Ship it\n')
+
+// Apple Notes: input goes through argv and markdown becomes Notes HTML
+assert.equal(markdownToNotesHtml('## Plan\n- a & b\n- c\n\nDone'), '<h2>Plan</h2><ul><li>a &amp; b</li><li>c</li></ul><div><br></div><div>Done</div>')
+const scripts: Array<{ script: string; args: string[] }> = []
+const apple = new ToolRegistryImpl()
+registerPersonalNoteTools(apple, new AppleNotes(async (script, args) => {
+  scripts.push({ script, args })
+  const note = { id: 'x-coredata://1', title: 'Groceries', folder: 'Notes', text: 'Groceries\nMilk, eggs', modifiedAt: '2026-10-17T08:00:00.000Z' }
+  if (script.includes('_contains')) return JSON.stringify([note])
+  return JSON.stringify(note)
+}))
+const searched = await apple.execute('personal_notes.search', { query: 'milk', limit: 3 }, ctx)
+assert.equal((searched.output as { notes: Array<{ snippet: string }> }).notes[0].snippet, 'Groceries Milk, eggs')
+assert.deepEqual(scripts[0].args, ['milk', '', '3'])
+await apple.execute('personal_notes.create', { title: 'Call <Mom>', body: 'Sunday' }, ctx)
+assert.deepEqual(JSON.parse(scripts[1].args[0]), { html: '<h1>Call &lt;Mom&gt;</h1><div>Sunday</div>' })
+assert.doesNotMatch(scripts[1].script, /Mom/)
+
+await fs.rm(vault, { recursive: true, force: true })
+console.log('Personal notes tool tests passed')
@@ -0,0 +1,186 @@
+import assert from 'node:assert/strict'
+import { promises as fs } from 'node:fs'
+import os from 'node:os'
+import path from 'node:path'
+import type { ApiKeyRecord, SystemPromptRecord } from '../repositories/types.js'
+import { parsePluginManifest, PluginError } from '../services/plugin-manifest.js'
+import { PluginManager, type PluginManagerDeps } from '../services/plugins.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+import type { ToolContext } from '../tools/types.js'
+
+const root = await fs.mkdtemp(path.join(os.tmpdir(), 'plugins-'))
+const pluginsDir = path.join(root, 'installed')
+const ctx = { agent_id: 'a1', session_id: 's1', signal: new AbortController().signal } as ToolContext
+
+const weather = {
+  name: 'weather-plus',
+  version: '1.2.0',
+  description: 'Marine forecasts',
+  backend: { type: 'http', url: 'https://api.example.com/tools', headers: { authorization: 'Bearer ${secrets.API_KEY}' } },
+  tools: [{
+    name: 'tides',
+    description: 'Tide times for a harbour',
+    parameters: { type: 'object', properties: { harbour: { type: 'string' } }, required: ['harbour'] },
+    category: 'read_only',
+  }],
+  prompts: [{ name: 'sailor', file: 'prompts/sailor.md' }],
+  secrets: [{ name: 'API_KEY', description: 'Key from api.example.com' }],
+}
+
+// Manifests are checked whole, and every field problem is reported at once
+assert.equal(parsePluginManifest(weather).backend.type, 'http')
+const invalid = (manifest: unknown) => {
+  try {
+    parsePluginManifest(manifest)
+  } catch (err) {
+    assert.ok(err instanceof PluginError)
+    return err.message
+  }
+  assert.fail('manifest was accepted')
+}
+const problems = invalid({ ...weather, name: 'Weather Plus', version: '1.2', prompts: [{ name: 'x', file: '../../etc/passwd' }] })
+assert.match(problems, /name must be 1-40 lowercase/)
+assert.match(problems, /version must be a semantic version/)
+assert.match(problems, /prompts\.0\.file must be a path inside the plugin folder/)
+assert.match(invalid({ ...weather, secrets: [] }), /backend refers to undeclared secret API_KEY/)
+assert.match(invalid({ ...weather, tools: [{ ...weather.tools[0], parameters: { type: 'object', required: ['harbour'] } }] }), /lists undefined properties: harbour/)
+assert.match(invalid({ ...weather, backend: { type: 'mcp', transport: 'stdio', command: 'npx' } }), /an mcp backend lists its own tools/)
+assert.match(invalid({ ...weather, tools: [] }), /an http backend must declare at least one tool/)
+
+// In-memory stores behind the manager
+const keys = new Map<string, string>()
+const prompts: SystemPromptRecord[] = []
+const registered: string[] = []
+const requests: Array<{ url: string; init: RequestInit }> = []
+const tools = new ToolRegistryImpl()
+const deps: PluginManagerDeps = {
+  tools,
+  apiKeys: {
+    getByProvider: async (provider) => (keys.has(provider) ? { encryptedKey: keys.get(provider) } as ApiKeyRecord : null),
+    upsert: async (provider, key) => { keys.set(provider, key); return { encryptedKey: key } as ApiKeyRecord },
+    delete: async (provider) => { keys.delete(provider) },
+  },
+  systemPrompts: {
+    list: async () => [...prompts],
+    create: async (input) => { const record = { id: `p${prompts.length}`, isDefault: false, createdAt: 0, updatedAt: 0, ...input }; prompts.push(record); return record },
+    getById: async (id) => prompts.find((prompt) => prompt.id === id) ?? null,
+    update: async (id, input) => Object.assign(prompts.find((prompt) => prompt.id === id)!, input),
+    delete: async (id) => { prompts.splice(prompts.findIndex((prompt) => prompt.id === id), 1) },
+  },
+  mcps: { listServers: async () => [], createServer: async () => { throw new Error('not needed') }, updateServer: async () => { throw new Error('not needed') }, deleteServer: async () => false },
+  redactor: { register: (value: string) => { registered.push(value) } },
+  fetchImpl: (async (url: string, init: RequestInit) => {
+    if (url.startsWith('https://plugins.example/')) return serve(url)
+    requests.push({ url, init })
+    return new Response(JSON.stringify({ output: { high: '06:12' } }), { headers: { 'content-type': 'application/json' } })
+  }) as typeof fetch,
+}
+
+// Installing from a folder copies it in; the tools wait for the declared secret
+const source = path.join(root, 'weather-plus')
+await fs.mkdir(path.join(source, 'prompts'), { recursive: true })
+await fs.writeFile(path.join(source, 'plugin.json'), JSON.stringify(weather))
+await fs.writeFile(path.join(source, 'prompts', 'sailor.md'), 'Answer like a sailor.')
+const manager = new PluginManager(pluginsDir, deps)
+const installed = await manager.install('u1', source)
+assert.equal(installed.status, 'needs_secrets')
+assert.deepEqual(installed.secrets, [{ name: 'API_KEY', description: 'Key from api.example.com', set: false }])
+assert.equal(tools.getMetadata('weather-plus.tides'), undefined)
+assert.deepEqual(prompts.map((prompt) => [prompt.name, prompt.content]), [['weather-plus/sailor', 'Answer like a sailor.']])
+await assert.rejects(manager.setSecrets('u1', 'weather-plus', { OTHER: 'x' }), /declares no secret OTHER/)
+
+// Saving the secret registers the tools, which call the backend with it filled in
+const active = await manager.setSecrets('u1', 'weather-plus', { API_KEY: 'sk-weather-123' })
+assert.equal(active.status, 'active')
+assert.deepEqual(active.tools, ['weather-plus.tides'])
+assert.deepEqual(registered, ['sk-weather-123'])
+assert.equal(tools.getMetadata('weather-plus.tides')?.requires_approval, false)
+assert.deepEqual(await tools.execute('weather-plus.tides', { harbour: 'Brest' }, ctx), { ok: true, output: { high: '06:12' } })
+assert.equal(requests[0].url, 'https://api.example.com/tools')
+assert.equal((requests[0].init.headers as Record<string, string>).authorization, 'Bearer sk-weather-123')
+assert.deepEqual(JSON.parse(requests[0].init.body as string), { tool: 'tides', args: { harbour: 'Brest' } })
+
+// A plugin named after a built-in area is refused
+tools.register({ metadata: { name: 'mail.send', description: '', parameters: { type: 'object' }, requires_approval: true }, handle: async () => ({ ok: true }) })
+await fs.writeFile(path.join(source, 'plugin.json'), JSON.stringify({ ...weather, name: 'mail' }))
+await assert.rejects(manager.install('u1', source), /clashes with the built-in tool mail.send/)
+
+// Installed plugins come back on restart
+tools.unregister('weather-plus.tides')
+const restarted = new PluginManager(pluginsDir, deps)
+await restarted.loadAll()
+assert.equal(restarted.get('weather-plus')?.status, 'active')
+assert.ok(tools.getMetadata('weather-plus.tides'))
+
+// WebAssembly plugins install from a URL and run in a worker thread
+const echo = {
+  name: 'echo',
+  version: '0.1.0',
+  backend: { type: 'wasm', module: 'echo.wasm' },
+  tools: [{ name: 'ping', description: 'Answers pong', parameters: { type: 'object', properties: {} } }],
+}
+const spin = { ...echo, name: 'spin', backend: { type: 'wasm', module: 'spin.wasm' } }
+const files: Record<string, Uint8Array | string> = {
+  'https://plugins.example/echo/plugin.json': JSON.stringify(echo),
+  'https://plugins.example/echo/echo.wasm': wasmAnswering('{"output":"pong"}'),
+  'https://plugins.example/spin/plugin.json': JSON.stringify(spin),
+  'https://plugins.example/spin/spin.wasm': wasmLooping(),
+}
+function serve(url: string): Response {
+  return url in files ? new Response(files[url]) : new Response('missing', { status: 404 })
+}
+const wasm = await restarted.install('u1', 'https://plugins.example/echo/')
+assert.equal(wasm.status, 'active')
+assert.equal(tools.getMetadata('echo.ping')?.requires_approval, true)
+assert.deepEqual(await tools.execute('echo.ping', {}, ctx), { ok: true, output: 'pong' })
+await assert.rejects(restarted.install('u1', 'https://plugins.example/nothing'), /Could not read plugin.json/)
+
+// A call that never returns is stopped when the tool is aborted, and the server keeps answering
+assert.equal((await restarted.install('u1', 'https://plugins.example/spin/')).status, 'active')
+const ticks = setInterval(() => undefined, 10)
+const spun = await tools.execute('spin.ping', {}, { ...ctx, signal: AbortSignal.timeout(200) })
+clearInterval(ticks)
+assert.equal(spun.ok, false)
+assert.deepEqual(await tools.execute('echo.ping', {}, ctx), { ok: true, output: 'pong' })
+assert.equal(await restarted.uninstall('u1', 'spin'), true)
+
+// Uninstalling takes everything it brought
+assert.equal(await restarted.uninstall('u1', 'weather-plus'), true)
+assert.equal(tools.getMetadata('weather-plus.tides'), undefined)
+assert.equal(prompts.length, 0)
+assert.equal(keys.size, 0)
+assert.deepEqual(await fs.readdir(pluginsDir), ['echo'])
+assert.equal(await restarted.uninstall('u1', 'weather-plus'), false)
+
+await fs.rm(root, { recursive: true, force: true })
+
+/** A module whose `call` always answers `answer` (under 64 bytes), stored at address 0. */
+function wasmAnswering(answer: string): Uint8Array {
+  const text = [...Buffer.from(answer)]
+  // call returns pointer 0 and the answer's length
+  return wasmModule([0x42, text.length, 0x0b], [0x41, 0, 0x0b, text.length, ...text])
+}
+
+/** A module whose `call` loops forever. */
+function wasmLooping(): Uint8Array {
+  return wasmModule([0x03, 0x40, 0x0c, 0x00, 0x0b, 0x42, 0x00, 0x0b], [0x41, 0, 0x0b, 0])
+}
+
+/** `callCode` is the body of `call`; `data` is one active data segment. */
+function wasmModule(callCode: number[], data: number[]): Uint8Array {
+  const section = (id: number, bytes: number[]) => [id, bytes.length, ...bytes]
+  const name = (value: string) => [value.length, ...Buffer.from(value)]
+  return new Uint8Array([
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+    // Types: alloc (i32) -> i32, call (i32 x4) -> i64
+    ...section(1, [2, 0x60, 1, 0x7f, 1, 0x7f, 0x60, 4, 0x7f, 0x7f, 0x7f, 0x7f, 1, 0x7e]),
+    ...section(3, [2, 0, 1]),
+    ...section(5, [1, 0, 1]),
+    ...section(7, [3, ...name('memory'), 2, 0, ...name('alloc'), 0, 0, ...name('call'), 0, 1]),
+    // alloc returns 1024, clear of the data
+    ...section(10, [2, 5, 0, 0x41, 0x80, 0x08, 0x0b, callCode.length + 1, 0, ...callCode]),
+    ...section(11, [1, 0, ...data]),
+  ])
+}
+
+console.log('plugin tests passed')
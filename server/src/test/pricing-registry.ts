@@ -0,0 +1,115 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { BACKEND_PRICING_PREFERENCE_KEY, BUILTIN_PRICES, parsePriceCatalog, PriceCatalogError, PricingRegistry } from '../usage/pricing.js'
+
+// Catalog parsing: per-million entries, LiteLLM per-token entries, bare keys win
+const litellm = parsePriceCatalog({
+  'openai/gpt-4o': { input_cost_per_token: 0.000009, output_cost_per_token: 0.00003 },
+  'gpt-4o': { input_cost_per_token: 0.0000025, output_cost_per_token: 0.00001 },
+  'azure/gpt-4o': { input_cost_per_token: 0.000005, output_cost_per_token: 0.00002 },
+  'text-embedding-3-small': { input_cost_per_token: 0.00000002 },
+  sample_spec: 'not a model',
+})
+assert.deepEqual(litellm.get('gpt-4o'), { input: 2.5, output: 10 })
+assert.equal(litellm.has('text-embedding-3-small'), false, 'entries without both prices are skipped')
+assert.equal(litellm.size, 1)
+assert.deepEqual(
+  parsePriceCatalog({
+    'claude-sonnet-4-5': {
+      input_cost_per_token: 0.000003,
+      output_cost_per_token: 0.000015,
+      cache_read_input_token_cost: 0.0000003,
+      cache_creation_input_token_cost: 0.00000375,
+    },
+  }).get('claude-sonnet-4-5'),
+  { input: 3, output: 15, cacheRead: 0.3, cacheWrite: 3.75 },
+)
+assert.deepEqual(parsePriceCatalog({ prices: { 'new-model-20250101': { input: 1, output: 2 } } }).get('new-model'), { input: 1, output: 2 })
+assert.equal(parsePriceCatalog({ bad: { input: -1, output: 2 } }).size, 0)
+
+const dir = mkdtempSync(join(tmpdir(), 'pricing-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'pricing.db')))
+  let catalog: unknown = {}
+  const fakeFetch = (async () => new Response(JSON.stringify(catalog))) as typeof fetch
+  const pricing = new PricingRegistry(repos.modelPrices, 'https://example.test/catalog.json', fakeFetch)
+
+  // Before loading, lookups fall back to the bundled table
+  assert.deepEqual(pricing.lookup('openai', 'gpt-4o'), BUILTIN_PRICES['gpt-4o'])
+
+  await pricing.load()
+  assert.equal(pricing.list().length, Object.keys(BUILTIN_PRICES).length)
+  assert.ok(pricing.list().every((record) => record.source === 'bundled'))
+  assert.deepEqual(pricing.lookup('anthropic', 'anthropic:claude-sonnet-4-20250514'), { input: 3, output: 15, cacheRead: 0.3, cacheWrite: 3.75 })
+  assert.deepEqual(pricing.lookup('ollama', 'llama3'), { input: 0, output: 0 })
+
+  // Hand edits stick and survive a reload
+  const edited = await pricing.set('openai/gpt-4o', { input: 2, output: 8 })
+  assert.equal(edited.modelId, 'gpt-4o')
+  assert.equal(edited.source, 'user')
+  await assert.rejects(pricing.set('gpt-4o', { input: -1, output: 8 }), /non-negative/)
+  const reloaded = new PricingRegistry(repos.modelPrices)
+  await reloaded.load()
+  assert.deepEqual(reloaded.lookup('openai', 'gpt-4o'), { input: 2, output: 8 }, 'seeding does not overwrite edits')
+
+  // A refresh updates and adds models but leaves hand edits alone
+  catalog = {
+    'gpt-4o': { input_cost_per_token: 0.000003, output_cost_per_token: 0.000012 },
+    'gpt-4o-mini': { input_cost_per_token: 0.0000002, output_cost_per_token: 0.0000008 },
+    'gpt-5': { input_cost_per_token: 0.00000125, output_cost_per_token: 0.00001, cache_read_input_token_cost: 0.000000125 },
+    'brand-new-model': { input_cost_per_token: 0.000001, output_cost_per_token: 0.000002 },
+  }
+  const result = await pricing.refresh()
+  assert.deepEqual(
+    { updated: result.updated, added: result.added, skippedUserEdits: result.skippedUserEdits },
+    { updated: 1, added: 1, skippedUserEdits: 1 },
+  )
+  assert.deepEqual(pricing.lookup('openai', 'gpt-4o'), { input: 2, output: 8 })
+  assert.deepEqual(pricing.lookup('openai', 'gpt-4o-mini'), { input: 0.2, output: 0.8 })
+  assert.deepEqual(pricing.lookup('openai', 'brand-new-model'), { input: 1, output: 2 })
+
+  // Stale bundled rows catch up with the bundled table on load; edited ones do not
+  await repos.modelPrices.upsert({ modelId: 'claude-haiku-4-5', input: 1, output: 5, source: 'bundled' })
+  await reloaded.load()
+  assert.deepEqual(reloaded.lookup('anthropic', 'claude-haiku-4-5'), BUILTIN_PRICES['claude-haiku-4-5'])
+
+  // Reset returns bundled models to the bundled price and drops the rest
+  assert.equal((await pricing.reset('gpt-4o'))?.source, 'bundled')
+  assert.deepEqual(pricing.lookup('openai', 'gpt-4o'), BUILTIN_PRICES['gpt-4o'])
+  assert.equal(await pricing.reset('brand-new-model'), null)
+  assert.equal(pricing.lookup('openai', 'brand-new-model'), null)
+  assert.equal(await pricing.reset('never-priced'), undefined)
+
+  // Backend prices cover every model of a provider, including local and renamed ones
+  const backends = new PricingRegistry(repos.modelPrices, undefined, fetch, repos.preferences)
+  await backends.load()
+  assert.deepEqual(await backends.setBackend(' Ollama ', { input: 0.2, output: 0.4 }), { input: 0.2, output: 0.4 })
+  await backends.setBackend('acme', { input: 1, output: 3 })
+  assert.deepEqual(backends.lookup('ollama', 'llama3'), { input: 0.2, output: 0.4 })
+  assert.deepEqual(backends.lookup('acme', 'gpt-4o'), { input: 1, output: 3 }, 'a backend price wins over the model name')
+  assert.deepEqual(backends.lookup('openai', 'gpt-4o'), BUILTIN_PRICES['gpt-4o'])
+  await assert.rejects(backends.setBackend('acme', { input: 1 }), /output/)
+
+  const restarted = new PricingRegistry(repos.modelPrices, undefined, fetch, repos.preferences)
+  await restarted.load()
+  assert.deepEqual(restarted.backends(), { ollama: { input: 0.2, output: 0.4 }, acme: { input: 1, output: 3 } })
+  assert.equal(await restarted.resetBackend('ollama'), true)
+  assert.equal(await restarted.resetBackend('ollama'), false)
+  assert.deepEqual(restarted.lookup('ollama', 'llama3'), { input: 0, output: 0 })
+  assert.deepEqual(JSON.parse((await repos.preferences.get(BACKEND_PRICING_PREFERENCE_KEY))!), { acme: { input: 1, output: 3 } })
+  await assert.rejects(pricing.setBackend('acme', { input: 1, output: 3 }), /preference store/)
+
+  // Unreachable or empty catalogs are reported, not applied
+  const failing = new PricingRegistry(repos.modelPrices, 'https://example.test/down', (async () => new Response('nope', { status: 503 })) as typeof fetch)
+  await assert.rejects(failing.refresh(), PriceCatalogError)
+  catalog = { nothing: 'here' }
+  await assert.rejects(pricing.refresh(), /no usable entries/)
+  await assert.rejects(new PricingRegistry(repos.modelPrices).refresh(), /No price catalog URL/)
+
+  console.log('pricing registry tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,101 @@
+import assert from 'node:assert/strict'
+import {
+  isPrivacyMode,
+  PII_MAPPING_PREFIX,
+  PiiMapping,
+  PRIVACY_MODE_PREFERENCE_KEY,
+  PrivacyVault,
+  reidentifyResponse,
+  setPrivacyMode,
+  StreamReidentifier,
+} from '../orchestrator/privacy.js'
+import type { LLMRequest } from '../providers/types.js'
+
+const stored = new Map<string, string>()
+const preferences = {
+  get: async (key: string) => stored.get(key) ?? null,
+  set: async (key: string, value: string) => { stored.set(key, value) },
+  delete: async (key: string) => { stored.delete(key) },
+}
+
+// Off unless the default or the conversation turns it on; the conversation wins
+assert.equal(await isPrivacyMode(preferences, 's1'), false)
+await preferences.set(PRIVACY_MODE_PREFERENCE_KEY, 'true')
+assert.equal(await isPrivacyMode(preferences, 's1'), true)
+await setPrivacyMode(preferences, 's1', false)
+assert.equal(await isPrivacyMode(preferences, 's1'), false)
+await setPrivacyMode(preferences, 's1', null)
+assert.equal(await isPrivacyMode(preferences, 's1'), true)
+
+// Detection: addresses, phone numbers and names next to them, but not dates, IPs or ids
+const mapping = new PiiMapping()
+mapping.learn([
+  'From: Jane Doe <jane.doe@example.com>',
+  '"organizer": "Marek Kowalski"',
+  'Call +48 601 234 567 or (555) 123-4567 before 2024-05-01 10:30.',
+  'Host 192.168.100.200, order 12345678901, build 3.14.15.',
+].join('\n'))
+assert.deepEqual(mapping.toJSON(), {
+  '[EMAIL_1]': 'jane.doe@example.com',
+  '[PHONE_1]': '+48 601 234 567',
+  '[PHONE_2]': '(555) 123-4567',
+  '[PERSON_1]': 'Jane Doe',
+  '[PERSON_2]': 'Marek Kowalski',
+})
+assert.equal(
+  mapping.pseudonymize('Reply to Jane Doe at jane.doe@example.com'),
+  'Reply to [PERSON_1] at [EMAIL_1]',
+)
+assert.equal(mapping.reidentify('Sent to [PERSON_1] ([EMAIL_1]), not [EMAIL_9]'), 'Sent to Jane Doe (jane.doe@example.com), not [EMAIL_9]')
+
+// A value keeps its placeholder, and numbering continues from a stored mapping
+const reloaded = new PiiMapping(mapping.toJSON())
+reloaded.learn('cc: bob@example.org, jane.doe@example.com')
+assert.equal(reloaded.pseudonymize('bob@example.org'), '[EMAIL_2]')
+assert.equal(reloaded.pseudonymize('jane.doe@example.com'), '[EMAIL_1]')
+
+// Placeholders split across streamed chunks come out whole
+const stream = new StreamReidentifier(mapping)
+const chunks = ['Mail [EMA', 'IL_1] and [PERS', 'ON_2', '] today [', 'x]'].map((chunk) => stream.push(chunk))
+assert.equal(chunks.join('') + stream.flush(), 'Mail jane.doe@example.com and Marek Kowalski today [x]')
+
+// Requests: learned from tool outputs only, replaced everywhere but the system prompt
+stored.clear()
+await setPrivacyMode(preferences, 's2', true)
+const vault = new PrivacyVault(preferences)
+assert.equal(await vault.forSession('s1'), null)
+const session = (await vault.forSession('s2'))!
+const request: LLMRequest = {
+  model: 'test',
+  messages: [
+    { role: 'system', content: 'The user writes from ann@example.com.' },
+    { role: 'user', content: 'Who mailed me? I am ann@example.com.' },
+    { role: 'assistant', content: '', tool_calls: [{ call_id: 'c1', name: 'mail.search', arguments: { to: 'ann@example.com' } }] },
+    { role: 'tool', tool_call_id: 'c1', content: 'From: Tom Lee <tom@example.com>\nTo: ann@example.com' },
+  ],
+}
+const sent = await vault.pseudonymizeRequest('s2', session, request)
+assert.equal(sent.messages[0].content, 'The user writes from ann@example.com.')
+assert.equal(sent.messages[1].content, 'Who mailed me? I am [EMAIL_2].')
+assert.deepEqual(sent.messages[2].tool_calls?.[0].arguments, { to: '[EMAIL_2]' })
+assert.equal(sent.messages[3].content, 'From: [PERSON_1] <[EMAIL_1]>\nTo: [EMAIL_2]')
+assert.equal(request.messages[1].content, 'Who mailed me? I am ann@example.com.')
+assert.deepEqual(JSON.parse(stored.get(`${PII_MAPPING_PREFIX}s2`)!), session.toJSON())
+
+// Responses: text and tool arguments get the real values back
+const response = reidentifyResponse(session, {
+  content: 'It was [PERSON_1].',
+  tool_calls: [{ call_id: 'c2', name: 'mail.reply', arguments: { to: '[EMAIL_1]' } }],
+  usage: { input_tokens: 1, output_tokens: 1 },
+  finish_reason: 'tool_use',
+})
+assert.equal(response.content, 'It was Tom Lee.')
+assert.deepEqual(response.tool_calls?.[0].arguments, { to: 'tom@example.com' })
+
+// The mapping is reused across runs until the conversation is purged
+assert.equal(await vault.forSession('s2'), session)
+await vault.forget('s2')
+assert.equal(stored.has(`${PII_MAPPING_PREFIX}s2`), false)
+assert.deepEqual((await vault.forSession('s2'))?.toJSON(), {})
+
+console.log('privacy mode tests passed')
@@ -0,0 +1,132 @@
+import assert from 'node:assert/strict'
+import { mkdirSync, mkdtempSync, rmSync, symlinkSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { dirname, join } from 'node:path'
+import { AttachmentStore } from '../lib/attachment-store.js'
+import { IgnoreRules } from '../lib/gitignore.js'
+import type { LLMEmbeddingRequest } from '../providers/types.js'
+import type { SessionRepository } from '../repositories/types.js'
+import {
+  deleteProject,
+  ingestProject,
+  linkedProjects,
+  linkProjects,
+  listProjectHashes,
+  listProjects,
+  ProjectInputError,
+  requireProjects,
+  walkProject,
+  type ProjectSearchDeps,
+} from '../services/projects.js'
+import { registerProjectTools } from '../tools/projects.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+// Ignore rules: last match wins, anchoring, directory-only patterns and globs
+const rules = IgnoreRules.empty().with('# build output\n*.log\n!keep.log\n/docs/drafts\nout/\nsrc/**/fixtures/*.json\n')
+assert.equal(rules.ignores('debug.log', false), true)
+assert.equal(rules.ignores('nested/debug.log', false), true)
+assert.equal(rules.ignores('nested/keep.log', false), false)
+assert.equal(rules.ignores('docs/drafts', true), true)
+assert.equal(rules.ignores('api/docs/drafts', true), false)
+assert.equal(rules.ignores('out', true), true)
+assert.equal(rules.ignores('out', false), false)
+assert.equal(rules.ignores('src/a/b/fixtures/user.json', false), true)
+assert.equal(rules.ignores('src/fixtures/user.json', false), true)
+assert.equal(rules.ignores('src/fixtures/user.ts', false), false)
+assert.equal(rules.with('generated.ts', 'web').ignores('web/lib/generated.ts', false), true)
+assert.equal(rules.with('generated.ts', 'web').ignores('api/generated.ts', false), false)
+
+const dir = mkdtempSync(join(tmpdir(), 'projects-'))
+const write = (relative: string, content: string | Buffer) => {
+  const file = join(dir, 'repo', relative)
+  mkdirSync(dirname(file), { recursive: true })
+  writeFileSync(file, content)
+}
+
+try {
+  write('.gitignore', 'secrets.txt\n')
+  write('.env', 'API_KEY=hunter2')
+  write('README.md', 'Billing service. Invoices are sent on the first of the month.')
+  write('secrets.txt', 'invoice password')
+  write('src/invoice.ts', 'export function sendInvoice() { /* invoice email */ }')
+  write('src/deploy/.gitignore', '*.yaml\n')
+  write('src/deploy/cluster.yaml', 'kubernetes: true')
+  write('src/deploy/notes.md', 'Kubernetes rollout checklist for the billing pods.')
+  write('node_modules/left-pad/index.js', 'module.exports = invoice')
+  write('assets/logo.png', Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x00, 0x00]))
+  symlinkSync(join(dir, 'repo', 'src'), join(dir, 'repo', 'src-link'))
+
+  assert.deepEqual(await walkProject(join(dir, 'repo')), {
+    paths: ['assets/logo.png', 'README.md', 'src/deploy/notes.md', 'src/invoice.ts'],
+    truncated: false,
+  })
+
+  const attachments = new AttachmentStore(join(dir, 'attachments'))
+  const embedRequests: LLMEmbeddingRequest[] = []
+  const sessionIds = new Set(['session-1', 'session-2'])
+  const deps = {
+    attachments,
+    config: { attachmentEmbeddingModel: 'openai:embed' },
+    providers: {
+      resolve: () => ({
+        async embed(request: LLMEmbeddingRequest) {
+          embedRequests.push(request)
+          return { embeddings: request.input.map((text) => ['invoice', 'kubernetes'].map((topic) => text.toLowerCase().split(topic).length - 1)) }
+        },
+      }),
+    },
+    projectsDir: join(dir, 'projects'),
+    sessionFilesRoot: join(dir, 'sessions'),
+    sessions: { getById: async (id: string) => sessionIds.has(id) ? { id, userId: 'user-1' } : null } as unknown as SessionRepository,
+  } as unknown as ProjectSearchDeps
+
+  await assert.rejects(ingestProject(deps, 'user-1', { root: 'repo' }), ProjectInputError)
+  await assert.rejects(ingestProject(deps, 'user-1', { root: join(dir, 'repo', 'README.md') }), /Not a folder/)
+  await assert.rejects(ingestProject(deps, 'user-1', { root: join(dir, 'repo'), name: 'a/b' }), /Invalid project name/)
+
+  // Binary files are skipped; every text file is stored and embedded
+  const project = await ingestProject(deps, 'user-1', { root: join(dir, 'repo') })
+  assert.equal(project.name, 'repo')
+  assert.equal(project.files, 3)
+  assert.equal(project.truncated, false)
+  assert.equal(embedRequests.length, 3)
+  assert.deepEqual(await listProjects(deps, 'user-1'), [project])
+  assert.deepEqual(await listProjects(deps, 'user-2'), [])
+  assert.equal((await listProjectHashes(deps)).length, 3)
+
+  // project.search only sees projects linked to the conversation
+  const registry = new ToolRegistryImpl()
+  registerProjectTools(registry, deps)
+  const ctx = (session: string) => ({ agent_id: 'agent', session_id: session, signal: new AbortController().signal })
+  const unlinked = await registry.execute('project.search', { query: 'invoice' }, ctx('session-1'))
+  assert.equal(unlinked.ok, false)
+  assert.match(String(unlinked.error), /No project is linked/)
+
+  await assert.rejects(requireProjects(deps, 'user-1', ['repo', 'other']), /Unknown project: other/)
+  assert.deepEqual(await requireProjects(deps, 'user-1', ['repo', 'repo']), ['repo'])
+  await assert.rejects(requireProjects(deps, 'user-2', ['repo']), ProjectInputError)
+  assert.deepEqual(await linkProjects(deps, 'session-1', ['repo']), ['repo'])
+  assert.deepEqual(await linkProjects(deps, 'session-1', ['repo']), ['repo'])
+  assert.deepEqual(await linkedProjects(deps, 'session-2'), [])
+
+  const found = await registry.execute('project.search', { query: 'kubernetes rollout', limit: 1 }, ctx('session-1'))
+  assert.equal(found.ok, true)
+  const [top] = (found.output as { results: Array<{ project: string; path: string; text: string }> }).results
+  assert.equal(top.project, 'repo')
+  assert.equal(top.path, 'src/deploy/notes.md')
+  assert.match(top.text, /rollout checklist/)
+
+  const wrongProject = await registry.execute('project.search', { query: 'invoice', project: 'other' }, ctx('session-1'))
+  assert.match(String(wrongProject.error), /not linked to this conversation/)
+
+  // Deleted projects drop out of search and orphan protection
+  assert.equal(await deleteProject(deps, 'user-1', 'repo'), true)
+  assert.equal(await deleteProject(deps, 'user-1', 'repo'), false)
+  assert.deepEqual(await listProjectHashes(deps), [])
+  const afterDelete = await registry.execute('project.search', { query: 'invoice' }, ctx('session-1'))
+  assert.match(String(afterDelete.error), /no indexed files/)
+
+  console.log('Project tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
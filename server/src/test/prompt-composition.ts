@@ -0,0 +1,62 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { ARTIFACT_REFERENCE_HEADER } from '../orchestrator/output.js'
+import type { LLMMessage } from '../providers/types.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { estimatePromptComposition } from '../usage/attribution.js'
+import { UsageTracker } from '../usage/tracker.js'
+
+const summary = [ARTIFACT_REFERENCE_HEADER, 'file://out.txt', '', 'bytes: 90000', 'lines: 12'].join('\n')
+const messages: LLMMessage[] = [
+  { role: 'system', content: 's'.repeat(400) },
+  { role: 'user', content: 'u'.repeat(40) },
+  { role: 'assistant', content: 'a'.repeat(20), tool_calls: [{ call_id: 'c1', name: 'files.read', arguments: {} }] },
+  { role: 'tool', content: 'r'.repeat(800), tool_call_id: 'c1' },
+  { role: 'tool', content: summary, tool_call_id: 'c2' },
+]
+const tools = [{ name: 'files.read', description: 'Read a file', parameters: {} }]
+
+const composition = estimatePromptComposition({ messages, tools })
+assert.deepEqual(composition.system, { bytes: 400, tokens: 100 })
+assert.deepEqual(composition.conversation, { bytes: 62, tokens: 16 })
+assert.deepEqual(composition.toolResults, { bytes: 800, tokens: 200 })
+assert.equal(composition.toolSummaries.bytes, Buffer.byteLength(summary), 'saved-artifact references count as summaries')
+assert.ok(composition.tools.tokens > 0)
+
+// Everything up to the last assistant reply was sent last turn too; the tool results after it are new
+const sum = (...parts: (keyof typeof composition)[]) => parts.reduce((total, key) => total + composition[key].bytes, 0)
+assert.equal(composition.stablePrefix.bytes, sum('tools', 'system', 'conversation'))
+assert.equal(composition.tail.bytes, sum('toolResults', 'toolSummaries'))
+assert.equal(composition.total.bytes, sum('tools', 'system', 'conversation', 'toolResults', 'toolSummaries'))
+assert.equal(composition.total.tokens, composition.stablePrefix.tokens + composition.tail.tokens)
+
+// With no reply yet the whole history is tail, but the system prompt and tools stay in the prefix
+const first = estimatePromptComposition({ messages: messages.slice(0, 2) })
+assert.deepEqual(first.stablePrefix, first.system)
+assert.deepEqual(first.tail, first.conversation)
+
+// The tracker stores the breakdown alongside each call
+const dir = mkdtempSync(join(tmpdir(), 'prompt-composition-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'usage.db')))
+  const tracker = new UsageTracker(repos.usage, repos.sessions)
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id })
+  const agent = await repos.agents.create({
+    sessionId: session.id,
+    task: 'Read files',
+    config: { model: 'gpt-4o', provider: 'openai', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 },
+  })
+
+  await tracker.record({ agent, provider: 'openai', model: 'gpt-4o', usage: { input_tokens: 400, output_tokens: 0 }, request: { messages, tools } })
+  await tracker.record({ agent, provider: 'openai', model: 'gpt-4o', usage: { input_tokens: 10, output_tokens: 0 } })
+  const recorded = await repos.usage.list({ sessionId: session.id })
+  assert.deepEqual(recorded.find((r) => r.inputTokens === 400)?.promptComposition, composition)
+  assert.equal(recorded.find((r) => r.inputTokens === 10)?.promptComposition, null)
+
+  console.log('prompt composition tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,96 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { DEFAULT_CONTROLLER_PROMPTS } from '../orchestrator/prompts.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { ControllerPromptStore } from '../services/controller-prompts.js'
+import { normalizeExperimentInput, PromptExperiments } from '../services/experiments.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'prompt-experiments-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'experiments.db')))
+  const store = new ControllerPromptStore(repos.controllerPrompts)
+  await store.seed()
+  const experiments = new PromptExperiments({
+    preferences: repos.preferences,
+    experiments: repos.experiments,
+    agents: repos.agents,
+    usage: repos.usage,
+    prompts: store,
+  })
+  const config = { model: 'test:a', provider: 'test', max_turns: 5, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+
+  // Input needs exactly two distinctly named variants and known prompt names
+  assert.throws(() => normalizeExperimentInput({ name: 'x', variants: [{ name: 'a' }] }), /exactly two/)
+  assert.throws(() => normalizeExperimentInput({ name: 'x', variants: [{ name: 'a' }, { name: 'a' }] }), /different names/)
+  assert.throws(() => normalizeExperimentInput({ name: 'x', variants: [{ name: 'a', prompts: { responder: 'x' } }, { name: 'b' }] }), /unknown prompt/)
+
+  // Without an experiment nothing is enrolled and every session gets the stored prompts
+  const outside = await repos.sessions.create({ userId: user.id })
+  assert.equal(await experiments.enroll(outside.id, { modelPinned: false }), null)
+  assert.deepEqual(await experiments.active(outside.id), DEFAULT_CONTROLLER_PROMPTS)
+  assert.equal(await experiments.results(), null)
+
+  // New sessions alternate between the arms and keep theirs
+  await experiments.start({
+    name: 'Terse controller',
+    variants: [{ name: 'control' }, { name: 'terse', prompts: { controller_anthropic: 'Be terse.' } }],
+  })
+  const sessions = []
+  for (let i = 0; i < 4; i++) {
+    const session = await repos.sessions.create({ userId: user.id })
+    sessions.push({ id: session.id, variant: (await experiments.enroll(session.id, { modelPinned: true }))?.name })
+  }
+  assert.deepEqual(sessions.map((session) => session.variant), ['control', 'terse', 'control', 'terse'])
+  assert.equal((await repos.experiments.getBySession(sessions[1].id))?.variant, 'terse')
+  assert.equal((await experiments.active(sessions[0].id)).controller_anthropic, DEFAULT_CONTROLLER_PROMPTS.controller_anthropic)
+  assert.equal((await experiments.active(sessions[1].id)).controller_anthropic, 'Be terse.')
+  assert.equal((await experiments.active(sessions[1].id)).controller_openai, DEFAULT_CONTROLLER_PROMPTS.controller_openai)
+  assert.deepEqual(await experiments.active(outside.id), DEFAULT_CONTROLLER_PROMPTS)
+
+  // Results: success, turns and cost per arm, from finished sessions only
+  const finish = async (sessionId: string, status: 'completed' | 'failed' | 'running', turnCount: number, costUsd: number) => {
+    const agent = await repos.agents.create({ sessionId, task: 'task', config })
+    await repos.agents.update(agent.id, { status, turnCount })
+    await repos.usage.record({ sessionId, agentId: agent.id, provider: 'test', model: 'a', inputTokens: 10, outputTokens: 10, costUsd })
+  }
+  await finish(sessions[0].id, 'completed', 4, 0.02)
+  await finish(sessions[1].id, 'completed', 2, 0.01)
+  await finish(sessions[2].id, 'failed', 6, 0.04)
+  await finish(sessions[3].id, 'running', 1, 0.005)
+  const results = (await experiments.results())!
+  const [control, terse] = results.variants
+  assert.deepEqual(
+    { sessions: control.sessions, completed: control.completed, failed: control.failed, unfinished: control.unfinished },
+    { sessions: 2, completed: 1, failed: 1, unfinished: 0 },
+  )
+  assert.equal(control.successRate, 0.5)
+  assert.equal(control.averageTurns, 5)
+  assert.equal(control.averageCostUsd, 0.03)
+  assert.equal(control.totalCostUsd, 0.06)
+  assert.deepEqual(
+    { sessions: terse.sessions, completed: terse.completed, unfinished: terse.unfinished, successRate: terse.successRate, averageTurns: terse.averageTurns },
+    { sessions: 2, completed: 1, unfinished: 1, successRate: 1, averageTurns: 2 },
+  )
+
+  // A model comparison leaves out sessions whose caller picked a model
+  await experiments.start({ name: 'Models', variants: [{ name: 'a', model: 'test:a' }, { name: 'b', model: 'test:b' }] })
+  const pinned = await repos.sessions.create({ userId: user.id })
+  assert.equal(await experiments.enroll(pinned.id, { modelPinned: true }), null)
+  const open = await repos.sessions.create({ userId: user.id })
+  assert.equal((await experiments.enroll(open.id, { modelPinned: false }))?.model, 'test:a')
+  // Sessions of a replaced experiment go back to the stored prompts
+  assert.equal((await experiments.active(sessions[1].id)).controller_anthropic, DEFAULT_CONTROLLER_PROMPTS.controller_anthropic)
+
+  // Stopping keeps the results but enrolls no one else
+  assert.ok((await experiments.stop())?.stoppedAt)
+  const late = await repos.sessions.create({ userId: user.id })
+  assert.equal(await experiments.enroll(late.id, { modelPinned: false }), null)
+  assert.equal((await experiments.results())?.variants[0].sessions, 0)
+
+  console.log('prompt experiment tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
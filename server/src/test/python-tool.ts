@@ -0,0 +1,105 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, readFileSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { listAgentArtifacts, writeAgentArtifact, type AgentArtifact } from '../services/agent-artifacts.js'
+import { getSessionFilesDir } from '../tools/path-policy.js'
+import { registerPythonTools } from '../tools/python.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+type Output = {
+  exit_code: number | null
+  success: boolean
+  timed_out: boolean
+  stdout: string
+  stderr: string
+  stdout_artifact?: string
+  files: AgentArtifact[]
+}
+
+const root = mkdtempSync(join(tmpdir(), 'python-tool-'))
+try {
+  const registry = new ToolRegistryImpl()
+  registerPythonTools(registry, {
+    sessionFilesRoot: join(root, 'sessions'),
+    notesDir: join(root, 'notes'),
+    pythonBin: process.env.PYTHON_BIN ?? 'python3',
+    timeoutMs: 10_000,
+    maxTimeoutMs: 20_000,
+    memoryMb: 1024,
+  })
+  const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+  assert.equal(registry.getMetadata('python.run')?.requires_approval, true)
+  assert.deepEqual(registry.getPreview('python.run', { code: '# totals\nimport csv\nprint(1)', network: true }, ctx), {
+    summary: 'Run Python with network access: import csv',
+    details: { code: '# totals\nimport csv\nprint(1)' },
+  })
+
+  // Saved files go in by name; what the script writes comes back as artifacts
+  await writeAgentArtifact(join(root, 'sessions'), 'session', { name: 'sales.csv', content: 'region,amount\nnorth,10\nsouth,32\n' })
+  const summed = await registry.execute('python.run', {
+    code: [
+      'import csv, os',
+      'rows = list(csv.DictReader(open("sales.csv")))',
+      'total = sum(int(row["amount"]) for row in rows)',
+      'print(f"total={total}")',
+      'os.makedirs("out", exist_ok=True)',
+      'open("out/summary.txt", "w").write(f"{len(rows)} regions, {total} in all")',
+    ].join('\n'),
+    files: ['artifact://files/sales.csv'],
+  }, ctx)
+  assert.equal(summed.ok, true)
+  const output = summed.output as Output
+  assert.equal(output.stdout, 'total=42\n')
+  assert.equal(output.exit_code, 0)
+  assert.deepEqual(output.files.map((file) => file.ref), ['artifact://files/summary.txt'])
+  const saved = join(getSessionFilesDir(join(root, 'sessions'), 'session'), 'artifacts', 'files', 'summary.txt')
+  assert.equal(readFileSync(saved, 'utf-8'), '2 regions, 42 in all')
+  assert.ok(!(await listAgentArtifacts(join(root, 'sessions'), 'session')).some((file) => file.name === 'sales (2).csv'), 'unchanged inputs are not saved again')
+
+  // Errors are reported, not thrown; the environment starts empty
+  const failed = (await registry.execute('python.run', { code: 'import os\nprint(sorted(os.environ))\nraise SystemExit("bad input")' }, ctx)).output as Output
+  assert.equal(failed.exit_code, 1)
+  assert.equal(failed.success, false)
+  assert.match(failed.stderr, /bad input/)
+  assert.doesNotMatch(failed.stdout, /SANDBOX_|PYTHON_BIN/)
+
+  // No network unless the call asks for it
+  const offline = (await registry.execute('python.run', { code: 'import socket\nsocket.create_connection(("example.com", 80), timeout=1)' }, ctx)).output as Output
+  assert.match(offline.stderr, /Network access is disabled/)
+
+  // Long output is kept whole as an artifact
+  const chatty = (await registry.execute('python.run', { code: 'for i in range(5000): print("line", i)' }, ctx)).output as Output
+  assert.equal(chatty.stdout_artifact, 'artifact://files/python-stdout.txt')
+  assert.match(chatty.stdout, /\[full output in artifact:\/\/files\/python-stdout.txt\]$/)
+
+  // Runaway scripts are killed at the timeout
+  const looping = await registry.execute('python.run', { code: 'while True: pass', timeout_ms: 500 }, ctx)
+  assert.equal(looping.ok, false)
+  assert.match(looping.error ?? '', /timed out after 500ms/)
+  assert.equal((looping.output as Output).timed_out, true)
+
+  // Bad timeouts fall back to the default; long ones are capped
+  for (const timeout_ms of [-1, 0, Number.NaN, Number.POSITIVE_INFINITY]) {
+    assert.equal((await registry.execute('python.run', { code: 'print(1)', timeout_ms }, ctx)).ok, true)
+  }
+  const capped = await registry.execute('python.run', { code: 'import time\ntime.sleep(1)', timeout_ms: 1e12 }, ctx)
+  assert.equal(capped.ok, true)
+
+  // Subprocesses the script started go with it, at the end or at the timeout
+  const startedAt = Date.now()
+  const forked = (await registry.execute('python.run', { code: 'import subprocess\nsubprocess.Popen(["sleep", "30"])\nprint("done")' }, ctx)).output as Output
+  assert.equal(forked.stdout, 'done\n')
+  const stuck = await registry.execute('python.run', { code: 'import subprocess\nsubprocess.Popen(["sleep", "30"])\nwhile True: pass', timeout_ms: 500 }, ctx)
+  assert.equal((stuck.output as Output).timed_out, true)
+  assert.ok(Date.now() - startedAt < 10_000, 'background processes do not hold the run open')
+
+  // Only managed paths can be passed in
+  const escaped = await registry.execute('python.run', { code: 'print(1)', files: ['/etc/passwd'] }, ctx)
+  assert.equal(escaped.ok, false)
+  assert.match(escaped.error ?? '', /Absolute paths are not accepted/)
+
+  console.log('python tool tests passed')
+} finally {
+  rmSync(root, { recursive: true, force: true })
+}
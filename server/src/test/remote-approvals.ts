@@ -0,0 +1,132 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { createServer } from 'node:http'
+import type { AddressInfo } from 'node:net'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { setTimeout as sleep } from 'node:timers/promises'
+import { AgentEventEmitter } from '../events/emitter.js'
+import { EVENT_TYPES } from '../events/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { ApprovalHistory } from '../orchestrator/approval-history.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { remoteApprovalRoutes } from '../routes/remote-approvals.js'
+import {
+  RemoteApprovalRelay,
+  signApprovalToken,
+  verifyApprovalToken,
+  type RemoteApprovalNotification,
+} from '../services/remote-approvals.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+// Tokens are bound to their secret and expiry
+const token = signApprovalToken({ agentId: 'a', callId: 'c', expiresAt: 2_000 }, 'secret')
+assert.deepEqual(verifyApprovalToken(token, 'secret', 1_000), { agentId: 'a', callId: 'c', expiresAt: 2_000 })
+assert.equal(verifyApprovalToken(token, 'other', 1_000), null)
+assert.equal(verifyApprovalToken(token, 'secret', 2_000), null)
+assert.equal(verifyApprovalToken(`${token}x`, 'secret', 1_000), null)
+assert.equal(verifyApprovalToken('garbage', 'secret', 1_000), null)
+
+const received: RemoteApprovalNotification[] = []
+const webhook = createServer((req, res) => {
+  let body = ''
+  req.on('data', (chunk) => { body += chunk })
+  req.on('end', () => {
+    received.push(JSON.parse(body) as RemoteApprovalNotification)
+    res.writeHead(204).end()
+  })
+})
+await new Promise<void>((resolve) => webhook.listen(0, '127.0.0.1', resolve))
+
+const dir = mkdtempSync(join(tmpdir(), 'remote-approvals-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'remote.db')))
+  const events = new AgentEventEmitter()
+  const tools = new ToolRegistryImpl()
+  const ran: string[] = []
+  tools.register({
+    metadata: { name: 'files.write', description: 'files.write', parameters: { type: 'object', properties: {} }, requires_approval: true },
+    async handle() {
+      ran.push('files.write')
+      return { ok: true, output: 'written' }
+    },
+  })
+  const runtime = {
+    events,
+    tools,
+    repositories: repos,
+    providers: { resolve: () => ({}) },
+    approvalHistory: new ApprovalHistory(repos.approvalHistory, repos.sessions),
+    sessionFilesRoot: join(dir, 'files'),
+    remoteApprovals: null,
+  } as unknown as RuntimeContext
+  const relay = new RemoteApprovalRelay(runtime, {
+    baseUrl: 'https://assistant.example.com/',
+    secret: 'relay-secret',
+    webhookUrl: `http://127.0.0.1:${(webhook.address() as AddressInfo).port}/push`,
+    linkTtlMs: 60_000,
+  })
+  runtime.remoteApprovals = relay
+  relay.start()
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Nightly refactor' })
+  const config = { model: 'test:model', provider: 'test', max_turns: 1, max_tool_calls_per_step: 3, tool_execution_timeout_ms: 1000 }
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Refactor', config })
+  for (const callId of ['r1', 'r2']) {
+    await repos.items.create({ agentId: agent.id, type: 'function_call', callId, name: 'files.write', arguments: '{}', turnNumber: 1 })
+  }
+  await repos.agents.update(agent.id, {
+    status: 'waiting',
+    waitingFor: ['r1', 'r2'].map((callId) => ({ callId, type: 'approval' as const, name: 'files.write', args: { path: '<notes>.md' } })),
+  })
+
+  // Every proposed call is pushed with a signed link to the companion page
+  events.emit({
+    type: EVENT_TYPES.TOOL_PROPOSED,
+    agent_id: agent.id,
+    session_id: session.id,
+    payload: { callId: 'r1', name: 'files.write', args: {}, parentId: null, depth: 0 },
+    timestamp: Date.now(),
+  })
+  for (let i = 0; i < 50 && received.length === 0; i++) await sleep(10)
+  relay.stop()
+  assert.equal(received.length, 1)
+  assert.equal(received[0].callId, 'r1')
+  assert.equal(received[0].tool, 'files.write')
+  assert.match(received[0].message, /Nightly refactor/)
+  assert.ok(received[0].url.startsWith('https://assistant.example.com/remote-approvals/'))
+
+  const app = remoteApprovalRoutes(runtime)
+  const path = new URL(received[0].url).pathname.replace('/remote-approvals', '')
+
+  // Opening the link only renders the request, escaped
+  const page = await app.request(path)
+  assert.equal(page.status, 200)
+  const html = await page.text()
+  assert.match(html, /files\.write/)
+  assert.match(html, /&lt;notes&gt;\.md/)
+  assert.equal((await repos.agents.getById(agent.id))?.waitingFor.length, 2)
+
+  assert.equal((await app.request('/not-a-token')).status, 404)
+  const invalid = await app.request(path, { method: 'POST', body: new URLSearchParams({ decision: 'maybe' }) })
+  assert.equal(invalid.status, 400)
+
+  // Approving from the page runs that call once; the other one still waits
+  const approved = await app.request(path, { method: 'POST', body: new URLSearchParams({ decision: 'approved' }) })
+  assert.equal(approved.status, 200)
+  assert.match(await approved.text(), /Approved/)
+  for (let i = 0; i < 50 && (await repos.agents.getById(agent.id))?.waitingFor.length !== 1; i++) await sleep(10)
+  assert.deepEqual((await repos.agents.getById(agent.id))?.waitingFor.map((w) => w.callId), ['r2'])
+  assert.deepEqual(ran, ['files.write'])
+  assert.deepEqual((await repos.approvalHistory.list({ outcome: 'approved' })).map((r) => [r.callId, r.scope]), [['r1', 'once']])
+
+  // A used link cannot decide twice
+  assert.equal((await app.request(path)).status, 410)
+  assert.equal((await app.request(path, { method: 'POST', body: new URLSearchParams({ decision: 'denied' }) })).status, 410)
+
+  console.log('remote approval tests passed')
+} finally {
+  webhook.close()
+  rmSync(dir, { recursive: true, force: true })
+}
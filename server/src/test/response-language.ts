@@ -0,0 +1,48 @@
+import assert from 'node:assert/strict'
+import type { Item } from '../domain/types.js'
+import {
+  detectLanguage,
+  normalizeLanguage,
+  resolveResponseLanguage,
+  RESPONSE_LANGUAGE_PREFIX,
+} from '../orchestrator/language.js'
+import { buildControllerMessages } from '../orchestrator/prompts.js'
+import { USER_PROFILE_PREFERENCE_KEY } from '../orchestrator/user-profile.js'
+
+// Detection: scripts decide outright, Latin text needs enough common words
+assert.equal(detectLanguage('Jaka jest pogoda w Warszawie?'), 'Polish')
+assert.equal(detectLanguage('Wie ist das Wetter heute in Berlin?'), 'German')
+assert.equal(detectLanguage('¿Qué tiempo hace hoy en Madrid?'), 'Spanish')
+assert.equal(detectLanguage('Can you help me with this?'), 'English')
+assert.equal(detectLanguage('Яка сьогодні погода?'), 'Ukrainian')
+assert.equal(detectLanguage('今日の天気はどうですか'), 'Japanese')
+assert.equal(detectLanguage('ok thanks'), null)
+
+assert.equal(normalizeLanguage('  Polish '), 'Polish')
+assert.equal(normalizeLanguage(''), null)
+assert.throws(() => normalizeLanguage(7), /must be a string/)
+
+// The conversation's language wins over the profile's
+const stored = new Map<string, string>()
+const preferences = {
+  get: async (key: string) => stored.get(key) ?? null,
+  set: async (key: string, value: string) => { stored.set(key, value) },
+  delete: async (key: string) => { stored.delete(key) },
+}
+assert.deepEqual(await resolveResponseLanguage(preferences, 's1'), { language: null, source: 'none' })
+stored.set(USER_PROFILE_PREFERENCE_KEY, JSON.stringify({ language: 'German' }))
+assert.deepEqual(await resolveResponseLanguage(preferences, 's1'), { language: 'German', source: 'profile' })
+stored.set(`${RESPONSE_LANGUAGE_PREFIX}s1`, 'Polish')
+assert.deepEqual(await resolveResponseLanguage(preferences, 's1'), { language: 'Polish', source: 'conversation' })
+
+// The instruction: the set language, else the detected one, never for sub-agents
+const history = [{ type: 'message', role: 'user', content: 'Jaka jest pogoda w Warszawie?' }] as Item[]
+const system = (responseLanguage?: string | null) => {
+  const [message] = buildControllerMessages('CONTROLLER', '', history, { useNativeFunctionCalling: true, agentTask: 'Weather', responseLanguage })
+  return message.content as string
+}
+assert.ok(system('French').includes('Reply in French, unless the user asks for a different language.'))
+assert.ok(system().includes("Reply in Polish, the language of the user's latest message."))
+assert.ok(!system(null).includes('Reply in'))
+
+console.log('response language tests passed')
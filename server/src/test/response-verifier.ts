@@ -0,0 +1,71 @@
+import assert from 'node:assert/strict'
+import type { Agent, ToolOutput } from '../domain/types.js'
+import { parseVerdict, ResponseVerifier, type VerifierContext } from '../orchestrator/verifier.js'
+import type { LLMRequest, LLMResponse } from '../providers/types.js'
+
+const agent = { id: 'agent-1', sessionId: 's1', depth: 0, config: { model: 'anthropic:claude', provider: 'anthropic' } } as Agent
+const outputs: ToolOutput[] = [
+  { id: 'old', agentId: 'agent-1', callId: 'c0', toolName: 'web.fetch', data: 'from an earlier turn', createdAt: 50 },
+  { id: 'out-1', agentId: 'agent-1', callId: 'c1', toolName: 'calendar.list', data: [{ title: 'Standup', start: '09:30' }], createdAt: 150 },
+]
+const usage: string[] = []
+const lookups: Array<Record<string, unknown>> = []
+
+function context(replies: Array<Partial<LLMResponse>>, requests: LLMRequest[] = []): VerifierContext {
+  return {
+    agent,
+    signal: new AbortController().signal,
+    provider: {
+      generate: async (request: LLMRequest) => {
+        requests.push(structuredClone({ ...request, signal: undefined }))
+        const reply = replies.shift()
+        if (!reply) throw new Error('provider down')
+        return { content: '', usage: { input_tokens: 1, output_tokens: 1 }, finish_reason: 'stop', ...reply }
+      },
+    },
+    tools: {
+      getMetadata: (name: string) => name === 'tool_outputs.extract'
+        ? { name, description: 'Extract value by dot path from persisted output.', parameters: {}, requires_approval: false }
+        : undefined,
+      execute: async (_name: string, args: Record<string, unknown>) => {
+        lookups.push(args)
+        return { ok: true, output: '09:30' }
+      },
+    },
+    toolOutputs: { listByAgent: async () => outputs },
+    usage: { record: async ({ phase }: { phase?: string }) => { usage.push(phase ?? '') } },
+    redactor: { redact: (text: string) => text.replace('09:30', '[REDACTED]') },
+  } as unknown as VerifierContext
+}
+
+const verifier = new ResponseVerifier()
+
+// Looks facts up in this turn's outputs only, then reports what it could not back up
+const requests: LLMRequest[] = []
+const verification = await verifier.verify(context([
+  { tool_calls: [{ call_id: 'v1', name: 'tool_outputs.extract', arguments: { id: 'out-1', path: '0.start' } }, { call_id: 'v2', name: 'tool_outputs.extract', arguments: { id: 'old', path: '' } }] },
+  { content: 'Verdict: {"confidence": "medium", "unsupported_claims": ["It is in room 4", ""]}' },
+], requests), 'Standup is at 9:30 in room 4.', 100)
+assert.deepEqual(verification, { confidence: 'medium', unsupportedClaims: ['It is in room 4'], checkedOutputs: 1, model: 'anthropic:claude' })
+assert.deepEqual(lookups, [{ id: 'out-1', path: '0.start' }])
+assert.deepEqual(usage, ['verifier', 'verifier'])
+const first = requests[0].messages[1].content as string
+assert.match(first, /out-1 \(calendar\.list\): array of 1, items with keys title, start/)
+assert.equal(first.includes('from an earlier turn'), false)
+assert.deepEqual(requests[0].tools?.map((tool) => tool.name), ['tool_outputs.extract'])
+assert.deepEqual(requests[1].messages.slice(-2).map((message) => message.content), ['"[REDACTED]"', 'Error: not an output of this turn: old'])
+
+// Nothing persisted this turn, or a failing check: no annotation, and the run goes on
+assert.equal(await verifier.verify(context([]), 'Hello!', 1000), null)
+assert.equal(await verifier.verify(context([]), 'Standup is at 9:30.', 100), null)
+
+// A verifier that never stops looking is cut off
+const endless = Array.from({ length: 4 }, () => ({ tool_calls: [{ call_id: 'v', name: 'tool_outputs.extract', arguments: { id: 'out-1', path: '0.title' } }] }))
+assert.equal(await verifier.verify(context(endless), 'Standup is at 9:30.', 100), null)
+
+// Verdicts: JSON anywhere in the reply, known confidence levels only
+assert.deepEqual(parseVerdict('{"confidence":"high","unsupported_claims":[]}', 2, 'm'), { confidence: 'high', unsupportedClaims: [], checkedOutputs: 2, model: 'm' })
+assert.equal(parseVerdict('{"confidence":"sure"}', 1, 'm'), null)
+assert.equal(parseVerdict('Looks fine to me.', 1, 'm'), null)
+
+console.log('response verifier tests passed')
@@ -0,0 +1,71 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import {
+  normalizeRetentionPolicy,
+  PURGED_OUTPUT_PLACEHOLDER,
+  RETENTION_PREFERENCE_KEY,
+  RetentionMaintenance,
+  retentionCutoffs,
+} from '../services/retention.js'
+
+const DAY_MS = 24 * 60 * 60 * 1000
+
+assert.throws(() => normalizeRetentionPolicy({ toolOutputsDays: 1.5 }), /toolOutputsDays/)
+assert.equal(
+  retentionCutoffs({ sessionsMonths: 3, toolOutputsDays: null, usageDays: null }, Date.UTC(2025, 4, 15)).sessionsBefore,
+  Date.UTC(2025, 1, 15),
+)
+
+const dir = mkdtempSync(join(tmpdir(), 'retention-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'retention.db')))
+  const runtime = {
+    repositories: repos,
+    sessionFilesRoot: join(dir, 'sessions'),
+    debugTraces: { removeSession: async () => {} },
+    inlineOutputBudget: { forget: () => {} },
+    sync: null,
+  } as unknown as RuntimeContext
+  const maintenance = new RetentionMaintenance(runtime)
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Old trip' })
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Plan', config })
+  await repos.items.create({ agentId: agent.id, type: 'message', role: 'user', content: 'Hi', turnNumber: 1 })
+  await repos.items.create({ agentId: agent.id, type: 'function_call_output', callId: 'c1', output: 'big result', turnNumber: 1 })
+  await repos.toolOutputs.save({ agentId: agent.id, callId: 'c1', toolName: 'web.fetch', data: { body: 'x' } })
+  await repos.usage.record({ userId: user.id, sessionId: session.id, provider: 'openai', model: 'gpt-4o', inputTokens: 1, outputTokens: 1, costUsd: 0.01 })
+
+  // Default policy touches nothing.
+  const idle = await maintenance.preview(Date.now() + 1000 * DAY_MS)
+  assert.deepEqual([idle.sessions.count, idle.toolOutputs, idle.usageRecords], [0, 0, 0])
+
+  await repos.preferences.set(RETENTION_PREFERENCE_KEY, JSON.stringify({ toolOutputsDays: 30 }))
+  const later = Date.now() + 31 * DAY_MS
+  assert.equal((await maintenance.preview(later)).toolOutputs, 2)
+  assert.equal((await maintenance.run(later)).toolOutputs, 2)
+  const output = await repos.items.getOutputByCallId('c1')
+  assert.equal(output?.output, PURGED_OUTPUT_PLACEHOLDER)
+  assert.equal((await maintenance.preview(later)).toolOutputs, 0, 'already purged outputs are not counted again')
+
+  await repos.preferences.set(RETENTION_PREFERENCE_KEY, JSON.stringify({ sessionsMonths: 6 }))
+  assert.equal((await maintenance.preview(Date.now() + 90 * DAY_MS)).sessions.count, 0)
+  const preview = await maintenance.preview(Date.now() + 200 * DAY_MS)
+  assert.equal(preview.sessions.count, 1)
+  assert.equal(preview.sessions.items, 2)
+  assert.equal(preview.sessions.examples[0].title, 'Old trip')
+
+  const result = await maintenance.run(Date.now() + 200 * DAY_MS)
+  assert.equal(result.sessions, 1)
+  assert.equal(await repos.sessions.getById(session.id), null)
+  assert.equal((await repos.usage.list({ userId: user.id })).length, 1, 'usage is kept by default')
+
+  console.log('retention tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
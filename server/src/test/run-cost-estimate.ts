@@ -0,0 +1,69 @@
+import assert from 'node:assert/strict'
+import type { Agent, Item } from '../domain/types.js'
+import type { ToolMetadata } from '../tools/types.js'
+import { estimatePromptTokens } from '../usage/attribution.js'
+import { estimateRunCost } from '../usage/estimate.js'
+import { computeCost, lookupPrice } from '../usage/pricing.js'
+
+// Prompt estimates split system, tool definitions and history
+assert.deepEqual(
+  estimatePromptTokens({
+    messages: [{ role: 'system', content: 's'.repeat(40) }, { role: 'user', content: 'u'.repeat(8) }],
+    tools: [{ name: 'x', description: '', parameters: {} }],
+  }),
+  { system: 10, tools: 11, history: 2, total: 23 },
+)
+
+const tools = {
+  listMetadata: (): ToolMetadata[] => [
+    { name: 'files.read', description: 'Read a file', parameters: { type: 'object', properties: { path: { type: 'string' } } }, requires_approval: false },
+    { name: 'mcp.github_1a2b3c4d.search', description: 'Search GitHub', parameters: {}, requires_approval: false },
+  ],
+}
+const agent: Pick<Agent, 'id' | 'task' | 'depth' | 'config' | 'turnCount'> = {
+  id: 'agent-1',
+  task: 'Summarise the repo',
+  depth: 0,
+  turnCount: 1,
+  config: { model: 'openai:gpt-4o', provider: 'openai', max_turns: 20, max_tool_calls_per_step: 5, tool_execution_timeout_ms: 1000 },
+}
+const items = [
+  { id: 'i1', agentId: 'agent-1', sequence: 1, type: 'message', role: 'user', content: 'u'.repeat(400), turnNumber: 0 },
+  { id: 'i2', agentId: 'agent-1', sequence: 2, type: 'message', role: 'assistant', content: 'a'.repeat(200), turnNumber: 0 },
+].map((item) => ({
+  callId: null, name: null, arguments: null, output: null, contentBlocks: null, isError: null, saveOutput: null, durationMs: null, createdAt: 0, ...item,
+})) as Item[]
+const pricing = { lookup: lookupPrice }
+
+const estimate = estimateRunCost(agent, tools, items, pricing)
+assert.equal(estimate.model, 'openai:gpt-4o')
+assert.equal(estimate.maxTurns, 20)
+assert.equal(estimate.maxOutputTokens, 12_000)
+assert.ok(estimate.prompt.system > 0)
+assert.ok(estimate.prompt.tools > 0, 'native providers send tool definitions')
+assert.equal(estimate.prompt.history, 150)
+assert.equal(estimate.prompt.total, estimate.prompt.system + estimate.prompt.tools + estimate.prompt.history)
+assert.equal(estimate.perTurn.promptCostUsd, computeCost(lookupPrice('openai', 'gpt-4o'), estimate.prompt.total, 0))
+assert.equal(estimate.perTurn.maxCostUsd, computeCost(lookupPrice('openai', 'gpt-4o'), estimate.prompt.total, 12_000))
+
+// Snapshotted MCP tools count; live ones outside the snapshot do not
+const withMcp = estimateRunCost({ ...agent, config: { ...agent.config, tools: [tools.listMetadata()[1]] } }, tools, items, pricing)
+assert.ok(withMcp.prompt.tools > estimate.prompt.tools)
+
+// A draft message adds to the history
+const drafted = estimateRunCost(agent, tools, items, pricing, { draft: 'd'.repeat(80) })
+assert.equal(drafted.prompt.history, estimate.prompt.history + 20)
+
+// Another model: its own price, output budget, and tool-list placement
+const local = estimateRunCost({ ...agent, config: { ...agent.config, max_output_tokens: 2_000 } }, tools, items, pricing, { model: 'ollama:llama3' })
+assert.equal(local.provider, 'ollama')
+assert.equal(local.maxOutputTokens, 2_000)
+assert.deepEqual(local.perTurn, { promptCostUsd: 0, maxCostUsd: 0 })
+
+const listed = estimateRunCost(agent, tools, items, pricing, { model: 'gemini:unpriced-model' })
+assert.equal(listed.prompt.tools, 0, 'non-native providers list tools in the system prompt')
+assert.ok(listed.prompt.system > estimate.prompt.system)
+assert.equal(listed.price, null)
+assert.deepEqual(listed.perTurn, { promptCostUsd: null, maxCostUsd: null })
+
+console.log('run cost estimate tests passed')
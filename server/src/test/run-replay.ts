@@ -0,0 +1,112 @@
+import assert from 'node:assert/strict'
+import { createHash } from 'node:crypto'
+import fs from 'node:fs/promises'
+import os from 'node:os'
+import path from 'node:path'
+
+import { loadConfig } from '../lib/config.js'
+import { initRuntime, shutdownRuntime, type RuntimeContext } from '../lib/runtime.js'
+import type { Item } from '../domain/types.js'
+import type { LLMRequest, LLMResponse } from '../providers/types.js'
+import { buildDeps, prepareSessionTurn } from '../services/session-runner.js'
+import { runAgent } from '../orchestrator/runner.js'
+import { buildRunCapture, ReplayProvider, ReplayTools, type CapturedTrace, type RunCapture } from '../orchestrator/replay.js'
+
+async function main() {
+  const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), 'run-replay-test-'))
+  process.env.DATABASE_URL = path.join(tmpDir, 'test.db')
+  process.env.TASKS_DIR = path.join(tmpDir, 'tasks')
+  process.env.WORKSPACE_DIR = path.join(tmpDir, 'workspace')
+  process.env.SESSION_FILES_DIR = path.join(tmpDir, 'sessions')
+  process.env.TRACES_DIR = path.join(tmpDir, 'traces')
+  process.env.DEBUG_TRACES = 'true'
+  process.env.ENCRYPTION_KEY = 'test-encryption-key'
+  delete process.env.ANTHROPIC_API_KEY
+  delete process.env.OPENAI_API_KEY
+  delete process.env.OLLAMA_BASE_URL
+  delete process.env.OPENROUTER_API_KEY
+  process.env.LANGFUSE_ENABLED = 'false'
+
+  let runtime: RuntimeContext | null = null
+  try {
+    runtime = await initRuntime(loadConfig())
+    const user = await runtime.repositories.users.create({
+      email: 'run-replay@test.local',
+      apiKeyHash: createHash('sha256').update('run-replay-test-key').digest('hex'),
+    })
+
+    // Record: a live run that calls the calculator once, then answers
+    runtime.providers.register('openrouter', {
+      async generate(request: LLMRequest): Promise<LLMResponse> {
+        const tool = request.messages.find((message) => message.role === 'tool')
+        if (tool) {
+          return { content: `3 * 7 is ${String(tool.content).match(/"result": (\d+)/)?.[1]}.`, usage: { input_tokens: 1, output_tokens: 1 }, finish_reason: 'stop' }
+        }
+        return {
+          content: '',
+          tool_calls: [{ call_id: 'call_1', name: 'calculator.evaluate', arguments: { expression: '3 * 7' } }],
+          usage: { input_tokens: 1, output_tokens: 1 },
+          finish_reason: 'tool_calls',
+        }
+      },
+      async *stream() {
+        throw new Error('stream unused')
+      },
+    })
+    const recorded = await prepareSessionTurn(runtime, { userId: user.id, agent: 'planner', input: 'What is 3 * 7?' })
+    const original = await runAgent(recorded.agent.id, buildDeps(runtime, recorded.model))
+    assert.equal(original.status, 'completed')
+    assert.equal(original.result, '3 * 7 is 21.')
+
+    // Capture from the debug traces and stored items, through a capture file
+    const traces: CapturedTrace[] = []
+    for (const trace of await runtime.debugTraces.list(recorded.sessionId)) {
+      traces.push(JSON.parse((await runtime.debugTraces.read(recorded.sessionId, trace.file))!) as CapturedTrace)
+    }
+    const agent = (await runtime.repositories.agents.getById(recorded.agent.id))!
+    const originalItems = await runtime.repositories.items.listByAgent(agent.id)
+    const captureFile = path.join(tmpDir, 'capture.json')
+    await fs.writeFile(captureFile, JSON.stringify(buildRunCapture(agent, traces, originalItems)))
+    const capture = JSON.parse(await fs.readFile(captureFile, 'utf-8')) as RunCapture
+    assert.equal(capture.responses.length, 2)
+    assert.deepEqual(capture.toolCalls.map((call) => [call.name, call.args]), [['calculator.evaluate', { expression: '3 * 7' }]])
+
+    // Replay: the same run again, with no provider and no live tool behind it
+    const replay = async (from: RunCapture) => {
+      const session = await runtime!.repositories.sessions.create({ userId: user.id, title: 'Replay' })
+      const replayed = await runtime!.repositories.agents.create({ sessionId: session.id, task: from.task, config: from.config })
+      await runtime!.repositories.items.create({ agentId: replayed.id, type: 'message', role: 'user', content: from.task, turnNumber: 0 })
+      const provider = new ReplayProvider(from.responses)
+      const tools = new ReplayTools(runtime!.tools, from.toolCalls)
+      const result = await runAgent(replayed.id, { ...buildDeps(runtime!, from.config.model), provider, tools })
+      return { result, provider, tools, items: await runtime!.repositories.items.listByAgent(replayed.id) }
+    }
+    const shape = (items: Item[]) => items.map((item) => [item.type, item.role, item.name, item.arguments, item.output, item.content])
+
+    const exact = await replay(capture)
+    assert.equal(exact.result.status, 'completed')
+    assert.equal(exact.result.result, original.result)
+    assert.deepEqual(shape(exact.items), shape(originalItems))
+    assert.deepEqual(exact.tools.divergences, [])
+    assert.deepEqual(exact.tools.unused, [])
+    assert.equal(exact.provider.remaining, 0)
+    assert.equal(exact.provider.requests.length, 2)
+
+    // A run whose calls no longer match the capture says how
+    const drifted = await replay({ ...capture, toolCalls: [{ ...capture.toolCalls[0], args: { expression: '3*7' } }] })
+    assert.equal(drifted.tools.divergences.length, 1)
+    assert.match(drifted.tools.divergences[0], /calculator\.evaluate was called with/)
+
+    // Asking for more responses than were recorded fails the run instead of hanging
+    const truncated = await replay({ ...capture, responses: capture.responses.slice(0, 1) })
+    assert.equal(truncated.result.status, 'failed')
+    assert.match(truncated.result.error ?? '', /Replay has no response for request 2/)
+
+    console.log('run replay tests passed')
+  } finally {
+    if (runtime) await shutdownRuntime(runtime)
+    await fs.rm(tmpDir, { recursive: true, force: true })
+  }
+}
+
+main()
@@ -0,0 +1,204 @@
+import assert from 'node:assert/strict'
+import type { Item } from '../domain/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { SecretRedactor } from '../lib/secrets.js'
+import type { ApprovalRecord } from '../repositories/types.js'
+import type { InspectorAgent } from '../services/inspector.js'
+import { buildRunReport, isRunReportFormat, markdownToHtml, renderRunReport, reportFileName, type RunReport } from '../services/run-report.js'
+
+const usage = { requests: 2, inputTokens: 1200, outputTokens: 300, costUsd: 0.0123 }
+const root: InspectorAgent = {
+  id: 'agent-1',
+  parentId: null,
+  sourceCallId: null,
+  depth: 0,
+  task: 'Email the <team> about the outage',
+  model: 'anthropic:claude',
+  provider: 'anthropic',
+  phase: 'completed',
+  plan: { goal: 'Notify the team', steps: [{ id: 'p1', description: 'Send the email', status: 'completed', result: 'sent' }] },
+  waitingFor: [],
+  result: 'Done.',
+  error: null,
+  turnCount: 2,
+  usage,
+  turns: [
+    {
+      turn: 1,
+      input: null,
+      reasoning: [],
+      steps: [{
+        callId: 'call-1',
+        name: 'mail.send',
+        arguments: { to: 'team@example.com', apiKey: 'sk-ant-REDACTED' },
+        result: 'x'.repeat(2000),
+        isError: false,
+        durationMs: 1500,
+        childAgentId: null,
+      }],
+      response: null,
+    },
+    { turn: 2, input: null, reasoning: [], steps: [], response: 'Sent the ``` notice to the team.' },
+  ],
+  createdAt: 0,
+  completedAt: 1000,
+}
+const approval: ApprovalRecord = {
+  id: 'a1',
+  userId: 'u1',
+  sessionId: 's1',
+  agentId: 'agent-1',
+  callId: 'call-1',
+  toolName: 'mail.send',
+  args: {},
+  amendedArgs: { to: 'team@example.com' },
+  outcome: 'approved',
+  scope: 'once',
+  ruleId: null,
+  requestedAt: 100,
+  decidedAt: 2100,
+  latencyMs: 2000,
+}
+const report: RunReport = {
+  session: {
+    session: {
+      id: 's1', userId: 'u1', rootAgentId: 'agent-1', parentSessionId: null, forkedFromItemId: null, source: null,
+      title: 'Outage | notice', summary: null, status: 'active', deletedAt: null, createdAt: 0, updatedAt: 0,
+    },
+    exchanges: [{ itemId: 'i1', content: 'Tell the team', createdAt: 0, fromTurn: 1, toTurn: 2 }],
+    agents: [root],
+    usage,
+  },
+  approvals: [approval],
+  transcript: null,
+  generatedAt: 0,
+}
+
+assert.equal(isRunReportFormat('html'), true)
+assert.equal(isRunReportFormat('pdf'), false)
+assert.equal(reportFileName(report, 'markdown'), 'outage-notice-report.md')
+
+// Markdown: every part of the run, long outputs cut, fences that content cannot close
+const markdown = renderRunReport(report, 'markdown')
+assert.match(markdown, /^# Outage \| notice\n/)
+assert.match(markdown, /- \[completed\] Send the email — sent/)
+assert.match(markdown, /mail\.send — ok, 1\.5 s, approval: approved/)
+assert.match(markdown, /… \(500 more characters\)/)
+assert.match(markdown, /\| .* \| mail\.send \| approved \(edited\) \| once \| 2\.0 s \|/)
+assert.match(markdown, /## Final response\n\n````\nSent the ``` notice to the team\.\n````/)
+assert.match(markdown, /\| Total \| {2}\| 2 \| 1200 \| 300 \| 0\.0123 \|/)
+
+// HTML: self-contained and escaped; secrets are removed from the whole document
+const secrets = new SecretRedactor()
+const html = renderRunReport(report, 'html', (text) => secrets.redact(text))
+assert.match(html, /^<!DOCTYPE html>/)
+assert.match(html, /<style>/)
+assert.match(html, /Email the &lt;team&gt; about the outage/)
+assert.equal(html.includes('<team>'), false)
+assert.equal(html.includes('sk-ant-api03'), false)
+assert.match(html, /\[REDACTED\]/)
+
+let sequence = 0
+const item = (fields: Partial<Item>): Item => ({
+  id: `item-${++sequence}`,
+  agentId: 'agent-1',
+  sequence,
+  type: 'message',
+  role: null,
+  content: null,
+  callId: null,
+  name: null,
+  arguments: null,
+  output: null,
+  contentBlocks: null,
+  isError: null,
+  saveOutput: null,
+  turnNumber: 1,
+  durationMs: null,
+  createdAt: sequence * 1000,
+  ...fields,
+})
+
+const items = [
+  item({ role: 'system', content: 'You are helpful' }),
+  item({ role: 'user', content: 'What is in <this> photo?', contentBlocks: [
+    { type: 'image', media_type: 'image/jpeg', name: 'cat.jpg', hash: 'hash-cat' },
+    { type: 'document', media_type: 'application/pdf', name: 'notes.pdf', hash: 'hash-notes' },
+  ] }),
+  item({ type: 'reasoning', content: 'Thinking it over' }),
+  item({ type: 'function_call', callId: 'c1', name: 'web.search', arguments: '{"query":"cats"}' }),
+  item({ type: 'function_call', callId: 'c2', name: 'image.generate', arguments: '{"prompt":"a cat"}' }),
+  item({ type: 'function_call_output', callId: 'c1', output: 'Error: offline', isError: true }),
+  item({ type: 'function_call_output', callId: 'c2', output: 'Generated', contentBlocks: [
+    { type: 'image', media_type: 'image/png', name: 'cat.png', data: 'R0VOCg==' },
+  ] }),
+  item({ role: 'assistant', content: '## A cat\n\nIt is a **tabby**, see [more](https://example.com) and `code`.\n\n- one\n- two' }),
+]
+
+const runtime = {
+  repositories: {
+    sessions: { getById: async (id: string) => (id === 's1' ? { id, userId: 'u1', title: 'Cat photo / questions', createdAt: 0 } : null) },
+    agents: { findRootAgent: async () => ({ id: 'agent-1' }), listBySession: async () => [] },
+    items: { listByAgent: async () => items },
+    usage: { list: async () => [] },
+    approvalHistory: { list: async () => [] },
+  },
+  attachments: {
+    get: async (hash: string) => (hash === 'hash-cat' ? Buffer.from('full-size') : null),
+    forVision: async (_hash: string, _data: Buffer, mediaType: string | undefined) => ({ data: Buffer.from('small'), mediaType }),
+  },
+} as unknown as Pick<RuntimeContext, 'repositories' | 'attachments'>
+
+// Conversation format: other users cannot export it, and the transcript is only built for it
+assert.equal(await buildRunReport(runtime, 'u2', 's1', 'conversation'), null)
+assert.equal(await buildRunReport(runtime, 'u1', 'missing', 'conversation'), null)
+assert.equal((await buildRunReport(runtime, 'u1', 's1'))!.transcript, null)
+assert.throws(() => renderRunReport(report, 'conversation'), /conversation format/)
+
+// Messages keep their images, tool calls group together, reasoning and system prompts are left out
+const conversation = (await buildRunReport(runtime, 'u1', 's1', 'conversation'))!
+const transcript = conversation.transcript!
+assert.deepEqual(transcript.map((entry) => entry.kind === 'message' ? entry.role : entry.kind), ['user', 'tools', 'assistant'])
+const [question, tools] = transcript
+assert.ok(question.kind === 'message' && tools.kind === 'tools')
+assert.deepEqual(question.images, [{ mediaType: 'image/jpeg', data: Buffer.from('small').toString('base64'), name: 'cat.jpg' }])
+assert.deepEqual(question.attachments, ['notes.pdf'])
+assert.deepEqual(tools.calls.map((call) => [call.name, call.isError, call.images.length]), [['web.search', true, 0], ['image.generate', false, 1]])
+assert.equal(tools.calls[0].arguments, '{\n  "query": "cats"\n}')
+
+// The page needs nothing beyond itself
+const page = renderRunReport(conversation, 'conversation')
+assert.ok(page.startsWith('<!DOCTYPE html>'))
+assert.doesNotMatch(page, /<(script|link)\b|src="https?:/)
+assert.match(page, /<img src="data:image\/jpeg;base64,c21hbGw=" alt="cat.jpg">/)
+assert.match(page, /<details><summary>Ran 2 tool calls: web.search, image.generate <span class="failed">\(1 failed\)<\/span><\/summary>/)
+assert.match(page, /What is in &lt;this&gt; photo\?/)
+assert.match(page, /<h3>A cat<\/h3>/)
+assert.match(page, /<strong>tabby<\/strong>, see <a href="https:\/\/example.com">more<\/a> and <code>code<\/code>/)
+assert.match(page, /<ul><li>one<\/li><li>two<\/li><\/ul>/)
+assert.doesNotMatch(page, /Thinking it over|You are helpful/)
+assert.equal(reportFileName(conversation, 'conversation'), 'cat-photo-questions.html')
+
+// Secrets are redacted from the text, never from image data
+const leaked: RunReport = { ...conversation, transcript: [
+  { kind: 'message', role: 'user', text: 'key sk-ant-REDACTED', images: [{ mediaType: 'image/png', data: 'sk-ant-REDACTED', name: null }], attachments: [], createdAt: 0 },
+] }
+const redacted = renderRunReport(leaked, 'conversation', (text) => secrets.redact(text))
+assert.doesNotMatch(redacted, /key sk-ant-api03/)
+assert.match(redacted, /base64,sk-ant-REDACTED"/)
+
+// Image fields cannot close the attribute they are written into
+const hostile: RunReport = { ...conversation, transcript: [
+  { kind: 'message', role: 'user', text: 'look', images: [{ mediaType: 'image/png" onerror="alert(1)', data: 'AAAA" onload="alert(2)', name: null }], attachments: [], createdAt: 0 },
+] }
+const escaped = renderRunReport(hostile, 'conversation')
+assert.doesNotMatch(escaped, /" on(error|load)=/)
+assert.match(escaped, /data:image\/png&quot; onerror=&quot;alert\(1\);base64,AAAA&quot; onload=&quot;alert\(2\)"/)
+
+// Markdown: code is left alone, unsafe links and remote images are not followed
+assert.equal(markdownToHtml('```\n**not bold** <b>\n```'), '<pre><code>**not bold** &lt;b&gt;</code></pre>')
+assert.equal(markdownToHtml('[x](javascript:alert(1))'), '<p>[x](javascript:alert(1))</p>')
+assert.equal(markdownToHtml('![chart](https://example.com/c.png)'), '<p><a href="https://example.com/c.png">chart</a></p>')
+assert.equal(markdownToHtml('1. first\n2. second\n\nafter'), '<ol><li>first</li><li>second</li></ol>\n<p>after</p>')
+
+console.log('run report tests passed')
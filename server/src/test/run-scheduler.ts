@@ -0,0 +1,75 @@
+import assert from 'node:assert/strict'
+import { RunScheduler } from '../orchestrator/run-scheduler.js'
+
+function deferred() {
+  let resolve!: () => void
+  const promise = new Promise<void>((done) => { resolve = done })
+  return { promise, resolve }
+}
+const tick = () => new Promise((resolve) => setImmediate(resolve))
+
+const scheduler = new RunScheduler({ maxRuns: 2 })
+const started: string[] = []
+const positions = new Map<string, number[]>()
+const gates = new Map<string, ReturnType<typeof deferred>>()
+const run = (sessionId: string, name: string, signal?: AbortSignal) => {
+  const gate = deferred()
+  gates.set(name, gate)
+  return scheduler.run(sessionId, async () => {
+    started.push(name)
+    await gate.promise
+    return name
+  }, {
+    signal,
+    onPosition: (position) => positions.set(name, [...(positions.get(name) ?? []), position]),
+  })
+}
+
+// A second run of a busy conversation waits even with a slot free; other conversations fill the limit
+const a1 = run('a', 'a1')
+const a2 = run('a', 'a2')
+const b1 = run('b', 'b1')
+const c1 = run('c', 'c1')
+await tick()
+assert.deepEqual(started, ['a1', 'b1'])
+assert.equal(scheduler.active, 2)
+assert.equal(scheduler.queued, 2)
+assert.deepEqual(positions.get('a2'), [1])
+assert.deepEqual(positions.get('c1'), [2])
+assert.equal(positions.get('a1'), undefined)
+
+// Freed slots go to the oldest run whose conversation is idle; the rest move up
+gates.get('b1')!.resolve()
+assert.equal(await b1, 'b1')
+await tick()
+assert.deepEqual(started, ['a1', 'b1', 'c1'])
+assert.deepEqual(positions.get('a2'), [1])
+gates.get('a1')!.resolve()
+await a1
+await tick()
+assert.deepEqual(started, ['a1', 'b1', 'c1', 'a2'])
+
+// A run cancelled while queued leaves the queue and never starts
+const controller = new AbortController()
+const d1 = run('d', 'd1', controller.signal)
+const e1 = run('e', 'e1')
+await tick()
+assert.deepEqual(positions.get('e1'), [2])
+controller.abort()
+await assert.rejects(d1, { name: 'AbortError' })
+assert.deepEqual(positions.get('e1'), [2, 1])
+
+// Failures free their slot too
+gates.get('c1')!.resolve()
+await c1
+await tick()
+assert.deepEqual(started, ['a1', 'b1', 'c1', 'a2', 'e1'])
+const failing = scheduler.run('f', async () => { throw new Error('boom') })
+for (const name of ['a2', 'e1']) gates.get(name)!.resolve()
+await assert.rejects(failing, /boom/)
+await Promise.all([a2, e1])
+assert.equal(scheduler.active, 0)
+assert.equal(scheduler.queued, 0)
+assert.ok(!started.includes('d1'))
+
+console.log('Run scheduler tests passed')
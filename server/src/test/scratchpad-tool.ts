@@ -0,0 +1,46 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { registerScratchpadTools } from '../tools/scratchpad.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'scratchpad-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'scratchpad.db')))
+  const registry = new ToolRegistryImpl()
+  registerScratchpadTools(registry, repos.scratchpad)
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Scheduling' })
+  const other = await repos.sessions.create({ userId: user.id, title: 'Other' })
+  const ctx = { agent_id: 'agent', session_id: session.id, signal: new AbortController().signal }
+  const otherCtx = { ...ctx, session_id: other.id }
+
+  // Notes are replaced, appended to, and read back by key or all at once
+  assert.equal((await registry.execute('scratchpad.write', { key: 'slots', value: 'Tue 10:00' }, ctx)).ok, true)
+  await registry.execute('scratchpad.write', { key: 'slots', value: 'Wed 14:00', append: true }, ctx)
+  await registry.execute('scratchpad.write', { key: 'attendees', value: 'Ana, Ben' }, ctx)
+  const slots = await registry.execute('scratchpad.read', { key: 'slots' }, ctx)
+  assert.deepEqual((slots.output as { value: string }).value, 'Tue 10:00\nWed 14:00')
+  const all = await registry.execute('scratchpad.read', {}, ctx)
+  assert.deepEqual((all.output as { notes: Array<{ key: string }> }).notes.map((note) => note.key), ['attendees', 'slots'])
+
+  // Each conversation has its own pad
+  const elsewhere = await registry.execute('scratchpad.read', { key: 'slots' }, otherCtx)
+  assert.equal((elsewhere.output as { found: boolean }).found, false)
+
+  // An empty value removes the key; bad keys and oversized notes are refused
+  const removed = await registry.execute('scratchpad.write', { key: 'attendees', value: '' }, ctx)
+  assert.equal((removed.output as { removed: boolean }).removed, true)
+  assert.match(String((await registry.execute('scratchpad.write', { key: '../x', value: 'a' }, ctx)).error), /key must be/)
+  assert.match(String((await registry.execute('scratchpad.write', { key: 'big', value: 'x'.repeat(8_001) }, ctx)).error), /limit is 8000/)
+
+  // Deleting the session deletes its notes
+  await repos.sessions.delete(session.id)
+  assert.deepEqual(await repos.scratchpad.list(session.id), [])
+
+  console.log('Scratchpad tool tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -6,7 +6,7 @@ import { ToolRegistryImpl } from '../tools/registry.js'
 import { registerFileTools } from '../tools/files.js'
 import { registerSearchTools } from '../tools/search.js'
 import { registerNoteTools } from '../tools/notes.js'
-import { materializeTextOutput } from '../orchestrator/output.js'
+import { InlineOutputBudget, materializeTextOutput } from '../orchestrator/output.js'
 import { deleteSessionFiles, resolveManagedFilePath } from '../tools/path-policy.js'
 
 const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), 'session-files-test-'))
@@ -126,6 +126,48 @@ const artifactSearchOutput = artifactSearch.output as { count: number; matches:
 assert.equal(artifactSearchOutput.count, 1)
 assert.equal(artifactSearchOutput.matches[0].path, artifactRef)
 
+// Spilled JSON goes through a temp file; one cut short by a crash is moved aside on read
+const jsonNotice = await materializeTextOutput(JSON.stringify({ rows: ['a', 'b'] }), {
+  sessionFilesRoot,
+  inlineLimitBytes: 1,
+  sessionId,
+  agentId,
+  callId: 'call-json',
+  toolName: 'rows',
+  extension: 'json',
+})
+const jsonRef = jsonNotice.match(/artifact:\/\/\S+/)?.[0]
+assert.equal((await registry.execute('files.read', { path: jsonRef }, ctx)).ok, true)
+const agentArtifactsDir = path.join(sessionFilesRoot, sessionId, 'artifacts', agentId)
+assert.ok(!(await fs.readdir(agentArtifactsDir)).some((name) => name.endsWith('.tmp')))
+const truncatedPath = path.join(agentArtifactsDir, 'call-cut-rows.json')
+await fs.writeFile(truncatedPath, '{"rows": ["a", "b')
+const truncatedRead = await registry.execute('files.read', { path: 'artifact://agent-123/call-cut-rows.json' }, ctx)
+assert.equal(truncatedRead.ok, false)
+assert.match(String(truncatedRead.error), /incomplete/)
+await assert.rejects(() => fs.stat(truncatedPath), /ENOENT/)
+await fs.stat(`${truncatedPath}.corrupt`)
+
+// Outputs that fit on their own still spill once the session's inline total passes its budget
+const budget = new InlineOutputBudget(10)
+const budgeted = (callId: string, text: string, session = sessionId) => materializeTextOutput(text, {
+  sessionFilesRoot,
+  sessionBudget: budget,
+  sessionId: session,
+  agentId,
+  callId,
+  toolName: 'lookup',
+})
+assert.equal(await budgeted('call-b1', 'abcdef'), 'abcdef')
+const overBudget = await budgeted('call-b2', 'ghijkl')
+assert.match(overBudget, /^reason: session_inline_budget_exceeded$/m)
+assert.equal(overBudget.match(/artifact:\/\/\S+/)?.[0], 'artifact://agent-123/call-b2-lookup.txt')
+assert.notEqual(await budgeted('call-b3', 'x'), 'x', 'the session stays over budget')
+assert.equal(await budgeted('call-b4', 'abcdef', 'other-session'), 'abcdef')
+budget.forget(sessionId)
+assert.equal(await budgeted('call-b5', 'abc'), 'abc')
+assert.doesNotMatch(artifactNotice, /reason:/)
+
 const noteSave = await registry.execute('notes.save_research_note', {
   title: 'Logical Ref Note',
   markdown: '# Note\n\nnote needle\n\n## Sources\n- https://example.com/source\n',
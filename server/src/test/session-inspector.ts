@@ -0,0 +1,63 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { getInspectorSession, listInspectorSessions } from '../services/inspector.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'session-inspector-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'inspector.db')))
+  const runtime = { repositories: repos } as unknown as RuntimeContext
+  const config = { model: 'openai:gpt-4o', provider: 'openai', max_turns: 5, max_tool_calls_per_step: 5, tool_execution_timeout_ms: 1000 }
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const other = await repos.users.create({ apiKeyHash: 'other' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Research' })
+  const root = await repos.agents.create({ sessionId: session.id, task: 'Find flights', config })
+  await repos.agents.update(root.id, {
+    status: 'waiting',
+    waitingFor: [{ callId: 'call-2', type: 'approval', name: 'book' }],
+    turnCount: 2,
+  })
+
+  await repos.items.create({ agentId: root.id, type: 'message', role: 'user', content: 'Find flights', turnNumber: 1 })
+  await repos.items.create({ agentId: root.id, type: 'function_call', callId: 'call-1', name: 'delegate', arguments: '{"agent":"researcher"}', turnNumber: 1 })
+  const child = await repos.agents.create({ sessionId: session.id, parentId: root.id, sourceCallId: 'call-1', depth: 1, task: 'Search', config })
+  await repos.items.create({ agentId: root.id, type: 'function_call_output', callId: 'call-1', output: 'Found 3', durationMs: 42, turnNumber: 1 })
+  await repos.items.create({ agentId: root.id, type: 'message', role: 'assistant', content: 'Booking the cheapest.', turnNumber: 1 })
+  await repos.items.create({ agentId: root.id, type: 'message', role: 'user', content: 'Go ahead', turnNumber: 2 })
+  await repos.items.create({ agentId: root.id, type: 'function_call', callId: 'call-2', name: 'book', arguments: 'not json', turnNumber: 2 })
+
+  const summaries = await listInspectorSessions(runtime, user.id)
+  assert.equal(summaries.length, 1)
+  assert.equal(summaries[0].agentCount, 2)
+  assert.equal(summaries[0].rootPhase, 'awaiting_approval')
+
+  assert.equal(await getInspectorSession(runtime, other.id, session.id), null, 'other users cannot inspect the session')
+
+  const inspected = await getInspectorSession(runtime, user.id, session.id)
+  assert.ok(inspected)
+  assert.deepEqual(inspected.agents.map((agent) => agent.id), [root.id, child.id])
+  assert.deepEqual(inspected.exchanges.map((e) => [e.content, e.fromTurn, e.toTurn]), [['Find flights', 1, 1], ['Go ahead', 2, 2]])
+
+  const [first, second] = inspected.agents[0].turns
+  assert.equal(first.input, 'Find flights')
+  assert.equal(first.response, 'Booking the cheapest.')
+  assert.deepEqual(first.steps[0], {
+    callId: 'call-1',
+    name: 'delegate',
+    arguments: { agent: 'researcher' },
+    result: 'Found 3',
+    isError: null,
+    durationMs: 42,
+    childAgentId: child.id,
+  })
+  assert.equal(second.steps[0].arguments, 'not json')
+  assert.equal(second.steps[0].result, null, 'pending calls have no result yet')
+
+  console.log('session inspector tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
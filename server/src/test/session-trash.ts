@@ -0,0 +1,57 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { Hono } from 'hono'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { sessionRoutes } from '../routes/sessions.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'session-trash-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'trash.db')))
+  const runtime = { repositories: repos, config: { trashRetentionDays: 30 } } as unknown as RuntimeContext
+  const config = { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 }
+
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id })
+  const agent = await repos.agents.create({ sessionId: session.id, task: 'Chat', config })
+  const message = await repos.items.create({ agentId: agent.id, type: 'message', role: 'user', content: 'hello', turnNumber: 1 })
+
+  const app = new Hono<{ Variables: { userId: string } }>()
+  app.use('*', async (c, next) => {
+    c.set('userId', user.id)
+    await next()
+  })
+  app.route('/', sessionRoutes(runtime))
+  const request = (path: string, init?: RequestInit) => app.request(path, init)
+
+  assert.equal((await request(`/${session.id}`)).status, 200)
+  assert.equal((await request(`/${session.id}`, { method: 'DELETE' })).status, 200)
+
+  // Once trashed, the session and its messages are not served or edited
+  const gone = await request(`/${session.id}`)
+  assert.equal(gone.status, 404)
+  assert.deepEqual(await gone.json(), { error: `Session not found: ${session.id}` })
+  assert.equal((await request(`/${session.id}/stats`)).status, 404)
+  const edit = await request(`/${session.id}/messages/${message.id}`, {
+    method: 'PATCH',
+    body: JSON.stringify({ content: 'edited' }),
+    headers: { 'content-type': 'application/json' },
+  })
+  assert.equal(edit.status, 404)
+  assert.equal((await repos.items.getById(message.id))?.content, 'hello')
+
+  // The trash listing still shows it, and restoring brings it back whole
+  const trash = await (await request('/trash')).json() as { sessions: Array<{ id: string }> }
+  assert.deepEqual(trash.sessions.map((s) => s.id), [session.id])
+  assert.equal((await request(`/${session.id}/restore`, { method: 'POST' })).status, 200)
+  const restored = await request(`/${session.id}`)
+  assert.equal(restored.status, 200)
+  const body = await restored.json() as { items: Array<{ id: string }> }
+  assert.deepEqual(body.items.map((i) => i.id), [message.id])
+
+  console.log('session trash tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
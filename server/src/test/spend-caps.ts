@@ -0,0 +1,75 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { AgentEventEmitter } from '../events/emitter.js'
+import { classifyError } from '../orchestrator/errors.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { BUDGET_PREFERENCE_KEY, BudgetMonitor, capMatches, normalizeBudget, periodStart, SpendCapExceededError } from '../usage/budget.js'
+import { UsageTracker } from '../usage/tracker.js'
+
+assert.equal(periodStart('daily', Date.UTC(2025, 1, 17, 23, 59)), Date.UTC(2025, 1, 17))
+assert.equal(capMatches('anthropic', 'anthropic:claude-sonnet-4-5'), true)
+assert.equal(capMatches('anthropic:claude-opus-4-1', 'anthropic:claude-sonnet-4-5'), false)
+assert.equal(capMatches('openai', 'openrouter:openai/gpt-4o'), false)
+
+// Caps default to daily with no fallback, and are validated
+assert.deepEqual(normalizeBudget({}).caps, [])
+assert.deepEqual(normalizeBudget({ caps: [{ scope: ' Anthropic ', limitUsd: 5 } as never] }).caps, [
+  { scope: 'anthropic', period: 'daily', limitUsd: 5, fallbackModel: null },
+])
+assert.throws(() => normalizeBudget({ caps: [{ scope: 'openai' } as never] }), /caps\[0\]\.limitUsd/)
+assert.throws(() => normalizeBudget({ caps: [{ scope: 'openai', limitUsd: 1, period: 'hourly' } as never] }), /period/)
+assert.throws(() => normalizeBudget({ caps: [{ scope: 'openai', limitUsd: 1, fallbackModel: 'gpt-4o-mini' } as never] }), /provider:model/)
+assert.throws(() => normalizeBudget({ caps: [{ scope: 'openai', limitUsd: 1, fallbackModel: 'openai:gpt-4o-mini' } as never] }), /same cap/)
+
+const dir = mkdtempSync(join(tmpdir(), 'spend-caps-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'caps.db')))
+  const budget = new BudgetMonitor(repos.usage, repos.preferences, new AgentEventEmitter(), repos.sessions)
+  const tracker = new UsageTracker(repos.usage, repos.sessions, budget)
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id })
+  const agent = await repos.agents.create({
+    sessionId: session.id,
+    task: 'Spend money',
+    config: { model: 'anthropic:claude-sonnet-4-5', provider: 'anthropic', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 },
+  })
+
+  await repos.preferences.set(BUDGET_PREFERENCE_KEY, JSON.stringify({
+    caps: [
+      { scope: 'anthropic', period: 'daily', limitUsd: 1, fallbackModel: 'openai:gpt-4o' },
+      { scope: 'openai:gpt-4o', period: 'daily', limitUsd: 1, fallbackModel: null },
+    ],
+  }))
+
+  // Under every cap the requested model is used as is
+  assert.deepEqual(await budget.resolveModel(agent, 'anthropic:claude-sonnet-4-5'), { model: 'anthropic:claude-sonnet-4-5', caps: [] })
+
+  // claude-sonnet-4-5 input is $3 per 1M tokens, so 400k tokens is $1.20 of the $1 cap
+  await tracker.record({ agent, provider: 'anthropic', model: 'claude-sonnet-4-5', usage: { input_tokens: 400_000, output_tokens: 0 } })
+  const [anthropic, gpt] = await budget.capStatus(user.id)
+  assert.equal(anthropic.spentUsd, 1.2)
+  assert.equal(anthropic.exceeded, true)
+  assert.equal(gpt.spentUsd, 0)
+
+  const switched = await budget.resolveModel(agent, 'anthropic:claude-sonnet-4-5')
+  assert.equal(switched.model, 'openai:gpt-4o')
+  assert.deepEqual(switched.caps.map((c) => c.scope), ['anthropic'])
+  assert.equal((await budget.resolveModel(agent, 'openai:gpt-4o-mini')).model, 'openai:gpt-4o-mini', 'other models are not capped')
+
+  // Once the fallback is capped too, the call is refused
+  await tracker.record({ agent, provider: 'openai', model: 'gpt-4o', usage: { input_tokens: 0, output_tokens: 100_000 } })
+  const refused = await budget.resolveModel(agent, 'anthropic:claude-sonnet-4-5').catch((err: unknown) => err)
+  assert.ok(refused instanceof SpendCapExceededError)
+  assert.deepEqual(refused.caps.map((c) => c.scope), ['anthropic', 'openai:gpt-4o'])
+  assert.equal(classifyError(refused), 'budget_exceeded')
+
+  // Caps reset with their period
+  const tomorrow = periodStart('daily', Date.now()) + 86_400_000
+  assert.equal((await budget.resolveModel(agent, 'anthropic:claude-sonnet-4-5', tomorrow)).model, 'anthropic:claude-sonnet-4-5')
+
+  console.log('spend cap tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,64 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { StalledRunError } from '../orchestrator/errors.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { StuckRunWatchdog } from '../services/stuck-runs.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'stuck-runs-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'stuck.db')))
+  const events: AgentEvent[] = []
+  const controllers = new Map<string, AbortController>()
+  const runtime = {
+    config: { stuckRunMultiple: 3, approvalTimeoutMs: 60_000 },
+    repositories: repos,
+    events: { emit: (event: AgentEvent) => events.push(event) },
+    agentAbortControllers: controllers,
+  } as unknown as RuntimeContext
+  const watchdog = new StuckRunWatchdog(runtime)
+
+  const config = { model: 'test', provider: 'test', max_turns: 5, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 60_000 }
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id, title: 'Stuck' })
+  const live = await repos.agents.create({ sessionId: session.id, task: 'live', config })
+  await repos.agents.update(live.id, { status: 'running' })
+  const orphan = await repos.agents.create({ sessionId: session.id, task: 'orphan', config })
+  await repos.agents.update(orphan.id, { status: 'running' })
+  const asking = await repos.agents.create({ sessionId: session.id, task: 'asking', config })
+  await repos.agents.update(asking.id, { status: 'waiting', waitingFor: [{ callId: 'c1', type: 'approval', name: 'shell.exec' }] })
+  const delegating = await repos.agents.create({ sessionId: session.id, task: 'delegating', config })
+  await repos.agents.update(delegating.id, { status: 'waiting', waitingFor: [{ callId: 'c2', type: 'agent', name: 'delegate' }] })
+  const run = new AbortController()
+  controllers.set(live.id, run)
+
+  // Nothing is past its limit yet: 10 minutes floor for runs, 3 minutes for a one-minute approval
+  assert.equal(await watchdog.sweep(Date.now() + 2 * 60_000), 0)
+
+  // Past the limits: the live run is aborted, the orphaned run and the unanswered approval are failed
+  const later = Date.now() + 11 * 60_000
+  assert.equal(await watchdog.sweep(later), 3)
+  assert.ok(run.signal.reason instanceof StalledRunError)
+  assert.equal((await repos.agents.getById(live.id))?.status, 'running', 'a live run records its own failure')
+  const failedOrphan = await repos.agents.getById(orphan.id)
+  assert.equal(failedOrphan?.status, 'failed')
+  assert.equal(failedOrphan?.errorCode, 'stalled')
+  assert.equal((await repos.agents.getById(asking.id))?.errorCode, 'approval_expired')
+  assert.equal((await repos.agents.getById(delegating.id))?.status, 'waiting', 'waits on children are left alone')
+  assert.deepEqual(
+    events.map((event) => event.type === EVENT_TYPES.AGENT_FAILED && event.agent_id),
+    [orphan.id, asking.id],
+  )
+
+  // A run that ignored the abort is failed on the next sweep and its registry entry released
+  assert.equal(await watchdog.sweep(later), 1)
+  assert.equal((await repos.agents.getById(live.id))?.errorCode, 'stalled')
+  assert.equal(controllers.size, 0)
+
+  console.log('stuck-run watchdog tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,94 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { SyncEngine } from '../services/sync.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'sync-engine-'))
+const syncDir = join(dir, 'shared')
+
+function openDevice(name: string) {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, `${name}.db`)))
+  const engine = new SyncEngine({
+    repository: repos.sync,
+    preferences: repos.preferences,
+    users: repos.users,
+    dir: syncDir,
+    deviceId: name,
+  })
+  return { repos, engine }
+}
+
+try {
+  const laptop = openDevice('laptop')
+  const desktop = openDevice('desktop')
+  const laptopUser = await laptop.repos.users.create({ apiKeyHash: 'hash-laptop' })
+  const desktopUser = await desktop.repos.users.create({ apiKeyHash: 'hash-desktop' })
+
+  const session = await laptop.repos.sessions.create({ userId: laptopUser.id, title: 'Trip planning' })
+  const agent = await laptop.repos.agents.create({
+    sessionId: session.id,
+    task: 'Plan a trip',
+    config: { model: 'test', provider: 'test', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 },
+  })
+  await laptop.repos.items.create({ agentId: agent.id, type: 'message', role: 'user', content: 'Hello', turnNumber: 1 })
+  await laptop.repos.preferences.set('response_language', 'pl')
+
+  const first = await laptop.engine.runOnce()
+  assert.equal(first.exported, 4)
+
+  const pulled = await desktop.engine.runOnce()
+  assert.equal(pulled.imported, 4)
+  const mirrored = await desktop.repos.sessions.getById(session.id)
+  assert.equal(mirrored?.title, 'Trip planning')
+  assert.equal(mirrored?.userId, desktopUser.id, 'imported sessions are re-owned by the local user')
+  assert.equal((await desktop.repos.items.listBySession(session.id)).length, 1)
+  assert.equal(await desktop.repos.preferences.get('response_language'), 'pl')
+  assert.notEqual(await desktop.repos.preferences.get('sync:export_cursor'), null)
+
+  // Re-reading the same log is idempotent.
+  assert.equal((await desktop.engine.runOnce()).imported, 0)
+
+  // Last writer wins per record.
+  await new Promise((resolve) => setTimeout(resolve, 5))
+  await desktop.repos.sessions.update(session.id, { title: 'Trip to Kraków' })
+  await desktop.engine.runOnce()
+  await laptop.engine.runOnce()
+  assert.equal((await laptop.repos.sessions.getById(session.id))?.title, 'Trip to Kraków')
+
+  // Edited messages carry their edit time and replace the older copy.
+  await new Promise((resolve) => setTimeout(resolve, 5))
+  const [message] = await desktop.repos.items.listBySession(session.id)
+  await desktop.repos.messageRevisions.revise(message, session.id, 'edit', { content: 'Hello there', contentBlocks: null })
+  await desktop.engine.runOnce()
+  await laptop.engine.runOnce()
+  assert.equal((await laptop.repos.items.listBySession(session.id))[0].content, 'Hello there')
+
+  // Rows written in the millisecond of the last export are still sent, and only once.
+  await laptop.engine.runOnce()
+  const realNow = Date.now
+  const frozen = realNow() + 10
+  Date.now = () => frozen
+  try {
+    await laptop.repos.items.create({ agentId: agent.id, type: 'message', role: 'assistant', content: 'First', turnNumber: 1 })
+    assert.equal((await laptop.engine.runOnce()).exported, 1)
+    await laptop.repos.items.create({ agentId: agent.id, type: 'message', role: 'user', content: 'Second', turnNumber: 2 })
+    assert.equal((await laptop.engine.runOnce()).exported, 1)
+  } finally {
+    Date.now = realNow
+  }
+  await desktop.engine.runOnce()
+  assert.deepEqual((await desktop.repos.items.listBySession(session.id)).map((item) => item.content), ['Hello there', 'First', 'Second'])
+
+  // Tombstones propagate deletes.
+  await new Promise((resolve) => setTimeout(resolve, 5))
+  await laptop.repos.sessions.delete(session.id)
+  await laptop.engine.recordDeletion('session', session.id)
+  await desktop.engine.runOnce()
+  assert.equal(await desktop.repos.sessions.getById(session.id), null)
+
+  console.log('sync engine tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
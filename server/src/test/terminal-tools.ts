@@ -0,0 +1,75 @@
+import assert from 'node:assert/strict'
+import { mkdirSync, mkdtempSync, realpathSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { MAX_TERMINALS_PER_SESSION, TerminalManager } from '../services/terminals.js'
+import { registerTerminalTools } from '../tools/terminal.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+type Output = { output: string; running: boolean; exit_code?: number; cwd: string }
+
+const dir = realpathSync(mkdtempSync(join(tmpdir(), 'terminal-')))
+const terminals = new TerminalManager()
+try {
+  mkdirSync(join(dir, 'app'))
+  const registry = new ToolRegistryImpl()
+  registerTerminalTools(registry, terminals)
+  const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+
+  // Every command needs approval; opening and reading do not
+  assert.equal(registry.getMetadata('terminal.run')?.requires_approval, true)
+  assert.equal(registry.getMetadata('terminal.open')?.requires_approval, false)
+  assert.equal(registry.getMetadata('terminal.read_output')?.requires_approval, false)
+
+  const opened = await registry.execute('terminal.open', { working_dir: dir }, ctx)
+  const id = (opened.output as { terminal: { id: string } }).terminal.id
+  assert.deepEqual(registry.getPreview('terminal.run', { terminal_id: id, command: 'npm test' }, ctx), {
+    summary: `Execute in ${id}: npm test`,
+    details: { working_dir: dir },
+  })
+
+  // The directory and environment carry over between commands
+  const cd = (await registry.execute('terminal.run', { terminal_id: id, command: 'cd app && export GREETING="hi there"' }, ctx)).output as Output
+  assert.deepEqual(cd, { output: '', running: false, exit_code: 0, cwd: join(dir, 'app') })
+  const echoed = (await registry.execute('terminal.run', { terminal_id: id, command: 'echo "$GREETING from $(basename "$PWD")"; echo oops >&2' }, ctx)).output as Output
+  assert.equal(echoed.output, 'hi there from app\noops\n')
+  assert.equal(echoed.exit_code, 0)
+
+  // Failures report their exit code; quotes and syntax errors do not break the shell
+  assert.equal(((await registry.execute('terminal.run', { terminal_id: id, command: 'exit_status() { return 3; }; exit_status' }, ctx)).output as Output).exit_code, 3)
+  const quoted = (await registry.execute('terminal.run', { terminal_id: id, command: `printf '%s\\n' 'it'"'"'s fine'` }, ctx)).output as Output
+  assert.equal(quoted.output, "it's fine\n")
+  const broken = (await registry.execute('terminal.run', { terminal_id: id, command: 'if then' }, ctx)).output as Output
+  assert.notEqual(broken.exit_code, 0)
+  assert.equal(((await registry.execute('terminal.run', { terminal_id: id, command: 'echo still here' }, ctx)).output as Output).output, 'still here\n')
+
+  // Commands cannot wait for input
+  assert.equal(((await registry.execute('terminal.run', { terminal_id: id, command: 'cat; echo done' }, ctx)).output as Output).output, 'done\n')
+
+  // A long command keeps running after the wait; its output is read later
+  const slow = (await registry.execute('terminal.run', { terminal_id: id, command: 'echo started; sleep 0.5; echo finished', wait_ms: 100 }, ctx)).output as Output
+  assert.deepEqual(slow, { output: 'started\n', running: true, cwd: join(dir, 'app') })
+  assert.match(String((await registry.execute('terminal.run', { terminal_id: id, command: 'echo too soon' }, ctx)).error), /still running "echo started/)
+  const rest = (await registry.execute('terminal.read_output', { terminal_id: id, wait_ms: 5000 }, ctx)).output as Output
+  assert.deepEqual(rest, { output: 'finished\n', running: false, exit_code: 0, cwd: join(dir, 'app') })
+
+  // Terminals belong to their conversation
+  const other = { ...ctx, session_id: 'other' }
+  assert.match(String((await registry.execute('terminal.read_output', { terminal_id: id }, other)).error), /No open terminal/)
+  for (let i = 0; i < MAX_TERMINALS_PER_SESSION; i++) assert.equal((await registry.execute('terminal.open', {}, other)).ok, true)
+  assert.match(String((await registry.execute('terminal.open', {}, other)).error), /At most 4 terminals/)
+  assert.match(String((await registry.execute('terminal.open', { working_dir: join(dir, 'missing') }, ctx)).error), /Not a directory/)
+
+  // A shell that exits, or is closed, takes no more commands
+  const exited = (await registry.execute('terminal.run', { terminal_id: id, command: 'exit 4' }, ctx)).output as Output
+  assert.equal(exited.exit_code, 4)
+  assert.match(String((await registry.execute('terminal.run', { terminal_id: id, command: 'pwd' }, ctx)).error), /has exited/)
+  const second = ((await registry.execute('terminal.open', {}, ctx)).output as { terminal: { id: string } }).terminal.id
+  assert.deepEqual((await registry.execute('terminal.close', { terminal_id: second }, ctx)).output, { closed: second })
+  assert.match(String((await registry.execute('terminal.run', { terminal_id: second, command: 'pwd' }, ctx)).error), /No open terminal/)
+
+  console.log('Terminal tool tests passed')
+} finally {
+  terminals.closeAll()
+  rmSync(dir, { recursive: true, force: true })
+}
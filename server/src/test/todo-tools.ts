@@ -0,0 +1,96 @@
+import assert from 'node:assert/strict'
+import { RemindersBackend, TodoistBackend } from '../services/todos.js'
+import { registerTodoTools } from '../tools/todos.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const json = (value: unknown, status = 200) => new Response(JSON.stringify(value), { status, headers: { 'content-type': 'application/json' } })
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+
+// Todoist: filters, project names and batch creation
+const todoist: Array<{ method: string; path: string; query: string | null; body: Record<string, unknown> | undefined }> = []
+let nextId = 100
+const todoistFetch = (async (input: string | URL | Request, init?: RequestInit) => {
+  const url = new URL(String(input))
+  const method = init?.method ?? 'GET'
+  const body = init?.body ? JSON.parse(String(init.body)) as Record<string, unknown> : undefined
+  todoist.push({ method, path: url.pathname, query: url.searchParams.get('query'), body })
+  if (url.pathname === '/api/v1/projects') return json({ results: [{ id: 'p1', name: 'Inbox' }, { id: 'p2', name: 'Work' }], next_cursor: null })
+  if (url.pathname === '/api/v1/tasks/filter') {
+    return json({ results: [{ id: '1', content: 'Reply to Ana', due: { date: '2026-10-18' }, priority: 4, project_id: 'p2' }], next_cursor: null })
+  }
+  if (url.pathname === '/api/v1/tasks' && method === 'POST') {
+    if (body?.content === 'Broken') return new Response('Invalid due string', { status: 400 })
+    return json({ id: String(nextId++), content: body?.content, description: body?.description ?? '', due: null, priority: body?.priority ?? 1, project_id: body?.project_id ?? 'p1' })
+  }
+  if (url.pathname === '/api/v1/tasks/1') return json({ id: '1', content: 'Reply to Ana' })
+  if (url.pathname === '/api/v1/tasks/1/close') return new Response(null, { status: 204 })
+  return new Response('Not found', { status: 404 })
+}) as typeof fetch
+
+const registry = new ToolRegistryImpl()
+registerTodoTools(registry, new TodoistBackend('token', todoistFetch))
+const listed = await registry.execute('todos.list', { project: 'Work', filter: 'today | overdue' }, ctx)
+assert.deepEqual((listed.output as { todos: unknown[] }).todos, [{
+  id: '1', title: 'Reply to Ana', notes: '', due: '2026-10-18', priority: 4, project: 'Work', url: 'https://app.todoist.com/app/task/1',
+}])
+assert.equal(todoist.find((request) => request.path === '/api/v1/tasks/filter')?.query, '(today | overdue) & #Work')
+
+// A whole thread's action items go in with one call
+assert.equal(registry.getMetadata('todos.create')?.requires_approval, false)
+assert.deepEqual(registry.getPreview('todos.create', { todos: [{ title: 'A' }, { title: 'B' }] }, ctx), {
+  summary: 'Add 2 todos to Todoist',
+  details: { titles: ['A', 'B'] },
+})
+const created = await registry.execute('todos.create', {
+  todos: [
+    { title: 'Send the contract', due: 'friday', priority: 9, project: 'work' },
+    { title: 'Book the venue', notes: 'From the thread with Ana' },
+  ],
+}, ctx)
+assert.deepEqual((created.output as { created: Array<{ title: string; project: string }> }).created.map((todo) => [todo.title, todo.project]), [
+  ['Send the contract', 'Work'],
+  ['Book the venue', 'Inbox'],
+])
+assert.deepEqual(todoist.filter((request) => request.method === 'POST').map((request) => request.body), [
+  { content: 'Send the contract', due_string: 'friday', priority: 4, project_id: 'p2' },
+  { content: 'Book the venue', description: 'From the thread with Ana' },
+])
+// The projects list is fetched once
+assert.equal(todoist.filter((request) => request.path === '/api/v1/projects').length, 1)
+
+// A failure part-way says what was already added
+const partial = await registry.execute('todos.create', { todos: [{ title: 'First' }, { title: 'Broken' }, { title: 'Never' }] }, ctx)
+assert.match(String(partial.error), /Added 1 of 3 todos, then failed on "Broken"/)
+assert.equal((partial.output as { created: unknown[] }).created.length, 1)
+assert.match(String((await registry.execute('todos.create', { todos: [{ title: 'x', project: 'Nope' }] }, ctx)).error), /project not found/)
+assert.match(String((await registry.execute('todos.create', { todos: [] }, ctx)).error), /non-empty array/)
+
+assert.equal(registry.getMetadata('todos.complete')?.requires_approval, true)
+const done = await registry.execute('todos.complete', { id: '1' }, ctx)
+assert.deepEqual(done.output, { id: '1', title: 'Reply to Ana', completed: true })
+
+// Reminders: scripts get their input through argv and priorities are mapped
+const scripts: Array<{ script: string; args: string[] }> = []
+const reminders = new ToolRegistryImpl()
+registerTodoTools(reminders, new RemindersBackend(async (script, args) => {
+  scripts.push({ script, args })
+  if (script.includes('whose({ completed: false })')) {
+    return JSON.stringify([{ id: 'x-apple-reminder://1', title: 'Water plants', notes: '', due: null, priority: 1, list: 'Home' }])
+  }
+  if (script.includes('app.Reminder(')) {
+    const input = JSON.parse(args[0]) as { title: string; due: string; priority: number; list: string }
+    return JSON.stringify({ id: 'x-apple-reminder://2', title: input.title, notes: '', due: input.due, priority: input.priority, list: input.list })
+  }
+  return JSON.stringify({ id: args[0], title: 'Water plants' })
+}))
+assert.equal('filter' in (reminders.getMetadata('todos.list')!.parameters.properties as object), false)
+const home = await reminders.execute('todos.list', { project: 'Home', limit: 5 }, ctx)
+assert.deepEqual((home.output as { todos: Array<{ priority: number; project: string }> }).todos.map((todo) => [todo.priority, todo.project]), [[4, 'Home']])
+assert.deepEqual(scripts[0].args, ['Home', '5'])
+const added = await reminders.execute('todos.create', { todos: [{ title: 'Call "Mom"; rm -rf', due: '2026-10-20T17:00:00Z', priority: 3, project: 'Home' }] }, ctx)
+assert.equal((added.output as { created: Array<{ priority: number }> }).created[0].priority, 3)
+assert.deepEqual(JSON.parse(scripts[1].args[0]), { title: 'Call "Mom"; rm -rf', due: '2026-10-20T17:00:00.000Z', priority: 5, list: 'Home' })
+assert.doesNotMatch(scripts[1].script, /Mom/)
+assert.match(String((await reminders.execute('todos.create', { todos: [{ title: 'x', due: 'tomorrow' }] }, ctx)).error), /ISO date/)
+
+console.log('Todo tool tests passed')
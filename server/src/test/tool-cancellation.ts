@@ -0,0 +1,85 @@
+import assert from 'node:assert/strict'
+import { classifyToolError } from '../orchestrator/errors.js'
+import { stepDeadline, ToolRegistryImpl, toolCallLimits } from '../tools/registry.js'
+import { registerShellTools } from '../tools/shell.js'
+
+const registry = new ToolRegistryImpl()
+let calls = 0
+registry.register({
+  metadata: { name: 'stuck', description: 'Ignores its signal', parameters: { type: 'object', properties: {} }, requires_approval: false },
+  async handle() {
+    calls++
+    return new Promise(() => {})
+  },
+})
+registry.register({
+  metadata: { name: 'quick', description: 'Answers at once', parameters: { type: 'object', properties: {} }, requires_approval: false },
+  async handle() {
+    calls++
+    return { ok: true, output: 'done' }
+  },
+})
+const ctx = (signal: AbortSignal) => ({ agent_id: 'agent', session_id: 'session', signal })
+
+// A handler that ignores its signal no longer holds up the run
+const controller = new AbortController()
+const pending = registry.execute('stuck', {}, ctx(controller.signal))
+setTimeout(() => controller.abort(), 10)
+assert.deepEqual(await pending, { ok: false, error: 'Tool execution aborted' })
+
+// Timeouts say so, and are classified as timeouts
+// (AbortSignal.timeout's timer would not keep the test alive, so its abort is simulated)
+const deadline = new AbortController()
+const late = registry.execute('stuck', {}, ctx(deadline.signal))
+setTimeout(() => deadline.abort(new DOMException('signal timed out', 'TimeoutError')), 10)
+const timedOut = await late
+assert.deepEqual(timedOut, { ok: false, error: 'Tool execution timed out' })
+assert.equal(classifyToolError(timedOut, deadline.signal), 'tool_timeout')
+
+// A call whose signal is already aborted does not start
+calls = 0
+assert.deepEqual(await registry.execute('quick', {}, ctx(AbortSignal.abort())), { ok: false, error: 'Tool execution aborted' })
+assert.equal(calls, 0)
+assert.deepEqual(await registry.execute('quick', {}, ctx(new AbortController().signal)), { ok: true, output: 'done' })
+
+// Per-call limits carry the run's cancellation through to the handler
+const run = new AbortController()
+const limits = toolCallLimits(60_000, run.signal)
+assert.ok(limits.deadline! > Date.now() + 59_000)
+let observed = false
+registry.register({
+  metadata: { name: 'polite', description: 'Stops when told', parameters: { type: 'object', properties: {} }, requires_approval: false },
+  async handle(_args, toolCtx) {
+    return new Promise((resolve) => toolCtx.signal.addEventListener('abort', () => {
+      observed = true
+      resolve({ ok: false, error: 'stopped' })
+    }))
+  },
+})
+const polite = registry.execute('polite', {}, { agent_id: 'agent', session_id: 'session', ...limits })
+setTimeout(() => run.abort(), 10)
+assert.deepEqual(await polite, { ok: false, error: 'Tool execution aborted' })
+assert.ok(observed)
+
+// A step deadline shortens each call's timeout, and a call left after it passes does not start
+const stepEnd = Date.now() + 1_000
+assert.ok(toolCallLimits(60_000, undefined, stepEnd).deadline! <= stepEnd + 5)
+assert.equal(stepDeadline({}), undefined)
+calls = 0
+const overdue = await registry.execute('quick', {}, { ...ctx(new AbortController().signal), deadline: Date.now() - 1 })
+assert.equal(overdue.ok, false)
+assert.match(overdue.error!, /step deadline passed/)
+assert.equal(classifyToolError(overdue), 'tool_timeout')
+assert.equal(calls, 0)
+
+// A shell command is killed at the call's deadline even when it asked for longer
+registerShellTools(registry)
+const started = Date.now()
+const shell = await registry.execute('shell.exec', { command: 'sleep 5', timeout_ms: 60_000 }, {
+  ...ctx(new AbortController().signal),
+  deadline: Date.now() + 50,
+})
+assert.equal(shell.error, 'Command timed out')
+assert.ok(Date.now() - started < 2000)
+
+console.log('Tool cancellation tests passed')
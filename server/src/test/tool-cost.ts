@@ -0,0 +1,78 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { LLMMessage } from '../providers/types.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { attributeToolContext, buildToolCostReport, estimateToolContext } from '../usage/attribution.js'
+import { UsageTracker } from '../usage/tracker.js'
+
+// Tool results are credited to the tool that produced them
+const messages: LLMMessage[] = [
+  { role: 'system', content: 's'.repeat(400) },
+  { role: 'user', content: 'u'.repeat(200) },
+  { role: 'assistant', content: '', tool_calls: [
+    { call_id: 'c1', name: 'files.read', arguments: {} },
+    { call_id: 'c2', name: 'mcp.github_1a2b3c4d.search', arguments: {} },
+  ] },
+  { role: 'tool', tool_call_id: 'c1', content: 'f'.repeat(800) },
+  { role: 'tool', tool_call_id: 'c2', content: [{ type: 'text', text: 'g'.repeat(400) }, { type: 'image', media_type: 'image/png', data: 'x' }] },
+  { role: 'tool', tool_call_id: 'orphan', content: 'o'.repeat(40) },
+]
+const estimate = estimateToolContext({ messages })
+assert.equal(estimate.byTool.get('files.read'), 200)
+assert.equal(estimate.byTool.get('mcp.github_1a2b3c4d.search'), 1_600)
+assert.equal(estimate.byTool.get('(unknown)'), 10)
+assert.equal(estimate.total, 100 + 50 + 2 + 200 + 1_600 + 10)
+
+const shares = attributeToolContext({ total: 1_000, byTool: new Map([['files.read', 250], ['web.fetch', 500]]) }, 4_000, 0.02)
+assert.deepEqual(shares, {
+  'files.read': { tokens: 1_000, costUsd: 0.005 },
+  'web.fetch': { tokens: 2_000, costUsd: 0.01 },
+})
+assert.equal(attributeToolContext({ total: 100, byTool: new Map() }, 100, 1), null, 'no tool results, nothing to attribute')
+
+// The report ranks tools and rolls them up per integration
+const report = buildToolCostReport([
+  { sessionId: 's1', costUsd: 0.03, toolContext: { 'files.read': { tokens: 1_000, costUsd: 0.005 }, 'web.fetch': { tokens: 2_000, costUsd: 0.01 } } },
+  { sessionId: 's2', costUsd: 0.02, toolContext: { 'files.list': { tokens: 500, costUsd: 0.002 }, 'web.fetch': { tokens: 3_000, costUsd: 0.012 } } },
+  { sessionId: 's2', costUsd: 0.01, toolContext: null },
+].map((r) => r as unknown as Parameters<typeof buildToolCostReport>[0][number]))
+assert.equal(report.totalCost, 0.06)
+assert.deepEqual(report.tools.map((t) => [t.tool, t.calls, t.sessions, t.tokens, t.cost]), [
+  ['web.fetch', 2, 2, 5_000, 0.022],
+  ['files.read', 1, 1, 1_000, 0.005],
+  ['files.list', 1, 1, 500, 0.002],
+])
+assert.deepEqual(report.integrations.map((g) => [g.integration, g.tools, g.sessions, g.cost]), [
+  ['web', ['web.fetch'], 2, 0.022],
+  ['files', ['files.list', 'files.read'], 2, 0.007],
+])
+
+// The tracker stores the split alongside each call
+const dir = mkdtempSync(join(tmpdir(), 'tool-cost-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'usage.db')))
+  const tracker = new UsageTracker(repos.usage, repos.sessions)
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id })
+  const agent = await repos.agents.create({
+    sessionId: session.id,
+    task: 'Read files',
+    config: { model: 'gpt-4o', provider: 'openai', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 },
+  })
+
+  await tracker.record({ agent, provider: 'openai', model: 'gpt-4o', usage: { input_tokens: 2_012, output_tokens: 0 }, request: { messages } })
+  await tracker.record({ agent, provider: 'openai', model: 'gpt-4o', usage: { input_tokens: 10, output_tokens: 0 } })
+  const recorded = await repos.usage.list({ userId: user.id })
+  const withTools = recorded.find((r) => r.inputTokens === 2_012)!
+  const withoutRequest = recorded.find((r) => r.inputTokens === 10)!
+  // gpt-4o input is $2.50 per 1M tokens; files.read is 200 of the 1,962 estimated tokens
+  assert.equal(withTools.toolContext?.['files.read'].tokens, 205)
+  assert.equal(withTools.toolContext?.['files.read'].costUsd, 0.000513)
+  assert.equal(withoutRequest.toolContext, null)
+
+  console.log('tool cost tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
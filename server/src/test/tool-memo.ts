@@ -0,0 +1,83 @@
+import assert from 'node:assert/strict'
+import { ToolMemo } from '../tools/memo.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+import { materializeToolOutput } from '../orchestrator/output.js'
+
+const registry = new ToolRegistryImpl()
+let lookups = 0
+registry.register({
+  metadata: {
+    name: 'lookup',
+    description: 'Looks something up',
+    parameters: { type: 'object' },
+    requires_approval: false,
+    category: 'read_only',
+    memoize: true,
+  },
+  handle: async (args) => {
+    lookups += 1
+    if (args.fail) return { ok: false, error: 'not found' }
+    return { ok: true, output: { call: lookups } }
+  },
+})
+registry.register({
+  metadata: {
+    name: 'update',
+    description: 'Changes something',
+    parameters: { type: 'object' },
+    requires_approval: false,
+    category: 'mutating',
+  },
+  handle: async () => ({ ok: true, output: { updated: true } }),
+})
+
+const ctx = (sessionId: string) => ({
+  agent_id: 'agent-1',
+  session_id: sessionId,
+  signal: new AbortController().signal,
+})
+
+// Same args in a different key order hit the remembered result
+const first = await registry.execute('lookup', { q: 'x', page: 1 }, ctx('s1'))
+const repeat = await registry.execute('lookup', { page: 1, q: 'x' }, ctx('s1'))
+assert.equal(lookups, 1)
+assert.deepEqual(repeat.output, first.output)
+assert.equal(first.memoized, undefined)
+assert.equal(repeat.memoized, true)
+const text = await materializeToolOutput(repeat, {
+  sessionFilesRoot: '/unused',
+  sessionId: 's1',
+  agentId: 'agent-1',
+  callId: 'call-1',
+  toolName: 'lookup',
+})
+assert.match(text, /not run again/)
+
+// Other sessions and other args run the tool
+await registry.execute('lookup', { q: 'x', page: 1 }, ctx('s2'))
+await registry.execute('lookup', { q: 'y', page: 1 }, ctx('s1'))
+assert.equal(lookups, 3)
+
+// Failures are not remembered
+await registry.execute('lookup', { q: 'z', fail: true }, ctx('s1'))
+await registry.execute('lookup', { q: 'z', fail: true }, ctx('s1'))
+assert.equal(lookups, 5)
+
+// A successful write forgets the session's results
+assert.equal((await registry.execute('update', {}, ctx('s1'))).ok, true)
+const afterWrite = await registry.execute('lookup', { q: 'x', page: 1 }, ctx('s1'))
+assert.equal(lookups, 6)
+assert.equal(afterWrite.memoized, undefined)
+
+// Expired results and emptied sessions are dropped on the next write, not only when read again
+const memo = new ToolMemo(20)
+memo.set('old-1', 'lookup', { q: 'x' }, { ok: true, output: 1 })
+memo.set('old-2', 'lookup', { q: 'x' }, { ok: true, output: 2 })
+assert.equal(memo.sessionCount, 2)
+await new Promise((resolve) => setTimeout(resolve, 30))
+memo.set('new', 'lookup', { q: 'x' }, { ok: true, output: 3 })
+assert.equal(memo.sessionCount, 1)
+assert.deepEqual(memo.get('new', 'lookup', { q: 'x' }), { ok: true, output: 3 })
+assert.equal(memo.get('old-1', 'lookup', { q: 'x' }), undefined)
+
+console.log('Tool memo tests passed')
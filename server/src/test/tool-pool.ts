@@ -0,0 +1,63 @@
+import assert from 'node:assert/strict'
+import { ToolPool } from '../orchestrator/tool-pool.js'
+
+function deferred() {
+  let resolve!: () => void
+  const promise = new Promise<void>((done) => { resolve = done })
+  return { promise, resolve }
+}
+const tick = () => new Promise((resolve) => setImmediate(resolve))
+
+const pool = new ToolPool({ size: 3, perSession: 2 })
+const started: string[] = []
+const gates = new Map<string, ReturnType<typeof deferred>>()
+const call = (sessionId: string, name: string, signal?: AbortSignal) => {
+  const gate = deferred()
+  gates.set(name, gate)
+  return pool.run(sessionId, async () => {
+    started.push(name)
+    await gate.promise
+    return name
+  }, signal)
+}
+
+// One session's wide batch gets at most its share; another session still gets a slot
+const a1 = call('a', 'a1')
+const a2 = call('a', 'a2')
+const a3 = call('a', 'a3')
+const b1 = call('b', 'b1')
+const b2 = call('b', 'b2')
+await tick()
+assert.deepEqual(started, ['a1', 'a2', 'b1'])
+assert.equal(pool.active, 3)
+assert.equal(pool.queued, 2)
+
+// Freed slots go to the oldest call whose session has room
+gates.get('b1')!.resolve()
+assert.equal(await b1, 'b1')
+await tick()
+assert.deepEqual(started, ['a1', 'a2', 'b1', 'b2'])
+gates.get('a1')!.resolve()
+await a1
+await tick()
+assert.deepEqual(started, ['a1', 'a2', 'b1', 'b2', 'a3'])
+
+// A call cancelled while queued leaves the queue and never runs
+const controller = new AbortController()
+const c1 = call('c', 'c1', controller.signal)
+await tick()
+assert.equal(pool.queued, 1)
+controller.abort()
+await assert.rejects(c1, { name: 'AbortError' })
+assert.equal(pool.queued, 0)
+
+// Failures free their slot too
+const failing = pool.run('c', async () => { throw new Error('boom') })
+gates.get('a2')!.resolve()
+await assert.rejects(failing, /boom/)
+for (const name of ['a3', 'b2']) gates.get(name)!.resolve()
+await Promise.all([a2, a3, b2])
+assert.equal(pool.active, 0)
+assert.ok(!started.includes('c1'))
+
+console.log('Tool pool tests passed')
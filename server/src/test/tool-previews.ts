@@ -0,0 +1,67 @@
+import assert from 'node:assert/strict'
+import fs from 'fs/promises'
+import os from 'os'
+import path from 'path'
+import { unifiedDiff } from '../tools/diff.js'
+import { registerFileTools } from '../tools/files.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+// Unified diffs: new files, edits with context, unchanged content
+assert.deepEqual(unifiedDiff('a.md', null, 'one\ntwo\n'), {
+  diff: '--- /dev/null\n+++ b/a.md\n@@ -0,0 +1,2 @@\n+one\n+two\n',
+  truncated: false,
+})
+const before = Array.from({ length: 10 }, (_, i) => `line ${i + 1}`).join('\n') + '\n'
+const after = before.replace('line 5\n', 'line five\n')
+assert.equal(
+  unifiedDiff('a.md', before, after).diff,
+  '--- a/a.md\n+++ b/a.md\n@@ -2,7 +2,7 @@\n line 2\n line 3\n line 4\n-line 5\n+line five\n line 6\n line 7\n line 8\n',
+)
+assert.deepEqual(unifiedDiff('a.md', before, before), { diff: '', truncated: false })
+const far = before.replace('line 1\n', 'first\n').replace('line 10\n', 'last\n')
+assert.equal(unifiedDiff('a.md', before, far).diff.match(/^@@/gm)?.length, 2, 'distant changes get separate hunks')
+
+const huge = Array.from({ length: 2500 }, (_, i) => `old ${i}`).join('\n')
+const replaced = Array.from({ length: 2500 }, (_, i) => `new ${i}`).join('\n')
+assert.equal(unifiedDiff('big.txt', huge, replaced).truncated, true)
+
+// File tools preview what they would change before approval
+const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), 'tool-previews-test-'))
+try {
+  const sessionFilesRoot = path.join(tmpDir, 'sessions')
+  const registry = new ToolRegistryImpl()
+  registerFileTools(registry, { sessionFilesRoot, notesDir: path.join(tmpDir, 'notes') })
+  const ctx = { agent_id: 'agent-1', session_id: 'session-1', signal: new AbortController().signal }
+  const existing = path.join(sessionFilesRoot, 'session-1', 'workspace', 'plan.md')
+  await fs.mkdir(path.dirname(existing), { recursive: true })
+  await fs.writeFile(existing, 'Goals\n- ship\n', 'utf-8')
+
+  const write = registry.getPreview('files.write', { path: 'plan.md', content: 'Goals\n- ship it\n' }, ctx)
+  assert.equal(write?.change?.kind, 'file')
+  if (write?.change?.kind === 'file') {
+    assert.equal(write.change.operation, 'overwrite')
+    assert.equal(write.change.path, 'plan.md')
+    assert.match(write.change.diff, /^-- ship$/m)
+    assert.match(write.change.diff, /^\+- ship it$/m)
+  }
+
+  const edit = registry.getPreview('files.edit', { path: 'plan.md', old_text: 'Goals', new_text: 'Aims' }, ctx)
+  assert.ok(edit?.change?.kind === 'file' && edit.change.operation === 'edit' && edit.change.diff.includes('+Aims'))
+  const missed = registry.getPreview('files.edit', { path: 'plan.md', old_text: 'nope', new_text: 'x' }, ctx)
+  assert.equal(missed?.change, undefined, 'an edit that would fail has no diff')
+
+  const create = registry.getPreview('files.create', { path: 'new.md', content: 'hello\n' }, ctx)
+  assert.ok(create?.change?.kind === 'file' && create.change.diff.startsWith('--- /dev/null'))
+  assert.equal(registry.getPreview('files.create', { path: 'plan.md', content: 'x' }, ctx)?.change, undefined)
+
+  const append = registry.getPreview('files.append', { path: 'plan.md', content: '- test\n' }, ctx)
+  assert.ok(append?.change?.kind === 'file' && append.change.diff.includes('@@ -1,2 +1,3 @@'))
+
+  const escape = registry.getPreview('files.write', { path: '../escape.md', content: 'x' }, ctx)
+  assert.equal(escape?.change, undefined)
+  assert.equal(await fs.readFile(existing, 'utf-8'), 'Goals\n- ship\n', 'previews never write')
+
+  console.log('tool preview tests passed')
+} finally {
+  await fs.rm(tmpDir, { recursive: true, force: true })
+}
@@ -0,0 +1,71 @@
+import assert from 'node:assert/strict'
+import type { Agent, Plan } from '../domain/types.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import { runProgress, withToolProgress } from '../orchestrator/progress.js'
+
+const agent = { id: 'agent-1', sessionId: 'session-1', parentId: null, depth: 0 } as unknown as Agent
+const emitted: AgentEvent[] = []
+const events = { emit: (event: AgentEvent) => emitted.push(event) }
+const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms))
+
+// Heartbeats arrive on the interval and stop once the tool returns
+const result = await withToolProgress(events, agent, 'call-1', 'slow.tool', async () => {
+  await sleep(130)
+  return 'done'
+}, 40)
+assert.equal(result, 'done')
+const heartbeats = emitted.filter((event) => event.type === EVENT_TYPES.TOOL_PROGRESS)
+assert.ok(heartbeats.length >= 2, `expected heartbeats, got ${heartbeats.length}`)
+const first = heartbeats[0]
+assert.ok(first.type === EVENT_TYPES.TOOL_PROGRESS)
+assert.equal(first.payload.callId, 'call-1')
+assert.equal(first.payload.name, 'slow.tool')
+assert.ok(first.payload.elapsedMs >= 0)
+assert.equal(first.payload.progress, undefined)
+
+const count = emitted.length
+await sleep(100)
+assert.equal(emitted.length, count, 'no heartbeats after completion')
+
+// Tool-reported progress is emitted right away, clamped, and repeated on later heartbeats
+emitted.length = 0
+await withToolProgress(events, agent, 'call-2', 'reporting.tool', async (report) => {
+  report({ progress: 1.5, message: 'almost' })
+  report({ progress: 0.9 })
+  await sleep(60)
+}, 40)
+const reported = emitted.filter((event) => event.type === EVENT_TYPES.TOOL_PROGRESS)
+assert.ok(reported.length >= 2)
+assert.ok(reported[0].type === EVENT_TYPES.TOOL_PROGRESS)
+assert.equal(reported[0].payload.progress, 1, 'progress is clamped to 1')
+assert.equal(reported[0].payload.message, 'almost')
+const heartbeat = reported[reported.length - 1]
+assert.ok(heartbeat.type === EVENT_TYPES.TOOL_PROGRESS)
+assert.equal(heartbeat.payload.progress, 0.9, 'throttled report rides on the next heartbeat')
+assert.equal(heartbeat.payload.message, 'almost')
+
+// Failures still stop the heartbeat
+emitted.length = 0
+await assert.rejects(withToolProgress(events, agent, 'call-3', 'broken.tool', async () => {
+  throw new Error('boom')
+}, 20))
+await sleep(60)
+assert.equal(emitted.length, 0)
+
+// Step progress counts plan steps when there is a plan, else the run's own steps
+const plan: Plan = {
+  goal: 'Book a trip',
+  steps: [
+    { id: '1', description: 'Find flights', status: 'completed' },
+    { id: '2', description: 'Compare hotels', status: 'skipped' },
+    { id: '3', description: 'Book', status: 'running' },
+    { id: '4', description: 'Email the itinerary', status: 'pending' },
+    { id: '5', description: 'Add to calendar', status: 'failed' },
+  ],
+}
+const run = { stepsCompleted: 3, startedAt: 1_000, turnsUsed: 4, maxTurns: 50 }
+assert.deepEqual(runProgress(plan, run, 6_000), { stepsCompleted: 2, stepsRemaining: 2, elapsedMs: 5_000, turnsUsed: 4, maxTurns: 50 })
+assert.deepEqual(runProgress(null, run, 6_000), { stepsCompleted: 3, stepsRemaining: null, elapsedMs: 5_000, turnsUsed: 4, maxTurns: 50 })
+assert.equal(runProgress({ goal: 'Nothing yet', steps: [] }, run, 6_000).stepsRemaining, null)
+
+console.log('tool progress tests passed')
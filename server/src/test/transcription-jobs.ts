@@ -0,0 +1,76 @@
+import assert from 'node:assert/strict'
+import { chmodSync, mkdtempSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { AgentEvent } from '../events/types.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { AudioInputError } from '../services/audio-transcription.js'
+import { TranscriptionJobs } from '../services/transcription-jobs.js'
+import { parseWhisperProgress, whisperCppArgs } from '../services/whisper-cpp.js'
+
+assert.equal(parseWhisperProgress('whisper_print_progress_callback: progress =  45%'), 45)
+assert.equal(parseWhisperProgress('progress = 5%\nprogress = 10%\n'), 10, 'the latest report wins')
+assert.equal(parseWhisperProgress('whisper_init_from_file: loading model'), null)
+assert.deepEqual(whisperCppArgs('/m.bin', '/a.wav', '/out'), ['-m', '/m.bin', '-f', '/a.wav', '-l', 'auto', '-nt', '-otxt', '-of', '/out', '-pp'])
+
+const dir = mkdtempSync(join(tmpdir(), 'transcription-jobs-'))
+try {
+  // Stand-ins for ffmpeg (copies input to the output path) and whisper-cli (reports progress, writes -of.txt)
+  const ffmpeg = join(dir, 'ffmpeg')
+  writeFileSync(ffmpeg, '#!/bin/sh\nfor last; do :; done\ncp "$4" "$last"\n')
+  const whisper = join(dir, 'whisper-cli')
+  writeFileSync(whisper, [
+    '#!/bin/sh',
+    'while [ $# -gt 0 ]; do case "$1" in -m) model="$2"; shift;; -of) out="$2"; shift;; esac; shift; done',
+    'echo "whisper_print_progress_callback: progress =  50%" >&2',
+    'echo " transcript via $model " > "$out.txt"',
+  ].join('\n'))
+  chmodSync(ffmpeg, 0o755)
+  chmodSync(whisper, 0o755)
+
+  const events: AgentEvent[] = []
+  const runtime = (whisperCppBin: string) => ({
+    config: { audioTranscriptionModel: 'whispercpp:/models/ggml-base.bin', whisperCppBin, ffmpegBin: ffmpeg },
+    providers: {
+      resolve() {
+        throw new Error('whispercpp models never reach a provider')
+      },
+    },
+    events: { emit: (event: AgentEvent) => events.push(event) },
+    shutdownController: new AbortController(),
+  }) as unknown as RuntimeContext
+  const settle = async (jobs: TranscriptionJobs, id: string) => {
+    while (jobs.get('user', id)?.status === 'running') await new Promise((resolve) => setTimeout(resolve, 10))
+    return jobs.get('user', id)!
+  }
+
+  const jobs = new TranscriptionJobs(runtime(whisper))
+  assert.throws(() => jobs.start('user', { bytes: new Uint8Array(), fileName: 'empty.ogg' }), AudioInputError)
+  assert.throws(() => jobs.start('user', { bytes: new Uint8Array([1]), fileName: 'notes.txt' }), AudioInputError)
+
+  const started = jobs.start('user', { bytes: new Uint8Array([1, 2, 3]), mimeType: 'audio/ogg', fileName: 'voice.ogg' }, 'session-1')
+  assert.equal(started.status, 'running')
+  assert.equal(jobs.get('another-user', started.id), null)
+
+  const done = await settle(jobs, started.id)
+  assert.equal(done.status, 'completed')
+  assert.equal(done.text, 'transcript via /models/ggml-base.bin')
+  assert.equal(done.progress, 100)
+  assert.deepEqual(events.map((e) => [e.type, e.session_id]), [
+    ['transcription:progress', 'session-1'],
+    ['transcription:completed', 'session-1'],
+  ])
+  assert.deepEqual(events[0].payload, { jobId: started.id, fileName: 'voice.ogg', progress: 50 })
+
+  // A missing binary fails the job rather than the upload
+  events.length = 0
+  const missing = new TranscriptionJobs(runtime(join(dir, 'no-such-whisper')))
+  const failed = await settle(missing, missing.start('user', { bytes: new Uint8Array([1]), fileName: 'voice.wav' }).id)
+  assert.equal(failed.status, 'failed')
+  assert.match(failed.error ?? '', /was not found/)
+  assert.deepEqual(events.map((e) => [e.type, e.session_id]), [['transcription:failed', 'transcription']])
+
+  console.log('transcription job tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,34 @@
+import assert from 'node:assert/strict'
+import { TurnKeyError, TurnKeys } from '../services/turn-keys.js'
+
+const keys = new TurnKeys<string>(50)
+let started = 0
+const start = (turn: string) => async () => {
+  started++
+  await new Promise((resolve) => setTimeout(resolve, 10))
+  return turn
+}
+
+// A retry that arrives while the first send is still being prepared joins it
+const [first, retry] = await Promise.all([
+  keys.dedupe('user-1', 'send-1', start('turn-a')),
+  keys.dedupe('user-1', 'send-1', start('turn-b')),
+])
+assert.deepEqual(first, { turn: 'turn-a', duplicate: false })
+assert.deepEqual(retry, { turn: 'turn-a', duplicate: true })
+assert.equal(started, 1)
+
+// Keys belong to one user
+assert.deepEqual(await keys.dedupe('user-2', 'send-1', start('turn-c')), { turn: 'turn-c', duplicate: false })
+
+// A failed send forgets its key so the retry goes through
+await assert.rejects(keys.dedupe('user-1', 'send-2', async () => { throw new Error('boom') }), /boom/)
+assert.deepEqual(await keys.dedupe('user-1', 'send-2', start('turn-d')), { turn: 'turn-d', duplicate: false })
+
+// Keys expire
+await new Promise((resolve) => setTimeout(resolve, 60))
+assert.deepEqual(await keys.dedupe('user-1', 'send-1', start('turn-e')), { turn: 'turn-e', duplicate: false })
+
+await assert.rejects(keys.dedupe('user-1', '', start('turn-f')), TurnKeyError)
+
+console.log('Turn key tests passed')
@@ -0,0 +1,63 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { AgentEventEmitter } from '../events/emitter.js'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import { BUDGET_PREFERENCE_KEY, BudgetExceededError, BudgetMonitor, normalizeBudget, periodStart } from '../usage/budget.js'
+import { UsageTracker } from '../usage/tracker.js'
+
+assert.equal(periodStart('monthly', Date.UTC(2025, 1, 17, 8)), Date.UTC(2025, 1, 1))
+assert.equal(periodStart('weekly', Date.UTC(2025, 1, 16, 8)), Date.UTC(2025, 1, 10), 'Sunday belongs to the week starting Monday')
+assert.throws(() => normalizeBudget({ monthlyUsd: -1 }), /monthlyUsd/)
+assert.deepEqual(normalizeBudget({ warnAt: [1, 0.5, 1] }).warnAt, [0.5, 1])
+
+const dir = mkdtempSync(join(tmpdir(), 'usage-budget-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'budget.db')))
+  const events = new AgentEventEmitter()
+  const emitted: AgentEvent[] = []
+  const iterator = events.subscribe({ types: [EVENT_TYPES.BUDGET_THRESHOLD] })[Symbol.asyncIterator]()
+  const collect = async () => {
+    for (;;) {
+      const next = await Promise.race([iterator.next(), new Promise<null>((r) => setTimeout(() => r(null), 10))])
+      if (!next || next.done) return
+      emitted.push(next.value)
+    }
+  }
+
+  const budget = new BudgetMonitor(repos.usage, repos.preferences, events)
+  const tracker = new UsageTracker(repos.usage, repos.sessions, budget)
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  const session = await repos.sessions.create({ userId: user.id })
+  const agent = await repos.agents.create({
+    sessionId: session.id,
+    task: 'Spend money',
+    config: { model: 'gpt-4o', provider: 'openai', max_turns: 1, max_tool_calls_per_step: 1, tool_execution_timeout_ms: 1000 },
+  })
+
+  await repos.preferences.set(BUDGET_PREFERENCE_KEY, JSON.stringify({ monthlyUsd: 1, warnAt: [0.5, 1], blockWhenExceeded: true }))
+
+  // gpt-4o input is $2.50 per 1M tokens, so 250k tokens is $0.625 of the $1 cap.
+  const usage = (input: number) => ({ agent, provider: 'openai', model: 'gpt-4o', usage: { input_tokens: input, output_tokens: 0 } })
+  await tracker.record(usage(250_000))
+  await collect()
+  assert.equal(emitted.length, 1)
+  assert.equal((emitted[0].payload as { threshold: number }).threshold, 0.5)
+  await budget.assertWithinBudget(user.id)
+
+  await tracker.record(usage(250_000))
+  await tracker.record(usage(10))
+  await collect()
+  assert.equal(emitted.length, 2, 'each threshold fires once per period')
+  await assert.rejects(budget.assertWithinBudget(user.id), BudgetExceededError)
+
+  await repos.preferences.set(BUDGET_PREFERENCE_KEY, JSON.stringify({ monthlyUsd: 1 }))
+  await budget.assertWithinBudget(user.id)
+
+  await iterator.return?.()
+  console.log('usage budget tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
@@ -0,0 +1,133 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { createDatabase, SQLiteRepositories } from '../repositories/sqlite/index.js'
+import type { UsageRecord } from '../repositories/types.js'
+import { computeCacheSavings, computeCost, lookupPrice } from '../usage/pricing.js'
+import { buildUsageDashboard, buildUsageReport } from '../usage/report.js'
+import { csvField, usageToCsv } from '../usage/csv.js'
+
+function record(overrides: Partial<UsageRecord>): UsageRecord {
+  return {
+    id: 'r',
+    userId: 'user',
+    sessionId: 'session-a',
+    agentId: 'agent',
+    provider: 'openai',
+    model: 'gpt-4o',
+    phase: 'controller',
+    inputTokens: 100,
+    outputTokens: 50,
+    cacheReadTokens: 0,
+    cacheWriteTokens: 0,
+    costUsd: 0.01,
+    cacheSavingsUsd: 0,
+    toolContext: null,
+    promptComposition: null,
+    createdAt: Date.UTC(2025, 0, 6, 12),
+    ...overrides,
+  }
+}
+
+// Pricing
+assert.deepEqual(lookupPrice('ollama', 'llama3'), { input: 0, output: 0 })
+assert.equal(lookupPrice('openai', 'some-unreleased-model'), null)
+assert.equal(computeCost(null, 1000, 1000), 0)
+assert.equal(computeCost({ input: 2, output: 10 }, 1_000_000, 100_000), 3)
+
+// Cached prompt tokens bill at the cache rates, and the difference is reported as savings
+const sonnet = lookupPrice('anthropic', 'claude-sonnet-4-5')
+const cache = { readTokens: 1_000_000, writeTokens: 100_000 }
+assert.equal(computeCost(sonnet, 0, 0, cache), 0.675)
+assert.equal(computeCacheSavings(sonnet, cache), 2.625)
+assert.equal(computeCacheSavings(sonnet, { writeTokens: 1_000_000 }), -0.75, 'writes alone cost extra')
+assert.equal(computeCost({ input: 2, output: 10 }, 0, 0, { readTokens: 1_000_000 }), 2, 'no cache rate bills as input')
+assert.equal(computeCacheSavings({ input: 2, output: 10 }, { readTokens: 1_000_000 }), 0)
+assert.equal(computeCacheSavings(null, cache), 0)
+
+// Grouping
+const records = [
+  record({ createdAt: Date.UTC(2025, 0, 6, 9) }), // Monday
+  record({ createdAt: Date.UTC(2025, 0, 8, 9), sessionId: 'session-b', model: 'gpt-4o-mini', costUsd: 0.002 }),
+  record({ createdAt: Date.UTC(2025, 0, 13, 9), provider: 'anthropic', model: 'claude-sonnet-4', costUsd: 0.05 }),
+]
+
+const byDay = buildUsageReport(records, 'day')
+assert.deepEqual(byDay.buckets.map((b) => b.key), ['2025-01-06', '2025-01-08', '2025-01-13'])
+assert.equal(byDay.totals.requests, 3)
+assert.equal(byDay.totals.total_tokens, 450)
+assert.equal(byDay.totals.cost, 0.062)
+assert.equal(buildUsageReport([record({ cacheSavingsUsd: 0.004 }), record({ cacheSavingsUsd: -0.001 })], 'day').totals.cache_savings, 0.003)
+
+const byWeek = buildUsageReport(records, 'week')
+assert.deepEqual(byWeek.buckets.map((b) => [b.key, b.requests]), [['2025-01-06', 2], ['2025-01-13', 1]])
+
+const byModel = buildUsageReport(records, 'model')
+assert.equal(byModel.buckets[0].key, 'anthropic:claude-sonnet-4', 'model buckets are ordered by cost')
+
+const byPhase = buildUsageReport([
+  ...records,
+  record({ phase: 'responder', costUsd: 0.004 }),
+  record({ phase: 'title', costUsd: 0.001 }),
+  record({ phase: null, costUsd: 0.001 }),
+], 'phase')
+assert.deepEqual(byPhase.buckets.map((b) => [b.key, b.cost]), [
+  ['controller', 0.062],
+  ['responder', 0.004],
+  ['title', 0.001],
+  ['(unknown)', 0.001],
+])
+
+const byConversation = buildUsageReport(records, 'conversation')
+assert.deepEqual(byConversation.buckets.map((b) => b.key).sort(), ['session-a', 'session-b'])
+
+// Cache-hit ratio counts reads against the whole prompt
+assert.equal(byDay.totals.cache_hit_ratio, 0)
+assert.equal(buildUsageReport([record({ inputTokens: 100, cacheReadTokens: 250, cacheWriteTokens: 50 })], 'day').totals.cache_hit_ratio, 0.625)
+
+// Dashboard: daily series split by model, top conversations, phases
+const dashboard = buildUsageDashboard([
+  ...records,
+  record({ createdAt: Date.UTC(2025, 0, 6, 15), provider: 'anthropic', model: 'claude-sonnet-4', costUsd: 0.03, cacheReadTokens: 300 }),
+], { conversationLimit: 1 })
+assert.equal(dashboard.totals.cost, 0.092)
+assert.deepEqual(dashboard.daily.map((d) => [d.key, d.models]), [
+  ['2025-01-06', { 'openai:gpt-4o': 0.01, 'anthropic:claude-sonnet-4': 0.03 }],
+  ['2025-01-08', { 'openai:gpt-4o-mini': 0.002 }],
+  ['2025-01-13', { 'anthropic:claude-sonnet-4': 0.05 }],
+])
+assert.equal(dashboard.daily[0].cache_hit_ratio, 0.6)
+assert.deepEqual(dashboard.models.map((m) => [m.key, m.cost]), [['anthropic:claude-sonnet-4', 0.08], ['openai:gpt-4o', 0.01], ['openai:gpt-4o-mini', 0.002]])
+assert.deepEqual(dashboard.conversations.map((c) => [c.key, c.cost]), [['session-a', 0.09]])
+assert.deepEqual(dashboard.phases.map((p) => p.key), ['controller'])
+
+// CSV export
+assert.equal(csvField('Plan, "trip"'), '"Plan, ""trip"""')
+assert.equal(csvField('=SUM(A1)'), "'=SUM(A1)")
+const csv = usageToCsv(records.slice(0, 2), new Map([['session-a', 'Budget review']]))
+const rows = csv.trimEnd().split('\r\n')
+assert.equal(rows.length, 3)
+assert.equal(rows[0], 'timestamp,conversation_id,conversation_title,provider,model,prompt_tokens,completion_tokens,cached_tokens,cost_usd')
+assert.equal(rows[1], '2025-01-06T09:00:00.000Z,session-a,Budget review,openai,gpt-4o,100,50,0,0.010000')
+assert.equal(rows[2].split(',')[2], '', 'unknown sessions export with an empty title')
+
+// Repository range filtering
+const dir = mkdtempSync(join(tmpdir(), 'usage-report-'))
+try {
+  const repos = new SQLiteRepositories(createDatabase(join(dir, 'usage.db')))
+  const user = await repos.users.create({ apiKeyHash: 'hash' })
+  await repos.usage.record({ userId: user.id, provider: 'openai', model: 'gpt-4o', phase: 'responder', inputTokens: 10, outputTokens: 5, costUsd: 0.001 })
+  await repos.usage.record({ userId: 'someone-else', provider: 'openai', model: 'gpt-4o', inputTokens: 10, outputTokens: 5, costUsd: 0.001 })
+
+  const now = Date.now()
+  assert.equal((await repos.usage.list({ userId: user.id })).length, 1)
+  assert.equal((await repos.usage.list({ userId: user.id }))[0].phase, 'responder')
+  assert.equal((await repos.usage.list({ userId: 'someone-else' }))[0].phase, null)
+  assert.equal((await repos.usage.list({ userId: user.id, from: now - 60_000, to: now + 60_000 })).length, 1)
+  assert.equal((await repos.usage.list({ userId: user.id, to: now - 60_000 })).length, 0)
+
+  console.log('usage report tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
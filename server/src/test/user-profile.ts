@@ -0,0 +1,36 @@
+import assert from 'node:assert/strict'
+import { buildControllerMessages } from '../orchestrator/prompts.js'
+import {
+  EMPTY_USER_PROFILE,
+  normalizeUserProfile,
+  parseUserProfile,
+  renderUserProfile,
+} from '../orchestrator/user-profile.js'
+
+// Blank fields are cleared and bad input is rejected
+const profile = normalizeUserProfile({ name: ' Ada ', timezone: 'Europe/Warsaw', role: 'Engineering manager', writingStyle: '' })
+assert.deepEqual(profile, { name: 'Ada', timezone: 'Europe/Warsaw', role: 'Engineering manager', writingStyle: null, language: null })
+assert.throws(() => normalizeUserProfile({ timezone: 'Mars/Olympus' }), /IANA time zone/)
+assert.throws(() => normalizeUserProfile({ name: 42 as unknown as string }), /name must be a string/)
+assert.throws(() => normalizeUserProfile({ writingStyle: 'x'.repeat(1001) }), /too long/)
+assert.deepEqual(parseUserProfile(null), EMPTY_USER_PROFILE)
+assert.deepEqual(parseUserProfile('{not json'), EMPTY_USER_PROFILE)
+assert.deepEqual(parseUserProfile(JSON.stringify(profile)), profile)
+
+assert.equal(renderUserProfile(EMPTY_USER_PROFILE), null)
+assert.equal(renderUserProfile(profile), '- Name: Ada\n- Role: Engineering manager\n- Time zone: Europe/Warsaw')
+
+// The profile follows the fixed prompt and precedes everything that varies per run
+const build = (task: string, userProfile = profile) => {
+  const [system] = buildControllerMessages('CONTROLLER', '', [], { useNativeFunctionCalling: true, agentTask: task, userProfile })
+  return system.content as string
+}
+const first = build('Plan the offsite')
+assert.ok(first.startsWith('CONTROLLER\n\n## About the User\n- Name: Ada'))
+assert.ok(first.indexOf('## About the User') < first.indexOf('## Current Task'))
+const second = build('Draft a status update')
+const shared = first.slice(0, first.indexOf('## Current Task'))
+assert.ok(second.startsWith(shared), 'runs with different tasks share the profile prefix')
+assert.ok(!build('Plan the offsite', EMPTY_USER_PROFILE).includes('## About the User'))
+
+console.log('User profile tests passed')
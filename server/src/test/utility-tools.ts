@@ -0,0 +1,87 @@
+import assert from 'node:assert/strict'
+import { evaluateExpression } from '../lib/calculator.js'
+import { convertUnits } from '../services/units.js'
+import { parseLocalTime, registerUtilityTools } from '../tools/utilities.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const json = (value: unknown, status = 200) => new Response(JSON.stringify(value), { status, headers: { 'content-type': 'application/json' } })
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+
+// Calculator: precedence, per cent, functions, and no way out to JavaScript
+assert.equal(evaluateExpression('1 + 2 * 3'), 7)
+assert.equal(evaluateExpression('-2^2'), -4)
+assert.equal(evaluateExpression('2^3^2'), 512)
+assert.equal(evaluateExpression('(1299 * 3) * (1 - 12.5%)'), 3409.875)
+assert.equal(evaluateExpression('round(pi, 3) + max(1, 5)'), 8.142)
+assert.throws(() => evaluateExpression('1 / 0'), /Division by zero/)
+assert.throws(() => evaluateExpression('constructor(1)'), /Unknown name/)
+assert.throws(() => evaluateExpression('process.exit(1)'), /Unexpected character/)
+
+// Units, including temperature offsets and plurals
+assert.equal(Math.round(convertUnits(10, 'miles', 'km').value * 1000) / 1000, 16.093)
+assert.equal(convertUnits(212, '°F', 'celsius').value, 100)
+assert.equal(convertUnits(2, 'cups', 'ml').value, 473.176473)
+assert.throws(() => convertUnits(1, 'kg', 'm'), /different kinds/)
+assert.throws(() => convertUnits(1, 'furlong', 'm'), /Unknown unit: furlong/)
+
+// Time zones: wall time in the source zone, across DST
+assert.equal(parseLocalTime('2026-10-20 09:00', 'Europe/Warsaw').toISOString(), '2026-10-20T07:00:00.000Z')
+assert.equal(parseLocalTime('2026-12-20 9am', 'Europe/Warsaw').toISOString(), '2026-12-20T08:00:00.000Z')
+
+const requests: string[] = []
+const fakeFetch = (async (input: string | URL | Request) => {
+  const url = new URL(String(input))
+  requests.push(url.hostname)
+  if (url.hostname === 'geocoding-api.open-meteo.com') {
+    return json({ results: [
+      { name: 'Portland', latitude: 43.66, longitude: -70.26, country: 'United States', country_code: 'US', admin1: 'Maine' },
+      { name: 'Portland', latitude: 45.52, longitude: -122.68, country: 'United States', country_code: 'US', admin1: 'Oregon' },
+    ] })
+  }
+  if (url.hostname === 'api.open-meteo.com') {
+    assert.equal(url.searchParams.get('latitude'), '45.52')
+    assert.equal(url.searchParams.get('temperature_unit'), 'fahrenheit')
+    return json({
+      timezone: 'America/Los_Angeles',
+      current: { time: '2026-10-17T09:00', temperature_2m: 52, apparent_temperature: 50, relative_humidity_2m: 80, wind_speed_10m: 6, weather_code: 61 },
+      daily: {
+        time: ['2026-10-17'],
+        weather_code: [63],
+        temperature_2m_min: [45],
+        temperature_2m_max: [58],
+        precipitation_sum: [0.3],
+        precipitation_probability_max: [90],
+      },
+    })
+  }
+  if (url.hostname === 'rates.example') return json({ rates: { USD: 1, EUR: 0.9, PLN: 3.6 } })
+  return new Response('Not found', { status: 404 })
+}) as typeof fetch
+
+const registry = new ToolRegistryImpl()
+registerUtilityTools(registry, { exchangeRateUrl: 'https://rates.example/latest/USD', fetchImpl: fakeFetch })
+
+const weather = await registry.execute('weather.forecast', { location: 'Portland, Oregon', days: 1, units: 'imperial' }, ctx)
+const report = weather.output as { location: { timezone: string }; current: { conditions: string }; daily: Array<{ conditions: string; precipitationChance: number }> }
+assert.equal(report.location.timezone, 'America/Los_Angeles')
+assert.equal(report.current.conditions, 'Light rain')
+assert.deepEqual([report.daily[0].conditions, report.daily[0].precipitationChance], ['Rain', 90])
+assert.match(String((await registry.execute('weather.forecast', { location: 'Portland, Narnia' }, ctx)).error), /Location not found/)
+
+const times = await registry.execute('time.convert', { time: '2026-10-20 09:00', from: 'Europe/Warsaw', to: ['America/New_York', 'Asia/Kathmandu'] }, ctx)
+assert.deepEqual((times.output as { times: Array<{ local: string; offset: string }> }).times.map((time) => [time.local, time.offset]), [
+  ['2026-10-20 03:00', '-04:00'],
+  ['2026-10-20 12:45', '+05:45'],
+])
+assert.match(String((await registry.execute('time.convert', { to: ['Mars/Base'] }, ctx)).error), /Unknown time zone/)
+
+const euros = await registry.execute('units.convert', { value: 100, from: 'EUR', to: 'PLN' }, ctx)
+assert.deepEqual(euros.output, { value: 400, from: 'EUR', to: 'PLN', dimension: 'currency', rate: 4 })
+const cups = await registry.execute('units.convert', { value: 1, from: 'cup', to: 'ml' }, ctx)
+assert.equal((cups.output as { dimension: string }).dimension, 'volume')
+
+const sum = await registry.execute('calculator.evaluate', { expression: '0.1 + 0.2' }, ctx)
+assert.deepEqual(sum.output, { expression: '0.1 + 0.2', result: 0.3 })
+assert.equal(registry.getMetadata('calculator.evaluate')?.category, 'read_only')
+
+console.log('Utility tool tests passed')
@@ -0,0 +1,114 @@
+import assert from 'node:assert/strict'
+import { chmodSync, mkdtempSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { Item } from '../domain/types.js'
+import { AttachmentStore } from '../lib/attachment-store.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { buildControllerMessages } from '../orchestrator/prompts.js'
+import type { LLMContentBlock } from '../providers/types.js'
+import {
+  extractVideo,
+  formatTimestamp,
+  frameTimestamps,
+  parseVideoProbe,
+  resolveVideoBlocks,
+  VideoInputError,
+} from '../services/video-extraction.js'
+
+const probeOutput = [
+  'Input #0, mov,mp4,m4a,3gp,3g2,mj2, from \'input\':',
+  '  Duration: 00:01:20.00, start: 0.000000, bitrate: 1021 kb/s',
+  '  Stream #0:0[0x1](und): Video: h264 (High) (avc1 / 0x31637661), yuv420p, 1920x1080, 30 fps',
+  '  Stream #0:1[0x2](und): Audio: aac (LC) (mp4a / 0x6134706D), 48000 Hz, stereo, fltp, 128 kb/s',
+].join('\n')
+
+assert.deepEqual(parseVideoProbe(probeOutput), { durationSeconds: 80, hasVideo: true, hasAudio: true })
+assert.deepEqual(parseVideoProbe('  Duration: N/A, bitrate: N/A\n  Stream #0:0: Video: vp8'), { durationSeconds: null, hasVideo: true, hasAudio: false })
+assert.deepEqual(frameTimestamps(80, 4), [10, 30, 50, 70])
+assert.deepEqual(frameTimestamps(null, 8), [0], 'without a duration only the opening frame is taken')
+assert.equal(formatTimestamp(70), '1:10')
+assert.equal(formatTimestamp(3725.5), '1:02:05')
+
+const dir = mkdtempSync(join(tmpdir(), 'video-extraction-'))
+try {
+  // Stand-in for ffmpeg: `-i` alone prints the probe and fails, frames record their seek position, audio is a stub
+  const ffmpeg = join(dir, 'ffmpeg')
+  writeFileSync(ffmpeg, [
+    '#!/bin/sh',
+    'for last; do :; done',
+    'case "$last" in',
+    '  *.jpg) while [ "$1" != "-ss" ]; do shift; done; printf "frame at %s" "$2" > "$last";;',
+    '  *.m4a) printf "audio track" > "$last";;',
+    `  *) printf '%s\\n' "${probeOutput.replace(/'/g, '')}" >&2; exit 1;;`,
+    'esac',
+  ].join('\n'))
+  chmodSync(ffmpeg, 0o755)
+
+  const attachments = new AttachmentStore(join(dir, 'attachments'))
+  const transcriptions: Array<{ format: string }> = []
+  const runtime = (audioTranscriptionModel?: string) => ({
+    attachments,
+    config: { ffmpegBin: ffmpeg, videoFrameCount: 4, visionImageMaxDimension: 1568, audioTranscriptionModel },
+    providers: {
+      resolve: () => ({
+        async transcribeAudio(request: { input_audio: { format: string } }) {
+          transcriptions.push({ format: request.input_audio.format })
+          return { text: ' We open settings and click deploy. ' }
+        },
+      }),
+    },
+    shutdownController: new AbortController(),
+  }) as unknown as RuntimeContext
+
+  await assert.rejects(extractVideo(runtime(), { bytes: new Uint8Array(), fileName: 'empty.mp4' }), VideoInputError)
+  await assert.rejects(extractVideo(runtime(), { bytes: new Uint8Array([1]), fileName: 'notes.txt' }), /Unsupported video type/)
+
+  const video = Buffer.from('fake screen recording')
+  const extraction = await extractVideo(runtime('openai:whisper-1'), { bytes: video, mimeType: 'video/mp4', fileName: 'demo.mp4' })
+  assert.equal(extraction.mediaType, 'video/mp4')
+  assert.equal(extraction.durationSeconds, 80)
+  assert.deepEqual(extraction.frames.map((frame) => frame.at), [10, 30, 50, 70])
+  assert.equal((await attachments.get(extraction.frames[1].hash))?.toString(), 'frame at 30', 'frames are stored as attachments')
+  assert.deepEqual(extraction.chunks, ['We open settings and click deploy.'])
+  assert.deepEqual(transcriptions, [{ format: 'm4a' }])
+
+  // Re-uploading returns the stored extraction without running ffmpeg or transcription again
+  assert.deepEqual(await extractVideo(runtime('openai:whisper-1'), { bytes: video, fileName: 'demo.mov' }), extraction)
+  assert.equal(transcriptions.length, 1)
+
+  // Without a transcription model the frames are still sampled
+  const silent = await extractVideo(runtime(), { bytes: Buffer.from('another recording'), fileName: 'clip.webm' })
+  assert.equal(silent.mediaType, 'video/webm')
+  assert.equal(silent.frames.length, 4)
+  assert.deepEqual(silent.chunks, [])
+
+  const blocks = await resolveVideoBlocks(runtime(), [{ type: 'text', text: 'note' }, { type: 'video', hash: extraction.hash, name: 'demo.mp4' }])
+  assert.deepEqual(blocks!.slice(0, 3), [
+    { type: 'text', text: 'note' },
+    { type: 'video', name: 'demo.mp4', media_type: 'video/mp4', hash: extraction.hash, bytes: video.length, chunks: extraction.chunks },
+    { type: 'image', name: 'demo.mp4 at 0:10', media_type: 'image/jpeg', hash: extraction.frames[0].hash, bytes: extraction.frames[0].bytes },
+  ])
+  assert.equal(blocks!.length, 6)
+  await assert.rejects(resolveVideoBlocks(runtime(), [{ type: 'video', hash: 'f'.repeat(64) }]), /Unknown video attachment/)
+
+  // The transcript goes inline and every frame reaches the model as a labelled image
+  const item = {
+    id: 'i1', agentId: 'a1', sequence: 1, type: 'message', role: 'user', content: 'What happens here?', contentBlocks: blocks!.slice(1),
+    callId: null, name: null, arguments: null, output: null, isError: null, saveOutput: null, turnNumber: 0, durationMs: null, createdAt: 0,
+  } as Item
+  const [hydrated] = await attachments.hydrate([item])
+  assert.equal(hydrated.contentBlocks?.[0].data, undefined, 'the video itself is never loaded')
+  const messages = buildControllerMessages('system', '', [hydrated], { useNativeFunctionCalling: true, agentTask: '' })
+  const content = messages[1].content as LLMContentBlock[]
+  assert.equal(content[0].text, 'What happens here?\n\n[Attached video: demo.mp4 (audio transcript)]\n```\nWe open settings and click deploy.\n```\n')
+  assert.deepEqual(content.slice(1, 3), [
+    { type: 'text', text: '[demo.mp4 at 0:10]' },
+    { type: 'image', media_type: 'image/jpeg', data: Buffer.from('frame at 10').toString('base64') },
+  ])
+  assert.equal(content.length, 9)
+
+  console.log('video extraction tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
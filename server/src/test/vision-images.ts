@@ -0,0 +1,102 @@
+import assert from 'node:assert/strict'
+import { chmodSync, mkdtempSync, rmSync, writeFileSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import type { Item } from '../domain/types.js'
+import { AttachmentStore, type ImagePreparer } from '../lib/attachment-store.js'
+import { readImageInfo } from '../lib/image-info.js'
+import { createVisionImagePreparer, visionResizeArgs } from '../services/vision-images.js'
+
+function png(width: number, height: number): Buffer {
+  const header = Buffer.alloc(24)
+  Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]).copy(header)
+  header.writeUInt32BE(13, 8)
+  header.write('IHDR', 12, 'latin1')
+  header.writeUInt32BE(width, 16)
+  header.writeUInt32BE(height, 20)
+  return header
+}
+
+const gif = Buffer.from([...Buffer.from('GIF89a'), 0x40, 0x01, 0xf0, 0x00])
+const jpeg = Buffer.from([
+  0xff, 0xd8,
+  0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, // APP0 with an empty body
+  0xff, 0xc0, 0x00, 0x11, 0x08, 0x02, 0x58, 0x03, 0x20, 0x03, // SOF0: 600 high, 800 wide
+  0x00, 0x00, 0x00, 0x00,
+])
+const webp = Buffer.alloc(30)
+webp.write('RIFF', 0, 'latin1')
+webp.write('WEBPVP8X', 8, 'latin1')
+webp.writeUIntLE(4095, 24, 3)
+webp.writeUIntLE(2047, 27, 3)
+
+assert.deepEqual(readImageInfo(png(3000, 2000)), { mediaType: 'image/png', width: 3000, height: 2000 })
+assert.deepEqual(readImageInfo(gif), { mediaType: 'image/gif', width: 320, height: 240 })
+assert.deepEqual(readImageInfo(jpeg), { mediaType: 'image/jpeg', width: 800, height: 600 })
+assert.deepEqual(readImageInfo(webp), { mediaType: 'image/webp', width: 4096, height: 2048 })
+assert.equal(readImageInfo(Buffer.from('BM not a supported format')), null)
+
+assert.deepEqual(visionResizeArgs('/in', '/out.jpg', 1568), [
+  '-nostdin', '-y', '-i', '/in', '-frames:v', '1',
+  '-vf', "scale='min(iw,1568)':'min(ih,1568)':force_original_aspect_ratio=decrease", '-q:v', '3', '/out.jpg',
+])
+
+const dir = mkdtempSync(join(tmpdir(), 'vision-images-'))
+try {
+  // Stand-in for ffmpeg: PNG output stays large, JPEG output is small
+  const ffmpeg = join(dir, 'ffmpeg')
+  writeFileSync(ffmpeg, '#!/bin/sh\nfor last; do :; done\ncase "$last" in *.png) head -c "${PNG_BYTES:-10}" /dev/zero > "$last";; *) printf jpeg > "$last";; esac\n')
+  chmodSync(ffmpeg, 0o755)
+  const preparer = (ffmpegBin: string) => createVisionImagePreparer({ ffmpegBin, maxDimension: 1568, maxBytes: 100 })
+
+  // Images inside the limits are never re-encoded
+  assert.equal(await preparer(join(dir, 'missing')).prepare(jpeg, 'image/jpeg'), null)
+
+  const screenshot = png(3000, 2000)
+  assert.deepEqual(await preparer(ffmpeg).prepare(screenshot, 'image/png'), { data: Buffer.alloc(10), mediaType: 'image/png' })
+  process.env.PNG_BYTES = '500'
+  assert.deepEqual(await preparer(ffmpeg).prepare(screenshot, 'image/png'), { data: Buffer.from('jpeg'), mediaType: 'image/jpeg' })
+  delete process.env.PNG_BYTES
+  assert.deepEqual(await preparer(ffmpeg).prepare(Buffer.from('BM bitmap'), 'image/bmp'), { data: Buffer.from('jpeg'), mediaType: 'image/jpeg' })
+  assert.equal(await preparer(join(dir, 'missing')).prepare(screenshot, 'image/png'), null, 'a failed resize sends the original')
+
+  // A stuck ffmpeg is killed and the original goes out instead
+  const stuck = join(dir, 'ffmpeg-stuck')
+  writeFileSync(stuck, '#!/bin/sh\nexec sleep 5\n')
+  chmodSync(stuck, 0o755)
+  const started = Date.now()
+  const slow = createVisionImagePreparer({ ffmpegBin: stuck, maxDimension: 1568, maxBytes: 100, timeoutMs: 200 })
+  assert.equal(await slow.prepare(screenshot, 'image/png'), null)
+  assert.ok(Date.now() - started < 4000, 'the resize gives up at its timeout')
+
+  // Hydrated history carries the vision copy; the original stays in the store
+  let prepared = 0
+  const stub = (key: string): ImagePreparer => ({
+    key,
+    async prepare() {
+      prepared++
+      return { data: Buffer.from('small'), mediaType: 'image/jpeg' }
+    },
+  })
+  const store = new AttachmentStore(join(dir, 'attachments'), stub('v1'))
+  const hash = await store.put(screenshot)
+  const item = {
+    id: 'i1', agentId: 'a1', sequence: 1, type: 'function_call_output', role: null, content: null,
+    contentBlocks: [{ type: 'image', media_type: 'image/png', hash, bytes: screenshot.length }],
+    callId: 'c1', name: 'files.read', arguments: null, output: 'ok', isError: false, saveOutput: null, turnNumber: 0, durationMs: null, createdAt: 0,
+  } as Item
+  const [hydrated] = await store.hydrate([item])
+  assert.deepEqual(hydrated.contentBlocks?.[0], {
+    type: 'image', media_type: 'image/jpeg', hash, bytes: screenshot.length, data: Buffer.from('small').toString('base64'),
+  })
+  assert.deepEqual(await store.get(hash), screenshot)
+
+  await store.hydrate([item])
+  assert.equal(prepared, 1, 'the vision copy is cached')
+  await new AttachmentStore(join(dir, 'attachments'), stub('v2')).hydrate([item])
+  assert.equal(prepared, 2, 'new settings redo the copy')
+
+  console.log('vision image tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
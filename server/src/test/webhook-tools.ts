@@ -0,0 +1,101 @@
+import assert from 'node:assert/strict'
+import type { ApiKeyRecord, ApiKeyRepository } from '../repositories/types.js'
+import { defaultApprovalAction } from '../orchestrator/approval-categories.js'
+import { loadWebhookEndpoints, normalizeWebhookEndpoint, saveWebhookEndpoints, webhookEndpointView, WebhookService } from '../services/webhooks.js'
+import { registerWebhookTools } from '../tools/webhooks.js'
+import { ToolRegistryImpl } from '../tools/registry.js'
+
+const stored = new Map<string, string>()
+const apiKeys = {
+  getByProvider: async (provider: string) => stored.has(provider) ? { provider, encryptedKey: stored.get(provider) } as ApiKeyRecord : null,
+  upsert: async (provider: string, encryptedKey: string) => {
+    stored.set(provider, encryptedKey)
+    return { provider, encryptedKey } as ApiKeyRecord
+  },
+  delete: async (provider: string) => {
+    stored.delete(provider)
+  },
+} as ApiKeyRepository
+
+const posts: Array<{ url: string; headers: Headers; body: unknown }> = []
+const fakeFetch = (async (input: string | URL | Request, init?: RequestInit) => {
+  const url = String(input)
+  posts.push({ url, headers: new Headers(init?.headers), body: JSON.parse(String(init?.body)) })
+  if (url.includes('broken')) return new Response('Zap is turned off', { status: 410 })
+  return new Response(JSON.stringify({ status: 'success', id: 'zap-1' }), { headers: { 'content-type': 'application/json' } })
+}) as typeof fetch
+
+// Validation: names, URLs and headers; missing headers keep the saved ones
+assert.throws(() => normalizeWebhookEndpoint('bad name', { url: 'https://example.com' }, null), /name must be/)
+assert.throws(() => normalizeWebhookEndpoint('crm', { url: 'ftp://example.com' }, null), /http\(s\) URL/)
+assert.throws(() => normalizeWebhookEndpoint('crm', { url: 'https://example.com', headers: { Authorization: 1 } }, null), /header Authorization/)
+const crm = normalizeWebhookEndpoint('crm', {
+  url: 'https://hooks.zapier.com/hooks/catch/1/abc/',
+  description: 'Adds a lead: { name, email, note }',
+  headers: { 'X-Secret': 'shh' },
+}, null)
+assert.deepEqual(webhookEndpointView(crm).headers, ['X-Secret'])
+await saveWebhookEndpoints(apiKeys, [crm, normalizeWebhookEndpoint('legacy', { url: 'https://broken.example.com/hook' }, null)])
+
+const service = new WebhookService(apiKeys, fakeFetch)
+await service.refresh()
+const registry = new ToolRegistryImpl()
+registerWebhookTools(registry, service)
+const ctx = { agent_id: 'agent', session_id: 'session', signal: new AbortController().signal }
+const meta = () => registry.getMetadata('webhook.post')
+assert.match(meta()!.description, /- crm: Adds a lead: \{ name, email, note \}\n- legacy$/)
+
+// The first post to an endpoint asks; once it has gone through, later posts do not
+assert.equal(defaultApprovalAction(meta(), {}, { endpoint: 'crm', payload: {} }), 'ask')
+assert.deepEqual(registry.getPreview('webhook.post', { endpoint: 'crm', payload: { name: 'Ada' } }, ctx), {
+  summary: 'Post to webhook "crm" (first use)',
+  details: { host: 'hooks.zapier.com', payload: '{"name":"Ada"}' },
+})
+const sent = await registry.execute('webhook.post', { endpoint: 'crm', payload: { name: 'Ada', email: 'ada@example.com' } }, ctx)
+assert.deepEqual(sent.output, { endpoint: 'crm', status: 200, response: { status: 'success', id: 'zap-1' } })
+assert.equal(posts[0].url, 'https://hooks.zapier.com/hooks/catch/1/abc/')
+assert.equal(posts[0].headers.get('x-secret'), 'shh')
+assert.equal(posts[0].headers.get('content-type'), 'application/json')
+assert.deepEqual(posts[0].body, { name: 'Ada', email: 'ada@example.com' })
+assert.equal(defaultApprovalAction(meta(), {}, { endpoint: 'crm', payload: {} }), 'allow')
+assert.equal(defaultApprovalAction(meta(), {}, { endpoint: 'legacy', payload: {} }), 'ask')
+assert.equal(defaultApprovalAction(meta(), {}, { endpoint: 'crm-2', payload: {} }), 'ask')
+assert.notEqual((await loadWebhookEndpoints(apiKeys)).find((entry) => entry.name === 'crm')?.confirmedAt, null)
+
+// Pointing an endpoint somewhere else needs a fresh approval; other edits keep it
+const [saved] = await loadWebhookEndpoints(apiKeys)
+assert.notEqual(normalizeWebhookEndpoint('crm', { url: saved.url, description: 'Leads' }, saved).confirmedAt, null)
+const moved = normalizeWebhookEndpoint('crm', { url: 'https://example.com/other' }, saved)
+assert.equal(moved.confirmedAt, null)
+assert.deepEqual(moved.headers, { 'X-Secret': 'shh' })
+
+// Failures
+assert.equal((await registry.execute('webhook.post', { endpoint: 'legacy', payload: { a: 1 } }, ctx)).error, 'Endpoint "legacy" answered 410: Zap is turned off')
+assert.equal((await registry.execute('webhook.post', { endpoint: 'nope', payload: {} }, ctx)).error, 'Unknown endpoint "nope"; registered: crm, legacy')
+assert.match(String((await registry.execute('webhook.post', { endpoint: 'crm', payload: [1, 2] }, ctx)).error), /'payload' must be object/)
+
+// A first post confirms only its own endpoint, and not at all once the endpoint was pointed elsewhere
+await saveWebhookEndpoints(apiKeys, [
+  ...await loadWebhookEndpoints(apiKeys),
+  normalizeWebhookEndpoint('leads', { url: 'https://leads.example.com/hook' }, null),
+])
+let racing!: ReturnType<typeof registry.execute>
+await service.locked(async () => {
+  racing = registry.execute('webhook.post', { endpoint: 'leads', payload: {} }, ctx)
+  await new Promise((resolve) => setTimeout(resolve, 0))
+  const endpoints = await loadWebhookEndpoints(apiKeys)
+  const leads = endpoints.find((entry) => entry.name === 'leads')!
+  await saveWebhookEndpoints(apiKeys, [
+    ...endpoints.filter((entry) => entry.name !== 'leads'),
+    normalizeWebhookEndpoint('leads', { url: 'https://leads.example.com/v2' }, leads),
+    normalizeWebhookEndpoint('billing', { url: 'https://billing.example.com/hook' }, null),
+  ])
+})
+assert.equal((await racing).error, 'Endpoint "leads" was changed while the post waited; try again')
+const afterRace = await loadWebhookEndpoints(apiKeys)
+assert.deepEqual(afterRace.map((entry) => entry.name), ['crm', 'legacy', 'leads', 'billing'])
+assert.equal(afterRace.find((entry) => entry.name === 'leads')?.url, 'https://leads.example.com/v2')
+assert.equal(afterRace.find((entry) => entry.name === 'leads')?.confirmedAt, null)
+assert.ok(!posts.some((post) => post.url.includes('leads.example.com')))
+
+console.log('Webhook tool tests passed')
@@ -0,0 +1,49 @@
+import assert from 'node:assert/strict'
+import { Duplex } from 'node:stream'
+import { decodeFrame, encodeFrame, WebSocketConnection } from '../lib/websocket.js'
+
+function clientFrame(opcode: number, text: string, fin = true): Buffer {
+  const payload = Buffer.from(text)
+  const mask = Buffer.from([1, 2, 3, 4])
+  const masked = Buffer.from(payload.map((byte, i) => byte ^ mask[i % 4]))
+  const header = payload.length < 126
+    ? Buffer.from([(fin ? 0x80 : 0) | opcode, 0x80 | payload.length])
+    : Buffer.from([(fin ? 0x80 : 0) | opcode, 0x80 | 126, payload.length >> 8, payload.length & 0xff])
+  return Buffer.concat([header, mask, masked])
+}
+
+// Frame codec
+const frame = decodeFrame(clientFrame(0x1, 'hello'))
+assert.equal(frame?.payload.toString(), 'hello')
+assert.equal(decodeFrame(clientFrame(0x1, 'hello').subarray(0, 4)), null, 'partial frames wait for more data')
+assert.equal(decodeFrame(clientFrame(0x1, 'x'.repeat(300)))?.payload.length, 300)
+assert.deepEqual([...encodeFrame(0x1, Buffer.from('hi'))], [0x81, 2, 0x68, 0x69])
+assert.throws(() => decodeFrame(encodeFrame(0x1, Buffer.from('unmasked'))), /masked/)
+
+// Connection: fragmented text, ping, close
+const written: Buffer[] = []
+const socket = new Duplex({
+  read() {},
+  write(chunk, _encoding, callback) {
+    written.push(chunk as Buffer)
+    callback()
+  },
+})
+const connection = new WebSocketConnection(socket)
+const messages: string[] = []
+connection.on('message', (text) => messages.push(text))
+let closed = false
+connection.on('close', () => { closed = true })
+
+socket.push(Buffer.concat([clientFrame(0x1, 'hel', false), clientFrame(0x9, 'p')]))
+socket.push(clientFrame(0x0, 'lo'))
+await new Promise((resolve) => setImmediate(resolve))
+assert.deepEqual(messages, ['hello'])
+assert.deepEqual([...written[0]], [0x8a, 1, 0x70], 'pings are answered with a pong')
+
+socket.push(clientFrame(0x8, ''))
+await new Promise((resolve) => setImmediate(resolve))
+assert.equal(closed, true)
+assert.equal(connection.isOpen, false)
+
+console.log('websocket tests passed')
@@ -0,0 +1,51 @@
+import assert from 'node:assert/strict'
+import { EVENT_TYPES, type AgentEvent } from '../events/types.js'
+import { WindowClaims } from '../services/window-routing.js'
+
+const emitted: AgentEvent[] = []
+const windows = new WindowClaims({ emit: (event) => emitted.push(event) })
+
+// Unclaimed conversations reach every window
+assert.equal(windows.routes('s1', 'main'), true)
+assert.equal(windows.routes('s1', 'monitor'), true)
+
+// A claim routes the conversation to the claiming window only
+assert.equal(windows.claim('s1', 'monitor'), null)
+assert.equal(windows.ownerOf('s1'), 'monitor')
+assert.equal(windows.routes('s1', 'monitor'), true)
+assert.equal(windows.routes('s1', 'main'), false)
+assert.equal(windows.routes('s1', undefined), true, 'clients without a window id are not filtered')
+assert.equal(windows.routes('s1', 'main', EVENT_TYPES.CONVERSATION_CLAIMED), true, 'claim changes reach everyone')
+assert.equal(windows.routes('s1', 'main', EVENT_TYPES.CONVERSATION_UPDATED), true, 'renames reach everyone')
+assert.equal(windows.routes('s1', 'main', EVENT_TYPES.DIGEST_READY), true, 'digests reach everyone')
+assert.equal(windows.routes('s2', 'main'), true)
+
+const announced = emitted[0]
+assert.ok(announced.type === EVENT_TYPES.CONVERSATION_CLAIMED)
+assert.equal(announced.session_id, 's1')
+assert.deepEqual(announced.payload, { windowId: 'monitor', previousWindowId: null })
+
+// Re-claiming is a no-op; claiming from another window takes over
+assert.equal(windows.claim('s1', 'monitor'), 'monitor')
+assert.equal(emitted.length, 1)
+assert.equal(windows.claim('s1', 'main'), 'monitor')
+assert.equal(windows.routes('s1', 'monitor'), false)
+
+// Only the owner can release
+assert.equal(windows.release('s1', 'monitor'), false)
+assert.equal(windows.release('s1', 'main'), true)
+assert.equal(windows.ownerOf('s1'), null)
+assert.equal(windows.routes('s1', 'monitor'), true)
+
+// Closing a window drops all of its claims
+windows.claim('a', 'monitor')
+windows.claim('b', 'monitor')
+windows.claim('c', 'main')
+assert.deepEqual(windows.releaseWindow('monitor').sort(), ['a', 'b'])
+assert.deepEqual(windows.claimedBy('monitor'), [])
+assert.deepEqual(windows.claimedBy('main'), ['c'])
+const last = emitted[emitted.length - 1]
+assert.ok(last.type === EVENT_TYPES.CONVERSATION_CLAIMED)
+assert.equal(last.payload.windowId, null)
+
+console.log('window routing tests passed')
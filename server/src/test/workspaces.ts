@@ -0,0 +1,74 @@
+import assert from 'node:assert/strict'
+import { mkdtempSync, readFileSync, rmSync } from 'node:fs'
+import { tmpdir } from 'node:os'
+import { join } from 'node:path'
+import { loadEnvConfig, type AppConfig } from '../lib/config.js'
+import type { RuntimeContext } from '../lib/runtime.js'
+import { SERVER_ROOT } from '../lib/server-root.js'
+import { applyWorkspace, WorkspaceSwitcher } from '../lib/workspace.js'
+import { workspaceRoutes } from '../routes/workspaces.js'
+
+const dir = mkdtempSync(join(tmpdir(), 'workspaces-'))
+try {
+  const base: AppConfig = { ...loadEnvConfig(), dbDialect: 'sqlite', databaseUrl: 'data/app.db', workspace: undefined, workspacesDir: dir }
+
+  // Every path of a workspace hangs off one root, whatever the working directory
+  const relative = applyWorkspace({ ...base, workspacesDir: 'data/workspaces', workspace: 'work' })
+  assert.equal(relative.workspacesDir, join(SERVER_ROOT, 'data/workspaces'))
+  assert.equal(relative.databaseUrl, join(SERVER_ROOT, 'data/workspaces/work/app.db'))
+  assert.equal(relative.tasksDir, join(SERVER_ROOT, 'data/workspaces/work/tasks'))
+  assert.equal(relative.sessionFilesDir, join(SERVER_ROOT, 'data/workspaces/work/sessions'))
+  assert.equal(applyWorkspace(base).databaseUrl, join(SERVER_ROOT, 'data/app.db'))
+  assert.equal(applyWorkspace({ ...base, databaseUrl: ':memory:' }).databaseUrl, ':memory:')
+
+  // Switching closes the running workspace and opens the next one in its place
+  type FakeRuntime = { config: AppConfig; closed: boolean }
+  const log: string[] = []
+  let failOn: string | null = null
+  let active: FakeRuntime | null = null
+  const switcher = new WorkspaceSwitcher<FakeRuntime>(base, { config: applyWorkspace(base), closed: false }, {
+    init: async (config) => {
+      log.push(`init ${config.workspace}`)
+      if (config.workspace === failOn) throw new Error('cannot open')
+      return { config, closed: false }
+    },
+    shutdown: async (runtime) => {
+      log.push(`shutdown ${runtime.config.workspace}`)
+      runtime.closed = true
+    },
+    activate: (runtime) => { active = runtime },
+  })
+  const work = await switcher.switch('work')
+  assert.deepEqual(log, ['shutdown default', 'init work'])
+  assert.equal(work.config.databaseUrl, join(dir, 'work', 'app.db'))
+  assert.equal(active, work)
+  assert.equal(switcher.runtime, work)
+  assert.equal(readFileSync(join(dir, 'active'), 'utf-8'), 'work\n')
+  assert.equal(await switcher.switch('work'), work, 'switching to the open workspace is a no-op')
+
+  // A workspace that fails to open leaves the previous one serving
+  log.length = 0
+  failOn = 'broken'
+  await assert.rejects(switcher.switch('broken'), /cannot open/)
+  assert.deepEqual(log, ['shutdown work', 'init broken', 'init work'])
+  assert.equal(switcher.runtime.config.workspace, 'work')
+  assert.equal(switcher.runtime.closed, false)
+  assert.equal(readFileSync(join(dir, 'active'), 'utf-8'), 'work\n')
+  await assert.rejects(switcher.switch('../etc'), /Invalid workspace name/)
+
+  // The route switches through the callback and reports the workspace now serving
+  const app = workspaceRoutes(
+    { config: switcher.runtime.config } as RuntimeContext,
+    async (name) => (await switcher.switch(name)) as unknown as RuntimeContext,
+  )
+  const post = (body: unknown) => app.request('/switch', { method: 'POST', body: JSON.stringify(body), headers: { 'content-type': 'application/json' } })
+  assert.equal((await post({ name: 'bad name' })).status, 400)
+  const switched = await post({ name: 'default' })
+  assert.equal(switched.status, 200)
+  assert.deepEqual(await switched.json(), { active: 'default', previous: 'work' })
+  assert.equal(switcher.runtime.config.workspace, 'default')
+
+  console.log('workspace tests passed')
+} finally {
+  rmSync(dir, { recursive: true, force: true })
+}
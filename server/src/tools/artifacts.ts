@@ -0,0 +1,40 @@
+import type { ToolContext, ToolHandler, ToolResult } from './types.js'
+import { writeAgentArtifact } from '../services/agent-artifacts.js'
+
+export interface ArtifactToolOptions {
+  sessionFilesRoot: string
+}
+
+export function registerArtifactTools(
+  registry: { register: (h: ToolHandler) => void },
+  options: ArtifactToolOptions,
+): void {
+  registry.register({
+    metadata: {
+      name: 'artifacts.write',
+      description: 'Create a file for the user to download, such as a report, CSV export or calendar (.ics) file. The user saves it from the conversation; use it for deliverables, not scratch work.',
+      parameters: {
+        type: 'object',
+        properties: {
+          name: { type: 'string', description: 'File name including its extension, e.g. "trip.ics"' },
+          content: { type: 'string', description: 'File contents' },
+          encoding: { type: 'string', enum: ['utf-8', 'base64'], description: 'Use base64 for binary files (default utf-8)' },
+        },
+        required: ['name', 'content'],
+      },
+      requires_approval: false,
+      category: 'mutating',
+    },
+    async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
+      const artifact = await writeAgentArtifact(options.sessionFilesRoot, ctx.session_id, {
+        name: String(args.name ?? ''),
+        content: String(args.content ?? ''),
+        encoding: args.encoding as 'utf-8' | 'base64' | undefined,
+      })
+      return { ok: true, output: artifact }
+    },
+    preview(args: Record<string, unknown>) {
+      return { summary: `Create ${String(args.name ?? 'file')} for download` }
+    },
+  })
+}
@@ -0,0 +1,33 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { searchAttachments, type AttachmentSearchDeps } from '../services/attachment-index.js'
+
+export function registerAttachmentTools(
+  registry: { register: (h: ToolHandler) => void },
+  deps: AttachmentSearchDeps,
+): void {
+  registry.register({
+    metadata: {
+      name: 'attachments.search',
+      description: 'Search files attached to this conversation and return the passages most relevant to a query. Use it for attachments too large to include in the message.',
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: 'What to look for, phrased as a question or keywords' },
+          attachment: { type: 'string', description: 'File name or hash to search; omit to search every attachment' },
+          limit: { type: 'integer', description: 'Maximum passages to return (default 5, max 20)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const results = await searchAttachments(deps, ctx.session_id, args.query as string, {
+        attachment: args.attachment as string | undefined,
+        limit: args.limit as number | undefined,
+        signal: ctx.signal,
+      })
+      return { ok: true, output: { results } }
+    },
+  })
+}
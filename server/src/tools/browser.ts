@@ -0,0 +1,38 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import type { SessionRepository } from '../repositories/types.js'
+import type { BrowserExtensions } from '../services/browser-extension.js'
+
+export interface BrowserToolDeps {
+  extensions: BrowserExtensions
+  sessions: SessionRepository
+}
+
+export function registerBrowserTools(
+  registry: { register: (h: ToolHandler) => void },
+  deps: BrowserToolDeps,
+): void {
+  registry.register({
+    metadata: {
+      name: 'browser.get_active_tab',
+      description: "Get the tab the user has open in their browser through the companion extension: its URL, title and any selected text. Use it when the user refers to \"this page\" or \"what I'm reading\". Set include_text to also get the page's readable text.",
+      parameters: {
+        type: 'object',
+        properties: {
+          include_text: { type: 'boolean', description: 'Also return the page text (default false)' },
+        },
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      try {
+        const session = await deps.sessions.getById(ctx.session_id)
+        if (!session) return { ok: false, error: `Session not found: ${ctx.session_id}` }
+        const page = await deps.extensions.getActiveTab(session.userId, { includeText: args.include_text === true }, ctx.signal)
+        return { ok: true, output: page }
+      } catch (err) {
+        return { ok: false, error: err instanceof Error ? err.message : String(err) }
+      }
+    },
+  })
+}
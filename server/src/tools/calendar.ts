@@ -0,0 +1,199 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import type { CalendarService } from '../services/caldav.js'
+import { buildEvent, type NewCalendarEvent } from '../services/icalendar.js'
+import { isEmailAddress } from '../services/mail-mime.js'
+import { parseLocalTime } from './utilities.js'
+
+export const CALENDAR_TOOL_NAMES = ['calendar.list_calendars', 'calendar.list_events', 'calendar.create_event', 'calendar.delete_event'] as const
+
+const DEFAULT_RANGE_DAYS = 7
+const MAX_RANGE_DAYS = 366
+const DEFAULT_DURATION_MINUTES = 60
+
+const ACCOUNT = { type: 'string', description: 'Account name from calendar.list_calendars; needed only when several are configured' }
+
+/** calendar.* over the CalDAV accounts saved with PUT /api/calendar/accounts/:name (iCloud, Fastmail, Nextcloud, ...). */
+export function registerCalendarTools(
+  registry: { register: (h: ToolHandler) => void },
+  calendar: CalendarService,
+): void {
+  registry.register({
+    metadata: {
+      name: 'calendar.list_calendars',
+      description: 'List the user\'s calendars across configured accounts.',
+      parameters: { type: 'object', properties: { account: ACCOUNT } },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(async () => ({ calendars: await calendar.listCalendars(optionalText(args.account), ctx.signal) }))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'calendar.list_events',
+      description: 'List events between two times (default: the next 7 days), soonest first. Recurring events are listed per occurrence.',
+      parameters: {
+        type: 'object',
+        properties: {
+          from: { type: 'string', description: 'ISO date or date-time; default now' },
+          to: { type: 'string', description: 'ISO date or date-time, exclusive; default 7 days after from' },
+          query: { type: 'string', description: 'Text to find in the title, description, location or attendees' },
+          calendar: { type: 'string', description: 'Calendar name or id; default all' },
+          account: ACCOUNT,
+        },
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(async () => {
+        const from = args.from === undefined ? new Date() : parseInstant(args.from, 'from')
+        const to = args.to === undefined ? new Date(from.getTime() + DEFAULT_RANGE_DAYS * 86_400_000) : parseInstant(args.to, 'to')
+        if (to <= from) throw new Error('to must be after from')
+        if (to.getTime() - from.getTime() > MAX_RANGE_DAYS * 86_400_000) throw new Error(`The range is limited to ${MAX_RANGE_DAYS} days`)
+        return calendar.listEvents({
+          account: optionalText(args.account),
+          calendar: optionalText(args.calendar),
+          from,
+          to,
+          query: optionalText(args.query),
+        }, ctx.signal)
+      })
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'calendar.create_event',
+      description: 'Create an event. Adding attendees emails them an invitation.',
+      parameters: {
+        type: 'object',
+        properties: {
+          summary: { type: 'string', description: 'Event title' },
+          start: { type: 'string', description: 'Local start such as "2026-10-20 09:00" in time_zone, or a date "2026-10-20" when all_day' },
+          end: { type: 'string', description: 'Local end in the same form; default one hour after start, or the start date when all_day' },
+          all_day: { type: 'boolean', description: 'All-day event; end is the last day, inclusive' },
+          time_zone: { type: 'string', description: 'IANA time zone of start and end, e.g. "Europe/Warsaw" (default UTC)' },
+          location: { type: 'string' },
+          description: { type: 'string' },
+          attendees: { type: 'array', items: { type: 'string' }, description: 'Email addresses to invite' },
+          calendar: { type: 'string', description: 'Calendar name or id; default the account\'s first calendar' },
+          account: ACCOUNT,
+        },
+        required: ['summary', 'start'],
+      },
+      requires_approval: false,
+      category: 'mutating',
+      approval_triggers: [{ when: [{ arg: 'attendees', op: 'regex', value: '@' }], reason: 'Attendees will be emailed an invitation.' }],
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(async () => {
+        const summary = optionalText(args.summary)
+        if (!summary) throw new Error('summary is required')
+        const start = optionalText(args.start)
+        if (!start) throw new Error('start is required')
+        const end = optionalText(args.end)
+        const attendees = (Array.isArray(args.attendees) ? args.attendees : []).map((entry) => String(entry).trim()).filter(Boolean)
+        const invalid = attendees.find((address) => !isEmailAddress(address))
+        if (invalid) throw new Error(`Invalid attendee address: ${invalid}`)
+        const account = optionalText(args.account)
+
+        let times: Pick<NewCalendarEvent, 'start' | 'end' | 'allDay'>
+        if (args.all_day === true) {
+          const first = parseDate(start, 'start')
+          const last = end ? parseDate(end, 'end') : first
+          if (last < first) throw new Error('end must not be before start')
+          // DTEND of an all-day event is the day after the last one
+          const after = new Date(Date.parse(`${last}T00:00:00Z`) + 86_400_000).toISOString().slice(0, 10)
+          times = { start: new Date(`${first}T00:00:00Z`), end: new Date(`${after}T00:00:00Z`), allDay: { start: first, end: after } }
+        } else {
+          const zone = optionalText(args.time_zone) ?? 'UTC'
+          const startAt = parseLocalTime(start, zone)
+          const endAt = end ? parseLocalTime(end, zone) : new Date(startAt.getTime() + DEFAULT_DURATION_MINUTES * 60_000)
+          if (endAt <= startAt) throw new Error('end must be after start')
+          times = { start: startAt, end: endAt }
+        }
+
+        const { uid, ics } = buildEvent({
+          summary,
+          ...times,
+          location: optionalText(args.location),
+          description: optionalText(args.description),
+          attendees,
+          organizer: attendees.length > 0 ? (await calendar.organizer(account)) ?? undefined : undefined,
+        })
+        const created = await calendar.createEvent({ account, calendar: optionalText(args.calendar), uid, ics }, ctx.signal)
+        return {
+          id: uid,
+          ...created,
+          start: times.allDay ? { date: times.allDay.start } : { dateTime: times.start.toISOString() },
+          end: times.allDay ? { date: times.allDay.end } : { dateTime: times.end.toISOString() },
+        }
+      })
+    },
+    preview(args) {
+      const attendees = Array.isArray(args.attendees) ? args.attendees.join(', ') : ''
+      return {
+        summary: `Create event "${String(args.summary ?? '')}" at ${String(args.start ?? '')}${attendees ? ` with ${attendees}` : ''}`,
+        details: { end: args.end, time_zone: args.time_zone, calendar: args.calendar, location: args.location },
+      }
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'calendar.delete_event',
+      description: 'Delete an event by the url from calendar.list_events. For a recurring event this removes the whole series.',
+      parameters: {
+        type: 'object',
+        properties: {
+          url: { type: 'string', description: 'Event url from calendar.list_events' },
+          account: ACCOUNT,
+        },
+        required: ['url'],
+      },
+      requires_approval: true,
+      category: 'destructive',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const url = optionalText(args.url)
+      if (!url) return { ok: false, error: 'url is required' }
+      return run(async () => {
+        await calendar.deleteEvent({ account: optionalText(args.account), url }, ctx.signal)
+        return { deleted: url }
+      })
+    },
+    preview(args) {
+      return { summary: `Delete calendar event ${String(args.url ?? '')}` }
+    },
+  })
+}
+
+/** "2026-10-20", "2026-10-20T09:00:00Z" or with an offset; a bare date is midnight UTC. */
+function parseInstant(value: unknown, field: string): Date {
+  const text = typeof value === 'string' ? value.trim() : ''
+  const parsed = new Date(/^\d{4}-\d{2}-\d{2}$/.test(text) ? `${text}T00:00:00Z` : text)
+  if (!text || Number.isNaN(parsed.getTime())) throw new Error(`${field} must be an ISO date or date-time`)
+  return parsed
+}
+
+function parseDate(value: string, field: string): string {
+  if (!/^\d{4}-\d{2}-\d{2}$/.test(value) || Number.isNaN(Date.parse(`${value}T00:00:00Z`))) {
+    throw new Error(`${field} must be a date such as 2026-10-20 for all-day events`)
+  }
+  return value
+}
+
+async function run(action: () => Promise<unknown>): Promise<ToolResult> {
+  try {
+    return { ok: true, output: await action() }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+}
+
+function optionalText(value: unknown): string | undefined {
+  return typeof value === 'string' && value.trim() ? value.trim() : undefined
+}
@@ -0,0 +1,33 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import type { ContactsDirectory } from '../services/contacts.js'
+
+export function registerContactTools(
+  registry: { register: (h: ToolHandler) => void },
+  directory: ContactsDirectory,
+): void {
+  registry.register({
+    metadata: {
+      name: 'contacts.search',
+      description: `Look up people in the user's address book (${directory.name}) by name, nickname or company, returning their email addresses and phone numbers.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: 'Name, nickname or company, e.g. "Sarah" or "Acme"' },
+          limit: { type: 'integer', description: 'Maximum contacts (default 5, max 25)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const query = typeof args.query === 'string' ? args.query.trim() : ''
+      if (!query) return { ok: false, error: 'query is required' }
+      try {
+        return { ok: true, output: { contacts: await directory.search(query, args.limit as number | undefined, ctx.signal) } }
+      } catch (err) {
+        return { ok: false, error: err instanceof Error ? err.message : String(err) }
+      }
+    },
+  })
+}
@@ -0,0 +1,114 @@
+/** Past this many changed lines per side the diff is cut short rather than computed. */
+const MAX_DIFF_LINES = 2000
+const CONTEXT_LINES = 3
+
+type DiffOp = { type: ' ' | '-' | '+'; line: string }
+
+export interface UnifiedDiff {
+  diff: string
+  truncated: boolean
+}
+
+/**
+ * Line-based unified diff of `before` → `after`, in the `--- a/ +++ b/` form
+ * the approval prompt already renders. `before` is null for a new file.
+ */
+export function unifiedDiff(path: string, before: string | null, after: string): UnifiedDiff {
+  const oldLines = splitLines(before ?? '')
+  const newLines = splitLines(after)
+  const header = [before === null ? '--- /dev/null' : `--- a/${path}`, `+++ b/${path}`]
+
+  // Common prefix/suffix first: most edits touch a small region of a large file
+  let start = 0
+  while (start < oldLines.length && start < newLines.length && oldLines[start] === newLines[start]) start++
+  let oldEnd = oldLines.length
+  let newEnd = newLines.length
+  while (oldEnd > start && newEnd > start && oldLines[oldEnd - 1] === newLines[newEnd - 1]) {
+    oldEnd--
+    newEnd--
+  }
+  if (start === oldEnd && start === newEnd) return { diff: '', truncated: false }
+
+  let truncated = false
+  let middle: DiffOp[]
+  if (oldEnd - start > MAX_DIFF_LINES || newEnd - start > MAX_DIFF_LINES) {
+    truncated = true
+    middle = [
+      ...oldLines.slice(start, Math.min(oldEnd, start + MAX_DIFF_LINES)).map((line) => ({ type: '-' as const, line })),
+      ...newLines.slice(start, Math.min(newEnd, start + MAX_DIFF_LINES)).map((line) => ({ type: '+' as const, line })),
+    ]
+  } else {
+    middle = diffLines(oldLines.slice(start, oldEnd), newLines.slice(start, newEnd))
+  }
+
+  const ops: DiffOp[] = [
+    ...oldLines.slice(0, start).map((line) => ({ type: ' ' as const, line })),
+    ...middle,
+    ...oldLines.slice(oldEnd).map((line) => ({ type: ' ' as const, line })),
+  ]
+  return { diff: [...header, ...hunks(ops)].join('\n') + '\n', truncated }
+}
+
+function splitLines(text: string): string[] {
+  if (text === '') return []
+  const lines = text.split('\n')
+  if (lines[lines.length - 1] === '') lines.pop()
+  return lines
+}
+
+/** Longest-common-subsequence diff; inputs are already bounded by MAX_DIFF_LINES. */
+function diffLines(a: string[], b: string[]): DiffOp[] {
+  const width = b.length + 1
+  const lcs = new Uint32Array((a.length + 1) * width)
+  for (let i = a.length - 1; i >= 0; i--) {
+    for (let j = b.length - 1; j >= 0; j--) {
+      lcs[i * width + j] = a[i] === b[j]
+        ? lcs[(i + 1) * width + j + 1] + 1
+        : Math.max(lcs[(i + 1) * width + j], lcs[i * width + j + 1])
+    }
+  }
+  const ops: DiffOp[] = []
+  let i = 0
+  let j = 0
+  while (i < a.length && j < b.length) {
+    if (a[i] === b[j]) {
+      ops.push({ type: ' ', line: a[i] })
+      i++
+      j++
+    } else if (lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]) {
+      ops.push({ type: '-', line: a[i++] })
+    } else {
+      ops.push({ type: '+', line: b[j++] })
+    }
+  }
+  while (i < a.length) ops.push({ type: '-', line: a[i++] })
+  while (j < b.length) ops.push({ type: '+', line: b[j++] })
+  return ops
+}
+
+function hunks(ops: DiffOp[]): string[] {
+  const out: string[] = []
+  let index = 0
+  while (index < ops.length) {
+    const firstChange = ops.findIndex((op, k) => k >= index && op.type !== ' ')
+    if (firstChange === -1) break
+
+    // Extend the hunk while changes are within 2×context of each other
+    let end = firstChange
+    for (let k = firstChange; k < ops.length && k <= end + CONTEXT_LINES * 2; k++) {
+      if (ops[k].type !== ' ') end = k
+    }
+    const from = Math.max(index, firstChange - CONTEXT_LINES)
+    const to = Math.min(ops.length, end + CONTEXT_LINES + 1)
+    const slice = ops.slice(from, to)
+
+    const oldStart = ops.slice(0, from).filter((op) => op.type !== '+').length
+    const newStart = ops.slice(0, from).filter((op) => op.type !== '-').length
+    const oldCount = slice.filter((op) => op.type !== '+').length
+    const newCount = slice.filter((op) => op.type !== '-').length
+    out.push(`@@ -${oldCount === 0 ? oldStart : oldStart + 1},${oldCount} +${newCount === 0 ? newStart : newStart + 1},${newCount} @@`)
+    for (const op of slice) out.push(`${op.type}${op.line}`)
+    index = to
+  }
+  return out
+}
@@ -0,0 +1,46 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import type { EntityKind } from '../repositories/types.js'
+import { lookupEntities, type EntityToolDeps } from '../services/entities.js'
+import { memoryBlockedReason } from '../services/memory-settings.js'
+
+const KINDS: EntityKind[] = ['person', 'organization', 'meeting']
+
+export function registerEntityTools(
+  registry: { register: (h: ToolHandler) => void },
+  deps: EntityToolDeps,
+): void {
+  registry.register({
+    metadata: {
+      name: 'entities.lookup',
+      description: 'Resolve a person, organization or recurring meeting the user mentions ("Sarah", "Acme", "the design review") to the concrete details learned from their email and calendar: addresses, organization, schedule and attendees. Use it before emailing or inviting someone named only by first name. Several results mean the name is ambiguous; ask which one unless context settles it.',
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: 'Name, address or description to look up' },
+          kind: { type: 'string', enum: KINDS, description: 'Only return this kind of entity' },
+          limit: { type: 'integer', description: 'Maximum results (default 5, max 20)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const query = typeof args.query === 'string' ? args.query.trim() : ''
+      if (!query) return { ok: false, error: 'query is required' }
+      if (args.kind !== undefined && !KINDS.includes(args.kind as EntityKind)) {
+        return { ok: false, error: `kind must be one of: ${KINDS.join(', ')}` }
+      }
+      const blocked = await memoryBlockedReason(deps.preferences, ctx.session_id)
+      if (blocked) return { ok: true, output: { results: [], note: blocked } }
+      const session = await deps.sessions.getById(ctx.session_id)
+      if (!session) return { ok: false, error: `Session not found: ${ctx.session_id}` }
+      const results = await lookupEntities(deps, session.userId, query, {
+        kind: args.kind as EntityKind | undefined,
+        limit: args.limit as number | undefined,
+        signal: ctx.signal,
+      })
+      return { ok: true, output: { results } }
+    },
+  })
+}
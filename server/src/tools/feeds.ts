@@ -0,0 +1,161 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import type { Feed, FeedItem, FeedRepository, SessionRepository } from '../repositories/types.js'
+import { subscribeFeed } from '../services/feeds.js'
+
+const DEFAULT_LIMIT = 30
+const MAX_LIMIT = 200
+/** Longer articles are cut here; the link has the rest. */
+const MAX_READ_CHARS = 20_000
+
+export interface FeedToolDeps {
+  feeds: FeedRepository
+  sessions: SessionRepository
+  fetchImpl?: typeof fetch
+}
+
+export function registerFeedTools(
+  registry: { register: (h: ToolHandler) => void },
+  deps: FeedToolDeps,
+): void {
+  const userFeeds = async (sessionId: string): Promise<{ userId: string; feeds: Feed[] }> => {
+    const session = await deps.sessions.getById(sessionId)
+    if (!session) throw new Error(`Session not found: ${sessionId}`)
+    return { userId: session.userId, feeds: await deps.feeds.listByUser(session.userId) }
+  }
+
+  registry.register({
+    metadata: {
+      name: 'feeds.subscribe',
+      description: 'Subscribe the user to an RSS or Atom feed so its items are polled and stored locally. Accepts the feed URL or a website that links to its feed.',
+      parameters: {
+        type: 'object',
+        properties: {
+          url: { type: 'string', description: 'Feed URL, or a site URL such as https://example.com/blog' },
+          title: { type: 'string', description: "Name for the feed; defaults to the feed's own title" },
+        },
+        required: ['url'],
+      },
+      requires_approval: false,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(async () => {
+        const { userId } = await userFeeds(ctx.session_id)
+        const { feed, added, existing } = await subscribeFeed(
+          { repositories: { feeds: deps.feeds } },
+          userId,
+          { url: String(args.url ?? ''), title: optionalText(args.title) },
+          deps.fetchImpl,
+          ctx.signal,
+        )
+        return {
+          feed: { id: feed.id, title: feed.title, url: feed.url, site: feed.siteUrl },
+          ...(existing ? { already_subscribed: true } : { items_stored: added }),
+        }
+      })
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'feeds.list_items',
+      description: "List items from the user's subscribed feeds, newest first, with a short summary of each. Use `since` for digests, e.g. \"24h\" for the last day. Read a full item with feeds.read_item.",
+      parameters: {
+        type: 'object',
+        properties: {
+          feed: { type: 'string', description: 'Feed title, ID or URL; omit for all feeds' },
+          since: { type: 'string', description: 'Only items published after this: an ISO 8601 date-time, or a period back from now such as "12h" or "7d"' },
+          limit: { type: 'integer', description: `Maximum items (default ${DEFAULT_LIMIT}, max ${MAX_LIMIT})` },
+        },
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(async () => {
+        let { feeds } = await userFeeds(ctx.session_id)
+        if (feeds.length === 0) throw new Error('No feeds are subscribed; add one with feeds.subscribe')
+        const wanted = optionalText(args.feed)
+        if (wanted) {
+          feeds = feeds.filter((feed) => feed.id === wanted || feed.url === wanted || feed.title.toLowerCase() === wanted.toLowerCase())
+          if (feeds.length === 0) throw new Error(`No subscribed feed named ${wanted}`)
+        }
+        const since = parseSince(optionalText(args.since))
+        const limit = Math.min(Math.max(1, Math.floor(typeof args.limit === 'number' ? args.limit : DEFAULT_LIMIT)), MAX_LIMIT)
+        const titles = new Map(feeds.map((feed) => [feed.id, feed.title]))
+        // One extra row tells whether the limit cut the list short
+        const items = await deps.feeds.listItems(feeds.map((feed) => feed.id), { since, limit: limit + 1 })
+        const failing = feeds.filter((feed) => feed.error).map((feed) => ({ feed: feed.title, error: feed.error }))
+        return {
+          items: items.slice(0, limit).map((item) => ({ ...describe(item, titles), summary: item.summary })),
+          ...(items.length > limit && { truncated: true }),
+          ...(failing.length > 0 && { failing }),
+        }
+      })
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'feeds.read_item',
+      description: 'Read the full text of a feed item listed by feeds.list_items, as the feed provides it. Some feeds only carry a summary; fetch the link for the whole article then.',
+      parameters: {
+        type: 'object',
+        properties: {
+          id: { type: 'string', description: 'Item ID from feeds.list_items' },
+        },
+        required: ['id'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(async () => {
+        const { feeds } = await userFeeds(ctx.session_id)
+        const item = await deps.feeds.getItem(String(args.id ?? ''))
+        const titles = new Map(feeds.map((feed) => [feed.id, feed.title]))
+        if (!item || !titles.has(item.feedId)) throw new Error(`Feed item not found: ${String(args.id ?? '')}`)
+        return {
+          ...describe(item, titles),
+          content: item.content.slice(0, MAX_READ_CHARS),
+          ...(item.content.length > MAX_READ_CHARS && { truncated: true }),
+        }
+      })
+    },
+  })
+}
+
+function describe(item: FeedItem, titles: Map<string, string>) {
+  return {
+    id: item.id,
+    feed: titles.get(item.feedId) ?? item.feedId,
+    title: item.title,
+    link: item.link,
+    ...(item.author && { author: item.author }),
+    published: new Date(item.publishedAt).toISOString(),
+  }
+}
+
+function parseSince(value: string | undefined): number | undefined {
+  if (!value) return undefined
+  const period = value.match(/^(\d+)\s*([hdw])$/i)
+  if (period) {
+    const unitMs = { h: 3_600_000, d: 86_400_000, w: 604_800_000 }[period[2].toLowerCase() as 'h' | 'd' | 'w']
+    return Date.now() - Number(period[1]) * unitMs
+  }
+  const time = Date.parse(value)
+  if (!Number.isFinite(time)) throw new Error(`since must be an ISO 8601 date-time or a period such as "24h", got "${value}"`)
+  return time
+}
+
+async function run(action: () => Promise<unknown>): Promise<ToolResult> {
+  try {
+    return { ok: true, output: await action() }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+}
+
+function optionalText(value: unknown): string | undefined {
+  return typeof value === 'string' && value.trim() ? value.trim() : undefined
+}
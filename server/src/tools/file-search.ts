@@ -0,0 +1,33 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { searchLocalFiles, type FileSearchDeps } from '../services/file-search.js'
+
+export function registerFileSearchTools(
+  registry: { register: (h: ToolHandler) => void },
+  deps: FileSearchDeps,
+): void {
+  registry.register({
+    metadata: {
+      name: 'files.semantic_search',
+      description: "Find code and documents by meaning across the user's indexed local files (projects linked to this conversation and knowledge-base folders). Returns the best-matching passages with each file's absolute path. Use it when you know what a file does or says but not the words it uses; read the file with files.read if you need more than the passage.",
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: 'What the code or text does or is about, in plain words' },
+          under: { type: 'string', description: 'Absolute folder to limit the search to; omit to search every indexed file' },
+          limit: { type: 'integer', description: 'Maximum passages to return (default 5, max 20)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const results = await searchLocalFiles(deps, ctx.session_id, args.query as string, {
+        under: args.under as string | undefined,
+        limit: args.limit as number | undefined,
+        signal: ctx.signal,
+      })
+      return { ok: true, output: { results } }
+    },
+  })
+}
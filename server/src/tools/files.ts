@@ -1,6 +1,10 @@
 import fs from 'fs/promises'
+import { readFileSync, statSync } from 'fs'
 import path from 'path'
-import type { ToolHandler, ToolResult, ToolContext } from './types.js'
+import type { ToolChange, ToolHandler, ToolPreview, ToolResult, ToolContext } from './types.js'
+import { unifiedDiff } from './diff.js'
+import { quarantineTruncatedOutput } from '../orchestrator/output.js'
+import type { ApprovalTrigger } from '../orchestrator/approval-rules.js'
 import {
   joinManagedFileRef,
   managedFileRefForPath,
@@ -9,6 +13,8 @@ import {
 } from './path-policy.js'
 
 const DEFAULT_MAX_LINES = 200
+/** Previews read the current file synchronously; bigger files only get a summary. */
+const MAX_PREVIEW_BYTES = 512 * 1024
 
 const IMAGE_EXTENSIONS: Record<string, string> = {
   '.png': 'image/png',
@@ -21,12 +27,26 @@ const IMAGE_EXTENSIONS: Record<string, string> = {
 export interface FileToolOptions {
   sessionFilesRoot: string
   notesDir: string
+  /** Managed paths where writes run without approval; calls outside them still ask. */
+  approvalFreeRoots?: string[]
+}
+
+/** Asks only when `path` falls outside every approval-free root. */
+export function outsideRootsTrigger(roots: string[] | undefined): ApprovalTrigger[] | undefined {
+  const normalized = (roots ?? []).map((root) => root.trim().replace(/^\.\//, '').replace(/\/+$/, '')).filter(Boolean)
+  if (normalized.length === 0) return undefined
+  return [{
+    when: normalized.map((root) => ({ arg: 'path', op: 'under' as const, value: root, not: true })),
+    reason: `The path is outside ${normalized.map((root) => `${root}/`).join(', ')}.`,
+  }]
 }
 
 export function registerFileTools(
   registry: { register: (h: ToolHandler) => void },
   options: FileToolOptions,
 ): void {
+  const approvalTriggers = outsideRootsTrigger(options.approvalFreeRoots)
+
   registry.register({
     metadata: {
       name: 'files.read',
@@ -41,6 +61,7 @@ export function registerFileTools(
         required: ['path'],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const resolved = resolveManagedFilePath(args.path as string, {
@@ -69,6 +90,16 @@ export function registerFileTools(
           }
         }
 
+        if (isSpilledJsonOutput(resolved)) {
+          const quarantined = await quarantineTruncatedOutput(filePath)
+          if (quarantined) {
+            return {
+              ok: false,
+              error: `${resolved.ref} is incomplete (its write was interrupted) and was moved aside; run the tool again for its output`,
+            }
+          }
+        }
+
         const content = await fs.readFile(filePath, 'utf-8')
         const lines = content.split('\n')
         const start = Math.max(1, startLine) - 1
@@ -108,6 +139,8 @@ export function registerFileTools(
         required: ['path', 'content'],
       },
       requires_approval: true,
+      category: 'destructive',
+      approval_triggers: approvalTriggers,
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const resolved = resolveManagedFilePath(args.path as string, {
@@ -127,8 +160,14 @@ export function registerFileTools(
         return { ok: false, error: `Failed to write file: ${(err as Error).message}` }
       }
     },
-    preview(args: Record<string, unknown>): { summary: string } {
-      return { summary: `Write ${Buffer.byteLength(String(args.content))} bytes to ${args.path}` }
+    preview(args: Record<string, unknown>, ctx: ToolContext): ToolPreview {
+      return {
+        summary: `Write ${Buffer.byteLength(String(args.content))} bytes to ${args.path}`,
+        change: previewFileChange(args, ctx, options, (before) => ({
+          operation: before === null ? 'create' : 'overwrite',
+          after: String(args.content),
+        })),
+      }
     },
   })
 
@@ -146,6 +185,8 @@ export function registerFileTools(
         required: ['path', 'old_text', 'new_text'],
       },
       requires_approval: true,
+      category: 'mutating',
+      approval_triggers: approvalTriggers,
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const resolved = resolveManagedFilePath(args.path as string, {
@@ -173,8 +214,14 @@ export function registerFileTools(
         return { ok: false, error: `Failed to edit file: ${(err as Error).message}` }
       }
     },
-    preview(args: Record<string, unknown>): { summary: string } {
-      return { summary: `Edit ${args.path}: replace text` }
+    preview(args: Record<string, unknown>, ctx: ToolContext): ToolPreview {
+      const oldText = String(args.old_text)
+      const change = previewFileChange(args, ctx, options, (before) => {
+        const idx = before?.indexOf(oldText) ?? -1
+        if (before === null || idx === -1) return null
+        return { operation: 'edit', after: before.slice(0, idx) + String(args.new_text) + before.slice(idx + oldText.length) }
+      })
+      return { summary: `Edit ${args.path}: replace text`, change }
     },
   })
 
@@ -191,6 +238,8 @@ export function registerFileTools(
         required: ['path', 'content'],
       },
       requires_approval: true,
+      category: 'mutating',
+      approval_triggers: approvalTriggers,
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const resolved = resolveManagedFilePath(args.path as string, {
@@ -217,8 +266,12 @@ export function registerFileTools(
         return { ok: false, error: `Failed to create file: ${(err as Error).message}` }
       }
     },
-    preview(args: Record<string, unknown>): { summary: string } {
-      return { summary: `Create ${args.path}` }
+    preview(args: Record<string, unknown>, ctx: ToolContext): ToolPreview {
+      return {
+        summary: `Create ${args.path}`,
+        change: previewFileChange(args, ctx, options, (before) =>
+          before === null ? { operation: 'create', after: String(args.content) } : null),
+      }
     },
   })
 
@@ -235,6 +288,8 @@ export function registerFileTools(
         required: ['path', 'content'],
       },
       requires_approval: true,
+      category: 'mutating',
+      approval_triggers: approvalTriggers,
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const resolved = resolveManagedFilePath(args.path as string, {
@@ -253,8 +308,14 @@ export function registerFileTools(
         return { ok: false, error: `Failed to append to file: ${(err as Error).message}` }
       }
     },
-    preview(args: Record<string, unknown>): { summary: string } {
-      return { summary: `Append to ${args.path}` }
+    preview(args: Record<string, unknown>, ctx: ToolContext): ToolPreview {
+      return {
+        summary: `Append to ${args.path}`,
+        change: previewFileChange(args, ctx, options, (before) => ({
+          operation: 'append',
+          after: (before ?? '') + String(args.content),
+        })),
+      }
     },
   })
 
@@ -273,6 +334,7 @@ export function registerFileTools(
         required: ['path'],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const resolved = resolveManagedFilePath(args.path as string, {
@@ -358,3 +420,46 @@ function matchesGlob(value: string, glob: string): boolean {
     .replace(/\?/g, '.')
   return new RegExp(`^${escaped}$`, 'i').test(value)
 }
+
+/** Tool output spilled as JSON; the agent's own files under artifact://files/ may hold anything. */
+function isSpilledJsonOutput(resolved: ResolvedManagedFile): boolean {
+  return resolved.kind === 'artifact'
+    && path.extname(resolved.fsPath).toLowerCase() === '.json'
+    && !resolved.logicalPath.startsWith('files/')
+}
+
+type FileChange = Extract<ToolChange, { kind: 'file' }>
+
+/**
+ * Diff of what a write-capable file tool would do, for the approval prompt.
+ * `apply` gets the current content (null when the file does not exist) and
+ * returns the result, or null when the call would fail anyway.
+ */
+function previewFileChange(
+  args: Record<string, unknown>,
+  ctx: ToolContext,
+  options: FileToolOptions,
+  apply: (before: string | null) => { operation: FileChange['operation']; after: string } | null,
+): FileChange | undefined {
+  try {
+    const resolved = resolveManagedFilePath(args.path as string, {
+      sessionFilesRoot: options.sessionFilesRoot,
+      notesDir: options.notesDir,
+      sessionId: ctx.session_id,
+      access: 'write',
+    })
+    let before: string | null = null
+    try {
+      if (statSync(resolved.fsPath).size > MAX_PREVIEW_BYTES) return undefined
+      before = readFileSync(resolved.fsPath, 'utf-8')
+    } catch {
+      // File does not exist yet
+    }
+    const result = apply(before)
+    if (!result) return undefined
+    const { diff, truncated } = unifiedDiff(resolved.ref, before, result.after)
+    return { kind: 'file', path: resolved.ref, operation: result.operation, diff, truncated }
+  } catch {
+    return undefined
+  }
+}
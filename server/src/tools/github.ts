@@ -0,0 +1,171 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { GitHubClient, parseRepo, type GitHubSearchType } from '../services/github.js'
+
+const SEARCH_TYPES: GitHubSearchType[] = ['issues', 'code', 'repositories']
+
+export function registerGitHubTools(
+  registry: { register: (h: ToolHandler) => void },
+  client: GitHubClient,
+): void {
+  registry.register({
+    metadata: {
+      name: 'github.list_issues',
+      description: 'List issues and pull requests in a GitHub repository, most recently updated first.',
+      parameters: {
+        type: 'object',
+        properties: {
+          repo: { type: 'string', description: 'Repository as "owner/name"' },
+          state: { type: 'string', enum: ['open', 'closed', 'all'], description: 'Default: open' },
+          kind: { type: 'string', enum: ['issue', 'pull', 'all'], description: 'Default: all' },
+          labels: { type: 'array', items: { type: 'string' }, description: 'Only items with all of these labels' },
+          limit: { type: 'integer', description: 'Maximum results (default 10, max 50)' },
+        },
+        required: ['repo'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(async () => {
+        const issues = await client.listIssues(parseRepo(args.repo), {
+          state: oneOf(args.state, ['open', 'closed', 'all'] as const),
+          kind: oneOf(args.kind, ['issue', 'pull', 'all'] as const),
+          labels: Array.isArray(args.labels) ? args.labels.filter((label): label is string => typeof label === 'string') : undefined,
+          limit: args.limit as number | undefined,
+        }, ctx.signal)
+        return { issues }
+      })
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'github.read_issue',
+      description: 'Read a GitHub issue or pull request with its description and comments. Pull requests also report branches, draft and merge state.',
+      parameters: {
+        type: 'object',
+        properties: {
+          repo: { type: 'string', description: 'Repository as "owner/name"' },
+          number: { type: 'integer', description: 'Issue or pull request number' },
+        },
+        required: ['repo', 'number'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+      memoize: true,
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(() => client.getIssue(parseRepo(args.repo), parseNumber(args.number), ctx.signal))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'github.search',
+      description: 'Search GitHub with its search syntax, e.g. "repo:owner/name is:open label:bug crash" for issues, "repo:owner/name parseConfig" for code.',
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: 'GitHub search query' },
+          type: { type: 'string', enum: SEARCH_TYPES, description: 'What to search (default issues, which includes pull requests)' },
+          limit: { type: 'integer', description: 'Maximum results (default 10, max 50)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+      memoize: true,
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const query = typeof args.query === 'string' ? args.query.trim() : ''
+      if (!query) return { ok: false, error: 'query is required' }
+      if (args.type !== undefined && !SEARCH_TYPES.includes(args.type as GitHubSearchType)) {
+        return { ok: false, error: `type must be one of: ${SEARCH_TYPES.join(', ')}` }
+      }
+      return run(() => client.search((args.type as GitHubSearchType | undefined) ?? 'issues', query, args.limit as number | undefined, ctx.signal))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'github.comment',
+      description: 'Post a comment on a GitHub issue or pull request as the user. Comments are public to everyone who can see the repository.',
+      parameters: {
+        type: 'object',
+        properties: {
+          repo: { type: 'string', description: 'Repository as "owner/name"' },
+          number: { type: 'integer', description: 'Issue or pull request number' },
+          body: { type: 'string', description: 'Comment in GitHub Markdown' },
+        },
+        required: ['repo', 'number', 'body'],
+      },
+      requires_approval: true,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const body = typeof args.body === 'string' ? args.body.trim() : ''
+      if (!body) return { ok: false, error: 'body is required' }
+      return run(async () => ({ comment: await client.comment(parseRepo(args.repo), parseNumber(args.number), body, ctx.signal) }))
+    },
+    preview(args) {
+      return {
+        summary: `Comment on ${String(args.repo)}#${String(args.number)}`,
+        details: { body: args.body },
+      }
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'github.create_issue',
+      description: 'Open a new issue in a GitHub repository as the user.',
+      parameters: {
+        type: 'object',
+        properties: {
+          repo: { type: 'string', description: 'Repository as "owner/name"' },
+          title: { type: 'string', description: 'Issue title' },
+          body: { type: 'string', description: 'Description in GitHub Markdown' },
+          labels: { type: 'array', items: { type: 'string' }, description: 'Existing labels to apply' },
+        },
+        required: ['repo', 'title'],
+      },
+      requires_approval: true,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const title = typeof args.title === 'string' ? args.title.trim() : ''
+      if (!title) return { ok: false, error: 'title is required' }
+      return run(async () => ({
+        issue: await client.createIssue(parseRepo(args.repo), {
+          title,
+          body: typeof args.body === 'string' ? args.body : undefined,
+          labels: Array.isArray(args.labels) ? args.labels.filter((label): label is string => typeof label === 'string') : undefined,
+        }, ctx.signal),
+      }))
+    },
+    preview(args) {
+      return {
+        summary: `Open issue in ${String(args.repo)}: ${String(args.title ?? '')}`,
+        details: { body: args.body, labels: args.labels },
+      }
+    },
+  })
+}
+
+async function run(action: () => Promise<unknown>): Promise<ToolResult> {
+  try {
+    return { ok: true, output: await action() }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+}
+
+function parseNumber(value: unknown): number {
+  const number = typeof value === 'string' ? Number(value.replace(/^#/, '')) : value
+  if (typeof number !== 'number' || !Number.isInteger(number) || number <= 0) throw new Error('number must be a positive integer')
+  return number
+}
+
+function oneOf<T extends string>(value: unknown, allowed: readonly T[]): T | undefined {
+  return allowed.find((option) => option === value)
+}
@@ -0,0 +1,31 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { searchHistory, type HistorySearchDeps } from '../services/history-search.js'
+
+export function registerHistoryTools(
+  registry: { register: (h: ToolHandler) => void },
+  deps: HistorySearchDeps,
+): void {
+  registry.register({
+    metadata: {
+      name: 'history.search',
+      description: "Search the user's other conversations and return matching message snippets with their conversation title, date and IDs. Use it when the user refers to something discussed before, such as what was decided about a topic.",
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: 'Keywords for what was discussed' },
+          limit: { type: 'integer', description: 'Maximum snippets to return (default 5, max 20)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const results = await searchHistory(deps, ctx.session_id, args.query as string, {
+        limit: args.limit as number | undefined,
+        signal: ctx.signal,
+      })
+      return { ok: true, output: { results } }
+    },
+  })
+}
@@ -0,0 +1,96 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { isEntityId, type HomeAssistantClient } from '../services/home-assistant.js'
+
+export function registerHomeAssistantTools(
+  registry: { register: (h: ToolHandler) => void },
+  client: HomeAssistantClient,
+): void {
+  registry.register({
+    metadata: {
+      name: 'home_assistant.states',
+      description: 'Read the state of the user\'s Home Assistant entities ("is the garage closed?"): one entity with all attributes, or a list filtered by domain, area or name.',
+      parameters: {
+        type: 'object',
+        properties: {
+          entity_id: { type: 'string', description: 'Exact id such as "cover.garage_door"; returns every attribute' },
+          domain: { type: 'string', description: 'e.g. light, switch, cover, lock, climate, sensor, binary_sensor' },
+          area: { type: 'string', description: 'Area name such as "Office"' },
+          search: { type: 'string', description: 'Words to find in the entity id, name or area' },
+          limit: { type: 'integer', description: 'Maximum entities (default 50, max 200)' },
+        },
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(async () => {
+        const entityId = optionalText(args.entity_id)
+        if (entityId) return client.state(entityId, ctx.signal)
+        return client.states({
+          domain: optionalText(args.domain),
+          area: optionalText(args.area),
+          search: optionalText(args.search),
+          limit: args.limit as number | undefined,
+        }, ctx.signal)
+      })
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'home_assistant.call_service',
+      description: 'Call a Home Assistant service, e.g. light.turn_off, cover.close_cover, lock.lock, climate.set_temperature, scene.turn_on. Look up entity ids with home_assistant.states first.',
+      parameters: {
+        type: 'object',
+        properties: {
+          domain: { type: 'string', description: 'Service domain, e.g. "light"' },
+          service: { type: 'string', description: 'Service name, e.g. "turn_off"' },
+          entity_id: { type: 'array', items: { type: 'string' }, description: 'Target entity ids, e.g. ["light.office_ceiling"]' },
+          data: { type: 'object', description: 'Extra service data, e.g. {"brightness_pct": 40} or {"temperature": 21}' },
+        },
+        required: ['domain', 'service'],
+      },
+      requires_approval: true,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(async () => {
+        const domain = optionalText(args.domain) ?? ''
+        const service = optionalText(args.service) ?? ''
+        const entityIds = entityIdList(args.entity_id)
+        const invalid = entityIds.find((id) => !isEntityId(id))
+        if (invalid) throw new Error(`Invalid entity_id "${invalid}"`)
+        const data = args.data && typeof args.data === 'object' && !Array.isArray(args.data) ? args.data as Record<string, unknown> : {}
+        const changed = await client.callService(domain, service, {
+          ...data,
+          ...(entityIds.length > 0 && { entity_id: entityIds.length === 1 ? entityIds[0] : entityIds }),
+        }, ctx.signal)
+        return { called: `${domain}.${service}`, changed }
+      })
+    },
+    preview(args) {
+      const targets = entityIdList(args.entity_id)
+      return {
+        summary: `Home Assistant: ${String(args.domain ?? '')}.${String(args.service ?? '')}${targets.length > 0 ? ` on ${targets.join(', ')}` : ''}`,
+        ...(args.data !== undefined && { details: { data: args.data } }),
+      }
+    },
+  })
+}
+
+function entityIdList(value: unknown): string[] {
+  const list = Array.isArray(value) ? value : typeof value === 'string' ? value.split(',') : []
+  return list.map((entry) => String(entry).trim()).filter(Boolean)
+}
+
+async function run(action: () => Promise<unknown>): Promise<ToolResult> {
+  try {
+    return { ok: true, output: await action() }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+}
+
+function optionalText(value: unknown): string | undefined {
+  return typeof value === 'string' && value.trim() ? value.trim() : undefined
+}
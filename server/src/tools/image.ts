@@ -0,0 +1,71 @@
+import type { ToolContext, ToolHandler, ToolResult } from './types.js'
+import type { AppConfig } from '../lib/config.js'
+import type { ProviderRegistry } from '../providers/types.js'
+import { writeAgentArtifact } from '../services/agent-artifacts.js'
+import { generateImage, IMAGE_ORIENTATIONS, type ImageOrientation } from '../services/image-generation.js'
+
+export interface ImageToolOptions {
+  sessionFilesRoot: string
+  config: AppConfig
+  providers: ProviderRegistry
+}
+
+export function registerImageTools(
+  registry: { register: (h: ToolHandler) => void },
+  options: ImageToolOptions,
+): void {
+  registry.register({
+    metadata: {
+      name: 'image.generate',
+      description: 'Generate an image such as an illustration or diagram from a text prompt. The image is shown to you and saved as a file the user can download.',
+      parameters: {
+        type: 'object',
+        properties: {
+          prompt: { type: 'string', description: 'Detailed description of the image, including style and any text it should contain' },
+          orientation: { type: 'string', enum: IMAGE_ORIENTATIONS, description: 'Image shape (default square)' },
+          name: { type: 'string', description: 'File name for the saved image, e.g. "onboarding-flow.png"' },
+        },
+        required: ['prompt'],
+      },
+      requires_approval: false,
+      category: 'mutating',
+    },
+    async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
+      const prompt = String(args.prompt ?? '')
+      const orientation = IMAGE_ORIENTATIONS.includes(args.orientation as ImageOrientation)
+        ? args.orientation as ImageOrientation
+        : 'square'
+      const image = await generateImage(options, { prompt, orientation, signal: ctx.signal })
+      const artifact = await writeAgentArtifact(options.sessionFilesRoot, ctx.session_id, {
+        name: imageFileName(typeof args.name === 'string' ? args.name : '', prompt),
+        content: image.data.toString('base64'),
+        encoding: 'base64',
+        metadata: {
+          prompt: prompt.trim(),
+          ...(image.revisedPrompt && { revisedPrompt: image.revisedPrompt }),
+          model: image.model,
+          orientation,
+        },
+      })
+      return {
+        ok: true,
+        output: artifact,
+        content_blocks: [
+          { type: 'text', text: `Generated image saved as ${artifact.ref}` },
+          { type: 'image', media_type: image.mediaType, data: image.data.toString('base64') },
+        ],
+      }
+    },
+    preview(args: Record<string, unknown>) {
+      return { summary: `Generate image: ${String(args.prompt ?? '').slice(0, 80)}` }
+    },
+  })
+}
+
+/** The requested name with a .png extension, or a short slug of the prompt. */
+export function imageFileName(name: string, prompt: string): string {
+  const base = name.trim().replace(/\.(png|jpe?g|webp)$/i, '')
+    || prompt.toLowerCase().replace(/[^a-z0-9]+/g, '-').replace(/^-+/, '').slice(0, 40).replace(/-+$/, '')
+    || 'image'
+  return `${base}.png`
+}
@@ -0,0 +1,166 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import type { IssueTracker, UpdateTrackerIssueInput } from '../services/issue-trackers.js'
+
+const LABELS: Record<IssueTracker['name'], { product: string; project: string; query: string; key: string }> = {
+  jira: {
+    product: 'Jira',
+    project: 'Project key, e.g. "OPS"',
+    query: 'Words to find, or JQL such as "assignee = currentUser() AND status != Done"',
+    key: 'Issue key, e.g. "OPS-42"',
+  },
+  linear: {
+    product: 'Linear',
+    project: 'Team key, e.g. "ENG"',
+    query: 'Words to find in titles and descriptions',
+    key: 'Issue identifier, e.g. "ENG-42"',
+  },
+}
+
+/** Registers <tracker>.search, .read_issue, .create_issue and .update_issue. */
+export function registerIssueTrackerTools(
+  registry: { register: (h: ToolHandler) => void },
+  tracker: IssueTracker,
+): void {
+  const { name } = tracker
+  const labels = LABELS[name]
+
+  registry.register({
+    metadata: {
+      name: `${name}.search`,
+      description: `Search ${labels.product} issues, most recently updated first.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: labels.query },
+          project: { type: 'string', description: `${labels.project}; omit to search everywhere` },
+          limit: { type: 'integer', description: 'Maximum results (default 10, max 50)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const query = typeof args.query === 'string' ? args.query : ''
+      return run(async () => ({
+        issues: await tracker.search(query, {
+          project: optionalText(args.project),
+          limit: args.limit as number | undefined,
+        }, ctx.signal),
+      }))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: `${name}.read_issue`,
+      description: `Read a ${labels.product} issue with its description and recent comments.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          key: { type: 'string', description: labels.key },
+        },
+        required: ['key'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const key = optionalText(args.key)
+      if (!key) return { ok: false, error: 'key is required' }
+      return run(() => tracker.getIssue(key, ctx.signal))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: `${name}.create_issue`,
+      description: `Create a ${labels.product} issue as the user.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          project: { type: 'string', description: labels.project },
+          title: { type: 'string', description: 'Issue title' },
+          description: { type: 'string', description: 'Issue description' },
+          ...(name === 'jira' && {
+            type: { type: 'string', description: 'Issue type name (default Task)' },
+            labels: { type: 'array', items: { type: 'string' }, description: 'Labels to apply' },
+          }),
+        },
+        required: ['project', 'title'],
+      },
+      requires_approval: true,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const project = optionalText(args.project)
+      const title = optionalText(args.title)
+      if (!project || !title) return { ok: false, error: 'project and title are required' }
+      return run(async () => ({
+        issue: await tracker.createIssue({
+          project,
+          title,
+          description: optionalText(args.description),
+          type: optionalText(args.type),
+          labels: Array.isArray(args.labels) ? args.labels.filter((label): label is string => typeof label === 'string') : undefined,
+        }, ctx.signal),
+      }))
+    },
+    preview(args) {
+      return {
+        summary: `Create ${labels.product} issue in ${String(args.project)}: ${String(args.title ?? '')}`,
+        details: { description: args.description, type: args.type, labels: args.labels },
+      }
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: `${name}.update_issue`,
+      description: `Change a ${labels.product} issue's title, description or status, and/or add a comment, as the user. Status is the target status name, e.g. "In Progress".`,
+      parameters: {
+        type: 'object',
+        properties: {
+          key: { type: 'string', description: labels.key },
+          title: { type: 'string', description: 'New title' },
+          description: { type: 'string', description: 'New description, replacing the old one' },
+          status: { type: 'string', description: 'Status to move the issue to' },
+          comment: { type: 'string', description: 'Comment to add' },
+        },
+        required: ['key'],
+      },
+      requires_approval: true,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const key = optionalText(args.key)
+      if (!key) return { ok: false, error: 'key is required' }
+      const input: UpdateTrackerIssueInput = {}
+      for (const field of ['title', 'description', 'status', 'comment'] as const) {
+        const value = optionalText(args[field])
+        if (value) input[field] = value
+      }
+      if (Object.keys(input).length === 0) return { ok: false, error: 'Nothing to update: pass title, description, status or comment' }
+      return run(async () => ({ issue: await tracker.updateIssue(key, input, ctx.signal) }))
+    },
+    preview(args) {
+      const changes = (['title', 'description', 'status', 'comment'] as const).filter((field) => args[field] !== undefined)
+      return {
+        summary: `Update ${labels.product} ${String(args.key)}: ${changes.join(', ') || 'nothing'}`,
+        details: Object.fromEntries(changes.map((field) => [field, args[field]])),
+      }
+    },
+  })
+}
+
+async function run(action: () => Promise<unknown>): Promise<ToolResult> {
+  try {
+    return { ok: true, output: await action() }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+}
+
+function optionalText(value: unknown): string | undefined {
+  return typeof value === 'string' && value.trim() ? value.trim() : undefined
+}
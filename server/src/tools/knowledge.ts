@@ -0,0 +1,33 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { searchKnowledge, type KnowledgeSearchDeps } from '../services/knowledge-base.js'
+
+export function registerKnowledgeTools(
+  registry: { register: (h: ToolHandler) => void },
+  deps: KnowledgeSearchDeps,
+): void {
+  registry.register({
+    metadata: {
+      name: 'kb.search',
+      description: "Search the user's knowledge base (folders, documents and web pages they registered) and return the most relevant passages, each with a citation. Cite the sources you use in your answer.",
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: 'What to look for, phrased as a question or keywords' },
+          source: { type: 'string', description: 'Knowledge source name or ID to search; omit to search all of them' },
+          limit: { type: 'integer', description: 'Maximum passages to return (default 5, max 20)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const results = await searchKnowledge(deps, ctx.session_id, args.query as string, {
+        source: args.source as string | undefined,
+        limit: args.limit as number | undefined,
+        signal: ctx.signal,
+      })
+      return { ok: true, output: { results } }
+    },
+  })
+}
@@ -0,0 +1,149 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import type { MailDraftInput, MailService } from '../services/mail.js'
+import { addressOf, isEmailAddress } from '../services/mail-mime.js'
+
+export const MAIL_TOOL_NAMES = ['mail.list', 'mail.read', 'mail.draft', 'mail.send'] as const
+
+const MESSAGE_PROPERTIES = {
+  to: { type: 'array', items: { type: 'string' }, description: 'Recipient addresses; defaults to the sender when replying' },
+  cc: { type: 'array', items: { type: 'string' }, description: 'Cc addresses' },
+  bcc: { type: 'array', items: { type: 'string' }, description: 'Bcc addresses' },
+  subject: { type: 'string', description: 'Subject; defaults to "Re: …" when replying' },
+  body: { type: 'string', description: 'Plain-text body' },
+  reply_to_id: { type: 'string', description: 'ID from mail.list of the message being answered' },
+}
+
+/** mail.* over the IMAP/SMTP account saved with PUT /api/mail/account. */
+export function registerMailTools(
+  registry: { register: (h: ToolHandler) => void },
+  mail: MailService,
+): void {
+  registry.register({
+    metadata: {
+      name: 'mail.list',
+      description: 'List recent messages in the user\'s mailbox, newest first, optionally filtered by text or unread.',
+      parameters: {
+        type: 'object',
+        properties: {
+          mailbox: { type: 'string', description: 'Mailbox name (default INBOX)' },
+          query: { type: 'string', description: 'Text to find in headers or body' },
+          unread_only: { type: 'boolean', description: 'Only unread messages' },
+          limit: { type: 'integer', description: 'Maximum messages (default 20, max 100)' },
+        },
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(async () => ({
+        messages: await mail.list({
+          mailbox: optionalText(args.mailbox),
+          query: optionalText(args.query),
+          unreadOnly: args.unread_only === true,
+          limit: args.limit as number | undefined,
+        }, ctx.signal),
+      }))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'mail.read',
+      description: 'Read a message: headers, plain text and the names of its attachments. Does not mark it as read.',
+      parameters: {
+        type: 'object',
+        properties: {
+          id: { type: 'string', description: 'Message ID from mail.list' },
+        },
+        required: ['id'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const id = optionalText(args.id)
+      if (!id) return { ok: false, error: 'id is required' }
+      return run(() => mail.read(id, ctx.signal))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'mail.draft',
+      description: 'Save a message to the user\'s Drafts mailbox for them to review and send. Prefer this over mail.send unless the user asked to send.',
+      parameters: { type: 'object', properties: MESSAGE_PROPERTIES, required: ['body'] },
+      requires_approval: false,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return withInput(args, (input) => run(() => mail.draft(input, ctx.signal)))
+    },
+    preview(args) {
+      return { summary: `Save draft to ${describeRecipients(args)}: ${String(args.subject ?? '(reply)')}`, details: { body: args.body } }
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'mail.send',
+      description: 'Send a message from the user\'s account.',
+      parameters: { type: 'object', properties: MESSAGE_PROPERTIES, required: ['body'] },
+      requires_approval: true,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return withInput(args, (input) => run(() => mail.send(input, ctx.signal)))
+    },
+    preview(args) {
+      return {
+        summary: `Send email to ${describeRecipients(args)}: ${String(args.subject ?? '(reply)')}`,
+        details: { cc: args.cc, bcc: args.bcc, body: args.body },
+      }
+    },
+  })
+}
+
+async function withInput(args: Record<string, unknown>, action: (input: MailDraftInput) => Promise<ToolResult>): Promise<ToolResult> {
+  const body = typeof args.body === 'string' ? args.body : ''
+  if (!body.trim()) return { ok: false, error: 'body is required' }
+  let input: MailDraftInput
+  try {
+    input = {
+      to: addresses(args.to, 'to'),
+      cc: addresses(args.cc, 'cc'),
+      bcc: addresses(args.bcc, 'bcc'),
+      subject: optionalText(args.subject),
+      body,
+      replyToId: optionalText(args.reply_to_id),
+    }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+  if (input.to.length === 0 && !input.replyToId) return { ok: false, error: 'to is required unless replying' }
+  return action(input)
+}
+
+function addresses(value: unknown, field: string): string[] {
+  const list = Array.isArray(value) ? value : typeof value === 'string' ? value.split(',') : []
+  return list.map((entry) => addressOf(String(entry))).filter(Boolean).map((address) => {
+    if (!isEmailAddress(address)) throw new Error(`Invalid ${field} address: ${address}`)
+    return address
+  })
+}
+
+function describeRecipients(args: Record<string, unknown>): string {
+  const to = Array.isArray(args.to) ? args.to.join(', ') : typeof args.to === 'string' ? args.to : ''
+  return to || 'the original sender'
+}
+
+async function run(action: () => Promise<unknown>): Promise<ToolResult> {
+  try {
+    return { ok: true, output: await action() }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+}
+
+function optionalText(value: unknown): string | undefined {
+  return typeof value === 'string' && value.trim() ? value.trim() : undefined
+}
@@ -0,0 +1,42 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { MEDIA_ACTIONS, type MediaAction, type MediaPlayer } from '../services/media.js'
+
+const PRODUCTS: Record<MediaPlayer['name'], string> = {
+  spotify: 'Spotify',
+  macos: 'Music or Spotify on the Mac',
+}
+
+export function registerMediaTools(
+  registry: { register: (h: ToolHandler) => void },
+  player: MediaPlayer,
+): void {
+  registry.register({
+    metadata: {
+      name: 'media.control',
+      description: `Control the user's music playback (${PRODUCTS[player.name]}): play, pause, toggle, skip to the next or previous track, or report what is playing with "status".`,
+      parameters: {
+        type: 'object',
+        properties: {
+          action: { type: 'string', enum: ['status', ...MEDIA_ACTIONS], description: 'What to do; "status" only reports the current track' },
+        },
+        required: ['action'],
+      },
+      requires_approval: false,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const action = typeof args.action === 'string' ? args.action.trim().toLowerCase() : ''
+      if (action !== 'status' && !MEDIA_ACTIONS.includes(action as MediaAction)) {
+        return { ok: false, error: `action must be one of status, ${MEDIA_ACTIONS.join(', ')}` }
+      }
+      try {
+        if (action === 'status') return { ok: true, output: await player.nowPlaying(ctx.signal) }
+        await player.control(action as MediaAction, ctx.signal)
+        // Skipping takes a moment to show up; report the new track when asked rather than a stale one
+        return { ok: true, output: { done: action } }
+      } catch (err) {
+        return { ok: false, error: err instanceof Error ? err.message : String(err) }
+      }
+    },
+  })
+}
@@ -0,0 +1,88 @@
+import { createHash } from 'node:crypto'
+import type { ToolResult } from './types.js'
+
+const DEFAULT_TTL_MS = 5 * 60 * 1000
+const MAX_ENTRIES_PER_SESSION = 50
+
+interface MemoEntry {
+  result: ToolResult
+  expiresAt: number
+}
+
+/**
+ * Results of tools that opt in with `memoize`, per session, so a controller
+ * that repeats a call it already made gets the earlier answer instead of
+ * hitting the external service again. Entries are keyed by tool name and a
+ * hash of the args with object keys sorted.
+ */
+export class ToolMemo {
+  private readonly sessions = new Map<string, Map<string, MemoEntry>>()
+
+  constructor(private readonly ttlMs = DEFAULT_TTL_MS) {}
+
+  get(sessionId: string, name: string, args: Record<string, unknown>): ToolResult | undefined {
+    const entries = this.sessions.get(sessionId)
+    const key = memoKey(name, args)
+    const entry = entries?.get(key)
+    if (!entry) return undefined
+    if (entry.expiresAt <= Date.now()) {
+      entries!.delete(key)
+      if (entries!.size === 0) this.sessions.delete(sessionId)
+      return undefined
+    }
+    return entry.result
+  }
+
+  set(sessionId: string, name: string, args: Record<string, unknown>, result: ToolResult): void {
+    const now = Date.now()
+    this.sweep(now)
+    let entries = this.sessions.get(sessionId)
+    if (!entries) {
+      entries = new Map()
+      this.sessions.set(sessionId, entries)
+    }
+    const key = memoKey(name, args)
+    // Re-inserted so the oldest key stays first
+    entries.delete(key)
+    entries.set(key, { result, expiresAt: now + this.ttlMs })
+    if (entries.size > MAX_ENTRIES_PER_SESSION) entries.delete(entries.keys().next().value!)
+  }
+
+  /** Sessions with results still held. */
+  get sessionCount(): number {
+    return this.sessions.size
+  }
+
+  /** Forgets a session's results, e.g. after a call that may have changed what they describe. */
+  clear(sessionId: string): void {
+    this.sessions.delete(sessionId)
+  }
+
+  /**
+   * Drops expired results in every session, and sessions left with none, so
+   * sessions that are never read again do not hold memory for good. Entries
+   * share one TTL and are kept in insertion order, so each session's expired
+   * ones come first.
+   */
+  private sweep(now: number): void {
+    for (const [sessionId, entries] of this.sessions) {
+      for (const [key, entry] of entries) {
+        if (entry.expiresAt > now) break
+        entries.delete(key)
+      }
+      if (entries.size === 0) this.sessions.delete(sessionId)
+    }
+  }
+}
+
+export function memoKey(name: string, args: Record<string, unknown>): string {
+  return `${name}:${createHash('sha256').update(JSON.stringify(sortKeys(args))).digest('hex')}`
+}
+
+function sortKeys(value: unknown): unknown {
+  if (Array.isArray(value)) return value.map(sortKeys)
+  if (value === null || typeof value !== 'object') return value
+  return Object.fromEntries(
+    Object.keys(value).sort().map((key) => [key, sortKeys((value as Record<string, unknown>)[key])]),
+  )
+}
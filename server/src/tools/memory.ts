@@ -0,0 +1,98 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { forgetMemory, rememberMemories, searchMemories, type MemoryToolDeps } from '../services/memory.js'
+import { MEMORY_CATEGORIES, memoryBlockedReason, readMemorySettings } from '../services/memory-settings.js'
+
+export function registerMemoryTools(
+  registry: { register: (h: ToolHandler) => void },
+  deps: MemoryToolDeps,
+): void {
+  const userOf = async (sessionId: string): Promise<string> => {
+    const session = await deps.sessions.getById(sessionId)
+    if (!session) throw new Error(`Session not found: ${sessionId}`)
+    return session.userId
+  }
+
+  registry.register({
+    metadata: {
+      name: 'memory.save',
+      description: 'Remember a durable fact about the user or a preference for how to respond, for use in future conversations. Write it as a short standalone sentence about "the user". Restating a saved memory updates it instead of adding a copy.',
+      parameters: {
+        type: 'object',
+        properties: {
+          content: { type: 'string', description: 'The memory, e.g. "The user works in UTC+2"' },
+          kind: { type: 'string', enum: ['fact', 'preference'], description: 'Default: fact' },
+          category: { type: 'string', enum: [...MEMORY_CATEGORIES], description: 'What the memory is about (default other)' },
+        },
+        required: ['content'],
+      },
+      requires_approval: false,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const content = typeof args.content === 'string' ? args.content.trim() : ''
+      if (!content) return { ok: false, error: 'content is required' }
+      const kind = args.kind === 'preference' ? 'preference' : 'fact'
+      const category = MEMORY_CATEGORIES.find((value) => value === args.category) ?? 'other'
+      const blocked = await memoryBlockedReason(deps.preferences, ctx.session_id)
+      if (blocked) return { ok: false, error: `${blocked}; nothing was saved` }
+      if ((await readMemorySettings(deps.preferences)).excludedCategories.includes(category)) {
+        return { ok: false, error: `The user excluded ${category} memories; nothing was saved` }
+      }
+      const userId = await userOf(ctx.session_id)
+      // Saved on request, so it is as certain as a memory gets
+      const [memory] = await rememberMemories(deps, userId, [{ content, kind, category, confidence: 1 }], {
+        sessionId: ctx.session_id,
+        signal: ctx.signal,
+      })
+      return { ok: true, output: { id: memory.id, content: memory.content, kind: memory.kind, category: memory.category, saved: true } }
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'memory.search',
+      description: "Search what is remembered about the user from earlier conversations and return the most relevant memories with their IDs. Use it when the user's background or preferences would change the answer.",
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: 'What to look for, phrased as a question or keywords' },
+          limit: { type: 'integer', description: 'Maximum memories to return (default 5, max 20)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const blocked = await memoryBlockedReason(deps.preferences, ctx.session_id)
+      if (blocked) return { ok: true, output: { results: [], note: blocked } }
+      const results = await searchMemories(deps, await userOf(ctx.session_id), args.query as string, {
+        limit: args.limit as number | undefined,
+        signal: ctx.signal,
+      })
+      return { ok: true, output: { results } }
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'memory.forget',
+      description: 'Delete a memory that is wrong or that the user asked to forget. Find its ID with memory.search first.',
+      parameters: {
+        type: 'object',
+        properties: {
+          id: { type: 'string', description: 'Memory ID from memory.search' },
+        },
+        required: ['id'],
+      },
+      requires_approval: true,
+      category: 'destructive',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const id = args.id as string
+      const removed = await forgetMemory(deps, await userOf(ctx.session_id), id)
+      if (!removed) return { ok: false, error: `Memory not found: ${id}` }
+      return { ok: true, output: { id, forgotten: true } }
+    },
+  })
+}
@@ -33,6 +33,7 @@ export function registerNoteTools(
         required: ['title', 'markdown'],
       },
       requires_approval: false,
+      category: 'mutating',
     },
     async handle(args: Record<string, unknown>): Promise<ToolResult> {
       const title = String(args.title ?? '').trim()
@@ -89,6 +90,7 @@ export function registerNoteTools(
         required: ['from', 'title'],
       },
       requires_approval: false,
+      category: 'mutating',
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const title = String(args.title ?? '').trim()
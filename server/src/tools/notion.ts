@@ -0,0 +1,126 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { markdownToBlocks, NotionClient, parsePageId } from '../services/notion.js'
+
+/** Approval previews show this much of the text being filed. */
+const PREVIEW_CHARS = 1_000
+
+export function registerNotionTools(
+  registry: { register: (h: ToolHandler) => void },
+  client: NotionClient,
+): void {
+  registry.register({
+    metadata: {
+      name: 'notion.search',
+      description: "Find pages in the user's Notion workspace by title, most recently edited first. Only pages shared with the integration are visible.",
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: 'Words in the page title' },
+          limit: { type: 'integer', description: 'Maximum results (default 10, max 50)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+      memoize: true,
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const query = typeof args.query === 'string' ? args.query.trim() : ''
+      return run(async () => ({ pages: await client.search(query, (args.limit as number | undefined) ?? 10, ctx.signal) }))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'notion.read_page',
+      description: 'Read a Notion page as Markdown-like text. Sub-pages are listed by ID; read them separately.',
+      parameters: {
+        type: 'object',
+        properties: {
+          page: { type: 'string', description: 'Page ID or URL' },
+        },
+        required: ['page'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+      memoize: true,
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(() => client.readPage(parsePageId(args.page), ctx.signal))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'notion.append',
+      description: 'Add content to the end of an existing Notion page. Content is Markdown: headings, lists, to-dos, quotes and code blocks become Notion blocks.',
+      parameters: {
+        type: 'object',
+        properties: {
+          page: { type: 'string', description: 'Page ID or URL' },
+          content: { type: 'string', description: 'Markdown to add' },
+        },
+        required: ['page', 'content'],
+      },
+      requires_approval: true,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const blocks = markdownToBlocks(typeof args.content === 'string' ? args.content : '')
+      if (blocks.length === 0) return { ok: false, error: 'content is required' }
+      return run(async () => {
+        const pageId = parsePageId(args.page)
+        return { page: pageId, blocksAdded: await client.append(pageId, blocks, ctx.signal) }
+      })
+    },
+    preview(args) {
+      return {
+        summary: `Add to Notion page ${String(args.page)}`,
+        details: { content: excerpt(args.content) },
+      }
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'notion.create_page',
+      description: 'Create a Notion page under an existing page, e.g. to file a research summary or meeting notes. Content is Markdown.',
+      parameters: {
+        type: 'object',
+        properties: {
+          parent: { type: 'string', description: 'Parent page ID or URL' },
+          title: { type: 'string', description: 'Page title' },
+          content: { type: 'string', description: 'Markdown body' },
+        },
+        required: ['parent', 'title'],
+      },
+      requires_approval: true,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const title = typeof args.title === 'string' ? args.title.trim() : ''
+      if (!title) return { ok: false, error: 'title is required' }
+      const blocks = markdownToBlocks(typeof args.content === 'string' ? args.content : '')
+      return run(async () => ({ page: await client.createPage(parsePageId(args.parent), title, blocks, ctx.signal) }))
+    },
+    preview(args) {
+      return {
+        summary: `Create Notion page "${String(args.title ?? '')}"`,
+        details: { parent: args.parent, content: excerpt(args.content) },
+      }
+    },
+  })
+}
+
+async function run(action: () => Promise<unknown>): Promise<ToolResult> {
+  try {
+    return { ok: true, output: await action() }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+}
+
+function excerpt(content: unknown): string {
+  const text = typeof content === 'string' ? content : ''
+  return text.length > PREVIEW_CHARS ? `${text.slice(0, PREVIEW_CHARS)}…` : text
+}
@@ -0,0 +1,104 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import type { PersonalNotesBackend } from '../services/personal-notes.js'
+
+const BACKEND_LABELS: Record<PersonalNotesBackend['name'], { product: string; id: string; folder: string }> = {
+  obsidian: { product: 'Obsidian vault', id: 'Note path in the vault, e.g. "Meetings/2026-10-17 Standup.md"', folder: 'Vault folder path, e.g. "Meetings"' },
+  apple_notes: { product: 'Apple Notes', id: 'Note ID from personal_notes.search', folder: 'Notes folder name' },
+}
+
+export function registerPersonalNoteTools(
+  registry: { register: (h: ToolHandler) => void },
+  backend: PersonalNotesBackend,
+): void {
+  const labels = BACKEND_LABELS[backend.name]
+
+  registry.register({
+    metadata: {
+      name: 'personal_notes.search',
+      description: `Search the user's own notes (${labels.product}). These are the user's notes, not research notes saved by agents. An empty query lists recent notes.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: 'Words to find in note titles and text' },
+          folder: { type: 'string', description: `${labels.folder}; omit to search everywhere` },
+          limit: { type: 'integer', description: 'Maximum results (default 10, max 50)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const query = typeof args.query === 'string' ? args.query : ''
+      return run(async () => ({
+        notes: await backend.search(query, {
+          folder: optionalText(args.folder),
+          limit: args.limit as number | undefined,
+        }, ctx.signal),
+      }))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'personal_notes.read',
+      description: `Read one of the user's notes in ${labels.product}.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          id: { type: 'string', description: labels.id },
+        },
+        required: ['id'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const id = optionalText(args.id)
+      if (!id) return { ok: false, error: 'id is required' }
+      return run(() => backend.read(id, ctx.signal))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'personal_notes.create',
+      description: `File a new note in the user's ${labels.product}, e.g. a meeting or research summary. Never overwrites an existing note.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          title: { type: 'string', description: 'Note title' },
+          body: { type: 'string', description: 'Note text in markdown' },
+          folder: { type: 'string', description: `${labels.folder}; default ${backend.name === 'obsidian' ? 'vault root' : 'Notes folder'}` },
+        },
+        required: ['title', 'body'],
+      },
+      requires_approval: false,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const title = optionalText(args.title)
+      const body = typeof args.body === 'string' ? args.body : ''
+      if (!title || !body.trim()) return { ok: false, error: 'title and body are required' }
+      return run(async () => ({ note: await backend.create({ title, body, folder: optionalText(args.folder) }, ctx.signal) }))
+    },
+    preview(args) {
+      return {
+        summary: `Create note "${String(args.title ?? '')}" in ${labels.product}${args.folder ? ` / ${String(args.folder)}` : ''}`,
+        details: { body: typeof args.body === 'string' ? args.body.slice(0, 500) : undefined },
+      }
+    },
+  })
+}
+
+async function run(action: () => Promise<unknown>): Promise<ToolResult> {
+  try {
+    return { ok: true, output: await action() }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+}
+
+function optionalText(value: unknown): string | undefined {
+  return typeof value === 'string' && value.trim() ? value.trim() : undefined
+}
@@ -0,0 +1,30 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { type PluginManifest, pluginToolName } from '../services/plugin-manifest.js'
+import type { PluginBackend } from '../services/plugins.js'
+
+/** Registers the tools a plugin declares, each named `<plugin>.<tool>` and run by its backend. */
+export function registerPluginTools(
+  registry: { register: (h: ToolHandler) => void },
+  manifest: PluginManifest,
+  backend: PluginBackend,
+): void {
+  for (const tool of manifest.tools) {
+    registry.register({
+      metadata: {
+        name: pluginToolName(manifest.name, tool.name),
+        description: `${tool.description} (from the ${manifest.name} plugin)`,
+        parameters: tool.parameters,
+        // Third-party code asks before acting unless it says it only reads
+        requires_approval: tool.requires_approval ?? tool.category !== 'read_only',
+        ...(tool.category ? { category: tool.category } : {}),
+      },
+      async handle(args, ctx): Promise<ToolResult> {
+        try {
+          return { ok: true, output: await backend.call(tool.name, args, ctx.signal) }
+        } catch (err) {
+          return { ok: false, error: `${manifest.name} plugin: ${err instanceof Error ? err.message : String(err)}` }
+        }
+      },
+    })
+  }
+}
@@ -17,6 +17,7 @@ export function registerPreferenceTools(
         required: ['key'],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args: Record<string, unknown>): Promise<ToolResult> {
       const key = args.key as string
@@ -41,6 +42,7 @@ export function registerPreferenceTools(
         required: ['key', 'value'],
       },
       requires_approval: false,
+      category: 'mutating',
     },
     async handle(args: Record<string, unknown>): Promise<ToolResult> {
       const key = args.key as string
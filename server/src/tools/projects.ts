@@ -0,0 +1,33 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { searchProjects, type ProjectSearchDeps } from '../services/projects.js'
+
+export function registerProjectTools(
+  registry: { register: (h: ToolHandler) => void },
+  deps: ProjectSearchDeps,
+): void {
+  registry.register({
+    metadata: {
+      name: 'project.search',
+      description: 'Search the project folders the user linked to this conversation and return the most relevant passages with their file paths. Use it for questions about the code or documents in those folders.',
+      parameters: {
+        type: 'object',
+        properties: {
+          query: { type: 'string', description: 'What to look for, phrased as a question or keywords' },
+          project: { type: 'string', description: 'Project name to search; omit to search every linked project' },
+          limit: { type: 'integer', description: 'Maximum passages to return (default 5, max 20)' },
+        },
+        required: ['query'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const results = await searchProjects(deps, ctx.session_id, args.query as string, {
+        project: args.project as string | undefined,
+        limit: args.limit as number | undefined,
+        signal: ctx.signal,
+      })
+      return { ok: true, output: { results } }
+    },
+  })
+}
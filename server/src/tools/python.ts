@@ -0,0 +1,141 @@
+import fs from 'fs/promises'
+import type { ToolContext, ToolHandler, ToolResult } from './types.js'
+import { resolveManagedFilePath } from './path-policy.js'
+import { writeAgentArtifact, type AgentArtifact } from '../services/agent-artifacts.js'
+import { MAX_PYTHON_FILE_BYTES, MAX_PYTHON_FILES, runPython } from '../services/python-sandbox.js'
+
+/** Captured per stream; longer output is cut. */
+const MAX_OUTPUT_BYTES = 1024 * 1024
+/** Output past this goes to an artifact, with its start kept inline. */
+const INLINE_OUTPUT_CHARS = 16_000
+
+export interface PythonToolOptions {
+  sessionFilesRoot: string
+  notesDir: string
+  pythonBin: string
+  timeoutMs: number
+  /** Caps the timeout_ms a call asks for. */
+  maxTimeoutMs: number
+  memoryMb: number
+}
+
+export function registerPythonTools(
+  registry: { register: (h: ToolHandler) => void },
+  options: PythonToolOptions,
+): void {
+  registry.register({
+    metadata: {
+      name: 'python.run',
+      description: `Run a Python script in a sandbox: a fresh temporary directory, no network unless asked for, CPU and memory limits. Use it to analyse or transform data already saved in the conversation — pass those files in \`files\` and they are copied into the working directory by name. Print results to stdout; files the script writes (CSV, charts, reports) become downloadable artifacts. Only the standard library and packages installed on the server are available.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          code: { type: 'string', description: 'Python source to run as a script' },
+          files: {
+            type: 'array',
+            items: { type: 'string' },
+            description: `Saved files to copy in: artifact:// refs, @note/ paths or session workspace paths (max ${MAX_PYTHON_FILES})`,
+          },
+          network: { type: 'boolean', description: 'Allow network access (default false)' },
+          timeout_ms: { type: 'integer', description: `Default: ${options.timeoutMs}, max: ${options.maxTimeoutMs}` },
+        },
+        required: ['code'],
+      },
+      requires_approval: true,
+      category: 'destructive',
+    },
+    async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
+      const code = typeof args.code === 'string' ? args.code : ''
+      if (!code.trim()) return { ok: false, error: 'code is required' }
+      const paths = Array.isArray(args.files) ? args.files.map(String) : []
+      if (paths.length > MAX_PYTHON_FILES) return { ok: false, error: `At most ${MAX_PYTHON_FILES} files can be passed in` }
+
+      const files: Array<{ name: string; data: Buffer }> = []
+      for (const requested of paths) {
+        try {
+          const resolved = resolveManagedFilePath(requested, {
+            sessionFilesRoot: options.sessionFilesRoot,
+            notesDir: options.notesDir,
+            sessionId: ctx.session_id,
+            access: 'read',
+          })
+          const stat = await fs.stat(resolved.fsPath)
+          if (!stat.isFile()) return { ok: false, error: `Not a file: ${requested}` }
+          if (stat.size > MAX_PYTHON_FILE_BYTES) return { ok: false, error: `File is too large to pass in: ${requested}` }
+          files.push({ name: resolved.logicalPath.split('/').pop()!, data: await fs.readFile(resolved.fsPath) })
+        } catch (err) {
+          return { ok: false, error: `Cannot read ${requested}: ${err instanceof Error ? err.message : String(err)}` }
+        }
+      }
+
+      const requestedMs = typeof args.timeout_ms === 'number' && Number.isFinite(args.timeout_ms) && args.timeout_ms > 0
+        ? Math.min(args.timeout_ms, options.maxTimeoutMs)
+        : options.timeoutMs
+      // The call's own deadline wins over a longer timeout_ms, so the interpreter is killed rather than orphaned
+      const timeoutMs = ctx.deadline ? Math.max(0, Math.min(requestedMs, ctx.deadline - Date.now())) : requestedMs
+
+      let result: Awaited<ReturnType<typeof runPython>>
+      try {
+        result = await runPython(
+          { pythonBin: options.pythonBin, memoryMb: options.memoryMb, maxOutputBytes: MAX_OUTPUT_BYTES },
+          { code, files, network: args.network === true, timeoutMs, signal: ctx.signal },
+        )
+      } catch (err) {
+        return { ok: false, error: err instanceof Error ? err.message : String(err) }
+      }
+
+      const artifacts: AgentArtifact[] = []
+      for (const file of result.files) {
+        artifacts.push(await writeAgentArtifact(options.sessionFilesRoot, ctx.session_id, {
+          name: file.name,
+          content: file.data.toString('base64'),
+          encoding: 'base64',
+          metadata: { source: 'python.run' },
+        }))
+      }
+      const stdout = await keepOutput(options.sessionFilesRoot, ctx.session_id, 'python-stdout.txt', result.stdout)
+      const stderr = await keepOutput(options.sessionFilesRoot, ctx.session_id, 'python-stderr.txt', result.stderr)
+
+      const output = {
+        exit_code: result.exitCode,
+        success: result.exitCode === 0 && !result.timedOut,
+        timed_out: result.timedOut,
+        stdout: stdout.text,
+        stderr: stderr.text,
+        ...(stdout.artifact && { stdout_artifact: stdout.artifact.ref }),
+        ...(stderr.artifact && { stderr_artifact: stderr.artifact.ref }),
+        ...(result.truncated && { output_truncated: true }),
+        files: artifacts,
+        ...(result.skippedFiles.length > 0 && { skipped_files: result.skippedFiles }),
+        duration_ms: result.durationMs,
+      }
+      if (result.timedOut) return { ok: false, error: `Script timed out after ${timeoutMs}ms`, output }
+      return { ok: true, output }
+    },
+    preview(args: Record<string, unknown>): { summary: string; details?: Record<string, unknown> } {
+      const code = String(args.code ?? '')
+      const firstLine = code.split('\n').find((line) => line.trim() && !line.trim().startsWith('#'))?.trim() ?? ''
+      const display = firstLine.length > 80 ? firstLine.slice(0, 77) + '...' : firstLine
+      const files = Array.isArray(args.files) ? args.files.map(String) : []
+      return {
+        summary: `Run Python${args.network === true ? ' with network access' : ''}: ${display}`,
+        details: {
+          code,
+          ...(files.length > 0 && { files }),
+        },
+      }
+    },
+  })
+}
+
+/** Long output is saved whole as an artifact; the tool result keeps its start. */
+async function keepOutput(
+  sessionFilesRoot: string,
+  sessionId: string,
+  name: string,
+  text: string,
+): Promise<{ text: string; artifact?: AgentArtifact }> {
+  if (text.length <= INLINE_OUTPUT_CHARS) return { text }
+  const artifact = await writeAgentArtifact(sessionFilesRoot, sessionId, { name, content: text, metadata: { source: 'python.run' } })
+  return { text: `${text.slice(0, INLINE_OUTPUT_CHARS)}\n... [full output in ${artifact.ref}]`, artifact }
+}
@@ -9,9 +9,11 @@ import type {
   ValidationResult,
   ToolPreview,
 } from './types.js'
+import { ToolMemo } from './memo.js'
 
 export class ToolRegistryImpl implements ToolExecutor {
   private handlers = new Map<string, ToolHandler>()
+  private readonly memo = new ToolMemo()
 
   register(handler: ToolHandler): void {
     if (this.handlers.has(handler.metadata.name)) {
@@ -42,11 +44,25 @@ export class ToolRegistryImpl implements ToolExecutor {
       }
     }
 
+    if (ctx.signal.aborted) return { ok: false, error: abortedError(ctx.signal) }
+    if (ctx.deadline !== undefined && ctx.deadline <= Date.now()) {
+      return { ok: false, error: 'Timed out: the step deadline passed before this call started, so it was not run' }
+    }
+    const { metadata } = handler
+    const memoized = metadata.memoize ? this.memo.get(ctx.session_id, name, args) : undefined
+    if (memoized) return { ...memoized, memoized: true }
     try {
-      return await handler.handle(args, ctx)
+      const result = await untilAborted(handler.handle(args, ctx), ctx.signal)
+      if (result.ok && metadata.memoize) {
+        this.memo.set(ctx.session_id, name, args, result)
+      } else if (result.ok && metadata.category !== 'read_only') {
+        // A call that may have changed the world makes the session's remembered results stale
+        this.memo.clear(ctx.session_id)
+      }
+      return result
     } catch (err) {
       if (ctx.signal.aborted) {
-        return { ok: false, error: 'Tool execution aborted' }
+        return { ok: false, error: abortedError(ctx.signal) }
       }
       const message = err instanceof Error ? err.message : String(err)
       return { ok: false, error: message }
@@ -155,3 +171,45 @@ function formatParameterHint(schema: Record<string, unknown>): string {
   }
   return lines.join('\n')
 }
+
+/**
+ * Signal and deadline for one tool call: the signal aborts when `cancel` does
+ * or once `timeoutMs` has passed, whichever comes first. A `stepDeadline`
+ * that falls earlier shortens the call's timeout to match.
+ */
+export function toolCallLimits(
+  timeoutMs: number,
+  cancel?: AbortSignal,
+  stepDeadline?: number,
+): Pick<ToolContext, 'signal' | 'deadline'> {
+  const limitMs = stepDeadline === undefined ? timeoutMs : Math.max(0, Math.min(timeoutMs, stepDeadline - Date.now()))
+  const timeout = AbortSignal.timeout(limitMs)
+  return {
+    signal: cancel ? AbortSignal.any([cancel, timeout]) : timeout,
+    deadline: Date.now() + limitMs,
+  }
+}
+
+/** Deadline for a step's tool calls under `config.step_timeout_ms`, or undefined when unset. */
+export function stepDeadline(config: { step_timeout_ms?: number }): number | undefined {
+  return config.step_timeout_ms ? Date.now() + config.step_timeout_ms : undefined
+}
+
+/**
+ * Settles with the handler, or rejects as soon as the signal aborts. Handlers
+ * should still stop their own work on abort; this only keeps one that does
+ * not from holding up the run, and its late result is dropped.
+ */
+function untilAborted<T>(work: Promise<T>, signal: AbortSignal): Promise<T> {
+  return new Promise<T>((resolve, reject) => {
+    const abort = () => reject(signal.reason)
+    signal.addEventListener('abort', abort, { once: true })
+    work.then(resolve, reject).finally(() => signal.removeEventListener('abort', abort))
+  })
+}
+
+function abortedError(signal: AbortSignal): string {
+  return (signal.reason as { name?: string } | undefined)?.name === 'TimeoutError'
+    ? 'Tool execution timed out'
+    : 'Tool execution aborted'
+}
@@ -0,0 +1,80 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import type { ScratchpadRepository } from '../repositories/types.js'
+
+/** Keeps the pad a place for short notes rather than a second artifact store. */
+const MAX_VALUE_CHARS = 8_000
+const MAX_KEYS = 50
+const KEY_PATTERN = /^[A-Za-z0-9][\w.-]{0,63}$/
+
+export function registerScratchpadTools(
+  registry: { register: (h: ToolHandler) => void },
+  repo: ScratchpadRepository,
+): void {
+  registry.register({
+    metadata: {
+      name: 'scratchpad.write',
+      description: 'Keep a working note for this conversation under a key, e.g. a running list of candidate meeting times, and read it back later with scratchpad.read. Notes are not shown to the user. Append adds a line to the existing note; an empty value removes the key.',
+      parameters: {
+        type: 'object',
+        properties: {
+          key: { type: 'string', description: 'Short name: letters, digits, ".", "_" or "-"' },
+          value: { type: 'string', description: 'Note text; empty to remove the key' },
+          append: { type: 'boolean', description: 'Add value as a new line instead of replacing the note (default false)' },
+        },
+        required: ['key', 'value'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const key = typeof args.key === 'string' ? args.key.trim() : ''
+      if (!KEY_PATTERN.test(key)) {
+        return { ok: false, error: 'key must be 1-64 letters, digits, ".", "_" or "-", starting with a letter or digit' }
+      }
+      const value = typeof args.value === 'string' ? args.value : ''
+      if (!value.trim()) {
+        const removed = await repo.delete(ctx.session_id, key)
+        return { ok: true, output: { key, removed } }
+      }
+
+      const existing = await repo.get(ctx.session_id, key)
+      if (!existing && (await repo.list(ctx.session_id)).length >= MAX_KEYS) {
+        return { ok: false, error: `The scratchpad already holds ${MAX_KEYS} keys; remove or reuse one` }
+      }
+      const next = args.append === true && existing ? `${existing.value}\n${value}` : value
+      if (next.length > MAX_VALUE_CHARS) {
+        return { ok: false, error: `Note is ${next.length} characters; the limit is ${MAX_VALUE_CHARS}. Summarize it or save long content with artifacts.write` }
+      }
+      const entry = await repo.set(ctx.session_id, key, next)
+      return { ok: true, output: { key, chars: entry.value.length, saved: true } }
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'scratchpad.read',
+      description: 'Read working notes kept with scratchpad.write in this conversation. Pass a key for one note, or omit it to get every note.',
+      parameters: {
+        type: 'object',
+        properties: {
+          key: { type: 'string', description: 'Note to read; omit for all notes' },
+        },
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const key = typeof args.key === 'string' ? args.key.trim() : ''
+      if (key) {
+        const entry = await repo.get(ctx.session_id, key)
+        if (!entry) return { ok: true, output: { key, value: null, found: false } }
+        return { ok: true, output: { key, value: entry.value, found: true, updatedAt: entry.updatedAt } }
+      }
+      const entries = await repo.list(ctx.session_id)
+      return {
+        ok: true,
+        output: { notes: entries.map((entry) => ({ key: entry.key, value: entry.value, updatedAt: entry.updatedAt })) },
+      }
+    },
+  })
+}
@@ -26,6 +26,7 @@ export function registerSearchTools(
         properties: {},
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(): Promise<ToolResult> {
       return {
@@ -54,6 +55,7 @@ export function registerSearchTools(
         required: ['query', 'path'],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const query = args.query as string
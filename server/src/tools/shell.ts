@@ -25,11 +25,14 @@ export function registerShellTools(registry: { register: (h: ToolHandler) => voi
         required: ['command'],
       },
       requires_approval: true,
+      category: 'destructive',
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const command = args.command as string
       const workingDir = args.working_dir as string | undefined
-      const timeoutMs = (args.timeout_ms as number) ?? DEFAULT_TIMEOUT_MS
+      // The call's own deadline wins over a longer timeout_ms, so the child is killed rather than orphaned
+      const requestedMs = (args.timeout_ms as number) ?? DEFAULT_TIMEOUT_MS
+      const timeoutMs = ctx.deadline ? Math.max(0, Math.min(requestedMs, ctx.deadline - Date.now())) : requestedMs
 
       const abortController = new AbortController()
 
@@ -68,7 +71,9 @@ export function registerShellTools(registry: { register: (h: ToolHandler) => voi
         if (error.killed || error.code === 'ABORT_ERR' || ctx.signal.aborted) {
           return {
             ok: false,
-            error: ctx.signal.aborted ? 'Command aborted' : 'Command timed out',
+            error: ctx.signal.aborted && (ctx.signal.reason as { name?: string } | undefined)?.name !== 'TimeoutError'
+              ? 'Command aborted'
+              : 'Command timed out',
             output: {
               stdout: truncate(error.stdout ?? '', MAX_OUTPUT_BYTES),
               stderr: truncate(error.stderr ?? '', MAX_OUTPUT_BYTES),
@@ -73,6 +73,7 @@ export function registerTaskTools(
         required: ['title', 'owner', 'body'],
       },
       requires_approval: false,
+      category: 'mutating',
     },
     async handle(args): Promise<ToolResult> {
       const title = String(args.title ?? '').trim()
@@ -157,6 +158,7 @@ export function registerTaskTools(
         required: ['title', 'owner', 'body'],
       },
       requires_approval: false,
+      category: 'mutating',
     },
     async handle(args, ctx): Promise<ToolResult> {
       const title = String(args.title ?? '').trim()
@@ -259,6 +261,7 @@ export function registerTaskTools(
         required: ['tasks'],
       },
       requires_approval: false,
+      category: 'mutating',
     },
     async handle(args): Promise<ToolResult> {
       const rawTasks = args.tasks
@@ -349,6 +352,7 @@ export function registerTaskTools(
         required: [],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args): Promise<ToolResult> {
       const filter = String(args.status ?? 'all')
@@ -435,6 +439,7 @@ export function registerTaskTools(
         required: ['id'],
       },
       requires_approval: false,
+      category: 'mutating',
     },
     async handle(args): Promise<ToolResult> {
       const id = String(args.id ?? '').trim()
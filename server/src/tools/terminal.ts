@@ -0,0 +1,117 @@
+import type { ToolHandler, ToolResult, ToolContext } from './types.js'
+import { MAX_TERMINALS_PER_SESSION, type TerminalManager } from '../services/terminals.js'
+
+const DEFAULT_WAIT_MS = 15_000
+/** Stays under the 60s tool timeout; longer commands keep running and are read later. */
+const MAX_WAIT_MS = 50_000
+
+export function registerTerminalTools(
+  registry: { register: (h: ToolHandler) => void },
+  terminals: TerminalManager,
+): void {
+  registry.register({
+    metadata: {
+      name: 'terminal.open',
+      description: `Open a persistent shell for a multi-step job (build, then test, then read logs). The working directory, environment variables and activated environments carry over between terminal.run calls. At most ${MAX_TERMINALS_PER_SESSION} per conversation; idle terminals close after 30 minutes.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          working_dir: { type: 'string', description: 'Starting directory' },
+        },
+      },
+      requires_approval: false,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(() => ({
+        terminal: terminals.open(ctx.session_id, typeof args.working_dir === 'string' && args.working_dir.trim() ? args.working_dir.trim() : undefined),
+        open: terminals.list(ctx.session_id).map((terminal) => terminal.id),
+      }))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'terminal.run',
+      description: 'Run a shell command in an open terminal and wait for it to finish. If it is still running when the wait ends, it keeps going; collect the rest with terminal.read_output. Commands cannot read input, so pass flags such as --yes instead.',
+      parameters: {
+        type: 'object',
+        properties: {
+          terminal_id: { type: 'string', description: 'ID from terminal.open' },
+          command: { type: 'string', description: 'Command line, e.g. "npm test 2>&1 | tail -50"' },
+          wait_ms: { type: 'integer', description: `How long to wait for it to finish (default ${DEFAULT_WAIT_MS}, max ${MAX_WAIT_MS})` },
+        },
+        required: ['terminal_id', 'command'],
+      },
+      requires_approval: true,
+      category: 'destructive',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const command = typeof args.command === 'string' ? args.command : ''
+      if (!command.trim()) return { ok: false, error: 'command is required' }
+      return run(() => terminals.run(ctx.session_id, String(args.terminal_id ?? ''), command, waitMs(args.wait_ms), ctx.signal))
+    },
+    preview(args: Record<string, unknown>, ctx: ToolContext): { summary: string; details?: Record<string, unknown> } {
+      const cmd = String(args.command)
+      const display = cmd.length > 80 ? cmd.slice(0, 77) + '...' : cmd
+      const terminal = terminals.list(ctx.session_id).find((open) => open.id === args.terminal_id)
+      return {
+        summary: `Execute in ${String(args.terminal_id)}: ${display}`,
+        details: terminal ? { working_dir: terminal.cwd } : undefined,
+      }
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'terminal.read_output',
+      description: 'Read what a terminal printed since the last terminal.run or read, waiting for a long command to finish. The result says whether it is still running and, once done, its exit code.',
+      parameters: {
+        type: 'object',
+        properties: {
+          terminal_id: { type: 'string', description: 'ID from terminal.open' },
+          wait_ms: { type: 'integer', description: `How long to wait for the command to finish (default ${DEFAULT_WAIT_MS}, max ${MAX_WAIT_MS}; 0 returns at once)` },
+        },
+        required: ['terminal_id'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(() => terminals.read(ctx.session_id, String(args.terminal_id ?? ''), waitMs(args.wait_ms), ctx.signal))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'terminal.close',
+      description: 'Close a terminal, stopping any command still running in it.',
+      parameters: {
+        type: 'object',
+        properties: {
+          terminal_id: { type: 'string', description: 'ID from terminal.open' },
+        },
+        required: ['terminal_id'],
+      },
+      requires_approval: false,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const id = String(args.terminal_id ?? '')
+      if (!terminals.close(ctx.session_id, id)) return { ok: false, error: `No open terminal ${id}` }
+      return { ok: true, output: { closed: id } }
+    },
+  })
+}
+
+function waitMs(value: unknown): number {
+  return typeof value === 'number' && Number.isFinite(value) ? Math.min(Math.max(0, Math.floor(value)), MAX_WAIT_MS) : DEFAULT_WAIT_MS
+}
+
+async function run(action: () => unknown): Promise<ToolResult> {
+  try {
+    return { ok: true, output: await action() }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+}
@@ -21,6 +21,7 @@ export function registerThinkTool(registry: { register: (h: ToolHandler) => void
         required: ['questions'],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args: Record<string, unknown>): Promise<ToolResult> {
       const questions = args.questions as string[]
@@ -0,0 +1,152 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import type { CreateTodoInput, TodoBackend } from '../services/todos.js'
+
+/** One call can turn a thread or meeting into todos without flooding the list. */
+const MAX_TODOS_PER_CALL = 25
+
+const BACKEND_NAMES: Record<TodoBackend['name'], { product: string; project: string }> = {
+  todoist: { product: 'Todoist', project: 'Todoist project name' },
+  reminders: { product: 'Apple Reminders', project: 'Reminders list name' },
+}
+
+export function registerTodoTools(
+  registry: { register: (h: ToolHandler) => void },
+  backend: TodoBackend,
+): void {
+  const { product, project } = BACKEND_NAMES[backend.name]
+
+  registry.register({
+    metadata: {
+      name: 'todos.list',
+      description: `List open todos in the user's personal task manager (${product}). These are the user's own tasks, not agent tasks.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          project: { type: 'string', description: `${project}; omit for all` },
+          ...(backend.name === 'todoist' && {
+            filter: { type: 'string', description: 'Todoist filter, e.g. "today | overdue" or "p1"' },
+          }),
+          limit: { type: 'integer', description: 'Maximum todos (default 30, max 200)' },
+        },
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      return run(async () => ({
+        todos: await backend.list({
+          project: optionalText(args.project),
+          filter: optionalText(args.filter),
+          limit: args.limit as number | undefined,
+        }, ctx.signal),
+      }))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'todos.create',
+      description: `Add one or more todos to the user's ${product}, e.g. the action items from an email thread or meeting. Pass every todo in one call.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          todos: {
+            type: 'array',
+            description: `Up to ${MAX_TODOS_PER_CALL} todos`,
+            items: {
+              type: 'object',
+              properties: {
+                title: { type: 'string', description: 'What to do' },
+                notes: { type: 'string', description: 'Details or a link back to the source' },
+                due: {
+                  type: 'string',
+                  description: backend.name === 'todoist' ? 'Due date in words, e.g. "tomorrow 5pm"' : 'Due date as ISO 8601, e.g. 2026-10-20T17:00',
+                },
+                priority: { type: 'integer', description: '1 (normal) to 4 (urgent)' },
+                project: { type: 'string', description: `${project}; default inbox` },
+              },
+              required: ['title'],
+            },
+          },
+        },
+        required: ['todos'],
+      },
+      requires_approval: false,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      let inputs: CreateTodoInput[]
+      try {
+        inputs = parseTodos(args.todos)
+      } catch (err) {
+        return { ok: false, error: err instanceof Error ? err.message : String(err) }
+      }
+      const created = []
+      for (const input of inputs) {
+        try {
+          created.push(await backend.create(input, ctx.signal))
+        } catch (err) {
+          // Report what was already added so the agent does not add it twice
+          return {
+            ok: false,
+            error: `Added ${created.length} of ${inputs.length} todos, then failed on "${input.title}": ${err instanceof Error ? err.message : String(err)}`,
+            output: { created },
+          }
+        }
+      }
+      return { ok: true, output: { created } }
+    },
+    preview(args) {
+      const titles = Array.isArray(args.todos) ? args.todos.map((todo) => String((todo as { title?: unknown })?.title ?? '')) : []
+      return { summary: `Add ${titles.length} todo${titles.length === 1 ? '' : 's'} to ${product}`, details: { titles } }
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'todos.complete',
+      description: `Mark a todo in the user's ${product} as done. Find its ID with todos.list.`,
+      parameters: {
+        type: 'object',
+        properties: {
+          id: { type: 'string', description: 'Todo ID from todos.list' },
+        },
+        required: ['id'],
+      },
+      requires_approval: true,
+      category: 'mutating',
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const id = optionalText(args.id)
+      if (!id) return { ok: false, error: 'id is required' }
+      return run(async () => ({ ...(await backend.complete(id, ctx.signal)), completed: true }))
+    },
+    preview(args) {
+      return { summary: `Complete ${product} todo ${String(args.id)}` }
+    },
+  })
+}
+
+function parseTodos(value: unknown): CreateTodoInput[] {
+  if (!Array.isArray(value) || value.length === 0) throw new Error('todos must be a non-empty array')
+  if (value.length > MAX_TODOS_PER_CALL) throw new Error(`At most ${MAX_TODOS_PER_CALL} todos per call`)
+  return value.map((entry, index) => {
+    const todo = (entry ?? {}) as Record<string, unknown>
+    const title = optionalText(todo.title)
+    if (!title) throw new Error(`todos[${index}].title is required`)
+    const priority = typeof todo.priority === 'number' && Number.isInteger(todo.priority) ? Math.min(4, Math.max(1, todo.priority)) : undefined
+    return { title, notes: optionalText(todo.notes), due: optionalText(todo.due), priority, project: optionalText(todo.project) }
+  })
+}
+
+async function run(action: () => Promise<unknown>): Promise<ToolResult> {
+  try {
+    return { ok: true, output: await action() }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+}
+
+function optionalText(value: unknown): string | undefined {
+  return typeof value === 'string' && value.trim() ? value.trim() : undefined
+}
@@ -17,6 +17,7 @@ export function registerToolOutputTools(
         required: ['id'],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args: Record<string, unknown>): Promise<ToolResult> {
       const id = args.id as string
@@ -40,6 +41,7 @@ export function registerToolOutputTools(
         required: ['agent_id'],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args: Record<string, unknown>): Promise<ToolResult> {
       const agentId = args.agent_id as string
@@ -68,6 +70,7 @@ export function registerToolOutputTools(
         required: ['id'],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args: Record<string, unknown>): Promise<ToolResult> {
       const id = args.id as string
@@ -115,6 +118,7 @@ export function registerToolOutputTools(
         required: ['id', 'path'],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args: Record<string, unknown>): Promise<ToolResult> {
       const id = args.id as string
@@ -147,6 +151,7 @@ export function registerToolOutputTools(
         required: ['id'],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args: Record<string, unknown>): Promise<ToolResult> {
       const id = args.id as string
@@ -188,6 +193,7 @@ export function registerToolOutputTools(
         required: ['id'],
       },
       requires_approval: false,
+      category: 'read_only',
     },
     async handle(args: Record<string, unknown>): Promise<ToolResult> {
       const id = args.id as string
@@ -1,3 +1,5 @@
+import type { ToolPreview } from '../events/payloads.js'
+import type { ApprovalTrigger } from '../orchestrator/approval-rules.js'
 import type { EventSink } from '../events/types.js'
 
 export interface ToolExecutor {
@@ -14,11 +16,30 @@ export interface ToolExecutor {
   getPreview(name: string, args: Record<string, unknown>, ctx: ToolContext): ToolPreview | undefined
 }
 
+export const TOOL_CATEGORIES = ['read_only', 'mutating', 'destructive'] as const
+/** What a tool does to the world; drives its approval default (see approval-categories). */
+export type ToolCategory = (typeof TOOL_CATEGORIES)[number]
+
 export interface ToolMetadata {
   name: string
   description: string
   parameters: Record<string, unknown> // JSON Schema
   requires_approval: boolean
+  /** Unset keeps `requires_approval` as the only default. */
+  category?: ToolCategory
+  /**
+   * Arg-based approval: when set, the tool asks only for calls that match a
+   * trigger and runs the rest, instead of following `requires_approval` or
+   * its category's built-in default. Evaluated before the call is proposed.
+   */
+  approval_triggers?: ApprovalTrigger[]
+  /**
+   * Repeating a call with the same args in the same session returns the
+   * earlier result for a few minutes instead of running again. For read-only
+   * lookups against external services; any successful call that is not
+   * read-only clears the session's remembered results.
+   */
+  memoize?: boolean
   /** Tool is intercepted by the orchestrator — handler is not called directly. */
   orchestrator_intercept?: boolean
 }
@@ -26,8 +47,23 @@ export interface ToolMetadata {
 export interface ToolContext {
   agent_id: string
   session_id: string
+  /**
+   * Aborts when the run is cancelled or the call outlives its timeout. The
+   * registry stops waiting at that point, so handlers should pass it on to
+   * fetches and child processes rather than let the work run on unobserved.
+   */
   signal: AbortSignal
+  /** Epoch ms by which the call must finish; unset when it has no timeout. */
+  deadline?: number
   events?: EventSink
+  /** Report progress on long-running work; forwarded to the UI as tool:progress. */
+  reportProgress?: (update: ToolProgressUpdate) => void
+}
+
+export interface ToolProgressUpdate {
+  /** Fraction complete, 0..1, when the tool can tell. */
+  progress?: number
+  message?: string
 }
 
 export interface ToolResult {
@@ -35,6 +71,8 @@ export interface ToolResult {
   output?: unknown
   error?: string
   content_blocks?: ContentBlock[]
+  /** The result of an identical earlier call in the session, returned without running the tool. */
+  memoized?: boolean
 }
 
 export interface ContentBlock {
@@ -61,10 +99,7 @@ export interface ValidationResult {
   errors?: string[]
 }
 
-export interface ToolPreview {
-  summary: string
-  details?: Record<string, unknown>
-}
+export type { ToolChange, ToolPreview } from '../events/payloads.js'
 
 // Internal to tools/ module — not used by orchestrator
 export interface ToolHandler {
@@ -0,0 +1,209 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import { evaluateExpression } from '../lib/calculator.js'
+import { convertUnits } from '../services/units.js'
+import { OpenMeteoClient } from '../services/weather.js'
+import { fetchExchangeRate } from '../usage/currency.js'
+
+export interface UtilityToolOptions {
+  /** USD-based rates feed shared with usage reports (EXCHANGE_RATE_URL). */
+  exchangeRateUrl: string
+  fetchImpl?: typeof fetch
+}
+
+/** Zero-config helpers for quick asks: weather, time zones, units and currencies, arithmetic. */
+export function registerUtilityTools(
+  registry: { register: (h: ToolHandler) => void },
+  options: UtilityToolOptions,
+): void {
+  const fetchImpl = options.fetchImpl ?? fetch
+  const weather = new OpenMeteoClient(fetchImpl)
+
+  registry.register({
+    metadata: {
+      name: 'weather.forecast',
+      description: 'Current weather and a daily forecast for a place.',
+      parameters: {
+        type: 'object',
+        properties: {
+          location: { type: 'string', description: 'City, optionally with region or country, e.g. "Portland, Oregon"' },
+          days: { type: 'integer', description: 'Forecast days including today (default 3, max 16)' },
+          units: { type: 'string', enum: ['metric', 'imperial'], description: 'Default metric' },
+        },
+        required: ['location'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+      memoize: true,
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const location = typeof args.location === 'string' ? args.location : ''
+      return run(() => weather.forecast(location, {
+        days: args.days as number | undefined,
+        units: args.units === 'imperial' ? 'imperial' : 'metric',
+      }, ctx.signal))
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'time.convert',
+      description: 'Show a moment in other time zones: the current time by default, or a given local time in a source zone.',
+      parameters: {
+        type: 'object',
+        properties: {
+          time: { type: 'string', description: 'Local time such as "15:00", "3:30pm" or "2026-10-20 09:00"; omit for now' },
+          from: { type: 'string', description: 'IANA time zone of `time`, e.g. "Europe/Warsaw" (default UTC)' },
+          to: { type: 'array', items: { type: 'string' }, description: 'IANA time zones to show, e.g. ["America/New_York", "Asia/Tokyo"]' },
+        },
+        required: ['to'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args): Promise<ToolResult> {
+      return run(async () => {
+        const from = typeof args.from === 'string' && args.from.trim() ? args.from.trim() : 'UTC'
+        const zones = (Array.isArray(args.to) ? args.to : [args.to]).filter((zone): zone is string => typeof zone === 'string' && zone.trim() !== '')
+        if (zones.length === 0) throw new Error('to must list at least one time zone')
+        const instant = typeof args.time === 'string' && args.time.trim() ? parseLocalTime(args.time.trim(), from) : new Date()
+        return {
+          utc: instant.toISOString(),
+          from: describeInZone(instant, from),
+          times: zones.map((zone) => describeInZone(instant, zone.trim())),
+        }
+      })
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'units.convert',
+      description: 'Convert an amount between units (length, mass, volume, area, speed, time, data, energy, temperature) or between currencies at current exchange rates.',
+      parameters: {
+        type: 'object',
+        properties: {
+          value: { type: 'number', description: 'Amount to convert' },
+          from: { type: 'string', description: 'Unit such as "mi", "lb", "°F", or a currency code in capitals such as "EUR"' },
+          to: { type: 'string', description: 'Target unit or currency code' },
+        },
+        required: ['value', 'from', 'to'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args): Promise<ToolResult> {
+      return run(async () => {
+        const value = Number(args.value)
+        const from = String(args.from ?? '').trim()
+        const to = String(args.to ?? '').trim()
+        if (!Number.isFinite(value)) throw new Error('value must be a number')
+        if (isCurrency(from) && isCurrency(to)) {
+          const rate = await usdRate(to) / await usdRate(from)
+          return { value: round(value * rate), from, to, dimension: 'currency', rate: round(rate) }
+        }
+        const converted = convertUnits(value, from, to)
+        return { ...converted, value: round(converted.value) }
+      })
+    },
+  })
+
+  registry.register({
+    metadata: {
+      name: 'calculator.evaluate',
+      description: 'Evaluate an arithmetic expression exactly instead of estimating. Supports + - * / ^, parentheses, % (per cent, e.g. "80 * 15%"), pi, e and sqrt, abs, round(x, digits), floor, ceil, ln, log, log2, exp, sin, cos, tan, min, max.',
+      parameters: {
+        type: 'object',
+        properties: {
+          expression: { type: 'string', description: 'e.g. "(1299 * 3) * (1 - 12.5%)"' },
+        },
+        required: ['expression'],
+      },
+      requires_approval: false,
+      category: 'read_only',
+    },
+    async handle(args): Promise<ToolResult> {
+      const expression = typeof args.expression === 'string' ? args.expression : ''
+      return run(async () => ({ expression, result: round(evaluateExpression(expression)) }))
+    },
+  })
+
+  function usdRate(code: string): Promise<number> {
+    return code === 'USD' ? Promise.resolve(1) : fetchExchangeRate(code, options.exchangeRateUrl, fetchImpl)
+  }
+}
+
+/** "15:00", "3:30pm", "2026-10-20 09:00" in `zone`, or any ISO timestamp with an offset. */
+export function parseLocalTime(text: string, zone: string): Date {
+  if (/(?:z|[+-]\d{2}:?\d{2})$/i.test(text) && /^\d{4}-\d{2}-\d{2}T/.test(text)) {
+    const parsed = new Date(text)
+    if (!Number.isNaN(parsed.getTime())) return parsed
+  }
+  const match = text.match(/^(?:(\d{4})-(\d{2})-(\d{2})[ T])?(\d{1,2})(?::(\d{2}))?\s*(am|pm)?$/i)
+  if (!match) throw new Error(`Unrecognized time "${text}"; use e.g. "15:00", "3pm" or "2026-10-20 09:00"`)
+  const [, year, month, day, rawHour, minute = '0', meridiem] = match
+  let hour = Number(rawHour)
+  if (meridiem) {
+    if (hour < 1 || hour > 12) throw new Error(`Invalid hour in "${text}"`)
+    hour = (hour % 12) + (meridiem.toLowerCase() === 'pm' ? 12 : 0)
+  }
+  if (hour > 23 || Number(minute) > 59) throw new Error(`Invalid time "${text}"`)
+  const today = zonedParts(new Date(), zone)
+  const wall = Date.UTC(
+    year ? Number(year) : today.year,
+    (month ? Number(month) : today.month) - 1,
+    day ? Number(day) : today.day,
+    hour,
+    Number(minute),
+  )
+  // Offset at the guessed instant, then again at the corrected one for DST edges
+  const guess = wall - offsetMinutes(new Date(wall), zone) * 60_000
+  return new Date(wall - offsetMinutes(new Date(guess), zone) * 60_000)
+}
+
+function describeInZone(instant: Date, zone: string): { zone: string; local: string; weekday: string; offset: string } {
+  const parts = zonedParts(instant, zone)
+  const pad = (value: number) => String(value).padStart(2, '0')
+  const offset = offsetMinutes(instant, zone)
+  return {
+    zone,
+    local: `${parts.year}-${pad(parts.month)}-${pad(parts.day)} ${pad(parts.hour)}:${pad(parts.minute)}`,
+    weekday: new Intl.DateTimeFormat('en-US', { timeZone: zone, weekday: 'long' }).format(instant),
+    offset: `${offset < 0 ? '-' : '+'}${pad(Math.floor(Math.abs(offset) / 60))}:${pad(Math.abs(offset) % 60)}`,
+  }
+}
+
+function zonedParts(instant: Date, zone: string): { year: number; month: number; day: number; hour: number; minute: number; second: number } {
+  let format: Intl.DateTimeFormat
+  try {
+    format = new Intl.DateTimeFormat('en-US', {
+      timeZone: zone, hourCycle: 'h23', year: 'numeric', month: 'numeric', day: 'numeric', hour: 'numeric', minute: 'numeric', second: 'numeric',
+    })
+  } catch {
+    throw new Error(`Unknown time zone "${zone}"; use an IANA name such as "Europe/London"`)
+  }
+  const values = Object.fromEntries(format.formatToParts(instant).map((part) => [part.type, Number(part.value)]))
+  return { year: values.year, month: values.month, day: values.day, hour: values.hour, minute: values.minute, second: values.second }
+}
+
+function offsetMinutes(instant: Date, zone: string): number {
+  const parts = zonedParts(instant, zone)
+  const asUtc = Date.UTC(parts.year, parts.month - 1, parts.day, parts.hour, parts.minute, parts.second)
+  return Math.round((asUtc - Math.floor(instant.getTime() / 1000) * 1000) / 60_000)
+}
+
+function isCurrency(code: string): boolean {
+  return /^[A-Z]{3}$/.test(code) && Intl.supportedValuesOf('currency').includes(code)
+}
+
+/** Enough precision for any quick ask without float noise like 0.30000000000000004. */
+function round(value: number): number {
+  return Number(value.toPrecision(12))
+}
+
+async function run(action: () => Promise<unknown>): Promise<ToolResult> {
+  try {
+    return { ok: true, output: await action() }
+  } catch (err) {
+    return { ok: false, error: err instanceof Error ? err.message : String(err) }
+  }
+}
@@ -17,6 +17,8 @@ export function registerWebTools(registry: { register: (h: ToolHandler) => void
         required: ['url'],
       },
       requires_approval: false,
+      category: 'read_only',
+      memoize: true,
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const url = args.url as string
@@ -53,6 +55,7 @@ export function registerWebTools(registry: { register: (h: ToolHandler) => void
       description: 'Clear session cookies.',
       parameters: { type: 'object', properties: {} },
       requires_approval: false,
+      category: 'mutating',
     },
     async handle(_args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const count = sessionCookieCount(ctx.session_id)
@@ -80,6 +83,7 @@ export function registerWebTools(registry: { register: (h: ToolHandler) => void
         required: ['method', 'url'],
       },
       requires_approval: false,
+      category: 'mutating',
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const method = (args.method as string).toUpperCase()
@@ -139,6 +143,7 @@ export function registerWebTools(registry: { register: (h: ToolHandler) => void
         required: ['url', 'fields'],
       },
       requires_approval: false,
+      category: 'mutating',
     },
     async handle(args: Record<string, unknown>, ctx: ToolContext): Promise<ToolResult> {
       const url = args.url as string
@@ -255,7 +260,7 @@ async function readResponseBody(
   return body
 }
 
-function extractReadableHtmlBody(html: string): string {
+export function extractReadableHtmlBody(html: string): string {
   const body = extractHtmlBodyFragment(html)
   return decodeHtmlEntities(
     body
@@ -282,7 +287,7 @@ function extractHtmlBodyFragment(html: string): string {
   return html.replace(/<head\b[\s\S]*?<\/head>/i, ' ')
 }
 
-function decodeHtmlEntities(text: string): string {
+export function decodeHtmlEntities(text: string): string {
   return text
     .replace(/&#(\d+);/g, (match, code: string) => decodeCodePoint(match, Number(code)))
     .replace(/&#x([0-9a-f]+);/gi, (match, code: string) => decodeCodePoint(match, Number.parseInt(code, 16)))
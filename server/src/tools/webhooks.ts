@@ -0,0 +1,66 @@
+import type { ToolHandler, ToolResult } from './types.js'
+import type { ApprovalTrigger } from '../orchestrator/approval-rules.js'
+import type { WebhookService } from '../services/webhooks.js'
+
+export function registerWebhookTools(
+  registry: { register: (h: ToolHandler) => void },
+  service: WebhookService,
+): void {
+  registry.register({
+    metadata: {
+      name: 'webhook.post',
+      // Endpoints are added in settings and confirmed by their first approved post, so both are read on every lookup
+      get description() {
+        const endpoints = service.endpoints
+          .map((endpoint) => `- ${endpoint.name}${endpoint.description ? `: ${endpoint.description}` : ''}`)
+          .join('\n')
+        return `Send a JSON payload to one of the user's registered webhook endpoints (Zapier, Make, n8n, IFTTT or their own automations) to hand off a result. The first post to each endpoint asks the user for approval. Endpoints:\n${endpoints || '- none'}`
+      },
+      parameters: {
+        type: 'object',
+        properties: {
+          endpoint: { type: 'string', description: 'Registered endpoint name' },
+          payload: { type: 'object', description: 'JSON object to send, shaped as the endpoint description asks' },
+        },
+        required: ['endpoint', 'payload'],
+      },
+      requires_approval: false,
+      category: 'mutating',
+      get approval_triggers(): ApprovalTrigger[] {
+        return [firstUseTrigger(service.endpoints.filter((endpoint) => endpoint.confirmedAt !== null).map((endpoint) => endpoint.name))]
+      },
+    },
+    async handle(args, ctx): Promise<ToolResult> {
+      const name = typeof args.endpoint === 'string' ? args.endpoint.trim() : ''
+      if (!name) return { ok: false, error: 'endpoint is required' }
+      if (!args.payload || typeof args.payload !== 'object' || Array.isArray(args.payload)) {
+        return { ok: false, error: 'payload must be a JSON object' }
+      }
+      try {
+        return { ok: true, output: await service.post(name, args.payload, ctx.signal) }
+      } catch (err) {
+        return { ok: false, error: err instanceof Error ? err.message : String(err) }
+      }
+    },
+    preview(args) {
+      const endpoint = service.endpoints.find((entry) => entry.name === args.endpoint)
+      const payload = JSON.stringify(args.payload ?? {})
+      return {
+        summary: `Post to webhook "${String(args.endpoint ?? '')}"${endpoint?.confirmedAt === null ? ' (first use)' : ''}`,
+        details: {
+          host: endpoint ? new URL(endpoint.url).host : undefined,
+          payload: payload.length > 500 ? payload.slice(0, 497) + '...' : payload,
+        },
+      }
+    },
+  })
+}
+
+/** Asks unless the endpoint is one of the confirmed names; with none confirmed, every post asks. */
+function firstUseTrigger(confirmed: string[]): ApprovalTrigger {
+  const names = confirmed.map((name) => name.replace(/[.*+?^${}()|[\]\\]/g, '\\$&'))
+  return {
+    when: [{ arg: 'endpoint', op: 'regex', value: names.length > 0 ? `^(?:${names.join('|')})$` : '(?!)', not: true }],
+    reason: 'This is the first post to this endpoint; later posts will not ask.',
+  }
+}
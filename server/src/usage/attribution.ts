@@ -0,0 +1,238 @@
+import type { PromptComposition, PromptPart } from '../events/payloads.js'
+import { ARTIFACT_REFERENCE_HEADER } from '../orchestrator/output.js'
+import type { LLMContentBlock, LLMMessage, LLMRequest } from '../providers/types.js'
+import type { ToolContextShare, UsageRecord } from '../repositories/types.js'
+
+/** Rough English/code average; only used to split a call's real prompt tokens between parts. */
+const CHARS_PER_TOKEN = 4
+/** Providers bill images by size; a typical screenshot lands near this. */
+const IMAGE_TOKEN_ESTIMATE = 1_500
+
+export interface ContextEstimate {
+  /** Estimated tokens for the whole prompt. */
+  total: number
+  /** Estimated tokens of tool results in the prompt, by tool name. */
+  byTool: Map<string, number>
+}
+
+/**
+ * Estimates how much of a prompt is tool results (or the summaries stored in
+ * their place), keyed by the tool that produced them. Tool names come from
+ * the assistant message that made the call.
+ */
+export function estimateToolContext(request: Pick<LLMRequest, 'messages' | 'tools'>): ContextEstimate {
+  const toolByCallId = new Map<string, string>()
+  const byTool = new Map<string, number>()
+  let total = request.tools ? textTokens(JSON.stringify(request.tools)) : 0
+
+  for (const message of request.messages) {
+    for (const call of message.tool_calls ?? []) toolByCallId.set(call.call_id, call.name)
+    const tokens = messageTokens(message)
+    total += tokens
+    if (message.role !== 'tool') continue
+    const tool = (message.tool_call_id && toolByCallId.get(message.tool_call_id)) || '(unknown)'
+    byTool.set(tool, (byTool.get(tool) ?? 0) + tokens)
+  }
+
+  return { total, byTool }
+}
+
+export interface PromptEstimate {
+  /** System messages: controller prompt, task, instructions and (for non-native providers) the tool list. */
+  system: number
+  /** Native tool definitions sent alongside the messages. */
+  tools: number
+  /** Conversation history, including tool calls and their results. */
+  history: number
+  total: number
+}
+
+/** Estimates a prompt's size before it is sent, split by where the tokens come from. */
+export function estimatePromptTokens(request: Pick<LLMRequest, 'messages' | 'tools'>): PromptEstimate {
+  const tools = request.tools ? textTokens(JSON.stringify(request.tools)) : 0
+  let system = 0
+  let history = 0
+  for (const message of request.messages) {
+    const tokens = messageTokens(message)
+    if (message.role === 'system') system += tokens
+    else history += tokens
+  }
+  return { system, tools, history, total: system + tools + history }
+}
+
+/**
+ * Breaks a prompt down by what it is made of and by how much of it is new
+ * since the previous turn, for tuning inline limits and history size.
+ */
+export function estimatePromptComposition(request: Pick<LLMRequest, 'messages' | 'tools'>): PromptComposition {
+  const part = (): PromptPart => ({ bytes: 0, tokens: 0 })
+  const composition: PromptComposition = {
+    system: part(),
+    tools: part(),
+    conversation: part(),
+    toolResults: part(),
+    toolSummaries: part(),
+    stablePrefix: part(),
+    tail: part(),
+    total: part(),
+  }
+  const add = (target: PromptPart, size: PromptPart) => {
+    target.bytes += size.bytes
+    target.tokens += size.tokens
+  }
+
+  if (request.tools) {
+    const json = JSON.stringify(request.tools)
+    add(composition.tools, { bytes: Buffer.byteLength(json), tokens: textTokens(json) })
+  }
+  add(composition.stablePrefix, composition.tools)
+
+  const lastAssistant = request.messages.map((m) => m.role).lastIndexOf('assistant')
+  request.messages.forEach((message, index) => {
+    const size = { bytes: messageBytes(message), tokens: messageTokens(message) }
+    if (message.role === 'system') add(composition.system, size)
+    else if (message.role !== 'tool') add(composition.conversation, size)
+    else if (isArtifactReference(message.content)) add(composition.toolSummaries, size)
+    else add(composition.toolResults, size)
+    add(message.role === 'system' || index <= lastAssistant ? composition.stablePrefix : composition.tail, size)
+  })
+
+  add(composition.total, composition.stablePrefix)
+  add(composition.total, composition.tail)
+  return composition
+}
+
+/**
+ * Splits a call's actual prompt tokens and prompt cost between tools in
+ * proportion to their estimated share of the prompt.
+ */
+export function attributeToolContext(
+  estimate: ContextEstimate,
+  promptTokens: number,
+  promptCostUsd: number,
+): Record<string, ToolContextShare> | null {
+  if (estimate.total === 0 || estimate.byTool.size === 0) return null
+  const shares: Record<string, ToolContextShare> = {}
+  for (const [tool, tokens] of estimate.byTool) {
+    const fraction = tokens / estimate.total
+    shares[tool] = {
+      tokens: Math.round(promptTokens * fraction),
+      costUsd: Math.round(promptCostUsd * fraction * 1_000_000) / 1_000_000,
+    }
+  }
+  return shares
+}
+
+export interface ToolCostRow {
+  tool: string
+  /** Tool name up to its last dot: `files`, `mcp.github_1a2b3c4d`, `web_search`. */
+  integration: string
+  /** LLM calls that carried this tool's results in their prompt. */
+  calls: number
+  /** Conversations those calls belong to. */
+  sessions: number
+  tokens: number
+  cost: number
+}
+
+export interface ToolCostReport {
+  from: number | null
+  to: number | null
+  /** Cost of every call in range, for scale. */
+  totalCost: number
+  /** Most expensive first. */
+  tools: ToolCostRow[]
+  /** Tools rolled up per integration, most expensive first. */
+  integrations: Array<Omit<ToolCostRow, 'tool' | 'integration'> & { integration: string; tools: string[] }>
+}
+
+export function buildToolCostReport(
+  records: UsageRecord[],
+  range: { from?: number; to?: number } = {},
+): ToolCostReport {
+  const tools = new Map<string, ToolCostRow & { sessionIds: Set<string> }>()
+  let totalCost = 0
+
+  for (const record of records) {
+    totalCost += record.costUsd
+    for (const [tool, share] of Object.entries(record.toolContext ?? {})) {
+      let row = tools.get(tool)
+      if (!row) {
+        row = { tool, integration: integrationOf(tool), calls: 0, sessions: 0, tokens: 0, cost: 0, sessionIds: new Set() }
+        tools.set(tool, row)
+      }
+      row.calls++
+      row.tokens += share.tokens
+      row.cost += share.costUsd
+      if (record.sessionId) row.sessionIds.add(record.sessionId)
+    }
+  }
+
+  const integrations = new Map<string, ToolCostReport['integrations'][number] & { sessionIds: Set<string> }>()
+  for (const row of tools.values()) {
+    let group = integrations.get(row.integration)
+    if (!group) {
+      group = { integration: row.integration, tools: [], calls: 0, sessions: 0, tokens: 0, cost: 0, sessionIds: new Set() }
+      integrations.set(row.integration, group)
+    }
+    group.tools.push(row.tool)
+    group.calls += row.calls
+    group.tokens += row.tokens
+    group.cost += row.cost
+    for (const id of row.sessionIds) group.sessionIds.add(id)
+  }
+
+  const byCost = (a: { cost: number; tokens: number }, b: { cost: number; tokens: number }) =>
+    b.cost - a.cost || b.tokens - a.tokens
+
+  return {
+    from: range.from ?? null,
+    to: range.to ?? null,
+    totalCost: round(totalCost),
+    tools: [...tools.values()]
+      .map(({ sessionIds, ...row }) => ({ ...row, sessions: sessionIds.size, cost: round(row.cost) }))
+      .sort(byCost),
+    integrations: [...integrations.values()]
+      .map(({ sessionIds, ...group }) => ({ ...group, tools: group.tools.sort(), sessions: sessionIds.size, cost: round(group.cost) }))
+      .sort(byCost),
+  }
+}
+
+function integrationOf(tool: string): string {
+  const dot = tool.lastIndexOf('.')
+  return dot > 0 ? tool.slice(0, dot) : tool
+}
+
+function messageTokens(message: LLMMessage): number {
+  return estimateContentTokens(message.content)
+    + (message.tool_calls ?? []).reduce((sum, call) => sum + textTokens(JSON.stringify(call.arguments ?? {})), 0)
+}
+
+function messageBytes(message: LLMMessage): number {
+  const content = typeof message.content === 'string'
+    ? Buffer.byteLength(message.content)
+    : message.content.reduce((sum, block) => sum + Buffer.byteLength(block.text ?? block.content ?? block.data ?? '')
+      + (block.input ? Buffer.byteLength(JSON.stringify(block.input)) : 0), 0)
+  return content + (message.tool_calls ?? []).reduce((sum, call) => sum + Buffer.byteLength(JSON.stringify(call.arguments ?? {})), 0)
+}
+
+function isArtifactReference(content: string | LLMContentBlock[]): boolean {
+  const text = typeof content === 'string' ? content : content[0]?.text ?? content[0]?.content ?? ''
+  return text.startsWith(ARTIFACT_REFERENCE_HEADER)
+}
+
+function estimateContentTokens(content: string | LLMContentBlock[]): number {
+  if (typeof content === 'string') return textTokens(content)
+  return content.reduce((sum, block) => {
+    if (block.type === 'image') return sum + IMAGE_TOKEN_ESTIMATE
+    return sum + textTokens(block.text ?? block.content ?? '') + (block.input ? textTokens(JSON.stringify(block.input)) : 0)
+  }, 0)
+}
+
+function textTokens(text: string): number {
+  return Math.ceil(text.length / CHARS_PER_TOKEN)
+}
+
+function round(value: number): number {
+  return Math.round(value * 1_000_000) / 1_000_000
+}
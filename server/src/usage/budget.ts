@@ -0,0 +1,270 @@
+import type { Agent } from '../domain/types.js'
+import { EVENT_TYPES, type EventSink } from '../events/types.js'
+import type { PreferenceRepository, SessionRepository, UsageRecord, UsageRepository } from '../repositories/types.js'
+import { logger } from '../lib/logger.js'
+import { splitModelId } from '../lib/model.js'
+
+export const BUDGET_PREFERENCE_KEY = 'usage_budget'
+
+export type BudgetPeriod = 'weekly' | 'monthly'
+export type CapPeriod = 'daily' | BudgetPeriod
+
+const CAP_PERIODS: CapPeriod[] = ['daily', 'weekly', 'monthly']
+
+/** A hard limit on what one provider, or one model, may cost per period. */
+export interface SpendCap {
+  /** A provider (`anthropic`) or a full model id (`anthropic:claude-opus-4-1`). */
+  scope: string
+  period: CapPeriod
+  limitUsd: number
+  /** Model to switch to once the cap is hit; null refuses further calls instead. */
+  fallbackModel: string | null
+}
+
+export interface BudgetSettings {
+  /** USD cap per calendar week (Monday, UTC); null disables it. */
+  weeklyUsd: number | null
+  /** USD cap per calendar month (UTC); null disables it. */
+  monthlyUsd: number | null
+  /** Fractions of a cap at which a warning is emitted, ascending. */
+  warnAt: number[]
+  /** Refuse new messages once a cap is reached unless the caller overrides. */
+  blockWhenExceeded: boolean
+  /** Per-provider and per-model caps, enforced before every LLM call of a run. */
+  caps: SpendCap[]
+}
+
+export const DEFAULT_BUDGET: BudgetSettings = {
+  weeklyUsd: null,
+  monthlyUsd: null,
+  warnAt: [0.8, 1],
+  blockWhenExceeded: false,
+  caps: [],
+}
+
+export interface BudgetStatus {
+  period: BudgetPeriod
+  periodStart: number
+  limitUsd: number
+  spentUsd: number
+  fraction: number
+  exceeded: boolean
+}
+
+export interface SpendCapStatus extends SpendCap {
+  periodStart: number
+  spentUsd: number
+  exceeded: boolean
+}
+
+export class BudgetExceededError extends Error {
+  constructor(readonly statuses: BudgetStatus[]) {
+    const names = statuses.map((s) => s.period).join(' and ')
+    super(`Usage budget exceeded (${names}). Resend with overrideBudget to continue.`)
+  }
+}
+
+/** A model's spend cap is hit and there is no fallback left to switch to. */
+export class SpendCapExceededError extends Error {
+  constructor(readonly model: string, readonly caps: SpendCapStatus[]) {
+    const limits = caps.map((c) => `$${c.limitUsd} ${c.period} on ${c.scope}`).join(', ')
+    super(`Spending cap reached for ${model} (${limits}). Raise the cap or set a fallback model.`)
+  }
+}
+
+export function parseBudget(raw: string | null): BudgetSettings {
+  if (!raw) return { ...DEFAULT_BUDGET }
+  try {
+    return normalizeBudget(JSON.parse(raw) as Partial<BudgetSettings>)
+  } catch {
+    logger.warn('Ignoring malformed usage_budget preference')
+    return { ...DEFAULT_BUDGET }
+  }
+}
+
+/** Validate user input; throws with a message suitable for a 400. */
+export function normalizeBudget(input: Partial<BudgetSettings>): BudgetSettings {
+  const cap = (value: unknown, name: string): number | null => {
+    if (value === undefined || value === null) return null
+    if (typeof value !== 'number' || !Number.isFinite(value) || value <= 0) {
+      throw new Error(`${name} must be a positive number or null`)
+    }
+    return value
+  }
+  const warnAt = input.warnAt ?? DEFAULT_BUDGET.warnAt
+  if (!Array.isArray(warnAt) || warnAt.some((t) => typeof t !== 'number' || !(t > 0))) {
+    throw new Error('warnAt must be an array of positive fractions')
+  }
+  const caps = input.caps ?? []
+  if (!Array.isArray(caps)) throw new Error('caps must be an array')
+  return {
+    weeklyUsd: cap(input.weeklyUsd, 'weeklyUsd'),
+    monthlyUsd: cap(input.monthlyUsd, 'monthlyUsd'),
+    warnAt: [...new Set(warnAt)].sort((a, b) => a - b),
+    blockWhenExceeded: input.blockWhenExceeded === true,
+    caps: caps.map((entry: Partial<SpendCap>, i) => {
+      const scope = typeof entry?.scope === 'string' ? entry.scope.trim().toLowerCase() : ''
+      if (!scope) throw new Error(`caps[${i}].scope must be a provider or model id`)
+      const period = entry.period ?? 'daily'
+      if (!CAP_PERIODS.includes(period)) throw new Error(`caps[${i}].period must be one of: ${CAP_PERIODS.join(', ')}`)
+      const limitUsd = cap(entry.limitUsd, `caps[${i}].limitUsd`)
+      if (limitUsd === null) throw new Error(`caps[${i}].limitUsd must be a positive number`)
+      const fallbackModel = entry.fallbackModel ?? null
+      if (fallbackModel !== null && (typeof fallbackModel !== 'string' || !fallbackModel.includes(':'))) {
+        throw new Error(`caps[${i}].fallbackModel must be a provider:model id or null`)
+      }
+      if (fallbackModel && capMatches(scope, fallbackModel)) {
+        throw new Error(`caps[${i}].fallbackModel falls under the same cap`)
+      }
+      return { scope, period, limitUsd, fallbackModel }
+    }),
+  }
+}
+
+/** Whether a cap's scope covers a `provider:model` id. */
+export function capMatches(scope: string, model: string): boolean {
+  const { provider } = splitModelId(model)
+  return scope === provider.toLowerCase() || scope === model.toLowerCase()
+}
+
+export function periodStart(period: CapPeriod, now: number): number {
+  const date = new Date(now)
+  if (period === 'daily') {
+    return Date.UTC(date.getUTCFullYear(), date.getUTCMonth(), date.getUTCDate())
+  }
+  if (period === 'monthly') {
+    return Date.UTC(date.getUTCFullYear(), date.getUTCMonth(), 1)
+  }
+  const day = (date.getUTCDay() + 6) % 7
+  return Date.UTC(date.getUTCFullYear(), date.getUTCMonth(), date.getUTCDate() - day)
+}
+
+export interface SpendCapResolution {
+  model: string
+  /** Caps that forced a switch away from the requested model; empty when it is unchanged. */
+  caps: SpendCapStatus[]
+}
+
+/** Picks the model for each LLM call of a run; see BudgetMonitor.resolveModel. */
+export interface SpendCapGuard {
+  resolveModel(agent: Pick<Agent, 'sessionId'>, model: string): Promise<SpendCapResolution>
+}
+
+/**
+ * Tracks spend against the configured caps. Warnings fire once per threshold
+ * per period; the high-water mark is kept in memory, so a restart may repeat
+ * the most recent warning at most once.
+ */
+export class BudgetMonitor implements SpendCapGuard {
+  private readonly alerted = new Map<string, number>()
+
+  constructor(
+    private readonly usage: UsageRepository,
+    private readonly preferences: PreferenceRepository,
+    private readonly events: EventSink,
+    private readonly sessions?: Pick<SessionRepository, 'getById'>,
+  ) {}
+
+  async settings(): Promise<BudgetSettings> {
+    return parseBudget(await this.preferences.get(BUDGET_PREFERENCE_KEY))
+  }
+
+  async status(userId: string, now = Date.now()): Promise<BudgetStatus[]> {
+    const settings = await this.settings()
+    const periods: Array<[BudgetPeriod, number | null]> = [
+      ['weekly', settings.weeklyUsd],
+      ['monthly', settings.monthlyUsd],
+    ]
+    const statuses: BudgetStatus[] = []
+    for (const [period, limitUsd] of periods) {
+      if (limitUsd === null) continue
+      const start = periodStart(period, now)
+      const records = await this.usage.list({ userId, from: start })
+      const spentUsd = Math.round(records.reduce((sum, r) => sum + r.costUsd, 0) * 1_000_000) / 1_000_000
+      statuses.push({
+        period,
+        periodStart: start,
+        limitUsd,
+        spentUsd,
+        fraction: spentUsd / limitUsd,
+        exceeded: spentUsd >= limitUsd,
+      })
+    }
+    return statuses
+  }
+
+  /** Spend against each per-provider/per-model cap. */
+  async capStatus(userId: string | null, now = Date.now()): Promise<SpendCapStatus[]> {
+    const { caps } = await this.settings()
+    const spent = new Map<number, UsageRecord[]>()
+    const statuses: SpendCapStatus[] = []
+    for (const cap of caps) {
+      const start = periodStart(cap.period, now)
+      let records = spent.get(start)
+      if (!records) {
+        records = await this.usage.list({ userId: userId ?? undefined, from: start })
+        spent.set(start, records)
+      }
+      const spentUsd = Math.round(records
+        .filter((r) => capMatches(cap.scope, `${r.provider}:${r.model}`))
+        .reduce((sum, r) => sum + r.costUsd, 0) * 1_000_000) / 1_000_000
+      statuses.push({ ...cap, periodStart: start, spentUsd, exceeded: spentUsd >= cap.limitUsd })
+    }
+    return statuses
+  }
+
+  /**
+   * The model an agent's next LLM call should use: its own, or the fallback
+   * of a cap it has hit, following fallbacks until one is under its caps.
+   * Throws SpendCapExceededError when a hit cap has no usable fallback.
+   */
+  async resolveModel(agent: Pick<Agent, 'sessionId'>, model: string, now = Date.now()): Promise<SpendCapResolution> {
+    const { caps } = await this.settings()
+    if (caps.length === 0) return { model, caps: [] }
+
+    const session = this.sessions ? await this.sessions.getById(agent.sessionId) : null
+    const statuses = await this.capStatus(session?.userId ?? null, now)
+    const hit: SpendCapStatus[] = []
+    const tried = new Set<string>()
+    let current = model
+    for (;;) {
+      tried.add(current)
+      const exceeded = statuses.filter((s) => s.exceeded && capMatches(s.scope, current))
+      if (exceeded.length === 0) return { model: current, caps: hit }
+      hit.push(...exceeded)
+      const next = exceeded.find((s) => s.fallbackModel && !tried.has(s.fallbackModel))?.fallbackModel
+      if (!next) throw new SpendCapExceededError(model, hit)
+      current = next
+    }
+  }
+
+  /** Throws BudgetExceededError when blocking is enabled and a cap is hit. */
+  async assertWithinBudget(userId: string): Promise<void> {
+    const settings = await this.settings()
+    if (!settings.blockWhenExceeded) return
+    const exceeded = (await this.status(userId)).filter((s) => s.exceeded)
+    if (exceeded.length > 0) throw new BudgetExceededError(exceeded)
+  }
+
+  /** Called after each recorded LLM call; emits budget:threshold on crossings. */
+  async observe(userId: string, agent: Agent): Promise<void> {
+    const settings = await this.settings()
+    if (settings.weeklyUsd === null && settings.monthlyUsd === null) return
+
+    for (const status of await this.status(userId)) {
+      const crossed = settings.warnAt.filter((t) => status.fraction >= t).pop()
+      if (crossed === undefined) continue
+      const key = `${userId}:${status.period}:${status.periodStart}`
+      if ((this.alerted.get(key) ?? 0) >= crossed) continue
+      this.alerted.set(key, crossed)
+
+      this.events.emit({
+        type: EVENT_TYPES.BUDGET_THRESHOLD,
+        agent_id: agent.id,
+        session_id: agent.sessionId,
+        timestamp: Date.now(),
+        payload: { ...status, threshold: crossed },
+      })
+    }
+  }
+}
@@ -0,0 +1,43 @@
+import type { UsageRecord } from '../repositories/types.js'
+
+export const USAGE_CSV_COLUMNS = [
+  'timestamp',
+  'conversation_id',
+  'conversation_title',
+  'provider',
+  'model',
+  'prompt_tokens',
+  'completion_tokens',
+  'cached_tokens',
+  'cost_usd',
+] as const
+
+/** RFC 4180 quoting; also neutralises leading =+-@ so spreadsheets don't evaluate titles. */
+export function csvField(value: string | number | null): string {
+  if (value === null) return ''
+  let text = String(value)
+  if (typeof value === 'string' && /^[=+\-@]/.test(text)) text = `'${text}`
+  return /[",\r\n]/.test(text) ? `"${text.replace(/"/g, '""')}"` : text
+}
+
+/**
+ * One row per LLM call. `titles` maps session id to conversation title;
+ * sessions that no longer exist export with an empty title.
+ */
+export function usageToCsv(records: UsageRecord[], titles: Map<string, string | null>): string {
+  const lines = [USAGE_CSV_COLUMNS.join(',')]
+  for (const record of records) {
+    lines.push([
+      new Date(record.createdAt).toISOString(),
+      record.sessionId,
+      record.sessionId ? titles.get(record.sessionId) ?? null : null,
+      record.provider,
+      record.model,
+      record.inputTokens,
+      record.outputTokens,
+      record.cacheReadTokens,
+      record.costUsd.toFixed(6),
+    ].map(csvField).join(','))
+  }
+  return lines.join('\r\n') + '\r\n'
+}
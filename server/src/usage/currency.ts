@@ -0,0 +1,147 @@
+import { logger } from '../lib/logger.js'
+import type { ToolCostReport } from './attribution.js'
+import type { UsageDashboard, UsageReport, UsageTotals } from './report.js'
+
+export const CURRENCY_PREFERENCE_KEY = 'display_currency'
+
+/**
+ * How costs are shown in usage reports. Costs are always stored in USD; the
+ * reports convert on the way out.
+ */
+export interface CurrencySettings {
+  /** ISO 4217 code, e.g. `EUR`. */
+  code: string
+  /** Units of `code` per US dollar. */
+  rate: number
+  /** BCP 47 locale for number formatting, e.g. `de-DE`; null leaves it to the client. */
+  locale: string | null
+  /** `remote` rates came from EXCHANGE_RATE_URL and can be refreshed. */
+  source: 'manual' | 'remote'
+  rateUpdatedAt: number | null
+}
+
+export const DEFAULT_CURRENCY: CurrencySettings = {
+  code: 'USD',
+  rate: 1,
+  locale: null,
+  source: 'manual',
+  rateUpdatedAt: null,
+}
+
+/** Attached to converted reports so clients can label and format amounts. */
+export interface DisplayCurrency {
+  code: string
+  rate: number
+  locale: string | null
+}
+
+export class ExchangeRateError extends Error {}
+
+export function parseCurrencySettings(raw: string | null): CurrencySettings {
+  if (!raw) return { ...DEFAULT_CURRENCY }
+  try {
+    const parsed = JSON.parse(raw) as Partial<CurrencySettings>
+    return {
+      ...normalizeCurrencySettings(parsed),
+      source: parsed.source === 'remote' ? 'remote' : 'manual',
+      rateUpdatedAt: typeof parsed.rateUpdatedAt === 'number' ? parsed.rateUpdatedAt : null,
+    }
+  } catch {
+    logger.warn('Ignoring malformed display_currency preference')
+    return { ...DEFAULT_CURRENCY }
+  }
+}
+
+/**
+ * Validate user input; throws with a message suitable for a 400. A missing
+ * rate is left at 0 for the caller to fetch; USD always has rate 1.
+ */
+export function normalizeCurrencySettings(input: Partial<CurrencySettings>): CurrencySettings {
+  const code = typeof input.code === 'string' ? input.code.trim().toUpperCase() : ''
+  if (!/^[A-Z]{3}$/.test(code)) throw new Error('code must be a three-letter ISO 4217 currency code')
+  if (input.rate !== undefined && input.rate !== null && (typeof input.rate !== 'number' || !Number.isFinite(input.rate) || input.rate <= 0)) {
+    throw new Error('rate must be a positive number (units per US dollar)')
+  }
+  let locale: string | null = null
+  if (input.locale !== undefined && input.locale !== null && input.locale !== '') {
+    if (typeof input.locale !== 'string') throw new Error('locale must be a BCP 47 tag such as de-DE')
+    try {
+      locale = Intl.getCanonicalLocales(input.locale.trim())[0]
+    } catch {
+      throw new Error('locale must be a BCP 47 tag such as de-DE')
+    }
+  }
+  return {
+    code,
+    rate: code === 'USD' ? 1 : input.rate ?? 0,
+    locale,
+    source: 'manual',
+    rateUpdatedAt: null,
+  }
+}
+
+/** Reads the rate for `code` from a `{ rates: { EUR: 0.92 } }` document (USD base). */
+export async function fetchExchangeRate(code: string, url: string, fetchImpl: typeof fetch = fetch): Promise<number> {
+  if (!url) throw new ExchangeRateError('No exchange rate URL configured (EXCHANGE_RATE_URL)')
+  let body: unknown
+  try {
+    const res = await fetchImpl(url, { signal: AbortSignal.timeout(15_000) })
+    if (!res.ok) throw new Error(`HTTP ${res.status}`)
+    body = await res.json()
+  } catch (err) {
+    throw new ExchangeRateError(`Failed to fetch exchange rates: ${err instanceof Error ? err.message : String(err)}`)
+  }
+  const rates = (body as { rates?: Record<string, unknown> } | null)?.rates
+  const rate = rates?.[code]
+  if (typeof rate !== 'number' || !Number.isFinite(rate) || rate <= 0) {
+    throw new ExchangeRateError(`Exchange rate feed has no rate for ${code}`)
+  }
+  return rate
+}
+
+export function displayCurrency(settings: CurrencySettings): DisplayCurrency {
+  return { code: settings.code, rate: settings.rate, locale: settings.locale }
+}
+
+export function convertUsageReport(report: UsageReport, currency: DisplayCurrency): UsageReport & { currency: DisplayCurrency } {
+  return {
+    ...report,
+    totals: convertTotals(report.totals, currency.rate),
+    buckets: report.buckets.map((bucket) => convertTotals(bucket, currency.rate)),
+    currency,
+  }
+}
+
+export function convertUsageDashboard(dashboard: UsageDashboard, currency: DisplayCurrency): UsageDashboard & { currency: DisplayCurrency } {
+  const rate = currency.rate
+  return {
+    ...dashboard,
+    totals: convertTotals(dashboard.totals, rate),
+    daily: dashboard.daily.map((day) => ({
+      ...convertTotals(day, rate),
+      models: Object.fromEntries(Object.entries(day.models).map(([model, cost]) => [model, convert(cost, rate)])),
+    })),
+    models: dashboard.models.map((bucket) => convertTotals(bucket, rate)),
+    conversations: dashboard.conversations.map((bucket) => convertTotals(bucket, rate)),
+    phases: dashboard.phases.map((bucket) => convertTotals(bucket, rate)),
+    currency,
+  }
+}
+
+export function convertToolCostReport(report: ToolCostReport, currency: DisplayCurrency): ToolCostReport & { currency: DisplayCurrency } {
+  return {
+    ...report,
+    totalCost: convert(report.totalCost, currency.rate),
+    tools: report.tools.map((row) => ({ ...row, cost: convert(row.cost, currency.rate) })),
+    integrations: report.integrations.map((group) => ({ ...group, cost: convert(group.cost, currency.rate) })),
+    currency,
+  }
+}
+
+function convertTotals<T extends UsageTotals>(totals: T, rate: number): T {
+  return { ...totals, cost: convert(totals.cost, rate), cache_savings: convert(totals.cache_savings, rate) }
+}
+
+function convert(usd: number, rate: number): number {
+  return Math.round(usd * rate * 1_000_000) / 1_000_000
+}
@@ -0,0 +1,96 @@
+import type { Agent, Item } from '../domain/types.js'
+import type { ToolExecutor } from '../tools/types.js'
+import { splitModelId } from '../lib/model.js'
+import { buildControllerPrompt, maxOutputTokens } from '../orchestrator/runner.js'
+import type { UserProfile } from '../orchestrator/user-profile.js'
+import type { InstructionFile } from '../services/instruction-files.js'
+import { estimatePromptTokens, type PromptEstimate } from './attribution.js'
+import { computeCost, type ModelPrice, type PricingRegistry } from './pricing.js'
+
+export interface RunCostEstimate {
+  /** Full model id, e.g. `anthropic:claude-sonnet-4-20250514`. */
+  model: string
+  provider: string
+  /** Estimated prompt tokens of the next controller turn. */
+  prompt: PromptEstimate
+  maxOutputTokens: number
+  maxTurns: number
+  /** USD per million tokens, or null when the model has no known price. */
+  price: ModelPrice | null
+  perTurn: {
+    /** Sending the prompt once. Later turns cost more as tool results pile up. */
+    promptCostUsd: number | null
+    /** Prompt plus a reply that uses the whole output budget. */
+    maxCostUsd: number | null
+  }
+}
+
+export interface EstimateOptions {
+  /** Estimate as if the run used this model instead of the agent's own. */
+  model?: string
+  /** A message not yet sent, appended to the history as the next user turn. */
+  draft?: string
+  /** Rendered into the system prompt the way a real run does. */
+  userProfile?: UserProfile | null
+  instructionFiles?: InstructionFile[]
+}
+
+/**
+ * Estimates what the next controller turn of an agent will send and cost,
+ * using the same prompt assembly as a real run. Token counts are rough
+ * (see `estimatePromptTokens`); they are meant for a sanity check, not billing.
+ */
+export function estimateRunCost(
+  agent: Pick<Agent, 'id' | 'task' | 'depth' | 'config' | 'turnCount'>,
+  tools: Pick<ToolExecutor, 'listMetadata'>,
+  items: Item[],
+  pricing: Pick<PricingRegistry, 'lookup'>,
+  options: EstimateOptions = {},
+): RunCostEstimate {
+  const model = options.model ?? agent.config.model
+  const { provider, model: modelName } = splitModelId(model)
+  const config = options.model ? { ...agent.config, model, provider } : agent.config
+  const history = options.draft ? [...items, draftItem(agent, options.draft)] : items
+
+  const request = buildControllerPrompt({ ...agent, config }, tools, history, {
+    userProfile: options.userProfile,
+    instructionFiles: options.instructionFiles,
+  })
+  const prompt = estimatePromptTokens(request)
+  const outputTokens = maxOutputTokens({ config })
+  const price = pricing.lookup(config.provider, modelName)
+
+  return {
+    model,
+    provider: config.provider,
+    prompt,
+    maxOutputTokens: outputTokens,
+    maxTurns: config.max_turns,
+    price,
+    perTurn: {
+      promptCostUsd: price ? computeCost(price, prompt.total, 0) : null,
+      maxCostUsd: price ? computeCost(price, prompt.total, outputTokens) : null,
+    },
+  }
+}
+
+function draftItem(agent: Pick<Agent, 'id' | 'turnCount'>, content: string): Item {
+  return {
+    id: 'draft',
+    agentId: agent.id,
+    sequence: Number.MAX_SAFE_INTEGER,
+    type: 'message',
+    role: 'user',
+    content,
+    callId: null,
+    name: null,
+    arguments: null,
+    output: null,
+    contentBlocks: null,
+    isError: null,
+    saveOutput: null,
+    turnNumber: agent.turnCount,
+    durationMs: null,
+    createdAt: Date.now(),
+  }
+}
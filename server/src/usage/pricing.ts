@@ -0,0 +1,342 @@
+import { logger } from '../lib/logger.js'
+import type { ModelPriceRecord, ModelPriceRepository, PreferenceRepository } from '../repositories/types.js'
+
+/**
+ * USD list prices per million tokens. Keys are bare model ids; lookups strip
+ * provider prefixes (`anthropic:`), OpenRouter vendor prefixes (`openai/`),
+ * and trailing date stamps (`-20250514`) before matching.
+ */
+export interface ModelPrice {
+  input: number
+  output: number
+  /** Cached prompt tokens (Anthropic cache reads, OpenAI cached input); defaults to `input`. */
+  cacheRead?: number
+  /** Prompt tokens written to the cache (Anthropic cache creation); defaults to `input`. */
+  cacheWrite?: number
+}
+
+/** Bundled prices; they seed the `model_prices` table and back lookups until it is loaded. */
+export const BUILTIN_PRICES: Record<string, ModelPrice> = {
+  // Anthropic: reads are 10% of input, 5-minute cache writes 125%
+  'claude-opus-4': { input: 15, output: 75, cacheRead: 1.5, cacheWrite: 18.75 },
+  'claude-opus-4-1': { input: 15, output: 75, cacheRead: 1.5, cacheWrite: 18.75 },
+  'claude-sonnet-4': { input: 3, output: 15, cacheRead: 0.3, cacheWrite: 3.75 },
+  'claude-sonnet-4-5': { input: 3, output: 15, cacheRead: 0.3, cacheWrite: 3.75 },
+  'claude-3-7-sonnet': { input: 3, output: 15, cacheRead: 0.3, cacheWrite: 3.75 },
+  'claude-3-5-sonnet': { input: 3, output: 15, cacheRead: 0.3, cacheWrite: 3.75 },
+  'claude-haiku-4-5': { input: 1, output: 5, cacheRead: 0.1, cacheWrite: 1.25 },
+  'claude-3-5-haiku': { input: 0.8, output: 4, cacheRead: 0.08, cacheWrite: 1 },
+  // OpenAI: caching is automatic and free to write
+  'gpt-5': { input: 1.25, output: 10, cacheRead: 0.125 },
+  'gpt-5-mini': { input: 0.25, output: 2, cacheRead: 0.025 },
+  'gpt-5-nano': { input: 0.05, output: 0.4, cacheRead: 0.005 },
+  'gpt-4.1': { input: 2, output: 8, cacheRead: 0.5 },
+  'gpt-4.1-mini': { input: 0.4, output: 1.6, cacheRead: 0.1 },
+  'gpt-4.1-nano': { input: 0.1, output: 0.4, cacheRead: 0.025 },
+  'gpt-4o': { input: 2.5, output: 10, cacheRead: 1.25 },
+  'gpt-4o-mini': { input: 0.15, output: 0.6, cacheRead: 0.075 },
+  'o3': { input: 2, output: 8, cacheRead: 0.5 },
+  'o4-mini': { input: 1.1, output: 4.4, cacheRead: 0.275 },
+}
+
+/**
+ * Flat prices for a whole backend (a self-hosted Ollama, a third-party
+ * OpenAI-compatible endpoint), keyed by provider name. They apply to every
+ * model served by that provider and take precedence over per-model prices.
+ */
+export const BACKEND_PRICING_PREFERENCE_KEY = 'backend_pricing'
+
+const DATE_SUFFIX_RE = /-\d{8}$|-\d{4}-\d{2}-\d{2}$/
+
+export function normalizeModelId(model: string): string {
+  let id = model.trim().toLowerCase()
+  const colon = id.indexOf(':')
+  if (colon !== -1) id = id.slice(colon + 1)
+  const slash = id.lastIndexOf('/')
+  if (slash !== -1) id = id.slice(slash + 1)
+  return id.replace(DATE_SUFFIX_RE, '').replace(/-latest$/, '')
+}
+
+/** Local models (Ollama) are free; unknown hosted models return null. */
+export function lookupPrice(provider: string, model: string): ModelPrice | null {
+  if (provider.toLowerCase() === 'ollama') return { input: 0, output: 0 }
+  return BUILTIN_PRICES[normalizeModelId(model)] ?? null
+}
+
+/** Prompt tokens served from or written to the provider's cache, on top of `inputTokens`. */
+export interface CacheTokens {
+  readTokens?: number
+  writeTokens?: number
+}
+
+export function computeCost(
+  price: ModelPrice | null,
+  inputTokens: number,
+  outputTokens: number,
+  cache: CacheTokens = {},
+): number {
+  if (!price) return 0
+  const cost = inputTokens * price.input
+    + outputTokens * price.output
+    + (cache.readTokens ?? 0) * (price.cacheRead ?? price.input)
+    + (cache.writeTokens ?? 0) * (price.cacheWrite ?? price.input)
+  return round(cost / 1_000_000)
+}
+
+/**
+ * What caching saved against paying the plain input rate for every cached
+ * token. Cache writes that cost more than input count against it, so a call
+ * that only populated the cache comes out negative.
+ */
+export function computeCacheSavings(price: ModelPrice | null, cache: CacheTokens): number {
+  if (!price) return 0
+  const saved = (cache.readTokens ?? 0) * (price.input - (price.cacheRead ?? price.input))
+    - (cache.writeTokens ?? 0) * ((price.cacheWrite ?? price.input) - price.input)
+  return round(saved / 1_000_000)
+}
+
+/** Validate user input; throws with a message suitable for a 400. Cache rates are optional. */
+export function normalizeModelPrice(input: Partial<ModelPrice>): ModelPrice {
+  const rate = (value: unknown, name: string): number => {
+    if (typeof value !== 'number' || !Number.isFinite(value) || value < 0) {
+      throw new Error(`${name} must be a non-negative number (USD per million tokens)`)
+    }
+    return value
+  }
+  const price: ModelPrice = { input: rate(input?.input, 'input'), output: rate(input?.output, 'output') }
+  if (input?.cacheRead !== undefined && input.cacheRead !== null) price.cacheRead = rate(input.cacheRead, 'cacheRead')
+  if (input?.cacheWrite !== undefined && input.cacheWrite !== null) price.cacheWrite = rate(input.cacheWrite, 'cacheWrite')
+  return price
+}
+
+/**
+ * Reads a remote price catalog. Accepts `{ "<model>": { input, output,
+ * cacheRead?, cacheWrite? } }` in USD per million tokens (optionally wrapped in
+ * `prices`/`pricing`), or LiteLLM-style `*_cost_per_token` entries.
+ * Entries without both prices are skipped; when several keys normalize to the
+ * same id, the bare key wins over vendor-prefixed ones.
+ */
+export function parsePriceCatalog(body: unknown): Map<string, ModelPrice> {
+  const root = body && typeof body === 'object' ? (body as Record<string, unknown>) : {}
+  const entries = (root.prices ?? root.pricing ?? root) as Record<string, unknown>
+  const prices = new Map<string, ModelPrice>()
+  const bare = new Set<string>()
+  for (const [key, value] of Object.entries(entries ?? {})) {
+    if (!value || typeof value !== 'object') continue
+    const entry = value as Record<string, unknown>
+    const perMillion = (value: unknown) => (typeof value === 'number' ? value * 1_000_000 : undefined)
+    const price = typeof entry.input_cost_per_token === 'number' && typeof entry.output_cost_per_token === 'number'
+      ? {
+          input: perMillion(entry.input_cost_per_token),
+          output: perMillion(entry.output_cost_per_token),
+          cacheRead: perMillion(entry.cache_read_input_token_cost),
+          cacheWrite: perMillion(entry.cache_creation_input_token_cost),
+        }
+      : { input: entry.input, output: entry.output, cacheRead: entry.cacheRead, cacheWrite: entry.cacheWrite }
+    let normalized: ModelPrice
+    try {
+      normalized = normalizeModelPrice(price as Partial<ModelPrice>)
+    } catch {
+      continue
+    }
+    const id = normalizeModelId(key)
+    if (!id) continue
+    const isBare = key.trim().toLowerCase() === id
+    if (prices.has(id) && (bare.has(id) || !isBare)) continue
+    prices.set(id, roundPrice(normalized))
+    if (isBare) bare.add(id)
+  }
+  return prices
+}
+
+export interface PriceRefreshResult {
+  url: string
+  /** Bundled or previously refreshed rows that now carry the remote price. */
+  updated: number
+  /** Models the catalog added. */
+  added: number
+  /** Catalog entries left alone because the user edited that price. */
+  skippedUserEdits: number
+}
+
+export class PriceCatalogError extends Error {}
+
+/**
+ * Prices used for cost accounting, kept in the `model_prices` table and cached
+ * in memory so lookups stay synchronous. The table is seeded from the bundled
+ * prices, can be edited per model, and refreshed from a remote catalog.
+ */
+export class PricingRegistry {
+  private prices = new Map<string, ModelPriceRecord>()
+  private backendPrices = new Map<string, ModelPrice>()
+
+  constructor(
+    private readonly repo: ModelPriceRepository,
+    private readonly catalogUrl?: string,
+    private readonly fetchImpl: typeof fetch = fetch,
+    private readonly preferences?: Pick<PreferenceRepository, 'get' | 'set'>,
+  ) {}
+
+  /**
+   * Seeds models the table does not know yet, brings untouched bundled rows up
+   * to the current bundled prices, and fills the cache.
+   */
+  async load(): Promise<void> {
+    const added = await this.repo.seed(
+      Object.entries(BUILTIN_PRICES).map(([modelId, price]) => ({ modelId, ...price, source: 'bundled' as const })),
+    )
+    if (added > 0) logger.info({ added }, 'Seeded model prices from the bundled table')
+    await this.reload()
+    this.backendPrices = await this.loadBackendPrices()
+    for (const [modelId, record] of this.prices) {
+      const bundled = BUILTIN_PRICES[modelId]
+      if (record.source !== 'bundled' || !bundled || samePrice(priceOf(record), bundled)) continue
+      this.prices.set(modelId, await this.repo.upsert({ modelId, ...bundled, source: 'bundled' }))
+    }
+  }
+
+  /** Same contract as `lookupPrice`, served from the table; a backend price wins over both. */
+  lookup(provider: string, model: string): ModelPrice | null {
+    const backend = this.backendPrices.get(provider.toLowerCase())
+    if (backend) return backend
+    if (provider.toLowerCase() === 'ollama') return { input: 0, output: 0 }
+    if (this.prices.size === 0) return lookupPrice(provider, model)
+    const record = this.prices.get(normalizeModelId(model))
+    return record ? priceOf(record) : null
+  }
+
+  list(): ModelPriceRecord[] {
+    return [...this.prices.values()]
+  }
+
+  /** Sets a price by hand; later refreshes leave it alone. */
+  async set(model: string, price: Partial<ModelPrice>): Promise<ModelPriceRecord> {
+    const modelId = normalizeModelId(model)
+    if (!modelId) throw new Error('model is required')
+    const record = await this.repo.upsert({ modelId, ...normalizeModelPrice(price), source: 'user' })
+    this.prices.set(modelId, record)
+    return record
+  }
+
+  /**
+   * Drops an edited or refreshed price. Bundled models go back to the bundled
+   * price. Returns what the model costs now, null if it has no price left, or
+   * undefined if it had none to begin with.
+   */
+  async reset(model: string): Promise<ModelPriceRecord | null | undefined> {
+    const modelId = normalizeModelId(model)
+    if (!this.prices.has(modelId)) return undefined
+    const bundled = BUILTIN_PRICES[modelId]
+    if (bundled) {
+      const record = await this.repo.upsert({ modelId, ...bundled, source: 'bundled' })
+      this.prices.set(modelId, record)
+      return record
+    }
+    await this.repo.delete(modelId)
+    this.prices.delete(modelId)
+    return null
+  }
+
+  /** Backend prices by provider name. */
+  backends(): Record<string, ModelPrice> {
+    return Object.fromEntries(this.backendPrices)
+  }
+
+  /** Prices every model of a provider at one rate, e.g. a GPU box's running cost. */
+  async setBackend(provider: string, price: Partial<ModelPrice>): Promise<ModelPrice> {
+    const name = provider.trim().toLowerCase()
+    if (!name) throw new Error('provider is required')
+    const normalized = roundPrice(normalizeModelPrice(price))
+    const next = new Map(this.backendPrices).set(name, normalized)
+    await this.saveBackendPrices(next)
+    return normalized
+  }
+
+  /** Drops a backend price; returns false if the provider had none. */
+  async resetBackend(provider: string): Promise<boolean> {
+    const name = provider.trim().toLowerCase()
+    if (!this.backendPrices.has(name)) return false
+    const next = new Map(this.backendPrices)
+    next.delete(name)
+    await this.saveBackendPrices(next)
+    return true
+  }
+
+  /** Pulls the remote catalog; prices the user set by hand win over it. */
+  async refresh(url = this.catalogUrl): Promise<PriceRefreshResult> {
+    if (!url) throw new PriceCatalogError('No price catalog URL configured (PRICING_CATALOG_URL)')
+    let body: unknown
+    try {
+      const res = await this.fetchImpl(url, { signal: AbortSignal.timeout(30_000) })
+      if (!res.ok) throw new Error(`HTTP ${res.status}`)
+      body = await res.json()
+    } catch (err) {
+      throw new PriceCatalogError(`Failed to fetch price catalog: ${err instanceof Error ? err.message : String(err)}`)
+    }
+    const catalog = parsePriceCatalog(body)
+    if (catalog.size === 0) throw new PriceCatalogError('Price catalog has no usable entries')
+
+    const result: PriceRefreshResult = { url, updated: 0, added: 0, skippedUserEdits: 0 }
+    for (const [modelId, price] of catalog) {
+      const current = this.prices.get(modelId)
+      if (current?.source === 'user') {
+        result.skippedUserEdits++
+        continue
+      }
+      if (current && samePrice(priceOf(current), price)) continue
+      this.prices.set(modelId, await this.repo.upsert({ modelId, ...price, source: 'remote' }))
+      if (current) result.updated++
+      else result.added++
+    }
+    logger.info(result, 'Refreshed model prices')
+    return result
+  }
+
+  private async reload(): Promise<void> {
+    this.prices = new Map((await this.repo.list()).map((record) => [record.modelId, record]))
+  }
+
+  private async loadBackendPrices(): Promise<Map<string, ModelPrice>> {
+    const raw = this.preferences ? await this.preferences.get(BACKEND_PRICING_PREFERENCE_KEY) : null
+    const prices = new Map<string, ModelPrice>()
+    if (!raw) return prices
+    try {
+      for (const [provider, price] of Object.entries(JSON.parse(raw) as Record<string, Partial<ModelPrice>>)) {
+        prices.set(provider, roundPrice(normalizeModelPrice(price)))
+      }
+    } catch {
+      logger.warn('Ignoring malformed backend_pricing preference')
+    }
+    return prices
+  }
+
+  private async saveBackendPrices(prices: Map<string, ModelPrice>): Promise<void> {
+    if (!this.preferences) throw new Error('Backend pricing needs a preference store')
+    await this.preferences.set(BACKEND_PRICING_PREFERENCE_KEY, JSON.stringify(Object.fromEntries(prices)))
+    this.backendPrices = prices
+  }
+}
+
+function round(value: number): number {
+  return Math.round(value * 1_000_000) / 1_000_000
+}
+
+function roundPrice(price: ModelPrice): ModelPrice {
+  const rounded: ModelPrice = { input: round(price.input), output: round(price.output) }
+  if (price.cacheRead !== undefined) rounded.cacheRead = round(price.cacheRead)
+  if (price.cacheWrite !== undefined) rounded.cacheWrite = round(price.cacheWrite)
+  return rounded
+}
+
+function samePrice(a: ModelPrice, b: ModelPrice): boolean {
+  return a.input === b.input && a.output === b.output && a.cacheRead === b.cacheRead && a.cacheWrite === b.cacheWrite
+}
+
+function priceOf(record: ModelPriceRecord): ModelPrice {
+  return roundPrice({
+    input: record.input,
+    output: record.output,
+    ...(record.cacheRead !== null && { cacheRead: record.cacheRead }),
+    ...(record.cacheWrite !== null && { cacheWrite: record.cacheWrite }),
+  })
+}
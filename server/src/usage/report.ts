@@ -0,0 +1,178 @@
+import type { UsageRecord } from '../repositories/types.js'
+
+export type UsageGroupBy = 'day' | 'week' | 'model' | 'conversation' | 'phase'
+
+export const USAGE_GROUP_BY: readonly UsageGroupBy[] = ['day', 'week', 'model', 'conversation', 'phase']
+
+export interface UsageTotals {
+  requests: number
+  input_tokens: number
+  output_tokens: number
+  cache_read_tokens: number
+  cache_write_tokens: number
+  total_tokens: number
+  cost: number
+  /** Saved by prompt caching versus paying the input rate; included in `cost` already. */
+  cache_savings: number
+  /** Share of prompt tokens (input + cache reads + cache writes) served from the cache. */
+  cache_hit_ratio: number
+}
+
+export interface UsageBucket extends UsageTotals {
+  /** Date (YYYY-MM-DD, UTC), week start (Monday), model id, session id, or phase. */
+  key: string
+}
+
+export interface UsageReport {
+  from: number | null
+  to: number | null
+  group_by: UsageGroupBy
+  totals: UsageTotals
+  /** Ordered chronologically for day/week, by descending cost otherwise. */
+  buckets: UsageBucket[]
+}
+
+export function buildUsageReport(
+  records: UsageRecord[],
+  groupBy: UsageGroupBy,
+  range: { from?: number; to?: number } = {},
+): UsageReport {
+  const buckets = new Map<string, UsageBucket>()
+  const totals = emptyTotals()
+
+  for (const record of records) {
+    const key = bucketKey(record, groupBy)
+    let bucket = buckets.get(key)
+    if (!bucket) {
+      bucket = { key, ...emptyTotals() }
+      buckets.set(key, bucket)
+    }
+    addRecord(bucket, record)
+    addRecord(totals, record)
+  }
+
+  const ordered = [...buckets.values()]
+  if (groupBy === 'day' || groupBy === 'week') {
+    ordered.sort((a, b) => a.key.localeCompare(b.key))
+  } else {
+    ordered.sort((a, b) => b.cost - a.cost || b.total_tokens - a.total_tokens)
+  }
+
+  return {
+    from: range.from ?? null,
+    to: range.to ?? null,
+    group_by: groupBy,
+    totals: roundTotals(totals),
+    buckets: ordered.map(roundTotals),
+  }
+}
+
+export interface UsageDashboard {
+  from: number | null
+  to: number | null
+  totals: UsageTotals
+  /** One entry per UTC day with usage, oldest first; `models` splits the day's cost for stacked charts. */
+  daily: Array<UsageBucket & { models: Record<string, number> }>
+  /** By descending cost. */
+  models: UsageBucket[]
+  /** The most expensive conversations, by descending cost. */
+  conversations: UsageBucket[]
+  phases: UsageBucket[]
+}
+
+/**
+ * Everything a usage dashboard draws, aggregated in one pass over the
+ * records so the client never has to pull raw rows.
+ */
+export function buildUsageDashboard(
+  records: UsageRecord[],
+  options: { from?: number; to?: number; conversationLimit?: number } = {},
+): UsageDashboard {
+  const range = { from: options.from, to: options.to }
+  const byModel = new Map<string, Map<string, number>>()
+  for (const record of records) {
+    const day = bucketKey(record, 'day')
+    const model = bucketKey(record, 'model')
+    const models = byModel.get(day) ?? new Map<string, number>()
+    models.set(model, (models.get(model) ?? 0) + record.costUsd)
+    byModel.set(day, models)
+  }
+
+  const daily = buildUsageReport(records, 'day', range)
+  return {
+    from: daily.from,
+    to: daily.to,
+    totals: daily.totals,
+    daily: daily.buckets.map((bucket) => ({
+      ...bucket,
+      models: Object.fromEntries([...byModel.get(bucket.key) ?? []].map(([model, cost]) => [model, round(cost)])),
+    })),
+    models: buildUsageReport(records, 'model', range).buckets,
+    conversations: buildUsageReport(records, 'conversation', range).buckets.slice(0, options.conversationLimit ?? 10),
+    phases: buildUsageReport(records, 'phase', range).buckets,
+  }
+}
+
+function bucketKey(record: UsageRecord, groupBy: UsageGroupBy): string {
+  switch (groupBy) {
+    case 'day':
+      return new Date(record.createdAt).toISOString().slice(0, 10)
+    case 'week':
+      return weekStart(record.createdAt)
+    case 'model':
+      return `${record.provider}:${record.model}`
+    case 'conversation':
+      return record.sessionId ?? '(none)'
+    case 'phase':
+      return record.phase ?? '(unknown)'
+  }
+}
+
+/** ISO week start (Monday, UTC) as YYYY-MM-DD. */
+function weekStart(timestamp: number): string {
+  const date = new Date(timestamp)
+  const day = (date.getUTCDay() + 6) % 7
+  date.setUTCDate(date.getUTCDate() - day)
+  return date.toISOString().slice(0, 10)
+}
+
+function emptyTotals(): UsageTotals {
+  return {
+    requests: 0,
+    input_tokens: 0,
+    output_tokens: 0,
+    cache_read_tokens: 0,
+    cache_write_tokens: 0,
+    total_tokens: 0,
+    cost: 0,
+    cache_savings: 0,
+    cache_hit_ratio: 0,
+  }
+}
+
+function addRecord(target: UsageTotals, record: UsageRecord): void {
+  target.requests++
+  target.input_tokens += record.inputTokens
+  target.output_tokens += record.outputTokens
+  target.cache_read_tokens += record.cacheReadTokens
+  target.cache_write_tokens += record.cacheWriteTokens
+  target.total_tokens += record.inputTokens + record.outputTokens
+  target.cost += record.costUsd
+  target.cache_savings += record.cacheSavingsUsd
+}
+
+// input_tokens is net of cached reads and writes for every provider, so the
+// prompt is the sum of all three, as in the prompt_cache_hit_ratio metric
+function roundTotals<T extends UsageTotals>(totals: T): T {
+  const prompt = totals.input_tokens + totals.cache_read_tokens + totals.cache_write_tokens
+  return {
+    ...totals,
+    cost: round(totals.cost),
+    cache_savings: round(totals.cache_savings),
+    cache_hit_ratio: prompt > 0 ? Math.round((totals.cache_read_tokens / prompt) * 10_000) / 10_000 : 0,
+  }
+}
+
+function round(value: number): number {
+  return Math.round(value * 1_000_000) / 1_000_000
+}
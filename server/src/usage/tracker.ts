@@ -0,0 +1,83 @@
+import type { Agent } from '../domain/types.js'
+import type { LLMRequest, LLMResponse } from '../providers/types.js'
+import type { SessionRepository, UsagePhase, UsageRepository } from '../repositories/types.js'
+import { logger } from '../lib/logger.js'
+import type { BudgetMonitor } from './budget.js'
+import { attributeToolContext, estimatePromptComposition, estimateToolContext } from './attribution.js'
+import { computeCacheSavings, computeCost, lookupPrice, type PricingRegistry } from './pricing.js'
+
+export interface UsageContext {
+  agent: Agent
+  provider: string
+  model: string
+  usage: LLMResponse['usage']
+  phase?: UsagePhase
+  /** The prompt that was sent; lets the cost be split between the tool results in it. */
+  request?: Pick<LLMRequest, 'messages' | 'tools'>
+}
+
+/** Records token usage and cost for every LLM call the app makes on a session's behalf. */
+export interface UsageSink {
+  record(context: UsageContext): Promise<void>
+}
+
+export class UsageTracker implements UsageSink {
+  private readonly sessionOwners = new Map<string, string | null>()
+
+  constructor(
+    private readonly usage: UsageRepository,
+    private readonly sessions: SessionRepository,
+    private readonly budget?: BudgetMonitor,
+    private readonly pricing?: Pick<PricingRegistry, 'lookup'>,
+  ) {}
+
+  async record(context: UsageContext): Promise<void> {
+    const { agent, provider, model, usage, phase, request } = context
+    try {
+      const inputTokens = usage.input_tokens ?? 0
+      const outputTokens = usage.output_tokens ?? 0
+      const cache = { readTokens: usage.cache_read_input_tokens ?? 0, writeTokens: usage.cache_creation_input_tokens ?? 0 }
+      const price = this.lookupPrice(provider, model)
+      const toolContext = request
+        ? attributeToolContext(
+            estimateToolContext(request),
+            inputTokens + cache.readTokens + cache.writeTokens,
+            computeCost(price, inputTokens, 0, cache),
+          )
+        : null
+      const userId = await this.resolveOwner(agent.sessionId)
+      await this.usage.record({
+        userId,
+        sessionId: agent.sessionId,
+        agentId: agent.id,
+        provider,
+        model,
+        phase: phase ?? null,
+        inputTokens,
+        outputTokens,
+        cacheReadTokens: cache.readTokens,
+        cacheWriteTokens: cache.writeTokens,
+        costUsd: computeCost(price, inputTokens, outputTokens, cache),
+        cacheSavingsUsd: computeCacheSavings(price, cache),
+        toolContext,
+        promptComposition: request ? estimatePromptComposition(request) : null,
+      })
+      if (userId && this.budget) await this.budget.observe(userId, agent)
+    } catch (err) {
+      // Usage accounting must never fail a run
+      logger.warn({ err, agentId: agent.id }, 'Failed to record usage')
+    }
+  }
+
+  private lookupPrice(provider: string, model: string) {
+    return this.pricing ? this.pricing.lookup(provider, model) : lookupPrice(provider, model)
+  }
+
+  private async resolveOwner(sessionId: string): Promise<string | null> {
+    if (this.sessionOwners.has(sessionId)) return this.sessionOwners.get(sessionId) ?? null
+    const session = await this.sessions.getById(sessionId)
+    const owner = session?.userId ?? null
+    this.sessionOwners.set(sessionId, owner)
+    return owner
+  }
+}
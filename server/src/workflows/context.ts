@@ -4,7 +4,14 @@ import type { ProviderRegistry } from '../providers/types.js'
 import type { EventSink } from '../events/types.js'
 import type { AgentRepository, ItemRepository, ToolOutputRepository, PreferenceRepository, WorkflowRunRepository } from '../repositories/types.js'
 import type { AgentDefinitionRegistry } from '../agents/registry.js'
-import type { InterceptHandler, OrchestratorDeps } from '../orchestrator/types.js'
+import type { ControllerPromptSource, InterceptHandler, OrchestratorDeps } from '../orchestrator/types.js'
+import type { AttachmentStore } from '../lib/attachment-store.js'
+import type { InlineOutputBudget } from '../orchestrator/output.js'
+import type { SecretRedactor } from '../lib/secrets.js'
+import type { PrivacyVault } from '../orchestrator/privacy.js'
+import type { ConnectivityMonitor } from '../lib/connectivity.js'
+import type { ResponseVerifier } from '../orchestrator/verifier.js'
+import type { GuardrailEngine } from '../orchestrator/guardrails.js'
 import { EVENT_TYPES } from '../events/types.js'
 import { runAgent } from '../orchestrator/runner.js'
 import { splitModelId } from '../lib/model.js'
@@ -28,6 +35,14 @@ export interface ContextDeps {
   interceptHandlers: Map<string, InterceptHandler>
   sessionFilesRoot: string
   inlineOutputLimitBytes?: number
+  inlineOutputBudget?: InlineOutputBudget
+  redactor?: SecretRedactor
+  privacy?: PrivacyVault
+  connectivity?: ConnectivityMonitor
+  verifier?: ResponseVerifier
+  guardrails?: GuardrailEngine
+  prompts?: ControllerPromptSource
+  attachments?: AttachmentStore
   defaultModel: string
   /** If set, only these tools can be called via ctx.tool(). */
   allowedTools?: string[]
@@ -101,6 +116,7 @@ export function buildWorkflowContext<TInput>(deps: ContextDeps): WorkflowContext
           provider: providerName,
           max_turns: agentDef?.max_turns ?? 10,
           ...(agentDef?.max_output_tokens ? { max_output_tokens: agentDef.max_output_tokens } : {}),
+          ...(agentDef?.step_timeout_ms ? { step_timeout_ms: agentDef.step_timeout_ms } : {}),
           max_tool_calls_per_step: 5,
           tool_execution_timeout_ms: 60_000,
           response_format: 'markdown',
@@ -131,7 +147,15 @@ export function buildWorkflowContext<TInput>(deps: ContextDeps): WorkflowContext
         agentDefinitions: deps.agentDefinitions,
         sessionFilesRoot: deps.sessionFilesRoot,
         inlineOutputLimitBytes: deps.inlineOutputLimitBytes,
+        inlineOutputBudget: deps.inlineOutputBudget,
+        redactor: deps.redactor,
+        privacy: deps.privacy,
+        connectivity: deps.connectivity,
+        verifier: deps.verifier,
+        guardrails: deps.guardrails,
+        prompts: deps.prompts,
         interceptHandlers: deps.interceptHandlers,
+        attachments: deps.attachments,
       }
 
       const result = await runAgent(agent.id, orchestratorDeps, {
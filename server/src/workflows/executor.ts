@@ -5,7 +5,14 @@ import type { ToolExecutor } from '../tools/types.js'
 import type { ProviderRegistry } from '../providers/types.js'
 import type { AgentRepository, ItemRepository, ToolOutputRepository, PreferenceRepository } from '../repositories/types.js'
 import type { AgentDefinitionRegistry } from '../agents/registry.js'
-import type { InterceptHandler } from '../orchestrator/types.js'
+import type { ControllerPromptSource, InterceptHandler } from '../orchestrator/types.js'
+import type { InlineOutputBudget } from '../orchestrator/output.js'
+import type { SecretRedactor } from '../lib/secrets.js'
+import type { PrivacyVault } from '../orchestrator/privacy.js'
+import type { ConnectivityMonitor } from '../lib/connectivity.js'
+import type { ResponseVerifier } from '../orchestrator/verifier.js'
+import type { GuardrailEngine } from '../orchestrator/guardrails.js'
+import type { AttachmentStore } from '../lib/attachment-store.js'
 import { EVENT_TYPES } from '../events/types.js'
 import { buildWorkflowContext } from './context.js'
 import { startRun, completeRun, failRun, cancelRun } from './domain.js'
@@ -24,6 +31,14 @@ export interface ExecutorDeps {
   interceptHandlers: Map<string, InterceptHandler>
   sessionFilesRoot: string
   inlineOutputLimitBytes?: number
+  inlineOutputBudget?: InlineOutputBudget
+  redactor?: SecretRedactor
+  privacy?: PrivacyVault
+  connectivity?: ConnectivityMonitor
+  verifier?: ResponseVerifier
+  guardrails?: GuardrailEngine
+  prompts?: ControllerPromptSource
+  attachments?: AttachmentStore
   defaultModel: string
 }
 
@@ -198,6 +213,14 @@ export class WorkflowExecutor {
         interceptHandlers: this.deps.interceptHandlers,
         sessionFilesRoot: this.deps.sessionFilesRoot,
         inlineOutputLimitBytes: this.deps.inlineOutputLimitBytes,
+        inlineOutputBudget: this.deps.inlineOutputBudget,
+        redactor: this.deps.redactor,
+        privacy: this.deps.privacy,
+        connectivity: this.deps.connectivity,
+        verifier: this.deps.verifier,
+        guardrails: this.deps.guardrails,
+        prompts: this.deps.prompts,
+        attachments: this.deps.attachments,
         defaultModel: this.deps.defaultModel,
         allowedTools: definition.tools,
         triggerAgentId: run.triggerAgentId ?? undefined,
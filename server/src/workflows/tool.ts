@@ -238,6 +238,8 @@ function createWorkflowInterceptHandler(
     output = await materializeTextOutput(output, {
       sessionFilesRoot: deps.sessionFilesRoot,
       inlineLimitBytes: deps.inlineOutputLimitBytes,
+      sessionBudget: deps.inlineOutputBudget,
+      redactor: deps.redactor,
       sessionId: ctx.agent.sessionId,
       agentId: ctx.agent.id,
       callId,
@@ -1,7 +1,41 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::io::Cursor;
+
+use tauri::api::dialog::blocking::FileDialogBuilder;
+
+/// Writes a file the agent created to a location the user picks.
+/// Returns the saved path, or `None` when the user cancels the dialog.
+#[tauri::command]
+async fn save_artifact(file_name: String, contents: Vec<u8>) -> Result<Option<String>, String> {
+    let Some(path) = FileDialogBuilder::new().set_file_name(&file_name).save_file() else {
+        return Ok(None);
+    };
+    std::fs::write(&path, contents).map_err(|err| format!("Failed to save {}: {err}", path.display()))?;
+    Ok(Some(path.display().to_string()))
+}
+
+/// Reads an image from the system clipboard as PNG bytes, for attaching to a
+/// message. Returns `None` when the clipboard holds no image.
+#[tauri::command]
+fn read_clipboard_image() -> Result<Option<Vec<u8>>, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|err| format!("Clipboard unavailable: {err}"))?;
+    let image = match clipboard.get_image() {
+        Ok(image) => image,
+        Err(arboard::Error::ContentNotAvailable) => return Ok(None),
+        Err(err) => return Err(format!("Failed to read clipboard image: {err}")),
+    };
+    let rgba = image::RgbaImage::from_raw(image.width as u32, image.height as u32, image.bytes.into_owned())
+        .ok_or("Clipboard image has an unexpected size")?;
+    let mut png = Vec::new();
+    rgba.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|err| format!("Failed to encode clipboard image: {err}"))?;
+    Ok(Some(png))
+}
+
 fn main() {
     tauri::Builder::default()
+        .invoke_handler(tauri::generate_handler![save_artifact, read_clipboard_image])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
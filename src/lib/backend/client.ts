@@ -397,8 +397,23 @@ class BackendClient {
     return this.http.listTools() as Promise<unknown> as Promise<ToolMetadata[]>;
   }
 
-  // Approvals are push-based via SSE — no polling endpoint
-  async listPendingToolApprovals(): Promise<ToolExecutionProposedPayload[]> { return []; }
+  // Approvals arrive over SSE; this only recovers the ones already waiting
+  async listPendingToolApprovals(conversationId?: string): Promise<ToolExecutionProposedPayload[]> {
+    if (!conversationId) return [];
+    const { approvals } = await this.http.getPendingApprovals(conversationId);
+    return approvals.map((approval) => ({
+      execution_id: approval.callId,
+      approval_id: approval.callId,
+      tool_name: approval.name,
+      args: approval.args,
+      preview: approval.preview ?? undefined,
+      batch: approval.batch ?? undefined,
+      iteration: 0,
+      conversation_id: conversationId,
+      agent_id: approval.agentId,
+      timestamp_ms: Date.now(),
+    }));
+  }
   async resolveToolExecutionApproval(_approvalId: string, _approved: boolean, _scope?: ToolExecutionApprovalScope): Promise<void> {}
   async setToolApprovalOverride(_toolName: string, _requiresApproval: boolean | null): Promise<void> {}
 
@@ -7,6 +7,8 @@
  * processing, etc.) are not covered here.
  */
 
+import type { AgentErrorCode, ApprovalBatch, PromptComposition, ToolPreview } from '$lib/types/server-events';
+
 // ---------------------------------------------------------------------------
 // Types
 // ---------------------------------------------------------------------------
@@ -27,6 +29,7 @@ export interface WaitingFor {
   name: string;
   args?: Record<string, unknown>;
   description?: string;
+  batch?: ApprovalBatch;
 }
 
 export interface Item {
@@ -40,6 +43,8 @@ export interface Item {
   arguments?: string | null;
   output?: string | null;
   isError?: boolean | null;
+  /** Attached documents, images and videos, referenced by the hash returned from `extractPdf`, `extractImageText` or `extractVideo`. */
+  contentBlocks?: Array<{ type: 'document' | 'image' | 'video'; hash: string; name?: string }>;
   turnNumber?: number;
 }
 
@@ -52,9 +57,14 @@ export interface CompletionRequest {
   systemPrompt?: string;
   tools?: string[];
   mcpServerIds?: string[];
+  /** Ingested projects to link to the session for project.search. */
+  projects?: string[];
   stream?: boolean;
   temperature?: number;
   maxTokens?: number;
+  overrideBudget?: boolean;
+  /** Unique per send; a retry with the same key joins the first attempt's run instead of starting another. */
+  idempotencyKey?: string;
 }
 
 export interface CompletionResponse {
@@ -65,6 +75,387 @@ export interface CompletionResponse {
   usage?: { turnCount: number };
   waitingFor?: WaitingFor[];
   error?: string;
+  errorCode?: AgentErrorCode;
+  /** The request repeated an idempotency key; this is the earlier send's run. */
+  duplicate?: boolean;
+}
+
+export type UsageGroupBy = 'day' | 'week' | 'model' | 'conversation' | 'phase';
+
+export interface UsageTotals {
+  requests: number;
+  input_tokens: number;
+  output_tokens: number;
+  cache_read_tokens: number;
+  cache_write_tokens: number;
+  total_tokens: number;
+  cost: number;
+  /** Saved by prompt caching; already reflected in `cost`. */
+  cache_savings: number;
+  /** Share of prompt tokens served from the provider cache. */
+  cache_hit_ratio: number;
+}
+
+/** Currency the amounts of a usage report are in; stored costs are always USD. */
+export interface DisplayCurrency {
+  code: string;
+  /** Units of `code` per US dollar. */
+  rate: number;
+  locale: string | null;
+}
+
+export interface CurrencySettings extends DisplayCurrency {
+  source: 'manual' | 'remote';
+  rateUpdatedAt: number | null;
+}
+
+export interface UsageReport {
+  from: number | null;
+  to: number | null;
+  group_by: UsageGroupBy;
+  totals: UsageTotals;
+  buckets: Array<UsageTotals & { key: string }>;
+  currency: DisplayCurrency;
+}
+
+export interface UsageDashboard {
+  from: number | null;
+  to: number | null;
+  totals: UsageTotals;
+  /** Oldest first; `models` is that day's cost per model. */
+  daily: Array<UsageTotals & { key: string; models: Record<string, number> }>;
+  models: Array<UsageTotals & { key: string }>;
+  conversations: Array<UsageTotals & { key: string; title: string | null }>;
+  phases: Array<UsageTotals & { key: string }>;
+  currency: DisplayCurrency;
+}
+
+export interface ToolCostRow {
+  tool: string;
+  /** Tool name up to its last dot, e.g. `files` or `mcp.github_1a2b3c4d`. */
+  integration: string;
+  calls: number;
+  sessions: number;
+  tokens: number;
+  cost: number;
+}
+
+export interface ToolCostReport {
+  from: number | null;
+  to: number | null;
+  totalCost: number;
+  tools: ToolCostRow[];
+  integrations: Array<Omit<ToolCostRow, 'tool' | 'integration'> & { integration: string; tools: string[] }>;
+  currency: DisplayCurrency;
+}
+
+export interface RunCostEstimate {
+  sessionId: string;
+  model: string;
+  provider: string;
+  /** Estimated prompt tokens of the next controller turn. */
+  prompt: { system: number; tools: number; history: number; total: number };
+  maxOutputTokens: number;
+  maxTurns: number;
+  /** USD per million tokens; null when the model has no known price. */
+  price: { input: number; output: number; cacheRead?: number; cacheWrite?: number } | null;
+  perTurn: { promptCostUsd: number | null; maxCostUsd: number | null };
+}
+
+export interface PromptCompositionCall {
+  createdAt: number;
+  agentId: string;
+  phase: string;
+  model: string;
+  /** Prompt tokens the provider reported, cached ones included. */
+  promptTokens: number;
+  /** Estimated breakdown of that prompt. */
+  composition: PromptComposition;
+}
+
+export interface BudgetSettings {
+  weeklyUsd: number | null;
+  monthlyUsd: number | null;
+  warnAt: number[];
+  blockWhenExceeded: boolean;
+  /** Hard per-provider or per-model caps, enforced before every LLM call. */
+  caps: SpendCap[];
+}
+
+export interface SpendCap {
+  /** A provider (`anthropic`) or a full model id (`anthropic:claude-opus-4-1`). */
+  scope: string;
+  period: 'daily' | 'weekly' | 'monthly';
+  limitUsd: number;
+  /** Model to switch to once the cap is hit; null refuses further calls. */
+  fallbackModel: string | null;
+}
+
+export interface SpendCapStatus extends SpendCap {
+  periodStart: number;
+  spentUsd: number;
+  exceeded: boolean;
+}
+
+export interface BudgetStatus {
+  period: 'weekly' | 'monthly';
+  periodStart: number;
+  limitUsd: number;
+  spentUsd: number;
+  fraction: number;
+  exceeded: boolean;
+}
+
+export interface ModelPriceEntry {
+  modelId: string;
+  /** USD per million input tokens. */
+  input: number;
+  /** USD per million output tokens. */
+  output: number;
+  /** USD per million cached prompt tokens; null bills them as input. */
+  cacheRead: number | null;
+  /** USD per million cache-write tokens; null bills them as input. */
+  cacheWrite: number | null;
+  source: 'bundled' | 'remote' | 'user';
+  updatedAt: number;
+}
+
+/** USD per million tokens for every model served by one provider. */
+export interface BackendPrice {
+  input: number;
+  output: number;
+  cacheRead?: number;
+  cacheWrite?: number;
+}
+
+export interface PriceRefreshResult {
+  url: string;
+  updated: number;
+  added: number;
+  skippedUserEdits: number;
+  prices: ModelPriceEntry[];
+}
+
+export type AuditEntryKind = 'tool_call' | 'tool_result' | 'approval' | 'llm_call';
+
+interface AuditEntryBase {
+  at: number;
+  userId: string | null;
+  sessionId: string;
+  agentId: string;
+}
+
+export type AuditEntry = AuditEntryBase & (
+  | { kind: 'tool_call'; callId: string; tool: string; args: Record<string, unknown> }
+  | {
+    kind: 'tool_result';
+    callId: string;
+    tool: string;
+    success: boolean;
+    durationMs: number;
+    errorCode: AgentErrorCode | null;
+    outputBytes: number;
+    output: string;
+  }
+  | { kind: 'approval'; callId: string; tool: string; decision: 'approved' | 'denied' | 'expired' }
+  | {
+    kind: 'llm_call';
+    provider: string;
+    model: string;
+    turnNumber: number;
+    stream: boolean;
+    durationMs: number;
+    inputTokens: number | null;
+    outputTokens: number | null;
+    error: string | null;
+  }
+);
+
+export interface MetricSeries {
+  labels: Record<string, string>;
+  value?: number;
+  count?: number;
+  sum?: number;
+  buckets?: Array<{ le: number; count: number }>;
+  p50?: number | null;
+  p95?: number | null;
+}
+
+export interface MetricsSnapshot {
+  collectedAt: number;
+  uptimeSeconds: number;
+  metrics: Array<{ name: string; help: string; type: 'counter' | 'gauge' | 'histogram'; series: MetricSeries[] }>;
+}
+
+export interface InspectorUsage {
+  requests: number;
+  inputTokens: number;
+  outputTokens: number;
+  costUsd: number;
+}
+
+export interface InspectorSessionSummary {
+  id: string;
+  title: string | null;
+  status: string;
+  deletedAt: number | null;
+  rootAgentId: string | null;
+  rootPhase: string | null;
+  agentCount: number;
+  turnCount: number;
+  usage: InspectorUsage;
+  createdAt: number;
+  updatedAt: number;
+}
+
+export interface InspectorStep {
+  callId: string | null;
+  name: string | null;
+  arguments: unknown;
+  result: string | null;
+  isError: boolean | null;
+  durationMs: number | null;
+  childAgentId: string | null;
+}
+
+export interface InspectorAgent {
+  id: string;
+  parentId: string | null;
+  sourceCallId: string | null;
+  depth: number;
+  task: string;
+  model: string;
+  provider: string;
+  phase: string;
+  plan: {
+    goal: string;
+    steps: Array<{ id: string; description: string; status: string; action?: string; result?: string }>;
+  } | null;
+  waitingFor: WaitingFor[];
+  result: string | null;
+  error: string | null;
+  turnCount: number;
+  usage: InspectorUsage;
+  turns: Array<{
+    turn: number;
+    input: string | null;
+    reasoning: string[];
+    steps: InspectorStep[];
+    response: string | null;
+  }>;
+  createdAt: number;
+  completedAt: number | null;
+}
+
+export interface InspectorSession {
+  session: Session;
+  exchanges: Array<{ itemId: string; content: string | null; createdAt: number; fromTurn: number; toTurn: number }>;
+  agents: InspectorAgent[];
+  usage: InspectorUsage;
+}
+
+export interface RetentionPolicy {
+  sessionsMonths: number | null;
+  toolOutputsDays: number | null;
+  usageDays: number | null;
+}
+
+export interface RetentionPreview {
+  policy: RetentionPolicy;
+  cutoffs: { sessionsBefore: number | null; toolOutputsBefore: number | null; usageBefore: number | null };
+  sessions: {
+    count: number;
+    items: number;
+    examples: Array<{ id: string; title: string | null; lastActivityAt: number; itemCount: number }>;
+  };
+  toolOutputs: number;
+  usageRecords: number;
+}
+
+export interface DbMaintenanceResult {
+  database: {
+    dialect: 'sqlite' | 'postgres';
+    sizeBytesBefore: number;
+    sizeBytesAfter: number;
+    reclaimedBytes: number;
+    reindexed: string[];
+    integrity: string | null;
+  };
+  artifacts: Array<{ name: 'sessions' | 'attachments' | 'tasks' | 'notes' | 'traces'; path: string; bytes: number; files: number }>;
+  totalBytes: number;
+  health: { status: 'ok' | 'degraded'; warnings: string[] };
+  durationMs: number;
+  completedAt: number;
+}
+
+export interface HealthReport {
+  status: 'ok' | 'degraded';
+  checks: Array<{
+    kind: 'provider' | 'mail' | 'calendar' | 'mcp' | 'storage';
+    name: string;
+    status: 'ok' | 'failed' | 'skipped';
+    detail: string | null;
+    durationMs: number;
+  }>;
+  checkedAt: number;
+}
+
+export interface OrphanReport {
+  database: { toolOutputs: number; agents: number; usageRecords: number; messageRevisions: number };
+  sessionDirs: string[];
+  traceDirs: string[];
+  attachments: { count: number; bytes: number };
+  removed: boolean;
+}
+
+export interface ConversationStats {
+  sessionId: string;
+  messages: { system: number; user: number; assistant: number };
+  tools: Array<{ name: string; calls: number; errors: number }>;
+  agentCount: number;
+  agentTurns: number;
+  averageResponseMs: number | null;
+  costUsd: number;
+}
+
+export interface PendingApproval {
+  agentId: string;
+  parentId: string | null;
+  depth: number;
+  callId: string;
+  name: string;
+  args: Record<string, unknown>;
+  description: string | null;
+  preview: ToolPreview | null;
+  batch: ApprovalBatch | null;
+}
+
+export interface ApprovalTimeoutSetting {
+  timeoutMs: number;
+  source: 'conversation' | 'default';
+}
+
+export interface WindowClaims {
+  windowId: string;
+  sessionIds: string[];
+}
+
+export interface ConversationClaim {
+  sessionId: string;
+  windowId: string;
+  previousWindowId: string | null;
+}
+
+export interface MessageRevision {
+  id: string;
+  itemId: string;
+  sessionId: string;
+  revision: number;
+  role: string | null;
+  content: string | null;
+  contentBlocks: unknown[] | null;
+  reason: 'edit' | 'regenerate' | 'restore';
+  createdAt: number;
 }
 
 export interface AudioTranscriptionResponse {
@@ -72,6 +463,85 @@ export interface AudioTranscriptionResponse {
   usage?: unknown;
 }
 
+export interface TranscriptionJob {
+  id: string;
+  status: 'running' | 'completed' | 'failed';
+  fileName: string | null;
+  /** 0–100; stays at 0 for backends that do not report progress. */
+  progress: number;
+  text: string | null;
+  error: string | null;
+  createdAt: number;
+  completedAt: number | null;
+}
+
+export interface PdfExtraction {
+  /** Reference for a `document` content block on the next user message. */
+  hash: string;
+  bytes: number;
+  pages: number;
+  /** `ocr` when the PDF had no text layer and its page images were read instead. */
+  source: 'text' | 'ocr';
+  chunks: string[];
+}
+
+export interface ImageExtraction {
+  /** Reference for an `image` content block on the next user message. */
+  hash: string;
+  bytes: number;
+  mediaType: string;
+  /** Text read from the image; empty when it contains none. */
+  chunks: string[];
+}
+
+export interface VideoExtraction {
+  /** Reference for a `video` content block on the next user message. */
+  hash: string;
+  bytes: number;
+  mediaType: string;
+  durationSeconds: number | null;
+  /** Sampled frames, sent to vision models with the message; `at` is in seconds. */
+  frames: Array<{ hash: string; bytes: number; at: number | null }>;
+  /** Transcript of the audio track; empty when there is none. */
+  chunks: string[];
+}
+
+export interface AgentArtifact {
+  /** Managed path the agent uses for the file, e.g. `artifact://files/report.csv`. */
+  ref: string;
+  name: string;
+  mediaType: string;
+  bytes: number;
+  createdAt: number;
+}
+
+export interface LibraryUpload {
+  /** Content hash; pass it to `attachFromLibrary` to reuse the file. */
+  hash: string;
+  name: string;
+  mediaType: string;
+  kind: 'pdf' | 'text' | 'office' | 'image' | 'video';
+  bytes: number;
+  createdAt: number;
+  lastUsedAt: number;
+}
+
+export interface LibraryAttachment extends LibraryUpload {
+  /** Messages in your conversations that include the file. */
+  references: number;
+}
+
+export interface ProjectSummary {
+  name: string;
+  /** Folder the project was ingested from. */
+  root: string;
+  files: number;
+  bytes: number;
+  /** True when the folder had more files than the server indexes. */
+  truncated: boolean;
+  indexedAt: number;
+}
+
 export interface AgentStatusResponse {
   id: string;
   sessionId: string;
@@ -82,6 +552,7 @@ export interface AgentStatusResponse {
   waitingFor?: WaitingFor[];
   result?: string | null;
   error?: string | null;
+  errorCode?: AgentErrorCode | null;
   turnCount: number;
 }
 
@@ -134,6 +605,7 @@ export interface Session {
   title: string | null;
   summary: string | null;
   status: 'active' | 'archived';
+  deletedAt?: number | null;
   createdAt: number;
   updatedAt: number;
   items?: SessionItem[];
@@ -141,12 +613,56 @@ export interface Session {
   workflowRuns?: WorkflowRunResponse[];
 }
 
+export type ToolCategory = 'read_only' | 'mutating' | 'destructive';
+
 export interface ToolMetadata {
   name: string;
   description?: string;
+  category?: ToolCategory;
   [key: string]: unknown;
 }
 
+/** Approval default per tool category; a category left out uses the built-in one. */
+export type CategoryApprovalDefaults = Partial<Record<ToolCategory, 'ask' | 'allow'>>;
+
+export interface ApprovalCategorySettings {
+  defaults: CategoryApprovalDefaults;
+  builtIn: CategoryApprovalDefaults;
+}
+
+export type ApprovalRuleAction = 'allow' | 'deny' | 'ask';
+
+export interface ApprovalRule {
+  id: string;
+  /** Tool name; `*` matches any run of characters. */
+  tool: string;
+  when: Array<{ arg: string; op: 'equals' | 'glob' | 'regex' | 'under' | 'exists'; value?: string; not?: boolean }>;
+  action: ApprovalRuleAction;
+  description: string | null;
+}
+
+export type ApprovalRuleInput = Omit<ApprovalRule, 'id' | 'when' | 'description'> &
+  Partial<Pick<ApprovalRule, 'when' | 'description'>>;
+
+export type ApprovalOutcome = 'approved' | 'denied' | 'auto_approved' | 'auto_denied' | 'expired' | 'cancelled';
+
+export interface ApprovalRecord {
+  id: string;
+  userId: string | null;
+  sessionId: string;
+  agentId: string;
+  callId: string;
+  toolName: string;
+  args: Record<string, unknown>;
+  amendedArgs: Record<string, unknown> | null;
+  outcome: ApprovalOutcome;
+  scope: 'once' | 'conversation' | 'always' | null;
+  ruleId: string | null;
+  requestedAt: number;
+  decidedAt: number;
+  latencyMs: number;
+}
+
 export interface McpTool {
   id: string;
   serverId: string;
@@ -216,6 +732,39 @@ export interface ModelInfo {
   [key: string]: unknown;
 }
 
+/** A model a provider's listing reported; null capabilities mean the listing does not say. */
+export interface CatalogModel {
+  provider: string;
+  modelId: string;
+  displayName: string | null;
+  contextWindow: number | null;
+  maxOutputTokens: number | null;
+  vision: boolean | null;
+  structuredOutputs: boolean | null;
+  tools: boolean | null;
+  fetchedAt: number;
+}
+
+export interface ModelCatalog {
+  models: CatalogModel[];
+  /** Providers whose listing failed on refresh; their cached models are kept. */
+  errors?: Record<string, string>;
+}
+
+/** A command palette entry; `parameters` is a JSON Schema object for its arguments. */
+export interface PaletteAction {
+  id: string;
+  label: string;
+  description?: string;
+  /** Suggested default hotkey, e.g. `Mod+Shift+N`. */
+  shortcut?: string;
+  parameters: {
+    type: 'object';
+    properties: Record<string, { type: string; description?: string; enum?: string[] }>;
+    required?: string[];
+  };
+}
+
 export interface SSEEvent {
   event: string;
   data: Record<string, unknown>;
@@ -416,6 +965,141 @@ export class HttpBackendClient {
     return await res.json() as AudioTranscriptionResponse;
   }
 
+  /**
+   * Start transcribing in the background. Progress arrives as transcription:* events
+   * (scoped to `sessionId` when given) or via `getTranscriptionJob`.
+   */
+  async startTranscriptionJob(
+    file: Blob,
+    fileName: string,
+    sessionId?: string,
+    signal?: AbortSignal,
+  ): Promise<TranscriptionJob> {
+    const form = new FormData();
+    form.append('file', file, fileName);
+    if (sessionId) form.append('sessionId', sessionId);
+    const headers = this.headers();
+    delete headers['Content-Type'];
+
+    let res: Response;
+    try {
+      res = await fetch(`${this.serverUrl}/api/audio/transcriptions/jobs`, {
+        method: 'POST',
+        headers,
+        body: form,
+        signal,
+      });
+    } catch (err) {
+      throw new HttpBackendError(
+        `Network error: ${err instanceof Error ? err.message : String(err)}`,
+        0,
+      );
+    }
+
+    if (!res.ok) {
+      const errorBody = await res.json().catch(() => null) as { error?: string } | null;
+      throw new HttpBackendError(errorBody?.error ?? `HTTP ${res.status}`, res.status, errorBody);
+    }
+    return await res.json() as TranscriptionJob;
+  }
+
+  async getTranscriptionJob(id: string, signal?: AbortSignal): Promise<TranscriptionJob> {
+    return this.request('GET', `/api/audio/transcriptions/jobs/${encodeURIComponent(id)}`, undefined, signal);
+  }
+
+  /** Upload a PDF; the server keeps it with its extracted text and returns the chunks. */
+  async extractPdf(file: Blob, fileName: string, signal?: AbortSignal): Promise<PdfExtraction> {
+    return this.uploadAttachment<PdfExtraction>('/api/attachments/pdf', file, fileName, signal);
+  }
+
+  /** Upload a text file; the server chunks it so large files can be searched instead of inlined. */
+  async extractTextFile(file: Blob, fileName: string, signal?: AbortSignal): Promise<PdfExtraction> {
+    return this.uploadAttachment<PdfExtraction>('/api/attachments/text', file, fileName, signal);
+  }
+
+  /** Upload a Word, Excel or PowerPoint file; the server converts it to Markdown text and tables. */
+  async extractOfficeDocument(file: Blob, fileName: string, signal?: AbortSignal): Promise<PdfExtraction> {
+    return this.uploadAttachment<PdfExtraction>('/api/attachments/office', file, fileName, signal);
+  }
+
+  /** Upload an image; the server keeps it with the text read by its OCR model. */
+  async extractImageText(file: Blob, fileName: string, signal?: AbortSignal): Promise<ImageExtraction> {
+    return this.uploadAttachment<ImageExtraction>('/api/attachments/image', file, fileName, signal);
+  }
+
+  /** Upload a video; the server samples frames for vision models and transcribes its audio. */
+  async extractVideo(file: Blob, fileName: string, signal?: AbortSignal): Promise<VideoExtraction> {
+    return this.uploadAttachment<VideoExtraction>('/api/attachments/video', file, fileName, signal);
+  }
+
+  /** Every file previously uploaded, one entry per distinct file. */
+  async listAttachmentLibrary(signal?: AbortSignal): Promise<LibraryAttachment[]> {
+    const result = await this.request<{ attachments: LibraryAttachment[] }>('GET', '/api/attachments/library', undefined, signal);
+    return result.attachments;
+  }
+
+  /** Fetch a library file's extraction so it can go on a new message without re-uploading. */
+  async attachFromLibrary(
+    hash: string,
+    signal?: AbortSignal,
+  ): Promise<{ upload: LibraryUpload; extraction: PdfExtraction | ImageExtraction | VideoExtraction }> {
+    return this.request('POST', `/api/attachments/library/${encodeURIComponent(hash)}/attach`, undefined, signal);
+  }
+
+  /** `purged` is true when nothing else referenced the file and its payload was deleted. */
+  async deleteLibraryAttachment(hash: string, signal?: AbortSignal): Promise<{ ok: boolean; purged: boolean }> {
+    return this.request('DELETE', `/api/attachments/library/${encodeURIComponent(hash)}`, undefined, signal);
+  }
+
+  /**
+   * Index a local folder as a project. With `sessionId` it is linked to that
+   * conversation right away; `linked` lists every project the conversation can search.
+   */
+  async ingestProject(
+    path: string,
+    options: { name?: string; sessionId?: string } = {},
+    signal?: AbortSignal,
+  ): Promise<{ project: ProjectSummary; linked: string[] }> {
+    return this.request('POST', '/api/projects', { ...options, path }, signal);
+  }
+
+  async listProjects(signal?: AbortSignal): Promise<ProjectSummary[]> {
+    const result = await this.request<{ projects: ProjectSummary[] }>('GET', '/api/projects', undefined, signal);
+    return result.projects;
+  }
+
+  async deleteProject(name: string, signal?: AbortSignal): Promise<{ ok: boolean }> {
+    return this.request('DELETE', `/api/projects/${encodeURIComponent(name)}`, undefined, signal);
+  }
+
+  private async uploadAttachment<T>(path: string, file: Blob, fileName: string, signal?: AbortSignal): Promise<T> {
+    const form = new FormData();
+    form.append('file', file, fileName);
+    const headers = this.headers();
+    delete headers['Content-Type'];
+
+    let res: Response;
+    try {
+      res = await fetch(`${this.serverUrl}${path}`, {
+        method: 'POST',
+        headers,
+        body: form,
+        signal,
+      });
+    } catch (err) {
+      throw new HttpBackendError(
+        `Network error: ${err instanceof Error ? err.message : String(err)}`,
+        0,
+      );
+    }
+
+    if (!res.ok) {
+      const errorBody = await res.json().catch(() => null) as { error?: string } | null;
+      throw new HttpBackendError(errorBody?.error ?? `HTTP ${res.status}`, res.status, errorBody);
+    }
+    return await res.json() as T;
+  }
+
   /**
    * Non-streaming completion. Returns when the agent finishes or is waiting.
    */
@@ -520,13 +1204,34 @@ export class HttpBackendClient {
   }
 
   /**
-   * Cancel a running or waiting agent.
+   * Approve or deny several of an agent's pending tool executions at once.
+   * `amendedArgs` (by callId) replaces the proposed args of approved calls.
    */
-  async cancelAgent(
+  async approveToolExecutions(
     agentId: string,
+    callIds: string[],
+    decision: 'approved' | 'denied',
+    scope?: 'once' | 'conversation' | 'always',
     signal?: AbortSignal,
-  ): Promise<{ id: string; status: string }> {
-    return this.request<{ id: string; status: string }>(
+    amendedArgs?: Record<string, Record<string, unknown>>,
+    feedback?: string,
+  ): Promise<CompletionResponse> {
+    return this.request<CompletionResponse>(
+      'POST',
+      `/api/chat/agents/${agentId}/approve`,
+      { callIds, decision, scope, amendedArgs, feedback },
+      signal,
+    );
+  }
+
+  /**
+   * Cancel a running or waiting agent.
+   */
+  async cancelAgent(
+    agentId: string,
+    signal?: AbortSignal,
+  ): Promise<{ id: string; status: string }> {
+    return this.request<{ id: string; status: string }>(
       'POST',
       `/api/chat/agents/${agentId}/cancel`,
       undefined,
@@ -549,16 +1254,45 @@ export class HttpBackendClient {
     );
   }
 
+  /**
+   * Fetch buffered events for the agent's session emitted at or after `since`,
+   * shaped like the SSE stream so a reloaded view can replay them.
+   */
+  async replayEvents(
+    agentId: string,
+    since = 0,
+    signal?: AbortSignal,
+  ): Promise<SSEEvent[]> {
+    const events = await this.request<Array<Record<string, unknown> & { type: string }>>(
+      'GET',
+      `/api/chat/agents/${agentId}/events/replay?since=${since}`,
+      undefined,
+      signal,
+    );
+    return events.map((data) => ({ event: data.type, data }));
+  }
+
   /**
    * Subscribe to real-time events for an agent via SSE.
    * The returned async iterable yields events until the agent completes/fails
-   * or the signal is aborted.
+   * or the signal is aborted. Pass `types` to receive only those event types,
+   * and `windowId` to stop receiving them while another window owns the conversation.
    */
   async *subscribeToEvents(
     agentId: string,
     signal?: AbortSignal,
+    types?: string[],
+    windowId?: string,
   ): AsyncIterable<SSEEvent> {
-    const url = `${this.serverUrl}/api/chat/agents/${agentId}/events`;
+    const params = new URLSearchParams();
+    if (types && types.length > 0) params.set('types', types.join(','));
+    if (windowId) params.set('window', windowId);
+    const query = params.toString();
+    yield* this.openEventStream(`/api/chat/agents/${agentId}/events${query ? `?${query}` : ''}`, signal);
+  }
+
+  private async *openEventStream(path: string, signal?: AbortSignal): AsyncIterable<SSEEvent> {
+    const url = `${this.serverUrl}${path}`;
     let res: Response;
 
     try {
@@ -598,6 +1332,54 @@ export class HttpBackendClient {
     yield* this.parseSSEStream(res.body);
   }
 
+  // ========================================================================
+  // Windows
+  // ========================================================================
+
+  async listWindowClaims(windowId: string, signal?: AbortSignal): Promise<WindowClaims> {
+    return this.request<WindowClaims>('GET', `/api/windows/${windowId}/claims`, undefined, signal);
+  }
+
+  /** Route the conversation's events to this window only, e.g. a detached agent monitor. */
+  async claimConversation(
+    windowId: string,
+    sessionId: string,
+    signal?: AbortSignal,
+  ): Promise<ConversationClaim> {
+    return this.request<ConversationClaim>(
+      'POST',
+      `/api/windows/${windowId}/claims`,
+      { sessionId },
+      signal,
+    );
+  }
+
+  async releaseConversation(
+    windowId: string,
+    sessionId: string,
+    signal?: AbortSignal,
+  ): Promise<{ sessionId: string; released: boolean }> {
+    return this.request<{ sessionId: string; released: boolean }>(
+      'DELETE',
+      `/api/windows/${windowId}/claims/${sessionId}`,
+      undefined,
+      signal,
+    );
+  }
+
+  /**
+   * Every event routed to this window across the user's conversations.
+   * Closing the stream (aborting the signal) releases the window's claims.
+   */
+  async *subscribeToWindowEvents(
+    windowId: string,
+    signal?: AbortSignal,
+    types?: string[],
+  ): AsyncIterable<SSEEvent> {
+    const query = types && types.length > 0 ? `?types=${encodeURIComponent(types.join(','))}` : '';
+    yield* this.openEventStream(`/api/windows/${windowId}/events${query}`, signal);
+  }
+
   // ========================================================================
   // Sessions
   // ========================================================================
@@ -615,6 +1397,59 @@ export class HttpBackendClient {
     );
   }
 
+  /** Files the agent created with `artifacts.write`, newest first. */
+  async listSessionArtifacts(id: string, signal?: AbortSignal): Promise<AgentArtifact[]> {
+    const result = await this.request<{ artifacts: AgentArtifact[] }>('GET', `/api/sessions/${id}/artifacts`, undefined, signal);
+    return result.artifacts;
+  }
+
+  async downloadSessionArtifact(id: string, name: string, signal?: AbortSignal): Promise<Blob> {
+    let res: Response;
+    try {
+      res = await fetch(`${this.serverUrl}/api/sessions/${id}/artifacts/${encodeURIComponent(name)}`, {
+        headers: this.headers(),
+        signal,
+      });
+    } catch (err) {
+      throw new HttpBackendError(
+        `Network error: ${err instanceof Error ? err.message : String(err)}`,
+        0,
+      );
+    }
+
+    if (!res.ok) {
+      const errorBody = await res.json().catch(() => null) as { error?: string } | null;
+      throw new HttpBackendError(errorBody?.error ?? `HTTP ${res.status}`, res.status, errorBody);
+    }
+    return await res.blob();
+  }
+
+  /**
+   * A shareable record of the run: plan, tool calls, approvals, final response
+   * and cost; with `conversation`, the chat itself as one self-contained HTML
+   * page, tool calls collapsed and images inline.
+   */
+  async downloadSessionReport(id: string, format: 'markdown' | 'html' | 'conversation', signal?: AbortSignal): Promise<Blob> {
+    let res: Response;
+    try {
+      res = await fetch(`${this.serverUrl}/api/sessions/${id}/report?format=${format}`, {
+        headers: this.headers(),
+        signal,
+      });
+    } catch (err) {
+      throw new HttpBackendError(
+        `Network error: ${err instanceof Error ? err.message : String(err)}`,
+        0,
+      );
+    }
+
+    if (!res.ok) {
+      const errorBody = await res.json().catch(() => null) as { error?: string } | null;
+      throw new HttpBackendError(errorBody?.error ?? `HTTP ${res.status}`, res.status, errorBody);
+    }
+    return await res.blob();
+  }
+
   async updateSession(
     id: string,
     updates: { title?: string; status?: 'active' | 'archived' },
@@ -640,6 +1475,155 @@ export class HttpBackendClient {
     );
   }
 
+  async listTrash(
+    signal?: AbortSignal,
+  ): Promise<{ sessions: Session[]; retentionDays: number }> {
+    return this.request<{ sessions: Session[]; retentionDays: number }>(
+      'GET',
+      '/api/sessions/trash',
+      undefined,
+      signal,
+    );
+  }
+
+  async restoreSession(id: string, signal?: AbortSignal): Promise<Session> {
+    return this.request<Session>(
+      'POST',
+      `/api/sessions/${id}/restore`,
+      undefined,
+      signal,
+    );
+  }
+
+  async getConversationStats(sessionId: string, signal?: AbortSignal): Promise<ConversationStats> {
+    return this.request<ConversationStats>('GET', `/api/sessions/${sessionId}/stats`, undefined, signal);
+  }
+
+  async getPendingApprovals(sessionId: string, signal?: AbortSignal): Promise<{ approvals: PendingApproval[] }> {
+    return this.request('GET', `/api/sessions/${sessionId}/approvals`, undefined, signal);
+  }
+
+  async getApprovalTimeout(sessionId: string, signal?: AbortSignal): Promise<ApprovalTimeoutSetting> {
+    return this.request('GET', `/api/sessions/${sessionId}/approval-timeout`, undefined, signal);
+  }
+
+  /** `null` drops the conversation's override; 0 waits indefinitely. */
+  async setApprovalTimeout(
+    sessionId: string,
+    timeoutMs: number | null,
+    signal?: AbortSignal,
+  ): Promise<ApprovalTimeoutSetting> {
+    return this.request('PUT', `/api/sessions/${sessionId}/approval-timeout`, { timeoutMs }, signal);
+  }
+
+  async editMessage(
+    sessionId: string,
+    itemId: string,
+    content: string,
+    signal?: AbortSignal,
+  ): Promise<{ item: Item; revision: MessageRevision }> {
+    return this.request('PATCH', `/api/sessions/${sessionId}/messages/${itemId}`, { content }, signal);
+  }
+
+  async listMessageRevisions(
+    sessionId: string,
+    itemId: string,
+    signal?: AbortSignal,
+  ): Promise<MessageRevision[]> {
+    return this.request<MessageRevision[]>(
+      'GET',
+      `/api/sessions/${sessionId}/messages/${itemId}/revisions`,
+      undefined,
+      signal,
+    );
+  }
+
+  async restoreMessageRevision(
+    sessionId: string,
+    itemId: string,
+    revisionId: string,
+    signal?: AbortSignal,
+  ): Promise<{ item: Item; revision: MessageRevision }> {
+    return this.request(
+      'POST',
+      `/api/sessions/${sessionId}/messages/${itemId}/revisions/${revisionId}/restore`,
+      undefined,
+      signal,
+    );
+  }
+
+  async emptyTrash(
+    signal?: AbortSignal,
+  ): Promise<{ ok: boolean; purged: number }> {
+    return this.request<{ ok: boolean; purged: number }>(
+      'DELETE',
+      '/api/sessions/trash',
+      undefined,
+      signal,
+    );
+  }
+
+  // ========================================================================
+  // Inspector
+  // ========================================================================
+
+  async listInspectorSessions(signal?: AbortSignal): Promise<InspectorSessionSummary[]> {
+    return this.request<InspectorSessionSummary[]>('GET', '/api/inspector/sessions', undefined, signal);
+  }
+
+  async getInspectorSession(id: string, signal?: AbortSignal): Promise<InspectorSession> {
+    return this.request<InspectorSession>('GET', `/api/inspector/sessions/${id}`, undefined, signal);
+  }
+
+  async listSessionTraces(
+    id: string,
+    signal?: AbortSignal,
+  ): Promise<{ enabled: boolean; traces: Array<{ file: string; bytes: number; createdAt: number }> }> {
+    return this.request('GET', `/api/inspector/sessions/${id}/traces`, undefined, signal);
+  }
+
+  async getSessionTrace(id: string, file: string, signal?: AbortSignal): Promise<Record<string, unknown>> {
+    return this.request('GET', `/api/inspector/sessions/${id}/traces/${encodeURIComponent(file)}`, undefined, signal);
+  }
+
+  // ========================================================================
+  // Retention
+  // ========================================================================
+
+  async getRetention(signal?: AbortSignal): Promise<RetentionPreview> {
+    return this.request<RetentionPreview>('GET', '/api/retention', undefined, signal);
+  }
+
+  async setRetention(policy: Partial<RetentionPolicy>, signal?: AbortSignal): Promise<RetentionPreview> {
+    return this.request<RetentionPreview>('PUT', '/api/retention', policy, signal);
+  }
+
+  async runRetention(
+    signal?: AbortSignal,
+  ): Promise<{ sessions: number; toolOutputs: number; usageRecords: number }> {
+    return this.request('POST', '/api/retention/run', undefined, signal);
+  }
+
+  // ========================================================================
+  // Maintenance
+  // ========================================================================
+
+  async runDbMaintenance(signal?: AbortSignal): Promise<DbMaintenanceResult> {
+    return this.request<DbMaintenanceResult>('POST', '/api/maintenance/db', undefined, signal);
+  }
+
+  async checkHealth(signal?: AbortSignal): Promise<HealthReport> {
+    return this.request<HealthReport>('GET', '/api/maintenance/health', undefined, signal);
+  }
+
+  async findOrphans(signal?: AbortSignal): Promise<OrphanReport> {
+    return this.request<OrphanReport>('GET', '/api/maintenance/orphans', undefined, signal);
+  }
+
+  async cleanupOrphans(signal?: AbortSignal): Promise<OrphanReport> {
+    return this.request<OrphanReport>('POST', '/api/maintenance/orphans/cleanup', undefined, signal);
+  }
+
   // ========================================================================
   // Models
   // ========================================================================
@@ -648,6 +1632,17 @@ export class HttpBackendClient {
     return this.request<ModelInfo[]>('GET', '/api/models', undefined, signal);
   }
 
+  async getModelCatalog(
+    options: { provider?: string; refresh?: boolean } = {},
+    signal?: AbortSignal,
+  ): Promise<ModelCatalog> {
+    const params = new URLSearchParams();
+    if (options.provider) params.set('provider', options.provider);
+    if (options.refresh) params.set('refresh', '1');
+    const query = params.toString();
+    return this.request<ModelCatalog>('GET', `/api/models/catalog${query ? `?${query}` : ''}`, undefined, signal);
+  }
+
   async addModel(
     provider: string,
     modelName: string,
@@ -673,6 +1668,20 @@ export class HttpBackendClient {
     );
   }
 
+  // ========================================================================
+  // Actions
+  // ========================================================================
+
+  async listActions(signal?: AbortSignal): Promise<PaletteAction[]> {
+    const { actions } = await this.request<{ actions: PaletteAction[] }>('GET', '/api/actions', undefined, signal);
+    return actions;
+  }
+
+  async executeAction<T = unknown>(id: string, args: Record<string, unknown> = {}, signal?: AbortSignal): Promise<T> {
+    const { result } = await this.request<{ result: T }>('POST', `/api/actions/${encodeURIComponent(id)}`, { args }, signal);
+    return result;
+  }
+
   // ========================================================================
   // Tools
   // ========================================================================
@@ -681,6 +1690,67 @@ export class HttpBackendClient {
     return this.request<ToolMetadata[]>('GET', '/api/tools', undefined, signal);
   }
 
+  /** Approval policy rules, in evaluation order (first match wins). */
+  async listApprovalRules(signal?: AbortSignal): Promise<{ rules: ApprovalRule[] }> {
+    return this.request('GET', '/api/tools/approval-rules', undefined, signal);
+  }
+
+  async createApprovalRule(
+    input: ApprovalRuleInput & { position?: number },
+    signal?: AbortSignal,
+  ): Promise<ApprovalRule> {
+    return this.request('POST', '/api/tools/approval-rules', input, signal);
+  }
+
+  async updateApprovalRule(
+    id: string,
+    input: Partial<ApprovalRuleInput>,
+    signal?: AbortSignal,
+  ): Promise<ApprovalRule> {
+    return this.request('PUT', `/api/tools/approval-rules/${id}`, input, signal);
+  }
+
+  async deleteApprovalRule(id: string, signal?: AbortSignal): Promise<{ ok: boolean }> {
+    return this.request('DELETE', `/api/tools/approval-rules/${id}`, undefined, signal);
+  }
+
+  /** Replace the whole list, e.g. after reordering. */
+  async replaceApprovalRules(
+    rules: Array<ApprovalRuleInput & { id?: string }>,
+    signal?: AbortSignal,
+  ): Promise<{ rules: ApprovalRule[] }> {
+    return this.request('PUT', '/api/tools/approval-rules', { rules }, signal);
+  }
+
+  /** Global approval defaults per tool category (read-only, mutating, destructive). */
+  async getApprovalCategoryDefaults(signal?: AbortSignal): Promise<ApprovalCategorySettings> {
+    return this.request('GET', '/api/tools/approval-categories', undefined, signal);
+  }
+
+  /** Merge changes; `null` puts a category back on its built-in default. */
+  async updateApprovalCategoryDefaults(
+    defaults: Partial<Record<ToolCategory, 'ask' | 'allow' | null>>,
+    signal?: AbortSignal,
+  ): Promise<ApprovalCategorySettings> {
+    return this.request('PUT', '/api/tools/approval-categories', { defaults }, signal);
+  }
+
+  /** Approval decisions, newest first. */
+  async getApprovalHistory(
+    params: { tool?: string; sessionId?: string; outcome?: ApprovalOutcome; from?: number; to?: number; limit?: number } = {},
+    signal?: AbortSignal,
+  ): Promise<{ records: ApprovalRecord[] }> {
+    const query = new URLSearchParams();
+    if (params.tool) query.set('tool', params.tool);
+    if (params.sessionId) query.set('sessionId', params.sessionId);
+    if (params.outcome) query.set('outcome', params.outcome);
+    if (params.from !== undefined) query.set('from', String(params.from));
+    if (params.to !== undefined) query.set('to', String(params.to));
+    if (params.limit !== undefined) query.set('limit', String(params.limit));
+    const qs = query.toString();
+    return this.request('GET', `/api/tools/approval-history${qs ? `?${qs}` : ''}`, undefined, signal);
+  }
+
   async listMcpServers(signal?: AbortSignal): Promise<McpServer[]> {
     return this.request<McpServer[]>('GET', '/api/mcps', undefined, signal);
   }
@@ -844,6 +1914,219 @@ export class HttpBackendClient {
     return this.request('GET', '/api/usage', undefined, signal);
   }
 
+  async getUsageReport(
+    params: { from?: number; to?: number; groupBy?: UsageGroupBy; currency?: 'USD' } = {},
+    signal?: AbortSignal,
+  ): Promise<UsageReport> {
+    const query = new URLSearchParams();
+    if (params.from !== undefined) query.set('from', String(params.from));
+    if (params.to !== undefined) query.set('to', String(params.to));
+    if (params.groupBy) query.set('group_by', params.groupBy);
+    if (params.currency) query.set('currency', params.currency);
+    const qs = query.toString();
+    const suffix = qs ? `?${qs}` : '';
+    return this.request('GET', `/api/usage/report${suffix}`, undefined, signal);
+  }
+
+  /** Pre-aggregated series for the usage dashboard; `conversations` caps the top-conversations list (default 10). */
+  async getUsageDashboard(
+    params: { from?: number; to?: number; conversations?: number; currency?: 'USD' } = {},
+    signal?: AbortSignal,
+  ): Promise<UsageDashboard> {
+    const query = new URLSearchParams();
+    if (params.from !== undefined) query.set('from', String(params.from));
+    if (params.to !== undefined) query.set('to', String(params.to));
+    if (params.conversations !== undefined) query.set('conversations', String(params.conversations));
+    if (params.currency) query.set('currency', params.currency);
+    const qs = query.toString();
+    return this.request('GET', `/api/usage/dashboard${qs ? `?${qs}` : ''}`, undefined, signal);
+  }
+
+  /** Prompt cost attributed to each tool's results, most expensive first. */
+  async getToolCostReport(
+    params: { from?: number; to?: number; sessionId?: string; currency?: 'USD' } = {},
+    signal?: AbortSignal,
+  ): Promise<ToolCostReport> {
+    const query = new URLSearchParams();
+    if (params.from !== undefined) query.set('from', String(params.from));
+    if (params.to !== undefined) query.set('to', String(params.to));
+    if (params.sessionId) query.set('sessionId', params.sessionId);
+    if (params.currency) query.set('currency', params.currency);
+    const qs = query.toString();
+    return this.request('GET', `/api/usage/tools${qs ? `?${qs}` : ''}`, undefined, signal);
+  }
+
+  /** Rough prompt size and per-turn cost of continuing a conversation, optionally with another model or a draft message. */
+  async estimateRunCost(
+    input: { sessionId: string; model?: string; message?: string },
+    signal?: AbortSignal,
+  ): Promise<RunCostEstimate> {
+    return this.request('POST', '/api/usage/estimate', input, signal);
+  }
+
+  /** Per-call breakdown of a session's prompts (system, tools, history, tool output; stable prefix vs new tail). */
+  async getPromptComposition(sessionId: string, signal?: AbortSignal): Promise<{ sessionId: string; calls: PromptCompositionCall[] }> {
+    return this.request('GET', `/api/usage/composition?sessionId=${encodeURIComponent(sessionId)}`, undefined, signal);
+  }
+
+  /** CSV of per-call usage rows (timestamp, conversation, model, tokens, cost). */
+  async exportUsageCsv(
+    params: { from?: number; to?: number } = {},
+    signal?: AbortSignal,
+  ): Promise<string> {
+    const query = new URLSearchParams();
+    if (params.from !== undefined) query.set('from', String(params.from));
+    if (params.to !== undefined) query.set('to', String(params.to));
+    const qs = query.toString();
+
+    let res: Response;
+    try {
+      res = await fetch(`${this.serverUrl}/api/usage/export.csv${qs ? `?${qs}` : ''}`, {
+        headers: this.headers(),
+        signal,
+      });
+    } catch (err) {
+      throw new HttpBackendError(
+        `Network error: ${err instanceof Error ? err.message : String(err)}`,
+        0,
+      );
+    }
+
+    if (!res.ok) {
+      const errorBody = await res.json().catch(() => null) as { error?: string } | null;
+      throw new HttpBackendError(errorBody?.error ?? `HTTP ${res.status}`, res.status, errorBody);
+    }
+    return await res.text();
+  }
+
+  async getBudget(
+    signal?: AbortSignal,
+  ): Promise<{ settings: BudgetSettings; status: BudgetStatus[]; caps: SpendCapStatus[] }> {
+    return this.request('GET', '/api/usage/budget', undefined, signal);
+  }
+
+  async setBudget(
+    settings: Partial<BudgetSettings>,
+    signal?: AbortSignal,
+  ): Promise<{ settings: BudgetSettings; status: BudgetStatus[]; caps: SpendCapStatus[] }> {
+    return this.request('PUT', '/api/usage/budget', settings, signal);
+  }
+
+  async getCurrencySettings(signal?: AbortSignal): Promise<CurrencySettings> {
+    return this.request('GET', '/api/usage/currency', undefined, signal);
+  }
+
+  /** Omit `rate` to fetch the current one from the server's exchange rate feed. */
+  async setCurrencySettings(
+    input: { code: string; rate?: number; locale?: string | null },
+    signal?: AbortSignal,
+  ): Promise<CurrencySettings> {
+    return this.request('PUT', '/api/usage/currency', input, signal);
+  }
+
+  async refreshExchangeRate(signal?: AbortSignal): Promise<CurrencySettings> {
+    return this.request('POST', '/api/usage/currency/refresh', undefined, signal);
+  }
+
+  async getModelPrices(
+    signal?: AbortSignal,
+  ): Promise<{ prices: ModelPriceEntry[]; backends: Record<string, BackendPrice>; catalogUrl: string | null }> {
+    return this.request('GET', '/api/usage/pricing', undefined, signal);
+  }
+
+  /** One rate for every model of a provider, e.g. a self-hosted or OpenAI-compatible backend. */
+  async setBackendPrice(
+    provider: string,
+    price: BackendPrice,
+    signal?: AbortSignal,
+  ): Promise<{ provider: string; price: BackendPrice }> {
+    return this.request('PUT', `/api/usage/pricing/backends/${encodeURIComponent(provider)}`, price, signal);
+  }
+
+  async resetBackendPrice(provider: string, signal?: AbortSignal): Promise<{ ok: boolean }> {
+    return this.request('DELETE', `/api/usage/pricing/backends/${encodeURIComponent(provider)}`, undefined, signal);
+  }
+
+  /** Hand-set prices survive catalog refreshes until reset. */
+  async setModelPrice(
+    model: string,
+    price: { input: number; output: number; cacheRead?: number | null; cacheWrite?: number | null },
+    signal?: AbortSignal,
+  ): Promise<ModelPriceEntry> {
+    return this.request('PUT', `/api/usage/pricing/${encodeURIComponent(model)}`, price, signal);
+  }
+
+  async resetModelPrice(
+    model: string,
+    signal?: AbortSignal,
+  ): Promise<{ price: ModelPriceEntry | null }> {
+    return this.request('DELETE', `/api/usage/pricing/${encodeURIComponent(model)}`, undefined, signal);
+  }
+
+  async refreshModelPrices(
+    url?: string,
+    signal?: AbortSignal,
+  ): Promise<PriceRefreshResult> {
+    return this.request('POST', '/api/usage/pricing/refresh', url ? { url } : {}, signal);
+  }
+
+  // ========================================================================
+  // Audit log
+  // ========================================================================
+
+  /** Most recent audit entries, newest first. */
+  async getAuditLog(
+    params: { from?: number; to?: number; kinds?: AuditEntryKind[]; limit?: number } = {},
+    signal?: AbortSignal,
+  ): Promise<{ enabled: boolean; entries: AuditEntry[] }> {
+    const query = new URLSearchParams();
+    if (params.from !== undefined) query.set('from', String(params.from));
+    if (params.to !== undefined) query.set('to', String(params.to));
+    if (params.kinds?.length) query.set('kind', params.kinds.join(','));
+    if (params.limit !== undefined) query.set('limit', String(params.limit));
+    const qs = query.toString();
+    return this.request('GET', `/api/audit${qs ? `?${qs}` : ''}`, undefined, signal);
+  }
+
+  /** JSONL of every audit entry in range, oldest first. */
+  async exportAuditLog(
+    params: { from?: number; to?: number } = {},
+    signal?: AbortSignal,
+  ): Promise<string> {
+    const query = new URLSearchParams();
+    if (params.from !== undefined) query.set('from', String(params.from));
+    if (params.to !== undefined) query.set('to', String(params.to));
+    const qs = query.toString();
+
+    let res: Response;
+    try {
+      res = await fetch(`${this.serverUrl}/api/audit/export.jsonl${qs ? `?${qs}` : ''}`, {
+        headers: this.headers(),
+        signal,
+      });
+    } catch (err) {
+      throw new HttpBackendError(
+        `Network error: ${err instanceof Error ? err.message : String(err)}`,
+        0,
+      );
+    }
+
+    if (!res.ok) {
+      const errorBody = await res.json().catch(() => null) as { error?: string } | null;
+      throw new HttpBackendError(errorBody?.error ?? `HTTP ${res.status}`, res.status, errorBody);
+    }
+    return await res.text();
+  }
+
+  // ========================================================================
+  // Metrics
+  // ========================================================================
+
+  /** In-process latency, token and queue-depth metrics. */
+  async getMetrics(signal?: AbortSignal): Promise<MetricsSnapshot> {
+    return this.request('GET', '/api/metrics', undefined, signal);
+  }
+
   // ========================================================================
   // Models (additional)
   // ========================================================================
@@ -0,0 +1,111 @@
+/**
+ * Recovery options for failed agent runs, keyed by the server's error code.
+ * The code travels on `agent:failed`, the completion stream's `done` event,
+ * and the persisted agent record, so reopened conversations show the same
+ * options as a live failure.
+ */
+
+import type { AgentErrorCode } from '$lib/types/server-events';
+
+export type RecoveryAction = 'retry' | 'open_settings' | 'new_conversation';
+
+export interface ErrorRecovery {
+  hint: string;
+  action?: RecoveryAction;
+  actionLabel?: string;
+}
+
+const RECOVERY: Record<AgentErrorCode, ErrorRecovery> = {
+  provider_auth: {
+    hint: 'The provider rejected the API key, or none is configured.',
+    action: 'open_settings',
+    actionLabel: 'Check API keys',
+  },
+  rate_limited: {
+    hint: 'The provider is rate limiting requests. Wait a moment, then retry.',
+    action: 'retry',
+    actionLabel: 'Retry',
+  },
+  provider_unavailable: {
+    hint: 'The provider could not be reached or returned a server error.',
+    action: 'retry',
+    actionLabel: 'Retry',
+  },
+  offline: {
+    hint: 'This machine is offline, so the provider could not be reached.',
+    action: 'retry',
+    actionLabel: 'Retry',
+  },
+  context_overflow: {
+    hint: 'The conversation no longer fits in the model’s context window.',
+    action: 'new_conversation',
+    actionLabel: 'Start a new conversation',
+  },
+  tool_timeout: {
+    hint: 'A tool took too long to respond.',
+    action: 'retry',
+    actionLabel: 'Retry',
+  },
+  tool_failed: {
+    hint: 'A tool call failed.',
+    action: 'retry',
+    actionLabel: 'Retry',
+  },
+  approval_denied: {
+    hint: 'A tool request was denied, so the run stopped.',
+  },
+  approval_expired: {
+    hint: 'A tool request went unanswered until its approval window ran out.',
+  },
+  budget_exceeded: {
+    hint: 'Your usage budget is used up for this period.',
+    action: 'open_settings',
+    actionLabel: 'Review budget',
+  },
+  max_turns: {
+    hint: 'The agent hit its turn limit before finishing.',
+    action: 'retry',
+    actionLabel: 'Continue',
+  },
+  guardrail_stop: {
+    hint: 'The agent stopped itself to avoid an unsafe or looping action.',
+  },
+  cancelled: {
+    hint: 'The run was cancelled.',
+  },
+  interrupted: {
+    hint: 'The server stopped while this run was in progress.',
+    action: 'retry',
+    actionLabel: 'Resume',
+  },
+  stalled: {
+    hint: 'The run stopped making progress and was ended.',
+    action: 'retry',
+    actionLabel: 'Retry',
+  },
+  persistence_failed: {
+    hint: 'The reply could not be saved, so it was discarded.',
+    action: 'retry',
+    actionLabel: 'Retry',
+  },
+  unknown: {
+    hint: 'Something went wrong while running the agent.',
+    action: 'retry',
+    actionLabel: 'Retry',
+  },
+};
+
+export function recoveryFor(code: AgentErrorCode | null | undefined): ErrorRecovery {
+  return (code && RECOVERY[code]) || RECOVERY.unknown;
+}
+
+/** A failed run surfaced by the completion stream, carrying its error code. */
+export class AgentRunError extends Error {
+  constructor(
+    message: string,
+    readonly code: AgentErrorCode = 'unknown',
+  ) {
+    super(message);
+    this.name = 'AgentRunError';
+  }
+}
@@ -0,0 +1,30 @@
+import { getHttpBackend, type AgentArtifact } from '$lib/backend/http-client';
+
+function isTauri(): boolean {
+  return typeof window !== 'undefined' && Boolean((window as any).__TAURI__);
+}
+
+/**
+ * Saves a file the agent created. The desktop app asks where to put it; the
+ * browser hands it to its usual download flow. Resolves to the saved path in
+ * the desktop app, or null when the user cancelled or the browser took over.
+ */
+export async function saveArtifact(sessionId: string, artifact: Pick<AgentArtifact, 'name'>): Promise<string | null> {
+  const blob = await getHttpBackend().downloadSessionArtifact(sessionId, artifact.name);
+
+  if (isTauri()) {
+    const { invoke } = await import('@tauri-apps/api/tauri');
+    const contents = Array.from(new Uint8Array(await blob.arrayBuffer()));
+    return invoke<string | null>('save_artifact', { fileName: artifact.name, contents });
+  }
+
+  const url = URL.createObjectURL(blob);
+  const a = document.createElement('a');
+  a.href = url;
+  a.download = artifact.name;
+  document.body.appendChild(a);
+  a.click();
+  document.body.removeChild(a);
+  URL.revokeObjectURL(url);
+  return null;
+}
@@ -0,0 +1,45 @@
+function isTauri(): boolean {
+  return typeof window !== 'undefined' && Boolean((window as any).__TAURI__);
+}
+
+/**
+ * Reads an image from the clipboard: the pasted file when the paste event
+ * carries one, otherwise the system clipboard (through the desktop app,
+ * whose webview does not expose copied images to paste events). Resolves to
+ * null when the clipboard holds no image.
+ */
+export async function readClipboardImage(event?: ClipboardEvent): Promise<Blob | null> {
+  const pasted = Array.from(event?.clipboardData?.files ?? []).find((file) => file.type.startsWith('image/'));
+  if (pasted) return pasted;
+
+  if (isTauri()) {
+    const { invoke } = await import('@tauri-apps/api/tauri');
+    const png = await invoke<number[] | null>('read_clipboard_image');
+    return png ? new Blob([new Uint8Array(png)], { type: 'image/png' }) : null;
+  }
+
+  // Without a paste event, e.g. from a button, the async clipboard API may still have it
+  if (event || !navigator.clipboard?.read) return null;
+  for (const item of await navigator.clipboard.read()) {
+    const type = item.types.find((candidate) => candidate.startsWith('image/'));
+    if (type) return item.getType(type);
+  }
+  return null;
+}
+
+/** `Pasted image 2026-03-14 09-26-53.png`, so several pastes in a row stay apart. */
+export function clipboardImageName(type: string, now = new Date()): string {
+  const pad = (value: number) => String(value).padStart(2, '0');
+  const stamp = `${now.getFullYear()}-${pad(now.getMonth() + 1)}-${pad(now.getDate())} ${pad(now.getHours())}-${pad(now.getMinutes())}-${pad(now.getSeconds())}`;
+  const extension = type === 'image/jpeg' ? 'jpg' : type.split('/')[1]?.split(/[+;]/)[0] || 'png';
+  return `Pasted image ${stamp}.${extension}`;
+}
+
+export function blobToDataUrl(blob: Blob): Promise<string> {
+  return new Promise((resolve, reject) => {
+    const reader = new FileReader();
+    reader.onload = () => resolve(reader.result as string);
+    reader.onerror = reject;
+    reader.readAsDataURL(blob);
+  });
+}
@@ -0,0 +1,122 @@
+/**
+ * OS notifications for agent milestones (run completed, failed, or blocked on
+ * a tool approval) while the app is in the background. Off unless the
+ * `notifications.desktop` preference is "true".
+ *
+ * Tauri v1 notifications cannot carry a click handler, so the deep link is
+ * remembered and followed the next time the window regains focus. In a plain
+ * browser the Web Notification's click handler does the same thing.
+ */
+
+import { backend } from '$lib/backend/client';
+
+export const DESKTOP_NOTIFICATIONS_KEY = 'notifications.desktop';
+
+export type AgentMilestone = 'completed' | 'failed' | 'approval';
+
+export interface MilestoneNotification {
+  conversationId: string;
+  conversationName?: string;
+  /** Result excerpt, error message, or the tool awaiting approval. */
+  detail?: string;
+}
+
+const MILESTONE_TITLES: Record<AgentMilestone, string> = {
+  completed: 'Agent finished',
+  failed: 'Agent run failed',
+  approval: 'Approval needed',
+};
+
+const MAX_BODY_CHARS = 160;
+
+let enabled: boolean | null = null;
+let pendingConversationId: string | null = null;
+let openConversation: ((conversationId: string) => void | Promise<void>) | null = null;
+
+function isTauri(): boolean {
+  return typeof window !== 'undefined' && Boolean((window as any).__TAURI__);
+}
+
+export async function isDesktopNotificationsEnabled(): Promise<boolean> {
+  if (enabled === null) {
+    enabled = (await backend.getPreference(DESKTOP_NOTIFICATIONS_KEY)) === 'true';
+  }
+  return enabled;
+}
+
+/** Persist the setting, asking for OS permission when turning it on. Returns the effective state. */
+export async function setDesktopNotificationsEnabled(value: boolean): Promise<boolean> {
+  const next = value ? await ensurePermission() : false;
+  await backend.setPreference(DESKTOP_NOTIFICATIONS_KEY, String(next));
+  enabled = next;
+  return next;
+}
+
+/**
+ * Follow notification deep links into the given conversation opener.
+ * Returns a cleanup function for component teardown.
+ */
+export function initNotificationDeepLinks(
+  open: (conversationId: string) => void | Promise<void>,
+): () => void {
+  openConversation = open;
+  const onFocus = () => {
+    const conversationId = pendingConversationId;
+    pendingConversationId = null;
+    if (conversationId) void open(conversationId);
+  };
+  window.addEventListener('focus', onFocus);
+  return () => {
+    window.removeEventListener('focus', onFocus);
+    if (openConversation === open) openConversation = null;
+  };
+}
+
+export async function notifyAgentMilestone(
+  milestone: AgentMilestone,
+  notification: MilestoneNotification,
+): Promise<void> {
+  if (typeof document === 'undefined' || document.hasFocus()) return;
+  try {
+    if (!(await isDesktopNotificationsEnabled())) return;
+
+    const title = notification.conversationName
+      ? `${MILESTONE_TITLES[milestone]} · ${notification.conversationName}`
+      : MILESTONE_TITLES[milestone];
+    const body = truncate(notification.detail ?? '');
+    pendingConversationId = notification.conversationId;
+
+    if (isTauri()) {
+      const { isPermissionGranted, sendNotification } = await import('@tauri-apps/api/notification');
+      if (await isPermissionGranted()) sendNotification({ title, body });
+      return;
+    }
+
+    if (typeof Notification === 'undefined' || Notification.permission !== 'granted') return;
+    const shown = new Notification(title, { body, tag: `agent-${notification.conversationId}` });
+    shown.onclick = () => {
+      window.focus();
+      pendingConversationId = null;
+      void openConversation?.(notification.conversationId);
+      shown.close();
+    };
+  } catch (error) {
+    // Notifications are best-effort; never let them break a run
+    console.warn('[desktopNotifications] Failed to notify:', error);
+  }
+}
+
+async function ensurePermission(): Promise<boolean> {
+  if (isTauri()) {
+    const { isPermissionGranted, requestPermission } = await import('@tauri-apps/api/notification');
+    return (await isPermissionGranted()) || (await requestPermission()) === 'granted';
+  }
+  if (typeof Notification === 'undefined') return false;
+  if (Notification.permission === 'granted') return true;
+  return (await Notification.requestPermission()) === 'granted';
+}
+
+function truncate(text: string): string {
+  const flat = text.replace(/\s+/g, ' ').trim();
+  return flat.length > MAX_BODY_CHARS ? `${flat.slice(0, MAX_BODY_CHARS - 1)}…` : flat;
+}
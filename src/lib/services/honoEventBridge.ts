@@ -8,8 +8,10 @@
  * (started/completed/failed) are emitted so the store can track them.
  */
 
-import { getHttpBackend } from '$lib/backend/http-client';
+import { getHttpBackend, type Item } from '$lib/backend/http-client';
 import { AGENT_EVENT_TYPES } from '$lib/types/events';
+import type { AgentErrorCode, ChatStreamEvent } from '$lib/types/server-events';
+import { AgentRunError } from '$lib/services/agentErrors';
 import type { AgentEvent } from '$lib/types';
 
 export interface HonoStreamOptions {
@@ -23,6 +25,10 @@ export interface HonoStreamOptions {
   instructions?: string;
   systemPrompt?: string;
   mcpServerIds?: string[];
+  /** Ingested projects to link to the session for project.search. */
+  projects?: string[];
+  /** Identifies this send so a repeated request cannot save the message twice. */
+  idempotencyKey?: string;
   /** Called as soon as the server's session ID is known (first SSE event).
    *  Persist it immediately so follow-up messages don't lose context on abort. */
   onSessionId?: (sessionId: string) => void;
@@ -36,6 +42,10 @@ export interface HonoStreamResult {
   sessionId?: string;
   /** Root agent ID — used to cancel the agent if needed */
   agentId?: string;
+  /** Root agent status from the `done` event ('completed' or 'waiting'; failures throw) */
+  status?: string;
+  /** Final assistant text */
+  content?: string;
 }
 
 /**
@@ -44,7 +54,7 @@ export interface HonoStreamResult {
  * can process it unchanged.
  */
 export async function streamMessageViaHono(
-  input: string,
+  input: string | Item[],
   options: HonoStreamOptions,
   onEvent: (event: AgentEvent) => void,
   signal: AbortSignal,
@@ -65,6 +75,8 @@ export async function streamMessageViaHono(
   let rootAgentId: string | undefined;
   let rootText = '';
   let resultSessionId: string | undefined;
+  let resultStatus: string | undefined;
+  let resultContent: string | undefined;
   const toolStartTimes = new Map<string, number>();
   const surfacedApprovalIds = new Set<string>();
   // Per-agent turn separator: after a tool_end for a given agent, the next
@@ -103,6 +115,8 @@ export async function streamMessageViaHono(
       instructions: options.instructions,
       systemPrompt: options.systemPrompt,
       mcpServerIds: options.mcpServerIds,
+      projects: options.projects,
+      idempotencyKey: options.idempotencyKey,
     },
     signal,
   )) {
@@ -177,19 +191,20 @@ export async function streamMessageViaHono(
         timestamp_ms: ts(),
       });
     } else if (event === 'tool_start') {
-      const callId = data.callId as string;
-      const parentId = data.parentId as string | null | undefined;
+      const tool = data as unknown as ChatStreamEvent<'tool_start'>;
+      const callId = tool.callId;
+      const parentId = tool.parentId;
       toolStartTimes.set(callId, Date.now());
 
       // Track workflow.run tool calls so we can route progress into the tool bubble
-      if ((data.name as string) === 'workflow.run') {
+      if (tool.name === 'workflow.run') {
         workflowToolCalls.set(callId, '');
       }
 
       // Track delegate tool calls so we can route child text into them
-      if ((data.name as string) === 'delegate') {
-        const callerAgentId = data.agentId as string;
-        const depth = ((data.depth as number) ?? 0) + 1;
+      if (tool.name === 'delegate') {
+        const callerAgentId = tool.agentId;
+        const depth = (tool.depth ?? 0) + 1;
         delegateCallByParent.set(callerAgentId, { callId, depth });
         delegateChildText.set(callId, '');
       }
@@ -198,33 +213,34 @@ export async function streamMessageViaHono(
         event_type: AGENT_EVENT_TYPES.TOOL_EXECUTION_STARTED,
         payload: {
           execution_id: callId,
-          tool_name: data.name as string,
-          args: (data.args as Record<string, unknown>) ?? {},
+          tool_name: tool.name,
+          args: tool.args ?? {},
           message_id: messageId,
           conversation_id: conversationId,
-          session_id: data.agentId as string | undefined,
+          session_id: tool.agentId,
           parent_session_id: parentId ?? null,
-          source_execution_id: sourceExecutionIdForAgent(data.agentId as string | undefined),
+          source_execution_id: sourceExecutionIdForAgent(tool.agentId),
           is_sub_agent: parentId != null,
           timestamp_ms: ts(),
         },
         timestamp_ms: ts(),
       });
     } else if (event === 'tool_end') {
-      const callId = data.callId as string;
+      const tool = data as unknown as ChatStreamEvent<'tool_end'>;
+      const callId = tool.callId;
       const started = toolStartTimes.get(callId) ?? Date.now();
-      const success = data.success !== false;
-      const parentId = data.parentId as string | null | undefined;
-      const agentId = data.agentId as string | undefined;
-      console.log('[honoEventBridge] tool_end:', data.name, callId, { success, agentId, parentId });
+      const success = tool.success !== false;
+      const parentId = tool.parentId;
+      const agentId = tool.agentId;
+      console.log('[honoEventBridge] tool_end:', tool.name, callId, { success, agentId, parentId });
 
       // Clean up workflow tracking when the workflow.run tool completes
-      if ((data.name as string) === 'workflow.run') {
+      if (tool.name === 'workflow.run') {
         workflowToolCalls.delete(callId);
       }
 
       // Clean up delegate tracking when the delegate tool completes
-      if ((data.name as string) === 'delegate' && agentId) {
+      if (tool.name === 'delegate' && agentId) {
         delegateCallByParent.delete(agentId);
         for (const [childAgentId, delegateInfo] of delegateCallByChild.entries()) {
           if (delegateInfo.callId === callId) {
@@ -244,10 +260,10 @@ export async function streamMessageViaHono(
         event_type: AGENT_EVENT_TYPES.TOOL_EXECUTION_COMPLETED,
         payload: {
           execution_id: callId,
-          tool_name: (data.name as string) ?? '',
-          result: data.output,
+          tool_name: tool.name ?? '',
+          result: tool.output,
           success,
-          error: success ? undefined : String(data.output ?? 'Tool failed'),
+          error: success ? undefined : String(tool.output ?? 'Tool failed'),
           duration_ms: Date.now() - started,
           message_id: messageId,
           conversation_id: conversationId,
@@ -315,6 +331,8 @@ export async function streamMessageViaHono(
           approval_id: callId,
           tool_name: toolName,
           args: (data.args as Record<string, unknown>) ?? {},
+          preview: data.preview ?? undefined,
+          batch: data.batch ?? undefined,
           agent_id: data.agentId as string | undefined,
           message_id: messageId,
           conversation_id: conversationId,
@@ -323,6 +341,22 @@ export async function streamMessageViaHono(
         },
         timestamp_ms: ts(),
       });
+    } else if (event === 'tool_progress') {
+      const progress = data as unknown as ChatStreamEvent<'tool_progress'>;
+      onEvent({
+        event_type: AGENT_EVENT_TYPES.TOOL_EXECUTION_PROGRESS,
+        payload: {
+          execution_id: progress.callId,
+          tool_name: progress.name,
+          elapsed_ms: progress.elapsedMs,
+          progress: progress.progress,
+          message: progress.message,
+          message_id: messageId,
+          conversation_id: conversationId,
+          timestamp_ms: ts(),
+        },
+        timestamp_ms: ts(),
+      });
     } else if (event === 'tool_approved') {
       const callId = data.callId as string;
       onEvent({
@@ -465,7 +499,7 @@ export async function streamMessageViaHono(
       // rather than silently completing with empty content (which gets filtered out).
       if (data.status === 'failed') {
         const errorMsg = (data.error as string) ?? 'Agent run failed';
-        throw new Error(errorMsg);
+        throw new AgentRunError(errorMsg, data.errorCode as AgentErrorCode | undefined);
       }
 
       // Final-fallback: if agent ended waiting with approvals not yet surfaced, surface them now
@@ -521,6 +555,8 @@ export async function streamMessageViaHono(
       // is empty (non-streaming path or no text output).
       const serverResult = data.result as string | undefined;
       const content = rootText || serverResult || '';
+      resultStatus = data.status as string | undefined;
+      resultContent = content;
 
       onEvent({
         event_type: AGENT_EVENT_TYPES.ASSISTANT_STREAM_COMPLETED,
@@ -538,7 +574,7 @@ export async function streamMessageViaHono(
     }
   }
 
-  return { sessionId: resultSessionId, agentId: rootAgentId };
+  return { sessionId: resultSessionId, agentId: rootAgentId, status: resultStatus, content: resultContent };
 }
 
 function honoStatusToPhase(status: string): string | null {
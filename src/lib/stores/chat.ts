@@ -1,5 +1,5 @@
 import { writable, derived, get } from 'svelte/store';
-import type { Message } from '$lib/types';
+import type { Conversation, Message } from '$lib/types';
 import type { Model } from '$lib/types/models';
 import type { SystemPrompt } from '$lib/types';
 import { conversationService } from '$lib/services/conversation';
@@ -8,10 +8,14 @@ import { modelService, apiKeyService } from '$lib/models';
 import { customBackendService } from '$lib/services/customBackendService.svelte';
 import { ollamaService } from '$lib/services/ollamaService.svelte';
 import { backend } from '$lib/backend/client';
-import { getHttpBackend } from '$lib/backend/http-client';
+import { getHttpBackend, HttpBackendError, type Item, type ProjectSummary } from '$lib/backend/http-client';
 import { v4 as uuidv4 } from 'uuid';
 import { branchStore } from '$lib/stores/branches';
 import { streamMessageViaHono } from '$lib/services/honoEventBridge';
+import { notifyAgentMilestone } from '$lib/services/desktopNotifications';
+import { blobToDataUrl, clipboardImageName, readClipboardImage } from '$lib/services/clipboardImages';
+import { AgentRunError, type RecoveryAction } from '$lib/services/agentErrors';
+import type { AgentErrorCode, ApprovalBatch } from '$lib/types/server-events';
 import { AGENT_EVENT_TYPES } from '$lib/types/events';
 import type { AgentEvent, Attachment, ToolCallRecord, MessageSegment } from '$lib/types';
 import type {
@@ -27,6 +31,7 @@ import type {
   ConversationUpdatedPayload,
   MessageSavedPayload,
   ToolExecutionCompletedPayload,
+  ToolExecutionProgressPayload,
   ToolExecutionStartedPayload,
   ToolExecutionApprovalScope,
   ToolExecutionDecisionPayload,
@@ -63,6 +68,8 @@ export const selectedSystemPrompt = writable<SystemPrompt | null>(null);
 export const streamingEnabled = writable<boolean>(true);
 export const isLoading = writable<boolean>(false);
 export const attachments = writable<any[]>([]);
+/** Projects ingested before the conversation had a server session; linked with the next message. */
+export const pendingProjects = writable<string[]>([]);
 export const currentMessage = writable<string>('');
 export const isFirstMessage = writable<boolean>(true);
 export const pendingToolApprovals = writable<ToolExecutionProposedPayload[]>([]);
@@ -188,6 +195,10 @@ export type ToolActivityEntry = {
   completed_at?: number;
   duration_ms?: number;
   error?: string;
+  /** Latest heartbeat while running. */
+  elapsed_ms?: number;
+  progress?: number;
+  progress_message?: string;
 };
 
 function getToolCallsForMessage(messageId: string): ToolCallRecord[] | undefined {
@@ -366,6 +377,27 @@ function isAgentErrorContent(content: string): boolean {
   return trimmed.startsWith('Agent error:') || trimmed.startsWith('Agent setup error:');
 }
 
+/** Failed runs and server rejections that carry an error code; other errors stay in the console. */
+function toRunError(error: unknown): AgentRunError | null {
+  if (error instanceof AgentRunError) return error;
+  if (error instanceof HttpBackendError) {
+    const code = (error.body as { errorCode?: AgentErrorCode } | null | undefined)?.errorCode;
+    if (code) return new AgentRunError(error.message, code);
+  }
+  return null;
+}
+
+function runErrorMessage(error: string, errorCode: AgentErrorCode | null | undefined): Message {
+  return {
+    id: uuidv4(),
+    type: 'received',
+    content: `Agent error: ${error}`,
+    timestamp: Date.now(),
+    isError: true,
+    errorCode: errorCode ?? undefined,
+  };
+}
+
 function flushStreamingChunks() {
   if (!streamingChunkBuffer) {
     streamingFlushPending = false;
@@ -729,6 +761,25 @@ function handleAgentEvent(event: AgentEvent) {
       });
     }
 
+    if (event.event_type === AGENT_EVENT_TYPES.TOOL_EXECUTION_PROGRESS) {
+      const payload = event.payload as ToolExecutionProgressPayload;
+      if (payload.message_id && cancelledAssistantMessageIds.has(payload.message_id)) {
+        return;
+      }
+      toolActivity.update((entries) =>
+        entries.map((entry) =>
+          entry.execution_id === payload.execution_id && entry.status === 'running'
+            ? {
+                ...entry,
+                elapsed_ms: payload.elapsed_ms,
+                progress: payload.progress ?? entry.progress,
+                progress_message: payload.message ?? entry.progress_message,
+              }
+            : entry
+        )
+      );
+    }
+
     if (event.event_type === AGENT_EVENT_TYPES.TOOL_EXECUTION_COMPLETED) {
       const payload = event.payload as ToolExecutionCompletedPayload;
       if (payload.message_id && cancelledAssistantMessageIds.has(payload.message_id)) {
@@ -848,6 +899,13 @@ function handleAgentEvent(event: AgentEvent) {
       }
 
       console.log('[chat] TOOL_EXECUTION_PROPOSED queued:', payload.tool_name, payload.approval_id);
+      if (!get(pendingToolApprovals).some((entry) => entry.approval_id === payload.approval_id)) {
+        void notifyAgentMilestone('approval', {
+          conversationId: payload.conversation_id ?? currentConversation?.id ?? '',
+          conversationName: currentConversation?.name,
+          detail: `${payload.tool_name} is waiting for your approval`,
+        });
+      }
       pendingToolApprovals.update((approvals) => {
         if (approvals.some((entry) => entry.approval_id === payload.approval_id)) {
           return approvals;
@@ -933,7 +991,7 @@ function handleAgentEvent(event: AgentEvent) {
 export async function startAgentEvents() {
   try {
     const currentConversation = conversationService.getCurrentConversation();
-    const pendingApprovals = await backend.listPendingToolApprovals();
+    const pendingApprovals = await backend.listPendingToolApprovals(currentConversation?.id);
     pendingToolApprovals.set(
       pendingApprovals.filter((approval) => {
         if (!approval.conversation_id) {
@@ -1309,6 +1367,12 @@ function honoItemsToMessages(
     result.push({ id: uuidv4(), type: 'received', content: '', tool_calls: [...pendingToolCalls] });
   }
 
+  // A failed root run keeps its error on the agent record, not in the items
+  const root = agents.find((a) => !a.parentId);
+  if (root?.status === 'failed' && root.error) {
+    result.push(runErrorMessage(root.error, root.errorCode));
+  }
+
   return result;
 }
 
@@ -1381,9 +1445,23 @@ function generateMessageId(): string {
   return uuidv4();
 }
 
+async function awaitTranscriptionJob(jobId: string, signal: AbortSignal): Promise<string> {
+  const client = getHttpBackend();
+  for (;;) {
+    const job = await client.getTranscriptionJob(jobId, signal);
+    if (job.status === 'completed') return job.text ?? '';
+    if (job.status === 'failed') throw new Error(job.error ?? 'Audio transcription failed');
+    await new Promise((resolve) => setTimeout(resolve, 500));
+    signal.throwIfAborted();
+  }
+}
+
 async function transcribeAudioAttachments(items: Attachment[], signal: AbortSignal): Promise<Attachment[]> {
   return Promise.all(items.map(async (attachment) => {
     if (attachment.attachment_type !== 'audio' || attachment.transcript) return attachment;
+    if (attachment.transcription_job_id) {
+      return { ...attachment, transcript: await awaitTranscriptionJob(attachment.transcription_job_id, signal) };
+    }
     const response = await fetch(attachment.data);
     if (!response.ok) throw new Error(`Could not read audio attachment: ${attachment.name}`);
     const result = await getHttpBackend().transcribeAudio(await response.blob(), attachment.name, signal);
@@ -1391,6 +1469,80 @@ async function transcribeAudioAttachments(items: Attachment[], signal: AbortSign
   }));
 }
 
+async function extractPdfAttachments(items: Attachment[], signal: AbortSignal): Promise<Attachment[]> {
+  return Promise.all(items.map(async (attachment) => {
+    if (attachment.attachment_type !== 'pdf' || attachment.document) return attachment;
+    const response = await fetch(attachment.data);
+    if (!response.ok) throw new Error(`Could not read PDF attachment: ${attachment.name}`);
+    const { hash, pages, source, chunks } = await getHttpBackend().extractPdf(await response.blob(), attachment.name, signal);
+    return { ...attachment, document: { hash, pages, source, chunks } };
+  }));
+}
+
+/** Text files go through the server too, which inlines small ones and indexes large ones for search. */
+async function extractTextAttachments(items: Attachment[], signal: AbortSignal): Promise<Attachment[]> {
+  return Promise.all(items.map(async (attachment) => {
+    if (attachment.attachment_type !== 'text' || attachment.document) return attachment;
+    const { hash, pages, source, chunks } = await getHttpBackend().extractTextFile(new Blob([attachment.data]), attachment.name, signal);
+    return { ...attachment, document: { hash, pages, source, chunks } };
+  }));
+}
+
+async function extractOfficeAttachments(items: Attachment[], signal: AbortSignal): Promise<Attachment[]> {
+  return Promise.all(items.map(async (attachment) => {
+    if (attachment.attachment_type !== 'office' || attachment.document) return attachment;
+    const response = await fetch(attachment.data);
+    if (!response.ok) throw new Error(`Could not read Office attachment: ${attachment.name}`);
+    const { hash, pages, source, chunks } = await getHttpBackend().extractOfficeDocument(await response.blob(), attachment.name, signal);
+    return { ...attachment, document: { hash, pages, source, chunks } };
+  }));
+}
+
+/** Files the server rejects on validation carry a `code` and fail the send; OCR being unavailable does not. */
+function isOcrUnavailable(err: unknown): boolean {
+  return err instanceof HttpBackendError && err.status === 400 && !(err.body as { code?: string } | null)?.code;
+}
+
+/** Images are OCR'd when the server has an OCR model; otherwise they stay client-side as before. */
+async function readImageAttachments(items: Attachment[], signal: AbortSignal): Promise<Attachment[]> {
+  return Promise.all(items.map(async (attachment) => {
+    if (attachment.attachment_type !== 'image' || attachment.ocr) return attachment;
+    const response = await fetch(attachment.data);
+    if (!response.ok) throw new Error(`Could not read image attachment: ${attachment.name}`);
+    try {
+      const { hash, chunks } = await getHttpBackend().extractImageText(await response.blob(), attachment.name, signal);
+      return { ...attachment, ocr: { hash, chunks } };
+    } catch (err) {
+      if (!isOcrUnavailable(err)) throw err;
+      console.warn(`[ChatStore] Image OCR skipped for ${attachment.name}: ${err.message}`);
+      return attachment;
+    }
+  }));
+}
+
+/** Videos are uploaded for frame sampling and transcription; there is no client-side fallback. */
+async function extractVideoAttachments(items: Attachment[], signal: AbortSignal): Promise<Attachment[]> {
+  return Promise.all(items.map(async (attachment) => {
+    if (attachment.attachment_type !== 'video' || attachment.video) return attachment;
+    const response = await fetch(attachment.data);
+    if (!response.ok) throw new Error(`Could not read video attachment: ${attachment.name}`);
+    const { hash, frames, chunks } = await getHttpBackend().extractVideo(await response.blob(), attachment.name, signal);
+    return { ...attachment, video: { hash, frames: frames.length, chunks } };
+  }));
+}
+
+/** Documents, OCR'd images and videos travel as references; the server inlines or indexes their text. */
+function buildMessageInput(message: string, items: Attachment[]): string | Item[] {
+  const blocks: NonNullable<Item['contentBlocks']> = [];
+  for (const attachment of items) {
+    if (attachment.document) blocks.push({ type: 'document', hash: attachment.document.hash, name: attachment.name });
+    if (attachment.ocr?.chunks.length) blocks.push({ type: 'image', hash: attachment.ocr.hash, name: attachment.name });
+    if (attachment.video) blocks.push({ type: 'video', hash: attachment.video.hash, name: attachment.name });
+  }
+  if (blocks.length === 0) return message;
+  return [{ type: 'message', role: 'user', content: message, contentBlocks: blocks }];
+}
+
 function appendAudioTranscripts(message: string, items: Attachment[]): string {
   const transcripts = items
     .filter((attachment) => attachment.attachment_type === 'audio' && attachment.transcript)
@@ -1398,6 +1550,67 @@ function appendAudioTranscripts(message: string, items: Attachment[]): string {
   return [message.trim(), ...transcripts].filter(Boolean).join('\n\n');
 }
 
+/** Adds a previously uploaded file to the pending message; its stored extraction is reused as is. */
+export async function attachLibraryFile(hash: string): Promise<void> {
+  const { upload, extraction } = await getHttpBackend().attachFromLibrary(hash);
+  let attachment: Attachment;
+  if (upload.kind === 'image') {
+    attachment = { attachment_type: 'image', name: upload.name, data: '', ocr: { hash: extraction.hash, chunks: extraction.chunks } };
+  } else if (upload.kind === 'video') {
+    const frames = 'frames' in extraction ? extraction.frames.length : 0;
+    attachment = { attachment_type: 'video', name: upload.name, data: '', video: { hash: extraction.hash, frames, chunks: extraction.chunks } };
+  } else {
+    attachment = {
+      attachment_type: upload.kind,
+      name: upload.name,
+      data: '',
+      document: {
+        hash: extraction.hash,
+        pages: 'pages' in extraction ? extraction.pages : 1,
+        source: 'source' in extraction ? extraction.source : 'text',
+        chunks: extraction.chunks,
+      },
+    };
+  }
+  attachments.update((items) => [...items.filter((item) => (item.document ?? item.ocr ?? item.video)?.hash !== hash), attachment]);
+}
+
+/**
+ * Attaches an image from the clipboard, uploaded right away so it is stored,
+ * deduplicated and OCR'd like a picked file; sending resizes it for vision
+ * models as usual. Resolves to false when the clipboard holds no image.
+ */
+export async function attachClipboardImage(event?: ClipboardEvent): Promise<boolean> {
+  const image = await readClipboardImage(event);
+  if (!image) return false;
+  const name = clipboardImageName(image.type);
+  const attachment: Attachment = { attachment_type: 'image', name, data: await blobToDataUrl(image) };
+  try {
+    const { hash, chunks } = await getHttpBackend().extractImageText(image, name);
+    attachment.ocr = { hash, chunks };
+  } catch (err) {
+    if (!isOcrUnavailable(err)) throw err;
+  }
+  // Pasting the same image twice keeps one copy
+  attachments.update((items) => [...items.filter((item) => !attachment.ocr || item.ocr?.hash !== attachment.ocr.hash), attachment]);
+  return true;
+}
+
+/**
+ * Indexes a dropped folder as a project the agent can search with
+ * project.search. A conversation with a server session is linked right away;
+ * otherwise the project goes out with the next message.
+ */
+export async function ingestProjectFolder(path: string): Promise<ProjectSummary> {
+  const conversation = conversationService.getCurrentConversation();
+  const sessionId = conversation ? sessionMap.get(conversation.id) : undefined;
+  const { project } = await getHttpBackend().ingestProject(path, { sessionId });
+  if (!sessionId) {
+    pendingProjects.update((names) => [...names.filter((name) => name !== project.name), project.name]);
+  }
+  return project;
+}
+
 export async function sendMessage() {
   if (get(isLoading)) return;
 
@@ -1407,6 +1620,7 @@ export async function sendMessage() {
   const selectedModelValue = get(selectedModel);
   const selectedSystemPromptValue = get(selectedSystemPrompt);
   const selectedMcpServerIdsValue = get(selectedMcpServerIds);
+  const pendingProjectsValue = get(pendingProjects);
   const isFirstMessageValue = get(isFirstMessage);
 
   if (!currentMessageValue.trim() && attachmentsValue.length === 0) return;
@@ -1414,6 +1628,7 @@ export async function sendMessage() {
   isLoading.set(true);
   const requestController = new AbortController();
   honoStreamController = requestController;
+  let runConversation: Conversation | null = null;
 
   try {
     const models = get(availableModels);
@@ -1427,7 +1642,22 @@ export async function sendMessage() {
       selectedModel.set(selectedModelObject.model_name);
     }
 
-    const normalizedAttachments = await transcribeAudioAttachments(attachmentsValue, requestController.signal);
+    const normalizedAttachments = await extractVideoAttachments(
+      await readImageAttachments(
+        await extractOfficeAttachments(
+          await extractTextAttachments(
+            await extractPdfAttachments(
+              await transcribeAudioAttachments(attachmentsValue, requestController.signal),
+              requestController.signal,
+            ),
+            requestController.signal,
+          ),
+          requestController.signal,
+        ),
+        requestController.signal,
+      ),
+      requestController.signal,
+    );
     const normalizedMessage = appendAudioTranscripts(currentMessageValue, normalizedAttachments);
 
     // Default system prompt
@@ -1445,11 +1675,13 @@ export async function sendMessage() {
     // Get or create the current conversation
     const currentConversation = conversationService.getCurrentConversation()
       ?? await conversationService.setCurrentConversation(null);
+    runConversation = currentConversation;
     if (requestController.signal.aborted) return;
 
     // Clear input fields only after preparation succeeds and cancellation is no longer pending.
     currentMessage.set('');
     attachments.set([]);
+    pendingProjects.set([]);
 
     // Check if this is the first message in a new conversation
     const shouldGenerateTitle = isFirstMessageValue;
@@ -1483,7 +1715,7 @@ export async function sendMessage() {
     ]);
 
     const result = await streamMessageViaHono(
-      normalizedMessage,
+      buildMessageInput(normalizedMessage, normalizedAttachments),
       {
         conversationId: currentConversation.id,
         messageId: assistantMessageId,
@@ -1494,6 +1726,8 @@ export async function sendMessage() {
         agent: 'planner',
         systemPrompt: systemPromptContent,
         mcpServerIds: isFirstMessageValue ? selectedMcpServerIdsValue : undefined,
+        projects: pendingProjectsValue.length ? pendingProjectsValue : undefined,
+        idempotencyKey: userMessageId,
         // Persist session ID eagerly — if the stream is aborted before `done`,
         // the next follow-up message still has the correct session to resume.
         onSessionId: (sid) => sessionMap.set(currentConversation.id, sid),
@@ -1510,6 +1744,13 @@ export async function sendMessage() {
     if (result.agentId) {
       honoAgentId = result.agentId;
     }
+    if (result.status === 'completed') {
+      void notifyAgentMilestone('completed', {
+        conversationId: currentConversation.id,
+        conversationName: currentConversation.name,
+        detail: result.content,
+      });
+    }
 
     // Generate a title for the conversation if this is the first message
     console.log('Generating title for conversation:', currentConversation?.id);
@@ -1526,8 +1767,19 @@ export async function sendMessage() {
     }
   } catch (error) {
     console.error('Error sending message:', error);
+    if (runConversation && !requestController.signal.aborted) {
+      void notifyAgentMilestone('failed', {
+        conversationId: runConversation.id,
+        conversationName: runConversation.name,
+        detail: error instanceof Error ? error.message : String(error),
+      });
+    }
     finalizeRunningToolCalls('Request failed', Date.now());
     resetStreamingState();
+    const runError = toRunError(error);
+    if (runError && runConversation?.id === conversationService.getCurrentConversation()?.id) {
+      messages.update((msgs) => [...msgs, runErrorMessage(runError.message, runError.code)]);
+    }
   } finally {
     if (honoStreamController === requestController) honoStreamController = null;
   }
@@ -1558,6 +1810,23 @@ export async function cancelCurrentAgentRequest() {
   resetStreamingState();
 }
 
+/**
+ * Act on a failed run's recovery option. Retrying sends a continuation turn,
+ * since the server resumes a failed root agent with its history intact.
+ * `open_settings` is navigation and is handled by the caller.
+ */
+export async function recoverFromRunError(action: RecoveryAction): Promise<void> {
+  if (action === 'new_conversation') {
+    clearConversation();
+    return;
+  }
+  if (action === 'retry') {
+    messages.update((msgs) => msgs.filter((msg) => !msg.errorCode && !msg.isError));
+    currentMessage.set('Please continue where you left off.');
+    await sendMessage();
+  }
+}
+
 export function clearConversation() {
   // Clear messages immediately
   messages.set([]);
@@ -1580,15 +1849,62 @@ export function clearConversation() {
 export async function resolveToolApproval(
   approvalId: string,
   approved: boolean,
-  scope?: ToolExecutionApprovalScope
+  scope?: ToolExecutionApprovalScope,
+  amendedArgs?: Record<string, unknown>,
+  feedback?: string
+) {
+  await resolveToolApprovals(
+    [approvalId],
+    approved,
+    scope,
+    amendedArgs ? { [approvalId]: amendedArgs } : undefined,
+    feedback
+  );
+}
+
+/**
+ * Approve or deny several pending approvals with one decision. Calls from the
+ * same agent go to the backend together, so the agent resumes once. A denial
+ * with feedback lets the agent continue with it instead of stopping.
+ */
+export async function resolveToolApprovals(
+  approvalIds: string[],
+  approved: boolean,
+  scope?: ToolExecutionApprovalScope,
+  amendedArgs?: Record<string, Record<string, unknown>>,
+  feedback?: string
+) {
+  const byAgent = new Map<string, ToolExecutionProposedPayload[]>();
+  for (const approval of get(pendingToolApprovals)) {
+    if (!approval.agent_id || !approvalIds.includes(approval.approval_id)) continue;
+    byAgent.set(approval.agent_id, [...(byAgent.get(approval.agent_id) ?? []), approval]);
+  }
+  for (const group of byAgent.values()) {
+    await resolveAgentApprovals(group, approved, scope, amendedArgs, feedback);
+  }
+}
+
+async function resolveAgentApprovals(
+  group: ToolExecutionProposedPayload[],
+  approved: boolean,
+  scope?: ToolExecutionApprovalScope,
+  amendedArgs?: Record<string, Record<string, unknown>>,
+  feedback?: string
 ) {
   {
-    const approvals = get(pendingToolApprovals);
-    const approval = approvals.find((a) => a.approval_id === approvalId);
+    const approval = group[0];
     const agentId = approval?.agent_id;
+    const approvalIds = group.map((a) => a.approval_id);
+    const toolNames = group.map((a) => a.tool_name);
     if (agentId) {
-      // Optimistically clear the approval and show loading
-      pendingToolApprovals.update((a) => a.filter((x) => x.approval_id !== approvalId));
+      // Optimistically clear the approvals and show loading. Approving for the
+      // conversation (or always) also settles this agent's other pending calls
+      // to the same tools, so those cards go too.
+      const coversSiblings = approved && (scope === 'conversation' || scope === 'always');
+      pendingToolApprovals.update((a) => a.filter((x) =>
+        !approvalIds.includes(x.approval_id) &&
+        !(coversSiblings && x.agent_id === agentId && toolNames.includes(x.tool_name))
+      ));
       isLoading.set(true);
 
       // Subscribe to the event stream BEFORE sending the approval so we receive
@@ -1665,6 +1981,8 @@ export async function resolveToolApproval(
                   approval_id: data.callId as string,
                   tool_name: data.name as string,
                   args: (data.args as Record<string, unknown>) ?? {},
+                  preview: data.preview ?? undefined,
+                  batch: (data.batch as ApprovalBatch | undefined) ?? undefined,
                   agent_id: data.agentId as string | undefined,
                   message_id: msgId,
                   conversation_id: convId,
@@ -1722,11 +2040,14 @@ export async function resolveToolApproval(
       })();
 
       try {
-        const result = await client.approveToolExecution(
+        const result = await client.approveToolExecutions(
           agentId,
-          approvalId,
+          approvalIds,
           approved ? 'approved' : 'denied',
           scope,
+          undefined,
+          amendedArgs,
+          approved ? undefined : feedback,
         );
 
         // Stop event subscription — the HTTP response has the final state
@@ -1738,6 +2059,24 @@ export async function resolveToolApproval(
         // approved tool itself, any tools the agent executed in the continued
         // run, and delegate tools whose tool:completed may not have arrived
         // before the event loop broke.
+        if ((result.status === 'completed' || result.status === 'failed') && approval?.conversation_id) {
+          const lastOutput = result.output?.[result.output.length - 1]?.content;
+          void notifyAgentMilestone(result.status, {
+            conversationId: approval.conversation_id,
+            conversationName: conversationService.getCurrentConversation()?.name,
+            detail: result.status === 'failed'
+              ? result.error ?? 'Agent run failed'
+              : typeof lastOutput === 'string' ? lastOutput : undefined,
+          });
+        }
+
+        if (
+          result.status === 'failed' &&
+          approval?.conversation_id === conversationService.getCurrentConversation()?.id
+        ) {
+          messages.update((msgs) => [...msgs, runErrorMessage(result.error ?? 'Agent run failed', result.errorCode)]);
+        }
+
         if (result.status === 'completed' || result.status === 'failed') {
           const msgId = approval?.message_id;
           if (msgId) {
@@ -1812,6 +2151,7 @@ export async function resolveToolApproval(
                   approval_id: w.callId,
                   tool_name: w.name,
                   args: w.args ?? {},
+                  batch: w.batch,
                   agent_id: result.id ?? agentId,
                   message_id: approval?.message_id,
                   conversation_id: approval?.conversation_id,
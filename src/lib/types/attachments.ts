@@ -20,17 +20,43 @@ export interface Attachment {
   name: string;
   data: string;
   attachment_url?: string;
-  attachment_type: "image" | "audio" | "text";
+  attachment_type: "image" | "audio" | "text" | "pdf" | "office" | "video";
   description?: string;
   created_at?: Date;
   transcript?: string;
+  /** Background transcription started when the audio was attached. */
+  transcription_job_id?: string;
+  /** Server-side extraction of a PDF, text or Office attachment. */
+  document?: { hash: string; pages: number; source: 'text' | 'ocr'; chunks: string[] };
+  /** Server-side OCR of an image attachment. */
+  ocr?: { hash: string; chunks: string[] };
+  /** Server-side frame sampling and audio transcript of a video attachment. */
+  video?: { hash: string; frames: number; chunks: string[] };
   // Fields for file-based attachments
   file_path?: string;
   file_metadata?: FileMetadata;
 }
 
+const VIDEO_FILE_EXTENSION = /\.(m4v|mkv|mov|mp4|webm)$/i;
+
+/** Checked before `isAudioFile`, which also claims .mp4 and .webm for voice recordings. */
+export function isVideoFile(file: Pick<File, 'name' | 'type'>): boolean {
+  return file.type.startsWith('video/') || (!file.type.startsWith('audio/') && VIDEO_FILE_EXTENSION.test(file.name));
+}
+
 const AUDIO_FILE_EXTENSION = /\.(aac|flac|m4a|mp3|mp4|oga|ogg|opus|wav|webm)$/i;
 
 export function isAudioFile(file: Pick<File, 'name' | 'type'>): boolean {
   return file.type.startsWith('audio/') || AUDIO_FILE_EXTENSION.test(file.name);
 }
+
+export function isPdfFile(file: Pick<File, 'name' | 'type'>): boolean {
+  return file.type === 'application/pdf' || /\.pdf$/i.test(file.name);
+}
+
+const OFFICE_FILE_EXTENSION = /\.(docx|xlsx|pptx)$/i;
+
+/** Word, Excel and PowerPoint files in the Office Open XML formats; legacy .doc/.xls/.ppt are not supported. */
+export function isOfficeFile(file: Pick<File, 'name' | 'type'>): boolean {
+  return file.type.startsWith('application/vnd.openxmlformats-officedocument.') || OFFICE_FILE_EXTENSION.test(file.name);
+}
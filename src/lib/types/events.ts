@@ -1,3 +1,5 @@
+import type { ApprovalBatch } from './server-events';
+
 export const AGENT_EVENT_TYPES = {
   MESSAGE_SAVED: 'message.saved',
   CONVERSATION_UPDATED: 'conversation.updated',
@@ -9,6 +11,7 @@ export const AGENT_EVENT_TYPES = {
   ASSISTANT_STREAM_COMPLETED: 'assistant.stream.completed',
   TOOL_EXECUTION_STARTED: 'tool.execution.started',
   TOOL_EXECUTION_COMPLETED: 'tool.execution.completed',
+  TOOL_EXECUTION_PROGRESS: 'tool.execution.progress',
   TOOL_EXECUTION_PROPOSED: 'tool.execution.proposed',
   TOOL_EXECUTION_APPROVED: 'tool.execution.approved',
   TOOL_EXECUTION_DENIED: 'tool.execution.denied',
@@ -59,6 +62,7 @@ export type AgentEventPayloadMap = {
   'assistant.stream.completed': AssistantStreamCompletedPayload;
   'tool.execution.started': ToolExecutionStartedPayload;
   'tool.execution.completed': ToolExecutionCompletedPayload;
+  'tool.execution.progress': ToolExecutionProgressPayload;
   'tool.execution.proposed': ToolExecutionProposedPayload;
   'tool.execution.approved': ToolExecutionDecisionPayload;
   'tool.execution.denied': ToolExecutionDecisionPayload;
@@ -154,6 +158,8 @@ export interface ToolExecutionProposedPayload {
   tool_name: string;
   args: Record<string, unknown>;
   preview?: unknown;
+  /** Set when the call can only be approved with the rest of its batch. */
+  batch?: ApprovalBatch;
   iteration: number;
   conversation_id?: string;
   message_id?: string;
@@ -162,6 +168,18 @@ export interface ToolExecutionProposedPayload {
   timestamp_ms: number;
 }
 
+/** Heartbeat while a tool runs; progress (0..1) and message only when the tool reports them. */
+export interface ToolExecutionProgressPayload {
+  execution_id: string;
+  tool_name: string;
+  elapsed_ms: number;
+  progress?: number;
+  message?: string;
+  conversation_id?: string;
+  message_id?: string;
+  timestamp_ms: number;
+}
+
 export type ToolExecutionApprovalScope = 'once' | 'conversation' | 'always';
 
 export interface ToolExecutionDecisionPayload {
@@ -14,6 +14,7 @@
  */
 
 import type { Attachment } from './attachments';
+import type { AgentErrorCode } from './server-events';
 
 /**
  * Base message interface with core properties
@@ -45,6 +46,8 @@ export interface DisplayMessage extends BaseMessage {
   tool_calls?: ToolCallRecord[];
   /** Whether this message represents an error (e.g., agent error) */
   isError?: boolean;
+  /** Server error code for a failed run; selects the recovery options shown */
+  errorCode?: AgentErrorCode;
   /**
    * Ordered segments for interleaved rendering (text + tool calls in streaming order).
    * When present, use this instead of rendering tool_calls before content.
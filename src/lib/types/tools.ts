@@ -6,5 +6,7 @@ export interface ToolMetadata {
   args_schema: unknown;
   result_schema: unknown;
   requires_approval: boolean;
+  /** What the tool does to the world; read-only tools run without approval by default. */
+  category?: 'read_only' | 'mutating' | 'destructive';
   result_mode: ToolResultMode;
 }